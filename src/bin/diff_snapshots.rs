@@ -0,0 +1,144 @@
+//! Compares simulation snapshots from two runs and prints what changed.
+//!
+//! ```text
+//! cargo run --bin diff_snapshots -- <old> <new> [--epsilon 0.001] [--max-changes 0]
+//! ```
+//!
+//! `<old>` and `<new>` are two snapshot files or two snapshot directories; directories are
+//! matched file by file on `day_<N>.json`. Exits 0 when the differences stay within the
+//! tolerances, 1 when they exceed them, and 2 on bad arguments or unreadable files.
+use std::{
+    collections::BTreeSet,
+    env, fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use serde_json::Value;
+use thegame::prelude::{diff_values, Tolerances};
+
+const USAGE: &str = "usage: diff_snapshots <old> <new> [--epsilon <float>] [--max-changes <count>]";
+
+struct Arguments {
+    old: PathBuf,
+    new: PathBuf,
+    tolerances: Tolerances,
+}
+
+fn main() -> ExitCode {
+    let arguments = match parse_arguments(env::args().skip(1)) {
+        Ok(arguments) => arguments,
+        Err(message) => {
+            eprintln!("{message}\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    match compare(&arguments) {
+        Ok(total) if arguments.tolerances.exceeded_by(total) => {
+            println!(
+                "{total} differences (allowed {})",
+                arguments.tolerances.max_changes
+            );
+            ExitCode::from(1)
+        }
+        Ok(total) => {
+            println!("{total} differences; within tolerance");
+            ExitCode::SUCCESS
+        }
+        Err(message) => {
+            eprintln!("{message}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn parse_arguments(mut args: impl Iterator<Item = String>) -> Result<Arguments, String> {
+    let mut paths = Vec::new();
+    let mut tolerances = Tolerances::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--epsilon" => {
+                tolerances.epsilon = parse_flag(&arg, args.next())?;
+                if !(tolerances.epsilon.is_finite() && tolerances.epsilon >= 0.0) {
+                    return Err("--epsilon must be a non-negative number".to_string());
+                }
+            }
+            "--max-changes" => tolerances.max_changes = parse_flag(&arg, args.next())?,
+            flag if flag.starts_with("--") => return Err(format!("unknown option {flag}")),
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+    let [old, new] = <[PathBuf; 2]>::try_from(paths)
+        .map_err(|paths| format!("expected two paths, got {}", paths.len()))?;
+    Ok(Arguments {
+        old,
+        new,
+        tolerances,
+    })
+}
+
+fn parse_flag<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{flag} needs a value"))?;
+    value
+        .parse()
+        .map_err(|_| format!("{flag}: `{value}` is not a valid value"))
+}
+
+/// Prints the differences and returns how many there were. A snapshot present on only
+/// one side counts as one difference.
+fn compare(arguments: &Arguments) -> Result<usize, String> {
+    let (old, new) = (&arguments.old, &arguments.new);
+    if !(old.is_dir() && new.is_dir()) {
+        return compare_files(old, new, arguments.tolerances.epsilon);
+    }
+
+    let (old_names, new_names) = (snapshot_names(old)?, snapshot_names(new)?);
+    let mut total = 0;
+    for name in old_names.union(&new_names) {
+        match (old_names.contains(name), new_names.contains(name)) {
+            (true, true) => {
+                total += compare_files(
+                    &old.join(name),
+                    &new.join(name),
+                    arguments.tolerances.epsilon,
+                )?
+            }
+            (true, false) => {
+                println!("- {name}: only in {}", old.display());
+                total += 1;
+            }
+            _ => {
+                println!("+ {name}: only in {}", new.display());
+                total += 1;
+            }
+        }
+    }
+    Ok(total)
+}
+
+fn compare_files(old: &Path, new: &Path, epsilon: f64) -> Result<usize, String> {
+    let differences = diff_values(&read_snapshot(old)?, &read_snapshot(new)?, epsilon);
+    if !differences.is_empty() {
+        println!("{} -> {}", old.display(), new.display());
+        for difference in &differences {
+            println!("  {difference}");
+        }
+    }
+    Ok(differences.len())
+}
+
+fn read_snapshot(path: &Path) -> Result<Value, String> {
+    let data = fs::read_to_string(path)
+        .map_err(|err| format!("unable to read {}: {err}", path.display()))?;
+    serde_json::from_str(&data).map_err(|err| format!("invalid JSON in {}: {err}", path.display()))
+}
+
+fn snapshot_names(directory: &Path) -> Result<BTreeSet<String>, String> {
+    let entries = fs::read_dir(directory)
+        .map_err(|err| format!("unable to list {}: {err}", directory.display()))?;
+    Ok(entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| name.ends_with(".json"))
+        .collect())
+}
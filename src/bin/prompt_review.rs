@@ -0,0 +1,208 @@
+//! Prints captured dialogue prompts next to the replies they produced.
+//!
+//! ```text
+//! cargo run --bin prompt_review -- [--prompts <file>] [--history <file>] [--request <id>] [--width 120]
+//! ```
+//!
+//! Reads the prompt capture (`logs/dialogue_prompts.jsonl`, written when the game runs with
+//! `DIALOGUE_CAPTURE_PROMPTS=1`) and the telemetry log (`logs/dialogue_history.jsonl`),
+//! matches them on request id, and prints each prompt in the left column with its reply or
+//! failure in the right. Exits 2 on bad arguments or unreadable files.
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use serde_json::Value;
+
+const USAGE: &str = "usage: prompt_review [--prompts <file>] [--history <file>] [--request <id>] [--width <columns>]";
+const DEFAULT_PROMPTS_PATH: &str = "logs/dialogue_prompts.jsonl";
+const DEFAULT_HISTORY_PATH: &str = "logs/dialogue_history.jsonl";
+const DEFAULT_WIDTH: usize = 120;
+const MIN_WIDTH: usize = 40;
+const COLUMN_SEPARATOR: &str = " | ";
+
+struct Arguments {
+    prompts: PathBuf,
+    history: PathBuf,
+    request: Option<u64>,
+    width: usize,
+}
+
+fn main() -> ExitCode {
+    let arguments = match parse_arguments(env::args().skip(1)) {
+        Ok(arguments) => arguments,
+        Err(message) => {
+            eprintln!("{message}\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    match review(&arguments) {
+        Ok(0) => {
+            println!("no captured prompts in {}", arguments.prompts.display());
+            ExitCode::SUCCESS
+        }
+        Ok(_) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("{message}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn parse_arguments(mut args: impl Iterator<Item = String>) -> Result<Arguments, String> {
+    let mut arguments = Arguments {
+        prompts: PathBuf::from(DEFAULT_PROMPTS_PATH),
+        history: PathBuf::from(DEFAULT_HISTORY_PATH),
+        request: None,
+        width: DEFAULT_WIDTH,
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--prompts" => arguments.prompts = parse_flag(&arg, args.next())?,
+            "--history" => arguments.history = parse_flag(&arg, args.next())?,
+            "--request" => arguments.request = Some(parse_flag(&arg, args.next())?),
+            "--width" => {
+                arguments.width = parse_flag(&arg, args.next())?;
+                if arguments.width < MIN_WIDTH {
+                    return Err(format!("--width must be at least {MIN_WIDTH}"));
+                }
+            }
+            other => return Err(format!("unknown argument {other}")),
+        }
+    }
+    Ok(arguments)
+}
+
+fn parse_flag<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{flag} needs a value"))?;
+    value
+        .parse()
+        .map_err(|_| format!("{flag}: `{value}` is not a valid value"))
+}
+
+/// Prints every selected prompt with its outcome and returns how many were printed.
+fn review(arguments: &Arguments) -> Result<usize, String> {
+    let outcomes = read_outcomes(&arguments.history)?;
+    let column = (arguments.width - COLUMN_SEPARATOR.len()) / 2;
+    let mut printed = 0;
+    for prompt in read_lines(&arguments.prompts)? {
+        let Some(request_id) = prompt["request_id"].as_u64() else {
+            continue;
+        };
+        if arguments.request.is_some_and(|wanted| wanted != request_id) {
+            continue;
+        }
+
+        let source = prompt["source"].as_str().unwrap_or("unknown");
+        let captured_at = prompt["captured_at"].as_str().unwrap_or("");
+        println!("=== request {request_id} ({source}) {captured_at}");
+        let left = wrap(&render_messages(&prompt["messages"]), column);
+        let right = wrap(
+            outcomes
+                .get(&request_id)
+                .map_or("(no reply logged)", String::as_str),
+            column,
+        );
+        for row in 0..left.len().max(right.len()) {
+            let cell = |lines: &[String]| lines.get(row).cloned().unwrap_or_default();
+            let row = format!("{:<column$}{COLUMN_SEPARATOR}{}", cell(&left), cell(&right));
+            println!("{}", row.trim_end());
+        }
+        println!();
+        printed += 1;
+    }
+    Ok(printed)
+}
+
+/// Latest reply or failure per request id in the telemetry log. A request that failed and
+/// was retried keeps whichever outcome came last.
+fn read_outcomes(path: &Path) -> Result<HashMap<u64, String>, String> {
+    let mut outcomes = HashMap::new();
+    for line in read_lines(path)? {
+        let event = &line["event"];
+        let Some(request_id) = event["request_id"].as_u64() else {
+            continue;
+        };
+        let outcome = match event["event_type"].as_str() {
+            Some("response") => event["content"].as_str().unwrap_or("").to_string(),
+            Some("failure") => format!("FAILED: {}", describe_error(&event["error"])),
+            _ => continue,
+        };
+        outcomes.insert(request_id, outcome);
+    }
+    Ok(outcomes)
+}
+
+fn describe_error(error: &Value) -> String {
+    ["message", "reason", "missing"]
+        .iter()
+        .find_map(|field| error[field].as_str())
+        .map_or_else(|| error.to_string(), str::to_string)
+}
+
+fn render_messages(messages: &Value) -> String {
+    messages
+        .as_array()
+        .map(|messages| {
+            messages
+                .iter()
+                .map(|message| {
+                    format!(
+                        "[{}] {}",
+                        message["role"].as_str().unwrap_or("?"),
+                        message["content"].as_str().unwrap_or("")
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .unwrap_or_default()
+}
+
+/// Splits `text` into lines of at most `width` characters, keeping its own line breaks and
+/// breaking long lines at spaces where possible.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split(' ') {
+            let mut word: Vec<char> = word.chars().collect();
+            let line_len = line.chars().count();
+            if line_len > 0 && line_len + 1 + word.len() > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            while word.len() > width {
+                let rest = word.split_off(width);
+                lines.push(word.into_iter().collect());
+                word = rest;
+            }
+            line.extend(word);
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// Parses each non-blank line of a JSONL file; a missing file reads as empty, since the
+/// telemetry log may not have been flushed yet.
+fn read_lines(path: &Path) -> Result<Vec<Value>, String> {
+    let data = match fs::read_to_string(path) {
+        Ok(data) => data,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(format!("unable to read {}: {err}", path.display())),
+    };
+    data.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line)
+                .map_err(|err| format!("invalid JSON in {}:{}: {err}", path.display(), index + 1))
+        })
+        .collect()
+}
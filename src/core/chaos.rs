@@ -0,0 +1,460 @@
+//! Chaos testing, compiled in with `--features chaos`. With `[chaos] enabled` in
+//! `config/chaos.toml`, `inject_chaos_faults` rolls a seeded die at the start of every frame
+//! and, at `fault_chance`, runs one fault from `ChaosFaults`. Plugins register faults for
+//! their own state with `App::register_chaos_fault`. Each injection logs one line with the
+//! seed and frame number, so a failing run replays with the same seed. Started with
+//! `--chaos-report`, the game prints a summary on exit (or on a panic) listing every fault
+//! and the warnings logged within `report_window_frames` after it.
+use std::{
+    fmt::Write as _,
+    fs,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+use bevy::{
+    log::{
+        tracing::{Event, Level, Subscriber},
+        tracing_subscriber::{layer::Context, Layer},
+        BoxedLayer, LogPlugin,
+    },
+    prelude::*,
+};
+use serde::Deserialize;
+
+use super::config::{report_config_result, ConfigDiagnostics, ConfigReloadRequested};
+use crate::economy::rng::DailyRng;
+
+pub const CONFIG_PATH: &str = "config/chaos.toml";
+/// Command-line flag that prints the chaos summary when the app exits.
+pub const REPORT_FLAG: &str = "--chaos-report";
+const DEFAULT_SEED: u64 = 1;
+const DEFAULT_FAULT_CHANCE: f32 = 0.002;
+const DEFAULT_REPORT_WINDOW_FRAMES: u64 = 120;
+const CHAOS_STREAM: u64 = 0x4348_414f;
+
+/// Warnings and errors logged since startup, counted by `warning_counter_layer`.
+static WARNINGS: AtomicU64 = AtomicU64::new(0);
+/// The latest summary, printed by the panic hook when the run dies.
+static LAST_SUMMARY: Mutex<String> = Mutex::new(String::new());
+
+/// Breaks one piece of simulation state. Returns what it did, or `None` when there was
+/// nothing to break this frame.
+pub type ChaosFaultFn = fn(&mut World, &mut DailyRng) -> Option<String>;
+
+#[derive(Debug, Clone, Deserialize, Default)]
+struct RawChaosConfig {
+    #[serde(default)]
+    chaos: RawChaosSection,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+struct RawChaosSection {
+    enabled: bool,
+    seed: u64,
+    fault_chance: f32,
+    report_window_frames: u64,
+}
+
+impl Default for RawChaosSection {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            seed: DEFAULT_SEED,
+            fault_chance: DEFAULT_FAULT_CHANCE,
+            report_window_frames: DEFAULT_REPORT_WINDOW_FRAMES,
+        }
+    }
+}
+
+/// Whether faults are injected, with which seed, and how often.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct ChaosSettings {
+    pub enabled: bool,
+    pub seed: u64,
+    /// Chance (0-1) that a frame injects a fault.
+    pub fault_chance: f32,
+    /// Frames after a fault during which warnings are attributed to it.
+    pub report_window_frames: u64,
+}
+
+impl Default for ChaosSettings {
+    fn default() -> Self {
+        RawChaosSection::default().into()
+    }
+}
+
+impl From<RawChaosSection> for ChaosSettings {
+    fn from(raw: RawChaosSection) -> Self {
+        Self {
+            enabled: raw.enabled,
+            seed: raw.seed,
+            fault_chance: if raw.fault_chance.is_finite() {
+                raw.fault_chance.clamp(0.0, 1.0)
+            } else {
+                0.0
+            },
+            report_window_frames: raw.report_window_frames,
+        }
+    }
+}
+
+impl ChaosSettings {
+    pub fn load() -> Result<Self, String> {
+        let data = fs::read_to_string(Path::new(CONFIG_PATH))
+            .map_err(|err| format!("unable to read file: {err}"))?;
+        Self::parse(&data)
+    }
+
+    pub fn parse(data: &str) -> Result<Self, String> {
+        let raw: RawChaosConfig =
+            toml::from_str(data).map_err(|err| format!("invalid TOML: {err}"))?;
+        Ok(raw.chaos.into())
+    }
+}
+
+/// Faults registered by the plugins, in registration order.
+#[derive(Resource, Debug, Default)]
+pub struct ChaosFaults {
+    faults: Vec<(&'static str, ChaosFaultFn)>,
+}
+
+impl ChaosFaults {
+    /// Adds `fault` under `name`, replacing an earlier fault of the same name.
+    pub fn register(&mut self, name: &'static str, fault: ChaosFaultFn) {
+        match self.faults.iter_mut().find(|(known, _)| *known == name) {
+            Some(entry) => entry.1 = fault,
+            None => self.faults.push((name, fault)),
+        }
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.faults.iter().map(|(name, _)| *name)
+    }
+}
+
+/// Lets plugins register their faults without knowing whether `CorePlugin` ran first.
+pub trait ChaosAppExt {
+    fn register_chaos_fault(&mut self, name: &'static str, fault: ChaosFaultFn) -> &mut Self;
+}
+
+impl ChaosAppExt for App {
+    fn register_chaos_fault(&mut self, name: &'static str, fault: ChaosFaultFn) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_init::<ChaosFaults>()
+            .register(name, fault);
+        self
+    }
+}
+
+/// One injected fault and the warnings that followed it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InjectedFault {
+    pub frame: u64,
+    pub name: &'static str,
+    pub detail: String,
+    pub warnings_after: u64,
+}
+
+/// Frames run and faults injected so far.
+#[derive(Resource, Debug, Default)]
+pub struct ChaosLog {
+    frame: u64,
+    warnings_seen: u64,
+    injected: Vec<InjectedFault>,
+}
+
+impl ChaosLog {
+    #[cfg(test)]
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    #[cfg(test)]
+    pub fn injected(&self) -> &[InjectedFault] {
+        &self.injected
+    }
+
+    /// Adds `new_warnings` to every fault injected within `window` frames.
+    fn attribute_warnings(&mut self, new_warnings: u64, window: u64) {
+        let frame = self.frame;
+        for fault in self
+            .injected
+            .iter_mut()
+            .filter(|fault| frame.saturating_sub(fault.frame) <= window)
+        {
+            fault.warnings_after += new_warnings;
+        }
+    }
+
+    /// A readable report: the run, then one line per fault.
+    pub fn summary(&self, seed: u64) -> String {
+        let mut summary = format!(
+            "Chaos run with seed {seed}: {} frames, {} faults injected",
+            self.frame,
+            self.injected.len()
+        );
+        for fault in &self.injected {
+            let _ = write!(
+                summary,
+                "\n  frame {} {}: {} ({} warnings after)",
+                fault.frame, fault.name, fault.detail, fault.warnings_after
+            );
+        }
+        summary
+    }
+}
+
+/// Whether `--chaos-report` was passed.
+#[derive(Resource, Debug, Clone, Copy, Default)]
+pub struct ChaosReport {
+    pub requested: bool,
+}
+
+/// Counts every warning and error the log sees, so the report can attribute them to faults.
+struct WarningCounter;
+
+impl<S: Subscriber> Layer<S> for WarningCounter {
+    fn on_event(&self, event: &Event<'_>, _context: Context<'_, S>) {
+        if *event.metadata().level() <= Level::WARN {
+            WARNINGS.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+fn warning_counter_layer(_app: &mut App) -> Option<BoxedLayer> {
+    Some(Box::new(WarningCounter))
+}
+
+/// `LogPlugin` with the warning counter installed. `main` swaps it into `DefaultPlugins`.
+pub fn chaos_log_plugin() -> LogPlugin {
+    LogPlugin {
+        custom_layer: warning_counter_layer,
+        ..default()
+    }
+}
+
+/// Picks an index below `len`, or `None` for an empty list.
+pub fn pick_index(rng: &mut DailyRng, len: usize) -> Option<usize> {
+    let last = u32::try_from(len.checked_sub(1)?).unwrap_or(u32::MAX);
+    Some(rng.range_inclusive(0, last) as usize)
+}
+
+/// Registers the settings, the fault registry, the injection system, and the report.
+pub struct ChaosPlugin;
+
+impl Plugin for ChaosPlugin {
+    fn build(&self, app: &mut App) {
+        let settings = report_config_result(
+            app.world_mut(),
+            CONFIG_PATH,
+            ChaosSettings::load(),
+            ChaosSettings::default,
+        );
+        let report = ChaosReport {
+            requested: std::env::args().any(|arg| arg == REPORT_FLAG),
+        };
+        if report.requested {
+            install_panic_report();
+        }
+        app.insert_resource(settings)
+            .insert_resource(report)
+            .init_resource::<ChaosLog>()
+            .init_resource::<ChaosFaults>()
+            .add_systems(Startup, log_chaos_settings)
+            .add_systems(First, inject_chaos_faults)
+            .add_systems(Update, reload_chaos_settings)
+            .add_systems(Last, print_chaos_report);
+    }
+}
+
+fn log_chaos_settings(settings: Res<ChaosSettings>, faults: Res<ChaosFaults>) {
+    if settings.enabled {
+        warn!(
+            "Chaos mode on: seed {}, fault chance {} per frame, faults: {}",
+            settings.seed,
+            settings.fault_chance,
+            faults.names().collect::<Vec<_>>().join(", ")
+        );
+    }
+}
+
+/// Runs at most one registered fault per frame, drawn from the seed and frame number.
+pub fn inject_chaos_faults(world: &mut World) {
+    let settings = world.resource::<ChaosSettings>().clone();
+    if !settings.enabled {
+        return;
+    }
+
+    let warnings = WARNINGS.load(Ordering::Relaxed);
+    let frame = {
+        let mut log = world.resource_mut::<ChaosLog>();
+        log.frame += 1;
+        let new_warnings = warnings.saturating_sub(log.warnings_seen);
+        log.warnings_seen = warnings;
+        if new_warnings > 0 {
+            log.attribute_warnings(new_warnings, settings.report_window_frames);
+        }
+        log.frame
+    };
+
+    let mut rng = DailyRng::for_day(settings.seed, frame, CHAOS_STREAM);
+    if !rng.chance(settings.fault_chance) {
+        return;
+    }
+    let faults = world.resource::<ChaosFaults>();
+    let Some((name, fault)) = pick_index(&mut rng, faults.faults.len()).map(|i| faults.faults[i])
+    else {
+        return;
+    };
+    let Some(detail) = fault(world, &mut rng) else {
+        debug!(
+            "chaos seed={} frame={frame} fault={name}: nothing to break",
+            settings.seed
+        );
+        return;
+    };
+
+    info!(
+        "chaos seed={} frame={frame} fault={name}: {detail}",
+        settings.seed
+    );
+    world
+        .resource_mut::<ChaosLog>()
+        .injected
+        .push(InjectedFault {
+            frame,
+            name,
+            detail,
+            warnings_after: 0,
+        });
+    if world.resource::<ChaosReport>().requested {
+        let summary = world.resource::<ChaosLog>().summary(settings.seed);
+        if let Ok(mut last) = LAST_SUMMARY.lock() {
+            *last = summary;
+        }
+    }
+}
+
+fn reload_chaos_settings(
+    mut requests: MessageReader<ConfigReloadRequested>,
+    mut diagnostics: ResMut<ConfigDiagnostics>,
+    mut settings: ResMut<ChaosSettings>,
+) {
+    if !requests.read().any(|request| request.is_for(CONFIG_PATH)) {
+        return;
+    }
+    if let Some(reloaded) = diagnostics.report_reload(CONFIG_PATH, ChaosSettings::load()) {
+        *settings = reloaded;
+    }
+}
+
+fn print_chaos_report(
+    mut exits: MessageReader<AppExit>,
+    report: Res<ChaosReport>,
+    settings: Res<ChaosSettings>,
+    log: Res<ChaosLog>,
+) {
+    if exits.read().next().is_some() && report.requested {
+        println!("{}", log.summary(settings.seed));
+    }
+}
+
+/// Prints the last summary before the default panic message, so the faults leading up to
+/// the panic are on screen.
+fn install_panic_report() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if let Ok(summary) = LAST_SUMMARY.lock() {
+            eprintln!("{summary}\nPanicked after the faults above:");
+        }
+        default_hook(info);
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn break_nothing(_: &mut World, _: &mut DailyRng) -> Option<String> {
+        None
+    }
+
+    fn count_frame(world: &mut World, _: &mut DailyRng) -> Option<String> {
+        let frame = world.resource::<ChaosLog>().frame();
+        Some(format!("hit on frame {frame}"))
+    }
+
+    fn chaos_app(seed: u64) -> App {
+        let mut app = App::new();
+        app.insert_resource(ChaosSettings {
+            enabled: true,
+            seed,
+            fault_chance: 0.5,
+            report_window_frames: 2,
+        })
+        .init_resource::<ChaosLog>()
+        .init_resource::<ChaosReport>()
+        .add_systems(Update, inject_chaos_faults);
+        app.register_chaos_fault("noop", break_nothing)
+            .register_chaos_fault("count", count_frame);
+        app
+    }
+
+    fn run(seed: u64) -> Vec<InjectedFault> {
+        let mut app = chaos_app(seed);
+        for _ in 0..200 {
+            app.update();
+        }
+        app.world().resource::<ChaosLog>().injected().to_vec()
+    }
+
+    #[test]
+    fn a_seed_replays_the_same_faults_on_the_same_frames() {
+        let first = run(7);
+        assert!(!first.is_empty());
+        assert_eq!(first, run(7));
+        assert_ne!(first, run(8));
+        assert!(first.iter().all(|fault| fault.name == "count"
+            && fault.detail == format!("hit on frame {}", fault.frame)));
+
+        let mut disabled = chaos_app(7);
+        disabled.world_mut().resource_mut::<ChaosSettings>().enabled = false;
+        disabled.update();
+        assert_eq!(disabled.world().resource::<ChaosLog>().frame(), 0);
+    }
+
+    #[test]
+    fn warnings_are_attributed_to_recent_faults_only() {
+        let mut log = ChaosLog::default();
+        for frame in [1, 5] {
+            log.frame = frame;
+            log.injected.push(InjectedFault {
+                frame,
+                name: "count",
+                detail: String::new(),
+                warnings_after: 0,
+            });
+        }
+        log.frame = 6;
+        log.attribute_warnings(3, 2);
+        assert_eq!(log.injected[0].warnings_after, 0);
+        assert_eq!(log.injected[1].warnings_after, 3);
+        assert!(log
+            .summary(7)
+            .starts_with("Chaos run with seed 7: 6 frames, 2 faults injected"));
+
+        let settings = ChaosSettings::parse("[chaos]\nenabled = true\nfault_chance = 4.0").unwrap();
+        assert!(settings.enabled);
+        assert_eq!(settings.fault_chance, 1.0);
+        assert_eq!(settings.seed, DEFAULT_SEED);
+        let mut faults = ChaosFaults::default();
+        faults.register("count", break_nothing);
+        faults.register("count", count_frame);
+        assert_eq!(faults.names().count(), 1);
+    }
+}
@@ -0,0 +1,182 @@
+//! Load diagnostics shared by every config file: what loaded, what fell back, and why.
+use std::time::SystemTime;
+
+use bevy::prelude::*;
+
+/// Outcome of the most recent attempt to load a config file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigLoadStatus {
+    Loaded,
+    /// The file was missing or invalid and compiled-in defaults (or the previous values, on
+    /// reload) are in use.
+    Fallback,
+}
+
+/// One config file's latest load result.
+#[derive(Debug, Clone)]
+pub struct ConfigLoadRecord {
+    pub path: String,
+    pub status: ConfigLoadStatus,
+    pub error: Option<String>,
+    pub timestamp: SystemTime,
+}
+
+/// Latest load result per config path, surfaced in-game by the config banner.
+#[derive(Resource, Debug, Default)]
+pub struct ConfigDiagnostics {
+    records: Vec<ConfigLoadRecord>,
+}
+
+impl ConfigDiagnostics {
+    /// Stores `record`, replacing any earlier result for the same path.
+    pub fn record(&mut self, record: ConfigLoadRecord) {
+        match self
+            .records
+            .iter_mut()
+            .find(|existing| existing.path == record.path)
+        {
+            Some(existing) => *existing = record,
+            None => self.records.push(record),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn get(&self, path: &str) -> Option<&ConfigLoadRecord> {
+        self.records.iter().find(|record| record.path == path)
+    }
+
+    /// Configs currently running on fallback values, in load order.
+    pub fn fallbacks(&self) -> impl Iterator<Item = &ConfigLoadRecord> {
+        self.records
+            .iter()
+            .filter(|record| record.status == ConfigLoadStatus::Fallback)
+    }
+
+    /// Records a reload attempt. Returns the new value on success; on failure the caller
+    /// keeps what it already has and the error is recorded for the banner.
+    pub fn report_reload<T>(&mut self, path: &str, result: Result<T, String>) -> Option<T> {
+        match result {
+            Ok(value) => {
+                info!("Reloaded {}", path);
+                self.record(loaded(path));
+                Some(value)
+            }
+            Err(error) => {
+                warn!(
+                    "Reload of {} failed ({}); keeping current values.",
+                    path, error
+                );
+                self.record(fallback(path, error));
+                None
+            }
+        }
+    }
+}
+
+/// Asks the plugin owning `path` to re-run its loader and swap in the result.
+#[derive(Message, Debug, Clone, PartialEq, Eq)]
+pub struct ConfigReloadRequested {
+    pub path: String,
+}
+
+impl ConfigReloadRequested {
+    /// True when this request targets `path`.
+    pub fn is_for(&self, path: &str) -> bool {
+        self.path == path
+    }
+}
+
+/// Records a startup load in the world's `ConfigDiagnostics`, logging and substituting
+/// `fallback_value()` when `result` is an error.
+pub fn report_config_result<T>(
+    world: &mut World,
+    path: &str,
+    result: Result<T, String>,
+    fallback_value: impl FnOnce() -> T,
+) -> T {
+    let mut diagnostics = world.get_resource_or_init::<ConfigDiagnostics>();
+    match result {
+        Ok(value) => {
+            diagnostics.record(loaded(path));
+            value
+        }
+        Err(error) => {
+            warn!(
+                "Failed to load {} ({}). Falling back to defaults.",
+                path, error
+            );
+            diagnostics.record(fallback(path, error));
+            fallback_value()
+        }
+    }
+}
+
+fn loaded(path: &str) -> ConfigLoadRecord {
+    ConfigLoadRecord {
+        path: path.to_string(),
+        status: ConfigLoadStatus::Loaded,
+        error: None,
+        timestamp: SystemTime::now(),
+    }
+}
+
+fn fallback(path: &str, error: String) -> ConfigLoadRecord {
+    ConfigLoadRecord {
+        path: path.to_string(),
+        status: ConfigLoadStatus::Fallback,
+        error: Some(error),
+        timestamp: SystemTime::now(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn startup_reports_record_loads_and_fallbacks() {
+        let mut world = World::new();
+        let loaded_value = report_config_result(&mut world, "config/a.toml", Ok(3), || 0);
+        let fallback_value = report_config_result(
+            &mut world,
+            "config/b.toml",
+            Err::<i32, _>("invalid type at line 2".to_string()),
+            || 7,
+        );
+
+        assert_eq!((loaded_value, fallback_value), (3, 7));
+        let diagnostics = world.resource::<ConfigDiagnostics>();
+        assert_eq!(
+            diagnostics.get("config/a.toml").map(|record| record.status),
+            Some(ConfigLoadStatus::Loaded)
+        );
+        let fallbacks: Vec<_> = diagnostics.fallbacks().collect();
+        assert_eq!(fallbacks.len(), 1);
+        assert_eq!(fallbacks[0].path, "config/b.toml");
+        assert_eq!(
+            fallbacks[0].error.as_deref(),
+            Some("invalid type at line 2")
+        );
+    }
+
+    #[test]
+    fn reloads_replace_the_previous_record_for_a_path() {
+        let mut diagnostics = ConfigDiagnostics::default();
+        diagnostics.record(fallback("config/a.toml", "missing".to_string()));
+
+        assert_eq!(
+            diagnostics.report_reload::<i32>("config/a.toml", Err("still broken".to_string())),
+            None
+        );
+        assert_eq!(
+            diagnostics
+                .get("config/a.toml")
+                .and_then(|record| record.error.as_deref()),
+            Some("still broken")
+        );
+
+        assert_eq!(diagnostics.report_reload("config/a.toml", Ok(5)), Some(5));
+        assert_eq!(diagnostics.fallbacks().count(), 0);
+        assert_eq!(diagnostics.records.len(), 1);
+    }
+}
@@ -0,0 +1,262 @@
+//! Window focus tracking. While the window is in the background the app updates at a
+//! reduced rate and cosmetic systems pause, but the simulation keeps running unless
+//! `config/window.toml` asks for a full pause.
+use std::{fs, path::Path, time::Duration};
+
+use bevy::{
+    prelude::*,
+    window::WindowFocused,
+    winit::{UpdateMode, WinitSettings},
+};
+use serde::Deserialize;
+
+use super::{
+    config::{ConfigDiagnostics, ConfigReloadRequested},
+    plugin::SimulationClock,
+};
+
+pub const CONFIG_PATH: &str = "config/window.toml";
+const MIN_UNFOCUSED_UPDATE_HZ: f32 = 0.1;
+
+#[derive(Debug, Clone, Deserialize, Default)]
+struct RawWindowConfig {
+    #[serde(default)]
+    focus: RawFocusSection,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+struct RawFocusSection {
+    unfocused_update_hz: f32,
+    pause_when_unfocused: bool,
+}
+
+impl Default for RawFocusSection {
+    fn default() -> Self {
+        Self {
+            unfocused_update_hz: 10.0,
+            pause_when_unfocused: false,
+        }
+    }
+}
+
+/// How the app behaves while its window does not have focus.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct FocusSettings {
+    /// Update rate cap while unfocused.
+    pub unfocused_update_hz: f32,
+    /// Freeze the simulation clock instead of letting the village carry on.
+    pub pause_when_unfocused: bool,
+}
+
+impl FocusSettings {
+    /// Reads and parses the `[focus]` section of `config/window.toml`.
+    pub fn load() -> Result<Self, String> {
+        let data = fs::read_to_string(Path::new(CONFIG_PATH))
+            .map_err(|err| format!("unable to read file: {err}"))?;
+        let raw = toml::from_str::<RawWindowConfig>(&data)
+            .map_err(|err| format!("invalid window config: {err}"))?;
+        Ok(raw.into())
+    }
+
+    /// Delay between unfocused updates. Never longer than `max_frame_delta`, so throttled
+    /// frames are not clamped and the simulation keeps its pace.
+    pub fn unfocused_wait(&self, max_frame_delta: Duration) -> Duration {
+        Duration::from_secs_f64(1.0 / f64::from(self.unfocused_update_hz)).min(max_frame_delta)
+    }
+}
+
+impl Default for FocusSettings {
+    fn default() -> Self {
+        RawWindowConfig::default().into()
+    }
+}
+
+impl From<RawWindowConfig> for FocusSettings {
+    fn from(value: RawWindowConfig) -> Self {
+        let focus = value.focus;
+        let unfocused_update_hz = if focus.unfocused_update_hz.is_finite() {
+            focus.unfocused_update_hz.max(MIN_UNFOCUSED_UPDATE_HZ)
+        } else {
+            RawFocusSection::default().unfocused_update_hz
+        };
+        Self {
+            unfocused_update_hz,
+            pause_when_unfocused: focus.pause_when_unfocused,
+        }
+    }
+}
+
+/// A change in window focus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusTransition {
+    Lost,
+    Regained,
+}
+
+/// Whether the game window currently has focus. Starts focused, since a freshly opened
+/// window normally does.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WindowFocusState {
+    focused: bool,
+}
+
+impl Default for WindowFocusState {
+    fn default() -> Self {
+        Self { focused: true }
+    }
+}
+
+impl WindowFocusState {
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    /// Records the latest focus report, returning the transition if it changed anything.
+    pub fn apply(&mut self, focused: bool) -> Option<FocusTransition> {
+        if self.focused == focused {
+            return None;
+        }
+        self.focused = focused;
+        Some(if focused {
+            FocusTransition::Regained
+        } else {
+            FocusTransition::Lost
+        })
+    }
+
+    /// False only while unfocused with `pause_when_unfocused` set.
+    pub fn simulation_running(&self, settings: &FocusSettings) -> bool {
+        self.focused || !settings.pause_when_unfocused
+    }
+}
+
+/// Run condition for cosmetic systems (lighting, carried-goods bobbing, facing partners),
+/// which have nothing to show while the window is in the background.
+pub fn window_focused(state: Res<WindowFocusState>) -> bool {
+    state.is_focused()
+}
+
+/// Folds window focus events into `WindowFocusState`. Runs in `PreUpdate`, so on refocus
+/// every cosmetic system catches up in that same frame.
+pub fn track_window_focus(
+    mut events: MessageReader<WindowFocused>,
+    mut state: ResMut<WindowFocusState>,
+    settings: Res<FocusSettings>,
+) {
+    for event in events.read() {
+        match state.apply(event.focused) {
+            Some(FocusTransition::Lost) if settings.pause_when_unfocused => {
+                info!("Window lost focus; simulation paused")
+            }
+            Some(FocusTransition::Lost) => info!(
+                "Window lost focus; throttling to {:.1} updates per second",
+                settings.unfocused_update_hz
+            ),
+            Some(FocusTransition::Regained) => info!("Window focused; full update rate restored"),
+            None => {}
+        }
+    }
+}
+
+/// Pushes the unfocused update rate into winit whenever the settings change.
+pub fn apply_focus_throttle(
+    settings: Res<FocusSettings>,
+    clock: Res<SimulationClock>,
+    winit: Option<ResMut<WinitSettings>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    if let Some(mut winit) = winit {
+        winit.unfocused_mode =
+            UpdateMode::reactive_low_power(settings.unfocused_wait(clock.max_frame_delta()));
+    }
+}
+
+/// Re-reads `config/window.toml` on request, swapping the settings in when it parses.
+pub fn reload_focus_settings(
+    mut requests: MessageReader<ConfigReloadRequested>,
+    mut diagnostics: ResMut<ConfigDiagnostics>,
+    mut settings: ResMut<FocusSettings>,
+) {
+    if !requests.read().any(|request| request.is_for(CONFIG_PATH)) {
+        return;
+    }
+    if let Some(reloaded) = diagnostics.report_reload(CONFIG_PATH, FocusSettings::load()) {
+        *settings = reloaded;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn focus_reports_only_real_transitions() {
+        let mut state = WindowFocusState::default();
+        assert!(state.is_focused());
+        assert_eq!(state.apply(true), None);
+        assert_eq!(state.apply(false), Some(FocusTransition::Lost));
+        assert_eq!(state.apply(false), None);
+        assert_eq!(state.apply(true), Some(FocusTransition::Regained));
+
+        let keep_running = FocusSettings::default();
+        let pause = FocusSettings {
+            pause_when_unfocused: true,
+            ..FocusSettings::default()
+        };
+        assert!(state.simulation_running(&pause));
+        state.apply(false);
+        assert!(state.simulation_running(&keep_running));
+        assert!(!state.simulation_running(&pause));
+    }
+
+    #[test]
+    fn cosmetic_systems_pause_and_resume_within_a_frame() {
+        #[derive(Resource, Default)]
+        struct Ticks(u32);
+
+        let mut app = App::new();
+        app.init_resource::<WindowFocusState>()
+            .init_resource::<FocusSettings>()
+            .init_resource::<Ticks>()
+            .add_message::<WindowFocused>()
+            .add_systems(PreUpdate, track_window_focus)
+            .add_systems(
+                Update,
+                (|mut ticks: ResMut<Ticks>| ticks.0 += 1).run_if(window_focused),
+            );
+        let focus = |app: &mut App, focused: bool| {
+            app.world_mut().write_message(WindowFocused {
+                window: Entity::PLACEHOLDER,
+                focused,
+            });
+            app.update();
+        };
+
+        app.update();
+        focus(&mut app, false);
+        app.update();
+        assert_eq!(app.world().resource::<Ticks>().0, 1);
+
+        focus(&mut app, true);
+        assert_eq!(app.world().resource::<Ticks>().0, 2, "refocus frame runs");
+    }
+
+    #[test]
+    fn unfocused_wait_never_exceeds_the_frame_clamp() {
+        let clamp = Duration::from_millis(250);
+        assert_eq!(
+            FocusSettings::default().unfocused_wait(clamp),
+            Duration::from_millis(100)
+        );
+        let slow: FocusSettings = toml::from_str::<RawWindowConfig>(
+            "[focus]\nunfocused_update_hz = 0.0\npause_when_unfocused = true",
+        )
+        .unwrap()
+        .into();
+        assert_eq!(slow.unfocused_update_hz, MIN_UNFOCUSED_UPDATE_HZ);
+        assert_eq!(slow.unfocused_wait(clamp), clamp);
+    }
+}
@@ -0,0 +1,383 @@
+//! Text formatting for quantities and times of day. Prompts are rendered from the request
+//! alone, often off the main thread, so the prompt-facing helpers (`format_quantity`,
+//! `spoken_time`, `relative_day_phrase`) are plain functions; `FormatSettings` from
+//! `config/locale.toml` adds the player's clock preference for on-screen times.
+use std::{fs, path::Path};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use super::config::{ConfigDiagnostics, ConfigReloadRequested};
+use crate::world::time::{format_clock_time, minute_of_day};
+
+pub const CONFIG_PATH: &str = "config/locale.toml";
+
+/// Words whose plural does not follow the suffix rules in `pluralize`.
+const IRREGULAR_PLURALS: &[(&str, &str)] = &[
+    ("barley", "barley"),
+    ("knife", "knives"),
+    ("loaf", "loaves"),
+    ("sheaf", "sheaves"),
+    ("sheep", "sheep"),
+];
+
+/// Spoken phrase for each stretch of the day, keyed by its first minute. Minutes before the
+/// first entry belong to the last one, so "around midnight" spans the day wrap.
+const SPOKEN_TIMES: &[(u32, &str)] = &[
+    (60, "in the dead of night"),
+    (5 * 60, "early in the morning"),
+    (8 * 60, "mid-morning"),
+    (11 * 60, "around midday"),
+    (13 * 60, "early in the afternoon"),
+    (15 * 60, "late in the afternoon"),
+    (17 * 60, "early in the evening"),
+    (20 * 60, "late in the evening"),
+    (23 * 60, "around midnight"),
+];
+
+/// First minute of the morning, afternoon, and evening for `relative_day_phrase`; earlier
+/// minutes are the small hours.
+const MORNING_START_MINUTE: u32 = 5 * 60;
+const AFTERNOON_START_MINUTE: u32 = 12 * 60;
+const EVENING_START_MINUTE: u32 = 17 * 60;
+/// Oldest event, in days, that still gets a relative phrase instead of its day number.
+const RELATIVE_DAY_LIMIT: u64 = 7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DayPart {
+    SmallHours,
+    Morning,
+    Afternoon,
+    Evening,
+}
+
+impl DayPart {
+    fn of(fraction: f32) -> Self {
+        match minute_of_day(fraction) {
+            minute if minute < MORNING_START_MINUTE => Self::SmallHours,
+            minute if minute < AFTERNOON_START_MINUTE => Self::Morning,
+            minute if minute < EVENING_START_MINUTE => Self::Afternoon,
+            _ => Self::Evening,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+struct RawLocaleConfig {
+    #[serde(default)]
+    format: RawFormatSection,
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(default)]
+struct RawFormatSection {
+    clock: ClockFormat,
+}
+
+/// How on-screen clock times are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+pub enum ClockFormat {
+    /// "13:20"
+    #[default]
+    #[serde(rename = "24h")]
+    TwentyFourHour,
+    /// "1:20 PM"
+    #[serde(rename = "12h")]
+    TwelveHour,
+}
+
+/// Player-facing formatting preferences.
+#[derive(Resource, Debug, Clone, PartialEq, Default)]
+pub struct FormatSettings {
+    pub clock: ClockFormat,
+}
+
+impl FormatSettings {
+    /// Reads and parses `config/locale.toml`.
+    pub fn load() -> Result<Self, String> {
+        let data = fs::read_to_string(Path::new(CONFIG_PATH))
+            .map_err(|err| format!("unable to read file: {err}"))?;
+        let raw = toml::from_str::<RawLocaleConfig>(&data)
+            .map_err(|err| format!("invalid locale config: {err}"))?;
+        Ok(Self {
+            clock: raw.format.clock,
+        })
+    }
+
+    /// Clock time of a day fraction for on-screen text; out-of-range input wraps. Prompts
+    /// use `spoken_time` instead.
+    pub fn format_time(&self, fraction: f32) -> String {
+        match self.clock {
+            ClockFormat::TwentyFourHour => format_clock_time(fraction),
+            ClockFormat::TwelveHour => {
+                let minute = minute_of_day(fraction);
+                let (hour, minute) = (minute / 60, minute % 60);
+                let suffix = if hour < 12 { "AM" } else { "PM" };
+                let hour = match hour % 12 {
+                    0 => 12,
+                    hour => hour,
+                };
+                format!("{hour}:{minute:02} {suffix}")
+            }
+        }
+    }
+}
+
+/// "1 tool crate", "3 tool crates": trade good labels are a noun then a unit, and only the
+/// unit (the last word) takes the plural.
+pub fn format_quantity(label: &str, quantity: u32) -> String {
+    if quantity == 1 {
+        return format!("1 {label}");
+    }
+    match label.rsplit_once(' ') {
+        Some((noun, unit)) => format!("{quantity} {noun} {}", pluralize(unit)),
+        None => format!("{quantity} {}", pluralize(label)),
+    }
+}
+
+/// English plural of a single word.
+pub fn pluralize(word: &str) -> String {
+    if let Some((_, plural)) = IRREGULAR_PLURALS
+        .iter()
+        .find(|(singular, _)| singular.eq_ignore_ascii_case(word))
+    {
+        return (*plural).to_string();
+    }
+    if ["s", "x", "z", "ch", "sh"]
+        .iter()
+        .any(|suffix| word.ends_with(suffix))
+    {
+        return format!("{word}es");
+    }
+    if let Some(stem) = word.strip_suffix('y') {
+        if stem
+            .chars()
+            .last()
+            .is_some_and(|c| !"aeiou".contains(c.to_ascii_lowercase()))
+        {
+            return format!("{stem}ies");
+        }
+    }
+    format!("{word}s")
+}
+
+/// Fuzzy phrase for a day fraction, e.g. "around midday"; out-of-range input wraps.
+pub fn spoken_time(fraction: f32) -> &'static str {
+    let minute = minute_of_day(fraction);
+    SPOKEN_TIMES
+        .iter()
+        .rev()
+        .find(|(start, _)| *start <= minute)
+        .or(SPOKEN_TIMES.last())
+        .map_or("", |(_, phrase)| *phrase)
+}
+
+/// How long ago `event_day` was as of `now_day` at `now_fraction`, in the words a villager
+/// would use: "this morning", "yesterday evening", "a few days ago". `event_fraction` narrows
+/// same-day and previous-day events to a part of the day when known. Events more than a
+/// week old, or dated after `now_day`, fall back to "on day N".
+pub fn relative_day_phrase(
+    event_day: u64,
+    event_fraction: Option<f32>,
+    now_day: u64,
+    now_fraction: f32,
+) -> String {
+    let Some(days_ago) = now_day
+        .checked_sub(event_day)
+        .filter(|days| *days <= RELATIVE_DAY_LIMIT)
+    else {
+        return format!("on day {event_day}");
+    };
+    let event_part = event_fraction.map(DayPart::of);
+    let now_part = DayPart::of(now_fraction);
+    let phrase = match (days_ago, event_part) {
+        (0, Some(part)) if part != now_part => match part {
+            DayPart::SmallHours => "last night",
+            DayPart::Morning => "this morning",
+            DayPart::Afternoon => "this afternoon",
+            DayPart::Evening => "this evening",
+        },
+        (0, _) => "earlier today",
+        (1, Some(DayPart::Evening)) if now_part == DayPart::SmallHours => "last night",
+        (1, Some(DayPart::Morning)) => "yesterday morning",
+        (1, Some(DayPart::Afternoon)) => "yesterday afternoon",
+        (1, Some(DayPart::Evening)) => "yesterday evening",
+        (1, _) => "yesterday",
+        (2, _) => "the day before yesterday",
+        (RELATIVE_DAY_LIMIT, _) => "a week ago",
+        _ => "a few days ago",
+    };
+    phrase.to_string()
+}
+
+/// Re-reads `config/locale.toml` on request.
+pub fn reload_format_settings(
+    mut requests: MessageReader<ConfigReloadRequested>,
+    mut diagnostics: ResMut<ConfigDiagnostics>,
+    mut settings: ResMut<FormatSettings>,
+) {
+    if !requests.read().any(|request| request.is_for(CONFIG_PATH)) {
+        return;
+    }
+    if let Some(reloaded) = diagnostics.report_reload(CONFIG_PATH, FormatSettings::load()) {
+        *settings = reloaded;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(hour: u32, minute: u32) -> f32 {
+        (hour * 60 + minute) as f32 / 1440.0 + 1e-5
+    }
+
+    #[test]
+    fn quantities_pluralize_the_unit() {
+        assert_eq!(format_quantity("tool crate", 1), "1 tool crate");
+        assert_eq!(format_quantity("tool crate", 3), "3 tool crates");
+        assert_eq!(format_quantity("ale cask", 0), "0 ale casks");
+        assert_eq!(format_quantity("loaf", 2), "2 loaves");
+
+        for (singular, plural) in [
+            ("crate", "crates"),
+            ("box", "boxes"),
+            ("bunch", "bunches"),
+            ("berry", "berries"),
+            ("day", "days"),
+            ("Knife", "knives"),
+            ("sheep", "sheep"),
+        ] {
+            assert_eq!(pluralize(singular), plural);
+        }
+    }
+
+    #[test]
+    fn spoken_times_bucket_the_day_and_wrap_at_midnight() {
+        assert_eq!(spoken_time(at(12, 0)), "around midday");
+        assert_eq!(spoken_time(at(11, 59)), "around midday");
+        assert_eq!(spoken_time(at(10, 59)), "mid-morning");
+        assert_eq!(spoken_time(at(22, 59)), "late in the evening");
+        assert_eq!(spoken_time(at(23, 0)), "around midnight");
+        assert_eq!(spoken_time(at(23, 59)), "around midnight");
+        assert_eq!(spoken_time(0.99999), "around midnight");
+        assert_eq!(spoken_time(0.0), "around midnight");
+        assert_eq!(spoken_time(at(0, 59)), "around midnight");
+        assert_eq!(spoken_time(at(1, 0)), "in the dead of night");
+        assert_eq!(spoken_time(-0.25), "early in the evening");
+    }
+
+    #[test]
+    fn same_day_events_name_the_part_of_the_day() {
+        let evening = at(19, 30);
+        assert_eq!(
+            relative_day_phrase(4, Some(at(9, 0)), 4, evening),
+            "this morning"
+        );
+        assert_eq!(
+            relative_day_phrase(4, Some(at(4, 59)), 4, evening),
+            "last night"
+        );
+        assert_eq!(
+            relative_day_phrase(4, Some(at(5, 0)), 4, evening),
+            "this morning"
+        );
+        assert_eq!(
+            relative_day_phrase(4, Some(at(11, 59)), 4, evening),
+            "this morning"
+        );
+        assert_eq!(
+            relative_day_phrase(4, Some(at(12, 0)), 4, evening),
+            "this afternoon"
+        );
+        assert_eq!(
+            relative_day_phrase(4, Some(at(16, 59)), 4, evening),
+            "this afternoon"
+        );
+        assert_eq!(
+            relative_day_phrase(4, Some(at(17, 0)), 4, evening),
+            "earlier today"
+        );
+        assert_eq!(
+            relative_day_phrase(4, Some(at(8, 0)), 4, at(10, 0)),
+            "earlier today"
+        );
+        assert_eq!(relative_day_phrase(4, None, 4, evening), "earlier today");
+        assert_eq!(
+            relative_day_phrase(4, Some(at(20, 0)), 4, at(23, 0)),
+            "earlier today"
+        );
+        assert_eq!(
+            relative_day_phrase(4, Some(at(12, 0)), 4, at(23, 59)),
+            "this afternoon"
+        );
+    }
+
+    #[test]
+    fn previous_day_events_wrap_at_midnight() {
+        assert_eq!(
+            relative_day_phrase(3, Some(at(21, 0)), 4, at(0, 30)),
+            "last night"
+        );
+        assert_eq!(
+            relative_day_phrase(3, Some(at(21, 0)), 4, at(4, 59)),
+            "last night"
+        );
+        assert_eq!(
+            relative_day_phrase(3, Some(at(21, 0)), 4, at(5, 0)),
+            "yesterday evening"
+        );
+        assert_eq!(
+            relative_day_phrase(3, Some(at(17, 0)), 4, at(12, 0)),
+            "yesterday evening"
+        );
+        assert_eq!(
+            relative_day_phrase(3, Some(at(16, 59)), 4, at(12, 0)),
+            "yesterday afternoon"
+        );
+        assert_eq!(
+            relative_day_phrase(3, Some(at(6, 0)), 4, at(0, 10)),
+            "yesterday morning"
+        );
+        assert_eq!(
+            relative_day_phrase(3, Some(at(2, 0)), 4, at(12, 0)),
+            "yesterday"
+        );
+        assert_eq!(relative_day_phrase(3, None, 4, at(0, 10)), "yesterday");
+    }
+
+    #[test]
+    fn older_events_blur_and_fall_back_to_day_numbers_past_a_week() {
+        let noon = at(12, 0);
+        assert_eq!(
+            relative_day_phrase(8, Some(noon), 10, noon),
+            "the day before yesterday"
+        );
+        for event_day in 4..=7 {
+            assert_eq!(
+                relative_day_phrase(event_day, Some(noon), 10, noon),
+                "a few days ago"
+            );
+        }
+        assert_eq!(relative_day_phrase(3, Some(noon), 10, noon), "a week ago");
+        assert_eq!(relative_day_phrase(3, None, 10, 0.0), "a week ago");
+        assert_eq!(relative_day_phrase(2, Some(noon), 10, noon), "on day 2");
+        assert_eq!(relative_day_phrase(0, None, 100, noon), "on day 0");
+        assert_eq!(relative_day_phrase(11, Some(noon), 10, noon), "on day 11");
+    }
+
+    #[test]
+    fn clock_times_follow_the_configured_format() {
+        let settings = FormatSettings::default();
+        assert_eq!(settings.format_time(at(13, 20)), "13:20");
+
+        let raw: RawLocaleConfig = toml::from_str("[format]\nclock = \"12h\"").unwrap();
+        let twelve = FormatSettings {
+            clock: raw.format.clock,
+        };
+        assert_eq!(twelve.format_time(at(13, 20)), "1:20 PM");
+        assert_eq!(twelve.format_time(0.0), "12:00 AM");
+        assert_eq!(twelve.format_time(0.5), "12:00 PM");
+        assert_eq!(twelve.format_time(at(9, 5)), "9:05 AM");
+    }
+}
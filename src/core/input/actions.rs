@@ -0,0 +1,179 @@
+//! The actions the player can bind, with their config names and the default key or
+//! button for each, debug commands included.
+use bevy::prelude::*;
+
+use super::InputBinding;
+
+/// Everything the player can do with a key or mouse button.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum InputAction {
+    MoveForward,
+    MoveBackward,
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    MoveFast,
+    CameraLook,
+    Interact,
+    SelectNpc,
+    FollowSelected,
+    Deselect,
+    ToggleSubtitles,
+    DialogueProbe,
+    DialogueProbeSendModifier,
+    DialogueProbeTopicModifier,
+    DialogueQueueDump,
+    DialogueTraceDump,
+    DialogueTraceOlderModifier,
+    RetryDeadLetters,
+    CycleDialogueProvider,
+    SkipDay,
+    SkipYearModifier,
+    ReloadConfig,
+    CycleUiVisibility,
+    CycleSchedule,
+    PrintBindings,
+    ToggleConsole,
+    ToggleMinimap,
+    ToggleJournal,
+}
+
+/// Actions sharing an input only conflict within the same group, so a held camera key and
+/// a one-shot command may both use Shift. Modifiers never conflict: each is only read
+/// together with its own command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum ActionGroup {
+    Camera,
+    Command,
+    Modifier,
+}
+
+impl InputAction {
+    pub const ALL: [InputAction; 30] = [
+        Self::MoveForward,
+        Self::MoveBackward,
+        Self::MoveLeft,
+        Self::MoveRight,
+        Self::MoveUp,
+        Self::MoveDown,
+        Self::MoveFast,
+        Self::CameraLook,
+        Self::Interact,
+        Self::SelectNpc,
+        Self::FollowSelected,
+        Self::Deselect,
+        Self::ToggleSubtitles,
+        Self::DialogueProbe,
+        Self::DialogueProbeSendModifier,
+        Self::DialogueProbeTopicModifier,
+        Self::DialogueQueueDump,
+        Self::DialogueTraceDump,
+        Self::DialogueTraceOlderModifier,
+        Self::RetryDeadLetters,
+        Self::CycleDialogueProvider,
+        Self::SkipDay,
+        Self::SkipYearModifier,
+        Self::ReloadConfig,
+        Self::CycleUiVisibility,
+        Self::CycleSchedule,
+        Self::PrintBindings,
+        Self::ToggleConsole,
+        Self::ToggleMinimap,
+        Self::ToggleJournal,
+    ];
+
+    /// Key used for the action in `config/input.toml`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::MoveForward => "move_forward",
+            Self::MoveBackward => "move_backward",
+            Self::MoveLeft => "move_left",
+            Self::MoveRight => "move_right",
+            Self::MoveUp => "move_up",
+            Self::MoveDown => "move_down",
+            Self::MoveFast => "move_fast",
+            Self::CameraLook => "camera_look",
+            Self::Interact => "interact",
+            Self::SelectNpc => "select_npc",
+            Self::FollowSelected => "follow_selected",
+            Self::Deselect => "deselect",
+            Self::ToggleSubtitles => "toggle_subtitles",
+            Self::DialogueProbe => "dialogue_probe",
+            Self::DialogueProbeSendModifier => "dialogue_probe_send_modifier",
+            Self::DialogueProbeTopicModifier => "dialogue_probe_topic_modifier",
+            Self::DialogueQueueDump => "dialogue_queue_dump",
+            Self::DialogueTraceDump => "dialogue_trace_dump",
+            Self::DialogueTraceOlderModifier => "dialogue_trace_older_modifier",
+            Self::RetryDeadLetters => "retry_dead_letters",
+            Self::CycleDialogueProvider => "cycle_dialogue_provider",
+            Self::SkipDay => "skip_day",
+            Self::SkipYearModifier => "skip_year_modifier",
+            Self::ReloadConfig => "reload_config",
+            Self::CycleUiVisibility => "cycle_ui_visibility",
+            Self::CycleSchedule => "cycle_schedule",
+            Self::PrintBindings => "print_bindings",
+            Self::ToggleConsole => "toggle_console",
+            Self::ToggleMinimap => "toggle_minimap",
+            Self::ToggleJournal => "toggle_journal",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.name() == name)
+    }
+
+    pub fn default_binding(self) -> InputBinding {
+        use InputBinding::{Key, Mouse};
+        match self {
+            Self::MoveForward => Key(KeyCode::KeyW),
+            Self::MoveBackward => Key(KeyCode::KeyS),
+            Self::MoveLeft => Key(KeyCode::KeyA),
+            Self::MoveRight => Key(KeyCode::KeyD),
+            Self::MoveUp => Key(KeyCode::Space),
+            Self::MoveDown => Key(KeyCode::ShiftLeft),
+            Self::MoveFast => Key(KeyCode::ControlLeft),
+            Self::CameraLook => Mouse(MouseButton::Right),
+            Self::Interact => Key(KeyCode::KeyE),
+            Self::SelectNpc => Mouse(MouseButton::Left),
+            Self::FollowSelected => Key(KeyCode::KeyF),
+            Self::Deselect => Key(KeyCode::Escape),
+            Self::ToggleSubtitles => Key(KeyCode::F6),
+            Self::DialogueProbe => Key(KeyCode::F7),
+            Self::DialogueProbeSendModifier => Key(KeyCode::ShiftLeft),
+            Self::DialogueProbeTopicModifier => Key(KeyCode::ControlLeft),
+            Self::DialogueQueueDump => Key(KeyCode::F5),
+            Self::DialogueTraceDump => Key(KeyCode::F2),
+            Self::DialogueTraceOlderModifier => Key(KeyCode::ShiftLeft),
+            Self::RetryDeadLetters => Key(KeyCode::F3),
+            Self::CycleDialogueProvider => Key(KeyCode::F4),
+            Self::SkipDay => Key(KeyCode::F9),
+            Self::SkipYearModifier => Key(KeyCode::ShiftLeft),
+            Self::ReloadConfig => Key(KeyCode::F10),
+            Self::CycleUiVisibility => Key(KeyCode::F11),
+            Self::CycleSchedule => Key(KeyCode::F12),
+            Self::PrintBindings => Key(KeyCode::F1),
+            Self::ToggleConsole => Key(KeyCode::Backquote),
+            Self::ToggleMinimap => Key(KeyCode::KeyM),
+            Self::ToggleJournal => Key(KeyCode::KeyJ),
+        }
+    }
+
+    pub(super) fn group(self) -> ActionGroup {
+        match self {
+            Self::MoveForward
+            | Self::MoveBackward
+            | Self::MoveLeft
+            | Self::MoveRight
+            | Self::MoveUp
+            | Self::MoveDown
+            | Self::MoveFast
+            | Self::CameraLook => ActionGroup::Camera,
+            Self::SkipYearModifier
+            | Self::DialogueProbeSendModifier
+            | Self::DialogueProbeTopicModifier
+            | Self::DialogueTraceOlderModifier => ActionGroup::Modifier,
+            _ => ActionGroup::Command,
+        }
+    }
+}
@@ -0,0 +1,352 @@
+//! The binding table: inputs nameable in `config/input.toml`, and the resource holding
+//! each action's current binding after unknown names and conflicts are dealt with.
+use std::{collections::BTreeMap, fmt, fs, path::Path};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use super::{
+    actions::{ActionGroup, InputAction},
+    CONFIG_PATH,
+};
+
+/// A key or mouse button an action is bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputBinding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+}
+
+/// Names accepted in `config/input.toml`, matching Bevy's `KeyCode` variants.
+const NAMED_INPUTS: &[(&str, InputBinding)] = {
+    use InputBinding::{Key, Mouse};
+    &[
+        ("KeyA", Key(KeyCode::KeyA)),
+        ("KeyB", Key(KeyCode::KeyB)),
+        ("KeyC", Key(KeyCode::KeyC)),
+        ("KeyD", Key(KeyCode::KeyD)),
+        ("KeyE", Key(KeyCode::KeyE)),
+        ("KeyF", Key(KeyCode::KeyF)),
+        ("KeyG", Key(KeyCode::KeyG)),
+        ("KeyH", Key(KeyCode::KeyH)),
+        ("KeyI", Key(KeyCode::KeyI)),
+        ("KeyJ", Key(KeyCode::KeyJ)),
+        ("KeyK", Key(KeyCode::KeyK)),
+        ("KeyL", Key(KeyCode::KeyL)),
+        ("KeyM", Key(KeyCode::KeyM)),
+        ("KeyN", Key(KeyCode::KeyN)),
+        ("KeyO", Key(KeyCode::KeyO)),
+        ("KeyP", Key(KeyCode::KeyP)),
+        ("KeyQ", Key(KeyCode::KeyQ)),
+        ("KeyR", Key(KeyCode::KeyR)),
+        ("KeyS", Key(KeyCode::KeyS)),
+        ("KeyT", Key(KeyCode::KeyT)),
+        ("KeyU", Key(KeyCode::KeyU)),
+        ("KeyV", Key(KeyCode::KeyV)),
+        ("KeyW", Key(KeyCode::KeyW)),
+        ("KeyX", Key(KeyCode::KeyX)),
+        ("KeyY", Key(KeyCode::KeyY)),
+        ("KeyZ", Key(KeyCode::KeyZ)),
+        ("Digit0", Key(KeyCode::Digit0)),
+        ("Digit1", Key(KeyCode::Digit1)),
+        ("Digit2", Key(KeyCode::Digit2)),
+        ("Digit3", Key(KeyCode::Digit3)),
+        ("Digit4", Key(KeyCode::Digit4)),
+        ("Digit5", Key(KeyCode::Digit5)),
+        ("Digit6", Key(KeyCode::Digit6)),
+        ("Digit7", Key(KeyCode::Digit7)),
+        ("Digit8", Key(KeyCode::Digit8)),
+        ("Digit9", Key(KeyCode::Digit9)),
+        ("F1", Key(KeyCode::F1)),
+        ("F2", Key(KeyCode::F2)),
+        ("F3", Key(KeyCode::F3)),
+        ("F4", Key(KeyCode::F4)),
+        ("F5", Key(KeyCode::F5)),
+        ("F6", Key(KeyCode::F6)),
+        ("F7", Key(KeyCode::F7)),
+        ("F8", Key(KeyCode::F8)),
+        ("F9", Key(KeyCode::F9)),
+        ("F10", Key(KeyCode::F10)),
+        ("F11", Key(KeyCode::F11)),
+        ("F12", Key(KeyCode::F12)),
+        ("Escape", Key(KeyCode::Escape)),
+        ("Space", Key(KeyCode::Space)),
+        ("Enter", Key(KeyCode::Enter)),
+        ("Tab", Key(KeyCode::Tab)),
+        ("Backspace", Key(KeyCode::Backspace)),
+        ("Backquote", Key(KeyCode::Backquote)),
+        ("ShiftLeft", Key(KeyCode::ShiftLeft)),
+        ("ShiftRight", Key(KeyCode::ShiftRight)),
+        ("ControlLeft", Key(KeyCode::ControlLeft)),
+        ("ControlRight", Key(KeyCode::ControlRight)),
+        ("AltLeft", Key(KeyCode::AltLeft)),
+        ("AltRight", Key(KeyCode::AltRight)),
+        ("ArrowUp", Key(KeyCode::ArrowUp)),
+        ("ArrowDown", Key(KeyCode::ArrowDown)),
+        ("ArrowLeft", Key(KeyCode::ArrowLeft)),
+        ("ArrowRight", Key(KeyCode::ArrowRight)),
+        ("MouseLeft", Mouse(MouseButton::Left)),
+        ("MouseRight", Mouse(MouseButton::Right)),
+        ("MouseMiddle", Mouse(MouseButton::Middle)),
+    ]
+};
+
+impl InputBinding {
+    pub fn from_name(name: &str) -> Option<Self> {
+        NAMED_INPUTS
+            .iter()
+            .find(|(candidate, _)| *candidate == name)
+            .map(|(_, binding)| *binding)
+    }
+
+    pub fn name(self) -> &'static str {
+        NAMED_INPUTS
+            .iter()
+            .find(|(_, binding)| *binding == self)
+            .map_or("?", |(name, _)| name)
+    }
+}
+
+impl fmt::Display for InputBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Something in `config/input.toml` that was ignored or overridden.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BindingIssue {
+    UnknownAction(String),
+    UnknownInput {
+        action: InputAction,
+        value: String,
+    },
+    /// The actions were bound to the same input and were reset to their defaults.
+    Conflict {
+        input: InputBinding,
+        actions: Vec<InputAction>,
+    },
+}
+
+impl fmt::Display for BindingIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownAction(name) => write!(f, "unknown action '{name}'"),
+            Self::UnknownInput { action, value } => {
+                write!(f, "{} is bound to unknown input '{value}'", action.name())
+            }
+            Self::Conflict { input, actions } => {
+                let names: Vec<&str> = actions.iter().map(|action| action.name()).collect();
+                write!(
+                    f,
+                    "{} share {input}; using their defaults",
+                    names.join(" and ")
+                )
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+struct RawInputConfig {
+    #[serde(default)]
+    bindings: BTreeMap<String, String>,
+}
+
+/// Current binding for every action.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct InputBindings {
+    bindings: BTreeMap<InputAction, InputBinding>,
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        Self {
+            bindings: InputAction::ALL
+                .into_iter()
+                .map(|action| (action, action.default_binding()))
+                .collect(),
+        }
+    }
+}
+
+impl InputBindings {
+    /// Reads and parses `config/input.toml`, logging anything it had to ignore.
+    pub fn load() -> Result<Self, String> {
+        let data = fs::read_to_string(Path::new(CONFIG_PATH))
+            .map_err(|err| format!("unable to read file: {err}"))?;
+        let (bindings, issues) = Self::parse(&data)?;
+        for issue in &issues {
+            warn!("{CONFIG_PATH}: {issue}");
+        }
+        Ok(bindings)
+    }
+
+    /// Parses a bindings file. Unknown actions and inputs are skipped, and actions left
+    /// sharing an input with another in their group fall back to their defaults.
+    pub fn parse(data: &str) -> Result<(Self, Vec<BindingIssue>), String> {
+        let raw = toml::from_str::<RawInputConfig>(data)
+            .map_err(|err| format!("invalid input config: {err}"))?;
+        let mut bindings = Self::default();
+        let mut issues = Vec::new();
+
+        for (name, value) in raw.bindings {
+            let Some(action) = InputAction::from_name(&name) else {
+                issues.push(BindingIssue::UnknownAction(name));
+                continue;
+            };
+            match InputBinding::from_name(&value) {
+                Some(binding) => {
+                    bindings.bindings.insert(action, binding);
+                }
+                None => issues.push(BindingIssue::UnknownInput { action, value }),
+            }
+        }
+
+        // Restoring a default can collide with another rebound action, so repeat until
+        // stable; the defaults themselves never conflict.
+        while let Some((input, actions)) = bindings.first_conflict() {
+            for action in &actions {
+                bindings.bindings.insert(*action, action.default_binding());
+            }
+            issues.push(BindingIssue::Conflict { input, actions });
+        }
+        Ok((bindings, issues))
+    }
+
+    pub fn binding(&self, action: InputAction) -> InputBinding {
+        self.bindings
+            .get(&action)
+            .copied()
+            .unwrap_or_else(|| action.default_binding())
+    }
+
+    /// `action = input` lines in action order, for the print-bindings command.
+    pub fn describe(&self) -> String {
+        InputAction::ALL
+            .into_iter()
+            .map(|action| format!("{} = {}", action.name(), self.binding(action)))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// First input shared by rebound actions of the same group, with every action sharing
+    /// it that is not on its default.
+    fn first_conflict(&self) -> Option<(InputBinding, Vec<InputAction>)> {
+        for (index, first) in InputAction::ALL.iter().enumerate() {
+            if first.group() == ActionGroup::Modifier {
+                continue;
+            }
+            let input = self.binding(*first);
+            let sharing: Vec<InputAction> = InputAction::ALL[index..]
+                .iter()
+                .copied()
+                .filter(|other| other.group() == first.group() && self.binding(*other) == input)
+                .collect();
+            if sharing.len() < 2 {
+                continue;
+            }
+            let rebound: Vec<InputAction> = sharing
+                .iter()
+                .copied()
+                .filter(|action| self.binding(*action) != action.default_binding())
+                .collect();
+            return Some((input, if rebound.is_empty() { sharing } else { rebound }));
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_cover_every_action_without_conflicts() {
+        let (bindings, issues) = InputBindings::parse("").unwrap();
+        assert!(issues.is_empty());
+        assert_eq!(bindings, InputBindings::default());
+        for action in InputAction::ALL {
+            assert_eq!(InputAction::from_name(action.name()), Some(action));
+            let binding = action.default_binding();
+            assert_eq!(InputBinding::from_name(binding.name()), Some(binding));
+        }
+    }
+
+    #[test]
+    fn file_rebinds_actions_and_reports_unknown_names() {
+        let (bindings, issues) = InputBindings::parse(
+            r#"
+            [bindings]
+            interact = "KeyG"
+            camera_look = "MouseMiddle"
+            teleport = "KeyT"
+            deselect = "Hyper"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            bindings.binding(InputAction::Interact),
+            InputBinding::Key(KeyCode::KeyG)
+        );
+        assert_eq!(
+            bindings.binding(InputAction::CameraLook),
+            InputBinding::Mouse(MouseButton::Middle)
+        );
+        assert_eq!(
+            bindings.binding(InputAction::Deselect),
+            InputBinding::Key(KeyCode::Escape)
+        );
+        assert_eq!(
+            issues,
+            [
+                BindingIssue::UnknownInput {
+                    action: InputAction::Deselect,
+                    value: "Hyper".to_string(),
+                },
+                BindingIssue::UnknownAction("teleport".to_string()),
+            ]
+        );
+        assert!(InputBindings::parse("bindings = 3").is_err());
+    }
+
+    #[test]
+    fn conflicting_rebinds_fall_back_to_defaults() {
+        let (bindings, issues) = InputBindings::parse(
+            r#"
+            [bindings]
+            dialogue_probe = "KeyE"
+            skip_day = "KeyQ"
+            skip_year_modifier = "KeyW"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            bindings.binding(InputAction::DialogueProbe),
+            InputBinding::Key(KeyCode::F7),
+            "probe took interact's key and was reset"
+        );
+        assert_eq!(
+            bindings.binding(InputAction::Interact),
+            InputBinding::Key(KeyCode::KeyE)
+        );
+        assert_eq!(
+            bindings.binding(InputAction::SkipDay),
+            InputBinding::Key(KeyCode::KeyQ)
+        );
+        assert_eq!(
+            bindings.binding(InputAction::SkipYearModifier),
+            InputBinding::Key(KeyCode::KeyW),
+            "a modifier may share a camera key"
+        );
+        assert_eq!(
+            issues,
+            [BindingIssue::Conflict {
+                input: InputBinding::Key(KeyCode::KeyE),
+                actions: vec![InputAction::DialogueProbe],
+            }]
+        );
+    }
+}
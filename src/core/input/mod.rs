@@ -0,0 +1,235 @@
+//! Named input actions and their key/mouse bindings. Defaults are compiled in; any action
+//! can be rebound in `config/input.toml`. Systems ask `ActionInput` about actions rather
+//! than matching `KeyCode`s themselves.
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use super::config::{ConfigDiagnostics, ConfigReloadRequested};
+
+mod actions;
+mod bindings;
+
+pub use actions::InputAction;
+pub use bindings::{InputBinding, InputBindings};
+
+pub const CONFIG_PATH: &str = "config/input.toml";
+
+/// Checks whether `action`'s input is held.
+pub fn action_pressed(
+    bindings: &InputBindings,
+    keyboard: &ButtonInput<KeyCode>,
+    mouse: Option<&ButtonInput<MouseButton>>,
+    action: InputAction,
+) -> bool {
+    match bindings.binding(action) {
+        InputBinding::Key(key) => keyboard.pressed(key),
+        InputBinding::Mouse(button) => mouse.is_some_and(|mouse| mouse.pressed(button)),
+    }
+}
+
+/// Checks whether `action`'s input went down this frame.
+pub fn action_just_pressed(
+    bindings: &InputBindings,
+    keyboard: &ButtonInput<KeyCode>,
+    mouse: Option<&ButtonInput<MouseButton>>,
+    action: InputAction,
+) -> bool {
+    match bindings.binding(action) {
+        InputBinding::Key(key) => keyboard.just_pressed(key),
+        InputBinding::Mouse(button) => mouse.is_some_and(|mouse| mouse.just_pressed(button)),
+    }
+}
+
+/// Checks whether `action`'s input was let go this frame.
+pub fn action_just_released(
+    bindings: &InputBindings,
+    keyboard: &ButtonInput<KeyCode>,
+    mouse: Option<&ButtonInput<MouseButton>>,
+    action: InputAction,
+) -> bool {
+    match bindings.binding(action) {
+        InputBinding::Key(key) => keyboard.just_released(key),
+        InputBinding::Mouse(button) => mouse.is_some_and(|mouse| mouse.just_released(button)),
+    }
+}
+
+/// Set while a text field (the developer console) takes typed keys. Keyboard-bound
+/// actions other than `ToggleConsole` then read as idle through `ActionInput`, so typing
+/// "wasd" does not fly the camera; mouse-bound actions carry on.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct KeyboardCapture {
+    pub active: bool,
+}
+
+/// System parameter bundling the bindings with keyboard and mouse state. The mouse is
+/// optional so headless apps without a window still run.
+#[derive(SystemParam)]
+pub struct ActionInput<'w> {
+    bindings: Res<'w, InputBindings>,
+    keyboard: Res<'w, ButtonInput<KeyCode>>,
+    mouse: Option<Res<'w, ButtonInput<MouseButton>>>,
+    capture: Option<Res<'w, KeyboardCapture>>,
+}
+
+impl ActionInput<'_> {
+    /// Whether `KeyboardCapture` is holding `action`'s key back from the game.
+    fn captured(&self, action: InputAction) -> bool {
+        action != InputAction::ToggleConsole
+            && self
+                .capture
+                .as_deref()
+                .is_some_and(|capture| capture.active)
+            && matches!(self.bindings.binding(action), InputBinding::Key(_))
+    }
+
+    pub fn pressed(&self, action: InputAction) -> bool {
+        !self.captured(action)
+            && action_pressed(
+                &self.bindings,
+                &self.keyboard,
+                self.mouse.as_deref(),
+                action,
+            )
+    }
+
+    pub fn just_pressed(&self, action: InputAction) -> bool {
+        !self.captured(action)
+            && action_just_pressed(
+                &self.bindings,
+                &self.keyboard,
+                self.mouse.as_deref(),
+                action,
+            )
+    }
+
+    pub fn just_released(&self, action: InputAction) -> bool {
+        !self.captured(action)
+            && action_just_released(
+                &self.bindings,
+                &self.keyboard,
+                self.mouse.as_deref(),
+                action,
+            )
+    }
+}
+
+/// Debug command: logs every action with its current binding.
+pub fn print_input_bindings(input: ActionInput, bindings: Res<InputBindings>) {
+    if input.just_pressed(InputAction::PrintBindings) {
+        info!("Input bindings:\n{}", bindings.describe());
+    }
+}
+
+/// Re-reads `config/input.toml` on request, swapping the bindings in when it parses.
+pub fn reload_input_bindings(
+    mut requests: MessageReader<ConfigReloadRequested>,
+    mut diagnostics: ResMut<ConfigDiagnostics>,
+    mut bindings: ResMut<InputBindings>,
+) {
+    if !requests.read().any(|request| request.is_for(CONFIG_PATH)) {
+        return;
+    }
+    if let Some(reloaded) = diagnostics.report_reload(CONFIG_PATH, InputBindings::load()) {
+        *bindings = reloaded;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn helpers_distinguish_held_from_just_pressed() {
+        let bindings = InputBindings::default();
+        let mut keyboard = ButtonInput::<KeyCode>::default();
+        let mut mouse = ButtonInput::<MouseButton>::default();
+
+        keyboard.press(KeyCode::KeyE);
+        mouse.press(MouseButton::Right);
+        assert!(action_just_pressed(
+            &bindings,
+            &keyboard,
+            Some(&mouse),
+            InputAction::Interact
+        ));
+        assert!(action_pressed(
+            &bindings,
+            &keyboard,
+            Some(&mouse),
+            InputAction::Interact
+        ));
+        assert!(action_pressed(
+            &bindings,
+            &keyboard,
+            Some(&mouse),
+            InputAction::CameraLook
+        ));
+        assert!(!action_pressed(
+            &bindings,
+            &keyboard,
+            None,
+            InputAction::CameraLook
+        ));
+
+        keyboard.clear();
+        mouse.clear();
+        assert!(!action_just_pressed(
+            &bindings,
+            &keyboard,
+            Some(&mouse),
+            InputAction::Interact
+        ));
+        assert!(action_pressed(
+            &bindings,
+            &keyboard,
+            Some(&mouse),
+            InputAction::Interact
+        ));
+
+        mouse.release(MouseButton::Right);
+        assert!(action_just_released(
+            &bindings,
+            &keyboard,
+            Some(&mouse),
+            InputAction::CameraLook
+        ));
+        assert!(!action_pressed(
+            &bindings,
+            &keyboard,
+            Some(&mouse),
+            InputAction::CameraLook
+        ));
+    }
+
+    #[test]
+    fn keyboard_capture_mutes_key_actions_but_not_the_console_toggle() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        let mut app = App::new();
+        let mut keyboard = ButtonInput::<KeyCode>::default();
+        keyboard.press(KeyCode::KeyW);
+        keyboard.press(KeyCode::Backquote);
+        let mut mouse = ButtonInput::<MouseButton>::default();
+        mouse.press(MouseButton::Right);
+        app.init_resource::<InputBindings>()
+            .insert_resource(keyboard)
+            .insert_resource(mouse)
+            .insert_resource(KeyboardCapture { active: true });
+
+        let read = |input: ActionInput| {
+            [
+                input.pressed(InputAction::MoveForward),
+                input.just_pressed(InputAction::ToggleConsole),
+                input.pressed(InputAction::CameraLook),
+            ]
+        };
+        assert_eq!(
+            app.world_mut().run_system_once(read).unwrap(),
+            [false, true, true]
+        );
+        app.world_mut().resource_mut::<KeyboardCapture>().active = false;
+        assert_eq!(
+            app.world_mut().run_system_once(read).unwrap(),
+            [true, true, true]
+        );
+    }
+}
@@ -0,0 +1,386 @@
+//! Config schema versions and the one-time migrations between them. Each versioned config
+//! file carries a top-level `schema_version`; a file without one predates versioning and
+//! counts as version 1. Loaders read through `read_migrated`, which upgrades an old file
+//! step by step, keeps the original beside it as `.bak`, writes the upgraded file back and
+//! logs every field a step changed or filled in, so the next launch loads it as current.
+//! Steps edit the file as a `toml_edit` document, so its comments and key order survive.
+//! A file newer than this build refuses to load, and the loader falls back to defaults.
+use std::{
+    borrow::Cow,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::log::{info, warn};
+use toml_edit::{DocumentMut, Item, Value};
+
+/// Top-level key holding a config file's schema version.
+pub const SCHEMA_VERSION_KEY: &str = "schema_version";
+/// Version of files written before schema versions existed.
+pub const LEGACY_SCHEMA_VERSION: u32 = 1;
+
+/// One upgrade from a schema version to the next. `apply` is pure: it edits the parsed
+/// file in place, leaving what it does not touch as written, and returns a line per field
+/// it transformed or filled with a default.
+#[derive(Debug, Clone, Copy)]
+pub struct MigrationStep {
+    /// What the step introduces, for the log.
+    pub summary: &'static str,
+    pub apply: fn(&mut DocumentMut) -> Vec<String>,
+}
+
+/// A config file's schema: `steps[i]` upgrades version `i + 1` to `i + 2`, so the current
+/// version is one past the last step.
+#[derive(Debug, Clone, Copy)]
+pub struct ConfigSchema {
+    /// Name used in errors, e.g. "economy".
+    pub name: &'static str,
+    pub steps: &'static [MigrationStep],
+}
+
+impl ConfigSchema {
+    pub const fn current_version(&self) -> u32 {
+        LEGACY_SCHEMA_VERSION + self.steps.len() as u32
+    }
+}
+
+/// What a migration did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationReport {
+    pub from: u32,
+    pub to: u32,
+    /// One line per field changed, prefixed with the step's summary.
+    pub changes: Vec<String>,
+}
+
+/// The `schema_version` of a parsed file: `LEGACY_SCHEMA_VERSION` when absent, an error
+/// when it is not a positive integer.
+pub fn schema_version(document: &DocumentMut) -> Result<u32, String> {
+    let Some(item) = document.get(SCHEMA_VERSION_KEY) else {
+        return Ok(LEGACY_SCHEMA_VERSION);
+    };
+    match item.as_integer() {
+        Some(version) if version >= 1 => u32::try_from(version)
+            .map_err(|_| format!("{SCHEMA_VERSION_KEY} {version} is out of range")),
+        _ => Err(format!(
+            "{SCHEMA_VERSION_KEY} must be a positive integer, found {}",
+            item.to_string().trim()
+        )),
+    }
+}
+
+/// Upgrades `document` to `schema`'s current version in place, running every step after
+/// its version in order. Returns `None` when it is already current. A version newer than
+/// the current one is an error, since this build cannot know what it changed. An existing
+/// `schema_version` is rewritten where it stands; a legacy file gets one among its
+/// top-level keys.
+pub fn migrate_document(
+    schema: &ConfigSchema,
+    document: &mut DocumentMut,
+) -> Result<Option<MigrationReport>, String> {
+    let from = schema_version(document)?;
+    let current = schema.current_version();
+    if from > current {
+        return Err(format!(
+            "{SCHEMA_VERSION_KEY} {from} is newer than this build's {} config (version \
+             {current}); refusing to load it",
+            schema.name
+        ));
+    }
+    if from == current {
+        return Ok(None);
+    }
+
+    let mut changes = Vec::new();
+    for step in &schema.steps[(from - LEGACY_SCHEMA_VERSION) as usize..] {
+        changes.extend(
+            (step.apply)(document)
+                .into_iter()
+                .map(|change| format!("{}: {change}", step.summary)),
+        );
+    }
+    match document
+        .get_mut(SCHEMA_VERSION_KEY)
+        .and_then(Item::as_value_mut)
+    {
+        Some(version) => {
+            let decor = version.decor().clone();
+            *version = Value::from(i64::from(current));
+            *version.decor_mut() = decor;
+        }
+        None => {
+            document.insert(SCHEMA_VERSION_KEY, toml_edit::value(i64::from(current)));
+        }
+    }
+    Ok(Some(MigrationReport {
+        from,
+        to: current,
+        changes,
+    }))
+}
+
+/// `data` upgraded to the current version, or unchanged when it is current. Text that is
+/// not valid TOML is returned as is, so the loader reports the parse error with its line.
+pub fn migrate_str<'a>(schema: &ConfigSchema, data: &'a str) -> Result<Cow<'a, str>, String> {
+    Ok(match migrate_text(schema, data)? {
+        Some((text, _)) => Cow::Owned(text),
+        None => Cow::Borrowed(data),
+    })
+}
+
+fn migrate_text(
+    schema: &ConfigSchema,
+    data: &str,
+) -> Result<Option<(String, MigrationReport)>, String> {
+    let Ok(mut document) = data.parse::<DocumentMut>() else {
+        return Ok(None);
+    };
+    let Some(report) = migrate_document(schema, &mut document)? else {
+        return Ok(None);
+    };
+    let text = format!(
+        "# Migrated from schema version {} to {}; the original file is kept beside this one \
+         as .bak.\n{document}",
+        report.from, report.to
+    );
+    Ok(Some((text, report)))
+}
+
+/// Reads the config at `path`, migrating it first when it is older than `schema`. A
+/// migrated file is written back, with the original copied to `<path>.bak` (numbered when
+/// one already exists), so the migration runs once. If the write fails, the migrated text
+/// is still returned and the migration is retried on the next load, reusing the backup it
+/// already made rather than adding another.
+pub fn read_migrated(schema: &ConfigSchema, path: impl AsRef<Path>) -> Result<String, String> {
+    let path = path.as_ref();
+    let data = fs::read_to_string(path).map_err(|err| format!("unable to read file: {err}"))?;
+    let Some((migrated, report)) = migrate_text(schema, &data)? else {
+        return Ok(data);
+    };
+
+    let (backup, backed_up) = backup_path(path, &data);
+    let written = if backed_up {
+        Ok(())
+    } else {
+        fs::write(&backup, &data)
+    }
+    .and_then(|_| fs::write(path, &migrated));
+    match written {
+        Ok(()) => info!(
+            "Migrated {} from schema version {} to {}; the original is at {}",
+            path.display(),
+            report.from,
+            report.to,
+            backup.display()
+        ),
+        Err(err) => warn!(
+            "Migrated {} from schema version {} to {} in memory only ({err}); it will be \
+             migrated again next time",
+            path.display(),
+            report.from,
+            report.to
+        ),
+    }
+    for change in &report.changes {
+        info!("  {}: {change}", path.display());
+    }
+    Ok(migrated)
+}
+
+/// `<path>.bak`, or `<path>.bak.N` for the first N not already taken, and whether it
+/// already holds `original`. A backup with the same contents is reused, so a migration
+/// that keeps failing to write back does not leave a new copy on every load.
+fn backup_path(path: &Path, original: &str) -> (PathBuf, bool) {
+    let base = format!("{}.bak", path.display());
+    std::iter::once(PathBuf::from(&base))
+        .chain((2..).map(|n| PathBuf::from(format!("{base}.{n}"))))
+        .find_map(|candidate| {
+            if !candidate.exists() {
+                Some((candidate, false))
+            } else if fs::read_to_string(&candidate).is_ok_and(|backup| backup == original) {
+                Some((candidate, true))
+            } else {
+                None
+            }
+        })
+        .expect("an unused backup name exists")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add_colour(document: &mut DocumentMut) -> Vec<String> {
+        if document.contains_key("colour") {
+            return Vec::new();
+        }
+        document.insert("colour", toml_edit::value("red"));
+        vec!["colour: defaulted to \"red\"".to_string()]
+    }
+
+    fn rename_size(document: &mut DocumentMut) -> Vec<String> {
+        match document.remove("size") {
+            Some(size) => {
+                document.insert("width", size);
+                vec!["size: renamed to width".to_string()]
+            }
+            None => Vec::new(),
+        }
+    }
+
+    const TEST_SCHEMA: ConfigSchema = ConfigSchema {
+        name: "test",
+        steps: &[
+            MigrationStep {
+                summary: "colours",
+                apply: add_colour,
+            },
+            MigrationStep {
+                summary: "widths",
+                apply: rename_size,
+            },
+        ],
+    };
+
+    fn parse(data: &str) -> DocumentMut {
+        data.parse().expect("test TOML parses")
+    }
+
+    /// The values of `document`, without its formatting.
+    fn values(document: &DocumentMut) -> toml::Table {
+        document
+            .to_string()
+            .parse()
+            .expect("a document prints as TOML")
+    }
+
+    #[test]
+    fn versions_default_to_legacy_and_reject_nonsense() {
+        assert_eq!(schema_version(&parse("a = 1")), Ok(LEGACY_SCHEMA_VERSION));
+        assert_eq!(schema_version(&parse("schema_version = 3")), Ok(3));
+        assert!(schema_version(&parse("schema_version = 0")).is_err());
+        assert!(schema_version(&parse("schema_version = \"2\"")).is_err());
+        assert_eq!(TEST_SCHEMA.current_version(), 3);
+    }
+
+    #[test]
+    fn legacy_files_run_every_step_in_order() {
+        let mut document = parse("size = 4");
+        let report = migrate_document(&TEST_SCHEMA, &mut document)
+            .unwrap()
+            .expect("legacy file migrates");
+        assert_eq!((report.from, report.to), (1, 3));
+        assert_eq!(
+            report.changes,
+            [
+                "colours: colour: defaulted to \"red\"",
+                "widths: size: renamed to width"
+            ]
+        );
+        assert_eq!(
+            values(&document),
+            values(&parse("schema_version = 3\ncolour = \"red\"\nwidth = 4"))
+        );
+    }
+
+    #[test]
+    fn later_versions_skip_the_steps_they_already_have() {
+        let mut document = parse("schema_version = 2\nsize = 4");
+        let report = migrate_document(&TEST_SCHEMA, &mut document)
+            .unwrap()
+            .unwrap();
+        assert_eq!(report.changes, ["widths: size: renamed to width"]);
+        assert!(!document.contains_key("colour"), "step 1 did not run again");
+
+        let mut current = parse("schema_version = 3\nsize = 4");
+        assert_eq!(migrate_document(&TEST_SCHEMA, &mut current), Ok(None));
+        assert!(current.contains_key("size"), "a current file is untouched");
+    }
+
+    #[test]
+    fn comments_and_key_order_survive_a_migration() {
+        let data = r#"# Window settings.
+schema_version = 2 # bumped by migrations
+
+# Height stays first.
+height = 3
+size = 4
+
+[extra]
+# Tuned by hand.
+flag = true
+"#;
+        let migrated = migrate_str(&TEST_SCHEMA, data).unwrap();
+        let (header, body) = migrated.split_once('\n').unwrap();
+        assert!(header.starts_with("# Migrated from schema version 2 to 3"));
+        assert_eq!(
+            body,
+            data.replace("= 2 #", "= 3 #").replace("size", "width"),
+            "only the version and the renamed key differ"
+        );
+    }
+
+    #[test]
+    fn future_versions_refuse_to_load() {
+        let error = migrate_str(&TEST_SCHEMA, "schema_version = 9").unwrap_err();
+        assert!(error.contains("newer"), "{error}");
+
+        assert!(matches!(
+            migrate_str(&TEST_SCHEMA, "schema_version = 3\nsize = 4"),
+            Ok(Cow::Borrowed(_))
+        ));
+        assert!(
+            matches!(migrate_str(&TEST_SCHEMA, "size = ["), Ok(Cow::Borrowed(_))),
+            "broken TOML is left for the loader to report"
+        );
+    }
+
+    #[test]
+    fn migrated_files_are_written_back_once_with_a_backup() {
+        let dir =
+            std::env::temp_dir().join(format!("thegame_config_migration_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test.toml");
+        fs::write(&path, "size = 4\n").unwrap();
+
+        let migrated = read_migrated(&TEST_SCHEMA, &path).unwrap();
+        assert_eq!(schema_version(&parse(&migrated)), Ok(3));
+        assert_eq!(fs::read_to_string(&path).unwrap(), migrated);
+        assert_eq!(
+            fs::read_to_string(dir.join("test.toml.bak")).unwrap(),
+            "size = 4\n"
+        );
+
+        assert_eq!(read_migrated(&TEST_SCHEMA, &path).unwrap(), migrated);
+        assert!(
+            !dir.join("test.toml.bak.2").exists(),
+            "a current file is not backed up again"
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn an_identical_backup_is_reused() {
+        let dir = std::env::temp_dir().join(format!(
+            "thegame_config_migration_backups_{}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test.toml");
+        fs::write(dir.join("test.toml.bak"), "size = 2\n").unwrap();
+        fs::write(dir.join("test.toml.bak.2"), "size = 4\n").unwrap();
+        fs::write(&path, "size = 4\n").unwrap();
+
+        read_migrated(&TEST_SCHEMA, &path).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.join("test.toml.bak")).unwrap(),
+            "size = 2\n",
+            "an older, different backup is left alone"
+        );
+        assert!(
+            !dir.join("test.toml.bak.3").exists(),
+            "the matching backup stands in for a new one"
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
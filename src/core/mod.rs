@@ -1,4 +1,15 @@
 //! Core module exporting foundational plugins and resources.
+#[cfg(feature = "chaos")]
+pub mod chaos;
+pub mod config;
+pub mod focus;
+pub mod format;
+pub mod input;
+pub mod migration;
 pub mod plugin;
+pub mod preset;
+pub mod profiling;
+pub mod schedule;
+pub mod time_basis;
 
 pub use plugin::CorePlugin;
@@ -4,8 +4,32 @@ use bevy::prelude::*;
 use bevy::time::TimerMode;
 use std::time::Duration;
 
+use bevy::window::WindowFocused;
+
+use super::{
+    config::{report_config_result, ConfigDiagnostics, ConfigReloadRequested},
+    focus::{
+        apply_focus_throttle, reload_focus_settings, track_window_focus, FocusSettings,
+        WindowFocusState, CONFIG_PATH as WINDOW_CONFIG_PATH,
+    },
+    format::{reload_format_settings, FormatSettings, CONFIG_PATH as LOCALE_CONFIG_PATH},
+    input::{
+        print_input_bindings, reload_input_bindings, InputBindings, KeyboardCapture,
+        CONFIG_PATH as INPUT_CONFIG_PATH,
+    },
+    profiling::{
+        finish_stopwatch_frame, monitor_frame_budget, reload_frame_budget, FrameBudgetMonitor,
+        SystemStopwatch,
+    },
+    schedule::{configure_frame_phases, FramePhase},
+};
+
 const DEFAULT_TIME_SCALE: f32 = 1.0;
 const MIN_TIME_SCALE: f32 = 0.001;
+const DEFAULT_MAX_FRAME_DELTA_SECONDS: f32 = 0.25;
+const MIN_MAX_FRAME_DELTA_SECONDS: f32 = 0.001;
+/// Clamped-away time above this is reported, so ordinary hitches stay quiet.
+const LARGE_DELTA_LOG_SECONDS: f32 = 1.0;
 
 #[cfg(feature = "core_debug")]
 #[derive(Resource)]
@@ -23,12 +47,17 @@ impl Default for DebugTickTimer {
 }
 
 /// Tracks scaled simulation time derived from real frame deltas.
+///
+/// Real deltas are capped at `max_frame_delta` before scaling, so a suspended laptop or a
+/// blocked main loop does not leap the simulation forward in one frame.
 #[derive(Resource, Debug)]
 pub struct SimulationClock {
     time_scale: f32,
+    max_frame_delta: Duration,
     last_real_delta: Duration,
     last_scaled_delta: Duration,
     elapsed: Duration,
+    clamped_total: Duration,
 }
 
 impl SimulationClock {
@@ -37,12 +66,20 @@ impl SimulationClock {
         let clamped = time_scale.max(MIN_TIME_SCALE);
         Self {
             time_scale: clamped,
+            max_frame_delta: Duration::from_secs_f32(DEFAULT_MAX_FRAME_DELTA_SECONDS),
             last_real_delta: Duration::ZERO,
             last_scaled_delta: Duration::ZERO,
             elapsed: Duration::ZERO,
+            clamped_total: Duration::ZERO,
         }
     }
 
+    /// Sets the longest real delta a single tick may apply (clamped to a small minimum).
+    pub fn with_max_frame_delta(mut self, seconds: f32) -> Self {
+        self.max_frame_delta = Duration::from_secs_f32(seconds.max(MIN_MAX_FRAME_DELTA_SECONDS));
+        self
+    }
+
     /// Sets the time-scale multiplier (clamped to a small positive minimum).
     #[cfg_attr(not(test), allow(dead_code))]
     pub fn set_time_scale(&mut self, scale: f32) {
@@ -66,17 +103,32 @@ impl SimulationClock {
         self.last_scaled_delta
     }
 
+    /// Longest real delta a single tick applies.
+    pub fn max_frame_delta(&self) -> Duration {
+        self.max_frame_delta
+    }
+
     /// Returns the total scaled duration elapsed since the clock was initialised.
     #[cfg_attr(not(feature = "core_debug"), allow(dead_code))]
     pub fn elapsed(&self) -> Duration {
         self.elapsed
     }
 
-    /// Applies a real delta to the clock, storing both the real and scaled durations.
-    pub fn tick(&mut self, real_delta: Duration) {
+    /// Total real time discarded by frame-delta clamping since the clock started.
+    pub fn clamped_total(&self) -> Duration {
+        self.clamped_total
+    }
+
+    /// Applies a real delta to the clock, capping it at the maximum frame delta before
+    /// scaling. Returns the real time clamped away this tick.
+    pub fn tick(&mut self, real_delta: Duration) -> Duration {
+        let applied = real_delta.min(self.max_frame_delta);
+        let clamped = real_delta - applied;
         self.last_real_delta = real_delta;
-        self.last_scaled_delta = real_delta.mul_f32(self.time_scale);
+        self.last_scaled_delta = applied.mul_f32(self.time_scale);
         self.elapsed += self.last_scaled_delta;
+        self.clamped_total += clamped;
+        clamped
     }
 }
 
@@ -90,12 +142,22 @@ impl Default for SimulationClock {
 #[derive(Debug, Clone, Copy)]
 pub struct CorePlugin {
     time_scale: f32,
+    max_frame_delta_seconds: f32,
 }
 
 impl CorePlugin {
     /// Creates a CorePlugin with the provided time-scale multiplier.
     pub const fn with_time_scale(time_scale: f32) -> Self {
-        Self { time_scale }
+        Self {
+            time_scale,
+            max_frame_delta_seconds: DEFAULT_MAX_FRAME_DELTA_SECONDS,
+        }
+    }
+
+    /// Overrides the longest real frame delta the simulation clock will apply.
+    pub const fn with_max_frame_delta(mut self, seconds: f32) -> Self {
+        self.max_frame_delta_seconds = seconds;
+        self
     }
 }
 
@@ -107,20 +169,99 @@ impl Default for CorePlugin {
 
 impl Plugin for CorePlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(SimulationClock::new(self.time_scale))
-            .add_systems(Startup, log_startup_time_scale)
-            .add_systems(Update, update_simulation_clock);
+        let focus_settings = report_config_result(
+            app.world_mut(),
+            WINDOW_CONFIG_PATH,
+            FocusSettings::load(),
+            FocusSettings::default,
+        );
+        let input_bindings = report_config_result(
+            app.world_mut(),
+            INPUT_CONFIG_PATH,
+            InputBindings::load(),
+            InputBindings::default,
+        );
+        let frame_budget = report_config_result(
+            app.world_mut(),
+            WINDOW_CONFIG_PATH,
+            FrameBudgetMonitor::load(),
+            FrameBudgetMonitor::default,
+        );
+        let format_settings = report_config_result(
+            app.world_mut(),
+            LOCALE_CONFIG_PATH,
+            FormatSettings::load(),
+            FormatSettings::default,
+        );
+        configure_frame_phases(app);
+        app.insert_resource(
+            SimulationClock::new(self.time_scale)
+                .with_max_frame_delta(self.max_frame_delta_seconds),
+        )
+        .insert_resource(focus_settings)
+        .insert_resource(input_bindings)
+        .insert_resource(frame_budget)
+        .insert_resource(format_settings)
+        .init_resource::<SystemStopwatch>()
+        .init_resource::<ConfigDiagnostics>()
+        .init_resource::<WindowFocusState>()
+        .init_resource::<KeyboardCapture>()
+        .add_message::<ConfigReloadRequested>()
+        .add_message::<WindowFocused>()
+        .add_systems(Startup, log_startup_time_scale)
+        .add_systems(PreUpdate, track_window_focus)
+        .add_systems(
+            Update,
+            (
+                reload_focus_settings,
+                apply_focus_throttle,
+                update_simulation_clock,
+            )
+                .chain()
+                .in_set(FramePhase::SimTick),
+        )
+        .add_systems(
+            Update,
+            (
+                reload_input_bindings,
+                print_input_bindings,
+                reload_frame_budget,
+                reload_format_settings,
+            )
+                .in_set(FramePhase::SimTick),
+        )
+        .add_systems(Last, (monitor_frame_budget, finish_stopwatch_frame).chain());
+
+        #[cfg(feature = "chaos")]
+        app.add_plugins(super::chaos::ChaosPlugin);
 
         #[cfg(feature = "core_debug")]
         {
             app.insert_resource(DebugTickTimer::default())
-                .add_systems(Update, log_scaled_ticks);
+                .add_systems(Update, log_scaled_ticks.in_set(FramePhase::SimTick));
         }
     }
 }
 
-fn update_simulation_clock(mut clock: ResMut<SimulationClock>, time: Res<Time>) {
-    clock.tick(time.delta());
+fn update_simulation_clock(
+    mut clock: ResMut<SimulationClock>,
+    time: Res<Time>,
+    focus: Res<WindowFocusState>,
+    focus_settings: Res<FocusSettings>,
+) {
+    if !focus.simulation_running(&focus_settings) {
+        clock.tick(Duration::ZERO);
+        return;
+    }
+    let clamped = clock.tick(time.delta());
+    if clamped.as_secs_f32() >= LARGE_DELTA_LOG_SECONDS {
+        warn!(
+            "Frame delta of {:.2}s clamped; skipped {:.2}s of simulation ({:.2}s in total)",
+            clock.last_real_delta().as_secs_f32(),
+            clamped.as_secs_f32(),
+            clock.clamped_total().as_secs_f32()
+        );
+    }
 }
 
 fn log_startup_time_scale(clock: Res<SimulationClock>) {
@@ -150,7 +291,7 @@ mod tests {
 
     #[test]
     fn clock_scales_delta_with_multiplier() {
-        let mut clock = SimulationClock::new(2.5);
+        let mut clock = SimulationClock::new(2.5).with_max_frame_delta(2.0);
         clock.tick(Duration::from_secs_f32(1.2));
 
         assert_eq!(clock.time_scale(), 2.5);
@@ -170,4 +311,22 @@ mod tests {
         clock.set_time_scale(-5.0);
         assert!((clock.time_scale() - MIN_TIME_SCALE).abs() < f32::EPSILON);
     }
+
+    #[test]
+    fn large_deltas_are_clamped_and_accounted() {
+        let mut clock = SimulationClock::new(2.0);
+        let clamped = clock.tick(Duration::from_secs(10));
+
+        let max = Duration::from_secs_f32(DEFAULT_MAX_FRAME_DELTA_SECONDS);
+        assert_eq!(clamped, Duration::from_secs(10) - max);
+        assert_eq!(clock.last_real_delta(), Duration::from_secs(10));
+        assert_eq!(clock.last_scaled_delta(), max.mul_f32(2.0));
+
+        assert_eq!(clock.tick(Duration::from_millis(100)), Duration::ZERO);
+        assert_eq!(
+            clock.elapsed(),
+            max.mul_f32(2.0) + Duration::from_millis(100).mul_f32(2.0)
+        );
+        assert_eq!(clock.clamped_total(), Duration::from_secs(10) - max);
+    }
 }
@@ -0,0 +1,385 @@
+//! Village presets: the NPC, economy, and time config files bundled into one TOML file so
+//! a whole setup can be shared. `--export-preset <path>` writes the effective
+//! configuration, and `--import-preset <path>` validates a bundle and writes its sections
+//! back into `config/`. Both run at startup, before any plugin reads its config.
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    economy::data::{self as economy_data, ECONOMY_CONFIG_PATH},
+    npc::{census, household, patrol, voice},
+    world::time,
+};
+
+/// Version written into exported bundles. Imports accept any `1.x`.
+pub const FORMAT_VERSION: &str = "1.0";
+const SUPPORTED_MAJOR: u64 = 1;
+const VERSION_KEY: &str = "format_version";
+const CONFIG_DIR: &str = "config";
+const IMPORT_FLAG: &str = "--import-preset";
+const EXPORT_FLAG: &str = "--export-preset";
+
+/// One loader's view of a config file: how it parses the file, and what it falls back to
+/// when the file is missing or invalid.
+struct PresetPart {
+    explicit: fn(&str) -> Result<toml::Table, String>,
+    fallback: fn() -> toml::Table,
+}
+
+/// A bundle section and the config file it is written to. `config/npcs.toml` is read by
+/// four loaders, each falling back on its own.
+struct PresetSection {
+    name: &'static str,
+    path: &'static str,
+    parts: &'static [PresetPart],
+}
+
+static SECTIONS: [PresetSection; 3] = [
+    PresetSection {
+        name: "npcs",
+        path: household::CONFIG_PATH,
+        parts: &[
+            PresetPart {
+                explicit: household::explicit_toml,
+                fallback: household::fallback_toml,
+            },
+            PresetPart {
+                explicit: census::explicit_toml,
+                fallback: census::fallback_toml,
+            },
+            PresetPart {
+                explicit: voice::explicit_toml,
+                fallback: voice::fallback_toml,
+            },
+            PresetPart {
+                explicit: patrol::explicit_toml,
+                fallback: patrol::fallback_toml,
+            },
+        ],
+    },
+    PresetSection {
+        name: "economy",
+        path: ECONOMY_CONFIG_PATH,
+        parts: &[PresetPart {
+            explicit: economy_data::explicit_toml,
+            fallback: economy_data::fallback_toml,
+        }],
+    },
+    PresetSection {
+        name: "time",
+        path: time::CONFIG_PATH,
+        parts: &[PresetPart {
+            explicit: time::explicit_toml,
+            fallback: time::fallback_toml,
+        }],
+    },
+];
+
+impl PresetSection {
+    /// Where this section lives under `config_dir`.
+    fn file_in(&self, config_dir: &Path) -> PathBuf {
+        config_dir.join(Path::new(self.path).file_name().unwrap_or_default())
+    }
+}
+
+/// The effective configuration under `config_dir` as one bundle. Every loader's view is
+/// written out in full; one whose file is missing or invalid contributes the defaults it
+/// would fall back to. Returns the bundle and the sections that used any defaults.
+pub fn export_preset(config_dir: &Path) -> (toml::Table, Vec<&'static str>) {
+    let mut bundle = toml::Table::new();
+    bundle.insert(VERSION_KEY.into(), FORMAT_VERSION.into());
+    let mut defaulted = Vec::new();
+    for section in &SECTIONS {
+        let data = fs::read_to_string(section.file_in(config_dir)).ok();
+        let mut table = toml::Table::new();
+        for part in section.parts {
+            let explicit = data.as_deref().and_then(|data| (part.explicit)(data).ok());
+            if explicit.is_none() && !defaulted.contains(&section.name) {
+                defaulted.push(section.name);
+            }
+            table.extend(explicit.unwrap_or_else(part.fallback));
+        }
+        tidy_floats(&mut table);
+        bundle.insert(section.name.into(), table.into());
+    }
+    (bundle, defaulted)
+}
+
+/// Config files an import wrote, and the sections the bundle left alone.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PresetImport {
+    pub written: Vec<PathBuf>,
+    pub unchanged: Vec<&'static str>,
+}
+
+/// Validates `bundle` through the config loaders and writes each of its sections into
+/// `config_dir`. Every problem is collected before anything is written, so a bad bundle
+/// leaves the config files untouched.
+pub fn import_preset(bundle: &str, config_dir: &Path) -> Result<PresetImport, Vec<String>> {
+    let bundle = toml::from_str::<toml::Table>(bundle)
+        .map_err(|err| vec![format!("invalid preset: {err}")])?;
+    check_version(&bundle).map_err(|err| vec![err])?;
+
+    let mut errors: Vec<String> = bundle
+        .keys()
+        .filter(|key| {
+            key.as_str() != VERSION_KEY
+                && !SECTIONS.iter().any(|section| section.name == key.as_str())
+        })
+        .map(|key| format!("unknown preset section [{key}]"))
+        .collect();
+    let mut sections = Vec::new();
+    let mut unchanged = Vec::new();
+    for section in &SECTIONS {
+        let Some(value) = bundle.get(section.name) else {
+            unchanged.push(section.name);
+            continue;
+        };
+        let Some(table) = value.as_table() else {
+            errors.push(format!("[{}] must be a table", section.name));
+            continue;
+        };
+        let text = match toml::to_string(table) {
+            Ok(text) => text,
+            Err(err) => {
+                errors.push(format!("[{}] {err}", section.name));
+                continue;
+            }
+        };
+        let mut valid = true;
+        for part in section.parts {
+            if let Err(err) = (part.explicit)(&text) {
+                errors.push(format!("[{}] {err}", section.name));
+                valid = false;
+            }
+        }
+        if valid {
+            sections.push((section, text));
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    fs::create_dir_all(config_dir)
+        .map_err(|err| vec![format!("unable to create {}: {err}", config_dir.display())])?;
+    let mut written = Vec::new();
+    for (section, text) in sections {
+        let path = section.file_in(config_dir);
+        fs::write(&path, text)
+            .map_err(|err| vec![format!("unable to write {}: {err}", path.display())])?;
+        written.push(path);
+    }
+    Ok(PresetImport { written, unchanged })
+}
+
+fn check_version(bundle: &toml::Table) -> Result<(), String> {
+    let Some(version) = bundle.get(VERSION_KEY).and_then(toml::Value::as_str) else {
+        return Err(format!(
+            "preset needs a {VERSION_KEY} string, e.g. \"{FORMAT_VERSION}\""
+        ));
+    };
+    let major = version
+        .split('.')
+        .next()
+        .and_then(|major| major.parse::<u64>().ok());
+    if major != Some(SUPPORTED_MAJOR) {
+        return Err(format!(
+            "unsupported preset {VERSION_KEY} \"{version}\"; this build reads {SUPPORTED_MAJOR}.x"
+        ));
+    }
+    Ok(())
+}
+
+/// Config floats are all `f32`, which serialize as their widened `f64` ("0.2199999988").
+/// Writes each back in its shortest `f32` form, which parses to the same value.
+fn tidy_floats(table: &mut toml::Table) {
+    fn tidy(value: &mut toml::Value) {
+        match value {
+            toml::Value::Float(float) => {
+                if let Ok(short) = (*float as f32).to_string().parse::<f64>() {
+                    *float = short;
+                }
+            }
+            toml::Value::Array(items) => items.iter_mut().for_each(tidy),
+            toml::Value::Table(table) => table.iter_mut().for_each(|(_, value)| tidy(value)),
+            _ => {}
+        }
+    }
+    table.iter_mut().for_each(|(_, value)| tidy(value));
+}
+
+/// Handles `--import-preset <path>` and `--export-preset <path>` before the app starts.
+/// An import runs first and the game then launches with it. An export writes the bundle
+/// and asks the caller to exit. Returns the exit code when the process should stop.
+pub fn handle_preset_args(args: impl IntoIterator<Item = String>) -> Option<i32> {
+    let mut import = None;
+    let mut export = None;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let slot = match arg.as_str() {
+            IMPORT_FLAG => &mut import,
+            EXPORT_FLAG => &mut export,
+            _ => continue,
+        };
+        let Some(path) = args.next() else {
+            eprintln!("{arg} needs a file path");
+            return Some(2);
+        };
+        *slot = Some(PathBuf::from(path));
+    }
+
+    let config_dir = Path::new(CONFIG_DIR);
+    if let Some(path) = import {
+        let result = fs::read_to_string(&path)
+            .map_err(|err| vec![format!("unable to read {}: {err}", path.display())])
+            .and_then(|bundle| import_preset(&bundle, config_dir));
+        match result {
+            Ok(import) => {
+                println!("Imported village preset {}", path.display());
+                for written in &import.written {
+                    println!("  wrote {}", written.display());
+                }
+                if !import.unchanged.is_empty() {
+                    println!("  left unchanged: {}", import.unchanged.join(", "));
+                }
+            }
+            Err(errors) => {
+                eprintln!(
+                    "Village preset {} was not imported ({} problem(s)):",
+                    path.display(),
+                    errors.len()
+                );
+                for error in errors {
+                    eprintln!("  {error}");
+                }
+                return Some(1);
+            }
+        }
+    }
+
+    let path = export?;
+    let (bundle, defaulted) = export_preset(config_dir);
+    let written = toml::to_string(&bundle)
+        .map_err(|err| err.to_string())
+        .and_then(|text| fs::write(&path, text).map_err(|err| err.to_string()));
+    match written {
+        Ok(()) => {
+            println!("Exported village preset to {}", path.display());
+            if !defaulted.is_empty() {
+                println!("  defaults written for: {}", defaulted.join(", "));
+            }
+            Some(0)
+        }
+        Err(err) => {
+            eprintln!(
+                "Unable to export village preset to {}: {err}",
+                path.display()
+            );
+            Some(1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, time::SystemTime};
+
+    use super::*;
+
+    fn temp_config_dir(label: &str) -> PathBuf {
+        let unique_suffix = SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        env::temp_dir().join(format!("thegame_preset_{label}_{unique_suffix}"))
+    }
+
+    fn bundle_text(bundle: &toml::Table) -> String {
+        toml::to_string(bundle).unwrap()
+    }
+
+    #[test]
+    fn export_then_import_reproduces_the_effective_config() {
+        let (exported, defaulted) = export_preset(Path::new(CONFIG_DIR));
+        assert!(defaulted.is_empty(), "the shipped config files all load");
+        assert_eq!(
+            exported["npcs"]["census"]["interval_minutes"].as_integer(),
+            Some(60)
+        );
+        assert_eq!(
+            exported["time"]["clock"]["sunrise_fraction"].as_float(),
+            Some(0.22)
+        );
+
+        let dir = temp_config_dir("round_trip");
+        let import = import_preset(&bundle_text(&exported), &dir).unwrap();
+        assert_eq!(import.written.len(), SECTIONS.len());
+        assert!(import.unchanged.is_empty());
+        let (reexported, defaulted) = export_preset(&dir);
+        assert!(defaulted.is_empty());
+        assert_eq!(reexported, exported);
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn missing_files_export_their_defaults_explicitly() {
+        let dir = temp_config_dir("empty");
+        let (exported, defaulted) = export_preset(&dir);
+        assert_eq!(defaulted, vec!["npcs", "economy", "time"]);
+        assert_eq!(
+            exported["npcs"]["storage"]["personal_keep"].as_integer(),
+            Some(1)
+        );
+        assert_eq!(
+            exported["time"]["clock"]["day_length_minutes"].as_float(),
+            Some(10.0)
+        );
+        assert_eq!(
+            exported["economy"]["recipes"].as_array().map(Vec::len),
+            Some(4),
+            "the fallback economy"
+        );
+    }
+
+    #[test]
+    fn every_bad_section_is_reported_and_nothing_is_written() {
+        let bundle = r#"
+format_version = "1.0"
+
+[economy]
+recipes = []
+
+[time.clock]
+day_length_minutes = "long"
+
+[weather]
+rain = true
+"#;
+        let dir = temp_config_dir("bad");
+        let errors = import_preset(bundle, &dir).unwrap_err();
+        assert_eq!(errors.len(), 3, "{errors:?}");
+        assert!(errors[0].contains("[weather]"));
+        assert!(errors[1].starts_with("[economy]") && errors[1].contains("recipe"));
+        assert!(errors[2].starts_with("[time]"));
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn unknown_major_versions_are_rejected() {
+        let dir = temp_config_dir("version");
+        let errors = import_preset("format_version = \"2.0\"\n[time]\n", &dir).unwrap_err();
+        assert_eq!(
+            errors,
+            vec!["unsupported preset format_version \"2.0\"; this build reads 1.x".to_string()]
+        );
+        let errors = import_preset("[time]\n", &dir).unwrap_err();
+        assert!(errors[0].contains("format_version"));
+
+        let import = import_preset("format_version = \"1.3\"\n[time]\n", &dir).unwrap();
+        assert_eq!(import.unchanged, vec!["npcs", "economy"]);
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,289 @@
+//! Frame budget monitoring. A frame whose real delta exceeds `[frame_budget] budget_ms` from
+//! `config/window.toml` is logged along with the slowest timed systems of that frame. Timing
+//! and tracing spans around the heavier systems are only compiled in with the `profiling`
+//! feature; without it an overrun is logged on its own.
+use std::{
+    collections::HashMap,
+    fs,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+#[cfg(feature = "profiling")]
+use bevy::ecs::schedule::ScheduleLabel;
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use super::{
+    config::{ConfigDiagnostics, ConfigReloadRequested},
+    focus::{WindowFocusState, CONFIG_PATH},
+};
+
+const DEFAULT_BUDGET_MS: f32 = 33.0;
+const DEFAULT_TOP_OFFENDERS: usize = 5;
+const MIN_BUDGET_MS: f32 = 1.0;
+
+#[derive(Debug, Clone, Deserialize, Default)]
+struct RawFrameBudgetConfig {
+    #[serde(default)]
+    frame_budget: RawFrameBudgetSection,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+struct RawFrameBudgetSection {
+    budget_ms: f32,
+    top_offenders: usize,
+}
+
+impl Default for RawFrameBudgetSection {
+    fn default() -> Self {
+        Self {
+            budget_ms: DEFAULT_BUDGET_MS,
+            top_offenders: DEFAULT_TOP_OFFENDERS,
+        }
+    }
+}
+
+/// Frame time budget and the number of overruns seen so far.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct FrameBudgetMonitor {
+    budget: Duration,
+    top_offenders: usize,
+    overruns: u64,
+}
+
+impl FrameBudgetMonitor {
+    /// Reads and parses the `[frame_budget]` section of `config/window.toml`.
+    pub fn load() -> Result<Self, String> {
+        let data = fs::read_to_string(Path::new(CONFIG_PATH))
+            .map_err(|err| format!("unable to read file: {err}"))?;
+        let raw = toml::from_str::<RawFrameBudgetConfig>(&data)
+            .map_err(|err| format!("invalid window config: {err}"))?;
+        Ok(raw.into())
+    }
+
+    pub fn budget(&self) -> Duration {
+        self.budget
+    }
+
+    /// How many of the slowest systems an overrun report lists.
+    pub fn top_offenders(&self) -> usize {
+        self.top_offenders
+    }
+
+    #[cfg(test)]
+    pub fn overruns(&self) -> u64 {
+        self.overruns
+    }
+
+    /// Counts and reports a frame that took longer than the budget.
+    pub fn check(&mut self, frame: Duration) -> bool {
+        if frame <= self.budget {
+            return false;
+        }
+        self.overruns += 1;
+        true
+    }
+}
+
+impl Default for FrameBudgetMonitor {
+    fn default() -> Self {
+        RawFrameBudgetConfig::default().into()
+    }
+}
+
+impl From<RawFrameBudgetConfig> for FrameBudgetMonitor {
+    fn from(value: RawFrameBudgetConfig) -> Self {
+        let section = value.frame_budget;
+        let budget_ms = if section.budget_ms.is_finite() {
+            section.budget_ms.max(MIN_BUDGET_MS)
+        } else {
+            DEFAULT_BUDGET_MS
+        };
+        Self {
+            budget: Duration::from_secs_f32(budget_ms / 1000.0),
+            top_offenders: section.top_offenders,
+            overruns: 0,
+        }
+    }
+}
+
+/// Per-system wall time, filled by the stopwatch systems that bracket timed systems. The
+/// brackets are ordered around their system but other systems may run in between, so a
+/// reading is an upper bound.
+#[derive(Resource, Debug, Default)]
+pub struct SystemStopwatch {
+    running: HashMap<&'static str, Instant>,
+    current: HashMap<&'static str, Duration>,
+    last_frame: HashMap<&'static str, Duration>,
+}
+
+impl SystemStopwatch {
+    #[cfg_attr(not(feature = "profiling"), allow(dead_code))]
+    pub fn start(&mut self, name: &'static str) {
+        self.running.insert(name, Instant::now());
+    }
+
+    #[cfg_attr(not(feature = "profiling"), allow(dead_code))]
+    pub fn stop(&mut self, name: &'static str) {
+        if let Some(started) = self.running.remove(name) {
+            self.record(name, started.elapsed());
+        }
+    }
+
+    /// Adds `elapsed` to this frame's total for `name`.
+    #[cfg_attr(not(any(test, feature = "profiling")), allow(dead_code))]
+    pub fn record(&mut self, name: &'static str, elapsed: Duration) {
+        *self.current.entry(name).or_default() += elapsed;
+    }
+
+    /// Makes this frame's totals the ones `top` reports and starts a fresh frame.
+    pub fn finish_frame(&mut self) {
+        self.last_frame = std::mem::take(&mut self.current);
+        self.running.clear();
+    }
+
+    /// Up to `count` of the last finished frame's slowest systems, slowest first.
+    pub fn top(&self, count: usize) -> Vec<(&'static str, Duration)> {
+        let mut entries: Vec<(&'static str, Duration)> = self
+            .last_frame
+            .iter()
+            .map(|(name, elapsed)| (*name, *elapsed))
+            .collect();
+        entries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        entries.truncate(count);
+        entries
+    }
+}
+
+/// "advance_actor_tasks 12.3 ms, poll_dialogue_tasks 4.0 ms" style list.
+pub fn format_offenders(offenders: &[(&'static str, Duration)]) -> String {
+    offenders
+        .iter()
+        .map(|(name, elapsed)| format!("{name} {:.1} ms", elapsed.as_secs_f64() * 1000.0))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Warns about a frame over budget, listing the slowest timed systems when there are any.
+/// Frames while unfocused, or right after focus changes, are throttled on purpose and
+/// skipped.
+pub fn monitor_frame_budget(
+    time: Res<Time<Real>>,
+    focus: Res<WindowFocusState>,
+    stopwatch: Res<SystemStopwatch>,
+    mut monitor: ResMut<FrameBudgetMonitor>,
+) {
+    if !focus.is_focused() || focus.is_changed() {
+        return;
+    }
+    let frame = time.delta();
+    if !monitor.check(frame) {
+        return;
+    }
+    let offenders = stopwatch.top(monitor.top_offenders());
+    let frame_ms = frame.as_secs_f64() * 1000.0;
+    let budget_ms = monitor.budget().as_secs_f64() * 1000.0;
+    if offenders.is_empty() {
+        warn!("Frame took {frame_ms:.1} ms (budget {budget_ms:.1} ms)");
+    } else {
+        warn!(
+            "Frame took {frame_ms:.1} ms (budget {budget_ms:.1} ms); slowest systems: {}",
+            format_offenders(&offenders)
+        );
+    }
+}
+
+/// Rolls the stopwatch over once every timed system has run.
+pub fn finish_stopwatch_frame(mut stopwatch: ResMut<SystemStopwatch>) {
+    stopwatch.finish_frame();
+}
+
+/// Brackets `system` with stopwatch systems recording its time under `name`.
+#[cfg(feature = "profiling")]
+pub fn time_system<M>(
+    app: &mut App,
+    schedule: impl ScheduleLabel,
+    name: &'static str,
+    system: impl IntoSystemSet<M> + Copy,
+) {
+    app.add_systems(
+        schedule,
+        (
+            (move |mut stopwatch: ResMut<SystemStopwatch>| stopwatch.start(name)).before(system),
+            (move |mut stopwatch: ResMut<SystemStopwatch>| stopwatch.stop(name)).after(system),
+        ),
+    );
+}
+
+/// Re-reads the `[frame_budget]` section on request, keeping the overrun count.
+pub fn reload_frame_budget(
+    mut requests: MessageReader<ConfigReloadRequested>,
+    mut diagnostics: ResMut<ConfigDiagnostics>,
+    mut monitor: ResMut<FrameBudgetMonitor>,
+) {
+    if !requests.read().any(|request| request.is_for(CONFIG_PATH)) {
+        return;
+    }
+    if let Some(reloaded) = diagnostics.report_reload(CONFIG_PATH, FrameBudgetMonitor::load()) {
+        *monitor = FrameBudgetMonitor {
+            overruns: monitor.overruns,
+            ..reloaded
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(value: u64) -> Duration {
+        Duration::from_millis(value)
+    }
+
+    #[test]
+    fn overruns_count_only_frames_past_the_budget() {
+        let mut monitor = FrameBudgetMonitor::default();
+        assert_eq!(monitor.budget(), Duration::from_secs_f32(0.033));
+        assert!(!monitor.check(ms(16)));
+        assert!(!monitor.check(monitor.budget()));
+        assert!(monitor.check(ms(50)));
+        assert_eq!(monitor.overruns(), 1);
+
+        let raw: RawFrameBudgetConfig =
+            toml::from_str("[frame_budget]\nbudget_ms = 0.0\ntop_offenders = 2").unwrap();
+        let strict = FrameBudgetMonitor::from(raw);
+        assert_eq!(strict.budget(), ms(1));
+        assert_eq!(strict.top_offenders(), 2);
+    }
+
+    #[test]
+    fn top_lists_last_frame_slowest_first() {
+        let mut stopwatch = SystemStopwatch::default();
+        stopwatch.record("poll_dialogue_tasks", ms(4));
+        stopwatch.record("advance_actor_tasks", ms(9));
+        stopwatch.record("advance_actor_tasks", ms(3));
+        stopwatch.record("drive_npc_locomotion", ms(4));
+        stopwatch.record("update_dialogue_panel", ms(1));
+        assert!(stopwatch.top(3).is_empty(), "nothing reported mid-frame");
+
+        stopwatch.finish_frame();
+        let top = stopwatch.top(3);
+        assert_eq!(
+            top,
+            [
+                ("advance_actor_tasks", ms(12)),
+                ("drive_npc_locomotion", ms(4)),
+                ("poll_dialogue_tasks", ms(4)),
+            ]
+        );
+        assert_eq!(format_offenders(&top[..1]), "advance_actor_tasks 12.0 ms");
+
+        stopwatch.finish_frame();
+        assert!(
+            stopwatch.top(3).is_empty(),
+            "an idle frame clears the report"
+        );
+    }
+}
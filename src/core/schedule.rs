@@ -0,0 +1,46 @@
+//! Frame phases for the `Update` schedule. Every plugin places its `Update` systems in one
+//! of these sets, and `CorePlugin` chains them, so cross-plugin order no longer depends on
+//! the order plugins are added in.
+//!
+//! One frame runs:
+//! 1. `SimTick`: config reloads, the `SimulationClock`, the world clock, and world events.
+//! 2. `EconomyExecute`: day preparation, spoilage, actor tasks, and trades. Trade chatter
+//!    enqueued here is dispatched the same frame.
+//! 3. `DialogueDispatch`: queue timers, rumor relays, and `run_dialogue_request_queue`
+//!    starting broker tasks. `DialogueRequestedEvent`s go out here.
+//! 4. `NpcUpdate`: conversations start from this frame's dispatches, then schedules,
+//!    motivation, locomotion, and facing. Requests enqueued here wait for the next frame.
+//! 5. `DialoguePoll`: finished broker tasks become `DialogueResponseEvent`s, then pending
+//!    speech, telemetry, transcripts, and rumors learned from replies.
+//! 6. `Presentation`: camera, selection, collisions, player windows, dialogue panels,
+//!    subtitles, and snapshot reporting, all reading the frame's results.
+//!
+//! Readers in an earlier phase than their writer (motivation rewards from dialogue replies,
+//! for example) see those messages one frame later, which message double-buffering allows.
+use bevy::prelude::*;
+
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FramePhase {
+    SimTick,
+    EconomyExecute,
+    DialogueDispatch,
+    NpcUpdate,
+    DialoguePoll,
+    Presentation,
+}
+
+/// Chains the phases in `Update` in frame order.
+pub fn configure_frame_phases(app: &mut App) {
+    app.configure_sets(
+        Update,
+        (
+            FramePhase::SimTick,
+            FramePhase::EconomyExecute,
+            FramePhase::DialogueDispatch,
+            FramePhase::NpcUpdate,
+            FramePhase::DialoguePoll,
+            FramePhase::Presentation,
+        )
+            .chain(),
+    );
+}
@@ -0,0 +1,61 @@
+//! Which clock a timer follows. World-space elements that belong to the simulation follow
+//! the `SimulationClock`, so fast-forwarding shortens them too; screen-space, player-facing
+//! elements follow real time.
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use super::plugin::SimulationClock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeBasis {
+    /// Bevy's `Time`: wall-clock seconds, unaffected by the simulation time scale.
+    Real,
+    /// `SimulationClock`: scaled by the time scale and frame-delta clamp.
+    Simulation,
+}
+
+impl TimeBasis {
+    /// This frame's delta on this basis.
+    pub fn delta(self, time: &Time, clock: &SimulationClock) -> Duration {
+        match self {
+            Self::Real => time.delta(),
+            Self::Simulation => clock.last_scaled_delta(),
+        }
+    }
+
+    /// Time elapsed on this basis since startup. Only compare stamps taken on the same
+    /// basis.
+    pub fn elapsed(self, time: &Time, clock: &SimulationClock) -> Duration {
+        match self {
+            Self::Real => time.elapsed(),
+            Self::Simulation => clock.elapsed(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn simulation_basis_follows_the_time_scale() {
+        let mut time = Time::<()>::default();
+        time.advance_by(Duration::from_millis(125));
+        let mut clock = SimulationClock::new(10.0);
+        clock.tick(Duration::from_millis(125));
+
+        assert_eq!(
+            TimeBasis::Real.delta(&time, &clock),
+            Duration::from_millis(125)
+        );
+        assert_eq!(
+            TimeBasis::Simulation.delta(&time, &clock),
+            Duration::from_millis(1250)
+        );
+        assert_eq!(
+            TimeBasis::Simulation.elapsed(&time, &clock),
+            Duration::from_millis(1250)
+        );
+    }
+}
@@ -0,0 +1,232 @@
+//! Opt-in capture of the rendered prompts sent to the provider, for offline prompt review.
+//!
+//! With `DIALOGUE_CAPTURE_PROMPTS` set, every message list the OpenAI broker renders (or, in
+//! fallback mode, would have sent) is appended to `logs/dialogue_prompts.jsonl` as one JSON
+//! line keyed by request id. Only message roles and text are written; the API key and
+//! request headers never are. `cargo run --bin prompt_review` pairs the file with the
+//! telemetry log. Writes happen on the dialogue worker thread and never fail a request: the
+//! first error is logged and later ones are dropped quietly.
+use std::{
+    env,
+    fs::{create_dir_all, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+use bevy::log::warn;
+use chrono::Local;
+use serde::Serialize;
+
+use super::openai::ChatMessage;
+use crate::dialogue::types::DialogueRequestId;
+
+pub const ENV_CAPTURE_PROMPTS: &str = "DIALOGUE_CAPTURE_PROMPTS";
+const DEFAULT_PROMPT_CAPTURE_PATH: &str = "logs/dialogue_prompts.jsonl";
+
+/// Where a captured message list came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PromptSource {
+    /// Sent to the provider for this request alone.
+    Live,
+    /// Sent to the provider as one scenario of a batched call.
+    Batch,
+    /// Rendered for review only; the broker answered locally.
+    Fallback,
+}
+
+/// One captured prompt, as written to the capture file.
+#[derive(Debug, Serialize)]
+struct PromptCaptureRecord<'a> {
+    request_id: u64,
+    source: PromptSource,
+    captured_at: String,
+    messages: &'a [ChatMessage],
+}
+
+/// Appends rendered prompts to a JSONL file. Shared by the broker's worker threads.
+#[derive(Debug)]
+pub struct PromptCapture {
+    path: PathBuf,
+    file: Mutex<Option<File>>,
+    warned: AtomicBool,
+}
+
+impl PromptCapture {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            file: Mutex::new(None),
+            warned: AtomicBool::new(false),
+        }
+    }
+
+    /// Capture to the default path when `DIALOGUE_CAPTURE_PROMPTS` is switched on.
+    pub fn from_env() -> Option<Self> {
+        Self::from_lookup(|key| env::var(key).ok())
+    }
+
+    /// `1`, `true`, `yes`, or `on` (any case) enable capture; anything else leaves it off.
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Option<Self> {
+        let value = lookup(ENV_CAPTURE_PROMPTS)?;
+        let enabled = ["1", "true", "yes", "on"]
+            .iter()
+            .any(|flag| value.trim().eq_ignore_ascii_case(flag));
+        enabled.then(|| Self::new(DEFAULT_PROMPT_CAPTURE_PATH))
+    }
+
+    #[cfg(test)]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Appends `messages` for `request_id` and returns the capture file's path, or `None`
+    /// when the write failed.
+    pub(super) fn record(
+        &self,
+        request_id: DialogueRequestId,
+        source: PromptSource,
+        messages: &[ChatMessage],
+    ) -> Option<PathBuf> {
+        match self.append(request_id, source, messages) {
+            Ok(()) => Some(self.path.clone()),
+            Err(err) => {
+                if !self.warned.swap(true, Ordering::Relaxed) {
+                    warn!(
+                        "Failed to capture dialogue prompts to {:?} ({}); further capture errors are not logged",
+                        self.path, err
+                    );
+                }
+                None
+            }
+        }
+    }
+
+    fn append(
+        &self,
+        request_id: DialogueRequestId,
+        source: PromptSource,
+        messages: &[ChatMessage],
+    ) -> io::Result<()> {
+        let mut line = serialize_record(request_id, source, messages)?;
+        line.push(b'\n');
+
+        let mut file = match self.file.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let handle = match file.as_mut() {
+            Some(handle) => handle,
+            None => file.insert(open_append(&self.path)?),
+        };
+        let written = handle.write_all(&line).and_then(|()| handle.flush());
+        if written.is_err() {
+            // Reopen on the next capture rather than writing to a broken handle.
+            *file = None;
+        }
+        written
+    }
+}
+
+fn serialize_record(
+    request_id: DialogueRequestId,
+    source: PromptSource,
+    messages: &[ChatMessage],
+) -> io::Result<Vec<u8>> {
+    let record = PromptCaptureRecord {
+        request_id: request_id.value(),
+        source,
+        captured_at: Local::now().to_rfc3339(),
+        messages,
+    };
+    Ok(serde_json::to_vec(&record)?)
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    if let Some(parent) = path.parent() {
+        create_dir_all(parent)?;
+    }
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, fs};
+
+    use serde_json::Value;
+
+    use super::*;
+
+    fn messages() -> Vec<ChatMessage> {
+        vec![
+            ChatMessage {
+                role: "system",
+                content: "You are a villager.".to_string(),
+            },
+            ChatMessage {
+                role: "user",
+                content: "Speaker: 1\nTarget: 2".to_string(),
+            },
+        ]
+    }
+
+    #[test]
+    fn capture_is_off_unless_the_flag_is_set() {
+        let lookup = |value: Option<&str>| {
+            let vars: HashMap<&str, String> = value
+                .map(|value| (ENV_CAPTURE_PROMPTS, value.to_string()))
+                .into_iter()
+                .collect();
+            PromptCapture::from_lookup(move |key| vars.get(key).cloned())
+        };
+        assert!(lookup(None).is_none());
+        assert!(lookup(Some("0")).is_none());
+        assert!(lookup(Some("nope")).is_none());
+        let capture = lookup(Some(" TRUE ")).expect("capture on");
+        assert_eq!(capture.path(), Path::new(DEFAULT_PROMPT_CAPTURE_PATH));
+    }
+
+    #[test]
+    fn records_are_appended_as_json_lines() {
+        let dir = env::temp_dir().join(format!("thegame_prompt_capture_{}", std::process::id()));
+        let path = dir.join("prompts.jsonl");
+        let _ = fs::remove_dir_all(&dir);
+        let capture = PromptCapture::new(&path);
+
+        let written = capture.record(DialogueRequestId::new(7), PromptSource::Live, &messages());
+        assert_eq!(written.as_deref(), Some(path.as_path()));
+        capture.record(DialogueRequestId::new(8), PromptSource::Fallback, &[]);
+
+        let contents = fs::read_to_string(&path).unwrap();
+        let lines: Vec<Value> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["request_id"], 7);
+        assert_eq!(lines[0]["source"], "live");
+        assert_eq!(lines[0]["messages"][0]["role"], "system");
+        assert_eq!(lines[0]["messages"][1]["content"], "Speaker: 1\nTarget: 2");
+        assert!(lines[0]["captured_at"].is_string());
+        assert_eq!(lines[1]["source"], "fallback");
+        assert_eq!(lines[1]["messages"], Value::Array(Vec::new()));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn write_failures_do_not_fail_the_caller() {
+        // A directory cannot be opened for appending.
+        let capture = PromptCapture::new(env::temp_dir());
+        assert!(capture
+            .record(DialogueRequestId::new(1), PromptSource::Live, &messages())
+            .is_none());
+        assert!(capture.warned.load(Ordering::Relaxed));
+        assert!(capture
+            .record(DialogueRequestId::new(2), PromptSource::Live, &messages())
+            .is_none());
+    }
+}
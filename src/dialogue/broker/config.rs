@@ -1,95 +1,412 @@
 use std::{env, fmt, time::Duration};
 
 const DEFAULT_BASE_URL: &str = "https://api.openai.com";
-const DEFAULT_CHAT_PATH: &str = "/v1/chat/completions";
+const DEFAULT_API_VERSION_PATH: &str = "/v1";
+const CHAT_COMPLETIONS_PATH: &str = "/chat/completions";
 const DEFAULT_MODEL: &str = "gpt-4o-mini";
 const DEFAULT_TEMPERATURE: f32 = 0.7;
+const MAX_TEMPERATURE: f32 = 2.0;
 const DEFAULT_MAX_OUTPUT_TOKENS: u16 = 220;
 const DEFAULT_TIMEOUT_SECS: u64 = 15;
+const DEFAULT_BATCH_SIZE: usize = 1;
+const DEFAULT_MAX_PROMPT_TOKENS: u32 = 1200;
+
+const ENV_API_KEY: &str = "OPENAI_API_KEY";
+const ENV_BASE_URL: &str = "OPENAI_BASE_URL";
+const ENV_ORG: &str = "OPENAI_ORG";
+const ENV_PROJECT: &str = "OPENAI_PROJECT";
+const ENV_MODEL: &str = "OPENAI_MODEL";
+const ENV_TIMEOUT_SECONDS: &str = "OPENAI_TIMEOUT_SECONDS";
+const ENV_TIMEOUT_SECS_LEGACY: &str = "OPENAI_TIMEOUT_SECS";
+const ENV_MAX_TOKENS: &str = "OPENAI_MAX_TOKENS";
+const ENV_MAX_OUTPUT_TOKENS_LEGACY: &str = "OPENAI_MAX_OUTPUT_TOKENS";
+const ENV_TEMPERATURE: &str = "OPENAI_TEMPERATURE";
+const ENV_BATCH_SIZE: &str = "OPENAI_BATCH_SIZE";
+const ENV_MAX_PROMPT_TOKENS: &str = "OPENAI_MAX_PROMPT_TOKENS";
+const ENV_AUTO_CONTINUE: &str = "OPENAI_AUTO_CONTINUE";
 
 /// OpenAI chat configuration sourced from the environment.
 #[derive(Debug, Clone)]
 pub struct OpenAiConfig {
     pub api_key: String,
     pub base_url: String,
+    pub organization: Option<String>,
+    pub project: Option<String>,
     pub model: String,
     pub max_output_tokens: u16,
     pub temperature: f32,
     pub timeout: Duration,
+    /// Ambient requests bundled into one call; 1 (the default) disables batching.
+    pub batch_size: usize,
+    /// Estimated tokens a single-request prompt may use; example lines and then the oldest
+    /// context events are left out to stay under it.
+    pub max_prompt_tokens: u32,
+    /// Finish a reply cut off at `max_output_tokens` with one follow-up call, when the
+    /// request allows it. Off by default; truncated replies then end in an ellipsis.
+    pub auto_continue: bool,
 }
 
 impl OpenAiConfig {
     pub fn from_env() -> Result<Self, OpenAiConfigError> {
-        let api_key = env::var("OPENAI_API_KEY")
-            .map_err(|_| OpenAiConfigError::MissingApiKey)
-            .and_then(|value| {
-                let trimmed = value.trim();
-                if trimmed.is_empty() {
-                    Err(OpenAiConfigError::MissingApiKey)
-                } else {
-                    Ok(trimmed.to_string())
-                }
-            })?;
-
-        let base_url = env::var("OPENAI_BASE_URL")
-            .map(|value| value.trim().to_string())
-            .unwrap_or_else(|_| DEFAULT_BASE_URL.to_string());
-
-        let model = env::var("OPENAI_MODEL")
-            .map(|value| value.trim().to_string())
-            .unwrap_or_else(|_| DEFAULT_MODEL.to_string());
-
-        let timeout = env::var("OPENAI_TIMEOUT_SECS")
-            .ok()
-            .and_then(|value| value.parse::<u64>().ok())
-            .filter(|value| *value > 0)
-            .map(Duration::from_secs)
-            .unwrap_or_else(|| Duration::from_secs(DEFAULT_TIMEOUT_SECS));
-
-        let max_output_tokens = env::var("OPENAI_MAX_OUTPUT_TOKENS")
-            .ok()
-            .and_then(|value| value.parse::<u16>().ok())
-            .filter(|value| *value > 0)
-            .unwrap_or(DEFAULT_MAX_OUTPUT_TOKENS);
-
-        let temperature = env::var("OPENAI_TEMPERATURE")
-            .ok()
-            .and_then(|value| value.parse::<f32>().ok())
-            .filter(|value| *value >= 0.0)
-            .unwrap_or(DEFAULT_TEMPERATURE);
+        Self::from_lookup(|key| env::var(key).ok())
+    }
+
+    /// Parses and validates configuration from an arbitrary key lookup (the environment in
+    /// production, a map in tests). Unset values use defaults; set-but-invalid values error.
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, OpenAiConfigError> {
+        let read = |key: &str| lookup(key).map(|value| value.trim().to_string());
+
+        let api_key = read(ENV_API_KEY)
+            .filter(|value| !value.is_empty())
+            .ok_or(OpenAiConfigError::MissingApiKey)?;
+
+        let base_url = match read(ENV_BASE_URL) {
+            Some(value) => parse_base_url(&value)?,
+            None => DEFAULT_BASE_URL.to_string(),
+        };
+
+        let organization = read(ENV_ORG).filter(|value| !value.is_empty());
+        let project = read(ENV_PROJECT).filter(|value| !value.is_empty());
+
+        let model = match read(ENV_MODEL) {
+            Some(value) if value.is_empty() => {
+                return Err(OpenAiConfigError::invalid(ENV_MODEL, "must not be empty"));
+            }
+            Some(value) => value,
+            None => DEFAULT_MODEL.to_string(),
+        };
+
+        let timeout = match read_with_legacy(&read, ENV_TIMEOUT_SECONDS, ENV_TIMEOUT_SECS_LEGACY) {
+            Some((field, value)) => Duration::from_secs(parse_positive::<u64>(field, &value)?),
+            None => Duration::from_secs(DEFAULT_TIMEOUT_SECS),
+        };
+
+        let max_output_tokens =
+            match read_with_legacy(&read, ENV_MAX_TOKENS, ENV_MAX_OUTPUT_TOKENS_LEGACY) {
+                Some((field, value)) => parse_positive::<u16>(field, &value)?,
+                None => DEFAULT_MAX_OUTPUT_TOKENS,
+            };
+
+        let temperature = match read(ENV_TEMPERATURE) {
+            Some(value) => parse_temperature(&value)?,
+            None => DEFAULT_TEMPERATURE,
+        };
+
+        let batch_size = match read(ENV_BATCH_SIZE) {
+            Some(value) => parse_positive::<usize>(ENV_BATCH_SIZE, &value)?,
+            None => DEFAULT_BATCH_SIZE,
+        };
+
+        let max_prompt_tokens = match read(ENV_MAX_PROMPT_TOKENS) {
+            Some(value) => parse_positive::<u32>(ENV_MAX_PROMPT_TOKENS, &value)?,
+            None => DEFAULT_MAX_PROMPT_TOKENS,
+        };
+
+        let auto_continue = match read(ENV_AUTO_CONTINUE) {
+            Some(value) => parse_flag(ENV_AUTO_CONTINUE, &value)?,
+            None => false,
+        };
 
         Ok(Self {
             api_key,
             base_url,
+            organization,
+            project,
             model,
             max_output_tokens,
             temperature,
             timeout,
+            batch_size,
+            max_prompt_tokens,
+            auto_continue,
         })
     }
 
+    /// Full chat completions endpoint. Accepts bare hosts, versioned paths (`/v1`), and
+    /// bases that already point at the completions route.
     pub fn chat_url(&self) -> String {
-        format!(
-            "{}{}",
-            self.base_url.trim_end_matches('/'),
-            DEFAULT_CHAT_PATH
-        )
+        let base = self.base_url.trim_end_matches('/');
+        if base.ends_with(CHAT_COMPLETIONS_PATH) {
+            return base.to_string();
+        }
+
+        let last_segment = base.rsplit('/').next().unwrap_or_default();
+        if is_version_segment(last_segment) {
+            format!("{base}{CHAT_COMPLETIONS_PATH}")
+        } else {
+            format!("{base}{DEFAULT_API_VERSION_PATH}{CHAT_COMPLETIONS_PATH}")
+        }
+    }
+}
+
+fn read_with_legacy(
+    read: &impl Fn(&str) -> Option<String>,
+    field: &'static str,
+    legacy_field: &'static str,
+) -> Option<(&'static str, String)> {
+    read(field)
+        .map(|value| (field, value))
+        .or_else(|| read(legacy_field).map(|value| (legacy_field, value)))
+}
+
+fn parse_base_url(value: &str) -> Result<String, OpenAiConfigError> {
+    if value.is_empty() {
+        return Err(OpenAiConfigError::invalid(
+            ENV_BASE_URL,
+            "must not be empty",
+        ));
+    }
+    if !(value.starts_with("http://") || value.starts_with("https://")) {
+        return Err(OpenAiConfigError::invalid(
+            ENV_BASE_URL,
+            "must start with http:// or https://",
+        ));
+    }
+    Ok(value.trim_end_matches('/').to_string())
+}
+
+fn parse_positive<T>(field: &'static str, value: &str) -> Result<T, OpenAiConfigError>
+where
+    T: std::str::FromStr + PartialOrd + Default,
+{
+    let parsed = value.parse::<T>().map_err(|_| {
+        OpenAiConfigError::invalid(field, format!("`{value}` is not a valid number"))
+    })?;
+    if parsed <= T::default() {
+        return Err(OpenAiConfigError::invalid(
+            field,
+            "must be greater than zero",
+        ));
+    }
+    Ok(parsed)
+}
+
+fn parse_temperature(value: &str) -> Result<f32, OpenAiConfigError> {
+    let parsed = value.parse::<f32>().map_err(|_| {
+        OpenAiConfigError::invalid(ENV_TEMPERATURE, format!("`{value}` is not a valid number"))
+    })?;
+    if !parsed.is_finite() || !(0.0..=MAX_TEMPERATURE).contains(&parsed) {
+        return Err(OpenAiConfigError::invalid(
+            ENV_TEMPERATURE,
+            format!("must be between 0 and {MAX_TEMPERATURE}"),
+        ));
+    }
+    Ok(parsed)
+}
+
+fn parse_flag(field: &'static str, value: &str) -> Result<bool, OpenAiConfigError> {
+    match value.to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "0" | "false" | "no" | "off" => Ok(false),
+        _ => Err(OpenAiConfigError::invalid(
+            field,
+            format!("`{value}` is not true or false"),
+        )),
     }
 }
 
+fn is_version_segment(segment: &str) -> bool {
+    segment
+        .strip_prefix('v')
+        .is_some_and(|rest| !rest.is_empty() && rest.chars().all(|c| c.is_ascii_digit()))
+}
+
 #[derive(Debug)]
 pub enum OpenAiConfigError {
     MissingApiKey,
+    InvalidValue { field: &'static str, reason: String },
     ClientBuild(String),
 }
 
+impl OpenAiConfigError {
+    fn invalid(field: &'static str, reason: impl Into<String>) -> Self {
+        Self::InvalidValue {
+            field,
+            reason: reason.into(),
+        }
+    }
+}
+
 impl fmt::Display for OpenAiConfigError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::MissingApiKey => write!(f, "missing OPENAI_API_KEY"),
+            Self::InvalidValue { field, reason } => write!(f, "invalid {}: {}", field, reason),
             Self::ClientBuild(message) => write!(f, "client build failure: {}", message),
         }
     }
 }
 
 impl std::error::Error for OpenAiConfigError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn parse(pairs: &[(&str, &str)]) -> Result<OpenAiConfig, OpenAiConfigError> {
+        let mut vars: HashMap<String, String> = pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        vars.entry(ENV_API_KEY.to_string())
+            .or_insert_with(|| "sk-test".to_string());
+        OpenAiConfig::from_lookup(|key| vars.get(key).cloned())
+    }
+
+    fn invalid_field(result: Result<OpenAiConfig, OpenAiConfigError>) -> &'static str {
+        match result {
+            Err(OpenAiConfigError::InvalidValue { field, .. }) => field,
+            other => panic!("expected InvalidValue, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn defaults_apply_when_unset() {
+        let config = parse(&[]).expect("defaults should parse");
+        assert_eq!(config.base_url, DEFAULT_BASE_URL);
+        assert_eq!(config.model, DEFAULT_MODEL);
+        assert_eq!(config.timeout, Duration::from_secs(DEFAULT_TIMEOUT_SECS));
+        assert_eq!(config.max_output_tokens, DEFAULT_MAX_OUTPUT_TOKENS);
+        assert_eq!(config.temperature, DEFAULT_TEMPERATURE);
+        assert!(config.organization.is_none());
+        assert!(config.project.is_none());
+        assert_eq!(config.batch_size, DEFAULT_BATCH_SIZE);
+        assert_eq!(config.max_prompt_tokens, DEFAULT_MAX_PROMPT_TOKENS);
+        assert!(!config.auto_continue);
+    }
+
+    #[test]
+    fn missing_or_blank_api_key_is_reported() {
+        let result = OpenAiConfig::from_lookup(|_| None);
+        assert!(matches!(result, Err(OpenAiConfigError::MissingApiKey)));
+        let result = parse(&[(ENV_API_KEY, "  ")]);
+        assert!(matches!(result, Err(OpenAiConfigError::MissingApiKey)));
+    }
+
+    #[test]
+    fn overrides_are_parsed() {
+        let config = parse(&[
+            (ENV_BASE_URL, "https://proxy.example/openai/"),
+            (ENV_ORG, "org-123"),
+            (ENV_PROJECT, "proj-456"),
+            (ENV_MODEL, "gpt-4o"),
+            (ENV_TIMEOUT_SECONDS, "30"),
+            (ENV_MAX_TOKENS, "400"),
+            (ENV_TEMPERATURE, "1.2"),
+            (ENV_BATCH_SIZE, "3"),
+            (ENV_MAX_PROMPT_TOKENS, "800"),
+            (ENV_AUTO_CONTINUE, "On"),
+        ])
+        .expect("overrides should parse");
+        assert_eq!(config.base_url, "https://proxy.example/openai");
+        assert_eq!(config.organization.as_deref(), Some("org-123"));
+        assert_eq!(config.project.as_deref(), Some("proj-456"));
+        assert_eq!(config.model, "gpt-4o");
+        assert_eq!(config.timeout, Duration::from_secs(30));
+        assert_eq!(config.max_output_tokens, 400);
+        assert_eq!(config.temperature, 1.2);
+        assert_eq!(config.batch_size, 3);
+        assert_eq!(config.max_prompt_tokens, 800);
+        assert!(config.auto_continue);
+    }
+
+    #[test]
+    fn legacy_names_still_apply() {
+        let config = parse(&[
+            (ENV_TIMEOUT_SECS_LEGACY, "9"),
+            (ENV_MAX_OUTPUT_TOKENS_LEGACY, "64"),
+        ])
+        .unwrap();
+        assert_eq!(config.timeout, Duration::from_secs(9));
+        assert_eq!(config.max_output_tokens, 64);
+
+        let config = parse(&[(ENV_TIMEOUT_SECONDS, "5"), (ENV_TIMEOUT_SECS_LEGACY, "9")]).unwrap();
+        assert_eq!(config.timeout, Duration::from_secs(5), "new name wins");
+    }
+
+    #[test]
+    fn invalid_values_name_their_field() {
+        assert_eq!(invalid_field(parse(&[(ENV_MODEL, " ")])), ENV_MODEL);
+        assert_eq!(invalid_field(parse(&[(ENV_BASE_URL, "")])), ENV_BASE_URL);
+        assert_eq!(
+            invalid_field(parse(&[(ENV_BASE_URL, "api.openai.com")])),
+            ENV_BASE_URL
+        );
+        assert_eq!(
+            invalid_field(parse(&[(ENV_TIMEOUT_SECONDS, "0")])),
+            ENV_TIMEOUT_SECONDS
+        );
+        assert_eq!(
+            invalid_field(parse(&[(ENV_TIMEOUT_SECS_LEGACY, "soon")])),
+            ENV_TIMEOUT_SECS_LEGACY
+        );
+        assert_eq!(
+            invalid_field(parse(&[(ENV_MAX_TOKENS, "0")])),
+            ENV_MAX_TOKENS
+        );
+        assert_eq!(
+            invalid_field(parse(&[(ENV_MAX_TOKENS, "70000")])),
+            ENV_MAX_TOKENS
+        );
+        assert_eq!(
+            invalid_field(parse(&[(ENV_TEMPERATURE, "-0.1")])),
+            ENV_TEMPERATURE
+        );
+        assert_eq!(
+            invalid_field(parse(&[(ENV_TEMPERATURE, "2.5")])),
+            ENV_TEMPERATURE
+        );
+        assert_eq!(
+            invalid_field(parse(&[(ENV_TEMPERATURE, "NaN")])),
+            ENV_TEMPERATURE
+        );
+        assert_eq!(
+            invalid_field(parse(&[(ENV_AUTO_CONTINUE, "sometimes")])),
+            ENV_AUTO_CONTINUE
+        );
+    }
+
+    #[test]
+    fn invalid_value_display_mentions_field_and_reason() {
+        let error = OpenAiConfigError::invalid(ENV_TEMPERATURE, "must be between 0 and 2");
+        assert_eq!(
+            error.to_string(),
+            "invalid OPENAI_TEMPERATURE: must be between 0 and 2"
+        );
+    }
+
+    #[test]
+    fn chat_url_joins_paths() {
+        let url_for = |base: &str| parse(&[(ENV_BASE_URL, base)]).unwrap().chat_url();
+
+        assert_eq!(
+            parse(&[]).unwrap().chat_url(),
+            "https://api.openai.com/v1/chat/completions"
+        );
+        assert_eq!(
+            url_for("https://proxy.example"),
+            "https://proxy.example/v1/chat/completions"
+        );
+        assert_eq!(
+            url_for("https://proxy.example///"),
+            "https://proxy.example/v1/chat/completions"
+        );
+        assert_eq!(
+            url_for("https://proxy.example/v1"),
+            "https://proxy.example/v1/chat/completions"
+        );
+        assert_eq!(
+            url_for("https://proxy.example/openai/v2/"),
+            "https://proxy.example/openai/v2/chat/completions"
+        );
+        assert_eq!(
+            url_for("http://localhost:8080/v1/chat/completions"),
+            "http://localhost:8080/v1/chat/completions"
+        );
+        assert_eq!(
+            url_for("https://proxy.example/vision"),
+            "https://proxy.example/vision/v1/chat/completions"
+        );
+    }
+}
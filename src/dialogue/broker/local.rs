@@ -0,0 +1,30 @@
+//! Broker that answers every request with a local fabrication.
+use super::{DialogueBroker, DialogueProviderKind};
+use crate::dialogue::{
+    errors::DialogueError,
+    status::DialogueConnectionState,
+    types::{DialogueRequest, DialogueRequestId, DialogueResponse},
+};
+
+/// Offline provider for comparing live replies against the canned ones. Needs no
+/// credentials, so it is always available to the router.
+#[derive(Debug, Default)]
+pub struct LocalDialogueBroker;
+
+impl DialogueBroker for LocalDialogueBroker {
+    fn provider_kind(&self) -> DialogueProviderKind {
+        DialogueProviderKind::Local
+    }
+
+    fn connection_state(&self) -> DialogueConnectionState {
+        DialogueConnectionState::Fallback
+    }
+
+    fn process(
+        &self,
+        request_id: DialogueRequestId,
+        request: &DialogueRequest,
+    ) -> Result<DialogueResponse, DialogueError> {
+        Ok(self.fabricate(request_id, request))
+    }
+}
@@ -1,6 +1,8 @@
 //! Dialogue broker trait and OpenAI-backed implementation.
 
+pub mod capture;
 pub mod config;
+pub mod local;
 pub mod openai;
 
 use std::fmt;
@@ -11,18 +13,40 @@ use super::{
     types::{DialogueRequest, DialogueRequestId, DialogueResponse},
 };
 
+pub use local::LocalDialogueBroker;
 pub use openai::OpenAiDialogueBroker;
 
+/// Most characters a summary kept without the provider holds before it is cut.
+const FALLBACK_SUMMARY_CHARS: usize = 160;
+const FALLBACK_SUMMARY_ELLIPSIS: &str = "...";
+/// Rough characters per token, used to estimate prompt size before sending.
+const CHARS_PER_TOKEN: usize = 4;
+
 /// Dialogue provider flavours we can route to.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum DialogueProviderKind {
+    /// The OpenAI chat completions API.
     OpenAi,
+    /// Canned replies built from the request itself; never leaves the machine.
+    Local,
+}
+
+impl DialogueProviderKind {
+    /// Parses `openai` or `local`, ignoring case and surrounding whitespace.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "openai" => Some(Self::OpenAi),
+            "local" => Some(Self::Local),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for DialogueProviderKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let label = match self {
             Self::OpenAi => "OpenAi",
+            Self::Local => "Local",
         };
         write!(f, "{}", label)
     }
@@ -39,4 +63,66 @@ pub trait DialogueBroker: Send + Sync {
         request_id: DialogueRequestId,
         request: &DialogueRequest,
     ) -> Result<DialogueResponse, DialogueError>;
+
+    /// Local reply built from the request's own context, or now and then one of the
+    /// speaker's example lines, without calling the provider.
+    /// Used whenever live calls are off the table, e.g. once the daily API budget is spent.
+    fn fabricate(
+        &self,
+        request_id: DialogueRequestId,
+        request: &DialogueRequest,
+    ) -> DialogueResponse {
+        DialogueResponse::new(
+            request_id,
+            self.provider_kind(),
+            request.speaker,
+            request.target,
+            openai::fallback_reply(request_id, request),
+        )
+    }
+
+    /// Most requests one `process_batch` call may carry; 1 turns batching off.
+    fn max_batch_size(&self) -> usize {
+        1
+    }
+
+    /// Answers several requests at once, one result per entry in input order. The default
+    /// processes them one by one.
+    fn process_batch(
+        &self,
+        batch: &[(DialogueRequestId, DialogueRequest)],
+    ) -> Vec<Result<DialogueResponse, DialogueError>> {
+        batch
+            .iter()
+            .map(|(request_id, request)| self.process(*request_id, request))
+            .collect()
+    }
+
+    /// Condenses a finished conversation between `speaker_name` and the player (one
+    /// "Name: line" per line in `transcript`) into a note the speaker can be reminded of
+    /// later. The default keeps the start of the transcript without calling the provider.
+    fn summarize(&self, _speaker_name: &str, transcript: &str) -> String {
+        truncated_summary(transcript)
+    }
+}
+
+/// Rough token count of `text`, for budgeting prompts before they are sent.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.len().div_ceil(CHARS_PER_TOKEN)
+}
+
+/// `transcript` on one line, cut at a word boundary once it runs past
+/// `FALLBACK_SUMMARY_CHARS`.
+pub fn truncated_summary(transcript: &str) -> String {
+    let flattened = transcript.split_whitespace().collect::<Vec<_>>().join(" ");
+    if flattened.chars().count() <= FALLBACK_SUMMARY_CHARS {
+        return flattened;
+    }
+    let cut = flattened
+        .char_indices()
+        .nth(FALLBACK_SUMMARY_CHARS)
+        .map_or(flattened.len(), |(index, _)| index);
+    let kept = &flattened[..cut];
+    let kept = kept.rfind(' ').map_or(kept, |space| &kept[..space]);
+    format!("{}{}", kept.trim_end(), FALLBACK_SUMMARY_ELLIPSIS)
 }
@@ -0,0 +1,211 @@
+//! Several ambient requests in one call: numbered scenarios in, a JSON array of replies
+//! out, fanned back to one result per request.
+use std::fmt::Write;
+
+use serde::Deserialize;
+
+use super::{
+    client::ChatMessage, prompt::build_user_message, BATCH_MISSING_ENTRY_MESSAGE,
+    BATCH_RESPONSE_INSTRUCTION, BATCH_SCENARIO_PREFIX, BATCH_SYSTEM_SUFFIX,
+    BATCH_TRUNCATED_MESSAGE,
+};
+use crate::dialogue::broker::DialogueProviderKind;
+use crate::dialogue::{
+    errors::DialogueErrorKind,
+    prompts::PromptTemplates,
+    types::{DialogueRequest, DialogueRequestId, DialogueResponse},
+};
+
+/// Base system prompt plus batch instructions; per-topic guidance is left out because the
+/// scenarios may mix topics. Example lines are left out too, since each belongs to one
+/// speaker.
+pub(super) fn build_batch_messages(
+    templates: &PromptTemplates,
+    requests: &[&DialogueRequest],
+) -> Vec<ChatMessage> {
+    vec![
+        ChatMessage {
+            role: "system",
+            content: format!("{}\n\n{BATCH_SYSTEM_SUFFIX}", templates.system_prompt()),
+        },
+        ChatMessage {
+            role: "user",
+            content: build_batch_user_message(templates, requests),
+        },
+    ]
+}
+
+/// Numbered "Scenario N:" sections, each rendered like a single request, followed by the
+/// JSON reply instruction. Numbering starts at 1.
+fn build_batch_user_message(templates: &PromptTemplates, requests: &[&DialogueRequest]) -> String {
+    let mut text = String::new();
+    for (index, request) in requests.iter().enumerate() {
+        let _ = write!(
+            text,
+            "{BATCH_SCENARIO_PREFIX}{}:\n{}\n\n",
+            index + 1,
+            build_user_message(templates, request)
+        );
+    }
+    text.push_str(BATCH_RESPONSE_INSTRUCTION);
+    text
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchReplyEntry {
+    index: usize,
+    response: String,
+}
+
+/// Reply text per scenario, in scenario order. Tolerates code fences or chatter around the
+/// array, and an array cut off by the output cap keeps the entries that closed; entries
+/// that are blank, out of range, or repeat an index come back as `None`.
+fn parse_batch_responses(content: &str, count: usize) -> Vec<Option<String>> {
+    let mut replies = vec![None; count];
+    let array = match (content.find('['), content.rfind(']'), content.rfind('}')) {
+        (Some(start), Some(end), _) if start < end => content[start..=end].to_string(),
+        (Some(start), _, Some(last_entry)) if start < last_entry => {
+            format!("{}]", &content[start..=last_entry])
+        }
+        _ => return replies,
+    };
+    let Ok(values) = serde_json::from_str::<Vec<serde_json::Value>>(&array) else {
+        return replies;
+    };
+
+    let mut seen = vec![false; count];
+    for value in values {
+        let Ok(entry) = serde_json::from_value::<BatchReplyEntry>(value) else {
+            continue;
+        };
+        let Some(slot) = entry.index.checked_sub(1).filter(|slot| *slot < count) else {
+            continue;
+        };
+        if std::mem::replace(&mut seen[slot], true) {
+            replies[slot] = None;
+            continue;
+        }
+        let text = entry.response.trim();
+        if !text.is_empty() {
+            replies[slot] = Some(text.to_string());
+        }
+    }
+    replies
+}
+
+/// One result per entry: a response for each usable reply, a provider failure otherwise so
+/// the queue retries that request on its own. When the reply was `truncated`, the failure
+/// says the output cap cut the entry off rather than that the model skipped it.
+pub(super) fn fan_out_batch(
+    entries: &[(DialogueRequestId, &DialogueRequest)],
+    content: &str,
+    truncated: bool,
+) -> Vec<Result<DialogueResponse, DialogueErrorKind>> {
+    let missing = if truncated {
+        BATCH_TRUNCATED_MESSAGE
+    } else {
+        BATCH_MISSING_ENTRY_MESSAGE
+    };
+    entries
+        .iter()
+        .zip(parse_batch_responses(content, entries.len()))
+        .map(|((request_id, request), reply)| {
+            reply
+                .map(|text| {
+                    DialogueResponse::new(
+                        *request_id,
+                        DialogueProviderKind::OpenAi,
+                        request.speaker,
+                        request.target,
+                        text,
+                    )
+                })
+                .ok_or_else(|| DialogueErrorKind::provider_failure(missing))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::ambient;
+    use super::*;
+    use crate::npc::components::NpcId;
+
+    #[test]
+    fn batch_prompt_numbers_each_scenario() {
+        let templates = PromptTemplates::default();
+        let (first, second) = (ambient(1, "Morning!"), ambient(3, "Rain again?"));
+        let messages = build_batch_messages(&templates, &[&first, &second]);
+
+        assert!(messages[0].content.starts_with(templates.system_prompt()));
+        assert!(messages[0].content.ends_with(BATCH_SYSTEM_SUFFIX));
+        let user = &messages[1].content;
+        assert!(user.starts_with(&format!(
+            "Scenario 1:\n{}\n\nScenario 2:\n",
+            build_user_message(&templates, &first)
+        )));
+        assert!(user.contains(&build_user_message(&templates, &second)));
+        assert!(user.ends_with(BATCH_RESPONSE_INSTRUCTION));
+    }
+
+    #[test]
+    fn batch_replies_parse_through_fences_and_reject_bad_entries() {
+        let fenced = "```json\n[{\"index\": 2, \"response\": \" Wet again. \"}, \
+                      {\"index\": 1, \"response\": \"Morning!\"}]\n```";
+        assert_eq!(
+            parse_batch_responses(fenced, 2),
+            [Some("Morning!".to_string()), Some("Wet again.".to_string())]
+        );
+
+        let messy = r#"[{"index": 1, "response": "  "}, {"index": 0, "response": "zero"},
+            {"index": 4, "response": "too far"}, {"index": 2, "response": "one"},
+            {"index": 2, "response": "two"}, {"index": 3}, "noise",
+            {"index": 3, "response": "Fine day."}]"#;
+        assert_eq!(
+            parse_batch_responses(messy, 3),
+            [None, None, Some("Fine day.".to_string())]
+        );
+        assert_eq!(parse_batch_responses("Sorry, I can't.", 2), [None, None]);
+    }
+
+    #[test]
+    fn fan_out_answers_parsed_entries_and_fails_the_rest() {
+        let (first, second) = (ambient(1, "Morning!"), ambient(3, "Rain again?"));
+        let entries = [
+            (DialogueRequestId::new(10), &first),
+            (DialogueRequestId::new(11), &second),
+        ];
+        let results = fan_out_batch(
+            &entries,
+            r#"[{"index": 1, "response": "Morning to you."}]"#,
+            false,
+        );
+
+        let answered = results[0].as_ref().expect("first entry was answered");
+        assert_eq!(answered.request_id, DialogueRequestId::new(10));
+        assert_eq!(answered.speaker, NpcId::new(1));
+        assert_eq!(answered.target, Some(NpcId::new(2)));
+        assert_eq!(answered.content, "Morning to you.");
+        assert!(matches!(
+            results[1],
+            Err(DialogueErrorKind::ProviderFailure { .. })
+        ));
+    }
+
+    #[test]
+    fn truncated_batch_keeps_closed_entries_and_blames_the_cap_for_the_rest() {
+        let (first, second) = (ambient(1, "Morning!"), ambient(3, "Rain again?"));
+        let entries = [
+            (DialogueRequestId::new(10), &first),
+            (DialogueRequestId::new(11), &second),
+        ];
+        let cut = r#"[{"index": 1, "response": "Morning to you."}, {"index": 2, "respo"#;
+
+        let results = fan_out_batch(&entries, cut, true);
+        assert_eq!(results[0].as_ref().unwrap().content, "Morning to you.");
+        let Err(DialogueErrorKind::ProviderFailure { message, .. }) = &results[1] else {
+            panic!("the cut-off entry fails: {:?}", results[1]);
+        };
+        assert_eq!(message, BATCH_TRUNCATED_MESSAGE);
+    }
+}
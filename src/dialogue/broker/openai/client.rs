@@ -0,0 +1,558 @@
+//! Live calls to the OpenAI chat completions endpoint: single requests with an optional
+//! continuation, batches, conversation summaries, and sorting failed calls into classes.
+use std::path::PathBuf;
+
+use bevy::log::warn;
+use reqwest::{
+    blocking::Client,
+    header::{HeaderMap, RETRY_AFTER},
+    StatusCode,
+};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    batch::{build_batch_messages, fan_out_batch},
+    prompt::build_messages,
+    AUTH_ERROR_CODES, AUTH_ERROR_TYPES, CONTINUATION_PROMPT, DEFAULT_RATE_LIMIT_BACKOFF,
+    FINISH_REASON_LENGTH, MODEL_NOT_FOUND_CODE, OPENAI_ORGANIZATION_HEADER, OPENAI_PROJECT_HEADER,
+    PLAYER_SUMMARY_MAX_OUTPUT_TOKENS, PLAYER_SUMMARY_SYSTEM_PROMPT, POLICY_ERROR_CODES,
+    TRUNCATION_MARKER,
+};
+use crate::dialogue::broker::{
+    capture::{PromptCapture, PromptSource},
+    config::{OpenAiConfig, OpenAiConfigError},
+    DialogueProviderKind,
+};
+use crate::dialogue::{
+    errors::{DialogueErrorKind, ProviderFailureClass},
+    prompts::SharedPromptTemplates,
+    types::{DialogueRequest, DialogueRequestId, DialogueResponse},
+};
+
+pub(super) struct OpenAiLiveClient {
+    http: Client,
+    pub(super) config: OpenAiConfig,
+    templates: SharedPromptTemplates,
+}
+
+impl OpenAiLiveClient {
+    pub(super) fn new(
+        config: OpenAiConfig,
+        templates: SharedPromptTemplates,
+    ) -> Result<Self, OpenAiConfigError> {
+        let http = Client::builder()
+            .timeout(config.timeout)
+            .build()
+            .map_err(|err| OpenAiConfigError::ClientBuild(err.to_string()))?;
+
+        Ok(Self {
+            http,
+            config,
+            templates,
+        })
+    }
+
+    pub(super) fn send(
+        &self,
+        request_id: DialogueRequestId,
+        request: &DialogueRequest,
+        capture: Option<&PromptCapture>,
+    ) -> Result<DialogueResponse, DialogueErrorKind> {
+        let templates = self.templates.snapshot();
+        let max_tokens = templates
+            .max_output_tokens_for(request.topic_hint)
+            .unwrap_or(self.config.max_output_tokens);
+        let messages = build_messages(&templates, request, self.config.max_prompt_tokens as usize);
+        let prompt_file =
+            capture.and_then(|capture| capture.record(request_id, PromptSource::Live, &messages));
+        let mut completion = self.complete(&messages, max_tokens.into())?;
+        let mut continued = false;
+        if should_continue(self.config.auto_continue, request, &completion) {
+            let messages = continuation_messages(messages, &completion.text);
+            (completion, continued) =
+                merge_continuation(completion, self.complete(&messages, max_tokens.into()));
+        }
+
+        Ok(DialogueResponse::new(
+            request_id,
+            DialogueProviderKind::OpenAi,
+            request.speaker,
+            request.target,
+            completion.display_text(),
+        )
+        .with_tokens_used(completion.tokens_used)
+        .with_prompt_file(prompt_file)
+        .with_truncated(completion.truncated)
+        .with_continued(continued))
+    }
+
+    /// One call for every entry; the token cap is the sum of the per-entry caps. A failed
+    /// call fails every entry with the same error. Reported usage is split evenly across
+    /// the answered entries.
+    pub(super) fn send_batch(
+        &self,
+        entries: &[(DialogueRequestId, &DialogueRequest)],
+        capture: Option<&PromptCapture>,
+    ) -> Vec<Result<DialogueResponse, DialogueErrorKind>> {
+        let templates = self.templates.snapshot();
+        let max_tokens = entries
+            .iter()
+            .map(|(_, request)| {
+                u32::from(
+                    templates
+                        .max_output_tokens_for(request.topic_hint)
+                        .unwrap_or(self.config.max_output_tokens),
+                )
+            })
+            .sum();
+        let requests: Vec<&DialogueRequest> = entries.iter().map(|(_, request)| *request).collect();
+
+        let messages = build_batch_messages(&templates, &requests);
+        // Every entry is captured with the whole shared message list.
+        let prompt_files: Vec<Option<PathBuf>> = entries
+            .iter()
+            .map(|(request_id, _)| {
+                capture
+                    .and_then(|capture| capture.record(*request_id, PromptSource::Batch, &messages))
+            })
+            .collect();
+
+        match self.complete(&messages, max_tokens) {
+            Ok(completion) => {
+                let share = completion
+                    .tokens_used
+                    .map(|total| total.div_ceil(entries.len() as u32));
+                if completion.truncated {
+                    warn!(
+                        "Batched dialogue reply hit the output cap; unanswered entries retry alone"
+                    );
+                }
+                fan_out_batch(entries, &completion.text, completion.truncated)
+                    .into_iter()
+                    .zip(prompt_files)
+                    .map(|(result, prompt_file)| {
+                        result.map(|response| {
+                            response
+                                .with_tokens_used(share)
+                                .with_prompt_file(prompt_file)
+                        })
+                    })
+                    .collect()
+            }
+            Err(kind) => entries.iter().map(|_| Err(kind.clone())).collect(),
+        }
+    }
+
+    pub(super) fn summarize(
+        &self,
+        speaker_name: &str,
+        transcript: &str,
+    ) -> Result<String, DialogueErrorKind> {
+        let messages = vec![
+            ChatMessage {
+                role: "system",
+                content: PLAYER_SUMMARY_SYSTEM_PROMPT.replace("{speaker}", speaker_name),
+            },
+            ChatMessage {
+                role: "user",
+                content: transcript.to_string(),
+            },
+        ];
+        self.complete(&messages, PLAYER_SUMMARY_MAX_OUTPUT_TOKENS)
+            .map(|completion| completion.text)
+    }
+
+    /// Sends one chat completion and returns its trimmed, non-empty reply text.
+    fn complete(
+        &self,
+        messages: &[ChatMessage],
+        max_tokens: u32,
+    ) -> Result<Completion, DialogueErrorKind> {
+        let payload = ChatCompletionRequest {
+            model: self.config.model.as_str(),
+            messages,
+            max_tokens: Some(max_tokens),
+            temperature: self.config.temperature,
+        };
+
+        let url = self.config.chat_url();
+        let mut builder = self
+            .http
+            .post(url)
+            .bearer_auth(&self.config.api_key)
+            .json(&payload);
+        if let Some(organization) = &self.config.organization {
+            builder = builder.header(OPENAI_ORGANIZATION_HEADER, organization);
+        }
+        if let Some(project) = &self.config.project {
+            builder = builder.header(OPENAI_PROJECT_HEADER, project);
+        }
+
+        let response = builder
+            .send()
+            .map_err(|err| DialogueErrorKind::provider_failure(err.to_string()))?;
+
+        let status = response.status();
+        let headers = response.headers().clone();
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = parse_retry_after(&headers).unwrap_or(DEFAULT_RATE_LIMIT_BACKOFF);
+            return Err(DialogueErrorKind::rate_limited(retry_after));
+        }
+
+        if !status.is_success() {
+            if let Ok(body) = response.json::<OpenAiErrorResponse>() {
+                let message = format!(
+                    "{} (type: {}, code: {:?})",
+                    body.error.message, body.error.error_type, body.error.code
+                );
+                return Err(DialogueErrorKind::classified_failure(
+                    classify_http_failure(status, Some(&body.error)),
+                    message,
+                ));
+            }
+
+            return Err(DialogueErrorKind::classified_failure(
+                classify_http_failure(status, None),
+                format!("HTTP {} from OpenAI", status),
+            ));
+        }
+
+        let completion: ChatCompletionResponse = response
+            .json()
+            .map_err(|err| DialogueErrorKind::provider_failure(err.to_string()))?;
+
+        Completion::from_response(completion)
+    }
+}
+
+/// One answered chat completion.
+#[derive(Debug, Clone, PartialEq)]
+struct Completion {
+    text: String,
+    /// Total tokens OpenAI reported for the call, if any.
+    tokens_used: Option<u32>,
+    /// The reply stopped at the output cap rather than where the model meant to end.
+    truncated: bool,
+}
+
+impl Completion {
+    fn from_response(response: ChatCompletionResponse) -> Result<Self, DialogueErrorKind> {
+        let tokens_used = response.usage.map(|usage| usage.total_tokens);
+        response
+            .choices
+            .into_iter()
+            .find_map(|choice| Some((choice.message.content?, choice.finish_reason)))
+            .map(|(text, finish_reason)| Self {
+                text: text.trim().to_string(),
+                tokens_used,
+                truncated: finish_reason.as_deref() == Some(FINISH_REASON_LENGTH),
+            })
+            .filter(|completion| !completion.text.is_empty())
+            .ok_or_else(|| {
+                DialogueErrorKind::provider_failure(
+                    "OpenAI returned an empty completion for dialogue request",
+                )
+            })
+    }
+
+    /// The text to show: a truncated reply loses any dangling dash or comma and ends in
+    /// an ellipsis, so "I think we should—" reads as trailing off.
+    fn display_text(&self) -> String {
+        if !self.truncated {
+            return self.text.clone();
+        }
+        let trimmed = self
+            .text
+            .trim_end_matches(|c: char| c.is_whitespace() || "-–—,;:…".contains(c));
+        format!("{trimmed}{TRUNCATION_MARKER}")
+    }
+}
+
+/// Whether a reply is worth one follow-up call: it was cut off, the broker is configured
+/// to continue, and the queue found room in the budget for the request.
+fn should_continue(
+    auto_continue: bool,
+    request: &DialogueRequest,
+    completion: &Completion,
+) -> bool {
+    auto_continue && request.allow_continuation && completion.truncated
+}
+
+/// The original conversation, the partial reply as the assistant's turn, and a request
+/// to finish it.
+fn continuation_messages(mut messages: Vec<ChatMessage>, partial: &str) -> Vec<ChatMessage> {
+    messages.push(ChatMessage {
+        role: "assistant",
+        content: partial.to_string(),
+    });
+    messages.push(ChatMessage {
+        role: "user",
+        content: CONTINUATION_PROMPT.to_string(),
+    });
+    messages
+}
+
+/// Joins a follow-up onto the partial reply with combined usage; the result is still
+/// truncated if the follow-up ran out too. A failed follow-up keeps the partial reply.
+/// The flag reports whether the follow-up answered.
+fn merge_continuation(
+    partial: Completion,
+    rest: Result<Completion, DialogueErrorKind>,
+) -> (Completion, bool) {
+    let rest = match rest {
+        Ok(rest) => rest,
+        Err(kind) => {
+            warn!("Could not finish a truncated reply ({kind}); showing it cut short");
+            return (partial, false);
+        }
+    };
+    let joiner = if rest.text.starts_with(|c: char| ",.;:!?…".contains(c)) {
+        ""
+    } else {
+        " "
+    };
+    let tokens_used = match (partial.tokens_used, rest.tokens_used) {
+        (Some(first), Some(second)) => Some(first + second),
+        (first, second) => first.or(second),
+    };
+    let merged = Completion {
+        text: format!("{}{joiner}{}", partial.text.trim_end(), rest.text),
+        tokens_used,
+        truncated: rest.truncated,
+    };
+    (merged, true)
+}
+
+/// Sorts an unsuccessful response into a failure class from its status and, when the body
+/// parsed, the error's `type` and `code`. Policy refusals arrive as 400s, so the body is
+/// checked first; statuses not named here stay transient.
+fn classify_http_failure(
+    status: StatusCode,
+    detail: Option<&OpenAiErrorDetail>,
+) -> ProviderFailureClass {
+    let error_type = detail.map_or("", |detail| detail.error_type.as_str());
+    let code = detail
+        .and_then(|detail| detail.code.as_deref())
+        .unwrap_or("");
+    if POLICY_ERROR_CODES.contains(&code) {
+        return ProviderFailureClass::Policy;
+    }
+    if AUTH_ERROR_CODES.contains(&code)
+        || AUTH_ERROR_TYPES.contains(&error_type)
+        || matches!(status, StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN)
+    {
+        return ProviderFailureClass::Auth;
+    }
+    if code == MODEL_NOT_FOUND_CODE || status == StatusCode::NOT_FOUND {
+        return ProviderFailureClass::NotFound;
+    }
+    ProviderFailureClass::Transient
+}
+
+fn parse_retry_after(headers: &HeaderMap) -> Option<f32> {
+    headers.get(RETRY_AFTER).and_then(|value| {
+        value
+            .to_str()
+            .ok()
+            .and_then(|text| text.parse::<f32>().ok())
+    })
+}
+
+#[derive(Debug, Serialize)]
+struct ChatCompletionRequest<'a> {
+    model: &'a str,
+    messages: &'a [ChatMessage],
+    #[serde(rename = "max_tokens")]
+    max_tokens: Option<u32>,
+    temperature: f32,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct ChatMessage {
+    pub(crate) role: &'static str,
+    pub(crate) content: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatCompletionResponse {
+    choices: Vec<ChatChoice>,
+    #[serde(default)]
+    usage: Option<ChatUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatUsage {
+    total_tokens: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatChoice {
+    message: ChatChoiceMessage,
+    /// "stop" for a finished reply, "length" when `max_tokens` cut it off.
+    #[serde(default)]
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatChoiceMessage {
+    content: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiErrorResponse {
+    error: OpenAiErrorDetail,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiErrorDetail {
+    message: String,
+    #[serde(rename = "type")]
+    error_type: String,
+    code: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::ambient;
+    use super::*;
+
+    fn fixture(content: &str, finish_reason: &str, total_tokens: u32) -> Completion {
+        let body = serde_json::json!({
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": content},
+                "finish_reason": finish_reason,
+            }],
+            "usage": {"total_tokens": total_tokens},
+        });
+        Completion::from_response(serde_json::from_value(body).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn length_finish_reason_marks_the_reply_cut_short() {
+        let finished = fixture(" We should head north. ", "stop", 40);
+        assert!(!finished.truncated);
+        assert_eq!(finished.display_text(), "We should head north.");
+
+        let cut = fixture("I think we should—", FINISH_REASON_LENGTH, 60);
+        assert!(cut.truncated);
+        assert_eq!(cut.tokens_used, Some(60));
+        assert_eq!(cut.display_text(), "I think we should…");
+        assert_eq!(
+            fixture("Bread, cheese, ", FINISH_REASON_LENGTH, 10).display_text(),
+            "Bread, cheese…"
+        );
+
+        let legacy: ChatCompletionResponse =
+            serde_json::from_str(r#"{"choices": [{"message": {"content": "Hello."}}]}"#).unwrap();
+        let legacy = Completion::from_response(legacy).unwrap();
+        assert!(!legacy.truncated && legacy.tokens_used.is_none());
+    }
+
+    #[test]
+    fn continuations_need_the_flag_budget_and_a_cut_reply() {
+        let cut = fixture("I think we should", FINISH_REASON_LENGTH, 60);
+        let finished = fixture("We should go.", "stop", 40);
+        let mut request = ambient(1, "Where to?");
+        assert!(
+            !should_continue(true, &request, &cut),
+            "no budget room was granted"
+        );
+        request.allow_continuation = true;
+        assert!(should_continue(true, &request, &cut));
+        assert!(!should_continue(false, &request, &cut));
+        assert!(!should_continue(true, &request, &finished));
+
+        let messages = continuation_messages(
+            vec![ChatMessage {
+                role: "user",
+                content: "Where to?".to_string(),
+            }],
+            &cut.text,
+        );
+        let roles: Vec<&str> = messages.iter().map(|message| message.role).collect();
+        assert_eq!(roles, ["user", "assistant", "user"]);
+        assert_eq!(messages[1].content, "I think we should");
+        assert_eq!(messages[2].content, CONTINUATION_PROMPT);
+    }
+
+    #[test]
+    fn continuations_join_the_text_and_combine_usage() {
+        let cut = fixture("I think we should", FINISH_REASON_LENGTH, 60);
+
+        let (merged, continued) =
+            merge_continuation(cut.clone(), Ok(fixture("head north.", "stop", 25)));
+        assert!(continued);
+        assert_eq!(merged.text, "I think we should head north.");
+        assert_eq!(merged.tokens_used, Some(85));
+        assert_eq!(merged.display_text(), merged.text);
+
+        let (merged, continued) = merge_continuation(
+            cut.clone(),
+            Ok(fixture(", if the road", FINISH_REASON_LENGTH, 25)),
+        );
+        assert!(continued && merged.truncated);
+        assert_eq!(merged.display_text(), "I think we should, if the road…");
+
+        let (kept, continued) =
+            merge_continuation(cut.clone(), Err(DialogueErrorKind::rate_limited(2.0)));
+        assert!(!continued);
+        assert_eq!(kept, cut);
+        assert_eq!(kept.display_text(), "I think we should…");
+    }
+
+    #[test]
+    fn http_failures_classify_by_status_and_error_body() {
+        let detail = |error_type: &str, code: Option<&str>| OpenAiErrorDetail {
+            message: "rejected".to_string(),
+            error_type: error_type.to_string(),
+            code: code.map(str::to_string),
+        };
+        let cases = [
+            (StatusCode::UNAUTHORIZED, None, ProviderFailureClass::Auth),
+            (StatusCode::FORBIDDEN, None, ProviderFailureClass::Auth),
+            (
+                StatusCode::BAD_REQUEST,
+                Some(detail("invalid_request_error", Some("invalid_api_key"))),
+                ProviderFailureClass::Auth,
+            ),
+            (StatusCode::NOT_FOUND, None, ProviderFailureClass::NotFound),
+            (
+                StatusCode::BAD_REQUEST,
+                Some(detail("invalid_request_error", Some("model_not_found"))),
+                ProviderFailureClass::NotFound,
+            ),
+            (
+                StatusCode::BAD_REQUEST,
+                Some(detail(
+                    "invalid_request_error",
+                    Some("content_policy_violation"),
+                )),
+                ProviderFailureClass::Policy,
+            ),
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Some(detail("server_error", None)),
+                ProviderFailureClass::Transient,
+            ),
+            (
+                StatusCode::BAD_GATEWAY,
+                None,
+                ProviderFailureClass::Transient,
+            ),
+            (
+                StatusCode::BAD_REQUEST,
+                Some(detail("invalid_request_error", None)),
+                ProviderFailureClass::Transient,
+            ),
+        ];
+        for (status, detail, expected) in cases {
+            assert_eq!(
+                classify_http_failure(status, detail.as_ref()),
+                expected,
+                "{status} with {detail:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,122 @@
+//! Offline replies composed from the request's own prompt and context, for when no live
+//! call is made.
+use std::fmt::Write;
+
+use super::{
+    prompt::{capitalize_first, relative_when, usable_examples},
+    EVENT_CAPACITY_HINT, FALLBACK_EXAMPLE_INTERVAL, FALLBACK_SCHEDULE_LEAD, FALLBACK_STATUS_LEAD,
+    FALLBACK_TARGET_LABEL, FALLBACK_TRADE_LEAD, SCHEDULE_UPDATE_PREFIX, SENTENCE_SUFFIX,
+    SUMMARY_PREFIX, TRADE_DETAIL_DAY_PREFIX, TRADE_DETAIL_THEY_PREFIX, USER_MESSAGE_FROM_SUFFIX,
+    USER_MESSAGE_TARGET_PREFIX, USER_MESSAGE_WITH_SUFFIX, VILLAGE_NEWS_PREFIX,
+};
+use crate::core::format::format_quantity;
+use crate::dialogue::types::{
+    DialogueContextEvent, DialogueRequest, DialogueRequestId, DialogueTopicHint,
+};
+
+/// Offline reply: usually the request's own context, but every few requests an NPC with
+/// example lines says one of them verbatim. The choice follows the request id, so it is
+/// repeatable.
+pub(crate) fn fallback_reply(request_id: DialogueRequestId, request: &DialogueRequest) -> String {
+    let examples = usable_examples(request);
+    let id = request_id.value();
+    if !examples.is_empty() && id.is_multiple_of(FALLBACK_EXAMPLE_INTERVAL) {
+        let pick = (id / FALLBACK_EXAMPLE_INTERVAL) as usize % examples.len();
+        return examples[pick].to_string();
+    }
+    compose_context_segments(request)
+}
+
+pub(super) fn compose_context_segments(request: &DialogueRequest) -> String {
+    let lead = match request.topic_hint {
+        DialogueTopicHint::Status => FALLBACK_STATUS_LEAD,
+        DialogueTopicHint::Trade => FALLBACK_TRADE_LEAD,
+        DialogueTopicHint::Schedule => FALLBACK_SCHEDULE_LEAD,
+    };
+    let summary = request.context.summary.as_deref().map(str::trim);
+    let mut text = String::with_capacity(
+        lead.len()
+            + request.prompt.len()
+            + summary.map_or(0, str::len)
+            + (request.context.events.len() + 1) * EVENT_CAPACITY_HINT,
+    );
+    let _ = write!(text, "{} {}", lead, request.prompt.trim());
+
+    if let Some(summary) = summary.filter(|summary| !summary.is_empty()) {
+        let _ = write!(text, " {SUMMARY_PREFIX} {summary}");
+    }
+
+    match request.target {
+        Some(id) => {
+            let _ = write!(
+                text,
+                " {USER_MESSAGE_TARGET_PREFIX}{}",
+                request.participant_name(id)
+            );
+        }
+        None => {
+            let _ = write!(text, " {USER_MESSAGE_TARGET_PREFIX}{FALLBACK_TARGET_LABEL}");
+        }
+    }
+
+    for event in &request.context.events {
+        match event {
+            DialogueContextEvent::Trade(trade) => {
+                let when = relative_when(request, trade.day, trade.time_of_day).map_or_else(
+                    || format!("{TRADE_DETAIL_DAY_PREFIX}{}", trade.day),
+                    |phrase| capitalize_first(&phrase),
+                );
+                let _ = write!(
+                    text,
+                    " {when}{TRADE_DETAIL_THEY_PREFIX}{} {}",
+                    trade.reason.past_tense(),
+                    format_quantity(&trade.descriptor.label, trade.descriptor.quantity)
+                );
+                if let Some(target) = trade.to {
+                    let _ = write!(
+                        text,
+                        "{USER_MESSAGE_WITH_SUFFIX}{}",
+                        request.participant_name(target)
+                    );
+                }
+                if let Some(source) = trade.from {
+                    let _ = write!(
+                        text,
+                        "{USER_MESSAGE_FROM_SUFFIX}{}",
+                        request.participant_name(source)
+                    );
+                }
+                text.push_str(SENTENCE_SUFFIX);
+            }
+            DialogueContextEvent::ScheduleUpdate { description } => {
+                let _ = write!(
+                    text,
+                    " {SCHEDULE_UPDATE_PREFIX} {description}{SENTENCE_SUFFIX}"
+                );
+            }
+            DialogueContextEvent::Custom { text: custom } => {
+                let custom = custom.trim();
+                if !custom.is_empty() {
+                    let _ = write!(text, " {custom}");
+                }
+            }
+            DialogueContextEvent::Hearsay(hearsay) => {
+                let _ = write!(
+                    text,
+                    " {} {}{SENTENCE_SUFFIX}",
+                    hearsay.fidelity.hedge(),
+                    hearsay.subject
+                );
+            }
+            DialogueContextEvent::VillageNews(news) => {
+                let _ = write!(
+                    text,
+                    " {VILLAGE_NEWS_PREFIX} {}{SENTENCE_SUFFIX}",
+                    news.text
+                );
+            }
+        }
+    }
+
+    text
+}
@@ -0,0 +1,379 @@
+//! The OpenAI dialogue broker. `client` makes the live HTTP calls, `prompt` renders the
+//! messages they send, `batch` packs several ambient requests into one call, and `fallback`
+//! composes offline replies. Constants for retry timing and prompt wording are grouped here.
+use bevy::log::warn;
+
+use super::super::errors::{DialogueError, DialogueErrorKind};
+use super::{
+    capture::{PromptCapture, PromptSource},
+    config::{OpenAiConfig, OpenAiConfigError},
+    truncated_summary, DialogueBroker, DialogueProviderKind,
+};
+use crate::dialogue::{
+    prompts::SharedPromptTemplates,
+    status::DialogueConnectionState,
+    types::{DialogueRequest, DialogueRequestId, DialogueResponse},
+};
+
+mod batch;
+mod client;
+mod fallback;
+mod prompt;
+
+pub(crate) use client::ChatMessage;
+pub(super) use fallback::fallback_reply;
+#[cfg(test)]
+pub use prompt::render_prompt;
+
+use client::OpenAiLiveClient;
+use prompt::build_messages;
+
+const MANUAL_RETRY_PROMPT: &str = "retry later";
+const MANUAL_RETRY_BACKOFF_SECONDS: f32 = 3.0;
+const FALLBACK_TARGET_LABEL: &str = "player";
+const SUMMARY_PREFIX: &str = "Summary:";
+const SCHEDULE_UPDATE_PREFIX: &str = "Schedule update:";
+const CUSTOM_CONTEXT_PREFIX: &str = "Also worth knowing:";
+const HEARSAY_PREFIX: &str = "Heard secondhand:";
+const HEARSAY_ORIGIN_PREFIX: &str = " (about ";
+const HEARSAY_DAY_PREFIX: &str = "day ";
+const VILLAGE_NEWS_PREFIX: &str = "Around the village:";
+const VILLAGE_NEWS_DAY_PREFIX: &str = "day ";
+const CONTEXT_FALLBACK_MESSAGE: &str = "No notable context available.";
+const SENTENCE_SUFFIX: &str = ".";
+const DEFAULT_RATE_LIMIT_BACKOFF: f32 = 10.0;
+const POLICY_ERROR_CODES: &[&str] = &["content_policy_violation", "content_filter"];
+const AUTH_ERROR_CODES: &[&str] = &["invalid_api_key", "invalid_organization"];
+const AUTH_ERROR_TYPES: &[&str] = &["authentication_error", "permission_error"];
+const MODEL_NOT_FOUND_CODE: &str = "model_not_found";
+const OPENAI_ORGANIZATION_HEADER: &str = "OpenAI-Organization";
+const OPENAI_PROJECT_HEADER: &str = "OpenAI-Project";
+const USER_MESSAGE_TARGET_PREFIX: &str = "Target: ";
+const USER_MESSAGE_CONTEXT_SUMMARY_PREFIX: &str = "Context summary: ";
+const USER_MESSAGE_TRADE_EVENT_PREFIX: &str = "Trade event: ";
+const USER_MESSAGE_TRADE_DAY_PREFIX: &str = "Day ";
+const USER_MESSAGE_TRADE_FROM_PREFIX: &str = " (from ";
+const USER_MESSAGE_TRADE_TO_PREFIX: &str = " (to ";
+const USER_MESSAGE_WITH_SUFFIX: &str = " with ";
+const USER_MESSAGE_FROM_SUFFIX: &str = " after receiving it from ";
+const USER_MESSAGE_TRADE_SUFFIX: &str = ")";
+const TRADE_DETAIL_DAY_PREFIX: &str = "On day ";
+const TRADE_DETAIL_THEY_PREFIX: &str = " they ";
+const FALLBACK_STATUS_LEAD: &str = "Just passing the time:";
+const FALLBACK_TRADE_LEAD: &str = "About the goods:";
+const FALLBACK_SCHEDULE_LEAD: &str = "Next on my list:";
+/// Rough bytes per context event, used to pre-size the prompt buffers.
+const EVENT_CAPACITY_HINT: usize = 72;
+const BATCH_SYSTEM_SUFFIX: &str = "You will receive several numbered scenarios, each with its own speaker. Answer every scenario independently, following the same rules as for a single line.";
+const BATCH_SCENARIO_PREFIX: &str = "Scenario ";
+const BATCH_RESPONSE_INSTRUCTION: &str = "Reply with only a JSON array holding one object per scenario, like [{\"index\": 1, \"response\": \"...\"}], where index is the scenario number and response is that speaker's line.";
+const BATCH_MISSING_ENTRY_MESSAGE: &str = "no usable entry for this request in the batch response";
+const BATCH_TRUNCATED_MESSAGE: &str =
+    "the batch response hit the output token cap before this request's entry";
+const PLAYER_SUMMARY_SYSTEM_PROMPT: &str = "Summarize this conversation between {speaker} and the player in one or two short sentences, addressed to {speaker} in the second person, e.g. \"You told the player about the failing harvest.\" Mention only what was actually said.";
+const PLAYER_SUMMARY_MAX_OUTPUT_TOKENS: u32 = 60;
+/// `finish_reason` OpenAI reports when a reply ran into `max_tokens`.
+const FINISH_REASON_LENGTH: &str = "length";
+const CONTINUATION_PROMPT: &str = "You were cut off. Finish that thought in one short sentence, starting exactly where you stopped and without repeating anything.";
+/// Appended to a reply that ran out of tokens, after any dangling dash or comma.
+const TRUNCATION_MARKER: &str = "…";
+/// Every Nth fallback reply for an NPC with example lines is one of those lines verbatim.
+const FALLBACK_EXAMPLE_INTERVAL: u64 = 3;
+
+/// Primary OpenAI dialogue broker.
+pub struct OpenAiDialogueBroker {
+    mode: BrokerMode,
+    /// Renders the prompt a fallback reply stands in for, when capture is on.
+    templates: SharedPromptTemplates,
+    capture: Option<PromptCapture>,
+}
+
+enum BrokerMode {
+    Live(OpenAiLiveClient),
+    Fallback,
+}
+
+impl BrokerMode {
+    /// Live when the environment holds a usable OpenAI configuration, fallback otherwise.
+    fn from_env(templates: &SharedPromptTemplates) -> Self {
+        match OpenAiConfig::from_env() {
+            Ok(config) => match OpenAiLiveClient::new(config, templates.clone()) {
+                Ok(client) => BrokerMode::Live(client),
+                Err(err) => {
+                    warn!(
+                        "OpenAI broker running in fallback mode ({}). Check HTTP client configuration.",
+                        err
+                    );
+                    BrokerMode::Fallback
+                }
+            },
+            Err(OpenAiConfigError::MissingApiKey) => {
+                warn!("OPENAI_API_KEY not set; dialogue broker using local fallback responses.");
+                BrokerMode::Fallback
+            }
+            Err(err @ OpenAiConfigError::InvalidValue { .. }) => {
+                warn!(
+                    "OpenAI configuration rejected ({}); dialogue broker using local fallback responses.",
+                    err
+                );
+                BrokerMode::Fallback
+            }
+            Err(OpenAiConfigError::ClientBuild(message)) => {
+                warn!(
+                    "Failed to construct OpenAI HTTP client ({}). Falling back to local responses.",
+                    message
+                );
+                BrokerMode::Fallback
+            }
+        }
+    }
+}
+
+impl OpenAiDialogueBroker {
+    /// Builds the broker; live requests render prompts from the shared templates.
+    /// Prompt capture follows `DIALOGUE_CAPTURE_PROMPTS`.
+    pub fn new(templates: SharedPromptTemplates) -> Self {
+        Self {
+            mode: BrokerMode::from_env(&templates),
+            templates,
+            capture: PromptCapture::from_env(),
+        }
+    }
+
+    /// Broker that always answers locally, regardless of the environment.
+    #[cfg(test)]
+    pub fn fallback() -> Self {
+        Self {
+            mode: BrokerMode::Fallback,
+            templates: SharedPromptTemplates::default(),
+            capture: None,
+        }
+    }
+
+    /// Provider-specific checks layered on top of `validate_dialogue_request`.
+    fn validate(&self, request: &DialogueRequest) -> Result<(), DialogueErrorKind> {
+        if request.prompt.eq_ignore_ascii_case(MANUAL_RETRY_PROMPT) {
+            return Err(DialogueErrorKind::rate_limited(
+                MANUAL_RETRY_BACKOFF_SECONDS,
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl DialogueBroker for OpenAiDialogueBroker {
+    fn provider_kind(&self) -> DialogueProviderKind {
+        DialogueProviderKind::OpenAi
+    }
+
+    fn connection_state(&self) -> DialogueConnectionState {
+        match self.mode {
+            BrokerMode::Live(_) => DialogueConnectionState::Live,
+            BrokerMode::Fallback => DialogueConnectionState::Fallback,
+        }
+    }
+
+    fn process(
+        &self,
+        request_id: DialogueRequestId,
+        request: &DialogueRequest,
+    ) -> Result<DialogueResponse, DialogueError> {
+        if let Err(kind) = self.validate(request) {
+            return Err(DialogueError::new(request_id, self.provider_kind(), kind));
+        }
+
+        match &self.mode {
+            BrokerMode::Live(client) => {
+                match client.send(request_id, request, self.capture.as_ref()) {
+                    Ok(response) => Ok(response),
+                    Err(kind) => Err(DialogueError::new(request_id, self.provider_kind(), kind)),
+                }
+            }
+            BrokerMode::Fallback => {
+                let response = self.fabricate(request_id, request);
+                let Some(capture) = &self.capture else {
+                    return Ok(response);
+                };
+                // No live budget applies, so the review copy is left untrimmed.
+                let messages = build_messages(&self.templates.snapshot(), request, usize::MAX);
+                let prompt_file = capture.record(request_id, PromptSource::Fallback, &messages);
+                Ok(response.with_prompt_file(prompt_file))
+            }
+        }
+    }
+
+    fn max_batch_size(&self) -> usize {
+        match &self.mode {
+            BrokerMode::Live(client) => client.config.batch_size,
+            BrokerMode::Fallback => 1,
+        }
+    }
+
+    fn process_batch(
+        &self,
+        batch: &[(DialogueRequestId, DialogueRequest)],
+    ) -> Vec<Result<DialogueResponse, DialogueError>> {
+        let BrokerMode::Live(client) = &self.mode else {
+            return batch
+                .iter()
+                .map(|(request_id, request)| self.process(*request_id, request))
+                .collect();
+        };
+
+        let mut results: Vec<Option<Result<DialogueResponse, DialogueError>>> = batch
+            .iter()
+            .map(|(request_id, request)| {
+                self.validate(request)
+                    .err()
+                    .map(|kind| Err(DialogueError::new(*request_id, self.provider_kind(), kind)))
+            })
+            .collect();
+        let entries: Vec<(usize, &DialogueRequestId, &DialogueRequest)> = batch
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| results[*index].is_none())
+            .map(|(index, (request_id, request))| (index, request_id, request))
+            .collect();
+        let sendable: Vec<(DialogueRequestId, &DialogueRequest)> = entries
+            .iter()
+            .map(|(_, request_id, request)| (**request_id, *request))
+            .collect();
+
+        for ((index, request_id, _), result) in entries
+            .iter()
+            .zip(client.send_batch(&sendable, self.capture.as_ref()))
+        {
+            results[*index] = Some(
+                result.map_err(|kind| DialogueError::new(**request_id, self.provider_kind(), kind)),
+            );
+        }
+        results.into_iter().flatten().collect()
+    }
+
+    /// One short completion when live; the truncated transcript in fallback mode or when
+    /// the call fails.
+    fn summarize(&self, speaker_name: &str, transcript: &str) -> String {
+        let BrokerMode::Live(client) = &self.mode else {
+            return truncated_summary(transcript);
+        };
+        client
+            .summarize(speaker_name, transcript)
+            .unwrap_or_else(|kind| {
+                warn!(
+                    "Conversation summary for {} fell back to the transcript: {}",
+                    speaker_name, kind
+                );
+                truncated_summary(transcript)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue::types::{
+        DialogueContext, DialogueContextEvent, DialogueTopicHint, TradeContext, TradeContextReason,
+        TradeDescriptor,
+    };
+    use crate::npc::components::NpcId;
+
+    #[test]
+    fn fallback_response_includes_context() {
+        let broker = OpenAiDialogueBroker::fallback();
+
+        let trade_context = DialogueContextEvent::Trade(TradeContext {
+            day: 3,
+            time_of_day: None,
+            from: Some(NpcId::new(1)),
+            to: Some(NpcId::new(2)),
+            descriptor: TradeDescriptor::new("grain crate", 2),
+            reason: TradeContextReason::Exchange,
+        });
+
+        let request = DialogueRequest::new(
+            NpcId::new(1),
+            Some(NpcId::new(2)),
+            "Discuss the latest trade",
+            DialogueTopicHint::Trade,
+            DialogueContext {
+                summary: Some("Short summary".to_string()),
+                events: vec![trade_context],
+                now: None,
+            },
+        );
+
+        let response = broker
+            .process(DialogueRequestId::new(7), &request)
+            .expect("fallback should succeed");
+        assert!(response.content.starts_with(FALLBACK_TRADE_LEAD));
+        assert!(response.content.contains("Summary"));
+        assert!(response.content.contains("grain crate"));
+        assert_eq!(response.provider, DialogueProviderKind::OpenAi);
+    }
+
+    #[test]
+    fn fallback_replies_link_their_captured_prompt() {
+        let path = std::env::temp_dir().join(format!(
+            "thegame_fallback_prompts_{}.jsonl",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let broker = OpenAiDialogueBroker {
+            capture: Some(PromptCapture::new(&path)),
+            ..OpenAiDialogueBroker::fallback()
+        };
+        let request = DialogueRequest::new(
+            NpcId::new(1),
+            None,
+            "Say hello",
+            DialogueTopicHint::Status,
+            DialogueContext::default(),
+        );
+
+        let response = broker
+            .process(DialogueRequestId::new(11), &request)
+            .expect("fallback should succeed");
+
+        assert_eq!(response.prompt_file.as_deref(), Some(path.as_path()));
+        let captured: serde_json::Value =
+            serde_json::from_str(std::fs::read_to_string(&path).unwrap().trim()).unwrap();
+        assert_eq!(captured["request_id"], 11);
+        assert_eq!(captured["source"], "fallback");
+        assert_eq!(captured["messages"][0]["role"], "system");
+        assert!(OpenAiDialogueBroker::fallback()
+            .process(DialogueRequestId::new(12), &request)
+            .unwrap()
+            .prompt_file
+            .is_none());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn manual_retry_prompt_triggers_backoff() {
+        let broker = OpenAiDialogueBroker::fallback();
+
+        let request = DialogueRequest::new(
+            NpcId::new(1),
+            None,
+            MANUAL_RETRY_PROMPT,
+            DialogueTopicHint::Status,
+            DialogueContext::default(),
+        );
+
+        let error = broker
+            .process(DialogueRequestId::new(1), &request)
+            .expect_err("retry prompt should error");
+        assert!(matches!(error.kind, DialogueErrorKind::RateLimited { .. }));
+    }
+
+    pub(super) fn ambient(speaker: u64, prompt: &str) -> DialogueRequest {
+        DialogueRequest::new(
+            NpcId::new(speaker),
+            Some(NpcId::new(speaker + 1)),
+            prompt,
+            DialogueTopicHint::Status,
+            DialogueContext::default(),
+        )
+    }
+}
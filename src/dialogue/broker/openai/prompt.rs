@@ -0,0 +1,601 @@
+//! Rendering a request into the chat messages a live call sends: the system prompt, the
+//! speaker's example lines, and the user turn, trimmed to the prompt budget.
+use std::{borrow::Cow, fmt::Write};
+
+use super::{
+    client::ChatMessage, CONTEXT_FALLBACK_MESSAGE, CUSTOM_CONTEXT_PREFIX, EVENT_CAPACITY_HINT,
+    FALLBACK_TARGET_LABEL, HEARSAY_DAY_PREFIX, HEARSAY_ORIGIN_PREFIX, HEARSAY_PREFIX,
+    SCHEDULE_UPDATE_PREFIX, USER_MESSAGE_CONTEXT_SUMMARY_PREFIX, USER_MESSAGE_TRADE_DAY_PREFIX,
+    USER_MESSAGE_TRADE_EVENT_PREFIX, USER_MESSAGE_TRADE_FROM_PREFIX, USER_MESSAGE_TRADE_SUFFIX,
+    USER_MESSAGE_TRADE_TO_PREFIX, VILLAGE_NEWS_DAY_PREFIX, VILLAGE_NEWS_PREFIX,
+};
+use crate::core::format::{format_quantity, relative_day_phrase};
+use crate::dialogue::broker::estimate_tokens;
+use crate::dialogue::{
+    prompts::{PromptTemplates, PromptVariables},
+    types::{DialogueContextEvent, DialogueRequest},
+};
+
+/// Every message a live call would send for `request`, untrimmed and separated by blank
+/// lines, for checking what reaches the model.
+#[cfg(test)]
+pub fn render_prompt(templates: &PromptTemplates, request: &DialogueRequest) -> String {
+    build_messages(templates, request, usize::MAX)
+        .into_iter()
+        .map(|message| message.content)
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// System prompt, then the speaker's example lines as earlier assistant replies, then the
+/// user turn. When the estimate exceeds `prompt_budget` tokens, examples go first (last
+/// one first), then context events (oldest first); the rest is sent even if still over.
+pub(super) fn build_messages(
+    templates: &PromptTemplates,
+    request: &DialogueRequest,
+    prompt_budget: usize,
+) -> Vec<ChatMessage> {
+    let system = templates.system_prompt_for(request.topic_hint);
+    let mut examples = usable_examples(request);
+    let mut request = Cow::Borrowed(request);
+    let user = loop {
+        let user = build_user_message(templates, &request);
+        let estimate = estimate_tokens(&system)
+            + examples
+                .iter()
+                .map(|line| estimate_tokens(line))
+                .sum::<usize>()
+            + estimate_tokens(&user);
+        if estimate <= prompt_budget {
+            break user;
+        }
+        if examples.pop().is_some() {
+            continue;
+        }
+        if request.context.events.is_empty() {
+            break user;
+        }
+        request.to_mut().context.events.remove(0);
+    };
+
+    let mut messages = Vec::with_capacity(examples.len() + 2);
+    messages.push(ChatMessage {
+        role: "system",
+        content: system,
+    });
+    messages.extend(examples.into_iter().map(|line| ChatMessage {
+        role: "assistant",
+        content: line.to_string(),
+    }));
+    messages.push(ChatMessage {
+        role: "user",
+        content: user,
+    });
+    messages
+}
+
+/// Trimmed, non-blank example lines in configured order.
+pub(super) fn usable_examples(request: &DialogueRequest) -> Vec<&str> {
+    request
+        .speaker_examples
+        .iter()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+pub(super) fn build_user_message(templates: &PromptTemplates, request: &DialogueRequest) -> String {
+    // `write!` into a `String` cannot fail, so its results are ignored throughout.
+    let mut speaker = String::with_capacity(48);
+    speaker.push_str(&request.participant_name(request.speaker));
+    if let Some(profile) = request
+        .speaker_profile
+        .as_deref()
+        .map(str::trim)
+        .filter(|profile| !profile.is_empty())
+    {
+        let _ = write!(speaker, " ({profile})");
+    }
+
+    let mut target = String::with_capacity(16);
+    match request.target {
+        Some(id) => target.push_str(&request.participant_name(id)),
+        None => target.push_str(FALLBACK_TARGET_LABEL),
+    }
+
+    let mut summary = String::new();
+    if let Some(text) = request
+        .context
+        .summary
+        .as_deref()
+        .map(str::trim)
+        .filter(|summary| !summary.is_empty())
+    {
+        summary.reserve(USER_MESSAGE_CONTEXT_SUMMARY_PREFIX.len() + text.len());
+        summary.push_str(USER_MESSAGE_CONTEXT_SUMMARY_PREFIX);
+        summary.push_str(text);
+    }
+
+    let mut events = String::with_capacity(request.context.events.len() * EVENT_CAPACITY_HINT);
+    for event in &request.context.events {
+        match event {
+            DialogueContextEvent::Trade(trade) => {
+                push_line_break(&mut events);
+                let when = relative_when(request, trade.day, trade.time_of_day).map_or_else(
+                    || format!("{USER_MESSAGE_TRADE_DAY_PREFIX}{}", trade.day),
+                    |phrase| capitalize_first(&phrase),
+                );
+                let _ = write!(
+                    events,
+                    "{USER_MESSAGE_TRADE_EVENT_PREFIX}{when} {} {}",
+                    trade.reason.past_tense(),
+                    format_quantity(&trade.descriptor.label, trade.descriptor.quantity)
+                );
+                if let Some(from) = trade.from {
+                    let _ = write!(
+                        events,
+                        "{USER_MESSAGE_TRADE_FROM_PREFIX}{}{USER_MESSAGE_TRADE_SUFFIX}",
+                        request.participant_name(from)
+                    );
+                }
+                if let Some(to) = trade.to {
+                    let _ = write!(
+                        events,
+                        "{USER_MESSAGE_TRADE_TO_PREFIX}{}{USER_MESSAGE_TRADE_SUFFIX}",
+                        request.participant_name(to)
+                    );
+                }
+            }
+            DialogueContextEvent::ScheduleUpdate { description } => {
+                let description = description.trim();
+                if !description.is_empty() {
+                    push_line_break(&mut events);
+                    let _ = write!(events, "{SCHEDULE_UPDATE_PREFIX} {description}");
+                }
+            }
+            DialogueContextEvent::Custom { text } => {
+                let text = text.trim();
+                if !text.is_empty() {
+                    push_line_break(&mut events);
+                    let _ = write!(events, "{CUSTOM_CONTEXT_PREFIX} {text}");
+                }
+            }
+            DialogueContextEvent::Hearsay(hearsay) => {
+                push_line_break(&mut events);
+                let when = relative_when(request, hearsay.day, None)
+                    .unwrap_or_else(|| format!("{HEARSAY_DAY_PREFIX}{}", hearsay.day));
+                let _ = write!(
+                    events,
+                    "{HEARSAY_PREFIX} {} {}{HEARSAY_ORIGIN_PREFIX}{}, {when})",
+                    hearsay.fidelity.hedge(),
+                    hearsay.subject,
+                    request.participant_name(hearsay.origin)
+                );
+            }
+            DialogueContextEvent::VillageNews(news) => {
+                push_line_break(&mut events);
+                let when = relative_when(request, news.day, None)
+                    .unwrap_or_else(|| format!("{VILLAGE_NEWS_DAY_PREFIX}{}", news.day));
+                let _ = write!(events, "{VILLAGE_NEWS_PREFIX} {} ({when})", news.text);
+            }
+        }
+    }
+
+    if summary.is_empty() && events.is_empty() {
+        events.push_str(CONTEXT_FALLBACK_MESSAGE);
+    }
+
+    templates.render_user_message(
+        request.topic_hint,
+        &PromptVariables {
+            speaker: &speaker,
+            target: &target,
+            topic: request.topic_hint.label(),
+            prompt: request.prompt.trim(),
+            summary: &summary,
+            events: &events,
+        },
+    )
+}
+
+/// When an event on `day` happened, relative to the clock the queue stamped on the
+/// request ("yesterday evening"); `None` for unstamped requests, which keep day numbers.
+pub(super) fn relative_when(
+    request: &DialogueRequest,
+    day: u64,
+    fraction: Option<f32>,
+) -> Option<String> {
+    request
+        .context
+        .now
+        .map(|now| relative_day_phrase(day, fraction, now.day, now.time_of_day))
+}
+
+pub(super) fn capitalize_first(text: &str) -> String {
+    let mut chars = text.chars();
+    chars.next().map_or_else(String::new, |first| {
+        first.to_uppercase().chain(chars).collect()
+    })
+}
+
+fn push_line_break(text: &mut String) {
+    if !text.is_empty() {
+        text.push('\n');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{
+        fallback::{compose_context_segments, fallback_reply},
+        OpenAiDialogueBroker, FALLBACK_STATUS_LEAD,
+    };
+    use super::*;
+    use crate::dialogue::broker::DialogueBroker;
+    use crate::dialogue::types::{
+        ContextClock, DialogueContext, DialogueRequestId, DialogueTopicHint, HearsayContext,
+        RumorFidelity, TradeContext, TradeContextReason, TradeDescriptor, VillageNewsContext,
+    };
+    use crate::npc::components::NpcId;
+
+    #[test]
+    fn user_message_includes_speaker_profile() {
+        let mut request = DialogueRequest::new(
+            NpcId::new(1),
+            None,
+            "Morning!",
+            DialogueTopicHint::Status,
+            DialogueContext::default(),
+        );
+        let templates = PromptTemplates::default();
+        assert!(build_user_message(&templates, &request).contains("Speaker: NPC-0001\n"));
+
+        request.speaker_profile = Some("a 25-year-old farmer".to_string());
+        assert!(build_user_message(&templates, &request)
+            .contains("Speaker: NPC-0001 (a 25-year-old farmer)"));
+    }
+
+    #[test]
+    fn custom_context_renders_in_both_builders() {
+        let request = DialogueRequest::new(
+            NpcId::new(1),
+            None,
+            "Morning!",
+            DialogueTopicHint::Status,
+            DialogueContext::with_events(vec![
+                DialogueContextEvent::Custom {
+                    text: " The well froze overnight. ".to_string(),
+                },
+                DialogueContextEvent::Custom {
+                    text: "  ".to_string(),
+                },
+            ]),
+        );
+
+        let message = build_user_message(&PromptTemplates::default(), &request);
+        assert!(message.contains("Also worth knowing: The well froze overnight.\n"));
+        assert!(!message.contains(CONTEXT_FALLBACK_MESSAGE));
+        assert_eq!(message.matches(CUSTOM_CONTEXT_PREFIX).count(), 1);
+        assert!(compose_context_segments(&request).ends_with(" The well froze overnight."));
+    }
+
+    #[test]
+    fn hearsay_is_hedged_by_fidelity() {
+        let request = DialogueRequest::new(
+            NpcId::new(3),
+            Some(NpcId::new(4)),
+            "Any news?",
+            DialogueTopicHint::Status,
+            DialogueContext::with_events(vec![DialogueContextEvent::Hearsay(HearsayContext {
+                origin: NpcId::new(1),
+                subject: "NPC-0001 exchanged 2 grain crate".to_string(),
+                day: 5,
+                fidelity: RumorFidelity::Secondhand,
+            })]),
+        );
+
+        assert!(build_user_message(&PromptTemplates::default(), &request).contains(
+            "Heard secondhand: I heard that NPC-0001 exchanged 2 grain crate (about NPC-0001, day 5)"
+        ));
+        assert!(compose_context_segments(&request)
+            .ends_with(" I heard that NPC-0001 exchanged 2 grain crate."));
+    }
+
+    #[test]
+    fn village_news_reads_relative_to_the_send_day() {
+        let mut context = DialogueContext::with_events(vec![DialogueContextEvent::VillageNews(
+            VillageNewsContext {
+                text: "Bryn turned 31".to_string(),
+                day: 4,
+            },
+        )]);
+        context.now = Some(ContextClock::new(5, 0.5));
+        let request = DialogueRequest::new(
+            NpcId::new(3),
+            Some(NpcId::new(4)),
+            "Any news?",
+            DialogueTopicHint::Status,
+            context,
+        );
+
+        assert!(build_user_message(&PromptTemplates::default(), &request)
+            .contains("Around the village: Bryn turned 31 (yesterday)"));
+        assert!(
+            compose_context_segments(&request).ends_with(" Around the village: Bryn turned 31.")
+        );
+    }
+
+    #[test]
+    fn messages_route_system_prompt_by_topic() {
+        let templates = PromptTemplates::default();
+        let mut request = DialogueRequest::new(
+            NpcId::new(1),
+            None,
+            "What's next?",
+            DialogueTopicHint::Schedule,
+            DialogueContext::default(),
+        );
+        let schedule = build_messages(&templates, &request, usize::MAX);
+        assert_eq!(schedule[0].role, "system");
+        assert_eq!(
+            schedule[0].content,
+            templates.system_prompt_for(DialogueTopicHint::Schedule)
+        );
+
+        request.topic_hint = DialogueTopicHint::Status;
+        let status = build_messages(&templates, &request, usize::MAX);
+        assert_ne!(status[0].content, schedule[0].content);
+
+        let broker = OpenAiDialogueBroker::fallback();
+        let response = broker
+            .process(DialogueRequestId::new(2), &request)
+            .expect("fallback should succeed");
+        assert!(response.content.starts_with(FALLBACK_STATUS_LEAD));
+    }
+
+    fn voiced_request(events: usize) -> DialogueRequest {
+        let mut request = DialogueRequest::new(
+            NpcId::new(1),
+            None,
+            "Morning!",
+            DialogueTopicHint::Status,
+            DialogueContext::with_events(
+                (0..events)
+                    .map(|index| DialogueContextEvent::Custom {
+                        text: format!("Event {index} happened at the mill today."),
+                    })
+                    .collect(),
+            ),
+        );
+        request.speaker_examples = vec![
+            "Aye, the stones turn slow when the river's low.".to_string(),
+            "  ".to_string(),
+            "Flour doesn't mill itself, friend.".to_string(),
+        ];
+        request
+    }
+
+    #[test]
+    fn examples_sit_between_system_and_user_messages() {
+        let templates = PromptTemplates::default();
+        let mut request = voiced_request(1);
+        let roles = |messages: &[ChatMessage]| {
+            messages
+                .iter()
+                .map(|message| message.role)
+                .collect::<Vec<_>>()
+        };
+
+        let messages = build_messages(&templates, &request, usize::MAX);
+        assert_eq!(
+            roles(&messages),
+            ["system", "assistant", "assistant", "user"]
+        );
+        assert_eq!(messages[1].content, request.speaker_examples[0]);
+        assert_eq!(messages[2].content, "Flour doesn't mill itself, friend.");
+        assert_eq!(
+            messages[3].content,
+            build_user_message(&templates, &request)
+        );
+
+        request.speaker_examples.clear();
+        let messages = build_messages(&templates, &request, usize::MAX);
+        assert_eq!(roles(&messages), ["system", "user"]);
+    }
+
+    #[test]
+    fn tight_budgets_drop_examples_before_context() {
+        let templates = PromptTemplates::default();
+        let request = voiced_request(3);
+        let full = build_messages(&templates, &request, usize::MAX);
+        let total: usize = full
+            .iter()
+            .map(|message| estimate_tokens(&message.content))
+            .sum();
+        let last_example = estimate_tokens(&full[2].content);
+
+        let trimmed = build_messages(&templates, &request, total - 1);
+        assert_eq!(trimmed.len(), 3, "the last example goes first");
+        assert_eq!(trimmed[1].content, full[1].content);
+
+        let bare = build_messages(&templates, &request, total - last_example - 1);
+        assert_eq!(bare.len(), 2, "both examples go before any context");
+        assert!(bare[1].content.contains("Event 0"));
+
+        let bare_total = estimate_tokens(&bare[0].content) + estimate_tokens(&bare[1].content);
+        let tight = build_messages(&templates, &request, bare_total - 1);
+        assert_eq!(tight.len(), 2);
+        assert!(
+            !tight[1].content.contains("Event 0"),
+            "oldest event dropped"
+        );
+        assert!(tight[1].content.contains("Event 2"));
+
+        let starved = build_messages(&templates, &request, 1);
+        assert!(
+            !starved[1].content.contains("Event"),
+            "everything droppable goes; the rest is still sent"
+        );
+    }
+
+    #[test]
+    fn fallback_repeats_an_example_line_every_few_requests() {
+        let request = voiced_request(0);
+        let replies: Vec<String> = (1..=9)
+            .map(|id| fallback_reply(DialogueRequestId::new(id), &request))
+            .collect();
+        assert_eq!(replies[2], "Flour doesn't mill itself, friend.");
+        assert_eq!(replies[5], request.speaker_examples[0]);
+        assert_eq!(replies[8], "Flour doesn't mill itself, friend.");
+        for (index, reply) in replies.iter().enumerate() {
+            if (index + 1) % 3 != 0 {
+                assert!(reply.starts_with(FALLBACK_STATUS_LEAD));
+            }
+        }
+
+        let mut silent = request.clone();
+        silent.speaker_examples.clear();
+        assert!(
+            fallback_reply(DialogueRequestId::new(3), &silent).starts_with(FALLBACK_STATUS_LEAD)
+        );
+    }
+
+    fn golden_request() -> DialogueRequest {
+        let mut request = DialogueRequest::new(
+            NpcId::new(1),
+            Some(NpcId::new(2)),
+            "  How was the harvest?  ",
+            DialogueTopicHint::Trade,
+            DialogueContext {
+                summary: Some(" Grain is scarce. ".to_string()),
+                events: vec![
+                    DialogueContextEvent::Trade(TradeContext {
+                        day: 3,
+                        time_of_day: None,
+                        from: Some(NpcId::new(1)),
+                        to: Some(NpcId::new(2)),
+                        descriptor: TradeDescriptor::new("grain crate", 2),
+                        reason: TradeContextReason::Exchange,
+                    }),
+                    DialogueContextEvent::Trade(TradeContext {
+                        day: 4,
+                        time_of_day: None,
+                        from: None,
+                        to: None,
+                        descriptor: TradeDescriptor::new("flour sack", 1),
+                        reason: TradeContextReason::Processing,
+                    }),
+                    DialogueContextEvent::ScheduleUpdate {
+                        description: "Heading to the mill".to_string(),
+                    },
+                    DialogueContextEvent::ScheduleUpdate {
+                        description: "   ".to_string(),
+                    },
+                ],
+                now: None,
+            },
+        );
+        request.speaker_profile = Some("a 25-year-old farmer".to_string());
+        request
+    }
+
+    #[test]
+    fn prompt_builders_match_golden_output() {
+        let templates = PromptTemplates::default();
+        let request = golden_request();
+        assert_eq!(
+            build_user_message(&templates, &request),
+            "Speaker: NPC-0001 (a 25-year-old farmer)\nTarget: NPC-0002\nTopic: trade\n\
+             Prompt: How was the harvest?\nContext summary: Grain is scarce.\n\
+             Trade event: Day 3 exchanged 2 grain crates (from NPC-0001) (to NPC-0002)\n\
+             Trade event: Day 4 processed 1 flour sack\nSchedule update: Heading to the mill\n\
+             Respond as the speaker, addressing the target naturally."
+        );
+        assert_eq!(
+            compose_context_segments(&request),
+            "About the goods: How was the harvest? Summary: Grain is scarce. Target: NPC-0002 \
+             On day 3 they exchanged 2 grain crates with NPC-0002 after receiving it from \
+             NPC-0001. On day 4 they processed 1 flour sack. Schedule update: Heading to the \
+             mill. Schedule update:    ."
+        );
+
+        let bare = DialogueRequest::new(
+            NpcId::new(5),
+            None,
+            "Hi",
+            DialogueTopicHint::Status,
+            DialogueContext::default(),
+        );
+        assert_eq!(
+            build_user_message(&templates, &bare),
+            "Speaker: NPC-0005\nTarget: player\nTopic: status\nPrompt: Hi\n\
+             No notable context available.\nRespond as the speaker, addressing the target \
+             naturally."
+        );
+        assert_eq!(
+            compose_context_segments(&bare),
+            "Just passing the time: Hi Target: player"
+        );
+    }
+
+    #[test]
+    fn stamped_clock_renders_event_days_relative_to_now() {
+        let templates = PromptTemplates::default();
+        let mut request = golden_request();
+        if let DialogueContextEvent::Trade(trade) = &mut request.context.events[0] {
+            trade.time_of_day = Some(0.8);
+        }
+        request
+            .context
+            .events
+            .push(DialogueContextEvent::Hearsay(HearsayContext {
+                origin: NpcId::new(1),
+                subject: "NPC-0001 milled flour".to_string(),
+                day: 2,
+                fidelity: RumorFidelity::Exact,
+            }));
+        request.context.now = Some(ContextClock::new(4, 0.6));
+
+        let message = build_user_message(&templates, &request);
+        assert!(message
+            .contains("Trade event: Yesterday evening exchanged 2 grain crates (from NPC-0001)"));
+        assert!(message.contains("Trade event: Earlier today processed 1 flour sack"));
+        assert!(message.contains("(about NPC-0001, the day before yesterday)"));
+        assert!(!message.contains("Day 3"));
+        let fallback = compose_context_segments(&request);
+        assert!(fallback.contains(" Yesterday evening they exchanged 2 grain crates"));
+        assert!(fallback.contains(" Earlier today they processed 1 flour sack."));
+        assert!(!fallback.contains("On day"));
+
+        request.context.now = Some(ContextClock::new(12, 0.6));
+        assert!(
+            build_user_message(&templates, &request).contains("Trade event: On day 3 exchanged")
+        );
+        assert!(compose_context_segments(&request).contains(" On day 4 they processed"));
+    }
+
+    #[test]
+    fn named_participants_keep_ids_out_of_prompts_and_fallbacks() {
+        let templates = PromptTemplates::default();
+        let mut request = golden_request();
+        request.speaker_name = Some("Alric".to_string());
+        request.target_name = Some("Bryn".to_string());
+
+        let user = build_user_message(&templates, &request);
+        assert!(user.starts_with("Speaker: Alric (a 25-year-old farmer)\nTarget: Bryn\n"));
+        assert!(user.contains("(from Alric) (to Bryn)"));
+        let fallback = compose_context_segments(&request);
+        assert!(fallback.contains("with Bryn after receiving it from Alric."));
+        for text in [&user, &fallback] {
+            assert!(!text.contains("NPC-"), "id leaked into: {text}");
+        }
+
+        let mut to_player = golden_request();
+        to_player.target = Some(NpcId::player());
+        to_player.speaker_name = Some("Alric".to_string());
+        to_player.context.events.clear();
+        let reply = fallback_reply(DialogueRequestId::new(1), &to_player);
+        assert!(reply.ends_with("Target: Player"), "{reply}");
+        assert!(!build_user_message(&templates, &to_player).contains("NPC-"));
+    }
+}
@@ -0,0 +1,404 @@
+//! Daily spend guardrail for live dialogue calls. Every request sent to a live provider
+//! counts against `DailyApiBudget`, charged an estimated token cost up front and corrected
+//! to the provider's reported usage when it answers. Once a request no longer fits, the
+//! queue answers it with the broker's local fabrication instead. Days follow the real-world
+//! wall clock, not the sim calendar.
+use std::env;
+
+use bevy::prelude::*;
+use chrono::{DateTime, Local, TimeDelta};
+
+use super::{
+    events::ApiBudgetExhaustedEvent, status::DialogueBrokerStatus, types::DialoguePriority,
+};
+
+pub const ENV_MAX_REQUESTS: &str = "OPENAI_DAILY_MAX_REQUESTS";
+pub const ENV_MAX_TOKENS: &str = "OPENAI_DAILY_MAX_TOKENS";
+pub const ENV_PLAYER_RESERVE: &str = "OPENAI_DAILY_PLAYER_RESERVE";
+const DEFAULT_MAX_REQUESTS: u32 = 400;
+const DEFAULT_MAX_TOKENS: u64 = 200_000;
+const DEFAULT_PLAYER_RESERVE: f32 = 0.1;
+/// Charged per request at dispatch, and kept when the provider reports no usage.
+pub const ESTIMATED_TOKENS_PER_REQUEST: u32 = 500;
+const BUDGET_WINDOW_HOURS: i64 = 24;
+
+/// Daily caps on live provider calls.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ApiBudgetLimits {
+    pub max_requests: u32,
+    pub max_tokens: u64,
+    /// Share of both caps only player-initiated requests may use, so the player can still
+    /// talk after ambient chatter has eaten the rest. 0 disables the reserve.
+    pub player_reserve: f32,
+}
+
+impl Default for ApiBudgetLimits {
+    fn default() -> Self {
+        Self {
+            max_requests: DEFAULT_MAX_REQUESTS,
+            max_tokens: DEFAULT_MAX_TOKENS,
+            player_reserve: DEFAULT_PLAYER_RESERVE,
+        }
+    }
+}
+
+impl ApiBudgetLimits {
+    pub fn from_env() -> Self {
+        Self::from_lookup(|key| env::var(key).ok())
+    }
+
+    /// Reads overrides from `lookup`; unset values keep their defaults and invalid ones
+    /// are logged and ignored, so a typo never disables the guardrail.
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let mut limits = Self::default();
+        let read = |key: &str| lookup(key).map(|value| value.trim().to_string());
+
+        if let Some(value) = read(ENV_MAX_REQUESTS) {
+            match value.parse() {
+                Ok(max) => limits.max_requests = max,
+                Err(_) => warn!("Ignoring {ENV_MAX_REQUESTS}={value}: expected a whole number"),
+            }
+        }
+        if let Some(value) = read(ENV_MAX_TOKENS) {
+            match value.parse() {
+                Ok(max) => limits.max_tokens = max,
+                Err(_) => warn!("Ignoring {ENV_MAX_TOKENS}={value}: expected a whole number"),
+            }
+        }
+        if let Some(value) = read(ENV_PLAYER_RESERVE) {
+            match value.parse::<f32>() {
+                Ok(reserve) if (0.0..=1.0).contains(&reserve) => limits.player_reserve = reserve,
+                _ => warn!("Ignoring {ENV_PLAYER_RESERVE}={value}: expected a fraction in [0, 1]"),
+            }
+        }
+        limits
+    }
+
+    /// (requests, tokens) a request of `priority` may bring the day's totals up to.
+    fn caps_for(&self, priority: DialoguePriority) -> (u32, u64) {
+        match priority {
+            DialoguePriority::Player => (self.max_requests, self.max_tokens),
+            DialoguePriority::Ambient => {
+                let keep = 1.0 - self.player_reserve.clamp(0.0, 1.0);
+                (
+                    (self.max_requests as f32 * keep).floor() as u32,
+                    (self.max_tokens as f64 * f64::from(keep)).floor() as u64,
+                )
+            }
+        }
+    }
+}
+
+/// Which cap a request ran into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiBudgetLimit {
+    /// `max_requests` live calls.
+    Requests,
+    /// `max_tokens` estimated tokens.
+    Tokens,
+}
+
+impl ApiBudgetLimit {
+    /// Singular noun for messages, e.g. "daily token budget".
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Requests => "request",
+            Self::Tokens => "token",
+        }
+    }
+}
+
+/// Live calls and estimated tokens spent in the current real-world day.
+#[derive(Resource, Debug, Clone)]
+pub struct DailyApiBudget {
+    limits: ApiBudgetLimits,
+    /// When the current window ends; `None` until the first live request opens one.
+    resets_at: Option<DateTime<Local>>,
+    requests: u32,
+    tokens: u64,
+    exhausted: bool,
+    /// Cap that spent the budget, until `refresh_daily_api_budget` announces it.
+    unannounced: Option<ApiBudgetLimit>,
+}
+
+impl Default for DailyApiBudget {
+    fn default() -> Self {
+        Self::new(ApiBudgetLimits::default())
+    }
+}
+
+impl DailyApiBudget {
+    pub fn new(limits: ApiBudgetLimits) -> Self {
+        Self {
+            limits,
+            resets_at: None,
+            requests: 0,
+            tokens: 0,
+            exhausted: false,
+            unannounced: None,
+        }
+    }
+
+    pub fn requests_used(&self) -> u32 {
+        self.requests
+    }
+
+    pub fn tokens_used(&self) -> u64 {
+        self.tokens
+    }
+
+    pub fn resets_at(&self) -> Option<DateTime<Local>> {
+        self.resets_at
+    }
+
+    /// True once a request has been turned away in the current window.
+    pub fn is_exhausted(&self) -> bool {
+        self.exhausted
+    }
+
+    /// The cap `count` more requests of `priority` would break, if any.
+    pub fn blocking_limit(&self, priority: DialoguePriority, count: u32) -> Option<ApiBudgetLimit> {
+        let (max_requests, max_tokens) = self.limits.caps_for(priority);
+        if self.requests.saturating_add(count) > max_requests {
+            Some(ApiBudgetLimit::Requests)
+        } else if self.tokens + u64::from(count) * u64::from(ESTIMATED_TOKENS_PER_REQUEST)
+            > max_tokens
+        {
+            Some(ApiBudgetLimit::Tokens)
+        } else {
+            None
+        }
+    }
+
+    /// Counts `count` live requests at the estimated token cost, opening a window at `now`
+    /// if none is running.
+    pub fn charge(&mut self, now: DateTime<Local>, count: u32) {
+        self.resets_at.get_or_insert_with(|| budget_window_end(now));
+        self.requests = self.requests.saturating_add(count);
+        self.tokens += u64::from(count) * u64::from(ESTIMATED_TOKENS_PER_REQUEST);
+    }
+
+    /// Swaps one request's up-front estimate for the usage the provider reported.
+    pub fn reconcile(&mut self, reported_tokens: u32) {
+        self.tokens = (self.tokens + u64::from(reported_tokens))
+            .saturating_sub(u64::from(ESTIMATED_TOKENS_PER_REQUEST));
+    }
+
+    /// Counts the follow-up call that finished a truncated reply. Its tokens arrive in the
+    /// reply's combined usage, so only the request is added here.
+    pub fn record_continuation(&mut self) {
+        self.requests = self.requests.saturating_add(1);
+    }
+
+    /// Flags the budget as spent by `limit`; true only the first time in a window.
+    pub fn mark_exhausted(&mut self, limit: ApiBudgetLimit) -> bool {
+        let first = !std::mem::replace(&mut self.exhausted, true);
+        if first {
+            self.unannounced = Some(limit);
+        }
+        first
+    }
+
+    /// Whether `count` live requests of `priority` may go out; charges them if so and
+    /// flags the budget as spent if not.
+    pub fn try_spend(
+        &mut self,
+        now: DateTime<Local>,
+        priority: DialoguePriority,
+        count: u32,
+    ) -> bool {
+        match self.blocking_limit(priority, count) {
+            None => {
+                self.charge(now, count);
+                true
+            }
+            Some(limit) => {
+                // A zero cap never charges anything, so the window has to start here.
+                self.resets_at.get_or_insert_with(|| budget_window_end(now));
+                self.mark_exhausted(limit);
+                false
+            }
+        }
+    }
+
+    /// Starts a fresh day once the window has run out. Returns true when it reset.
+    pub fn roll_over(&mut self, now: DateTime<Local>) -> bool {
+        match self.resets_at {
+            Some(resets_at) if now >= resets_at => {
+                *self = Self::new(self.limits);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// End of the budget window opened at `start`: the next local midnight, or 24 hours later
+/// if that comes first (or midnight cannot be resolved, e.g. across a DST gap).
+pub fn budget_window_end(start: DateTime<Local>) -> DateTime<Local> {
+    let day_later = start + TimeDelta::hours(BUDGET_WINDOW_HOURS);
+    start
+        .date_naive()
+        .succ_opt()
+        .and_then(|tomorrow| tomorrow.and_hms_opt(0, 0, 0))
+        .and_then(|midnight| midnight.and_local_timezone(Local).earliest())
+        .map_or(day_later, |midnight| midnight.min(day_later))
+}
+
+/// Resets the budget when its window ends, announces a newly spent budget with a warning
+/// and one `ApiBudgetExhaustedEvent`, and mirrors the state into the broker status.
+pub fn refresh_daily_api_budget(
+    mut budget: ResMut<DailyApiBudget>,
+    mut status: ResMut<DialogueBrokerStatus>,
+    mut exhausted_writer: MessageWriter<ApiBudgetExhaustedEvent>,
+) {
+    if let Some(limit) = budget.unannounced.take() {
+        let resets_at = budget.resets_at();
+        warn!(
+            "Daily API budget spent ({} cap; {} requests, ~{} tokens used). Dialogue uses \
+             local fallback replies until {}.",
+            limit.label(),
+            budget.requests_used(),
+            budget.tokens_used(),
+            resets_at.map_or_else(
+                || "the budget resets".to_string(),
+                |at| at.format("%Y-%m-%d %H:%M").to_string()
+            ),
+        );
+        exhausted_writer.write(ApiBudgetExhaustedEvent {
+            limit,
+            requests_used: budget.requests_used(),
+            tokens_used: budget.tokens_used(),
+            resets_at,
+        });
+    }
+    if budget.roll_over(Local::now()) {
+        info!("Daily API budget reset; live dialogue calls resume.");
+    }
+    if status.budget_exhausted() != budget.is_exhausted() {
+        status.set_budget_exhausted(budget.is_exhausted());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(2026, 3, day, hour, minute, 0)
+            .single()
+            .unwrap()
+    }
+
+    fn limits(max_requests: u32, player_reserve: f32) -> ApiBudgetLimits {
+        ApiBudgetLimits {
+            max_requests,
+            max_tokens: u64::from(max_requests) * u64::from(ESTIMATED_TOKENS_PER_REQUEST) * 2,
+            player_reserve,
+        }
+    }
+
+    #[test]
+    fn caps_stop_requests_and_tokens() {
+        let now = at(10, 9, 0);
+        let mut budget = DailyApiBudget::new(limits(3, 0.0));
+        for _ in 0..3 {
+            assert_eq!(budget.blocking_limit(DialoguePriority::Ambient, 1), None);
+            budget.charge(now, 1);
+        }
+        assert_eq!(
+            budget.blocking_limit(DialoguePriority::Ambient, 1),
+            Some(ApiBudgetLimit::Requests)
+        );
+        assert_eq!(
+            budget.blocking_limit(DialoguePriority::Player, 1),
+            Some(ApiBudgetLimit::Requests)
+        );
+
+        let mut budget = DailyApiBudget::new(ApiBudgetLimits {
+            max_requests: 100,
+            max_tokens: 1_000,
+            player_reserve: 0.0,
+        });
+        budget.charge(now, 1);
+        budget.reconcile(900);
+        assert_eq!(
+            budget.tokens_used(),
+            900,
+            "reported usage replaces the estimate"
+        );
+        assert_eq!(
+            budget.blocking_limit(DialoguePriority::Ambient, 1),
+            Some(ApiBudgetLimit::Tokens)
+        );
+        assert_eq!(
+            DailyApiBudget::new(limits(3, 0.0)).blocking_limit(DialoguePriority::Ambient, 4),
+            Some(ApiBudgetLimit::Requests),
+            "a batch must fit whole"
+        );
+
+        assert!(!budget.try_spend(now, DialoguePriority::Ambient, 1));
+        assert!(budget.is_exhausted());
+        assert!(
+            !budget.mark_exhausted(ApiBudgetLimit::Requests),
+            "announced once per window"
+        );
+        assert_eq!(budget.unannounced, Some(ApiBudgetLimit::Tokens));
+    }
+
+    #[test]
+    fn player_requests_keep_a_reserved_slice() {
+        let now = at(10, 9, 0);
+        let mut budget = DailyApiBudget::new(limits(10, 0.2));
+        for _ in 0..8 {
+            budget.charge(now, 1);
+        }
+        assert_eq!(
+            budget.blocking_limit(DialoguePriority::Ambient, 1),
+            Some(ApiBudgetLimit::Requests)
+        );
+        assert_eq!(budget.blocking_limit(DialoguePriority::Player, 1), None);
+        budget.charge(now, 2);
+        assert!(budget.blocking_limit(DialoguePriority::Player, 1).is_some());
+
+        let parsed = ApiBudgetLimits::from_lookup(|key| match key {
+            ENV_MAX_REQUESTS => Some(" 25 ".to_string()),
+            ENV_MAX_TOKENS => Some("lots".to_string()),
+            ENV_PLAYER_RESERVE => Some("0".to_string()),
+            _ => None,
+        });
+        assert_eq!(parsed.max_requests, 25);
+        assert_eq!(parsed.max_tokens, DEFAULT_MAX_TOKENS);
+        assert_eq!(parsed.player_reserve, 0.0);
+    }
+
+    #[test]
+    fn window_resets_at_local_midnight_or_after_a_day() {
+        assert_eq!(budget_window_end(at(10, 21, 30)), at(11, 0, 0));
+        assert_eq!(budget_window_end(at(10, 0, 0)), at(11, 0, 0));
+
+        let mut budget = DailyApiBudget::new(limits(1, 0.0));
+        assert!(
+            !budget.roll_over(at(10, 9, 0)),
+            "no window before the first request"
+        );
+        budget.charge(at(10, 21, 30), 1);
+        budget.mark_exhausted(ApiBudgetLimit::Requests);
+        assert_eq!(
+            budget.resets_at(),
+            Some(at(11, 0, 0)),
+            "the first request opens the window"
+        );
+
+        assert!(!budget.roll_over(at(10, 23, 59)));
+        assert!(budget.is_exhausted());
+        assert!(budget.roll_over(at(11, 0, 0)));
+        assert_eq!((budget.requests_used(), budget.tokens_used()), (0, 0));
+        assert!(!budget.is_exhausted());
+        assert_eq!(budget.resets_at(), None);
+        assert_eq!(budget.blocking_limit(DialoguePriority::Ambient, 1), None);
+    }
+}
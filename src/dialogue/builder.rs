@@ -0,0 +1,311 @@
+//! Fluent construction of dialogue requests with build-time validation.
+use crate::npc::components::NpcId;
+
+use super::{
+    broker::DialogueProviderKind,
+    chatter::{ChatterPair, ChatterStamp, PairChatterCooldown},
+    errors::DialogueBuildError,
+    queue::DialogueRequestQueue,
+    types::{
+        ContextClock, DialogueContext, DialogueContextEvent, DialogueRequest, DialogueRequestId,
+        DialogueTopicHint, TradeContext,
+    },
+};
+
+/// Collects the pieces of a `DialogueRequest`; start one with `DialogueRequest::builder`.
+#[derive(Debug, Clone)]
+pub struct DialogueRequestBuilder {
+    speaker: NpcId,
+    target: Option<NpcId>,
+    topic: DialogueTopicHint,
+    prompt: String,
+    context: DialogueContext,
+    provider: Option<DialogueProviderKind>,
+    speaker_name: Option<String>,
+    target_name: Option<String>,
+    expires_at: Option<ContextClock>,
+}
+
+impl DialogueRequestBuilder {
+    pub(super) fn new(speaker: NpcId) -> Self {
+        Self {
+            speaker,
+            target: None,
+            topic: DialogueTopicHint::default(),
+            prompt: String::new(),
+            context: DialogueContext::default(),
+            provider: None,
+            speaker_name: None,
+            target_name: None,
+            expires_at: None,
+        }
+    }
+
+    /// NPC the line is addressed to; ambient lines have none.
+    pub fn target(mut self, npc: NpcId) -> Self {
+        self.target = Some(npc);
+        self
+    }
+
+    /// Display name the prompt uses for the speaker instead of its id.
+    pub fn speaker_name(mut self, name: impl Into<String>) -> Self {
+        self.speaker_name = Some(name.into());
+        self
+    }
+
+    /// Display name the prompt uses for the target instead of its id.
+    pub fn target_name(mut self, name: impl Into<String>) -> Self {
+        self.target_name = Some(name.into());
+        self
+    }
+
+    /// What the line is about; `Status` unless set.
+    pub fn topic(mut self, topic: DialogueTopicHint) -> Self {
+        self.topic = topic;
+        self
+    }
+
+    /// Instruction for the provider; `build` rejects a blank one.
+    pub fn prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.prompt = prompt.into();
+        self
+    }
+
+    /// Short background the prompt includes ahead of the events.
+    pub fn summary(mut self, summary: impl Into<String>) -> Self {
+        self.context.summary = Some(summary.into());
+        self
+    }
+
+    /// Adds a trade to the context; `Trade` topics need one.
+    pub fn trade_event(mut self, trade: TradeContext) -> Self {
+        self.context.events.push(DialogueContextEvent::Trade(trade));
+        self
+    }
+
+    /// Adds a schedule change the speaker can mention.
+    pub fn schedule_update(mut self, description: impl Into<String>) -> Self {
+        self.context
+            .events
+            .push(DialogueContextEvent::ScheduleUpdate {
+                description: description.into(),
+            });
+        self
+    }
+
+    /// Sends the request to `provider` regardless of pins or the router's default.
+    pub fn provider(mut self, provider: DialogueProviderKind) -> Self {
+        self.provider = Some(provider);
+        self
+    }
+
+    /// Drops the request undispatched once the world clock reaches `deadline`.
+    pub fn expires_at(mut self, deadline: ContextClock) -> Self {
+        self.expires_at = Some(deadline);
+        self
+    }
+
+    /// Drops the request undispatched once `days` of in-game time (fractions allowed) have
+    /// passed since `now`.
+    pub fn expires_after(self, now: ContextClock, days: f32) -> Self {
+        self.expires_at(now.later_by(days))
+    }
+
+    /// Checks the request is well formed: a non-blank prompt, and a trade event whenever
+    /// the topic is `Trade`.
+    pub fn build(self) -> Result<DialogueRequest, DialogueBuildError> {
+        if self.prompt.trim().is_empty() {
+            return Err(DialogueBuildError::EmptyPrompt);
+        }
+        if self.topic == DialogueTopicHint::Trade && self.trade().is_none() {
+            return Err(DialogueBuildError::MissingTradeEvent);
+        }
+
+        let mut request = DialogueRequest::new(
+            self.speaker,
+            self.target,
+            self.prompt,
+            self.topic,
+            self.context,
+        );
+        request.provider_override = self.provider;
+        request.speaker_name = self.speaker_name;
+        request.target_name = self.target_name;
+        request.expires_at = self.expires_at;
+        Ok(request)
+    }
+
+    /// Builds and queues the request.
+    pub(crate) fn enqueue(
+        self,
+        queue: &mut DialogueRequestQueue,
+    ) -> Result<DialogueRequestId, DialogueBuildError> {
+        Ok(queue.enqueue(self.build()?))
+    }
+
+    /// Builds and queues the request unless the speaker/target pair chatted too recently.
+    /// A trade event's good always gets through the first time that day. Returns `Ok(None)`
+    /// when the cooldown suppressed the request, and records the chatter otherwise.
+    pub(crate) fn enqueue_with_cooldown(
+        self,
+        queue: &mut DialogueRequestQueue,
+        cooldown: &mut PairChatterCooldown,
+        now: ChatterStamp,
+    ) -> Result<Option<DialogueRequestId>, DialogueBuildError> {
+        let target = self
+            .target
+            .ok_or(DialogueBuildError::CooldownWithoutTarget)?;
+        let pair = ChatterPair::new(self.speaker, target);
+        let good = self.trade().map(|trade| trade.descriptor.label.clone());
+        let request = self.build()?;
+
+        let allowed = match good {
+            Some(good) => cooldown.allow_trade_chatter(pair, now, &good),
+            None => cooldown.can_chat(pair, now),
+        };
+        if !allowed {
+            return Ok(None);
+        }
+
+        let id = queue.enqueue(request);
+        cooldown.record_chatter(pair, now.day, now.time_of_day);
+        Ok(Some(id))
+    }
+
+    fn trade(&self) -> Option<&TradeContext> {
+        self.context.events.iter().find_map(|event| match event {
+            DialogueContextEvent::Trade(trade) => Some(trade),
+            DialogueContextEvent::ScheduleUpdate { .. }
+            | DialogueContextEvent::Custom { .. }
+            | DialogueContextEvent::Hearsay(_)
+            | DialogueContextEvent::VillageNews(_) => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue::types::{TradeContextReason, TradeDescriptor};
+
+    fn trade() -> TradeContext {
+        TradeContext {
+            day: 2,
+            time_of_day: None,
+            from: Some(NpcId::new(1)),
+            to: Some(NpcId::new(2)),
+            descriptor: TradeDescriptor::new("grain crate", 1),
+            reason: TradeContextReason::Exchange,
+        }
+    }
+
+    #[test]
+    fn builder_fills_every_request_field() {
+        let request = DialogueRequest::builder(NpcId::new(1))
+            .target(NpcId::new(2))
+            .topic(DialogueTopicHint::Trade)
+            .prompt("NPC-0001 discusses a grain crate.")
+            .summary("Day 2 trade")
+            .trade_event(trade())
+            .schedule_update("Mill after lunch")
+            .provider(DialogueProviderKind::Local)
+            .speaker_name("Alric")
+            .target_name("Bryn")
+            .expires_after(ContextClock::new(2, 0.5), 0.25)
+            .build()
+            .expect("valid request");
+
+        assert_eq!(request.speaker, NpcId::new(1));
+        assert_eq!(request.target, Some(NpcId::new(2)));
+        assert_eq!(request.topic_hint, DialogueTopicHint::Trade);
+        assert_eq!(request.prompt, "NPC-0001 discusses a grain crate.");
+        assert_eq!(request.context.summary.as_deref(), Some("Day 2 trade"));
+        assert_eq!(request.provider_override, Some(DialogueProviderKind::Local));
+        assert_eq!(request.speaker_name.as_deref(), Some("Alric"));
+        assert_eq!(request.target_name.as_deref(), Some("Bryn"));
+        assert_eq!(request.expires_at, Some(ContextClock::new(2, 0.75)));
+        assert!(matches!(
+            request.context.events.as_slice(),
+            [
+                DialogueContextEvent::Trade(_),
+                DialogueContextEvent::ScheduleUpdate { .. }
+            ]
+        ));
+        assert!(request.speaker_profile.is_none());
+    }
+
+    #[test]
+    fn build_rejects_blank_prompts_and_trades_without_events() {
+        let blank = DialogueRequest::builder(NpcId::new(1))
+            .prompt("   ")
+            .build();
+        assert_eq!(blank.unwrap_err(), DialogueBuildError::EmptyPrompt);
+
+        let trade_topic = DialogueRequest::builder(NpcId::new(1))
+            .topic(DialogueTopicHint::Trade)
+            .prompt("Talks shop.")
+            .build();
+        assert_eq!(
+            trade_topic.unwrap_err(),
+            DialogueBuildError::MissingTradeEvent
+        );
+
+        let status = DialogueRequest::builder(NpcId::new(1))
+            .prompt("Talks shop.")
+            .build()
+            .expect("status needs no events");
+        assert_eq!(status.topic_hint, DialogueTopicHint::Status);
+        assert!(status.target.is_none());
+    }
+
+    #[test]
+    fn enqueue_returns_ids_and_skips_invalid_requests() {
+        let mut queue = DialogueRequestQueue::default();
+        let first = DialogueRequest::builder(NpcId::new(1))
+            .prompt("Hello.")
+            .enqueue(&mut queue)
+            .expect("queued");
+        let second = DialogueRequest::builder(NpcId::new(2))
+            .prompt("Hi.")
+            .enqueue(&mut queue)
+            .expect("queued");
+        assert_ne!(first, second);
+
+        let invalid = DialogueRequest::builder(NpcId::new(3)).enqueue(&mut queue);
+        assert_eq!(invalid.unwrap_err(), DialogueBuildError::EmptyPrompt);
+        assert_eq!(queue.iter_pending().count(), 2);
+    }
+
+    #[test]
+    fn enqueue_with_cooldown_suppresses_repeat_chatter() {
+        let mut queue = DialogueRequestQueue::default();
+        let mut cooldown = PairChatterCooldown::new(60.0);
+        let trade_request = || {
+            DialogueRequest::builder(NpcId::new(1))
+                .target(NpcId::new(2))
+                .topic(DialogueTopicHint::Trade)
+                .prompt("Trade talk.")
+                .trade_event(trade())
+        };
+        let now = ChatterStamp::new(2, 0.5);
+
+        let first = trade_request()
+            .enqueue_with_cooldown(&mut queue, &mut cooldown, now)
+            .expect("valid request");
+        assert!(first.is_some(), "first trade of the good is voiced");
+
+        let repeat = trade_request()
+            .enqueue_with_cooldown(&mut queue, &mut cooldown, now)
+            .expect("valid request");
+        assert!(repeat.is_none(), "same pair inside the window stays quiet");
+        assert_eq!(queue.iter_pending().count(), 1);
+
+        let untargeted = DialogueRequest::builder(NpcId::new(1))
+            .prompt("Alone.")
+            .enqueue_with_cooldown(&mut queue, &mut cooldown, now);
+        assert_eq!(
+            untargeted.unwrap_err(),
+            DialogueBuildError::CooldownWithoutTarget
+        );
+    }
+}
@@ -0,0 +1,27 @@
+//! Dialogue faults for chaos runs (`--features chaos`): a broker call that fails and a
+//! queued request that disappears before dispatch.
+use bevy::prelude::*;
+
+use crate::{
+    core::chaos::pick_index,
+    dialogue::queue::{DialogueRequestQueue, PendingDialogueTasks},
+    economy::rng::DailyRng,
+};
+
+/// Makes the next dispatched dialogue call fail as a provider failure.
+pub fn fail_next_dialogue_call(world: &mut World, _rng: &mut DailyRng) -> Option<String> {
+    world
+        .get_resource_mut::<PendingDialogueTasks>()?
+        .force_next_failure();
+    Some("next dialogue call fails".to_string())
+}
+
+/// Cancels a random request that is still waiting in the queue.
+pub fn drop_random_pending_request(world: &mut World, rng: &mut DailyRng) -> Option<String> {
+    let mut queue = world.get_resource_mut::<DialogueRequestQueue>()?;
+    let pending: Vec<_> = queue.iter_pending().map(|view| view.id).collect();
+    let id = pending[pick_index(rng, pending.len())?];
+    queue
+        .cancel(id)
+        .then(|| format!("dropped pending dialogue {id}"))
+}
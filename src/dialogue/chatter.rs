@@ -0,0 +1,367 @@
+//! Per-pair chatter cooldown so the same two NPCs don't repeat near-identical exchanges,
+//! and per-NPC daily budgets so moody NPCs chat less.
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+
+use crate::npc::{
+    components::NpcId,
+    motivation::{config::ChatterModifiers, state::NpcMood, MotivationConfig},
+};
+
+const IN_GAME_MINUTES_PER_DAY: f64 = 24.0 * 60.0;
+/// Used until `[chatter] pair_window_minutes` in `config/motivation.toml` is applied.
+pub const DEFAULT_PAIR_CHATTER_WINDOW_MINUTES: f32 = 120.0;
+
+/// Unordered pair of NPCs; `(a, b)` and `(b, a)` map to the same key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChatterPair(NpcId, NpcId);
+
+impl ChatterPair {
+    pub fn new(a: NpcId, b: NpcId) -> Self {
+        if a <= b {
+            Self(a, b)
+        } else {
+            Self(b, a)
+        }
+    }
+}
+
+/// Point on the world clock, expressed as day count plus fraction of the day.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChatterStamp {
+    pub day: u64,
+    pub time_of_day: f32,
+}
+
+impl ChatterStamp {
+    pub fn new(day: u64, time_of_day: f32) -> Self {
+        Self { day, time_of_day }
+    }
+
+    /// In-game minutes from `earlier` to `self`, crossing midnight correctly and
+    /// clamping to zero if the clock moved backwards.
+    fn minutes_since(&self, earlier: &ChatterStamp) -> f64 {
+        let days = self.day as f64 - earlier.day as f64;
+        let fraction = f64::from(self.time_of_day) - f64::from(earlier.time_of_day);
+        ((days + fraction) * IN_GAME_MINUTES_PER_DAY).max(0.0)
+    }
+}
+
+/// Applies `[chatter] pair_window_minutes` to the pair cooldown at startup and whenever
+/// the motivation config is reloaded.
+pub fn apply_pair_chatter_window(
+    config: Option<Res<MotivationConfig>>,
+    mut cooldown: ResMut<PairChatterCooldown>,
+) {
+    let Some(config) = config.filter(|config| config.is_changed()) else {
+        return;
+    };
+    let window_minutes = config.chatter.pair_window_minutes.max(0.0);
+    if cooldown.window_minutes != window_minutes {
+        cooldown.window_minutes = window_minutes;
+    }
+}
+
+/// Tracks when each NPC pair last chatted and which goods they already discussed today.
+#[derive(Resource, Debug)]
+pub struct PairChatterCooldown {
+    pub window_minutes: f32,
+    last_chatter: HashMap<ChatterPair, ChatterStamp>,
+    goods_announced: HashSet<(ChatterPair, String)>,
+    goods_day: Option<u64>,
+}
+
+impl Default for PairChatterCooldown {
+    fn default() -> Self {
+        Self::new(DEFAULT_PAIR_CHATTER_WINDOW_MINUTES)
+    }
+}
+
+impl PairChatterCooldown {
+    pub fn new(window_minutes: f32) -> Self {
+        Self {
+            window_minutes: window_minutes.max(0.0),
+            last_chatter: HashMap::new(),
+            goods_announced: HashSet::new(),
+            goods_day: None,
+        }
+    }
+
+    pub fn record_chatter(&mut self, pair: ChatterPair, day: u64, time_of_day: f32) {
+        self.last_chatter
+            .insert(pair, ChatterStamp::new(day, time_of_day));
+    }
+
+    /// True when the pair has never chatted or the configured window has elapsed.
+    pub fn can_chat(&self, pair: ChatterPair, now: ChatterStamp) -> bool {
+        self.last_chatter
+            .get(&pair)
+            .is_none_or(|last| now.minutes_since(last) >= f64::from(self.window_minutes))
+    }
+
+    /// Marks `good` as discussed by the pair on `day`. Returns true the first time that day,
+    /// so new goods are always announced regardless of the cooldown.
+    pub fn note_good_today(&mut self, pair: ChatterPair, day: u64, good: &str) -> bool {
+        if self.goods_day != Some(day) {
+            self.goods_day = Some(day);
+            self.goods_announced.clear();
+        }
+        self.goods_announced.insert((pair, good.to_string()))
+    }
+
+    /// Decides whether a trade between the pair should be voiced: the first trade of a good
+    /// each day always is, repeats only once the cooldown window has elapsed.
+    pub fn allow_trade_chatter(
+        &mut self,
+        pair: ChatterPair,
+        now: ChatterStamp,
+        good: &str,
+    ) -> bool {
+        let new_good_today = self.note_good_today(pair, now.day, good);
+        new_good_today || self.can_chat(pair, now)
+    }
+}
+
+/// Daily chatter allowance for an NPC in `mood`: `base` scaled by the mood's multiplier,
+/// rounded to the nearest request.
+pub fn compute_chatter_budget(mood: NpcMood, base: u32, modifiers: &ChatterModifiers) -> u32 {
+    let multiplier = match mood {
+        NpcMood::Energised => modifiers.energised,
+        NpcMood::Content => modifiers.content,
+        NpcMood::Tired => modifiers.tired,
+        NpcMood::Depressed => modifiers.depressed,
+    };
+    (base as f32 * multiplier.max(0.0)).round() as u32
+}
+
+/// Remaining NPC-initiated dialogue requests per speaker for the current day. Speakers
+/// without a budget (e.g. before the first day is prepared) are not limited.
+#[derive(Resource, Debug, Default)]
+pub struct ChatterBudgets {
+    day: Option<u64>,
+    budgets: HashMap<NpcId, ChatterAllowance>,
+}
+
+/// One speaker's daily allowance and what is left of it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChatterAllowance {
+    pub daily: u32,
+    pub remaining: u32,
+}
+
+impl ChatterBudgets {
+    pub fn day(&self) -> Option<u64> {
+        self.day
+    }
+
+    /// Replaces every budget with fresh allowances for `day`.
+    pub fn reset(&mut self, day: u64, allowances: impl IntoIterator<Item = (NpcId, u32)>) {
+        self.day = Some(day);
+        self.budgets.clear();
+        self.budgets
+            .extend(allowances.into_iter().map(|(npc, daily)| {
+                (
+                    npc,
+                    ChatterAllowance {
+                        daily,
+                        remaining: daily,
+                    },
+                )
+            }));
+    }
+
+    pub fn has_remaining(&self, speaker: NpcId) -> bool {
+        self.budgets
+            .get(&speaker)
+            .is_none_or(|allowance| allowance.remaining > 0)
+    }
+
+    /// Uses one request from the speaker's budget.
+    pub fn spend(&mut self, speaker: NpcId) {
+        if let Some(allowance) = self.budgets.get_mut(&speaker) {
+            allowance.remaining = allowance.remaining.saturating_sub(1);
+        }
+    }
+
+    /// Adds `extra` requests to the speaker's budget for the rest of the day. Speakers
+    /// without a budget are unlimited already and are left alone.
+    pub fn grant(&mut self, speaker: NpcId, extra: u32) {
+        if let Some(allowance) = self.budgets.get_mut(&speaker) {
+            allowance.daily = allowance.daily.saturating_add(extra);
+            allowance.remaining = allowance.remaining.saturating_add(extra);
+        }
+    }
+
+    #[cfg(test)]
+    pub fn allowance(&self, speaker: NpcId) -> Option<ChatterAllowance> {
+        self.budgets.get(&speaker).copied()
+    }
+
+    /// "NPC-0001 2/9, NPC-0002 0/2" in speaker order, for debug dumps.
+    pub fn summary(&self) -> String {
+        let mut entries: Vec<_> = self.budgets.iter().collect();
+        entries.sort_by_key(|(npc, _)| **npc);
+        entries
+            .into_iter()
+            .map(|(npc, allowance)| format!("{npc} {}/{}", allowance.remaining, allowance.daily))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pair() -> ChatterPair {
+        ChatterPair::new(NpcId::new(1), NpcId::new(3))
+    }
+
+    #[test]
+    fn pair_key_is_unordered() {
+        assert_eq!(
+            ChatterPair::new(NpcId::new(1), NpcId::new(3)),
+            ChatterPair::new(NpcId::new(3), NpcId::new(1))
+        );
+    }
+
+    #[test]
+    fn pair_window_follows_the_chatter_config() {
+        let mut app = App::new();
+        app.init_resource::<PairChatterCooldown>()
+            .add_systems(Update, apply_pair_chatter_window);
+        app.update();
+        assert_eq!(
+            app.world().resource::<PairChatterCooldown>().window_minutes,
+            DEFAULT_PAIR_CHATTER_WINDOW_MINUTES,
+            "no config yet"
+        );
+
+        let mut config = MotivationConfig::default();
+        config.chatter.pair_window_minutes = 45.0;
+        app.insert_resource(config);
+        app.update();
+        assert_eq!(
+            app.world().resource::<PairChatterCooldown>().window_minutes,
+            45.0
+        );
+
+        app.world_mut()
+            .resource_mut::<MotivationConfig>()
+            .chatter
+            .pair_window_minutes = 300.0;
+        app.update();
+        assert_eq!(
+            app.world().resource::<PairChatterCooldown>().window_minutes,
+            300.0
+        );
+    }
+
+    #[test]
+    fn cooldown_blocks_within_window() {
+        // 120 minutes is 1/12 of a day.
+        let mut cooldown = PairChatterCooldown::new(120.0);
+        assert!(cooldown.can_chat(pair(), ChatterStamp::new(0, 0.3)));
+
+        cooldown.record_chatter(pair(), 0, 0.3);
+        assert!(!cooldown.can_chat(pair(), ChatterStamp::new(0, 0.32)));
+        assert!(cooldown.can_chat(pair(), ChatterStamp::new(0, 0.3 + 1.0 / 12.0 + 0.001)));
+        assert!(
+            cooldown.can_chat(
+                ChatterPair::new(NpcId::new(1), NpcId::new(2)),
+                ChatterStamp::new(0, 0.31)
+            ),
+            "other pairs are unaffected"
+        );
+    }
+
+    #[test]
+    fn cooldown_math_survives_midnight_and_backwards_clock() {
+        let mut cooldown = PairChatterCooldown::new(120.0);
+        cooldown.record_chatter(pair(), 4, 0.98);
+
+        // 0.98 -> 0.02 next day is ~58 minutes.
+        assert!(!cooldown.can_chat(pair(), ChatterStamp::new(5, 0.02)));
+        // 0.98 -> 0.07 next day is ~130 minutes.
+        assert!(cooldown.can_chat(pair(), ChatterStamp::new(5, 0.07)));
+        // A clock that runs backwards never counts as elapsed time.
+        assert!(!cooldown.can_chat(pair(), ChatterStamp::new(4, 0.5)));
+    }
+
+    #[test]
+    fn trade_chatter_skips_repeats_but_announces_new_goods() {
+        let mut cooldown = PairChatterCooldown::new(120.0);
+        let morning = ChatterStamp::new(1, 0.3);
+        assert!(cooldown.allow_trade_chatter(pair(), morning, "tools crate"));
+        cooldown.record_chatter(pair(), morning.day, morning.time_of_day);
+
+        let soon = ChatterStamp::new(1, 0.31);
+        assert!(
+            !cooldown.allow_trade_chatter(pair(), soon, "tools crate"),
+            "repeat good inside the window is skipped"
+        );
+        assert!(
+            cooldown.allow_trade_chatter(pair(), soon, "flour sack"),
+            "first flour of the day is announced despite the cooldown"
+        );
+
+        let later = ChatterStamp::new(1, 0.5);
+        assert!(cooldown.allow_trade_chatter(pair(), later, "tools crate"));
+    }
+
+    #[test]
+    fn new_goods_are_exempt_once_per_day() {
+        let mut cooldown = PairChatterCooldown::new(120.0);
+        assert!(cooldown.note_good_today(pair(), 1, "tools crate"));
+        assert!(!cooldown.note_good_today(pair(), 1, "tools crate"));
+        assert!(cooldown.note_good_today(pair(), 1, "flour sack"));
+        assert!(
+            cooldown.note_good_today(pair(), 2, "tools crate"),
+            "a new day resets announcements"
+        );
+    }
+
+    #[test]
+    fn chatter_budget_scales_with_mood() {
+        let modifiers = ChatterModifiers {
+            energised: 1.5,
+            content: 1.0,
+            tired: 1.0,
+            depressed: 0.3,
+        };
+        assert_eq!(compute_chatter_budget(NpcMood::Energised, 6, &modifiers), 9);
+        assert_eq!(compute_chatter_budget(NpcMood::Content, 6, &modifiers), 6);
+        assert_eq!(compute_chatter_budget(NpcMood::Tired, 6, &modifiers), 6);
+        assert_eq!(compute_chatter_budget(NpcMood::Depressed, 6, &modifiers), 2);
+        assert_eq!(compute_chatter_budget(NpcMood::Depressed, 1, &modifiers), 0);
+    }
+
+    #[test]
+    fn budgets_run_out_and_reset_daily() {
+        let (gloomy, unknown) = (NpcId::new(1), NpcId::new(9));
+        let mut budgets = ChatterBudgets::default();
+        assert!(budgets.has_remaining(gloomy), "unlimited before day prep");
+
+        budgets.reset(1, [(gloomy, 1)]);
+        assert!(budgets.has_remaining(gloomy));
+        budgets.spend(gloomy);
+        assert!(!budgets.has_remaining(gloomy));
+        assert!(budgets.has_remaining(unknown));
+        assert_eq!(budgets.summary(), "NPC-0001 0/1");
+
+        budgets.grant(gloomy, 2);
+        budgets.grant(unknown, 2);
+        assert_eq!(budgets.summary(), "NPC-0001 2/3");
+
+        budgets.reset(2, [(gloomy, 1)]);
+        assert_eq!(budgets.day(), Some(2));
+        assert_eq!(
+            budgets.allowance(gloomy),
+            Some(ChatterAllowance {
+                daily: 1,
+                remaining: 1
+            })
+        );
+    }
+}
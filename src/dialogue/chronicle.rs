@@ -0,0 +1,691 @@
+//! The village chronicle: notable happenings distilled from gameplay messages, so NPCs can
+//! remark on what went on around the village and not only on their own trades. Features
+//! contribute by registering a distiller for their message type with
+//! `ChronicleAppExt::add_chronicle_distiller`; each distilled sentence is recorded under
+//! the current day. On a request's first dispatch the most significant recent entries the
+//! speaker was not part of ride along as `DialogueContextEvent::VillageNews`.
+use std::{
+    any::{type_name, Any, TypeId},
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::Path,
+};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use super::{
+    broker::estimate_tokens,
+    types::{DialogueContextEvent, DialogueRequest, VillageNewsContext},
+};
+use crate::{
+    core::{
+        config::{ConfigDiagnostics, ConfigReloadRequested},
+        schedule::FramePhase,
+    },
+    economy::components::Profession,
+    npc::components::{Identity, NpcId, PLAYER_DISPLAY_NAME},
+    world::time::WorldClock,
+};
+
+pub const CONFIG_PATH: &str = "config/dialogue.toml";
+const DEFAULT_RETENTION_DAYS: u64 = 3;
+const DEFAULT_DECAY_PER_DAY: f32 = 0.35;
+const DEFAULT_ENTRIES_PER_DAY: usize = 12;
+const DEFAULT_ENTRIES_PER_REQUEST: usize = 3;
+const DEFAULT_TOKEN_BUDGET: usize = 48;
+
+#[derive(Debug, Clone, Deserialize, Default)]
+struct RawDialogueConfig {
+    #[serde(default)]
+    chronicle: RawChronicleSection,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+struct RawChronicleSection {
+    retention_days: u64,
+    decay_per_day: f32,
+    entries_per_day: usize,
+    entries_per_request: usize,
+    token_budget: usize,
+}
+
+impl Default for RawChronicleSection {
+    fn default() -> Self {
+        Self {
+            retention_days: DEFAULT_RETENTION_DAYS,
+            decay_per_day: DEFAULT_DECAY_PER_DAY,
+            entries_per_day: DEFAULT_ENTRIES_PER_DAY,
+            entries_per_request: DEFAULT_ENTRIES_PER_REQUEST,
+            token_budget: DEFAULT_TOKEN_BUDGET,
+        }
+    }
+}
+
+/// Tunables for how much the chronicle keeps and how much of it reaches a prompt, from the
+/// `[chronicle]` section of `config/dialogue.toml`.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct ChronicleConfig {
+    /// Days are dropped once they are more than this many days old.
+    pub retention_days: u64,
+    /// Share of an entry's significance lost with each day of age.
+    pub decay_per_day: f32,
+    /// Entries kept per day; the least significant is dropped first.
+    pub entries_per_day: usize,
+    /// Most entries attached to one request (the top K).
+    pub entries_per_request: usize,
+    /// Estimated tokens the attached entries may use between them.
+    pub token_budget: usize,
+}
+
+impl ChronicleConfig {
+    /// Reads and parses `config/dialogue.toml`.
+    pub fn load() -> Result<Self, String> {
+        let data = fs::read_to_string(Path::new(CONFIG_PATH))
+            .map_err(|err| format!("unable to read file: {err}"))?;
+        Self::from_toml_str(&data)
+    }
+
+    pub fn from_toml_str(data: &str) -> Result<Self, String> {
+        let raw = toml::from_str::<RawDialogueConfig>(data)
+            .map_err(|err| format!("invalid dialogue config: {err}"))?;
+        Ok(raw.into())
+    }
+}
+
+impl Default for ChronicleConfig {
+    fn default() -> Self {
+        RawDialogueConfig::default().into()
+    }
+}
+
+impl From<RawDialogueConfig> for ChronicleConfig {
+    fn from(value: RawDialogueConfig) -> Self {
+        let chronicle = value.chronicle;
+        let decay_per_day = if chronicle.decay_per_day.is_finite() {
+            chronicle.decay_per_day.clamp(0.0, 1.0)
+        } else {
+            DEFAULT_DECAY_PER_DAY
+        };
+        Self {
+            retention_days: chronicle.retention_days,
+            decay_per_day,
+            entries_per_day: chronicle.entries_per_day,
+            entries_per_request: chronicle.entries_per_request,
+            token_budget: chronicle.token_budget,
+        }
+    }
+}
+
+/// What a distiller makes of one message; the chronicle stamps the day.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChronicleDraft {
+    /// Short sentence without a trailing full stop ("Bryn turned 31").
+    pub text: String,
+    /// NPCs the happening concerns; they are not told about it as news.
+    pub involved: Vec<NpcId>,
+    /// How notable it is on the day it happens, from 0 to 1.
+    pub significance: f32,
+}
+
+impl ChronicleDraft {
+    pub fn new(text: impl Into<String>, involved: Vec<NpcId>, significance: f32) -> Self {
+        Self {
+            text: text.into(),
+            involved,
+            significance,
+        }
+    }
+}
+
+/// A recorded happening.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChronicleEntry {
+    pub day: u64,
+    pub text: String,
+    pub involved: Vec<NpcId>,
+    /// Significance on `day`; see `significance_on` for its value later.
+    pub significance: f32,
+}
+
+impl ChronicleEntry {
+    /// Significance on `today`, shrinking by `decay_per_day` for each day since it happened.
+    pub fn significance_on(&self, today: u64, decay_per_day: f32) -> f32 {
+        let age = today.saturating_sub(self.day).min(i32::MAX as u64) as i32;
+        self.significance * (1.0 - decay_per_day.clamp(0.0, 1.0)).powi(age)
+    }
+
+    pub fn involves(&self, npc: NpcId) -> bool {
+        self.involved.contains(&npc)
+    }
+}
+
+/// Display names and professions for distillers to write into their sentences.
+#[derive(Debug, Clone, Default)]
+pub struct ChronicleRoster {
+    names: HashMap<NpcId, String>,
+    professions: Vec<(NpcId, Profession)>,
+}
+
+impl ChronicleRoster {
+    pub fn new<'a>(npcs: impl IntoIterator<Item = (&'a Identity, Option<&'a Profession>)>) -> Self {
+        let mut roster = Self::default();
+        for (identity, profession) in npcs {
+            roster
+                .names
+                .insert(identity.id, identity.display_name.clone());
+            if let Some(profession) = profession {
+                roster.professions.push((identity.id, *profession));
+            }
+        }
+        roster
+    }
+
+    /// Display name of `npc`, "Player" for the player, or its id when unknown.
+    pub fn name(&self, npc: NpcId) -> String {
+        if npc.is_player() {
+            return PLAYER_DISPLAY_NAME.to_string();
+        }
+        self.names
+            .get(&npc)
+            .cloned()
+            .unwrap_or_else(|| npc.to_string())
+    }
+
+    /// NPCs working `profession`.
+    pub fn workers(&self, profession: Profession) -> Vec<NpcId> {
+        self.professions
+            .iter()
+            .filter(|(_, worked)| *worked == profession)
+            .map(|(npc, _)| *npc)
+            .collect()
+    }
+}
+
+/// Turns one message into a chronicle entry, or `None` when it is not worth telling.
+pub type ChronicleDistiller<E> = fn(&E, &ChronicleRoster) -> Option<ChronicleDraft>;
+
+type ErasedDistiller =
+    Box<dyn Fn(&dyn Any, &ChronicleRoster) -> Option<ChronicleDraft> + Send + Sync>;
+
+/// One distiller per message type, registered by the feature that owns the message.
+#[derive(Resource, Default)]
+pub struct ChronicleDistillers {
+    by_message: HashMap<TypeId, (&'static str, ErasedDistiller)>,
+}
+
+impl ChronicleDistillers {
+    /// Registers `distiller` for `E`. Returns false, keeping the first, when `E` already
+    /// has one.
+    pub fn register<E: 'static>(&mut self, distiller: ChronicleDistiller<E>) -> bool {
+        if self.by_message.contains_key(&TypeId::of::<E>()) {
+            return false;
+        }
+        let erased: ErasedDistiller =
+            Box::new(move |message: &dyn Any, roster: &ChronicleRoster| {
+                message
+                    .downcast_ref::<E>()
+                    .and_then(|message| distiller(message, roster))
+            });
+        self.by_message
+            .insert(TypeId::of::<E>(), (type_name::<E>(), erased));
+        true
+    }
+
+    /// What `E`'s distiller makes of `message`; `None` when it has no distiller.
+    pub fn distill<E: 'static>(
+        &self,
+        message: &E,
+        roster: &ChronicleRoster,
+    ) -> Option<ChronicleDraft> {
+        let (_, distiller) = self.by_message.get(&TypeId::of::<E>())?;
+        distiller(message, roster)
+    }
+
+    /// Type names of the messages with a distiller, sorted.
+    #[cfg(test)]
+    pub fn message_names(&self) -> Vec<&'static str> {
+        let mut names: Vec<_> = self.by_message.values().map(|(name, _)| *name).collect();
+        names.sort_unstable();
+        names
+    }
+}
+
+/// Recent village happenings, bucketed by day.
+#[derive(Resource, Debug, Default)]
+pub struct VillageChronicle {
+    days: BTreeMap<u64, Vec<ChronicleEntry>>,
+}
+
+impl VillageChronicle {
+    /// Records `draft` under `day`. A day over `entries_per_day` drops its least
+    /// significant entry, which may be the new one.
+    pub fn record(&mut self, day: u64, draft: ChronicleDraft, config: &ChronicleConfig) {
+        let text = draft.text.trim();
+        if text.is_empty() || config.entries_per_day == 0 {
+            return;
+        }
+        let entries = self.days.entry(day).or_default();
+        entries.push(ChronicleEntry {
+            day,
+            text: text.to_string(),
+            involved: draft.involved,
+            significance: draft.significance.clamp(0.0, 1.0),
+        });
+        if entries.len() > config.entries_per_day {
+            if let Some(weakest) = entries
+                .iter()
+                .enumerate()
+                .min_by(|(_, a), (_, b)| a.significance.total_cmp(&b.significance))
+                .map(|(index, _)| index)
+            {
+                entries.remove(weakest);
+            }
+        }
+    }
+
+    /// Drops days more than `retention_days` before `today`.
+    pub fn prune(&mut self, today: u64, retention_days: u64) {
+        let oldest_kept = today.saturating_sub(retention_days);
+        self.days = self.days.split_off(&oldest_kept);
+    }
+
+    /// Every kept entry, oldest day first.
+    #[cfg(test)]
+    pub fn entries(&self) -> impl Iterator<Item = &ChronicleEntry> {
+        self.days.values().flatten()
+    }
+
+    /// Up to `entries_per_request` entries `speaker` was not part of, most significant on
+    /// `today` first (newer first on ties), skipping any that would take the total past
+    /// `token_budget`. Entries older than `retention_days` or from future days are left out.
+    pub fn top_for(
+        &self,
+        speaker: NpcId,
+        today: u64,
+        config: &ChronicleConfig,
+    ) -> Vec<&ChronicleEntry> {
+        let oldest = today.saturating_sub(config.retention_days);
+        let mut candidates: Vec<(f32, &ChronicleEntry)> = self
+            .days
+            .range(oldest..=today)
+            .rev()
+            .flat_map(|(_, entries)| entries)
+            .filter(|entry| !entry.involves(speaker))
+            .map(|entry| (entry.significance_on(today, config.decay_per_day), entry))
+            .filter(|(significance, _)| *significance > 0.0)
+            .collect();
+        // Stable, so newer days stay first among equals.
+        candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        let mut tokens = 0;
+        let mut chosen = Vec::new();
+        for (_, entry) in candidates {
+            if chosen.len() >= config.entries_per_request {
+                break;
+            }
+            let cost = estimate_tokens(&entry.text);
+            if tokens + cost > config.token_budget {
+                continue;
+            }
+            tokens += cost;
+            chosen.push(entry);
+        }
+        chosen
+    }
+
+    /// Adds the speaker's `top_for` entries as `VillageNews` context, judged against the
+    /// clock stamped on the request. Player lines and unstamped requests are left alone.
+    pub fn attach_to(&self, request: &mut DialogueRequest, config: &ChronicleConfig) {
+        let Some(now) = request.context.now else {
+            return;
+        };
+        if request.speaker.is_player() {
+            return;
+        }
+        let news: Vec<DialogueContextEvent> = self
+            .top_for(request.speaker, now.day, config)
+            .into_iter()
+            .map(|entry| {
+                DialogueContextEvent::VillageNews(VillageNewsContext {
+                    text: entry.text.clone(),
+                    day: entry.day,
+                })
+            })
+            .collect();
+        request.context.events.extend(news);
+    }
+}
+
+/// Lets plugins register distillers for their messages without knowing whether
+/// `DialoguePlugin` ran first.
+pub trait ChronicleAppExt {
+    fn add_chronicle_distiller<E: Message>(
+        &mut self,
+        distiller: ChronicleDistiller<E>,
+    ) -> &mut Self;
+}
+
+impl ChronicleAppExt for App {
+    fn add_chronicle_distiller<E: Message>(
+        &mut self,
+        distiller: ChronicleDistiller<E>,
+    ) -> &mut Self {
+        self.init_resource::<VillageChronicle>()
+            .init_resource::<ChronicleConfig>();
+        if !self
+            .world_mut()
+            .get_resource_or_init::<ChronicleDistillers>()
+            .register(distiller)
+        {
+            warn!(
+                "{} already has a chronicle distiller; keeping the first",
+                type_name::<E>()
+            );
+            return self;
+        }
+        self.add_systems(
+            Update,
+            distill_chronicle_messages::<E>.in_set(FramePhase::DialoguePoll),
+        )
+    }
+}
+
+/// Records what `E`'s distiller makes of each message, under today's date.
+pub fn distill_chronicle_messages<E: Message>(
+    mut messages: MessageReader<E>,
+    distillers: Res<ChronicleDistillers>,
+    config: Res<ChronicleConfig>,
+    clock: Option<Res<WorldClock>>,
+    npcs: Query<(&Identity, Option<&Profession>)>,
+    mut chronicle: ResMut<VillageChronicle>,
+) {
+    let Some(clock) = clock else {
+        messages.clear();
+        return;
+    };
+    if messages.is_empty() {
+        return;
+    }
+    let roster = ChronicleRoster::new(npcs.iter());
+    let today = clock.day_count();
+    for message in messages.read() {
+        if let Some(draft) = distillers.distill(message, &roster) {
+            debug!("Chronicle, day {today}: {}", draft.text);
+            chronicle.record(today, draft, &config);
+        }
+    }
+}
+
+/// Re-reads `config/dialogue.toml` on request.
+pub fn reload_chronicle_config(
+    mut requests: MessageReader<ConfigReloadRequested>,
+    mut diagnostics: ResMut<ConfigDiagnostics>,
+    mut config: ResMut<ChronicleConfig>,
+) {
+    if !requests.read().any(|request| request.is_for(CONFIG_PATH)) {
+        return;
+    }
+    if let Some(reloaded) = diagnostics.report_reload(CONFIG_PATH, ChronicleConfig::load()) {
+        *config = reloaded;
+    }
+}
+
+/// Drops chronicle days past `retention_days`.
+pub fn prune_village_chronicle(
+    clock: Option<Res<WorldClock>>,
+    config: Res<ChronicleConfig>,
+    mut chronicle: ResMut<VillageChronicle>,
+) {
+    if let Some(clock) = clock {
+        chronicle.prune(clock.day_count(), config.retention_days);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue::types::{ContextClock, DialogueContext, DialogueTopicHint};
+
+    #[derive(Message, Debug, Clone)]
+    struct BellRung {
+        ringer: NpcId,
+        loud: bool,
+    }
+
+    fn distill_bell(message: &BellRung, roster: &ChronicleRoster) -> Option<ChronicleDraft> {
+        message.loud.then(|| {
+            ChronicleDraft::new(
+                format!("{} rang the bell", roster.name(message.ringer)),
+                vec![message.ringer],
+                0.5,
+            )
+        })
+    }
+
+    fn distill_bell_quietly(_: &BellRung, _: &ChronicleRoster) -> Option<ChronicleDraft> {
+        None
+    }
+
+    fn draft(text: &str, involved: &[u64], significance: f32) -> ChronicleDraft {
+        ChronicleDraft::new(
+            text,
+            involved.iter().map(|id| NpcId::new(*id)).collect(),
+            significance,
+        )
+    }
+
+    fn texts(entries: &[&ChronicleEntry]) -> Vec<String> {
+        entries.iter().map(|entry| entry.text.clone()).collect()
+    }
+
+    #[test]
+    fn config_reads_the_chronicle_section_and_defaults_the_rest() {
+        let config = ChronicleConfig::from_toml_str(
+            "[chronicle]\nretention_days = 5\ndecay_per_day = 1.5\ntoken_budget = 80\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            ChronicleConfig {
+                retention_days: 5,
+                decay_per_day: 1.0,
+                token_budget: 80,
+                ..ChronicleConfig::default()
+            }
+        );
+        assert_eq!(
+            ChronicleConfig::from_toml_str("").unwrap(),
+            ChronicleConfig::default()
+        );
+        assert!(ChronicleConfig::from_toml_str("[chronicle]\nretention_days = -1\n").is_err());
+    }
+
+    #[test]
+    fn distillers_are_found_by_message_type_and_registered_once() {
+        let alric = Identity::new(NpcId::new(1), "Alric", 30.0);
+        let roster = ChronicleRoster::new([(&alric, Some(&Profession::Farmer))]);
+        let mut distillers = ChronicleDistillers::default();
+        assert_eq!(distillers.distill(&7_u32, &roster), None);
+
+        assert!(distillers.register(distill_bell));
+        assert!(
+            !distillers.register(distill_bell_quietly),
+            "the first distiller is kept"
+        );
+        let rung = BellRung {
+            ringer: alric.id,
+            loud: true,
+        };
+        assert_eq!(
+            distillers.distill(&rung, &roster),
+            Some(draft("Alric rang the bell", &[1], 0.5))
+        );
+        assert_eq!(
+            distillers.distill(
+                &BellRung {
+                    loud: false,
+                    ..rung
+                },
+                &roster
+            ),
+            None
+        );
+        assert_eq!(distillers.message_names().len(), 1);
+        assert_eq!(roster.name(NpcId::new(9)), NpcId::new(9).to_string());
+        assert_eq!(roster.workers(Profession::Farmer), [alric.id]);
+    }
+
+    #[test]
+    fn significance_decays_and_old_days_are_pruned() {
+        let config = ChronicleConfig::default();
+        let mut chronicle = VillageChronicle::default();
+        chronicle.record(2, draft("The mill wheel cracked", &[], 0.8), &config);
+        let entry = chronicle.entries().next().unwrap().clone();
+        assert_eq!(entry.significance_on(2, 0.5), 0.8);
+        assert!((entry.significance_on(4, 0.5) - 0.2).abs() < 1e-6);
+        assert_eq!(
+            entry.significance_on(1, 0.5),
+            0.8,
+            "no growth before the day"
+        );
+
+        chronicle.record(4, draft("Bryn turned 31", &[2], 0.5), &config);
+        chronicle.prune(5, 3);
+        assert_eq!(chronicle.entries().count(), 2);
+        chronicle.prune(6, 3);
+        let kept: Vec<_> = chronicle.entries().map(|entry| entry.day).collect();
+        assert_eq!(kept, [4], "day 2 is more than three days before day 6");
+    }
+
+    #[test]
+    fn busy_days_keep_their_most_significant_entries() {
+        let config = ChronicleConfig {
+            entries_per_day: 2,
+            ..ChronicleConfig::default()
+        };
+        let mut chronicle = VillageChronicle::default();
+        for (text, significance) in [("a", 0.4), ("b", 0.9), ("c", 0.1), ("d", 0.6)] {
+            chronicle.record(1, draft(text, &[], significance), &config);
+        }
+        chronicle.record(1, draft("   ", &[], 1.0), &config);
+        let kept: Vec<_> = chronicle
+            .entries()
+            .map(|entry| entry.text.as_str())
+            .collect();
+        assert_eq!(kept, ["b", "d"]);
+    }
+
+    #[test]
+    fn top_entries_skip_the_speaker_and_respect_k_and_the_token_budget() {
+        let config = ChronicleConfig {
+            decay_per_day: 0.5,
+            entries_per_request: 2,
+            token_budget: 12,
+            ..ChronicleConfig::default()
+        };
+        let mut chronicle = VillageChronicle::default();
+        chronicle.record(3, draft("Market day opened", &[], 0.6), &config);
+        chronicle.record(4, draft("Bryn turned 31", &[2], 0.5), &config);
+        chronicle.record(4, draft("Dagna got a new anvil", &[4], 0.45), &config);
+        chronicle.record(
+            4,
+            draft(
+                "A very long account of everything the miller said",
+                &[],
+                0.9,
+            ),
+            &config,
+        );
+
+        let top = chronicle.top_for(NpcId::new(1), 4, &config);
+        assert_eq!(
+            texts(&top),
+            ["Bryn turned 31", "Dagna got a new anvil"],
+            "the long entry busts the budget and yesterday's market decayed to 0.3"
+        );
+        let for_bryn = chronicle.top_for(NpcId::new(2), 4, &config);
+        assert_eq!(
+            texts(&for_bryn),
+            ["Dagna got a new anvil", "Market day opened"]
+        );
+
+        let roomy = ChronicleConfig {
+            entries_per_request: 5,
+            token_budget: 100,
+            ..config
+        };
+        let everything = chronicle.top_for(NpcId::new(1), 4, &roomy);
+        assert_eq!(everything.len(), 4);
+        assert_eq!(
+            everything[0].text,
+            "A very long account of everything the miller said"
+        );
+        assert!(
+            chronicle.top_for(NpcId::new(1), 2, &roomy).is_empty(),
+            "nothing yet on day 2"
+        );
+    }
+
+    #[test]
+    fn news_is_attached_to_stamped_npc_requests_only() {
+        let config = ChronicleConfig::default();
+        let mut chronicle = VillageChronicle::default();
+        chronicle.record(4, draft("Bryn turned 31", &[2], 0.5), &config);
+
+        let mut request = DialogueRequest::new(
+            NpcId::new(1),
+            Some(NpcId::new(3)),
+            "Morning.",
+            DialogueTopicHint::Status,
+            DialogueContext::default(),
+        );
+        chronicle.attach_to(&mut request, &config);
+        assert!(request.context.events.is_empty(), "no clock, no news");
+
+        request.context.now = Some(ContextClock::new(5, 0.3));
+        chronicle.attach_to(&mut request, &config);
+        match request.context.events.as_slice() {
+            [DialogueContextEvent::VillageNews(news)] => assert_eq!(
+                news,
+                &VillageNewsContext {
+                    text: "Bryn turned 31".to_string(),
+                    day: 4,
+                }
+            ),
+            other => panic!("expected one piece of village news, got {other:?}"),
+        }
+
+        request.speaker = NpcId::player();
+        request.context.events.clear();
+        chronicle.attach_to(&mut request, &config);
+        assert!(request.context.events.is_empty());
+    }
+
+    #[test]
+    fn registered_distillers_record_messages_under_today() {
+        let mut app = App::new();
+        app.insert_resource(WorldClock::new())
+            .add_message::<BellRung>()
+            .add_chronicle_distiller(distill_bell)
+            .add_chronicle_distiller(distill_bell_quietly);
+        let ringer = NpcId::new(1);
+        app.world_mut().spawn(Identity::new(ringer, "Alric", 30.0));
+        app.world_mut().resource_mut::<WorldClock>().skip_days(2);
+        app.world_mut()
+            .write_message(BellRung { ringer, loud: true });
+        app.update();
+
+        let entries: Vec<_> = app
+            .world()
+            .resource::<VillageChronicle>()
+            .entries()
+            .map(|entry| (entry.day, entry.text.clone(), entry.involved.clone()))
+            .collect();
+        assert_eq!(
+            entries,
+            [(2, "Alric rang the bell".to_string(), vec![ringer])]
+        );
+    }
+}
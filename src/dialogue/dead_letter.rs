@@ -0,0 +1,212 @@
+//! Dead letters: dialogue requests that kept failing past `max_retries`, held so they can be
+//! resent once the provider is reachable again.
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use super::{
+    errors::DialogueError,
+    queue::{DialogueRateLimitConfig, DialogueRequestQueue},
+    types::{DialoguePriority, DialogueRequest, DialogueRequestId},
+};
+use crate::core::input::{ActionInput, InputAction};
+use crate::npc::{components::NpcId, spatial::NpcIndex};
+use crate::world::time::{minute_of_day, WorldClock};
+
+const MINUTES_PER_DAY: u64 = 24 * 60;
+
+/// A request that ran out of retries, with the error that finished it off.
+#[derive(Debug, Clone)]
+pub struct DeadLetter {
+    pub id: DialogueRequestId,
+    pub request: DialogueRequest,
+    pub error: DialogueError,
+    pub attempts: u8,
+    /// In-game minute since day 0 when the last attempt failed.
+    pub failed_at_minute: u64,
+}
+
+/// What a resubmission did with each letter; the store is empty afterwards.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DeadLetterResubmission {
+    pub resent: usize,
+    /// Older than `dead_letter_max_age_minutes`.
+    pub expired: usize,
+    /// The speaker is gone.
+    pub orphaned: usize,
+    /// Player conversations, which have moved on by the time anyone retries them.
+    pub player: usize,
+}
+
+/// Failed requests, oldest first, bounded by `DialogueRateLimitConfig::dead_letter_capacity`.
+#[derive(Resource, Debug, Default)]
+pub struct DialogueDeadLetterStore {
+    letters: VecDeque<DeadLetter>,
+}
+
+impl DialogueDeadLetterStore {
+    /// Stores `letter`, returning the oldest one when that pushes the store past `capacity`.
+    /// With zero capacity nothing is kept and `letter` comes straight back.
+    pub fn push(&mut self, letter: DeadLetter, capacity: usize) -> Option<DeadLetter> {
+        if capacity == 0 {
+            return Some(letter);
+        }
+        self.letters.push_back(letter);
+        if self.letters.len() > capacity {
+            self.letters.pop_front()
+        } else {
+            None
+        }
+    }
+
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.letters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.letters.is_empty()
+    }
+
+    #[cfg(test)]
+    pub fn iter(&self) -> impl Iterator<Item = &DeadLetter> {
+        self.letters.iter()
+    }
+
+    /// Empties the store, enqueueing each ambient letter no older than `max_age_minutes`
+    /// whose speaker `speaker_exists` still. Resent requests get a new id and a fresh attempt
+    /// count, and targeted ones are announced again like any new request. Letters involving
+    /// the player are dropped rather than resent: their priority follows from the speaker and
+    /// target, so they cannot go back as ambient chatter.
+    pub fn resubmit(
+        &mut self,
+        queue: &mut DialogueRequestQueue,
+        now_minute: u64,
+        max_age_minutes: u64,
+        speaker_exists: impl Fn(NpcId) -> bool,
+    ) -> DeadLetterResubmission {
+        let mut summary = DeadLetterResubmission::default();
+        for letter in self.letters.drain(..) {
+            if now_minute.saturating_sub(letter.failed_at_minute) > max_age_minutes {
+                summary.expired += 1;
+            } else if letter.request.priority() == DialoguePriority::Player {
+                summary.player += 1;
+            } else if !speaker_exists(letter.request.speaker) {
+                summary.orphaned += 1;
+            } else {
+                queue.enqueue(letter.request);
+                summary.resent += 1;
+            }
+        }
+        summary
+    }
+}
+
+/// In-game minutes since day 0, the clock dead letters are stamped with.
+pub fn world_minute(clock: &WorldClock) -> u64 {
+    clock.day_count() * MINUTES_PER_DAY + u64::from(minute_of_day(clock.time_of_day()))
+}
+
+/// Resends the dead letters when `RetryDeadLetters` (F3) is pressed, e.g. after an outage.
+pub fn retry_dead_letters(
+    input: ActionInput,
+    clock: Option<Res<WorldClock>>,
+    config: Res<DialogueRateLimitConfig>,
+    npc_index: Res<NpcIndex>,
+    mut store: ResMut<DialogueDeadLetterStore>,
+    mut queue: ResMut<DialogueRequestQueue>,
+) {
+    if !input.just_pressed(InputAction::RetryDeadLetters) {
+        return;
+    }
+    if store.is_empty() {
+        info!("No failed dialogue requests to retry.");
+        return;
+    }
+    let summary = store.resubmit(
+        &mut queue,
+        clock.as_deref().map_or(0, world_minute),
+        config.dead_letter_max_age_minutes,
+        |speaker| npc_index.entity(speaker).is_some(),
+    );
+    info!(
+        "Retrying failed dialogue requests: {} resent, {} too old, {} without a speaker, {} \
+         player conversations dropped.",
+        summary.resent, summary.expired, summary.orphaned, summary.player
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue::{
+        broker::DialogueProviderKind,
+        errors::DialogueErrorKind,
+        types::{DialogueContext, DialogueTopicHint},
+    };
+
+    fn letter(id: u64, speaker: u64, target: Option<NpcId>, failed_at_minute: u64) -> DeadLetter {
+        let id = DialogueRequestId::new(id);
+        DeadLetter {
+            id,
+            request: DialogueRequest::new(
+                NpcId::new(speaker),
+                target,
+                "Fine weather.",
+                DialogueTopicHint::Status,
+                DialogueContext::default(),
+            ),
+            error: DialogueError::new(
+                id,
+                DialogueProviderKind::OpenAi,
+                DialogueErrorKind::provider_failure("offline"),
+            ),
+            attempts: 3,
+            failed_at_minute,
+        }
+    }
+
+    #[test]
+    fn full_store_evicts_the_oldest_letter() {
+        let mut store = DialogueDeadLetterStore::default();
+        assert!(store.push(letter(1, 1, None, 0), 2).is_none());
+        assert!(store.push(letter(2, 2, None, 0), 2).is_none());
+        let evicted = store.push(letter(3, 3, None, 0), 2).expect("over capacity");
+        assert_eq!(evicted.id, DialogueRequestId::new(1));
+        assert_eq!(store.len(), 2);
+        let kept: Vec<_> = store.iter().map(|letter| letter.id.value()).collect();
+        assert_eq!(kept, vec![2, 3]);
+
+        let refused = store.push(letter(4, 4, None, 0), 0);
+        assert_eq!(refused.map(|letter| letter.id.value()), Some(4));
+        assert_eq!(store.len(), 2);
+    }
+
+    #[test]
+    fn resubmission_skips_old_orphaned_and_player_letters() {
+        let mut store = DialogueDeadLetterStore::default();
+        store.push(letter(1, 1, Some(NpcId::new(2)), 100), 8);
+        store.push(letter(2, 2, None, 10), 8);
+        store.push(letter(3, 9, None, 100), 8);
+        store.push(letter(4, 3, Some(NpcId::player()), 100), 8);
+
+        let mut queue = DialogueRequestQueue::default();
+        let summary = store.resubmit(&mut queue, 200, 120, |speaker| speaker != NpcId::new(9));
+
+        assert_eq!(
+            summary,
+            DeadLetterResubmission {
+                resent: 1,
+                expired: 1,
+                orphaned: 1,
+                player: 1,
+            }
+        );
+        assert!(store.is_empty());
+        let pending: Vec<_> = queue
+            .iter_pending()
+            .map(|view| (view.speaker, view.attempts))
+            .collect();
+        assert_eq!(pending, vec![(NpcId::new(1), 0)]);
+    }
+}
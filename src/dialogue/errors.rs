@@ -1,32 +1,80 @@
 //! Error types surfaced by the dialogue request runner.
 use std::fmt;
 
+use serde::Serialize;
+
 use super::{broker::DialogueProviderKind, types::DialogueRequestId};
 
 /// Error categories returned when processing dialogue requests.
 #[derive(Debug, Clone)]
 pub enum DialogueErrorKind {
-    RateLimited { retry_after_seconds: f32 },
-    ProviderFailure { message: String },
-    ContextMissing { missing: DialogueContextSource },
+    /// The provider asked us to slow down.
+    RateLimited {
+        /// Seconds to wait before sending again.
+        retry_after_seconds: f32,
+    },
+    /// The provider could not answer.
+    ProviderFailure {
+        /// The provider's explanation, or the transport error.
+        message: String,
+        /// Whether a retry could help.
+        class: ProviderFailureClass,
+    },
+    /// The request needed context that was not available.
+    ContextMissing {
+        /// The context that was missing.
+        missing: DialogueContextSource,
+    },
+    /// The request itself is malformed.
+    InvalidRequest {
+        /// What is wrong with it.
+        reason: String,
+    },
 }
 
 impl DialogueErrorKind {
+    /// Rate limit lifting after `retry_after_seconds`.
     pub fn rate_limited(retry_after_seconds: f32) -> Self {
         Self::RateLimited {
             retry_after_seconds,
         }
     }
 
+    /// A provider failure worth retrying, such as a dropped connection or a server error.
     pub fn provider_failure(message: impl Into<String>) -> Self {
+        Self::classified_failure(ProviderFailureClass::Transient, message)
+    }
+
+    /// A provider failure of a known `class`.
+    pub fn classified_failure(class: ProviderFailureClass, message: impl Into<String>) -> Self {
         Self::ProviderFailure {
             message: message.into(),
+            class,
         }
     }
 
+    /// The request lacked `missing` context.
     pub fn context_missing(missing: DialogueContextSource) -> Self {
         Self::ContextMissing { missing }
     }
+
+    /// The request is malformed for `reason`.
+    pub fn invalid_request(reason: impl Into<String>) -> Self {
+        Self::InvalidRequest {
+            reason: reason.into(),
+        }
+    }
+
+    /// Whether sending the same request again could succeed: rate limits and transient
+    /// provider failures. Rejected credentials, unknown models, policy refusals, and
+    /// requests that failed validation fail the same way every time.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::RateLimited { .. } => true,
+            Self::ProviderFailure { class, .. } => *class == ProviderFailureClass::Transient,
+            Self::ContextMissing { .. } | Self::InvalidRequest { .. } => false,
+        }
+    }
 }
 
 impl fmt::Display for DialogueErrorKind {
@@ -35,10 +83,43 @@ impl fmt::Display for DialogueErrorKind {
             Self::RateLimited {
                 retry_after_seconds,
             } => write!(f, "Rate limited. Retry after {:.2}s", retry_after_seconds),
-            Self::ProviderFailure { message } => write!(f, "Provider failure: {}", message),
+            Self::ProviderFailure {
+                message,
+                class: ProviderFailureClass::Transient,
+            } => write!(f, "Provider failure: {}", message),
+            Self::ProviderFailure { message, class } => {
+                write!(f, "Provider failure ({}): {}", class.label(), message)
+            }
             Self::ContextMissing { missing } => {
                 write!(f, "Missing context: {}", missing)
             }
+            Self::InvalidRequest { reason } => write!(f, "Invalid request: {}", reason),
+        }
+    }
+}
+
+/// Why a provider failed a request, which decides whether it is retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderFailureClass {
+    /// Network errors, server errors, and anything unrecognised.
+    Transient,
+    /// The provider rejected the API key or its permissions.
+    Auth,
+    /// The model or endpoint does not exist.
+    NotFound,
+    /// The provider refused the content.
+    Policy,
+}
+
+impl ProviderFailureClass {
+    /// Short name for logs and error messages.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Transient => "transient",
+            Self::Auth => "auth",
+            Self::NotFound => "not found",
+            Self::Policy => "policy",
         }
     }
 }
@@ -46,8 +127,11 @@ impl fmt::Display for DialogueErrorKind {
 /// Context sources that can cause provider rejections when missing.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum DialogueContextSource {
+    /// Recent trades involving the speaker.
     TradeHistory,
+    /// The speaker's current schedule entry.
     ScheduleState,
+    /// What the speaker holds.
     InventoryState,
 }
 
@@ -65,12 +149,16 @@ impl fmt::Display for DialogueContextSource {
 /// Full error with provider metadata and request id.
 #[derive(Debug, Clone)]
 pub struct DialogueError {
+    /// The request that failed.
     pub request_id: DialogueRequestId,
+    /// Provider that failed it.
     pub provider: DialogueProviderKind,
+    /// What went wrong.
     pub kind: DialogueErrorKind,
 }
 
 impl DialogueError {
+    /// Error `kind` for `request_id` from `provider`.
     pub fn new(
         request_id: DialogueRequestId,
         provider: DialogueProviderKind,
@@ -88,16 +176,38 @@ impl fmt::Display for DialogueError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "Dialogue error ({} - request {}): {}",
-            self.provider,
-            self.request_id.value(),
-            self.kind
+            "Dialogue error ({} - {}): {}",
+            self.provider, self.request_id, self.kind
         )
     }
 }
 
 impl std::error::Error for DialogueError {}
 
+/// Mistakes caught while building a request, before it reaches the queue or a broker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DialogueBuildError {
+    /// The prompt is blank.
+    EmptyPrompt,
+    /// `DialogueTopicHint::Trade` requests must carry a trade event for context.
+    MissingTradeEvent,
+    /// Pair cooldowns need both a speaker and a target.
+    CooldownWithoutTarget,
+}
+
+impl fmt::Display for DialogueBuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            Self::EmptyPrompt => "prompt is empty",
+            Self::MissingTradeEvent => "trade topic requires a trade event",
+            Self::CooldownWithoutTarget => "chatter cooldown requires a target",
+        };
+        write!(f, "Invalid dialogue request: {}", message)
+    }
+}
+
+impl std::error::Error for DialogueBuildError {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -115,7 +225,10 @@ mod tests {
         let provider_failure = DialogueErrorKind::provider_failure("unreachable");
         assert!(matches!(
             provider_failure,
-            DialogueErrorKind::ProviderFailure { .. }
+            DialogueErrorKind::ProviderFailure {
+                class: ProviderFailureClass::Transient,
+                ..
+            }
         ));
 
         let missing_schedule =
@@ -146,4 +259,26 @@ mod tests {
         assert!(error.to_string().contains("OpenAi"));
         assert_eq!(format!("{}", error.kind), format!("{}", provider_failure));
     }
+
+    #[test]
+    fn only_rate_limits_and_transient_failures_retry() {
+        assert!(DialogueErrorKind::rate_limited(1.0).is_retryable());
+        assert!(DialogueErrorKind::provider_failure("timed out").is_retryable());
+        for class in [
+            ProviderFailureClass::Auth,
+            ProviderFailureClass::NotFound,
+            ProviderFailureClass::Policy,
+        ] {
+            let kind = DialogueErrorKind::classified_failure(class, "rejected");
+            assert!(!kind.is_retryable(), "{class:?} should not retry");
+            assert_eq!(
+                kind.to_string(),
+                format!("Provider failure ({}): rejected", class.label())
+            );
+        }
+        assert!(!DialogueErrorKind::invalid_request("empty").is_retryable());
+        assert!(
+            !DialogueErrorKind::context_missing(DialogueContextSource::TradeHistory).is_retryable()
+        );
+    }
 }
@@ -1,9 +1,12 @@
 //! Events emitted by the dialogue queue runner.
 use bevy::prelude::{Event, Message};
+use chrono::{DateTime, Local};
+use serde::Serialize;
 
 use super::{
+    budget::ApiBudgetLimit,
     errors::DialogueError,
-    types::{DialogueRequestId, DialogueResponse},
+    types::{DialogueContext, DialogueRequestId, DialogueResponse},
 };
 use crate::npc::components::NpcId;
 
@@ -19,12 +22,74 @@ pub struct DialogueRequestedEvent {
 #[derive(Event, Message, Debug, Clone)]
 pub struct DialogueResponseEvent {
     pub response: DialogueResponse,
+    /// Context of the request this line answers, so listeners can remember what they heard.
+    pub context: DialogueContext,
 }
 
 /// Fired when a dialogue request fails after exhausting retries.
 #[derive(Event, Message, Debug, Clone)]
 pub struct DialogueRequestFailedEvent {
     pub error: DialogueError,
+    /// Speaker of the failed request, so UI can show feedback above them.
+    pub speaker: NpcId,
+    /// Intended listener; `NpcId::player()` when the player started the exchange.
+    pub target: Option<NpcId>,
+}
+
+/// Fired once per budget window, when the first request is answered locally because the
+/// daily API budget is spent.
+#[derive(Event, Message, Debug, Clone)]
+pub struct ApiBudgetExhaustedEvent {
+    /// The cap that was reached.
+    pub limit: ApiBudgetLimit,
+    /// Live calls made in the window.
+    pub requests_used: u32,
+    /// Estimated tokens spent in the window.
+    pub tokens_used: u64,
+    /// When live calls resume.
+    pub resets_at: Option<DateTime<Local>>,
+}
+
+/// Fired by the player systems so telemetry can follow the interaction funnel.
+#[derive(Event, Message, Debug, Clone, PartialEq)]
+pub enum PlayerInteractionEvent {
+    /// The player greeted `npc` from `distance` world units away.
+    Started {
+        /// The NPC greeted.
+        npc: NpcId,
+        /// World units between the player and the NPC.
+        distance: f32,
+    },
+    /// The player picked canned response `option_index` in the response window.
+    ResponseChosen {
+        /// The NPC answered.
+        npc: NpcId,
+        /// Zero-based position of the option in the window.
+        option_index: usize,
+        /// The option's text as shown.
+        option_text: String,
+    },
+    /// The conversation with `npc` is over after `turns` player replies.
+    ConversationEnded {
+        /// The NPC the conversation was with.
+        npc: NpcId,
+        /// Player replies sent.
+        turns: u32,
+        /// What ended it.
+        ended_by: ConversationEndReason,
+    },
+}
+
+/// Why a player conversation ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConversationEndReason {
+    /// The NPC's conversation lapsed without the player answering.
+    Timeout,
+    /// The player dismissed the NPC with "Leave them be."
+    Goodbye,
+    /// The player turned to someone else mid-conversation.
+    WalkedAway,
 }
 
 #[cfg(test)]
@@ -33,9 +98,7 @@ mod tests {
     use crate::dialogue::{
         broker::DialogueProviderKind,
         errors::{DialogueError, DialogueErrorKind},
-        types::{DialogueRequestId, DialogueResponse},
     };
-    use crate::npc::components::NpcId;
 
     #[test]
     fn wraps_dialogue_payloads() {
@@ -49,7 +112,10 @@ mod tests {
             "Hello there",
         );
 
-        let response_event = DialogueResponseEvent { response };
+        let response_event = DialogueResponseEvent {
+            response,
+            context: DialogueContext::default(),
+        };
         assert_eq!(response_event.response.content, "Hello there");
         assert_eq!(response_event.response.request_id.value(), 11);
 
@@ -58,11 +124,16 @@ mod tests {
             DialogueProviderKind::OpenAi,
             DialogueErrorKind::provider_failure("boom"),
         );
-        let failure_event = DialogueRequestFailedEvent { error };
+        let failure_event = DialogueRequestFailedEvent {
+            error,
+            speaker,
+            target: Some(NpcId::player()),
+        };
         assert!(matches!(
             failure_event.error.kind,
             DialogueErrorKind::ProviderFailure { .. }
         ));
         assert_eq!(failure_event.error.request_id.value(), 11);
+        assert_eq!(failure_event.speaker, speaker);
     }
 }
@@ -1,12 +1,31 @@
 //! Dialogue module hosting broker abstractions, request queueing, and context types.
 pub mod broker;
+pub mod budget;
+pub mod builder;
+#[cfg(feature = "chaos")]
+pub mod chaos;
+pub mod chatter;
+pub mod chronicle;
+pub mod dead_letter;
 pub mod errors;
 pub mod events;
+pub mod ordering;
+pub mod pending_speech;
+pub mod player_memory;
 pub mod plugin;
+pub mod probe;
+pub mod prompts;
 pub mod queue;
+pub mod router;
+#[cfg(feature = "scripting")]
+pub mod scripting;
+pub mod simulation;
 pub mod status;
 pub mod telemetry;
+pub mod trace;
+pub mod transcripts;
 pub mod types;
+pub mod validation;
 
 pub use plugin::DialoguePlugin;
 
@@ -16,6 +35,7 @@ mod tests {
         broker::{DialogueBroker, DialogueProviderKind, OpenAiDialogueBroker},
         errors::{DialogueError, DialogueErrorKind},
         events::{DialogueRequestFailedEvent, DialogueResponseEvent},
+        prompts::SharedPromptTemplates,
         queue::{DialogueRateLimitConfig, DialogueRateLimitState, DialogueRequestQueue},
         types::{
             DialogueContext, DialogueContextEvent, DialogueRequest, DialogueTopicHint,
@@ -38,7 +58,7 @@ mod tests {
         assert_eq!(request_id.value(), 0);
         assert!(queue.front_ready());
 
-        let broker = OpenAiDialogueBroker::new();
+        let broker = OpenAiDialogueBroker::new(SharedPromptTemplates::default());
         let response = broker
             .process(
                 request_id,
@@ -61,18 +81,26 @@ mod tests {
 
         let _failure_event = DialogueRequestFailedEvent {
             error: error.clone(),
+            speaker: NpcId::new(1),
+            target: None,
         };
         let _response_event = DialogueResponseEvent {
             response: response.clone(),
+            context: DialogueContext::default(),
         };
 
         let mut limits = DialogueRateLimitState::default();
-        limits.record_success(NpcId::new(1), &DialogueRateLimitConfig::default());
-        assert!(!limits.can_process(NpcId::new(1)));
+        limits.record_success(
+            DialogueProviderKind::OpenAi,
+            NpcId::new(1),
+            &DialogueRateLimitConfig::default(),
+        );
+        assert!(!limits.can_process(DialogueProviderKind::OpenAi, NpcId::new(1)));
 
         let trade_descriptor = TradeDescriptor::new("grain", 5);
         let trade_context = TradeContext {
             day: 1,
+            time_of_day: None,
             from: Some(NpcId::new(1)),
             to: Some(NpcId::new(2)),
             descriptor: trade_descriptor,
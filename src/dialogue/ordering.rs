@@ -0,0 +1,257 @@
+//! Per-speaker release order for dialogue outcomes. Background tasks finish in whatever
+//! order the provider answers, so `SpeakerSequencer` numbers each speaker's dispatches and
+//! holds a finished outcome back until every earlier dispatch for that speaker has resolved.
+use std::collections::{BTreeMap, HashMap};
+
+use crate::npc::components::NpcId;
+
+/// One speaker's dispatch counter and the outcomes waiting on an earlier one. Lanes are
+/// kept for good, so an outcome arriving after its number was skipped is still recognised.
+#[derive(Debug)]
+struct SpeakerLane<T> {
+    next_dispatch: u64,
+    next_release: u64,
+    /// Resolved out of order, by sequence number, with the time each was pushed. `None`
+    /// marks a dispatch that was abandoned without an outcome.
+    waiting: BTreeMap<u64, (Option<T>, f64)>,
+}
+
+impl<T> Default for SpeakerLane<T> {
+    fn default() -> Self {
+        Self {
+            next_dispatch: 0,
+            next_release: 0,
+            waiting: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T> SpeakerLane<T> {
+    /// Moves every outcome that no longer waits on a gap into `ready`.
+    fn release_run(&mut self, ready: &mut Vec<T>) {
+        while let Some((item, _)) = self.waiting.remove(&self.next_release) {
+            ready.extend(item);
+            self.next_release += 1;
+        }
+    }
+}
+
+/// Releases each speaker's outcomes in dispatch order. `dispatch` hands out a speaker's next
+/// sequence number, `push` files the outcome for one, and `poll` returns whatever may go out.
+/// An outcome held longer than the timeout is released anyway, skipping the earlier numbers
+/// it waited on, so a lost task never blocks its speaker for good; those late outcomes go
+/// out as soon as they arrive. Speakers never wait on each other.
+#[derive(Debug)]
+pub struct SpeakerSequencer<T> {
+    lanes: HashMap<NpcId, SpeakerLane<T>>,
+    ready: Vec<T>,
+    /// Sequence numbers given up on after a timeout, across all speakers.
+    skipped: u64,
+}
+
+impl<T> Default for SpeakerSequencer<T> {
+    fn default() -> Self {
+        Self {
+            lanes: HashMap::new(),
+            ready: Vec::new(),
+            skipped: 0,
+        }
+    }
+}
+
+impl<T> SpeakerSequencer<T> {
+    /// The next sequence number for `speaker`, counting from zero.
+    pub fn dispatch(&mut self, speaker: NpcId) -> u64 {
+        let lane = self.lanes.entry(speaker).or_default();
+        let sequence = lane.next_dispatch;
+        lane.next_dispatch += 1;
+        sequence
+    }
+
+    /// Files `item` as the outcome of `speaker`'s dispatch `sequence`, at `now` seconds. It is
+    /// ready at once when nothing earlier is outstanding or the number was already skipped.
+    pub fn push(&mut self, speaker: NpcId, sequence: u64, item: T, now: f64) {
+        let lane = self.lanes.entry(speaker).or_default();
+        if sequence < lane.next_release {
+            self.ready.push(item);
+        } else {
+            lane.waiting.insert(sequence, (Some(item), now));
+            lane.release_run(&mut self.ready);
+        }
+    }
+
+    /// Resolves `speaker`'s dispatch `sequence` with no outcome, for a request withdrawn
+    /// while it waited to be retried. Later outcomes stop waiting on it.
+    pub fn abandon(&mut self, speaker: NpcId, sequence: u64, now: f64) {
+        let lane = self.lanes.entry(speaker).or_default();
+        if sequence >= lane.next_release {
+            lane.waiting.insert(sequence, (None, now));
+            lane.release_run(&mut self.ready);
+        }
+    }
+
+    /// Everything releasable at `now`: outcomes in dispatch order per speaker, plus held
+    /// outcomes that have waited `timeout_seconds` or more. Timed-out speakers are handled in
+    /// id order so a run replays the same way.
+    pub fn poll(&mut self, now: f64, timeout_seconds: f64) -> Vec<T> {
+        let mut timed_out: Vec<NpcId> = self
+            .lanes
+            .iter()
+            .filter(|(_, lane)| {
+                lane.waiting
+                    .first_key_value()
+                    .is_some_and(|(_, (_, pushed_at))| now - pushed_at >= timeout_seconds)
+            })
+            .map(|(speaker, _)| *speaker)
+            .collect();
+        timed_out.sort();
+        for speaker in timed_out {
+            let Some(lane) = self.lanes.get_mut(&speaker) else {
+                continue;
+            };
+            // Skip the gap in front of the oldest held outcome, then release from it on.
+            while let Some((&sequence, (_, pushed_at))) = lane.waiting.first_key_value() {
+                if now - pushed_at < timeout_seconds {
+                    break;
+                }
+                self.skipped += sequence - lane.next_release;
+                lane.next_release = sequence;
+                lane.release_run(&mut self.ready);
+            }
+        }
+        std::mem::take(&mut self.ready)
+    }
+
+    /// Held outcomes, in no particular order.
+    pub fn held(&self) -> impl Iterator<Item = &T> {
+        self.lanes
+            .values()
+            .flat_map(|lane| lane.waiting.values().filter_map(|(item, _)| item.as_ref()))
+    }
+
+    /// Sequence numbers released past without their outcome so far.
+    pub fn skipped(&self) -> u64 {
+        self.skipped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMEOUT: f64 = 10.0;
+
+    fn npc(id: u64) -> NpcId {
+        NpcId::new(id)
+    }
+
+    #[test]
+    fn in_order_outcomes_release_immediately() {
+        let mut sequencer = SpeakerSequencer::default();
+        let first = sequencer.dispatch(npc(1));
+        let second = sequencer.dispatch(npc(1));
+        assert_eq!((first, second), (0, 1));
+
+        sequencer.push(npc(1), first, "a", 0.0);
+        assert_eq!(sequencer.poll(0.0, TIMEOUT), ["a"]);
+        sequencer.push(npc(1), second, "b", 0.5);
+        assert_eq!(sequencer.poll(0.5, TIMEOUT), ["b"]);
+        assert_eq!(sequencer.dispatch(npc(1)), 2);
+    }
+
+    #[test]
+    fn out_of_order_outcomes_wait_for_earlier_dispatches() {
+        let mut sequencer = SpeakerSequencer::default();
+        let sequences: Vec<u64> = (0..3).map(|_| sequencer.dispatch(npc(1))).collect();
+
+        sequencer.push(npc(1), sequences[2], "c", 0.0);
+        sequencer.push(npc(1), sequences[1], "b", 0.1);
+        assert!(sequencer.poll(0.2, TIMEOUT).is_empty());
+        assert_eq!(sequencer.held().count(), 2);
+
+        sequencer.push(npc(1), sequences[0], "a", 0.3);
+        assert_eq!(sequencer.poll(0.3, TIMEOUT), ["a", "b", "c"]);
+        assert_eq!(sequencer.held().count(), 0);
+        assert_eq!(sequencer.skipped(), 0);
+    }
+
+    #[test]
+    fn held_outcomes_release_after_the_timeout_and_late_ones_follow() {
+        let mut sequencer = SpeakerSequencer::default();
+        let lost = sequencer.dispatch(npc(1));
+        let slow = sequencer.dispatch(npc(1));
+        let held = sequencer.dispatch(npc(1));
+        let fresh = sequencer.dispatch(npc(1));
+
+        sequencer.push(npc(1), held, "held", 1.0);
+        sequencer.push(npc(1), fresh, "fresh", 8.0);
+        assert!(sequencer.poll(10.9, TIMEOUT).is_empty());
+        assert_eq!(
+            sequencer.poll(11.0, TIMEOUT),
+            ["held", "fresh"],
+            "a newer outcome right behind the timed-out one goes with it"
+        );
+        assert_eq!(sequencer.skipped(), 2);
+
+        sequencer.push(npc(1), slow, "slow", 12.0);
+        sequencer.push(npc(1), lost, "lost", 13.0);
+        assert_eq!(sequencer.poll(13.0, TIMEOUT), ["slow", "lost"]);
+        assert_eq!(sequencer.held().count(), 0);
+    }
+
+    #[test]
+    fn timeout_skips_only_up_to_the_next_gap() {
+        let mut sequencer = SpeakerSequencer::default();
+        let sequences: Vec<u64> = (0..4).map(|_| sequencer.dispatch(npc(1))).collect();
+
+        sequencer.push(npc(1), sequences[1], "old", 0.0);
+        sequencer.push(npc(1), sequences[3], "new", 9.0);
+        assert_eq!(sequencer.poll(10.0, TIMEOUT), ["old"]);
+        assert_eq!(sequencer.skipped(), 1);
+
+        sequencer.push(npc(1), sequences[2], "middle", 12.0);
+        assert_eq!(sequencer.poll(12.0, TIMEOUT), ["middle", "new"]);
+        assert_eq!(sequencer.skipped(), 1);
+    }
+
+    #[test]
+    fn speakers_are_ordered_independently() {
+        let mut sequencer = SpeakerSequencer::default();
+        let alric_first = sequencer.dispatch(npc(1));
+        let bryn_first = sequencer.dispatch(npc(2));
+        let alric_second = sequencer.dispatch(npc(1));
+        let bryn_second = sequencer.dispatch(npc(2));
+        assert_eq!((alric_first, bryn_first), (0, 0));
+
+        sequencer.push(npc(1), alric_second, "alric 2", 0.0);
+        sequencer.push(npc(2), bryn_first, "bryn 1", 0.1);
+        assert_eq!(
+            sequencer.poll(0.1, TIMEOUT),
+            ["bryn 1"],
+            "one speaker's gap never holds up another"
+        );
+
+        sequencer.push(npc(2), bryn_second, "bryn 2", 0.2);
+        sequencer.push(npc(1), alric_first, "alric 1", 0.3);
+        assert_eq!(
+            sequencer.poll(0.3, TIMEOUT),
+            ["bryn 2", "alric 1", "alric 2"]
+        );
+    }
+
+    #[test]
+    fn abandoned_dispatches_stop_holding_up_later_ones() {
+        let mut sequencer = SpeakerSequencer::default();
+        let dropped = sequencer.dispatch(npc(1));
+        let kept = sequencer.dispatch(npc(1));
+
+        sequencer.push(npc(1), kept, "kept", 0.0);
+        assert!(sequencer.poll(0.0, TIMEOUT).is_empty());
+        sequencer.abandon(npc(1), dropped, 0.5);
+        assert_eq!(sequencer.poll(0.5, TIMEOUT), ["kept"]);
+        assert_eq!(sequencer.skipped(), 0);
+
+        sequencer.abandon(npc(1), dropped, 1.0);
+        assert_eq!(sequencer.held().count(), 0, "abandoning twice is harmless");
+    }
+}
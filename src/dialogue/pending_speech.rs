@@ -0,0 +1,229 @@
+//! Keeps `PendingSpeech` on NPCs whose dialogue request has been dispatched and not yet
+//! answered, so the UI can show them thinking.
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+
+use super::queue::{DialogueRequestQueue, PendingDialogueTasks};
+use crate::npc::components::{Identity, PendingSpeech};
+
+/// Marks speakers of in-flight requests. Runs right after `poll_dialogue_tasks`, so the
+/// marker comes off in the frame the reply or the final failure is emitted: the request is
+/// then neither in flight nor queued. A request waiting out a retry is still queued and keeps
+/// its marker; one withdrawn with `cancel` loses it. Markers on despawned NPCs go with the
+/// entity.
+pub fn track_pending_speech(
+    mut commands: Commands,
+    queue: Res<DialogueRequestQueue>,
+    tasks: Res<PendingDialogueTasks>,
+    speakers: Query<(Entity, &Identity, Option<&PendingSpeech>)>,
+) {
+    let in_flight: HashMap<_, _> = tasks
+        .in_flight_views()
+        .map(|view| (view.speaker, view.id))
+        .collect();
+    let queued: HashSet<_> = queue.iter_pending().map(|view| view.id).collect();
+
+    for (entity, identity, marker) in &speakers {
+        if let Some(&request_id) = in_flight.get(&identity.id) {
+            if marker.map(|marker| marker.request_id) != Some(request_id) {
+                commands.entity(entity).insert(PendingSpeech { request_id });
+            }
+        } else if let Some(marker) = marker {
+            if !queued.contains(&marker.request_id) {
+                commands.entity(entity).remove::<PendingSpeech>();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    };
+    use std::time::Duration;
+
+    use bevy::tasks::{AsyncComputeTaskPool, TaskPool};
+
+    use super::*;
+    use crate::dialogue::{
+        broker::{DialogueBroker, DialogueProviderKind},
+        errors::{DialogueError, DialogueErrorKind},
+        events::{DialogueRequestFailedEvent, DialogueResponseEvent},
+        queue::{
+            poll_dialogue_tasks, run_dialogue_request_queue, DialogueRateLimitConfig,
+            DialogueRateLimitState, DialogueSpeakerProfiles,
+        },
+        router::DialogueProviderRouter,
+        status::DialogueConnectionState,
+        types::{
+            DialogueContext, DialogueRequest, DialogueRequestId, DialogueResponse,
+            DialogueTopicHint,
+        },
+        validation::DialogueValidationConfig,
+    };
+    use crate::npc::{components::NpcId, spatial::NpcIndex};
+
+    /// Holds every request until released, then answers or fails it.
+    struct HeldBroker {
+        release: Arc<AtomicBool>,
+        fail: bool,
+    }
+
+    impl DialogueBroker for HeldBroker {
+        fn provider_kind(&self) -> DialogueProviderKind {
+            DialogueProviderKind::OpenAi
+        }
+
+        fn connection_state(&self) -> DialogueConnectionState {
+            DialogueConnectionState::Fallback
+        }
+
+        fn process(
+            &self,
+            request_id: DialogueRequestId,
+            request: &DialogueRequest,
+        ) -> Result<DialogueResponse, DialogueError> {
+            while !self.release.load(Ordering::Acquire) {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            if self.fail {
+                return Err(DialogueError::new(
+                    request_id,
+                    self.provider_kind(),
+                    DialogueErrorKind::provider_failure("offline"),
+                ));
+            }
+            Ok(DialogueResponse::new(
+                request_id,
+                self.provider_kind(),
+                request.speaker,
+                request.target,
+                "Hmm, let me think.",
+            ))
+        }
+    }
+
+    fn setup(fail: bool, max_retries: u8) -> (App, Entity, Arc<AtomicBool>) {
+        AsyncComputeTaskPool::get_or_init(TaskPool::new);
+        let release = Arc::new(AtomicBool::new(false));
+        let mut app = App::new();
+        app.init_resource::<DialogueRequestQueue>()
+            .init_resource::<DialogueRateLimitState>()
+            .insert_resource(DialogueRateLimitConfig {
+                global_cooldown_seconds: 0.0,
+                per_npc_cooldown_seconds: 0.0,
+                max_retries,
+                retry_backoff_seconds: 600.0,
+                ..default()
+            })
+            .init_resource::<DialogueValidationConfig>()
+            .init_resource::<DialogueSpeakerProfiles>()
+            .init_resource::<PendingDialogueTasks>()
+            .init_resource::<NpcIndex>()
+            .insert_resource(DialogueProviderRouter::new(Box::new(HeldBroker {
+                release: release.clone(),
+                fail,
+            })))
+            .add_message::<DialogueRequestFailedEvent>()
+            .add_message::<DialogueResponseEvent>()
+            .add_systems(
+                Update,
+                (
+                    run_dialogue_request_queue,
+                    poll_dialogue_tasks,
+                    track_pending_speech,
+                )
+                    .chain(),
+            );
+        let speaker = app
+            .world_mut()
+            .spawn(Identity::new(NpcId::new(1), "Alric", 30.0))
+            .id();
+        app.world_mut()
+            .resource_mut::<DialogueRequestQueue>()
+            .enqueue(DialogueRequest::new(
+                NpcId::new(1),
+                Some(NpcId::new(2)),
+                "Good harvest this year.",
+                DialogueTopicHint::Status,
+                DialogueContext::default(),
+            ));
+        (app, speaker, release)
+    }
+
+    fn marker(app: &App, speaker: Entity) -> Option<PendingSpeech> {
+        app.world().get::<PendingSpeech>(speaker).copied()
+    }
+
+    /// Updates until the in-flight task is gone.
+    fn settle(app: &mut App) {
+        for _ in 0..500 {
+            app.update();
+            if app
+                .world()
+                .resource::<PendingDialogueTasks>()
+                .in_flight_views()
+                .next()
+                .is_none()
+            {
+                app.update();
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(2));
+        }
+        panic!("request never completed");
+    }
+
+    #[test]
+    fn marker_lasts_from_dispatch_until_the_reply() {
+        let (mut app, speaker, release) = setup(false, 0);
+        assert!(marker(&app, speaker).is_none());
+
+        app.update();
+        assert_eq!(
+            marker(&app, speaker),
+            Some(PendingSpeech {
+                request_id: DialogueRequestId::new(0)
+            })
+        );
+
+        release.store(true, Ordering::Release);
+        settle(&mut app);
+        assert!(marker(&app, speaker).is_none());
+    }
+
+    #[test]
+    fn final_failure_clears_the_marker() {
+        let (mut app, speaker, release) = setup(true, 0);
+        app.update();
+        assert!(marker(&app, speaker).is_some());
+
+        release.store(true, Ordering::Release);
+        settle(&mut app);
+        assert!(marker(&app, speaker).is_none());
+    }
+
+    #[test]
+    fn marker_waits_out_a_retry_and_goes_when_it_is_cancelled() {
+        let (mut app, speaker, release) = setup(true, 1);
+        app.update();
+        release.store(true, Ordering::Release);
+        settle(&mut app);
+        let request_id = DialogueRequestId::new(0);
+        assert_eq!(
+            marker(&app, speaker),
+            Some(PendingSpeech { request_id }),
+            "a retry is still pending speech"
+        );
+
+        assert!(app
+            .world_mut()
+            .resource_mut::<DialogueRequestQueue>()
+            .cancel(request_id));
+        app.update();
+        assert!(marker(&app, speaker).is_none());
+    }
+}
@@ -0,0 +1,414 @@
+//! Notes each NPC keeps about past conversations with the player, saved to disk so NPCs
+//! still know the player after a restart. Player-targeted requests carry the speaker's
+//! notes as context.
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    fs::{self, create_dir_all},
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+use bevy::{
+    prelude::*,
+    tasks::{block_on, poll_once, AsyncComputeTaskPool, Task},
+};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    broker::truncated_summary,
+    budget::DailyApiBudget,
+    events::PlayerInteractionEvent,
+    router::DialogueProviderRouter,
+    status::DialogueConnectionState,
+    transcripts::{TranscriptEntry, TranscriptStore},
+    types::{DialogueContextEvent, DialoguePriority, DialogueRequest},
+};
+use crate::{
+    npc::{
+        components::{Identity, NpcId, PLAYER_DISPLAY_NAME},
+        spatial::NpcIndex,
+    },
+    world::time::WorldClock,
+};
+
+/// There is no save system yet, so every run shares this file.
+pub const DEFAULT_PLAYER_MEMORY_PATH: &str = "logs/player_memory.json";
+const DEFAULT_NOTES_PER_NPC: usize = 5;
+const NOTE_CONTEXT_PREFIX: &str = "Earlier with the player";
+
+/// What an NPC took away from one conversation with the player.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerMemoryNote {
+    /// World day the conversation ended on.
+    pub day: u64,
+    pub text: String,
+}
+
+impl PlayerMemoryNote {
+    pub fn new(day: u64, text: impl Into<String>) -> Self {
+        Self {
+            day,
+            text: text.into(),
+        }
+    }
+}
+
+/// Per-NPC notes about the player, each list bounded to `capacity` with the oldest evicted
+/// first. Saved to `path` whenever a note is added; without a path the notes only last
+/// for the session.
+#[derive(Resource, Debug)]
+pub struct PlayerMemory {
+    capacity: usize,
+    path: Option<PathBuf>,
+    notes: HashMap<NpcId, VecDeque<PlayerMemoryNote>>,
+}
+
+impl Default for PlayerMemory {
+    fn default() -> Self {
+        Self::new(DEFAULT_NOTES_PER_NPC)
+    }
+}
+
+impl PlayerMemory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            path: None,
+            notes: HashMap::new(),
+        }
+    }
+
+    /// Notes saved at `path`, which later notes are saved back to. A missing file is a
+    /// fresh start; an unreadable one is logged and left alone until the next save.
+    pub fn load_or_default(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let mut memory = match Self::load(&path, DEFAULT_NOTES_PER_NPC) {
+            Ok(memory) => memory,
+            Err(err) if err.kind() == ErrorKind::NotFound => Self::default(),
+            Err(err) => {
+                warn!(
+                    "Failed to load player memory from {} ({}). NPCs start without notes.",
+                    path.display(),
+                    err
+                );
+                Self::default()
+            }
+        };
+        memory.path = Some(path);
+        memory
+    }
+
+    /// Reads notes saved by `save`, trimming each NPC's list to `capacity`.
+    pub fn load(path: impl AsRef<Path>, capacity: usize) -> io::Result<Self> {
+        let data = fs::read_to_string(path)?;
+        let saved: BTreeMap<u64, Vec<PlayerMemoryNote>> = serde_json::from_str(&data)?;
+        let mut memory = Self::new(capacity);
+        for (npc, notes) in saved {
+            for note in notes {
+                memory.record(NpcId::new(npc), note);
+            }
+        }
+        Ok(memory)
+    }
+
+    /// Writes every note as JSON keyed by NPC id, oldest note first.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent)?;
+        }
+        let saved: BTreeMap<u64, &VecDeque<PlayerMemoryNote>> = self
+            .notes
+            .iter()
+            .map(|(npc, notes)| (npc.value(), notes))
+            .collect();
+        fs::write(path, serde_json::to_string_pretty(&saved)?)
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Adds a note `npc` keeps about the player.
+    pub fn record(&mut self, npc: NpcId, note: PlayerMemoryNote) {
+        let notes = self.notes.entry(npc).or_default();
+        notes.push_back(note);
+        while notes.len() > self.capacity {
+            notes.pop_front();
+        }
+    }
+
+    /// `npc`'s notes about the player, oldest first.
+    pub fn notes(&self, npc: NpcId) -> impl DoubleEndedIterator<Item = &PlayerMemoryNote> {
+        self.notes.get(&npc).into_iter().flatten()
+    }
+
+    /// Adds the speaker's notes as context when an NPC addresses the player; other
+    /// requests are left untouched.
+    pub fn attach_to(&self, request: &mut DialogueRequest) {
+        if request.speaker.is_player() || !request.target.is_some_and(|target| target.is_player()) {
+            return;
+        }
+        request
+            .context
+            .events
+            .extend(
+                self.notes(request.speaker)
+                    .map(|note| DialogueContextEvent::Custom {
+                        text: format!("{} (day {}): {}", NOTE_CONTEXT_PREFIX, note.day, note.text),
+                    }),
+            );
+    }
+}
+
+/// Start stamps of running player conversations and the summaries still being written.
+#[derive(Resource, Default)]
+pub struct PendingPlayerSummaries {
+    /// NPC -> (day, time of day) the conversation started at.
+    started: HashMap<NpcId, (u64, f32)>,
+    tasks: Vec<Task<(NpcId, PlayerMemoryNote)>>,
+}
+
+/// "Name: line" per transcript entry, with the player under its display name.
+pub fn format_summary_transcript<'a>(
+    entries: impl IntoIterator<Item = &'a TranscriptEntry>,
+    npc_name: &str,
+) -> String {
+    entries
+        .into_iter()
+        .map(|entry| {
+            let speaker = if entry.speaker.is_player() {
+                PLAYER_DISPLAY_NAME
+            } else {
+                npc_name
+            };
+            format!("{}: {}", speaker, entry.text)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Summarizes each player conversation once it ends, on the async compute pool. Only the
+/// lines said since the conversation started count, and conversations the player never
+/// replied in leave no note. Live summaries go through the default broker and are charged
+/// to `DailyApiBudget` as one ambient request; once it is spent the transcript is
+/// truncated instead.
+#[allow(clippy::too_many_arguments)]
+pub fn summarize_player_conversations(
+    mut events: MessageReader<PlayerInteractionEvent>,
+    mut pending: ResMut<PendingPlayerSummaries>,
+    transcripts: Res<TranscriptStore>,
+    clock: Res<WorldClock>,
+    router: Res<DialogueProviderRouter>,
+    mut budget: Option<ResMut<DailyApiBudget>>,
+    npc_index: Res<NpcIndex>,
+    identities: Query<&Identity>,
+) {
+    for event in events.read() {
+        match *event {
+            PlayerInteractionEvent::Started { npc, .. } => {
+                pending
+                    .started
+                    .insert(npc, (clock.day_count(), clock.time_of_day()));
+            }
+            PlayerInteractionEvent::ConversationEnded { npc, turns, .. } => {
+                let Some(started) = pending.started.remove(&npc) else {
+                    continue;
+                };
+                if turns == 0 {
+                    continue;
+                }
+                let npc_name = Identity::name_of(&npc_index, &identities, npc);
+                let transcript = format_summary_transcript(
+                    transcripts
+                        .entries(npc, NpcId::player())
+                        .filter(|entry| (entry.day, entry.time_of_day) >= started),
+                    &npc_name,
+                );
+                if transcript.is_empty() {
+                    continue;
+                }
+
+                let broker = router.default_broker().clone();
+                let live = broker.connection_state() == DialogueConnectionState::Live
+                    && budget.as_deref_mut().is_none_or(|budget| {
+                        budget.try_spend(Local::now(), DialoguePriority::Ambient, 1)
+                    });
+                let day = clock.day_count();
+                let task = AsyncComputeTaskPool::get().spawn(async move {
+                    let text = if live {
+                        broker.summarize(&npc_name, &transcript)
+                    } else {
+                        truncated_summary(&transcript)
+                    };
+                    (npc, PlayerMemoryNote::new(day, text))
+                });
+                pending.tasks.push(task);
+            }
+            PlayerInteractionEvent::ResponseChosen { .. } => {}
+        }
+    }
+}
+
+/// Stores finished summaries and saves the memory file when one was added.
+pub fn collect_player_summaries(
+    mut pending: ResMut<PendingPlayerSummaries>,
+    mut memory: ResMut<PlayerMemory>,
+) {
+    let mut added = false;
+    pending
+        .tasks
+        .retain_mut(|task| match block_on(poll_once(task)) {
+            Some((npc, note)) => {
+                memory.record(npc, note);
+                added = true;
+                false
+            }
+            None => true,
+        });
+    if !added {
+        return;
+    }
+    if let Some(path) = memory.path() {
+        if let Err(err) = memory.save(path) {
+            warn!(
+                "Failed to save player memory to {}: {}",
+                path.display(),
+                err
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+    use crate::dialogue::{
+        broker::{DialogueBroker, LocalDialogueBroker},
+        types::{DialogueContext, DialogueTopicHint},
+    };
+
+    fn note_texts(memory: &PlayerMemory, npc: NpcId) -> Vec<&str> {
+        memory.notes(npc).map(|note| note.text.as_str()).collect()
+    }
+
+    fn context_texts(request: &DialogueRequest) -> Vec<&str> {
+        request
+            .context
+            .events
+            .iter()
+            .filter_map(|event| match event {
+                DialogueContextEvent::Custom { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn oldest_notes_are_evicted_per_npc() {
+        let alric = NpcId::new(1);
+        let bryn = NpcId::new(2);
+        let mut memory = PlayerMemory::new(2);
+        memory.record(alric, PlayerMemoryNote::new(1, "first"));
+        memory.record(alric, PlayerMemoryNote::new(2, "second"));
+        memory.record(bryn, PlayerMemoryNote::new(2, "other"));
+        memory.record(alric, PlayerMemoryNote::new(3, "third"));
+
+        assert_eq!(note_texts(&memory, alric), ["second", "third"]);
+        assert_eq!(note_texts(&memory, bryn), ["other"]);
+    }
+
+    #[test]
+    fn notes_survive_a_save_and_load() {
+        let dir = env::temp_dir().join(format!("thegame_player_memory_{}", std::process::id()));
+        let path = dir.join("player_memory.json");
+        let _ = fs::remove_dir_all(&dir);
+        let mut memory = PlayerMemory::default();
+        memory.record(
+            NpcId::new(7),
+            PlayerMemoryNote::new(2, "You sold the player bread."),
+        );
+        memory.record(
+            NpcId::new(7),
+            PlayerMemoryNote::new(4, "You warned about the harvest."),
+        );
+        memory.record(
+            NpcId::new(9),
+            PlayerMemoryNote::new(3, "You talked about the weather."),
+        );
+        memory.save(&path).unwrap();
+
+        let loaded = PlayerMemory::load(&path, 1).unwrap();
+        assert_eq!(
+            note_texts(&loaded, NpcId::new(7)),
+            ["You warned about the harvest."],
+            "capacity applies on load"
+        );
+        assert_eq!(
+            loaded.notes(NpcId::new(9)).collect::<Vec<_>>(),
+            [&PlayerMemoryNote::new(3, "You talked about the weather.")]
+        );
+
+        let fresh = PlayerMemory::load_or_default(dir.join("missing.json"));
+        assert_eq!(fresh.notes(NpcId::new(7)).count(), 0);
+        assert_eq!(fresh.path(), Some(dir.join("missing.json").as_path()));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn fallback_summary_truncates_the_transcript() {
+        let short = "Alric: Fresh bread!\nPlayer: I'll take one.";
+        assert_eq!(
+            LocalDialogueBroker.summarize("Alric", short),
+            "Alric: Fresh bread! Player: I'll take one."
+        );
+
+        let long = format!("Alric: {}", "the harvest is failing ".repeat(20));
+        let summary = LocalDialogueBroker.summarize("Alric", &long);
+        assert!(summary.starts_with("Alric: the harvest is failing"));
+        let kept = summary
+            .strip_suffix("...")
+            .expect("long summaries end in an ellipsis");
+        assert!(
+            long.starts_with(kept) && long[kept.len()..].starts_with(' '),
+            "cut at a word: {summary}"
+        );
+        assert!(summary.chars().count() <= 163);
+    }
+
+    #[test]
+    fn notes_attach_only_to_requests_addressed_to_the_player() {
+        let alric = NpcId::new(1);
+        let mut memory = PlayerMemory::default();
+        memory.record(
+            alric,
+            PlayerMemoryNote::new(3, "You told the player about the failing harvest."),
+        );
+        let request = |target: Option<NpcId>| {
+            DialogueRequest::new(
+                alric,
+                target,
+                "Greet them.",
+                DialogueTopicHint::Status,
+                DialogueContext::default(),
+            )
+        };
+
+        let mut to_player = request(Some(NpcId::player()));
+        memory.attach_to(&mut to_player);
+        assert_eq!(
+            context_texts(&to_player),
+            ["Earlier with the player (day 3): You told the player about the failing harvest."]
+        );
+
+        let mut to_npc = request(Some(NpcId::new(2)));
+        memory.attach_to(&mut to_npc);
+        let mut ambient = request(None);
+        memory.attach_to(&mut ambient);
+        assert!(to_npc.context.events.is_empty());
+        assert!(ambient.context.events.is_empty());
+    }
+}
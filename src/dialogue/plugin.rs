@@ -1,47 +1,116 @@
 //! Dialogue plugin wiring queue resources, systems, instrumentation, and debug tooling.
 use bevy::prelude::*;
 
+#[cfg(feature = "scripting")]
+use super::scripting::{ScriptedContextProviders, SCRIPT_DIR};
 use super::{
-    broker::{DialogueBroker, OpenAiDialogueBroker},
+    budget::{refresh_daily_api_budget, ApiBudgetLimits, DailyApiBudget},
+    chatter::{apply_pair_chatter_window, ChatterBudgets, PairChatterCooldown},
+    chronicle::{
+        prune_village_chronicle, reload_chronicle_config, ChronicleConfig, ChronicleDistillers,
+        VillageChronicle, CONFIG_PATH as DIALOGUE_CONFIG_PATH,
+    },
+    dead_letter::{retry_dead_letters, DialogueDeadLetterStore},
     errors::DialogueErrorKind,
-    events::{DialogueRequestFailedEvent, DialogueRequestedEvent, DialogueResponseEvent},
+    events::{
+        ApiBudgetExhaustedEvent, DialogueRequestFailedEvent, DialogueRequestedEvent,
+        DialogueResponseEvent, PlayerInteractionEvent,
+    },
+    pending_speech::track_pending_speech,
+    player_memory::{
+        collect_player_summaries, summarize_player_conversations, PendingPlayerSummaries,
+        PlayerMemory, DEFAULT_PLAYER_MEMORY_PATH,
+    },
+    probe::{handle_dialogue_debug_probe, DialogueProbeState},
+    prompts::{hot_reload_prompt_templates, load_default_prompt_templates, PromptTemplateWatcher},
     queue::{
-        advance_dialogue_queue_timers, poll_dialogue_tasks, run_dialogue_request_queue,
-        ActiveDialogueBroker, DialogueRateLimitConfig, DialogueRateLimitState,
-        DialogueRequestQueue, PendingDialogueTasks,
+        advance_dialogue_queue_timers, announce_queued_dialogue_requests,
+        forget_despawned_speakers, poll_dialogue_tasks, run_dialogue_request_queue,
+        DialogueQueueDump, DialogueRateLimitConfig, DialogueRateLimitState, DialogueRequestQueue,
+        DialogueSpeakerProfiles, PendingDialogueTasks,
+    },
+    router::{cycle_dialogue_provider, DialogueProviderRouter},
+    simulation::FallbackSimulation,
+    status::{
+        clear_misconfigured_providers_on_reload, flag_misconfigured_providers,
+        DialogueBrokerStatus, DialogueConnectionState,
     },
-    status::{DialogueBrokerStatus, DialogueConnectionState},
     telemetry::{
-        flush_dialogue_telemetry_log, record_dialogue_telemetry, DialogueTelemetry,
-        DialogueTelemetryEvent, DialogueTelemetryLog, DialogueTelemetryRecord,
+        flush_dialogue_telemetry_log, flush_dialogue_telemetry_on_exit, record_dialogue_telemetry,
+        reload_telemetry_flush_policy, DialogueTelemetry, DialogueTelemetryEvent,
+        DialogueTelemetryLog, DialogueTelemetryRecord, TelemetryFlushPolicy,
     },
-    types::{DialogueContext, DialogueRequest, DialogueTopicHint},
+    trace::{handle_dialogue_trace_dump, trace_queued_dialogue_requests, DialogueRequestTrace},
+    transcripts::{record_dialogue_transcripts, TranscriptStore},
+    validation::DialogueValidationConfig,
+};
+#[cfg(feature = "profiling")]
+use crate::core::profiling::time_system;
+use crate::core::{
+    config::report_config_result,
+    input::{ActionInput, InputAction, InputBindings},
+    schedule::FramePhase,
 };
-use crate::npc::components::Identity;
 
 const FALLBACK_DIALOGUE_TARGET: &str = "player";
-const DEBUG_DIALOGUE_PROBE_KEY: KeyCode = KeyCode::F7;
-const DEBUG_DIALOGUE_PROBE_SUMMARY: &str = "Developer-triggered dialogue probe.";
 
+/// Dialogue requests, brokers, the queue, and their telemetry.
 pub struct DialoguePlugin;
 
 impl Plugin for DialoguePlugin {
     fn build(&self, app: &mut App) {
-        let broker = OpenAiDialogueBroker::new();
-        let broker_status =
-            DialogueBrokerStatus::new(broker.provider_kind(), broker.connection_state());
+        let prompt_templates = load_default_prompt_templates(app.world_mut());
+        let router = DialogueProviderRouter::from_env(prompt_templates.clone());
+        let default_broker = router.default_broker();
+        let broker_status = DialogueBrokerStatus::new(
+            default_broker.provider_kind(),
+            default_broker.connection_state(),
+        );
+        let chronicle_config = report_config_result(
+            app.world_mut(),
+            DIALOGUE_CONFIG_PATH,
+            ChronicleConfig::load(),
+            ChronicleConfig::default,
+        );
+        let flush_policy = report_config_result(
+            app.world_mut(),
+            DIALOGUE_CONFIG_PATH,
+            TelemetryFlushPolicy::load(),
+            TelemetryFlushPolicy::default,
+        );
+        let mut telemetry_log = DialogueTelemetryLog::default();
+        telemetry_log.set_policy(flush_policy);
 
-        app.init_resource::<DialogueRateLimitConfig>()
+        app.insert_resource(DialogueRateLimitConfig::from_env())
+            .init_resource::<DialogueValidationConfig>()
             .init_resource::<DialogueRateLimitState>()
             .init_resource::<DialogueRequestQueue>()
+            .init_resource::<DialogueSpeakerProfiles>()
             .init_resource::<PendingDialogueTasks>()
             .init_resource::<DialogueTelemetry>()
-            .init_resource::<DialogueTelemetryLog>()
+            .insert_resource(telemetry_log)
+            .init_resource::<PairChatterCooldown>()
+            .init_resource::<ChatterBudgets>()
+            .init_resource::<TranscriptStore>()
+            .init_resource::<PendingPlayerSummaries>()
+            .init_resource::<PromptTemplateWatcher>()
+            .init_resource::<DialogueProbeState>()
+            .init_resource::<DialogueDeadLetterStore>()
+            .init_resource::<DialogueRequestTrace>()
+            .init_resource::<VillageChronicle>()
+            .insert_resource(chronicle_config)
+            .init_resource::<ChronicleDistillers>()
+            .insert_resource(prompt_templates)
             .insert_resource(broker_status)
-            .insert_resource(ActiveDialogueBroker::new(Box::new(broker)))
+            .insert_resource(DailyApiBudget::new(ApiBudgetLimits::from_env()))
+            .insert_resource(FallbackSimulation::from_env())
+            .insert_resource(router)
+            .insert_resource(PlayerMemory::load_or_default(DEFAULT_PLAYER_MEMORY_PATH))
             .add_message::<DialogueRequestedEvent>()
             .add_message::<DialogueResponseEvent>()
             .add_message::<DialogueRequestFailedEvent>()
+            .add_message::<ApiBudgetExhaustedEvent>()
+            .add_message::<PlayerInteractionEvent>()
             .add_systems(
                 Startup,
                 (log_dialogue_provider, record_dialogue_broker_status),
@@ -50,59 +119,108 @@ impl Plugin for DialoguePlugin {
                 Update,
                 (
                     handle_dialogue_debug_probe,
+                    handle_dialogue_queue_dump,
+                    handle_dialogue_trace_dump,
+                    cycle_dialogue_provider,
+                    retry_dead_letters,
+                    hot_reload_prompt_templates,
+                    announce_queued_dialogue_requests,
+                    trace_queued_dialogue_requests,
                     advance_dialogue_queue_timers,
+                    forget_despawned_speakers,
+                    clear_misconfigured_providers_on_reload,
+                    reload_chronicle_config,
+                    apply_pair_chatter_window,
+                    prune_village_chronicle,
                     run_dialogue_request_queue,
+                    refresh_daily_api_budget,
+                )
+                    .chain()
+                    .in_set(FramePhase::DialogueDispatch),
+            )
+            .add_systems(
+                Update,
+                (
                     poll_dialogue_tasks, // Poll background tasks for completed requests
+                    flag_misconfigured_providers,
+                    track_pending_speech,
                     record_dialogue_telemetry,
+                    record_dialogue_transcripts,
+                    summarize_player_conversations,
+                    collect_player_summaries,
+                    reload_telemetry_flush_policy,
                     flush_dialogue_telemetry_log,
                     log_dialogue_events,
                 )
-                    .chain(),
+                    .chain()
+                    .in_set(FramePhase::DialoguePoll),
+            )
+            .add_systems(Last, flush_dialogue_telemetry_on_exit);
+
+        #[cfg(feature = "profiling")]
+        {
+            time_system(
+                app,
+                Update,
+                "run_dialogue_request_queue",
+                run_dialogue_request_queue,
+            );
+            time_system(app, Update, "poll_dialogue_tasks", poll_dialogue_tasks);
+        }
+
+        #[cfg(feature = "scripting")]
+        app.insert_resource(ScriptedContextProviders::load_dir(SCRIPT_DIR));
+
+        #[cfg(feature = "chaos")]
+        {
+            use crate::core::chaos::ChaosAppExt;
+            app.register_chaos_fault(
+                "fail_dialogue_call",
+                crate::dialogue::chaos::fail_next_dialogue_call,
+            )
+            .register_chaos_fault(
+                "drop_dialogue_request",
+                crate::dialogue::chaos::drop_random_pending_request,
             );
+        }
     }
 }
 
-fn handle_dialogue_debug_probe(
-    keyboard: Res<ButtonInput<KeyCode>>,
-    mut queue: ResMut<DialogueRequestQueue>,
-    status: Res<DialogueBrokerStatus>,
-    identities: Query<&Identity>,
+/// Logs the queue, in-flight tasks, and cooldowns as a table and records a telemetry dump.
+#[allow(clippy::too_many_arguments)]
+fn handle_dialogue_queue_dump(
+    input: ActionInput,
+    time: Res<Time>,
+    queue: Res<DialogueRequestQueue>,
+    tasks: Res<PendingDialogueTasks>,
+    limits: Res<DialogueRateLimitState>,
+    budgets: Res<ChatterBudgets>,
+    mut telemetry: ResMut<DialogueTelemetry>,
+    mut log: ResMut<DialogueTelemetryLog>,
 ) {
-    if !keyboard.just_pressed(DEBUG_DIALOGUE_PROBE_KEY) {
+    if !input.just_pressed(InputAction::DialogueQueueDump) {
         return;
     }
 
-    let Some(identity) = identities.iter().next() else {
-        warn!("Dialogue probe skipped: no NPC identities available to speak.");
-        return;
-    };
+    let dump = DialogueQueueDump::capture(&queue, &tasks, &limits);
+    info!("{}", dump.format_table());
+    if let Some(day) = budgets.day() {
+        info!("Chatter budgets left on day {}: {}", day, budgets.summary());
+    }
 
-    let prompt = format!(
-        "{} runs a quick dialogue probe for debugging.",
-        identity.display_name
-    );
-    let context = DialogueContext {
-        summary: Some(DEBUG_DIALOGUE_PROBE_SUMMARY.to_string()),
-        events: Vec::new(),
+    let record = DialogueTelemetryRecord {
+        occurred_at_seconds: time.elapsed_secs_f64(),
+        event: DialogueTelemetryEvent::QueueDump(dump),
     };
-    let request = DialogueRequest::new(
-        identity.id,
-        None,
-        prompt,
-        DialogueTopicHint::Status,
-        context,
-    );
-    let request_id = queue.enqueue(request);
-
-    info!(
-        "Queued dialogue probe {} using provider {} ({})",
-        request_id.value(),
-        status.provider(),
-        status.connection_label()
-    );
+    log.push(&record);
+    telemetry.push(record);
 }
 
-fn log_dialogue_provider(status: Res<DialogueBrokerStatus>) {
+fn log_dialogue_provider(
+    status: Res<DialogueBrokerStatus>,
+    simulation: Res<FallbackSimulation>,
+    bindings: Res<InputBindings>,
+) {
     match status.connection_state() {
         DialogueConnectionState::Live => {
             info!(
@@ -117,10 +235,36 @@ fn log_dialogue_provider(status: Res<DialogueBrokerStatus>) {
                 status.provider()
             );
         }
+        DialogueConnectionState::Misconfigured => {
+            warn!(
+                "Dialogue provider {} is misconfigured; using fallback replies.",
+                status.provider()
+            );
+        }
+    }
+    if simulation.is_enabled() {
+        info!(
+            "Fallback replies simulate {}-{} ms of latency and fail {}% of the time",
+            simulation.latency.min_ms, simulation.latency.max_ms, simulation.failure_percent
+        );
     }
     info!(
-        "Press {:?} to enqueue a dialogue probe request for quick verification.",
-        DEBUG_DIALOGUE_PROBE_KEY
+        "Press {probe} to pick the dialogue probe speaker, {send}+{probe} to enqueue a probe \
+         request, and {topic}+{probe} to change its topic.",
+        probe = bindings.binding(InputAction::DialogueProbe),
+        send = bindings.binding(InputAction::DialogueProbeSendModifier),
+        topic = bindings.binding(InputAction::DialogueProbeTopicModifier),
+    );
+    info!(
+        "Press {} to dump the dialogue queue and rate-limit state, {} to log the latest \
+         request's trace ({}+{} for the one before), {} to retry requests that failed for \
+         good, {} to switch the default dialogue provider.",
+        bindings.binding(InputAction::DialogueQueueDump),
+        bindings.binding(InputAction::DialogueTraceDump),
+        bindings.binding(InputAction::DialogueTraceOlderModifier),
+        bindings.binding(InputAction::DialogueTraceDump),
+        bindings.binding(InputAction::RetryDeadLetters),
+        bindings.binding(InputAction::CycleDialogueProvider)
     );
 }
 
@@ -137,11 +281,7 @@ fn log_dialogue_events(
 
         info!(
             "Dialogue response [{} | {} -> {} | {}]: {}",
-            response.request_id.value(),
-            response.speaker,
-            target,
-            response.provider,
-            response.content
+            response.request_id, response.speaker, target, response.provider, response.content
         );
     }
 
@@ -152,22 +292,26 @@ fn log_dialogue_events(
                 retry_after_seconds,
             } => {
                 warn!(
-                    "Dialogue request {} rate limited for {:.2}s",
-                    error.request_id.value(),
-                    retry_after_seconds
+                    "Dialogue {} rate limited for {:.2}s",
+                    error.request_id, retry_after_seconds
                 );
             }
-            DialogueErrorKind::ProviderFailure { message } => {
+            DialogueErrorKind::ProviderFailure { message, class } => {
                 warn!(
-                    "Dialogue provider failure ({}): {}",
-                    error.provider, message
+                    "Dialogue provider failure ({} | {} | {}): {}",
+                    error.request_id,
+                    error.provider,
+                    class.label(),
+                    message
                 );
             }
             DialogueErrorKind::ContextMissing { missing } => {
+                warn!("Dialogue {} missing context: {}", error.request_id, missing);
+            }
+            DialogueErrorKind::InvalidRequest { reason } => {
                 warn!(
-                    "Dialogue request {} missing context: {}",
-                    error.request_id.value(),
-                    missing
+                    "Dialogue {} rejected before dispatch: {}",
+                    error.request_id, reason
                 );
             }
         }
@@ -0,0 +1,566 @@
+//! Prompt templates loaded from disk with hot-reload support for dialogue tuning.
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::SystemTime,
+};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use super::types::DialogueTopicHint;
+use crate::core::config::{report_config_result, ConfigDiagnostics, ConfigReloadRequested};
+
+const DEFAULT_PROMPT_TEMPLATE_PATH: &str = "assets/prompts/openai.toml";
+const DEFAULT_RELOAD_INTERVAL_SECONDS: f32 = 3.0;
+const DEFAULT_SYSTEM_PROMPT: &str = "You are a medieval villager in a life-simulation game. Respond briefly (1-3 sentences), stay in character, and reference only the supplied context. If information is missing, acknowledge the gap.";
+const DEFAULT_USER_TEMPLATE: &str = "Speaker: {speaker}
+Target: {target}
+Topic: {topic}
+Prompt: {prompt}
+{summary}
+{events}
+Respond as the speaker, addressing the target naturally.";
+const DEFAULT_STATUS_SYSTEM_PROMPT: &str =
+    "This is casual small talk: keep it light and friendly, and do not invent trades or plans.";
+const DEFAULT_TRADE_SYSTEM_PROMPT: &str = "This line is about a trade: name the goods and quantities involved and who gave or received them.";
+const DEFAULT_SCHEDULE_SYSTEM_PROMPT: &str =
+    "This is a quick schedule brief: say what the speaker is doing next in one short sentence.";
+const DEFAULT_SCHEDULE_MAX_OUTPUT_TOKENS: u16 = 60;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct RawPromptTemplates {
+    system_prompt: Option<String>,
+    #[serde(default)]
+    user_templates: RawUserTemplates,
+    #[serde(default)]
+    topic_system_prompts: RawTopicSystemPrompts,
+    #[serde(default)]
+    max_output_tokens: RawTopicTokenLimits,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct RawUserTemplates {
+    default: Option<String>,
+    status: Option<String>,
+    trade: Option<String>,
+    schedule: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct RawTopicSystemPrompts {
+    status: Option<String>,
+    trade: Option<String>,
+    schedule: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct RawTopicTokenLimits {
+    status: Option<u16>,
+    trade: Option<u16>,
+    schedule: Option<u16>,
+}
+
+/// Values substituted into the named placeholders of a user-message template.
+#[derive(Debug, Clone, Default)]
+pub struct PromptVariables<'a> {
+    /// `{speaker}`: the speaker's name.
+    pub speaker: &'a str,
+    /// `{target}`: who the line is addressed to.
+    pub target: &'a str,
+    /// `{topic}`: the topic label.
+    pub topic: &'a str,
+    /// `{prompt}`: the request's instruction.
+    pub prompt: &'a str,
+    /// `{summary}`: the context summary, possibly empty.
+    pub summary: &'a str,
+    /// `{events}`: the rendered context events.
+    pub events: &'a str,
+}
+
+impl PromptVariables<'_> {
+    /// Value for the placeholder `{name}`, or `None` when `name` is not one.
+    fn value(&self, name: &str) -> Option<&str> {
+        match name {
+            "speaker" => Some(self.speaker),
+            "target" => Some(self.target),
+            "topic" => Some(self.topic),
+            "prompt" => Some(self.prompt),
+            "summary" => Some(self.summary),
+            "events" => Some(self.events),
+            _ => None,
+        }
+    }
+
+    /// Fills the placeholders of `template` in one pass over it, so braces inside a
+    /// substituted value are never read as placeholders. Unknown names are left as written.
+    fn fill(&self, template: &str) -> String {
+        let mut rendered = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(open) = rest.find('{') {
+            rendered.push_str(&rest[..open]);
+            let after = &rest[open + 1..];
+            let value = after
+                .find('}')
+                .and_then(|close| Some((self.value(&after[..close])?, close)));
+            match value {
+                Some((value, close)) => {
+                    rendered.push_str(value);
+                    rest = &after[close + 1..];
+                }
+                None => {
+                    rendered.push('{');
+                    rest = after;
+                }
+            }
+        }
+        rendered.push_str(rest);
+        rendered
+    }
+}
+
+/// System prompt plus per-topic system guidance, user-message templates, and token caps.
+#[derive(Debug, Clone)]
+pub struct PromptTemplates {
+    system_prompt: String,
+    topic_system_prompts: HashMap<DialogueTopicHint, String>,
+    default_user_template: String,
+    topic_templates: HashMap<DialogueTopicHint, String>,
+    topic_max_output_tokens: HashMap<DialogueTopicHint, u16>,
+}
+
+impl PromptTemplates {
+    /// Loads templates from a TOML file, falling back to compiled-in defaults per field.
+    pub fn load_from_file(path: impl AsRef<Path>) -> Result<Self, String> {
+        let data = fs::read_to_string(path.as_ref())
+            .map_err(|err| format!("unable to read file: {err}"))?;
+        let raw: RawPromptTemplates =
+            toml::from_str(&data).map_err(|err| format!("invalid prompt templates: {err}"))?;
+        Ok(raw.into())
+    }
+
+    /// Loads templates from `path`, logging and returning the defaults on failure.
+    pub fn load_or_default(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        match Self::load_from_file(path) {
+            Ok(templates) => templates,
+            Err(err) => {
+                warn!(
+                    "Failed to load prompt templates from {} ({}). Using built-in prompts.",
+                    path.display(),
+                    err
+                );
+                Self::default()
+            }
+        }
+    }
+
+    /// Base system prompt, without topic guidance.
+    pub fn system_prompt(&self) -> &str {
+        &self.system_prompt
+    }
+
+    /// Base system prompt followed by the topic's guidance, separated by a blank line.
+    pub fn system_prompt_for(&self, topic: DialogueTopicHint) -> String {
+        match self.topic_system_prompts.get(&topic) {
+            Some(guidance) => format!("{}\n\n{}", self.system_prompt(), guidance),
+            None => self.system_prompt().to_string(),
+        }
+    }
+
+    /// Output token cap for a topic; `None` defers to the broker's configured limit.
+    pub fn max_output_tokens_for(&self, topic: DialogueTopicHint) -> Option<u16> {
+        self.topic_max_output_tokens.get(&topic).copied()
+    }
+
+    /// Renders the user message for a topic, dropping lines left empty by blank placeholders.
+    pub fn render_user_message(
+        &self,
+        topic: DialogueTopicHint,
+        variables: &PromptVariables<'_>,
+    ) -> String {
+        let template = self
+            .topic_templates
+            .get(&topic)
+            .unwrap_or(&self.default_user_template);
+
+        variables
+            .fill(template)
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl Default for PromptTemplates {
+    fn default() -> Self {
+        RawPromptTemplates::default().into()
+    }
+}
+
+impl From<RawPromptTemplates> for PromptTemplates {
+    fn from(value: RawPromptTemplates) -> Self {
+        let non_empty = |text: Option<String>| text.filter(|value| !value.trim().is_empty());
+
+        let mut topic_templates = HashMap::new();
+        let users = value.user_templates;
+        for (topic, template) in [
+            (DialogueTopicHint::Status, users.status),
+            (DialogueTopicHint::Trade, users.trade),
+            (DialogueTopicHint::Schedule, users.schedule),
+        ] {
+            if let Some(template) = non_empty(template) {
+                topic_templates.insert(topic, template);
+            }
+        }
+
+        let prompts = value.topic_system_prompts;
+        let topic_system_prompts = [
+            (
+                DialogueTopicHint::Status,
+                prompts.status,
+                DEFAULT_STATUS_SYSTEM_PROMPT,
+            ),
+            (
+                DialogueTopicHint::Trade,
+                prompts.trade,
+                DEFAULT_TRADE_SYSTEM_PROMPT,
+            ),
+            (
+                DialogueTopicHint::Schedule,
+                prompts.schedule,
+                DEFAULT_SCHEDULE_SYSTEM_PROMPT,
+            ),
+        ]
+        .into_iter()
+        .map(|(topic, prompt, default)| {
+            let prompt = non_empty(prompt)
+                .map(|prompt| prompt.trim().to_string())
+                .unwrap_or_else(|| default.to_string());
+            (topic, prompt)
+        })
+        .collect();
+
+        let limits = value.max_output_tokens;
+        let topic_max_output_tokens = [
+            (DialogueTopicHint::Status, limits.status),
+            (DialogueTopicHint::Trade, limits.trade),
+            (
+                DialogueTopicHint::Schedule,
+                limits.schedule.or(Some(DEFAULT_SCHEDULE_MAX_OUTPUT_TOKENS)),
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(topic, limit)| limit.filter(|limit| *limit > 0).map(|limit| (topic, limit)))
+        .collect();
+
+        Self {
+            system_prompt: non_empty(value.system_prompt)
+                .map(|prompt| prompt.trim().to_string())
+                .unwrap_or_else(|| DEFAULT_SYSTEM_PROMPT.to_string()),
+            topic_system_prompts,
+            default_user_template: non_empty(users.default)
+                .unwrap_or_else(|| DEFAULT_USER_TEMPLATE.to_string()),
+            topic_templates,
+            topic_max_output_tokens,
+        }
+    }
+}
+
+/// Thread-safe handle shared between the Bevy world and background broker tasks.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct SharedPromptTemplates {
+    inner: Arc<RwLock<PromptTemplates>>,
+}
+
+impl SharedPromptTemplates {
+    pub fn new(templates: PromptTemplates) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(templates)),
+        }
+    }
+
+    /// Returns a copy of the current templates for rendering a single request.
+    pub fn snapshot(&self) -> PromptTemplates {
+        match self.inner.read() {
+            Ok(guard) => guard.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    pub fn replace(&self, templates: PromptTemplates) {
+        match self.inner.write() {
+            Ok(mut guard) => *guard = templates,
+            Err(poisoned) => *poisoned.into_inner() = templates,
+        }
+    }
+}
+
+/// Polls the template file's modification time and swaps templates when it changes.
+#[derive(Resource, Debug)]
+pub struct PromptTemplateWatcher {
+    path: PathBuf,
+    last_modified: Option<SystemTime>,
+    timer: Timer,
+}
+
+impl PromptTemplateWatcher {
+    pub fn new(path: impl Into<PathBuf>, interval_seconds: f32) -> Self {
+        let path = path.into();
+        let last_modified = modified_time(&path);
+        Self {
+            path,
+            last_modified,
+            timer: Timer::from_seconds(interval_seconds.max(0.1), TimerMode::Repeating),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Reloads templates into `shared` when the file changed. Returns true on reload.
+    pub fn poll(&mut self, shared: &SharedPromptTemplates) -> bool {
+        let current = modified_time(&self.path);
+        if current.is_none() || current == self.last_modified {
+            return false;
+        }
+
+        self.last_modified = current;
+        match PromptTemplates::load_from_file(&self.path) {
+            Ok(templates) => {
+                shared.replace(templates);
+                true
+            }
+            Err(err) => {
+                warn!(
+                    "Prompt template reload from {} failed ({}); keeping previous templates.",
+                    self.path.display(),
+                    err
+                );
+                false
+            }
+        }
+    }
+}
+
+impl Default for PromptTemplateWatcher {
+    fn default() -> Self {
+        Self::new(
+            DEFAULT_PROMPT_TEMPLATE_PATH,
+            DEFAULT_RELOAD_INTERVAL_SECONDS,
+        )
+    }
+}
+
+/// Loads templates from the default path for broker construction, recording the result in
+/// `ConfigDiagnostics`.
+pub fn load_default_prompt_templates(world: &mut World) -> SharedPromptTemplates {
+    SharedPromptTemplates::new(report_config_result(
+        world,
+        DEFAULT_PROMPT_TEMPLATE_PATH,
+        PromptTemplates::load_from_file(DEFAULT_PROMPT_TEMPLATE_PATH),
+        PromptTemplates::default,
+    ))
+}
+
+/// Periodically checks the template file and hot-swaps prompts for subsequent requests.
+/// Explicit reload requests skip the timer.
+pub fn hot_reload_prompt_templates(
+    time: Res<Time>,
+    mut requests: MessageReader<ConfigReloadRequested>,
+    mut diagnostics: ResMut<ConfigDiagnostics>,
+    mut watcher: ResMut<PromptTemplateWatcher>,
+    shared: Res<SharedPromptTemplates>,
+) {
+    let path = watcher.path().to_string_lossy().into_owned();
+    if requests.read().any(|request| request.is_for(&path)) {
+        if let Some(templates) =
+            diagnostics.report_reload(&path, PromptTemplates::load_from_file(&path))
+        {
+            shared.replace(templates);
+        }
+        return;
+    }
+
+    if !watcher.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    if watcher.poll(&shared) {
+        diagnostics.report_reload(&path, Ok(()));
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, time::Duration};
+
+    fn temp_path(label: &str) -> PathBuf {
+        let unique_suffix = SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        env::temp_dir().join(format!("prompt_templates_{label}_{unique_suffix}.toml"))
+    }
+
+    fn variables() -> PromptVariables<'static> {
+        PromptVariables {
+            speaker: "Alric",
+            target: "Bryn",
+            topic: "trade",
+            prompt: "Discuss grain",
+            summary: "",
+            events: "Trade event: Day 1 exchanged 1 grain crate",
+        }
+    }
+
+    #[test]
+    fn renders_named_placeholders() {
+        let templates = PromptTemplates::default();
+        let rendered = templates.render_user_message(DialogueTopicHint::Trade, &variables());
+
+        assert!(rendered.starts_with("Speaker: Alric\nTarget: Bryn\nTopic: trade"));
+        assert!(rendered.contains("Prompt: Discuss grain"));
+        assert!(rendered.contains("Trade event: Day 1"));
+        assert!(!rendered.contains("\n\n"), "blank summary line should drop");
+        assert!(!rendered.contains('{'));
+    }
+
+    #[test]
+    fn substituted_values_are_not_rendered_again() {
+        let variables = PromptVariables {
+            speaker: "{target}",
+            prompt: "say {events} {unknown",
+            events: "market {day}",
+            ..variables()
+        };
+        assert_eq!(
+            variables.fill("{speaker} -> {target}: {prompt} | {events} {weather}"),
+            "{target} -> Bryn: say {events} {unknown | market {day} {weather}"
+        );
+    }
+
+    #[test]
+    fn missing_file_falls_back_to_defaults() {
+        let templates = PromptTemplates::load_or_default(temp_path("missing"));
+        assert_eq!(templates.system_prompt(), DEFAULT_SYSTEM_PROMPT);
+        assert!(templates.topic_templates.is_empty());
+    }
+
+    #[test]
+    fn topic_template_overrides_default() {
+        let raw: RawPromptTemplates = toml::from_str(
+            r#"
+            system_prompt = "Be terse."
+            [user_templates]
+            trade = "{speaker} haggles with {target}: {prompt}"
+            "#,
+        )
+        .expect("templates should parse");
+        let templates = PromptTemplates::from(raw);
+
+        assert_eq!(templates.system_prompt(), "Be terse.");
+        assert_eq!(
+            templates.render_user_message(DialogueTopicHint::Trade, &variables()),
+            "Alric haggles with Bryn: Discuss grain"
+        );
+        assert!(templates
+            .render_user_message(DialogueTopicHint::Status, &variables())
+            .starts_with("Speaker: Alric"));
+    }
+
+    #[test]
+    fn system_prompt_appends_topic_guidance() {
+        let raw: RawPromptTemplates = toml::from_str(
+            r#"
+            system_prompt = "Be terse."
+            [topic_system_prompts]
+            trade = "Quote prices."
+            [max_output_tokens]
+            trade = 40
+            "#,
+        )
+        .expect("templates should parse");
+        let templates = PromptTemplates::from(raw);
+
+        assert_eq!(
+            templates.system_prompt_for(DialogueTopicHint::Trade),
+            "Be terse.\n\nQuote prices."
+        );
+        assert_eq!(
+            templates.system_prompt_for(DialogueTopicHint::Status),
+            format!("Be terse.\n\n{DEFAULT_STATUS_SYSTEM_PROMPT}")
+        );
+        assert_eq!(
+            templates.max_output_tokens_for(DialogueTopicHint::Trade),
+            Some(40)
+        );
+        assert_eq!(
+            templates.max_output_tokens_for(DialogueTopicHint::Status),
+            None
+        );
+        assert_eq!(
+            templates.max_output_tokens_for(DialogueTopicHint::Schedule),
+            Some(DEFAULT_SCHEDULE_MAX_OUTPUT_TOKENS)
+        );
+    }
+
+    #[test]
+    fn omitted_topics_use_built_in_guidance() {
+        let templates = PromptTemplates::default();
+        for (topic, guidance) in [
+            (DialogueTopicHint::Status, DEFAULT_STATUS_SYSTEM_PROMPT),
+            (DialogueTopicHint::Trade, DEFAULT_TRADE_SYSTEM_PROMPT),
+            (DialogueTopicHint::Schedule, DEFAULT_SCHEDULE_SYSTEM_PROMPT),
+        ] {
+            let prompt = templates.system_prompt_for(topic);
+            assert!(prompt.starts_with(DEFAULT_SYSTEM_PROMPT));
+            assert!(prompt.ends_with(guidance), "{topic:?} guidance");
+        }
+    }
+
+    #[test]
+    fn reload_changes_subsequent_output() {
+        let path = temp_path("reload");
+        fs::write(&path, "[user_templates]\ndefault = \"first {speaker}\"\n").unwrap();
+
+        let shared = SharedPromptTemplates::new(PromptTemplates::load_or_default(&path));
+        let mut watcher = PromptTemplateWatcher::new(&path, 1.0);
+        assert!(!watcher.poll(&shared), "unchanged file should not reload");
+        assert_eq!(
+            shared
+                .snapshot()
+                .render_user_message(DialogueTopicHint::Status, &variables()),
+            "first Alric"
+        );
+
+        fs::write(&path, "[user_templates]\ndefault = \"second {speaker}\"\n").unwrap();
+        let bumped = SystemTime::now() + Duration::from_secs(5);
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(bumped))
+            .unwrap();
+
+        assert!(watcher.poll(&shared));
+        assert_eq!(
+            shared
+                .snapshot()
+                .render_user_message(DialogueTopicHint::Status, &variables()),
+            "second Alric"
+        );
+
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,639 @@
+//! Dispatching queued requests: routing each to its provider, rejecting invalid ones,
+//! batching ambient chatter, charging the daily budget, and spawning the background task.
+use std::collections::HashSet;
+
+use bevy::{ecs::system::SystemParam, prelude::*, tasks::AsyncComputeTaskPool};
+use chrono::Local;
+
+use super::{
+    ActiveDialogueBroker, DialogueRateLimitState, DialogueRequestQueue, DialogueSpeakerProfiles,
+    InFlightDialogueTask, InFlightRequestView, PendingDialogueTasks, QueuedDialogueRequest,
+};
+#[cfg(feature = "scripting")]
+use crate::dialogue::scripting::ScriptedContextProviders;
+use crate::dialogue::{
+    broker::DialogueProviderKind,
+    budget::DailyApiBudget,
+    chronicle::{ChronicleConfig, VillageChronicle},
+    errors::{DialogueError, DialogueErrorKind},
+    events::DialogueRequestFailedEvent,
+    player_memory::PlayerMemory,
+    router::{DialogueProviderRouter, SpeakerPins},
+    simulation::FallbackSimulation,
+    status::{DialogueBrokerStatus, DialogueConnectionState},
+    trace::{RequestTracing, TracePhase},
+    types::{
+        ContextClock, DialogueContextEvent, DialoguePriority, DialogueRequest, DialogueResponse,
+    },
+    validation::{validate_dialogue_request, DialogueValidationConfig},
+};
+use crate::player::reputation::PlayerReputation;
+use crate::world::{
+    time::{format_clock_time, WorldClock},
+    world_event::WorldEvent,
+};
+
+const FORCED_FAILURE_MESSAGE: &str = "forced failure";
+
+/// Village state a request picks up as context on its first dispatch.
+#[derive(SystemParam)]
+pub struct FirstDispatchContext<'w> {
+    world_event: Option<Res<'w, WorldEvent>>,
+    player_memory: Option<Res<'w, PlayerMemory>>,
+    reputation: Option<Res<'w, PlayerReputation>>,
+    chronicle: Option<Res<'w, VillageChronicle>>,
+    chronicle_config: Option<Res<'w, ChronicleConfig>>,
+}
+
+impl FirstDispatchContext<'_> {
+    fn attach_to(&self, request: &mut DialogueRequest) {
+        if let Some(notice) = self.world_event.as_ref().and_then(|event| event.notice()) {
+            request.context.events.push(DialogueContextEvent::Custom {
+                text: notice.to_string(),
+            });
+        }
+        if let Some(memory) = self.player_memory.as_deref() {
+            memory.attach_to(request);
+        }
+        if let Some(reputation) = self.reputation.as_deref() {
+            reputation.attach_to(request);
+        }
+        if let (Some(chronicle), Some(config)) =
+            (self.chronicle.as_deref(), self.chronicle_config.as_deref())
+        {
+            chronicle.attach_to(request, config);
+        }
+    }
+}
+
+/// Spawns dialogue requests to background tasks if rate limits allow.
+///
+/// This prevents blocking the main thread during HTTP requests to OpenAI. Requests that
+/// fail pre-flight validation are rejected here and never reach a background task. Each
+/// request goes to the broker `DialogueProviderRouter::resolve` picks for its override and
+/// its speaker's `PinnedProvider`, and waits on that provider's global cooldown. When the
+/// default broker supports batching and an ambient request is up next, ready ambient
+/// requests routed to it share a single broker call. On its first dispatch each request
+/// picks up the market-day notice while the market is announced or open, the speaker's
+/// `PlayerMemory` notes and the village's `PlayerReputation` tier when addressing the
+/// player, the `VillageChronicle` happenings an NPC speaker was not part of, and, with the
+/// `scripting` feature, lines from context scripts. Every dispatch stamps the `WorldClock`
+/// reading so event days render relative to it. A live broker's
+/// calls are charged to `DailyApiBudget`; requests that no longer fit, or whose provider
+/// `DialogueBrokerStatus` marks misconfigured, get the broker's local fabrication instead.
+/// Calls that are not live take the delay and failures of `FallbackSimulation`, when set.
+/// Dispatches and pre-flight rejections are stamped on `DialogueRequestTrace`. Before
+/// anything goes out, ambient requests whose `expires_at` the `WorldClock` has reached are
+/// dropped and stamped `Expired`; telemetry records them from `take_expired`.
+#[allow(clippy::too_many_arguments)]
+pub fn run_dialogue_request_queue(
+    mut queue: ResMut<DialogueRequestQueue>,
+    limits: Res<DialogueRateLimitState>,
+    router: Res<DialogueProviderRouter>,
+    pins: SpeakerPins,
+    mut warned_unavailable: bevy::prelude::Local<HashSet<DialogueProviderKind>>,
+    mut budget: Option<ResMut<DailyApiBudget>>,
+    status: Option<Res<DialogueBrokerStatus>>,
+    validation: Res<DialogueValidationConfig>,
+    profiles: Res<DialogueSpeakerProfiles>,
+    #[cfg(feature = "scripting")] mut scripts: Option<ResMut<ScriptedContextProviders>>,
+    clock: Option<Res<WorldClock>>,
+    first_dispatch: FirstDispatchContext,
+    simulation: Option<Res<FallbackSimulation>>,
+    mut pending_tasks: ResMut<PendingDialogueTasks>,
+    mut failure_writer: MessageWriter<DialogueRequestFailedEvent>,
+    mut tracing: RequestTracing,
+) {
+    #[cfg(feature = "profiling")]
+    let _span = info_span!("run_dialogue_request_queue").entered();
+    if queue.is_empty() {
+        return;
+    }
+
+    if let Some(now) = clock
+        .as_deref()
+        .map(|clock| ContextClock::new(clock.day_count(), clock.time_of_day()))
+    {
+        for expired in queue.drop_expired(now) {
+            debug!(
+                "Dropping stale {} dialogue {} from {}: expired day {} at {}",
+                expired.topic.label(),
+                expired.id,
+                expired.speaker,
+                expired.expires_at.day,
+                format_clock_time(expired.expires_at.time_of_day)
+            );
+            tracing.finish(expired.id, TracePhase::Expired);
+        }
+        if queue.is_empty() {
+            return;
+        }
+    }
+
+    let resolve = |request: &DialogueRequest| {
+        router.resolve(request.provider_override, pins.pinned(request.speaker))
+    };
+    let mut route = |request: &DialogueRequest| {
+        let (broker, unavailable) = resolve(request);
+        if let Some(wanted) = unavailable {
+            if warned_unavailable.insert(wanted) {
+                warn!(
+                    "Dialogue provider {} is not available; routing to {} instead",
+                    wanted,
+                    broker.provider_kind()
+                );
+            }
+        }
+        broker.clone()
+    };
+
+    let mut reject = |queued: &QueuedDialogueRequest, provider: DialogueProviderKind| {
+        match validate_dialogue_request(&queued.request, &validation) {
+            Ok(()) => false,
+            Err(kind) => {
+                tracing.finish(
+                    queued.id,
+                    TracePhase::Failed {
+                        attempt: queued.attempts.saturating_add(1),
+                    },
+                );
+                failure_writer.write(DialogueRequestFailedEvent {
+                    error: DialogueError::new(queued.id, provider, kind),
+                    speaker: queued.request.speaker,
+                    target: queued.request.target,
+                });
+                true
+            }
+        }
+    };
+
+    let mut prepare = |queued: &mut QueuedDialogueRequest| {
+        let request = &mut queued.request;
+        if request.speaker_profile.is_none() {
+            request.speaker_profile = profiles.get(request.speaker).map(str::to_string);
+        }
+        if request.speaker_examples.is_empty() {
+            request.speaker_examples = profiles.examples(request.speaker).to_vec();
+        }
+        // Restamped on every dispatch, so a retry reads event days against its own send time.
+        request.context.now = clock
+            .as_deref()
+            .map(|clock| ContextClock::new(clock.day_count(), clock.time_of_day()));
+        // Retries already carry their notices and scripted context from the first attempt.
+        if queued.attempts == 0 {
+            first_dispatch.attach_to(request);
+        }
+        #[cfg(feature = "scripting")]
+        if let (Some(scripts), 0) = (scripts.as_deref_mut(), queued.attempts) {
+            let day = clock.as_ref().map_or(0, |clock| clock.day_count());
+            scripts.extend_context(request, day);
+        }
+    };
+
+    let mut go_live = |broker: &ActiveDialogueBroker, priority: DialoguePriority, count: usize| {
+        if status
+            .as_deref()
+            .is_some_and(|status| status.is_misconfigured(broker.provider_kind()))
+        {
+            return false;
+        }
+        match budget.as_deref_mut() {
+            Some(budget) if broker.connection_state() == DialogueConnectionState::Live => {
+                budget.try_spend(Local::now(), priority, count as u32)
+            }
+            _ => true,
+        }
+    };
+
+    let simulate = |broker: &ActiveDialogueBroker, live: bool| {
+        simulation.as_deref().copied().filter(|simulation| {
+            simulation.is_enabled()
+                && (!live || broker.connection_state() != DialogueConnectionState::Live)
+        })
+    };
+
+    let default = router.default_broker().clone();
+    let batch_size = default.max_batch_size();
+    let ambient_next = queue.front_ready()
+        && queue
+            .pending
+            .front()
+            .is_some_and(|queued| queued.request.priority() == DialoguePriority::Ambient);
+    if batch_size >= 2 && ambient_next {
+        let mut batch = queue.take_ready_batch(
+            DialoguePriority::Ambient,
+            batch_size,
+            default.provider_kind(),
+            |request| resolve(request).0.provider_kind() == default.provider_kind(),
+            &limits,
+        );
+        if !batch.is_empty() {
+            batch.retain(|queued| {
+                route(&queued.request);
+                !reject(queued, default.provider_kind())
+            });
+            if !batch.is_empty() {
+                let live = go_live(&default, DialoguePriority::Ambient, batch.len());
+                trace_dispatch(&batch, &mut tracing);
+                spawn_dialogue_task(
+                    batch,
+                    &default,
+                    &mut prepare,
+                    live,
+                    simulate(&default, live),
+                    &mut pending_tasks,
+                );
+            }
+            return;
+        }
+    }
+
+    let (mut queued, broker) = loop {
+        if !queue.front_ready() {
+            return;
+        }
+
+        let Some(queued) = queue.pending.pop_front() else {
+            return;
+        };
+
+        let broker = route(&queued.request);
+        if !reject(&queued, broker.provider_kind()) {
+            break (queued, broker);
+        }
+    };
+
+    if !limits.can_process(broker.provider_kind(), queued.request.speaker) {
+        queue.pending.push_front(queued);
+        return;
+    }
+
+    let priority = queued.request.priority();
+    let live = go_live(&broker, priority, 1);
+    // A truncated reply may be finished with one more live call if that call still fits.
+    queued.request.allow_continuation = live
+        && broker.connection_state() == DialogueConnectionState::Live
+        && budget
+            .as_deref()
+            .is_none_or(|budget| budget.blocking_limit(priority, 1).is_none());
+    trace_dispatch(std::slice::from_ref(&queued), &mut tracing);
+    spawn_dialogue_task(
+        vec![queued],
+        &broker,
+        &mut prepare,
+        live,
+        simulate(&broker, live),
+        &mut pending_tasks,
+    );
+}
+
+fn trace_dispatch(batch: &[QueuedDialogueRequest], tracing: &mut RequestTracing) {
+    for queued in batch {
+        tracing.record(
+            queued.id,
+            TracePhase::Dispatched {
+                attempt: queued.attempts.saturating_add(1),
+            },
+        );
+    }
+}
+
+/// Hands `batch` to the broker on the async compute pool; a lone request goes through
+/// `process`, several through `process_batch`, and everything through `fabricate` when
+/// `live` is false. `prepare` fills in speaker profiles and any scripted context first.
+/// With a `simulation`, the task first sleeps the longest delay drawn for the batch, and
+/// requests drawn to fail report that error instead of their reply.
+fn spawn_dialogue_task(
+    batch: Vec<QueuedDialogueRequest>,
+    broker: &ActiveDialogueBroker,
+    prepare: &mut impl FnMut(&mut QueuedDialogueRequest),
+    live: bool,
+    simulation: Option<FallbackSimulation>,
+    pending_tasks: &mut PendingDialogueTasks,
+) {
+    // Clone data needed for the background task
+    let mut entries = Vec::with_capacity(batch.len());
+    let mut attempts = Vec::with_capacity(batch.len());
+    let mut views = Vec::with_capacity(batch.len());
+    for mut queued in batch {
+        prepare(&mut queued);
+        pending_tasks.sequence(queued.id, queued.request.speaker);
+        let request = queued.request;
+        views.push(InFlightRequestView {
+            id: queued.id,
+            speaker: request.speaker,
+            target: request.target,
+            topic: request.topic_hint,
+            attempts: queued.attempts,
+        });
+        attempts.push(queued.attempts);
+        entries.push((queued.id, request));
+    }
+    let broker_clone = broker.clone();
+    let forced_failure = pending_tasks.take_forced_failure();
+
+    // Spawn to background thread to avoid blocking the game
+    let task_pool = AsyncComputeTaskPool::get();
+    let task = task_pool.spawn(async move {
+        let outcomes: Vec<_> = entries
+            .iter()
+            .zip(&attempts)
+            .map(|((request_id, _), attempt)| {
+                simulation.map(|simulation| simulation.outcome(*request_id, *attempt))
+            })
+            .collect();
+        if let Some(delay) = outcomes.iter().flatten().map(|outcome| outcome.delay).max() {
+            std::thread::sleep(delay);
+        }
+        let results: Vec<Result<DialogueResponse, DialogueError>> = match entries.as_slice() {
+            batch if !live => batch
+                .iter()
+                .map(|(request_id, request)| Ok(broker_clone.fabricate(*request_id, request)))
+                .collect(),
+            [(request_id, request)] => vec![broker_clone.process(*request_id, request)],
+            batch => broker_clone.process_batch(batch),
+        };
+        entries
+            .into_iter()
+            .zip(attempts)
+            .zip(results.into_iter().zip(outcomes))
+            .enumerate()
+            .map(
+                |(index, (((request_id, request), attempts), (result, outcome)))| {
+                    let failure = if forced_failure && index == 0 {
+                        Some(DialogueErrorKind::provider_failure(FORCED_FAILURE_MESSAGE))
+                    } else {
+                        outcome.and_then(|outcome| outcome.failure)
+                    };
+                    let result = match failure {
+                        Some(kind) => Err(DialogueError::new(
+                            request_id,
+                            broker_clone.provider_kind(),
+                            kind,
+                        )),
+                        None => result,
+                    };
+                    (request_id, request, result, attempts)
+                },
+            )
+            .collect()
+    });
+
+    pending_tasks
+        .tasks
+        .push(InFlightDialogueTask { views, task });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use bevy::tasks::TaskPool;
+
+    use super::super::{poll_dialogue_tasks, test_support::*, DialogueRateLimitConfig};
+    use super::*;
+    use crate::dialogue::{
+        events::DialogueResponseEvent,
+        router::PinnedProvider,
+        simulation::SimulatedLatency,
+        types::{DialogueContext, DialogueRequestId, DialogueTopicHint},
+    };
+    use crate::npc::{
+        components::{Identity, NpcId},
+        spatial::{index_npcs, NpcIndex},
+    };
+
+    #[test]
+    fn invalid_request_fails_without_spawning_task() {
+        let mut app = App::new();
+        app.init_resource::<DialogueRequestQueue>()
+            .init_resource::<DialogueRateLimitState>()
+            .init_resource::<DialogueValidationConfig>()
+            .init_resource::<DialogueSpeakerProfiles>()
+            .init_resource::<PendingDialogueTasks>()
+            .init_resource::<NpcIndex>()
+            .insert_resource(DialogueProviderRouter::new(Box::new(UnreachableBroker)))
+            .add_message::<DialogueRequestFailedEvent>()
+            .add_systems(Update, run_dialogue_request_queue);
+
+        let request_id = app
+            .world_mut()
+            .resource_mut::<DialogueRequestQueue>()
+            .enqueue(DialogueRequest::new(
+                NpcId::new(1),
+                Some(NpcId::new(1)),
+                "Talking to myself",
+                DialogueTopicHint::Status,
+                DialogueContext::default(),
+            ));
+        app.update();
+
+        assert!(app
+            .world()
+            .resource::<PendingDialogueTasks>()
+            .in_flight_views()
+            .next()
+            .is_none());
+        assert!(app.world().resource::<DialogueRequestQueue>().is_empty());
+        let limits = app.world().resource::<DialogueRateLimitState>();
+        assert!(limits.provider_remaining.is_empty());
+        assert!(limits.npc_remaining.is_empty());
+
+        let failures = app
+            .world()
+            .resource::<Messages<DialogueRequestFailedEvent>>();
+        let mut cursor = failures.get_cursor();
+        let failure = cursor
+            .read(failures)
+            .next()
+            .expect("validation failure should be reported immediately");
+        assert_eq!(failure.error.request_id, request_id);
+        assert!(matches!(
+            failure.error.kind,
+            DialogueErrorKind::InvalidRequest { .. }
+        ));
+    }
+
+    #[test]
+    fn simulated_fallback_failures_go_through_the_retry_path() {
+        let request_id = DialogueRequestId::new(0);
+        // A seed whose first attempt fails as an outage and whose retry goes through.
+        let simulation = (0..)
+            .map(|seed| FallbackSimulation {
+                latency: SimulatedLatency {
+                    min_ms: 5,
+                    max_ms: 10,
+                },
+                failure_percent: 50.0,
+                seed,
+            })
+            .find(|simulation| {
+                matches!(
+                    simulation.outcome(request_id, 0).failure,
+                    Some(DialogueErrorKind::ProviderFailure { .. })
+                ) && simulation.outcome(request_id, 1).failure.is_none()
+            })
+            .unwrap();
+        let mut app = retry_app();
+        app.insert_resource(simulation);
+
+        assert_eq!(
+            attempts_until_answered(&mut app),
+            vec![0, 1],
+            "the delayed first attempt failed and was retried"
+        );
+    }
+
+    #[test]
+    fn a_forced_failure_fails_one_dispatch_only() {
+        let mut app = retry_app();
+        // A little latency keeps each attempt in flight long enough to be seen.
+        app.insert_resource(FallbackSimulation {
+            latency: SimulatedLatency {
+                min_ms: 5,
+                max_ms: 10,
+            },
+            ..default()
+        });
+        app.world_mut()
+            .resource_mut::<PendingDialogueTasks>()
+            .force_next_failure();
+
+        assert_eq!(attempts_until_answered(&mut app), vec![0, 1]);
+        assert_eq!(
+            attempts_until_answered(&mut app),
+            vec![0],
+            "later requests are answered first time"
+        );
+    }
+
+    #[test]
+    fn requests_route_by_override_then_pin_then_default() {
+        AsyncComputeTaskPool::get_or_init(TaskPool::new);
+        let mut app = App::new();
+        app.init_resource::<DialogueRequestQueue>()
+            .init_resource::<DialogueRateLimitState>()
+            .insert_resource(DialogueRateLimitConfig {
+                global_cooldown_seconds: 0.0,
+                per_npc_cooldown_seconds: 0.0,
+                ..Default::default()
+            })
+            .init_resource::<DialogueValidationConfig>()
+            .init_resource::<DialogueSpeakerProfiles>()
+            .init_resource::<PendingDialogueTasks>()
+            .init_resource::<NpcIndex>()
+            .init_resource::<AnsweredBy>()
+            .insert_resource(
+                DialogueProviderRouter::new(Box::new(FabricatingBroker(
+                    DialogueProviderKind::OpenAi,
+                )))
+                .with_broker(Box::new(FabricatingBroker(DialogueProviderKind::Local))),
+            )
+            .add_message::<DialogueRequestFailedEvent>()
+            .add_message::<DialogueResponseEvent>()
+            .add_systems(
+                Update,
+                (
+                    index_npcs,
+                    run_dialogue_request_queue,
+                    poll_dialogue_tasks,
+                    collect_providers,
+                )
+                    .chain(),
+            );
+
+        let (pinned, overridden, plain) = (NpcId::new(1), NpcId::new(2), NpcId::new(3));
+        app.world_mut().spawn((
+            Identity::new(pinned, "Ada", 30.0),
+            PinnedProvider(DialogueProviderKind::Local),
+        ));
+        app.world_mut().spawn((
+            Identity::new(overridden, "Bo", 30.0),
+            PinnedProvider(DialogueProviderKind::Local),
+        ));
+        let mut request = ambient(2);
+        request.provider_override = Some(DialogueProviderKind::OpenAi);
+        {
+            let mut queue = app.world_mut().resource_mut::<DialogueRequestQueue>();
+            queue.enqueue(ambient(1));
+            queue.enqueue(request);
+            queue.enqueue(ambient(3));
+        }
+
+        for _ in 0..500 {
+            app.update();
+            if app.world().resource::<AnsweredBy>().0.len() == 3 {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        let mut answered = app.world().resource::<AnsweredBy>().0.clone();
+        answered.sort_by_key(|(speaker, _)| *speaker);
+        assert_eq!(
+            answered,
+            [
+                (pinned, DialogueProviderKind::Local),
+                (overridden, DialogueProviderKind::OpenAi),
+                (plain, DialogueProviderKind::OpenAi),
+            ]
+        );
+    }
+
+    #[test]
+    fn spent_budget_routes_requests_to_fabrication_and_announces_once() {
+        use crate::dialogue::{events::ApiBudgetExhaustedEvent, status::DialogueBrokerStatus};
+
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let mut app = live_budget_app(
+            1,
+            Box::new(MeteredBroker {
+                calls: calls.clone(),
+            }),
+        );
+        let lines = answer_all(&mut app, 3);
+
+        assert_eq!(
+            calls.load(Ordering::SeqCst),
+            1,
+            "one live call fits the budget"
+        );
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], ("Live line.".to_string(), Some(120)));
+        assert!(lines[1..]
+            .iter()
+            .all(|(content, tokens)| content != "Live line." && tokens.is_none()));
+
+        let world = app.world();
+        let budget = world.resource::<DailyApiBudget>();
+        assert_eq!((budget.requests_used(), budget.tokens_used()), (1, 120));
+        assert!(world.resource::<DialogueBrokerStatus>().budget_exhausted());
+        let exhausted = world.resource::<Messages<ApiBudgetExhaustedEvent>>();
+        assert_eq!(exhausted.get_cursor().read(exhausted).count(), 1);
+    }
+
+    #[test]
+    fn continuations_are_allowed_only_with_budget_room_and_counted_as_calls() {
+        for (max_requests, allowed, requests_used) in [(2, true, 2), (1, false, 1)] {
+            let calls = Arc::new(Mutex::new(Vec::new()));
+            let mut app = live_budget_app(
+                max_requests,
+                Box::new(ContinuingBroker {
+                    allowed: calls.clone(),
+                }),
+            );
+            let lines = answer_all(&mut app, 1);
+
+            assert_eq!(lines, [("Live line, finished.".to_string(), Some(300))]);
+            assert_eq!(*calls.lock().unwrap(), [allowed]);
+            let budget = app.world().resource::<DailyApiBudget>();
+            assert_eq!(
+                (budget.requests_used(), budget.tokens_used()),
+                (requests_used, 300),
+                "the follow-up counts as a call; its tokens are in the combined usage"
+            );
+        }
+    }
+}
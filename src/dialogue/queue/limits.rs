@@ -0,0 +1,245 @@
+//! Rate limits for the dialogue queue: the configured cooldowns and retry budget, the
+//! cooldowns still running per provider and per NPC, and the speaker profiles attached at
+//! dispatch.
+use std::collections::HashMap;
+use std::env;
+
+use bevy::prelude::*;
+
+use crate::dialogue::broker::DialogueProviderKind;
+use crate::npc::components::NpcId;
+
+const DEFAULT_GLOBAL_COOLDOWN_SECONDS: f32 = 1.5;
+const DEFAULT_PER_NPC_COOLDOWN_SECONDS: f32 = 8.0;
+const DEFAULT_MAX_RETRIES: u8 = 2;
+const DEFAULT_RETRY_BACKOFF_SECONDS: f32 = 5.0;
+const DEFAULT_DEAD_LETTER_CAPACITY: usize = 32;
+const DEFAULT_DEAD_LETTER_MAX_AGE_MINUTES: u64 = 240;
+const DEFAULT_ORDERING_TIMEOUT_SECONDS: f32 = 20.0;
+pub const ENV_ORDERED_RESPONSES: &str = "DIALOGUE_ORDERED_RESPONSES";
+pub const ENV_ORDERING_TIMEOUT_SECS: &str = "DIALOGUE_ORDERING_TIMEOUT_SECS";
+
+/// Configurable rate limit values for the dialogue queue.
+#[derive(Resource, Debug, Clone)]
+pub struct DialogueRateLimitConfig {
+    pub global_cooldown_seconds: f32,
+    pub per_npc_cooldown_seconds: f32,
+    pub max_retries: u8,
+    pub retry_backoff_seconds: f32,
+    /// Requests kept in `DialogueDeadLetterStore` once retries run out; oldest go first.
+    pub dead_letter_capacity: usize,
+    /// Dead letters older than this many in-game minutes are dropped instead of resent.
+    pub dead_letter_max_age_minutes: u64,
+    /// Hold each speaker's replies and final failures until every request that speaker
+    /// dispatched earlier has resolved, so events and telemetry follow dispatch order.
+    pub ordered_responses_per_speaker: bool,
+    /// Real seconds an outcome is held for an earlier one before it goes out anyway.
+    pub ordering_timeout_seconds: f32,
+}
+
+impl Default for DialogueRateLimitConfig {
+    fn default() -> Self {
+        Self {
+            global_cooldown_seconds: DEFAULT_GLOBAL_COOLDOWN_SECONDS,
+            per_npc_cooldown_seconds: DEFAULT_PER_NPC_COOLDOWN_SECONDS,
+            max_retries: DEFAULT_MAX_RETRIES,
+            retry_backoff_seconds: DEFAULT_RETRY_BACKOFF_SECONDS,
+            dead_letter_capacity: DEFAULT_DEAD_LETTER_CAPACITY,
+            dead_letter_max_age_minutes: DEFAULT_DEAD_LETTER_MAX_AGE_MINUTES,
+            ordered_responses_per_speaker: false,
+            ordering_timeout_seconds: DEFAULT_ORDERING_TIMEOUT_SECONDS,
+        }
+    }
+}
+
+impl DialogueRateLimitConfig {
+    /// Defaults with the response-ordering overrides from the environment.
+    pub fn from_env() -> Self {
+        Self::from_lookup(|key| env::var(key).ok())
+    }
+
+    /// Reads overrides from `lookup`; unset values keep their defaults and invalid ones are
+    /// logged and ignored.
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let mut config = Self::default();
+        let read = |key: &str| lookup(key).map(|value| value.trim().to_ascii_lowercase());
+
+        if let Some(value) = read(ENV_ORDERED_RESPONSES) {
+            match value.as_str() {
+                "1" | "true" | "yes" | "on" => config.ordered_responses_per_speaker = true,
+                "0" | "false" | "no" | "off" => config.ordered_responses_per_speaker = false,
+                _ => warn!("Ignoring {ENV_ORDERED_RESPONSES}={value}: expected true or false"),
+            }
+        }
+        if let Some(value) = read(ENV_ORDERING_TIMEOUT_SECS) {
+            match value.parse::<f32>() {
+                Ok(seconds) if seconds.is_finite() && seconds >= 0.0 => {
+                    config.ordering_timeout_seconds = seconds;
+                }
+                _ => warn!(
+                    "Ignoring {ENV_ORDERING_TIMEOUT_SECS}={value}: expected seconds of zero or more"
+                ),
+            }
+        }
+        config
+    }
+}
+
+/// Latest speaker profile and example lines per NPC, attached to requests at dispatch time.
+#[derive(Resource, Debug, Default)]
+pub struct DialogueSpeakerProfiles {
+    profiles: HashMap<NpcId, String>,
+    examples: HashMap<NpcId, Vec<String>>,
+}
+
+impl DialogueSpeakerProfiles {
+    pub fn set(&mut self, npc: NpcId, profile: impl Into<String>) {
+        self.profiles.insert(npc, profile.into());
+    }
+
+    pub fn get(&self, npc: NpcId) -> Option<&str> {
+        self.profiles.get(&npc).map(String::as_str)
+    }
+
+    /// Replaces `npc`'s example lines; an empty list forgets them.
+    pub fn set_examples(&mut self, npc: NpcId, lines: Vec<String>) {
+        if lines.is_empty() {
+            self.examples.remove(&npc);
+        } else {
+            self.examples.insert(npc, lines);
+        }
+    }
+
+    pub fn examples(&self, npc: NpcId) -> &[String] {
+        self.examples.get(&npc).map_or(&[], Vec::as_slice)
+    }
+
+    /// Forgets `npc`'s profile and example lines.
+    pub fn forget(&mut self, npc: NpcId) {
+        self.profiles.remove(&npc);
+        self.examples.remove(&npc);
+    }
+}
+
+/// Tracks the remaining time until requests can be processed again. The global cooldown
+/// is kept per provider, so one provider's backoff never holds up another; per-NPC
+/// cooldowns are shared by every provider.
+#[derive(Resource, Debug, Default)]
+pub struct DialogueRateLimitState {
+    pub provider_remaining: HashMap<DialogueProviderKind, f32>,
+    pub npc_remaining: HashMap<NpcId, f32>,
+}
+
+impl DialogueRateLimitState {
+    pub fn tick(&mut self, delta_seconds: f32) {
+        let delta = delta_seconds.max(0.0);
+        for cooldown in self
+            .provider_remaining
+            .values_mut()
+            .chain(self.npc_remaining.values_mut())
+        {
+            if *cooldown > 0.0 {
+                *cooldown = (*cooldown - delta).max(0.0);
+            }
+        }
+    }
+
+    /// Seconds left on `provider`'s global cooldown.
+    pub fn global_remaining(&self, provider: DialogueProviderKind) -> f32 {
+        self.provider_remaining
+            .get(&provider)
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    pub fn can_process(&self, provider: DialogueProviderKind, speaker: NpcId) -> bool {
+        if self.global_remaining(provider) > 0.0 {
+            return false;
+        }
+        !matches!(self.npc_remaining.get(&speaker), Some(value) if *value > 0.0)
+    }
+
+    /// Seconds until `provider` may process `speaker` again (the larger of global and
+    /// per-NPC).
+    pub fn remaining_for(&self, provider: DialogueProviderKind, speaker: NpcId) -> f32 {
+        self.npc_remaining
+            .get(&speaker)
+            .copied()
+            .unwrap_or(0.0)
+            .max(self.global_remaining(provider))
+    }
+
+    pub fn record_success(
+        &mut self,
+        provider: DialogueProviderKind,
+        speaker: NpcId,
+        config: &DialogueRateLimitConfig,
+    ) {
+        self.provider_remaining
+            .insert(provider, config.global_cooldown_seconds.max(0.0));
+        self.npc_remaining
+            .insert(speaker, config.per_npc_cooldown_seconds.max(0.0));
+    }
+
+    /// Drops `npc`'s per-NPC cooldown.
+    pub fn forget_npc(&mut self, npc: NpcId) {
+        self.npc_remaining.remove(&npc);
+    }
+
+    pub fn apply_backoff(&mut self, provider: DialogueProviderKind, speaker: NpcId, seconds: f32) {
+        let backoff = seconds.max(0.0);
+        self.provider_remaining
+            .entry(provider)
+            .and_modify(|value| *value = value.max(backoff))
+            .or_insert(backoff);
+        self.npc_remaining
+            .entry(speaker)
+            .and_modify(|value| *value = value.max(backoff))
+            .or_insert(backoff);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn provider_cooldowns_are_isolated_while_npc_cooldowns_are_shared() {
+        let mut limits = DialogueRateLimitState::default();
+        let (openai, local) = (DialogueProviderKind::OpenAi, DialogueProviderKind::Local);
+        let (talker, other) = (NpcId::new(1), NpcId::new(2));
+
+        limits.record_success(openai, talker, &DialogueRateLimitConfig::default());
+        assert!(
+            !limits.can_process(openai, other),
+            "openai's bucket is cooling"
+        );
+        assert!(limits.can_process(local, other), "local has its own bucket");
+        assert!(
+            !limits.can_process(local, talker),
+            "the speaker's own cooldown applies on every provider"
+        );
+
+        limits.apply_backoff(local, other, 30.0);
+        limits.tick(DEFAULT_GLOBAL_COOLDOWN_SECONDS);
+        assert_eq!(limits.global_remaining(openai), 0.0);
+        assert!(limits.global_remaining(local) > 0.0);
+        assert!(limits.can_process(openai, NpcId::new(3)));
+        assert!(!limits.can_process(local, NpcId::new(3)));
+    }
+
+    #[test]
+    fn ordering_overrides_come_from_the_environment() {
+        let config = DialogueRateLimitConfig::from_lookup(|key| match key {
+            ENV_ORDERED_RESPONSES => Some(" Yes ".to_string()),
+            ENV_ORDERING_TIMEOUT_SECS => Some("-3".to_string()),
+            _ => None,
+        });
+        assert!(config.ordered_responses_per_speaker);
+        assert_eq!(
+            config.ordering_timeout_seconds,
+            DEFAULT_ORDERING_TIMEOUT_SECONDS
+        );
+        assert!(!DialogueRateLimitConfig::from_lookup(|_| None).ordered_responses_per_speaker);
+    }
+}
@@ -0,0 +1,554 @@
+//! Dialogue request queue and rate limiting resources. Rate limits live in `limits`,
+//! dispatching in `dispatch`, background tasks and their outcomes in `tasks`, and debug
+//! snapshots in `views`.
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use bevy::prelude::*;
+
+use crate::npc::events::NpcDespawnedEvent;
+
+use super::{
+    broker::{DialogueBroker, DialogueProviderKind},
+    errors::DialogueError,
+    events::DialogueRequestedEvent,
+    status::DialogueConnectionState,
+    trace::TracePhase,
+    types::{ContextClock, DialoguePriority, DialogueRequest, DialogueRequestId, DialogueResponse},
+};
+
+mod dispatch;
+mod limits;
+mod tasks;
+#[cfg(test)]
+mod test_support;
+mod views;
+
+pub use dispatch::run_dialogue_request_queue;
+pub use limits::{DialogueRateLimitConfig, DialogueRateLimitState, DialogueSpeakerProfiles};
+pub use tasks::{poll_dialogue_tasks, PendingDialogueTasks};
+pub use views::{DialogueQueueDump, ExpiredRequestView, InFlightRequestView, PendingRequestView};
+
+use tasks::InFlightDialogueTask;
+
+/// Resource holding pending dialogue requests.
+#[derive(Resource, Default)]
+pub struct DialogueRequestQueue {
+    next_request_id: u64,
+    pending: VecDeque<QueuedDialogueRequest>,
+    /// Targeted requests accepted since the last `announce_queued_dialogue_requests` run.
+    unannounced: Vec<DialogueRequestedEvent>,
+    /// Requests accepted or withdrawn since the last `trace_queued_dialogue_requests` run,
+    /// which stamps them with the time.
+    untraced: Vec<(DialogueRequestId, TracePhase)>,
+    /// Requests dropped as stale since the last `record_dialogue_telemetry` run.
+    expired: Vec<ExpiredRequestView>,
+    /// Requests cancelled or expired since the last `poll_dialogue_tasks` run, which frees
+    /// their place in their speaker's response order.
+    withdrawn: Vec<DialogueRequestId>,
+}
+
+impl DialogueRequestQueue {
+    /// Accepts `request`. Requests an NPC addresses to someone are also announced as a
+    /// `DialogueRequestedEvent` on the next frame, which is what starts a conversation.
+    pub fn enqueue(&mut self, request: DialogueRequest) -> DialogueRequestId {
+        let id = DialogueRequestId::new(self.next_request_id);
+        self.next_request_id = self.next_request_id.wrapping_add(1);
+        if request.target.is_some() {
+            self.unannounced.push(DialogueRequestedEvent {
+                request_id: id,
+                speaker: request.speaker,
+                target: request.target,
+            });
+        }
+        self.pending.push_back(QueuedDialogueRequest {
+            id,
+            request,
+            attempts: 0,
+            cooldown_remaining: 0.0,
+        });
+        self.untraced.push((id, TracePhase::Enqueued));
+        id
+    }
+
+    /// Requests accepted since startup; retries are not counted again.
+    pub fn total_enqueued(&self) -> u64 {
+        self.next_request_id
+    }
+
+    /// Puts a failed request back under its original id, keeping the attempt count so
+    /// `DialogueRateLimitConfig::max_retries` is honoured.
+    fn requeue_for_retry(
+        &mut self,
+        id: DialogueRequestId,
+        request: DialogueRequest,
+        attempts: u8,
+        cooldown_seconds: f32,
+    ) {
+        self.pending.push_back(QueuedDialogueRequest {
+            id,
+            request,
+            attempts,
+            cooldown_remaining: cooldown_seconds.max(0.0),
+        });
+    }
+
+    /// Withdraws a request that has not been dispatched yet, along with its announcement if
+    /// that has not gone out. Returns false when the id is unknown or already in flight.
+    pub fn cancel(&mut self, id: DialogueRequestId) -> bool {
+        self.unannounced.retain(|event| event.request_id != id);
+        let before = self.pending.len();
+        self.pending.retain(|queued| queued.id != id);
+        let cancelled = self.pending.len() != before;
+        if cancelled {
+            self.untraced.push((id, TracePhase::Cancelled));
+            self.withdrawn.push(id);
+        }
+        cancelled
+    }
+
+    /// Drops every pending request `DialogueRequest::is_expired` at `now`, along with any
+    /// announcement still waiting, and returns them oldest first. Retries keep the deadline
+    /// of their first attempt, so a failed request cannot outlive it either.
+    fn drop_expired(&mut self, now: ContextClock) -> Vec<ExpiredRequestView> {
+        let mut dropped = Vec::new();
+        self.pending.retain(|queued| {
+            let Some(expires_at) = queued
+                .request
+                .expires_at
+                .filter(|_| queued.request.is_expired(now))
+            else {
+                return true;
+            };
+            dropped.push(ExpiredRequestView {
+                id: queued.id,
+                speaker: queued.request.speaker,
+                target: queued.request.target,
+                topic: queued.request.topic_hint,
+                attempts: queued.attempts,
+                expires_at,
+                dropped_at: now,
+            });
+            false
+        });
+        self.unannounced
+            .retain(|event| dropped.iter().all(|view| view.id != event.request_id));
+        self.expired.extend(dropped.iter().cloned());
+        self.withdrawn.extend(dropped.iter().map(|view| view.id));
+        dropped
+    }
+
+    /// Requests dropped as stale since the last call, oldest first.
+    pub fn take_expired(&mut self) -> Vec<ExpiredRequestView> {
+        std::mem::take(&mut self.expired)
+    }
+
+    /// Requests cancelled or expired since the last call, oldest first.
+    fn take_withdrawn(&mut self) -> Vec<DialogueRequestId> {
+        std::mem::take(&mut self.withdrawn)
+    }
+
+    /// Enqueued and cancelled phases recorded since the last call, oldest first.
+    pub fn take_untraced(&mut self) -> Vec<(DialogueRequestId, TracePhase)> {
+        std::mem::take(&mut self.untraced)
+    }
+
+    /// A still-pending request, for systems that add context before it is dispatched.
+    pub fn pending_mut(&mut self, id: DialogueRequestId) -> Option<&mut DialogueRequest> {
+        self.pending
+            .iter_mut()
+            .find(|queued| queued.id == id)
+            .map(|queued| &mut queued.request)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Removes up to `max` ready, first-attempt requests of `priority` with distinct speakers
+    /// that `routes_here` sends to `provider` and `limits` would let through, in queue order.
+    /// Returns nothing unless at least two qualify, and never batches `Player` requests.
+    /// Retries are left to go out on their own.
+    fn take_ready_batch(
+        &mut self,
+        priority: DialoguePriority,
+        max: usize,
+        provider: DialogueProviderKind,
+        routes_here: impl Fn(&DialogueRequest) -> bool,
+        limits: &DialogueRateLimitState,
+    ) -> Vec<QueuedDialogueRequest> {
+        if priority == DialoguePriority::Player || max < 2 {
+            return Vec::new();
+        }
+
+        let mut speakers = Vec::with_capacity(max);
+        let mut picked = Vec::with_capacity(max);
+        for (index, queued) in self.pending.iter().enumerate() {
+            if picked.len() == max {
+                break;
+            }
+            let speaker = queued.request.speaker;
+            if queued.cooldown_remaining > 0.0
+                || queued.attempts > 0
+                || queued.request.priority() != priority
+                || speakers.contains(&speaker)
+                || !limits.can_process(provider, speaker)
+                || !routes_here(&queued.request)
+            {
+                continue;
+            }
+            speakers.push(speaker);
+            picked.push(index);
+        }
+        if picked.len() < 2 {
+            return Vec::new();
+        }
+
+        let mut batch: Vec<_> = picked
+            .into_iter()
+            .rev()
+            .filter_map(|index| self.pending.remove(index))
+            .collect();
+        batch.reverse();
+        batch
+    }
+
+    /// Queued requests in dispatch order.
+    pub fn iter_pending(&self) -> impl Iterator<Item = PendingRequestView> + '_ {
+        self.pending.iter().map(|queued| PendingRequestView {
+            id: queued.id,
+            speaker: queued.request.speaker,
+            target: queued.request.target,
+            topic: queued.request.topic_hint,
+            attempts: queued.attempts,
+            cooldown_remaining: queued.cooldown_remaining,
+        })
+    }
+
+    pub fn front_ready(&self) -> bool {
+        self.pending
+            .front()
+            .map(|req| req.cooldown_remaining <= 0.0)
+            .unwrap_or(false)
+    }
+
+    fn tick(&mut self, delta_seconds: f32) {
+        let delta = delta_seconds.max(0.0);
+        for req in &mut self.pending {
+            if req.cooldown_remaining > 0.0 {
+                req.cooldown_remaining = (req.cooldown_remaining - delta).max(0.0);
+            }
+        }
+    }
+}
+
+/// Sends a `DialogueRequestedEvent` for every targeted request enqueued since the last run,
+/// whichever system queued it, so `start_conversations` sees them all.
+pub fn announce_queued_dialogue_requests(
+    mut queue: ResMut<DialogueRequestQueue>,
+    mut requested: MessageWriter<DialogueRequestedEvent>,
+) {
+    if queue.unannounced.is_empty() {
+        return;
+    }
+    requested.write_batch(std::mem::take(&mut queue.unannounced));
+}
+
+/// Wrapper for a dynamic dialogue broker instance.
+///
+/// Uses Arc internally to allow cheap cloning for background tasks.
+#[derive(Resource, Clone)]
+pub struct ActiveDialogueBroker {
+    inner: Arc<Box<dyn DialogueBroker>>,
+}
+
+impl ActiveDialogueBroker {
+    pub fn new(inner: Box<dyn DialogueBroker>) -> Self {
+        Self {
+            inner: Arc::new(inner),
+        }
+    }
+
+    pub fn provider_kind(&self) -> DialogueProviderKind {
+        self.inner.provider_kind()
+    }
+
+    pub fn connection_state(&self) -> DialogueConnectionState {
+        self.inner.connection_state()
+    }
+
+    pub fn fabricate(
+        &self,
+        request_id: DialogueRequestId,
+        request: &DialogueRequest,
+    ) -> DialogueResponse {
+        self.inner.fabricate(request_id, request)
+    }
+
+    pub fn process(
+        &self,
+        request_id: DialogueRequestId,
+        request: &DialogueRequest,
+    ) -> Result<DialogueResponse, DialogueError> {
+        self.inner.process(request_id, request)
+    }
+
+    pub fn max_batch_size(&self) -> usize {
+        self.inner.max_batch_size()
+    }
+
+    pub fn process_batch(
+        &self,
+        batch: &[(DialogueRequestId, DialogueRequest)],
+    ) -> Vec<Result<DialogueResponse, DialogueError>> {
+        self.inner.process_batch(batch)
+    }
+
+    pub fn summarize(&self, speaker_name: &str, transcript: &str) -> String {
+        self.inner.summarize(speaker_name, transcript)
+    }
+}
+
+/// Internal queue entry storing retry metadata.
+#[derive(Debug, Clone)]
+struct QueuedDialogueRequest {
+    id: DialogueRequestId,
+    request: DialogueRequest,
+    attempts: u8,
+    cooldown_remaining: f32,
+}
+
+/// Advances rate-limiter and per-request cooldown timers.
+pub fn advance_dialogue_queue_timers(
+    time: Res<Time>,
+    mut queue: ResMut<DialogueRequestQueue>,
+    mut limits: ResMut<DialogueRateLimitState>,
+) {
+    let delta = time.delta_secs().max(0.0);
+    queue.tick(delta);
+    limits.tick(delta);
+}
+
+/// Drops despawned NPCs' per-NPC cooldowns and speaker profiles.
+pub fn forget_despawned_speakers(
+    mut despawned: MessageReader<NpcDespawnedEvent>,
+    mut limits: ResMut<DialogueRateLimitState>,
+    mut profiles: ResMut<DialogueSpeakerProfiles>,
+) {
+    for event in despawned.read() {
+        limits.forget_npc(event.npc_id);
+        profiles.forget(event.npc_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_support::{ambient, retry_app, stale_by};
+    use super::*;
+    use crate::dialogue::{
+        trace::DialogueRequestTrace,
+        types::{DialogueContext, DialogueTopicHint},
+    };
+    use crate::npc::components::NpcId;
+    use crate::world::time::WorldClock;
+    use std::time::Duration;
+
+    #[test]
+    fn queue_reports_ready_state() {
+        let mut queue = DialogueRequestQueue::default();
+        assert!(queue.is_empty());
+
+        let request = DialogueRequest::new(
+            NpcId::new(1),
+            None,
+            "Hello",
+            DialogueTopicHint::Status,
+            DialogueContext::default(),
+        );
+        let request_id = queue.enqueue(request);
+
+        assert_eq!(request_id.value(), 0);
+        assert!(!queue.is_empty());
+        assert!(queue.front_ready());
+
+        // Tick the queue and ensure it remains ready with zero cooldown.
+        queue.tick(0.5);
+        assert!(queue.front_ready());
+    }
+
+    #[test]
+    fn cancel_withdraws_only_undispatched_requests() {
+        let mut queue = DialogueRequestQueue::default();
+        let kept = queue.enqueue(ambient(1));
+        let cancelled = queue.enqueue(ambient(2));
+
+        assert!(queue.cancel(cancelled));
+        assert!(!queue.cancel(cancelled), "already gone");
+        assert!(!queue.cancel(DialogueRequestId::new(99)));
+        let pending: Vec<_> = queue.iter_pending().map(|view| view.id).collect();
+        assert_eq!(pending, [kept]);
+        let announced: Vec<_> = queue
+            .unannounced
+            .iter()
+            .map(|event| event.request_id)
+            .collect();
+        assert_eq!(
+            announced,
+            [kept],
+            "a withdrawn request never starts a conversation"
+        );
+    }
+
+    #[test]
+    fn stale_ambient_requests_expire_in_a_backlog_but_player_ones_do_not() {
+        let mut app = retry_app();
+        app.insert_resource(WorldClock::new())
+            .init_resource::<DialogueRequestTrace>();
+        let (stale, player) = {
+            let mut queue = app.world_mut().resource_mut::<DialogueRequestQueue>();
+            queue.enqueue(ambient(1));
+            let stale = queue.enqueue(stale_by(2, 0.25));
+            let mut greeting = stale_by(3, 0.25);
+            greeting.target = Some(NpcId::player());
+            (stale, queue.enqueue(greeting))
+        };
+
+        app.update();
+        let waiting: Vec<_> = app
+            .world()
+            .resource::<DialogueRequestQueue>()
+            .iter_pending()
+            .map(|view| view.id)
+            .collect();
+        assert_eq!(
+            waiting,
+            vec![stale, player],
+            "one request goes out per frame"
+        );
+
+        app.world_mut()
+            .resource_mut::<WorldClock>()
+            .set_time_of_day(0.5);
+        app.update();
+        let mut queue = app.world_mut().resource_mut::<DialogueRequestQueue>();
+        assert!(
+            queue.iter_pending().next().is_none(),
+            "the player's request went out past its deadline"
+        );
+        let expired = queue.take_expired();
+        assert_eq!(
+            expired,
+            vec![ExpiredRequestView {
+                id: stale,
+                speaker: NpcId::new(2),
+                target: Some(NpcId::new(12)),
+                topic: DialogueTopicHint::Status,
+                attempts: 0,
+                expires_at: ContextClock::new(0, 0.25),
+                dropped_at: ContextClock::new(0, 0.5),
+            }]
+        );
+        assert!(
+            queue.take_expired().is_empty(),
+            "each drop is reported once"
+        );
+
+        let trace = app.world().resource::<DialogueRequestTrace>();
+        let phases: Vec<_> = trace.get(stale).unwrap().phases().collect();
+        assert_eq!(phases, vec![TracePhase::Expired]);
+        assert!(trace.get(stale).unwrap().is_finished());
+    }
+
+    #[test]
+    fn retries_keep_the_deadline_of_their_first_attempt() {
+        let mut app = retry_app();
+        app.insert_resource(WorldClock::new());
+        app.world_mut()
+            .resource_mut::<PendingDialogueTasks>()
+            .force_next_failure();
+        let id = app
+            .world_mut()
+            .resource_mut::<DialogueRequestQueue>()
+            .enqueue(stale_by(1, 0.25));
+
+        let mut retry = None;
+        for _ in 0..500 {
+            app.update();
+            retry = app
+                .world()
+                .resource::<DialogueRequestQueue>()
+                .iter_pending()
+                .find(|view| view.attempts == 1);
+            if retry.is_some() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(2));
+        }
+        assert_eq!(
+            retry.map(|view| view.id),
+            Some(id),
+            "the failure is retried"
+        );
+
+        app.world_mut()
+            .resource_mut::<WorldClock>()
+            .set_time_of_day(0.3);
+        app.update();
+        let expired = app
+            .world_mut()
+            .resource_mut::<DialogueRequestQueue>()
+            .take_expired();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].id, id);
+        assert_eq!(expired[0].attempts, 1);
+        assert_eq!(expired[0].expires_at, ContextClock::new(0, 0.25));
+        assert_eq!(
+            app.world()
+                .resource::<PendingDialogueTasks>()
+                .in_flight_views()
+                .count(),
+            0,
+            "the stale retry was never dispatched"
+        );
+    }
+
+    #[test]
+    fn batches_take_distinct_ready_ambient_speakers() {
+        let mut queue = DialogueRequestQueue::default();
+        let first = queue.enqueue(ambient(1));
+        let player = queue.enqueue(DialogueRequest::new(
+            NpcId::new(2),
+            Some(NpcId::player()),
+            "Welcome, traveller.",
+            DialogueTopicHint::Status,
+            DialogueContext::default(),
+        ));
+        let repeat = queue.enqueue(ambient(1));
+        let other = queue.enqueue(ambient(3));
+        let cooling = queue.enqueue(ambient(4));
+        let mut limits = DialogueRateLimitState::default();
+        limits.apply_backoff(DialogueProviderKind::OpenAi, NpcId::new(4), 3.0);
+        limits.provider_remaining.clear();
+        let openai = DialogueProviderKind::OpenAi;
+
+        assert!(queue
+            .take_ready_batch(DialoguePriority::Player, 3, openai, |_| true, &limits)
+            .is_empty());
+        let batch: Vec<_> = queue
+            .take_ready_batch(DialoguePriority::Ambient, 3, openai, |_| true, &limits)
+            .into_iter()
+            .map(|queued| queued.id)
+            .collect();
+        assert_eq!(batch, [first, other]);
+        let pending: Vec<_> = queue.iter_pending().map(|view| view.id).collect();
+        assert_eq!(pending, [player, repeat, cooling]);
+
+        assert!(
+            queue
+                .take_ready_batch(DialoguePriority::Ambient, 3, openai, |_| true, &limits)
+                .is_empty(),
+            "a lone qualifying request is left for the single path"
+        );
+        assert_eq!(queue.iter_pending().count(), 3);
+    }
+}
@@ -0,0 +1,707 @@
+//! Background dialogue tasks: what is in flight, and turning finished tasks into
+//! responses, retries, dead letters, and failures, in each speaker's dispatch order when
+//! that is asked for.
+use std::collections::HashMap;
+
+use bevy::{
+    prelude::*,
+    tasks::{block_on, poll_once, Task},
+};
+
+use super::{
+    DialogueRateLimitConfig, DialogueRateLimitState, DialogueRequestQueue, InFlightRequestView,
+};
+use crate::dialogue::{
+    budget::DailyApiBudget,
+    dead_letter::{world_minute, DeadLetter, DialogueDeadLetterStore},
+    errors::{DialogueError, DialogueErrorKind},
+    events::{DialogueRequestFailedEvent, DialogueResponseEvent},
+    ordering::SpeakerSequencer,
+    trace::{RequestTracing, TracePhase},
+    types::{DialogueRequest, DialogueRequestId, DialogueResponse},
+};
+use crate::npc::components::NpcId;
+use crate::world::time::WorldClock;
+
+/// Result of a dialogue task (success or failure with retry info).
+pub(super) type DialogueTaskResult = (
+    DialogueRequestId,
+    DialogueRequest, // Original request for retry
+    Result<DialogueResponse, DialogueError>,
+    u8, // attempts
+);
+
+/// Background task (one request, or a batch of ambient ones) plus the metadata needed to
+/// describe it while it runs.
+pub(super) struct InFlightDialogueTask {
+    pub(super) views: Vec<InFlightRequestView>,
+    pub(super) task: Task<Vec<DialogueTaskResult>>,
+}
+
+/// A reply or final failure, not yet written as an event.
+enum DialogueOutcome {
+    Response(DialogueResponseEvent),
+    Failure(DialogueRequestFailedEvent),
+}
+
+/// An outcome held for the speaker's earlier requests, with the view that keeps listing the
+/// request as in flight meanwhile.
+struct HeldOutcome {
+    view: InFlightRequestView,
+    outcome: DialogueOutcome,
+}
+
+/// Resource tracking background dialogue processing tasks.
+///
+/// These tasks run blocking HTTP requests to OpenAI in a background thread pool
+/// to prevent freezing the main game thread.
+#[derive(Resource, Default)]
+pub struct PendingDialogueTasks {
+    pub(super) tasks: Vec<InFlightDialogueTask>,
+    /// Dispatches still to fail on purpose, as a provider failure.
+    forced_failures: u32,
+    /// Each unresolved request's speaker and place in that speaker's dispatch order. Set on
+    /// the first dispatch and kept through retries.
+    sequences: HashMap<DialogueRequestId, (NpcId, u64)>,
+    /// Outcomes held for `ordered_responses_per_speaker`.
+    ordering: SpeakerSequencer<HeldOutcome>,
+}
+
+impl PendingDialogueTasks {
+    /// Requests with a running task, then finished ones held for an earlier request from
+    /// the same speaker.
+    pub fn in_flight_views(&self) -> impl Iterator<Item = &InFlightRequestView> {
+        self.tasks
+            .iter()
+            .flat_map(|entry| &entry.views)
+            .chain(self.ordering.held().map(|held| &held.view))
+    }
+
+    /// Gives up the response-order places of requests withdrawn while they waited for a
+    /// retry, so their speakers' later replies are not held for them.
+    fn release_withdrawn(&mut self, withdrawn: Vec<DialogueRequestId>, now: f64) {
+        for id in withdrawn {
+            if let Some((speaker, sequence)) = self.sequences.remove(&id) {
+                self.ordering.abandon(speaker, sequence, now);
+            }
+        }
+    }
+
+    /// Numbers `id` in `speaker`'s dispatch order unless an earlier attempt already did.
+    pub(super) fn sequence(&mut self, id: DialogueRequestId, speaker: NpcId) {
+        if !self.sequences.contains_key(&id) {
+            let sequence = self.ordering.dispatch(speaker);
+            self.sequences.insert(id, (speaker, sequence));
+        }
+    }
+
+    /// Makes the next dispatched call fail as a provider failure, whatever the broker
+    /// answers. The request then takes the normal retry path.
+    #[cfg_attr(not(any(test, feature = "chaos")), allow(dead_code))]
+    pub fn force_next_failure(&mut self) {
+        self.forced_failures = self.forced_failures.saturating_add(1);
+    }
+
+    pub(super) fn take_forced_failure(&mut self) -> bool {
+        let forced = self.forced_failures > 0;
+        self.forced_failures = self.forced_failures.saturating_sub(1);
+        forced
+    }
+}
+
+/// Polls completed dialogue tasks and emits events.
+///
+/// Runs every frame to check if any background dialogue requests have finished. Each
+/// request in a finished batch is handled on its own, so failures retry individually. Only
+/// rate limits and transient provider failures are retried; permanent ones (rejected
+/// credentials, unknown models, policy refusals) fail on the first attempt.
+/// Reported token usage replaces the estimate charged to `DailyApiBudget`. Requests out of
+/// retries are kept in `DialogueDeadLetterStore`, when present, for a manual resend.
+/// Completions, failures, and retries are stamped on `DialogueRequestTrace`. With
+/// `ordered_responses_per_speaker`, response and failure events wait until the speaker's
+/// earlier requests have resolved, or `ordering_timeout_seconds` of real time have passed.
+/// A request cancelled or expired while waiting for a retry counts as resolved.
+#[allow(clippy::too_many_arguments)]
+pub fn poll_dialogue_tasks(
+    time: Option<Res<Time<Real>>>,
+    mut pending_tasks: ResMut<PendingDialogueTasks>,
+    mut queue: ResMut<DialogueRequestQueue>,
+    mut limits: ResMut<DialogueRateLimitState>,
+    mut budget: Option<ResMut<DailyApiBudget>>,
+    config: Res<DialogueRateLimitConfig>,
+    mut dead_letters: Option<ResMut<DialogueDeadLetterStore>>,
+    clock: Option<Res<WorldClock>>,
+    mut response_writer: MessageWriter<DialogueResponseEvent>,
+    mut failure_writer: MessageWriter<DialogueRequestFailedEvent>,
+    mut tracing: RequestTracing,
+) {
+    #[cfg(feature = "profiling")]
+    let _span = info_span!("poll_dialogue_tasks").entered();
+    let mut finished = Vec::new();
+    // Poll all tasks and collect completed ones
+    let mut i = 0;
+    while i < pending_tasks.tasks.len() {
+        if let Some(results) = block_on(poll_once(&mut pending_tasks.tasks[i].task)) {
+            // Task completed - remove it, keeping its views for outcomes that are held
+            let InFlightDialogueTask { views, task } = pending_tasks.tasks.swap_remove(i);
+            drop(task);
+
+            for (request_id, original_request, result, mut attempts) in results {
+                let view = views.iter().find(|view| view.id == request_id).cloned();
+                // Handle result
+                match result {
+                    Ok(response) => {
+                        tracing.finish(
+                            request_id,
+                            TracePhase::Completed {
+                                attempt: attempts.saturating_add(1),
+                            },
+                        );
+                        limits.record_success(response.provider, original_request.speaker, &config);
+                        if let Some(budget) = budget.as_deref_mut() {
+                            if response.continued {
+                                budget.record_continuation();
+                            }
+                            if let Some(tokens) = response.tokens_used {
+                                budget.reconcile(tokens);
+                            }
+                        }
+                        let outcome = DialogueOutcome::Response(DialogueResponseEvent {
+                            response,
+                            context: original_request.context,
+                        });
+                        finished.push((request_id, view, outcome));
+                    }
+                    Err(err) => {
+                        attempts = attempts.saturating_add(1);
+                        let failed = TracePhase::Failed { attempt: attempts };
+                        match err.kind {
+                            DialogueErrorKind::RateLimited {
+                                retry_after_seconds,
+                            } => {
+                                limits.apply_backoff(
+                                    err.provider,
+                                    original_request.speaker,
+                                    retry_after_seconds,
+                                );
+                            }
+                            DialogueErrorKind::ProviderFailure { .. }
+                            | DialogueErrorKind::ContextMissing { .. }
+                            | DialogueErrorKind::InvalidRequest { .. } => {
+                                limits.apply_backoff(
+                                    err.provider,
+                                    original_request.speaker,
+                                    config.retry_backoff_seconds,
+                                );
+                            }
+                        }
+
+                        if err.kind.is_retryable() && attempts <= config.max_retries {
+                            tracing.record(request_id, failed);
+                            tracing.record(
+                                request_id,
+                                TracePhase::Retried {
+                                    attempt: attempts.saturating_add(1),
+                                },
+                            );
+                            // Re-queue the original request with backoff
+                            queue.requeue_for_retry(
+                                request_id,
+                                original_request,
+                                attempts,
+                                config.retry_backoff_seconds,
+                            );
+                        } else {
+                            tracing.finish(request_id, failed);
+                            let outcome = DialogueOutcome::Failure(DialogueRequestFailedEvent {
+                                error: err.clone(),
+                                speaker: original_request.speaker,
+                                target: original_request.target,
+                            });
+                            finished.push((request_id, view, outcome));
+                            if let Some(store) = dead_letters.as_deref_mut() {
+                                let letter = DeadLetter {
+                                    id: request_id,
+                                    request: original_request,
+                                    error: err,
+                                    attempts,
+                                    failed_at_minute: clock.as_deref().map_or(0, world_minute),
+                                };
+                                if let Some(evicted) =
+                                    store.push(letter, config.dead_letter_capacity)
+                                {
+                                    debug!(
+                                        "Dead-letter store full; dropped {} after {} attempts: {}",
+                                        evicted.id, evicted.attempts, evicted.error
+                                    );
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        } else {
+            // Task still pending
+            i += 1;
+        }
+    }
+
+    let mut emit = |outcome: DialogueOutcome| match outcome {
+        DialogueOutcome::Response(event) => {
+            response_writer.write(event);
+        }
+        DialogueOutcome::Failure(event) => {
+            failure_writer.write(event);
+        }
+    };
+    let ordered = config.ordered_responses_per_speaker;
+    let now = time.map_or(0.0, |time| time.elapsed_secs_f64());
+    pending_tasks.release_withdrawn(queue.take_withdrawn(), now);
+    for (request_id, view, outcome) in finished {
+        match (pending_tasks.sequences.remove(&request_id), view) {
+            (Some((speaker, sequence)), Some(view)) if ordered => {
+                let held = HeldOutcome { view, outcome };
+                pending_tasks.ordering.push(speaker, sequence, held, now);
+            }
+            _ => emit(outcome),
+        }
+    }
+    if ordered {
+        let skipped = pending_tasks.ordering.skipped();
+        let timeout = f64::from(config.ordering_timeout_seconds);
+        for held in pending_tasks.ordering.poll(now, timeout) {
+            emit(held.outcome);
+        }
+        let gave_up = pending_tasks.ordering.skipped() - skipped;
+        if gave_up > 0 {
+            warn!(
+                "Released dialogue outcomes out of order after {timeout:.1}s; {gave_up} earlier \
+                 request(s) still unresolved"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use bevy::tasks::{AsyncComputeTaskPool, TaskPool};
+
+    use super::super::{run_dialogue_request_queue, test_support::*, DialogueSpeakerProfiles};
+    use super::*;
+    use crate::dialogue::{
+        broker::DialogueProviderKind, router::DialogueProviderRouter, types::ContextClock,
+        validation::DialogueValidationConfig,
+    };
+    use crate::npc::spatial::NpcIndex;
+
+    #[test]
+    fn exhausted_requests_land_in_the_dead_letter_store_and_can_be_resent() {
+        AsyncComputeTaskPool::get_or_init(TaskPool::new);
+        let failing = NpcId::new(1);
+        let mut app = App::new();
+        app.init_resource::<DialogueRequestQueue>()
+            .init_resource::<DialogueRateLimitState>()
+            .insert_resource(DialogueRateLimitConfig {
+                global_cooldown_seconds: 0.0,
+                per_npc_cooldown_seconds: 0.0,
+                max_retries: 0,
+                retry_backoff_seconds: 0.0,
+                ..default()
+            })
+            .init_resource::<DialogueValidationConfig>()
+            .init_resource::<DialogueSpeakerProfiles>()
+            .init_resource::<PendingDialogueTasks>()
+            .init_resource::<NpcIndex>()
+            .init_resource::<DialogueDeadLetterStore>()
+            .insert_resource(DialogueProviderRouter::new(Box::new(GatedBroker {
+                release: Arc::new(AtomicBool::new(true)),
+                failing_speaker: failing,
+            })))
+            .add_message::<DialogueRequestFailedEvent>()
+            .add_message::<DialogueResponseEvent>()
+            .add_systems(
+                Update,
+                (run_dialogue_request_queue, poll_dialogue_tasks).chain(),
+            );
+        let mut failures = app
+            .world()
+            .resource::<Messages<DialogueRequestFailedEvent>>()
+            .get_cursor();
+        let mut responses = app
+            .world()
+            .resource::<Messages<DialogueResponseEvent>>()
+            .get_cursor();
+        let first = app
+            .world_mut()
+            .resource_mut::<DialogueRequestQueue>()
+            .enqueue(ambient(1));
+
+        let mut failed = Vec::new();
+        for _ in 0..500 {
+            app.update();
+            let messages = app
+                .world()
+                .resource::<Messages<DialogueRequestFailedEvent>>();
+            failed.extend(failures.read(messages).map(|event| event.error.request_id));
+            if !failed.is_empty() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(2));
+        }
+        assert_eq!(failed, vec![first], "the failure event still goes out");
+        let store = app.world().resource::<DialogueDeadLetterStore>();
+        assert_eq!(store.len(), 1);
+        let letter = store.iter().next().unwrap();
+        assert_eq!((letter.id, letter.attempts), (first, 1));
+        assert_eq!(letter.request.speaker, failing);
+        assert!(matches!(
+            letter.error.kind,
+            DialogueErrorKind::ProviderFailure { .. }
+        ));
+
+        // The provider recovers; the letter goes back through the normal queue.
+        app.insert_resource(DialogueProviderRouter::new(Box::new(GatedBroker {
+            release: Arc::new(AtomicBool::new(true)),
+            failing_speaker: NpcId::new(99),
+        })));
+        let world = app.world_mut();
+        let summary = world.resource_scope(|world, mut store: Mut<DialogueDeadLetterStore>| {
+            let mut queue = world.resource_mut::<DialogueRequestQueue>();
+            store.resubmit(&mut queue, 0, 60, |_| true)
+        });
+        assert_eq!(summary.resent, 1);
+
+        let mut answered = Vec::new();
+        for _ in 0..500 {
+            app.update();
+            let messages = app.world().resource::<Messages<DialogueResponseEvent>>();
+            answered.extend(responses.read(messages).map(|event| event.response.speaker));
+            if !answered.is_empty() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(2));
+        }
+        assert_eq!(answered, vec![failing]);
+        assert!(app.world().resource::<DialogueDeadLetterStore>().is_empty());
+    }
+
+    #[test]
+    fn batch_fans_out_and_retries_missing_entries_alone() {
+        AsyncComputeTaskPool::get_or_init(TaskPool::new);
+        let release = Arc::new(AtomicBool::new(false));
+        let failing = NpcId::new(3);
+
+        let mut app = App::new();
+        app.init_resource::<DialogueRequestQueue>()
+            .init_resource::<DialogueRateLimitState>()
+            .init_resource::<DialogueRateLimitConfig>()
+            .init_resource::<DialogueValidationConfig>()
+            .init_resource::<DialogueSpeakerProfiles>()
+            .init_resource::<PendingDialogueTasks>()
+            .init_resource::<NpcIndex>()
+            .insert_resource(DialogueProviderRouter::new(Box::new(BatchingBroker {
+                release: release.clone(),
+                failing_speaker: failing,
+            })))
+            .add_message::<DialogueRequestFailedEvent>()
+            .add_message::<DialogueResponseEvent>()
+            .add_systems(
+                Update,
+                (run_dialogue_request_queue, poll_dialogue_tasks).chain(),
+            );
+
+        let mut queue = app.world_mut().resource_mut::<DialogueRequestQueue>();
+        let answered = queue.enqueue(ambient(1));
+        let missing = queue.enqueue(ambient(3));
+
+        app.update();
+        let in_flight: Vec<_> = app
+            .world()
+            .resource::<PendingDialogueTasks>()
+            .in_flight_views()
+            .map(|view| view.id)
+            .collect();
+        assert_eq!(in_flight, [answered, missing], "one task carries both");
+
+        release.store(true, Ordering::Release);
+        for _ in 0..500 {
+            app.update();
+            if app
+                .world()
+                .resource::<PendingDialogueTasks>()
+                .in_flight_views()
+                .next()
+                .is_none()
+            {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(2));
+        }
+
+        let world = app.world();
+        let responses = world.resource::<Messages<DialogueResponseEvent>>();
+        let ids: Vec<_> = responses
+            .get_cursor()
+            .read(responses)
+            .map(|event| event.response.request_id)
+            .collect();
+        assert_eq!(ids, [answered]);
+        let failures = world.resource::<Messages<DialogueRequestFailedEvent>>();
+        assert!(failures.is_empty());
+        let pending: Vec<_> = world
+            .resource::<DialogueRequestQueue>()
+            .iter_pending()
+            .map(|view| (view.id, view.attempts))
+            .collect();
+        assert_eq!(
+            pending,
+            [(missing, 1)],
+            "missing entry is retried on its own"
+        );
+    }
+
+    #[test]
+    fn permanent_failures_skip_retries_and_auth_turns_live_calls_off() {
+        use crate::core::config::ConfigReloadRequested;
+        use crate::dialogue::{
+            errors::ProviderFailureClass,
+            status::{
+                clear_misconfigured_providers_on_reload, credentials_source,
+                flag_misconfigured_providers, DialogueBrokerStatus, DialogueConnectionState,
+            },
+        };
+
+        /// Enqueues an ambient request and runs until it is answered or fails, returning
+        /// how many of each happened.
+        fn settle(app: &mut App, speaker: u64) -> (usize, usize) {
+            let mut responses = app
+                .world()
+                .resource::<Messages<DialogueResponseEvent>>()
+                .get_cursor_current();
+            let mut failures = app
+                .world()
+                .resource::<Messages<DialogueRequestFailedEvent>>()
+                .get_cursor_current();
+            app.world_mut()
+                .resource_mut::<DialogueRequestQueue>()
+                .enqueue(ambient(speaker));
+            let (mut answered, mut failed) = (0, 0);
+            for _ in 0..500 {
+                app.update();
+                let world = app.world();
+                answered += responses
+                    .read(world.resource::<Messages<DialogueResponseEvent>>())
+                    .count();
+                failed += failures
+                    .read(world.resource::<Messages<DialogueRequestFailedEvent>>())
+                    .count();
+                let idle = world.resource::<DialogueRequestQueue>().is_empty()
+                    && world
+                        .resource::<PendingDialogueTasks>()
+                        .in_flight_views()
+                        .next()
+                        .is_none();
+                if idle && answered + failed > 0 {
+                    break;
+                }
+                std::thread::sleep(Duration::from_millis(2));
+            }
+            (answered, failed)
+        }
+
+        for (class, flags) in [
+            (ProviderFailureClass::Auth, true),
+            (ProviderFailureClass::Policy, false),
+        ] {
+            let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+            let mut app = retry_app();
+            app.insert_resource(DialogueProviderRouter::new(Box::new(RejectingBroker {
+                class,
+                calls: calls.clone(),
+            })))
+            .insert_resource(DialogueBrokerStatus::new(
+                DialogueProviderKind::OpenAi,
+                DialogueConnectionState::Live,
+            ))
+            .add_message::<ConfigReloadRequested>()
+            .add_systems(
+                Update,
+                (
+                    clear_misconfigured_providers_on_reload.before(run_dialogue_request_queue),
+                    flag_misconfigured_providers.after(poll_dialogue_tasks),
+                ),
+            );
+
+            assert_eq!(settle(&mut app, 1), (0, 1), "{class:?} fails at once");
+            assert_eq!(calls.load(Ordering::SeqCst), 1, "{class:?} is not retried");
+            let state = app
+                .world()
+                .resource::<DialogueBrokerStatus>()
+                .connection_state();
+            assert_eq!(state == DialogueConnectionState::Misconfigured, flags);
+
+            if flags {
+                assert_eq!(
+                    settle(&mut app, 2),
+                    (1, 0),
+                    "a misconfigured provider answers with fallback replies"
+                );
+                assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+                app.world_mut().write_message(ConfigReloadRequested {
+                    path: credentials_source(DialogueProviderKind::OpenAi),
+                });
+                app.update();
+                assert_eq!(
+                    app.world()
+                        .resource::<DialogueBrokerStatus>()
+                        .connection_state(),
+                    DialogueConnectionState::Live
+                );
+            } else {
+                assert_eq!(settle(&mut app, 2), (0, 1));
+                assert_eq!(calls.load(Ordering::SeqCst), 2);
+            }
+        }
+    }
+
+    /// Pending tasks holding finished replies to `finished`, listed in the given order,
+    /// after numbering every `(request, speaker)` in `dispatched` in its speaker's
+    /// dispatch order.
+    fn finished_tasks(
+        finished: &[DialogueRequestId],
+        dispatched: &[(DialogueRequestId, u64)],
+    ) -> PendingDialogueTasks {
+        let mut pending = PendingDialogueTasks::default();
+        for &(id, speaker) in dispatched {
+            pending.sequence(id, NpcId::new(speaker));
+        }
+        for &id in finished {
+            let (_, speaker) = dispatched
+                .iter()
+                .find(|(request, _)| *request == id)
+                .unwrap();
+            let request = ambient(*speaker);
+            let view = InFlightRequestView {
+                id,
+                speaker: request.speaker,
+                target: request.target,
+                topic: request.topic_hint,
+                attempts: 0,
+            };
+            let response = DialogueResponse::new(
+                id,
+                DialogueProviderKind::OpenAi,
+                request.speaker,
+                request.target,
+                "Fine weather.",
+            );
+            let task = AsyncComputeTaskPool::get()
+                .spawn(async move { vec![(id, request, Ok(response), 0)] });
+            pending.tasks.push(InFlightDialogueTask {
+                views: vec![view],
+                task,
+            });
+        }
+        while !pending.tasks.iter().all(|entry| entry.task.is_finished()) {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        pending
+    }
+
+    #[test]
+    fn ordered_responses_follow_each_speakers_dispatch_order() {
+        let first = DialogueRequestId::new(0);
+        let second = DialogueRequestId::new(1);
+        let other = DialogueRequestId::new(2);
+        // `poll_dialogue_tasks` swap-removes finished tasks, so these are polled as
+        // `second`, `first`, `other`.
+        let finished = [second, other, first];
+        let dispatched = [(first, 1), (other, 2), (second, 1)];
+
+        let answered_with = |ordered: bool, pending: PendingDialogueTasks| {
+            let mut app = retry_app();
+            app.insert_resource(DialogueRateLimitConfig {
+                ordered_responses_per_speaker: ordered,
+                ..default()
+            })
+            .insert_resource(pending);
+            app.update();
+            let answered: Vec<_> = app
+                .world()
+                .resource::<Messages<DialogueResponseEvent>>()
+                .iter_current_update_messages()
+                .map(|event| event.response.request_id)
+                .collect();
+            let tasks = app.world().resource::<PendingDialogueTasks>();
+            assert!(tasks.sequences.is_empty());
+            assert_eq!(tasks.in_flight_views().count(), 0);
+            answered
+        };
+
+        AsyncComputeTaskPool::get_or_init(TaskPool::new);
+        assert_eq!(
+            answered_with(false, finished_tasks(&finished, &dispatched)),
+            vec![second, first, other],
+            "without the flag replies go out as polled"
+        );
+        assert_eq!(
+            answered_with(true, finished_tasks(&finished, &dispatched)),
+            vec![first, second, other]
+        );
+
+        // Until the earlier request resolves, the later one stays listed as in flight.
+        let mut app = retry_app();
+        app.insert_resource(DialogueRateLimitConfig {
+            ordered_responses_per_speaker: true,
+            ..default()
+        })
+        .insert_resource(finished_tasks(&[second], &[(first, 1), (second, 1)]));
+        app.update();
+        let world = app.world();
+        assert!(world
+            .resource::<Messages<DialogueResponseEvent>>()
+            .is_empty());
+        let in_flight: Vec<_> = world
+            .resource::<PendingDialogueTasks>()
+            .in_flight_views()
+            .map(|view| view.id)
+            .collect();
+        assert_eq!(in_flight, vec![second]);
+    }
+
+    #[test]
+    fn an_expired_retry_does_not_hold_up_its_speakers_next_reply() {
+        let first = DialogueRequestId::new(0);
+        let second = DialogueRequestId::new(1);
+        let mut app = retry_app();
+        app.insert_resource(DialogueRateLimitConfig {
+            ordered_responses_per_speaker: true,
+            ..default()
+        })
+        .insert_resource(WorldClock::new())
+        .insert_resource(finished_tasks(&[second], &[(first, 1), (second, 1)]));
+        // `first` failed once and waits for its retry, which has gone stale.
+        let mut stale = ambient(1);
+        stale.expires_at = Some(ContextClock::new(0, 0.0));
+        app.world_mut()
+            .resource_mut::<DialogueRequestQueue>()
+            .requeue_for_retry(first, stale, 1, 0.0);
+        app.update();
+
+        let world = app.world();
+        let answered: Vec<_> = world
+            .resource::<Messages<DialogueResponseEvent>>()
+            .iter_current_update_messages()
+            .map(|event| event.response.request_id)
+            .collect();
+        assert_eq!(answered, [second], "no wait for the ordering timeout");
+        let tasks = world.resource::<PendingDialogueTasks>();
+        assert!(tasks.sequences.is_empty());
+        assert_eq!(tasks.ordering.skipped(), 0);
+    }
+}
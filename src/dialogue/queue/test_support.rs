@@ -0,0 +1,437 @@
+//! Brokers and headless apps shared by the dialogue queue tests.
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::tasks::{AsyncComputeTaskPool, TaskPool};
+
+use super::{
+    poll_dialogue_tasks, run_dialogue_request_queue, DialogueRateLimitConfig,
+    DialogueRateLimitState, DialogueRequestQueue, DialogueSpeakerProfiles, PendingDialogueTasks,
+};
+use crate::dialogue::{
+    broker::{DialogueBroker, DialogueProviderKind, OpenAiDialogueBroker},
+    budget::DailyApiBudget,
+    errors::{DialogueError, DialogueErrorKind},
+    events::{DialogueRequestFailedEvent, DialogueResponseEvent},
+    router::DialogueProviderRouter,
+    status::DialogueConnectionState,
+    types::{
+        ContextClock, DialogueContext, DialogueRequest, DialogueRequestId, DialogueResponse,
+        DialogueTopicHint,
+    },
+    validation::DialogueValidationConfig,
+};
+use crate::npc::{components::NpcId, spatial::NpcIndex};
+
+pub struct UnreachableBroker;
+
+impl DialogueBroker for UnreachableBroker {
+    fn provider_kind(&self) -> DialogueProviderKind {
+        DialogueProviderKind::OpenAi
+    }
+
+    fn connection_state(&self) -> DialogueConnectionState {
+        DialogueConnectionState::Fallback
+    }
+
+    fn process(
+        &self,
+        _request_id: DialogueRequestId,
+        _request: &DialogueRequest,
+    ) -> Result<DialogueResponse, DialogueError> {
+        panic!("invalid requests must not reach the broker");
+    }
+}
+
+/// Blocks until released, then fails requests from `failing_speaker` and answers the rest.
+pub struct GatedBroker {
+    pub release: Arc<AtomicBool>,
+    pub failing_speaker: NpcId,
+}
+
+impl DialogueBroker for GatedBroker {
+    fn provider_kind(&self) -> DialogueProviderKind {
+        DialogueProviderKind::OpenAi
+    }
+
+    fn connection_state(&self) -> DialogueConnectionState {
+        DialogueConnectionState::Fallback
+    }
+
+    fn process(
+        &self,
+        request_id: DialogueRequestId,
+        request: &DialogueRequest,
+    ) -> Result<DialogueResponse, DialogueError> {
+        while !self.release.load(Ordering::Acquire) {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        if request.speaker == self.failing_speaker {
+            return Err(DialogueError::new(
+                request_id,
+                self.provider_kind(),
+                DialogueErrorKind::provider_failure("offline"),
+            ));
+        }
+        Ok(DialogueResponse::new(
+            request_id,
+            self.provider_kind(),
+            request.speaker,
+            request.target,
+            "Hello there.",
+        ))
+    }
+}
+
+pub fn ambient(speaker: u64) -> DialogueRequest {
+    DialogueRequest::new(
+        NpcId::new(speaker),
+        Some(NpcId::new(speaker + 10)),
+        "Fine weather.",
+        DialogueTopicHint::Status,
+        DialogueContext::default(),
+    )
+}
+
+/// A queue and poll loop with no cooldowns or retry backoff, answering from the
+/// fallback broker.
+pub fn retry_app() -> App {
+    AsyncComputeTaskPool::get_or_init(TaskPool::new);
+    let mut app = App::new();
+    app.init_resource::<DialogueRequestQueue>()
+        .init_resource::<DialogueRateLimitState>()
+        .insert_resource(DialogueRateLimitConfig {
+            global_cooldown_seconds: 0.0,
+            per_npc_cooldown_seconds: 0.0,
+            retry_backoff_seconds: 0.0,
+            ..default()
+        })
+        .init_resource::<DialogueValidationConfig>()
+        .init_resource::<DialogueSpeakerProfiles>()
+        .init_resource::<PendingDialogueTasks>()
+        .init_resource::<NpcIndex>()
+        .insert_resource(DialogueProviderRouter::new(Box::new(
+            OpenAiDialogueBroker::fallback(),
+        )))
+        .add_message::<DialogueRequestFailedEvent>()
+        .add_message::<DialogueResponseEvent>()
+        .add_systems(
+            Update,
+            (run_dialogue_request_queue, poll_dialogue_tasks).chain(),
+        );
+    app
+}
+
+/// Enqueues one ambient request and runs until it is answered, returning the attempt
+/// numbers seen in flight.
+pub fn attempts_until_answered(app: &mut App) -> Vec<u8> {
+    let mut responses = app
+        .world()
+        .resource::<Messages<DialogueResponseEvent>>()
+        .get_cursor_current();
+    let request_id = app
+        .world_mut()
+        .resource_mut::<DialogueRequestQueue>()
+        .enqueue(ambient(1));
+
+    let mut attempts_in_flight = Vec::new();
+    let mut answered = Vec::new();
+    for _ in 0..500 {
+        app.update();
+        attempts_in_flight.extend(
+            app.world()
+                .resource::<PendingDialogueTasks>()
+                .in_flight_views()
+                .map(|view| view.attempts),
+        );
+        let messages = app.world().resource::<Messages<DialogueResponseEvent>>();
+        answered.extend(
+            responses
+                .read(messages)
+                .map(|event| event.response.request_id),
+        );
+        if !answered.is_empty() {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(2));
+    }
+
+    assert_eq!(answered, vec![request_id]);
+    assert!(app
+        .world()
+        .resource::<Messages<DialogueRequestFailedEvent>>()
+        .is_empty());
+    attempts_in_flight.dedup();
+    attempts_in_flight
+}
+
+/// An ambient request from `speaker` that goes stale at `time_of_day` on day 0.
+pub fn stale_by(speaker: u64, time_of_day: f32) -> DialogueRequest {
+    let mut request = ambient(speaker);
+    request.expires_at = Some(ContextClock::new(0, time_of_day));
+    request
+}
+
+/// Answers everything locally under the given provider, so routing shows up in replies.
+pub struct FabricatingBroker(pub DialogueProviderKind);
+
+impl DialogueBroker for FabricatingBroker {
+    fn provider_kind(&self) -> DialogueProviderKind {
+        self.0
+    }
+
+    fn connection_state(&self) -> DialogueConnectionState {
+        DialogueConnectionState::Fallback
+    }
+
+    fn process(
+        &self,
+        request_id: DialogueRequestId,
+        request: &DialogueRequest,
+    ) -> Result<DialogueResponse, DialogueError> {
+        Ok(self.fabricate(request_id, request))
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct AnsweredBy(pub Vec<(NpcId, DialogueProviderKind)>);
+
+pub fn collect_providers(
+    mut responses: MessageReader<DialogueResponseEvent>,
+    mut answered: ResMut<AnsweredBy>,
+) {
+    answered.0.extend(
+        responses
+            .read()
+            .map(|event| (event.response.speaker, event.response.provider)),
+    );
+}
+
+/// Batches up to three requests, failing any from `failing_speaker`.
+pub struct BatchingBroker {
+    pub release: Arc<AtomicBool>,
+    pub failing_speaker: NpcId,
+}
+
+impl DialogueBroker for BatchingBroker {
+    fn provider_kind(&self) -> DialogueProviderKind {
+        DialogueProviderKind::OpenAi
+    }
+
+    fn connection_state(&self) -> DialogueConnectionState {
+        DialogueConnectionState::Live
+    }
+
+    fn process(
+        &self,
+        _request_id: DialogueRequestId,
+        _request: &DialogueRequest,
+    ) -> Result<DialogueResponse, DialogueError> {
+        panic!("batchable requests must go through process_batch");
+    }
+
+    fn max_batch_size(&self) -> usize {
+        3
+    }
+
+    fn process_batch(
+        &self,
+        batch: &[(DialogueRequestId, DialogueRequest)],
+    ) -> Vec<Result<DialogueResponse, DialogueError>> {
+        while !self.release.load(Ordering::Acquire) {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        batch
+            .iter()
+            .map(|(request_id, request)| {
+                if request.speaker == self.failing_speaker {
+                    return Err(DialogueError::new(
+                        *request_id,
+                        self.provider_kind(),
+                        DialogueErrorKind::provider_failure("missing from batch"),
+                    ));
+                }
+                Ok(DialogueResponse::new(
+                    *request_id,
+                    self.provider_kind(),
+                    request.speaker,
+                    request.target,
+                    "Lovely day.",
+                ))
+            })
+            .collect()
+    }
+}
+
+/// Live broker that answers every call with a fixed line and reported usage.
+pub struct MeteredBroker {
+    pub calls: Arc<std::sync::atomic::AtomicUsize>,
+}
+
+impl DialogueBroker for MeteredBroker {
+    fn provider_kind(&self) -> DialogueProviderKind {
+        DialogueProviderKind::OpenAi
+    }
+
+    fn connection_state(&self) -> DialogueConnectionState {
+        DialogueConnectionState::Live
+    }
+
+    fn process(
+        &self,
+        request_id: DialogueRequestId,
+        request: &DialogueRequest,
+    ) -> Result<DialogueResponse, DialogueError> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        Ok(DialogueResponse::new(
+            request_id,
+            self.provider_kind(),
+            request.speaker,
+            request.target,
+            "Live line.",
+        )
+        .with_tokens_used(Some(120)))
+    }
+}
+
+/// Queue app around a live `broker` with a daily budget of `max_requests` calls.
+pub fn live_budget_app(max_requests: u32, broker: Box<dyn DialogueBroker>) -> App {
+    use crate::dialogue::{
+        budget::{refresh_daily_api_budget, ApiBudgetLimits},
+        events::ApiBudgetExhaustedEvent,
+        status::{DialogueBrokerStatus, DialogueConnectionState},
+    };
+
+    AsyncComputeTaskPool::get_or_init(TaskPool::new);
+    let mut app = App::new();
+    app.init_resource::<DialogueRequestQueue>()
+        .init_resource::<DialogueRateLimitState>()
+        .insert_resource(DialogueRateLimitConfig {
+            global_cooldown_seconds: 0.0,
+            per_npc_cooldown_seconds: 0.0,
+            ..default()
+        })
+        .init_resource::<DialogueValidationConfig>()
+        .init_resource::<DialogueSpeakerProfiles>()
+        .init_resource::<PendingDialogueTasks>()
+        .init_resource::<NpcIndex>()
+        .insert_resource(DailyApiBudget::new(ApiBudgetLimits {
+            max_requests,
+            max_tokens: 10_000,
+            player_reserve: 0.0,
+        }))
+        .insert_resource(DialogueBrokerStatus::new(
+            DialogueProviderKind::OpenAi,
+            DialogueConnectionState::Live,
+        ))
+        .insert_resource(DialogueProviderRouter::new(broker))
+        .add_message::<DialogueRequestFailedEvent>()
+        .add_message::<DialogueResponseEvent>()
+        .add_message::<ApiBudgetExhaustedEvent>()
+        .add_systems(
+            Update,
+            (
+                run_dialogue_request_queue,
+                refresh_daily_api_budget,
+                poll_dialogue_tasks,
+            )
+                .chain(),
+        );
+    app
+}
+
+/// Lines answered for every queued request, with their reported usage.
+pub fn answer_all(app: &mut App, speakers: u64) -> Vec<(String, Option<u32>)> {
+    for speaker in 1..=speakers {
+        app.world_mut()
+            .resource_mut::<DialogueRequestQueue>()
+            .enqueue(ambient(speaker));
+    }
+
+    let mut lines = Vec::new();
+    let mut cursor = app
+        .world()
+        .resource::<Messages<DialogueResponseEvent>>()
+        .get_cursor();
+    for _ in 0..500 {
+        app.update();
+        let messages = app.world().resource::<Messages<DialogueResponseEvent>>();
+        lines.extend(
+            cursor
+                .read(messages)
+                .map(|event| (event.response.content.clone(), event.response.tokens_used)),
+        );
+        if lines.len() == speakers as usize {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(2));
+    }
+    lines
+}
+
+/// Live broker that reports whether each call was allowed a continuation, and takes
+/// it when allowed.
+pub struct ContinuingBroker {
+    pub allowed: Arc<Mutex<Vec<bool>>>,
+}
+
+impl DialogueBroker for ContinuingBroker {
+    fn provider_kind(&self) -> DialogueProviderKind {
+        DialogueProviderKind::OpenAi
+    }
+
+    fn connection_state(&self) -> DialogueConnectionState {
+        DialogueConnectionState::Live
+    }
+
+    fn process(
+        &self,
+        request_id: DialogueRequestId,
+        request: &DialogueRequest,
+    ) -> Result<DialogueResponse, DialogueError> {
+        self.allowed
+            .lock()
+            .unwrap()
+            .push(request.allow_continuation);
+        Ok(DialogueResponse::new(
+            request_id,
+            self.provider_kind(),
+            request.speaker,
+            request.target,
+            "Live line, finished.",
+        )
+        .with_tokens_used(Some(300))
+        .with_continued(request.allow_continuation))
+    }
+}
+
+/// Live broker that rejects every call with `class`.
+pub struct RejectingBroker {
+    pub class: crate::dialogue::errors::ProviderFailureClass,
+    pub calls: Arc<std::sync::atomic::AtomicUsize>,
+}
+
+impl DialogueBroker for RejectingBroker {
+    fn provider_kind(&self) -> DialogueProviderKind {
+        DialogueProviderKind::OpenAi
+    }
+
+    fn connection_state(&self) -> DialogueConnectionState {
+        DialogueConnectionState::Live
+    }
+
+    fn process(
+        &self,
+        request_id: DialogueRequestId,
+        _request: &DialogueRequest,
+    ) -> Result<DialogueResponse, DialogueError> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        Err(DialogueError::new(
+            request_id,
+            self.provider_kind(),
+            DialogueErrorKind::classified_failure(self.class, "rejected"),
+        ))
+    }
+}
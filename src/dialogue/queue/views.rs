@@ -0,0 +1,312 @@
+//! Read-only snapshots of queued, in-flight, and expired requests, and the queue dump
+//! table built from them.
+use bevy::prelude::*;
+
+use super::{DialogueRateLimitState, DialogueRequestQueue, PendingDialogueTasks};
+use crate::dialogue::types::{ContextClock, DialogueRequestId, DialogueTopicHint};
+use crate::npc::components::NpcId;
+
+/// Read-only snapshot of a queued request, for debugging stalls.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingRequestView {
+    /// The request's id.
+    pub id: DialogueRequestId,
+    /// Who would say the line.
+    pub speaker: NpcId,
+    /// Who it is addressed to, if anyone.
+    pub target: Option<NpcId>,
+    /// What it is about.
+    pub topic: DialogueTopicHint,
+    /// Sends made so far.
+    pub attempts: u8,
+    /// Seconds of retry backoff left before it may be sent again.
+    pub cooldown_remaining: f32,
+}
+
+/// Read-only snapshot of a request currently being processed by the broker.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InFlightRequestView {
+    /// The request's id.
+    pub id: DialogueRequestId,
+    /// Who would say the line.
+    pub speaker: NpcId,
+    /// Who it is addressed to, if anyone.
+    pub target: Option<NpcId>,
+    /// What it is about.
+    pub topic: DialogueTopicHint,
+    /// Sends made so far.
+    pub attempts: u8,
+}
+
+/// An ambient request the queue dropped undispatched because the world clock passed its
+/// `expires_at`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpiredRequestView {
+    /// The request's id.
+    pub id: DialogueRequestId,
+    /// Who would say the line.
+    pub speaker: NpcId,
+    /// Who it is addressed to, if anyone.
+    pub target: Option<NpcId>,
+    /// What it is about.
+    pub topic: DialogueTopicHint,
+    /// Sends made so far.
+    pub attempts: u8,
+    /// The deadline it missed.
+    pub expires_at: ContextClock,
+    /// The world clock reading when it was dropped.
+    pub dropped_at: ContextClock,
+}
+
+/// Point-in-time view of queued and in-flight requests plus rate-limit cooldowns.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DialogueQueueDump {
+    /// Queued requests, front first.
+    pub pending: Vec<PendingRequestView>,
+    /// Requests a broker is working on.
+    pub in_flight: Vec<InFlightRequestView>,
+    /// Longest global cooldown across providers.
+    pub global_cooldown_remaining: f32,
+    /// Per-NPC cooldowns still running, sorted by NPC id.
+    pub npc_cooldowns: Vec<(NpcId, f32)>,
+}
+
+impl DialogueQueueDump {
+    pub(crate) fn capture(
+        queue: &DialogueRequestQueue,
+        tasks: &PendingDialogueTasks,
+        limits: &DialogueRateLimitState,
+    ) -> Self {
+        let mut npc_cooldowns: Vec<(NpcId, f32)> = limits
+            .npc_remaining
+            .iter()
+            .filter(|(_, remaining)| **remaining > 0.0)
+            .map(|(npc, remaining)| (*npc, *remaining))
+            .collect();
+        npc_cooldowns.sort_by_key(|(npc, _)| *npc);
+
+        Self {
+            pending: queue.iter_pending().collect(),
+            in_flight: tasks.in_flight_views().cloned().collect(),
+            global_cooldown_remaining: limits
+                .provider_remaining
+                .values()
+                .copied()
+                .fold(0.0, f32::max),
+            npc_cooldowns,
+        }
+    }
+
+    /// Multi-line table suitable for the log.
+    pub fn format_table(&self) -> String {
+        let mut lines = vec![format!(
+            "Dialogue queue: {} pending, {} in flight, global cooldown {:.2}s",
+            self.pending.len(),
+            self.in_flight.len(),
+            self.global_cooldown_remaining
+        )];
+        lines.push(format!(
+            "  {:<9} {:>5}  {:<9} {:<9} {:<8} {:>8} {:>9}",
+            "state", "id", "speaker", "target", "topic", "attempts", "cooldown"
+        ));
+        for view in &self.pending {
+            lines.push(format_row(
+                "pending",
+                view.id,
+                view.speaker,
+                view.target,
+                view.topic,
+                view.attempts,
+                Some(view.cooldown_remaining),
+            ));
+        }
+        for view in &self.in_flight {
+            lines.push(format_row(
+                "in-flight",
+                view.id,
+                view.speaker,
+                view.target,
+                view.topic,
+                view.attempts,
+                None,
+            ));
+        }
+        for (npc, remaining) in &self.npc_cooldowns {
+            lines.push(format!("  npc cooldown {npc}: {remaining:.2}s"));
+        }
+        lines.join("\n")
+    }
+}
+
+fn format_row(
+    state: &str,
+    id: DialogueRequestId,
+    speaker: NpcId,
+    target: Option<NpcId>,
+    topic: DialogueTopicHint,
+    attempts: u8,
+    cooldown: Option<f32>,
+) -> String {
+    let target = target.map_or_else(|| "-".to_string(), |npc| npc.to_string());
+    let cooldown = cooldown.map_or_else(|| "-".to_string(), |seconds| format!("{seconds:.2}s"));
+    format!(
+        "  {:<9} {:>5}  {:<9} {:<9} {:<8} {:>8} {:>9}",
+        state,
+        id.value(),
+        speaker.to_string(),
+        target,
+        topic.label(),
+        attempts,
+        cooldown
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use bevy::tasks::{AsyncComputeTaskPool, TaskPool};
+
+    use super::super::{
+        poll_dialogue_tasks, run_dialogue_request_queue, test_support::GatedBroker,
+        DialogueRateLimitConfig,
+    };
+    use super::*;
+    use crate::dialogue::queue::DialogueSpeakerProfiles;
+    use crate::dialogue::{
+        events::{DialogueRequestFailedEvent, DialogueResponseEvent},
+        router::DialogueProviderRouter,
+        types::{DialogueContext, DialogueRequest},
+        validation::DialogueValidationConfig,
+    };
+    use crate::npc::spatial::NpcIndex;
+
+    #[test]
+    fn views_follow_requests_through_dispatch_retry_and_completion() {
+        AsyncComputeTaskPool::get_or_init(TaskPool::new);
+        let release = Arc::new(AtomicBool::new(false));
+        let failing = NpcId::new(1);
+        let answered = NpcId::new(3);
+
+        let mut app = App::new();
+        app.init_resource::<DialogueRequestQueue>()
+            .init_resource::<DialogueRateLimitState>()
+            .init_resource::<DialogueRateLimitConfig>()
+            .init_resource::<DialogueValidationConfig>()
+            .init_resource::<DialogueSpeakerProfiles>()
+            .init_resource::<PendingDialogueTasks>()
+            .init_resource::<NpcIndex>()
+            .insert_resource(DialogueProviderRouter::new(Box::new(GatedBroker {
+                release: release.clone(),
+                failing_speaker: failing,
+            })))
+            .add_message::<DialogueRequestFailedEvent>()
+            .add_message::<DialogueResponseEvent>()
+            .add_systems(
+                Update,
+                (run_dialogue_request_queue, poll_dialogue_tasks).chain(),
+            );
+
+        let mut queue = app.world_mut().resource_mut::<DialogueRequestQueue>();
+        let first = queue.enqueue(DialogueRequest::new(
+            failing,
+            Some(NpcId::new(2)),
+            "Morning!",
+            DialogueTopicHint::Status,
+            DialogueContext::default(),
+        ));
+        let second = queue.enqueue(DialogueRequest::new(
+            answered,
+            None,
+            "Fine weather.",
+            DialogueTopicHint::Status,
+            DialogueContext::default(),
+        ));
+        let pending: Vec<_> = queue.iter_pending().map(|view| view.id).collect();
+        assert_eq!(pending, vec![first, second]);
+
+        app.update();
+        let world = app.world();
+        let pending: Vec<_> = world
+            .resource::<DialogueRequestQueue>()
+            .iter_pending()
+            .collect();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].id, second);
+        assert_eq!(pending[0].speaker, answered);
+        assert_eq!(pending[0].attempts, 0);
+        let in_flight: Vec<_> = world
+            .resource::<PendingDialogueTasks>()
+            .in_flight_views()
+            .cloned()
+            .collect();
+        assert_eq!(
+            in_flight,
+            vec![InFlightRequestView {
+                id: first,
+                speaker: failing,
+                target: Some(NpcId::new(2)),
+                topic: DialogueTopicHint::Status,
+                attempts: 0,
+            }]
+        );
+
+        app.update();
+        assert!(app
+            .world()
+            .resource::<DialogueRequestQueue>()
+            .iter_pending()
+            .next()
+            .is_none());
+        assert_eq!(
+            app.world()
+                .resource::<PendingDialogueTasks>()
+                .in_flight_views()
+                .count(),
+            2
+        );
+
+        release.store(true, Ordering::Release);
+        for _ in 0..500 {
+            app.update();
+            if app
+                .world()
+                .resource::<PendingDialogueTasks>()
+                .in_flight_views()
+                .next()
+                .is_none()
+            {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(2));
+        }
+
+        let world = app.world();
+        let dump = DialogueQueueDump::capture(
+            world.resource::<DialogueRequestQueue>(),
+            world.resource::<PendingDialogueTasks>(),
+            world.resource::<DialogueRateLimitState>(),
+        );
+        assert!(dump.in_flight.is_empty(), "both tasks should complete");
+        let backoff = DialogueRateLimitConfig::default().retry_backoff_seconds;
+        assert_eq!(
+            dump.pending,
+            vec![PendingRequestView {
+                id: first,
+                speaker: failing,
+                target: Some(NpcId::new(2)),
+                topic: DialogueTopicHint::Status,
+                attempts: 1,
+                cooldown_remaining: backoff,
+            }],
+            "failed request is retried under its original id"
+        );
+        let cooled: Vec<_> = dump.npc_cooldowns.iter().map(|(npc, _)| *npc).collect();
+        assert_eq!(cooled, vec![failing, answered]);
+        let table = dump.format_table();
+        assert!(table.contains("1 pending, 0 in flight"));
+        assert!(table.contains("NPC-0001"));
+    }
+}
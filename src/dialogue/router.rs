@@ -0,0 +1,309 @@
+//! Routing of dialogue requests across the instantiated provider brokers.
+use std::env;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use super::{
+    broker::{DialogueBroker, DialogueProviderKind, LocalDialogueBroker, OpenAiDialogueBroker},
+    prompts::SharedPromptTemplates,
+    queue::ActiveDialogueBroker,
+    status::DialogueBrokerStatus,
+    telemetry::{
+        DialogueTelemetry, DialogueTelemetryEvent, DialogueTelemetryLog, DialogueTelemetryRecord,
+    },
+};
+use crate::{
+    core::input::{ActionInput, InputAction},
+    npc::{components::NpcId, spatial::NpcIndex},
+};
+
+/// Names the default provider (`openai` or `local`); OpenAI when unset.
+const ENV_PROVIDER: &str = "DIALOGUE_PROVIDER";
+
+/// Sends every request from this NPC to one provider unless the request names its own.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PinnedProvider(pub DialogueProviderKind);
+
+/// Looks up a speaker's `PinnedProvider` through the `NpcIndex`.
+#[derive(SystemParam)]
+pub struct SpeakerPins<'w, 's> {
+    npc_index: Res<'w, NpcIndex>,
+    pins: Query<'w, 's, &'static PinnedProvider>,
+}
+
+impl SpeakerPins<'_, '_> {
+    pub fn pinned(&self, speaker: NpcId) -> Option<DialogueProviderKind> {
+        let entity = self.npc_index.entity(speaker)?;
+        self.pins.get(entity).ok().map(|pin| pin.0)
+    }
+}
+
+/// Every broker that could be instantiated, plus the one requests go to by default.
+/// Routing precedence is request override, then the speaker's `PinnedProvider`, then the
+/// default; a provider that was never instantiated falls back to the default.
+#[derive(Resource, Clone)]
+pub struct DialogueProviderRouter {
+    /// Registration order, which is also the order `cycle_default` walks.
+    brokers: Vec<ActiveDialogueBroker>,
+    default: DialogueProviderKind,
+}
+
+impl DialogueProviderRouter {
+    /// Router with a single broker, which is also the default.
+    pub fn new(default: Box<dyn DialogueBroker>) -> Self {
+        let broker = ActiveDialogueBroker::new(default);
+        Self {
+            default: broker.provider_kind(),
+            brokers: vec![broker],
+        }
+    }
+
+    /// Brokers for the provider `DIALOGUE_PROVIDER` selects; see `from_lookup`.
+    pub fn from_env(templates: SharedPromptTemplates) -> Self {
+        Self::from_lookup(|key| env::var(key).ok(), templates)
+    }
+
+    /// With `local` selected only the local broker is built, so nothing reaches OpenAI.
+    /// Otherwise the OpenAI broker is the default, next to the always-available local one.
+    /// OpenAI is built even without an API key because it answers locally (and still
+    /// captures prompts) until one is set; providers that need credentials to do anything
+    /// at all belong behind a credential check here. An unknown name is logged and treated
+    /// as unset.
+    pub fn from_lookup(
+        lookup: impl Fn(&str) -> Option<String>,
+        templates: SharedPromptTemplates,
+    ) -> Self {
+        let selected = match lookup(ENV_PROVIDER) {
+            Some(value) => DialogueProviderKind::from_name(&value).unwrap_or_else(|| {
+                warn!(
+                    "{}={} names no dialogue provider; using OpenAI.",
+                    ENV_PROVIDER, value
+                );
+                DialogueProviderKind::OpenAi
+            }),
+            None => DialogueProviderKind::OpenAi,
+        };
+        match selected {
+            DialogueProviderKind::Local => Self::new(Box::new(LocalDialogueBroker)),
+            DialogueProviderKind::OpenAi => {
+                Self::new(Box::new(OpenAiDialogueBroker::new(templates)))
+                    .with_broker(Box::new(LocalDialogueBroker))
+            }
+        }
+    }
+
+    /// Adds `broker`, replacing any broker already registered for its provider.
+    pub fn with_broker(mut self, broker: Box<dyn DialogueBroker>) -> Self {
+        let broker = ActiveDialogueBroker::new(broker);
+        match self
+            .brokers
+            .iter_mut()
+            .find(|existing| existing.provider_kind() == broker.provider_kind())
+        {
+            Some(existing) => *existing = broker,
+            None => self.brokers.push(broker),
+        }
+        self
+    }
+
+    pub fn default_kind(&self) -> DialogueProviderKind {
+        self.default
+    }
+
+    pub fn default_broker(&self) -> &ActiveDialogueBroker {
+        self.broker(self.default)
+            .expect("the default provider is always registered")
+    }
+
+    pub fn broker(&self, provider: DialogueProviderKind) -> Option<&ActiveDialogueBroker> {
+        self.brokers
+            .iter()
+            .find(|broker| broker.provider_kind() == provider)
+    }
+
+    /// Providers in registration order.
+    #[cfg(test)]
+    pub fn providers(&self) -> impl Iterator<Item = DialogueProviderKind> + '_ {
+        self.brokers.iter().map(ActiveDialogueBroker::provider_kind)
+    }
+
+    /// Broker for a request with `request_override` whose speaker is pinned to `pinned`.
+    /// The second value names the provider that was asked for but is not instantiated,
+    /// when the default had to stand in for it.
+    pub fn resolve(
+        &self,
+        request_override: Option<DialogueProviderKind>,
+        pinned: Option<DialogueProviderKind>,
+    ) -> (&ActiveDialogueBroker, Option<DialogueProviderKind>) {
+        let Some(wanted) = request_override.or(pinned) else {
+            return (self.default_broker(), None);
+        };
+        match self.broker(wanted) {
+            Some(broker) => (broker, None),
+            None => (self.default_broker(), Some(wanted)),
+        }
+    }
+
+    /// Makes the next registered provider the default and returns it.
+    pub fn cycle_default(&mut self) -> DialogueProviderKind {
+        let current = self
+            .brokers
+            .iter()
+            .position(|broker| broker.provider_kind() == self.default)
+            .unwrap_or(0);
+        self.default = self.brokers[(current + 1) % self.brokers.len()].provider_kind();
+        self.default
+    }
+}
+
+/// Cycles the default provider on the bound key, updating `DialogueBrokerStatus` and
+/// recording the new status in telemetry.
+pub fn cycle_dialogue_provider(
+    input: ActionInput,
+    time: Res<Time>,
+    mut router: ResMut<DialogueProviderRouter>,
+    mut status: ResMut<DialogueBrokerStatus>,
+    mut telemetry: ResMut<DialogueTelemetry>,
+    mut log: ResMut<DialogueTelemetryLog>,
+) {
+    if !input.just_pressed(InputAction::CycleDialogueProvider) {
+        return;
+    }
+
+    let previous = router.default_kind();
+    let provider = router.cycle_default();
+    if provider == previous {
+        info!("Only one dialogue provider is available ({})", provider);
+        return;
+    }
+
+    let broker = router.default_broker();
+    status.switch_provider(provider, broker.connection_state());
+    info!(
+        "Default dialogue provider switched from {} to {} ({})",
+        previous,
+        provider,
+        status.connection_label()
+    );
+
+    let record = DialogueTelemetryRecord {
+        occurred_at_seconds: time.elapsed_secs_f64(),
+        event: DialogueTelemetryEvent::BrokerStatus(status.to_snapshot()),
+    };
+    log.push(&record);
+    telemetry.push(record);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue::{
+        errors::DialogueError,
+        prompts::PromptTemplates,
+        status::DialogueConnectionState,
+        types::{DialogueRequest, DialogueRequestId, DialogueResponse},
+    };
+
+    struct LiveStub;
+
+    impl DialogueBroker for LiveStub {
+        fn provider_kind(&self) -> DialogueProviderKind {
+            DialogueProviderKind::OpenAi
+        }
+
+        fn connection_state(&self) -> DialogueConnectionState {
+            DialogueConnectionState::Live
+        }
+
+        fn process(
+            &self,
+            request_id: DialogueRequestId,
+            request: &DialogueRequest,
+        ) -> Result<DialogueResponse, DialogueError> {
+            Ok(self.fabricate(request_id, request))
+        }
+    }
+
+    fn router() -> DialogueProviderRouter {
+        DialogueProviderRouter::new(Box::new(LiveStub)).with_broker(Box::new(LocalDialogueBroker))
+    }
+
+    #[test]
+    fn the_environment_selects_which_brokers_are_built() {
+        let built = |provider: Option<&str>| {
+            let router = DialogueProviderRouter::from_lookup(
+                |key| provider.filter(|_| key == ENV_PROVIDER).map(str::to_string),
+                SharedPromptTemplates::new(PromptTemplates::default()),
+            );
+            router.providers().collect::<Vec<_>>()
+        };
+        assert_eq!(
+            built(Some(" Local ")),
+            [DialogueProviderKind::Local],
+            "OpenAI is never built"
+        );
+        assert_eq!(
+            built(None),
+            [DialogueProviderKind::OpenAi, DialogueProviderKind::Local]
+        );
+        assert_eq!(
+            built(Some("openai")),
+            [DialogueProviderKind::OpenAi, DialogueProviderKind::Local]
+        );
+        assert_eq!(
+            built(Some("gossip")),
+            [DialogueProviderKind::OpenAi, DialogueProviderKind::Local],
+            "an unknown name falls back to the usual brokers"
+        );
+    }
+
+    #[test]
+    fn request_override_beats_pin_and_pin_beats_default() {
+        let router = router();
+        let kind =
+            |request_override, pinned| router.resolve(request_override, pinned).0.provider_kind();
+
+        assert_eq!(kind(None, None), DialogueProviderKind::OpenAi);
+        assert_eq!(
+            kind(None, Some(DialogueProviderKind::Local)),
+            DialogueProviderKind::Local
+        );
+        assert_eq!(
+            kind(
+                Some(DialogueProviderKind::OpenAi),
+                Some(DialogueProviderKind::Local)
+            ),
+            DialogueProviderKind::OpenAi
+        );
+        assert_eq!(
+            kind(Some(DialogueProviderKind::Local), None),
+            DialogueProviderKind::Local
+        );
+    }
+
+    #[test]
+    fn unavailable_provider_falls_back_to_the_default() {
+        let router = DialogueProviderRouter::new(Box::new(LiveStub));
+
+        let (broker, missing) = router.resolve(Some(DialogueProviderKind::Local), None);
+        assert_eq!(broker.provider_kind(), DialogueProviderKind::OpenAi);
+        assert_eq!(missing, Some(DialogueProviderKind::Local));
+
+        let (_, missing) = router.resolve(None, None);
+        assert_eq!(missing, None);
+    }
+
+    #[test]
+    fn cycling_walks_registered_providers_and_wraps() {
+        let mut router = router();
+        assert_eq!(router.cycle_default(), DialogueProviderKind::Local);
+        assert_eq!(
+            router.default_broker().connection_state(),
+            DialogueConnectionState::Fallback
+        );
+        assert_eq!(router.cycle_default(), DialogueProviderKind::OpenAi);
+
+        let mut single = DialogueProviderRouter::new(Box::new(LiveStub));
+        assert_eq!(single.cycle_default(), DialogueProviderKind::OpenAi);
+    }
+}
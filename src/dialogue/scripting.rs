@@ -0,0 +1,267 @@
+//! Rhai-scripted dialogue context providers (behind the `scripting` feature). Every
+//! `scripts/context/*.rhai` file defines `provide(speaker_info, topic, day)` returning an
+//! array of strings; each string joins the request as a `Custom` context event just before
+//! dispatch. Scripts run on the main thread under an operation and time budget, and a
+//! failing script only ever costs its own lines.
+use std::{
+    fs,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use bevy::prelude::*;
+use rhai::{Array, CallFnOptions, Dynamic, Engine, Map, Scope, AST};
+
+use super::types::{DialogueContextEvent, DialogueRequest};
+
+pub const SCRIPT_DIR: &str = "scripts/context";
+const SCRIPT_EXTENSION: &str = "rhai";
+const PROVIDER_FN: &str = "provide";
+const PROVIDER_ARITY: usize = 3;
+const MAX_OPERATIONS: u64 = 50_000;
+const TIME_BUDGET: Duration = Duration::from_millis(5);
+/// Checking the clock every operation is wasteful; every few hundred is precise enough.
+const TIME_CHECK_INTERVAL: u64 = 256;
+const FALLBACK_TARGET_LABEL: &str = "player";
+
+struct ContextScript {
+    name: String,
+    ast: AST,
+    /// Set after the first failure is logged, so a broken script does not flood the log.
+    reported: bool,
+}
+
+/// Loaded context scripts plus the sandboxed engine that runs them.
+#[derive(Resource)]
+pub struct ScriptedContextProviders {
+    engine: Engine,
+    deadline: Arc<Mutex<Instant>>,
+    scripts: Vec<ContextScript>,
+}
+
+impl Default for ScriptedContextProviders {
+    fn default() -> Self {
+        let deadline = Arc::new(Mutex::new(Instant::now()));
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        let watched = Arc::clone(&deadline);
+        engine.on_progress(move |operations| {
+            if operations % TIME_CHECK_INTERVAL != 0 {
+                return None;
+            }
+            let expired = watched
+                .lock()
+                .map(|deadline| Instant::now() >= *deadline)
+                .unwrap_or(true);
+            expired.then(|| Dynamic::from("time budget exceeded"))
+        });
+        engine.on_print(|text| info!("Context script: {text}"));
+        Self {
+            engine,
+            deadline,
+            scripts: Vec::new(),
+        }
+    }
+}
+
+impl ScriptedContextProviders {
+    /// Compiles every `.rhai` file in `dir`, in file-name order. Scripts that fail to
+    /// compile or lack `provide` are skipped with a warning; a missing directory simply
+    /// means no providers.
+    pub fn load_dir(dir: impl AsRef<Path>) -> Self {
+        let mut providers = Self::default();
+        let Ok(entries) = fs::read_dir(dir.as_ref()) else {
+            return providers;
+        };
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == SCRIPT_EXTENSION))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            let name = path.display().to_string();
+            let result = fs::read_to_string(&path)
+                .map_err(|err| format!("unable to read file: {err}"))
+                .and_then(|source| providers.add_script(name.clone(), &source));
+            match result {
+                Ok(()) => info!("Loaded dialogue context script {name}"),
+                Err(err) => warn!("Skipping dialogue context script {name}: {err}"),
+            }
+        }
+        providers
+    }
+
+    /// Compiles `source` and registers it under `name`.
+    pub fn add_script(&mut self, name: impl Into<String>, source: &str) -> Result<(), String> {
+        let ast = self
+            .engine
+            .compile(source)
+            .map_err(|err| format!("compile error: {err}"))?;
+        if !ast
+            .iter_functions()
+            .any(|function| function.name == PROVIDER_FN && function.params.len() == PROVIDER_ARITY)
+        {
+            return Err(format!(
+                "no `{PROVIDER_FN}(speaker_info, topic, day)` function"
+            ));
+        }
+        self.scripts.push(ContextScript {
+            name: name.into(),
+            ast,
+            reported: false,
+        });
+        Ok(())
+    }
+
+    /// Runs every script for `request` and appends their lines as `Custom` context events.
+    /// Blank lines are dropped; failures are logged once per script and otherwise ignored.
+    pub fn extend_context(&mut self, request: &mut DialogueRequest, day: u64) {
+        let speaker_info = speaker_info(request);
+        let topic = request.topic_hint.label();
+        for script in &mut self.scripts {
+            if let Ok(mut deadline) = self.deadline.lock() {
+                *deadline = Instant::now() + TIME_BUDGET;
+            }
+            let result = self
+                .engine
+                .call_fn_with_options::<Array>(
+                    CallFnOptions::new().eval_ast(false),
+                    &mut Scope::new(),
+                    &script.ast,
+                    PROVIDER_FN,
+                    (speaker_info.clone(), topic.to_string(), day as i64),
+                )
+                .map_err(|err| err.to_string());
+
+            match result {
+                Ok(lines) => request.context.events.extend(
+                    lines
+                        .into_iter()
+                        .map(|line| line.to_string().trim().to_string())
+                        .filter(|text| !text.is_empty())
+                        .map(|text| DialogueContextEvent::Custom { text }),
+                ),
+                Err(err) if !script.reported => {
+                    script.reported = true;
+                    warn!(
+                        "Dialogue context script {} failed (further errors muted): {}",
+                        script.name, err
+                    );
+                }
+                Err(_) => {}
+            }
+        }
+    }
+}
+
+/// The `speaker_info` map scripts receive: `id`, `profile`, `target`, and `prompt`.
+fn speaker_info(request: &DialogueRequest) -> Map {
+    let mut info = Map::new();
+    info.insert("id".into(), request.speaker.to_string().into());
+    info.insert(
+        "profile".into(),
+        request.speaker_profile.clone().unwrap_or_default().into(),
+    );
+    info.insert(
+        "target".into(),
+        request
+            .target
+            .map_or_else(|| FALLBACK_TARGET_LABEL.to_string(), |id| id.to_string())
+            .into(),
+    );
+    info.insert("prompt".into(), request.prompt.clone().into());
+    info
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dialogue::types::{DialogueContext, DialogueTopicHint},
+        npc::components::NpcId,
+    };
+
+    fn request() -> DialogueRequest {
+        let mut request = DialogueRequest::new(
+            NpcId::new(1),
+            None,
+            "Morning!",
+            DialogueTopicHint::Status,
+            DialogueContext::default(),
+        );
+        request.speaker_profile = Some("a 25-year-old farmer".to_string());
+        request
+    }
+
+    fn custom_lines(request: &DialogueRequest) -> Vec<&str> {
+        request
+            .context
+            .events
+            .iter()
+            .filter_map(|event| match event {
+                DialogueContextEvent::Custom { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sample_script_adds_context() {
+        let mut shipped = ScriptedContextProviders::load_dir(SCRIPT_DIR);
+        assert!(!shipped.scripts.is_empty(), "the sample script compiles");
+        let mut market_day = request();
+        shipped.extend_context(&mut market_day, 7);
+        assert_eq!(custom_lines(&market_day).len(), 1);
+
+        let mut providers = ScriptedContextProviders::default();
+        providers
+            .add_script(
+                "sample",
+                r#"
+                fn provide(speaker_info, topic, day) {
+                    let lines = [`${speaker_info.id} is ${speaker_info.profile}.`, "  "];
+                    if topic == "status" && day % 7 == 0 {
+                        lines.push("It is market day.");
+                    }
+                    lines
+                }
+                "#,
+            )
+            .unwrap();
+
+        let mut request = request();
+        providers.extend_context(&mut request, 14);
+        assert_eq!(
+            custom_lines(&request),
+            ["NPC-0001 is a 25-year-old farmer.", "It is market day."]
+        );
+    }
+
+    #[test]
+    fn runaway_scripts_are_cut_off_without_failing_the_request() {
+        let mut providers = ScriptedContextProviders::default();
+        providers
+            .add_script("runaway", "fn provide(a, b, c) { loop {} }")
+            .unwrap();
+        providers
+            .add_script("wrong_type", "fn provide(a, b, c) { 42 }")
+            .unwrap();
+        providers
+            .add_script("steady", r#"fn provide(a, b, c) { ["Still here."] }"#)
+            .unwrap();
+        assert!(providers
+            .add_script("no_provider", "fn other() { [] }")
+            .is_err());
+
+        let started = Instant::now();
+        let mut request = request();
+        providers.extend_context(&mut request, 1);
+        providers.extend_context(&mut request, 2);
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(custom_lines(&request), ["Still here.", "Still here."]);
+        assert!(providers.scripts[0].reported && providers.scripts[1].reported);
+        assert!(!providers.scripts[2].reported);
+    }
+}
@@ -0,0 +1,233 @@
+//! Live-like pacing for fallback replies. Without a provider the broker answers instantly,
+//! so conversations skip the approach-and-wait rhythm they have in live mode. With
+//! `DIALOGUE_FALLBACK_LATENCY_MS` and `DIALOGUE_FALLBACK_FAILURE_PERCENT` set, every
+//! fabricated reply sleeps a sampled delay inside its background task, and a share of them
+//! fail as a provider outage or a rate limit, so the thinking indicator, backpressure, and
+//! retry paths all run. Both default to off.
+use std::{env, time::Duration};
+
+use bevy::prelude::*;
+
+use super::{errors::DialogueErrorKind, types::DialogueRequestId};
+use crate::economy::rng::DailyRng;
+
+pub const ENV_LATENCY_MS: &str = "DIALOGUE_FALLBACK_LATENCY_MS";
+pub const ENV_FAILURE_PERCENT: &str = "DIALOGUE_FALLBACK_FAILURE_PERCENT";
+pub const ENV_SEED: &str = "DIALOGUE_FALLBACK_SEED";
+const DEFAULT_SEED: u64 = 0x4641_4c4c;
+/// Retry-after reported by a simulated rate limit, in seconds.
+const SIMULATED_RETRY_AFTER_SECONDS: f32 = 2.0;
+const SIMULATED_OUTAGE_MESSAGE: &str = "simulated outage";
+
+/// Artificial delay range for fallback replies, in milliseconds. `0-0` adds none.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SimulatedLatency {
+    pub min_ms: u32,
+    pub max_ms: u32,
+}
+
+impl SimulatedLatency {
+    /// Parses `"300"` (fixed) or `"200-900"` (uniform range); a reversed range is swapped.
+    pub fn parse(value: &str) -> Option<Self> {
+        let (min, max) = match value.split_once('-') {
+            Some((min, max)) => (min.trim().parse().ok()?, max.trim().parse().ok()?),
+            None => {
+                let fixed = value.trim().parse().ok()?;
+                (fixed, fixed)
+            }
+        };
+        Some(Self {
+            min_ms: u32::min(min, max),
+            max_ms: u32::max(min, max),
+        })
+    }
+
+    /// A delay within `[min_ms, max_ms]`.
+    pub fn sample(&self, rng: &mut DailyRng) -> Duration {
+        Duration::from_millis(rng.range_inclusive(self.min_ms, self.max_ms).into())
+    }
+}
+
+/// What a simulated fallback call does before answering.
+#[derive(Debug, Clone)]
+pub struct SimulatedOutcome {
+    pub delay: Duration,
+    /// Returned instead of the fabricated reply when set.
+    pub failure: Option<DialogueErrorKind>,
+}
+
+/// Latency and failure injection for fallback replies, read from the environment.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct FallbackSimulation {
+    pub latency: SimulatedLatency,
+    /// Share of fallback calls that fail, 0-100. Half report a provider failure and half a
+    /// rate limit.
+    pub failure_percent: f32,
+    pub seed: u64,
+}
+
+impl Default for FallbackSimulation {
+    fn default() -> Self {
+        Self {
+            latency: SimulatedLatency::default(),
+            failure_percent: 0.0,
+            seed: DEFAULT_SEED,
+        }
+    }
+}
+
+impl FallbackSimulation {
+    pub fn from_env() -> Self {
+        Self::from_lookup(|key| env::var(key).ok())
+    }
+
+    /// Reads overrides from `lookup`; unset values keep the defaults (no delay, no
+    /// failures) and invalid ones are logged and ignored.
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let mut simulation = Self::default();
+        let read = |key: &str| lookup(key).map(|value| value.trim().to_string());
+
+        if let Some(value) = read(ENV_LATENCY_MS) {
+            match SimulatedLatency::parse(&value) {
+                Some(latency) => simulation.latency = latency,
+                None => warn!("Ignoring {ENV_LATENCY_MS}={value}: expected `ms` or `min-max`"),
+            }
+        }
+        if let Some(value) = read(ENV_FAILURE_PERCENT) {
+            match value.parse::<f32>() {
+                Ok(percent) if (0.0..=100.0).contains(&percent) => {
+                    simulation.failure_percent = percent;
+                }
+                _ => warn!("Ignoring {ENV_FAILURE_PERCENT}={value}: expected a percentage 0-100"),
+            }
+        }
+        if let Some(value) = read(ENV_SEED) {
+            match value.parse() {
+                Ok(seed) => simulation.seed = seed,
+                Err(_) => warn!("Ignoring {ENV_SEED}={value}: expected a whole number"),
+            }
+        }
+        simulation
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.latency.max_ms > 0 || self.failure_percent > 0.0
+    }
+
+    /// Delay and possible failure for `attempt` (0 for the first dispatch) at
+    /// `request_id`. The same seed, request, and attempt always draw the same outcome, and
+    /// each retry draws afresh.
+    pub fn outcome(&self, request_id: DialogueRequestId, attempt: u8) -> SimulatedOutcome {
+        let mut rng = DailyRng::for_day(self.seed, request_id.value(), attempt.into());
+        let delay = self.latency.sample(&mut rng);
+        let failure = rng.chance(self.failure_percent / 100.0).then(|| {
+            if rng.chance(0.5) {
+                DialogueErrorKind::provider_failure(SIMULATED_OUTAGE_MESSAGE)
+            } else {
+                DialogueErrorKind::rate_limited(SIMULATED_RETRY_AFTER_SECONDS)
+            }
+        });
+        SimulatedOutcome { delay, failure }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup<'a>(pairs: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |key: &str| {
+            pairs
+                .iter()
+                .find(|(name, _)| *name == key)
+                .map(|(_, value)| value.to_string())
+        }
+    }
+
+    #[test]
+    fn latency_samples_stay_within_the_configured_range() {
+        assert_eq!(
+            SimulatedLatency::parse("900 - 200"),
+            Some(SimulatedLatency {
+                min_ms: 200,
+                max_ms: 900
+            })
+        );
+        assert_eq!(
+            SimulatedLatency::parse("300"),
+            Some(SimulatedLatency {
+                min_ms: 300,
+                max_ms: 300
+            })
+        );
+        assert_eq!(SimulatedLatency::parse("soon"), None);
+
+        let simulation = FallbackSimulation::from_lookup(lookup(&[(ENV_LATENCY_MS, "200-900")]));
+        assert!(simulation.is_enabled());
+        let delays: Vec<Duration> = (0..200)
+            .map(|id| simulation.outcome(DialogueRequestId::new(id), 0).delay)
+            .collect();
+        assert!(delays
+            .iter()
+            .all(|delay| (200..=900).contains(&delay.as_millis())));
+        assert!(
+            delays.iter().any(|delay| *delay != delays[0]),
+            "delays vary"
+        );
+
+        let off = FallbackSimulation::from_lookup(lookup(&[(ENV_LATENCY_MS, "fast")]));
+        assert_eq!(off, FallbackSimulation::default());
+        assert!(!off.is_enabled());
+        assert_eq!(
+            off.outcome(DialogueRequestId::new(1), 0).delay,
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn failures_replay_for_a_fixed_seed_and_vary_across_attempts() {
+        let simulation = FallbackSimulation::from_lookup(lookup(&[
+            (ENV_FAILURE_PERCENT, "40"),
+            (ENV_SEED, "7"),
+        ]));
+        assert_eq!(simulation.seed, 7);
+        let draw = |simulation: &FallbackSimulation, attempt: u8| -> Vec<Option<String>> {
+            (0..100)
+                .map(|id| {
+                    simulation
+                        .outcome(DialogueRequestId::new(id), attempt)
+                        .failure
+                        .map(|kind| kind.to_string())
+                })
+                .collect()
+        };
+
+        let first = draw(&simulation, 0);
+        assert_eq!(first, draw(&simulation, 0), "same seed, same failures");
+        assert_ne!(first, draw(&simulation, 1), "retries draw afresh");
+        let reseeded = FallbackSimulation {
+            seed: 8,
+            ..simulation
+        };
+        assert_ne!(first, draw(&reseeded, 0));
+
+        let failures = first.iter().flatten().count();
+        assert!((20..=60).contains(&failures), "{failures} of 100 failed");
+        assert!(first
+            .iter()
+            .flatten()
+            .any(|message| message.starts_with("Rate limited")));
+        assert!(first
+            .iter()
+            .flatten()
+            .any(|message| message.contains(SIMULATED_OUTAGE_MESSAGE)));
+
+        let always = FallbackSimulation {
+            failure_percent: 100.0,
+            ..simulation
+        };
+        assert!(draw(&always, 0).iter().all(Option::is_some));
+        let out_of_range = FallbackSimulation::from_lookup(lookup(&[(ENV_FAILURE_PERCENT, "150")]));
+        assert_eq!(out_of_range.failure_percent, 0.0);
+    }
+}
@@ -1,15 +1,31 @@
 //! Dialogue broker status tracking for runtime telemetry and UI.
-use bevy::prelude::Resource;
+use std::time::SystemTime;
+
+use bevy::prelude::*;
 use serde::Serialize;
 
-use super::broker::DialogueProviderKind;
+use super::{
+    broker::DialogueProviderKind,
+    errors::{DialogueErrorKind, ProviderFailureClass},
+    events::DialogueRequestFailedEvent,
+};
+use crate::core::config::{
+    ConfigDiagnostics, ConfigLoadRecord, ConfigLoadStatus, ConfigReloadRequested,
+};
+
+const BUDGET_EXHAUSTED_LABEL: &str = "fallback (daily budget spent)";
 
 /// Connection state for the active dialogue broker.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum DialogueConnectionState {
+    /// Requests go to the provider.
     Live,
+    /// Requests are answered locally, e.g. without an API key.
     Fallback,
+    /// The provider rejected the credentials; live calls stay off until a restart or
+    /// config reload.
+    Misconfigured,
 }
 
 impl DialogueConnectionState {
@@ -18,6 +34,7 @@ impl DialogueConnectionState {
         match self {
             Self::Live => "live",
             Self::Fallback => "fallback",
+            Self::Misconfigured => "misconfigured",
         }
     }
 }
@@ -27,6 +44,10 @@ impl DialogueConnectionState {
 pub struct DialogueBrokerStatus {
     provider: DialogueProviderKind,
     connection_state: DialogueConnectionState,
+    /// Live calls are paused for the rest of the day; see `DailyApiBudget`.
+    budget_exhausted: bool,
+    /// Providers whose credentials were rejected; their live calls are skipped.
+    misconfigured: Vec<DialogueProviderKind>,
 }
 
 impl DialogueBrokerStatus {
@@ -34,25 +55,74 @@ impl DialogueBrokerStatus {
         Self {
             provider,
             connection_state,
+            budget_exhausted: false,
+            misconfigured: Vec::new(),
         }
     }
 
+    /// Points the status at a newly selected default provider, keeping the budget and
+    /// misconfiguration flags.
+    pub fn switch_provider(
+        &mut self,
+        provider: DialogueProviderKind,
+        connection_state: DialogueConnectionState,
+    ) {
+        self.provider = provider;
+        self.connection_state = connection_state;
+    }
+
     pub fn provider(&self) -> DialogueProviderKind {
         self.provider
     }
 
+    /// `Misconfigured` while the current provider's credentials are flagged.
     pub fn connection_state(&self) -> DialogueConnectionState {
-        self.connection_state
+        if self.is_misconfigured(self.provider) {
+            DialogueConnectionState::Misconfigured
+        } else {
+            self.connection_state
+        }
+    }
+
+    pub fn is_misconfigured(&self, provider: DialogueProviderKind) -> bool {
+        self.misconfigured.contains(&provider)
+    }
+
+    /// Flags `provider`'s credentials as rejected.
+    pub fn mark_misconfigured(&mut self, provider: DialogueProviderKind) {
+        if !self.is_misconfigured(provider) {
+            self.misconfigured.push(provider);
+        }
+    }
+
+    pub fn clear_misconfigured(&mut self, provider: DialogueProviderKind) {
+        self.misconfigured.retain(|flagged| *flagged != provider);
+    }
+
+    pub fn budget_exhausted(&self) -> bool {
+        self.budget_exhausted
+    }
+
+    pub fn set_budget_exhausted(&mut self, exhausted: bool) {
+        self.budget_exhausted = exhausted;
     }
 
     pub fn connection_label(&self) -> &'static str {
-        self.connection_state.label()
+        let state = self.connection_state();
+        if state == DialogueConnectionState::Misconfigured {
+            state.label()
+        } else if self.budget_exhausted {
+            BUDGET_EXHAUSTED_LABEL
+        } else {
+            self.connection_state.label()
+        }
     }
 
     pub fn to_snapshot(&self) -> DialogueBrokerStatusSnapshot {
         DialogueBrokerStatusSnapshot {
             provider: self.provider.to_string(),
-            connection_state: self.connection_state,
+            connection_state: self.connection_state(),
+            budget_exhausted: self.budget_exhausted,
         }
     }
 }
@@ -60,6 +130,81 @@ impl DialogueBrokerStatus {
 /// Serializable snapshot for telemetry logging.
 #[derive(Debug, Clone, Serialize)]
 pub struct DialogueBrokerStatusSnapshot {
+    /// Display name of the active provider.
     pub provider: String,
+    /// Whether the provider is live, falling back, or misconfigured.
     pub connection_state: DialogueConnectionState,
+    /// Live calls are paused for the rest of the budget window.
+    pub budget_exhausted: bool,
+}
+
+/// Config banner entry for a provider whose credentials were rejected; the reload binding
+/// sends it back as a `ConfigReloadRequested` path.
+pub fn credentials_source(provider: DialogueProviderKind) -> String {
+    format!("{provider} credentials")
+}
+
+/// Flags a provider misconfigured when it rejects the credentials, which stops its live
+/// calls, and lists the rejection on the config banner.
+pub fn flag_misconfigured_providers(
+    mut failures: MessageReader<DialogueRequestFailedEvent>,
+    mut status: ResMut<DialogueBrokerStatus>,
+    mut diagnostics: Option<ResMut<ConfigDiagnostics>>,
+) {
+    for event in failures.read() {
+        let DialogueErrorKind::ProviderFailure {
+            message,
+            class: ProviderFailureClass::Auth,
+        } = &event.error.kind
+        else {
+            continue;
+        };
+        let provider = event.error.provider;
+        if status.is_misconfigured(provider) {
+            continue;
+        }
+        status.mark_misconfigured(provider);
+        warn!(
+            "Dialogue provider {} rejected its credentials ({}); using fallback replies until \
+             a restart or config reload.",
+            provider, message
+        );
+        if let Some(diagnostics) = diagnostics.as_deref_mut() {
+            diagnostics.record(ConfigLoadRecord {
+                path: credentials_source(provider),
+                status: ConfigLoadStatus::Fallback,
+                error: Some(message.clone()),
+                timestamp: SystemTime::now(),
+            });
+        }
+    }
+}
+
+/// Lets a misconfigured provider try live calls again when the reload binding asks for
+/// its credentials. The key itself is still the one read at startup.
+pub fn clear_misconfigured_providers_on_reload(
+    mut requests: MessageReader<ConfigReloadRequested>,
+    mut status: ResMut<DialogueBrokerStatus>,
+    mut diagnostics: Option<ResMut<ConfigDiagnostics>>,
+) {
+    for request in requests.read() {
+        let Some(provider) = status
+            .misconfigured
+            .iter()
+            .copied()
+            .find(|provider| request.is_for(&credentials_source(*provider)))
+        else {
+            continue;
+        };
+        status.clear_misconfigured(provider);
+        info!("Dialogue provider {} will try live calls again", provider);
+        if let Some(diagnostics) = diagnostics.as_deref_mut() {
+            diagnostics.record(ConfigLoadRecord {
+                path: request.path.clone(),
+                status: ConfigLoadStatus::Loaded,
+                error: None,
+                timestamp: SystemTime::now(),
+            });
+        }
+    }
 }
@@ -0,0 +1,569 @@
+//! Batched, append-only writes of telemetry records to disk, and the flush policy read
+//! from the `[telemetry]` section of the dialogue config.
+use std::{
+    fmt,
+    fs::{self, create_dir_all, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use bevy::{log::warn, prelude::*};
+use serde::Deserialize;
+
+use super::DialogueTelemetryRecord;
+use crate::core::config::{ConfigDiagnostics, ConfigReloadRequested};
+use crate::dialogue::chronicle::CONFIG_PATH;
+
+const DEFAULT_DIALOGUE_TELEMETRY_LOG_PATH: &str = "logs/dialogue_history.jsonl";
+const DEFAULT_TELEMETRY_BATCH_SIZE: usize = 16;
+
+const DEFAULT_TELEMETRY_FLUSH_INTERVAL_SECONDS: f64 = 5.0;
+#[derive(Debug, Clone, Deserialize, Default)]
+struct RawDialogueConfig {
+    #[serde(default)]
+    telemetry: RawTelemetrySection,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+struct RawTelemetrySection {
+    batch_size: usize,
+    flush_interval_seconds: f64,
+}
+
+impl Default for RawTelemetrySection {
+    fn default() -> Self {
+        Self {
+            batch_size: DEFAULT_TELEMETRY_BATCH_SIZE,
+            flush_interval_seconds: DEFAULT_TELEMETRY_FLUSH_INTERVAL_SECONDS,
+        }
+    }
+}
+
+/// When buffered telemetry is written to disk: once `batch_size` records are pending or
+/// `flush_interval_seconds` have passed since the last flush, whichever comes first. Read
+/// from the `[telemetry]` section of `config/dialogue.toml`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TelemetryFlushPolicy {
+    pub batch_size: usize,
+    pub flush_interval_seconds: f64,
+}
+
+impl TelemetryFlushPolicy {
+    /// Reads and parses `config/dialogue.toml`.
+    pub fn load() -> Result<Self, String> {
+        let data = fs::read_to_string(Path::new(CONFIG_PATH))
+            .map_err(|err| format!("unable to read file: {err}"))?;
+        Self::from_toml_str(&data)
+    }
+
+    pub fn from_toml_str(data: &str) -> Result<Self, String> {
+        let raw = toml::from_str::<RawDialogueConfig>(data)
+            .map_err(|err| format!("invalid dialogue config: {err}"))?;
+        Ok(raw.into())
+    }
+}
+
+impl Default for TelemetryFlushPolicy {
+    fn default() -> Self {
+        RawDialogueConfig::default().into()
+    }
+}
+
+impl From<RawDialogueConfig> for TelemetryFlushPolicy {
+    fn from(value: RawDialogueConfig) -> Self {
+        let telemetry = value.telemetry;
+        let flush_interval_seconds = if telemetry.flush_interval_seconds.is_finite() {
+            telemetry.flush_interval_seconds.max(0.0)
+        } else {
+            DEFAULT_TELEMETRY_FLUSH_INTERVAL_SECONDS
+        };
+        Self {
+            batch_size: telemetry.batch_size.max(1),
+            flush_interval_seconds,
+        }
+    }
+}
+
+type TelemetryWriter = Box<dyn Write + Send + Sync>;
+type TelemetryOpener = Box<dyn Fn(&Path) -> io::Result<TelemetryWriter> + Send + Sync>;
+
+/// Rolling log that writes dialogue telemetry to disk for offline inspection.
+///
+/// The file handle is opened on the first flush and kept across flushes; a write error
+/// drops it so the next flush reopens the file. Records stay pending until written, and a
+/// write that fails partway resumes after the bytes that already reached the file.
+#[derive(Resource)]
+pub struct DialogueTelemetryLog {
+    output_path: PathBuf,
+    pending: Vec<DialogueTelemetryRecord>,
+    /// Rest of a line a failed flush cut short; written before the pending records.
+    unwritten_tail: Vec<u8>,
+    policy: TelemetryFlushPolicy,
+    last_flush_at: f64,
+    writer: Option<TelemetryWriter>,
+    opener: TelemetryOpener,
+}
+
+impl DialogueTelemetryLog {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self::with_policy(path, TelemetryFlushPolicy::default())
+    }
+
+    pub fn with_policy(path: impl Into<PathBuf>, policy: TelemetryFlushPolicy) -> Self {
+        Self {
+            output_path: path.into(),
+            pending: Vec::new(),
+            unwritten_tail: Vec::new(),
+            policy,
+            last_flush_at: 0.0,
+            writer: None,
+            opener: Box::new(open_append),
+        }
+    }
+
+    pub fn push(&mut self, record: &DialogueTelemetryRecord) {
+        self.pending.push(record.clone());
+    }
+
+    pub fn set_policy(&mut self, policy: TelemetryFlushPolicy) {
+        self.policy = policy;
+    }
+
+    /// Flushes when the batch is full or the interval has elapsed. Returns whether a
+    /// flush was attempted. The interval restarts even when the write fails, so a broken
+    /// disk is retried once per interval rather than every frame.
+    pub fn maybe_flush(&mut self, now: f64) -> io::Result<bool> {
+        let batch_full = self.pending.len() >= self.policy.batch_size.max(1);
+        let interval_elapsed = now - self.last_flush_at >= self.policy.flush_interval_seconds;
+        if !batch_full && !interval_elapsed {
+            return Ok(false);
+        }
+
+        self.last_flush_at = now;
+        self.flush().map(|()| true)
+    }
+
+    /// Writes every pending record through the cached handle, opening it if needed.
+    pub fn flush(&mut self) -> io::Result<()> {
+        if self.pending.is_empty() && self.unwritten_tail.is_empty() {
+            return Ok(());
+        }
+
+        let mut buffer = self.unwritten_tail.clone();
+        let tail_length = buffer.len();
+        let mut line_ends = Vec::with_capacity(self.pending.len());
+        for record in &self.pending {
+            buffer.extend_from_slice(record.to_json_line()?.as_bytes());
+            buffer.push(b'\n');
+            line_ends.push(buffer.len());
+        }
+
+        let mut writer = match self.writer.take() {
+            Some(writer) => writer,
+            None => (self.opener)(&self.output_path)?,
+        };
+
+        let mut written = 0;
+        let result = write_from(writer.as_mut(), &buffer, &mut written);
+        // Lines that went out whole are done. A line cut partway is done too, and the rest
+        // of it goes out first next time, so nothing is written twice or torn.
+        let mut done = line_ends.partition_point(|end| *end <= written);
+        let line_start = done
+            .checked_sub(1)
+            .map_or(tail_length, |last| line_ends[last]);
+        self.unwritten_tail.clear();
+        if written < tail_length {
+            self.unwritten_tail = buffer[written..tail_length].to_vec();
+        } else if written > line_start {
+            self.unwritten_tail = buffer[written..line_ends[done]].to_vec();
+            done += 1;
+        }
+        self.pending.drain(..done);
+
+        // On error the handle is dropped here and reopened by the next flush.
+        result?;
+        writer.flush()?;
+
+        self.writer = Some(writer);
+        Ok(())
+    }
+
+    #[allow(dead_code)]
+    pub fn path(&self) -> &Path {
+        &self.output_path
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty() && self.unwritten_tail.is_empty()
+    }
+}
+
+impl Default for DialogueTelemetryLog {
+    fn default() -> Self {
+        Self::new(DEFAULT_DIALOGUE_TELEMETRY_LOG_PATH)
+    }
+}
+
+impl fmt::Debug for DialogueTelemetryLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DialogueTelemetryLog")
+            .field("output_path", &self.output_path)
+            .field("pending", &self.pending.len())
+            .field("policy", &self.policy)
+            .field("last_flush_at", &self.last_flush_at)
+            .field("handle_open", &self.writer.is_some())
+            .finish()
+    }
+}
+
+/// Writes `buffer[*written..]`, advancing `written` past every byte the writer accepts so
+/// a failure leaves it at the first byte that did not go out.
+fn write_from(writer: &mut dyn Write, buffer: &[u8], written: &mut usize) -> io::Result<()> {
+    while *written < buffer.len() {
+        match writer.write(&buffer[*written..]) {
+            Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+            Ok(count) => *written += count,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(())
+}
+
+fn open_append(path: &Path) -> io::Result<TelemetryWriter> {
+    if let Some(parent) = path.parent() {
+        create_dir_all(parent)?;
+    }
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    Ok(Box::new(file))
+}
+
+/// Flushes pending telemetry log entries per the batch/interval policy, logging a warning
+/// if persistence fails.
+pub fn flush_dialogue_telemetry_log(time: Res<Time>, mut log: ResMut<DialogueTelemetryLog>) {
+    if let Err(err) = log.maybe_flush(time.elapsed_secs_f64()) {
+        warn!(
+            "Failed to persist dialogue telemetry to {:?}: {}",
+            log.path(),
+            err
+        );
+    }
+}
+
+/// Re-reads the flush policy from `config/dialogue.toml` on request.
+pub fn reload_telemetry_flush_policy(
+    mut requests: MessageReader<ConfigReloadRequested>,
+    mut diagnostics: ResMut<ConfigDiagnostics>,
+    mut log: ResMut<DialogueTelemetryLog>,
+) {
+    if !requests.read().any(|request| request.is_for(CONFIG_PATH)) {
+        return;
+    }
+    if let Some(policy) = diagnostics.report_reload(CONFIG_PATH, TelemetryFlushPolicy::load()) {
+        log.set_policy(policy);
+    }
+}
+
+/// Writes whatever is still pending when the app is shutting down.
+pub fn flush_dialogue_telemetry_on_exit(
+    mut exits: MessageReader<AppExit>,
+    mut log: ResMut<DialogueTelemetryLog>,
+) {
+    if exits.read().last().is_none() {
+        return;
+    }
+
+    if let Err(err) = log.flush() {
+        warn!(
+            "Failed to persist dialogue telemetry to {:?} on exit: {}",
+            log.path(),
+            err
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::record;
+    use super::*;
+    use crate::dialogue::telemetry::DialogueTelemetryEvent;
+    use crate::dialogue::{
+        broker::DialogueProviderKind,
+        status::{DialogueBrokerStatusSnapshot, DialogueConnectionState},
+        types::{DialogueRequestId, DialogueResponse},
+    };
+    use crate::npc::components::NpcId;
+    use serde_json::Value;
+    use std::{
+        env, fs,
+        sync::{
+            atomic::{AtomicBool, AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+        time::SystemTime,
+    };
+
+    #[test]
+    fn telemetry_log_writes_json_lines() {
+        let temp_dir = env::temp_dir();
+        let unique_suffix = SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let path = temp_dir.join(format!("dialogue_log_test_{}.jsonl", unique_suffix));
+        if path.exists() {
+            let _ = fs::remove_file(&path);
+        }
+
+        let mut log = DialogueTelemetryLog::new(&path);
+
+        let status_record = DialogueTelemetryRecord {
+            occurred_at_seconds: 11.0,
+            event: DialogueTelemetryEvent::BrokerStatus(DialogueBrokerStatusSnapshot {
+                provider: DialogueProviderKind::OpenAi.to_string(),
+                connection_state: DialogueConnectionState::Live,
+                budget_exhausted: false,
+            }),
+        };
+
+        let response_record = DialogueTelemetryRecord {
+            occurred_at_seconds: 12.5,
+            event: DialogueTelemetryEvent::Response(DialogueResponse::new(
+                DialogueRequestId::new(9),
+                DialogueProviderKind::OpenAi,
+                NpcId::new(42),
+                Some(NpcId::new(7)),
+                "Greetings",
+            )),
+        };
+
+        log.push(&status_record);
+        log.push(&response_record);
+        log.flush().expect("telemetry log should flush");
+
+        let raw = fs::read_to_string(&path).expect("log file should exist");
+        let lines: Vec<_> = raw.lines().collect();
+        assert_eq!(lines.len(), 2);
+
+        let status_value: Value =
+            serde_json::from_str(lines[0]).expect("status json line should parse");
+        assert_eq!(status_value["event"]["event_type"], "broker_status");
+        assert_eq!(status_value["event"]["provider"], "OpenAi");
+        assert_eq!(status_value["event"]["connection_state"], "live");
+
+        let value: Value = serde_json::from_str(lines[1]).expect("json line should parse");
+        assert_eq!(value["event"]["event_type"], "response");
+        assert_eq!(value["event"]["provider"], "OpenAi");
+        assert_eq!(value["event"]["speaker"], "NPC-0042");
+        assert_eq!(value["event"]["target"], "NPC-0007");
+        assert!(value["event"].get("prompt_file").is_none());
+        assert!(value["event"].get("truncated").is_none());
+
+        let _ = fs::remove_file(&path);
+    }
+
+    /// In-memory sink that counts opens and can be told to fail writes, or to fail once a
+    /// number of bytes has gone through.
+    #[derive(Clone, Default)]
+    struct TestSink {
+        bytes: Arc<Mutex<Vec<u8>>>,
+        opens: Arc<AtomicUsize>,
+        fail_writes: Arc<AtomicBool>,
+        byte_allowance: Arc<Mutex<Option<usize>>>,
+    }
+
+    impl TestSink {
+        fn log(&self, policy: TelemetryFlushPolicy) -> DialogueTelemetryLog {
+            let mut log = DialogueTelemetryLog::with_policy("unused.jsonl", policy);
+            let sink = self.clone();
+            log.opener = Box::new(move |_| {
+                sink.opens.fetch_add(1, Ordering::SeqCst);
+                Ok(Box::new(sink.clone()))
+            });
+            log
+        }
+
+        fn lines(&self) -> usize {
+            self.bytes
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|byte| **byte == b'\n')
+                .count()
+        }
+    }
+
+    impl Write for TestSink {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if self.fail_writes.load(Ordering::SeqCst) {
+                return Err(std::io::Error::other("disk full"));
+            }
+            let mut allowance = self.byte_allowance.lock().unwrap();
+            let count = match allowance.as_mut() {
+                Some(0) => return Err(std::io::Error::other("disk full")),
+                Some(left) => {
+                    let count = buf.len().min(*left);
+                    *left -= count;
+                    count
+                }
+                None => buf.len(),
+            };
+            self.bytes.lock().unwrap().extend_from_slice(&buf[..count]);
+            Ok(count)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn policy(batch_size: usize, flush_interval_seconds: f64) -> TelemetryFlushPolicy {
+        TelemetryFlushPolicy {
+            batch_size,
+            flush_interval_seconds,
+        }
+    }
+
+    #[test]
+    fn flushes_when_batch_fills() {
+        let sink = TestSink::default();
+        let mut log = sink.log(policy(3, 60.0));
+
+        log.push(&record(1));
+        log.push(&record(2));
+        assert!(!log.maybe_flush(1.0).unwrap());
+        assert_eq!(sink.lines(), 0);
+
+        log.push(&record(3));
+        assert!(log.maybe_flush(1.1).unwrap());
+        assert_eq!(sink.lines(), 3);
+        assert!(log.is_empty());
+    }
+
+    #[test]
+    fn flushes_when_interval_elapses() {
+        let sink = TestSink::default();
+        let mut log = sink.log(policy(100, 5.0));
+
+        log.push(&record(1));
+        assert!(!log.maybe_flush(4.9).unwrap());
+        assert!(log.maybe_flush(5.0).unwrap());
+        assert_eq!(sink.lines(), 1);
+
+        log.push(&record(2));
+        assert!(
+            !log.maybe_flush(9.0).unwrap(),
+            "interval restarts after a flush"
+        );
+        assert!(log.maybe_flush(10.0).unwrap());
+        assert_eq!(sink.lines(), 2);
+    }
+
+    #[test]
+    fn reuses_the_open_handle_across_flushes() {
+        let sink = TestSink::default();
+        let mut log = sink.log(policy(1, 60.0));
+
+        assert_eq!(
+            sink.opens.load(Ordering::SeqCst),
+            0,
+            "nothing opened before a flush"
+        );
+        for index in 0..4 {
+            log.push(&record(index));
+            log.maybe_flush(index as f64).unwrap();
+        }
+
+        assert_eq!(sink.lines(), 4);
+        assert_eq!(
+            sink.opens.load(Ordering::SeqCst),
+            1,
+            "handle opened lazily once"
+        );
+    }
+
+    #[test]
+    fn write_error_keeps_records_and_reopens() {
+        let sink = TestSink::default();
+        let mut log = sink.log(policy(1, 60.0));
+
+        log.push(&record(1));
+        log.flush().unwrap();
+        assert_eq!(sink.opens.load(Ordering::SeqCst), 1);
+
+        sink.fail_writes.store(true, Ordering::SeqCst);
+        log.push(&record(2));
+        assert!(log.maybe_flush(1.0).is_err());
+        assert!(!log.is_empty(), "failed records stay pending");
+
+        sink.fail_writes.store(false, Ordering::SeqCst);
+        log.push(&record(3));
+        assert!(log.maybe_flush(2.0).unwrap());
+        assert!(log.is_empty());
+        assert_eq!(sink.lines(), 3);
+        assert_eq!(
+            sink.opens.load(Ordering::SeqCst),
+            2,
+            "handle reopened after the error"
+        );
+    }
+
+    #[test]
+    fn a_partial_write_resumes_without_repeating_lines() {
+        let sink = TestSink::default();
+        let mut log = sink.log(policy(100, 60.0));
+        for index in 1..=3 {
+            log.push(&record(index));
+        }
+        let line_length = record(1).to_json_line().unwrap().len() + 1;
+
+        *sink.byte_allowance.lock().unwrap() = Some(line_length + line_length / 2);
+        assert!(log.flush().is_err());
+        assert_eq!(sink.lines(), 1, "the first line went out whole");
+
+        *sink.byte_allowance.lock().unwrap() = None;
+        log.flush().unwrap();
+        assert!(log.is_empty());
+        let written = String::from_utf8(sink.bytes.lock().unwrap().clone()).unwrap();
+        let expected: String = (1..=3)
+            .map(|index| record(index).to_json_line().unwrap() + "\n")
+            .collect();
+        assert_eq!(written, expected, "each record exactly once, none torn");
+    }
+
+    #[test]
+    fn flush_policy_reads_the_telemetry_section() {
+        assert_eq!(
+            TelemetryFlushPolicy::from_toml_str(
+                "[telemetry]\nbatch_size = 0\nflush_interval_seconds = 2.5\n"
+            )
+            .unwrap(),
+            policy(1, 2.5)
+        );
+        assert_eq!(
+            TelemetryFlushPolicy::from_toml_str("[chronicle]\nretention_days = 2\n").unwrap(),
+            TelemetryFlushPolicy::default()
+        );
+    }
+
+    #[test]
+    fn exit_flushes_pending_records() {
+        let sink = TestSink::default();
+        let mut app = App::new();
+        app.insert_resource(sink.log(policy(100, 600.0)))
+            .add_message::<AppExit>()
+            .add_systems(Last, flush_dialogue_telemetry_on_exit);
+
+        app.world_mut()
+            .resource_mut::<DialogueTelemetryLog>()
+            .push(&record(1));
+        app.update();
+        assert_eq!(sink.lines(), 0);
+
+        app.world_mut().write_message(AppExit::Success);
+        app.update();
+        assert_eq!(sink.lines(), 1);
+    }
+}
@@ -0,0 +1,327 @@
+//! Telemetry storage for dialogue responses and failures. `log` writes the records to
+//! `logs/dialogue_history.jsonl` and `serialize` shapes each one into its JSON line.
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use super::{
+    errors::DialogueError,
+    events::{
+        ApiBudgetExhaustedEvent, ConversationEndReason, DialogueRequestFailedEvent,
+        DialogueResponseEvent, PlayerInteractionEvent,
+    },
+    queue::{DialogueQueueDump, DialogueRequestQueue, ExpiredRequestView},
+    status::DialogueBrokerStatusSnapshot,
+    trace::{DialogueRequestTrace, RequestTrace},
+    types::DialogueResponse,
+};
+use crate::npc::{
+    components::{Identity, NpcId},
+    spatial::NpcIndex,
+};
+
+mod log;
+mod serialize;
+
+pub use log::{
+    flush_dialogue_telemetry_log, flush_dialogue_telemetry_on_exit, reload_telemetry_flush_policy,
+    DialogueTelemetryLog, TelemetryFlushPolicy,
+};
+
+use serialize::SerializableDialogueTelemetryRecord;
+
+const DEFAULT_DIALOGUE_TELEMETRY_CAPACITY: usize = 64;
+/// Rolling log of dialogue responses/failures for UI consumers.
+#[derive(Resource, Debug)]
+pub struct DialogueTelemetry {
+    capacity: usize,
+    records: VecDeque<DialogueTelemetryRecord>,
+}
+
+impl DialogueTelemetry {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            records: VecDeque::new(),
+        }
+    }
+
+    pub fn push(&mut self, record: DialogueTelemetryRecord) {
+        while self.records.len() >= self.capacity {
+            self.records.pop_front();
+        }
+        self.records.push_back(record);
+    }
+
+    #[allow(dead_code)]
+    pub fn records(&self) -> impl Iterator<Item = &DialogueTelemetryRecord> {
+        self.records.iter()
+    }
+
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+}
+
+impl Default for DialogueTelemetry {
+    fn default() -> Self {
+        Self::new(DEFAULT_DIALOGUE_TELEMETRY_CAPACITY)
+    }
+}
+
+/// Single telemetry entry.
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub struct DialogueTelemetryRecord {
+    /// Elapsed app time when it was recorded.
+    pub occurred_at_seconds: f64,
+    /// What happened.
+    pub event: DialogueTelemetryEvent,
+}
+
+impl DialogueTelemetryRecord {
+    /// The record as one line of `logs/dialogue_history.jsonl`, without the newline.
+    pub fn to_json_line(&self) -> serde_json::Result<String> {
+        serde_json::to_string(&SerializableDialogueTelemetryRecord::from(self.clone()))
+    }
+}
+
+/// A response, failure, broker status snapshot, spent API budget, debug queue dump, a
+/// finished request's phase trace, a request dropped as stale, or a step in the player's
+/// interaction funnel. Player steps carry the NPC's resolved name.
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub enum DialogueTelemetryEvent {
+    /// A provider answered.
+    Response(DialogueResponse),
+    /// A request failed for good.
+    Failure(DialogueError),
+    /// The active broker or its connection state changed.
+    BrokerStatus(DialogueBrokerStatusSnapshot),
+    /// Live calls stopped for the rest of the budget window.
+    BudgetExhausted(ApiBudgetExhaustedEvent),
+    /// A queue dump requested from the debug binding.
+    QueueDump(DialogueQueueDump),
+    /// Every phase of a finished request.
+    Trace(RequestTrace),
+    /// A request dropped undispatched as stale.
+    Expired(ExpiredRequestView),
+    /// See `PlayerInteractionEvent::Started`.
+    PlayerInteractionStarted {
+        /// The NPC greeted.
+        npc: NpcId,
+        /// Their display name.
+        npc_name: String,
+        /// World units between the player and the NPC.
+        distance: f32,
+    },
+    /// See `PlayerInteractionEvent::ResponseChosen`.
+    PlayerResponseChosen {
+        /// The NPC answered.
+        npc: NpcId,
+        /// Their display name.
+        npc_name: String,
+        /// Zero-based position of the option in the window.
+        option_index: usize,
+        /// The option's text as shown.
+        option_text: String,
+    },
+    /// See `PlayerInteractionEvent::ConversationEnded`.
+    PlayerConversationEnded {
+        /// The NPC the conversation was with.
+        npc: NpcId,
+        /// Their display name.
+        npc_name: String,
+        /// Player replies sent.
+        turns: u32,
+        /// What ended it.
+        ended_by: ConversationEndReason,
+    },
+}
+
+impl DialogueTelemetryEvent {
+    /// Telemetry entry for a player interaction step, naming the NPC with `name_of` (usually
+    /// `Identity::name_of`).
+    pub fn from_player(
+        event: PlayerInteractionEvent,
+        name_of: impl FnOnce(NpcId) -> String,
+    ) -> Self {
+        match event {
+            PlayerInteractionEvent::Started { npc, distance } => Self::PlayerInteractionStarted {
+                npc,
+                npc_name: name_of(npc),
+                distance,
+            },
+            PlayerInteractionEvent::ResponseChosen {
+                npc,
+                option_index,
+                option_text,
+            } => Self::PlayerResponseChosen {
+                npc,
+                npc_name: name_of(npc),
+                option_index,
+                option_text,
+            },
+            PlayerInteractionEvent::ConversationEnded {
+                npc,
+                turns,
+                ended_by,
+            } => Self::PlayerConversationEnded {
+                npc,
+                npc_name: name_of(npc),
+                turns,
+                ended_by,
+            },
+        }
+    }
+}
+
+/// System that records dialogue telemetry for later UI display, including the trace of
+/// every request that finished since the last run and every request the queue dropped as
+/// stale.
+#[allow(clippy::too_many_arguments)]
+pub fn record_dialogue_telemetry(
+    time: Res<Time>,
+    mut telemetry: ResMut<DialogueTelemetry>,
+    mut responses: MessageReader<DialogueResponseEvent>,
+    mut failures: MessageReader<DialogueRequestFailedEvent>,
+    mut budget_events: MessageReader<ApiBudgetExhaustedEvent>,
+    mut player_events: MessageReader<PlayerInteractionEvent>,
+    npc_index: Res<NpcIndex>,
+    identities: Query<&Identity>,
+    mut log: ResMut<DialogueTelemetryLog>,
+    trace: Option<ResMut<DialogueRequestTrace>>,
+    queue: Option<ResMut<DialogueRequestQueue>>,
+) {
+    let now = time.elapsed_secs_f64();
+
+    for event in responses.read() {
+        let record = DialogueTelemetryRecord {
+            occurred_at_seconds: now,
+            event: DialogueTelemetryEvent::Response(event.response.clone()),
+        };
+        log.push(&record);
+        telemetry.push(record);
+    }
+
+    for event in failures.read() {
+        let record = DialogueTelemetryRecord {
+            occurred_at_seconds: now,
+            event: DialogueTelemetryEvent::Failure(event.error.clone()),
+        };
+        log.push(&record);
+        telemetry.push(record);
+    }
+
+    for event in budget_events.read() {
+        let record = DialogueTelemetryRecord {
+            occurred_at_seconds: now,
+            event: DialogueTelemetryEvent::BudgetExhausted(event.clone()),
+        };
+        log.push(&record);
+        telemetry.push(record);
+    }
+
+    for event in player_events.read() {
+        let record = DialogueTelemetryRecord {
+            occurred_at_seconds: now,
+            event: DialogueTelemetryEvent::from_player(event.clone(), |npc| {
+                Identity::name_of(&npc_index, &identities, npc)
+            }),
+        };
+        log.push(&record);
+        telemetry.push(record);
+    }
+
+    for finished in trace
+        .map(|mut trace| trace.take_finished())
+        .unwrap_or_default()
+    {
+        let record = DialogueTelemetryRecord {
+            occurred_at_seconds: now,
+            event: DialogueTelemetryEvent::Trace(finished),
+        };
+        log.push(&record);
+        telemetry.push(record);
+    }
+
+    for expired in queue
+        .map(|mut queue| queue.take_expired())
+        .unwrap_or_default()
+    {
+        let record = DialogueTelemetryRecord {
+            occurred_at_seconds: now,
+            event: DialogueTelemetryEvent::Expired(expired),
+        };
+        log.push(&record);
+        telemetry.push(record);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue::{
+        broker::DialogueProviderKind,
+        errors::DialogueErrorKind,
+        types::{DialogueRequestId, DialogueResponse},
+    };
+    use crate::npc::components::NpcId;
+
+    #[test]
+    fn telemetry_drops_old_records_when_full() {
+        let mut telemetry = DialogueTelemetry::new(2);
+        telemetry.push(DialogueTelemetryRecord {
+            occurred_at_seconds: 1.0,
+            event: DialogueTelemetryEvent::Response(DialogueResponse::new(
+                DialogueRequestId::new(1),
+                DialogueProviderKind::OpenAi,
+                NpcId::new(1),
+                None,
+                "Hello",
+            )),
+        });
+        telemetry.push(DialogueTelemetryRecord {
+            occurred_at_seconds: 2.0,
+            event: DialogueTelemetryEvent::Failure(crate::dialogue::errors::DialogueError::new(
+                DialogueRequestId::new(2),
+                DialogueProviderKind::OpenAi,
+                DialogueErrorKind::provider_failure("boom"),
+            )),
+        });
+        telemetry.push(DialogueTelemetryRecord {
+            occurred_at_seconds: 3.0,
+            event: DialogueTelemetryEvent::Response(DialogueResponse::new(
+                DialogueRequestId::new(3),
+                DialogueProviderKind::OpenAi,
+                NpcId::new(2),
+                None,
+                "Hi",
+            )),
+        });
+
+        assert_eq!(telemetry.len(), 2);
+        assert!(telemetry
+            .records()
+            .all(|record| record.occurred_at_seconds >= 2.0));
+    }
+
+    pub(super) fn record(index: u64) -> DialogueTelemetryRecord {
+        DialogueTelemetryRecord {
+            occurred_at_seconds: index as f64,
+            event: DialogueTelemetryEvent::Response(DialogueResponse::new(
+                DialogueRequestId::new(index),
+                DialogueProviderKind::OpenAi,
+                NpcId::new(1),
+                None,
+                "Hello",
+            )),
+        }
+    }
+}
@@ -0,0 +1,504 @@
+//! The JSON shape of each telemetry record, kept apart from the in-memory types so the
+//! log format can change without touching the systems that record events.
+use serde::Serialize;
+
+use super::{DialogueTelemetryEvent, DialogueTelemetryRecord};
+use crate::dialogue::{
+    errors::{DialogueErrorKind, ProviderFailureClass},
+    events::ConversationEndReason,
+    status::DialogueConnectionState,
+};
+
+#[derive(Serialize)]
+pub(super) struct SerializableDialogueTelemetryRecord {
+    occurred_at_seconds: f64,
+    event: SerializableDialogueTelemetryEvent,
+}
+
+impl From<DialogueTelemetryRecord> for SerializableDialogueTelemetryRecord {
+    fn from(value: DialogueTelemetryRecord) -> Self {
+        Self {
+            occurred_at_seconds: value.occurred_at_seconds,
+            event: value.event.into(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "event_type", rename_all = "snake_case")]
+enum SerializableDialogueTelemetryEvent {
+    Response {
+        request_id: u64,
+        provider: String,
+        speaker: String,
+        target: Option<String>,
+        content: String,
+        /// Capture file holding this request's prompt, keyed by the same `request_id`.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        prompt_file: Option<String>,
+        /// Reported usage, covering the follow-up call when the reply was continued.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        tokens_used: Option<u32>,
+        #[serde(skip_serializing_if = "std::ops::Not::not")]
+        truncated: bool,
+        #[serde(skip_serializing_if = "std::ops::Not::not")]
+        continued: bool,
+    },
+    Failure {
+        request_id: u64,
+        provider: String,
+        error: SerializableDialogueError,
+    },
+    BrokerStatus {
+        provider: String,
+        connection_state: DialogueConnectionState,
+        budget_exhausted: bool,
+    },
+    BudgetExhausted {
+        limit: &'static str,
+        requests_used: u32,
+        tokens_used: u64,
+        resets_at: Option<String>,
+    },
+    QueueDump {
+        pending: Vec<SerializableQueueEntry>,
+        in_flight: Vec<SerializableQueueEntry>,
+        global_cooldown_remaining: f32,
+        npc_cooldowns: Vec<SerializableNpcCooldown>,
+    },
+    Trace {
+        request_id: u64,
+        total_seconds: f64,
+        phases: Vec<SerializableTracePhase>,
+    },
+    Expired {
+        request_id: u64,
+        speaker: String,
+        target: Option<String>,
+        topic: &'static str,
+        attempts: u8,
+        expires_day: u64,
+        expires_time_of_day: f32,
+        dropped_day: u64,
+        dropped_time_of_day: f32,
+    },
+    PlayerInteractionStarted {
+        npc: String,
+        npc_name: String,
+        distance: f32,
+    },
+    PlayerResponseChosen {
+        npc: String,
+        npc_name: String,
+        option_index: usize,
+        option_text: String,
+    },
+    PlayerConversationEnded {
+        npc: String,
+        npc_name: String,
+        turns: u32,
+        ended_by: ConversationEndReason,
+    },
+}
+
+#[derive(Serialize)]
+struct SerializableQueueEntry {
+    request_id: u64,
+    speaker: String,
+    target: Option<String>,
+    topic: &'static str,
+    attempts: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cooldown_remaining: Option<f32>,
+}
+
+#[derive(Serialize)]
+struct SerializableTracePhase {
+    phase: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    attempt: Option<u8>,
+    at_seconds: f64,
+    /// Seconds until the next phase; absent on the last one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_seconds: Option<f64>,
+}
+
+#[derive(Serialize)]
+struct SerializableNpcCooldown {
+    npc: String,
+    remaining_seconds: f32,
+}
+
+impl From<DialogueTelemetryEvent> for SerializableDialogueTelemetryEvent {
+    fn from(value: DialogueTelemetryEvent) -> Self {
+        match value {
+            DialogueTelemetryEvent::Response(response) => Self::Response {
+                request_id: response.request_id.value(),
+                provider: response.provider.to_string(),
+                speaker: response.speaker.to_string(),
+                target: response.target.map(|id| id.to_string()),
+                content: response.content,
+                prompt_file: response
+                    .prompt_file
+                    .map(|path| path.to_string_lossy().into_owned()),
+                tokens_used: response.tokens_used,
+                truncated: response.truncated,
+                continued: response.continued,
+            },
+            DialogueTelemetryEvent::Failure(error) => Self::Failure {
+                request_id: error.request_id.value(),
+                provider: error.provider.to_string(),
+                error: error.kind.into(),
+            },
+            DialogueTelemetryEvent::BrokerStatus(status) => Self::BrokerStatus {
+                provider: status.provider,
+                connection_state: status.connection_state,
+                budget_exhausted: status.budget_exhausted,
+            },
+            DialogueTelemetryEvent::BudgetExhausted(event) => Self::BudgetExhausted {
+                limit: event.limit.label(),
+                requests_used: event.requests_used,
+                tokens_used: event.tokens_used,
+                resets_at: event.resets_at.map(|at| at.to_rfc3339()),
+            },
+            DialogueTelemetryEvent::QueueDump(dump) => Self::QueueDump {
+                pending: dump
+                    .pending
+                    .into_iter()
+                    .map(|view| SerializableQueueEntry {
+                        request_id: view.id.value(),
+                        speaker: view.speaker.to_string(),
+                        target: view.target.map(|id| id.to_string()),
+                        topic: view.topic.label(),
+                        attempts: view.attempts,
+                        cooldown_remaining: Some(view.cooldown_remaining),
+                    })
+                    .collect(),
+                in_flight: dump
+                    .in_flight
+                    .into_iter()
+                    .map(|view| SerializableQueueEntry {
+                        request_id: view.id.value(),
+                        speaker: view.speaker.to_string(),
+                        target: view.target.map(|id| id.to_string()),
+                        topic: view.topic.label(),
+                        attempts: view.attempts,
+                        cooldown_remaining: None,
+                    })
+                    .collect(),
+                global_cooldown_remaining: dump.global_cooldown_remaining,
+                npc_cooldowns: dump
+                    .npc_cooldowns
+                    .into_iter()
+                    .map(|(npc, remaining_seconds)| SerializableNpcCooldown {
+                        npc: npc.to_string(),
+                        remaining_seconds,
+                    })
+                    .collect(),
+            },
+            DialogueTelemetryEvent::Trace(trace) => Self::Trace {
+                request_id: trace.id().value(),
+                total_seconds: trace.total_seconds(),
+                phases: trace
+                    .entries()
+                    .iter()
+                    .zip(trace.durations())
+                    .map(|(entry, (_, duration_seconds))| SerializableTracePhase {
+                        phase: entry.phase.label(),
+                        attempt: entry.phase.attempt(),
+                        at_seconds: entry.at_seconds,
+                        duration_seconds,
+                    })
+                    .collect(),
+            },
+            DialogueTelemetryEvent::Expired(expired) => Self::Expired {
+                request_id: expired.id.value(),
+                speaker: expired.speaker.to_string(),
+                target: expired.target.map(|id| id.to_string()),
+                topic: expired.topic.label(),
+                attempts: expired.attempts,
+                expires_day: expired.expires_at.day,
+                expires_time_of_day: expired.expires_at.time_of_day,
+                dropped_day: expired.dropped_at.day,
+                dropped_time_of_day: expired.dropped_at.time_of_day,
+            },
+            DialogueTelemetryEvent::PlayerInteractionStarted {
+                npc,
+                npc_name,
+                distance,
+            } => Self::PlayerInteractionStarted {
+                npc: npc.to_string(),
+                npc_name,
+                distance,
+            },
+            DialogueTelemetryEvent::PlayerResponseChosen {
+                npc,
+                npc_name,
+                option_index,
+                option_text,
+            } => Self::PlayerResponseChosen {
+                npc: npc.to_string(),
+                npc_name,
+                option_index,
+                option_text,
+            },
+            DialogueTelemetryEvent::PlayerConversationEnded {
+                npc,
+                npc_name,
+                turns,
+                ended_by,
+            } => Self::PlayerConversationEnded {
+                npc: npc.to_string(),
+                npc_name,
+                turns,
+                ended_by,
+            },
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "error_kind", rename_all = "snake_case")]
+enum SerializableDialogueError {
+    RateLimited {
+        retry_after_seconds: f32,
+        message: String,
+    },
+    ProviderFailure {
+        message: String,
+        failure_class: ProviderFailureClass,
+        retryable: bool,
+    },
+    ContextMissing {
+        missing: String,
+    },
+    InvalidRequest {
+        reason: String,
+    },
+}
+
+impl From<DialogueErrorKind> for SerializableDialogueError {
+    fn from(value: DialogueErrorKind) -> Self {
+        let retryable = value.is_retryable();
+        match value {
+            DialogueErrorKind::RateLimited {
+                retry_after_seconds,
+            } => Self::RateLimited {
+                retry_after_seconds,
+                message: format!(
+                    "Rate limited; retry after {:.2} seconds",
+                    retry_after_seconds
+                ),
+            },
+            DialogueErrorKind::ProviderFailure { message, class } => Self::ProviderFailure {
+                message,
+                failure_class: class,
+                retryable,
+            },
+            DialogueErrorKind::ContextMissing { missing } => Self::ContextMissing {
+                missing: missing.to_string(),
+            },
+            DialogueErrorKind::InvalidRequest { reason } => Self::InvalidRequest { reason },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::record;
+    use super::*;
+    use crate::dialogue::{
+        broker::DialogueProviderKind, errors::DialogueError, events::PlayerInteractionEvent,
+        queue::ExpiredRequestView, status::DialogueBrokerStatusSnapshot, types::DialogueRequestId,
+    };
+    use crate::npc::components::{Identity, NpcId};
+    use serde_json::Value;
+
+    #[test]
+    fn responses_link_their_captured_prompt() {
+        let mut record = record(4);
+        if let DialogueTelemetryEvent::Response(response) = &mut record.event {
+            response.prompt_file = Some("logs/dialogue_prompts.jsonl".into());
+        }
+
+        let value = serde_json::to_value(SerializableDialogueTelemetryRecord::from(record))
+            .expect("record should serialize");
+        assert_eq!(value["event"]["request_id"], 4);
+        assert_eq!(value["event"]["prompt_file"], "logs/dialogue_prompts.jsonl");
+    }
+
+    #[test]
+    fn continued_responses_log_their_combined_usage() {
+        let mut record = record(5);
+        if let DialogueTelemetryEvent::Response(response) = &mut record.event {
+            response.tokens_used = Some(85);
+            response.continued = true;
+        }
+
+        let value = serde_json::to_value(SerializableDialogueTelemetryRecord::from(record))
+            .expect("record should serialize");
+        assert_eq!(value["event"]["request_id"], 5);
+        assert_eq!(value["event"]["tokens_used"], 85);
+        assert_eq!(value["event"]["continued"], true);
+        assert!(value["event"].get("truncated").is_none());
+    }
+
+    #[test]
+    fn finished_traces_serialize_with_phase_durations() {
+        use crate::dialogue::trace::{DialogueRequestTrace, TracePhase};
+
+        let id = DialogueRequestId::new(9);
+        let mut store = DialogueRequestTrace::default();
+        store.record(id, TracePhase::Enqueued, 2.0);
+        store.record(id, TracePhase::Dispatched { attempt: 1 }, 2.5);
+        store.finish(id, TracePhase::Completed { attempt: 1 }, 4.0);
+        let trace = store.take_finished().remove(0);
+
+        let record = DialogueTelemetryRecord {
+            occurred_at_seconds: 4.0,
+            event: DialogueTelemetryEvent::Trace(trace),
+        };
+        let value: Value = serde_json::from_str(&record.to_json_line().unwrap()).unwrap();
+        let event = &value["event"];
+        assert_eq!(event["event_type"], "trace");
+        assert_eq!(event["request_id"], 9);
+        assert_eq!(event["total_seconds"], 2.0);
+        let phases = event["phases"].as_array().unwrap();
+        let labels: Vec<_> = phases.iter().map(|phase| &phase["phase"]).collect();
+        assert_eq!(labels, ["enqueued", "dispatched", "completed"]);
+        assert!(phases[0].get("attempt").is_none());
+        assert_eq!(phases[1]["attempt"], 1);
+        assert_eq!(phases[1]["duration_seconds"], 1.5);
+        assert!(phases[2].get("duration_seconds").is_none());
+    }
+
+    #[test]
+    fn expired_requests_serialize_with_their_deadline() {
+        use crate::dialogue::types::{ContextClock, DialogueTopicHint};
+
+        let record = DialogueTelemetryRecord {
+            occurred_at_seconds: 6.0,
+            event: DialogueTelemetryEvent::Expired(ExpiredRequestView {
+                id: DialogueRequestId::new(11),
+                speaker: NpcId::new(2),
+                target: Some(NpcId::new(5)),
+                topic: DialogueTopicHint::Trade,
+                attempts: 1,
+                expires_at: ContextClock::new(3, 0.5),
+                dropped_at: ContextClock::new(4, 0.25),
+            }),
+        };
+        let value: Value = serde_json::from_str(&record.to_json_line().unwrap()).unwrap();
+        let event = &value["event"];
+        assert_eq!(event["event_type"], "expired");
+        assert_eq!(event["request_id"], 11);
+        assert_eq!(event["speaker"], "NPC-0002");
+        assert_eq!(event["target"], "NPC-0005");
+        assert_eq!(event["topic"], "trade");
+        assert_eq!(event["attempts"], 1);
+        assert_eq!(event["expires_day"], 3);
+        assert_eq!(event["expires_time_of_day"], 0.5);
+        assert_eq!(event["dropped_day"], 4);
+        assert_eq!(event["dropped_time_of_day"], 0.25);
+    }
+
+    #[test]
+    fn player_interaction_steps_serialize_with_resolved_names() {
+        let brom = Identity::new(NpcId::new(3), "Brom", 45.0);
+        let events = [
+            PlayerInteractionEvent::Started {
+                npc: NpcId::new(3),
+                distance: 2.5,
+            },
+            PlayerInteractionEvent::ResponseChosen {
+                npc: NpcId::new(3),
+                option_index: 1,
+                option_text: "How can I help with that?".to_string(),
+            },
+            PlayerInteractionEvent::ConversationEnded {
+                npc: NpcId::new(8),
+                turns: 2,
+                ended_by: ConversationEndReason::WalkedAway,
+            },
+        ];
+
+        let values: Vec<Value> = events
+            .into_iter()
+            .map(|event| {
+                let record = DialogueTelemetryRecord {
+                    occurred_at_seconds: 1.0,
+                    event: DialogueTelemetryEvent::from_player(event, |npc| {
+                        Identity::name_or_id((npc == brom.id).then_some(&brom), npc)
+                    }),
+                };
+                serde_json::to_value(SerializableDialogueTelemetryRecord::from(record))
+                    .expect("record should serialize")
+            })
+            .collect();
+
+        assert_eq!(
+            values[0]["event"]["event_type"],
+            "player_interaction_started"
+        );
+        assert_eq!(values[0]["event"]["npc"], "NPC-0003");
+        assert_eq!(values[0]["event"]["npc_name"], "Brom");
+        assert_eq!(values[0]["event"]["distance"], 2.5);
+
+        assert_eq!(values[1]["event"]["event_type"], "player_response_chosen");
+        assert_eq!(values[1]["event"]["option_index"], 1);
+        assert_eq!(
+            values[1]["event"]["option_text"],
+            "How can I help with that?"
+        );
+
+        assert_eq!(
+            values[2]["event"]["event_type"],
+            "player_conversation_ended"
+        );
+        assert_eq!(values[2]["event"]["npc_name"], "NPC-0008");
+        assert_eq!(values[2]["event"]["turns"], 2);
+        assert_eq!(values[2]["event"]["ended_by"], "walked_away");
+    }
+
+    #[test]
+    fn failure_records_carry_the_provider_failure_class() {
+        let serialize = |kind: DialogueErrorKind| {
+            let record = DialogueTelemetryRecord {
+                occurred_at_seconds: 1.0,
+                event: DialogueTelemetryEvent::Failure(DialogueError::new(
+                    DialogueRequestId::new(3),
+                    DialogueProviderKind::OpenAi,
+                    kind,
+                )),
+            };
+            serde_json::to_value(SerializableDialogueTelemetryRecord::from(record))
+                .expect("record should serialize")
+        };
+
+        let auth = serialize(DialogueErrorKind::classified_failure(
+            ProviderFailureClass::Auth,
+            "Incorrect API key provided",
+        ));
+        let error = &auth["event"]["error"];
+        assert_eq!(error["error_kind"], "provider_failure");
+        assert_eq!(error["failure_class"], "auth");
+        assert_eq!(error["retryable"], false);
+
+        let transient = serialize(DialogueErrorKind::provider_failure("timed out"));
+        let error = &transient["event"]["error"];
+        assert_eq!(error["failure_class"], "transient");
+        assert_eq!(error["retryable"], true);
+
+        let status = DialogueTelemetryRecord {
+            occurred_at_seconds: 2.0,
+            event: DialogueTelemetryEvent::BrokerStatus(DialogueBrokerStatusSnapshot {
+                provider: DialogueProviderKind::OpenAi.to_string(),
+                connection_state: DialogueConnectionState::Misconfigured,
+                budget_exhausted: false,
+            }),
+        };
+        let status = serde_json::to_value(SerializableDialogueTelemetryRecord::from(status))
+            .expect("record should serialize");
+        assert_eq!(status["event"]["connection_state"], "misconfigured");
+    }
+}
@@ -0,0 +1,421 @@
+//! Lifecycle trace of individual dialogue requests. Queueing, dispatch, polling,
+//! cancellation, and the dialogue panel each stamp a phase on the request's trace, so one
+//! id shows its whole history in a single dump instead of across half a dozen log lines.
+use std::collections::VecDeque;
+use std::fmt;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use super::{queue::DialogueRequestQueue, types::DialogueRequestId};
+use crate::core::input::{ActionInput, InputAction};
+
+const DEFAULT_TRACE_CAPACITY: usize = 64;
+
+/// A step in a request's life. Attempts count from 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TracePhase {
+    /// Added to the queue.
+    Enqueued,
+    /// Handed to a broker.
+    Dispatched {
+        /// Which send this was.
+        attempt: u8,
+    },
+    /// The broker answered.
+    Completed {
+        /// The send that succeeded.
+        attempt: u8,
+    },
+    /// The broker failed; a `Retried` phase follows unless retries ran out.
+    Failed {
+        /// The send that failed.
+        attempt: u8,
+    },
+    /// Back in the queue after a failure, waiting to go out as `attempt`.
+    Retried {
+        /// The send it will go out as.
+        attempt: u8,
+    },
+    /// Withdrawn before dispatch.
+    Cancelled,
+    /// Dropped before dispatch because its context went stale.
+    Expired,
+    /// Shown in a dialogue panel.
+    Rendered,
+}
+
+impl TracePhase {
+    /// Lowercase name without the attempt.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Enqueued => "enqueued",
+            Self::Dispatched { .. } => "dispatched",
+            Self::Completed { .. } => "completed",
+            Self::Failed { .. } => "failed",
+            Self::Retried { .. } => "retried",
+            Self::Cancelled => "cancelled",
+            Self::Expired => "expired",
+            Self::Rendered => "rendered",
+        }
+    }
+
+    /// The attempt number, for the phases that carry one.
+    pub fn attempt(self) -> Option<u8> {
+        match self {
+            Self::Dispatched { attempt }
+            | Self::Completed { attempt }
+            | Self::Failed { attempt }
+            | Self::Retried { attempt } => Some(attempt),
+            Self::Enqueued | Self::Cancelled | Self::Expired | Self::Rendered => None,
+        }
+    }
+}
+
+impl fmt::Display for TracePhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.attempt() {
+            Some(attempt) => write!(f, "{} (attempt {attempt})", self.label()),
+            None => f.write_str(self.label()),
+        }
+    }
+}
+
+/// A phase and the elapsed app time, in seconds, when it happened.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TraceEntry {
+    /// What happened.
+    pub phase: TracePhase,
+    /// Elapsed app time when it happened.
+    pub at_seconds: f64,
+}
+
+/// Every phase one request went through, in order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestTrace {
+    id: DialogueRequestId,
+    entries: Vec<TraceEntry>,
+    finished: bool,
+}
+
+impl RequestTrace {
+    /// The traced request.
+    pub fn id(&self) -> DialogueRequestId {
+        self.id
+    }
+
+    /// Every stamped phase, oldest first.
+    pub fn entries(&self) -> &[TraceEntry] {
+        &self.entries
+    }
+
+    /// The phases alone, oldest first.
+    pub fn phases(&self) -> impl Iterator<Item = TracePhase> + '_ {
+        self.entries.iter().map(|entry| entry.phase)
+    }
+
+    /// True once the request completed, failed for good, or was cancelled or expired. A
+    /// rendered reply is stamped after that.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Each phase with the seconds until the next one; the last phase has no duration yet.
+    pub fn durations(&self) -> Vec<(TracePhase, Option<f64>)> {
+        self.entries
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| {
+                let next = self.entries.get(index + 1);
+                let duration = next.map(|next| (next.at_seconds - entry.at_seconds).max(0.0));
+                (entry.phase, duration)
+            })
+            .collect()
+    }
+
+    /// Seconds from the first phase to the last.
+    pub fn total_seconds(&self) -> f64 {
+        match (self.entries.first(), self.entries.last()) {
+            (Some(first), Some(last)) => (last.at_seconds - first.at_seconds).max(0.0),
+            _ => 0.0,
+        }
+    }
+
+    /// Multi-line dump suitable for the log.
+    pub fn format(&self) -> String {
+        let state = if self.finished {
+            "finished"
+        } else {
+            "in progress"
+        };
+        let mut lines = vec![format!(
+            "Dialogue trace {} ({state}, {:.2}s):",
+            self.id,
+            self.total_seconds()
+        )];
+        let start = self.entries.first().map_or(0.0, |entry| entry.at_seconds);
+        for (entry, (_, duration)) in self.entries.iter().zip(self.durations()) {
+            let duration = duration.map_or_else(String::new, |seconds| format!(", {seconds:.2}s"));
+            lines.push(format!(
+                "  +{:.2}s {}{duration}",
+                entry.at_seconds - start,
+                entry.phase
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Traces of the most recent requests, oldest first. Finished traces wait in an outbox
+/// until `record_dialogue_telemetry` writes them as `trace` records.
+#[derive(Resource, Debug)]
+pub struct DialogueRequestTrace {
+    capacity: usize,
+    traces: VecDeque<RequestTrace>,
+    finished: Vec<DialogueRequestId>,
+}
+
+impl DialogueRequestTrace {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            traces: VecDeque::new(),
+            finished: Vec::new(),
+        }
+    }
+
+    /// Stamps `phase` on `id`'s trace, starting one (and evicting the oldest past
+    /// capacity) for an id not seen yet.
+    pub fn record(&mut self, id: DialogueRequestId, phase: TracePhase, at_seconds: f64) {
+        let entry = TraceEntry { phase, at_seconds };
+        if let Some(trace) = self.traces.iter_mut().find(|trace| trace.id == id) {
+            trace.entries.push(entry);
+            return;
+        }
+        while self.traces.len() >= self.capacity {
+            if let Some(evicted) = self.traces.pop_front() {
+                self.finished.retain(|finished| *finished != evicted.id);
+            }
+        }
+        self.traces.push_back(RequestTrace {
+            id,
+            entries: vec![entry],
+            finished: false,
+        });
+    }
+
+    /// Stamps the phase that ends `id`'s life and queues the trace for telemetry once.
+    pub fn finish(&mut self, id: DialogueRequestId, phase: TracePhase, at_seconds: f64) {
+        self.record(id, phase, at_seconds);
+        if let Some(trace) = self.traces.iter_mut().find(|trace| trace.id == id) {
+            if !trace.finished {
+                trace.finished = true;
+                self.finished.push(id);
+            }
+        }
+    }
+
+    pub fn get(&self, id: DialogueRequestId) -> Option<&RequestTrace> {
+        self.traces.iter().find(|trace| trace.id == id)
+    }
+
+    /// The most recently started trace.
+    pub fn latest(&self) -> Option<&RequestTrace> {
+        self.traces.back()
+    }
+
+    /// The trace started just before `id`'s, if both are still kept.
+    pub fn before(&self, id: DialogueRequestId) -> Option<&RequestTrace> {
+        let index = self.traces.iter().position(|trace| trace.id == id)?;
+        index
+            .checked_sub(1)
+            .and_then(|index| self.traces.get(index))
+    }
+
+    /// Traces finished since the last call, in the order they finished.
+    pub fn take_finished(&mut self) -> Vec<RequestTrace> {
+        std::mem::take(&mut self.finished)
+            .into_iter()
+            .filter_map(|id| self.get(id).cloned())
+            .collect()
+    }
+
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.traces.len()
+    }
+}
+
+impl Default for DialogueRequestTrace {
+    fn default() -> Self {
+        Self::new(DEFAULT_TRACE_CAPACITY)
+    }
+}
+
+/// Lets a system stamp phases with the current app time. Both resources are optional, so
+/// apps without `Time` or the trace store (unit tests) skip tracing.
+#[derive(SystemParam)]
+pub struct RequestTracing<'w> {
+    time: Option<Res<'w, Time>>,
+    trace: Option<ResMut<'w, DialogueRequestTrace>>,
+}
+
+impl RequestTracing<'_> {
+    pub fn record(&mut self, id: DialogueRequestId, phase: TracePhase) {
+        let now = self.now();
+        if let Some(trace) = self.trace.as_deref_mut() {
+            trace.record(id, phase, now);
+        }
+    }
+
+    pub fn finish(&mut self, id: DialogueRequestId, phase: TracePhase) {
+        let now = self.now();
+        if let Some(trace) = self.trace.as_deref_mut() {
+            trace.finish(id, phase, now);
+        }
+    }
+
+    fn now(&self) -> f64 {
+        self.time
+            .as_ref()
+            .map_or(0.0, |time| time.elapsed_secs_f64())
+    }
+}
+
+/// Stamps requests the queue accepted or withdrew since the last run. Runs before dispatch,
+/// so a request enqueued and sent in the same frame still starts with `Enqueued`.
+pub fn trace_queued_dialogue_requests(
+    mut queue: ResMut<DialogueRequestQueue>,
+    mut tracing: RequestTracing,
+) {
+    for (id, phase) in queue.take_untraced() {
+        if phase == TracePhase::Cancelled {
+            tracing.finish(id, phase);
+        } else {
+            tracing.record(id, phase);
+        }
+    }
+}
+
+/// Logs a request's trace when `DialogueTraceDump` (F2) is pressed: the latest request, or
+/// with `DialogueTraceOlderModifier` (Shift) held, the one before the trace shown last.
+pub fn handle_dialogue_trace_dump(
+    input: ActionInput,
+    trace: Res<DialogueRequestTrace>,
+    mut shown: Local<Option<DialogueRequestId>>,
+) {
+    if !input.just_pressed(InputAction::DialogueTraceDump) {
+        return;
+    }
+
+    let older = input.pressed(InputAction::DialogueTraceOlderModifier);
+    let picked = match (*shown, older) {
+        (Some(last), true) => trace.before(last),
+        _ => trace.latest(),
+    };
+    match picked {
+        Some(picked) => {
+            info!("{}", picked.format());
+            *shown = Some(picked.id());
+        }
+        None if older => info!("No older dialogue trace is kept."),
+        None => info!("No dialogue requests traced yet."),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(value: u64) -> DialogueRequestId {
+        DialogueRequestId::new(value)
+    }
+
+    #[test]
+    fn phases_accumulate_per_request_and_finish_once() {
+        let mut store = DialogueRequestTrace::default();
+        store.record(id(1), TracePhase::Enqueued, 1.0);
+        store.record(id(2), TracePhase::Enqueued, 1.5);
+        store.record(id(1), TracePhase::Dispatched { attempt: 1 }, 2.0);
+        store.finish(id(1), TracePhase::Completed { attempt: 1 }, 2.5);
+        store.record(id(1), TracePhase::Rendered, 2.6);
+
+        let trace = store.get(id(1)).unwrap();
+        assert!(trace.is_finished());
+        assert_eq!(
+            trace.phases().collect::<Vec<_>>(),
+            [
+                TracePhase::Enqueued,
+                TracePhase::Dispatched { attempt: 1 },
+                TracePhase::Completed { attempt: 1 },
+                TracePhase::Rendered,
+            ]
+        );
+        assert!(!store.get(id(2)).unwrap().is_finished());
+        assert_eq!(store.latest().map(RequestTrace::id), Some(id(2)));
+        assert_eq!(store.before(id(2)).map(RequestTrace::id), Some(id(1)));
+        assert!(store.before(id(1)).is_none());
+
+        let finished = store.take_finished();
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].id(), id(1));
+        assert!(
+            store.take_finished().is_empty(),
+            "each trace goes to telemetry once"
+        );
+        store.finish(id(1), TracePhase::Cancelled, 3.0);
+        assert!(store.take_finished().is_empty());
+    }
+
+    #[test]
+    fn durations_measure_the_gap_to_the_next_phase() {
+        let mut store = DialogueRequestTrace::default();
+        store.record(id(7), TracePhase::Enqueued, 10.0);
+        store.record(id(7), TracePhase::Dispatched { attempt: 1 }, 10.5);
+        store.record(id(7), TracePhase::Failed { attempt: 1 }, 12.0);
+        store.record(id(7), TracePhase::Retried { attempt: 2 }, 12.0);
+        store.record(id(7), TracePhase::Dispatched { attempt: 2 }, 17.0);
+        store.finish(id(7), TracePhase::Completed { attempt: 2 }, 17.25);
+
+        let trace = store.get(id(7)).unwrap();
+        assert_eq!(
+            trace.durations(),
+            [
+                (TracePhase::Enqueued, Some(0.5)),
+                (TracePhase::Dispatched { attempt: 1 }, Some(1.5)),
+                (TracePhase::Failed { attempt: 1 }, Some(0.0)),
+                (TracePhase::Retried { attempt: 2 }, Some(5.0)),
+                (TracePhase::Dispatched { attempt: 2 }, Some(0.25)),
+                (TracePhase::Completed { attempt: 2 }, None),
+            ]
+        );
+        assert_eq!(trace.total_seconds(), 7.25);
+
+        let dump = trace.format();
+        assert!(dump.starts_with("Dialogue trace request=7 (finished, 7.25s):"));
+        assert!(dump.contains("  +2.00s failed (attempt 1), 0.00s"));
+        assert!(dump.ends_with("  +7.25s completed (attempt 2)"));
+    }
+
+    #[test]
+    fn the_store_keeps_only_the_most_recent_requests() {
+        let mut store = DialogueRequestTrace::new(2);
+        store.finish(id(1), TracePhase::Cancelled, 0.0);
+        store.record(id(2), TracePhase::Enqueued, 1.0);
+        store.record(id(3), TracePhase::Enqueued, 2.0);
+
+        assert_eq!(store.len(), 2);
+        assert!(store.get(id(1)).is_none());
+        assert!(
+            store.take_finished().is_empty(),
+            "an evicted trace is not reported"
+        );
+
+        // A phase for a forgotten id starts a fresh, partial trace.
+        store.record(id(1), TracePhase::Rendered, 3.0);
+        assert_eq!(
+            store.get(id(1)).unwrap().phases().collect::<Vec<_>>(),
+            [TracePhase::Rendered]
+        );
+        assert!(store.get(id(2)).is_none());
+    }
+}
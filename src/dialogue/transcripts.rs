@@ -0,0 +1,154 @@
+//! Per-pair conversation transcripts, so everything two participants said to each other
+//! can be pulled up later (and fed back into prompts as history).
+use std::collections::{HashMap, VecDeque};
+
+use bevy::prelude::*;
+
+use super::{chatter::ChatterPair, events::DialogueResponseEvent};
+use crate::{npc::components::NpcId, world::time::WorldClock};
+
+const DEFAULT_TRANSCRIPT_CAPACITY: usize = 50;
+
+/// One line of a conversation, stamped with the world clock when it was said.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TranscriptEntry {
+    pub speaker: NpcId,
+    pub text: String,
+    pub day: u64,
+    pub time_of_day: f32,
+}
+
+impl TranscriptEntry {
+    pub fn new(speaker: NpcId, text: impl Into<String>, day: u64, time_of_day: f32) -> Self {
+        Self {
+            speaker,
+            text: text.into(),
+            day,
+            time_of_day,
+        }
+    }
+}
+
+/// Transcripts keyed by unordered participant pair (NPC-NPC or NPC-player), each bounded
+/// to `capacity` lines with the oldest evicted first.
+#[derive(Resource, Debug)]
+pub struct TranscriptStore {
+    capacity: usize,
+    pairs: HashMap<ChatterPair, VecDeque<TranscriptEntry>>,
+    recorded: HashMap<ChatterPair, u64>,
+}
+
+impl Default for TranscriptStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_TRANSCRIPT_CAPACITY)
+    }
+}
+
+impl TranscriptStore {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            pairs: HashMap::new(),
+            recorded: HashMap::new(),
+        }
+    }
+
+    /// Appends a line `entry.speaker` said to `listener`.
+    pub fn record(&mut self, listener: NpcId, entry: TranscriptEntry) {
+        let pair = ChatterPair::new(entry.speaker, listener);
+        *self.recorded.entry(pair).or_default() += 1;
+        let lines = self.pairs.entry(pair).or_default();
+        lines.push_back(entry);
+        while lines.len() > self.capacity {
+            lines.pop_front();
+        }
+    }
+
+    /// Lines exchanged between `a` and `b`, oldest first.
+    pub fn entries(&self, a: NpcId, b: NpcId) -> impl DoubleEndedIterator<Item = &TranscriptEntry> {
+        self.pairs
+            .get(&ChatterPair::new(a, b))
+            .into_iter()
+            .flatten()
+    }
+
+    /// Lines ever recorded between `a` and `b`, evicted ones included, so a viewer can tell
+    /// whether this pair's transcript changed since it last looked.
+    pub fn recorded(&self, a: NpcId, b: NpcId) -> u64 {
+        self.recorded
+            .get(&ChatterPair::new(a, b))
+            .copied()
+            .unwrap_or(0)
+    }
+}
+
+/// Appends every addressed dialogue response to its pair's transcript. Lines without a
+/// listener have no pair and are left to telemetry.
+pub fn record_dialogue_transcripts(
+    mut responses: MessageReader<DialogueResponseEvent>,
+    clock: Res<WorldClock>,
+    mut store: ResMut<TranscriptStore>,
+) {
+    for event in responses.read() {
+        let response = &event.response;
+        let Some(listener) = response.target else {
+            continue;
+        };
+        store.record(
+            listener,
+            TranscriptEntry::new(
+                response.speaker,
+                response.content.clone(),
+                clock.day_count(),
+                clock.time_of_day(),
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(speaker: NpcId, text: &str) -> TranscriptEntry {
+        TranscriptEntry::new(speaker, text, 1, 0.5)
+    }
+
+    fn texts(store: &TranscriptStore, a: NpcId, b: NpcId) -> Vec<String> {
+        store
+            .entries(a, b)
+            .map(|entry| entry.text.clone())
+            .collect()
+    }
+
+    #[test]
+    fn pair_order_does_not_matter() {
+        let alric = NpcId::new(1);
+        let player = NpcId::player();
+        let mut store = TranscriptStore::default();
+        store.record(player, line(alric, "Morning."));
+        store.record(alric, line(player, "Morning to you."));
+
+        assert_eq!(
+            texts(&store, alric, player),
+            ["Morning.", "Morning to you."]
+        );
+        assert_eq!(texts(&store, player, alric), texts(&store, alric, player));
+        assert!(store.entries(alric, NpcId::new(2)).next().is_none());
+    }
+
+    #[test]
+    fn oldest_lines_are_evicted_per_pair() {
+        let (a, b, c) = (NpcId::new(1), NpcId::new(2), NpcId::new(3));
+        let mut store = TranscriptStore::new(2);
+        store.record(b, line(a, "one"));
+        store.record(a, line(b, "two"));
+        store.record(b, line(a, "three"));
+        store.record(c, line(a, "elsewhere"));
+
+        assert_eq!(texts(&store, a, b), ["two", "three"]);
+        assert_eq!(texts(&store, a, c), ["elsewhere"]);
+        assert_eq!(store.recorded(b, a), 3, "evicted lines still count");
+        assert_eq!(store.recorded(b, c), 0);
+    }
+}
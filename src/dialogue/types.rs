@@ -1,40 +1,103 @@
 //! Shared request/response types exposed by the dialogue module.
-use crate::npc::components::NpcId;
+use std::{borrow::Cow, fmt, path::PathBuf};
+
+use crate::npc::components::{NpcId, PLAYER_DISPLAY_NAME};
+
+use super::builder::DialogueRequestBuilder;
 
 /// Identifier assigned to queued dialogue requests.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct DialogueRequestId(u64);
 
 impl DialogueRequestId {
+    /// Wraps a raw id; the queue numbers requests from 1.
     pub fn new(value: u64) -> Self {
         Self(value)
     }
 
+    /// The raw id.
     pub fn value(self) -> u64 {
         self.0
     }
 }
 
+/// Formats as the `request=<id>` field every log line about a request uses, so one
+/// request's lines can be grepped together.
+impl fmt::Display for DialogueRequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "request={}", self.0)
+    }
+}
+
 /// Hint to help providers frame responses without full prompt templates yet.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub enum DialogueTopicHint {
+    /// Small talk about how the speaker is doing.
     #[default]
     Status,
+    /// A trade the context describes.
     Trade,
+    /// What the speaker is doing next.
     Schedule,
 }
 
+impl DialogueTopicHint {
+    /// Lowercase name, as used in prompt templates and logs.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Status => "status",
+            Self::Trade => "trade",
+            Self::Schedule => "schedule",
+        }
+    }
+}
+
+/// How urgently a request should be answered. Player lines are never held back or
+/// bundled; ambient NPC chatter may share a provider call with other ambient lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DialoguePriority {
+    /// The player speaks or is spoken to.
+    Player,
+    /// NPCs talking among themselves or to nobody in particular.
+    Ambient,
+}
+
 /// Dialogue request describing who is speaking, the target, and prompt context.
 #[derive(Debug, Clone)]
 pub struct DialogueRequest {
+    /// Who says the line.
     pub speaker: NpcId,
+    /// Who it is said to; `None` for lines to nobody in particular.
     pub target: Option<NpcId>,
+    /// Instruction for the provider, e.g. "Greet the miller".
     pub prompt: String,
+    /// What the line is about; picks the topic's system prompt.
     pub topic_hint: DialogueTopicHint,
+    /// Summary and events the provider may refer to.
     pub context: DialogueContext,
+    /// Short description of the speaker, e.g. "a 25-year-old farmer".
+    pub speaker_profile: Option<String>,
+    /// Lines in the speaker's established voice, replayed to the provider as earlier
+    /// replies so their wording stays consistent.
+    pub speaker_examples: Vec<String>,
+    /// Provider that must answer this request, ahead of any `PinnedProvider` on the
+    /// speaker and the router's default.
+    pub provider_override: Option<DialogueProviderKind>,
+    /// Display names of the speaker and target. Prompts and fallback replies use them in
+    /// place of id formatting, so the model never sees (or echoes) "NPC-0001".
+    pub speaker_name: Option<String>,
+    /// See `speaker_name`.
+    pub target_name: Option<String>,
+    /// Set by the queue at dispatch when the daily budget has room for one more live
+    /// call, letting a broker finish a truncated reply with a follow-up request.
+    pub allow_continuation: bool,
+    /// World clock reading after which the queue drops the request undispatched, because
+    /// what it talks about is stale. Ignored for `Player` requests; retries keep it.
+    pub expires_at: Option<ContextClock>,
 }
 
 impl DialogueRequest {
+    /// Request with no names, profile, or deadline; prefer `builder`, which validates.
     pub fn new(
         speaker: NpcId,
         target: Option<NpcId>,
@@ -48,21 +111,83 @@ impl DialogueRequest {
             prompt: prompt.into(),
             topic_hint,
             context,
+            speaker_profile: None,
+            speaker_examples: Vec::new(),
+            provider_override: None,
+            speaker_name: None,
+            target_name: None,
+            allow_continuation: false,
+            expires_at: None,
         }
     }
+
+    /// How prompts refer to `npc`: the player's display name, the speaker's or target's
+    /// name when known, and the id form otherwise.
+    pub fn participant_name(&self, npc: NpcId) -> Cow<'_, str> {
+        let known = if npc.is_player() {
+            Some(PLAYER_DISPLAY_NAME)
+        } else if npc == self.speaker {
+            self.speaker_name.as_deref()
+        } else if Some(npc) == self.target {
+            self.target_name.as_deref()
+        } else {
+            None
+        };
+        match known.map(str::trim).filter(|name| !name.is_empty()) {
+            Some(name) => Cow::Borrowed(name),
+            None => Cow::Owned(npc.to_string()),
+        }
+    }
+
+    /// `Player` when the player speaks or is addressed, `Ambient` otherwise.
+    pub fn priority(&self) -> DialoguePriority {
+        if self.speaker.is_player() || self.target.is_some_and(|target| target.is_player()) {
+            DialoguePriority::Player
+        } else {
+            DialoguePriority::Ambient
+        }
+    }
+
+    /// True once `now` has reached `expires_at`. Requests involving the player never expire.
+    pub fn is_expired(&self, now: ContextClock) -> bool {
+        self.priority() == DialoguePriority::Ambient
+            && self
+                .expires_at
+                .is_some_and(|deadline| now.has_reached(deadline))
+    }
+
+    /// Starts a fluent, validated request for `speaker` (Status topic, no target).
+    pub fn builder(speaker: NpcId) -> DialogueRequestBuilder {
+        DialogueRequestBuilder::new(speaker)
+    }
 }
 
 /// Result returned by dialogue providers.
 #[derive(Debug, Clone)]
 pub struct DialogueResponse {
+    /// The request this answers.
     pub request_id: DialogueRequestId,
+    /// Provider that produced the line.
     pub provider: DialogueProviderKind,
+    /// Copied from the request.
     pub speaker: NpcId,
+    /// Copied from the request.
     pub target: Option<NpcId>,
+    /// The line itself.
     pub content: String,
+    /// Tokens the provider reported for this line; `None` for local or unreported replies.
+    pub tokens_used: Option<u32>,
+    /// Prompt capture file holding the messages behind this line, when capture is on.
+    pub prompt_file: Option<PathBuf>,
+    /// The provider stopped at its output cap; `content` ends in an ellipsis and the UI
+    /// marks the line as cut short.
+    pub truncated: bool,
+    /// A follow-up call finished a truncated reply. `tokens_used` covers both calls.
+    pub continued: bool,
 }
 
 impl DialogueResponse {
+    /// Complete, untruncated line with no token count or prompt file.
     pub fn new(
         request_id: DialogueRequestId,
         provider: DialogueProviderKind,
@@ -76,51 +201,204 @@ impl DialogueResponse {
             speaker,
             target,
             content: content.into(),
+            tokens_used: None,
+            prompt_file: None,
+            truncated: false,
+            continued: false,
         }
     }
+
+    /// Sets `tokens_used`.
+    pub fn with_tokens_used(mut self, tokens: Option<u32>) -> Self {
+        self.tokens_used = tokens;
+        self
+    }
+
+    /// Sets `prompt_file`.
+    pub fn with_prompt_file(mut self, path: Option<PathBuf>) -> Self {
+        self.prompt_file = path;
+        self
+    }
+
+    /// Sets `truncated`.
+    pub fn with_truncated(mut self, truncated: bool) -> Self {
+        self.truncated = truncated;
+        self
+    }
+
+    /// Sets `continued`.
+    pub fn with_continued(mut self, continued: bool) -> Self {
+        self.continued = continued;
+        self
+    }
 }
 
 /// High level context summary plus a list of structured events.
 #[derive(Debug, Clone, Default)]
 pub struct DialogueContext {
+    /// Short background rendered ahead of the events.
     pub summary: Option<String>,
+    /// What the speaker may mention, oldest first.
     pub events: Vec<DialogueContextEvent>,
+    /// World clock at dispatch, stamped by the request queue. Event days are rendered
+    /// relative to it ("yesterday evening"), or as day numbers when it is unset.
+    pub now: Option<ContextClock>,
 }
 
 impl DialogueContext {
+    /// Context with `events` and no summary or clock.
     pub fn with_events(events: Vec<DialogueContextEvent>) -> Self {
         Self {
             summary: None,
             events,
+            now: None,
+        }
+    }
+}
+
+/// A world clock reading carried by a request.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContextClock {
+    /// Days since the world started.
+    pub day: u64,
+    /// Fraction of the day in [0, 1); 0.5 is noon.
+    pub time_of_day: f32,
+}
+
+impl ContextClock {
+    /// Reading at `time_of_day` on `day`.
+    pub fn new(day: u64, time_of_day: f32) -> Self {
+        Self { day, time_of_day }
+    }
+
+    /// The reading `days` (fractions allowed) later, carrying into the next day past
+    /// midnight. Negative or non-finite spans count as zero.
+    pub fn later_by(self, days: f32) -> Self {
+        let days = if days.is_finite() { days.max(0.0) } else { 0.0 };
+        let total = self.time_of_day + days;
+        let whole = total.floor();
+        Self {
+            day: self.day.saturating_add(whole as u64),
+            time_of_day: total - whole,
         }
     }
+
+    /// Midnight at the end of this reading's day.
+    pub fn end_of_day(self) -> Self {
+        Self::new(self.day.saturating_add(1), 0.0)
+    }
+
+    /// True when this reading is at or after `other`, comparing the day first so a
+    /// deadline just past midnight is not mistaken for one earlier in the day.
+    pub fn has_reached(self, other: Self) -> bool {
+        (self.day, self.time_of_day) >= (other.day, other.time_of_day)
+    }
 }
 
 /// Context event categories provided to dialogue providers.
 #[derive(Debug, Clone)]
 pub enum DialogueContextEvent {
+    /// Goods the speaker made, gave, or received.
     Trade(TradeContext),
-    ScheduleUpdate { description: String },
+    /// A change to the speaker's routine.
+    ScheduleUpdate {
+        /// What changed, e.g. "The harvest failed".
+        description: String,
+    },
+    /// Free-form context: a world event notice such as "The market is on today.", or a
+    /// line from a scripted provider (see `scripting`, behind the `scripting` feature).
+    Custom {
+        /// The notice, rendered as written.
+        text: String,
+    },
+    /// A rumor the speaker picked up from someone else, relayed with hedged wording.
+    Hearsay(HearsayContext),
+    /// Something notable that happened around the village, from the `VillageChronicle`.
+    VillageNews(VillageNewsContext),
+}
+
+/// A village-wide happening the speaker may have noticed, though they were not part of it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VillageNewsContext {
+    /// Short sentence describing it ("Bryn turned 31").
+    pub text: String,
+    /// Day it happened on.
+    pub day: u64,
+}
+
+/// Secondhand information passed along in conversation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HearsayContext {
+    /// The NPC the information started with.
+    pub origin: NpcId,
+    /// What happened, phrased as a plain clause ("NPC-0001 exchanged 2 grain crates").
+    pub subject: String,
+    /// Day the underlying event happened; rumors age from here.
+    pub day: u64,
+    /// How many retellings it has been through.
+    pub fidelity: RumorFidelity,
+}
+
+/// How reliable a rumor is. Each retelling degrades it one step; the vaguest rumors are
+/// not passed on again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RumorFidelity {
+    /// Heard straight from the NPC it concerns.
+    Exact,
+    /// Heard from someone who heard it from the source.
+    Secondhand,
+    /// Passed along too many times to be trusted.
+    Hazy,
+}
+
+impl RumorFidelity {
+    /// The fidelity after one more retelling, or `None` when it is too vague to repeat.
+    pub fn degrade(self) -> Option<Self> {
+        match self {
+            Self::Exact => Some(Self::Secondhand),
+            Self::Secondhand => Some(Self::Hazy),
+            Self::Hazy => None,
+        }
+    }
+
+    /// Lead-in the speaker uses when relaying a rumor of this fidelity.
+    pub fn hedge(self) -> &'static str {
+        match self {
+            Self::Exact => "I know that",
+            Self::Secondhand => "I heard that",
+            Self::Hazy => "Someone mentioned, if I have it right, that",
+        }
+    }
 }
 
 /// Trade-specific context that dialogue can reference.
 #[derive(Debug, Clone)]
 pub struct TradeContext {
+    /// Day the trade happened on.
     pub day: u64,
+    /// Day fraction the trade happened at, when known.
+    pub time_of_day: Option<f32>,
+    /// Who gave the goods; `None` when they came out of household storage.
     pub from: Option<NpcId>,
+    /// Who received them; `None` when they went into household storage.
     pub to: Option<NpcId>,
+    /// What changed hands.
     pub descriptor: TradeDescriptor,
+    /// Why it changed hands.
     pub reason: TradeContextReason,
 }
 
 /// Descriptor describing the traded good in simple language.
 #[derive(Debug, Clone)]
 pub struct TradeDescriptor {
+    /// Singular unit label, e.g. "grain crate".
     pub label: String,
+    /// Units moved.
     pub quantity: u32,
 }
 
 impl TradeDescriptor {
+    /// `quantity` units of `label`.
     pub fn new(label: impl Into<String>, quantity: u32) -> Self {
         Self {
             label: label.into(),
@@ -129,12 +407,32 @@ impl TradeDescriptor {
     }
 }
 
-/// Why a trade occurred (production, processing, or exchange).
+/// Why a trade occurred (production, processing, exchange, or a player transfer).
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TradeContextReason {
+    /// Made from nothing by a recipe.
     Production,
+    /// Made from other goods by a recipe.
     Processing,
+    /// Delivered from one NPC to another.
     Exchange,
+    /// Moved between the player and an NPC.
+    PlayerTransfer,
+    /// Moved between an NPC's crate and their household storage.
+    Storage,
+}
+
+impl TradeContextReason {
+    /// Past-tense verb used when describing the trade in prose.
+    pub fn past_tense(self) -> &'static str {
+        match self {
+            Self::Production => "produced",
+            Self::Processing => "processed",
+            Self::Exchange => "exchanged",
+            Self::PlayerTransfer => "handed over",
+            Self::Storage => "stored",
+        }
+    }
 }
 
 // DialogueProviderKind is defined in broker.rs but referenced here.
@@ -155,6 +453,7 @@ mod tests {
 
         let trade_context = TradeContext {
             day: 12,
+            time_of_day: None,
             from: Some(speaker),
             to: target,
             descriptor: descriptor.clone(),
@@ -211,4 +510,37 @@ mod tests {
         assert_eq!(response.content, "All good");
         assert_eq!(response.target, target);
     }
+
+    #[test]
+    fn expiry_deadlines_carry_past_midnight() {
+        let evening = ContextClock::new(3, 0.95);
+        let deadline = evening.later_by(2.0 / 24.0);
+        assert_eq!(deadline.day, 4);
+        assert!((deadline.time_of_day - (0.95 + 2.0 / 24.0 - 1.0)).abs() < 1e-5);
+        assert_eq!(evening.end_of_day(), ContextClock::new(4, 0.0));
+
+        assert!(!ContextClock::new(3, 0.99).has_reached(deadline));
+        assert!(
+            !ContextClock::new(4, 0.01).has_reached(deadline),
+            "early tomorrow is still before a deadline later tomorrow"
+        );
+        assert!(ContextClock::new(4, 0.5).has_reached(deadline));
+        assert!(ContextClock::new(5, 0.0).has_reached(deadline));
+        assert_eq!(evening.later_by(f32::NAN), evening);
+
+        let mut request = DialogueRequest::new(
+            NpcId::new(1),
+            Some(NpcId::player()),
+            "Evening greeting",
+            DialogueTopicHint::Status,
+            DialogueContext::default(),
+        );
+        request.expires_at = Some(deadline);
+        assert!(
+            !request.is_expired(ContextClock::new(9, 0.0)),
+            "requests involving the player never expire"
+        );
+        request.target = Some(NpcId::new(2));
+        assert!(request.is_expired(ContextClock::new(9, 0.0)));
+    }
 }
@@ -0,0 +1,218 @@
+//! Provider-agnostic pre-flight validation for dialogue requests.
+use bevy::prelude::Resource;
+
+use super::{
+    errors::{DialogueContextSource, DialogueErrorKind},
+    types::{DialogueContextEvent, DialogueRequest, DialogueTopicHint},
+};
+
+const DEFAULT_MAX_PROMPT_CHARS: usize = 500;
+const EMPTY_PROMPT_ERROR: &str = "prompt cannot be empty";
+const SELF_TARGET_ERROR: &str = "speaker cannot target themselves";
+const ZERO_QUANTITY_ERROR: &str = "trade context quantity must be greater than zero";
+
+/// Tunable limits applied before a request reaches any broker.
+#[derive(Resource, Debug, Clone)]
+pub struct DialogueValidationConfig {
+    pub max_prompt_chars: usize,
+}
+
+impl Default for DialogueValidationConfig {
+    fn default() -> Self {
+        Self {
+            max_prompt_chars: DEFAULT_MAX_PROMPT_CHARS,
+        }
+    }
+}
+
+/// Checks rules shared by every broker. Runs on the main thread before a background task
+/// is spawned, so invalid requests fail immediately without touching the rate limiter.
+pub fn validate_dialogue_request(
+    request: &DialogueRequest,
+    config: &DialogueValidationConfig,
+) -> Result<(), DialogueErrorKind> {
+    let prompt = request.prompt.trim();
+    if prompt.is_empty() {
+        return Err(DialogueErrorKind::invalid_request(EMPTY_PROMPT_ERROR));
+    }
+
+    let prompt_chars = prompt.chars().count();
+    if prompt_chars > config.max_prompt_chars {
+        return Err(DialogueErrorKind::invalid_request(format!(
+            "prompt is {} characters (limit {})",
+            prompt_chars, config.max_prompt_chars
+        )));
+    }
+
+    if request.target == Some(request.speaker) {
+        return Err(DialogueErrorKind::invalid_request(SELF_TARGET_ERROR));
+    }
+
+    let has_event =
+        |predicate: fn(&DialogueContextEvent) -> bool| request.context.events.iter().any(predicate);
+
+    if has_event(
+        |event| matches!(event, DialogueContextEvent::Trade(trade) if trade.descriptor.quantity == 0),
+    ) {
+        return Err(DialogueErrorKind::invalid_request(ZERO_QUANTITY_ERROR));
+    }
+
+    match request.topic_hint {
+        DialogueTopicHint::Trade => {
+            if request.context.summary.is_none() {
+                return Err(DialogueErrorKind::context_missing(
+                    DialogueContextSource::InventoryState,
+                ));
+            }
+
+            if !has_event(|event| matches!(event, DialogueContextEvent::Trade(_))) {
+                return Err(DialogueErrorKind::context_missing(
+                    DialogueContextSource::TradeHistory,
+                ));
+            }
+        }
+        DialogueTopicHint::Schedule => {
+            if !has_event(|event| matches!(event, DialogueContextEvent::ScheduleUpdate { .. })) {
+                return Err(DialogueErrorKind::context_missing(
+                    DialogueContextSource::ScheduleState,
+                ));
+            }
+        }
+        DialogueTopicHint::Status => {}
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue::types::{
+        DialogueContext, TradeContext, TradeContextReason, TradeDescriptor,
+    };
+    use crate::npc::components::NpcId;
+
+    fn request(
+        prompt: &str,
+        topic: DialogueTopicHint,
+        context: DialogueContext,
+    ) -> DialogueRequest {
+        DialogueRequest::new(NpcId::new(1), Some(NpcId::new(2)), prompt, topic, context)
+    }
+
+    fn trade_context(quantity: u32) -> DialogueContext {
+        let mut context =
+            DialogueContext::with_events(vec![DialogueContextEvent::Trade(TradeContext {
+                day: 1,
+                time_of_day: None,
+                from: Some(NpcId::new(1)),
+                to: Some(NpcId::new(2)),
+                descriptor: TradeDescriptor::new("grain crate", quantity),
+                reason: TradeContextReason::Exchange,
+            })]);
+        context.summary = Some("Day 1 trade".to_string());
+        context
+    }
+
+    fn validate(request: &DialogueRequest) -> Result<(), DialogueErrorKind> {
+        validate_dialogue_request(request, &DialogueValidationConfig::default())
+    }
+
+    #[test]
+    fn accepts_well_formed_requests() {
+        let status = request(
+            "Morning!",
+            DialogueTopicHint::Status,
+            DialogueContext::default(),
+        );
+        assert!(validate(&status).is_ok());
+        let trade = request("Grain for you", DialogueTopicHint::Trade, trade_context(2));
+        assert!(validate(&trade).is_ok());
+    }
+
+    #[test]
+    fn rejects_empty_prompt() {
+        let blank = request("   ", DialogueTopicHint::Status, DialogueContext::default());
+        assert!(matches!(
+            validate(&blank),
+            Err(DialogueErrorKind::InvalidRequest { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_prompts_over_the_configured_cap() {
+        let config = DialogueValidationConfig {
+            max_prompt_chars: 5,
+        };
+        let exact = request(
+            "hello",
+            DialogueTopicHint::Status,
+            DialogueContext::default(),
+        );
+        assert!(validate_dialogue_request(&exact, &config).is_ok());
+        let long = request(
+            "hello!",
+            DialogueTopicHint::Status,
+            DialogueContext::default(),
+        );
+        assert!(matches!(
+            validate_dialogue_request(&long, &config),
+            Err(DialogueErrorKind::InvalidRequest { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_self_targeted_requests() {
+        let mut talking_to_self =
+            request("Hmm", DialogueTopicHint::Status, DialogueContext::default());
+        talking_to_self.target = Some(talking_to_self.speaker);
+        assert!(matches!(
+            validate(&talking_to_self),
+            Err(DialogueErrorKind::InvalidRequest { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_zero_quantity_trades() {
+        let empty_trade = request("Nothing?", DialogueTopicHint::Trade, trade_context(0));
+        assert!(matches!(
+            validate(&empty_trade),
+            Err(DialogueErrorKind::InvalidRequest { .. })
+        ));
+    }
+
+    #[test]
+    fn trade_and_schedule_topics_require_context() {
+        let mut no_summary = trade_context(1);
+        no_summary.summary = None;
+        assert!(matches!(
+            validate(&request("Trade", DialogueTopicHint::Trade, no_summary)),
+            Err(DialogueErrorKind::ContextMissing {
+                missing: DialogueContextSource::InventoryState
+            })
+        ));
+
+        let summary_only = DialogueContext {
+            summary: Some("summary".to_string()),
+            events: Vec::new(),
+            now: None,
+        };
+        assert!(matches!(
+            validate(&request("Trade", DialogueTopicHint::Trade, summary_only)),
+            Err(DialogueErrorKind::ContextMissing {
+                missing: DialogueContextSource::TradeHistory
+            })
+        ));
+
+        assert!(matches!(
+            validate(&request(
+                "Plans",
+                DialogueTopicHint::Schedule,
+                DialogueContext::default()
+            )),
+            Err(DialogueErrorKind::ContextMissing {
+                missing: DialogueContextSource::ScheduleState
+            })
+        ));
+    }
+}
@@ -0,0 +1,37 @@
+//! Economy faults for chaos runs (`--features chaos`): a worker's crate emptied in one go.
+use bevy::prelude::*;
+
+use crate::{
+    economy::{
+        components::{Inventory, TradeGood},
+        events::InventoryChangedEvent,
+        rng::DailyRng,
+    },
+    npc::chaos::pick_npc,
+    world::time::WorldClock,
+};
+
+/// Empties a random worker's inventory, reporting each removed stack as an
+/// `InventoryChangedEvent` so placeholders and queued tasks see the loss.
+pub fn clear_random_inventory(world: &mut World, rng: &mut DailyRng) -> Option<String> {
+    let (entity, identity) = pick_npc::<With<Inventory>>(world, rng)?;
+    let day = world
+        .get_resource::<WorldClock>()
+        .map_or(0, WorldClock::day_count);
+    let mut inventory = world.get_mut::<Inventory>(entity)?;
+    let changes: Vec<_> = TradeGood::ALL
+        .into_iter()
+        .filter_map(|good| {
+            let held = inventory.quantity_of(good);
+            (held > 0).then(|| inventory.remove_good(good, held))?
+        })
+        .collect();
+    let removed: i64 = changes.iter().map(|change| -change.delta).sum();
+    for change in changes {
+        world.write_message(InventoryChangedEvent::from_change(identity.id, day, change));
+    }
+    Some(format!(
+        "cleared {} units from {}'s inventory",
+        removed, identity.display_name
+    ))
+}
@@ -2,21 +2,38 @@
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
 
+use crate::npc::components::NpcId;
+
 /// Placeholder professions used by the micro trade loop.
 #[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Profession {
+    /// Grows grain.
     Farmer,
+    /// Grinds grain into flour.
     Miller,
+    /// Makes tools.
     Blacksmith,
+    /// Brews ale.
+    Innkeeper,
 }
 
 impl Profession {
+    /// Every profession, in declaration order.
+    pub const ALL: [Profession; 4] = [
+        Self::Farmer,
+        Self::Miller,
+        Self::Blacksmith,
+        Self::Innkeeper,
+    ];
+
+    /// Lowercase name, as written in configs.
     pub fn label(self) -> &'static str {
         match self {
             Self::Farmer => "farmer",
             Self::Miller => "miller",
             Self::Blacksmith => "blacksmith",
+            Self::Innkeeper => "innkeeper",
         }
     }
 }
@@ -25,19 +42,46 @@ impl Profession {
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum TradeGood {
+    /// Crates of grain, from the farm.
     Grain,
+    /// Crates of flour, from the mill.
     Flour,
+    /// Crates of tools, from the smithy.
     Tools,
+    /// Casks of ale, from the inn.
+    Ale,
 }
 
 impl TradeGood {
+    /// Every good, in declaration order.
+    pub const ALL: [TradeGood; 4] = [Self::Grain, Self::Flour, Self::Tools, Self::Ale];
+
+    /// Singular "noun unit" label; `format_quantity` pluralizes the unit for counts.
     pub fn label(self) -> &'static str {
         match self {
             Self::Grain => "grain crate",
             Self::Flour => "flour crate",
             Self::Tools => "tool crate",
+            Self::Ale => "ale cask",
+        }
+    }
+
+    /// Parses a good by its config name ("grain", "tools"), ignoring case; "tool" works
+    /// too.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "grain" => Some(Self::Grain),
+            "flour" => Some(Self::Flour),
+            "tools" | "tool" => Some(Self::Tools),
+            "ale" => Some(Self::Ale),
+            _ => None,
         }
     }
+
+    /// Drinks may be consumed as soon as they are delivered, so nobody waits to hold them.
+    pub fn is_drink(self) -> bool {
+        matches!(self, Self::Ale)
+    }
 }
 
 /// Marker identifying a crate entity representing a profession's work spot.
@@ -46,6 +90,15 @@ pub struct ProfessionCrate {
     pub profession: Profession,
 }
 
+/// The NPC who keeps a profession crate, so the player trades with one worker there even
+/// when several share the profession. Set by `assign_crate_owners`.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CrateOwner(pub NpcId);
+
+/// Marker for the market stall where exchange deliveries are handed over.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Marketplace;
+
 /// Marker for a spawned placeholder representing a trade good near a profession crate.
 #[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct TradeGoodPlaceholder {
@@ -53,50 +106,215 @@ pub struct TradeGoodPlaceholder {
     pub good: TradeGood,
 }
 
-/// Inventory storing simple stacks of goods.
+/// Marker for a goods placeholder riding above a courier during a delivery.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct CarriedGoodPlaceholder {
+    pub good: TradeGood,
+    pub bob_phase: f32,
+}
+
+/// Inventory storing goods as sub-stacks keyed by the day they were acquired, so
+/// perishable goods can spoil and removal can consume the oldest stock first.
 #[derive(Component, Debug, Clone, Default)]
 pub struct Inventory {
+    /// Sorted by acquisition day within each good; at most one stack per good and day.
     items: Vec<InventoryItem>,
 }
 
+/// Describes a single stack mutation so callers can forward it as an event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InventoryChange {
+    pub good: TradeGood,
+    pub delta: i64,
+    pub new_total: u32,
+}
+
 impl Inventory {
-    pub fn add_good(&mut self, good: TradeGood, quantity: u32) {
+    /// Adds goods acquired on day 0, for fixtures that don't care about spoilage.
+    #[cfg(test)]
+    pub fn add_good(&mut self, good: TradeGood, quantity: u32) -> Option<InventoryChange> {
+        self.add_good_on(good, quantity, 0)
+    }
+
+    /// Adds goods acquired on `day`, returning the change when anything was added.
+    pub fn add_good_on(
+        &mut self,
+        good: TradeGood,
+        quantity: u32,
+        day: u64,
+    ) -> Option<InventoryChange> {
         if quantity == 0 {
-            return;
+            return None;
         }
-        if let Some(entry) = self.items.iter_mut().find(|entry| entry.good == good) {
+        if let Some(entry) = self
+            .items
+            .iter_mut()
+            .find(|entry| entry.good == good && entry.acquired_day == day)
+        {
             entry.quantity = entry.quantity.saturating_add(quantity);
         } else {
-            self.items.push(InventoryItem { good, quantity });
+            let position = self
+                .items
+                .iter()
+                .rposition(|entry| entry.good == good && entry.acquired_day < day)
+                .map_or_else(
+                    || {
+                        self.items
+                            .iter()
+                            .position(|entry| entry.good == good)
+                            .unwrap_or(self.items.len())
+                    },
+                    |index| index + 1,
+                );
+            self.items.insert(
+                position,
+                InventoryItem {
+                    good,
+                    quantity,
+                    acquired_day: day,
+                },
+            );
         }
+        Some(InventoryChange {
+            good,
+            delta: i64::from(quantity),
+            new_total: self.quantity_of(good),
+        })
     }
 
-    pub fn remove_good(&mut self, good: TradeGood, quantity: u32) -> bool {
-        if quantity == 0 {
-            return true;
+    /// Removes goods oldest first when enough stock exists. Returns `None` when the stock
+    /// is too small.
+    pub fn remove_good(&mut self, good: TradeGood, quantity: u32) -> Option<InventoryChange> {
+        self.take_good(good, quantity).map(|(change, _)| change)
+    }
+
+    /// Removes goods like `remove_good`, also returning the `(acquired_day, quantity)`
+    /// stacks taken so a transfer can hand them on with `add_stacks` without re-dating them.
+    pub fn take_good(
+        &mut self,
+        good: TradeGood,
+        quantity: u32,
+    ) -> Option<(InventoryChange, Vec<(u64, u32)>)> {
+        let current = self.quantity_of(good);
+        if quantity > current {
+            return None;
         }
-        if let Some(position) = self
-            .items
+        let mut taken_stacks = Vec::new();
+        let mut remaining = quantity;
+        for entry in self.items.iter_mut().filter(|entry| entry.good == good) {
+            if remaining == 0 {
+                break;
+            }
+            let taken = entry.quantity.min(remaining);
+            entry.quantity -= taken;
+            remaining -= taken;
+            if taken > 0 {
+                taken_stacks.push((entry.acquired_day, taken));
+            }
+        }
+        self.items.retain(|entry| entry.quantity > 0);
+        Some((
+            InventoryChange {
+                good,
+                delta: -i64::from(quantity),
+                new_total: current - quantity,
+            },
+            taken_stacks,
+        ))
+    }
+
+    /// Removes goods like `remove_good`, but refuses to dip into the `reserved` units held
+    /// back for the owner's own recipes (see `ReservedStock`).
+    #[cfg(test)]
+    pub fn remove_unreserved(
+        &mut self,
+        good: TradeGood,
+        quantity: u32,
+        reserved: u32,
+    ) -> Option<InventoryChange> {
+        self.take_unreserved(good, quantity, reserved)
+            .map(|(change, _)| change)
+    }
+
+    /// `take_good` that leaves the `reserved` units alone, like `remove_unreserved`.
+    pub fn take_unreserved(
+        &mut self,
+        good: TradeGood,
+        quantity: u32,
+        reserved: u32,
+    ) -> Option<(InventoryChange, Vec<(u64, u32)>)> {
+        if quantity > self.available_unreserved(good, reserved) {
+            return None;
+        }
+        self.take_good(good, quantity)
+    }
+
+    /// Adds stacks taken from another inventory, keeping each one's acquisition day.
+    /// Returns the combined change when anything was added.
+    pub fn add_stacks(
+        &mut self,
+        good: TradeGood,
+        stacks: &[(u64, u32)],
+    ) -> Option<InventoryChange> {
+        let added: u32 = stacks
             .iter()
-            .position(|entry| entry.good == good && entry.quantity >= quantity)
-        {
-            let entry = &mut self.items[position];
-            entry.quantity -= quantity;
-            if entry.quantity == 0 {
-                self.items.remove(position);
+            .filter_map(|&(day, quantity)| self.add_good_on(good, quantity, day))
+            .fold(0, |total, change| total.saturating_add(change.delta as u32));
+        (added > 0).then(|| InventoryChange {
+            good,
+            delta: i64::from(added),
+            new_total: self.quantity_of(good),
+        })
+    }
+
+    /// Units of `good` free for anything other than the recipes holding `reserved` units.
+    pub fn available_unreserved(&self, good: TradeGood, reserved: u32) -> u32 {
+        self.quantity_of(good).saturating_sub(reserved)
+    }
+
+    /// Drops stacks of `good` acquired `shelf_life_days` or more days before `today`,
+    /// oldest first, but never below `reserved` units in total. Returns the change when
+    /// anything spoiled.
+    pub fn remove_expired(
+        &mut self,
+        good: TradeGood,
+        shelf_life_days: u64,
+        today: u64,
+        reserved: u32,
+    ) -> Option<InventoryChange> {
+        let mut spoilable = self.available_unreserved(good, reserved);
+        let mut spoiled = 0u32;
+        for entry in self.items.iter_mut().filter(|entry| entry.good == good) {
+            if today < entry.acquired_day.saturating_add(shelf_life_days) {
+                continue;
             }
-            true
-        } else {
-            false
+            let lost = entry.quantity.min(spoilable);
+            entry.quantity -= lost;
+            spoilable -= lost;
+            spoiled = spoiled.saturating_add(lost);
         }
+        self.items.retain(|entry| entry.quantity > 0);
+        (spoiled > 0).then(|| InventoryChange {
+            good,
+            delta: -i64::from(spoiled),
+            new_total: self.quantity_of(good),
+        })
     }
 
     pub fn quantity_of(&self, good: TradeGood) -> u32 {
         self.items
             .iter()
-            .find(|entry| entry.good == good)
-            .map(|entry| entry.quantity)
-            .unwrap_or(0)
+            .filter(|entry| entry.good == good)
+            .fold(0, |total, entry| total.saturating_add(entry.quantity))
+    }
+
+    /// `(acquired_day, quantity)` for each stack of `good`, oldest first.
+    #[cfg(test)]
+    pub fn stacks(&self, good: TradeGood) -> impl Iterator<Item = (u64, u32)> + '_ {
+        self.items
+            .iter()
+            .filter(move |entry| entry.good == good)
+            .map(|entry| (entry.acquired_day, entry.quantity))
     }
 }
 
@@ -104,6 +322,7 @@ impl Inventory {
 struct InventoryItem {
     good: TradeGood,
     quantity: u32,
+    acquired_day: u64,
 }
 
 #[cfg(test)]
@@ -120,9 +339,9 @@ mod tests {
         inventory.add_good(TradeGood::Tools, 1);
 
         assert_eq!(inventory.quantity_of(TradeGood::Grain), 7);
-        assert!(inventory.remove_good(TradeGood::Grain, 4));
+        assert!(inventory.remove_good(TradeGood::Grain, 4).is_some());
         assert_eq!(inventory.quantity_of(TradeGood::Grain), 3);
-        assert!(!inventory.remove_good(TradeGood::Grain, 5));
+        assert!(inventory.remove_good(TradeGood::Grain, 5).is_none());
 
         assert_eq!(Profession::Farmer.label(), "farmer");
         assert_eq!(TradeGood::Tools.label(), "tool crate");
@@ -132,4 +351,123 @@ mod tests {
         };
         assert_eq!(marker.profession, Profession::Farmer);
     }
+
+    #[test]
+    fn inventory_reports_change_deltas() {
+        let mut inventory = Inventory::default();
+
+        let added = inventory
+            .add_good(TradeGood::Flour, 3)
+            .expect("adding goods should report a change");
+        assert_eq!(added.delta, 3);
+        assert_eq!(added.new_total, 3);
+
+        let removed = inventory
+            .remove_good(TradeGood::Flour, 3)
+            .expect("removal should succeed");
+        assert_eq!(removed.delta, -3);
+        assert_eq!(removed.new_total, 0);
+
+        assert!(inventory.add_good(TradeGood::Flour, 0).is_none());
+    }
+
+    #[test]
+    fn stacks_are_kept_per_acquisition_day_in_order() {
+        let mut inventory = Inventory::default();
+        inventory.add_good_on(TradeGood::Grain, 2, 3);
+        inventory.add_good_on(TradeGood::Tools, 1, 1);
+        inventory.add_good_on(TradeGood::Grain, 1, 1);
+        let change = inventory
+            .add_good_on(TradeGood::Grain, 4, 3)
+            .expect("merged into the day 3 stack");
+
+        assert_eq!(change.new_total, 7);
+        assert_eq!(
+            inventory.stacks(TradeGood::Grain).collect::<Vec<_>>(),
+            [(1, 1), (3, 6)]
+        );
+        assert_eq!(
+            inventory.stacks(TradeGood::Tools).collect::<Vec<_>>(),
+            [(1, 1)]
+        );
+    }
+
+    #[test]
+    fn removal_consumes_the_oldest_stock_first() {
+        let mut inventory = Inventory::default();
+        inventory.add_good_on(TradeGood::Flour, 2, 5);
+        inventory.add_good_on(TradeGood::Flour, 2, 2);
+        inventory.add_good_on(TradeGood::Flour, 1, 4);
+
+        let change = inventory.remove_good(TradeGood::Flour, 3).expect("enough");
+        assert_eq!((change.delta, change.new_total), (-3, 2));
+        assert_eq!(
+            inventory.stacks(TradeGood::Flour).collect::<Vec<_>>(),
+            [(5, 2)]
+        );
+        assert!(inventory.remove_good(TradeGood::Flour, 3).is_none());
+        assert_eq!(inventory.quantity_of(TradeGood::Flour), 2);
+    }
+
+    #[test]
+    fn taken_stacks_keep_their_acquisition_days() {
+        let mut source = Inventory::default();
+        source.add_good_on(TradeGood::Grain, 2, 1);
+        source.add_good_on(TradeGood::Grain, 3, 4);
+
+        let (change, stacks) = source.take_good(TradeGood::Grain, 4).expect("enough");
+        assert_eq!((change.delta, change.new_total), (-4, 1));
+        assert_eq!(stacks, [(1, 2), (4, 2)]);
+
+        let mut target = Inventory::default();
+        target.add_good_on(TradeGood::Grain, 1, 4);
+        let added = target
+            .add_stacks(TradeGood::Grain, &stacks)
+            .expect("stacks added");
+        assert_eq!((added.delta, added.new_total), (4, 5));
+        assert_eq!(
+            target.stacks(TradeGood::Grain).collect::<Vec<_>>(),
+            [(1, 2), (4, 3)]
+        );
+        assert!(source.take_unreserved(TradeGood::Grain, 1, 1).is_none());
+    }
+
+    #[test]
+    fn stock_expires_on_the_boundary_day() {
+        let mut inventory = Inventory::default();
+        inventory.add_good_on(TradeGood::Grain, 2, 2);
+        inventory.add_good_on(TradeGood::Grain, 3, 3);
+
+        assert!(inventory
+            .remove_expired(TradeGood::Grain, 3, 4, 0)
+            .is_none());
+        let change = inventory
+            .remove_expired(TradeGood::Grain, 3, 5, 0)
+            .expect("day 2 stock spoils on day 5");
+        assert_eq!((change.delta, change.new_total), (-2, 3));
+        let change = inventory
+            .remove_expired(TradeGood::Grain, 3, 9, 0)
+            .expect("the rest spoils later");
+        assert_eq!(change.new_total, 0);
+        assert_eq!(inventory.stacks(TradeGood::Grain).count(), 0);
+    }
+
+    #[test]
+    fn reserved_stock_does_not_spoil() {
+        let mut inventory = Inventory::default();
+        inventory.add_good_on(TradeGood::Grain, 3, 1);
+        inventory.add_good_on(TradeGood::Grain, 1, 6);
+
+        let change = inventory
+            .remove_expired(TradeGood::Grain, 3, 6, 2)
+            .expect("unreserved stock spoils");
+        assert_eq!((change.delta, change.new_total), (-2, 2));
+        assert_eq!(
+            inventory.stacks(TradeGood::Grain).collect::<Vec<_>>(),
+            [(1, 1), (6, 1)]
+        );
+        assert!(inventory
+            .remove_expired(TradeGood::Grain, 3, 6, 4)
+            .is_none());
+    }
 }
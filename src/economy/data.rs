@@ -1,89 +1,330 @@
 //! Economy data loading and recipe registry.
 use std::collections::HashMap;
-use std::fs;
 use std::path::Path;
 
-use bevy::{log::warn, prelude::Resource};
-use serde::Deserialize;
+use bevy::{
+    log::warn,
+    prelude::{Resource, Vec3},
+};
+use serde::{Deserialize, Serialize};
 
-use super::components::{Profession, TradeGood};
+use super::{
+    components::{Profession, TradeGood},
+    encumbrance::EncumbranceConfig,
+    fairness::FairnessConfig,
+    migration::ECONOMY_SCHEMA,
+    negotiation::NegotiationConfig,
+    routing::RoutingConfig,
+    skills::SkillCurve,
+};
+use crate::{core::migration, world::time::WorldTimeSettings};
 
-const ECONOMY_CONFIG_PATH: &str = "config/economy.toml";
+/// Where `EconomyRegistry::load` reads the economy config.
+pub const ECONOMY_CONFIG_PATH: &str = "config/economy.toml";
+/// Days in the economy week that `days_of_week` indexes into (`day_count % 7`).
+pub use crate::world::time::DAYS_PER_WEEK;
+const DEFAULT_MARKETPLACE_POSITION: [f32; 3] = [0.5, 0.25, 0.5];
 
-#[derive(Debug, Clone, Deserialize)]
+/// The parsed `config/economy.toml`, before `EconomyRegistry::from_config` validates it.
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct EconomyConfig {
+    /// Always the current `ECONOMY_SCHEMA` version once parsed; older files are migrated
+    /// first.
+    #[serde(default = "current_schema_version")]
+    pub schema_version: u32,
+    /// Seed for daily demand and scarcity rolls; the same seed replays the same days.
+    #[serde(default)]
+    pub seed: u64,
+    /// `[[recipes]]`: what each profession makes; at least one is required.
     pub recipes: Vec<RecipeConfig>,
+    /// `[[daily_requests]]`: goods professions may ask for each day.
     #[serde(default)]
     pub daily_requests: Vec<DailyRequestConfig>,
+    /// `[[scarcity_events]]`: chances of a profession's production failing for a day.
+    #[serde(default)]
+    pub scarcity_events: Vec<ScarcityEventConfig>,
+    /// `[skills]`: how experience turns into levels and extra yield.
+    #[serde(default)]
+    pub skills: SkillCurve,
+    /// `[[goods]]`: per-good properties such as shelf life.
+    #[serde(default)]
+    pub goods: Vec<GoodConfig>,
+    /// `[marketplace]`: where exchange deliveries meet.
+    #[serde(default)]
+    pub marketplace: MarketplaceConfig,
+    /// `[fairness]`: how the day's requests are spread across households.
+    #[serde(default)]
+    pub fairness: FairnessConfig,
+    /// `[negotiation]`: whether recipients may refuse deliveries.
+    #[serde(default)]
+    pub negotiation: NegotiationConfig,
+    /// `[routing]`: whether couriers batch deliveries into trips.
+    #[serde(default)]
+    pub routing: RoutingConfig,
+    /// `[encumbrance]`: how heavy loads slow couriers.
+    #[serde(default)]
+    pub encumbrance: EncumbranceConfig,
+}
+
+/// Where exchange deliveries meet. The stall is spawned once at startup, so a changed
+/// position takes effect on the next launch.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MarketplaceConfig {
+    /// World position of the stall as `[x, y, z]`.
+    pub position: [f32; 3],
+}
+
+impl Default for MarketplaceConfig {
+    fn default() -> Self {
+        Self {
+            position: DEFAULT_MARKETPLACE_POSITION,
+        }
+    }
+}
+
+/// Per-good properties; goods without an entry never spoil.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GoodConfig {
+    /// The good described; each may be listed once.
+    pub good: TradeGood,
+    /// Days a unit keeps after it was acquired; it spoils on the day this many days later.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shelf_life_days: Option<u64>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+/// A `[[recipes]]` entry as written; `EconomyRegistry` turns it into a `Recipe`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct RecipeConfig {
+    /// Unique, non-blank name, e.g. "grain_harvest".
     pub id: String,
+    /// Profession that makes it.
     pub actor: Profession,
+    /// Goods made; at least one is required.
     #[serde(default)]
     pub produces: Vec<ProductConfig>,
+    /// Goods used up; empty for raw production.
     #[serde(default)]
     pub consumes: Vec<ProductConfig>,
+    /// Skill experience earned each time the recipe is made.
+    #[serde(default = "default_recipe_xp")]
+    pub xp: u32,
+    /// Calendar seasons the recipe can be made in; empty means all year.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub seasons: Vec<String>,
+    /// Output multiplier by season name; seasons not listed yield 1.0.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub seasonal_yield: HashMap<String, f32>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+/// A good and quantity in a recipe's `produces` or `consumes`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ProductConfig {
+    /// The good.
     pub good: TradeGood,
+    /// Units per batch; 0 is read as 1.
     pub quantity: u32,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+/// A `[[daily_requests]]` entry as written; `EconomyRegistry` turns it into a
+/// `DailyRequest`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct DailyRequestConfig {
+    /// Profession that wants the good.
     pub requester: Profession,
+    /// The good wanted.
     pub good: TradeGood,
+    /// Units wanted, unless `quantity_range` is set; 0 is read as 1.
+    #[serde(default = "default_request_quantity")]
     pub quantity: u32,
+    /// Chance the request appears on a given day.
+    #[serde(default = "default_probability")]
+    pub probability: f32,
+    /// Inclusive `[min, max]` sampled daily in place of `quantity`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quantity_range: Option<[u32; 2]>,
+    /// Week days (0-6) the request may appear on; empty means every day.
+    #[serde(default)]
+    pub days_of_week: Vec<u64>,
+}
+
+/// A `[[scarcity_events]]` entry as written.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ScarcityEventConfig {
+    /// Profession whose production recipes fail for the day.
+    pub profession: Profession,
+    /// Chance it happens on a given day, clamped to [0, 1].
+    pub probability: f32,
+    /// Schedule note the affected NPC talks about, e.g. "The harvest failed".
+    pub description: String,
+}
+
+fn current_schema_version() -> u32 {
+    ECONOMY_SCHEMA.current_version()
+}
+
+fn default_recipe_xp() -> u32 {
+    10
+}
+
+fn default_request_quantity() -> u32 {
+    1
+}
+
+fn default_probability() -> f32 {
+    1.0
 }
 
+/// A validated recipe: the profession that makes it, and what it turns into what.
 #[derive(Debug, Clone)]
 pub struct Recipe {
+    /// Unique name from the config.
     pub id: String,
+    /// Profession that makes it.
     pub actor: Profession,
+    /// Goods made per batch; never empty.
     pub produces: Vec<RecipeOutput>,
+    /// Goods used up per batch.
     pub consumes: Vec<RecipeInput>,
+    /// Skill experience earned per batch.
+    pub xp: u32,
+    /// Lowercase season names the recipe runs in; empty means all year.
+    pub seasons: Vec<String>,
+    /// Output multipliers by lowercase season name, each finite and at least 0.
+    pub seasonal_yield: HashMap<String, f32>,
 }
 
+impl Recipe {
+    /// Whether the recipe may be made in `season`. Without a calendar season every recipe
+    /// is in season.
+    pub fn in_season(&self, season: Option<&str>) -> bool {
+        season.is_none_or(|season| {
+            self.seasons.is_empty() || self.seasons.iter().any(|listed| listed == season)
+        })
+    }
+
+    /// Output multiplier in `season`; 1.0 unless `seasonal_yield` names it.
+    pub fn yield_multiplier(&self, season: Option<&str>) -> f32 {
+        season
+            .and_then(|season| self.seasonal_yield.get(season))
+            .copied()
+            .unwrap_or(1.0)
+    }
+
+    /// `quantity` scaled by the season's multiplier and rounded to whole units; may be 0.
+    pub fn seasonal_quantity(&self, quantity: u32, season: Option<&str>) -> u32 {
+        (quantity as f32 * self.yield_multiplier(season))
+            .round()
+            .max(0.0) as u32
+    }
+
+    /// In season and yielding at least one unit of something. A multiplier that rounds
+    /// every output down to nothing counts as out of season.
+    pub fn available_in(&self, season: Option<&str>) -> bool {
+        self.in_season(season)
+            && self
+                .produces
+                .iter()
+                .any(|output| self.seasonal_quantity(output.quantity, season) > 0)
+    }
+}
+
+/// A good a recipe consumes; quantities are at least one.
 #[derive(Debug, Clone)]
 pub struct RecipeInput {
+    /// The good used up.
     pub good: TradeGood,
+    /// Units per batch.
     pub quantity: u32,
 }
 
+/// A good a recipe produces; quantities are at least one.
 #[derive(Debug, Clone)]
 pub struct RecipeOutput {
+    /// The good made.
     pub good: TradeGood,
+    /// Units per batch, before skill and season multipliers.
     pub quantity: u32,
 }
 
+/// A validated daily request.
 #[derive(Debug, Clone)]
 pub struct DailyRequest {
+    /// Profession that wants the good.
     pub requester: Profession,
+    /// The good wanted.
     pub good: TradeGood,
-    pub quantity: u32,
+    /// Inclusive quantity bounds; equal when the config gave a fixed `quantity`.
+    pub quantity_min: u32,
+    /// See `quantity_min`.
+    pub quantity_max: u32,
+    /// Chance it appears on a given day, in [0, 1].
+    pub probability: f32,
+    /// Week days (0-6) it may appear on; empty means every day.
+    pub days_of_week: Vec<u64>,
 }
 
+/// A validated scarcity event.
+#[derive(Debug, Clone)]
+pub struct ScarcityEvent {
+    /// Profession whose production recipes fail for the day.
+    pub profession: Profession,
+    /// Chance it happens on a given day, in [0, 1].
+    pub probability: f32,
+    /// Schedule note the affected NPC talks about.
+    pub description: String,
+}
+
+/// The validated economy config, with recipes indexed by id and output good.
 #[derive(Resource, Debug, Clone)]
 pub struct EconomyRegistry {
+    seed: u64,
     recipes: HashMap<String, Recipe>,
     recipe_by_output: HashMap<TradeGood, String>,
     daily_requests: Vec<DailyRequest>,
+    scarcity_events: Vec<ScarcityEvent>,
+    skill_curve: SkillCurve,
+    shelf_lives: HashMap<TradeGood, u64>,
+    marketplace_position: Vec3,
+    fairness: FairnessConfig,
+    negotiation: NegotiationConfig,
+    routing: RoutingConfig,
+    encumbrance: EncumbranceConfig,
 }
 
 impl EconomyRegistry {
+    /// Reads, parses, and validates `config/economy.toml`.
+    ///
+    /// ```
+    /// use thegame::prelude::EconomyRegistry;
+    ///
+    /// let registry = EconomyRegistry::load()?;
+    /// assert!(registry.recipe("grain_harvest").is_some());
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn load() -> Result<Self, String> {
+        Self::load_from_file(ECONOMY_CONFIG_PATH)
+    }
+
     fn load_from_file(path: impl AsRef<Path>) -> Result<Self, String> {
-        let data =
-            fs::read_to_string(&path).map_err(|err| format!("unable to read file: {err}"))?;
-        let config: EconomyConfig =
-            toml::from_str(&data).map_err(|err| format!("invalid economy config: {err}"))?;
-        Self::from_config(config)
+        let data = migration::read_migrated(&ECONOMY_SCHEMA, path)?;
+        Self::from_config_with_seasons(
+            EconomyConfig::from_toml_str(&data)?,
+            &WorldTimeSettings::load_or_default().seasons,
+        )
+    }
+
+    /// Validates `config` against the default calendar's seasons; see
+    /// `from_config_with_seasons`.
+    pub fn from_config(config: EconomyConfig) -> Result<Self, String> {
+        Self::from_config_with_seasons(config, &WorldTimeSettings::default().seasons)
     }
 
-    fn from_config(config: EconomyConfig) -> Result<Self, String> {
+    /// Validates `config` and indexes its recipes by id and output good. Season names in
+    /// recipes must be among `calendar_seasons`, ignoring case.
+    pub fn from_config_with_seasons(
+        config: EconomyConfig,
+        calendar_seasons: &[String],
+    ) -> Result<Self, String> {
         if config.recipes.is_empty() {
             return Err("economy config must define at least one recipe".to_string());
         }
@@ -103,6 +344,24 @@ impl EconomyRegistry {
                 ));
             }
 
+            let seasons = recipe
+                .seasons
+                .iter()
+                .map(|season| known_season(&recipe.id, season, calendar_seasons))
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut seasonal_yield = HashMap::new();
+            for (season, multiplier) in &recipe.seasonal_yield {
+                let season = known_season(&recipe.id, season, calendar_seasons)?;
+                if !multiplier.is_finite() || *multiplier < 0.0 {
+                    return Err(format!(
+                        "recipe '{}' has seasonal_yield {multiplier} for {season}; expected a \
+                         number of at least 0",
+                        recipe.id
+                    ));
+                }
+                seasonal_yield.insert(season, *multiplier);
+            }
+
             let converted = Recipe {
                 id: recipe.id.clone(),
                 actor: recipe.actor,
@@ -122,6 +381,9 @@ impl EconomyRegistry {
                         quantity: product.quantity.max(1),
                     })
                     .collect(),
+                xp: recipe.xp,
+                seasons,
+                seasonal_yield,
             };
 
             for output in &converted.produces {
@@ -137,88 +399,332 @@ impl EconomyRegistry {
             recipes.insert(converted.id.clone(), converted);
         }
 
-        let daily_requests = config
-            .daily_requests
-            .into_iter()
-            .map(|request| DailyRequest {
+        let mut daily_requests = Vec::new();
+        for request in config.daily_requests {
+            if let Some(day) = request
+                .days_of_week
+                .iter()
+                .find(|day| **day >= DAYS_PER_WEEK)
+            {
+                return Err(format!(
+                    "daily request for {:?} lists day_of_week {day}; expected 0-{}",
+                    request.good,
+                    DAYS_PER_WEEK - 1
+                ));
+            }
+
+            let (quantity_min, quantity_max) = match request.quantity_range {
+                Some([a, b]) => (a.min(b), a.max(b)),
+                None => (request.quantity.max(1), request.quantity.max(1)),
+            };
+            daily_requests.push(DailyRequest {
                 requester: request.requester,
                 good: request.good,
-                quantity: request.quantity.max(1),
+                quantity_min,
+                quantity_max,
+                probability: request.probability.clamp(0.0, 1.0),
+                days_of_week: request.days_of_week,
+            });
+        }
+
+        let scarcity_events = config
+            .scarcity_events
+            .into_iter()
+            .map(|event| ScarcityEvent {
+                profession: event.profession,
+                probability: event.probability.clamp(0.0, 1.0),
+                description: event.description,
             })
             .collect();
 
+        let mut shelf_lives = HashMap::new();
+        for good in config.goods {
+            let Some(days) = good.shelf_life_days else {
+                continue;
+            };
+            if days == 0 {
+                return Err(format!(
+                    "{:?} has shelf_life_days = 0; expected at least 1",
+                    good.good
+                ));
+            }
+            if shelf_lives.insert(good.good, days).is_some() {
+                return Err(format!("{:?} is listed in goods more than once", good.good));
+            }
+        }
+
         Ok(Self {
+            seed: config.seed,
             recipes,
             recipe_by_output,
             daily_requests,
+            scarcity_events,
+            skill_curve: config.skills.sanitised(),
+            shelf_lives,
+            marketplace_position: Vec3::from_array(config.marketplace.position),
+            fairness: config.fairness.sanitised(),
+            negotiation: config.negotiation.sanitised(),
+            routing: config.routing.sanitised(),
+            encumbrance: config.encumbrance.sanitised(),
         })
     }
 
-    fn fallback() -> Self {
-        let fallback_config = EconomyConfig {
-            recipes: vec![
-                RecipeConfig {
-                    id: "grain_harvest".to_string(),
-                    actor: Profession::Farmer,
-                    produces: vec![ProductConfig {
-                        good: TradeGood::Grain,
-                        quantity: 1,
-                    }],
-                    consumes: vec![],
-                },
-                RecipeConfig {
-                    id: "flour_milling".to_string(),
-                    actor: Profession::Miller,
-                    produces: vec![ProductConfig {
-                        good: TradeGood::Flour,
-                        quantity: 1,
-                    }],
-                    consumes: vec![ProductConfig {
-                        good: TradeGood::Grain,
-                        quantity: 1,
-                    }],
-                },
-                RecipeConfig {
-                    id: "toolsmithing".to_string(),
-                    actor: Profession::Blacksmith,
-                    produces: vec![ProductConfig {
-                        good: TradeGood::Tools,
-                        quantity: 1,
-                    }],
-                    consumes: vec![ProductConfig {
-                        good: TradeGood::Flour,
-                        quantity: 1,
-                    }],
-                },
-            ],
-            daily_requests: vec![DailyRequestConfig {
-                requester: Profession::Farmer,
-                good: TradeGood::Tools,
-                quantity: 1,
-            }],
-        };
+    pub(super) fn fallback() -> Self {
+        Self::from_config(fallback_config()).expect("fallback economy config should be valid")
+    }
 
-        Self::from_config(fallback_config).expect("fallback economy config should be valid")
+    /// Experience curve, with out-of-range values clamped.
+    pub fn skill_curve(&self) -> &SkillCurve {
+        &self.skill_curve
     }
 
+    /// The recipe called `id`.
     pub fn recipe(&self, id: &str) -> Option<&Recipe> {
         self.recipes.get(id)
     }
 
+    /// Every recipe, sorted by id.
+    pub fn recipes(&self) -> Vec<&Recipe> {
+        let mut recipes: Vec<&Recipe> = self.recipes.values().collect();
+        recipes.sort_by(|a, b| a.id.cmp(&b.id));
+        recipes
+    }
+
+    /// The recipe that makes `good`; at most one does.
     pub fn recipe_for_output(&self, good: TradeGood) -> Option<&Recipe> {
         self.recipe_by_output
             .get(&good)
             .and_then(|id| self.recipes.get(id))
     }
 
+    /// Daily requests in config order.
     pub fn daily_requests(&self) -> &[DailyRequest] {
         &self.daily_requests
     }
+
+    /// Scarcity events in config order.
+    pub fn scarcity_events(&self) -> &[ScarcityEvent] {
+        &self.scarcity_events
+    }
+
+    /// Seed for daily demand and scarcity rolls.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Days `good` keeps before spoiling, or `None` when it never spoils.
+    pub fn shelf_life_days(&self, good: TradeGood) -> Option<u64> {
+        self.shelf_lives.get(&good).copied()
+    }
+
+    /// Fairness settings, with out-of-range values clamped.
+    pub fn fairness(&self) -> &FairnessConfig {
+        &self.fairness
+    }
+
+    #[cfg(test)]
+    pub(crate) fn set_fairness_for_tests(&mut self, fairness: FairnessConfig) {
+        self.fairness = fairness;
+    }
+
+    /// Negotiation settings, with out-of-range values clamped.
+    pub fn negotiation(&self) -> &NegotiationConfig {
+        &self.negotiation
+    }
+
+    #[cfg(test)]
+    pub(crate) fn set_negotiation_for_tests(&mut self, negotiation: NegotiationConfig) {
+        self.negotiation = negotiation;
+    }
+
+    /// Routing settings, with out-of-range values clamped.
+    pub fn routing(&self) -> &RoutingConfig {
+        &self.routing
+    }
+
+    /// Encumbrance settings, with out-of-range values clamped.
+    pub fn encumbrance(&self) -> &EncumbranceConfig {
+        &self.encumbrance
+    }
+
+    #[cfg(test)]
+    pub(crate) fn set_encumbrance_for_tests(&mut self, encumbrance: EncumbranceConfig) {
+        self.encumbrance = encumbrance;
+    }
+
+    /// Where exchange deliveries meet.
+    pub fn marketplace_position(&self) -> Vec3 {
+        self.marketplace_position
+    }
+
+    /// Perishable goods with their shelf lives, in `TradeGood::ALL` order.
+    pub fn perishable_goods(&self) -> impl Iterator<Item = (TradeGood, u64)> + '_ {
+        TradeGood::ALL
+            .into_iter()
+            .filter_map(|good| self.shelf_life_days(good).map(|days| (good, days)))
+    }
+}
+
+impl EconomyConfig {
+    /// Parses economy TOML without validating it; see `EconomyRegistry::from_config`. An
+    /// older schema version is migrated in memory first, and a newer one is an error.
+    pub fn from_toml_str(data: &str) -> Result<Self, String> {
+        let data = migration::migrate_str(&ECONOMY_SCHEMA, data)
+            .map_err(|err| format!("invalid economy config: {err}"))?;
+        toml::from_str(&data).map_err(|err| format!("invalid economy config: {err}"))
+    }
+}
+
+/// `season` lowercased, or an error naming the recipe when the calendar has no such
+/// season.
+fn known_season(recipe: &str, season: &str, calendar_seasons: &[String]) -> Result<String, String> {
+    let name = season.trim().to_lowercase();
+    if calendar_seasons.contains(&name) {
+        return Ok(name);
+    }
+    Err(format!(
+        "recipe '{recipe}' lists unknown season '{season}'; expected one of: {}",
+        calendar_seasons.join(", ")
+    ))
+}
+
+/// The compiled-in economy used when `config/economy.toml` cannot be loaded.
+fn fallback_config() -> EconomyConfig {
+    EconomyConfig {
+        schema_version: current_schema_version(),
+        seed: 0,
+        recipes: vec![
+            RecipeConfig {
+                id: "grain_harvest".to_string(),
+                actor: Profession::Farmer,
+                produces: vec![ProductConfig {
+                    good: TradeGood::Grain,
+                    quantity: 1,
+                }],
+                consumes: vec![],
+                xp: default_recipe_xp(),
+                seasons: Vec::new(),
+                seasonal_yield: HashMap::new(),
+            },
+            RecipeConfig {
+                id: "flour_milling".to_string(),
+                actor: Profession::Miller,
+                produces: vec![ProductConfig {
+                    good: TradeGood::Flour,
+                    quantity: 1,
+                }],
+                consumes: vec![ProductConfig {
+                    good: TradeGood::Grain,
+                    quantity: 1,
+                }],
+                xp: default_recipe_xp(),
+                seasons: Vec::new(),
+                seasonal_yield: HashMap::new(),
+            },
+            RecipeConfig {
+                id: "toolsmithing".to_string(),
+                actor: Profession::Blacksmith,
+                produces: vec![ProductConfig {
+                    good: TradeGood::Tools,
+                    quantity: 1,
+                }],
+                consumes: vec![ProductConfig {
+                    good: TradeGood::Flour,
+                    quantity: 1,
+                }],
+                xp: default_recipe_xp(),
+                seasons: Vec::new(),
+                seasonal_yield: HashMap::new(),
+            },
+            RecipeConfig {
+                id: "brewing".to_string(),
+                actor: Profession::Innkeeper,
+                produces: vec![ProductConfig {
+                    good: TradeGood::Ale,
+                    quantity: 1,
+                }],
+                consumes: vec![ProductConfig {
+                    good: TradeGood::Grain,
+                    quantity: 1,
+                }],
+                xp: default_recipe_xp(),
+                seasons: Vec::new(),
+                seasonal_yield: HashMap::new(),
+            },
+        ],
+        daily_requests: vec![
+            DailyRequestConfig {
+                requester: Profession::Farmer,
+                good: TradeGood::Tools,
+                quantity: 1,
+                probability: 1.0,
+                quantity_range: None,
+                days_of_week: Vec::new(),
+            },
+            DailyRequestConfig {
+                requester: Profession::Farmer,
+                good: TradeGood::Ale,
+                quantity: 1,
+                probability: 1.0,
+                quantity_range: None,
+                days_of_week: Vec::new(),
+            },
+            DailyRequestConfig {
+                requester: Profession::Miller,
+                good: TradeGood::Ale,
+                quantity: 1,
+                probability: 1.0,
+                quantity_range: None,
+                days_of_week: Vec::new(),
+            },
+            DailyRequestConfig {
+                requester: Profession::Blacksmith,
+                good: TradeGood::Ale,
+                quantity: 1,
+                probability: 1.0,
+                quantity_range: None,
+                days_of_week: Vec::new(),
+            },
+        ],
+        scarcity_events: Vec::new(),
+        skills: SkillCurve::default(),
+        goods: vec![
+            GoodConfig {
+                good: TradeGood::Grain,
+                shelf_life_days: Some(4),
+            },
+            GoodConfig {
+                good: TradeGood::Flour,
+                shelf_life_days: Some(6),
+            },
+        ],
+        marketplace: MarketplaceConfig::default(),
+        fairness: FairnessConfig::default(),
+        negotiation: NegotiationConfig::default(),
+        routing: RoutingConfig::default(),
+        encumbrance: EncumbranceConfig::default(),
+    }
+}
+
+/// Parses and validates `data` like `EconomyRegistry::load`, and returns it with every
+/// default written out, for village presets.
+pub fn explicit_toml(data: &str) -> Result<toml::Table, String> {
+    let config = EconomyConfig::from_toml_str(data)?;
+    EconomyRegistry::from_config_with_seasons(
+        config.clone(),
+        &WorldTimeSettings::load_or_default().seasons,
+    )?;
+    toml::Table::try_from(config).map_err(|err| format!("unable to write economy config: {err}"))
+}
+
+/// The fallback economy, written out in full.
+pub fn fallback_toml() -> toml::Table {
+    toml::Table::try_from(fallback_config()).expect("fallback economy config serializes")
 }
 
 impl Default for EconomyRegistry {
     fn default() -> Self {
-        match Self::load_from_file(ECONOMY_CONFIG_PATH) {
+        match Self::load() {
             Ok(registry) => registry,
             Err(error) => {
                 warn!(
@@ -10,6 +10,7 @@ use crate::economy::components::{Profession, TradeGood};
 pub enum DependencyCategory {
     Food,
     Tools,
+    Leisure,
 }
 
 impl DependencyCategory {
@@ -17,6 +18,7 @@ impl DependencyCategory {
         match self {
             Self::Food => "food",
             Self::Tools => "tools",
+            Self::Leisure => "leisure",
         }
     }
 }
@@ -49,6 +51,9 @@ impl Default for EconomyDependencyMatrix {
         matrix
             .good_categories
             .insert(TradeGood::Tools, vec![DependencyCategory::Tools]);
+        matrix
+            .good_categories
+            .insert(TradeGood::Ale, vec![DependencyCategory::Leisure]);
 
         matrix.set_profession_requirements(
             Profession::Farmer,
@@ -62,6 +67,10 @@ impl Default for EconomyDependencyMatrix {
             Profession::Blacksmith,
             vec![DependencyCategory::Food, DependencyCategory::Tools],
         );
+        matrix.set_profession_requirements(
+            Profession::Innkeeper,
+            vec![DependencyCategory::Food, DependencyCategory::Tools],
+        );
 
         matrix
     }
@@ -114,5 +123,9 @@ mod tests {
             matrix.categories_for_good(TradeGood::Grain)[0],
             DependencyCategory::Food
         );
+        assert_eq!(
+            matrix.categories_for_good(TradeGood::Ale),
+            &[DependencyCategory::Leisure]
+        );
     }
 }
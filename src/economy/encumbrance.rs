@@ -0,0 +1,163 @@
+//! Encumbrance. With `[encumbrance] enabled` in `config/economy.toml`, a courier carrying a
+//! delivery walks slower the closer the load comes to their carrying capacity, and grumbles
+//! about it now and then when it is nearly full. The slowdown is a `SpeedModifiers` factor,
+//! so it stacks with anything else that changes how fast an NPC walks.
+use serde::{Deserialize, Serialize};
+
+/// `SpeedModifiers` key for the load a courier is carrying.
+pub const ENCUMBRANCE_SPEED_SOURCE: &str = "encumbrance";
+
+const DEFAULT_CAPACITY: u32 = 5;
+const DEFAULT_UNHINDERED_LOAD: f32 = 0.5;
+const DEFAULT_MIN_SPEED_FACTOR: f32 = 0.6;
+const DEFAULT_GRUMBLE_LOAD: f32 = 0.8;
+const DEFAULT_GRUMBLE_COOLDOWN_HOURS: f32 = 6.0;
+const MIN_SPEED_FACTOR_FLOOR: f32 = 0.1;
+
+/// How much a courier carries comfortably and how a heavier load slows them.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct EncumbranceConfig {
+    /// Off by default; couriers then walk at full speed whatever they carry.
+    pub enabled: bool,
+    /// Units that make a full load.
+    pub capacity: u32,
+    /// Load factor (carried units over capacity) up to which couriers keep full speed.
+    pub unhindered_load: f32,
+    /// Fraction of their speed couriers keep at a full load or more.
+    pub min_speed_factor: f32,
+    /// Load factor from which a courier grumbles about the weight.
+    pub grumble_load: f32,
+    /// In-game hours between one courier's grumbles.
+    pub grumble_cooldown_hours: f32,
+}
+
+impl Default for EncumbranceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            capacity: DEFAULT_CAPACITY,
+            unhindered_load: DEFAULT_UNHINDERED_LOAD,
+            min_speed_factor: DEFAULT_MIN_SPEED_FACTOR,
+            grumble_load: DEFAULT_GRUMBLE_LOAD,
+            grumble_cooldown_hours: DEFAULT_GRUMBLE_COOLDOWN_HOURS,
+        }
+    }
+}
+
+impl EncumbranceConfig {
+    /// Clamps values that would divide by zero, stop couriers outright, or make the curve
+    /// run backwards.
+    pub fn sanitised(self) -> Self {
+        Self {
+            capacity: self.capacity.max(1),
+            unhindered_load: finite_or(self.unhindered_load, DEFAULT_UNHINDERED_LOAD)
+                .clamp(0.0, 1.0),
+            min_speed_factor: finite_or(self.min_speed_factor, DEFAULT_MIN_SPEED_FACTOR)
+                .clamp(MIN_SPEED_FACTOR_FLOOR, 1.0),
+            grumble_load: finite_or(self.grumble_load, DEFAULT_GRUMBLE_LOAD).max(0.0),
+            grumble_cooldown_hours: finite_or(
+                self.grumble_cooldown_hours,
+                DEFAULT_GRUMBLE_COOLDOWN_HOURS,
+            )
+            .max(0.0),
+            ..self
+        }
+    }
+
+    /// Speed factor for carrying `carried` units.
+    pub fn speed_factor_for(&self, carried: u32) -> f32 {
+        speed_factor(
+            load_factor(carried, self.capacity),
+            self.unhindered_load,
+            self.min_speed_factor,
+        )
+    }
+
+    /// True when `carried` units are heavy enough to grumble about.
+    pub fn grumbles_at(&self, carried: u32) -> bool {
+        carried > 0 && load_factor(carried, self.capacity) >= self.grumble_load
+    }
+}
+
+fn finite_or(value: f32, fallback: f32) -> f32 {
+    if value.is_finite() {
+        value
+    } else {
+        fallback
+    }
+}
+
+/// `carried` over `capacity` (at least 1). Overloads go past 1.
+pub fn load_factor(carried: u32, capacity: u32) -> f32 {
+    carried as f32 / capacity.max(1) as f32
+}
+
+/// Full speed (1) up to `unhindered` load, then easing linearly down to `min_factor` at a
+/// full load (1) and staying there for heavier ones. A non-finite load counts as heavy.
+pub fn speed_factor(load: f32, unhindered: f32, min_factor: f32) -> f32 {
+    let min_factor = min_factor.clamp(0.0, 1.0);
+    if !load.is_finite() {
+        return min_factor;
+    }
+    if load <= unhindered {
+        return 1.0;
+    }
+    let span = (1.0 - unhindered).max(f32::EPSILON);
+    let strain = ((load - unhindered) / span).min(1.0);
+    1.0 - strain * (1.0 - min_factor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-5, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn load_curve_keeps_full_speed_to_half_load_then_eases_to_the_minimum() {
+        assert_close(speed_factor(0.0, 0.5, 0.6), 1.0);
+        assert_close(speed_factor(0.5, 0.5, 0.6), 1.0);
+        assert_close(speed_factor(0.75, 0.5, 0.6), 0.8);
+        assert_close(speed_factor(1.0, 0.5, 0.6), 0.6);
+        assert_close(speed_factor(3.0, 0.5, 0.6), 0.6);
+        assert_close(speed_factor(1.0, 1.0, 0.6), 1.0);
+        assert_close(speed_factor(1.5, 1.0, 0.6), 0.6);
+        assert_close(speed_factor(f32::NAN, 0.5, 0.6), 0.6);
+
+        assert_close(load_factor(3, 6), 0.5);
+        assert_close(load_factor(9, 6), 1.5);
+        assert_close(load_factor(2, 0), 2.0);
+    }
+
+    #[test]
+    fn config_is_sanitised_and_reads_loads_in_units() {
+        let config = EncumbranceConfig {
+            enabled: true,
+            capacity: 0,
+            unhindered_load: 2.0,
+            min_speed_factor: 0.0,
+            grumble_load: f32::NAN,
+            grumble_cooldown_hours: -1.0,
+        }
+        .sanitised();
+        assert_eq!(config.capacity, 1);
+        assert_eq!(config.unhindered_load, 1.0);
+        assert_eq!(config.min_speed_factor, MIN_SPEED_FACTOR_FLOOR);
+        assert_eq!(config.grumble_load, DEFAULT_GRUMBLE_LOAD);
+        assert_eq!(config.grumble_cooldown_hours, 0.0);
+
+        let defaults = EncumbranceConfig::default();
+        assert_close(defaults.speed_factor_for(2), 1.0);
+        assert_close(defaults.speed_factor_for(4), 0.76);
+        assert_close(defaults.speed_factor_for(5), DEFAULT_MIN_SPEED_FACTOR);
+        assert!(!defaults.grumbles_at(3));
+        assert!(
+            defaults.grumbles_at(4),
+            "a full routed trip is worth a grumble"
+        );
+        assert!(!defaults.grumbles_at(0));
+    }
+}
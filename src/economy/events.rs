@@ -2,9 +2,11 @@
 use bevy::prelude::{Event, Message};
 
 use crate::{
+    dialogue::chronicle::{ChronicleDraft, ChronicleRoster},
     economy::{
-        components::{Profession, TradeGood},
+        components::{InventoryChange, Profession, TradeGood},
         dependency::DependencyCategory,
+        negotiation::TradeDecision,
     },
     npc::components::NpcId,
 };
@@ -19,6 +21,18 @@ pub struct TradeCompletedEvent {
     pub reason: TradeReason,
 }
 
+/// A courier offered goods at the marketplace and the recipient answered. The handoff
+/// waits for the answer to be acted on in the next tick.
+#[derive(Event, Message, Debug, Clone, PartialEq, Eq)]
+pub struct TradeProposedEvent {
+    pub day: u64,
+    pub courier: NpcId,
+    pub recipient: NpcId,
+    pub good: TradeGood,
+    pub quantity: u32,
+    pub decision: TradeDecision,
+}
+
 /// Snapshot recording whether a profession satisfied dependency categories for a day.
 #[derive(Event, Message, Debug, Clone)]
 pub struct ProfessionDependencyUpdateEvent {
@@ -29,18 +43,130 @@ pub struct ProfessionDependencyUpdateEvent {
     pub missing_categories: Vec<DependencyCategory>,
 }
 
+/// Fired whenever an NPC inventory stack changes, so consumers can react without polling.
+#[derive(Event, Message, Debug, Clone)]
+pub struct InventoryChangedEvent {
+    pub npc: NpcId,
+    pub good: TradeGood,
+    pub delta: i64,
+    pub new_total: u32,
+    pub day: u64,
+}
+
+impl InventoryChangedEvent {
+    pub fn from_change(npc: NpcId, day: u64, change: InventoryChange) -> Self {
+        Self {
+            npc,
+            good: change.good,
+            delta: change.delta,
+            new_total: change.new_total,
+            day,
+        }
+    }
+
+    /// Quantity held before the change was applied.
+    pub fn previous_total(&self) -> u32 {
+        (i64::from(self.new_total) - self.delta).max(0) as u32
+    }
+}
+
+/// Fired when perishable stock in an NPC's inventory expires at the start of a day.
+#[derive(Event, Message, Debug, Clone, PartialEq, Eq)]
+pub struct GoodsSpoiledEvent {
+    pub npc: NpcId,
+    pub good: TradeGood,
+    pub quantity: u32,
+    pub day: u64,
+}
+
+/// Fired after a day's dependency evaluation when inequality of crate totals exceeds the
+/// `[fairness]` threshold.
+#[derive(Event, Message, Debug, Clone, PartialEq)]
+pub struct EconomyImbalanceEvent {
+    pub day: u64,
+    /// Gini coefficient of every evaluated NPC's crate total.
+    pub gini: f32,
+    pub richest: NpcId,
+    pub richest_units: u32,
+    pub poorest: NpcId,
+    pub poorest_units: u32,
+}
+
+/// Fired when a Manufacture task lifts an NPC to a new skill level in their profession.
+#[derive(Event, Message, Debug, Clone, PartialEq, Eq)]
+pub struct SkillLevelUpEvent {
+    pub npc: NpcId,
+    pub profession: Profession,
+    pub level: u32,
+    pub day: u64,
+}
+
+/// Notable economy-wide happenings for a day, such as a failed harvest.
+#[derive(Event, Message, Debug, Clone, PartialEq, Eq)]
+pub struct EconomyEventOccurred {
+    pub kind: EconomyEventKind,
+    pub day: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EconomyEventKind {
+    /// The profession's production recipes are suppressed for the day.
+    Scarcity { profession: Profession },
+}
+
+const SCARCITY_SIGNIFICANCE: f32 = 0.7;
+const IMBALANCE_BASE_SIGNIFICANCE: f32 = 0.3;
+
+/// Chronicle line for a scarcity, involving everyone working the stalled profession.
+pub fn distill_economy_event(
+    event: &EconomyEventOccurred,
+    roster: &ChronicleRoster,
+) -> Option<ChronicleDraft> {
+    match event.kind {
+        EconomyEventKind::Scarcity { profession } => Some(ChronicleDraft::new(
+            format!("The {}s had to stop production", profession.label()),
+            roster.workers(profession),
+            SCARCITY_SIGNIFICANCE,
+        )),
+    }
+}
+
+/// Chronicle line for an imbalance, more significant the more unequal the village is.
+pub fn distill_economy_imbalance(
+    event: &EconomyImbalanceEvent,
+    roster: &ChronicleRoster,
+) -> Option<ChronicleDraft> {
+    Some(ChronicleDraft::new(
+        format!(
+            "Goods piled up with {} while {} went short",
+            roster.name(event.richest),
+            roster.name(event.poorest)
+        ),
+        vec![event.richest, event.poorest],
+        IMBALANCE_BASE_SIGNIFICANCE + event.gini.clamp(0.0, 1.0) * 0.5,
+    ))
+}
+
+/// Why goods changed hands in a `TradeCompletedEvent`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TradeReason {
+    /// A recipe with no inputs made the goods.
     Production,
+    /// A recipe turned other goods into these.
     Processing,
+    /// Goods delivered from one NPC to another.
     Exchange,
+    /// Goods moved between the player and an NPC's crate stock.
+    PlayerTransfer,
+    /// Goods moved between an NPC's crate and their household storage.
+    Storage,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::economy::{components::Profession, dependency::EconomyDependencyMatrix};
-    use crate::npc::components::NpcId;
+    use crate::npc::components::{Identity, NpcId};
 
     #[test]
     fn trade_event_exposes_fields() {
@@ -78,4 +204,40 @@ mod tests {
         assert_eq!(event.satisfied_categories, categories);
         assert_eq!(event.missing_categories, vec![DependencyCategory::Tools]);
     }
+
+    #[test]
+    fn scarcity_and_imbalance_become_chronicle_lines() {
+        let alric = Identity::new(NpcId::new(1), "Alric", 30.0);
+        let bryn = Identity::new(NpcId::new(2), "Bryn", 30.0);
+        let roster = ChronicleRoster::new([
+            (&alric, Some(&Profession::Farmer)),
+            (&bryn, Some(&Profession::Miller)),
+        ]);
+
+        let scarcity = EconomyEventOccurred {
+            kind: EconomyEventKind::Scarcity {
+                profession: Profession::Farmer,
+            },
+            day: 3,
+        };
+        let draft = distill_economy_event(&scarcity, &roster).unwrap();
+        assert_eq!(draft.text, "The farmers had to stop production");
+        assert_eq!(draft.involved, [alric.id]);
+
+        let imbalance = EconomyImbalanceEvent {
+            day: 3,
+            gini: 0.6,
+            richest: alric.id,
+            richest_units: 12,
+            poorest: bryn.id,
+            poorest_units: 1,
+        };
+        let draft = distill_economy_imbalance(&imbalance, &roster).unwrap();
+        assert_eq!(
+            draft.text,
+            "Goods piled up with Alric while Bryn went short"
+        );
+        assert_eq!(draft.involved, [alric.id, bryn.id]);
+        assert!((draft.significance - 0.6).abs() < 1e-6);
+    }
 }
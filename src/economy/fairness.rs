@@ -0,0 +1,553 @@
+//! Village fairness. After each day's dependency evaluation every working NPC gets a
+//! wellbeing score, and inequality of their crate totals is measured with a Gini
+//! coefficient. Above the `[fairness]` threshold in `config/economy.toml` the poorest NPC
+//! complains, and, when enabled, the richest profession owes the poorest a delivery the
+//! next day.
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dialogue::{
+        queue::DialogueRequestQueue,
+        types::{DialogueRequest, DialogueTopicHint},
+    },
+    npc::{
+        components::{Identity, NpcId},
+        motivation::{MotivationConfig, NpcMotivation},
+        spatial::NpcIndex,
+    },
+};
+
+use super::{
+    components::{Inventory, Profession, TradeGood},
+    data::EconomyRegistry,
+    events::{EconomyImbalanceEvent, ProfessionDependencyUpdateEvent},
+    planning::SampledRequest,
+};
+
+const DEFAULT_INEQUALITY_THRESHOLD: f32 = 0.5;
+const DEFAULT_COMPLAINT_COOLDOWN_DAYS: u64 = 3;
+const DEFAULT_HISTORY_DAYS: usize = 14;
+
+/// When inequality counts as an imbalance and what the village does about it.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct FairnessConfig {
+    /// Gini coefficient of crate totals (0 equal, towards 1 one NPC holds everything) above
+    /// which an imbalance is reported.
+    pub inequality_threshold: f32,
+    /// Days between complaints from the poorest NPC.
+    pub complaint_cooldown_days: u64,
+    /// Queue a delivery from the richest profession to the poorest on the next day.
+    pub rebalance: bool,
+    /// Units the richest profession owes.
+    pub rebalance_quantity: u32,
+    /// Days of history kept in `VillageFairness`.
+    pub history_days: usize,
+}
+
+impl Default for FairnessConfig {
+    fn default() -> Self {
+        Self {
+            inequality_threshold: DEFAULT_INEQUALITY_THRESHOLD,
+            complaint_cooldown_days: DEFAULT_COMPLAINT_COOLDOWN_DAYS,
+            rebalance: false,
+            rebalance_quantity: 1,
+            history_days: DEFAULT_HISTORY_DAYS,
+        }
+    }
+}
+
+impl FairnessConfig {
+    /// Clamps values that would make the metric meaningless.
+    pub fn sanitised(self) -> Self {
+        Self {
+            inequality_threshold: self.inequality_threshold.clamp(0.0, 1.0),
+            rebalance_quantity: self.rebalance_quantity.max(1),
+            history_days: self.history_days.max(1),
+            ..self
+        }
+    }
+}
+
+/// One NPC's standing at the end of a day.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Wellbeing {
+    pub npc: NpcId,
+    pub profession: Profession,
+    /// Share of the profession's dependency categories that were satisfied.
+    pub satisfied_ratio: f32,
+    /// Units in the NPC's own crate; household storage is shared and not attributed.
+    /// Goods have no prices, so every unit counts the same.
+    pub inventory_units: u32,
+    /// Dopamine within the configured range, 0 at the minimum and 1 at the maximum.
+    pub dopamine_fraction: f32,
+}
+
+impl Wellbeing {
+    /// Mean of the satisfied ratio and dopamine fraction, 0-1. Inventory is left out, since
+    /// it is what the inequality metric measures.
+    pub fn score(&self) -> f32 {
+        (self.satisfied_ratio + self.dopamine_fraction) / 2.0
+    }
+}
+
+/// Share of required categories that were satisfied; 1.0 when nothing is required.
+pub fn satisfied_ratio(satisfied: usize, missing: usize) -> f32 {
+    let required = satisfied + missing;
+    if required == 0 {
+        return 1.0;
+    }
+    satisfied as f32 / required as f32
+}
+
+/// `dopamine` placed within `min..=max`, clamped to 0-1.
+pub fn dopamine_fraction(dopamine: f32, min: f32, max: f32) -> f32 {
+    if max <= min {
+        return 1.0;
+    }
+    ((dopamine - min) / (max - min)).clamp(0.0, 1.0)
+}
+
+/// Gini coefficient of `values`: the mean absolute difference over every ordered pair,
+/// divided by twice the mean. 0 when everyone holds the same (or nothing), approaching 1
+/// as one holder takes everything.
+pub fn gini(values: &[u32]) -> f32 {
+    let total: u64 = values.iter().map(|value| u64::from(*value)).sum();
+    if values.len() < 2 || total == 0 {
+        return 0.0;
+    }
+    let differences: u64 = values
+        .iter()
+        .flat_map(|a| values.iter().map(move |b| a.abs_diff(*b) as u64))
+        .sum();
+    let n = values.len() as f64;
+    (differences as f64 / (2.0 * n * total as f64)) as f32
+}
+
+/// Fairness measured at the end of one day.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FairnessDay {
+    pub day: u64,
+    pub gini: f32,
+    pub wellbeing: Vec<Wellbeing>,
+}
+
+impl FairnessDay {
+    /// Largest crate; the lowest id wins ties.
+    pub fn richest(&self) -> Option<&Wellbeing> {
+        self.wellbeing
+            .iter()
+            .min_by_key(|entry| (std::cmp::Reverse(entry.inventory_units), entry.npc))
+    }
+
+    /// Smallest crate, then lowest wellbeing; the lowest id wins remaining ties.
+    pub fn poorest(&self) -> Option<&Wellbeing> {
+        self.wellbeing.iter().min_by(|a, b| {
+            a.inventory_units
+                .cmp(&b.inventory_units)
+                .then(a.score().total_cmp(&b.score()))
+                .then(a.npc.cmp(&b.npc))
+        })
+    }
+}
+
+/// A delivery the village injects into a future day's plan.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OwedDelivery {
+    pub day: u64,
+    pub request: SampledRequest,
+}
+
+/// Per-day fairness history, plus complaint rate-limiting and owed deliveries.
+#[derive(Resource, Debug, Default)]
+pub struct VillageFairness {
+    history: VecDeque<FairnessDay>,
+    last_complaint_day: Option<u64>,
+    owed: Vec<OwedDelivery>,
+}
+
+impl VillageFairness {
+    #[cfg(test)]
+    pub fn latest(&self) -> Option<&FairnessDay> {
+        self.history.back()
+    }
+
+    fn record(&mut self, day: FairnessDay, capacity: usize) {
+        self.history.retain(|entry| entry.day != day.day);
+        self.history.push_back(day);
+        while self.history.len() > capacity {
+            self.history.pop_front();
+        }
+    }
+
+    fn may_complain(&self, day: u64, cooldown_days: u64) -> bool {
+        self.last_complaint_day
+            .is_none_or(|last| day >= last + cooldown_days.max(1))
+    }
+
+    /// Removes and returns the deliveries owed on `day`.
+    pub fn take_owed(&mut self, day: u64) -> Vec<SampledRequest> {
+        let (due, later): (Vec<_>, Vec<_>) = std::mem::take(&mut self.owed)
+            .into_iter()
+            .partition(|owed| owed.day == day);
+        self.owed = later;
+        due.into_iter().map(|owed| owed.request).collect()
+    }
+}
+
+/// First good produced by a recipe `profession` runs.
+fn good_made_by(registry: &EconomyRegistry, profession: Profession) -> Option<TradeGood> {
+    TradeGood::ALL.into_iter().find(|good| {
+        registry
+            .recipe_for_output(*good)
+            .is_some_and(|recipe| recipe.actor == profession)
+    })
+}
+
+/// Scores every NPC in the day's dependency evaluation and records the village's
+/// inequality. Above the threshold it reports an `EconomyImbalanceEvent`, lets the poorest
+/// NPC complain (at most once per cooldown), and, if enabled, owes a delivery from the
+/// richest profession to the poorest for the next day.
+#[allow(clippy::too_many_arguments)]
+pub fn evaluate_village_fairness(
+    mut updates: MessageReader<ProfessionDependencyUpdateEvent>,
+    registry: Res<EconomyRegistry>,
+    motivation_config: Res<MotivationConfig>,
+    mut fairness: ResMut<VillageFairness>,
+    mut queue: ResMut<DialogueRequestQueue>,
+    mut imbalances: MessageWriter<EconomyImbalanceEvent>,
+    npc_index: Res<NpcIndex>,
+    npcs: Query<(&Identity, &Inventory, Option<&NpcMotivation>)>,
+) {
+    let mut days: Vec<(u64, Vec<Wellbeing>)> = Vec::new();
+    for update in updates.read() {
+        let Some((_, inventory, motivation)) = npc_index
+            .entity(update.npc)
+            .and_then(|entity| npcs.get(entity).ok())
+        else {
+            continue;
+        };
+        let defaults = &motivation_config.defaults;
+        let dopamine = motivation.map_or(defaults.start, NpcMotivation::dopamine);
+        let wellbeing = Wellbeing {
+            npc: update.npc,
+            profession: update.profession,
+            satisfied_ratio: satisfied_ratio(
+                update.satisfied_categories.len(),
+                update.missing_categories.len(),
+            ),
+            inventory_units: TradeGood::ALL
+                .iter()
+                .map(|good| inventory.quantity_of(*good))
+                .sum(),
+            dopamine_fraction: dopamine_fraction(dopamine, defaults.min, defaults.max),
+        };
+        match days.iter_mut().find(|(day, _)| *day == update.day) {
+            Some((_, entries)) => entries.push(wellbeing),
+            None => days.push((update.day, vec![wellbeing])),
+        }
+    }
+
+    let config = registry.fairness();
+    for (day, wellbeing) in days {
+        let totals: Vec<u32> = wellbeing
+            .iter()
+            .map(|entry| entry.inventory_units)
+            .collect();
+        let evaluated = FairnessDay {
+            day,
+            gini: gini(&totals),
+            wellbeing,
+        };
+        info!(
+            "Village fairness day {day}: gini {:.2} across {} NPCs",
+            evaluated.gini,
+            evaluated.wellbeing.len()
+        );
+
+        let imbalance = match (evaluated.richest(), evaluated.poorest()) {
+            (Some(richest), Some(poorest))
+                if evaluated.gini > config.inequality_threshold && richest.npc != poorest.npc =>
+            {
+                Some((richest.clone(), poorest.clone()))
+            }
+            _ => None,
+        };
+        let gini = evaluated.gini;
+        fairness.record(evaluated, config.history_days);
+        let Some((richest, poorest)) = imbalance else {
+            continue;
+        };
+
+        imbalances.write(EconomyImbalanceEvent {
+            day,
+            gini,
+            richest: richest.npc,
+            richest_units: richest.inventory_units,
+            poorest: poorest.npc,
+            poorest_units: poorest.inventory_units,
+        });
+
+        if fairness.may_complain(day, config.complaint_cooldown_days) {
+            queue_imbalance_complaint(&mut queue, &npc_index, &npcs, &richest, &poorest, day);
+            fairness.last_complaint_day = Some(day);
+        }
+
+        if config.rebalance && richest.profession != poorest.profession {
+            match good_made_by(&registry, richest.profession) {
+                Some(good) => {
+                    info!(
+                        "Day {}: the {} owes the {} {} x{}",
+                        day + 1,
+                        richest.profession.label(),
+                        poorest.profession.label(),
+                        good.label(),
+                        config.rebalance_quantity
+                    );
+                    fairness.owed.push(OwedDelivery {
+                        day: day + 1,
+                        request: SampledRequest {
+                            requester: poorest.profession,
+                            good,
+                            quantity: config.rebalance_quantity,
+                        },
+                    });
+                }
+                None => debug!(
+                    "No rebalancing delivery: the {} makes nothing",
+                    richest.profession.label()
+                ),
+            }
+        }
+    }
+}
+
+fn queue_imbalance_complaint(
+    queue: &mut DialogueRequestQueue,
+    npc_index: &NpcIndex,
+    npcs: &Query<(&Identity, &Inventory, Option<&NpcMotivation>)>,
+    richest: &Wellbeing,
+    poorest: &Wellbeing,
+    day: u64,
+) {
+    let name_of = |npc: NpcId| {
+        let identity = npc_index
+            .entity(npc)
+            .and_then(|entity| npcs.get(entity).ok())
+            .map(|(identity, _, _)| identity);
+        Identity::name_or_id(identity, npc)
+    };
+    let poorest_name = name_of(poorest.npc);
+    let richest_name = name_of(richest.npc);
+    let request = DialogueRequest::builder(poorest.npc)
+        .speaker_name(poorest_name.clone())
+        .topic(DialogueTopicHint::Status)
+        .prompt(format!(
+            "{poorest_name} complains that {richest_name} has plenty while their own crate \
+             is nearly empty."
+        ))
+        .summary(format!(
+            "Day {day}: {richest_name} holds {} units, {poorest_name} only {}",
+            richest.inventory_units, poorest.inventory_units
+        ))
+        .enqueue(queue);
+    if let Err(error) = request {
+        warn!("Imbalance complaint from {poorest_name} not queued: {error}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dialogue::chatter::ChatterBudgets,
+        economy::{
+            dependency::DependencyCategory,
+            events::EconomyEventOccurred,
+            reservations::ReservedStock,
+            resources::EconomyActorCache,
+            systems::{prepare_economy_day, refresh_economy_actor_cache},
+            tasks::{ActorTaskQueues, EconomyDayState},
+        },
+        npc::{sleep::SleepRoster, spatial::index_npcs},
+        world::time::{announce_day_change, DayChangedEvent, WorldClock},
+    };
+
+    #[test]
+    fn gini_matches_hand_computed_values() {
+        assert_eq!(gini(&[]), 0.0);
+        assert_eq!(gini(&[7]), 0.0);
+        assert_eq!(gini(&[0, 0, 0]), 0.0);
+        assert_eq!(gini(&[5, 5]), 0.0);
+        // Ordered-pair differences 60 over 2 * 4 * 10.
+        assert!((gini(&[0, 0, 0, 10]) - 0.75).abs() < 1e-6);
+        // Ordered-pair differences 20 over 2 * 4 * 10.
+        assert!((gini(&[1, 2, 3, 4]) - 0.25).abs() < 1e-6);
+        // Ordered-pair differences 8 over 2 * 2 * 6.
+        assert!((gini(&[1, 5]) - 1.0 / 3.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn wellbeing_inputs_are_normalised() {
+        assert_eq!(satisfied_ratio(0, 0), 1.0);
+        assert_eq!(satisfied_ratio(1, 3), 0.25);
+        assert_eq!(dopamine_fraction(40.0, 0.0, 100.0), 0.4);
+        assert_eq!(dopamine_fraction(-5.0, 0.0, 100.0), 0.0);
+        assert_eq!(dopamine_fraction(5.0, 10.0, 10.0), 1.0);
+
+        let wellbeing = Wellbeing {
+            npc: NpcId::new(1),
+            profession: Profession::Farmer,
+            satisfied_ratio: 0.5,
+            inventory_units: 3,
+            dopamine_fraction: 0.3,
+        };
+        assert!((wellbeing.score() - 0.4).abs() < 1e-6);
+    }
+
+    #[test]
+    fn richest_and_poorest_break_ties_predictably() {
+        let entry = |id: u64, units: u32, satisfied_ratio: f32| Wellbeing {
+            npc: NpcId::new(id),
+            profession: Profession::Farmer,
+            satisfied_ratio,
+            inventory_units: units,
+            dopamine_fraction: 0.5,
+        };
+        let day = FairnessDay {
+            day: 0,
+            gini: 0.0,
+            wellbeing: vec![
+                entry(4, 9, 1.0),
+                entry(2, 9, 1.0),
+                entry(1, 0, 1.0),
+                entry(3, 0, 0.0),
+            ],
+        };
+        assert_eq!(day.richest().map(|entry| entry.npc), Some(NpcId::new(2)));
+        assert_eq!(
+            day.poorest().map(|entry| entry.npc),
+            Some(NpcId::new(3)),
+            "the emptier wellbeing loses the tie"
+        );
+    }
+
+    #[test]
+    fn rigged_imbalance_reports_and_owes_a_delivery_next_day() {
+        let mut registry = EconomyRegistry::fallback();
+        registry.set_fairness_for_tests(FairnessConfig {
+            rebalance: true,
+            rebalance_quantity: 2,
+            ..FairnessConfig::default()
+        });
+        let mut app = App::new();
+        app.insert_resource(WorldClock::new())
+            .insert_resource(registry)
+            .init_resource::<MotivationConfig>()
+            .init_resource::<VillageFairness>()
+            .init_resource::<DialogueRequestQueue>()
+            .init_resource::<EconomyDayState>()
+            .init_resource::<ActorTaskQueues>()
+            .init_resource::<ReservedStock>()
+            .init_resource::<ChatterBudgets>()
+            .init_resource::<SleepRoster>()
+            .init_resource::<EconomyActorCache>()
+            .init_resource::<NpcIndex>()
+            .add_message::<ProfessionDependencyUpdateEvent>()
+            .add_message::<EconomyImbalanceEvent>()
+            .add_message::<EconomyEventOccurred>()
+            .add_message::<DayChangedEvent>()
+            .add_systems(
+                Update,
+                (
+                    announce_day_change,
+                    index_npcs,
+                    refresh_economy_actor_cache,
+                    prepare_economy_day,
+                    evaluate_village_fairness,
+                )
+                    .chain(),
+            );
+
+        let mut hoard = Inventory::default();
+        hoard.add_good(TradeGood::Tools, 12);
+        let professions = [
+            (Profession::Blacksmith, "Brom", hoard),
+            (Profession::Farmer, "Maren", Inventory::default()),
+            (Profession::Miller, "Oswin", Inventory::default()),
+        ];
+        for (index, (profession, name, inventory)) in professions.into_iter().enumerate() {
+            app.world_mut().spawn((
+                Identity::new(NpcId::new(index as u64), name, 30.0),
+                profession,
+                inventory,
+            ));
+        }
+        app.update();
+
+        let updates = [
+            (0, Profession::Blacksmith, 2, 0),
+            (1, Profession::Farmer, 0, 2),
+            (2, Profession::Miller, 1, 1),
+        ];
+        for (id, profession, satisfied, missing) in updates {
+            app.world_mut()
+                .write_message(ProfessionDependencyUpdateEvent {
+                    day: 0,
+                    npc: NpcId::new(id),
+                    profession,
+                    satisfied_categories: vec![DependencyCategory::Food; satisfied],
+                    missing_categories: vec![DependencyCategory::Food; missing],
+                });
+        }
+        app.update();
+
+        let messages = app.world().resource::<Messages<EconomyImbalanceEvent>>();
+        let imbalances: Vec<_> = messages.get_cursor().read(messages).cloned().collect();
+        assert_eq!(imbalances.len(), 1);
+        let imbalance = &imbalances[0];
+        // Totals [12, 0, 0]: differences 48 over 2 * 3 * 12.
+        assert!((imbalance.gini - 2.0 / 3.0).abs() < 1e-6);
+        assert_eq!(
+            (imbalance.richest, imbalance.poorest),
+            (NpcId::new(0), NpcId::new(1)),
+            "the farmer's missing needs make them poorer than the miller"
+        );
+        let complaint = app
+            .world()
+            .resource::<DialogueRequestQueue>()
+            .iter_pending()
+            .find(|request| request.speaker == NpcId::new(1))
+            .expect("the poorest complains")
+            .id;
+        let mut queue = app.world_mut().resource_mut::<DialogueRequestQueue>();
+        assert!(queue
+            .pending_mut(complaint)
+            .unwrap()
+            .prompt
+            .contains("Brom has plenty"));
+
+        app.world_mut().resource_mut::<WorldClock>().skip_days(1);
+        app.update();
+
+        let planned = &app.world().resource::<EconomyDayState>().planned_requests;
+        assert!(
+            planned.contains(&SampledRequest {
+                requester: Profession::Farmer,
+                good: TradeGood::Tools,
+                quantity: 2,
+            }),
+            "day 1 plans the owed tools: {planned:?}"
+        );
+        assert_eq!(
+            app.world()
+                .resource::<VillageFairness>()
+                .latest()
+                .map(|day| day.day),
+            Some(0)
+        );
+    }
+}
@@ -0,0 +1,201 @@
+//! Marketplace meetings: exchange deliveries are handed over at the market stall once the
+//! courier and the recipient both stand there.
+use std::collections::HashMap;
+
+use bevy::prelude::Resource;
+
+use super::negotiation::TradeDecision;
+use crate::npc::components::NpcId;
+
+/// One courier's pending handoff at the marketplace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MarketMeeting {
+    pub recipient: NpcId,
+    /// World day the meeting was arranged on; meetings never carry over to the next day.
+    pub day: u64,
+    courier_present: bool,
+    recipient_present: bool,
+    /// Set once a negotiated offer was made: the handoff is awaiting acceptance until the
+    /// courier acts on the answer.
+    awaiting_acceptance: Option<TradeDecision>,
+}
+
+impl MarketMeeting {
+    fn new(recipient: NpcId, day: u64) -> Self {
+        Self {
+            recipient,
+            day,
+            courier_present: false,
+            recipient_present: false,
+            awaiting_acceptance: None,
+        }
+    }
+
+    pub fn both_present(&self) -> bool {
+        self.courier_present && self.recipient_present
+    }
+
+    /// The recipient's answer to a negotiated offer, once one was made.
+    pub fn awaiting_acceptance(&self) -> Option<TradeDecision> {
+        self.awaiting_acceptance
+    }
+}
+
+/// Who is meeting whom at the marketplace, keyed by courier. A courier has at most one
+/// meeting at a time: the delivery at the front of their queue.
+#[derive(Resource, Debug, Default)]
+pub struct MarketMeetings {
+    meetings: HashMap<NpcId, MarketMeeting>,
+}
+
+impl MarketMeetings {
+    /// Starts `courier`'s meeting with `recipient`, keeping one already arranged with the
+    /// same recipient today. A different recipient or day starts over.
+    pub fn arrange(&mut self, courier: NpcId, recipient: NpcId, day: u64) {
+        let meeting = self
+            .meetings
+            .entry(courier)
+            .or_insert_with(|| MarketMeeting::new(recipient, day));
+        if meeting.recipient != recipient || meeting.day != day {
+            *meeting = MarketMeeting::new(recipient, day);
+        }
+    }
+
+    pub fn get(&self, courier: NpcId) -> Option<&MarketMeeting> {
+        self.meetings.get(&courier)
+    }
+
+    /// Meetings as `(courier, meeting)`, lowest courier id first.
+    pub fn iter(&self) -> impl Iterator<Item = (NpcId, &MarketMeeting)> {
+        let mut meetings: Vec<_> = self
+            .meetings
+            .iter()
+            .map(|(courier, meeting)| (*courier, meeting))
+            .collect();
+        meetings.sort_by_key(|(courier, _)| *courier);
+        meetings.into_iter()
+    }
+
+    /// Records whether `npc`, either party of `courier`'s meeting, stands at the market.
+    /// Returns true when they have just arrived.
+    pub fn set_present(&mut self, courier: NpcId, npc: NpcId, present: bool) -> bool {
+        let Some(meeting) = self.meetings.get_mut(&courier) else {
+            return false;
+        };
+        let flag = if npc == courier {
+            &mut meeting.courier_present
+        } else if npc == meeting.recipient {
+            &mut meeting.recipient_present
+        } else {
+            return false;
+        };
+        let arrived = present && !*flag;
+        *flag = present;
+        arrived
+    }
+
+    /// The other party `npc` is standing at the market waiting for, if any.
+    pub fn waiting_for(&self, npc: NpcId) -> Option<NpcId> {
+        self.iter().find_map(|(courier, meeting)| {
+            if courier == npc && meeting.courier_present && !meeting.recipient_present {
+                Some(meeting.recipient)
+            } else if meeting.recipient == npc
+                && meeting.recipient_present
+                && !meeting.courier_present
+            {
+                Some(courier)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Records the recipient's answer to `courier`'s offer. False without a meeting.
+    pub fn propose(&mut self, courier: NpcId, decision: TradeDecision) -> bool {
+        let Some(meeting) = self.meetings.get_mut(&courier) else {
+            return false;
+        };
+        meeting.awaiting_acceptance = Some(decision);
+        true
+    }
+
+    /// Ends `courier`'s meeting once the goods have changed hands.
+    pub fn finish(&mut self, courier: NpcId) -> Option<MarketMeeting> {
+        self.meetings.remove(&courier)
+    }
+
+    /// Drops meetings `keep` rejects, e.g. those whose delivery is no longer queued.
+    pub fn retain(&mut self, mut keep: impl FnMut(NpcId, &MarketMeeting) -> bool) {
+        self.meetings
+            .retain(|courier, meeting| keep(*courier, meeting));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handoff_waits_until_both_parties_are_present() {
+        let courier = NpcId::new(1);
+        let recipient = NpcId::new(2);
+        let mut meetings = MarketMeetings::default();
+        meetings.arrange(courier, recipient, 3);
+
+        assert!(meetings.set_present(courier, courier, true));
+        assert!(
+            !meetings.set_present(courier, courier, true),
+            "already there"
+        );
+        assert_eq!(meetings.waiting_for(courier), Some(recipient));
+        assert_eq!(meetings.waiting_for(recipient), None);
+        assert!(!meetings.get(courier).unwrap().both_present());
+
+        assert!(
+            !meetings.set_present(courier, NpcId::new(9), true),
+            "not a party"
+        );
+        assert!(meetings.set_present(courier, recipient, true));
+        assert!(meetings.get(courier).unwrap().both_present());
+        assert_eq!(meetings.waiting_for(courier), None);
+
+        meetings.set_present(courier, courier, false);
+        assert_eq!(meetings.waiting_for(recipient), Some(courier));
+
+        assert_eq!(
+            meetings.finish(courier).map(|meeting| meeting.recipient),
+            Some(recipient)
+        );
+        assert!(meetings.get(courier).is_none());
+    }
+
+    #[test]
+    fn rearranging_keeps_progress_only_for_the_same_recipient_and_day() {
+        let courier = NpcId::new(1);
+        let mut meetings = MarketMeetings::default();
+        meetings.arrange(courier, NpcId::new(2), 3);
+        meetings.set_present(courier, courier, true);
+
+        meetings.arrange(courier, NpcId::new(2), 3);
+        assert_eq!(meetings.waiting_for(courier), Some(NpcId::new(2)));
+
+        meetings.arrange(courier, NpcId::new(4), 3);
+        assert_eq!(meetings.get(courier).unwrap().recipient, NpcId::new(4));
+        assert_eq!(
+            meetings.waiting_for(courier),
+            None,
+            "new recipient starts over"
+        );
+
+        meetings.set_present(courier, courier, true);
+        meetings.arrange(courier, NpcId::new(4), 4);
+        assert_eq!(
+            meetings.waiting_for(courier),
+            None,
+            "yesterday's arrival is stale"
+        );
+
+        meetings.retain(|_, meeting| meeting.day != 4);
+        assert_eq!(meetings.iter().count(), 0);
+    }
+}
@@ -0,0 +1,104 @@
+//! Schema versions of `config/economy.toml`. Version 2 introduced the `[[goods]]` table;
+//! before it nothing spoiled.
+use toml_edit::{ArrayOfTables, DocumentMut, Item, Table};
+
+use crate::core::migration::{ConfigSchema, MigrationStep};
+
+pub const ECONOMY_SCHEMA: ConfigSchema = ConfigSchema {
+    name: "economy",
+    steps: &[MigrationStep {
+        summary: "goods table",
+        apply: introduce_goods_table,
+    }],
+};
+
+/// Shelf lives the goods table shipped with, pinned here so later changes to the shipped
+/// file do not change what old files migrate to.
+const SHIPPED_SHELF_LIVES: [(&str, i64); 2] = [("grain", 4), ("flour", 6)];
+
+/// Adds the shipped `[[goods]]` shelf lives to a file that has no goods table, which
+/// would otherwise load with nothing perishable. A file that already has one keeps it.
+fn introduce_goods_table(document: &mut DocumentMut) -> Vec<String> {
+    if document.contains_key("goods") {
+        return Vec::new();
+    }
+    let mut goods = ArrayOfTables::new();
+    for (good, days) in SHIPPED_SHELF_LIVES {
+        let mut entry = Table::new();
+        entry.insert("good", toml_edit::value(good));
+        entry.insert("shelf_life_days", toml_edit::value(days));
+        goods.push(entry);
+    }
+    document.insert("goods", Item::ArrayOfTables(goods));
+    SHIPPED_SHELF_LIVES
+        .iter()
+        .map(|(good, days)| {
+            format!(
+                "goods.{good}.shelf_life_days defaulted to {days}; remove the entry to keep \
+                 {good} from spoiling"
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        core::migration::migrate_document,
+        economy::{components::TradeGood, data::EconomyConfig},
+    };
+
+    /// `config/economy.toml` as it shipped before versioning, trimmed to one recipe.
+    const LEGACY_ECONOMY: &str = r#"
+[[recipes]]
+id = "grain_harvest"
+actor = "farmer"
+produces = [{ good = "grain", quantity = 1 }]
+consumes = []
+
+[[daily_requests]]
+requester = "farmer"
+good = "tools"
+quantity = 1
+"#;
+
+    #[test]
+    fn legacy_files_gain_the_shipped_goods_table() {
+        let mut document: DocumentMut = LEGACY_ECONOMY.parse().unwrap();
+        let report = migrate_document(&ECONOMY_SCHEMA, &mut document)
+            .unwrap()
+            .expect("a legacy file migrates");
+        assert_eq!((report.from, report.to), (1, 2));
+        assert_eq!(report.changes.len(), 2);
+        assert!(report.changes[0].starts_with("goods table: goods.grain.shelf_life_days"));
+
+        let config = EconomyConfig::from_toml_str(LEGACY_ECONOMY).unwrap();
+        assert_eq!(config.schema_version, ECONOMY_SCHEMA.current_version());
+        let shelf_lives: Vec<_> = config
+            .goods
+            .iter()
+            .map(|good| (good.good, good.shelf_life_days))
+            .collect();
+        assert_eq!(
+            shelf_lives,
+            [(TradeGood::Grain, Some(4)), (TradeGood::Flour, Some(6))]
+        );
+        assert_eq!(config.recipes.len(), 1, "the rest of the file is kept");
+    }
+
+    #[test]
+    fn existing_goods_tables_are_kept() {
+        let mut document: DocumentMut = "[[goods]]\ngood = \"flour\"\nshelf_life_days = 9"
+            .parse()
+            .unwrap();
+        assert!(introduce_goods_table(&mut document).is_empty());
+        assert_eq!(
+            document["goods"]
+                .as_array_of_tables()
+                .map(ArrayOfTables::len),
+            Some(1),
+            "a customised table is not extended"
+        );
+    }
+}
@@ -1,11 +1,23 @@
 //! Economy module hosting placeholder trade loops and resource definitions.
+#[cfg(feature = "chaos")]
+pub mod chaos;
 pub mod components;
 pub mod data;
 pub mod dependency;
+pub mod encumbrance;
 pub mod events;
+pub mod fairness;
+pub mod market;
+pub mod migration;
+pub mod negotiation;
 pub mod planning;
 pub mod plugin;
+pub mod relationships;
+pub mod reservations;
 pub mod resources;
+pub mod rng;
+pub mod routing;
+pub mod skills;
 pub mod systems;
 pub mod tasks;
 
@@ -0,0 +1,373 @@
+//! Trade negotiation. With `[negotiation] enabled` in `config/economy.toml`, an exchange
+//! delivery that ends a request is offered at the marketplace instead of handed straight
+//! over. The recipient weighs their need for the good, room in their crate, affinity with
+//! the courier, and mood, and answers with a short line. Inputs for the recipient's own
+//! recipes are always taken, so a refusal never stalls a production chain.
+use serde::{Deserialize, Serialize};
+
+use crate::npc::motivation::state::NpcMood;
+
+use super::components::{Profession, TradeGood};
+use super::dependency::{DependencyCategory, EconomyDependencyMatrix};
+
+const DEFAULT_INVENTORY_CAPACITY: u32 = 12;
+const DEFAULT_HOUSEMATE_AFFINITY: f32 = 0.5;
+const DEFAULT_TRADE_AFFINITY: f32 = 0.1;
+const DEFAULT_DECLINE_AFFINITY: f32 = 0.2;
+const DEFAULT_AFFINITY_DECAY_PER_DAY: f32 = 0.05;
+const ACCEPT_LINE: &str = "Gladly!";
+const NO_ROOM_LINE: &str = "Not today, I've no room.";
+const UNWANTED_LINE: &str = "Not today, thank you.";
+
+/// Whether deliveries are negotiated and how the recipient weighs an offer.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct NegotiationConfig {
+    /// Off by default; deliveries are then handed straight over.
+    pub enabled: bool,
+    /// Units a crate holds; offers that would overfill it are refused.
+    pub inventory_capacity: u32,
+    /// Weight of the need for the good (0 covered, 1 every matching category missing).
+    pub need_weight: f32,
+    /// Weight of affinity with the courier (-1 to 1).
+    pub affinity_weight: f32,
+    /// Affinity housemates add to their trading history (see `trade_affinity`).
+    pub housemate_affinity: f32,
+    /// Trading history a completed exchange adds to the pair.
+    pub trade_affinity: f32,
+    /// Trading history a declined offer takes from the pair.
+    pub decline_affinity: f32,
+    /// How far trading history drifts back towards neutral each day.
+    pub affinity_decay_per_day: f32,
+    /// Weight of the recipient's mood (`mood_score`).
+    pub mood_weight: f32,
+    /// Offers scoring at least this are accepted.
+    pub accept_threshold: f32,
+}
+
+impl Default for NegotiationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            inventory_capacity: DEFAULT_INVENTORY_CAPACITY,
+            need_weight: 1.0,
+            affinity_weight: 0.5,
+            housemate_affinity: DEFAULT_HOUSEMATE_AFFINITY,
+            trade_affinity: DEFAULT_TRADE_AFFINITY,
+            decline_affinity: DEFAULT_DECLINE_AFFINITY,
+            affinity_decay_per_day: DEFAULT_AFFINITY_DECAY_PER_DAY,
+            mood_weight: 0.5,
+            accept_threshold: 0.0,
+        }
+    }
+}
+
+impl NegotiationConfig {
+    /// Clamps values that would make every offer fail or the weights meaningless.
+    pub fn sanitised(self) -> Self {
+        Self {
+            inventory_capacity: self.inventory_capacity.max(1),
+            need_weight: self.need_weight.max(0.0),
+            affinity_weight: self.affinity_weight.max(0.0),
+            housemate_affinity: self.housemate_affinity.clamp(0.0, 1.0),
+            trade_affinity: self.trade_affinity.clamp(0.0, 1.0),
+            decline_affinity: self.decline_affinity.clamp(0.0, 1.0),
+            affinity_decay_per_day: self.affinity_decay_per_day.clamp(0.0, 1.0),
+            mood_weight: self.mood_weight.max(0.0),
+            ..self
+        }
+    }
+}
+
+/// What the recipient knows when a courier offers goods.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TradeOffer {
+    /// 0 when the good covers nothing the recipient lacks, 1 when every dependency
+    /// category it counts toward is missing.
+    pub need: f32,
+    /// Units already in the recipient's crate.
+    pub held: u32,
+    pub quantity: u32,
+    /// -1 (hostile) to 1 (close) towards the courier (`trade_affinity`).
+    pub affinity: f32,
+    pub mood: NpcMood,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeclineReason {
+    /// The crate cannot hold the offered goods.
+    NoRoom,
+    /// Need, affinity, and mood together fell short of the threshold.
+    Unwanted,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TradeDecision {
+    Accept,
+    Decline(DeclineReason),
+}
+
+impl TradeDecision {
+    #[cfg(test)]
+    pub fn is_accepted(self) -> bool {
+        self == Self::Accept
+    }
+
+    /// What the recipient says to the courier.
+    pub fn line(self) -> &'static str {
+        match self {
+            Self::Accept => ACCEPT_LINE,
+            Self::Decline(DeclineReason::NoRoom) => NO_ROOM_LINE,
+            Self::Decline(DeclineReason::Unwanted) => UNWANTED_LINE,
+        }
+    }
+}
+
+/// How a mood colours an offer: energised NPCs lean towards yes, depressed ones towards no.
+pub fn mood_score(mood: NpcMood) -> f32 {
+    match mood {
+        NpcMood::Energised => 1.0,
+        NpcMood::Content => 0.25,
+        NpcMood::Tired => -0.25,
+        NpcMood::Depressed => -1.0,
+    }
+}
+
+/// Accepts or declines `offer`. A full crate always declines; otherwise the weighted sum
+/// of need, affinity, and mood must reach `accept_threshold`.
+pub fn decide_trade(offer: &TradeOffer, config: &NegotiationConfig) -> TradeDecision {
+    if offer.held.saturating_add(offer.quantity) > config.inventory_capacity {
+        return TradeDecision::Decline(DeclineReason::NoRoom);
+    }
+    let need = if offer.need.is_finite() {
+        offer.need.clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let affinity = if offer.affinity.is_finite() {
+        offer.affinity.clamp(-1.0, 1.0)
+    } else {
+        0.0
+    };
+    let score = config.need_weight * need
+        + config.affinity_weight * affinity
+        + config.mood_weight * mood_score(offer.mood);
+    if score >= config.accept_threshold {
+        TradeDecision::Accept
+    } else {
+        TradeDecision::Decline(DeclineReason::Unwanted)
+    }
+}
+
+/// Share of the dependency categories `good` counts toward for `profession` that
+/// `satisfied` reports missing; 0 when the good covers none of them.
+pub fn need_for_good(
+    matrix: &EconomyDependencyMatrix,
+    profession: Profession,
+    good: TradeGood,
+    satisfied: impl Fn(DependencyCategory) -> bool,
+) -> f32 {
+    let covered: Vec<DependencyCategory> = matrix
+        .requirements(profession)
+        .iter()
+        .copied()
+        .filter(|category| matrix.categories_for_good(good).contains(category))
+        .collect();
+    if covered.is_empty() {
+        return 0.0;
+    }
+    let missing = covered
+        .iter()
+        .filter(|category| !satisfied(**category))
+        .count();
+    missing as f32 / covered.len() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offer(need: f32, held: u32, affinity: f32, mood: NpcMood) -> TradeOffer {
+        TradeOffer {
+            need,
+            held,
+            quantity: 1,
+            affinity,
+            mood,
+        }
+    }
+
+    fn enabled() -> NegotiationConfig {
+        NegotiationConfig {
+            enabled: true,
+            ..NegotiationConfig::default()
+        }
+    }
+
+    #[test]
+    fn a_full_crate_declines_whatever_the_need_mood_or_affinity() {
+        let config = enabled();
+        for mood in [
+            NpcMood::Energised,
+            NpcMood::Content,
+            NpcMood::Tired,
+            NpcMood::Depressed,
+        ] {
+            for (need, affinity) in [(0.0, -1.0), (1.0, 1.0), (0.5, 0.0)] {
+                let full = offer(need, config.inventory_capacity, affinity, mood);
+                assert_eq!(
+                    decide_trade(&full, &config),
+                    TradeDecision::Decline(DeclineReason::NoRoom),
+                    "need {need}, affinity {affinity}, {mood:?}"
+                );
+            }
+        }
+
+        // One unit below capacity still fits a single-unit offer.
+        let fits = offer(0.0, config.inventory_capacity - 1, 0.0, NpcMood::Content);
+        assert!(decide_trade(&fits, &config).is_accepted());
+        let too_many = TradeOffer {
+            quantity: 2,
+            ..fits
+        };
+        assert_eq!(
+            decide_trade(&too_many, &config),
+            TradeDecision::Decline(DeclineReason::NoRoom)
+        );
+    }
+
+    #[test]
+    fn need_outweighs_a_bad_mood_and_a_cool_relationship() {
+        let config = enabled();
+        // 1.0 - 0.5 - 0.5 = 0.0 reaches the default threshold.
+        assert!(decide_trade(&offer(1.0, 0, -1.0, NpcMood::Depressed), &config).is_accepted());
+        // Without the need the same recipient turns it down.
+        assert_eq!(
+            decide_trade(&offer(0.0, 0, -1.0, NpcMood::Depressed), &config),
+            TradeDecision::Decline(DeclineReason::Unwanted)
+        );
+        // Half the need is not enough for a depressed recipient with no ties to the courier.
+        assert_eq!(
+            decide_trade(&offer(0.4, 0, 0.0, NpcMood::Depressed), &config),
+            TradeDecision::Decline(DeclineReason::Unwanted)
+        );
+    }
+
+    #[test]
+    fn without_need_mood_and_affinity_decide() {
+        let config = enabled();
+        let cases = [
+            (0.0, NpcMood::Energised, true),
+            (0.0, NpcMood::Content, true),
+            (0.0, NpcMood::Tired, false),
+            (0.0, NpcMood::Depressed, false),
+            (1.0, NpcMood::Tired, true),
+            (1.0, NpcMood::Depressed, true),
+            (-1.0, NpcMood::Content, false),
+            (-1.0, NpcMood::Energised, true),
+        ];
+        for (affinity, mood, accepted) in cases {
+            assert_eq!(
+                decide_trade(&offer(0.0, 0, affinity, mood), &config).is_accepted(),
+                accepted,
+                "affinity {affinity}, {mood:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn out_of_range_and_non_finite_inputs_are_clamped() {
+        let config = enabled();
+        assert_eq!(
+            decide_trade(&offer(25.0, 0, -1.0, NpcMood::Depressed), &config),
+            decide_trade(&offer(1.0, 0, -1.0, NpcMood::Depressed), &config)
+        );
+        assert_eq!(
+            decide_trade(&offer(0.0, 0, -9.0, NpcMood::Energised), &config),
+            decide_trade(&offer(0.0, 0, -1.0, NpcMood::Energised), &config)
+        );
+        assert_eq!(
+            decide_trade(&offer(f32::NAN, 0, f32::INFINITY, NpcMood::Tired), &config),
+            TradeDecision::Decline(DeclineReason::Unwanted)
+        );
+    }
+
+    #[test]
+    fn threshold_and_weights_shift_the_outcome() {
+        let picky = NegotiationConfig {
+            accept_threshold: 0.9,
+            ..enabled()
+        };
+        assert!(!decide_trade(&offer(0.5, 0, 0.0, NpcMood::Content), &picky).is_accepted());
+        assert!(decide_trade(&offer(1.0, 0, 0.0, NpcMood::Content), &picky).is_accepted());
+
+        let moody = NegotiationConfig {
+            need_weight: 0.0,
+            affinity_weight: 0.0,
+            ..enabled()
+        };
+        assert!(decide_trade(&offer(0.0, 0, 0.0, NpcMood::Energised), &moody).is_accepted());
+        assert!(!decide_trade(&offer(1.0, 0, 1.0, NpcMood::Tired), &moody).is_accepted());
+
+        let sanitised = NegotiationConfig {
+            inventory_capacity: 0,
+            need_weight: -3.0,
+            ..enabled()
+        }
+        .sanitised();
+        assert_eq!(sanitised.inventory_capacity, 1);
+        assert_eq!(sanitised.need_weight, 0.0);
+        let sanitised = NegotiationConfig {
+            housemate_affinity: 3.0,
+            decline_affinity: -1.0,
+            ..enabled()
+        }
+        .sanitised();
+        assert_eq!(sanitised.housemate_affinity, 1.0);
+        assert_eq!(sanitised.decline_affinity, 0.0);
+    }
+
+    #[test]
+    fn decisions_have_short_replies() {
+        assert_eq!(TradeDecision::Accept.line(), "Gladly!");
+        assert_eq!(
+            TradeDecision::Decline(DeclineReason::NoRoom).line(),
+            "Not today, I've no room."
+        );
+        assert_eq!(
+            TradeDecision::Decline(DeclineReason::Unwanted).line(),
+            "Not today, thank you."
+        );
+    }
+
+    #[test]
+    fn need_is_the_missing_share_of_matching_categories() {
+        let matrix = EconomyDependencyMatrix::default();
+        let profession = Profession::Miller;
+        let good = TradeGood::Grain;
+        let covered: Vec<_> = matrix
+            .requirements(profession)
+            .iter()
+            .copied()
+            .filter(|category| matrix.categories_for_good(good).contains(category))
+            .collect();
+        assert!(!covered.is_empty(), "grain covers a miller need");
+
+        assert_eq!(need_for_good(&matrix, profession, good, |_| false), 1.0);
+        assert_eq!(need_for_good(&matrix, profession, good, |_| true), 0.0);
+
+        let unrelated = TradeGood::ALL
+            .into_iter()
+            .find(|good| {
+                matrix
+                    .categories_for_good(*good)
+                    .iter()
+                    .all(|category| !matrix.requirements(profession).contains(category))
+            })
+            .expect("some good covers nothing the miller needs");
+        assert_eq!(
+            need_for_good(&matrix, profession, unrelated, |_| false),
+            0.0
+        );
+    }
+}
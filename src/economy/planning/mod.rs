@@ -0,0 +1,387 @@
+//! Planner that converts economy requests into actor task queues.
+
+use std::collections::HashMap;
+
+use crate::economy::{
+    components::{Profession, TradeGood},
+    data::{EconomyRegistry, ScarcityEvent, DAYS_PER_WEEK},
+    rng::DailyRng,
+};
+
+mod schedule;
+
+pub use schedule::schedule_daily_requests;
+
+const DEMAND_STREAM: u64 = 0;
+const SCARCITY_STREAM: u64 = 1;
+
+/// A configured request after the day's probability, weekday, and quantity rolls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SampledRequest {
+    pub requester: Profession,
+    pub good: TradeGood,
+    pub quantity: u32,
+}
+
+/// Rolls which configured requests appear on `day` and how much each asks for.
+pub fn sample_daily_requests(registry: &EconomyRegistry, day: u64) -> Vec<SampledRequest> {
+    let mut rng = DailyRng::for_day(registry.seed(), day, DEMAND_STREAM);
+    let weekday = day % DAYS_PER_WEEK;
+
+    registry
+        .daily_requests()
+        .iter()
+        .filter_map(|request| {
+            // Roll both values for every entry so one request's gating doesn't reshuffle
+            // the quantities sampled for the ones after it.
+            let appears = rng.chance(request.probability);
+            let quantity = rng.range_inclusive(request.quantity_min, request.quantity_max);
+            let scheduled_today =
+                request.days_of_week.is_empty() || request.days_of_week.contains(&weekday);
+            (appears && scheduled_today && quantity > 0).then_some(SampledRequest {
+                requester: request.requester,
+                good: request.good,
+                quantity,
+            })
+        })
+        .collect()
+}
+
+/// Scarcity events that strike on `day`.
+pub fn roll_scarcity_events(registry: &EconomyRegistry, day: u64) -> Vec<&ScarcityEvent> {
+    let mut rng = DailyRng::for_day(registry.seed(), day, SCARCITY_STREAM);
+    registry
+        .scarcity_events()
+        .iter()
+        .filter(|event| rng.chance(event.probability))
+        .collect()
+}
+
+/// The part of `current` that `previous` did not already ask for, matched by requester
+/// and good.
+pub fn requests_added_since(
+    previous: &[SampledRequest],
+    current: &[SampledRequest],
+) -> Vec<SampledRequest> {
+    let mut already: HashMap<(Profession, TradeGood), u32> = HashMap::new();
+    for request in previous {
+        *already
+            .entry((request.requester, request.good))
+            .or_default() += request.quantity;
+    }
+    current
+        .iter()
+        .filter_map(|request| {
+            let covered = already
+                .entry((request.requester, request.good))
+                .or_default();
+            let quantity = request.quantity.saturating_sub(*covered);
+            *covered = covered.saturating_sub(request.quantity);
+            (quantity > 0).then_some(SampledRequest {
+                quantity,
+                ..*request
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::Entity;
+
+    use super::*;
+    use crate::{
+        economy::{
+            data::EconomyConfig,
+            resources::{EconomyActor, EconomyActorCache},
+            tasks::ActorTaskQueues,
+        },
+        npc::components::NpcId,
+    };
+
+    pub(super) const RECIPES: &str = r#"
+        seed = 99
+
+        [[recipes]]
+        id = "grain_harvest"
+        actor = "farmer"
+        produces = [{ good = "grain", quantity = 1 }]
+
+        [[recipes]]
+        id = "flour_milling"
+        actor = "miller"
+        produces = [{ good = "flour", quantity = 1 }]
+        consumes = [{ good = "grain", quantity = 1 }]
+
+        [[recipes]]
+        id = "toolsmithing"
+        actor = "blacksmith"
+        produces = [{ good = "tools", quantity = 1 }]
+        consumes = [{ good = "flour", quantity = 1 }]
+    "#;
+
+    pub(super) fn registry(extra: &str) -> EconomyRegistry {
+        let config: EconomyConfig =
+            toml::from_str(&format!("{RECIPES}\n{extra}")).expect("valid test config");
+        EconomyRegistry::from_config(config).expect("valid registry")
+    }
+
+    /// A cache with one NPC per listed profession, ids in list order.
+    pub(super) fn staffed(professions: &[Profession]) -> EconomyActorCache {
+        let mut actors = EconomyActorCache::default();
+        actors.rebuild(
+            professions
+                .iter()
+                .enumerate()
+                .map(|(index, profession)| EconomyActor {
+                    entity: Entity::PLACEHOLDER,
+                    npc_id: NpcId::new(index as u64),
+                    display_name: profession.label().to_string(),
+                    profession: *profession,
+                }),
+        );
+        actors
+    }
+
+    #[test]
+    fn quantities_sample_within_range_and_replay_per_seed() {
+        let registry = registry(
+            r#"
+            [[daily_requests]]
+            requester = "farmer"
+            good = "tools"
+            quantity_range = [4, 2]
+            "#,
+        );
+
+        let mut seen = Vec::new();
+        for day in 0..60 {
+            let requests = sample_daily_requests(&registry, day);
+            assert_eq!(requests.len(), 1);
+            assert!((2..=4).contains(&requests[0].quantity));
+            assert_eq!(requests, sample_daily_requests(&registry, day));
+            seen.push(requests[0].quantity);
+        }
+        for quantity in 2..=4 {
+            assert!(seen.contains(&quantity), "{quantity} never sampled");
+        }
+    }
+
+    #[test]
+    fn probability_and_weekdays_gate_requests() {
+        let registry = registry(
+            r#"
+            [[daily_requests]]
+            requester = "farmer"
+            good = "tools"
+            probability = 0.0
+
+            [[daily_requests]]
+            requester = "miller"
+            good = "grain"
+            probability = 0.5
+
+            [[daily_requests]]
+            requester = "blacksmith"
+            good = "flour"
+            days_of_week = [2]
+            "#,
+        );
+
+        let mut coin_flips = 0;
+        for day in 0..200 {
+            let requests = sample_daily_requests(&registry, day);
+            assert!(requests.iter().all(|r| r.requester != Profession::Farmer));
+            assert_eq!(
+                requests
+                    .iter()
+                    .any(|request| request.requester == Profession::Blacksmith),
+                day % DAYS_PER_WEEK == 2,
+                "day {day}"
+            );
+            coin_flips += requests
+                .iter()
+                .filter(|request| request.requester == Profession::Miller)
+                .count();
+        }
+        assert!((60..140).contains(&coin_flips), "{coin_flips} of 200");
+
+        let config: EconomyConfig = toml::from_str(&format!(
+            "{RECIPES}\n[[daily_requests]]\nrequester = \"farmer\"\ngood = \"tools\"\ndays_of_week = [7]"
+        ))
+        .unwrap();
+        assert!(EconomyRegistry::from_config(config).is_err());
+    }
+
+    #[test]
+    fn only_new_demand_counts_as_added() {
+        let request = |requester, good, quantity| SampledRequest {
+            requester,
+            good,
+            quantity,
+        };
+        let planned = [
+            request(Profession::Farmer, TradeGood::Tools, 1),
+            request(Profession::Miller, TradeGood::Ale, 1),
+        ];
+        let current = [
+            request(Profession::Farmer, TradeGood::Tools, 3),
+            request(Profession::Miller, TradeGood::Ale, 1),
+            request(Profession::Innkeeper, TradeGood::Flour, 2),
+        ];
+        assert_eq!(
+            requests_added_since(&planned, &current),
+            vec![
+                request(Profession::Farmer, TradeGood::Tools, 2),
+                request(Profession::Innkeeper, TradeGood::Flour, 2),
+            ]
+        );
+        assert!(requests_added_since(&current, &planned).is_empty());
+    }
+
+    #[test]
+    fn unknown_season_names_are_rejected() {
+        for (field, value) in [
+            ("seasons", r#"["spring", "monsoon"]"#),
+            ("seasonal_yield", "{ monsoon = 2.0 }"),
+        ] {
+            let config: EconomyConfig = toml::from_str(&format!(
+                "{RECIPES}\n[[recipes]]\nid = \"brewing\"\nactor = \"innkeeper\"\n\
+                 produces = [{{ good = \"ale\", quantity = 1 }}]\n{field} = {value}"
+            ))
+            .unwrap();
+            let error = EconomyRegistry::from_config(config).unwrap_err();
+            assert!(
+                error.contains("brewing") && error.contains("monsoon"),
+                "{field}: {error}"
+            );
+        }
+
+        let config: EconomyConfig = toml::from_str(&format!(
+            "{RECIPES}\n[[recipes]]\nid = \"brewing\"\nactor = \"innkeeper\"\n\
+             produces = [{{ good = \"ale\", quantity = 1 }}]\nseasons = [\"Monsoon\"]"
+        ))
+        .unwrap();
+        let monsoon = ["dry".to_string(), "monsoon".to_string()];
+        let registry = EconomyRegistry::from_config_with_seasons(config, &monsoon)
+            .expect("the calendar decides which names exist");
+        assert_eq!(registry.recipe("brewing").unwrap().seasons, ["monsoon"]);
+    }
+
+    #[test]
+    fn seasonal_yield_rounds_to_whole_units() {
+        let registry = registry(
+            r#"
+            [[recipes]]
+            id = "brewing"
+            actor = "innkeeper"
+            produces = [{ good = "ale", quantity = 3 }]
+            seasonal_yield = { summer = 1.5, autumn = 0.5, winter = 0.1 }
+            "#,
+        );
+        let brewing = registry.recipe("brewing").unwrap();
+        assert_eq!(brewing.seasonal_quantity(3, Some("spring")), 3);
+        assert_eq!(
+            brewing.seasonal_quantity(3, Some("summer")),
+            5,
+            "4.5 rounds up"
+        );
+        assert_eq!(
+            brewing.seasonal_quantity(3, Some("autumn")),
+            2,
+            "1.5 rounds up"
+        );
+        assert_eq!(brewing.seasonal_quantity(3, Some("winter")), 0);
+        assert_eq!(brewing.seasonal_quantity(3, None), 3);
+        assert!(brewing.available_in(Some("autumn")));
+        assert!(
+            !brewing.available_in(Some("winter")),
+            "a yield of nothing is no supply"
+        );
+
+        let config: EconomyConfig = toml::from_str(&format!(
+            "{RECIPES}\n[[recipes]]\nid = \"brewing\"\nactor = \"innkeeper\"\n\
+             produces = [{{ good = \"ale\", quantity = 1 }}]\nseasonal_yield = {{ winter = -1.0 }}"
+        ))
+        .unwrap();
+        assert!(EconomyRegistry::from_config(config).is_err());
+    }
+
+    #[test]
+    fn out_of_season_recipes_drop_dependent_units_in_winter() {
+        let harvest = r#"produces = [{ good = "grain", quantity = 1 }]"#;
+        let seasonal = RECIPES.replacen(
+            harvest,
+            &format!("{harvest}\nseasons = [\"spring\", \"summer\", \"autumn\"]"),
+            1,
+        );
+        let config: EconomyConfig = toml::from_str(&format!(
+            r#"{seasonal}
+            [[recipes]]
+            id = "brewing"
+            actor = "innkeeper"
+            produces = [{{ good = "ale", quantity = 1 }}]
+            seasonal_yield = {{ autumn = 2.0 }}
+            "#
+        ))
+        .expect("valid test config");
+        let registry = EconomyRegistry::from_config(config).expect("valid registry");
+        let requests = [
+            SampledRequest {
+                requester: Profession::Farmer,
+                good: TradeGood::Tools,
+                quantity: 1,
+            },
+            SampledRequest {
+                requester: Profession::Miller,
+                good: TradeGood::Ale,
+                quantity: 1,
+            },
+        ];
+        let actors = staffed(&[
+            Profession::Farmer,
+            Profession::Miller,
+            Profession::Blacksmith,
+            Profession::Innkeeper,
+        ]);
+
+        let mut winter = ActorTaskQueues::default();
+        schedule_daily_requests(
+            &registry,
+            &actors,
+            &requests,
+            &[],
+            Some("winter"),
+            &mut winter,
+        )
+        .unwrap();
+        assert_eq!(
+            winter.remaining_tasks(NpcId::new(0)),
+            0,
+            "no grain, so no tools chain"
+        );
+        assert_eq!(winter.remaining_tasks(NpcId::new(1)), 0);
+        assert_eq!(
+            winter.remaining_tasks(NpcId::new(3)),
+            2,
+            "brewing runs all year: brew and deliver"
+        );
+
+        let mut autumn = ActorTaskQueues::default();
+        schedule_daily_requests(
+            &registry,
+            &actors,
+            &requests,
+            &[],
+            Some("autumn"),
+            &mut autumn,
+        )
+        .unwrap();
+        assert!(autumn.remaining_tasks(NpcId::new(0)) > 0);
+        assert_eq!(
+            autumn.remaining_tasks(NpcId::new(3)),
+            3,
+            "the autumn yield doubles the deliveries"
+        );
+    }
+}
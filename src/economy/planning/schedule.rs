@@ -0,0 +1,356 @@
+use std::collections::HashMap;
+
+use bevy::log::debug;
+
+use crate::{
+    economy::{
+        components::{Profession, TradeGood},
+        data::EconomyRegistry,
+        resources::EconomyActorCache,
+        tasks::{ActorTask, ActorTaskQueues},
+    },
+    npc::components::NpcId,
+};
+
+use super::SampledRequest;
+
+/// Queues tasks for each sampled request. Units that depend on a recipe run by a
+/// `suppressed` profession are dropped, so nobody waits on goods that won't be made today,
+/// and so are units relying on a recipe that is unavailable in `season` and units touching
+/// a profession nobody in `actors` works.
+pub fn schedule_daily_requests(
+    registry: &EconomyRegistry,
+    actors: &EconomyActorCache,
+    requests: &[SampledRequest],
+    suppressed: &[Profession],
+    season: Option<&str>,
+    queues: &mut ActorTaskQueues,
+) -> Result<(), String> {
+    let constraints = PlanConstraints { suppressed, season };
+    for request in requests {
+        schedule_request(registry, actors, queues, request, &constraints)?;
+    }
+    Ok(())
+}
+
+/// What rules recipes out for the day being planned.
+struct PlanConstraints<'a> {
+    suppressed: &'a [Profession],
+    season: Option<&'a str>,
+}
+
+fn schedule_request(
+    registry: &EconomyRegistry,
+    actors: &EconomyActorCache,
+    queues: &mut ActorTaskQueues,
+    request: &SampledRequest,
+    constraints: &PlanConstraints,
+) -> Result<(), String> {
+    for _ in 0..request.quantity {
+        let mut pending: HashMap<Profession, Vec<ActorTask>> = HashMap::new();
+        let Some(producer) = plan_request_unit(
+            registry,
+            request.good,
+            request.requester,
+            constraints,
+            &mut pending,
+        )?
+        else {
+            debug!(
+                "Skipping {} for {}: supply chain suppressed or out of season today",
+                request.good.label(),
+                request.requester.label()
+            );
+            continue;
+        };
+
+        // Drinks can be drunk on arrival, so the requester never holds them to wait on.
+        if producer != request.requester && !request.good.is_drink() {
+            pending
+                .entry(request.requester)
+                .or_default()
+                .push(ActorTask::WaitForGood {
+                    good: request.good,
+                    quantity: 1,
+                });
+        }
+
+        let Some(assigned) = assign_workers(actors, queues, &pending, request.requester) else {
+            debug!(
+                "Skipping {} for {}: a profession in the chain has no worker",
+                request.good.label(),
+                request.requester.label()
+            );
+            continue;
+        };
+
+        for (profession, tasks) in pending {
+            queues
+                .ensure_queue(assigned[&profession])
+                .extend(tasks.into_iter().map(|mut task| {
+                    if let ActorTask::Deliver {
+                        target, recipient, ..
+                    } = &mut task
+                    {
+                        *recipient = assigned.get(target).copied();
+                    }
+                    task
+                }));
+        }
+    }
+
+    Ok(())
+}
+
+/// Hands each profession one unit touches to its least-loaded worker (lowest id on ties),
+/// so a unit's waits and deliveries line up between the same NPCs. `None` when a
+/// profession has nobody.
+fn assign_workers(
+    actors: &EconomyActorCache,
+    queues: &ActorTaskQueues,
+    pending: &HashMap<Profession, Vec<ActorTask>>,
+    requester: Profession,
+) -> Option<HashMap<Profession, NpcId>> {
+    Profession::ALL
+        .into_iter()
+        .filter(|profession| *profession == requester || pending.contains_key(profession))
+        .map(|profession| {
+            actors
+                .workers(profession)
+                .min_by_key(|actor| queues.remaining_tasks(actor.npc_id))
+                .map(|actor| (profession, actor.npc_id))
+        })
+        .collect()
+}
+
+/// Plans one unit of `good` for `target`, returning the producer, or `None` when the
+/// chain runs through a suppressed profession or a recipe out of season.
+fn plan_request_unit(
+    registry: &EconomyRegistry,
+    good: TradeGood,
+    target: Profession,
+    constraints: &PlanConstraints,
+    tasks: &mut HashMap<Profession, Vec<ActorTask>>,
+) -> Result<Option<Profession>, String> {
+    let recipe = registry
+        .recipe_for_output(good)
+        .ok_or_else(|| format!("no recipe produces good {:?}", good))?;
+    if constraints.suppressed.contains(&recipe.actor) || !recipe.in_season(constraints.season) {
+        return Ok(None);
+    }
+
+    let outputs: Vec<u32> = recipe
+        .produces
+        .iter()
+        .filter(|output| output.good == good)
+        .map(|output| output.quantity.max(1))
+        .collect();
+    if outputs.is_empty() {
+        return Err(format!(
+            "recipe '{}' does not produce requested good {:?}",
+            recipe.id, good
+        ));
+    }
+    // One delivery per unit the season actually yields; a yield of nothing is no supply.
+    let total_outputs: u32 = outputs
+        .into_iter()
+        .map(|quantity| recipe.seasonal_quantity(quantity, constraints.season))
+        .sum();
+    if total_outputs == 0 {
+        return Ok(None);
+    }
+
+    for input in &recipe.consumes {
+        for _ in 0..input.quantity.max(1) {
+            if plan_request_unit(registry, input.good, recipe.actor, constraints, tasks)?.is_none()
+            {
+                return Ok(None);
+            }
+            tasks
+                .entry(recipe.actor)
+                .or_default()
+                .push(ActorTask::WaitForGood {
+                    good: input.good,
+                    quantity: 1,
+                });
+        }
+    }
+
+    tasks
+        .entry(recipe.actor)
+        .or_default()
+        .push(ActorTask::Manufacture {
+            recipe: recipe.clone(),
+        });
+
+    for _ in 0..total_outputs {
+        tasks
+            .entry(recipe.actor)
+            .or_default()
+            .push(ActorTask::Deliver {
+                good,
+                quantity: 1,
+                target,
+                recipient: None,
+            });
+    }
+
+    Ok(Some(recipe.actor))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{
+        roll_scarcity_events,
+        tests::{registry, staffed},
+    };
+    use super::*;
+    use crate::economy::data::EconomyConfig;
+
+    #[test]
+    fn suppressed_profession_drops_dependent_units_only() {
+        let registry = registry(
+            r#"
+            [[scarcity_events]]
+            profession = "farmer"
+            probability = 1.0
+            description = "The harvest failed"
+            "#,
+        );
+        let suppressed: Vec<Profession> = roll_scarcity_events(&registry, 3)
+            .iter()
+            .map(|event| event.profession)
+            .collect();
+        assert_eq!(suppressed, vec![Profession::Farmer]);
+
+        let requests = [
+            SampledRequest {
+                requester: Profession::Farmer,
+                good: TradeGood::Tools,
+                quantity: 2,
+            },
+            SampledRequest {
+                requester: Profession::Miller,
+                good: TradeGood::Grain,
+                quantity: 1,
+            },
+        ];
+        let actors = staffed(&[
+            Profession::Farmer,
+            Profession::Miller,
+            Profession::Blacksmith,
+        ]);
+        let mut queues = ActorTaskQueues::default();
+        schedule_daily_requests(
+            &registry,
+            &actors,
+            &requests,
+            &suppressed,
+            None,
+            &mut queues,
+        )
+        .unwrap();
+        assert!(queues.is_empty(), "every unit depends on grain");
+
+        schedule_daily_requests(&registry, &actors, &requests, &[], None, &mut queues).unwrap();
+        // Per tools unit: harvest, deliver grain, wait for tools; plus harvest and deliver.
+        assert_eq!(queues.remaining_tasks(NpcId::new(0)), 2 * 3 + 2);
+    }
+
+    #[test]
+    fn revalidation_drops_only_tasks_the_new_registry_cannot_honour() {
+        let actors = staffed(&[
+            Profession::Farmer,
+            Profession::Miller,
+            Profession::Blacksmith,
+        ]);
+        let requests = [SampledRequest {
+            requester: Profession::Farmer,
+            good: TradeGood::Tools,
+            quantity: 1,
+        }];
+        let mut queues = ActorTaskQueues::default();
+        schedule_daily_requests(&registry(""), &actors, &requests, &[], None, &mut queues).unwrap();
+        queues
+            .ensure_queue(NpcId::new(2))
+            .push_back(ActorTask::DepositSurplus);
+
+        let without_tools: EconomyConfig = toml::from_str(
+            r#"
+            [[recipes]]
+            id = "grain_harvest"
+            actor = "farmer"
+            produces = [{ good = "grain", quantity = 1 }]
+
+            [[recipes]]
+            id = "flour_milling"
+            actor = "miller"
+            produces = [{ good = "flour", quantity = 1 }]
+            consumes = [{ good = "grain", quantity = 1 }]
+            "#,
+        )
+        .unwrap();
+        let revalidation =
+            queues.revalidate_against(&EconomyRegistry::from_config(without_tools).unwrap());
+
+        assert_eq!(revalidation.total(), 3);
+        assert_eq!(
+            revalidation.summary(),
+            "1x deliver tool crate to farmer, 1x manufacture toolsmithing, 1x wait for tool crate"
+        );
+        // The grain and flour legs of the chain are still makeable, so they stay.
+        assert_eq!(queues.remaining_tasks(NpcId::new(0)), 2);
+        assert_eq!(queues.remaining_tasks(NpcId::new(1)), 3);
+        assert_eq!(queues.remaining_tasks(NpcId::new(2)), 2);
+        assert_eq!(queues.awaited_quantity(NpcId::new(0), TradeGood::Tools), 0);
+    }
+
+    #[test]
+    fn units_split_across_workers_and_skip_unstaffed_chains() {
+        let registry = registry("");
+        let requests = [SampledRequest {
+            requester: Profession::Blacksmith,
+            good: TradeGood::Flour,
+            quantity: 4,
+        }];
+        let actors = staffed(&[
+            Profession::Farmer,
+            Profession::Farmer,
+            Profession::Miller,
+            Profession::Blacksmith,
+        ]);
+        let mut queues = ActorTaskQueues::default();
+        schedule_daily_requests(&registry, &actors, &requests, &[], None, &mut queues).unwrap();
+
+        // Each unit: harvest and deliver grain.
+        assert_eq!(queues.remaining_tasks(NpcId::new(0)), 2 * 2);
+        assert_eq!(queues.remaining_tasks(NpcId::new(1)), 2 * 2);
+        let miller = NpcId::new(2);
+        for farmer in [NpcId::new(0), NpcId::new(1)] {
+            while let Some(task) = queues.peek(farmer) {
+                if let ActorTask::Deliver { recipient, .. } = task {
+                    assert_eq!(
+                        *recipient,
+                        Some(miller),
+                        "deliveries name the waiting miller"
+                    );
+                }
+                queues.pop_front(farmer);
+            }
+        }
+        assert_eq!(queues.awaited_quantity(miller, TradeGood::Grain), 4);
+
+        let mut unstaffed = ActorTaskQueues::default();
+        let farmers_only = staffed(&[Profession::Farmer, Profession::Farmer]);
+        schedule_daily_requests(
+            &registry,
+            &farmers_only,
+            &requests,
+            &[],
+            None,
+            &mut unstaffed,
+        )
+        .unwrap();
+        assert!(unstaffed.is_empty(), "nobody mills the grain");
+    }
+}
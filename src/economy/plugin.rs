@@ -1,43 +1,82 @@
 //! Economy plugin wiring placeholder trade systems.
 use bevy::{ecs::schedule::IntoScheduleConfigs, prelude::*};
 
+#[cfg(feature = "profiling")]
+use crate::core::profiling::time_system;
 use crate::{
-    npc::systems::spawn_debug_npcs,
-    world::{systems::spawn_world_environment, time::advance_world_clock},
+    core::{config::report_config_result, focus::window_focused, schedule::FramePhase},
+    dialogue::chronicle::ChronicleAppExt,
+    npc::{components::Identity, spatial::NpcIndex, systems::spawn_debug_npcs},
+    world::systems::spawn_world_environment,
 };
 
 use super::{
-    data::EconomyRegistry,
+    data::{EconomyRegistry, ECONOMY_CONFIG_PATH},
     dependency::EconomyDependencyMatrix,
-    events::{ProfessionDependencyUpdateEvent, TradeCompletedEvent},
+    events::{
+        distill_economy_event, distill_economy_imbalance, EconomyEventOccurred,
+        EconomyImbalanceEvent, GoodsSpoiledEvent, InventoryChangedEvent,
+        ProfessionDependencyUpdateEvent, SkillLevelUpEvent, TradeCompletedEvent,
+        TradeProposedEvent,
+    },
+    fairness::{evaluate_village_fairness, VillageFairness},
+    market::MarketMeetings,
+    relationships::{track_trade_relationships, TradeRelationships},
+    reservations::ReservedStock,
     resources::{
+        CarriedGoodsRegistry, EconomyActorCache, PendingEconomyReload, PlaceholderStackConfig,
         ProfessionCrateRegistry, TradeGoodPlaceholderRegistry, TradeGoodPlaceholderVisuals,
     },
+    skills::celebrate_skill_level_ups,
     systems::{
-        advance_actor_tasks, assign_placeholder_professions, prepare_economy_day,
-        spawn_profession_crates,
+        advance_actor_tasks, animate_carried_goods, apply_encumbrance,
+        apply_pending_economy_reload, assign_crate_owners, assign_placeholder_professions,
+        forget_despawned_crates, prepare_economy_day, refresh_economy_actor_cache,
+        reload_economy_config, reset_chatter_budgets, spawn_marketplace, spawn_profession_crates,
+        spoil_expired_goods, sync_carried_goods, sync_trade_good_placeholders,
     },
     tasks::{ActorTaskQueues, EconomyDayState},
 };
 
 const SYSTEM_ACTOR_LABEL: &str = "system";
 
+/// Daily planning, tasks, trades, and the goods they move.
 pub struct EconomyPlugin;
 
 impl Plugin for EconomyPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<EconomyRegistry>()
+        let registry = report_config_result(
+            app.world_mut(),
+            ECONOMY_CONFIG_PATH,
+            EconomyRegistry::load(),
+            EconomyRegistry::fallback,
+        );
+        app.insert_resource(registry)
+            .init_resource::<PendingEconomyReload>()
             .init_resource::<ProfessionCrateRegistry>()
             .init_resource::<TradeGoodPlaceholderRegistry>()
             .init_resource::<TradeGoodPlaceholderVisuals>()
+            .init_resource::<PlaceholderStackConfig>()
+            .init_resource::<CarriedGoodsRegistry>()
             .init_resource::<ActorTaskQueues>()
+            .init_resource::<MarketMeetings>()
+            .init_resource::<ReservedStock>()
+            .init_resource::<TradeRelationships>()
+            .init_resource::<EconomyActorCache>()
             .init_resource::<EconomyDayState>()
             .init_resource::<EconomyDependencyMatrix>()
+            .init_resource::<VillageFairness>()
             .add_message::<TradeCompletedEvent>()
             .add_message::<ProfessionDependencyUpdateEvent>()
+            .add_message::<InventoryChangedEvent>()
+            .add_message::<EconomyEventOccurred>()
+            .add_message::<SkillLevelUpEvent>()
+            .add_message::<GoodsSpoiledEvent>()
+            .add_message::<EconomyImbalanceEvent>()
+            .add_message::<TradeProposedEvent>()
             .add_systems(
                 Startup,
-                spawn_profession_crates.after(spawn_world_environment),
+                (spawn_profession_crates, spawn_marketplace).after(spawn_world_environment),
             )
             .add_systems(
                 Startup,
@@ -45,11 +84,47 @@ impl Plugin for EconomyPlugin {
             )
             .add_systems(
                 Update,
-                (prepare_economy_day, advance_actor_tasks)
+                (
+                    reload_economy_config,
+                    apply_pending_economy_reload,
+                    reset_chatter_budgets,
+                    refresh_economy_actor_cache,
+                    forget_despawned_crates,
+                    assign_crate_owners,
+                    prepare_economy_day,
+                    spoil_expired_goods,
+                    advance_actor_tasks,
+                    sync_carried_goods,
+                    sync_trade_good_placeholders,
+                    apply_encumbrance,
+                    animate_carried_goods.run_if(window_focused),
+                )
                     .chain()
-                    .after(advance_world_clock),
+                    .in_set(FramePhase::EconomyExecute),
             )
-            .add_systems(Update, log_trade_events);
+            .add_systems(
+                Update,
+                (
+                    celebrate_skill_level_ups.after(advance_actor_tasks),
+                    evaluate_village_fairness.after(advance_actor_tasks),
+                    track_trade_relationships.after(advance_actor_tasks),
+                    log_trade_events,
+                    log_economy_imbalances.after(evaluate_village_fairness),
+                )
+                    .in_set(FramePhase::EconomyExecute),
+            )
+            .add_chronicle_distiller(distill_economy_event)
+            .add_chronicle_distiller(distill_economy_imbalance);
+
+        #[cfg(feature = "profiling")]
+        time_system(app, Update, "advance_actor_tasks", advance_actor_tasks);
+
+        #[cfg(feature = "chaos")]
+        crate::core::chaos::ChaosAppExt::register_chaos_fault(
+            app,
+            "clear_inventory",
+            crate::economy::chaos::clear_random_inventory,
+        );
     }
 }
 
@@ -75,3 +150,21 @@ fn log_trade_events(mut events: MessageReader<TradeCompletedEvent>) {
         );
     }
 }
+
+fn log_economy_imbalances(
+    mut events: MessageReader<EconomyImbalanceEvent>,
+    npc_index: Res<NpcIndex>,
+    npcs: Query<&Identity>,
+) {
+    for event in events.read() {
+        warn!(
+            "Economy imbalance day {}: gini {:.2}, {} holds {} units, {} holds {}",
+            event.day,
+            event.gini,
+            Identity::name_of(&npc_index, &npcs, event.richest),
+            event.richest_units,
+            Identity::name_of(&npc_index, &npcs, event.poorest),
+            event.poorest_units
+        );
+    }
+}
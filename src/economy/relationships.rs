@@ -0,0 +1,197 @@
+//! Trade relationships: how each pair of NPCs feels about trading with one another.
+//! Every completed exchange warms the pair, every declined offer cools it, and the score
+//! drifts back towards neutral each day. A negotiated handoff weighs this score, plus a
+//! bonus for housemates, as the recipient's affinity with the courier.
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use super::{
+    data::EconomyRegistry,
+    events::{TradeCompletedEvent, TradeProposedEvent, TradeReason},
+    negotiation::{NegotiationConfig, TradeDecision},
+};
+use crate::{npc::components::NpcId, world::time::DayChangedEvent};
+
+/// Trading history between pairs of NPCs, from -1 (soured) to 1 (trusted). Pairs are
+/// unordered and absent pairs are neutral.
+#[derive(Resource, Debug, Default)]
+pub struct TradeRelationships {
+    scores: HashMap<(NpcId, NpcId), f32>,
+}
+
+impl TradeRelationships {
+    /// How `a` and `b` get on as trading partners.
+    pub fn score(&self, a: NpcId, b: NpcId) -> f32 {
+        self.scores.get(&pair(a, b)).copied().unwrap_or(0.0)
+    }
+
+    /// Moves the pair's score by `delta`, within -1..=1. An NPC has no relationship with
+    /// themselves.
+    pub fn adjust(&mut self, a: NpcId, b: NpcId, delta: f32) {
+        if a == b || !delta.is_finite() {
+            return;
+        }
+        let score = self.scores.entry(pair(a, b)).or_default();
+        *score = (*score + delta).clamp(-1.0, 1.0);
+    }
+
+    /// Moves every score `amount` closer to neutral, forgetting pairs that reach it.
+    pub fn decay(&mut self, amount: f32) {
+        self.scores.retain(|_, score| {
+            *score = if *score > 0.0 {
+                (*score - amount).max(0.0)
+            } else {
+                (*score + amount).min(0.0)
+            };
+            *score != 0.0
+        });
+    }
+}
+
+fn pair(a: NpcId, b: NpcId) -> (NpcId, NpcId) {
+    (a.min(b), a.max(b))
+}
+
+/// The recipient's affinity with a courier: their trading history, raised by
+/// `housemate_affinity` when they share a household, within -1..=1.
+pub fn trade_affinity(history: f32, housemates: bool, config: &NegotiationConfig) -> f32 {
+    let history = if history.is_finite() { history } else { 0.0 };
+    let bonus = if housemates {
+        config.housemate_affinity
+    } else {
+        0.0
+    };
+    (history + bonus).clamp(-1.0, 1.0)
+}
+
+/// Lets relationships fade when a day starts, then warms the pair behind every completed
+/// exchange and cools the pair behind every declined offer.
+pub fn track_trade_relationships(
+    mut day_changes: MessageReader<DayChangedEvent>,
+    mut completed: MessageReader<TradeCompletedEvent>,
+    mut proposed: MessageReader<TradeProposedEvent>,
+    registry: Res<EconomyRegistry>,
+    mut relationships: ResMut<TradeRelationships>,
+) {
+    let config = registry.negotiation();
+    for _ in day_changes.read() {
+        relationships.decay(config.affinity_decay_per_day);
+    }
+    for event in completed.read() {
+        if let (TradeReason::Exchange, Some(from), Some(to)) = (event.reason, event.from, event.to)
+        {
+            relationships.adjust(from, to, config.trade_affinity);
+        }
+    }
+    for event in proposed.read() {
+        if let TradeDecision::Decline(_) = event.decision {
+            relationships.adjust(event.courier, event.recipient, -config.decline_affinity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::economy::{components::TradeGood, negotiation::DeclineReason};
+
+    #[test]
+    fn scores_are_shared_by_the_pair_and_stay_in_range() {
+        let (a, b, c) = (NpcId::new(1), NpcId::new(2), NpcId::new(3));
+        let mut relationships = TradeRelationships::default();
+        relationships.adjust(a, b, 0.4);
+        relationships.adjust(b, a, 0.4);
+        assert!((relationships.score(a, b) - 0.8).abs() < 1e-6);
+        assert_eq!(relationships.score(a, b), relationships.score(b, a));
+        assert_eq!(relationships.score(a, c), 0.0, "strangers are neutral");
+
+        relationships.adjust(a, b, 5.0);
+        assert_eq!(relationships.score(a, b), 1.0);
+        relationships.adjust(a, c, -5.0);
+        assert_eq!(relationships.score(a, c), -1.0);
+        relationships.adjust(a, a, 1.0);
+        assert_eq!(relationships.score(a, a), 0.0);
+
+        relationships.decay(0.25);
+        assert_eq!(relationships.score(a, b), 0.75);
+        assert_eq!(relationships.score(a, c), -0.75);
+        relationships.decay(1.0);
+        assert!(
+            relationships.scores.is_empty(),
+            "neutral pairs are forgotten"
+        );
+    }
+
+    #[test]
+    fn affinity_grades_history_and_housemates() {
+        let config = NegotiationConfig::default();
+        assert_eq!(trade_affinity(0.0, false, &config), 0.0);
+        assert_eq!(trade_affinity(0.3, false, &config), 0.3);
+        assert_eq!(trade_affinity(-0.6, false, &config), -0.6);
+        assert_eq!(
+            trade_affinity(0.0, true, &config),
+            config.housemate_affinity
+        );
+        assert_eq!(trade_affinity(0.9, true, &config), 1.0);
+        assert_eq!(
+            trade_affinity(f32::NAN, true, &config),
+            config.housemate_affinity
+        );
+    }
+
+    #[test]
+    fn trades_warm_declines_cool_and_days_fade() {
+        let mut app = App::new();
+        app.init_resource::<EconomyRegistry>()
+            .init_resource::<TradeRelationships>()
+            .add_message::<DayChangedEvent>()
+            .add_message::<TradeCompletedEvent>()
+            .add_message::<TradeProposedEvent>()
+            .add_systems(Update, track_trade_relationships);
+        let config = app
+            .world()
+            .resource::<EconomyRegistry>()
+            .negotiation()
+            .clone();
+        let (farmer, miller) = (NpcId::new(1), NpcId::new(2));
+        let score = |app: &App| {
+            app.world()
+                .resource::<TradeRelationships>()
+                .score(farmer, miller)
+        };
+
+        let trade = |reason| TradeCompletedEvent {
+            day: 1,
+            from: Some(farmer),
+            to: Some(miller),
+            good: TradeGood::Grain,
+            quantity: 2,
+            reason,
+        };
+        app.world_mut().write_message(trade(TradeReason::Exchange));
+        app.world_mut()
+            .write_message(trade(TradeReason::Production));
+        app.update();
+        assert_eq!(score(&app), config.trade_affinity, "only exchanges count");
+
+        app.world_mut().write_message(TradeProposedEvent {
+            day: 1,
+            courier: farmer,
+            recipient: miller,
+            good: TradeGood::Grain,
+            quantity: 2,
+            decision: TradeDecision::Decline(DeclineReason::Unwanted),
+        });
+        app.update();
+        let cooled = config.trade_affinity - config.decline_affinity;
+        assert!((score(&app) - cooled).abs() < 1e-6);
+
+        app.world_mut().write_message(DayChangedEvent {
+            previous_day: Some(1),
+            new_day: 2,
+        });
+        app.update();
+        assert!((score(&app) - (cooled + config.affinity_decay_per_day).min(0.0)).abs() < 1e-6);
+    }
+}
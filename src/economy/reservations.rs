@@ -0,0 +1,154 @@
+//! Input reservations: when a day is planned, every `Manufacture` task claims its recipe
+//! inputs against its actor's inventory, so the goods the planner counted on cannot be
+//! taken by the player, lost to spoilage, or carried off on another delivery before the
+//! task runs. The task consumes its inputs and releases the claim when it completes or is
+//! dropped; anything left expires when the next day is planned.
+use std::collections::HashMap;
+
+use bevy::prelude::Resource;
+
+use super::{
+    components::TradeGood,
+    data::RecipeInput,
+    tasks::{ActorTask, ActorTaskQueues},
+};
+use crate::npc::components::NpcId;
+
+/// Units of each good held back for the holder's own queued recipes today.
+#[derive(Resource, Debug, Default)]
+pub struct ReservedStock {
+    day: Option<u64>,
+    held: HashMap<(NpcId, TradeGood), u32>,
+}
+
+impl ReservedStock {
+    /// Units of `good` that only `holder`'s own recipes may consume.
+    pub fn reserved(&self, holder: NpcId, good: TradeGood) -> u32 {
+        self.held.get(&(holder, good)).copied().unwrap_or(0)
+    }
+
+    #[cfg(test)]
+    pub fn day(&self) -> Option<u64> {
+        self.day
+    }
+
+    /// Replaces every reservation with the inputs of the `Manufacture` tasks queued for
+    /// `day`. Called when a day is planned and again when today's plan is revised, so
+    /// yesterday's claims expire and dropped tasks release theirs.
+    pub fn reserve_queued(&mut self, day: u64, task_queues: &ActorTaskQueues) {
+        self.day = Some(day);
+        self.held.clear();
+        for (npc, task) in task_queues.iter() {
+            if let ActorTask::Manufacture { recipe } = task {
+                for input in &recipe.consumes {
+                    self.reserve(npc, input.good, input.quantity);
+                }
+            }
+        }
+    }
+
+    pub fn reserve(&mut self, holder: NpcId, good: TradeGood, quantity: u32) {
+        if quantity == 0 {
+            return;
+        }
+        let held = self.held.entry((holder, good)).or_default();
+        *held = held.saturating_add(quantity);
+    }
+
+    /// Releases a finished or abandoned recipe's claim on `holder`'s stock.
+    pub fn release_inputs(&mut self, holder: NpcId, inputs: &[RecipeInput]) {
+        for input in inputs {
+            if let Some(held) = self.held.get_mut(&(holder, input.good)) {
+                *held = held.saturating_sub(input.quantity);
+                if *held == 0 {
+                    self.held.remove(&(holder, input.good));
+                }
+            }
+        }
+    }
+
+    /// Drops every reservation held by an NPC `keep` rejects.
+    pub fn retain_holders(&mut self, mut keep: impl FnMut(NpcId) -> bool) {
+        self.held.retain(|(holder, _), _| keep(*holder));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::economy::{
+        components::{Inventory, Profession},
+        data::Recipe,
+    };
+
+    fn milling() -> Recipe {
+        Recipe {
+            id: "milling".to_string(),
+            actor: Profession::Miller,
+            produces: Vec::new(),
+            consumes: vec![RecipeInput {
+                good: TradeGood::Grain,
+                quantity: 2,
+            }],
+            xp: 1,
+            seasons: Vec::new(),
+            seasonal_yield: HashMap::new(),
+        }
+    }
+
+    fn planned(miller: NpcId) -> ActorTaskQueues {
+        let mut queues = ActorTaskQueues::default();
+        let queue = queues.ensure_queue(miller);
+        queue.push_back(ActorTask::Manufacture { recipe: milling() });
+        queue.push_back(ActorTask::Manufacture { recipe: milling() });
+        queue.push_back(ActorTask::DepositSurplus);
+        queues
+    }
+
+    #[test]
+    fn reservations_block_external_removal() {
+        let miller = NpcId::new(2);
+        let mut reserved = ReservedStock::default();
+        reserved.reserve_queued(0, &planned(miller));
+        assert_eq!(reserved.reserved(miller, TradeGood::Grain), 4);
+        assert_eq!(reserved.reserved(NpcId::new(3), TradeGood::Grain), 0);
+
+        let mut inventory = Inventory::default();
+        inventory.add_good(TradeGood::Grain, 5);
+        let held = reserved.reserved(miller, TradeGood::Grain);
+        assert_eq!(inventory.available_unreserved(TradeGood::Grain, held), 1);
+        assert!(inventory
+            .remove_unreserved(TradeGood::Grain, 2, held)
+            .is_none());
+        assert!(inventory
+            .remove_unreserved(TradeGood::Grain, 1, held)
+            .is_some());
+        assert_eq!(inventory.quantity_of(TradeGood::Grain), 4);
+    }
+
+    #[test]
+    fn completion_releases_the_claim() {
+        let miller = NpcId::new(2);
+        let mut reserved = ReservedStock::default();
+        reserved.reserve_queued(0, &planned(miller));
+
+        reserved.release_inputs(miller, &milling().consumes);
+        assert_eq!(reserved.reserved(miller, TradeGood::Grain), 2);
+        reserved.release_inputs(miller, &milling().consumes);
+        reserved.release_inputs(miller, &milling().consumes);
+        assert_eq!(reserved.reserved(miller, TradeGood::Grain), 0);
+    }
+
+    #[test]
+    fn reservations_expire_when_the_next_day_is_planned() {
+        let miller = NpcId::new(2);
+        let mut reserved = ReservedStock::default();
+        reserved.reserve_queued(0, &planned(miller));
+        reserved.reserve(NpcId::new(5), TradeGood::Flour, 1);
+
+        reserved.reserve_queued(1, &ActorTaskQueues::default());
+        assert_eq!(reserved.day(), Some(1));
+        assert_eq!(reserved.reserved(miller, TradeGood::Grain), 0);
+        assert_eq!(reserved.reserved(NpcId::new(5), TradeGood::Flour), 0);
+    }
+}
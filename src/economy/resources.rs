@@ -1,5 +1,5 @@
 //! Economy resources for economy task execution and visuals.
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use bevy::{
     ecs::world::FromWorld,
@@ -7,46 +7,276 @@ use bevy::{
     prelude::{default, Assets, Color, Entity, Handle, Mesh, Resource, StandardMaterial, World},
 };
 
-use crate::economy::components::{Profession, TradeGood};
+use crate::{
+    economy::{
+        components::{Profession, TradeGood},
+        data::EconomyRegistry,
+    },
+    npc::components::NpcId,
+};
 
 pub const PLACEHOLDER_SIZE: f32 = 0.32;
+const DEFAULT_MAX_VISIBLE_STACK: usize = 5;
+
+/// A reloaded economy config waiting for the next day's planning, so today's task queues
+/// never mix recipes from two configs.
+#[derive(Resource, Debug, Default)]
+pub struct PendingEconomyReload {
+    registry: Option<EconomyRegistry>,
+}
+
+impl PendingEconomyReload {
+    pub fn stage(&mut self, registry: EconomyRegistry) {
+        self.registry = Some(registry);
+    }
+
+    #[cfg(test)]
+    pub fn is_pending(&self) -> bool {
+        self.registry.is_some()
+    }
+
+    pub fn take(&mut self) -> Option<EconomyRegistry> {
+        self.registry.take()
+    }
+}
+
+/// An NPC working a profession, as the task runner sees it.
+#[derive(Debug, Clone)]
+pub struct EconomyActor {
+    pub entity: Entity,
+    pub npc_id: NpcId,
+    pub display_name: String,
+    pub profession: Profession,
+}
+
+/// Every working NPC, sorted by id, rebuilt only when an `Identity` or `Profession`
+/// changes so the planner and task runner can borrow it every frame without reallocating.
+#[derive(Resource, Debug, Default)]
+pub struct EconomyActorCache {
+    actors: Vec<EconomyActor>,
+}
+
+impl EconomyActorCache {
+    pub fn rebuild(&mut self, actors: impl IntoIterator<Item = EconomyActor>) {
+        self.actors.clear();
+        self.actors.extend(actors);
+        self.actors.sort_by_key(|actor| actor.npc_id);
+    }
+
+    pub fn get(&self, npc: NpcId) -> Option<&EconomyActor> {
+        self.actors
+            .binary_search_by_key(&npc, |actor| actor.npc_id)
+            .ok()
+            .map(|index| &self.actors[index])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &EconomyActor> {
+        self.actors.iter()
+    }
+
+    /// NPCs working `profession`, lowest id first.
+    pub fn workers(
+        &self,
+        profession: Profession,
+    ) -> impl DoubleEndedIterator<Item = &EconomyActor> {
+        self.actors
+            .iter()
+            .filter(move |actor| actor.profession == profession)
+    }
+}
 
 /// Tracks the spawned crate entity for each profession.
 #[derive(Resource, Debug, Default)]
 pub struct ProfessionCrateRegistry {
     entries: HashMap<Profession, Entity>,
+    /// Professions whose registered crate was despawned.
+    lost: HashSet<Profession>,
 }
 
 impl ProfessionCrateRegistry {
     pub fn insert(&mut self, profession: Profession, entity: Entity) {
         self.entries.insert(profession, entity);
+        self.lost.remove(&profession);
     }
 
     pub fn get(&self, profession: Profession) -> Option<Entity> {
         self.entries.get(&profession).copied()
     }
+
+    /// Forgets the despawned crate `entity`, returning the profession it belonged to.
+    pub fn remove_entity(&mut self, entity: Entity) -> Option<Profession> {
+        let profession = self.entries.iter().find_map(|(profession, crate_entity)| {
+            (*crate_entity == entity).then_some(*profession)
+        })?;
+        self.entries.remove(&profession);
+        self.lost.insert(profession);
+        Some(profession)
+    }
+
+    /// True once `profession`'s crate has been despawned and not replaced. Unlike a crate
+    /// that was never spawned, tasks bound for a lost one fail.
+    pub fn is_lost(&self, profession: Profession) -> bool {
+        self.lost.contains(&profession)
+    }
+}
+
+/// Most placeholder cubes stacked per (profession, good) before the top cube grows instead.
+#[derive(Resource, Debug, Clone)]
+pub struct PlaceholderStackConfig {
+    pub max_visible_stack: usize,
 }
 
-/// Tracks placeholder entities spawned to represent goods near profession crates.
+impl Default for PlaceholderStackConfig {
+    fn default() -> Self {
+        Self {
+            max_visible_stack: DEFAULT_MAX_VISIBLE_STACK,
+        }
+    }
+}
+
+/// Cubes (bottom first) and optional count label shown for one crate-side stock.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PlaceholderStack {
+    pub cubes: Vec<Entity>,
+    pub label: Option<Entity>,
+    /// Units the owner holds, including any a courier is carrying away.
+    pub stock: u32,
+    /// Units the cubes and label currently show: `stock` minus the units in transit.
+    pub shown: u32,
+}
+
+/// Tracks placeholder stacks spawned to represent goods near profession crates.
 #[derive(Resource, Debug, Default)]
 pub struct TradeGoodPlaceholderRegistry {
-    entries: HashMap<(Profession, TradeGood), Entity>,
+    entries: HashMap<(Profession, TradeGood), PlaceholderStack>,
 }
 
 impl TradeGoodPlaceholderRegistry {
-    pub fn contains(&self, profession: Profession, good: TradeGood) -> bool {
-        self.entries.contains_key(&(profession, good))
+    pub fn stack(&self, profession: Profession, good: TradeGood) -> Option<&PlaceholderStack> {
+        self.entries.get(&(profession, good))
+    }
+
+    /// Adds a cube to the top of the stack, creating the stack if needed.
+    pub fn push_cube(&mut self, profession: Profession, good: TradeGood, entity: Entity) {
+        self.entries
+            .entry((profession, good))
+            .or_default()
+            .cubes
+            .push(entity);
     }
 
-    pub fn insert(&mut self, profession: Profession, good: TradeGood, entity: Entity) {
-        self.entries.insert((profession, good), entity);
+    /// Removes the top cube, keeping the (possibly empty) stack entry.
+    pub fn pop_cube(&mut self, profession: Profession, good: TradeGood) -> Option<Entity> {
+        self.entries.get_mut(&(profession, good))?.cubes.pop()
     }
 
-    pub fn take(&mut self, profession: Profession, good: TradeGood) -> Option<Entity> {
+    /// Replaces the count label, returning the previous one so it can be despawned.
+    pub fn replace_label(
+        &mut self,
+        profession: Profession,
+        good: TradeGood,
+        label: Option<Entity>,
+    ) -> Option<Entity> {
+        let stack = self.entries.entry((profession, good)).or_default();
+        std::mem::replace(&mut stack.label, label)
+    }
+
+    /// Records the owner's stock and the part of it on show, creating the stack if needed.
+    pub fn set_quantities(
+        &mut self,
+        profession: Profession,
+        good: TradeGood,
+        stock: u32,
+        shown: u32,
+    ) {
+        let stack = self.entries.entry((profession, good)).or_default();
+        stack.stock = stock;
+        stack.shown = shown;
+    }
+
+    /// Every stack with stock, as (profession, good, stock).
+    pub fn stocked(&self) -> Vec<(Profession, TradeGood, u32)> {
+        self.entries
+            .iter()
+            .filter(|(_, stack)| stack.stock > 0)
+            .map(|(&(profession, good), stack)| (profession, good, stack.stock))
+            .collect()
+    }
+
+    /// Removes the whole stack so every cube and the label can be despawned.
+    pub fn take(&mut self, profession: Profession, good: TradeGood) -> Option<PlaceholderStack> {
         self.entries.remove(&(profession, good))
     }
 }
 
+/// Placeholder entity a courier carries while a delivery is underway.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CarriedGood {
+    pub entity: Entity,
+    pub good: TradeGood,
+    /// Units in the load; the placeholder's size shows it against the carrying capacity.
+    pub quantity: u32,
+}
+
+/// Tracks which couriers are visibly carrying goods between crates.
+#[derive(Resource, Debug, Default)]
+pub struct CarriedGoodsRegistry {
+    entries: HashMap<NpcId, CarriedGood>,
+}
+
+impl CarriedGoodsRegistry {
+    pub fn get(&self, courier: NpcId) -> Option<CarriedGood> {
+        self.entries.get(&courier).copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (NpcId, CarriedGood)> + '_ {
+        self.entries
+            .iter()
+            .map(|(courier, carried)| (*courier, *carried))
+    }
+
+    /// Records a new carried placeholder, returning any previous one so it can be despawned.
+    pub fn start(
+        &mut self,
+        courier: NpcId,
+        good: TradeGood,
+        quantity: u32,
+        entity: Entity,
+    ) -> Option<Entity> {
+        self.entries
+            .insert(
+                courier,
+                CarriedGood {
+                    entity,
+                    good,
+                    quantity,
+                },
+            )
+            .map(|previous| previous.entity)
+    }
+
+    pub fn finish(&mut self, courier: NpcId) -> Option<CarriedGood> {
+        self.entries.remove(&courier)
+    }
+
+    /// Drops entries whose courier no longer qualifies, returning the orphaned placeholders.
+    pub fn abandon_where(
+        &mut self,
+        mut abandoned: impl FnMut(NpcId, CarriedGood) -> bool,
+    ) -> Vec<Entity> {
+        let mut orphaned = Vec::new();
+        self.entries.retain(|courier, carried| {
+            if abandoned(*courier, *carried) {
+                orphaned.push(carried.entity);
+                false
+            } else {
+                true
+            }
+        });
+        orphaned
+    }
+}
+
 /// Shared mesh/material handles for placeholder goods.
 #[derive(Resource, Debug)]
 pub struct TradeGoodPlaceholderVisuals {
@@ -82,6 +312,7 @@ impl FromWorld for TradeGoodPlaceholderVisuals {
             (TradeGood::Grain, Color::srgb_u8(214, 181, 102)),
             (TradeGood::Flour, Color::srgb_u8(236, 235, 230)),
             (TradeGood::Tools, Color::srgb_u8(110, 118, 132)),
+            (TradeGood::Ale, Color::srgb_u8(196, 128, 42)),
         ];
 
         for (good, color) in color_map {
@@ -0,0 +1,398 @@
+//! Delivery routing. With `[routing] enabled` in `config/economy.toml`, day prep merges a
+//! courier's deliveries of the same good to the same recipient into as few trips as the
+//! carry capacity allows, then orders them so the courier always heads for the nearest
+//! target crate next. Deliveries never move past a wait: a courier who waits on goods made
+//! from their own delivery still hands it over first.
+use std::collections::VecDeque;
+
+use bevy::prelude::Vec3;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    components::{Profession, TradeGood},
+    tasks::ActorTask,
+};
+use crate::npc::components::NpcId;
+
+const DEFAULT_CARRY_CAPACITY: u32 = 4;
+
+/// Whether deliveries are batched and how much a courier carries per trip.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RoutingConfig {
+    /// Off by default; deliveries then run one by one in planned order.
+    pub enabled: bool,
+    /// Units a courier carries on one trip; larger deliveries are split.
+    pub carry_capacity: u32,
+}
+
+impl Default for RoutingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            carry_capacity: DEFAULT_CARRY_CAPACITY,
+        }
+    }
+}
+
+impl RoutingConfig {
+    /// Clamps a capacity that would leave every delivery without a trip.
+    pub fn sanitised(self) -> Self {
+        Self {
+            carry_capacity: self.carry_capacity.max(1),
+            ..self
+        }
+    }
+}
+
+/// The fewest loads of at most `capacity` (at least 1) that carry `quantity`, full loads
+/// first.
+pub fn split_into_trips(quantity: u32, capacity: u32) -> Vec<u32> {
+    let capacity = capacity.max(1);
+    let mut trips = vec![capacity; (quantity / capacity) as usize];
+    if !quantity.is_multiple_of(capacity) {
+        trips.push(quantity % capacity);
+    }
+    trips
+}
+
+/// Visiting order for `stops` from `start`, always walking to the nearest unvisited stop
+/// (the lower index on ties). Stops without a known position follow in their given order.
+pub fn nearest_neighbor_order(start: Vec3, stops: &[Option<Vec3>]) -> Vec<usize> {
+    let mut remaining: Vec<(usize, Vec3)> = stops
+        .iter()
+        .enumerate()
+        .filter_map(|(index, position)| position.map(|position| (index, position)))
+        .collect();
+    let mut order = Vec::with_capacity(stops.len());
+    let mut here = start;
+    while let Some(slot) = remaining
+        .iter()
+        .enumerate()
+        .min_by(|(_, (_, a)), (_, (_, b))| {
+            here.distance_squared(*a)
+                .total_cmp(&here.distance_squared(*b))
+        })
+        .map(|(slot, _)| slot)
+    {
+        let (index, position) = remaining.remove(slot);
+        order.push(index);
+        here = position;
+    }
+    order.extend(
+        stops
+            .iter()
+            .enumerate()
+            .filter(|(_, position)| position.is_none())
+            .map(|(index, _)| index),
+    );
+    order
+}
+
+/// Length of the walk from `start` through `stops` in order.
+#[cfg(test)]
+pub fn route_length(start: Vec3, stops: &[Vec3]) -> f32 {
+    stops
+        .iter()
+        .fold((start, 0.0), |(here, walked), stop| {
+            (*stop, walked + here.distance(*stop))
+        })
+        .1
+}
+
+/// Deliveries merged within one run of a courier's queue.
+struct MergedDelivery {
+    good: TradeGood,
+    target: Profession,
+    recipient: Option<NpcId>,
+    quantity: u32,
+}
+
+/// Rewrites one courier's planned `queue`. Between waits, deliveries move behind the
+/// run's manufactures, merge per good, target, and recipient, split into loads of
+/// `capacity`, and are ordered nearest-first from `start` using `position_of(target)`.
+/// Without a `start` the merged deliveries keep their planned order.
+pub fn batch_deliveries(
+    queue: &mut VecDeque<ActorTask>,
+    capacity: u32,
+    start: Option<Vec3>,
+    position_of: impl Fn(Profession) -> Option<Vec3>,
+) {
+    let mut batched = VecDeque::with_capacity(queue.len());
+    let mut run: Vec<MergedDelivery> = Vec::new();
+    let flush = |run: &mut Vec<MergedDelivery>, batched: &mut VecDeque<ActorTask>| {
+        let positions: Vec<Option<Vec3>> = run
+            .iter()
+            .map(|delivery| start.and(position_of(delivery.target)))
+            .collect();
+        for index in nearest_neighbor_order(start.unwrap_or(Vec3::ZERO), &positions) {
+            let delivery = &run[index];
+            for quantity in split_into_trips(delivery.quantity, capacity) {
+                batched.push_back(ActorTask::Deliver {
+                    good: delivery.good,
+                    quantity,
+                    target: delivery.target,
+                    recipient: delivery.recipient,
+                });
+            }
+        }
+        run.clear();
+    };
+
+    for task in queue.drain(..) {
+        match task {
+            ActorTask::Deliver {
+                good,
+                quantity,
+                target,
+                recipient,
+            } => match run.iter_mut().find(|merged| {
+                merged.good == good && merged.target == target && merged.recipient == recipient
+            }) {
+                Some(merged) => merged.quantity = merged.quantity.saturating_add(quantity),
+                None => run.push(MergedDelivery {
+                    good,
+                    target,
+                    recipient,
+                    quantity,
+                }),
+            },
+            ActorTask::Manufacture { .. } => batched.push_back(task),
+            barrier => {
+                flush(&mut run, &mut batched);
+                batched.push_back(barrier);
+            }
+        }
+    }
+    flush(&mut run, &mut batched);
+    *queue = batched;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use bevy::prelude::Entity;
+
+    use super::*;
+    use crate::economy::{
+        data::{EconomyConfig, EconomyRegistry},
+        planning::{schedule_daily_requests, SampledRequest},
+        resources::{EconomyActor, EconomyActorCache},
+        tasks::ActorTaskQueues,
+    };
+
+    fn deliver(target: Profession, quantity: u32) -> ActorTask {
+        ActorTask::Deliver {
+            good: TradeGood::Ale,
+            quantity,
+            target,
+            recipient: None,
+        }
+    }
+
+    /// `(target, quantity)` of each delivery in `queue`, in order.
+    fn deliveries(queue: &VecDeque<ActorTask>) -> Vec<(Profession, u32)> {
+        queue
+            .iter()
+            .filter_map(|task| match task {
+                ActorTask::Deliver {
+                    target, quantity, ..
+                } => Some((*target, *quantity)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn trips_split_only_what_exceeds_the_carry_capacity() {
+        assert_eq!(split_into_trips(3, 4), vec![3]);
+        assert_eq!(split_into_trips(4, 4), vec![4]);
+        assert_eq!(split_into_trips(9, 4), vec![4, 4, 1]);
+        assert_eq!(split_into_trips(2, 0), vec![1, 1]);
+        assert!(split_into_trips(0, 4).is_empty());
+
+        // A single target within capacity becomes one trip.
+        let mut queue = VecDeque::from([
+            deliver(Profession::Miller, 1),
+            deliver(Profession::Miller, 1),
+            deliver(Profession::Miller, 1),
+        ]);
+        batch_deliveries(&mut queue, 4, Some(Vec3::ZERO), |_| None);
+        assert_eq!(deliveries(&queue), vec![(Profession::Miller, 3)]);
+
+        // Over capacity it takes the fewest trips that fit.
+        let mut queue: VecDeque<ActorTask> =
+            (0..5).map(|_| deliver(Profession::Miller, 1)).collect();
+        batch_deliveries(&mut queue, 2, Some(Vec3::ZERO), |_| None);
+        assert_eq!(
+            deliveries(&queue),
+            vec![
+                (Profession::Miller, 2),
+                (Profession::Miller, 2),
+                (Profession::Miller, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn three_targets_are_visited_nearest_first() {
+        let stops = [
+            Some(Vec3::new(10.0, 0.0, 0.0)),
+            Some(Vec3::new(2.0, 0.0, 0.0)),
+            None,
+            Some(Vec3::new(-3.0, 0.0, 0.0)),
+        ];
+        // 2 is nearest to the start, then -3 (5 away) beats 10 (8 away).
+        assert_eq!(nearest_neighbor_order(Vec3::ZERO, &stops), vec![1, 3, 0, 2]);
+        assert_eq!(
+            nearest_neighbor_order(Vec3::ZERO, &[Some(Vec3::X), Some(-Vec3::X)]),
+            vec![0, 1],
+            "ties keep the planned order"
+        );
+        assert_eq!(
+            route_length(
+                Vec3::ZERO,
+                &[Vec3::new(2.0, 0.0, 0.0), Vec3::new(-3.0, 0.0, 0.0)]
+            ),
+            7.0
+        );
+    }
+
+    #[test]
+    fn deliveries_never_move_past_a_wait() {
+        let wait = ActorTask::WaitForGood {
+            good: TradeGood::Tools,
+            quantity: 1,
+        };
+        let mut queue = VecDeque::from([
+            deliver(Profession::Miller, 1),
+            wait.clone(),
+            deliver(Profession::Miller, 1),
+        ]);
+        batch_deliveries(&mut queue, 4, Some(Vec3::ZERO), |_| None);
+        assert!(matches!(queue[0], ActorTask::Deliver { quantity: 1, .. }));
+        assert!(matches!(queue[1], ActorTask::WaitForGood { .. }));
+        assert!(matches!(queue[2], ActorTask::Deliver { quantity: 1, .. }));
+
+        let mut other_goods = VecDeque::from([
+            deliver(Profession::Miller, 1),
+            ActorTask::Deliver {
+                good: TradeGood::Grain,
+                quantity: 1,
+                target: Profession::Miller,
+                recipient: None,
+            },
+        ]);
+        batch_deliveries(&mut other_goods, 4, Some(Vec3::ZERO), |_| None);
+        assert_eq!(other_goods.len(), 2, "different goods stay separate trips");
+    }
+
+    #[test]
+    fn batched_plan_walks_less_than_the_naive_one() {
+        let registry = EconomyRegistry::from_config(
+            toml::from_str::<EconomyConfig>(
+                r#"
+                [[recipes]]
+                id = "brewing"
+                actor = "innkeeper"
+                produces = [{ good = "ale", quantity = 1 }]
+
+                [[daily_requests]]
+                requester = "farmer"
+                good = "ale"
+                quantity = 2
+
+                [[daily_requests]]
+                requester = "blacksmith"
+                good = "ale"
+                quantity = 1
+
+                [[daily_requests]]
+                requester = "miller"
+                good = "ale"
+                quantity = 2
+                "#,
+            )
+            .expect("valid test config"),
+        )
+        .expect("valid registry");
+        let requests: Vec<SampledRequest> = registry
+            .daily_requests()
+            .iter()
+            .map(|request| SampledRequest {
+                requester: request.requester,
+                good: request.good,
+                quantity: request.quantity_min,
+            })
+            .collect();
+        let mut actors = EconomyActorCache::default();
+        actors.rebuild(
+            Profession::ALL
+                .into_iter()
+                .enumerate()
+                .map(|(index, profession)| EconomyActor {
+                    entity: Entity::PLACEHOLDER,
+                    npc_id: NpcId::new(index as u64),
+                    display_name: profession.label().to_string(),
+                    profession,
+                }),
+        );
+        let innkeeper = actors
+            .workers(Profession::Innkeeper)
+            .next()
+            .expect("the innkeeper is staffed")
+            .npc_id;
+        let mut queues = ActorTaskQueues::default();
+        schedule_daily_requests(&registry, &actors, &requests, &[], None, &mut queues).unwrap();
+
+        let crates = HashMap::from([
+            (Profession::Innkeeper, Vec3::ZERO),
+            (Profession::Farmer, Vec3::new(10.0, 0.0, 0.0)),
+            (Profession::Blacksmith, Vec3::new(-7.0, 0.0, 0.0)),
+            (Profession::Miller, Vec3::new(2.0, 0.0, 0.0)),
+        ]);
+        let mut queue = VecDeque::new();
+        while let Some(task) = queues.peek(innkeeper) {
+            queue.push_back(task.clone());
+            queues.pop_front(innkeeper);
+        }
+        let walked = |queue: &VecDeque<ActorTask>| {
+            let stops: Vec<Vec3> = deliveries(queue)
+                .into_iter()
+                .map(|(target, _)| crates[&target])
+                .collect();
+            route_length(crates[&Profession::Innkeeper], &stops)
+        };
+
+        let naive = walked(&queue);
+        assert_eq!(deliveries(&queue).len(), 5, "one unit per trip");
+        batch_deliveries(
+            &mut queue,
+            2,
+            Some(crates[&Profession::Innkeeper]),
+            |profession| crates.get(&profession).copied(),
+        );
+        assert_eq!(
+            deliveries(&queue),
+            vec![
+                (Profession::Miller, 2),
+                (Profession::Farmer, 2),
+                (Profession::Blacksmith, 1)
+            ]
+        );
+        assert_eq!(
+            queue
+                .iter()
+                .filter(|task| matches!(task, ActorTask::Manufacture { .. }))
+                .count(),
+            5,
+            "every brew stays planned"
+        );
+        assert!(
+            walked(&queue) < naive,
+            "batched {} vs naive {naive}",
+            walked(&queue)
+        );
+    }
+}
@@ -0,0 +1,231 @@
+//! Profession skill. Completing a Manufacture task earns the recipe's experience in the
+//! NPC's profession; levels follow the `[skills]` curve in `config/economy.toml`, and each
+//! level above the first scales recipe output.
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dialogue::{
+        queue::DialogueRequestQueue,
+        types::{DialogueRequest, DialogueTopicHint},
+    },
+    economy::{components::Profession, data::EconomyRegistry, events::SkillLevelUpEvent},
+    npc::{
+        components::Identity,
+        motivation::{state::MotivationReason, MotivationAdjustmentEvent, MotivationConfig},
+        spatial::NpcIndex,
+    },
+};
+
+const DEFAULT_BASE_XP: f32 = 40.0;
+const DEFAULT_GROWTH: f32 = 1.5;
+const DEFAULT_MAX_LEVEL: u32 = 5;
+const DEFAULT_YIELD_PER_LEVEL: f32 = 0.25;
+
+/// Experience needed per level and how much each level adds to recipe yield.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SkillCurve {
+    /// Experience needed to reach level 2; later levels need `base_xp * (level - 1)^growth`.
+    pub base_xp: f32,
+    /// Exponent on `level - 1`; above 1 makes each level harder than the last.
+    pub growth: f32,
+    /// Highest level; experience past it adds nothing.
+    pub max_level: u32,
+    /// Added to the yield multiplier for every level above 1.
+    pub yield_per_level: f32,
+}
+
+impl Default for SkillCurve {
+    fn default() -> Self {
+        Self {
+            base_xp: DEFAULT_BASE_XP,
+            growth: DEFAULT_GROWTH,
+            max_level: DEFAULT_MAX_LEVEL,
+            yield_per_level: DEFAULT_YIELD_PER_LEVEL,
+        }
+    }
+}
+
+impl SkillCurve {
+    /// Clamps values that would make the curve meaningless.
+    pub fn sanitised(self) -> Self {
+        Self {
+            base_xp: self.base_xp.max(1.0),
+            growth: self.growth.max(0.0),
+            max_level: self.max_level.max(1),
+            yield_per_level: self.yield_per_level.max(0.0),
+        }
+    }
+}
+
+/// Total experience needed to reach `level`; level 1 is free.
+pub fn xp_for_level(level: u32, curve: &SkillCurve) -> u32 {
+    if level <= 1 {
+        return 0;
+    }
+    (curve.base_xp * ((level - 1) as f32).powf(curve.growth)).round() as u32
+}
+
+/// Highest level `xp` has reached, capped at `curve.max_level`.
+pub fn level_for_xp(xp: u32, curve: &SkillCurve) -> u32 {
+    (2..=curve.max_level)
+        .take_while(|level| xp_for_level(*level, curve) <= xp)
+        .last()
+        .unwrap_or(1)
+}
+
+/// Factor applied to recipe output at `level`; 1.0 at level 1.
+pub fn yield_multiplier(level: u32, curve: &SkillCurve) -> f32 {
+    1.0 + curve.yield_per_level * level.saturating_sub(1) as f32
+}
+
+/// `quantity` scaled by `multiplier`, rounded, never below 1.
+pub fn scaled_output(quantity: u32, multiplier: f32) -> u32 {
+    ((quantity as f32 * multiplier).round() as u32).max(1)
+}
+
+/// Experience per profession. Serializable so a save system can persist it as-is.
+#[derive(Component, Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Skill {
+    experience: HashMap<Profession, u32>,
+}
+
+impl Skill {
+    pub fn experience(&self, profession: Profession) -> u32 {
+        self.experience.get(&profession).copied().unwrap_or(0)
+    }
+
+    pub fn level(&self, profession: Profession, curve: &SkillCurve) -> u32 {
+        level_for_xp(self.experience(profession), curve)
+    }
+
+    /// Adds `xp` to `profession`, returning the new level if it went up.
+    pub fn gain(&mut self, profession: Profession, xp: u32, curve: &SkillCurve) -> Option<u32> {
+        let before = self.level(profession, curve);
+        let total = self.experience.entry(profession).or_default();
+        *total = total.saturating_add(xp);
+        let after = self.level(profession, curve);
+        (after > before).then_some(after)
+    }
+
+    /// "farmer 3 (130 xp)" style summary of every practised profession, for logs and UI.
+    pub fn summary(&self, curve: &SkillCurve) -> String {
+        let mut entries: Vec<_> = self.experience.iter().collect();
+        entries.sort_by_key(|(profession, _)| profession.label());
+        entries
+            .into_iter()
+            .map(|(profession, xp)| {
+                format!(
+                    "{} {} ({} xp)",
+                    profession.label(),
+                    level_for_xp(*xp, curve),
+                    xp
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Rewards NPCs who levelled up and has them say so.
+pub fn celebrate_skill_level_ups(
+    mut level_ups: MessageReader<SkillLevelUpEvent>,
+    config: Res<MotivationConfig>,
+    registry: Res<EconomyRegistry>,
+    mut queue: ResMut<DialogueRequestQueue>,
+    mut adjustments: MessageWriter<MotivationAdjustmentEvent>,
+    npc_index: Res<NpcIndex>,
+    npcs: Query<(&Identity, &Skill)>,
+) {
+    for event in level_ups.read() {
+        let Some((identity, skill)) = npc_index
+            .entity(event.npc)
+            .and_then(|entity| npcs.get(entity).ok())
+        else {
+            continue;
+        };
+
+        adjustments.write(MotivationAdjustmentEvent::new(
+            identity.id,
+            config.skill.level_up_reward,
+            MotivationReason::SkillLevelUp,
+        ));
+        info!(
+            "{} reached {} level {} [{}]",
+            identity.display_name,
+            event.profession.label(),
+            event.level,
+            skill.summary(registry.skill_curve())
+        );
+        let request = DialogueRequest::builder(identity.id)
+            .speaker_name(identity.display_name.clone())
+            .topic(DialogueTopicHint::Status)
+            .prompt(format!(
+                "{} proudly tells the village they have become a better {}.",
+                identity.display_name,
+                event.profession.label()
+            ))
+            .summary(format!(
+                "{} just reached {} skill level {} and their work yields more now.",
+                identity.display_name,
+                event.profession.label(),
+                event.level
+            ))
+            .enqueue(&mut queue);
+        if let Err(error) = request {
+            warn!(
+                "Skipped level-up line for {}: {error}",
+                identity.display_name
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn experience_curve_levels_up_and_caps() {
+        let curve = SkillCurve::default();
+        let thresholds: Vec<u32> = (1..=5).map(|level| xp_for_level(level, &curve)).collect();
+        assert_eq!(thresholds, [0, 40, 113, 208, 320]);
+        assert_eq!(level_for_xp(0, &curve), 1);
+        assert_eq!(level_for_xp(39, &curve), 1);
+        assert_eq!(level_for_xp(40, &curve), 2);
+        assert_eq!(level_for_xp(113, &curve), 3);
+        assert_eq!(level_for_xp(10_000, &curve), 5, "capped at max_level");
+
+        let mut skill = Skill::default();
+        assert_eq!(skill.gain(Profession::Farmer, 30, &curve), None);
+        assert_eq!(skill.gain(Profession::Farmer, 100, &curve), Some(3));
+        assert_eq!(skill.level(Profession::Miller, &curve), 1);
+        assert_eq!(skill.summary(&curve), "farmer 3 (130 xp)");
+    }
+
+    #[test]
+    fn yield_grows_with_level_and_never_drops_below_one() {
+        let curve = SkillCurve::default();
+        assert_eq!(yield_multiplier(1, &curve), 1.0);
+        assert_eq!(scaled_output(1, yield_multiplier(2, &curve)), 1);
+        assert_eq!(
+            scaled_output(1, yield_multiplier(3, &curve)),
+            2,
+            "a level 3 harvest yields 2 grain"
+        );
+        assert_eq!(scaled_output(4, yield_multiplier(5, &curve)), 8);
+        assert_eq!(scaled_output(1, 0.1), 1);
+    }
+
+    #[test]
+    fn skill_round_trips_through_serde() {
+        let mut skill = Skill::default();
+        skill.gain(Profession::Miller, 57, &SkillCurve::default());
+        let json = serde_json::to_string(&skill).unwrap();
+        assert_eq!(json, r#"{"experience":{"miller":57}}"#);
+        assert_eq!(serde_json::from_str::<Skill>(&json).unwrap(), skill);
+    }
+}
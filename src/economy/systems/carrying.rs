@@ -0,0 +1,440 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+
+use crate::{
+    core::plugin::SimulationClock,
+    dialogue::{chatter::ChatterBudgets, queue::DialogueRequestQueue, types::ContextClock},
+    npc::{
+        components::{Identity, NpcId, SpeedModifiers},
+        events::NpcDespawnedEvent,
+        sleep::SleepRoster,
+    },
+    world::time::WorldClock,
+};
+
+use super::super::{
+    components::{CarriedGoodPlaceholder, Inventory, Profession, TradeGood},
+    data::EconomyRegistry,
+    encumbrance::{load_factor, ENCUMBRANCE_SPEED_SOURCE},
+    resources::{CarriedGoodsRegistry, TradeGoodPlaceholderVisuals},
+    tasks::{ActorTask, ActorTaskQueues},
+};
+use super::dialogue::{queue_load_grumble, LoadGrumbleInput};
+
+const CARRY_HEIGHT: f32 = 1.35;
+const CARRY_FORWARD_OFFSET: f32 = 0.25;
+/// Placeholder scale for a full load; lighter loads shrink towards `LIGHT_LOAD_SCALE` of it.
+const CARRY_SCALE: f32 = 0.7;
+const LIGHT_LOAD_SCALE: f32 = 0.6;
+const HOURS_PER_DAY: f32 = 24.0;
+const BOB_AMPLITUDE: f32 = 0.05;
+const BOB_FREQUENCY: f32 = 6.0;
+const BOB_PHASE_STEP: f32 = 1.7;
+
+/// Vertical bob applied to carried goods; `phase` desynchronises couriers.
+pub fn bob_offset(elapsed_seconds: f32, phase: f32) -> f32 {
+    (elapsed_seconds * BOB_FREQUENCY + phase).sin() * BOB_AMPLITUDE
+}
+
+/// Placeholder scale for a load at `load` (carried units over capacity): `CARRY_SCALE` at a
+/// full load or more, down to `LIGHT_LOAD_SCALE` of it for a token one.
+pub fn carry_scale(load: f32) -> f32 {
+    let load = if load.is_finite() {
+        load.clamp(0.0, 1.0)
+    } else {
+        1.0
+    };
+    CARRY_SCALE * (LIGHT_LOAD_SCALE + (1.0 - LIGHT_LOAD_SCALE) * load)
+}
+
+/// The good and units `npc` is carrying: their front task's delivery once their inventory
+/// holds all of it.
+fn carried_delivery(
+    task_queues: &ActorTaskQueues,
+    npc: NpcId,
+    inventory: &Inventory,
+) -> Option<(TradeGood, u32)> {
+    match task_queues.peek(npc) {
+        Some(ActorTask::Deliver { good, quantity, .. })
+            if inventory.quantity_of(*good) >= *quantity =>
+        {
+            Some((*good, *quantity))
+        }
+        _ => None,
+    }
+}
+
+/// Attaches a goods placeholder above couriers while their front task is a stocked
+/// delivery, sized by the load against the carrying capacity, and removes it once the
+/// delivery completes or is abandoned.
+pub fn sync_carried_goods(
+    mut commands: Commands,
+    registry: Res<EconomyRegistry>,
+    task_queues: Res<ActorTaskQueues>,
+    mut carriers: ResMut<CarriedGoodsRegistry>,
+    visuals: Res<TradeGoodPlaceholderVisuals>,
+    actors: Query<(Entity, &Identity, &Inventory), With<Profession>>,
+) {
+    let capacity = registry.encumbrance().capacity;
+    let mut present = HashSet::new();
+
+    for (entity, identity, inventory) in actors.iter() {
+        present.insert(identity.id);
+
+        let carrying = carried_delivery(&task_queues, identity.id, inventory);
+        match (carrying, carriers.get(identity.id)) {
+            (Some((good, quantity)), Some(current))
+                if current.good == good && current.quantity == quantity => {}
+            (Some((good, quantity)), _) => {
+                let placeholder = spawn_carried_placeholder(
+                    &mut commands,
+                    &visuals,
+                    entity,
+                    good,
+                    carry_scale(load_factor(quantity, capacity)),
+                    entity.index() as f32 * BOB_PHASE_STEP,
+                );
+                if let Some(previous) = carriers.start(identity.id, good, quantity, placeholder) {
+                    commands.entity(previous).try_despawn();
+                }
+                debug!(
+                    "{} picks up {} for delivery",
+                    identity.display_name,
+                    good.label()
+                );
+            }
+            (None, Some(_)) => {
+                if let Some(finished) = carriers.finish(identity.id) {
+                    commands.entity(finished.entity).try_despawn();
+                }
+            }
+            (None, None) => {}
+        }
+    }
+
+    // Only a real abandonment counts as a change, so crate stacks re-sync just when needed.
+    let orphans = carriers
+        .bypass_change_detection()
+        .abandon_where(|courier, _| !present.contains(&courier));
+    if !orphans.is_empty() {
+        carriers.set_changed();
+    }
+    for orphan in orphans {
+        commands.entity(orphan).try_despawn();
+    }
+}
+
+/// Slows couriers by the weight of the delivery they carry, through their
+/// `ENCUMBRANCE_SPEED_SOURCE` speed factor, and lets heavily loaded ones grumble, at most
+/// once per `grumble_cooldown_hours` each. Couriers at full speed lose the factor. Does
+/// nothing beyond clearing factors while `[encumbrance]` is disabled. Despawned NPCs'
+/// grumble times are forgotten.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn apply_encumbrance(
+    mut commands: Commands,
+    registry: Res<EconomyRegistry>,
+    task_queues: Res<ActorTaskQueues>,
+    clock: Option<Res<WorldClock>>,
+    mut dialogue_queue: ResMut<DialogueRequestQueue>,
+    mut chatter_budgets: ResMut<ChatterBudgets>,
+    sleepers: Res<SleepRoster>,
+    mut actors: Query<
+        (Entity, &Identity, &Inventory, Option<&mut SpeedModifiers>),
+        With<Profession>,
+    >,
+    mut despawned: MessageReader<NpcDespawnedEvent>,
+    mut last_grumble: Local<HashMap<NpcId, ContextClock>>,
+) {
+    for event in despawned.read() {
+        last_grumble.remove(&event.npc_id);
+    }
+    let config = registry.encumbrance();
+    let now = clock
+        .as_deref()
+        .map(|clock| ContextClock::new(clock.day_count(), clock.time_of_day()));
+
+    for (entity, identity, inventory, modifiers) in &mut actors {
+        let carrying =
+            carried_delivery(&task_queues, identity.id, inventory).filter(|_| config.enabled);
+        let quantity = carrying.map_or(0, |(_, quantity)| quantity);
+        let factor = config.speed_factor_for(quantity);
+        let slowed = factor < 1.0;
+        match modifiers {
+            Some(mut modifiers)
+                if slowed && modifiers.get(ENCUMBRANCE_SPEED_SOURCE) != Some(factor) =>
+            {
+                modifiers.set(ENCUMBRANCE_SPEED_SOURCE, factor);
+            }
+            Some(mut modifiers) if !slowed && modifiers.get(ENCUMBRANCE_SPEED_SOURCE).is_some() => {
+                modifiers.remove(ENCUMBRANCE_SPEED_SOURCE);
+            }
+            Some(_) => {}
+            None if slowed => {
+                let mut modifiers = SpeedModifiers::default();
+                modifiers.set(ENCUMBRANCE_SPEED_SOURCE, factor);
+                commands.entity(entity).insert(modifiers);
+            }
+            None => {}
+        }
+
+        let (Some((good, quantity)), Some(now)) = (carrying, now) else {
+            continue;
+        };
+        if !config.grumbles_at(quantity) {
+            continue;
+        }
+        let cooldown_days = config.grumble_cooldown_hours / HOURS_PER_DAY;
+        if last_grumble
+            .get(&identity.id)
+            .is_some_and(|last| !now.has_reached(last.later_by(cooldown_days)))
+        {
+            continue;
+        }
+        let queued = queue_load_grumble(
+            &mut dialogue_queue,
+            &mut chatter_budgets,
+            &sleepers,
+            LoadGrumbleInput {
+                now,
+                speaker: identity.id,
+                speaker_name: &identity.display_name,
+                good,
+                quantity,
+            },
+        );
+        if queued {
+            last_grumble.insert(identity.id, now);
+        }
+    }
+}
+
+/// Bobs carried goods gently while couriers walk.
+pub fn animate_carried_goods(
+    sim_clock: Res<SimulationClock>,
+    mut placeholders: Query<(&CarriedGoodPlaceholder, &mut Transform)>,
+) {
+    let elapsed = sim_clock.elapsed().as_secs_f32();
+    for (carried, mut transform) in placeholders.iter_mut() {
+        transform.translation.y = CARRY_HEIGHT + bob_offset(elapsed, carried.bob_phase);
+    }
+}
+
+fn spawn_carried_placeholder(
+    commands: &mut Commands,
+    visuals: &TradeGoodPlaceholderVisuals,
+    courier: Entity,
+    good: TradeGood,
+    scale: f32,
+    bob_phase: f32,
+) -> Entity {
+    let entity = commands
+        .spawn((
+            Mesh3d(visuals.mesh()),
+            MeshMaterial3d(visuals.material(good)),
+            Transform::from_xyz(0.0, CARRY_HEIGHT, CARRY_FORWARD_OFFSET)
+                .with_scale(Vec3::splat(scale)),
+            CarriedGoodPlaceholder { good, bob_phase },
+            Name::new(format!("Carried {}", good.label())),
+        ))
+        .id();
+    commands.entity(courier).add_child(entity);
+    entity
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dialogue::types::DialogueTopicHint,
+        economy::{
+            encumbrance::EncumbranceConfig,
+            systems::{advance_actor_tasks, test_support::GrainCourier},
+        },
+        npc::{components::NpcId, systems::drive_npc_locomotion},
+    };
+
+    fn carrier_app() -> (App, Entity, NpcId) {
+        let mut app = App::new();
+        app.init_resource::<Assets<Mesh>>()
+            .init_resource::<Assets<StandardMaterial>>()
+            .init_resource::<TradeGoodPlaceholderVisuals>()
+            .init_resource::<CarriedGoodsRegistry>()
+            .init_resource::<ActorTaskQueues>()
+            .insert_resource(EconomyRegistry::fallback())
+            .add_systems(Update, sync_carried_goods);
+
+        let npc = NpcId::new(0);
+        let mut inventory = Inventory::default();
+        inventory.add_good(TradeGood::Tools, 1);
+        let courier = app
+            .world_mut()
+            .spawn((
+                Transform::default(),
+                Identity::new(npc, "Cedric", 41.0),
+                Profession::Blacksmith,
+                inventory,
+            ))
+            .id();
+        (app, courier, npc)
+    }
+
+    fn queue_delivery(app: &mut App) {
+        app.world_mut()
+            .resource_mut::<ActorTaskQueues>()
+            .ensure_queue(NpcId::new(0))
+            .push_back(ActorTask::Deliver {
+                good: TradeGood::Tools,
+                quantity: 1,
+                target: Profession::Farmer,
+                recipient: None,
+            });
+    }
+
+    fn carried(app: &App, npc: NpcId) -> Option<Entity> {
+        app.world()
+            .resource::<CarriedGoodsRegistry>()
+            .get(npc)
+            .map(|carried| carried.entity)
+    }
+
+    #[test]
+    fn carried_placeholder_follows_delivery_lifecycle() {
+        let (mut app, courier, npc) = carrier_app();
+        app.update();
+        assert!(carried(&app, npc).is_none(), "no delivery queued yet");
+
+        queue_delivery(&mut app);
+        app.update();
+        let placeholder = carried(&app, npc).expect("delivery start should attach goods");
+        assert_eq!(
+            app.world().get::<ChildOf>(placeholder).map(ChildOf::parent),
+            Some(courier)
+        );
+
+        app.update();
+        assert_eq!(
+            carried(&app, npc),
+            Some(placeholder),
+            "no respawn each frame"
+        );
+
+        app.world_mut()
+            .resource_mut::<ActorTaskQueues>()
+            .pop_front(npc);
+        app.update();
+        assert!(carried(&app, npc).is_none());
+        assert!(app.world().get_entity(placeholder).is_err());
+    }
+
+    #[test]
+    fn day_reset_and_despawn_abandon_carried_goods() {
+        let (mut app, courier, npc) = carrier_app();
+        queue_delivery(&mut app);
+        app.update();
+        let placeholder = carried(&app, npc).unwrap();
+
+        app.world_mut().resource_mut::<ActorTaskQueues>().clear();
+        app.update();
+        assert!(carried(&app, npc).is_none(), "day reset clears queues");
+        assert!(app.world().get_entity(placeholder).is_err());
+
+        queue_delivery(&mut app);
+        app.update();
+        assert!(carried(&app, npc).is_some());
+
+        app.world_mut().entity_mut(courier).despawn();
+        app.update();
+        assert!(
+            carried(&app, npc).is_none(),
+            "despawned courier is abandoned"
+        );
+    }
+
+    #[test]
+    fn registry_returns_replaced_entities() {
+        let mut world = World::new();
+        let first = world.spawn_empty().id();
+        let second = world.spawn_empty().id();
+        let mut registry = CarriedGoodsRegistry::default();
+        let npc = NpcId::new(3);
+
+        assert_eq!(registry.start(npc, TradeGood::Grain, 1, first), None);
+        assert_eq!(
+            registry.start(npc, TradeGood::Flour, 2, second),
+            Some(first)
+        );
+        assert_eq!(registry.finish(npc).map(|c| c.entity), Some(second));
+        assert_eq!(registry.finish(npc), None);
+    }
+
+    #[test]
+    fn placeholder_grows_with_the_load() {
+        assert!((carry_scale(1.0) - CARRY_SCALE).abs() < 1e-5);
+        assert!((carry_scale(3.0) - CARRY_SCALE).abs() < 1e-5);
+        assert!((carry_scale(0.0) - CARRY_SCALE * LIGHT_LOAD_SCALE).abs() < 1e-5);
+        assert!(carry_scale(0.2) < carry_scale(0.8));
+        assert!((carry_scale(f32::NAN) - CARRY_SCALE).abs() < 1e-5);
+    }
+
+    /// Runs a farmer-to-miller delivery of `quantity` grain with encumbrance on. Returns the
+    /// frames it took, whether the miller got the grain, and whether the farmer grumbled.
+    fn deliver_load(quantity: u32) -> (u32, bool, bool) {
+        let mut courier = GrainCourier::new(quantity);
+        courier.app.add_message::<NpcDespawnedEvent>().add_systems(
+            Update,
+            (apply_encumbrance, drive_npc_locomotion)
+                .chain()
+                .after(advance_actor_tasks),
+        );
+        courier
+            .app
+            .world_mut()
+            .resource_mut::<EconomyRegistry>()
+            .set_encumbrance_for_tests(EncumbranceConfig {
+                enabled: true,
+                ..EncumbranceConfig::default()
+            });
+
+        let farmer = courier.farmer;
+        let farmer_id = courier.app.world().get::<Identity>(farmer).unwrap().id;
+        let mut grumbled = false;
+        let (frames, delivered) = courier.run(1000, |app| {
+            grumbled |= app
+                .world()
+                .resource::<DialogueRequestQueue>()
+                .iter_pending()
+                .any(|view| view.speaker == farmer_id && view.topic == DialogueTopicHint::Status);
+        });
+        assert!(
+            courier
+                .app
+                .world()
+                .get::<SpeedModifiers>(farmer)
+                .is_none_or(|modifiers| modifiers.get(ENCUMBRANCE_SPEED_SOURCE).is_none()),
+            "the slowdown ends with the delivery"
+        );
+        (frames, delivered, grumbled)
+    }
+
+    #[test]
+    fn loaded_courier_is_slower_on_the_same_route_and_grumbles() {
+        let (light_frames, light_delivered, light_grumbled) = deliver_load(1);
+        let (heavy_frames, heavy_delivered, heavy_grumbled) = deliver_load(5);
+        assert!(light_delivered && heavy_delivered);
+        assert!(
+            heavy_frames as f32 > light_frames as f32 * 1.2,
+            "a full load slows the courier ({heavy_frames} vs {light_frames} frames)"
+        );
+        assert!(heavy_grumbled, "a full load is worth a grumble");
+        assert!(!light_grumbled);
+    }
+
+    #[test]
+    fn bob_stays_within_amplitude() {
+        for step in 0..50 {
+            let offset = bob_offset(step as f32 * 0.1, 0.5);
+            assert!(offset.abs() <= BOB_AMPLITUDE + f32::EPSILON);
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use bevy::prelude::*;
+
+use crate::{
+    dialogue::chatter::{compute_chatter_budget, ChatterBudgets},
+    npc::{
+        components::Identity,
+        motivation::{MotivationConfig, NpcMotivation},
+    },
+    world::time::DayChangedEvent,
+};
+
+/// Hands every NPC a fresh chatter budget when a new day starts, scaled by their mood at
+/// that moment.
+pub fn reset_chatter_budgets(
+    mut day_changes: MessageReader<DayChangedEvent>,
+    config: Res<MotivationConfig>,
+    mut budgets: ResMut<ChatterBudgets>,
+    npcs: Query<(&Identity, &NpcMotivation)>,
+) {
+    let Some(day) = day_changes.read().last().map(|change| change.new_day) else {
+        return;
+    };
+    let chatter = &config.chatter;
+    budgets.reset(
+        day,
+        npcs.iter().map(|(identity, motivation)| {
+            (
+                identity.id,
+                compute_chatter_budget(motivation.mood(), chatter.base_budget, &chatter.modifiers),
+            )
+        }),
+    );
+    debug!("Chatter budgets for day {day}: {}", budgets.summary());
+}
@@ -0,0 +1,415 @@
+//! Daily economy planning: sampling requests, queueing tasks, and announcing the day.
+
+use bevy::prelude::*;
+
+use crate::{
+    core::format::pluralize,
+    dialogue::{chatter::ChatterBudgets, queue::DialogueRequestQueue},
+    economy::{
+        components::{Profession, ProfessionCrate},
+        data::EconomyRegistry,
+        events::{EconomyEventKind, EconomyEventOccurred},
+        fairness::VillageFairness,
+        planning::{roll_scarcity_events, sample_daily_requests, schedule_daily_requests},
+        reservations::ReservedStock,
+        resources::{EconomyActorCache, ProfessionCrateRegistry},
+        routing::batch_deliveries,
+        systems::dialogue::queue_schedule_brief,
+        tasks::{ActorTask, ActorTaskQueues, EconomyDayState},
+    },
+    npc::sleep::SleepRoster,
+    world::{
+        time::{DayChangedEvent, WorldTimeSettings},
+        world_event::MarketDayConfig,
+    },
+};
+
+mod chatter;
+mod reload;
+
+pub use chatter::reset_chatter_budgets;
+pub use reload::{apply_pending_economy_reload, reload_economy_config};
+
+use reload::replan_after_registry_change;
+
+/// Prepares the list of tasks each economy actor should complete when a `DayChangedEvent`
+/// arrives, or when `EconomyDayState::request_replan` asks for the day again; a multi-day
+/// jump plans only the day it lands on. Replanning the planned day schedules only its
+/// outstanding requests under the same scarcity, and announces nothing again. If the
+/// registry changes after the day is planned, today's queues are revised in place.
+/// Manufacture tasks reserve their inputs in `ReservedStock` whenever the plan changes. On
+/// market days, deliveries are held until the market opens. Deliveries `VillageFairness`
+/// owes for the day are planned alongside the sampled requests. With `[routing]` enabled,
+/// each courier's deliveries are batched and ordered by where the target crates stand.
+/// Recipes out of season (see `WorldTimeSettings::season_of`) are skipped like scarcity,
+/// and their makers mention it once per season.
+#[allow(clippy::too_many_arguments)]
+pub fn prepare_economy_day(
+    mut day_changes: MessageReader<DayChangedEvent>,
+    registry: Res<EconomyRegistry>,
+    mut day_state: ResMut<EconomyDayState>,
+    mut task_queues: ResMut<ActorTaskQueues>,
+    mut reserved: ResMut<ReservedStock>,
+    time_settings: Option<Res<WorldTimeSettings>>,
+    mut dialogue_queue: ResMut<DialogueRequestQueue>,
+    mut chatter_budgets: ResMut<ChatterBudgets>,
+    sleepers: Res<SleepRoster>,
+    mut economy_events: MessageWriter<EconomyEventOccurred>,
+    actors: Res<EconomyActorCache>,
+    market_day: Option<Res<MarketDayConfig>>,
+    fairness: Option<ResMut<VillageFairness>>,
+    crate_registry: Option<Res<ProfessionCrateRegistry>>,
+    crates: Query<&GlobalTransform, With<ProfessionCrate>>,
+) {
+    // Checked first so an idle frame does not mark the state changed.
+    let replan = if day_state.replan_requested.is_some() {
+        day_state.replan_requested.take()
+    } else {
+        None
+    };
+    let new_day = day_changes.read().last().map(|change| change.new_day);
+    let crate_position = |profession| {
+        let entity = crate_registry.as_deref()?.get(profession)?;
+        crates.get(entity).ok().map(GlobalTransform::translation)
+    };
+    let Some(day) = new_day.or(replan) else {
+        // A freshly inserted registry is what the day was planned against, not a change.
+        if let Some(day) = day_state.last_planned_day {
+            if registry.is_changed() && !registry.is_added() {
+                replan_after_registry_change(
+                    day,
+                    &registry,
+                    &actors,
+                    &mut day_state,
+                    &mut task_queues,
+                    crate_position,
+                );
+                reserved.reserve_queued(day, &task_queues);
+            }
+        }
+        return;
+    };
+
+    let replanning = new_day.is_none() && day_state.last_planned_day == Some(day);
+    task_queues.clear();
+
+    let season = time_settings
+        .as_deref()
+        .and_then(|settings| settings.season_of(day))
+        .map(str::to_string);
+    let (scarcity, suppressed, requests) = if replanning {
+        // Owed deliveries were taken from `VillageFairness` with the first plan and live
+        // on in `planned_requests`.
+        let outstanding = day_state.outstanding_requests();
+        info!(
+            "Replanning day {day}: {} of {} requests outstanding",
+            outstanding.len(),
+            day_state.planned_requests.len()
+        );
+        (Vec::new(), day_state.suppressed.clone(), outstanding)
+    } else {
+        let scarcity = roll_scarcity_events(&registry, day);
+        let suppressed: Vec<Profession> = scarcity.iter().map(|event| event.profession).collect();
+        let mut requests = sample_daily_requests(&registry, day);
+        if let Some(mut fairness) = fairness {
+            let owed = fairness.take_owed(day);
+            if !owed.is_empty() {
+                info!("Day {day}: planning {} rebalancing deliveries", owed.len());
+            }
+            requests.extend(owed);
+        }
+        (scarcity, suppressed, requests)
+    };
+
+    let scheduled = schedule_daily_requests(
+        &registry,
+        &actors,
+        &requests,
+        &suppressed,
+        season.as_deref(),
+        &mut task_queues,
+    );
+    // Yesterday's claims expire with its queues; today's recipes claim their inputs.
+    reserved.reserve_queued(day, &task_queues);
+    if let Err(error) = scheduled {
+        // Nothing to revise in place; the next day change plans afresh.
+        day_state.last_planned_day = None;
+        warn!("Unable to schedule economy tasks for day {day}: {error}");
+        return;
+    }
+
+    batch_queued_deliveries(&registry, &actors, &mut task_queues, crate_position);
+
+    // Surplus goes home once the day's deliveries are done.
+    for actor in actors.iter() {
+        task_queues
+            .ensure_queue(actor.npc_id)
+            .push_back(ActorTask::DepositSurplus);
+    }
+
+    day_state.last_planned_day = Some(day);
+    day_state.season = season;
+    day_state.last_dependency_evaluation_day = None;
+    if !replanning {
+        day_state.planned_requests = requests;
+        day_state.suppressed = suppressed;
+        day_state.unmet_requests.clear();
+        day_state.delivered_requests.clear();
+    }
+    day_state.deliveries_open_at = market_day
+        .filter(|market_day| market_day.is_market_day(day))
+        .map(|market_day| market_day.start_fraction);
+    if let Some(opens) = day_state.deliveries_open_at {
+        info!("Market day {day}: deliveries wait for the market to open at {opens:.3}");
+    }
+
+    for event in scarcity {
+        info!(
+            "Scarcity on day {day}: {} production suspended ({})",
+            event.profession.label(),
+            event.description
+        );
+        economy_events.write(EconomyEventOccurred {
+            kind: EconomyEventKind::Scarcity {
+                profession: event.profession,
+            },
+            day,
+        });
+        for actor in actors.workers(event.profession) {
+            queue_schedule_brief(
+                &mut dialogue_queue,
+                &mut chatter_budgets,
+                &sleepers,
+                day,
+                actor.npc_id,
+                &actor.display_name,
+                event.description.clone(),
+            );
+        }
+    }
+
+    let season_number = time_settings
+        .as_deref()
+        .and_then(|settings| settings.season_number(day));
+    if let (Some(season), Some(season_number)) = (day_state.season.clone(), season_number) {
+        for recipe in registry.recipes() {
+            if recipe.available_in(Some(&season))
+                || day_state.off_season_announced.get(&recipe.id) == Some(&season_number)
+            {
+                continue;
+            }
+            day_state
+                .off_season_announced
+                .insert(recipe.id.clone(), season_number);
+            info!("Day {day}: {} is out of season in {season}", recipe.id);
+            let goods: Vec<String> = recipe
+                .produces
+                .iter()
+                .map(|output| pluralize(output.good.label()))
+                .collect();
+            let description = format!(
+                "It is {season}, so no {} get made until the season turns",
+                goods.join(" or ")
+            );
+            for actor in actors.workers(recipe.actor) {
+                queue_schedule_brief(
+                    &mut dialogue_queue,
+                    &mut chatter_budgets,
+                    &sleepers,
+                    day,
+                    actor.npc_id,
+                    &actor.display_name,
+                    description.clone(),
+                );
+            }
+        }
+    }
+
+    for actor in actors.iter() {
+        debug!(
+            "Planned {} tasks for {} ({})",
+            task_queues.remaining_tasks(actor.npc_id),
+            actor.display_name,
+            actor.profession.label()
+        );
+    }
+}
+
+/// Batches each courier's queued deliveries when routing is enabled, starting from its own
+/// crate.
+fn batch_queued_deliveries(
+    registry: &EconomyRegistry,
+    actors: &EconomyActorCache,
+    task_queues: &mut ActorTaskQueues,
+    crate_position: impl Fn(Profession) -> Option<Vec3> + Copy,
+) {
+    let routing = registry.routing();
+    if !routing.enabled {
+        return;
+    }
+    for actor in actors.iter() {
+        batch_deliveries(
+            task_queues.ensure_queue(actor.npc_id),
+            routing.carry_capacity,
+            crate_position(actor.profession),
+            crate_position,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        dialogue::{chatter::ChatterBudgets, queue::DialogueRequestQueue},
+        economy::{
+            components::Profession,
+            data::{EconomyConfig, EconomyRegistry},
+            events::EconomyEventOccurred,
+            reservations::ReservedStock,
+            resources::{EconomyActor, EconomyActorCache},
+            tasks::{ActorTaskQueues, EconomyDayState},
+        },
+        npc::{components::NpcId, sleep::SleepRoster},
+        world::time::{announce_day_change, DayChangedEvent, WorldClock, WorldTimeSettings},
+    };
+
+    pub(super) fn day_app() -> App {
+        let mut app = App::new();
+        app.insert_resource(WorldClock::new())
+            .add_message::<DayChangedEvent>()
+            .add_systems(Update, announce_day_change);
+        app
+    }
+
+    #[test]
+    fn economy_planning_runs_once_per_day() {
+        let mut app = day_app();
+        app.insert_resource(EconomyRegistry::fallback())
+            .init_resource::<EconomyDayState>()
+            .init_resource::<ActorTaskQueues>()
+            .init_resource::<ReservedStock>()
+            .init_resource::<DialogueRequestQueue>()
+            .init_resource::<ChatterBudgets>()
+            .init_resource::<SleepRoster>()
+            .add_message::<EconomyEventOccurred>()
+            .add_systems(Update, prepare_economy_day.after(announce_day_change));
+        let mut actors = EconomyActorCache::default();
+        actors.rebuild(
+            Profession::ALL
+                .into_iter()
+                .enumerate()
+                .map(|(index, profession)| EconomyActor {
+                    entity: app.world_mut().spawn_empty().id(),
+                    npc_id: NpcId::new(index as u64),
+                    display_name: profession.label().to_string(),
+                    profession,
+                }),
+        );
+        app.insert_resource(actors);
+
+        app.update();
+        assert_eq!(
+            app.world().resource::<EconomyDayState>().last_planned_day,
+            Some(0),
+            "the startup announcement plans day 0"
+        );
+        assert!(!app.world().resource::<ActorTaskQueues>().is_empty());
+
+        // Finishing the day's work must not trigger another plan the same day.
+        app.world_mut().resource_mut::<ActorTaskQueues>().clear();
+        for _ in 0..3 {
+            app.update();
+        }
+        assert!(app.world().resource::<ActorTaskQueues>().is_empty());
+
+        app.world_mut().resource_mut::<WorldClock>().skip_days(3);
+        app.update();
+        assert_eq!(
+            app.world().resource::<EconomyDayState>().last_planned_day,
+            Some(3),
+            "a jump plans only the day it lands on"
+        );
+        assert!(!app.world().resource::<ActorTaskQueues>().is_empty());
+
+        app.world_mut().resource_mut::<ActorTaskQueues>().clear();
+        app.world_mut()
+            .resource_mut::<EconomyDayState>()
+            .request_replan(3);
+        app.update();
+        let day_state = app.world().resource::<EconomyDayState>();
+        assert_eq!(day_state.last_planned_day, Some(3));
+        assert_eq!(day_state.replan_requested, None);
+        assert!(
+            !app.world().resource::<ActorTaskQueues>().is_empty(),
+            "a requested replan plans the day again without a day change"
+        );
+    }
+
+    #[test]
+    fn off_season_is_mentioned_once_per_season() {
+        let mut app = day_app();
+        let config = EconomyConfig::from_toml_str(
+            r#"
+            [[recipes]]
+            id = "grain_harvest"
+            actor = "farmer"
+            produces = [{ good = "grain", quantity = 1 }]
+            seasons = ["spring"]
+            "#,
+        )
+        .unwrap();
+        // One day per season.
+        let settings = WorldTimeSettings::from_toml_str("[calendar]\ndays_per_year = 4.0\n")
+            .expect("valid time toml");
+        app.insert_resource(EconomyRegistry::from_config(config).unwrap())
+            .insert_resource(settings)
+            .init_resource::<EconomyDayState>()
+            .init_resource::<ActorTaskQueues>()
+            .init_resource::<ReservedStock>()
+            .init_resource::<DialogueRequestQueue>()
+            .init_resource::<ChatterBudgets>()
+            .init_resource::<SleepRoster>()
+            .add_message::<EconomyEventOccurred>()
+            .add_systems(Update, prepare_economy_day.after(announce_day_change));
+        let mut actors = EconomyActorCache::default();
+        actors.rebuild([EconomyActor {
+            entity: app.world_mut().spawn_empty().id(),
+            npc_id: NpcId::new(1),
+            display_name: "Alric".to_string(),
+            profession: Profession::Farmer,
+        }]);
+        app.insert_resource(actors);
+        let briefs = |app: &App| {
+            app.world()
+                .resource::<DialogueRequestQueue>()
+                .total_enqueued()
+        };
+
+        app.update();
+        assert_eq!(briefs(&app), 0, "spring is harvest season");
+
+        app.world_mut().resource_mut::<WorldClock>().skip_days(1);
+        app.update();
+        assert_eq!(briefs(&app), 1);
+        assert_eq!(
+            app.world().resource::<EconomyDayState>().season.as_deref(),
+            Some("summer")
+        );
+
+        app.world_mut()
+            .resource_mut::<EconomyDayState>()
+            .request_replan(1);
+        app.update();
+        assert_eq!(briefs(&app), 1, "planning summer again says nothing new");
+
+        app.world_mut().resource_mut::<WorldClock>().skip_days(1);
+        app.update();
+        assert_eq!(briefs(&app), 2, "autumn is a new season");
+
+        app.world_mut().resource_mut::<WorldClock>().skip_days(2);
+        app.update();
+        assert_eq!(briefs(&app), 2, "next spring the harvest is back");
+    }
+}
@@ -0,0 +1,339 @@
+use bevy::prelude::*;
+
+use crate::{
+    core::config::{ConfigDiagnostics, ConfigReloadRequested},
+    economy::{
+        components::Profession,
+        data::{EconomyRegistry, ECONOMY_CONFIG_PATH},
+        planning::{
+            requests_added_since, roll_scarcity_events, sample_daily_requests,
+            schedule_daily_requests,
+        },
+        resources::{EconomyActorCache, PendingEconomyReload},
+        tasks::{ActorTask, ActorTaskQueues, EconomyDayState},
+    },
+    world::time::DayChangedEvent,
+};
+
+use super::batch_queued_deliveries;
+
+/// Re-reads `config/economy.toml` on request. A valid config is staged rather than swapped
+/// in, because today's queues were planned against the current recipes.
+pub fn reload_economy_config(
+    mut requests: MessageReader<ConfigReloadRequested>,
+    mut diagnostics: ResMut<ConfigDiagnostics>,
+    mut pending: ResMut<PendingEconomyReload>,
+) {
+    if !requests
+        .read()
+        .any(|request| request.is_for(ECONOMY_CONFIG_PATH))
+    {
+        return;
+    }
+    if let Some(registry) = diagnostics.report_reload(ECONOMY_CONFIG_PATH, EconomyRegistry::load())
+    {
+        info!("Economy config reload staged; it applies when the next day is planned");
+        pending.stage(registry);
+    }
+}
+
+/// Swaps a staged economy config in just before a new day is planned.
+pub fn apply_pending_economy_reload(
+    mut day_changes: MessageReader<DayChangedEvent>,
+    mut pending: ResMut<PendingEconomyReload>,
+    mut registry: ResMut<EconomyRegistry>,
+) {
+    let Some(change) = day_changes.read().last() else {
+        return;
+    };
+    if let Some(reloaded) = pending.take() {
+        info!(
+            "Applying reloaded economy config for day {}",
+            change.new_day
+        );
+        *registry = reloaded;
+    }
+}
+
+/// Drops queued tasks a changed registry can no longer honour and schedules the requests
+/// it newly asks for today, batched with the deliveries still queued. Scarcity is
+/// re-rolled against the new registry but not re-announced.
+pub(super) fn replan_after_registry_change(
+    day: u64,
+    registry: &EconomyRegistry,
+    actors: &EconomyActorCache,
+    day_state: &mut EconomyDayState,
+    task_queues: &mut ActorTaskQueues,
+    crate_position: impl Fn(Profession) -> Option<Vec3> + Copy,
+) {
+    let revalidation = task_queues.revalidate_against(registry);
+    if revalidation.total() > 0 {
+        info!(
+            "Economy config changed on day {day}: dropped {} queued tasks ({})",
+            revalidation.total(),
+            revalidation.summary()
+        );
+    }
+
+    let requests = sample_daily_requests(registry, day);
+    let added = requests_added_since(&day_state.planned_requests, &requests);
+    day_state.planned_requests = requests;
+    if added.is_empty() {
+        return;
+    }
+
+    let suppressed: Vec<Profession> = roll_scarcity_events(registry, day)
+        .iter()
+        .map(|event| event.profession)
+        .collect();
+    let deposits = task_queues.remove_tasks(|_, task| !matches!(task, ActorTask::DepositSurplus));
+    let scheduled = schedule_daily_requests(
+        registry,
+        actors,
+        &added,
+        &suppressed,
+        day_state.season.as_deref(),
+        task_queues,
+    );
+    batch_queued_deliveries(registry, actors, task_queues, crate_position);
+
+    // Keep the trip to storage last, and give anyone who just got work one too.
+    for actor in actors.iter() {
+        let had_deposit = deposits.iter().any(|(npc, _)| *npc == actor.npc_id);
+        if had_deposit || task_queues.remaining_tasks(actor.npc_id) > 0 {
+            task_queues
+                .ensure_queue(actor.npc_id)
+                .push_back(ActorTask::DepositSurplus);
+        }
+    }
+
+    match scheduled {
+        Ok(()) => info!(
+            "Economy config changed on day {day}: scheduled {} new requests",
+            added.len()
+        ),
+        Err(error) => warn!("Unable to schedule new economy tasks for day {day}: {error}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{prepare_economy_day, tests::day_app};
+    use super::*;
+
+    use crate::{
+        dialogue::{chatter::ChatterBudgets, queue::DialogueRequestQueue},
+        economy::{
+            components::{Profession, TradeGood},
+            data::{EconomyConfig, EconomyRegistry},
+            events::EconomyEventOccurred,
+            planning::SampledRequest,
+            reservations::ReservedStock,
+            resources::{EconomyActor, EconomyActorCache, PendingEconomyReload},
+            tasks::{ActorTask, ActorTaskQueues, EconomyDayState},
+        },
+        npc::{components::NpcId, sleep::SleepRoster},
+        world::time::{announce_day_change, WorldClock},
+    };
+
+    #[test]
+    fn staged_economy_reload_waits_for_the_next_day() {
+        let mut app = day_app();
+        app.insert_resource(EconomyRegistry::fallback())
+            .init_resource::<PendingEconomyReload>()
+            .add_systems(
+                Update,
+                apply_pending_economy_reload.after(announce_day_change),
+            );
+        app.update();
+
+        let reloaded = EconomyRegistry::load().expect("shipped economy config is valid");
+        let reloaded_seed = reloaded.seed();
+        assert_ne!(reloaded_seed, EconomyRegistry::fallback().seed());
+        app.world_mut()
+            .resource_mut::<PendingEconomyReload>()
+            .stage(reloaded);
+
+        app.update();
+        assert!(
+            app.world().resource::<PendingEconomyReload>().is_pending(),
+            "today is already planned, so the reload must wait"
+        );
+        assert_ne!(
+            app.world().resource::<EconomyRegistry>().seed(),
+            reloaded_seed
+        );
+
+        app.world_mut().resource_mut::<WorldClock>().skip_days(1);
+        app.update();
+        assert!(!app.world().resource::<PendingEconomyReload>().is_pending());
+        assert_eq!(
+            app.world().resource::<EconomyRegistry>().seed(),
+            reloaded_seed
+        );
+    }
+
+    #[test]
+    fn a_replan_schedules_only_outstanding_requests_without_announcing_again() {
+        let mut app = day_app();
+        let shipped = std::fs::read_to_string("config/economy.toml").expect("economy config");
+        let config = EconomyConfig::from_toml_str(&format!(
+            "{shipped}\n[[scarcity_events]]\nprofession = \"blacksmith\"\nprobability = 1.0\ndescription = \"The forge is cold\""
+        ))
+        .unwrap();
+        app.insert_resource(EconomyRegistry::from_config(config).unwrap())
+            .init_resource::<EconomyDayState>()
+            .init_resource::<ActorTaskQueues>()
+            .init_resource::<ReservedStock>()
+            .init_resource::<DialogueRequestQueue>()
+            .init_resource::<ChatterBudgets>()
+            .init_resource::<SleepRoster>()
+            .add_message::<EconomyEventOccurred>()
+            .add_systems(Update, prepare_economy_day.after(announce_day_change));
+        let mut actors = EconomyActorCache::default();
+        actors.rebuild(
+            Profession::ALL
+                .into_iter()
+                .enumerate()
+                .map(|(index, profession)| EconomyActor {
+                    entity: app.world_mut().spawn_empty().id(),
+                    npc_id: NpcId::new(index as u64),
+                    display_name: profession.label().to_string(),
+                    profession,
+                }),
+        );
+        app.insert_resource(actors);
+        let mut cursor = app
+            .world()
+            .resource::<Messages<EconomyEventOccurred>>()
+            .get_cursor();
+        let mut scarcity_events = |app: &App| {
+            let messages = app.world().resource::<Messages<EconomyEventOccurred>>();
+            cursor.read(messages).count()
+        };
+        let briefs = |app: &App| {
+            app.world()
+                .resource::<DialogueRequestQueue>()
+                .total_enqueued()
+        };
+        let deliveries = |app: &App| {
+            let mut deliveries: Vec<(Profession, TradeGood)> = app
+                .world()
+                .resource::<ActorTaskQueues>()
+                .iter()
+                .filter_map(|(_, task)| match task {
+                    ActorTask::Deliver { good, target, .. } => Some((*target, *good)),
+                    _ => None,
+                })
+                .collect();
+            deliveries.sort_by_key(|(target, good)| (target.label(), good.label()));
+            deliveries
+        };
+
+        app.update();
+        assert_eq!(scarcity_events(&app), 1);
+        assert_eq!(briefs(&app), 1, "the blacksmith hears about the cold forge");
+        let planned = app
+            .world()
+            .resource::<EconomyDayState>()
+            .planned_requests
+            .clone();
+        assert!(deliveries(&app).contains(&(Profession::Farmer, TradeGood::Ale)));
+        assert!(!deliveries(&app).contains(&(Profession::Farmer, TradeGood::Tools)));
+
+        app.world_mut()
+            .resource_mut::<EconomyDayState>()
+            .delivered_requests
+            .push(SampledRequest {
+                requester: Profession::Farmer,
+                good: TradeGood::Ale,
+                quantity: 1,
+            });
+        app.world_mut()
+            .resource_mut::<EconomyDayState>()
+            .request_replan(0);
+        app.update();
+
+        assert_eq!(scarcity_events(&app), 0, "scarcity is not announced again");
+        assert_eq!(briefs(&app), 1);
+        let day_state = app.world().resource::<EconomyDayState>();
+        assert_eq!(day_state.planned_requests, planned);
+        assert_eq!(day_state.suppressed, [Profession::Blacksmith]);
+        let replanned = deliveries(&app);
+        assert!(!replanned.contains(&(Profession::Farmer, TradeGood::Ale)));
+        assert!(!replanned.contains(&(Profession::Farmer, TradeGood::Tools)));
+        assert!(replanned.contains(&(Profession::Miller, TradeGood::Ale)));
+    }
+
+    #[test]
+    fn registry_change_batches_new_deliveries_with_the_queued_ones() {
+        let config = |quantity: u32| {
+            EconomyConfig::from_toml_str(&format!(
+                r#"
+                [[recipes]]
+                id = "grain_harvest"
+                actor = "farmer"
+                produces = [{{ good = "grain", quantity = 1 }}]
+
+                [[daily_requests]]
+                requester = "miller"
+                good = "grain"
+                quantity = {quantity}
+
+                [routing]
+                enabled = true
+                carry_capacity = 4
+                "#
+            ))
+            .unwrap()
+        };
+        let mut app = day_app();
+        app.insert_resource(EconomyRegistry::from_config(config(3)).unwrap())
+            .init_resource::<EconomyDayState>()
+            .init_resource::<ActorTaskQueues>()
+            .init_resource::<ReservedStock>()
+            .init_resource::<DialogueRequestQueue>()
+            .init_resource::<ChatterBudgets>()
+            .init_resource::<SleepRoster>()
+            .add_message::<EconomyEventOccurred>()
+            .add_systems(Update, prepare_economy_day.after(announce_day_change));
+        let mut actors = EconomyActorCache::default();
+        actors.rebuild([
+            EconomyActor {
+                entity: app.world_mut().spawn_empty().id(),
+                npc_id: NpcId::new(1),
+                display_name: "Alric".to_string(),
+                profession: Profession::Farmer,
+            },
+            EconomyActor {
+                entity: app.world_mut().spawn_empty().id(),
+                npc_id: NpcId::new(2),
+                display_name: "Berit".to_string(),
+                profession: Profession::Miller,
+            },
+        ]);
+        app.insert_resource(actors);
+        let loads = |app: &App| {
+            app.world()
+                .resource::<ActorTaskQueues>()
+                .iter()
+                .filter_map(|(_, task)| match task {
+                    ActorTask::Deliver { quantity, .. } => Some(*quantity),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        app.update();
+        assert_eq!(loads(&app), [3]);
+
+        *app.world_mut().resource_mut::<EconomyRegistry>() =
+            EconomyRegistry::from_config(config(5)).unwrap();
+        app.update();
+        assert_eq!(
+            loads(&app),
+            [4, 1],
+            "the two extra units join the queued load instead of a second trip"
+        );
+    }
+}
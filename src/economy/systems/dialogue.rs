@@ -1,65 +1,227 @@
-use bevy::prelude::{debug, MessageWriter};
+use bevy::prelude::{debug, warn, MessageWriter};
 
+use crate::core::format::{format_quantity, spoken_time};
 use crate::dialogue::{
-    events::DialogueRequestedEvent,
+    chatter::{ChatterBudgets, ChatterStamp, PairChatterCooldown},
     queue::DialogueRequestQueue,
     types::{
-        DialogueContext, DialogueContextEvent, DialogueRequest, DialogueTopicHint, TradeContext,
-        TradeContextReason, TradeDescriptor,
+        ContextClock, DialogueRequest, DialogueTopicHint, TradeContext, TradeContextReason,
+        TradeDescriptor,
     },
 };
-use crate::npc::components::NpcId;
+use crate::npc::{
+    components::{NpcId, PLAYER_DISPLAY_NAME},
+    sleep::SleepRoster,
+};
 
 use super::super::{
     components::TradeGood,
     events::{TradeCompletedEvent, TradeReason},
+    negotiation::TradeDecision,
 };
 
-const TRADE_PROMPT_VERB: &str = "discusses exchanging a";
+const TRADE_PROMPT_VERB: &str = "discusses exchanging";
 const SCHEDULE_PROMPT_ACTION: &str = "reviews the day's schedule";
 const SCHEDULE_SUMMARY_PREFIX: &str = "Daily plan:";
 const SENTENCE_SUFFIX: &str = ".";
+const UNKNOWN_PARTICIPANT: &str = "An NPC";
+/// Trade chatter still waiting in the dialogue queue this long (two in-game hours) after
+/// the trade is dropped rather than voiced late.
+const TRADE_CHATTER_LIFETIME_DAYS: f32 = 2.0 / 24.0;
+/// Load grumbles still waiting an in-game hour after they were raised are dropped.
+const LOAD_GRUMBLE_LIFETIME_DAYS: f32 = 1.0 / 24.0;
 
 pub(super) struct TradeDialogueInput {
     pub(super) day: u64,
+    pub(super) time_of_day: f32,
     pub(super) from: Option<NpcId>,
     pub(super) to: Option<NpcId>,
+    /// Display names for `from` and `to`; the prompt falls back to ids without them.
+    pub(super) from_name: Option<String>,
+    pub(super) to_name: Option<String>,
     pub(super) good: TradeGood,
     pub(super) quantity: u32,
     pub(super) reason: TradeReason,
 }
 
+/// Queues a schedule brief unless the speaker is asleep or their chatter budget for the
+/// day is spent. A brief not voiced by the end of `day` is dropped.
 pub(super) fn queue_schedule_brief(
     queue: &mut DialogueRequestQueue,
+    budgets: &mut ChatterBudgets,
+    sleepers: &SleepRoster,
     day: u64,
     speaker: NpcId,
+    speaker_name: &str,
     description: String,
 ) {
-    let mut context =
-        DialogueContext::with_events(vec![DialogueContextEvent::ScheduleUpdate { description }]);
-    context.summary = Some(format!("{SCHEDULE_SUMMARY_PREFIX} Day {day}"));
-
+    if sleepers.is_asleep(speaker) {
+        debug!("Skipping schedule brief for {speaker}: asleep");
+        return;
+    }
+    if !budgets.has_remaining(speaker) {
+        debug!("Skipping schedule brief for {speaker}: chatter budget spent");
+        return;
+    }
     let prompt = format!(
         "{speaker} {action}{suffix}",
-        speaker = speaker,
+        speaker = speaker_name,
         action = SCHEDULE_PROMPT_ACTION,
         suffix = SENTENCE_SUFFIX
     );
 
-    let request = DialogueRequest::new(speaker, None, prompt, DialogueTopicHint::Schedule, context);
-    let id = queue.enqueue(request);
-    debug!(
-        "Queued schedule update dialogue {} for speaker {} on day {}",
-        id.value(),
-        speaker,
-        day
-    );
+    match DialogueRequest::builder(speaker)
+        .speaker_name(speaker_name)
+        .topic(DialogueTopicHint::Schedule)
+        .prompt(prompt)
+        .summary(format!("{SCHEDULE_SUMMARY_PREFIX} Day {day}"))
+        .schedule_update(description)
+        .expires_at(ContextClock::new(day, 0.0).end_of_day())
+        .enqueue(queue)
+    {
+        Ok(id) => {
+            budgets.spend(speaker);
+            debug!(
+                "Queued schedule update dialogue ({}) for speaker {} on day {}",
+                id, speaker, day
+            );
+        }
+        Err(error) => warn!("Schedule dialogue for {speaker} not queued: {error}"),
+    }
+}
+
+/// What a negotiated offer was and who answered it.
+pub(super) struct TradeReplyInput<'a> {
+    pub(super) day: u64,
+    pub(super) time_of_day: f32,
+    pub(super) courier: NpcId,
+    pub(super) courier_name: &'a str,
+    pub(super) recipient: NpcId,
+    pub(super) recipient_name: &'a str,
+    pub(super) good: TradeGood,
+    pub(super) quantity: u32,
+    pub(super) decision: TradeDecision,
+}
+
+/// Queues the recipient's short answer to a courier's offer ("Gladly!"), unless either is
+/// asleep or the recipient's chatter budget is spent. Like trade chatter, it goes stale
+/// two in-game hours after the offer.
+pub(super) fn queue_trade_reply(
+    queue: &mut DialogueRequestQueue,
+    budgets: &mut ChatterBudgets,
+    sleepers: &SleepRoster,
+    input: TradeReplyInput<'_>,
+) {
+    let speaker = input.recipient;
+    if sleepers.is_asleep(speaker) || sleepers.is_asleep(input.courier) {
+        debug!("Skipping trade reply from {speaker}: asleep");
+        return;
+    }
+    if !budgets.has_remaining(speaker) {
+        debug!("Skipping trade reply from {speaker}: chatter budget spent");
+        return;
+    }
+    let goods = format_quantity(input.good.label(), input.quantity);
+    let queued = DialogueRequest::builder(speaker)
+        .speaker_name(input.recipient_name)
+        .target(input.courier)
+        .target_name(input.courier_name)
+        .topic(DialogueTopicHint::Trade)
+        .prompt(format!(
+            "{} answers {}'s offer of {goods}: \"{}\"",
+            input.recipient_name,
+            input.courier_name,
+            input.decision.line()
+        ))
+        .summary(format!(
+            "Day {}: {} offered {goods} to {}.",
+            input.day, input.courier_name, input.recipient_name
+        ))
+        .trade_event(TradeContext {
+            day: input.day,
+            time_of_day: None,
+            from: Some(input.courier),
+            to: Some(speaker),
+            descriptor: TradeDescriptor::new(input.good.label(), input.quantity),
+            reason: TradeContextReason::Exchange,
+        })
+        .expires_after(
+            ContextClock::new(input.day, input.time_of_day),
+            TRADE_CHATTER_LIFETIME_DAYS,
+        )
+        .enqueue(queue);
+    match queued {
+        Ok(id) => {
+            budgets.spend(speaker);
+            debug!("Queued trade reply ({id}) from {speaker}");
+        }
+        Err(error) => warn!("Trade reply from {speaker} not queued: {error}"),
+    }
+}
+
+/// A heavily loaded courier and what they are hauling.
+pub(super) struct LoadGrumbleInput<'a> {
+    pub(super) now: ContextClock,
+    pub(super) speaker: NpcId,
+    pub(super) speaker_name: &'a str,
+    pub(super) good: TradeGood,
+    pub(super) quantity: u32,
+}
+
+/// Queues a courier's grumble about the weight they carry, unless they are asleep or
+/// their chatter budget is spent. Returns whether it was queued. A grumble not voiced
+/// within the hour is dropped; by then the load may be delivered.
+pub(super) fn queue_load_grumble(
+    queue: &mut DialogueRequestQueue,
+    budgets: &mut ChatterBudgets,
+    sleepers: &SleepRoster,
+    input: LoadGrumbleInput<'_>,
+) -> bool {
+    let speaker = input.speaker;
+    if sleepers.is_asleep(speaker) {
+        debug!("Skipping load grumble from {speaker}: asleep");
+        return false;
+    }
+    if !budgets.has_remaining(speaker) {
+        debug!("Skipping load grumble from {speaker}: chatter budget spent");
+        return false;
+    }
+    let goods = format_quantity(input.good.label(), input.quantity);
+    let queued = DialogueRequest::builder(speaker)
+        .speaker_name(input.speaker_name)
+        .topic(DialogueTopicHint::Status)
+        .prompt(format!(
+            "{} grumbles about hauling {goods}{SENTENCE_SUFFIX}",
+            input.speaker_name
+        ))
+        .summary(format!(
+            "Day {}: {} is weighed down by {goods}.",
+            input.now.day, input.speaker_name
+        ))
+        .expires_after(input.now, LOAD_GRUMBLE_LIFETIME_DAYS)
+        .enqueue(queue);
+    match queued {
+        Ok(id) => {
+            budgets.spend(speaker);
+            debug!("Queued load grumble ({id}) from {speaker}");
+            true
+        }
+        Err(error) => {
+            warn!("Load grumble from {speaker} not queued: {error}");
+            false
+        }
+    }
 }
 
+/// Records the trade and voices it between the two NPCs. NPC-to-NPC chatter is skipped
+/// once the speaker's daily budget is spent or either NPC is asleep, and dropped from the
+/// queue if still waiting two in-game hours later; lines involving the player are exempt.
 pub(super) fn send_trade_and_dialogue(
     trade_writer: &mut MessageWriter<TradeCompletedEvent>,
-    dialogue_requested_writer: &mut MessageWriter<DialogueRequestedEvent>,
     queue: &mut DialogueRequestQueue,
+    chatter: &mut PairChatterCooldown,
+    budgets: &mut ChatterBudgets,
+    sleepers: &SleepRoster,
     input: TradeDialogueInput,
 ) {
     trade_writer.write(TradeCompletedEvent {
@@ -72,33 +234,61 @@ pub(super) fn send_trade_and_dialogue(
     });
 
     if let (Some(speaker), Some(target)) = (input.from, input.to) {
-        let descriptor = TradeDescriptor::new(input.good.label(), input.quantity);
-        let mut context =
-            DialogueContext::with_events(vec![DialogueContextEvent::Trade(TradeContext {
+        let budgeted = !speaker.is_player() && !target.is_player();
+        if budgeted && (sleepers.is_asleep(speaker) || sleepers.is_asleep(target)) {
+            debug!("Skipping trade chatter between {speaker} and {target}: asleep");
+            return;
+        }
+        if budgeted && !budgets.has_remaining(speaker) {
+            debug!("Skipping trade chatter from {speaker}: chatter budget spent");
+            return;
+        }
+        let mut builder = DialogueRequest::builder(speaker)
+            .target(target)
+            .topic(DialogueTopicHint::Trade)
+            .prompt(build_trade_prompt(&input))
+            .summary(build_trade_summary(&input))
+            .trade_event(TradeContext {
                 day: input.day,
+                time_of_day: Some(input.time_of_day),
                 from: input.from,
                 to: input.to,
-                descriptor,
+                descriptor: TradeDescriptor::new(input.good.label(), input.quantity),
                 reason: input.reason.into(),
-            })]);
-        context.summary = Some(build_trade_summary(&input));
-        let prompt = build_trade_prompt(speaker, input.good.label());
-        let request = DialogueRequest::new(
-            speaker,
-            Some(target),
-            prompt,
-            DialogueTopicHint::Trade,
-            context,
+            })
+            .expires_after(
+                ContextClock::new(input.day, input.time_of_day),
+                TRADE_CHATTER_LIFETIME_DAYS,
+            );
+        if let Some(name) = &input.from_name {
+            builder = builder.speaker_name(name.clone());
+        }
+        if let Some(name) = &input.to_name {
+            builder = builder.target_name(name.clone());
+        }
+        let queued = builder.enqueue_with_cooldown(
+            queue,
+            chatter,
+            ChatterStamp::new(input.day, input.time_of_day),
         );
-        let id = queue.enqueue(request);
-        debug!("Queued dialogue request {} for trade", id.value());
-
-        // Emit event for conversation behavior coordination
-        dialogue_requested_writer.write(DialogueRequestedEvent {
-            request_id: id,
-            speaker,
-            target: Some(target),
-        });
+        let id = match queued {
+            Ok(Some(id)) => id,
+            Ok(None) => {
+                debug!(
+                    "Skipping trade chatter between {} and {}: pair cooldown active",
+                    speaker, target
+                );
+                return;
+            }
+            Err(error) => {
+                warn!("Trade dialogue between {speaker} and {target} not queued: {error}");
+                return;
+            }
+        };
+        if budgeted {
+            budgets.spend(speaker);
+        }
+        debug!("Queued trade dialogue ({id})");
     }
 }
 
@@ -108,16 +298,20 @@ impl From<TradeReason> for TradeContextReason {
             TradeReason::Production => TradeContextReason::Production,
             TradeReason::Processing => TradeContextReason::Processing,
             TradeReason::Exchange => TradeContextReason::Exchange,
+            TradeReason::PlayerTransfer => TradeContextReason::PlayerTransfer,
+            TradeReason::Storage => TradeContextReason::Storage,
         }
     }
 }
 
-fn build_trade_prompt(speaker: NpcId, good_label: &str) -> String {
+/// "Alric discusses exchanging 2 grain crates.", naming the sender by id only when no
+/// display name was passed in.
+fn build_trade_prompt(input: &TradeDialogueInput) -> String {
     format!(
-        "{speaker} {verb} {good}{suffix}",
-        speaker = speaker,
+        "{speaker} {verb} {goods}{suffix}",
+        speaker = participant(input.from, input.from_name.as_deref()),
         verb = TRADE_PROMPT_VERB,
-        good = good_label,
+        goods = format_quantity(input.good.label(), input.quantity),
         suffix = SENTENCE_SUFFIX
     )
 }
@@ -127,26 +321,129 @@ fn build_trade_summary(input: &TradeDialogueInput) -> String {
         TradeReason::Production => "produced",
         TradeReason::Processing => "processed",
         TradeReason::Exchange => "exchanged",
+        TradeReason::PlayerTransfer => "handed over",
+        TradeReason::Storage => "stored",
     };
 
+    let goods = format_quantity(input.good.label(), input.quantity);
+    let when = spoken_time(input.time_of_day);
+    let from = participant(input.from, input.from_name.as_deref());
     match (input.from, input.to) {
-        (Some(from), Some(to)) => format!(
-            "Day {}: {} {} {} for {}.",
+        (Some(_), Some(to)) => format!(
+            "Day {}, {}: {} {} {} for {}.",
             input.day,
+            when,
             from,
             reason,
-            input.good.label(),
-            to
+            goods,
+            participant(Some(to), input.to_name.as_deref())
         ),
         _ => format!(
-            "Day {}: {} {} {}.",
-            input.day,
-            input
-                .from
-                .map(|npc| npc.to_string())
-                .unwrap_or_else(|| "An NPC".to_string()),
-            reason,
-            input.good.label()
+            "Day {}, {}: {} {} {}.",
+            input.day, when, from, reason, goods
         ),
     }
 }
+
+/// `name` when given, the player's display name, the id form, or "An NPC" for nobody.
+fn participant(npc: Option<NpcId>, name: Option<&str>) -> String {
+    match (name, npc) {
+        (Some(name), _) => name.to_string(),
+        (None, Some(npc)) if npc.is_player() => PLAYER_DISPLAY_NAME.to_string(),
+        (None, Some(npc)) => npc.to_string(),
+        (None, None) => UNKNOWN_PARTICIPANT.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(from_name: Option<&str>, to_name: Option<&str>) -> TradeDialogueInput {
+        TradeDialogueInput {
+            day: 2,
+            time_of_day: 0.5,
+            from: Some(NpcId::new(1)),
+            to: Some(NpcId::new(2)),
+            from_name: from_name.map(str::to_string),
+            to_name: to_name.map(str::to_string),
+            good: TradeGood::Grain,
+            quantity: 2,
+            reason: TradeReason::Exchange,
+        }
+    }
+
+    #[test]
+    fn trade_lines_use_display_names_when_known() {
+        let named = input(Some("Alric"), Some("Bryn"));
+        let prompt = build_trade_prompt(&named);
+        let summary = build_trade_summary(&named);
+        assert!(
+            prompt.starts_with("Alric discusses exchanging 2 grain"),
+            "{prompt}"
+        );
+        assert!(
+            summary.ends_with("Alric exchanged 2 grain crates for Bryn."),
+            "{summary}"
+        );
+        assert!(!prompt.contains("NPC-") && !summary.contains("NPC-"));
+    }
+
+    #[test]
+    fn trade_lines_fall_back_to_ids_without_names() {
+        let bare = input(None, None);
+        assert!(build_trade_prompt(&bare).starts_with("NPC-0001 discusses exchanging"));
+        assert!(
+            build_trade_summary(&bare).ends_with("NPC-0001 exchanged 2 grain crates for NPC-0002.")
+        );
+
+        let from_player = TradeDialogueInput {
+            from: Some(NpcId::player()),
+            to: None,
+            ..bare
+        };
+        assert!(build_trade_summary(&from_player).ends_with("Player exchanged 2 grain crates."));
+    }
+
+    #[test]
+    fn briefs_expire_at_day_end_and_trade_replies_two_hours_on() {
+        let mut queue = DialogueRequestQueue::default();
+        let mut budgets = ChatterBudgets::default();
+        let sleepers = SleepRoster::default();
+        queue_schedule_brief(
+            &mut queue,
+            &mut budgets,
+            &sleepers,
+            4,
+            NpcId::new(1),
+            "Alric",
+            "Mill after lunch".to_string(),
+        );
+        let brief = queue.iter_pending().last().unwrap().id;
+        queue_trade_reply(
+            &mut queue,
+            &mut budgets,
+            &sleepers,
+            TradeReplyInput {
+                day: 4,
+                time_of_day: 0.95,
+                courier: NpcId::new(1),
+                courier_name: "Alric",
+                recipient: NpcId::new(2),
+                recipient_name: "Bryn",
+                good: TradeGood::Grain,
+                quantity: 1,
+                decision: TradeDecision::Accept,
+            },
+        );
+        let reply = queue.iter_pending().last().unwrap().id;
+
+        let mut deadline = |id| queue.pending_mut(id).unwrap().expires_at.unwrap();
+        assert_eq!(deadline(brief), ContextClock::new(5, 0.0));
+        let reply_deadline = deadline(reply);
+        assert_eq!(reply_deadline.day, 5, "late replies expire after midnight");
+        assert!(
+            (reply_deadline.time_of_day - (0.95 + TRADE_CHATTER_LIFETIME_DAYS - 1.0)).abs() < 1e-5
+        );
+    }
+}
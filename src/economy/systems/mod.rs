@@ -1,10 +1,24 @@
 //! Systems powering the config-driven economy planner.
 
+pub mod carrying;
 pub mod day_prep;
 pub mod dialogue;
+pub mod placeholders;
 pub mod spawning;
+pub mod spoilage;
+pub mod storage;
 pub mod task_execution;
+#[cfg(test)]
+pub(crate) mod test_support;
 
-pub use day_prep::prepare_economy_day;
-pub use spawning::{assign_placeholder_professions, spawn_profession_crates};
-pub use task_execution::advance_actor_tasks;
+pub use carrying::{animate_carried_goods, apply_encumbrance, sync_carried_goods};
+pub use day_prep::{
+    apply_pending_economy_reload, prepare_economy_day, reload_economy_config, reset_chatter_budgets,
+};
+pub use placeholders::sync_trade_good_placeholders;
+pub use spawning::{
+    assign_crate_owners, assign_placeholder_professions, forget_despawned_crates,
+    spawn_marketplace, spawn_profession_crates,
+};
+pub use spoilage::spoil_expired_goods;
+pub use task_execution::{advance_actor_tasks, refresh_economy_actor_cache};
@@ -0,0 +1,549 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::{
+    npc::{components::NpcId, spatial::NpcIndex},
+    ui::world_label::world_label,
+};
+
+use super::super::{
+    components::{Profession, TradeGood, TradeGoodPlaceholder},
+    events::InventoryChangedEvent,
+    resources::{
+        CarriedGoodsRegistry, PlaceholderStackConfig, ProfessionCrateRegistry,
+        TradeGoodPlaceholderRegistry, TradeGoodPlaceholderVisuals, PLACEHOLDER_SIZE,
+    },
+};
+
+const GRAIN_PLACEHOLDER_OFFSET: Vec3 = Vec3::new(0.35, 0.55, 0.0);
+const FLOUR_PLACEHOLDER_OFFSET: Vec3 = Vec3::new(-0.35, 0.55, 0.0);
+const TOOLS_PLACEHOLDER_OFFSET: Vec3 = Vec3::new(0.0, 0.6, 0.35);
+const ALE_PLACEHOLDER_OFFSET: Vec3 = Vec3::new(0.0, 0.6, -0.35);
+const STACK_STEP: f32 = PLACEHOLDER_SIZE * 1.05;
+/// Alternating sideways nudge so stacked cubes read as hand-piled rather than a column.
+const STACK_STAGGER: f32 = 0.03;
+const OVERFLOW_TOP_SCALE: f32 = 1.2;
+const COUNT_LABEL_GAP: f32 = 0.3;
+const COUNT_LABEL_FONT_SIZE: f32 = 14.0;
+
+/// How many cubes to show for a stock level, and how many units sit above the cap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StackLayout {
+    pub cubes: usize,
+    pub overflow: u32,
+}
+
+/// One cube per unit up to `max_visible` (at least one); the rest count as overflow.
+pub fn stack_layout(quantity: u32, max_visible: usize) -> StackLayout {
+    let cap = max_visible.max(1);
+    let cubes = (quantity as usize).min(cap);
+    StackLayout {
+        cubes,
+        overflow: quantity - cubes as u32,
+    }
+}
+
+/// Grows or shrinks crate-side placeholder stacks as inventory quantities change. Units a
+/// courier is carrying away (`CarriedGoodsRegistry`) are left off their crate's stack until
+/// the delivery completes or is abandoned, so the load is never drawn twice.
+#[allow(clippy::too_many_arguments)]
+pub fn sync_trade_good_placeholders(
+    mut commands: Commands,
+    mut events: MessageReader<InventoryChangedEvent>,
+    mut placeholders: ResMut<TradeGoodPlaceholderRegistry>,
+    crate_registry: Res<ProfessionCrateRegistry>,
+    visuals: Res<TradeGoodPlaceholderVisuals>,
+    config: Res<PlaceholderStackConfig>,
+    carriers: Res<CarriedGoodsRegistry>,
+    npc_index: Res<NpcIndex>,
+    professions: Query<&Profession>,
+) {
+    let profession_of = |npc: NpcId| {
+        let entity = npc_index.entity(npc)?;
+        professions.get(entity).ok().copied()
+    };
+    let in_transit = goods_in_transit(&carriers, profession_of);
+    let carried = |profession: Profession, good: TradeGood| {
+        in_transit.get(&(profession, good)).copied().unwrap_or(0)
+    };
+
+    for event in events.read() {
+        let Some(profession) = profession_of(event.npc) else {
+            continue;
+        };
+
+        debug!(
+            "{} {} stock changed from {} to {} on day {}",
+            profession.label(),
+            event.good.label(),
+            event.previous_total(),
+            event.new_total,
+            event.day
+        );
+
+        if event.new_total == 0 {
+            despawn_trade_good_stack(&mut commands, &mut placeholders, profession, event.good);
+            continue;
+        }
+
+        let Some(crate_entity) = crate_registry.get(profession) else {
+            warn!(
+                "Skipping placeholder spawn: no crate registered for {}",
+                profession.label()
+            );
+            continue;
+        };
+
+        show_trade_good_stock(
+            &mut commands,
+            &mut placeholders,
+            &visuals,
+            &config,
+            crate_entity,
+            (profession, event.good),
+            event.new_total,
+            carried(profession, event.good),
+        );
+    }
+
+    if !carriers.is_changed() {
+        return;
+    }
+    for (profession, good, stock) in placeholders.stocked() {
+        let shown = stock.saturating_sub(carried(profession, good));
+        if placeholders
+            .stack(profession, good)
+            .is_some_and(|stack| stack.shown == shown)
+        {
+            continue;
+        }
+        let Some(crate_entity) = crate_registry.get(profession) else {
+            continue;
+        };
+        show_trade_good_stock(
+            &mut commands,
+            &mut placeholders,
+            &visuals,
+            &config,
+            crate_entity,
+            (profession, good),
+            stock,
+            carried(profession, good),
+        );
+    }
+}
+
+/// Units of each (profession, good) couriers of that profession are carrying away.
+fn goods_in_transit(
+    carriers: &CarriedGoodsRegistry,
+    profession_of: impl Fn(NpcId) -> Option<Profession>,
+) -> HashMap<(Profession, TradeGood), u32> {
+    let mut in_transit = HashMap::new();
+    for (courier, carried) in carriers.iter() {
+        let Some(profession) = profession_of(courier) else {
+            continue;
+        };
+        *in_transit.entry((profession, carried.good)).or_default() += carried.quantity;
+    }
+    in_transit
+}
+
+/// Sizes the stack and count label for `stock` units, less the `carried` ones in transit.
+#[allow(clippy::too_many_arguments)]
+fn show_trade_good_stock(
+    commands: &mut Commands,
+    placeholders: &mut TradeGoodPlaceholderRegistry,
+    visuals: &TradeGoodPlaceholderVisuals,
+    config: &PlaceholderStackConfig,
+    crate_entity: Entity,
+    (profession, good): (Profession, TradeGood),
+    stock: u32,
+    carried: u32,
+) {
+    let shown = stock.saturating_sub(carried);
+    let layout = stack_layout(shown, config.max_visible_stack);
+    resize_trade_good_stack(
+        commands,
+        placeholders,
+        visuals,
+        crate_entity,
+        profession,
+        good,
+        layout,
+    );
+    update_count_label(
+        commands,
+        placeholders,
+        crate_entity,
+        profession,
+        good,
+        shown,
+        layout,
+    );
+    placeholders.set_quantities(profession, good, stock, shown);
+}
+
+fn resize_trade_good_stack(
+    commands: &mut Commands,
+    placeholders: &mut TradeGoodPlaceholderRegistry,
+    visuals: &TradeGoodPlaceholderVisuals,
+    crate_entity: Entity,
+    profession: Profession,
+    good: TradeGood,
+    layout: StackLayout,
+) {
+    let current = placeholders
+        .stack(profession, good)
+        .map_or(0, |stack| stack.cubes.len());
+
+    for level in current..layout.cubes {
+        let entity = commands
+            .spawn((
+                Mesh3d(visuals.mesh()),
+                MeshMaterial3d(visuals.material(good)),
+                Transform::from_translation(stack_offset(good, level)),
+                TradeGoodPlaceholder { profession, good },
+                Name::new(format!(
+                    "{} {} #{}",
+                    profession.label(),
+                    good.label(),
+                    level + 1
+                )),
+            ))
+            .id();
+        commands.entity(crate_entity).add_child(entity);
+        placeholders.push_cube(profession, good, entity);
+    }
+
+    for _ in layout.cubes..current {
+        if let Some(entity) = placeholders.pop_cube(profession, good) {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    let Some(stack) = placeholders.stack(profession, good) else {
+        return;
+    };
+    for (level, entity) in stack.cubes.iter().enumerate() {
+        let top_scale = if level + 1 == layout.cubes && layout.overflow > 0 {
+            OVERFLOW_TOP_SCALE
+        } else {
+            1.0
+        };
+        commands.entity(*entity).insert(
+            Transform::from_translation(stack_offset(good, level))
+                .with_scale(Vec3::splat(top_scale)),
+        );
+    }
+}
+
+fn update_count_label(
+    commands: &mut Commands,
+    placeholders: &mut TradeGoodPlaceholderRegistry,
+    crate_entity: Entity,
+    profession: Profession,
+    good: TradeGood,
+    quantity: u32,
+    layout: StackLayout,
+) {
+    let label = (layout.overflow > 0).then(|| {
+        let mut position = stack_offset(good, layout.cubes.saturating_sub(1));
+        position.y += COUNT_LABEL_GAP;
+        commands
+            .spawn((
+                world_label(
+                    format!("x{quantity}"),
+                    COUNT_LABEL_FONT_SIZE,
+                    crate_entity,
+                    position,
+                ),
+                Name::new(format!("{} {} count", profession.label(), good.label())),
+            ))
+            .id()
+    });
+
+    if let Some(previous) = placeholders.replace_label(profession, good, label) {
+        commands.entity(previous).despawn();
+    }
+}
+
+fn despawn_trade_good_stack(
+    commands: &mut Commands,
+    placeholders: &mut TradeGoodPlaceholderRegistry,
+    profession: Profession,
+    good: TradeGood,
+) {
+    let Some(stack) = placeholders.take(profession, good) else {
+        return;
+    };
+    for entity in stack.cubes.into_iter().chain(stack.label) {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn stack_offset(good: TradeGood, level: usize) -> Vec3 {
+    let stagger = if level % 2 == 1 { STACK_STAGGER } else { 0.0 };
+    trade_good_offset(good) + Vec3::new(stagger, level as f32 * STACK_STEP, 0.0)
+}
+
+fn trade_good_offset(good: TradeGood) -> Vec3 {
+    match good {
+        TradeGood::Grain => GRAIN_PLACEHOLDER_OFFSET,
+        TradeGood::Flour => FLOUR_PLACEHOLDER_OFFSET,
+        TradeGood::Tools => TOOLS_PLACEHOLDER_OFFSET,
+        TradeGood::Ale => ALE_PLACEHOLDER_OFFSET,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_4;
+
+    use bevy::camera::RenderTargetInfo;
+
+    use crate::{
+        economy::resources::PlaceholderStack,
+        npc::{
+            components::{Identity, NpcId},
+            spatial::index_npcs,
+        },
+        ui::world_label::{place_world_labels, WorldLabel},
+        world::components::FlyCamera,
+    };
+
+    fn placeholder_app() -> (App, NpcId) {
+        let mut app = App::new();
+        app.init_resource::<Assets<Mesh>>()
+            .init_resource::<Assets<StandardMaterial>>()
+            .init_resource::<ProfessionCrateRegistry>()
+            .init_resource::<TradeGoodPlaceholderRegistry>()
+            .init_resource::<TradeGoodPlaceholderVisuals>()
+            .init_resource::<PlaceholderStackConfig>()
+            .init_resource::<CarriedGoodsRegistry>()
+            .init_resource::<NpcIndex>()
+            .add_message::<InventoryChangedEvent>()
+            .add_systems(Update, (index_npcs, sync_trade_good_placeholders).chain());
+
+        let crate_entity = app.world_mut().spawn(Transform::default()).id();
+        app.world_mut()
+            .resource_mut::<ProfessionCrateRegistry>()
+            .insert(Profession::Miller, crate_entity);
+
+        let npc = NpcId::new(4);
+        app.world_mut()
+            .spawn((Identity::new(npc, "Bryn", 30.0), Profession::Miller));
+        (app, npc)
+    }
+
+    fn changed(npc: NpcId, delta: i64, new_total: u32) -> InventoryChangedEvent {
+        InventoryChangedEvent {
+            npc,
+            good: TradeGood::Grain,
+            delta,
+            new_total,
+            day: 0,
+        }
+    }
+
+    fn crate_entity(app: &App) -> Entity {
+        app.world()
+            .resource::<ProfessionCrateRegistry>()
+            .get(Profession::Miller)
+            .unwrap()
+    }
+
+    fn stack(app: &App) -> Option<PlaceholderStack> {
+        app.world()
+            .resource::<TradeGoodPlaceholderRegistry>()
+            .stack(Profession::Miller, TradeGood::Grain)
+            .cloned()
+    }
+
+    #[test]
+    fn layout_caps_cubes_and_reports_overflow() {
+        assert_eq!(
+            stack_layout(0, 5),
+            StackLayout {
+                cubes: 0,
+                overflow: 0
+            }
+        );
+        assert_eq!(
+            stack_layout(3, 5),
+            StackLayout {
+                cubes: 3,
+                overflow: 0
+            }
+        );
+        assert_eq!(
+            stack_layout(5, 5),
+            StackLayout {
+                cubes: 5,
+                overflow: 0
+            }
+        );
+        assert_eq!(
+            stack_layout(40, 5),
+            StackLayout {
+                cubes: 5,
+                overflow: 35
+            }
+        );
+        assert_eq!(
+            stack_layout(2, 0),
+            StackLayout {
+                cubes: 1,
+                overflow: 1
+            },
+            "a zero cap still shows one cube"
+        );
+    }
+
+    #[test]
+    fn registry_tracks_partial_pushes_and_pops() {
+        let mut world = World::new();
+        let [a, b, label] = [(); 3].map(|_| world.spawn_empty().id());
+        let mut registry = TradeGoodPlaceholderRegistry::default();
+        let key = (Profession::Farmer, TradeGood::Grain);
+
+        registry.push_cube(key.0, key.1, a);
+        registry.push_cube(key.0, key.1, b);
+        assert_eq!(registry.replace_label(key.0, key.1, Some(label)), None);
+        assert_eq!(registry.pop_cube(key.0, key.1), Some(b));
+        assert_eq!(registry.stack(key.0, key.1).unwrap().cubes, vec![a]);
+        assert_eq!(registry.replace_label(key.0, key.1, None), Some(label));
+
+        let taken = registry.take(key.0, key.1).expect("stack present");
+        assert_eq!(taken.cubes, vec![a]);
+        assert!(registry.stack(key.0, key.1).is_none());
+        assert_eq!(registry.pop_cube(key.0, key.1), None);
+    }
+
+    #[test]
+    fn stack_grows_shrinks_and_despawns_with_quantity() {
+        let (mut app, npc) = placeholder_app();
+
+        app.world_mut().write_message(changed(npc, 2, 2));
+        app.update();
+        let first = stack(&app).expect("stack spawned");
+        assert_eq!(first.cubes.len(), 2);
+        assert_eq!(first.label, None);
+
+        app.world_mut().write_message(changed(npc, 6, 8));
+        app.update();
+        let full = stack(&app).unwrap();
+        assert_eq!(full.cubes.len(), 5);
+        assert_eq!(
+            &full.cubes[..2],
+            &first.cubes[..],
+            "existing cubes are kept"
+        );
+        let label = full.label.expect("overflow shows a count label");
+        assert_eq!(
+            app.world().get::<Text>(label).map(|text| text.0.as_str()),
+            Some("x8")
+        );
+        let pinned = app.world().get::<WorldLabel>(label).unwrap();
+        assert_eq!(pinned.anchor, crate_entity(&app));
+        assert!(app.world().get::<Node>(label).is_some());
+        let top = app.world().get::<Transform>(full.cubes[4]).unwrap();
+        assert_eq!(top.scale, Vec3::splat(OVERFLOW_TOP_SCALE));
+
+        app.world_mut().write_message(changed(npc, -5, 3));
+        app.update();
+        let shrunk = stack(&app).unwrap();
+        assert_eq!(shrunk.cubes, full.cubes[..3].to_vec());
+        assert_eq!(shrunk.label, None);
+        assert!(app.world().get_entity(label).is_err());
+        assert!(app.world().get_entity(full.cubes[4]).is_err());
+        let top = app.world().get::<Transform>(shrunk.cubes[2]).unwrap();
+        assert_eq!(top.scale, Vec3::ONE);
+
+        app.world_mut().write_message(changed(npc, -3, 0));
+        app.update();
+        assert!(stack(&app).is_none());
+        for cube in shrunk.cubes {
+            assert!(app.world().get_entity(cube).is_err());
+        }
+    }
+
+    #[test]
+    fn goods_in_transit_leave_the_crate_stack_until_delivered() {
+        let (mut app, npc) = placeholder_app();
+        app.world_mut().write_message(changed(npc, 3, 3));
+        app.update();
+        let cubes = |app: &App| stack(app).map_or(0, |stack| stack.cubes.len());
+        assert_eq!(cubes(&app), 3);
+
+        let load = app.world_mut().spawn_empty().id();
+        app.world_mut()
+            .resource_mut::<CarriedGoodsRegistry>()
+            .start(npc, TradeGood::Grain, 2, load);
+        app.update();
+        assert_eq!(cubes(&app), 1, "the courier's load is not drawn twice");
+        assert_eq!(stack(&app).unwrap().stock, 3);
+
+        app.world_mut()
+            .resource_mut::<CarriedGoodsRegistry>()
+            .finish(npc);
+        app.update();
+        assert_eq!(cubes(&app), 3, "an abandoned delivery returns to the crate");
+
+        app.world_mut()
+            .resource_mut::<CarriedGoodsRegistry>()
+            .start(npc, TradeGood::Grain, 3, load);
+        app.update();
+        let emptied = stack(&app).expect("the stock still has an entry");
+        assert!(emptied.cubes.is_empty());
+        assert_eq!(emptied.label, None);
+
+        app.world_mut()
+            .resource_mut::<CarriedGoodsRegistry>()
+            .finish(npc);
+        app.world_mut().write_message(changed(npc, -3, 0));
+        app.update();
+        assert!(stack(&app).is_none(), "a delivered load is gone for good");
+    }
+
+    #[test]
+    fn count_label_is_projected_to_a_visible_node() {
+        let (mut app, npc) = placeholder_app();
+        app.add_systems(
+            Update,
+            place_world_labels.after(sync_trade_good_placeholders),
+        );
+        let mut camera = Camera::default();
+        camera.computed.clip_from_view =
+            Mat4::perspective_infinite_reverse_rh(FRAC_PI_4, 800.0 / 600.0, 0.1);
+        camera.computed.target_info = Some(RenderTargetInfo {
+            physical_size: UVec2::new(800, 600),
+            scale_factor: 1.0,
+        });
+        app.world_mut().spawn((
+            FlyCamera::new(0.0, 0.0),
+            camera,
+            GlobalTransform::from(
+                Transform::from_xyz(0.0, 2.0, 6.0).looking_at(Vec3::ZERO, Vec3::Y),
+            ),
+        ));
+
+        app.world_mut().write_message(changed(npc, 8, 8));
+        app.update();
+        app.update();
+
+        let label = stack(&app).unwrap().label.expect("count label");
+        let world = app.world();
+        assert_eq!(
+            world.get::<Visibility>(label),
+            Some(&Visibility::Inherited),
+            "the crate is in view"
+        );
+        let node = world.get::<Node>(label).unwrap();
+        let (Val::Px(left), Val::Px(top)) = (node.left, node.top) else {
+            panic!("label placed in pixels");
+        };
+        assert!((0.0..800.0).contains(&left) && (0.0..300.0).contains(&top));
+    }
+}
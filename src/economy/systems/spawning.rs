@@ -1,20 +1,28 @@
-use bevy::{math::primitives::Cuboid, prelude::*};
+use bevy::{ecs::system::SystemParam, math::primitives::Cuboid, prelude::*};
 
-use crate::npc::components::Identity;
+use crate::{
+    npc::{components::Identity, spatial::NpcIndex},
+    world::collision::StaticCollider,
+};
 
 use super::super::{
-    components::{Inventory, Profession, ProfessionCrate},
-    resources::ProfessionCrateRegistry,
+    components::{CrateOwner, Inventory, Marketplace, Profession, ProfessionCrate},
+    data::EconomyRegistry,
+    resources::{EconomyActorCache, ProfessionCrateRegistry},
+    skills::Skill,
 };
 
 pub(super) const FARMER_NAME: &str = "Alric";
 pub(super) const MILLER_NAME: &str = "Bryn";
 pub(super) const BLACKSMITH_NAME: &str = "Cedric";
+pub(super) const INNKEEPER_NAME: &str = "Dunstan";
 
 const CRATE_MESH_DIMENSIONS: (f32, f32, f32) = (0.9, 0.6, 0.9);
 const CRATE_PERCEPTUAL_ROUGHNESS: f32 = 0.6;
 const CRATE_METALLIC: f32 = 0.1;
 const CRATE_HEIGHT: f32 = 0.25;
+const MARKET_STALL_DIMENSIONS: (f32, f32, f32) = (1.6, 0.5, 1.0);
+const MARKET_STALL_COLOR: (u8, u8, u8) = (196, 120, 72);
 
 #[derive(Clone, Copy)]
 struct ProfessionCrateSpec {
@@ -23,7 +31,7 @@ struct ProfessionCrateSpec {
     color: (u8, u8, u8),
 }
 
-const PROFESSION_CRATE_SPECS: [ProfessionCrateSpec; 3] = [
+const PROFESSION_CRATE_SPECS: [ProfessionCrateSpec; 4] = [
     ProfessionCrateSpec {
         profession: Profession::Farmer,
         translation: Vec3::new(8.0, CRATE_HEIGHT, 3.0),
@@ -39,6 +47,11 @@ const PROFESSION_CRATE_SPECS: [ProfessionCrateSpec; 3] = [
         translation: Vec3::new(-6.0, CRATE_HEIGHT, 1.5),
         color: (110, 110, 130),
     },
+    ProfessionCrateSpec {
+        profession: Profession::Innkeeper,
+        translation: Vec3::new(-3.5, CRATE_HEIGHT, 6.5),
+        color: (150, 95, 60),
+    },
 ];
 
 /// Spawns placeholder crate entities representing profession work spots.
@@ -71,6 +84,13 @@ pub fn spawn_profession_crates(
                 ProfessionCrate {
                     profession: spec.profession,
                 },
+                StaticCollider::new(
+                    Vec3::new(
+                        CRATE_MESH_DIMENSIONS.0,
+                        CRATE_MESH_DIMENSIONS.1,
+                        CRATE_MESH_DIMENSIONS.2,
+                    ) * 0.5,
+                ),
                 Name::new(format!("{} crate", spec.profession.label())),
             ))
             .id();
@@ -86,6 +106,118 @@ pub fn spawn_profession_crates(
     }
 }
 
+/// Drops despawned crates from `ProfessionCrateRegistry`, so a recycled entity id is never
+/// mistaken for a work spot.
+pub fn forget_despawned_crates(
+    mut removed: RemovedComponents<ProfessionCrate>,
+    mut registry: ResMut<ProfessionCrateRegistry>,
+) {
+    for entity in removed.read() {
+        if let Some(profession) = registry.remove_entity(entity) {
+            warn!(
+                "{} crate was despawned; deliveries to it will fail",
+                profession.label()
+            );
+        }
+    }
+}
+
+/// Gives every profession crate a `CrateOwner`: its owner stays while they still work the
+/// profession, otherwise the lowest-id worker takes over. A crate nobody works loses its
+/// owner.
+pub fn assign_crate_owners(
+    mut commands: Commands,
+    actors: Res<EconomyActorCache>,
+    crates: Query<(Entity, &ProfessionCrate, Option<&CrateOwner>)>,
+    added: Query<(), Added<ProfessionCrate>>,
+) {
+    if !actors.is_changed() && added.is_empty() {
+        return;
+    }
+    for (entity, profession_crate, owner) in &crates {
+        let keeps_working = |owner: &CrateOwner| {
+            actors
+                .get(owner.0)
+                .is_some_and(|actor| actor.profession == profession_crate.profession)
+        };
+        let assigned = match owner {
+            Some(owner) if keeps_working(owner) => continue,
+            _ => actors
+                .workers(profession_crate.profession)
+                .next()
+                .map(|actor| CrateOwner(actor.npc_id)),
+        };
+        match assigned {
+            Some(owner) => {
+                commands.entity(entity).insert(owner);
+            }
+            None if owner.is_some() => {
+                commands.entity(entity).remove::<CrateOwner>();
+            }
+            None => {}
+        }
+    }
+}
+
+/// Looks up the NPC who keeps a profession's crate.
+#[derive(SystemParam)]
+pub struct CrateOwners<'w, 's> {
+    crate_registry: Res<'w, ProfessionCrateRegistry>,
+    npc_index: Res<'w, NpcIndex>,
+    owners: Query<'w, 's, &'static CrateOwner>,
+}
+
+impl CrateOwners<'_, '_> {
+    /// Entity of the NPC keeping `profession`'s crate, if it has a living owner.
+    pub fn owner_of(&self, profession: Profession) -> Option<Entity> {
+        let crate_entity = self.crate_registry.get(profession)?;
+        let owner = self.owners.get(crate_entity).ok()?;
+        self.npc_index.entity(owner.0)
+    }
+}
+
+/// Spawns the market stall where exchange deliveries are handed over, at the configured
+/// `[marketplace] position`.
+pub fn spawn_marketplace(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    registry: Res<EconomyRegistry>,
+    existing: Query<(), With<Marketplace>>,
+) {
+    if !existing.is_empty() {
+        return;
+    }
+
+    let position = registry.marketplace_position();
+    let dimensions = Vec3::new(
+        MARKET_STALL_DIMENSIONS.0,
+        MARKET_STALL_DIMENSIONS.1,
+        MARKET_STALL_DIMENSIONS.2,
+    );
+    commands.spawn((
+        Mesh3d(meshes.add(Mesh::from(Cuboid::from_size(dimensions)))),
+        MeshMaterial3d(materials.add(StandardMaterial {
+            base_color: Color::srgb_u8(
+                MARKET_STALL_COLOR.0,
+                MARKET_STALL_COLOR.1,
+                MARKET_STALL_COLOR.2,
+            ),
+            perceptual_roughness: CRATE_PERCEPTUAL_ROUGHNESS,
+            metallic: CRATE_METALLIC,
+            ..default()
+        })),
+        Transform::from_translation(position),
+        Marketplace,
+        StaticCollider::new(dimensions * 0.5),
+        Name::new("Marketplace"),
+    ));
+    info!(
+        "Spawned marketplace at ({:.1}, {:.1}, {:.1})",
+        position.x, position.y, position.z
+    );
+}
+
 /// Assigns placeholder professions and empty inventories to debug NPCs.
 pub fn assign_placeholder_professions(
     mut commands: Commands,
@@ -96,6 +228,7 @@ pub fn assign_placeholder_professions(
             FARMER_NAME => Some(Profession::Farmer),
             MILLER_NAME => Some(Profession::Miller),
             BLACKSMITH_NAME => Some(Profession::Blacksmith),
+            INNKEEPER_NAME => Some(Profession::Innkeeper),
             _ => None,
         };
 
@@ -108,7 +241,64 @@ pub fn assign_placeholder_professions(
             );
             commands
                 .entity(entity)
-                .insert((profession, Inventory::default()));
+                .insert((profession, Inventory::default(), Skill::default()));
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{economy::systems::refresh_economy_actor_cache, npc::components::NpcId};
+
+    fn owner_of(app: &App, crate_entity: Entity) -> Option<NpcId> {
+        app.world()
+            .get::<CrateOwner>(crate_entity)
+            .map(|owner| owner.0)
+    }
+
+    #[test]
+    fn crates_keep_one_owner_among_several_workers() {
+        let mut app = App::new();
+        app.init_resource::<EconomyActorCache>().add_systems(
+            Update,
+            (refresh_economy_actor_cache, assign_crate_owners).chain(),
+        );
+        let farm_crate = app
+            .world_mut()
+            .spawn(ProfessionCrate {
+                profession: Profession::Farmer,
+            })
+            .id();
+        let spawn_farmer = |app: &mut App, id: u64| {
+            app.world_mut()
+                .spawn((
+                    Identity::new(NpcId::new(id), format!("Farmer {id}"), 30.0),
+                    Profession::Farmer,
+                ))
+                .id()
+        };
+        let second = spawn_farmer(&mut app, 2);
+        spawn_farmer(&mut app, 5);
+        app.update();
+        assert_eq!(owner_of(&app, farm_crate), Some(NpcId::new(2)));
+
+        spawn_farmer(&mut app, 1);
+        app.update();
+        assert_eq!(
+            owner_of(&app, farm_crate),
+            Some(NpcId::new(2)),
+            "the owner stays while still a farmer"
+        );
+
+        app.world_mut()
+            .entity_mut(second)
+            .insert(Profession::Miller);
+        app.update();
+        assert_eq!(
+            owner_of(&app, farm_crate),
+            Some(NpcId::new(1)),
+            "the lowest-id farmer takes over"
+        );
+    }
+}
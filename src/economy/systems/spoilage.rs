@@ -0,0 +1,263 @@
+//! Once-a-day spoilage of perishable goods held in NPC inventories and household storage.
+use bevy::prelude::*;
+
+use crate::{
+    core::format::format_quantity,
+    dialogue::{
+        chatter::ChatterBudgets,
+        queue::DialogueRequestQueue,
+        types::{DialogueRequest, DialogueTopicHint},
+    },
+    npc::{
+        components::Identity,
+        household::HouseholdStorage,
+        motivation::{state::MotivationReason, MotivationAdjustmentEvent, MotivationConfig},
+    },
+    world::time::DayChangedEvent,
+};
+
+use super::super::{
+    components::{Inventory, TradeGood},
+    data::EconomyRegistry,
+    events::{GoodsSpoiledEvent, InventoryChangedEvent},
+    reservations::ReservedStock,
+};
+
+/// Removes expired stock when a `DayChangedEvent` arrives, then has each NPC who lost goods take a
+/// small motivation hit and grumble about it. Runs after the day is planned, so stock
+/// reserved for today's recipes is kept. Placeholders follow the inventory events.
+/// Household storage spoils by the same shelf lives; nobody owns it, so it is only logged.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn spoil_expired_goods(
+    mut day_changes: MessageReader<DayChangedEvent>,
+    registry: Res<EconomyRegistry>,
+    config: Res<MotivationConfig>,
+    reserved: Res<ReservedStock>,
+    mut queue: ResMut<DialogueRequestQueue>,
+    mut budgets: ResMut<ChatterBudgets>,
+    mut spoiled_writer: MessageWriter<GoodsSpoiledEvent>,
+    mut inventory_writer: MessageWriter<InventoryChangedEvent>,
+    mut adjustments: MessageWriter<MotivationAdjustmentEvent>,
+    mut npcs: Query<(&Identity, &mut Inventory)>,
+    mut storages: Query<(&Name, &mut Inventory), (With<HouseholdStorage>, Without<Identity>)>,
+) {
+    let Some(day) = day_changes.read().last().map(|change| change.new_day) else {
+        return;
+    };
+
+    for (identity, mut inventory) in npcs.iter_mut() {
+        let mut spoiled = Vec::new();
+        for (good, shelf_life_days) in registry.perishable_goods() {
+            let Some(change) = inventory.remove_expired(
+                good,
+                shelf_life_days,
+                day,
+                reserved.reserved(identity.id, good),
+            ) else {
+                continue;
+            };
+            let quantity = change.delta.unsigned_abs() as u32;
+            spoiled_writer.write(GoodsSpoiledEvent {
+                npc: identity.id,
+                good,
+                quantity,
+                day,
+            });
+            inventory_writer.write(InventoryChangedEvent::from_change(identity.id, day, change));
+            spoiled.push((good, quantity));
+        }
+        if spoiled.is_empty() {
+            continue;
+        }
+
+        let units: u32 = spoiled.iter().map(|(_, quantity)| quantity).sum();
+        let penalty = config.spoilage.penalty_for(units);
+        if penalty > 0.0 {
+            adjustments.write(MotivationAdjustmentEvent::new(
+                identity.id,
+                -penalty,
+                MotivationReason::Spoilage,
+            ));
+        }
+        let description = describe_spoiled(&spoiled);
+        info!(
+            "{}'s {} spoiled on day {}",
+            identity.display_name, description, day
+        );
+        queue_spoilage_grumble(&mut queue, &mut budgets, identity, &description, day);
+    }
+
+    for (name, mut inventory) in storages.iter_mut() {
+        let spoiled: Vec<(TradeGood, u32)> = registry
+            .perishable_goods()
+            .filter_map(|(good, shelf_life_days)| {
+                let change = inventory.remove_expired(good, shelf_life_days, day, 0)?;
+                Some((good, change.delta.unsigned_abs() as u32))
+            })
+            .collect();
+        if !spoiled.is_empty() {
+            info!(
+                "{}: {} spoiled on day {}",
+                name,
+                describe_spoiled(&spoiled),
+                day
+            );
+        }
+    }
+}
+
+/// "3 grain crates and 1 flour crate" style list of what spoiled.
+pub fn describe_spoiled(spoiled: &[(TradeGood, u32)]) -> String {
+    let parts: Vec<String> = spoiled
+        .iter()
+        .map(|(good, quantity)| format_quantity(good.label(), *quantity))
+        .collect();
+    match parts.as_slice() {
+        [] => String::new(),
+        [only] => only.clone(),
+        [rest @ .., last] => format!("{} and {last}", rest.join(", ")),
+    }
+}
+
+/// Spoilage is checked at midnight, so the grumble skips the sleep check; it still spends
+/// the speaker's chatter budget.
+fn queue_spoilage_grumble(
+    queue: &mut DialogueRequestQueue,
+    budgets: &mut ChatterBudgets,
+    identity: &Identity,
+    description: &str,
+    day: u64,
+) {
+    let speaker = identity.id;
+    if !budgets.has_remaining(speaker) {
+        debug!("Skipping spoilage grumble for {speaker}: chatter budget spent");
+        return;
+    }
+    match DialogueRequest::builder(speaker)
+        .speaker_name(identity.display_name.clone())
+        .topic(DialogueTopicHint::Status)
+        .prompt(format!(
+            "{} grumbles that their {description} went bad before it could be used.",
+            identity.display_name
+        ))
+        .summary(format!("Day {day}: {description} spoiled"))
+        .enqueue(queue)
+    {
+        Ok(_) => budgets.spend(speaker),
+        Err(error) => warn!("Spoilage grumble for {speaker} not queued: {error}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        npc::components::NpcId,
+        world::time::{announce_day_change, WorldClock},
+    };
+
+    fn spoilage_app() -> App {
+        let mut app = App::new();
+        app.insert_resource(WorldClock::new())
+            .insert_resource(EconomyRegistry::fallback())
+            .insert_resource(MotivationConfig::load_or_default())
+            .init_resource::<DialogueRequestQueue>()
+            .init_resource::<ChatterBudgets>()
+            .init_resource::<ReservedStock>()
+            .add_message::<GoodsSpoiledEvent>()
+            .add_message::<InventoryChangedEvent>()
+            .add_message::<MotivationAdjustmentEvent>()
+            .add_message::<DayChangedEvent>()
+            .add_systems(Update, (announce_day_change, spoil_expired_goods).chain());
+        app
+    }
+
+    fn read<M: Message + Clone>(app: &App) -> Vec<M> {
+        let messages = app.world().resource::<Messages<M>>();
+        messages.get_cursor().read(messages).cloned().collect()
+    }
+
+    #[test]
+    fn expired_stock_is_removed_once_per_day_with_event_payloads() {
+        let mut app = spoilage_app();
+        let npc = NpcId::new(5);
+        let mut inventory = Inventory::default();
+        inventory.add_good_on(TradeGood::Grain, 3, 0);
+        inventory.add_good_on(TradeGood::Grain, 1, 2);
+        inventory.add_good_on(TradeGood::Flour, 2, 0);
+        inventory.add_good_on(TradeGood::Tools, 1, 0);
+        let entity = app
+            .world_mut()
+            .spawn((Identity::new(npc, "Maren", 31.0), inventory))
+            .id();
+        app.update();
+        assert!(read::<GoodsSpoiledEvent>(&app).is_empty());
+
+        // Fallback shelf lives: grain 4 days, flour 6 days, tools never.
+        app.world_mut().resource_mut::<WorldClock>().skip_days(4);
+        app.update();
+        app.update();
+
+        assert_eq!(
+            read::<GoodsSpoiledEvent>(&app),
+            [GoodsSpoiledEvent {
+                npc,
+                good: TradeGood::Grain,
+                quantity: 3,
+                day: 4,
+            }],
+            "only the day 0 grain expires, and only once"
+        );
+        let changes = read::<InventoryChangedEvent>(&app);
+        assert_eq!(changes.len(), 1);
+        assert_eq!((changes[0].delta, changes[0].new_total), (-3, 1));
+        let adjustments = read::<MotivationAdjustmentEvent>(&app);
+        assert_eq!(adjustments.len(), 1);
+        assert_eq!(adjustments[0].reason, MotivationReason::Spoilage);
+        assert!(adjustments[0].amount < 0.0);
+        let mut queue = app.world_mut().resource_mut::<DialogueRequestQueue>();
+        let grumble = queue.iter_pending().next().expect("grumble queued").id;
+        let grumble = queue.pending_mut(grumble).unwrap();
+        assert_eq!(grumble.speaker, npc);
+        assert!(grumble.prompt.starts_with("Maren grumbles"));
+        assert_eq!(grumble.speaker_name.as_deref(), Some("Maren"));
+
+        let inventory = app.world().get::<Inventory>(entity).unwrap();
+        assert_eq!(inventory.quantity_of(TradeGood::Grain), 1);
+        assert_eq!(inventory.quantity_of(TradeGood::Flour), 2);
+        assert_eq!(inventory.quantity_of(TradeGood::Tools), 1);
+    }
+
+    #[test]
+    fn household_storage_spoils_by_the_same_shelf_lives() {
+        let mut app = spoilage_app();
+        let mut stored = Inventory::default();
+        stored.add_good_on(TradeGood::Grain, 2, 0);
+        stored.add_good_on(TradeGood::Grain, 1, 3);
+        let storage = app
+            .world_mut()
+            .spawn((HouseholdStorage, Name::new("Miller storage"), stored))
+            .id();
+        app.update();
+
+        app.world_mut().resource_mut::<WorldClock>().skip_days(4);
+        app.update();
+
+        let stored = app.world().get::<Inventory>(storage).unwrap();
+        assert_eq!(
+            stored.stacks(TradeGood::Grain).collect::<Vec<_>>(),
+            [(3, 1)]
+        );
+        assert!(read::<GoodsSpoiledEvent>(&app).is_empty());
+        assert!(read::<MotivationAdjustmentEvent>(&app).is_empty());
+    }
+
+    #[test]
+    fn spoiled_goods_read_as_a_list() {
+        assert_eq!(describe_spoiled(&[(TradeGood::Grain, 3)]), "3 grain crates");
+        assert_eq!(
+            describe_spoiled(&[(TradeGood::Grain, 3), (TradeGood::Flour, 1)]),
+            "3 grain crates and 1 flour crate"
+        );
+    }
+}
@@ -0,0 +1,92 @@
+//! Stock arithmetic for household storage: what to deposit and what to withdraw.
+use super::super::{
+    components::{Inventory, TradeGood},
+    data::RecipeInput,
+};
+
+/// Goods held above `keep`, with the quantity that should move into household storage.
+pub fn surplus_above_keep(inventory: &Inventory, keep: u32) -> Vec<(TradeGood, u32)> {
+    TradeGood::ALL
+        .into_iter()
+        .filter_map(|good| {
+            let surplus = inventory.quantity_of(good).saturating_sub(keep);
+            (surplus > 0).then_some((good, surplus))
+        })
+        .collect()
+}
+
+/// Shortfall per recipe input that household storage must cover. Returns `None` when the
+/// crate and storage together still lack an input; an empty list means no withdrawal.
+pub fn withdrawal_for_inputs(
+    own: &Inventory,
+    storage: &Inventory,
+    inputs: &[RecipeInput],
+) -> Option<Vec<(TradeGood, u32)>> {
+    let mut withdrawals = Vec::new();
+    for input in inputs {
+        let missing = input.quantity.saturating_sub(own.quantity_of(input.good));
+        if missing == 0 {
+            continue;
+        }
+        if storage.quantity_of(input.good) < missing {
+            return None;
+        }
+        withdrawals.push((input.good, missing));
+    }
+    Some(withdrawals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inventory(goods: &[(TradeGood, u32)]) -> Inventory {
+        let mut inventory = Inventory::default();
+        for (good, quantity) in goods {
+            inventory.add_good(*good, *quantity);
+        }
+        inventory
+    }
+
+    #[test]
+    fn surplus_keeps_the_configured_amount_of_each_good() {
+        let stock = inventory(&[
+            (TradeGood::Tools, 3),
+            (TradeGood::Ale, 1),
+            (TradeGood::Grain, 2),
+        ]);
+
+        assert_eq!(
+            surplus_above_keep(&stock, 1),
+            vec![(TradeGood::Grain, 1), (TradeGood::Tools, 2)]
+        );
+        assert!(surplus_above_keep(&stock, 3).is_empty());
+        assert_eq!(surplus_above_keep(&stock, 0).len(), 3);
+    }
+
+    #[test]
+    fn withdrawal_covers_only_the_shortfall() {
+        let inputs = [RecipeInput {
+            good: TradeGood::Grain,
+            quantity: 3,
+        }];
+        let own = inventory(&[(TradeGood::Grain, 1)]);
+
+        assert_eq!(
+            withdrawal_for_inputs(&own, &inventory(&[(TradeGood::Grain, 5)]), &inputs),
+            Some(vec![(TradeGood::Grain, 2)])
+        );
+        assert_eq!(
+            withdrawal_for_inputs(&own, &inventory(&[(TradeGood::Grain, 1)]), &inputs),
+            None
+        );
+        assert_eq!(
+            withdrawal_for_inputs(
+                &inventory(&[(TradeGood::Grain, 3)]),
+                &Inventory::default(),
+                &inputs
+            ),
+            Some(Vec::new())
+        );
+    }
+}
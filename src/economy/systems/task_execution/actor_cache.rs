@@ -0,0 +1,76 @@
+use bevy::prelude::*;
+
+use crate::{
+    economy::{
+        components::Profession,
+        resources::{EconomyActor, EconomyActorCache},
+    },
+    npc::components::Identity,
+};
+
+/// Rebuilds `EconomyActorCache` when a profession or identity is added, changed, or removed.
+#[allow(clippy::type_complexity)]
+pub fn refresh_economy_actor_cache(
+    changed: Query<(), Or<(Changed<Identity>, Changed<Profession>)>>,
+    mut removed_professions: RemovedComponents<Profession>,
+    mut removed_identities: RemovedComponents<Identity>,
+    actors: Query<(Entity, &Identity, &Profession)>,
+    mut cache: ResMut<EconomyActorCache>,
+) {
+    let removed = removed_professions.read().count() + removed_identities.read().count() > 0;
+    if changed.is_empty() && !removed {
+        return;
+    }
+    cache.rebuild(
+        actors
+            .iter()
+            .map(|(entity, identity, profession)| EconomyActor {
+                entity,
+                npc_id: identity.id,
+                display_name: identity.display_name.clone(),
+                profession: *profession,
+            }),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::economy::{
+        components::Profession, resources::EconomyActorCache,
+        systems::test_support::headless_economy_app,
+    };
+
+    #[test]
+    fn actor_cache_follows_profession_changes() {
+        let (mut app, actors) = headless_economy_app();
+        app.update();
+        let workers = |app: &App, profession: Profession| -> Vec<Entity> {
+            app.world()
+                .resource::<EconomyActorCache>()
+                .workers(profession)
+                .map(|actor| actor.entity)
+                .collect()
+        };
+        assert_eq!(
+            workers(&app, Profession::Miller),
+            [actors[&Profession::Miller]]
+        );
+
+        app.world_mut()
+            .entity_mut(actors[&Profession::Miller])
+            .remove::<Profession>();
+        app.update();
+        assert!(workers(&app, Profession::Miller).is_empty());
+
+        app.world_mut()
+            .entity_mut(actors[&Profession::Farmer])
+            .insert(Profession::Miller);
+        app.update();
+        assert_eq!(
+            workers(&app, Profession::Miller),
+            [actors[&Profession::Farmer]]
+        );
+        assert!(workers(&app, Profession::Farmer).is_empty());
+    }
+}
@@ -0,0 +1,335 @@
+use bevy::{ecs::system::ParamSet, prelude::*};
+
+use crate::{
+    dialogue::{
+        chatter::{ChatterBudgets, PairChatterCooldown},
+        queue::DialogueRequestQueue,
+    },
+    economy::{
+        components::{Inventory, Profession, TradeGood},
+        events::{InventoryChangedEvent, TradeCompletedEvent, TradeReason},
+        market::MarketMeetings,
+        resources::{EconomyActor, EconomyActorCache},
+        systems::{
+            dialogue::{queue_schedule_brief, send_trade_and_dialogue, TradeDialogueInput},
+            spawning::{BLACKSMITH_NAME, MILLER_NAME},
+        },
+        tasks::ActorTaskQueues,
+    },
+    npc::{
+        components::{NpcId, NpcLocomotion},
+        sleep::SleepRoster,
+    },
+    world::collision::MoverCollider,
+};
+
+use super::{
+    forward_inventory_change,
+    market::attend_market_meeting,
+    movement::{ensure_actor_at_location, TaskLocation, TaskLocations},
+    negotiation::{negotiate_handoff, HandoffTerms, NegotiationAccess},
+    TaskResult,
+};
+
+#[allow(clippy::too_many_arguments)]
+pub(super) fn execute_deliver(
+    locations: &TaskLocations,
+    actors: &EconomyActorCache,
+    task_queues: &ActorTaskQueues,
+    actor: &EconomyActor,
+    target: Profession,
+    recipient: Option<NpcId>,
+    good: TradeGood,
+    quantity: u32,
+    reserved: u32,
+    day: u64,
+    time_of_day: f32,
+    locomotion_query: &mut Query<(&GlobalTransform, &mut NpcLocomotion, Option<&MoverCollider>)>,
+    inventory_queries: &mut ParamSet<(Query<&mut Inventory>, Query<&Inventory>)>,
+    trade_writer: &mut MessageWriter<TradeCompletedEvent>,
+    inventory_writer: &mut MessageWriter<InventoryChangedEvent>,
+    dialogue_queue: &mut DialogueRequestQueue,
+    chatter_cooldown: &mut PairChatterCooldown,
+    chatter_budgets: &mut ChatterBudgets,
+    sleepers: &SleepRoster,
+    meetings: &mut MarketMeetings,
+    terms: HandoffTerms,
+    negotiation: &mut NegotiationAccess,
+) -> TaskResult {
+    if locations.crate_registry.is_lost(target) {
+        warn!(
+            "{} drops a {} delivery: the {} crate is gone",
+            actor.display_name,
+            good.label(),
+            target.label()
+        );
+        return TaskResult::Completed;
+    }
+
+    let Some(target_actor) = delivery_recipient(actors, task_queues, target, recipient, good)
+    else {
+        warn!(
+            "{} attempted delivery to missing {}",
+            actor.display_name,
+            target.label()
+        );
+        return TaskResult::Completed;
+    };
+
+    // Nobody is called to the market before the goods are in hand. Units the courier's
+    // own recipes have reserved stay behind.
+    let stocked = inventory_queries
+        .p1()
+        .get(actor.entity)
+        .map_or(true, |inventory| {
+            inventory.available_unreserved(good, reserved) >= quantity
+        });
+    if !stocked {
+        ensure_actor_at_location(
+            actor.profession,
+            TaskLocation::Crate(actor.profession),
+            actor,
+            locations,
+            locomotion_query,
+        );
+        return TaskResult::InProgress;
+    }
+
+    meetings.arrange(actor.npc_id, target_actor.npc_id, day);
+    if !attend_market_meeting(
+        actor.npc_id,
+        actor,
+        actors,
+        locations,
+        meetings,
+        locomotion_query,
+    ) {
+        return TaskResult::InProgress;
+    }
+
+    // Inputs for the recipient's own recipes are taken without asking.
+    if terms.config.enabled && !task_queues.has_recipe_consuming(target_actor.npc_id, good) {
+        if let Some(result) = negotiate_handoff(
+            &terms,
+            actor,
+            target_actor,
+            target,
+            good,
+            quantity,
+            day,
+            time_of_day,
+            &inventory_queries.p1(),
+            dialogue_queue,
+            chatter_budgets,
+            sleepers,
+            meetings,
+            negotiation,
+        ) {
+            return result;
+        }
+    }
+
+    let stacks = {
+        let mut inventories = inventory_queries.p0();
+        let Ok(mut inventory) = inventories.get_mut(actor.entity) else {
+            warn!(
+                "{} is missing an inventory; cannot deliver goods",
+                actor.display_name
+            );
+            meetings.finish(actor.npc_id);
+            return TaskResult::Completed;
+        };
+
+        let Some((change, stacks)) = inventory.take_unreserved(good, quantity, reserved) else {
+            return TaskResult::InProgress;
+        };
+        forward_inventory_change(inventory_writer, actor.npc_id, day, Some(change));
+        stacks
+    };
+    meetings.finish(actor.npc_id);
+
+    {
+        let mut inventories = inventory_queries.p0();
+        if let Ok(mut target_inventory) = inventories.get_mut(target_actor.entity) {
+            let change = target_inventory.add_stacks(good, &stacks);
+            forward_inventory_change(inventory_writer, target_actor.npc_id, day, change);
+        } else {
+            warn!(
+                "{} is missing an inventory; delivery from {} discarded",
+                target_actor.display_name, actor.display_name
+            );
+        }
+    }
+
+    send_trade_and_dialogue(
+        trade_writer,
+        dialogue_queue,
+        chatter_cooldown,
+        chatter_budgets,
+        sleepers,
+        TradeDialogueInput {
+            day,
+            time_of_day,
+            from: Some(actor.npc_id),
+            to: Some(target_actor.npc_id),
+            from_name: Some(actor.display_name.clone()),
+            to_name: Some(target_actor.display_name.clone()),
+            good,
+            quantity,
+            reason: TradeReason::Exchange,
+        },
+    );
+
+    if target == Profession::Farmer && good == TradeGood::Tools {
+        queue_schedule_brief(
+            dialogue_queue,
+            chatter_budgets,
+            sleepers,
+            day,
+            target_actor.npc_id,
+            &target_actor.display_name,
+            format!(
+                "{} coordinated trades with {} and {}",
+                target_actor.display_name, MILLER_NAME, BLACKSMITH_NAME
+            ),
+        );
+    }
+
+    TaskResult::Completed
+}
+
+/// The planned `recipient` while they still work `target`, otherwise the `target` worker
+/// still waiting on the most `good` (lowest id on ties).
+fn delivery_recipient<'a>(
+    actors: &'a EconomyActorCache,
+    task_queues: &ActorTaskQueues,
+    target: Profession,
+    recipient: Option<NpcId>,
+    good: TradeGood,
+) -> Option<&'a EconomyActor> {
+    recipient
+        .and_then(|npc| actors.get(npc))
+        .filter(|actor| actor.profession == target)
+        .or_else(|| {
+            actors
+                .workers(target)
+                .rev()
+                .max_by_key(|actor| task_queues.awaited_quantity(actor.npc_id, good))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dialogue::events::DialogueRequestedEvent,
+        economy::{
+            components::{Inventory, Profession, ProfessionCrate, TradeGood},
+            events::TradeReason,
+            resources::ProfessionCrateRegistry,
+            systems::{
+                spawning::forget_despawned_crates,
+                task_execution::advance_actor_tasks,
+                test_support::{headless_economy_app, queue_only, run_until_idle, spawn_prop},
+            },
+            tasks::{ActorTask, ActorTaskQueues},
+        },
+        npc::{
+            components::Identity,
+            motivation::{MotivationConfig, NpcMotivation},
+        },
+    };
+
+    #[test]
+    fn delivery_to_a_despawned_crate_fails_instead_of_waiting() {
+        let (mut app, actors) = headless_economy_app();
+        app.add_systems(Update, forget_despawned_crates.before(advance_actor_tasks));
+        let farmer = actors[&Profession::Farmer];
+        let miller_crate = spawn_prop(
+            &mut app,
+            Vec3::new(-6.0, 0.25, 0.0),
+            ProfessionCrate {
+                profession: Profession::Miller,
+            },
+        );
+        app.world_mut()
+            .resource_mut::<ProfessionCrateRegistry>()
+            .insert(Profession::Miller, miller_crate);
+        app.world_mut()
+            .get_mut::<Inventory>(farmer)
+            .unwrap()
+            .add_good(TradeGood::Grain, 1);
+        let miller_id = app
+            .world()
+            .get::<Identity>(actors[&Profession::Miller])
+            .unwrap()
+            .id;
+        queue_only(
+            &mut app,
+            farmer,
+            vec![ActorTask::Deliver {
+                good: TradeGood::Grain,
+                quantity: 1,
+                target: Profession::Miller,
+                recipient: Some(miller_id),
+            }],
+        );
+        app.world_mut().despawn(miller_crate);
+
+        let trades = run_until_idle(&mut app);
+
+        let world = app.world();
+        assert!(world.resource::<ActorTaskQueues>().is_empty());
+        let registry = world.resource::<ProfessionCrateRegistry>();
+        assert_eq!(registry.get(Profession::Miller), None);
+        assert!(registry.is_lost(Profession::Miller));
+        assert!(!trades
+            .iter()
+            .any(|trade| trade.reason == TradeReason::Exchange));
+        assert_eq!(
+            world
+                .get::<Inventory>(farmer)
+                .unwrap()
+                .quantity_of(TradeGood::Grain),
+            1,
+            "the grain stays with the farmer"
+        );
+    }
+
+    /// Runs one headless day with every actor starting at `dopamine` and counts the
+    /// NPC-to-NPC dialogue requests it produced.
+    fn dialogue_requests_in_a_day(dopamine: f32) -> usize {
+        let (mut app, actors) = headless_economy_app();
+        let mut config = MotivationConfig::default();
+        config.defaults.start = dopamine;
+        for entity in actors.values() {
+            app.world_mut()
+                .entity_mut(*entity)
+                .insert(NpcMotivation::new(&config));
+        }
+        let mut cursor = app
+            .world()
+            .resource::<Messages<DialogueRequestedEvent>>()
+            .get_cursor();
+        let mut requests = 0;
+        for _ in 0..100 {
+            app.update();
+            let messages = app.world().resource::<Messages<DialogueRequestedEvent>>();
+            requests += cursor.read(messages).count();
+            if app.world().resource::<ActorTaskQueues>().is_empty() {
+                break;
+            }
+        }
+        requests
+    }
+
+    #[test]
+    fn depressed_villages_chatter_less_than_energised_ones() {
+        let energised = dialogue_requests_in_a_day(95.0);
+        let depressed = dialogue_requests_in_a_day(5.0);
+        assert!(
+            depressed < energised,
+            "depressed NPCs queued {depressed} requests, energised ones {energised}"
+        );
+    }
+}
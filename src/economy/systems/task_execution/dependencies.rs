@@ -0,0 +1,137 @@
+use bevy::prelude::*;
+
+use crate::{
+    economy::{
+        components::{Inventory, Profession, TradeGood},
+        dependency::{DependencyCategory, EconomyDependencyMatrix},
+        events::ProfessionDependencyUpdateEvent,
+    },
+    npc::components::Identity,
+};
+
+use super::params::HouseholdAccess;
+
+#[allow(clippy::too_many_arguments)]
+pub(super) fn emit_dependency_updates(
+    day: u64,
+    matrix: &EconomyDependencyMatrix,
+    writer: &mut MessageWriter<ProfessionDependencyUpdateEvent>,
+    identity_query: &Query<(Entity, &Identity, &Profession)>,
+    inventories: &Query<&Inventory>,
+    households: &HouseholdAccess,
+) {
+    for (entity, identity, profession) in identity_query.iter() {
+        let Ok(inventory) = inventories.get(entity) else {
+            warn!(
+                "{} missing inventory; skipping dependency update",
+                identity.display_name
+            );
+            continue;
+        };
+
+        let storage = households
+            .household_of(entity)
+            .and_then(|household| inventories.get(household.storage).ok());
+        let stocks: Vec<&Inventory> = std::iter::once(inventory).chain(storage).collect();
+
+        let mut satisfied = Vec::new();
+        let mut missing = Vec::new();
+        for category in matrix.requirements(*profession) {
+            if category_satisfied(matrix, *category, &stocks) {
+                satisfied.push(*category);
+            } else {
+                missing.push(*category);
+            }
+        }
+
+        writer.write(ProfessionDependencyUpdateEvent {
+            day,
+            npc: identity.id,
+            profession: *profession,
+            satisfied_categories: satisfied,
+            missing_categories: missing,
+        });
+    }
+}
+
+/// True when any of `stocks` holds a good that counts toward `category`.
+pub(super) fn category_satisfied(
+    matrix: &EconomyDependencyMatrix,
+    category: DependencyCategory,
+    stocks: &[&Inventory],
+) -> bool {
+    TradeGood::ALL.iter().any(|good| {
+        matrix.categories_for_good(*good).contains(&category)
+            && stocks.iter().any(|stock| stock.quantity_of(*good) > 0)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dialogue::queue::DialogueRequestQueue,
+        economy::{
+            components::{Profession, TradeGood},
+            data::EconomyRegistry,
+            events::{EconomyEventKind, EconomyEventOccurred, ProfessionDependencyUpdateEvent},
+            systems::test_support::{headless_economy_app, run_until_idle},
+            tasks::ActorTaskQueues,
+        },
+        npc::components::Identity,
+    };
+
+    #[test]
+    fn failed_harvest_day_completes_without_deadlock() {
+        let (mut app, actors) = headless_economy_app();
+        let config = std::fs::read_to_string("config/economy.toml").expect("economy config");
+        let config: crate::economy::data::EconomyConfig = toml::from_str(&format!(
+            "{config}\n[[scarcity_events]]\nprofession = \"farmer\"\nprobability = 1.0\ndescription = \"The harvest failed\""
+        ))
+        .expect("valid config");
+        app.insert_resource(EconomyRegistry::from_config(config).expect("valid registry"));
+        let mut dependency_cursor = app
+            .world()
+            .resource::<Messages<ProfessionDependencyUpdateEvent>>()
+            .get_cursor();
+
+        let trades = run_until_idle(&mut app);
+        app.update();
+
+        let world = app.world();
+        assert!(world.resource::<ActorTaskQueues>().is_empty());
+        assert!(
+            trades.iter().all(|trade| trade.good != TradeGood::Grain),
+            "no grain harvested or delivered"
+        );
+
+        let messages = world.resource::<Messages<EconomyEventOccurred>>();
+        let events: Vec<_> = messages.get_cursor().read(messages).cloned().collect();
+        assert_eq!(
+            events,
+            vec![EconomyEventOccurred {
+                kind: EconomyEventKind::Scarcity {
+                    profession: Profession::Farmer
+                },
+                day: 0,
+            }]
+        );
+
+        let farmer = world
+            .get::<Identity>(actors[&Profession::Farmer])
+            .unwrap()
+            .id;
+        assert!(world
+            .resource::<DialogueRequestQueue>()
+            .iter_pending()
+            .any(|request| request.speaker == farmer
+                && request.topic == crate::dialogue::types::DialogueTopicHint::Schedule));
+
+        let updates = world.resource::<Messages<ProfessionDependencyUpdateEvent>>();
+        assert_eq!(
+            dependency_cursor.read(updates).count(),
+            Profession::ALL.len(),
+            "day still closes with dependency snapshots"
+        );
+    }
+}
@@ -0,0 +1,162 @@
+use bevy::{ecs::system::ParamSet, prelude::*};
+
+use crate::{
+    economy::{
+        components::{Inventory, Profession, TradeGood},
+        data::EconomyRegistry,
+        dependency::EconomyDependencyMatrix,
+        resources::{EconomyActor, EconomyActorCache},
+        skills::Skill,
+        tasks::{ActorTask, ActorTaskQueues},
+    },
+    npc::components::{NpcId, NpcLocomotion},
+    world::collision::MoverCollider,
+};
+
+use super::{
+    deliver::execute_deliver,
+    manufacture::{execute_manufacture, execute_wait_for_good},
+    market::attend_market_meeting,
+    movement::{ensure_actor_at_location, TaskLocation, TaskLocations},
+    negotiation::{HandoffTerms, NegotiationAccess},
+    params::{EconomyOutputs, HouseholdAccess},
+    surplus::execute_deposit_surplus,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum TaskResult {
+    Completed,
+    InProgress,
+    /// The recipient turned a negotiated delivery down; the courier keeps the goods.
+    Declined {
+        requester: Profession,
+        recipient: NpcId,
+        good: TradeGood,
+        quantity: u32,
+    },
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(super) fn execute_task(
+    registry: &EconomyRegistry,
+    dependency_matrix: &EconomyDependencyMatrix,
+    locations: &TaskLocations,
+    actors: &EconomyActorCache,
+    task_queues: &ActorTaskQueues,
+    households: &HouseholdAccess,
+    actor: &EconomyActor,
+    task: ActorTask,
+    day: u64,
+    time_of_day: f32,
+    season: Option<&str>,
+    locomotion_query: &mut Query<(&GlobalTransform, &mut NpcLocomotion, Option<&MoverCollider>)>,
+    inventory_queries: &mut ParamSet<(Query<&mut Inventory>, Query<&Inventory>)>,
+    skills: &mut Query<&mut Skill>,
+    outputs: &mut EconomyOutputs,
+    negotiation: &mut NegotiationAccess,
+) -> TaskResult {
+    let profession = actor.profession;
+    match task {
+        ActorTask::WaitForGood { good, quantity } => execute_wait_for_good(
+            locations,
+            profession,
+            actor,
+            good,
+            quantity,
+            locomotion_query,
+            inventory_queries,
+        ),
+        ActorTask::Manufacture { recipe } => execute_manufacture(
+            registry,
+            locations,
+            households,
+            profession,
+            actor,
+            &recipe,
+            day,
+            season,
+            locomotion_query,
+            inventory_queries,
+            skills,
+            &mut outputs.trade_writer,
+            &mut outputs.inventory_writer,
+            &mut outputs.skill_writer,
+        ),
+        ActorTask::Deliver {
+            good,
+            quantity,
+            target,
+            recipient,
+        } => execute_deliver(
+            locations,
+            actors,
+            task_queues,
+            actor,
+            target,
+            recipient,
+            good,
+            quantity,
+            outputs.reserved.reserved(actor.npc_id, good),
+            day,
+            time_of_day,
+            locomotion_query,
+            inventory_queries,
+            &mut outputs.trade_writer,
+            &mut outputs.inventory_writer,
+            outputs.dialogue_queue.as_mut(),
+            outputs.chatter_cooldown.as_mut(),
+            outputs.chatter_budgets.as_mut(),
+            &outputs.sleepers,
+            outputs.meetings.as_mut(),
+            HandoffTerms {
+                config: registry.negotiation(),
+                dependency_matrix,
+                households,
+            },
+            negotiation,
+        ),
+        ActorTask::DepositSurplus => execute_deposit_surplus(
+            households,
+            actor,
+            task_queues.has_delivery_for(profession),
+            &outputs.reserved,
+            day,
+            locomotion_query,
+            inventory_queries,
+            &mut outputs.trade_writer,
+            &mut outputs.inventory_writer,
+        ),
+        ActorTask::MeetAtMarket { courier } => {
+            if outputs
+                .meetings
+                .get(courier)
+                .is_none_or(|meeting| meeting.recipient != actor.npc_id)
+            {
+                // Handed over, or the courier's delivery was dropped.
+                return TaskResult::Completed;
+            }
+            attend_market_meeting(
+                courier,
+                actor,
+                actors,
+                locations,
+                outputs.meetings.as_mut(),
+                locomotion_query,
+            );
+            TaskResult::InProgress
+        }
+        ActorTask::ReturnToCrate => {
+            if ensure_actor_at_location(
+                profession,
+                TaskLocation::Crate(profession),
+                actor,
+                locations,
+                locomotion_query,
+            ) {
+                TaskResult::Completed
+            } else {
+                TaskResult::InProgress
+            }
+        }
+    }
+}
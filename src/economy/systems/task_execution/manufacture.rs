@@ -0,0 +1,380 @@
+use bevy::{ecs::system::ParamSet, prelude::*};
+
+use crate::{
+    economy::{
+        components::{Inventory, Profession, TradeGood},
+        data::{EconomyRegistry, Recipe},
+        events::{InventoryChangedEvent, SkillLevelUpEvent, TradeCompletedEvent, TradeReason},
+        resources::EconomyActor,
+        skills::{scaled_output, yield_multiplier, Skill},
+        systems::storage::withdrawal_for_inputs,
+    },
+    npc::components::NpcLocomotion,
+    world::collision::MoverCollider,
+};
+
+use super::{
+    forward_inventory_change,
+    movement::{ensure_actor_at_location, TaskLocation, TaskLocations},
+    params::HouseholdAccess,
+    TaskResult,
+};
+
+#[allow(clippy::too_many_arguments)]
+pub(super) fn execute_wait_for_good(
+    locations: &TaskLocations,
+    profession: Profession,
+    actor: &EconomyActor,
+    good: TradeGood,
+    quantity: u32,
+    locomotion_query: &mut Query<(&GlobalTransform, &mut NpcLocomotion, Option<&MoverCollider>)>,
+    inventory_queries: &mut ParamSet<(Query<&mut Inventory>, Query<&Inventory>)>,
+) -> TaskResult {
+    if !ensure_actor_at_location(
+        profession,
+        TaskLocation::Crate(profession),
+        actor,
+        locations,
+        locomotion_query,
+    ) {
+        return TaskResult::InProgress;
+    }
+
+    let inventories = inventory_queries.p1();
+    if let Ok(inventory) = inventories.get(actor.entity) {
+        if inventory.quantity_of(good) >= quantity {
+            TaskResult::Completed
+        } else {
+            TaskResult::InProgress
+        }
+    } else {
+        warn!(
+            "{} is missing an inventory; cannot wait for goods",
+            actor.display_name
+        );
+        TaskResult::Completed
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(super) fn execute_manufacture(
+    registry: &EconomyRegistry,
+    locations: &TaskLocations,
+    households: &HouseholdAccess,
+    profession: Profession,
+    actor: &EconomyActor,
+    recipe: &Recipe,
+    day: u64,
+    season: Option<&str>,
+    locomotion_query: &mut Query<(&GlobalTransform, &mut NpcLocomotion, Option<&MoverCollider>)>,
+    inventory_queries: &mut ParamSet<(Query<&mut Inventory>, Query<&Inventory>)>,
+    skills: &mut Query<&mut Skill>,
+    trade_writer: &mut MessageWriter<TradeCompletedEvent>,
+    inventory_writer: &mut MessageWriter<InventoryChangedEvent>,
+    skill_writer: &mut MessageWriter<SkillLevelUpEvent>,
+) -> TaskResult {
+    if !ensure_actor_at_location(
+        profession,
+        TaskLocation::Crate(profession),
+        actor,
+        locations,
+        locomotion_query,
+    ) {
+        return TaskResult::InProgress;
+    }
+
+    let storage = households
+        .household_of(actor.entity)
+        .map(|household| household.storage);
+    let withdrawals = {
+        let inventories = inventory_queries.p1();
+        let Ok(inventory) = inventories.get(actor.entity) else {
+            warn!(
+                "{} is missing an inventory; cannot manufacture goods",
+                actor.display_name
+            );
+            return TaskResult::Completed;
+        };
+        let empty = Inventory::default();
+        let stored = storage
+            .and_then(|entity| inventories.get(entity).ok())
+            .unwrap_or(&empty);
+        let Some(withdrawals) = withdrawal_for_inputs(inventory, stored, &recipe.consumes) else {
+            return TaskResult::InProgress;
+        };
+        withdrawals
+    };
+
+    let mut inventories = inventory_queries.p0();
+    if let Some(storage) = storage {
+        for (good, quantity) in withdrawals {
+            let Ok(mut stored) = inventories.get_mut(storage) else {
+                break;
+            };
+            let Some((_, stacks)) = stored.take_good(good, quantity) else {
+                continue;
+            };
+            if let Ok(mut inventory) = inventories.get_mut(actor.entity) {
+                let change = inventory.add_stacks(good, &stacks);
+                forward_inventory_change(inventory_writer, actor.npc_id, day, change);
+            }
+            trade_writer.write(TradeCompletedEvent {
+                day,
+                from: None,
+                to: Some(actor.npc_id),
+                good,
+                quantity,
+                reason: TradeReason::Storage,
+            });
+        }
+    }
+
+    let Ok(mut inventory) = inventories.get_mut(actor.entity) else {
+        warn!(
+            "{} is missing an inventory; cannot manufacture goods",
+            actor.display_name
+        );
+        return TaskResult::Completed;
+    };
+
+    for input in &recipe.consumes {
+        let change = inventory.remove_good(input.good, input.quantity);
+        forward_inventory_change(inventory_writer, actor.npc_id, day, change);
+    }
+
+    let curve = registry.skill_curve();
+    let mut skill = skills.get_mut(actor.entity).ok();
+    let multiplier = skill.as_ref().map_or(1.0, |skill| {
+        yield_multiplier(skill.level(profession, curve), curve)
+    });
+
+    for output in &recipe.produces {
+        // The season scales the base yield; a season that yields nothing makes nothing.
+        let base = recipe.seasonal_quantity(output.quantity, season);
+        if base == 0 {
+            continue;
+        }
+        let quantity = scaled_output(base, multiplier);
+        let change = inventory.add_good_on(output.good, quantity, day);
+        forward_inventory_change(inventory_writer, actor.npc_id, day, change);
+
+        let reason = if recipe.consumes.is_empty() {
+            TradeReason::Production
+        } else {
+            TradeReason::Processing
+        };
+
+        trade_writer.write(TradeCompletedEvent {
+            day,
+            from: Some(actor.npc_id),
+            to: Some(actor.npc_id),
+            good: output.good,
+            quantity,
+            reason,
+        });
+    }
+
+    if let Some(level) = skill
+        .as_mut()
+        .and_then(|skill| skill.gain(profession, recipe.xp, curve))
+    {
+        skill_writer.write(SkillLevelUpEvent {
+            npc: actor.npc_id,
+            profession,
+            level,
+            day,
+        });
+    }
+
+    TaskResult::Completed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        economy::{
+            components::{Inventory, Profession, TradeGood},
+            events::{TradeCompletedEvent, TradeReason},
+            reservations::ReservedStock,
+            systems::test_support::{
+                flour_milling, headless_economy_app, join_household, queue_only, run_until_idle,
+            },
+            tasks::{ActorTask, ActorTaskQueues},
+        },
+        npc::components::{Identity, NpcId},
+        player::inventory::{transfer_with_npc, CrateTransferDirection, PlayerInventory},
+    };
+
+    #[test]
+    fn reserved_grain_survives_a_greedy_player_and_the_flour_gets_milled() {
+        let (mut app, actors) = headless_economy_app();
+        let miller = actors[&Profession::Miller];
+        let miller_id = app.world().get::<Identity>(miller).unwrap().id;
+        let mut player = PlayerInventory::default();
+        let mut cursor = app
+            .world()
+            .resource::<Messages<TradeCompletedEvent>>()
+            .get_cursor();
+        let mut milled = 0;
+
+        for _ in 0..100 {
+            app.update();
+            let messages = app.world().resource::<Messages<TradeCompletedEvent>>();
+            milled += cursor
+                .read(messages)
+                .filter(|trade| {
+                    trade.good == TradeGood::Flour && trade.reason == TradeReason::Processing
+                })
+                .count();
+
+            // Between frames the player grabs every grain the miller's crate will give up.
+            let reserved = app
+                .world()
+                .resource::<ReservedStock>()
+                .reserved(miller_id, TradeGood::Grain);
+            let mut stock = app.world_mut().get_mut::<Inventory>(miller).unwrap();
+            let grabbable = stock.available_unreserved(TradeGood::Grain, reserved);
+            if grabbable > 0 {
+                transfer_with_npc(
+                    CrateTransferDirection::Take,
+                    miller_id,
+                    &mut stock,
+                    reserved,
+                    &mut player,
+                    TradeGood::Grain,
+                    grabbable,
+                    0,
+                );
+            }
+            assert!(transfer_with_npc(
+                CrateTransferDirection::Take,
+                miller_id,
+                &mut stock,
+                reserved,
+                &mut player,
+                TradeGood::Grain,
+                1,
+                0,
+            )
+            .is_none());
+
+            if app.world().resource::<ActorTaskQueues>().is_empty() {
+                break;
+            }
+        }
+
+        assert!(
+            app.world().resource::<ActorTaskQueues>().is_empty(),
+            "the miller's recipe must not stall on stolen grain"
+        );
+        assert!(milled > 0);
+        assert_eq!(
+            app.world()
+                .resource::<ReservedStock>()
+                .reserved(miller_id, TradeGood::Grain),
+            0,
+            "finished recipes release their claim"
+        );
+    }
+
+    #[test]
+    fn two_farmers_split_the_harvest_and_milling_still_completes() {
+        let (mut app, actors) = headless_economy_app();
+        let second_farmer = app
+            .world_mut()
+            .spawn((
+                Identity::new(NpcId::new(10), "Second farmer", 30.0),
+                Profession::Farmer,
+                Inventory::default(),
+            ))
+            .id();
+        let farmers = [actors[&Profession::Farmer], second_farmer]
+            .map(|entity| app.world().get::<Identity>(entity).unwrap().id);
+
+        let trades = run_until_idle(&mut app);
+
+        assert!(app.world().resource::<ActorTaskQueues>().is_empty());
+        let harvests = farmers.map(|farmer| {
+            trades
+                .iter()
+                .filter(|trade| {
+                    trade.good == TradeGood::Grain
+                        && trade.reason == TradeReason::Production
+                        && trade.from == Some(farmer)
+                })
+                .count()
+        });
+        assert!(harvests.iter().all(|count| *count > 0), "{harvests:?}");
+        assert!(harvests[0].abs_diff(harvests[1]) <= 1, "{harvests:?}");
+        assert!(
+            trades
+                .iter()
+                .any(|trade| trade.good == TradeGood::Flour
+                    && trade.reason == TradeReason::Processing)
+        );
+        assert!(trades.iter().any(|trade| trade.good == TradeGood::Tools
+            && trade.reason == TradeReason::Exchange
+            && farmers.iter().any(|farmer| trade.to == Some(*farmer))));
+    }
+
+    #[test]
+    fn manufacture_withdraws_missing_inputs_from_household_storage() {
+        let (mut app, actors) = headless_economy_app();
+        let miller = actors[&Profession::Miller];
+        let storage = join_household(&mut app, &[miller], &[(TradeGood::Grain, 2)]);
+        let recipe = flour_milling(&app);
+        queue_only(&mut app, miller, vec![ActorTask::Manufacture { recipe }]);
+
+        let trades = run_until_idle(&mut app);
+
+        let world = app.world();
+        assert_eq!(
+            world
+                .get::<Inventory>(miller)
+                .unwrap()
+                .quantity_of(TradeGood::Flour),
+            1
+        );
+        assert_eq!(
+            world
+                .get::<Inventory>(miller)
+                .unwrap()
+                .quantity_of(TradeGood::Grain),
+            0
+        );
+        assert_eq!(
+            world
+                .get::<Inventory>(storage)
+                .unwrap()
+                .quantity_of(TradeGood::Grain),
+            1
+        );
+        let miller_id = world.get::<Identity>(miller).unwrap().id;
+        assert!(trades
+            .iter()
+            .any(|trade| trade.reason == TradeReason::Storage
+                && trade.from.is_none()
+                && trade.to == Some(miller_id)
+                && trade.good == TradeGood::Grain
+                && trade.quantity == 1));
+    }
+
+    #[test]
+    fn manufacture_without_household_waits_for_inputs() {
+        let (mut app, actors) = headless_economy_app();
+        let miller = actors[&Profession::Miller];
+        let recipe = flour_milling(&app);
+        queue_only(&mut app, miller, vec![ActorTask::Manufacture { recipe }]);
+
+        run_until_idle(&mut app);
+
+        let miller_id = app.world().get::<Identity>(miller).unwrap().id;
+        assert_eq!(
+            app.world()
+                .resource::<ActorTaskQueues>()
+                .remaining_tasks(miller_id),
+            1
+        );
+    }
+}
@@ -0,0 +1,240 @@
+use bevy::prelude::*;
+
+use crate::{
+    economy::{
+        market::{MarketMeeting, MarketMeetings},
+        resources::{EconomyActor, EconomyActorCache},
+    },
+    npc::components::{NpcId, NpcLocomotion},
+    world::collision::MoverCollider,
+};
+
+use super::movement::{ensure_actor_at_location, TaskLocation, TaskLocations};
+
+/// Walks `actor`, either party of `courier`'s meeting, to the marketplace and records
+/// whether they are there. True once both parties are.
+pub(super) fn attend_market_meeting(
+    courier: NpcId,
+    actor: &EconomyActor,
+    actors: &EconomyActorCache,
+    locations: &TaskLocations,
+    meetings: &mut MarketMeetings,
+    locomotion_query: &mut Query<(&GlobalTransform, &mut NpcLocomotion, Option<&MoverCollider>)>,
+) -> bool {
+    let present = ensure_actor_at_location(
+        actor.profession,
+        TaskLocation::Marketplace,
+        actor,
+        locations,
+        locomotion_query,
+    );
+    if meetings.set_present(courier, actor.npc_id, present) {
+        if let Some(other) = meetings
+            .waiting_for(actor.npc_id)
+            .and_then(|npc| actors.get(npc))
+        {
+            info!(
+                "{} waits at the marketplace for {}",
+                actor.display_name, other.display_name
+            );
+        }
+    }
+    meetings
+        .get(courier)
+        .is_some_and(MarketMeeting::both_present)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{
+        core::plugin::SimulationClock,
+        economy::{
+            components::{Inventory, Marketplace, Profession, ProfessionCrate, TradeGood},
+            events::{TradeCompletedEvent, TradeReason},
+            market::MarketMeetings,
+            resources::ProfessionCrateRegistry,
+            systems::{
+                task_execution::advance_actor_tasks,
+                test_support::{headless_economy_app, queue_only, spawn_prop},
+            },
+            tasks::{ActorTask, ActorTaskQueues, EconomyDayState},
+        },
+        npc::{
+            components::{Identity, NpcLocomotion},
+            systems::drive_npc_locomotion,
+        },
+        world::{
+            collision::{resolve_static_collisions, MoverCollider, StaticCollider},
+            time::WorldClock,
+            world_event::MarketDayConfig,
+        },
+    };
+    use bevy::transform::TransformPlugin;
+    use std::time::Duration;
+
+    #[test]
+    fn market_day_deliveries_wait_for_the_market_to_open() {
+        let (mut app, _) = headless_economy_app();
+        let market_day = MarketDayConfig::default();
+        {
+            let mut clock = app.world_mut().resource_mut::<WorldClock>();
+            clock.skip_days(market_day.weekday);
+            clock.set_time_of_day(market_day.start_fraction - 0.05);
+        }
+        app.insert_resource(market_day.clone());
+        let mut cursor = app
+            .world()
+            .resource::<Messages<TradeCompletedEvent>>()
+            .get_cursor();
+        let mut exchanges = |app: &mut App| {
+            let messages = app.world().resource::<Messages<TradeCompletedEvent>>();
+            cursor
+                .read(messages)
+                .filter(|trade| trade.reason == TradeReason::Exchange)
+                .count()
+        };
+
+        for _ in 0..50 {
+            app.update();
+        }
+        assert_eq!(
+            app.world().resource::<EconomyDayState>().deliveries_open_at,
+            Some(market_day.start_fraction)
+        );
+        assert_eq!(
+            exchanges(&mut app),
+            0,
+            "no handoffs before the market opens"
+        );
+        assert!(!app.world().resource::<ActorTaskQueues>().is_empty());
+
+        app.world_mut()
+            .resource_mut::<WorldClock>()
+            .set_time_of_day(market_day.start_fraction);
+        let mut delivered = 0;
+        for _ in 0..100 {
+            app.update();
+            delivered += exchanges(&mut app);
+            if app.world().resource::<ActorTaskQueues>().is_empty() {
+                break;
+            }
+        }
+        assert!(delivered > 0, "deliveries run once the market is open");
+        assert!(app.world().resource::<ActorTaskQueues>().is_empty());
+    }
+
+    #[test]
+    fn exchange_is_handed_over_at_the_marketplace_and_both_return_to_work() {
+        let (mut app, actors) = headless_economy_app();
+        let mut clock = SimulationClock::new(1.0);
+        clock.tick(Duration::from_secs_f32(0.1));
+        app.insert_resource(clock)
+            .add_plugins(TransformPlugin)
+            .add_systems(
+                Update,
+                (drive_npc_locomotion, resolve_static_collisions)
+                    .chain()
+                    .after(advance_actor_tasks),
+            );
+
+        let market = Vec3::new(0.0, 0.25, 5.0);
+        spawn_prop(&mut app, market, Marketplace);
+        let farmer = actors[&Profession::Farmer];
+        let miller = actors[&Profession::Miller];
+        let mut crates = HashMap::new();
+        for (entity, profession, x) in [
+            (farmer, Profession::Farmer, 6.0),
+            (miller, Profession::Miller, -6.0),
+        ] {
+            let center = Vec3::new(x, 0.25, 0.0);
+            let crate_entity = spawn_prop(&mut app, center, ProfessionCrate { profession });
+            app.world_mut()
+                .resource_mut::<ProfessionCrateRegistry>()
+                .insert(profession, crate_entity);
+            app.world_mut().entity_mut(entity).insert((
+                Transform::from_xyz(x * 0.8, 1.0, 0.0),
+                NpcLocomotion::default(),
+                MoverCollider::new(0.3, 0.8),
+            ));
+            crates.insert(entity, center);
+        }
+        app.world_mut()
+            .get_mut::<Inventory>(farmer)
+            .unwrap()
+            .add_good(TradeGood::Grain, 2);
+        let miller_id = app.world().get::<Identity>(miller).unwrap().id;
+        queue_only(
+            &mut app,
+            farmer,
+            vec![ActorTask::Deliver {
+                good: TradeGood::Grain,
+                quantity: 1,
+                target: Profession::Miller,
+                recipient: Some(miller_id),
+            }],
+        );
+        queue_only(
+            &mut app,
+            miller,
+            vec![ActorTask::WaitForGood {
+                good: TradeGood::Grain,
+                quantity: 1,
+            }],
+        );
+
+        let mut cursor = app
+            .world()
+            .resource::<Messages<TradeCompletedEvent>>()
+            .get_cursor();
+        let mut trades = Vec::new();
+        let mut closest_to_market = HashMap::new();
+        for _ in 0..600 {
+            app.update();
+            trades.extend(
+                cursor
+                    .read(app.world().resource::<Messages<TradeCompletedEvent>>())
+                    .cloned(),
+            );
+            for entity in [farmer, miller] {
+                let position = app.world().get::<Transform>(entity).unwrap().translation;
+                let closest = closest_to_market.entry(entity).or_insert(f32::MAX);
+                *closest = closest.min(position.xz().distance(market.xz()));
+            }
+            if app.world().resource::<ActorTaskQueues>().is_empty() {
+                break;
+            }
+        }
+
+        let world = app.world();
+        assert!(world.resource::<ActorTaskQueues>().is_empty());
+        assert!(world.resource::<MarketMeetings>().iter().next().is_none());
+        let grain = |entity: Entity| {
+            world
+                .get::<Inventory>(entity)
+                .unwrap()
+                .quantity_of(TradeGood::Grain)
+        };
+        assert_eq!((grain(farmer), grain(miller)), (1, 1));
+        assert!(trades
+            .iter()
+            .any(|trade| trade.reason == TradeReason::Exchange && trade.to == Some(miller_id)));
+
+        let reach = StaticCollider::new(Vec3::new(0.45, 0.3, 0.45))
+            .arrival_reach(0.3, NpcLocomotion::default().arrive_distance());
+        for entity in [farmer, miller] {
+            assert!(
+                closest_to_market[&entity] <= reach,
+                "both parties reach the market ({:?})",
+                closest_to_market
+            );
+            let position = world.get::<Transform>(entity).unwrap().translation;
+            assert!(
+                position.xz().distance(crates[&entity].xz()) <= reach,
+                "both walk back to their crates"
+            );
+        }
+    }
+}
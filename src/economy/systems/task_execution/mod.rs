@@ -0,0 +1,379 @@
+//! Runs each profession's queued tasks: production at the crate, deliveries handed over at
+//! the marketplace, and surplus deposited in household storage.
+
+use bevy::{ecs::system::ParamSet, prelude::*};
+
+use crate::{
+    economy::{
+        components::{Inventory, InventoryChange, Profession},
+        data::EconomyRegistry,
+        dependency::EconomyDependencyMatrix,
+        events::InventoryChangedEvent,
+        planning::SampledRequest,
+        resources::EconomyActorCache,
+        skills::Skill,
+        tasks::{ActorTask, ActorTaskQueues, EconomyDayState},
+    },
+    npc::{
+        components::{Identity, NpcId, NpcLocomotion},
+        market_day::AttendingMarket,
+        sleep::{HeadingHome, Sleeping},
+    },
+    world::{collision::MoverCollider, time::WorldClock},
+};
+
+mod actor_cache;
+mod deliver;
+mod dependencies;
+mod dispatch;
+mod manufacture;
+mod market;
+mod movement;
+mod negotiation;
+mod params;
+mod surplus;
+
+pub use actor_cache::refresh_economy_actor_cache;
+pub use movement::TaskLocations;
+pub use negotiation::NegotiationAccess;
+pub use params::{EconomyOutputs, HouseholdAccess};
+
+use dependencies::emit_dependency_updates;
+use dispatch::{execute_task, TaskResult};
+
+/// Runs the queued tasks for each profession, driving production and trade. NPCs heading
+/// home or asleep keep their queue until sunrise, and market-day attendees until they are
+/// released. Exchange deliveries meet at the marketplace: the recipient is called there
+/// ahead of their other tasks, and both go back to their crates after the handoff. On
+/// market days couriers wait at their crates until the market opens. With negotiation
+/// enabled the recipient may decline a delivery that ends a request; the courier then takes
+/// the goods back and the request is recorded as unmet.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn advance_actor_tasks(
+    world_clock: Res<WorldClock>,
+    registry: Res<EconomyRegistry>,
+    dependency_matrix: Res<EconomyDependencyMatrix>,
+    mut day_state: ResMut<EconomyDayState>,
+    mut task_queues: ResMut<ActorTaskQueues>,
+    locations: TaskLocations,
+    mut inventory_queries: ParamSet<(Query<&mut Inventory>, Query<&Inventory>)>,
+    mut locomotion_query: Query<(&GlobalTransform, &mut NpcLocomotion, Option<&MoverCollider>)>,
+    identity_query: Query<(Entity, &Identity, &Profession)>,
+    resting: Query<(), Or<(With<HeadingHome>, With<Sleeping>, With<AttendingMarket>)>>,
+    mut skills: Query<&mut Skill>,
+    actors: Res<EconomyActorCache>,
+    households: HouseholdAccess,
+    mut outputs: EconomyOutputs,
+    mut negotiation: NegotiationAccess,
+) {
+    #[cfg(feature = "profiling")]
+    let _span = info_span!("advance_actor_tasks").entered();
+    if task_queues.is_empty() {
+        if let Some(day) = day_state.last_planned_day {
+            if day_state.last_dependency_evaluation_day != Some(day) {
+                {
+                    let inventory_ro = inventory_queries.p1();
+                    emit_dependency_updates(
+                        day,
+                        &dependency_matrix,
+                        &mut outputs.dependency_writer,
+                        &identity_query,
+                        &inventory_ro,
+                        &households,
+                    );
+                }
+                day_state.last_dependency_evaluation_day = Some(day);
+            }
+        }
+        return;
+    }
+
+    let dropped = task_queues.retain_npcs(|npc| actors.get(npc).is_some());
+    if dropped > 0 {
+        warn!("Dropped task queues for {dropped} NPCs who no longer work a profession");
+        outputs
+            .reserved
+            .retain_holders(|npc| actors.get(npc).is_some());
+    }
+
+    let day = world_clock.day_count();
+    outputs
+        .meetings
+        .retain(|courier, meeting| meeting.day == day && task_queues.has_pending_delivery(courier));
+
+    let mut all_complete = true;
+    let season = day_state.season.clone();
+
+    for actor in actors.iter() {
+        let Some(task) = task_queues.peek(actor.npc_id).cloned() else {
+            continue;
+        };
+        if resting.contains(actor.entity) {
+            all_complete = false;
+            continue;
+        }
+
+        if matches!(task, ActorTask::Deliver { .. })
+            && !day_state.deliveries_open(world_clock.time_of_day())
+        {
+            all_complete = false;
+            continue;
+        }
+
+        let market_bound = task.is_market_bound();
+        let claimed_inputs = match &task {
+            ActorTask::Manufacture { recipe } => Some(recipe.consumes.clone()),
+            _ => None,
+        };
+        let delivery = match &task {
+            ActorTask::Deliver {
+                good,
+                quantity,
+                target,
+                ..
+            } => Some(SampledRequest {
+                requester: *target,
+                good: *good,
+                quantity: *quantity,
+            }),
+            _ => None,
+        };
+        match execute_task(
+            &registry,
+            &dependency_matrix,
+            &locations,
+            &actors,
+            &task_queues,
+            &households,
+            actor,
+            task,
+            world_clock.day_count(),
+            world_clock.time_of_day(),
+            season.as_deref(),
+            &mut locomotion_query,
+            &mut inventory_queries,
+            &mut skills,
+            &mut outputs,
+            &mut negotiation,
+        ) {
+            TaskResult::Completed => {
+                task_queues.pop_front(actor.npc_id);
+                // Made or abandoned, the recipe no longer needs its inputs held.
+                if let Some(inputs) = claimed_inputs {
+                    outputs.reserved.release_inputs(actor.npc_id, &inputs);
+                }
+                day_state.delivered_requests.extend(delivery);
+                if market_bound {
+                    task_queues.queue_return_to_crate(actor.npc_id);
+                }
+            }
+            TaskResult::InProgress => {
+                all_complete = false;
+            }
+            TaskResult::Declined {
+                requester,
+                recipient,
+                good,
+                quantity,
+            } => {
+                task_queues.pop_front(actor.npc_id);
+                task_queues.cancel_awaited(recipient, good, quantity);
+                task_queues.queue_return_to_crate(actor.npc_id);
+                day_state.unmet_requests.push(SampledRequest {
+                    requester,
+                    good,
+                    quantity,
+                });
+            }
+        }
+    }
+
+    for (courier, meeting) in outputs.meetings.iter() {
+        task_queues.invite_to_market(meeting.recipient, courier);
+    }
+
+    if all_complete && task_queues.is_empty() {
+        if let Some(day) = day_state.last_planned_day {
+            let inventory_ro = inventory_queries.p1();
+            emit_dependency_updates(
+                day,
+                &dependency_matrix,
+                &mut outputs.dependency_writer,
+                &identity_query,
+                &inventory_ro,
+                &households,
+            );
+            day_state.last_dependency_evaluation_day = Some(day);
+        }
+    }
+}
+
+fn forward_inventory_change(
+    writer: &mut MessageWriter<InventoryChangedEvent>,
+    npc: NpcId,
+    day: u64,
+    change: Option<InventoryChange>,
+) {
+    if let Some(change) = change.filter(|change| change.delta != 0) {
+        writer.write(InventoryChangedEvent::from_change(npc, day, change));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        economy::{
+            components::{Inventory, Profession, TradeGood},
+            data::{EconomyConfig, EconomyRegistry},
+            events::{TradeCompletedEvent, TradeReason},
+            systems::test_support::{
+                flour_milling, headless_economy_app, queue_only, run_until_idle,
+            },
+            tasks::{ActorTask, ActorTaskQueues},
+        },
+        npc::{components::Identity, sleep::Sleeping},
+    };
+
+    #[test]
+    fn headless_day_brews_and_delivers_ale_to_every_other_profession() {
+        let (mut app, actors) = headless_economy_app();
+        let mut cursor = app
+            .world()
+            .resource::<Messages<TradeCompletedEvent>>()
+            .get_cursor();
+        let mut trades = Vec::new();
+
+        for _ in 0..100 {
+            app.update();
+            let messages = app.world().resource::<Messages<TradeCompletedEvent>>();
+            trades.extend(cursor.read(messages).cloned());
+            if app.world().resource::<ActorTaskQueues>().is_empty() {
+                break;
+            }
+        }
+
+        assert!(
+            app.world().resource::<ActorTaskQueues>().is_empty(),
+            "all planned tasks should complete within a headless day"
+        );
+        let brewed = trades
+            .iter()
+            .filter(|trade| trade.good == TradeGood::Ale && trade.reason == TradeReason::Processing)
+            .count();
+        assert_eq!(brewed, 3);
+
+        for profession in [
+            Profession::Farmer,
+            Profession::Miller,
+            Profession::Blacksmith,
+        ] {
+            let inventory = app.world().get::<Inventory>(actors[&profession]).unwrap();
+            assert_eq!(
+                inventory.quantity_of(TradeGood::Ale),
+                1,
+                "{} receives one ale",
+                profession.label()
+            );
+        }
+        let farmer = app
+            .world()
+            .get::<Inventory>(actors[&Profession::Farmer])
+            .unwrap();
+        assert_eq!(farmer.quantity_of(TradeGood::Tools), 1);
+    }
+
+    #[test]
+    fn sleeping_workers_keep_their_tasks_until_they_wake() {
+        let (mut app, actors) = headless_economy_app();
+        let miller = actors[&Profession::Miller];
+        app.world_mut()
+            .get_mut::<Inventory>(miller)
+            .unwrap()
+            .add_good(TradeGood::Grain, 2);
+        let recipe = flour_milling(&app);
+        queue_only(&mut app, miller, vec![ActorTask::Manufacture { recipe }]);
+        app.world_mut().entity_mut(miller).insert(Sleeping);
+
+        let trades = run_until_idle(&mut app);
+        assert!(trades.is_empty(), "nothing is milled overnight");
+        let miller_id = app.world().get::<Identity>(miller).unwrap().id;
+        assert_eq!(
+            app.world()
+                .resource::<ActorTaskQueues>()
+                .remaining_tasks(miller_id),
+            1
+        );
+
+        app.world_mut().entity_mut(miller).remove::<Sleeping>();
+        let trades = run_until_idle(&mut app);
+        assert!(
+            trades
+                .iter()
+                .any(|trade| trade.good == TradeGood::Flour
+                    && trade.reason == TradeReason::Processing)
+        );
+    }
+
+    #[test]
+    fn mid_day_registry_swap_drops_invalidated_tasks_and_runs_new_ones() {
+        let (mut app, actors) = headless_economy_app();
+        app.update();
+        assert!(!app.world().resource::<ActorTaskQueues>().is_empty());
+
+        // Toolsmithing is gone and the innkeeper now wants flour every day.
+        let config: EconomyConfig = toml::from_str(
+            r#"
+            [[recipes]]
+            id = "grain_harvest"
+            actor = "farmer"
+            produces = [{ good = "grain", quantity = 1 }]
+
+            [[recipes]]
+            id = "flour_milling"
+            actor = "miller"
+            produces = [{ good = "flour", quantity = 1 }]
+            consumes = [{ good = "grain", quantity = 1 }]
+
+            [[recipes]]
+            id = "brewing"
+            actor = "innkeeper"
+            produces = [{ good = "ale", quantity = 1 }]
+            consumes = [{ good = "grain", quantity = 1 }]
+
+            [[daily_requests]]
+            requester = "innkeeper"
+            good = "flour"
+            quantity = 2
+            "#,
+        )
+        .unwrap();
+        *app.world_mut().resource_mut::<EconomyRegistry>() =
+            EconomyRegistry::from_config(config).unwrap();
+
+        let mut cursor = app
+            .world()
+            .resource::<Messages<TradeCompletedEvent>>()
+            .get_cursor();
+        let mut trades = Vec::new();
+        for _ in 0..100 {
+            app.update();
+            let messages = app.world().resource::<Messages<TradeCompletedEvent>>();
+            trades.extend(cursor.read(messages).cloned());
+            if app.world().resource::<ActorTaskQueues>().is_empty() {
+                break;
+            }
+        }
+
+        assert!(app.world().resource::<ActorTaskQueues>().is_empty());
+        assert!(
+            trades.iter().all(|trade| trade.good != TradeGood::Tools),
+            "no tools are made once their recipe is gone"
+        );
+        let innkeeper = app
+            .world()
+            .get::<Inventory>(actors[&Profession::Innkeeper])
+            .unwrap();
+        assert_eq!(innkeeper.quantity_of(TradeGood::Flour), 2);
+    }
+}
@@ -0,0 +1,240 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    economy::{
+        components::{Marketplace, Profession, ProfessionCrate},
+        resources::{EconomyActor, ProfessionCrateRegistry},
+    },
+    npc::components::{LocomotionState, MovementTarget, NpcLocomotion},
+    world::collision::{arrival_distance, MoverCollider, StaticCollider},
+};
+
+const MARKETPLACE_LABEL: &str = "marketplace";
+
+/// Where economy tasks send actors: profession crates and the marketplace.
+#[allow(clippy::type_complexity)]
+#[derive(SystemParam)]
+pub struct TaskLocations<'w, 's> {
+    pub(super) crate_registry: Res<'w, ProfessionCrateRegistry>,
+    crates: Query<
+        'w,
+        's,
+        (&'static GlobalTransform, Option<&'static StaticCollider>),
+        With<ProfessionCrate>,
+    >,
+    marketplace: Query<
+        'w,
+        's,
+        (
+            Entity,
+            &'static GlobalTransform,
+            Option<&'static StaticCollider>,
+        ),
+        (With<Marketplace>, Without<ProfessionCrate>),
+    >,
+}
+
+/// A place `ensure_actor_at_location` can walk an actor to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum TaskLocation {
+    Crate(Profession),
+    Marketplace,
+}
+
+/// Walks `actor` to `location`, returning true once they are there. A missing crate or
+/// marketplace counts as reached so tasks are never stuck on absent scenery.
+pub(super) fn ensure_actor_at_location(
+    movement_owner: Profession,
+    location: TaskLocation,
+    actor: &EconomyActor,
+    locations: &TaskLocations,
+    locomotion_query: &mut Query<(&GlobalTransform, &mut NpcLocomotion, Option<&MoverCollider>)>,
+) -> bool {
+    let (entity, transform, collider, label) = match location {
+        TaskLocation::Crate(location_owner) => {
+            let Some(crate_entity) = locations.crate_registry.get(location_owner) else {
+                warn!("No crate registered for {}", location_owner.label());
+                return true;
+            };
+            let Ok((transform, collider)) = locations.crates.get(crate_entity) else {
+                warn!(
+                    "Crate entity for {} missing transform",
+                    location_owner.label()
+                );
+                return true;
+            };
+            let label = if movement_owner == location_owner {
+                format!("{} crate", movement_owner.label())
+            } else {
+                format!("{} crate (visiting)", location_owner.label())
+            };
+            (crate_entity, transform, collider, label)
+        }
+        TaskLocation::Marketplace => {
+            let Ok((entity, transform, collider)) = locations.marketplace.single() else {
+                warn!(
+                    "No marketplace spawned; handing over where {} stands",
+                    actor.display_name
+                );
+                return true;
+            };
+            (entity, transform, collider, MARKETPLACE_LABEL.to_string())
+        }
+    };
+
+    walk_toward(
+        actor,
+        entity,
+        transform.translation(),
+        collider,
+        label,
+        locomotion_query,
+    )
+}
+
+/// Steers the actor toward `destination`, returning true once they stand within arrive
+/// distance, or beside the destination when it has a collider.
+pub(super) fn walk_toward(
+    actor: &EconomyActor,
+    destination_entity: Entity,
+    destination: Vec3,
+    collider: Option<&StaticCollider>,
+    label: String,
+    locomotion_query: &mut Query<(&GlobalTransform, &mut NpcLocomotion, Option<&MoverCollider>)>,
+) -> bool {
+    let Ok((actor_transform, mut locomotion, body)) = locomotion_query.get_mut(actor.entity) else {
+        warn!("{} is missing locomotion data", actor.display_name);
+        return true;
+    };
+
+    let current = actor_transform.translation();
+    let mut target = destination;
+    target.y = current.y;
+
+    let displacement = Vec2::new(target.x - current.x, target.z - current.z);
+    if !displacement.is_finite() {
+        // A corrupted position never counts as arrived, or goods would change hands anywhere.
+        warn!(
+            "{} has a non-finite position; not walking toward {}",
+            actor.display_name, label
+        );
+        return false;
+    }
+    if displacement.length() <= arrival_distance(locomotion.arrive_distance(), collider, body) {
+        if locomotion.state() == LocomotionState::Moving {
+            locomotion.arrive_at(if collider.is_some() { current } else { target });
+        }
+        return true;
+    }
+
+    if locomotion.set_target(
+        MovementTarget::Entity(destination_entity),
+        label.clone(),
+        current,
+    ) {
+        info!("{} starts walking toward {}", actor.display_name, label);
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        core::plugin::SimulationClock,
+        economy::{
+            components::{Inventory, Profession, ProfessionCrate, TradeGood},
+            resources::ProfessionCrateRegistry,
+            systems::{
+                task_execution::advance_actor_tasks,
+                test_support::{flour_milling, headless_economy_app, queue_only},
+            },
+            tasks::{ActorTask, ActorTaskQueues},
+        },
+        npc::{components::NpcLocomotion, systems::drive_npc_locomotion},
+        world::collision::{resolve_static_collisions, MoverCollider, StaticCollider},
+    };
+    use bevy::transform::TransformPlugin;
+    use std::time::Duration;
+
+    #[test]
+    fn miller_walking_into_the_crate_stops_beside_it_and_mills() {
+        let (mut app, actors) = headless_economy_app();
+        let mut clock = SimulationClock::new(1.0);
+        clock.tick(Duration::from_secs_f32(0.1));
+        app.insert_resource(clock)
+            .add_plugins(TransformPlugin)
+            .add_systems(
+                Update,
+                (drive_npc_locomotion, resolve_static_collisions)
+                    .chain()
+                    .after(advance_actor_tasks),
+            );
+
+        let crate_center = Vec3::new(4.0, 0.25, 0.0);
+        let collider = StaticCollider::new(Vec3::new(0.45, 0.3, 0.45));
+        let crate_entity = app
+            .world_mut()
+            .spawn((
+                Transform::from_translation(crate_center),
+                ProfessionCrate {
+                    profession: Profession::Miller,
+                },
+                collider,
+            ))
+            .id();
+        app.world_mut()
+            .resource_mut::<ProfessionCrateRegistry>()
+            .insert(Profession::Miller, crate_entity);
+
+        let body = MoverCollider::new(0.3, 0.8);
+        let miller = actors[&Profession::Miller];
+        app.world_mut().entity_mut(miller).insert((
+            Transform::from_xyz(0.0, 1.0, 0.0),
+            NpcLocomotion::default(),
+            body,
+        ));
+        app.world_mut()
+            .get_mut::<Inventory>(miller)
+            .unwrap()
+            .add_good(TradeGood::Grain, 2);
+        let recipe = flour_milling(&app);
+        queue_only(&mut app, miller, vec![ActorTask::Manufacture { recipe }]);
+
+        let mut milled = false;
+        let mut closest = f32::MAX;
+        for _ in 0..200 {
+            app.update();
+            let position = app.world().get::<Transform>(miller).unwrap().translation;
+            closest = closest.min(position.xz().distance(crate_center.xz()));
+            if app.world().resource::<ActorTaskQueues>().is_empty() {
+                milled = true;
+                break;
+            }
+        }
+
+        assert!(milled, "the milling task completes at the crate");
+        let miller_inventory = app.world().get::<Inventory>(miller).unwrap();
+        assert!(miller_inventory.quantity_of(TradeGood::Flour) > 0);
+        assert!(
+            closest >= collider.half_extents.x + body.radius - 1e-4,
+            "never inside the crate (closest {closest})"
+        );
+        let stopped = app.world().get::<Transform>(miller).unwrap().translation;
+        let gap = stopped.xz().distance(crate_center.xz());
+        assert!(
+            gap <= collider.arrival_reach(body.radius, NpcLocomotion::default().arrive_distance()),
+            "stopped beside the crate (gap {gap})"
+        );
+        assert_eq!(
+            app.world()
+                .get::<NpcLocomotion>(miller)
+                .unwrap()
+                .arrival_point()
+                .map(|point| point.xz()),
+            Some(stopped.xz()),
+            "arrival point is where the miller stands"
+        );
+    }
+}
@@ -0,0 +1,406 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    dialogue::{chatter::ChatterBudgets, queue::DialogueRequestQueue},
+    economy::{
+        components::{Inventory, Profession, TradeGood},
+        dependency::EconomyDependencyMatrix,
+        events::TradeProposedEvent,
+        market::{MarketMeeting, MarketMeetings},
+        negotiation::{decide_trade, need_for_good, NegotiationConfig, TradeDecision, TradeOffer},
+        relationships::{trade_affinity, TradeRelationships},
+        resources::EconomyActor,
+        systems::dialogue::{queue_trade_reply, TradeReplyInput},
+    },
+    npc::{
+        motivation::{state::NpcMood, NpcMotivation},
+        sleep::SleepRoster,
+    },
+};
+
+use super::{dependencies::category_satisfied, params::HouseholdAccess, TaskResult};
+
+/// What a negotiated handoff is weighed against.
+pub(super) struct HandoffTerms<'a, 'w, 's> {
+    pub(super) config: &'a NegotiationConfig,
+    pub(super) dependency_matrix: &'a EconomyDependencyMatrix,
+    pub(super) households: &'a HouseholdAccess<'w, 's>,
+}
+
+/// Offers the goods to `recipient` once both stand at the market, then acts on the answer:
+/// `None` once the offer is accepted, otherwise what the delivery task reports this frame.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn negotiate_handoff(
+    terms: &HandoffTerms,
+    courier: &EconomyActor,
+    recipient: &EconomyActor,
+    requester: Profession,
+    good: TradeGood,
+    quantity: u32,
+    day: u64,
+    time_of_day: f32,
+    inventories: &Query<&Inventory>,
+    dialogue_queue: &mut DialogueRequestQueue,
+    chatter_budgets: &mut ChatterBudgets,
+    sleepers: &SleepRoster,
+    meetings: &mut MarketMeetings,
+    negotiation: &mut NegotiationAccess,
+) -> Option<TaskResult> {
+    match meetings
+        .get(courier.npc_id)
+        .and_then(MarketMeeting::awaiting_acceptance)
+    {
+        None => {
+            let offer = trade_offer(
+                terms,
+                courier,
+                recipient,
+                good,
+                quantity,
+                inventories,
+                &negotiation.moods,
+                &negotiation.relationships,
+            );
+            let decision = decide_trade(&offer, terms.config);
+            meetings.propose(courier.npc_id, decision);
+            negotiation.proposed_writer.write(TradeProposedEvent {
+                day,
+                courier: courier.npc_id,
+                recipient: recipient.npc_id,
+                good,
+                quantity,
+                decision,
+            });
+            queue_trade_reply(
+                dialogue_queue,
+                chatter_budgets,
+                sleepers,
+                TradeReplyInput {
+                    day,
+                    time_of_day,
+                    courier: courier.npc_id,
+                    courier_name: &courier.display_name,
+                    recipient: recipient.npc_id,
+                    recipient_name: &recipient.display_name,
+                    good,
+                    quantity,
+                    decision,
+                },
+            );
+            Some(TaskResult::InProgress)
+        }
+        Some(TradeDecision::Decline(reason)) => {
+            meetings.finish(courier.npc_id);
+            info!(
+                "{} declines {} from {} ({reason:?}); it goes back to the {} crate",
+                recipient.display_name,
+                good.label(),
+                courier.display_name,
+                courier.profession.label()
+            );
+            Some(TaskResult::Declined {
+                requester,
+                recipient: recipient.npc_id,
+                good,
+                quantity,
+            })
+        }
+        Some(TradeDecision::Accept) => None,
+    }
+}
+
+/// Moods and trading history of recipients weighing an offer, and where offers are
+/// announced.
+#[derive(SystemParam)]
+pub struct NegotiationAccess<'w, 's> {
+    moods: Query<'w, 's, &'static NpcMotivation>,
+    relationships: Res<'w, TradeRelationships>,
+    proposed_writer: MessageWriter<'w, TradeProposedEvent>,
+}
+
+/// The recipient's view of `courier`'s offer: need from their crate and household
+/// storage, units already held, affinity (`trade_affinity` of their trading history and
+/// whether they are housemates), and mood (content when unknown).
+#[allow(clippy::too_many_arguments)]
+fn trade_offer(
+    terms: &HandoffTerms,
+    courier: &EconomyActor,
+    recipient: &EconomyActor,
+    good: TradeGood,
+    quantity: u32,
+    inventories: &Query<&Inventory>,
+    moods: &Query<&NpcMotivation>,
+    relationships: &TradeRelationships,
+) -> TradeOffer {
+    let own = inventories.get(recipient.entity).ok();
+    let storage = terms
+        .households
+        .household_of(recipient.entity)
+        .and_then(|household| inventories.get(household.storage).ok());
+    let stocks: Vec<&Inventory> = own.into_iter().chain(storage).collect();
+    let need = need_for_good(
+        terms.dependency_matrix,
+        recipient.profession,
+        good,
+        |category| category_satisfied(terms.dependency_matrix, category, &stocks),
+    );
+    let held = own.map_or(0, |inventory| {
+        TradeGood::ALL.into_iter().fold(0u32, |total, good| {
+            total.saturating_add(inventory.quantity_of(good))
+        })
+    });
+    let affinity = trade_affinity(
+        relationships.score(courier.npc_id, recipient.npc_id),
+        terms
+            .households
+            .share_household(courier.entity, recipient.entity),
+        terms.config,
+    );
+    TradeOffer {
+        need,
+        held,
+        quantity,
+        affinity,
+        mood: moods
+            .get(recipient.entity)
+            .map_or(NpcMood::Content, NpcMotivation::mood),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dialogue::{queue::DialogueRequestQueue, types::DialogueTopicHint},
+        economy::{
+            components::{Inventory, Profession, TradeGood},
+            events::{
+                ProfessionDependencyUpdateEvent, TradeCompletedEvent, TradeProposedEvent,
+                TradeReason,
+            },
+            market::MarketMeetings,
+            negotiation::{DeclineReason, TradeDecision},
+            planning::SampledRequest,
+            relationships::TradeRelationships,
+            resources::CarriedGoodsRegistry,
+            systems::test_support::{
+                drain, flour_milling, grain_cubes, negotiated_grain_app, run_until_idle,
+            },
+            tasks::{ActorTask, ActorTaskQueues, EconomyDayState},
+        },
+        npc::{
+            components::Identity,
+            motivation::{MotivationConfig, NpcMotivation},
+        },
+    };
+
+    /// Runs until the task queues drain, returning the offers made and the trades completed
+    /// on the way.
+    fn negotiate_until_idle(app: &mut App) -> (Vec<TradeProposedEvent>, Vec<TradeCompletedEvent>) {
+        let mut proposals = app
+            .world()
+            .resource::<Messages<TradeProposedEvent>>()
+            .get_cursor();
+        let mut trade_cursor = app
+            .world()
+            .resource::<Messages<TradeCompletedEvent>>()
+            .get_cursor();
+        let (mut proposed, mut trades) = (Vec::new(), Vec::new());
+        for _ in 0..100 {
+            app.update();
+            proposed.extend(drain(app, &mut proposals));
+            trades.extend(drain(app, &mut trade_cursor));
+            if app.world().resource::<ActorTaskQueues>().is_empty() {
+                break;
+            }
+        }
+        (proposed, trades)
+    }
+
+    /// The prompt of the Trade line `speaker` queued for `target`, if any.
+    fn queued_reply(app: &mut App, speaker: Entity, target: Entity) -> Option<String> {
+        let speaker = app.world().get::<Identity>(speaker).unwrap().id;
+        let target = app.world().get::<Identity>(target).unwrap().id;
+        let id = app
+            .world()
+            .resource::<DialogueRequestQueue>()
+            .iter_pending()
+            .find(|view| {
+                view.speaker == speaker
+                    && view.target == Some(target)
+                    && view.topic == DialogueTopicHint::Trade
+            })?
+            .id;
+        app.world_mut()
+            .resource_mut::<DialogueRequestQueue>()
+            .pending_mut(id)
+            .map(|request| request.prompt.clone())
+    }
+
+    #[test]
+    fn negotiated_delivery_is_accepted_by_a_miller_short_of_food() {
+        let (mut app, farmer, miller) =
+            negotiated_grain_app(NpcMotivation::new(&MotivationConfig::default()));
+        app.update();
+        assert_eq!(
+            grain_cubes(&app, Profession::Farmer),
+            0,
+            "the grain rides with the courier, not in the crate"
+        );
+
+        let (proposed, trades) = negotiate_until_idle(&mut app);
+
+        assert_eq!(proposed.len(), 1, "one offer per delivery");
+        assert_eq!(proposed[0].decision, TradeDecision::Accept);
+        assert!(trades
+            .iter()
+            .any(|trade| trade.reason == TradeReason::Exchange && trade.good == TradeGood::Grain));
+        let reply = queued_reply(&mut app, miller, farmer).expect("the miller answers");
+        assert!(reply.contains("Gladly!"), "{reply}");
+
+        let world = app.world();
+        assert!(world.resource::<ActorTaskQueues>().is_empty());
+        assert!(world
+            .resource::<EconomyDayState>()
+            .unmet_requests
+            .is_empty());
+        let grain = |entity: Entity| {
+            world
+                .get::<Inventory>(entity)
+                .unwrap()
+                .quantity_of(TradeGood::Grain)
+        };
+        assert_eq!((grain(farmer), grain(miller)), (0, 1));
+        assert_eq!(grain_cubes(&app, Profession::Farmer), 0);
+        assert_eq!(grain_cubes(&app, Profession::Miller), 1);
+        let farmer_id = world.get::<Identity>(farmer).unwrap().id;
+        assert!(world
+            .resource::<CarriedGoodsRegistry>()
+            .get(farmer_id)
+            .is_none());
+    }
+
+    #[test]
+    fn declined_delivery_goes_home_and_leaves_the_request_unmet() {
+        // Fed and tired, the miller has no reason to take more grain.
+        let mut config = MotivationConfig::default();
+        config.defaults.start = config.thresholds.tired;
+        let (mut app, farmer, miller) = negotiated_grain_app(NpcMotivation::new(&config));
+        app.world_mut()
+            .get_mut::<Inventory>(miller)
+            .unwrap()
+            .add_good(TradeGood::Flour, 1);
+        let mut dependencies = app
+            .world()
+            .resource::<Messages<ProfessionDependencyUpdateEvent>>()
+            .get_cursor();
+
+        let (proposed, trades) = negotiate_until_idle(&mut app);
+        app.update();
+
+        assert_eq!(proposed.len(), 1);
+        assert_eq!(
+            proposed[0].decision,
+            TradeDecision::Decline(DeclineReason::Unwanted)
+        );
+        assert!(!trades
+            .iter()
+            .any(|trade| trade.reason == TradeReason::Exchange));
+        let reply = queued_reply(&mut app, miller, farmer).expect("the miller answers");
+        assert!(reply.contains("Not today"), "{reply}");
+
+        let world = app.world();
+        assert!(
+            world.resource::<ActorTaskQueues>().is_empty(),
+            "the miller stops waiting and the farmer walks home"
+        );
+        assert!(world.resource::<MarketMeetings>().iter().next().is_none());
+        assert_eq!(
+            world.resource::<EconomyDayState>().unmet_requests,
+            vec![SampledRequest {
+                requester: Profession::Miller,
+                good: TradeGood::Grain,
+                quantity: 1,
+            }]
+        );
+        let grain = |entity: Entity| {
+            world
+                .get::<Inventory>(entity)
+                .unwrap()
+                .quantity_of(TradeGood::Grain)
+        };
+        assert_eq!((grain(farmer), grain(miller)), (1, 0));
+        assert_eq!(grain_cubes(&app, Profession::Farmer), 1);
+        assert_eq!(grain_cubes(&app, Profession::Miller), 0);
+        let farmer_id = world.get::<Identity>(farmer).unwrap().id;
+        assert!(world
+            .resource::<CarriedGoodsRegistry>()
+            .get(farmer_id)
+            .is_none());
+        assert!(
+            drain(&app, &mut dependencies)
+                .iter()
+                .any(|update| update.profession == Profession::Miller),
+            "the day still closes with dependency snapshots"
+        );
+    }
+
+    #[test]
+    fn a_trusted_trading_partner_is_taken_when_a_stranger_would_be_declined() {
+        // The same fed, tired miller as above, but with a long record of good trades.
+        let mut config = MotivationConfig::default();
+        config.defaults.start = config.thresholds.tired;
+        let (mut app, farmer, miller) = negotiated_grain_app(NpcMotivation::new(&config));
+        app.world_mut()
+            .get_mut::<Inventory>(miller)
+            .unwrap()
+            .add_good(TradeGood::Flour, 1);
+        let farmer_id = app.world().get::<Identity>(farmer).unwrap().id;
+        let miller_id = app.world().get::<Identity>(miller).unwrap().id;
+        app.world_mut()
+            .resource_mut::<TradeRelationships>()
+            .adjust(farmer_id, miller_id, 1.0);
+
+        let (proposed, _) = negotiate_until_idle(&mut app);
+
+        assert_eq!(proposed.len(), 1);
+        assert_eq!(proposed[0].decision, TradeDecision::Accept);
+        let grain = app
+            .world()
+            .get::<Inventory>(miller)
+            .unwrap()
+            .quantity_of(TradeGood::Grain);
+        assert_eq!(grain, 1);
+    }
+
+    #[test]
+    fn recipe_inputs_are_handed_over_without_an_offer() {
+        let mut config = MotivationConfig::default();
+        config.defaults.start = config.thresholds.tired;
+        let (mut app, farmer, miller) = negotiated_grain_app(NpcMotivation::new(&config));
+        let milling = flour_milling(&app);
+        let miller_id = app.world().get::<Identity>(miller).unwrap().id;
+        app.world_mut()
+            .resource_mut::<ActorTaskQueues>()
+            .ensure_queue(miller_id)
+            .push_back(ActorTask::Manufacture { recipe: milling });
+        let mut proposals = app
+            .world()
+            .resource::<Messages<TradeProposedEvent>>()
+            .get_cursor();
+
+        let trades = run_until_idle(&mut app);
+
+        assert!(drain(&app, &mut proposals).is_empty());
+        assert!(trades
+            .iter()
+            .any(|trade| trade.reason == TradeReason::Exchange && trade.from.is_some()));
+        assert_eq!(
+            app.world()
+                .get::<Inventory>(farmer)
+                .unwrap()
+                .quantity_of(TradeGood::Grain),
+            0
+        );
+    }
+}
@@ -0,0 +1,62 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    dialogue::{
+        chatter::{ChatterBudgets, PairChatterCooldown},
+        queue::DialogueRequestQueue,
+    },
+    economy::{
+        events::{
+            InventoryChangedEvent, ProfessionDependencyUpdateEvent, SkillLevelUpEvent,
+            TradeCompletedEvent,
+        },
+        market::MarketMeetings,
+        reservations::ReservedStock,
+    },
+    npc::{
+        household::{Household, HouseholdConfig, HouseholdId, HouseholdRegistry, HouseholdStorage},
+        sleep::SleepRoster,
+    },
+};
+
+#[derive(SystemParam)]
+pub struct EconomyOutputs<'w> {
+    pub(super) trade_writer: MessageWriter<'w, TradeCompletedEvent>,
+    pub(super) dependency_writer: MessageWriter<'w, ProfessionDependencyUpdateEvent>,
+    pub(super) inventory_writer: MessageWriter<'w, InventoryChangedEvent>,
+    pub(super) skill_writer: MessageWriter<'w, SkillLevelUpEvent>,
+    pub(super) dialogue_queue: ResMut<'w, DialogueRequestQueue>,
+    pub(super) chatter_cooldown: ResMut<'w, PairChatterCooldown>,
+    pub(super) chatter_budgets: ResMut<'w, ChatterBudgets>,
+    pub(super) sleepers: Res<'w, SleepRoster>,
+    pub(super) meetings: ResMut<'w, MarketMeetings>,
+    pub(super) reserved: ResMut<'w, ReservedStock>,
+}
+
+/// Household membership and storage lookups for economy actors.
+#[derive(SystemParam)]
+pub struct HouseholdAccess<'w, 's> {
+    registry: Res<'w, HouseholdRegistry>,
+    pub(super) config: Res<'w, HouseholdConfig>,
+    members: Query<'w, 's, &'static HouseholdId>,
+    storage_transforms: Query<'w, 's, &'static GlobalTransform, With<HouseholdStorage>>,
+}
+
+impl HouseholdAccess<'_, '_> {
+    pub(super) fn household_of(&self, entity: Entity) -> Option<&Household> {
+        let id = self.members.get(entity).ok()?;
+        self.registry.get(*id)
+    }
+
+    pub(super) fn share_household(&self, a: Entity, b: Entity) -> bool {
+        matches!((self.members.get(a), self.members.get(b)), (Ok(a), Ok(b)) if a == b)
+    }
+
+    /// Where the storage crate actually stands, falling back to the configured home.
+    pub(super) fn storage_position(&self, household: &Household) -> Vec3 {
+        self.storage_transforms
+            .get(household.storage)
+            .map(GlobalTransform::translation)
+            .unwrap_or(household.home)
+    }
+}
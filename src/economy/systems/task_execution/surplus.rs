@@ -0,0 +1,179 @@
+use bevy::{ecs::system::ParamSet, prelude::*};
+
+use crate::{
+    economy::{
+        components::Inventory,
+        events::{InventoryChangedEvent, TradeCompletedEvent, TradeReason},
+        reservations::ReservedStock,
+        resources::EconomyActor,
+        systems::storage::surplus_above_keep,
+    },
+    npc::components::NpcLocomotion,
+    world::collision::MoverCollider,
+};
+
+use super::{forward_inventory_change, movement::walk_toward, params::HouseholdAccess, TaskResult};
+
+#[allow(clippy::too_many_arguments)]
+pub(super) fn execute_deposit_surplus(
+    households: &HouseholdAccess,
+    actor: &EconomyActor,
+    awaiting_delivery: bool,
+    reserved: &ReservedStock,
+    day: u64,
+    locomotion_query: &mut Query<(&GlobalTransform, &mut NpcLocomotion, Option<&MoverCollider>)>,
+    inventory_queries: &mut ParamSet<(Query<&mut Inventory>, Query<&Inventory>)>,
+    trade_writer: &mut MessageWriter<TradeCompletedEvent>,
+    inventory_writer: &mut MessageWriter<InventoryChangedEvent>,
+) -> TaskResult {
+    let Some(household) = households.household_of(actor.entity) else {
+        return TaskResult::Completed;
+    };
+
+    // Wait for inbound deliveries so their goods are part of the surplus.
+    if awaiting_delivery {
+        return TaskResult::InProgress;
+    }
+
+    let surplus = {
+        let inventories = inventory_queries.p1();
+        let Ok(inventory) = inventories.get(actor.entity) else {
+            warn!(
+                "{} is missing an inventory; cannot deposit surplus",
+                actor.display_name
+            );
+            return TaskResult::Completed;
+        };
+        surplus_above_keep(inventory, households.config.personal_keep)
+            .into_iter()
+            .map(|(good, quantity)| {
+                let free =
+                    inventory.available_unreserved(good, reserved.reserved(actor.npc_id, good));
+                (good, quantity.min(free))
+            })
+            .filter(|(_, quantity)| *quantity > 0)
+            .collect::<Vec<_>>()
+    };
+    if surplus.is_empty() {
+        return TaskResult::Completed;
+    }
+
+    let label = format!("{} storage", household.name);
+    if !walk_toward(
+        actor,
+        household.storage,
+        households.storage_position(household),
+        None,
+        label,
+        locomotion_query,
+    ) {
+        return TaskResult::InProgress;
+    }
+
+    let mut inventories = inventory_queries.p0();
+    for (good, quantity) in surplus {
+        let Ok(mut inventory) = inventories.get_mut(actor.entity) else {
+            break;
+        };
+        let Some((change, stacks)) = inventory.take_good(good, quantity) else {
+            continue;
+        };
+        forward_inventory_change(inventory_writer, actor.npc_id, day, Some(change));
+
+        if let Ok(mut stored) = inventories.get_mut(household.storage) {
+            stored.add_stacks(good, &stacks);
+        } else {
+            warn!(
+                "{} storage is missing an inventory; deposit from {} discarded",
+                household.name, actor.display_name
+            );
+        }
+
+        trade_writer.write(TradeCompletedEvent {
+            day,
+            from: Some(actor.npc_id),
+            to: None,
+            good,
+            quantity,
+            reason: TradeReason::Storage,
+        });
+    }
+
+    TaskResult::Completed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::economy::{
+        components::{Inventory, Profession, TradeGood},
+        dependency::DependencyCategory,
+        events::{ProfessionDependencyUpdateEvent, TradeReason},
+        systems::test_support::{headless_economy_app, join_household, queue_only, run_until_idle},
+        tasks::ActorTask,
+    };
+
+    #[test]
+    fn deposited_surplus_satisfies_housemates_dependencies() {
+        let (mut app, actors) = headless_economy_app();
+        let innkeeper = actors[&Profession::Innkeeper];
+        let blacksmith = actors[&Profession::Blacksmith];
+        app.world_mut()
+            .get_mut::<Inventory>(innkeeper)
+            .unwrap()
+            .add_good(TradeGood::Tools, 3);
+        let storage = join_household(&mut app, &[innkeeper, blacksmith], &[]);
+        queue_only(&mut app, innkeeper, vec![ActorTask::DepositSurplus]);
+        let mut cursor = app
+            .world()
+            .resource::<Messages<ProfessionDependencyUpdateEvent>>()
+            .get_cursor();
+
+        let trades = run_until_idle(&mut app);
+        app.update();
+
+        let world = app.world();
+        assert_eq!(
+            world
+                .get::<Inventory>(innkeeper)
+                .unwrap()
+                .quantity_of(TradeGood::Tools),
+            1
+        );
+        assert_eq!(
+            world
+                .get::<Inventory>(storage)
+                .unwrap()
+                .quantity_of(TradeGood::Tools),
+            2
+        );
+        assert!(trades
+            .iter()
+            .any(|trade| trade.reason == TradeReason::Storage
+                && trade.to.is_none()
+                && trade.quantity == 2));
+
+        let messages = world.resource::<Messages<ProfessionDependencyUpdateEvent>>();
+        let updates: Vec<_> = cursor.read(messages).cloned().collect();
+        let tools_met = |profession: Profession| {
+            updates
+                .iter()
+                .find(|update| update.profession == profession)
+                .map(|update| {
+                    update
+                        .satisfied_categories
+                        .contains(&DependencyCategory::Tools)
+                })
+        };
+        assert_eq!(
+            tools_met(Profession::Blacksmith),
+            Some(true),
+            "shared stock counts"
+        );
+        assert_eq!(
+            tools_met(Profession::Farmer),
+            Some(false),
+            "other households don't"
+        );
+    }
+}
@@ -0,0 +1,360 @@
+//! Headless economy fixtures shared by the task, carrying and yielding tests.
+use std::{collections::HashMap, time::Duration};
+
+use bevy::{ecs::message::MessageCursor, prelude::*, transform::TransformPlugin};
+
+use crate::{
+    core::plugin::SimulationClock,
+    dialogue::{
+        chatter::{ChatterBudgets, PairChatterCooldown},
+        events::DialogueRequestedEvent,
+        queue::{announce_queued_dialogue_requests, DialogueRequestQueue},
+    },
+    npc::{
+        components::{Identity, NpcId, NpcLocomotion},
+        household::{Household, HouseholdConfig, HouseholdId, HouseholdRegistry},
+        motivation::{MotivationConfig, NpcMotivation},
+        sleep::SleepRoster,
+        spatial::{index_npcs, NpcIndex},
+    },
+    world::{
+        collision::{MoverCollider, StaticCollider},
+        time::{announce_day_change, DayChangedEvent, WorldClock},
+    },
+};
+
+use super::super::{
+    components::{Inventory, Marketplace, Profession, ProfessionCrate, TradeGood},
+    data::{EconomyRegistry, Recipe},
+    dependency::EconomyDependencyMatrix,
+    events::{
+        EconomyEventOccurred, InventoryChangedEvent, ProfessionDependencyUpdateEvent,
+        SkillLevelUpEvent, TradeCompletedEvent, TradeProposedEvent,
+    },
+    market::MarketMeetings,
+    negotiation::NegotiationConfig,
+    relationships::TradeRelationships,
+    reservations::ReservedStock,
+    resources::{
+        CarriedGoodsRegistry, EconomyActorCache, PlaceholderStackConfig, ProfessionCrateRegistry,
+        TradeGoodPlaceholderRegistry, TradeGoodPlaceholderVisuals,
+    },
+    tasks::{ActorTask, ActorTaskQueues, EconomyDayState},
+};
+use super::{
+    carrying::sync_carried_goods,
+    day_prep::{prepare_economy_day, reset_chatter_budgets},
+    placeholders::sync_trade_good_placeholders,
+    task_execution::{advance_actor_tasks, refresh_economy_actor_cache},
+};
+
+/// An app that plans and runs the economy without rendering, with one NPC per profession
+/// (id = its index in `Profession::ALL`) and no props.
+pub fn headless_economy_app() -> (App, HashMap<Profession, Entity>) {
+    let mut app = App::new();
+    app.insert_resource(WorldClock::new())
+        .init_resource::<EconomyRegistry>()
+        .init_resource::<EconomyDependencyMatrix>()
+        .init_resource::<EconomyDayState>()
+        .init_resource::<ActorTaskQueues>()
+        .init_resource::<MarketMeetings>()
+        .init_resource::<ReservedStock>()
+        .init_resource::<TradeRelationships>()
+        .init_resource::<EconomyActorCache>()
+        .init_resource::<ProfessionCrateRegistry>()
+        .init_resource::<DialogueRequestQueue>()
+        .init_resource::<PairChatterCooldown>()
+        .init_resource::<ChatterBudgets>()
+        .init_resource::<SleepRoster>()
+        .init_resource::<MotivationConfig>()
+        .init_resource::<HouseholdRegistry>()
+        .init_resource::<HouseholdConfig>()
+        .add_message::<TradeCompletedEvent>()
+        .add_message::<ProfessionDependencyUpdateEvent>()
+        .add_message::<InventoryChangedEvent>()
+        .add_message::<SkillLevelUpEvent>()
+        .add_message::<DialogueRequestedEvent>()
+        .add_message::<EconomyEventOccurred>()
+        .add_message::<TradeProposedEvent>()
+        .add_message::<DayChangedEvent>()
+        .add_systems(
+            Update,
+            (
+                announce_day_change,
+                reset_chatter_budgets,
+                refresh_economy_actor_cache,
+                prepare_economy_day,
+                advance_actor_tasks,
+                announce_queued_dialogue_requests,
+            )
+                .chain(),
+        );
+
+    let mut actors = HashMap::new();
+    for (index, profession) in Profession::ALL.into_iter().enumerate() {
+        let entity = app
+            .world_mut()
+            .spawn((
+                Identity::new(NpcId::new(index as u64), profession.label(), 30.0),
+                profession,
+                Inventory::default(),
+            ))
+            .id();
+        actors.insert(profession, entity);
+    }
+    (app, actors)
+}
+
+/// Skips day planning and queues `tasks` for `actor` alone.
+pub fn queue_only(app: &mut App, actor: Entity, tasks: Vec<ActorTask>) {
+    // Swallow the startup announcement so day 0 is never planned.
+    app.world_mut()
+        .resource_mut::<WorldClock>()
+        .take_day_change();
+    app.world_mut()
+        .resource_mut::<EconomyDayState>()
+        .last_planned_day = Some(0);
+    let npc = app.world().get::<Identity>(actor).unwrap().id;
+    app.world_mut()
+        .resource_mut::<ActorTaskQueues>()
+        .ensure_queue(npc)
+        .extend(tasks);
+}
+
+/// Spawns a static prop at `center` with `marker`, e.g. a crate or the market stall.
+pub fn spawn_prop(app: &mut App, center: Vec3, marker: impl Bundle) -> Entity {
+    app.world_mut()
+        .spawn((
+            Transform::from_translation(center),
+            StaticCollider::new(Vec3::new(0.45, 0.3, 0.45)),
+            marker,
+        ))
+        .id()
+}
+
+/// Puts `members` in one household whose storage starts with `stock`.
+pub fn join_household(app: &mut App, members: &[Entity], stock: &[(TradeGood, u32)]) -> Entity {
+    let mut inventory = Inventory::default();
+    for (good, quantity) in stock {
+        inventory.add_good(*good, *quantity);
+    }
+    let storage = app.world_mut().spawn(inventory).id();
+    let id = HouseholdId::new(0);
+    app.world_mut().resource_mut::<HouseholdRegistry>().insert(
+        id,
+        Household {
+            name: "Test".to_string(),
+            storage,
+            home: Vec3::ZERO,
+        },
+    );
+    for member in members {
+        app.world_mut().entity_mut(*member).insert(id);
+    }
+    storage
+}
+
+/// Updates until every task queue is empty, at most 100 frames, returning the trades
+/// completed on the way.
+pub fn run_until_idle(app: &mut App) -> Vec<TradeCompletedEvent> {
+    let mut cursor = app
+        .world()
+        .resource::<Messages<TradeCompletedEvent>>()
+        .get_cursor();
+    let mut trades = Vec::new();
+    for _ in 0..100 {
+        app.update();
+        let messages = app.world().resource::<Messages<TradeCompletedEvent>>();
+        trades.extend(cursor.read(messages).cloned());
+        if app.world().resource::<ActorTaskQueues>().is_empty() {
+            break;
+        }
+    }
+    trades
+}
+
+/// The registry's flour milling recipe.
+pub fn flour_milling(app: &App) -> Recipe {
+    app.world()
+        .resource::<EconomyRegistry>()
+        .recipe("flour_milling")
+        .expect("flour milling recipe")
+        .clone()
+}
+
+/// The messages `cursor` has not read yet.
+pub fn drain<M: Message + Clone>(app: &App, cursor: &mut MessageCursor<M>) -> Vec<M> {
+    cursor
+        .read(app.world().resource::<Messages<M>>())
+        .cloned()
+        .collect()
+}
+
+/// One farmer-to-miller grain exchange at the marketplace on a walking headless economy:
+/// the farmer and miller stand beside their crates at x = 6 and x = -6, the stall is at
+/// z = 5, and the farmer holds the grain the waiting miller is queued for. Tests add the
+/// movement systems they exercise (after `advance_actor_tasks`) before calling `run`.
+pub struct GrainCourier {
+    pub app: App,
+    pub farmer: Entity,
+    pub miller: Entity,
+    quantity: u32,
+}
+
+impl GrainCourier {
+    pub fn new(quantity: u32) -> Self {
+        let (mut app, actors) = headless_economy_app();
+        let mut clock = SimulationClock::new(1.0);
+        clock.tick(Duration::from_secs_f32(0.1));
+        app.insert_resource(clock).add_plugins(TransformPlugin);
+
+        spawn_prop(&mut app, Vec3::new(0.0, 0.25, 5.0), Marketplace);
+        let farmer = actors[&Profession::Farmer];
+        let miller = actors[&Profession::Miller];
+        for (entity, profession, x) in [
+            (farmer, Profession::Farmer, 6.0),
+            (miller, Profession::Miller, -6.0),
+        ] {
+            let crate_entity = spawn_prop(
+                &mut app,
+                Vec3::new(x, 0.25, 0.0),
+                ProfessionCrate { profession },
+            );
+            app.world_mut()
+                .resource_mut::<ProfessionCrateRegistry>()
+                .insert(profession, crate_entity);
+            app.world_mut().entity_mut(entity).insert((
+                Transform::from_xyz(x * 0.8, 1.0, 0.0),
+                NpcLocomotion::default(),
+                MoverCollider::new(0.3, 0.8),
+            ));
+        }
+
+        app.world_mut()
+            .get_mut::<Inventory>(farmer)
+            .unwrap()
+            .add_good(TradeGood::Grain, quantity);
+        let miller_id = app.world().get::<Identity>(miller).unwrap().id;
+        queue_only(
+            &mut app,
+            farmer,
+            vec![ActorTask::Deliver {
+                good: TradeGood::Grain,
+                quantity,
+                target: Profession::Miller,
+                recipient: Some(miller_id),
+            }],
+        );
+        queue_only(
+            &mut app,
+            miller,
+            vec![ActorTask::WaitForGood {
+                good: TradeGood::Grain,
+                quantity,
+            }],
+        );
+        Self {
+            app,
+            farmer,
+            miller,
+            quantity,
+        }
+    }
+
+    /// Updates until every queue is empty or `max_frames` pass, calling `each_frame` after
+    /// every update. Returns the frames run and whether the miller got all the grain.
+    pub fn run(&mut self, max_frames: u32, mut each_frame: impl FnMut(&App)) -> (u32, bool) {
+        let mut frames = 0;
+        while frames < max_frames && !self.app.world().resource::<ActorTaskQueues>().is_empty() {
+            self.app.update();
+            frames += 1;
+            each_frame(&self.app);
+        }
+        let delivered = self.app.world().resource::<ActorTaskQueues>().is_empty()
+            && self
+                .app
+                .world()
+                .get::<Inventory>(self.miller)
+                .unwrap()
+                .quantity_of(TradeGood::Grain)
+                == self.quantity;
+        (frames, delivered)
+    }
+}
+
+/// A farmer with one grain (and its crate placeholder) queued to deliver it to a miller
+/// who waits for it, with negotiation on and placeholders synced after each tick.
+pub fn negotiated_grain_app(miller_motivation: NpcMotivation) -> (App, Entity, Entity) {
+    let (mut app, actors) = headless_economy_app();
+    app.init_resource::<Assets<Mesh>>()
+        .init_resource::<Assets<StandardMaterial>>()
+        .init_resource::<TradeGoodPlaceholderRegistry>()
+        .init_resource::<TradeGoodPlaceholderVisuals>()
+        .init_resource::<PlaceholderStackConfig>()
+        .init_resource::<CarriedGoodsRegistry>()
+        .init_resource::<NpcIndex>()
+        .add_systems(
+            Update,
+            (index_npcs, sync_carried_goods, sync_trade_good_placeholders)
+                .chain()
+                .after(advance_actor_tasks),
+        );
+    app.world_mut()
+        .resource_mut::<EconomyRegistry>()
+        .set_negotiation_for_tests(NegotiationConfig {
+            enabled: true,
+            ..NegotiationConfig::default()
+        });
+
+    let farmer = actors[&Profession::Farmer];
+    let miller = actors[&Profession::Miller];
+    for (profession, x) in [(Profession::Farmer, 6.0), (Profession::Miller, -6.0)] {
+        let crate_entity = spawn_prop(
+            &mut app,
+            Vec3::new(x, 0.25, 0.0),
+            ProfessionCrate { profession },
+        );
+        app.world_mut()
+            .resource_mut::<ProfessionCrateRegistry>()
+            .insert(profession, crate_entity);
+    }
+    app.world_mut().entity_mut(miller).insert(miller_motivation);
+    let farmer_id = app.world().get::<Identity>(farmer).unwrap().id;
+    let miller_id = app.world().get::<Identity>(miller).unwrap().id;
+    let change = app
+        .world_mut()
+        .get_mut::<Inventory>(farmer)
+        .unwrap()
+        .add_good(TradeGood::Grain, 1)
+        .unwrap();
+    app.world_mut()
+        .write_message(InventoryChangedEvent::from_change(farmer_id, 0, change));
+
+    queue_only(
+        &mut app,
+        farmer,
+        vec![ActorTask::Deliver {
+            good: TradeGood::Grain,
+            quantity: 1,
+            target: Profession::Miller,
+            recipient: Some(miller_id),
+        }],
+    );
+    queue_only(
+        &mut app,
+        miller,
+        vec![ActorTask::WaitForGood {
+            good: TradeGood::Grain,
+            quantity: 1,
+        }],
+    );
+    (app, farmer, miller)
+}
+
+/// Grain placeholder cubes shown on `profession`'s crate.
+pub fn grain_cubes(app: &App, profession: Profession) -> usize {
+    app.world()
+        .resource::<TradeGoodPlaceholderRegistry>()
+        .stack(profession, TradeGood::Grain)
+        .map_or(0, |stack| stack.cubes.len())
+}
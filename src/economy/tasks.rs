@@ -1,9 +1,14 @@
 //! Work order task queues for economy actors.
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeMap, HashMap, VecDeque};
 
 use bevy::prelude::Resource;
 
-use super::components::{Profession, TradeGood};
+use super::{
+    components::{Profession, TradeGood},
+    data::{EconomyRegistry, Recipe},
+    planning::{requests_added_since, SampledRequest},
+};
+use crate::npc::components::NpcId;
 
 #[derive(Debug, Clone)]
 pub enum ActorTask {
@@ -11,19 +16,41 @@ pub enum ActorTask {
         good: TradeGood,
         quantity: u32,
     },
+    /// Run `recipe` as it was resolved at planning time, so a reloaded registry cannot
+    /// change what the task consumes or produces halfway through the day.
     Manufacture {
-        recipe_id: String,
+        recipe: Recipe,
     },
+    /// Hand goods to a `target` worker. The planner names the `recipient` it queued the
+    /// matching wait for; `None` lets the courier pick whoever of `target` needs it most.
     Deliver {
         good: TradeGood,
         quantity: u32,
         target: Profession,
+        recipient: Option<NpcId>,
     },
+    /// Carry goods above the personal keep into the household's shared storage.
+    DepositSurplus,
+    /// Go to the marketplace and wait for `courier` to hand over a delivery. Queued ahead
+    /// of everything else when the courier sets off.
+    MeetAtMarket {
+        courier: NpcId,
+    },
+    /// Walk back to the own crate after a marketplace handoff.
+    ReturnToCrate,
+}
+
+impl ActorTask {
+    /// Tasks that end at the marketplace.
+    pub fn is_market_bound(&self) -> bool {
+        matches!(self, Self::Deliver { .. } | Self::MeetAtMarket { .. })
+    }
 }
 
+/// One task queue per working NPC, so several NPCs can share a profession.
 #[derive(Resource, Debug, Default)]
 pub struct ActorTaskQueues {
-    queues: HashMap<Profession, VecDeque<ActorTask>>,
+    queues: HashMap<NpcId, VecDeque<ActorTask>>,
 }
 
 impl ActorTaskQueues {
@@ -31,35 +58,207 @@ impl ActorTaskQueues {
         self.queues.clear();
     }
 
-    pub fn peek_mut(&mut self, profession: Profession) -> Option<&mut ActorTask> {
-        self.queues
-            .get_mut(&profession)
-            .and_then(VecDeque::front_mut)
+    pub fn peek(&self, npc: NpcId) -> Option<&ActorTask> {
+        self.queues.get(&npc).and_then(VecDeque::front)
     }
 
-    pub fn pop_front(&mut self, profession: Profession) {
-        if let Some(queue) = self.queues.get_mut(&profession) {
+    pub fn pop_front(&mut self, npc: NpcId) {
+        if let Some(queue) = self.queues.get_mut(&npc) {
             queue.pop_front();
             if queue.is_empty() {
-                self.queues.remove(&profession);
+                self.queues.remove(&npc);
             }
         }
     }
 
-    pub fn remaining_tasks(&self, profession: Profession) -> usize {
-        self.queues.get(&profession).map(|q| q.len()).unwrap_or(0)
+    pub fn remaining_tasks(&self, npc: NpcId) -> usize {
+        self.queues.get(&npc).map(|q| q.len()).unwrap_or(0)
+    }
+
+    /// True while any queued `Deliver` task still targets `target`.
+    pub fn has_delivery_for(&self, target: Profession) -> bool {
+        self.queues.values().flatten().any(
+            |task| matches!(task, ActorTask::Deliver { target: pending, .. } if *pending == target),
+        )
+    }
+
+    /// True while `npc` still has any `Deliver` task queued.
+    pub fn has_pending_delivery(&self, npc: NpcId) -> bool {
+        self.queues
+            .get(&npc)
+            .into_iter()
+            .flatten()
+            .any(|task| matches!(task, ActorTask::Deliver { .. }))
+    }
+
+    /// Puts a `MeetAtMarket` for `courier` at the front of `recipient`'s queue unless one is
+    /// already queued.
+    pub fn invite_to_market(&mut self, recipient: NpcId, courier: NpcId) {
+        let queue = self.ensure_queue(recipient);
+        let invited = queue.iter().any(|task| {
+            matches!(task, ActorTask::MeetAtMarket { courier: pending } if *pending == courier)
+        });
+        if !invited {
+            queue.push_front(ActorTask::MeetAtMarket { courier });
+        }
+    }
+
+    /// Sends `npc` back to their crate unless their next task takes them to the market
+    /// again anyway.
+    pub fn queue_return_to_crate(&mut self, npc: NpcId) {
+        if self
+            .peek(npc)
+            .is_some_and(|task| task.is_market_bound() || matches!(task, ActorTask::ReturnToCrate))
+        {
+            return;
+        }
+        self.ensure_queue(npc).push_front(ActorTask::ReturnToCrate);
+    }
+
+    /// Units of `good` that `npc`'s queued `WaitForGood` tasks still expect.
+    pub fn awaited_quantity(&self, npc: NpcId, good: TradeGood) -> u32 {
+        self.queues
+            .get(&npc)
+            .into_iter()
+            .flatten()
+            .map(|task| match task {
+                ActorTask::WaitForGood {
+                    good: awaited,
+                    quantity,
+                } if *awaited == good => *quantity,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    /// True while one of `npc`'s queued recipes consumes `good`.
+    pub fn has_recipe_consuming(&self, npc: NpcId, good: TradeGood) -> bool {
+        self.queues.get(&npc).into_iter().flatten().any(|task| {
+            matches!(task, ActorTask::Manufacture { recipe }
+                if recipe.consumes.iter().any(|input| input.good == good))
+        })
+    }
+
+    /// Stops `npc` waiting for `quantity` units of `good`, shrinking or dropping their
+    /// `WaitForGood` tasks front to back.
+    pub fn cancel_awaited(&mut self, npc: NpcId, good: TradeGood, quantity: u32) {
+        let Some(queue) = self.queues.get_mut(&npc) else {
+            return;
+        };
+        let mut remaining = quantity;
+        queue.retain_mut(|task| match task {
+            ActorTask::WaitForGood {
+                good: awaited,
+                quantity: waiting,
+            } if *awaited == good && remaining > 0 => {
+                let cancelled = remaining.min(*waiting);
+                remaining -= cancelled;
+                *waiting -= cancelled;
+                *waiting > 0
+            }
+            _ => true,
+        });
+        if queue.is_empty() {
+            self.queues.remove(&npc);
+        }
     }
 
     pub fn is_empty(&self) -> bool {
         self.queues.is_empty()
     }
 
-    pub fn professions(&self) -> impl Iterator<Item = Profession> + '_ {
-        self.queues.keys().copied()
+    /// Every queued task with its owner, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (NpcId, &ActorTask)> {
+        self.queues
+            .iter()
+            .flat_map(|(npc, queue)| queue.iter().map(|task| (*npc, task)))
     }
 
-    pub fn ensure_queue(&mut self, profession: Profession) -> &mut VecDeque<ActorTask> {
-        self.queues.entry(profession).or_default()
+    /// Drops the queues of NPCs `keep` rejects, returning how many were dropped.
+    pub fn retain_npcs(&mut self, mut keep: impl FnMut(NpcId) -> bool) -> usize {
+        let before = self.queues.len();
+        self.queues.retain(|npc, _| keep(*npc));
+        before - self.queues.len()
+    }
+
+    pub fn ensure_queue(&mut self, npc: NpcId) -> &mut VecDeque<ActorTask> {
+        self.queues.entry(npc).or_default()
+    }
+
+    /// Removes every task `keep` rejects, dropping queues left empty. Returns the removed
+    /// tasks with their owners.
+    pub fn remove_tasks(
+        &mut self,
+        mut keep: impl FnMut(NpcId, &ActorTask) -> bool,
+    ) -> Vec<(NpcId, ActorTask)> {
+        let mut removed = Vec::new();
+        for (npc, queue) in &mut self.queues {
+            let (kept, dropped): (VecDeque<_>, VecDeque<_>) =
+                queue.drain(..).partition(|task| keep(*npc, task));
+            *queue = kept;
+            removed.extend(dropped.into_iter().map(|task| (*npc, task)));
+        }
+        self.queues.retain(|_, queue| !queue.is_empty());
+        removed
+    }
+
+    /// Drops tasks `registry` can no longer honour: manufacturing a recipe that is gone or
+    /// moved to another profession, and waiting for or delivering a good nothing produces.
+    pub fn revalidate_against(&mut self, registry: &EconomyRegistry) -> TaskRevalidation {
+        let removed = self.remove_tasks(|_, task| task_still_valid(task, registry));
+        let mut dropped = BTreeMap::new();
+        for (_, task) in &removed {
+            *dropped.entry(task_label(task)).or_default() += 1;
+        }
+        TaskRevalidation { dropped }
+    }
+}
+
+fn task_still_valid(task: &ActorTask, registry: &EconomyRegistry) -> bool {
+    match task {
+        ActorTask::Manufacture { recipe } => registry
+            .recipe(&recipe.id)
+            .is_some_and(|current| current.actor == recipe.actor),
+        ActorTask::WaitForGood { good, .. } | ActorTask::Deliver { good, .. } => {
+            registry.recipe_for_output(*good).is_some()
+        }
+        ActorTask::DepositSurplus | ActorTask::MeetAtMarket { .. } | ActorTask::ReturnToCrate => {
+            true
+        }
+    }
+}
+
+fn task_label(task: &ActorTask) -> String {
+    match task {
+        ActorTask::WaitForGood { good, .. } => format!("wait for {}", good.label()),
+        ActorTask::Manufacture { recipe } => format!("manufacture {}", recipe.id),
+        ActorTask::Deliver { good, target, .. } => {
+            format!("deliver {} to {}", good.label(), target.label())
+        }
+        ActorTask::DepositSurplus => "deposit surplus".to_string(),
+        ActorTask::MeetAtMarket { courier } => format!("meet {courier} at the marketplace"),
+        ActorTask::ReturnToCrate => "return to crate".to_string(),
+    }
+}
+
+/// What `ActorTaskQueues::revalidate_against` removed, counted by task description.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct TaskRevalidation {
+    pub dropped: BTreeMap<String, usize>,
+}
+
+impl TaskRevalidation {
+    pub fn total(&self) -> usize {
+        self.dropped.values().sum()
+    }
+
+    /// "2x deliver grain crate to miller, 1x manufacture grain_harvest" style summary for logs.
+    pub fn summary(&self) -> String {
+        self.dropped
+            .iter()
+            .map(|(label, count)| format!("{count}x {label}"))
+            .collect::<Vec<_>>()
+            .join(", ")
     }
 }
 
@@ -67,4 +266,46 @@ impl ActorTaskQueues {
 pub struct EconomyDayState {
     pub last_planned_day: Option<u64>,
     pub last_dependency_evaluation_day: Option<u64>,
+    /// Requests scheduled for `last_planned_day`, so a mid-day config change only adds
+    /// what is new.
+    pub planned_requests: Vec<SampledRequest>,
+    /// Day fraction before which deliveries wait at the courier's crate; set on market
+    /// days so exchanges happen while the village is gathered.
+    pub deliveries_open_at: Option<f32>,
+    /// Today's deliveries a recipient declined, by requesting profession and good.
+    pub unmet_requests: Vec<SampledRequest>,
+    /// Today's delivery tasks that finished, by target profession and good.
+    pub delivered_requests: Vec<SampledRequest>,
+    /// Professions whose production today's scarcity suspended.
+    pub suppressed: Vec<Profession>,
+    /// Day to plan again without waiting for a day change; set by the developer
+    /// console's `plan`. The planned day keeps its requests and scarcity.
+    pub replan_requested: Option<u64>,
+    /// Calendar season `last_planned_day` falls in; manufacturing yields follow it.
+    pub season: Option<String>,
+    /// Recipe id to the season number its off-season was last announced for, so each
+    /// season is mentioned once rather than every day.
+    pub off_season_announced: HashMap<String, u64>,
+}
+
+impl EconomyDayState {
+    pub fn request_replan(&mut self, day: u64) {
+        self.replan_requested = Some(day);
+    }
+
+    /// The part of `planned_requests` not yet delivered or declined today.
+    pub fn outstanding_requests(&self) -> Vec<SampledRequest> {
+        let settled: Vec<SampledRequest> = self
+            .delivered_requests
+            .iter()
+            .chain(&self.unmet_requests)
+            .copied()
+            .collect();
+        requests_added_since(&settled, &self.planned_requests)
+    }
+
+    pub fn deliveries_open(&self, time_of_day: f32) -> bool {
+        self.deliveries_open_at
+            .is_none_or(|opens| time_of_day >= opens)
+    }
 }
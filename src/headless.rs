@@ -0,0 +1,673 @@
+//! Headless app builder and multi-day integration tests covering the economy, dialogue,
+//! and motivation loop together.
+use std::{collections::HashMap, time::Duration};
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+
+use crate::{
+    core::{schedule::FramePhase, CorePlugin},
+    dialogue::{
+        broker::{DialogueBroker, DialogueProviderKind},
+        chronicle::ChronicleAppExt,
+        errors::DialogueError,
+        events::DialogueResponseEvent,
+        player_memory::PlayerMemory,
+        queue::DialogueRateLimitConfig,
+        router::DialogueProviderRouter,
+        status::DialogueConnectionState,
+        telemetry::DialogueTelemetryLog,
+        types::{DialogueRequest, DialogueRequestId, DialogueResponse},
+        DialoguePlugin,
+    },
+    economy::{
+        components::{Profession, TradeGood},
+        events::{ProfessionDependencyUpdateEvent, TradeCompletedEvent, TradeReason},
+        EconomyPlugin,
+    },
+    npc::{
+        components::{Identity, NpcId},
+        motivation::{MotivationConfig, NpcMotivation},
+        NpcPlugin,
+    },
+    world::{
+        time::{
+            advance_world_clock, announce_day_change, announce_season_change,
+            distill_season_change, DayChangedEvent, SeasonChangedEvent, WorldClock,
+            WorldTimeSettings,
+        },
+        world_event::{
+            advance_world_event, distill_market_transition, MarketDayConfig, WorldEvent,
+            WorldEventTransition,
+        },
+    },
+};
+
+/// Fixed frame step fed to `Time`, so runs are independent of wall-clock speed.
+const HEADLESS_FRAME: Duration = Duration::from_millis(100);
+/// Long enough for every courier to walk its rounds, short enough to keep tests quick.
+const HEADLESS_SECONDS_PER_DAY: f32 = 90.0;
+const HEADLESS_TELEMETRY_FILE: &str = "thegame_headless_dialogue_history.jsonl";
+const STUB_REPLY: &str = "Good doing business with you.";
+
+/// Answers every request immediately with a fixed line.
+struct StubDialogueBroker;
+
+impl DialogueBroker for StubDialogueBroker {
+    fn provider_kind(&self) -> DialogueProviderKind {
+        DialogueProviderKind::OpenAi
+    }
+
+    fn connection_state(&self) -> DialogueConnectionState {
+        DialogueConnectionState::Fallback
+    }
+
+    fn process(
+        &self,
+        request_id: DialogueRequestId,
+        request: &DialogueRequest,
+    ) -> Result<DialogueResponse, DialogueError> {
+        Ok(DialogueResponse::new(
+            request_id,
+            self.provider_kind(),
+            request.speaker,
+            request.target,
+            STUB_REPLY,
+        ))
+    }
+}
+
+/// Builds the simulation without a window or renderer: `MinimalPlugins`, the real Core,
+/// Dialogue, Economy, and Npc plugins, a stub broker, and a shortened world day.
+pub fn build_headless_app() -> App {
+    let mut settings = WorldTimeSettings::load_or_default();
+    settings.seconds_per_day = HEADLESS_SECONDS_PER_DAY;
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .insert_resource(TimeUpdateStrategy::ManualDuration(HEADLESS_FRAME))
+        .init_resource::<Assets<Mesh>>()
+        .init_resource::<Assets<StandardMaterial>>()
+        .init_resource::<ButtonInput<KeyCode>>()
+        .insert_resource(settings)
+        .insert_resource(WorldClock::new())
+        .insert_resource(MarketDayConfig::default())
+        .init_resource::<WorldEvent>()
+        .add_message::<WorldEventTransition>()
+        .add_message::<DayChangedEvent>()
+        .add_message::<SeasonChangedEvent>()
+        .add_systems(
+            Update,
+            (
+                advance_world_clock,
+                announce_day_change.after(advance_world_clock),
+                announce_season_change.after(announce_day_change),
+                advance_world_event.after(advance_world_clock),
+            )
+                .in_set(FramePhase::SimTick),
+        )
+        .add_plugins((
+            CorePlugin::default().with_max_frame_delta(HEADLESS_FRAME.as_secs_f32()),
+            DialoguePlugin,
+            EconomyPlugin,
+            NpcPlugin,
+        ))
+        .add_chronicle_distiller(distill_market_transition)
+        .add_chronicle_distiller(distill_season_change)
+        .insert_resource(DialogueProviderRouter::new(Box::new(StubDialogueBroker)))
+        .insert_resource(PlayerMemory::default())
+        .insert_resource(DialogueRateLimitConfig {
+            global_cooldown_seconds: 0.0,
+            per_npc_cooldown_seconds: 0.0,
+            ..Default::default()
+        })
+        .insert_resource(DialogueTelemetryLog::new(
+            std::env::temp_dir().join(HEADLESS_TELEMETRY_FILE),
+        ));
+    app
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::{
+        dialogue::{broker::openai::render_prompt, prompts::PromptTemplates},
+        economy::{data::EconomyRegistry, skills::Skill},
+    };
+
+    const MAX_FRAMES: usize = 20_000;
+    const DAYS: u64 = 3;
+
+    #[derive(Default)]
+    struct Observed {
+        trades: Vec<TradeCompletedEvent>,
+        dependency_updates: Vec<ProfessionDependencyUpdateEvent>,
+        responses: Vec<(NpcId, Option<NpcId>)>,
+    }
+
+    fn run_days(app: &mut App, days: u64) -> Observed {
+        let world = app.world();
+        let mut trade_cursor = world
+            .resource::<Messages<TradeCompletedEvent>>()
+            .get_cursor();
+        let mut dependency_cursor = world
+            .resource::<Messages<ProfessionDependencyUpdateEvent>>()
+            .get_cursor();
+        let mut response_cursor = world
+            .resource::<Messages<DialogueResponseEvent>>()
+            .get_cursor();
+        let mut observed = Observed::default();
+
+        for _ in 0..MAX_FRAMES {
+            app.update();
+            let world = app.world();
+            observed.trades.extend(
+                trade_cursor
+                    .read(world.resource::<Messages<TradeCompletedEvent>>())
+                    .cloned(),
+            );
+            observed.dependency_updates.extend(
+                dependency_cursor
+                    .read(world.resource::<Messages<ProfessionDependencyUpdateEvent>>())
+                    .cloned(),
+            );
+            observed.responses.extend(
+                response_cursor
+                    .read(world.resource::<Messages<DialogueResponseEvent>>())
+                    .map(|event| (event.response.speaker, event.response.target)),
+            );
+
+            let min = app.world().resource::<MotivationConfig>().defaults.min;
+            let world = app.world_mut();
+            for motivation in world.query::<&NpcMotivation>().iter(world) {
+                let dopamine = motivation.dopamine();
+                assert!(
+                    dopamine.is_finite() && dopamine >= min,
+                    "dopamine out of range: {dopamine}"
+                );
+            }
+
+            if app.world().resource::<WorldClock>().day_count() >= days {
+                return observed;
+            }
+        }
+        panic!("world clock never reached day {days} within {MAX_FRAMES} frames");
+    }
+
+    fn farmer_grain_on_day_zero(mut app: App, starting_xp: u32) -> u32 {
+        app.update();
+        let curve = app
+            .world()
+            .resource::<EconomyRegistry>()
+            .skill_curve()
+            .clone();
+        let world = app.world_mut();
+        let mut farmers = world.query::<(&Identity, &Profession, &mut Skill)>();
+        let (farmer, _, mut skill) = farmers
+            .iter_mut(world)
+            .find(|(_, profession, _)| **profession == Profession::Farmer)
+            .expect("a farmer is staffed");
+        let farmer = farmer.id;
+        skill.gain(Profession::Farmer, starting_xp, &curve);
+
+        run_days(&mut app, 1)
+            .trades
+            .iter()
+            .filter(|trade| {
+                trade.day == 0
+                    && trade.reason == TradeReason::Production
+                    && trade.from == Some(farmer)
+                    && trade.good == TradeGood::Grain
+            })
+            .map(|trade| trade.quantity)
+            .sum()
+    }
+
+    #[test]
+    fn a_skilled_farmer_outproduces_a_fresh_one() {
+        let fresh = farmer_grain_on_day_zero(build_headless_app(), 0);
+        let skilled = farmer_grain_on_day_zero(build_headless_app(), 400);
+        assert!(fresh > 0, "the fresh farmer harvests something");
+        assert!(
+            skilled > fresh,
+            "skilled farmer made {skilled} grain, fresh farmer {fresh}"
+        );
+    }
+
+    #[test]
+    fn despawned_npcs_leave_no_trace_in_trackers_or_registries() {
+        use crate::{
+            dialogue::queue::{DialogueRateLimitState, DialogueSpeakerProfiles},
+            economy::{resources::EconomyActorCache, tasks::ActorTaskQueues},
+            npc::components::ActiveConversations,
+            ui::dialogue_panel::{
+                components::DialoguePanelTracker, systems::forget_despawned_npc_panels,
+            },
+        };
+
+        let mut app = build_headless_app();
+        app.init_resource::<DialoguePanelTracker>()
+            .add_systems(Update, forget_despawned_npc_panels);
+        app.update();
+        app.update();
+
+        let world = app.world_mut();
+        let (entity, npc) = world
+            .query_filtered::<(Entity, &Identity), With<Profession>>()
+            .iter(world)
+            .map(|(entity, identity)| (entity, identity.id))
+            .next()
+            .expect("a working NPC");
+        assert!(world
+            .resource::<DialogueSpeakerProfiles>()
+            .get(npc)
+            .is_some());
+        world
+            .resource_mut::<DialogueRateLimitState>()
+            .npc_remaining
+            .insert(npc, 30.0);
+        world
+            .resource_mut::<DialoguePanelTracker>()
+            .by_npc
+            .insert(npc, Entity::PLACEHOLDER);
+        world
+            .resource_mut::<ActiveConversations>()
+            .try_reserve(DialogueRequestId::new(u64::MAX), &[npc]);
+
+        world.despawn(entity);
+        app.update();
+        app.update();
+
+        let world = app.world();
+        assert!(!world
+            .resource::<DialogueRateLimitState>()
+            .npc_remaining
+            .contains_key(&npc));
+        assert!(world
+            .resource::<DialogueSpeakerProfiles>()
+            .get(npc)
+            .is_none());
+        assert!(!world
+            .resource::<DialoguePanelTracker>()
+            .by_npc
+            .contains_key(&npc));
+        assert!(!world
+            .resource::<ActiveConversations>()
+            .is_in_conversation(npc));
+        assert!(world.resource::<EconomyActorCache>().get(npc).is_none());
+        assert_eq!(world.resource::<ActorTaskQueues>().remaining_tasks(npc), 0);
+    }
+
+    #[test]
+    fn fast_forward_shortens_simulation_timers_but_not_real_ones() {
+        use crate::{
+            core::plugin::SimulationClock,
+            dialogue::events::DialogueRequestedEvent,
+            npc::components::InConversation,
+            ui::{
+                dialogue_panel::{
+                    components::{DialoguePanel, DialoguePanelSettings, DialoguePanelTracker},
+                    systems::update_dialogue_panel,
+                },
+                layout::UiLayout,
+            },
+        };
+
+        let mut app = build_headless_app();
+        app.insert_resource(DialoguePanelSettings::default())
+            .init_resource::<DialoguePanelTracker>()
+            .init_resource::<UiLayout>()
+            .add_systems(Update, update_dialogue_panel);
+        app.update();
+        app.world_mut()
+            .resource_mut::<SimulationClock>()
+            .set_time_scale(10.0);
+
+        let chatter = NpcId::new(9_001);
+        let greeter = NpcId::new(9_003);
+        for (npc, name) in [
+            (chatter, "Alric"),
+            (NpcId::new(9_002), "Bryn"),
+            (greeter, "Cedric"),
+        ] {
+            app.world_mut().spawn(Identity::new(npc, name, 30.0));
+        }
+        let panel = app
+            .world_mut()
+            .spawn((
+                DialoguePanel::new(greeter, "Cedric".into(), "Hello.".into(), 5.0, 1.0, 0.35),
+                Node::default(),
+                BackgroundColor::default(),
+                BorderColor::default(),
+            ))
+            .id();
+        for (id, speaker, target) in [
+            (u64::MAX - 1, chatter, NpcId::new(9_002)),
+            (u64::MAX, greeter, NpcId::player()),
+        ] {
+            app.world_mut().write_message(DialogueRequestedEvent {
+                request_id: DialogueRequestId::new(id),
+                speaker,
+                target: Some(target),
+            });
+        }
+
+        let talking = |app: &mut App, npc: NpcId| {
+            let world = app.world_mut();
+            world
+                .query::<(&Identity, &InConversation)>()
+                .iter(world)
+                .any(|(identity, _)| identity.id == npc)
+        };
+        app.update();
+        assert!(talking(&mut app, chatter) && talking(&mut app, greeter));
+
+        // Each 0.1 s frame is a full simulated second at 10x, so the 8 s NPC conversation
+        // ends in about 8 frames. The player conversation and panel still count real time.
+        for _ in 0..10 {
+            app.update();
+        }
+        assert!(
+            !talking(&mut app, chatter),
+            "simulation timeout runs 10x faster"
+        );
+        assert!(
+            talking(&mut app, greeter),
+            "player conversation keeps real time"
+        );
+        assert!(
+            app.world().get_entity(panel).is_ok(),
+            "dialogue panel keeps real time"
+        );
+
+        for _ in 0..80 {
+            app.update();
+        }
+        assert!(!talking(&mut app, greeter));
+        assert!(app.world().get_entity(panel).is_err());
+    }
+
+    #[test]
+    fn three_headless_days_trade_talk_and_stay_motivated() {
+        let mut app = build_headless_app();
+        let observed = run_days(&mut app, DAYS);
+
+        let mut npc_of = HashMap::new();
+        let world = app.world_mut();
+        let mut actors = world.query::<(&Identity, &Profession)>();
+        for (identity, profession) in actors.iter(world) {
+            npc_of.insert(*profession, identity.id);
+        }
+        assert_eq!(
+            npc_of.len(),
+            Profession::ALL.len(),
+            "every profession staffed"
+        );
+
+        let exchanges = [
+            (Profession::Farmer, Profession::Miller, TradeGood::Grain),
+            (Profession::Miller, Profession::Blacksmith, TradeGood::Flour),
+            (Profession::Blacksmith, Profession::Farmer, TradeGood::Tools),
+        ];
+        for day in 0..DAYS {
+            for (from, to, good) in exchanges {
+                assert!(
+                    observed.trades.iter().any(|trade| trade.day == day
+                        && trade.reason == TradeReason::Exchange
+                        && trade.from == Some(npc_of[&from])
+                        && trade.to == Some(npc_of[&to])
+                        && trade.good == good),
+                    "day {day}: expected {} -> {} {}",
+                    from.label(),
+                    to.label(),
+                    good.label()
+                );
+            }
+
+            for npc in npc_of.values() {
+                let updates = observed
+                    .dependency_updates
+                    .iter()
+                    .filter(|update| update.day == day && update.npc == *npc)
+                    .count();
+                assert_eq!(updates, 1, "day {day}: one dependency update for {npc}");
+            }
+        }
+
+        for (from, to, _) in exchanges {
+            let pair = (npc_of[&from], Some(npc_of[&to]));
+            assert!(
+                observed.responses.contains(&pair),
+                "no dialogue response for {} -> {}",
+                from.label(),
+                to.label()
+            );
+        }
+    }
+
+    #[test]
+    fn a_stub_reply_is_traced_from_queue_to_panel() {
+        use crate::{
+            dialogue::{
+                probe::build_probe_request,
+                queue::DialogueRequestQueue,
+                telemetry::{DialogueTelemetry, DialogueTelemetryEvent},
+                trace::{DialogueRequestTrace, TracePhase},
+                types::DialogueTopicHint,
+            },
+            ui::{
+                dialogue_panel::{
+                    components::{DialoguePanelSettings, DialoguePanelTracker},
+                    systems::spawn_dialogue_panel,
+                },
+                layout::UiLayout,
+            },
+        };
+
+        let mut app = build_headless_app();
+        app.insert_resource(DialoguePanelSettings::default())
+            .init_resource::<DialoguePanelTracker>()
+            .init_resource::<UiLayout>()
+            .add_systems(
+                Update,
+                spawn_dialogue_panel.in_set(FramePhase::Presentation),
+            );
+        app.update();
+
+        let world = app.world_mut();
+        let speaker = world
+            .query_filtered::<&Identity, With<Profession>>()
+            .iter(world)
+            .next()
+            .expect("a working NPC")
+            .clone();
+        let id = world
+            .resource_mut::<DialogueRequestQueue>()
+            .enqueue(build_probe_request(&speaker, DialogueTopicHint::Status, 0));
+
+        for _ in 0..200 {
+            app.update();
+            let traced = app.world().resource::<DialogueRequestTrace>().get(id);
+            if traced.is_some_and(|trace| trace.phases().any(|phase| phase == TracePhase::Rendered))
+            {
+                break;
+            }
+        }
+
+        let world = app.world();
+        let trace = world
+            .resource::<DialogueRequestTrace>()
+            .get(id)
+            .expect("the probe is traced");
+        assert!(trace.is_finished());
+        assert_eq!(
+            trace.phases().collect::<Vec<_>>(),
+            [
+                TracePhase::Enqueued,
+                TracePhase::Dispatched { attempt: 1 },
+                TracePhase::Completed { attempt: 1 },
+                TracePhase::Rendered,
+            ]
+        );
+        let reported = world
+            .resource::<DialogueTelemetry>()
+            .records()
+            .filter_map(|record| match &record.event {
+                DialogueTelemetryEvent::Trace(trace) if trace.id() == id => Some(trace),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(reported.len(), 1, "one trace record when the request ends");
+        assert_eq!(
+            reported[0].phases().last(),
+            Some(TracePhase::Completed { attempt: 1 })
+        );
+    }
+
+    type RecordedPrompts = Arc<Mutex<Vec<(DialogueRequestId, String)>>>;
+
+    /// Replies like `StubDialogueBroker`, keeping the full prompt each request would send.
+    struct PromptRecordingBroker(RecordedPrompts);
+
+    impl DialogueBroker for PromptRecordingBroker {
+        fn provider_kind(&self) -> DialogueProviderKind {
+            StubDialogueBroker.provider_kind()
+        }
+
+        fn connection_state(&self) -> DialogueConnectionState {
+            StubDialogueBroker.connection_state()
+        }
+
+        fn process(
+            &self,
+            request_id: DialogueRequestId,
+            request: &DialogueRequest,
+        ) -> Result<DialogueResponse, DialogueError> {
+            let prompt = render_prompt(&PromptTemplates::default(), request);
+            self.0.lock().unwrap().push((request_id, prompt));
+            StubDialogueBroker.process(request_id, request)
+        }
+    }
+
+    #[test]
+    fn a_season_change_reaches_the_next_npc_prompt() {
+        use crate::dialogue::{
+            probe::build_probe_request, queue::DialogueRequestQueue, types::DialogueTopicHint,
+        };
+
+        let prompts = RecordedPrompts::default();
+        let mut app = build_headless_app();
+        app.insert_resource(DialogueProviderRouter::new(Box::new(
+            PromptRecordingBroker(prompts.clone()),
+        )));
+        app.update();
+
+        let settings = app.world().resource::<WorldTimeSettings>();
+        let summer = (1..)
+            .find(|day| settings.season_number(*day) != settings.season_number(0))
+            .unwrap();
+        app.world_mut()
+            .resource_mut::<WorldClock>()
+            .skip_days(summer);
+        app.update();
+        let world = app.world_mut();
+        let speaker = world
+            .query_filtered::<&Identity, With<Profession>>()
+            .iter(world)
+            .next()
+            .expect("a working NPC")
+            .clone();
+        let id = world
+            .resource_mut::<DialogueRequestQueue>()
+            .enqueue(build_probe_request(&speaker, DialogueTopicHint::Status, 0));
+
+        let sent = (0..200).find_map(|_| {
+            app.update();
+            prompts
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|(request, _)| *request == id)
+                .map(|(_, prompt)| prompt.clone())
+        });
+        let prompt = sent.expect("the probe reaches the broker");
+        assert!(
+            prompt.contains("Around the village: Summer came to the village"),
+            "{prompt}"
+        );
+    }
+
+    #[derive(Resource, Default)]
+    struct PhaseLog(Vec<FramePhase>);
+
+    #[test]
+    fn frame_phases_run_in_order_regardless_of_registration() {
+        let mut app = build_headless_app();
+        app.init_resource::<PhaseLog>();
+        // Registered back to front, so only the set ordering can put them in order.
+        for phase in [
+            FramePhase::Presentation,
+            FramePhase::DialoguePoll,
+            FramePhase::NpcUpdate,
+            FramePhase::DialogueDispatch,
+            FramePhase::EconomyExecute,
+            FramePhase::SimTick,
+        ] {
+            app.add_systems(
+                Update,
+                (move |mut log: ResMut<PhaseLog>| log.0.push(phase)).in_set(phase),
+            );
+        }
+
+        app.update();
+
+        assert_eq!(
+            app.world().resource::<PhaseLog>().0,
+            vec![
+                FramePhase::SimTick,
+                FramePhase::EconomyExecute,
+                FramePhase::DialogueDispatch,
+                FramePhase::NpcUpdate,
+                FramePhase::DialoguePoll,
+                FramePhase::Presentation,
+            ]
+        );
+    }
+
+    /// Ten simulated minutes with faults firing often. Slow, so run it on demand with
+    /// `cargo test --features chaos -- --ignored chaos`.
+    #[cfg(feature = "chaos")]
+    #[test]
+    #[ignore]
+    fn the_village_survives_ten_minutes_of_chaos() {
+        use crate::core::chaos::{ChaosAppExt, ChaosLog, ChaosSettings};
+
+        let mut app = build_headless_app();
+        app.insert_resource(ChaosSettings {
+            enabled: true,
+            seed: 11,
+            fault_chance: 0.02,
+            report_window_frames: 120,
+        })
+        .register_chaos_fault("jump_clock", crate::world::chaos::jump_world_clock);
+
+        let frames = 10 * 60 * 1000 / HEADLESS_FRAME.as_millis() as usize;
+        for _ in 0..frames {
+            app.update();
+        }
+
+        let log = app.world().resource::<ChaosLog>();
+        assert!(
+            log.injected().len() > 10,
+            "{}",
+            log.summary(app.world().resource::<ChaosSettings>().seed)
+        );
+        let npcs = app
+            .world_mut()
+            .query::<&crate::npc::components::Identity>()
+            .iter(app.world())
+            .count();
+        assert!(npcs > 0, "respawned NPCs stay in the village");
+    }
+}
@@ -0,0 +1,117 @@
+//! Library half of the game. `main.rs` builds the Bevy app from the plugins re-exported
+//! here; external tools (balancing scripts, snapshot and prompt reviewers) link against
+//! `prelude`, which gathers the config loaders, dialogue and economy data types, and pure
+//! helpers that do not need a running app.
+//!
+//! The feature modules stay private. Systems, components, and plugin wiring can change
+//! freely; anything a tool depends on is re-exported through `prelude` on purpose.
+#![deny(missing_docs, unnameable_types)]
+
+mod core;
+mod dialogue;
+mod economy;
+#[cfg(test)]
+mod headless;
+mod npc;
+mod player;
+mod snapshot;
+mod ui;
+mod world;
+
+pub use crate::{
+    core::{preset::handle_preset_args, CorePlugin},
+    dialogue::DialoguePlugin,
+    economy::EconomyPlugin,
+    npc::NpcPlugin,
+    player::PlayerPlugin,
+    snapshot::SnapshotPlugin,
+    ui::UiPlugin,
+    world::WorldPlugin,
+};
+
+#[cfg(feature = "chaos")]
+pub use crate::core::chaos::chaos_log_plugin;
+
+pub mod prelude {
+    //! Public surface for tools that link against the game without running it.
+    //!
+    //! ```
+    //! use thegame::prelude::*;
+    //!
+    //! let registry = EconomyRegistry::from_config(EconomyConfig::from_toml_str(
+    //!     r#"
+    //!     [[recipes]]
+    //!     id = "grain_harvest"
+    //!     actor = "farmer"
+    //!     produces = [{ good = "grain", quantity = 2 }]
+    //!     "#,
+    //! )?)?;
+    //! assert_eq!(registry.recipe("grain_harvest").unwrap().produces[0].quantity, 2);
+    //! assert_eq!(format_clock_time(0.5), "12:00");
+    //! # Ok::<(), String>(())
+    //! ```
+
+    // Config loaders and the settings they produce.
+    pub use crate::{
+        economy::{
+            data::{
+                DailyRequest, DailyRequestConfig, EconomyConfig, EconomyRegistry, GoodConfig,
+                MarketplaceConfig, ProductConfig, Recipe, RecipeConfig, RecipeInput, RecipeOutput,
+                ScarcityEvent, ScarcityEventConfig, ECONOMY_CONFIG_PATH,
+            },
+            encumbrance::EncumbranceConfig,
+            fairness::FairnessConfig,
+            negotiation::NegotiationConfig,
+            routing::RoutingConfig,
+            skills::SkillCurve,
+        },
+        npc::motivation::config::{
+            AlcoholConfig, BirthdayConfig, ChatterConfig, ChatterModifiers, DependencyImpactConfig,
+            LeisureConfig, MarketDayRewardConfig, MotivationConfig, MotivationDecay,
+            MotivationDefaults, MotivationGains, MotivationHistoryConfig, MotivationMoodThresholds,
+            PatrolRewardConfig, PlayerTransferConfig, SkillRewardConfig, SleepConfig,
+            SpoilageConfig, CONFIG_PATH as MOTIVATION_CONFIG_PATH,
+        },
+        world::time::{WorldTimeSettings, CONFIG_PATH as TIME_CONFIG_PATH},
+    };
+
+    // Economy and NPC data shared by configs, requests, and reports.
+    pub use crate::{
+        economy::{
+            components::{Profession, TradeGood},
+            events::TradeReason,
+        },
+        npc::components::NpcId,
+    };
+
+    // Dialogue requests, replies, failures, telemetry records, and request traces.
+    pub use crate::dialogue::{
+        broker::DialogueProviderKind,
+        budget::ApiBudgetLimit,
+        builder::DialogueRequestBuilder,
+        errors::{
+            DialogueBuildError, DialogueContextSource, DialogueError, DialogueErrorKind,
+            ProviderFailureClass,
+        },
+        events::{ApiBudgetExhaustedEvent, ConversationEndReason, PlayerInteractionEvent},
+        queue::{DialogueQueueDump, ExpiredRequestView, InFlightRequestView, PendingRequestView},
+        status::{DialogueBrokerStatusSnapshot, DialogueConnectionState},
+        telemetry::{DialogueTelemetryEvent, DialogueTelemetryRecord},
+        trace::{RequestTrace, TraceEntry, TracePhase},
+        types::{
+            ContextClock, DialogueContext, DialogueContextEvent, DialoguePriority, DialogueRequest,
+            DialogueRequestId, DialogueResponse, DialogueTopicHint, HearsayContext, RumorFidelity,
+            TradeContext, TradeContextReason, TradeDescriptor, VillageNewsContext,
+        },
+    };
+
+    // Pure helpers: prompt rendering, calendar math, and snapshot comparison.
+    pub use crate::{
+        dialogue::prompts::{PromptTemplates, PromptVariables},
+        snapshot::{
+            canonical::{canonicalize, to_canonical_string},
+            diff::{diff_values, Change, FieldDiff, Tolerances, DEFAULT_EPSILON},
+        },
+        world::time::{format_clock_time, minute_of_day, weekday, DAYS_PER_WEEK},
+    };
+}
@@ -1,37 +1,42 @@
 use std::path::Path;
 
-use bevy::prelude::*;
-
-mod core;
-mod dialogue;
-mod economy;
-mod npc;
-mod player;
-mod ui;
-mod world;
-
-use crate::{
-    core::CorePlugin, dialogue::DialoguePlugin, economy::EconomyPlugin, npc::NpcPlugin,
-    player::PlayerPlugin, ui::UiPlugin, world::WorldPlugin,
+use bevy::{app::PluginGroupBuilder, prelude::*};
+
+use thegame::{
+    CorePlugin, DialoguePlugin, EconomyPlugin, NpcPlugin, PlayerPlugin, SnapshotPlugin, UiPlugin,
+    WorldPlugin,
 };
 
 fn main() {
     load_secrets_env();
+    if let Some(code) = thegame::handle_preset_args(std::env::args().skip(1)) {
+        std::process::exit(code);
+    }
 
     App::new()
         .add_plugins((
-            DefaultPlugins,
+            default_plugins(),
             CorePlugin::default(),
             DialoguePlugin,
             EconomyPlugin,
             WorldPlugin,
             PlayerPlugin, // Player interaction with NPCs
             NpcPlugin,
-            UiPlugin, // After DialoguePlugin to receive DialogueResponseEvent
+            UiPlugin,
+            SnapshotPlugin,
         ))
         .run();
 }
 
+/// `DefaultPlugins`, with the chaos build's warning counter in the log plugin.
+#[allow(clippy::let_and_return)]
+fn default_plugins() -> PluginGroupBuilder {
+    let plugins = DefaultPlugins.build();
+    #[cfg(feature = "chaos")]
+    let plugins = plugins.set(thegame::chaos_log_plugin());
+    plugins
+}
+
 fn load_secrets_env() {
     const SECRETS_FILE: &str = "secrets.env";
 
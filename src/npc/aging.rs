@@ -0,0 +1,320 @@
+//! NPC aging driven by the world calendar, birthday celebrations, and speaker profiles.
+use bevy::prelude::*;
+
+use crate::{
+    dialogue::{
+        chronicle::{ChronicleDraft, ChronicleRoster},
+        queue::{DialogueRequestQueue, DialogueSpeakerProfiles},
+        types::{DialogueContext, DialogueRequest, DialogueTopicHint},
+    },
+    economy::components::Profession,
+    npc::{
+        components::Identity,
+        events::NpcBirthdayEvent,
+        motivation::{state::MotivationReason, MotivationAdjustmentEvent, MotivationConfig},
+        spatial::{NpcIndex, SpatialIndex},
+    },
+    world::time::{DayChangedEvent, WorldTimeSettings},
+};
+
+/// Ages within this distance of a whole year snap to it, absorbing float drift.
+const YEAR_SNAP_EPSILON: f32 = 1e-4;
+const DEFAULT_PROFILE_ROLE: &str = "villager";
+const BIRTHDAY_SIGNIFICANCE: f32 = 0.5;
+
+type ProfileInputsChanged = Or<(Changed<Identity>, Changed<Profession>)>;
+
+/// Result of ageing one NPC across a span of days.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AgeAdvance {
+    pub age_years: f32,
+    /// Each whole age reached, oldest last; one entry per year crossed.
+    pub birthdays: Vec<u32>,
+}
+
+/// Adds `days / days_per_year` to `age_years`, listing every whole year crossed.
+pub fn advance_age(age_years: f32, days: u64, days_per_year: f32) -> AgeAdvance {
+    if days == 0 || days_per_year <= 0.0 {
+        return AgeAdvance {
+            age_years,
+            birthdays: Vec::new(),
+        };
+    }
+
+    let mut new_age = age_years + days as f32 / days_per_year;
+    if (new_age - new_age.round()).abs() < YEAR_SNAP_EPSILON {
+        new_age = new_age.round();
+    }
+
+    let previous_years = age_years.max(0.0).floor() as u32;
+    let new_years = new_age.max(0.0).floor() as u32;
+    AgeAdvance {
+        age_years: new_age,
+        birthdays: (previous_years + 1..=new_years).collect(),
+    }
+}
+
+/// Advances every NPC's age by the days each `DayChangedEvent` spans, catching up after
+/// clock jumps.
+pub fn advance_npc_ages(
+    mut day_changes: MessageReader<DayChangedEvent>,
+    settings: Res<WorldTimeSettings>,
+    mut npcs: Query<&mut Identity>,
+    mut birthdays: MessageWriter<NpcBirthdayEvent>,
+) {
+    let days: u64 = day_changes.read().map(DayChangedEvent::days_elapsed).sum();
+    if days == 0 {
+        return;
+    }
+
+    for mut identity in npcs.iter_mut() {
+        let advance = advance_age(identity.age_years, days, settings.days_per_year);
+        identity.age_years = advance.age_years;
+        for new_age in advance.birthdays {
+            birthdays.write(NpcBirthdayEvent {
+                npc: identity.id,
+                new_age,
+            });
+        }
+    }
+}
+
+/// Queues a birthday Status line and rewards the celebrant plus any NPCs nearby, found
+/// through the `SpatialIndex`.
+pub fn celebrate_npc_birthdays(
+    mut birthdays: MessageReader<NpcBirthdayEvent>,
+    config: Res<MotivationConfig>,
+    npc_index: Res<NpcIndex>,
+    spatial: Res<SpatialIndex>,
+    mut queue: ResMut<DialogueRequestQueue>,
+    mut adjustments: MessageWriter<MotivationAdjustmentEvent>,
+    npcs: Query<(&Identity, &GlobalTransform)>,
+) {
+    for event in birthdays.read() {
+        let Some(celebrant) = npc_index.entity(event.npc) else {
+            continue;
+        };
+        let Ok((identity, transform)) = npcs.get(celebrant) else {
+            continue;
+        };
+
+        adjustments.write(MotivationAdjustmentEvent::new(
+            identity.id,
+            config.birthday.reward,
+            MotivationReason::Birthday,
+        ));
+        queue.enqueue(birthday_request(identity, event.new_age));
+        info!(
+            "{} celebrates turning {} today",
+            identity.display_name, event.new_age
+        );
+
+        let neighbours = spatial
+            .neighbors_within(transform.translation(), config.birthday.neighbour_radius)
+            .into_iter()
+            .filter(|neighbour| neighbour.entity != celebrant);
+        for neighbour in neighbours {
+            adjustments.write(MotivationAdjustmentEvent::new(
+                neighbour.npc,
+                config.birthday.neighbour_reward,
+                MotivationReason::Social,
+            ));
+        }
+    }
+}
+
+/// Keeps dialogue speaker profiles in step with each NPC's age and profession.
+pub fn refresh_speaker_profiles(
+    mut profiles: ResMut<DialogueSpeakerProfiles>,
+    npcs: Query<(&Identity, Option<&Profession>), ProfileInputsChanged>,
+) {
+    for (identity, profession) in npcs.iter() {
+        let role = profession.map_or(DEFAULT_PROFILE_ROLE, |profession| profession.label());
+        profiles.set(identity.id, identity.profile_line(role));
+    }
+}
+
+/// Chronicle line for a birthday, news to everyone but the one celebrating.
+pub fn distill_birthday(
+    event: &NpcBirthdayEvent,
+    roster: &ChronicleRoster,
+) -> Option<ChronicleDraft> {
+    Some(ChronicleDraft::new(
+        format!("{} turned {}", roster.name(event.npc), event.new_age),
+        vec![event.npc],
+        BIRTHDAY_SIGNIFICANCE,
+    ))
+}
+
+fn birthday_request(identity: &Identity, new_age: u32) -> DialogueRequest {
+    let context = DialogueContext {
+        summary: Some(format!(
+            "Today is {}'s birthday; they turn {}.",
+            identity.display_name, new_age
+        )),
+        ..Default::default()
+    };
+    let prompt = format!(
+        "{} celebrates turning {} today and shares the occasion with the village.",
+        identity.id, new_age
+    );
+
+    DialogueRequest::new(
+        identity.id,
+        None,
+        prompt,
+        DialogueTopicHint::Status,
+        context,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        npc::{
+            components::NpcId,
+            spatial::{index_npcs, rebuild_spatial_index},
+        },
+        world::time::{announce_day_change, WorldClock},
+    };
+
+    #[test]
+    fn fractional_years_accumulate_without_birthdays() {
+        let advance = advance_age(24.0, 6, 24.0);
+        assert!((advance.age_years - 24.25).abs() < 1e-5);
+        assert!(advance.birthdays.is_empty());
+
+        let mut age = 24.0;
+        for _ in 0..23 {
+            let step = advance_age(age, 1, 24.0);
+            assert!(step.birthdays.is_empty());
+            age = step.age_years;
+        }
+        let last = advance_age(age, 1, 24.0);
+        assert_eq!(last.age_years, 25.0);
+        assert_eq!(last.birthdays, vec![25]);
+    }
+
+    #[test]
+    fn clock_jumps_yield_one_birthday_per_missed_year() {
+        let advance = advance_age(24.5, 24 * 3, 24.0);
+        assert!((advance.age_years - 27.5).abs() < 1e-4);
+        assert_eq!(advance.birthdays, vec![25, 26, 27]);
+    }
+
+    #[test]
+    fn aging_system_emits_birthday_payloads() {
+        let mut app = App::new();
+        app.insert_resource(WorldClock::new())
+            .insert_resource(WorldTimeSettings::load_or_default())
+            .init_resource::<DialogueSpeakerProfiles>()
+            .add_message::<DayChangedEvent>()
+            .add_message::<NpcBirthdayEvent>()
+            .add_systems(
+                Update,
+                (
+                    announce_day_change,
+                    advance_npc_ages,
+                    refresh_speaker_profiles,
+                )
+                    .chain(),
+            );
+        app.world_mut()
+            .resource_mut::<WorldTimeSettings>()
+            .days_per_year = 2.0;
+        let npc = NpcId::new(4);
+        app.world_mut()
+            .spawn((Identity::new(npc, "Alric", 29.5), Profession::Farmer));
+        app.update();
+        assert_eq!(
+            app.world().resource::<DialogueSpeakerProfiles>().get(npc),
+            Some("a 29-year-old farmer")
+        );
+
+        app.world_mut().resource_mut::<WorldClock>().skip_days(3);
+        app.update();
+
+        let messages = app.world().resource::<Messages<NpcBirthdayEvent>>();
+        let events: Vec<_> = messages.get_cursor().read(messages).cloned().collect();
+        assert_eq!(
+            events,
+            vec![
+                NpcBirthdayEvent { npc, new_age: 30 },
+                NpcBirthdayEvent { npc, new_age: 31 },
+            ]
+        );
+        assert_eq!(
+            app.world().resource::<DialogueSpeakerProfiles>().get(npc),
+            Some("a 31-year-old farmer")
+        );
+    }
+
+    #[test]
+    fn profile_line_picks_article() {
+        let identity = Identity::new(NpcId::new(1), "Bryn", 18.7);
+        assert_eq!(identity.profile_line("miller"), "an 18-year-old miller");
+        let identity = Identity::new(NpcId::new(1), "Bryn", 25.2);
+        assert_eq!(identity.profile_line("farmer"), "a 25-year-old farmer");
+    }
+
+    #[test]
+    fn birthday_rewards_reach_neighbours_within_the_radius() {
+        let mut app = App::new();
+        app.init_resource::<MotivationConfig>()
+            .init_resource::<NpcIndex>()
+            .init_resource::<SpatialIndex>()
+            .init_resource::<DialogueRequestQueue>()
+            .add_message::<NpcBirthdayEvent>()
+            .add_message::<MotivationAdjustmentEvent>()
+            .add_systems(
+                Update,
+                (index_npcs, rebuild_spatial_index, celebrate_npc_birthdays).chain(),
+            );
+        let radius = app
+            .world()
+            .resource::<MotivationConfig>()
+            .birthday
+            .neighbour_radius;
+        for (id, name, x) in [
+            (1, "Alric", 0.0),
+            (2, "Bryn", radius * 0.5),
+            (3, "Cedric", radius + 1.0),
+        ] {
+            let position = Transform::from_xyz(x, 0.0, 0.0);
+            app.world_mut().spawn((
+                Identity::new(NpcId::new(id), name, 30.0),
+                position,
+                GlobalTransform::from(position),
+            ));
+        }
+        app.world_mut().write_message(NpcBirthdayEvent {
+            npc: NpcId::new(1),
+            new_age: 31,
+        });
+        app.update();
+
+        let messages = app
+            .world()
+            .resource::<Messages<MotivationAdjustmentEvent>>();
+        let rewarded: Vec<_> = messages
+            .get_cursor()
+            .read(messages)
+            .map(|event| (event.npc, event.reason))
+            .collect();
+        assert_eq!(
+            rewarded,
+            [
+                (NpcId::new(1), MotivationReason::Birthday),
+                (NpcId::new(2), MotivationReason::Social),
+            ]
+        );
+        assert_eq!(
+            app.world()
+                .resource::<DialogueRequestQueue>()
+                .iter_pending()
+                .count(),
+            1
+        );
+    }
+}
@@ -0,0 +1,491 @@
+//! Village census: population, professions, mood, goods in circulation, and today's trade
+//! and dialogue activity, recomputed every `[census] interval_minutes` of in-game time.
+use std::{collections::HashMap, fs, path::Path};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    core::config::{ConfigDiagnostics, ConfigReloadRequested},
+    dialogue::queue::DialogueRequestQueue,
+    economy::{
+        components::{Inventory, Profession, TradeGood},
+        events::TradeCompletedEvent,
+    },
+    npc::{
+        components::Identity,
+        household::CONFIG_PATH,
+        motivation::{state::NpcMood, NpcMotivation},
+    },
+    world::time::{minute_of_day, WorldClock},
+};
+
+const MINUTES_PER_DAY: u64 = 24 * 60;
+const DEFAULT_INTERVAL_MINUTES: u32 = 60;
+
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+struct RawCensusConfig {
+    #[serde(default)]
+    census: RawCensusSection,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+struct RawCensusSection {
+    interval_minutes: u32,
+}
+
+impl Default for RawCensusSection {
+    fn default() -> Self {
+        Self {
+            interval_minutes: DEFAULT_INTERVAL_MINUTES,
+        }
+    }
+}
+
+/// How often `VillageStats` is recomputed, from the `[census]` section of `config/npcs.toml`.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct CensusSettings {
+    /// In-game minutes between recomputations; at least 1.
+    pub interval_minutes: u32,
+}
+
+impl CensusSettings {
+    /// Reads and parses `config/npcs.toml`.
+    pub fn load() -> Result<Self, String> {
+        let data = fs::read_to_string(Path::new(CONFIG_PATH))
+            .map_err(|err| format!("unable to read file: {err}"))?;
+        let raw = toml::from_str::<RawCensusConfig>(&data)
+            .map_err(|err| format!("invalid npc config: {err}"))?;
+        Ok(raw.into())
+    }
+}
+
+/// Parses the `[census]` section of `data` like `CensusSettings::load`, and returns it
+/// with every default written out, for village presets.
+pub fn explicit_toml(data: &str) -> Result<toml::Table, String> {
+    let raw = toml::from_str::<RawCensusConfig>(data)
+        .map_err(|err| format!("invalid npc config: {err}"))?;
+    toml::Table::try_from(raw).map_err(|err| format!("unable to write npc config: {err}"))
+}
+
+/// The `[census]` defaults `CensusSettings` falls back to, written out.
+pub fn fallback_toml() -> toml::Table {
+    toml::Table::try_from(RawCensusConfig::default()).expect("default census config serializes")
+}
+
+impl Default for CensusSettings {
+    fn default() -> Self {
+        RawCensusConfig::default().into()
+    }
+}
+
+impl From<RawCensusConfig> for CensusSettings {
+    fn from(value: RawCensusConfig) -> Self {
+        Self {
+            interval_minutes: value.census.interval_minutes.max(1),
+        }
+    }
+}
+
+/// Average and extremes of dopamine across NPCs that have motivation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DopamineSummary {
+    pub average: f32,
+    pub min: f32,
+    pub max: f32,
+}
+
+/// Trades and dialogue requests counted since the current day began.
+#[derive(Resource, Debug, Default)]
+pub struct VillageActivity {
+    day: u64,
+    trades: u32,
+    requests_before_today: u64,
+}
+
+impl VillageActivity {
+    /// Starts a new count when `day` differs from the one being counted.
+    fn roll_over(&mut self, day: u64, requests_total: u64) {
+        if day != self.day {
+            self.day = day;
+            self.trades = 0;
+            self.requests_before_today = requests_total;
+        }
+    }
+}
+
+/// Emitted after a recomputation that changed `VillageStats`.
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VillageStatsUpdatedEvent {
+    pub day: u64,
+    pub minute_of_day: u32,
+}
+
+/// Village-wide figures as of the last census. NPCs without a `Profession` count towards
+/// `without_profession`; NPCs without `NpcMotivation` are left out of dopamine and mood.
+#[derive(Resource, Debug, Clone, Default, PartialEq)]
+pub struct VillageStats {
+    population: u32,
+    professions: HashMap<Profession, u32>,
+    without_profession: u32,
+    dopamine: Option<DopamineSummary>,
+    moods: HashMap<NpcMood, u32>,
+    goods: HashMap<TradeGood, u32>,
+    trades_today: u32,
+    dialogue_requests_today: u64,
+}
+
+impl VillageStats {
+    /// Builds the stats from each NPC's optional components, every inventory in the world
+    /// (NPCs, household storage, and crates alike), and today's activity counts.
+    pub fn compute<'a>(
+        npcs: impl IntoIterator<Item = (Option<&'a Profession>, Option<&'a NpcMotivation>)>,
+        inventories: impl IntoIterator<Item = &'a Inventory>,
+        trades_today: u32,
+        dialogue_requests_today: u64,
+    ) -> Self {
+        let mut stats = Self {
+            trades_today,
+            dialogue_requests_today,
+            ..Self::default()
+        };
+        let mut dopamine_total = 0.0;
+        let mut motivated = 0u32;
+        for (profession, motivation) in npcs {
+            stats.population += 1;
+            match profession {
+                Some(profession) => *stats.professions.entry(*profession).or_default() += 1,
+                None => stats.without_profession += 1,
+            }
+            let Some(motivation) = motivation else {
+                continue;
+            };
+            let dopamine = motivation.dopamine();
+            dopamine_total += dopamine;
+            motivated += 1;
+            stats.dopamine = Some(match stats.dopamine {
+                Some(summary) => DopamineSummary {
+                    min: summary.min.min(dopamine),
+                    max: summary.max.max(dopamine),
+                    ..summary
+                },
+                None => DopamineSummary {
+                    average: dopamine,
+                    min: dopamine,
+                    max: dopamine,
+                },
+            });
+            *stats.moods.entry(motivation.mood()).or_default() += 1;
+        }
+        if let Some(summary) = stats.dopamine.as_mut() {
+            summary.average = dopamine_total / motivated as f32;
+        }
+        for inventory in inventories {
+            for good in TradeGood::ALL {
+                let quantity = inventory.quantity_of(good);
+                if quantity > 0 {
+                    *stats.goods.entry(good).or_default() += quantity;
+                }
+            }
+        }
+        stats
+    }
+
+    pub fn population(&self) -> u32 {
+        self.population
+    }
+
+    pub fn profession_count(&self, profession: Profession) -> u32 {
+        self.professions.get(&profession).copied().unwrap_or(0)
+    }
+
+    pub fn without_profession(&self) -> u32 {
+        self.without_profession
+    }
+
+    /// `None` until at least one NPC with motivation has been counted.
+    pub fn dopamine(&self) -> Option<DopamineSummary> {
+        self.dopamine
+    }
+
+    pub fn mood_count(&self, mood: NpcMood) -> u32 {
+        self.moods.get(&mood).copied().unwrap_or(0)
+    }
+
+    /// Units of `good` held across all inventories.
+    pub fn goods_in_circulation(&self, good: TradeGood) -> u32 {
+        self.goods.get(&good).copied().unwrap_or(0)
+    }
+
+    pub fn trades_today(&self) -> u32 {
+        self.trades_today
+    }
+
+    pub fn dialogue_requests_today(&self) -> u64 {
+        self.dialogue_requests_today
+    }
+
+    /// Multi-line summary for logs and debug readouts.
+    pub fn to_summary_string(&self) -> String {
+        let professions: Vec<String> = Profession::ALL
+            .iter()
+            .map(|profession| {
+                format!(
+                    "{} {}",
+                    profession.label(),
+                    self.profession_count(*profession)
+                )
+            })
+            .chain(
+                (self.without_profession() > 0)
+                    .then(|| format!("none {}", self.without_profession())),
+            )
+            .collect();
+        let dopamine = match self.dopamine() {
+            Some(summary) => format!(
+                "{:.1} average ({:.1}-{:.1})",
+                summary.average, summary.min, summary.max
+            ),
+            None => "n/a".to_string(),
+        };
+        let moods: Vec<String> = NpcMood::ALL
+            .iter()
+            .map(|mood| format!("{} {}", mood.label(), self.mood_count(*mood)))
+            .collect();
+        let goods: Vec<String> = TradeGood::ALL
+            .iter()
+            .map(|good| format!("{} {}", good.label(), self.goods_in_circulation(*good)))
+            .collect();
+        [
+            format!(
+                "Population: {} ({})",
+                self.population(),
+                professions.join(", ")
+            ),
+            format!("Dopamine: {dopamine}"),
+            format!("Moods: {}", moods.join(", ")),
+            format!("Goods: {}", goods.join(", ")),
+            format!(
+                "Today: {} trades, {} dialogue requests",
+                self.trades_today(),
+                self.dialogue_requests_today()
+            ),
+        ]
+        .join("\n")
+    }
+}
+
+/// Counts today's trades every frame, so none are missed between censuses.
+pub fn tally_village_activity(
+    world_clock: Res<WorldClock>,
+    queue: Res<DialogueRequestQueue>,
+    mut trades: MessageReader<TradeCompletedEvent>,
+    mut activity: ResMut<VillageActivity>,
+) {
+    let day = world_clock.day_count();
+    activity.roll_over(day, queue.total_enqueued());
+    let traded_today = trades.read().filter(|trade| trade.day == day).count();
+    activity.trades += traded_today as u32;
+}
+
+/// Recomputes `VillageStats` once `interval_minutes` of in-game time have passed since the
+/// last census, and announces the result only when it differs from the previous one.
+#[allow(clippy::too_many_arguments)]
+pub fn update_village_stats(
+    world_clock: Res<WorldClock>,
+    settings: Res<CensusSettings>,
+    activity: Res<VillageActivity>,
+    queue: Res<DialogueRequestQueue>,
+    mut stats: ResMut<VillageStats>,
+    mut last_census_minute: Local<Option<u64>>,
+    mut updates: MessageWriter<VillageStatsUpdatedEvent>,
+    npcs: Query<(Option<&Profession>, Option<&NpcMotivation>), With<Identity>>,
+    inventories: Query<&Inventory>,
+) {
+    let day = world_clock.day_count();
+    let minute = minute_of_day(world_clock.time_of_day());
+    let now = day * MINUTES_PER_DAY + u64::from(minute);
+    if let Some(last) = *last_census_minute {
+        if now < last + u64::from(settings.interval_minutes) {
+            return;
+        }
+    }
+    *last_census_minute = Some(now);
+
+    let requests_today = queue
+        .total_enqueued()
+        .saturating_sub(activity.requests_before_today);
+    let computed = VillageStats::compute(
+        npcs.iter(),
+        inventories.iter(),
+        activity.trades,
+        requests_today,
+    );
+    if computed != *stats {
+        *stats = computed;
+        updates.write(VillageStatsUpdatedEvent {
+            day,
+            minute_of_day: minute,
+        });
+    }
+}
+
+/// End-of-run report: logs the last census when the app exits.
+pub fn log_village_stats_on_exit(mut exits: MessageReader<AppExit>, stats: Res<VillageStats>) {
+    if exits.read().last().is_none() {
+        return;
+    }
+    info!("Village stats at exit:\n{}", stats.to_summary_string());
+}
+
+/// Re-reads the `[census]` section on request; the next census uses the new interval.
+pub fn reload_census_settings(
+    mut requests: MessageReader<ConfigReloadRequested>,
+    mut diagnostics: ResMut<ConfigDiagnostics>,
+    mut settings: ResMut<CensusSettings>,
+) {
+    if !requests.read().any(|request| request.is_for(CONFIG_PATH)) {
+        return;
+    }
+    if let Some(reloaded) = diagnostics.report_reload(CONFIG_PATH, CensusSettings::load()) {
+        *settings = reloaded;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dialogue::types::DialogueRequest,
+        npc::{
+            components::NpcId,
+            motivation::{state::MotivationReason, MotivationConfig},
+        },
+    };
+
+    fn motivation_at(config: &MotivationConfig, dopamine: f32) -> NpcMotivation {
+        let mut motivation = NpcMotivation::new(config);
+        let delta = dopamine - motivation.dopamine();
+        motivation.apply_adjustment(delta, MotivationReason::Leisure, config);
+        motivation
+    }
+
+    #[test]
+    fn compute_handles_npcs_missing_optional_components() {
+        let config = MotivationConfig::default();
+        let low = motivation_at(&config, 20.0);
+        let high = motivation_at(&config, 80.0);
+        let farmer = Profession::Farmer;
+        let miller = Profession::Miller;
+        let mut stocked = Inventory::default();
+        stocked.add_good_on(TradeGood::Grain, 3, 0);
+        stocked.add_good_on(TradeGood::Flour, 1, 0);
+        let mut storage = Inventory::default();
+        storage.add_good_on(TradeGood::Grain, 2, 0);
+
+        let stats = VillageStats::compute(
+            [
+                (Some(&farmer), Some(&low)),
+                (Some(&miller), Some(&high)),
+                (Some(&farmer), None),
+                (None, None),
+            ],
+            [&stocked, &storage, &Inventory::default()],
+            4,
+            7,
+        );
+
+        assert_eq!(stats.population(), 4);
+        assert_eq!(stats.profession_count(Profession::Farmer), 2);
+        assert_eq!(stats.profession_count(Profession::Blacksmith), 0);
+        assert_eq!(stats.without_profession(), 1);
+        let dopamine = stats.dopamine().expect("two NPCs have motivation");
+        assert!((dopamine.average - 50.0).abs() < 1e-4);
+        assert_eq!((dopamine.min, dopamine.max), (20.0, 80.0));
+        let counted: u32 = NpcMood::ALL
+            .iter()
+            .map(|mood| stats.mood_count(*mood))
+            .sum();
+        assert_eq!(counted, 2);
+        assert_eq!(stats.goods_in_circulation(TradeGood::Grain), 5);
+        assert_eq!(stats.goods_in_circulation(TradeGood::Ale), 0);
+        assert_eq!(
+            (stats.trades_today(), stats.dialogue_requests_today()),
+            (4, 7)
+        );
+
+        let summary = stats.to_summary_string();
+        assert!(summary.starts_with("Population: 4 (farmer 2, miller 1"));
+        assert!(summary.contains("none 1"));
+        assert!(summary.contains("Dopamine: 50.0 average (20.0-80.0)"));
+        assert!(summary.contains("Today: 4 trades, 7 dialogue requests"));
+        assert!(VillageStats::compute([], [], 0, 0)
+            .to_summary_string()
+            .contains("Dopamine: n/a"));
+    }
+
+    #[test]
+    fn census_waits_for_the_interval_and_only_announces_changes() {
+        let mut app = App::new();
+        let mut clock = WorldClock::new();
+        clock.set_time_of_day(0.25);
+        app.insert_resource(clock)
+            .insert_resource(CensusSettings {
+                interval_minutes: 60,
+            })
+            .init_resource::<DialogueRequestQueue>()
+            .init_resource::<VillageActivity>()
+            .init_resource::<VillageStats>()
+            .add_message::<TradeCompletedEvent>()
+            .add_message::<VillageStatsUpdatedEvent>()
+            .add_systems(
+                Update,
+                (tally_village_activity, update_village_stats).chain(),
+            );
+        app.world_mut().spawn((
+            Identity::new(NpcId::new(1), "Alric", 30.0),
+            Inventory::default(),
+        ));
+        let mut cursor = app
+            .world()
+            .resource::<Messages<VillageStatsUpdatedEvent>>()
+            .get_cursor();
+        let mut updates = |app: &App| {
+            let messages = app.world().resource::<Messages<VillageStatsUpdatedEvent>>();
+            cursor.read(messages).count()
+        };
+        let advance_minutes = |app: &mut App, minutes: f32| {
+            let mut clock = app.world_mut().resource_mut::<WorldClock>();
+            let fraction = clock.time_of_day() + minutes / MINUTES_PER_DAY as f32;
+            clock.set_time_of_day(fraction);
+            app.update();
+        };
+
+        app.update();
+        assert_eq!(app.world().resource::<VillageStats>().population(), 1);
+        assert_eq!(updates(&app), 1);
+
+        app.world_mut()
+            .spawn(Identity::new(NpcId::new(2), "Bryn", 28.0));
+        DialogueRequest::builder(NpcId::new(2))
+            .prompt("Bryn says hello.")
+            .enqueue(&mut app.world_mut().resource_mut::<DialogueRequestQueue>())
+            .unwrap();
+        advance_minutes(&mut app, 30.0);
+        assert_eq!(
+            app.world().resource::<VillageStats>().population(),
+            1,
+            "half an hour is inside the interval"
+        );
+
+        advance_minutes(&mut app, 31.0);
+        let stats = app.world().resource::<VillageStats>();
+        assert_eq!(stats.population(), 2);
+        assert_eq!(stats.dialogue_requests_today(), 1);
+        assert_eq!(updates(&app), 1, "one update per changed census");
+
+        advance_minutes(&mut app, 61.0);
+        assert_eq!(updates(&app), 0, "an unchanged census stays quiet");
+    }
+}
@@ -0,0 +1,140 @@
+//! NPC faults for chaos runs (`--features chaos`): an NPC vanishing and coming back as a
+//! fresh villager, and an NPC's dopamine dropping to the floor.
+use bevy::prelude::*;
+
+use crate::{
+    core::chaos::pick_index,
+    economy::rng::DailyRng,
+    npc::{
+        components::{DailySchedule, Identity, NpcIdGenerator, NpcLocomotion, ScheduleState},
+        facing::DesiredFacing,
+        motivation::{state::MotivationReason, MotivationConfig, NpcMotivation},
+        rumors::NpcKnowledge,
+        sleep::HomePosition,
+        systems::despawn_npc,
+    },
+    world::collision::MoverCollider,
+};
+
+/// A random NPC, lowest id first, so the same draw picks the same villager.
+pub fn pick_npc<F: bevy::ecs::query::QueryFilter>(
+    world: &mut World,
+    rng: &mut DailyRng,
+) -> Option<(Entity, Identity)> {
+    let mut npcs: Vec<(Entity, Identity)> = world
+        .query_filtered::<(Entity, &Identity), F>()
+        .iter(world)
+        .map(|(entity, identity)| (entity, identity.clone()))
+        .collect();
+    npcs.sort_by_key(|(_, identity)| identity.id);
+    let index = pick_index(rng, npcs.len())?;
+    Some(npcs.swap_remove(index))
+}
+
+/// Despawns a random NPC through `despawn_npc`, so its despawn is announced like any other,
+/// and spawns a new one with the same name, schedule, home, and looks under a fresh id.
+/// Everything else, from motivation to profession, starts over.
+pub fn respawn_random_npc(world: &mut World, rng: &mut DailyRng) -> Option<String> {
+    let (entity, identity) = pick_npc::<With<DailySchedule>>(world, rng)?;
+    let schedule = world.get::<DailySchedule>(entity)?.clone();
+    let home = world.get::<HomePosition>(entity).copied();
+    let translation = world
+        .get::<Transform>(entity)
+        .map_or(Vec3::ZERO, |transform| transform.translation);
+    let mesh = world.get::<Mesh3d>(entity).cloned();
+    let material = world
+        .get::<MeshMaterial3d<StandardMaterial>>(entity)
+        .cloned();
+    let collider = world.get::<MoverCollider>(entity).copied();
+    despawn_npc(&mut world.commands(), entity, identity.id);
+    world.flush();
+
+    let id = world.resource_mut::<NpcIdGenerator>().next_id();
+    let motivation = NpcMotivation::new(world.resource::<MotivationConfig>());
+    let position = home.map_or(translation, |home| home.0);
+    let name = identity.display_name.clone();
+    let mut respawned = world.spawn((
+        Transform::from_translation(position),
+        Identity::new(id, name.clone(), identity.age_years),
+        schedule,
+        ScheduleState::default(),
+        NpcLocomotion::default(),
+        motivation,
+        NpcKnowledge::default(),
+        DesiredFacing::default(),
+        Name::new(format!("{} ({})", name, id)),
+    ));
+    if let Some(home) = home {
+        respawned.insert(home);
+    }
+    if let Some(mesh) = mesh {
+        respawned.insert(mesh);
+    }
+    if let Some(material) = material {
+        respawned.insert(material);
+    }
+    if let Some(collider) = collider {
+        respawned.insert(collider);
+    }
+    Some(format!("respawned {name} ({}) as {id}", identity.id))
+}
+
+/// Drops a random NPC's dopamine to zero, or to the configured minimum if that is higher.
+pub fn zero_random_dopamine(world: &mut World, rng: &mut DailyRng) -> Option<String> {
+    let (entity, identity) = pick_npc::<With<NpcMotivation>>(world, rng)?;
+    world.resource_scope(|world, config: Mut<MotivationConfig>| {
+        let mut motivation = world.get_mut::<NpcMotivation>(entity)?;
+        let before = motivation.dopamine();
+        motivation.apply_adjustment(-before, MotivationReason::Decay, &config);
+        Some(format!(
+            "{} dopamine {before:.1} -> {:.1}",
+            identity.display_name,
+            motivation.dopamine()
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::npc::{components::NpcId, events::NpcDespawnedEvent};
+
+    #[test]
+    fn a_respawned_npc_is_announced_as_despawned() {
+        let mut app = App::new();
+        app.init_resource::<NpcIdGenerator>()
+            .insert_resource(MotivationConfig::default())
+            .add_message::<NpcDespawnedEvent>();
+        let old_id = app.world_mut().resource_mut::<NpcIdGenerator>().next_id();
+        let old = app
+            .world_mut()
+            .spawn((
+                Identity::new(old_id, "Alric", 40.0),
+                DailySchedule::new(Vec::new()),
+            ))
+            .id();
+
+        let mut rng = DailyRng::for_day(1, 0, 0);
+        let note = respawn_random_npc(app.world_mut(), &mut rng).expect("an NPC to respawn");
+
+        let messages = app.world().resource::<Messages<NpcDespawnedEvent>>();
+        let announced: Vec<_> = messages.get_cursor().read(messages).copied().collect();
+        assert_eq!(
+            announced,
+            vec![NpcDespawnedEvent {
+                entity: old,
+                npc_id: old_id,
+            }]
+        );
+        assert!(app.world().get_entity(old).is_err());
+        let ids: Vec<NpcId> = app
+            .world_mut()
+            .query::<&Identity>()
+            .iter(app.world())
+            .map(|identity| identity.id)
+            .collect();
+        assert_eq!(ids.len(), 1);
+        assert_ne!(ids[0], old_id, "the villager comes back under a fresh id");
+        assert!(note.starts_with("respawned Alric"), "{note}");
+    }
+}
@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::{core::time_basis::TimeBasis, dialogue::types::DialogueRequestId};
+
+use super::NpcId;
+
+/// Tracks when an NPC is engaged in a dialogue conversation.
+#[derive(Component, Debug, Clone)]
+pub struct InConversation {
+    pub partner: NpcId,
+    pub request_id: DialogueRequestId,
+    /// Elapsed seconds on the conversation's `TimeBasis` (see
+    /// `ConversationSettings::basis_for`) when it began.
+    pub started_at: f32,
+    pub state: ConversationState,
+}
+
+impl InConversation {
+    pub fn new(
+        partner: NpcId,
+        request_id: DialogueRequestId,
+        started_at: f32,
+        state: ConversationState,
+    ) -> Self {
+        Self {
+            partner,
+            request_id,
+            started_at,
+            state,
+        }
+    }
+}
+
+/// How long conversing NPCs stay put before resuming their tasks, and on which clock.
+#[derive(Resource, Debug, Clone)]
+pub struct ConversationSettings {
+    pub timeout_seconds: f32,
+    /// Clock for NPC-to-NPC conversations. Simulation by default, so fast-forwarding
+    /// does not hold NPCs in place for long stretches of sim time.
+    pub time_basis: TimeBasis,
+    /// Clock for an NPC talking with the player. Real by default, so the player's reply
+    /// window keeps its time at any time scale.
+    pub player_time_basis: TimeBasis,
+}
+
+impl Default for ConversationSettings {
+    fn default() -> Self {
+        Self {
+            timeout_seconds: 8.0,
+            time_basis: TimeBasis::Simulation,
+            player_time_basis: TimeBasis::Real,
+        }
+    }
+}
+
+impl ConversationSettings {
+    /// The clock a conversation with `partner` is timed on.
+    pub fn basis_for(&self, partner: NpcId) -> TimeBasis {
+        if partner.is_player() {
+            self.player_time_basis
+        } else {
+            self.time_basis
+        }
+    }
+}
+
+/// The NPC's dialogue request is with the provider, or waiting to retry; the UI shows a
+/// "thinking" ellipsis above them until the reply or a final failure arrives.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PendingSpeech {
+    pub request_id: DialogueRequestId,
+}
+
+/// State of an NPC conversation for coordinating movement and dialogue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConversationState {
+    /// Walking toward conversation partner, API call in progress
+    Approaching,
+    /// Arrived at destination, waiting for API response
+    WaitingAtDestination,
+    /// Dialogue panel is visible, speaking
+    #[allow(dead_code)] // Will be used when transitioning to speaking state
+    Speaking,
+}
+
+/// Authoritative record of which NPCs are in a conversation, keyed to the request that
+/// reserved them. Updated immediately, unlike `InConversation`, which waits on Commands.
+#[derive(Resource, Debug, Default)]
+pub struct ActiveConversations {
+    participants: HashMap<NpcId, DialogueRequestId>,
+}
+
+impl ActiveConversations {
+    pub fn is_in_conversation(&self, npc: NpcId) -> bool {
+        self.participants.contains_key(&npc)
+    }
+
+    /// Reserves every participant for `request`, or none of them if any is already taken.
+    pub fn try_reserve(&mut self, request: DialogueRequestId, participants: &[NpcId]) -> bool {
+        if participants
+            .iter()
+            .any(|npc| self.participants.contains_key(npc))
+        {
+            return false;
+        }
+        for npc in participants {
+            self.participants.insert(*npc, request);
+        }
+        true
+    }
+
+    /// Releases `npc` if its reservation still belongs to `request`.
+    pub fn release(&mut self, npc: NpcId, request: DialogueRequestId) {
+        if self.participants.get(&npc) == Some(&request) {
+            self.participants.remove(&npc);
+        }
+    }
+
+    /// Releases every participant reserved by `request`.
+    pub fn release_request(&mut self, request: DialogueRequestId) {
+        self.participants.retain(|_, reserved| *reserved != request);
+    }
+
+    /// Drops `npc`'s reservation whatever request holds it, for NPCs that no longer exist.
+    pub fn forget(&mut self, npc: NpcId) {
+        self.participants.remove(&npc);
+    }
+}
@@ -0,0 +1,201 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+
+/// Simple locomotion controller tracking destinations and movement state.
+#[derive(Component, Debug, Clone)]
+pub struct NpcLocomotion {
+    move_speed: f32,
+    arrive_distance: f32,
+    target: Option<MovementTarget>,
+    state: LocomotionState,
+    active_label: Option<String>,
+    arrival_point: Option<Vec3>,
+}
+
+impl NpcLocomotion {
+    pub fn new(move_speed: f32, arrive_distance: f32) -> Self {
+        Self {
+            move_speed,
+            arrive_distance,
+            target: None,
+            state: LocomotionState::Idle,
+            active_label: None,
+            arrival_point: None,
+        }
+    }
+
+    /// Base walking speed, before any `SpeedModifiers`.
+    #[cfg(test)]
+    pub fn move_speed(&self) -> f32 {
+        self.move_speed
+    }
+
+    /// Walking speed with `modifiers` applied, or the base speed without any.
+    pub fn effective_move_speed(&self, modifiers: Option<&SpeedModifiers>) -> f32 {
+        self.move_speed * modifiers.map_or(1.0, SpeedModifiers::multiplier)
+    }
+
+    pub fn arrive_distance(&self) -> f32 {
+        self.arrive_distance
+    }
+
+    pub fn target(&self) -> Option<MovementTarget> {
+        self.target
+    }
+
+    pub fn state(&self) -> LocomotionState {
+        self.state
+    }
+
+    pub fn active_label(&self) -> Option<&str> {
+        self.active_label.as_deref()
+    }
+
+    /// Where the NPC last arrived, kept until a new target is set.
+    pub fn arrival_point(&self) -> Option<Vec3> {
+        self.arrival_point
+    }
+
+    /// Returns true when a new travel target is registered. A position target that is not
+    /// finite, or that sits exactly where the mover stands (`from`, compared on XZ), is
+    /// rejected: there is nowhere to walk, and the direction toward it would be NaN.
+    pub fn set_target(
+        &mut self,
+        target: MovementTarget,
+        label: impl Into<String>,
+        from: Vec3,
+    ) -> bool {
+        if let MovementTarget::Position(position) = target {
+            if !position.is_finite() || position.xz() == from.xz() {
+                return false;
+            }
+        }
+        let label_string = label.into();
+        let is_duplicate = self.state == LocomotionState::Moving
+            && self.target == Some(target)
+            && self
+                .active_label
+                .as_ref()
+                .map(|existing| existing == &label_string)
+                .unwrap_or(false);
+
+        if is_duplicate {
+            return false;
+        }
+
+        self.target = Some(target);
+        self.state = LocomotionState::Moving;
+        self.active_label = Some(label_string);
+        self.arrival_point = None;
+        true
+    }
+
+    /// Clears the target and remembers `point` as the active arrival point.
+    pub fn arrive_at(&mut self, point: Vec3) {
+        self.clear_target();
+        self.arrival_point = Some(point);
+    }
+
+    pub fn clear_target(&mut self) {
+        self.target = None;
+        self.state = LocomotionState::Idle;
+        self.active_label = None;
+    }
+}
+
+impl Default for NpcLocomotion {
+    fn default() -> Self {
+        Self::new(2.5, 0.35)
+    }
+}
+
+/// Multiplicative speed factors on an NPC's walk, keyed by what set them (e.g.
+/// "encumbrance"), so several systems can slow or hurry the same NPC without touching
+/// `NpcLocomotion`'s base speed or each other's factors.
+#[derive(Component, Debug, Clone, Default, PartialEq)]
+pub struct SpeedModifiers {
+    factors: BTreeMap<&'static str, f32>,
+}
+
+impl SpeedModifiers {
+    /// Sets `source`'s factor, replacing its previous one. Negative factors count as 0 and
+    /// non-finite ones as 1.
+    pub fn set(&mut self, source: &'static str, factor: f32) {
+        let factor = if factor.is_finite() {
+            factor.max(0.0)
+        } else {
+            1.0
+        };
+        self.factors.insert(source, factor);
+    }
+
+    pub fn remove(&mut self, source: &'static str) {
+        self.factors.remove(source);
+    }
+
+    pub fn get(&self, source: &'static str) -> Option<f32> {
+        self.factors.get(source).copied()
+    }
+
+    /// Every factor multiplied together; 1 when none are set.
+    pub fn multiplier(&self) -> f32 {
+        stack_speed_factors(self.factors.values().copied())
+    }
+}
+
+/// The product of `factors`, never negative. An empty set leaves speed unchanged.
+pub fn stack_speed_factors(factors: impl IntoIterator<Item = f32>) -> f32 {
+    factors.into_iter().product::<f32>().max(0.0)
+}
+
+/// Where a locomotion controller should move.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MovementTarget {
+    Entity(Entity),
+    /// A fixed world position; the mover keeps its own height.
+    Position(Vec3),
+}
+
+/// Locomotion phase for logging and telemetry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocomotionState {
+    Idle,
+    Moving,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn speed_modifiers_stack_multiplicatively() {
+        assert_eq!(stack_speed_factors([]), 1.0);
+        assert!((stack_speed_factors([0.5, 0.8]) - 0.4).abs() < 1e-6);
+
+        let locomotion = NpcLocomotion::new(2.0, 0.35);
+        let mut modifiers = SpeedModifiers::default();
+        assert_eq!(locomotion.effective_move_speed(Some(&modifiers)), 2.0);
+        modifiers.set("encumbrance", 0.5);
+        modifiers.set("weather", 0.8);
+        assert!((locomotion.effective_move_speed(Some(&modifiers)) - 0.8).abs() < 1e-6);
+
+        modifiers.set("encumbrance", 0.75);
+        assert!(
+            (modifiers.multiplier() - 0.6).abs() < 1e-6,
+            "a source is replaced"
+        );
+        modifiers.remove("weather");
+        assert_eq!(modifiers.multiplier(), 0.75);
+        modifiers.set("mood", f32::NAN);
+        modifiers.set("chains", -3.0);
+        assert_eq!(modifiers.get("mood"), Some(1.0));
+        assert_eq!(
+            modifiers.multiplier(),
+            0.0,
+            "negative factors stop the walk"
+        );
+        assert_eq!(locomotion.move_speed(), 2.0, "the base speed is untouched");
+        assert_eq!(locomotion.effective_move_speed(None), 2.0);
+    }
+}
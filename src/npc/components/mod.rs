@@ -0,0 +1,122 @@
+//! NPC-specific components and supporting resources.
+
+use std::fmt;
+
+use bevy::prelude::*;
+
+use crate::npc::spatial::NpcIndex;
+
+mod conversation;
+mod locomotion;
+mod schedule;
+
+pub use conversation::{
+    ActiveConversations, ConversationSettings, ConversationState, InConversation, PendingSpeech,
+};
+pub use locomotion::{LocomotionState, MovementTarget, NpcLocomotion, SpeedModifiers};
+#[cfg(test)]
+pub use schedule::LATEST_START;
+pub use schedule::{
+    clamp_schedule_start, DailySchedule, ScheduleEntry, ScheduleState, ScheduleTicker,
+    START_TOLERANCE,
+};
+
+/// Unique identifier for an NPC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Component)]
+pub struct NpcId(u64);
+
+impl NpcId {
+    /// Wraps a raw id, as stored in saves and snapshots.
+    pub fn new(value: u64) -> Self {
+        Self(value)
+    }
+
+    /// The raw id.
+    pub fn value(self) -> u64 {
+        self.0
+    }
+
+    /// Special marker representing the player as a dialogue participant.
+    pub fn player() -> Self {
+        Self(u64::MAX)
+    }
+
+    /// Checks if this NpcId is the special player marker (u64::MAX).
+    pub fn is_player(&self) -> bool {
+        self.0 == u64::MAX
+    }
+}
+
+impl fmt::Display for NpcId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "NPC-{:04}", self.0)
+    }
+}
+
+/// Name used for the player wherever NPC display names are resolved.
+pub const PLAYER_DISPLAY_NAME: &str = "Player";
+
+/// Minimal identity data for debugging and future systems.
+#[derive(Component, Debug, Clone)]
+pub struct Identity {
+    pub id: NpcId,
+    pub display_name: String,
+    pub age_years: f32,
+}
+
+impl Identity {
+    pub fn new(id: NpcId, display_name: impl Into<String>, age_years: f32) -> Self {
+        Self {
+            id,
+            display_name: display_name.into(),
+            age_years,
+        }
+    }
+
+    /// Display name of `npc`, looked up through `index`; see `name_or_id`.
+    pub fn name_of(index: &NpcIndex, identities: &Query<&Identity>, npc: NpcId) -> String {
+        let identity = index
+            .entity(npc)
+            .and_then(|entity| identities.get(entity).ok());
+        Self::name_or_id(identity, npc)
+    }
+
+    /// Display name of `identity`, or `npc`'s id (`NPC-0007`) when it has none. The player
+    /// has no identity and reads as "Player".
+    pub fn name_or_id(identity: Option<&Identity>, npc: NpcId) -> String {
+        if npc.is_player() {
+            return PLAYER_DISPLAY_NAME.to_string();
+        }
+        identity.map_or_else(|| npc.to_string(), |identity| identity.display_name.clone())
+    }
+
+    /// Whole years for display; fractional progress towards the next birthday is dropped.
+    pub fn whole_years(&self) -> u32 {
+        self.age_years.max(0.0).floor() as u32
+    }
+
+    /// Dialogue speaker profile such as "a 25-year-old farmer" or "an 18-year-old villager".
+    pub fn profile_line(&self, role: &str) -> String {
+        let years = self.whole_years();
+        let article = if years == 8 || years == 11 || years == 18 || (80..90).contains(&years) {
+            "an"
+        } else {
+            "a"
+        };
+        format!("{article} {years}-year-old {role}")
+    }
+}
+
+/// Resource that issues monotonically increasing NPC ids.
+#[derive(Resource, Default)]
+pub struct NpcIdGenerator {
+    next: u64,
+}
+
+impl NpcIdGenerator {
+    pub fn next_id(&mut self) -> NpcId {
+        let id = self.next;
+        self.next += 1;
+        NpcId::new(id)
+    }
+}
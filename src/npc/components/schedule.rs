@@ -0,0 +1,305 @@
+use bevy::prelude::*;
+
+/// Activity reported in a gap between an entry's `end` and the next entry's start.
+pub const IDLE_ACTIVITY: &str = "Idle";
+/// Starts closer than half an in-game minute count as the same slot.
+pub const START_TOLERANCE: f32 = 0.5 / (24.0 * 60.0);
+/// Largest start below 1.0, so clamped entries never wrap onto midnight.
+pub const LATEST_START: f32 = 1.0 - f32::EPSILON;
+
+/// Clamps a start fraction into [0, 1); non-finite input lands on midnight.
+pub fn clamp_schedule_start(start: f32) -> f32 {
+    if start.is_finite() {
+        start.clamp(0.0, LATEST_START)
+    } else {
+        0.0
+    }
+}
+
+/// Describes a single scheduled activity starting at a fraction of the day. Without an
+/// `end` it runs until the next entry starts; with one, the NPC is `IDLE_ACTIVITY` from
+/// `end` until the next start. An `end` below `start` runs past midnight.
+#[derive(Debug, Clone)]
+pub struct ScheduleEntry {
+    pub start: f32,
+    pub end: Option<f32>,
+    pub activity: String,
+}
+
+impl ScheduleEntry {
+    pub fn new(start: f32, activity: impl Into<String>) -> Self {
+        Self {
+            start: start.rem_euclid(1.0),
+            end: None,
+            activity: activity.into(),
+        }
+    }
+
+    /// Stops this entry at `end`, leaving free time until the next entry.
+    pub fn until(mut self, end: f32) -> Self {
+        self.end = Some(end);
+        self
+    }
+
+    /// Fraction of the day from `start` to `end`, if the entry has an end.
+    fn length(&self) -> Option<f32> {
+        self.end
+            .map(|end| match (end - self.start).rem_euclid(1.0) {
+                // Midnight to 1.0 is the whole day, not nothing.
+                0.0 if end != self.start => 1.0,
+                length => length,
+            })
+    }
+}
+
+/// Daily schedule describing the activities an NPC performs.
+#[derive(Component, Debug, Clone)]
+pub struct DailySchedule {
+    pub entries: Vec<ScheduleEntry>,
+}
+
+impl DailySchedule {
+    /// Sorts and validates `entries`. Starts and ends outside the day are clamped, the last
+    /// of several entries sharing a start wins, and an explicit end that runs past the next
+    /// start is trimmed back to it. Every correction is logged as a warning.
+    pub fn new(entries: Vec<ScheduleEntry>) -> Self {
+        let mut entries: Vec<ScheduleEntry> = entries
+            .into_iter()
+            .map(|mut entry| {
+                let start = clamp_schedule_start(entry.start);
+                if start != entry.start {
+                    warn!(
+                        "Schedule entry '{}' starts at {}; clamped to {start:.3}",
+                        entry.activity, entry.start
+                    );
+                    entry.start = start;
+                }
+                if let Some(end) = entry.end {
+                    let clamped = if end.is_finite() {
+                        end.clamp(0.0, 1.0)
+                    } else {
+                        entry.start
+                    };
+                    if clamped != end {
+                        warn!(
+                            "Schedule entry '{}' ends at {end}; clamped to {clamped:.3}",
+                            entry.activity
+                        );
+                    }
+                    entry.end = Some(clamped);
+                }
+                if entry.length() == Some(0.0) {
+                    warn!(
+                        "Schedule entry '{}' ends where it starts; it runs until the next entry",
+                        entry.activity
+                    );
+                    entry.end = None;
+                }
+                entry
+            })
+            .collect();
+        entries.sort_by(|a, b| a.start.total_cmp(&b.start));
+
+        let mut deduplicated: Vec<ScheduleEntry> = Vec::with_capacity(entries.len());
+        for entry in entries {
+            match deduplicated.last_mut() {
+                Some(previous) if entry.start - previous.start < START_TOLERANCE => {
+                    warn!(
+                        "Schedule entries '{}' and '{}' both start at {:.3}; keeping '{}'",
+                        previous.activity, entry.activity, entry.start, entry.activity
+                    );
+                    *previous = entry;
+                }
+                _ => deduplicated.push(entry),
+            }
+        }
+
+        let count = deduplicated.len();
+        for index in 0..count {
+            let next_start = deduplicated[(index + 1) % count].start;
+            let entry = &mut deduplicated[index];
+            let until_next = match (next_start - entry.start).rem_euclid(1.0) {
+                0.0 => 1.0,
+                gap => gap,
+            };
+            if entry.length().is_some_and(|length| length > until_next) {
+                warn!(
+                    "Schedule entry '{}' runs past the next start at {next_start:.3}; trimmed",
+                    entry.activity
+                );
+                entry.end = Some(next_start);
+            }
+        }
+
+        Self {
+            entries: deduplicated,
+        }
+    }
+
+    /// Activity at `time_of_day`: the latest entry starting at or before it, wrapping to
+    /// the day's last entry before the first start, or `IDLE_ACTIVITY` once that entry has
+    /// ended.
+    pub fn current_activity(&self, time_of_day: f32) -> &str {
+        let Some(last) = self.entries.last() else {
+            return IDLE_ACTIVITY;
+        };
+        let selected = self
+            .entries
+            .iter()
+            .take_while(|entry| entry.start <= time_of_day)
+            .last()
+            .unwrap_or(last);
+        let elapsed = (time_of_day - selected.start).rem_euclid(1.0);
+        if selected.length().is_some_and(|length| elapsed >= length) {
+            return IDLE_ACTIVITY;
+        }
+        &selected.activity
+    }
+}
+
+/// Tracks the last activity assigned to an NPC (avoids spamming logs).
+#[derive(Component, Debug, Default, Clone)]
+pub struct ScheduleState {
+    pub current_activity: String,
+}
+
+/// Controls how often schedules advance (seconds of simulation time).
+#[derive(Resource)]
+pub struct ScheduleTicker {
+    pub interval_seconds: f32,
+    accumulated: f32,
+    pending_ticks: u32,
+}
+
+impl Default for ScheduleTicker {
+    fn default() -> Self {
+        Self {
+            interval_seconds: 5.0,
+            accumulated: 0.0,
+            pending_ticks: 0,
+        }
+    }
+}
+
+impl ScheduleTicker {
+    /// Accumulates delta time and returns how many ticks should fire.
+    pub fn accumulate(&mut self, delta_seconds: f32) -> u32 {
+        if self.interval_seconds <= f32::EPSILON {
+            return 0;
+        }
+
+        self.accumulated += delta_seconds.max(0.0);
+        let mut ticks = 0;
+        while self.accumulated >= self.interval_seconds {
+            self.accumulated -= self.interval_seconds;
+            ticks += 1;
+        }
+        self.pending_ticks = self.pending_ticks.saturating_add(ticks);
+        ticks
+    }
+
+    pub fn take_pending(&mut self) -> u32 {
+        let ticks = self.pending_ticks;
+        self.pending_ticks = 0;
+        ticks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn activities(schedule: &DailySchedule) -> Vec<(f32, Option<f32>, &str)> {
+        schedule
+            .entries
+            .iter()
+            .map(|entry| (entry.start, entry.end, entry.activity.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn gaps_after_an_end_are_idle() {
+        let schedule = DailySchedule::new(vec![
+            ScheduleEntry::new(0.0, "Sleeping").until(0.25),
+            ScheduleEntry::new(0.4, "Working"),
+            ScheduleEntry::new(0.75, "Supper"),
+        ]);
+
+        assert_eq!(schedule.current_activity(0.1), "Sleeping");
+        assert_eq!(schedule.current_activity(0.25), IDLE_ACTIVITY);
+        assert_eq!(schedule.current_activity(0.3), IDLE_ACTIVITY);
+        assert_eq!(schedule.current_activity(0.4), "Working");
+        assert_eq!(schedule.current_activity(0.9), "Supper");
+    }
+
+    #[test]
+    fn last_entry_ending_before_midnight_idles_across_the_wrap() {
+        let schedule = DailySchedule::new(vec![
+            ScheduleEntry::new(0.2, "Working"),
+            ScheduleEntry::new(0.6, "Tavern").until(0.9),
+        ]);
+
+        assert_eq!(schedule.current_activity(0.7), "Tavern");
+        assert_eq!(schedule.current_activity(0.95), IDLE_ACTIVITY);
+        assert_eq!(
+            schedule.current_activity(0.1),
+            IDLE_ACTIVITY,
+            "before the first start the day's last entry still applies"
+        );
+
+        let overnight = DailySchedule::new(vec![
+            ScheduleEntry::new(0.3, "Working"),
+            ScheduleEntry::new(0.9, "Sleeping").until(0.2),
+        ]);
+        assert_eq!(overnight.current_activity(0.95), "Sleeping");
+        assert_eq!(overnight.current_activity(0.1), "Sleeping");
+        assert_eq!(overnight.current_activity(0.25), IDLE_ACTIVITY);
+    }
+
+    #[test]
+    fn overlapping_ends_are_trimmed_to_the_next_start() {
+        let schedule = DailySchedule::new(vec![
+            ScheduleEntry::new(0.5, "Night shift").until(0.1),
+            ScheduleEntry::new(0.0, "Sleeping").until(0.6),
+            ScheduleEntry::new(0.3, "Working"),
+        ]);
+
+        assert_eq!(
+            activities(&schedule),
+            [
+                (0.0, Some(0.3), "Sleeping"),
+                (0.3, None, "Working"),
+                (0.5, Some(0.0), "Night shift"),
+            ]
+        );
+        assert_eq!(schedule.current_activity(0.95), "Night shift");
+        assert_eq!(schedule.current_activity(0.05), "Sleeping");
+    }
+
+    #[test]
+    fn out_of_range_values_clamp_and_duplicate_starts_keep_the_last() {
+        let schedule = DailySchedule::new(vec![
+            ScheduleEntry {
+                start: -0.2,
+                end: Some(1.5),
+                activity: "Early".to_string(),
+            },
+            ScheduleEntry::new(0.5, "First"),
+            ScheduleEntry::new(0.5, "Second"),
+            ScheduleEntry {
+                start: 3.0,
+                end: None,
+                activity: "Late".to_string(),
+            },
+        ]);
+
+        assert_eq!(
+            activities(&schedule),
+            [
+                (0.0, Some(0.5), "Early"),
+                (0.5, None, "Second"),
+                (LATEST_START, None, "Late"),
+            ]
+        );
+    }
+}
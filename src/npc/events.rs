@@ -1,5 +1,5 @@
 //! NPC-specific events broadcast between systems.
-use bevy::prelude::{Event, Message};
+use bevy::prelude::{Entity, Event, Message};
 
 use super::components::NpcId;
 
@@ -10,3 +10,24 @@ pub struct NpcActivityChangedEvent {
     pub activity: String,
     pub time_of_day: f32,
 }
+
+/// Fired once per whole year an NPC ages past, including years caught up after clock jumps.
+#[derive(Event, Message, Debug, Clone, PartialEq)]
+pub struct NpcBirthdayEvent {
+    pub npc: NpcId,
+    pub new_age: u32,
+}
+
+/// Fired when an NPC's `DailySchedule` is edited at runtime.
+#[derive(Event, Message, Debug, Clone, PartialEq)]
+pub struct NpcScheduleChangedEvent {
+    pub npc: NpcId,
+}
+
+/// Fired once an NPC entity has been despawned, so resources keyed by its `NpcId` or
+/// `Entity` can drop it before the id is recycled.
+#[derive(Event, Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NpcDespawnedEvent {
+    pub entity: Entity,
+    pub npc_id: NpcId,
+}
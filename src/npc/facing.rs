@@ -0,0 +1,213 @@
+//! NPC facing. Locomotion, conversations, and work each record where an NPC would like to
+//! look in `DesiredFacing`; `apply_npc_facing` picks one by precedence and turns the NPC
+//! toward it at a steady rate.
+use bevy::prelude::*;
+
+use crate::{
+    economy::{
+        components::{Profession, ProfessionCrate},
+        resources::ProfessionCrateRegistry,
+        tasks::{ActorTask, ActorTaskQueues},
+    },
+    npc::components::{Identity, LocomotionState, NpcLocomotion},
+};
+
+const DEFAULT_TURN_RATE: f32 = 5.0;
+/// Offsets shorter than this (on the XZ plane) have no meaningful direction.
+const MIN_FACING_DISTANCE: f32 = 1e-3;
+
+/// Tunables for how quickly NPCs turn.
+#[derive(Resource, Debug, Clone)]
+pub struct FacingConfig {
+    /// Fraction of the remaining turn covered per second; higher snaps faster.
+    pub turn_rate: f32,
+}
+
+impl Default for FacingConfig {
+    fn default() -> Self {
+        Self {
+            turn_rate: DEFAULT_TURN_RATE,
+        }
+    }
+}
+
+/// Yaw each facing source asks for this frame; `None` means the source has no opinion.
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq)]
+pub struct DesiredFacing {
+    /// Toward a conversation partner once the NPC has stopped to talk.
+    pub conversation: Option<f32>,
+    /// Along the direction of travel while walking.
+    pub travel: Option<f32>,
+    /// Toward the crate the NPC is manufacturing at.
+    pub work: Option<f32>,
+}
+
+impl DesiredFacing {
+    pub fn resolve(&self) -> Option<f32> {
+        resolve_facing(self.conversation, self.travel, self.work)
+    }
+}
+
+/// Conversation beats walking, and walking beats facing the workbench.
+pub fn resolve_facing(
+    conversation: Option<f32>,
+    travel: Option<f32>,
+    work: Option<f32>,
+) -> Option<f32> {
+    conversation.or(travel).or(work)
+}
+
+/// Yaw that points an NPC's forward (-Z) along `offset` (an XZ vector), or `None` when the
+/// offset is too short to have a direction.
+pub fn yaw_toward(offset: Vec2) -> Option<f32> {
+    if offset.length() < MIN_FACING_DISTANCE {
+        return None;
+    }
+    Some((-offset.x).atan2(-offset.y))
+}
+
+/// Rotation after turning toward `yaw` for `delta_seconds` at `turn_rate`. Never
+/// overshoots: a step larger than the remaining turn lands exactly on `yaw`.
+pub fn turn_toward(current: Quat, yaw: f32, turn_rate: f32, delta_seconds: f32) -> Quat {
+    current.slerp(
+        Quat::from_rotation_y(yaw),
+        (turn_rate * delta_seconds).clamp(0.0, 1.0),
+    )
+}
+
+/// NPCs standing at their crate with a Manufacture task up next face the crate.
+#[allow(clippy::type_complexity)]
+pub fn face_work_crates(
+    task_queues: Res<ActorTaskQueues>,
+    crate_registry: Res<ProfessionCrateRegistry>,
+    crates: Query<&GlobalTransform, With<ProfessionCrate>>,
+    mut npcs: Query<(
+        &Identity,
+        &Transform,
+        &Profession,
+        &NpcLocomotion,
+        &mut DesiredFacing,
+    )>,
+) {
+    for (identity, transform, profession, locomotion, mut facing) in npcs.iter_mut() {
+        let manufacturing = matches!(
+            task_queues.peek(identity.id),
+            Some(ActorTask::Manufacture { .. })
+        );
+        facing.work = if manufacturing && locomotion.state() != LocomotionState::Moving {
+            crate_registry
+                .get(*profession)
+                .and_then(|entity| crates.get(entity).ok())
+                .and_then(|global| {
+                    let position = global.translation();
+                    yaw_toward(Vec2::new(
+                        position.x - transform.translation.x,
+                        position.z - transform.translation.z,
+                    ))
+                })
+        } else {
+            None
+        };
+    }
+}
+
+/// Turns every NPC toward its highest-precedence desired facing.
+pub fn apply_npc_facing(
+    time: Res<Time>,
+    config: Res<FacingConfig>,
+    mut npcs: Query<(&DesiredFacing, &mut Transform)>,
+) {
+    let delta_seconds = time.delta_secs();
+    for (facing, mut transform) in npcs.iter_mut() {
+        if let Some(yaw) = facing.resolve() {
+            transform.rotation =
+                turn_toward(transform.rotation, yaw, config.turn_rate, delta_seconds);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{f32::consts::FRAC_PI_2, time::Duration};
+
+    use super::*;
+    use crate::{
+        core::plugin::SimulationClock,
+        npc::{
+            components::{MovementTarget, NpcId},
+            systems::drive_npc_locomotion,
+        },
+    };
+
+    #[test]
+    fn yaw_points_forward_along_the_offset() {
+        for offset in [Vec2::X, -Vec2::X, Vec2::Y, Vec2::new(1.0, -1.0)] {
+            let yaw = yaw_toward(offset).unwrap();
+            let forward = Transform::from_rotation(Quat::from_rotation_y(yaw)).forward();
+            let expected = Vec3::new(offset.x, 0.0, offset.y).normalize();
+            assert!(
+                forward.distance(expected) < 1e-5,
+                "{offset:?} -> {forward:?}"
+            );
+        }
+        assert_eq!(yaw_toward(Vec2::splat(1e-4)), None);
+    }
+
+    #[test]
+    fn conversation_then_travel_then_work_take_precedence() {
+        assert_eq!(resolve_facing(Some(1.0), Some(2.0), Some(3.0)), Some(1.0));
+        assert_eq!(resolve_facing(None, Some(2.0), Some(3.0)), Some(2.0));
+        assert_eq!(resolve_facing(None, None, Some(3.0)), Some(3.0));
+        assert_eq!(resolve_facing(None, None, None), None);
+    }
+
+    #[test]
+    fn turning_eases_toward_the_target_without_overshooting() {
+        let yaw_of = |rotation: Quat| rotation.to_euler(EulerRot::YXZ).0;
+        let half = turn_toward(Quat::IDENTITY, FRAC_PI_2, 5.0, 0.1);
+        assert!((yaw_of(half) - FRAC_PI_2 / 2.0).abs() < 1e-3);
+        let done = turn_toward(half, FRAC_PI_2, 5.0, 10.0);
+        assert!((yaw_of(done) - FRAC_PI_2).abs() < 1e-5);
+        assert_eq!(turn_toward(done, 0.0, 5.0, 0.0), done);
+    }
+
+    #[test]
+    fn walking_npcs_turn_to_face_their_travel_direction() {
+        let mut app = App::new();
+        app.insert_resource(SimulationClock::new(1.0))
+            .init_resource::<Time>()
+            .init_resource::<FacingConfig>()
+            .add_systems(Update, (drive_npc_locomotion, apply_npc_facing).chain());
+        let mut locomotion = NpcLocomotion::default();
+        locomotion.set_target(
+            MovementTarget::Position(Vec3::new(50.0, 0.0, 0.0)),
+            "mill",
+            Vec3::ZERO,
+        );
+        let npc = app
+            .world_mut()
+            .spawn((
+                Identity::new(NpcId::new(1), "Alric", 30.0),
+                Transform::default(),
+                locomotion,
+                DesiredFacing::default(),
+            ))
+            .id();
+
+        let step = Duration::from_millis(100);
+        let mut alignments = Vec::new();
+        for _ in 0..20 {
+            app.world_mut().resource_mut::<SimulationClock>().tick(step);
+            app.world_mut().resource_mut::<Time>().advance_by(step);
+            app.update();
+            let forward = app.world().get::<Transform>(npc).unwrap().forward();
+            alignments.push(forward.dot(Vec3::X));
+        }
+
+        assert!(alignments.windows(2).all(|pair| pair[1] >= pair[0] - 1e-5));
+        assert!(alignments[0] < 0.9, "turning is gradual");
+        assert!(*alignments.last().unwrap() > 0.999, "{alignments:?}");
+        let facing = app.world().get::<DesiredFacing>(npc).unwrap();
+        assert!(facing.travel.is_some());
+    }
+}
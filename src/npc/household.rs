@@ -0,0 +1,224 @@
+//! Households group NPCs around a shared storage crate at their home.
+use std::{collections::HashMap, fs, path::Path};
+
+use bevy::{math::primitives::Cuboid, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    core::config::{ConfigDiagnostics, ConfigReloadRequested},
+    economy::components::Inventory,
+    npc::{components::Identity, sleep::HomePosition},
+};
+
+pub const CONFIG_PATH: &str = "config/npcs.toml";
+const DEFAULT_PERSONAL_KEEP: u32 = 1;
+const STORAGE_MESH_DIMENSIONS: (f32, f32, f32) = (1.4, 0.8, 1.0);
+const STORAGE_COLOR: (u8, u8, u8) = (120, 85, 55);
+const STORAGE_PERCEPTUAL_ROUGHNESS: f32 = 0.8;
+
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+struct RawNpcConfig {
+    #[serde(default)]
+    storage: RawStorageSection,
+    #[serde(default)]
+    households: Vec<RawHousehold>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+struct RawStorageSection {
+    personal_keep: u32,
+}
+
+impl Default for RawStorageSection {
+    fn default() -> Self {
+        Self {
+            personal_keep: DEFAULT_PERSONAL_KEEP,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct RawHousehold {
+    name: String,
+    home: [f32; 3],
+    #[serde(default)]
+    members: Vec<String>,
+}
+
+/// One configured household: where its storage stands and who lives there.
+#[derive(Debug, Clone)]
+pub struct HouseholdSpec {
+    pub name: String,
+    pub home: Vec3,
+    /// NPC display names belonging to the household.
+    pub members: Vec<String>,
+}
+
+/// Household layout and storage tunables loaded from `config/npcs.toml`.
+#[derive(Resource, Debug, Clone)]
+pub struct HouseholdConfig {
+    /// Units of each good a member keeps in their own crate before depositing the rest.
+    pub personal_keep: u32,
+    pub households: Vec<HouseholdSpec>,
+}
+
+impl Default for HouseholdConfig {
+    fn default() -> Self {
+        RawNpcConfig::default().into()
+    }
+}
+
+impl HouseholdConfig {
+    /// Reads and parses `config/npcs.toml`.
+    pub fn load() -> Result<Self, String> {
+        let data = fs::read_to_string(Path::new(CONFIG_PATH))
+            .map_err(|err| format!("unable to read file: {err}"))?;
+        let raw = toml::from_str::<RawNpcConfig>(&data)
+            .map_err(|err| format!("invalid npc config: {err}"))?;
+        Ok(raw.into())
+    }
+}
+
+/// Parses the storage and household entries of `data` like `HouseholdConfig::load`, and
+/// returns them with every default written out, for village presets.
+pub fn explicit_toml(data: &str) -> Result<toml::Table, String> {
+    let raw =
+        toml::from_str::<RawNpcConfig>(data).map_err(|err| format!("invalid npc config: {err}"))?;
+    toml::Table::try_from(raw).map_err(|err| format!("unable to write npc config: {err}"))
+}
+
+/// The storage and household defaults `HouseholdConfig` falls back to, written out.
+pub fn fallback_toml() -> toml::Table {
+    toml::Table::try_from(RawNpcConfig::default()).expect("default npc config serializes")
+}
+
+impl From<RawNpcConfig> for HouseholdConfig {
+    fn from(value: RawNpcConfig) -> Self {
+        Self {
+            personal_keep: value.storage.personal_keep,
+            households: value
+                .households
+                .into_iter()
+                .map(|raw| HouseholdSpec {
+                    name: raw.name,
+                    home: Vec3::from_array(raw.home),
+                    members: raw.members,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Household membership for an NPC.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HouseholdId(u32);
+
+impl HouseholdId {
+    pub fn new(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+/// Marker for a household's shared storage crate; the entity also carries an `Inventory`.
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct HouseholdStorage;
+
+/// A spawned household with its storage entity.
+#[derive(Debug, Clone)]
+pub struct Household {
+    pub name: String,
+    pub storage: Entity,
+    pub home: Vec3,
+}
+
+/// Spawned households keyed by id.
+#[derive(Resource, Debug, Default)]
+pub struct HouseholdRegistry {
+    households: HashMap<HouseholdId, Household>,
+}
+
+impl HouseholdRegistry {
+    pub fn insert(&mut self, id: HouseholdId, household: Household) {
+        self.households.insert(id, household);
+    }
+
+    pub fn get(&self, id: HouseholdId) -> Option<&Household> {
+        self.households.get(&id)
+    }
+}
+
+/// Re-reads `config/npcs.toml` on request. The storage tunables apply at once; household
+/// layout changes only take effect on the next launch, since storage is spawned at startup.
+pub fn reload_household_config(
+    mut requests: MessageReader<ConfigReloadRequested>,
+    mut diagnostics: ResMut<ConfigDiagnostics>,
+    mut config: ResMut<HouseholdConfig>,
+) {
+    if !requests.read().any(|request| request.is_for(CONFIG_PATH)) {
+        return;
+    }
+    if let Some(reloaded) = diagnostics.report_reload(CONFIG_PATH, HouseholdConfig::load()) {
+        *config = reloaded;
+    }
+}
+
+/// Spawns each configured household's storage crate and tags its members, who also make
+/// the household home where they sleep.
+pub fn spawn_households(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    config: Res<HouseholdConfig>,
+    mut registry: ResMut<HouseholdRegistry>,
+    npcs: Query<(Entity, &Identity)>,
+) {
+    for (index, spec) in config.households.iter().enumerate() {
+        let id = HouseholdId::new(index as u32);
+        let storage = commands
+            .spawn((
+                Mesh3d(meshes.add(Mesh::from(Cuboid::new(
+                    STORAGE_MESH_DIMENSIONS.0,
+                    STORAGE_MESH_DIMENSIONS.1,
+                    STORAGE_MESH_DIMENSIONS.2,
+                )))),
+                MeshMaterial3d(materials.add(StandardMaterial {
+                    base_color: Color::srgb_u8(STORAGE_COLOR.0, STORAGE_COLOR.1, STORAGE_COLOR.2),
+                    perceptual_roughness: STORAGE_PERCEPTUAL_ROUGHNESS,
+                    ..default()
+                })),
+                Transform::from_translation(spec.home),
+                HouseholdStorage,
+                Inventory::default(),
+                Name::new(format!("{} storage", spec.name)),
+            ))
+            .id();
+
+        for member in &spec.members {
+            match npcs
+                .iter()
+                .find(|(_, identity)| identity.display_name == *member)
+            {
+                Some((entity, _)) => {
+                    commands
+                        .entity(entity)
+                        .insert((id, HomePosition(spec.home)));
+                }
+                None => warn!("Household {} lists unknown member {}", spec.name, member),
+            }
+        }
+
+        registry.insert(
+            id,
+            Household {
+                name: spec.name.clone(),
+                storage,
+                home: spec.home,
+            },
+        );
+        info!(
+            "Spawned {} household storage at ({:.1}, {:.1}, {:.1})",
+            spec.name, spec.home.x, spec.home.y, spec.home.z
+        );
+    }
+}
@@ -0,0 +1,347 @@
+//! Market day attendance: while the weekly market is open, NPCs with nothing pressing walk
+//! to the gathering point and mill around it, picking short wander targets within the
+//! configured radius. Turning up earns a motivation boost and a few extra chatter requests.
+use std::{collections::HashSet, f32::consts::TAU};
+
+use bevy::prelude::*;
+
+use crate::{
+    core::plugin::SimulationClock,
+    dialogue::chatter::ChatterBudgets,
+    economy::{components::Marketplace, rng::DailyRng, tasks::ActorTaskQueues},
+    npc::{
+        components::{Identity, InConversation, MovementTarget, NpcId, NpcLocomotion},
+        motivation::{state::MotivationReason, MotivationAdjustmentEvent, MotivationConfig},
+        sleep::{has_critical_task, HeadingHome, Sleeping},
+    },
+    world::{
+        time::WorldClock,
+        world_event::{MarketDayConfig, WorldEvent, WorldEventPhase, WorldEventTransition},
+    },
+};
+
+const MARKET_LABEL: &str = "market";
+/// RNG stream for wander targets, distinct from the economy's demand streams.
+const WANDER_STREAM: u64 = 0x4d41_524b;
+
+/// Attending the market. Economy tasks wait until the NPC is released.
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct AttendingMarket {
+    arrived: bool,
+    wanders: u64,
+    /// Scaled seconds left to linger before picking the next wander target.
+    pause_remaining: f32,
+}
+
+/// Who has reached today's market, so arrival rewards are paid once per NPC per day.
+#[derive(Resource, Debug, Default)]
+pub struct MarketAttendance {
+    day: Option<u64>,
+    arrived: HashSet<NpcId>,
+}
+
+impl MarketAttendance {
+    #[cfg(test)]
+    pub fn has_arrived(&self, npc: NpcId) -> bool {
+        self.arrived.contains(&npc)
+    }
+
+    /// Records an arrival; false when the NPC already arrived today.
+    fn arrive(&mut self, day: u64, npc: NpcId) -> bool {
+        if self.day != Some(day) {
+            self.day = Some(day);
+            self.arrived.clear();
+        }
+        self.arrived.insert(npc)
+    }
+}
+
+/// Sends free NPCs to the market while it is open and releases them when it closes, they
+/// head home, or a delivery needs them.
+#[allow(clippy::type_complexity)]
+pub fn update_market_attendance(
+    mut commands: Commands,
+    event: Res<WorldEvent>,
+    task_queues: Res<ActorTaskQueues>,
+    attendance: Res<MarketAttendance>,
+    mut transitions: MessageReader<WorldEventTransition>,
+    mut npcs: Query<(
+        Entity,
+        &Identity,
+        &mut NpcLocomotion,
+        Has<AttendingMarket>,
+        Has<HeadingHome>,
+        Has<Sleeping>,
+    )>,
+) {
+    for transition in transitions.read() {
+        if transition.phase == WorldEventPhase::Ended {
+            let turnout = if attendance.day == Some(transition.day) {
+                attendance.arrived.len()
+            } else {
+                0
+            };
+            info!(
+                "Market day {} closed with {} villagers attending",
+                transition.day, turnout
+            );
+        }
+    }
+
+    for (entity, identity, mut locomotion, attending, heading_home, sleeping) in npcs.iter_mut() {
+        let should_attend = event.is_active()
+            && !heading_home
+            && !sleeping
+            && !has_critical_task(&task_queues, identity.id);
+        if should_attend && !attending {
+            commands.entity(entity).insert(AttendingMarket::default());
+            locomotion.clear_target();
+            info!("{} heads to the market", identity.display_name);
+        } else if !should_attend && attending {
+            commands.entity(entity).remove::<AttendingMarket>();
+            if locomotion.active_label() == Some(MARKET_LABEL) {
+                locomotion.clear_target();
+            }
+        }
+    }
+}
+
+/// Walks attendees to the gathering point, rewards their arrival, then keeps them milling
+/// around it with a short pause between wander targets.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn mill_around_market(
+    clock: Res<WorldClock>,
+    sim_clock: Res<SimulationClock>,
+    config: Res<MarketDayConfig>,
+    motivation: Res<MotivationConfig>,
+    marketplace: Query<&GlobalTransform, With<Marketplace>>,
+    mut attendance: ResMut<MarketAttendance>,
+    mut budgets: ResMut<ChatterBudgets>,
+    mut attendees: Query<
+        (
+            &Identity,
+            &Transform,
+            &mut NpcLocomotion,
+            &mut AttendingMarket,
+        ),
+        Without<InConversation>,
+    >,
+    mut adjustments: MessageWriter<MotivationAdjustmentEvent>,
+) {
+    let Some(center) = config
+        .gathering_point
+        .or_else(|| marketplace.single().ok().map(GlobalTransform::translation))
+    else {
+        return;
+    };
+    let delta_seconds = sim_clock.last_scaled_delta().as_secs_f32();
+    let day = clock.day_count();
+
+    for (identity, transform, mut locomotion, mut attending) in attendees.iter_mut() {
+        let here = transform.translation;
+        if !attending.arrived {
+            let reach = config.wander_radius.max(locomotion.arrive_distance());
+            if here.xz().distance(center.xz()) > reach {
+                locomotion.set_target(MovementTarget::Position(center), MARKET_LABEL, here);
+                continue;
+            }
+            attending.arrived = true;
+            locomotion.clear_target();
+            if attendance.arrive(day, identity.id) {
+                adjustments.write(MotivationAdjustmentEvent::new(
+                    identity.id,
+                    motivation.market_day.attendance_reward,
+                    MotivationReason::MarketDay,
+                ));
+                budgets.grant(identity.id, motivation.chatter.market_day_bonus);
+                info!("{} arrives at the market", identity.display_name);
+            }
+        }
+
+        if locomotion.target().is_some() {
+            continue;
+        }
+        attending.pause_remaining -= delta_seconds;
+        if attending.pause_remaining > 0.0 {
+            continue;
+        }
+        let point = wander_point(identity.id, day, attending.wanders, center, &config);
+        attending.wanders += 1;
+        attending.pause_remaining = config.wander_pause_seconds;
+        locomotion.set_target(MovementTarget::Position(point), MARKET_LABEL, here);
+    }
+}
+
+/// Deterministic point within `wander_radius` of `center` for an NPC's nth wander today.
+fn wander_point(npc: NpcId, day: u64, wander: u64, center: Vec3, config: &MarketDayConfig) -> Vec3 {
+    let mut rng = DailyRng::for_day(npc.value(), day, WANDER_STREAM.wrapping_add(wander));
+    let angle = rng.next_f32() * TAU;
+    // The square root spreads points evenly over the disc instead of bunching at the centre.
+    let distance = rng.next_f32().sqrt() * config.wander_radius;
+    center + Vec3::new(angle.cos(), 0.0, angle.sin()) * distance
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::economy::{
+        components::{Profession, TradeGood},
+        tasks::ActorTask,
+    };
+
+    const MARKET_DAY: u64 = 3;
+
+    fn market_app(time_of_day: f32) -> App {
+        let mut app = App::new();
+        let mut clock = WorldClock::new();
+        clock.skip_days(MARKET_DAY);
+        clock.set_time_of_day(time_of_day);
+        let config = MarketDayConfig {
+            gathering_point: Some(Vec3::ZERO),
+            wander_pause_seconds: 0.0,
+            ..MarketDayConfig::default()
+        };
+        let mut event = WorldEvent::default();
+        event.advance(&config, MARKET_DAY, time_of_day);
+        app.insert_resource(clock)
+            .insert_resource(config)
+            .insert_resource(event)
+            .insert_resource(SimulationClock::new(1.0))
+            .insert_resource(MotivationConfig::default())
+            .init_resource::<ActorTaskQueues>()
+            .init_resource::<MarketAttendance>()
+            .init_resource::<ChatterBudgets>()
+            .add_message::<WorldEventTransition>()
+            .add_message::<MotivationAdjustmentEvent>()
+            .add_systems(
+                Update,
+                (update_market_attendance, mill_around_market).chain(),
+            );
+        app
+    }
+
+    fn villager(app: &mut App, id: u64, at: Vec3) -> Entity {
+        app.world_mut()
+            .spawn((
+                Identity::new(NpcId::new(id), "Villager", 30.0),
+                Transform::from_translation(at),
+                NpcLocomotion::default(),
+            ))
+            .id()
+    }
+
+    fn step(app: &mut App) {
+        app.world_mut()
+            .resource_mut::<SimulationClock>()
+            .tick(Duration::from_secs_f32(0.1));
+        app.update();
+    }
+
+    #[test]
+    fn free_villagers_attend_while_the_market_is_open() {
+        let mut app = market_app(0.5);
+        let free = villager(&mut app, 1, Vec3::new(10.0, 1.0, 0.0));
+        let courier = villager(&mut app, 2, Vec3::new(-10.0, 1.0, 0.0));
+        let sleeper = villager(&mut app, 3, Vec3::new(0.0, 1.0, 10.0));
+        app.world_mut().entity_mut(sleeper).insert(Sleeping);
+        app.world_mut()
+            .resource_mut::<ActorTaskQueues>()
+            .ensure_queue(NpcId::new(2))
+            .push_back(ActorTask::Deliver {
+                good: TradeGood::Grain,
+                quantity: 1,
+                target: Profession::Miller,
+                recipient: None,
+            });
+
+        step(&mut app);
+        step(&mut app);
+        let world = app.world();
+        assert!(world.get::<AttendingMarket>(free).is_some());
+        assert_eq!(
+            world.get::<NpcLocomotion>(free).unwrap().target(),
+            Some(MovementTarget::Position(Vec3::ZERO))
+        );
+        assert!(
+            world.get::<AttendingMarket>(courier).is_none(),
+            "a pending delivery comes first"
+        );
+        assert!(world.get::<AttendingMarket>(sleeper).is_none());
+
+        // Closing time releases everyone.
+        app.world_mut().resource_mut::<WorldEvent>().advance(
+            &MarketDayConfig::default(),
+            MARKET_DAY,
+            0.9,
+        );
+        step(&mut app);
+        let world = app.world();
+        assert!(world.get::<AttendingMarket>(free).is_none());
+        assert_eq!(world.get::<NpcLocomotion>(free).unwrap().target(), None);
+    }
+
+    #[test]
+    fn no_one_attends_before_the_market_opens() {
+        let mut app = market_app(0.3);
+        let npc = villager(&mut app, 1, Vec3::new(10.0, 1.0, 0.0));
+        step(&mut app);
+        assert!(app.world().get::<AttendingMarket>(npc).is_none());
+    }
+
+    #[test]
+    fn arrivals_are_rewarded_once_and_then_wander_within_the_radius() {
+        let mut app = market_app(0.5);
+        app.world_mut()
+            .resource_mut::<ChatterBudgets>()
+            .reset(MARKET_DAY, [(NpcId::new(1), 2)]);
+        let npc = villager(&mut app, 1, Vec3::new(1.0, 1.0, 0.0));
+        let mut cursor = app
+            .world()
+            .resource::<Messages<MotivationAdjustmentEvent>>()
+            .get_cursor();
+
+        let mut targets = Vec::new();
+        let mut rewards = Vec::new();
+        for _ in 0..6 {
+            step(&mut app);
+            let messages = app
+                .world()
+                .resource::<Messages<MotivationAdjustmentEvent>>();
+            rewards.extend(cursor.read(messages).map(|event| (event.npc, event.reason)));
+            let target = app.world().get::<NpcLocomotion>(npc).unwrap().target();
+            if let Some(MovementTarget::Position(point)) = target {
+                targets.push(point);
+                // Pretend the walk finished so the next wander is picked.
+                app.world_mut()
+                    .get_mut::<NpcLocomotion>(npc)
+                    .unwrap()
+                    .arrive_at(point);
+            }
+        }
+
+        let world = app.world();
+        assert!(world
+            .resource::<MarketAttendance>()
+            .has_arrived(NpcId::new(1)));
+        let radius = MarketDayConfig::default().wander_radius;
+        assert!(targets.len() >= 3);
+        assert!(targets
+            .iter()
+            .all(|point| point.xz().length() <= radius + 1e-4));
+        assert!(
+            targets.windows(2).any(|pair| pair[0] != pair[1]),
+            "wander targets vary"
+        );
+        assert_eq!(rewards, [(NpcId::new(1), MotivationReason::MarketDay)]);
+        let allowance = world
+            .resource::<ChatterBudgets>()
+            .allowance(NpcId::new(1))
+            .unwrap();
+        assert_eq!(
+            allowance.daily,
+            2 + MotivationConfig::default().chatter.market_day_bonus
+        );
+    }
+}
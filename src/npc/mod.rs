@@ -1,8 +1,26 @@
 //! NPC module exposes identity data and debug spawners.
+pub mod aging;
+pub mod census;
+#[cfg(feature = "chaos")]
+pub mod chaos;
 pub mod components;
 pub mod events;
+pub mod facing;
+pub mod household;
+pub mod market_day;
 pub mod motivation;
+pub mod patrol;
 pub mod plugin;
+pub mod reflection;
+pub mod rumors;
+#[cfg(any(test, feature = "transform_sanity"))]
+pub mod sanity;
+pub mod schedule_editor;
+pub mod separation;
+pub mod sleep;
+pub mod spatial;
 pub mod systems;
+pub mod voice;
+pub mod yielding;
 
 pub use plugin::NpcPlugin;
@@ -0,0 +1,428 @@
+//! Event-sourced motivation changes. Reward and penalty systems write a
+//! `MotivationAdjustmentEvent` instead of touching `NpcMotivation`; the applier is the one
+//! place those amounts land, so a day can be replayed or audited from the event stream.
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::npc::components::{Identity, NpcId};
+
+use super::{
+    config::MotivationConfig,
+    state::{adjusted_task_reward, MotivationChange, MotivationReason, NpcMotivation},
+};
+
+/// Signed dopamine change requested for one NPC. Positive amounts are rewards.
+#[derive(Message, Debug, Clone, Copy, PartialEq)]
+pub struct MotivationAdjustmentEvent {
+    pub npc: NpcId,
+    pub amount: f32,
+    pub reason: MotivationReason,
+}
+
+impl MotivationAdjustmentEvent {
+    pub fn new(npc: NpcId, amount: f32, reason: MotivationReason) -> Self {
+        Self {
+            npc,
+            amount,
+            reason,
+        }
+    }
+}
+
+/// Applies this frame's adjustments in the order they were written, through the usual
+/// clamp and mood logic. Trade rewards take the drink quality penalty as it stands when
+/// they are applied. Each NPC's applied changes are summed per reason and recorded once
+/// for the motivation timeline. Runs after every emitter in the same frame.
+pub fn apply_motivation_adjustments(
+    mut events: MessageReader<MotivationAdjustmentEvent>,
+    config: Res<MotivationConfig>,
+    mut query: Query<(&Identity, &mut NpcMotivation)>,
+) {
+    let mut by_npc: HashMap<NpcId, Vec<MotivationAdjustmentEvent>> = HashMap::new();
+    for event in events.read() {
+        by_npc.entry(event.npc).or_default().push(*event);
+    }
+    if by_npc.is_empty() {
+        return;
+    }
+
+    for (identity, mut motivation) in query.iter_mut() {
+        let Some(adjustments) = by_npc.remove(&identity.id) else {
+            continue;
+        };
+        let previous_mood = motivation.mood();
+
+        let mut applied: Vec<MotivationChange> = Vec::new();
+        for adjustment in adjustments {
+            let amount = if adjustment.reason == MotivationReason::Trade && adjustment.amount > 0.0
+            {
+                adjusted_task_reward(adjustment.amount, &config.alcohol, &motivation)
+            } else {
+                adjustment.amount
+            };
+            let delta = motivation.apply_adjustment(amount, adjustment.reason, &config);
+            match applied
+                .iter_mut()
+                .find(|change| change.reason == adjustment.reason)
+            {
+                Some(change) => change.delta += delta,
+                None => applied.push(MotivationChange {
+                    reason: adjustment.reason,
+                    delta,
+                }),
+            }
+        }
+
+        for change in applied {
+            motivation.record_change(change.reason, change.delta);
+            debug!(
+                "{} {:+.1} motivation from {}",
+                identity.display_name,
+                change.delta,
+                change.reason.label()
+            );
+        }
+        if motivation.mood() != previous_mood {
+            info!(
+                "{} mood shifts to {} (dopamine {:.1})",
+                identity.display_name,
+                motivation.mood().label(),
+                motivation.dopamine()
+            );
+        }
+    }
+
+    for npc in by_npc.keys() {
+        debug!("Dropping motivation adjustments for {npc}: no NPC with that id");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        core::plugin::SimulationClock,
+        dialogue::{
+            broker::DialogueProviderKind,
+            events::DialogueResponseEvent,
+            types::{DialogueContext, DialogueRequestId, DialogueResponse},
+        },
+        economy::{
+            components::{Inventory, Profession, TradeGood},
+            dependency::{DependencyCategory, EconomyDependencyMatrix},
+            events::{
+                InventoryChangedEvent, ProfessionDependencyUpdateEvent, TradeCompletedEvent,
+                TradeReason,
+            },
+        },
+        npc::{
+            events::NpcActivityChangedEvent,
+            motivation::{
+                history::{record_motivation_history, MotivationHistory},
+                state::DailyDependencyTracker,
+                systems::{
+                    drink_delivered_ale, evaluate_dependency_impacts,
+                    reward_from_dialogue_responses, reward_from_leisure, reward_from_trade_events,
+                    track_dependency_satisfaction,
+                },
+            },
+            spatial::{index_npcs, NpcIndex},
+        },
+        world::time::{announce_day_change, DayChangedEvent, WorldClock},
+    };
+
+    fn applier_app(config: &MotivationConfig) -> App {
+        let mut app = App::new();
+        app.insert_resource(config.clone())
+            .add_message::<MotivationAdjustmentEvent>()
+            .add_systems(Update, apply_motivation_adjustments);
+        app
+    }
+
+    fn spawn(app: &mut App, npc: NpcId, config: &MotivationConfig) -> Entity {
+        app.world_mut()
+            .spawn((
+                Identity::new(npc, "Maren", 28.0),
+                NpcMotivation::new(config),
+            ))
+            .id()
+    }
+
+    #[test]
+    fn several_adjustments_in_one_frame_apply_in_order_and_record_once_per_reason() {
+        let mut config = MotivationConfig::load_or_default();
+        config.defaults.start = 95.0;
+        let mut app = applier_app(&config);
+        let npc = NpcId::new(1);
+        let entity = spawn(&mut app, npc, &config);
+
+        for (amount, reason) in [
+            (4.0, MotivationReason::Social),
+            (4.0, MotivationReason::Social),
+            (-10.0, MotivationReason::DependencyDeficit),
+        ] {
+            app.world_mut()
+                .write_message(MotivationAdjustmentEvent::new(npc, amount, reason));
+        }
+        app.update();
+
+        let mut motivation = app.world_mut().get_mut::<NpcMotivation>(entity).unwrap();
+        // 95 + 4 + 4 clamps at 100 before the penalty, exactly as separate calls would.
+        assert_eq!(motivation.dopamine(), 90.0);
+        assert_eq!(
+            motivation.take_changes(),
+            vec![
+                MotivationChange {
+                    reason: MotivationReason::Social,
+                    delta: 5.0,
+                },
+                MotivationChange {
+                    reason: MotivationReason::DependencyDeficit,
+                    delta: -10.0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn alcohol_adjustments_start_intoxication_and_dampen_later_trade_rewards() {
+        let config = MotivationConfig::load_or_default();
+        let mut app = applier_app(&config);
+        let npc = NpcId::new(1);
+        let entity = spawn(&mut app, npc, &config);
+
+        app.world_mut()
+            .write_message(MotivationAdjustmentEvent::new(
+                npc,
+                config.alcohol.boost,
+                MotivationReason::Alcohol,
+            ));
+        app.world_mut()
+            .write_message(MotivationAdjustmentEvent::new(
+                npc,
+                config.gains.task,
+                MotivationReason::Trade,
+            ));
+        app.update();
+
+        let motivation = app.world().get::<NpcMotivation>(entity).unwrap();
+        assert!(motivation.is_intoxicated());
+        let expected = config.defaults.start
+            + config.alcohol.boost
+            + config.gains.task * (1.0 - config.alcohol.quality_penalty);
+        assert!((motivation.dopamine() - expected).abs() < 1e-4);
+    }
+
+    #[test]
+    fn dependency_reasons_reach_the_motivation_timeline() {
+        let config = MotivationConfig::load_or_default();
+        let mut app = applier_app(&config);
+        app.insert_resource(WorldClock::new())
+            .insert_resource(SimulationClock::new(1.0))
+            .init_resource::<MotivationHistory>()
+            .add_message::<DayChangedEvent>()
+            .add_systems(
+                Update,
+                record_motivation_history.after(apply_motivation_adjustments),
+            );
+        let npc = NpcId::new(1);
+        spawn(&mut app, npc, &config);
+
+        app.world_mut()
+            .write_message(MotivationAdjustmentEvent::new(
+                npc,
+                -config.dependency.deficit_penalty,
+                MotivationReason::DependencyDeficit,
+            ));
+        app.world_mut()
+            .write_message(MotivationAdjustmentEvent::new(
+                npc,
+                config.history.notable_change,
+                MotivationReason::PlayerTransfer,
+            ));
+        app.update();
+
+        let history = app.world().resource::<MotivationHistory>();
+        let causes: Vec<_> = history
+            .timeline(npc)
+            .expect("timeline")
+            .causes()
+            .map(|cause| (cause.reason, cause.delta))
+            .collect();
+        assert_eq!(
+            causes,
+            vec![
+                (
+                    MotivationReason::DependencyDeficit,
+                    -config.dependency.deficit_penalty
+                ),
+                (
+                    MotivationReason::PlayerTransfer,
+                    config.history.notable_change
+                ),
+            ]
+        );
+    }
+
+    /// The old inline behaviour for the scripted day below: each system mutated the NPC
+    /// directly in schedule order.
+    fn legacy_outcome(config: &MotivationConfig) -> (f32, f32) {
+        let mut alric = NpcMotivation::new(config);
+        alric.apply_reward(config.gains.leisure, MotivationReason::Leisure, config);
+        alric.apply_reward(config.alcohol.boost, MotivationReason::Alcohol, config);
+        let trade = adjusted_task_reward(config.gains.task * 1.5, &config.alcohol, &alric);
+        alric.apply_reward(trade, MotivationReason::Trade, config);
+        alric.apply_reward(config.gains.social, MotivationReason::Social, config);
+        for _ in 0..2 {
+            alric.apply_penalty(
+                config.dependency.deficit_penalty,
+                MotivationReason::DependencyDeficit,
+                config,
+            );
+        }
+
+        let mut bryn = NpcMotivation::new(config);
+        bryn.apply_reward(
+            config.player_transfer.give_bonus * 2.0,
+            MotivationReason::PlayerTransfer,
+            config,
+        );
+        bryn.apply_reward(config.alcohol.boost, MotivationReason::Alcohol, config);
+        bryn.apply_reward(config.gains.social * 0.6, MotivationReason::Social, config);
+        bryn.apply_reward(
+            config.dependency.satisfaction_bonus,
+            MotivationReason::DependencySatisfied,
+            config,
+        );
+        (alric.dopamine(), bryn.dopamine())
+    }
+
+    #[test]
+    fn scripted_day_ends_where_direct_mutation_did() {
+        let mut config = MotivationConfig::load_or_default();
+        config.alcohol.evening_start_fraction = 0.0;
+
+        let mut app = App::new();
+        app.insert_resource(config.clone())
+            .insert_resource(WorldClock::new())
+            .init_resource::<EconomyDependencyMatrix>()
+            .init_resource::<DailyDependencyTracker>()
+            .add_message::<MotivationAdjustmentEvent>()
+            .add_message::<NpcActivityChangedEvent>()
+            .add_message::<TradeCompletedEvent>()
+            .add_message::<InventoryChangedEvent>()
+            .add_message::<DialogueResponseEvent>()
+            .add_message::<ProfessionDependencyUpdateEvent>()
+            .add_message::<DayChangedEvent>()
+            .init_resource::<NpcIndex>()
+            .add_systems(
+                Update,
+                (
+                    announce_day_change,
+                    index_npcs,
+                    reward_from_leisure,
+                    reward_from_trade_events,
+                    drink_delivered_ale,
+                    reward_from_dialogue_responses,
+                    track_dependency_satisfaction,
+                    evaluate_dependency_impacts,
+                    apply_motivation_adjustments,
+                )
+                    .chain(),
+            );
+
+        let (alric_id, bryn_id) = (NpcId::new(1), NpcId::new(2));
+        let alric = app
+            .world_mut()
+            .spawn((
+                Identity::new(alric_id, "Alric", 30.0),
+                Profession::Farmer,
+                Inventory::default(),
+                NpcMotivation::new(&config),
+            ))
+            .id();
+        let mut ale = Inventory::default();
+        ale.add_good(TradeGood::Ale, 1);
+        let bryn = app
+            .world_mut()
+            .spawn((
+                Identity::new(bryn_id, "Bryn", 30.0),
+                Profession::Miller,
+                ale,
+                NpcMotivation::new(&config),
+            ))
+            .id();
+
+        let world = app.world_mut();
+        world.write_message(NpcActivityChangedEvent {
+            npc: alric_id,
+            activity: "Tavern stories".to_string(),
+            time_of_day: 0.8,
+        });
+        for (from, to, good, quantity, reason) in [
+            (alric_id, None, TradeGood::Grain, 1, TradeReason::Production),
+            (
+                alric_id,
+                Some(bryn_id),
+                TradeGood::Ale,
+                1,
+                TradeReason::Exchange,
+            ),
+            (
+                NpcId::player(),
+                Some(bryn_id),
+                TradeGood::Grain,
+                2,
+                TradeReason::PlayerTransfer,
+            ),
+        ] {
+            world.write_message(TradeCompletedEvent {
+                day: 0,
+                from: Some(from),
+                to,
+                good,
+                quantity,
+                reason,
+            });
+        }
+        world.write_message(DialogueResponseEvent {
+            response: DialogueResponse::new(
+                DialogueRequestId::new(1),
+                DialogueProviderKind::OpenAi,
+                alric_id,
+                Some(bryn_id),
+                "Fine harvest.",
+            ),
+            context: DialogueContext::default(),
+        });
+        for (npc, profession, satisfied) in [
+            (alric_id, Profession::Farmer, vec![]),
+            (
+                bryn_id,
+                Profession::Miller,
+                vec![DependencyCategory::Food, DependencyCategory::Tools],
+            ),
+        ] {
+            world.write_message(ProfessionDependencyUpdateEvent {
+                day: 0,
+                npc,
+                profession,
+                satisfied_categories: satisfied,
+                missing_categories: Vec::new(),
+            });
+        }
+        world.resource_mut::<WorldClock>().skip_days(1);
+        app.update();
+
+        let (expected_alric, expected_bryn) = legacy_outcome(&config);
+        let dopamine = |entity| app.world().get::<NpcMotivation>(entity).unwrap().dopamine();
+        assert!((dopamine(alric) - expected_alric).abs() < 1e-4);
+        assert!((dopamine(bryn) - expected_bryn).abs() < 1e-4);
+        assert!(app
+            .world()
+            .get::<NpcMotivation>(bryn)
+            .unwrap()
+            .is_intoxicated());
+    }
+}
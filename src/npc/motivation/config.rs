@@ -1,9 +1,11 @@
-use std::{fs, path::Path};
-
 use bevy::prelude::*;
 use serde::Deserialize;
 
-const CONFIG_PATH: &str = "config/motivation.toml";
+use super::migration::MOTIVATION_SCHEMA;
+use crate::{core::migration, dialogue::chatter::DEFAULT_PAIR_CHATTER_WINDOW_MINUTES};
+
+/// Where `MotivationConfig::load` reads the motivation config.
+pub const CONFIG_PATH: &str = "config/motivation.toml";
 
 #[derive(Debug, Clone, Deserialize, Default)]
 struct RawMotivationConfig {
@@ -21,6 +23,24 @@ struct RawMotivationConfig {
     alcohol: RawAlcohol,
     #[serde(default)]
     leisure: RawLeisure,
+    #[serde(default)]
+    history: RawHistory,
+    #[serde(default)]
+    player_transfer: RawPlayerTransfer,
+    #[serde(default)]
+    birthday: RawBirthday,
+    #[serde(default)]
+    chatter: RawChatter,
+    #[serde(default)]
+    sleep: RawSleep,
+    #[serde(default)]
+    skill: RawSkill,
+    #[serde(default)]
+    spoilage: RawSpoilage,
+    #[serde(default)]
+    market_day: RawMarketDay,
+    #[serde(default)]
+    patrol: RawPatrol,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -115,6 +135,7 @@ struct RawAlcohol {
     hangover_duration_seconds: f32,
     quality_penalty: f32,
     trigger_keywords: Vec<String>,
+    evening_start_fraction: f32,
 }
 
 impl Default for RawAlcohol {
@@ -132,6 +153,7 @@ impl Default for RawAlcohol {
                 "mead".to_string(),
                 "wine".to_string(),
             ],
+            evening_start_fraction: 0.7,
         }
     }
 }
@@ -151,93 +173,430 @@ impl Default for RawLeisure {
                 "lute".to_string(),
                 "rest".to_string(),
                 "tavern".to_string(),
+                "idle".to_string(),
             ],
         }
     }
 }
 
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+struct RawHistory {
+    sample_interval_seconds: f32,
+    capacity: usize,
+    notable_change: f32,
+}
+
+impl Default for RawHistory {
+    fn default() -> Self {
+        Self {
+            sample_interval_seconds: 5.0,
+            capacity: 288,
+            notable_change: 10.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+struct RawPlayerTransfer {
+    give_bonus: f32,
+    take_penalty: f32,
+}
+
+impl Default for RawPlayerTransfer {
+    fn default() -> Self {
+        Self {
+            give_bonus: 3.0,
+            take_penalty: 2.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+struct RawBirthday {
+    reward: f32,
+    neighbour_reward: f32,
+    neighbour_radius: f32,
+}
+
+impl Default for RawBirthday {
+    fn default() -> Self {
+        Self {
+            reward: 10.0,
+            neighbour_reward: 3.0,
+            neighbour_radius: 12.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+struct RawSkill {
+    level_up_reward: f32,
+}
+
+impl Default for RawSkill {
+    fn default() -> Self {
+        Self {
+            level_up_reward: 8.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+struct RawSpoilage {
+    penalty_per_unit: f32,
+    max_penalty: f32,
+}
+
+impl Default for RawSpoilage {
+    fn default() -> Self {
+        Self {
+            penalty_per_unit: 1.0,
+            max_penalty: 5.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+struct RawMarketDay {
+    attendance_reward: f32,
+}
+
+impl Default for RawMarketDay {
+    fn default() -> Self {
+        Self {
+            attendance_reward: 4.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+struct RawPatrol {
+    duty_reward: f32,
+    reward_interval_seconds: f32,
+}
+
+impl Default for RawPatrol {
+    fn default() -> Self {
+        Self {
+            duty_reward: 0.5,
+            reward_interval_seconds: 30.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+struct RawChatter {
+    base_budget: u32,
+    market_day_bonus: u32,
+    energised_multiplier: f32,
+    content_multiplier: f32,
+    tired_multiplier: f32,
+    depressed_multiplier: f32,
+    pair_window_minutes: f32,
+}
+
+impl Default for RawChatter {
+    fn default() -> Self {
+        Self {
+            base_budget: 6,
+            market_day_bonus: 3,
+            energised_multiplier: 1.5,
+            content_multiplier: 1.0,
+            tired_multiplier: 1.0,
+            depressed_multiplier: 0.3,
+            pair_window_minutes: DEFAULT_PAIR_CHATTER_WINDOW_MINUTES,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+struct RawSleep {
+    regen_per_second: f32,
+}
+
+impl Default for RawSleep {
+    fn default() -> Self {
+        Self {
+            regen_per_second: 0.4,
+        }
+    }
+}
+
 /// Runtime configuration derived from `config/motivation.toml`.
 #[derive(Resource, Debug, Clone)]
 pub struct MotivationConfig {
+    /// `[defaults]`: dopamine bounds and starting value.
     pub defaults: MotivationDefaults,
+    /// `[gains]`: rewards for work, conversation, and downtime.
     pub gains: MotivationGains,
+    /// `[decay]`: how fast dopamine drains.
     pub decay: MotivationDecay,
+    /// `[dependency]`: daily wellbeing needs met or missed.
     pub dependency: DependencyImpactConfig,
+    /// `[mood_thresholds]`: where each mood starts.
     pub thresholds: MotivationMoodThresholds,
+    /// `[alcohol]`: drinking, intoxication, and hangovers.
     pub alcohol: AlcoholConfig,
+    /// `[leisure]`: which schedule activities count as downtime.
     pub leisure: LeisureConfig,
+    /// `[history]`: mood timeline sampling.
+    pub history: MotivationHistoryConfig,
+    /// `[player_transfer]`: goods given or taken by the player.
+    pub player_transfer: PlayerTransferConfig,
+    /// `[birthday]`: birthday boosts.
+    pub birthday: BirthdayConfig,
+    /// `[chatter]`: daily NPC dialogue allowance.
+    pub chatter: ChatterConfig,
+    /// `[sleep]`: overnight recovery.
+    pub sleep: SleepConfig,
+    /// `[skill]`: skill level-up boost.
+    pub skill: SkillRewardConfig,
+    /// `[spoilage]`: penalty for spoiled stock.
+    pub spoilage: SpoilageConfig,
+    /// `[market_day]`: market attendance boost.
+    pub market_day: MarketDayRewardConfig,
+    /// `[patrol]`: night patrol reward.
+    pub patrol: PatrolRewardConfig,
 }
 
+/// Dopamine bounds and the value new NPCs start at; `start` lies within the bounds.
 #[derive(Debug, Clone)]
 pub struct MotivationDefaults {
+    /// Lowest dopamine.
     pub min: f32,
+    /// Highest dopamine; at least `min + 1`.
     pub max: f32,
+    /// Dopamine of a newly spawned NPC.
     pub start: f32,
 }
 
+/// Dopamine rewards for everyday activity; none is negative.
 #[derive(Debug, Clone)]
 pub struct MotivationGains {
+    /// Per recipe batch made; exchanges earn half.
     pub task: f32,
+    /// For speaking a line; the listener gets 60% of it.
     pub social: f32,
+    /// For a schedule activity matching a `[leisure]` keyword.
     pub leisure: f32,
 }
 
+/// Steady dopamine drain while awake.
 #[derive(Debug, Clone)]
 pub struct MotivationDecay {
+    /// Dopamine lost per scaled second; never negative.
     pub per_second: f32,
 }
 
+/// Daily check of an NPC's wellbeing dependencies.
 #[derive(Debug, Clone)]
 pub struct DependencyImpactConfig {
+    /// Reward when every dependency was met that day.
     pub satisfaction_bonus: f32,
+    /// Penalty per dependency missed that day.
     pub deficit_penalty: f32,
 }
 
+/// Dopamine at which each mood starts; below `tired` an NPC is depressed.
 #[derive(Debug, Clone)]
 pub struct MotivationMoodThresholds {
+    /// Energised at or above this.
     pub energised: f32,
+    /// Content at or above this.
     pub content: f32,
+    /// Tired at or above this.
     pub tired: f32,
 }
 
+/// A drink lifts the mood, then leaves a hangover that dulls work and speeds up decay.
 #[derive(Debug, Clone)]
 pub struct AlcoholConfig {
+    /// Dopamine gained per drink.
     pub boost: f32,
+    /// Scaled seconds a drink lasts before the hangover sets in.
     pub intoxication_seconds: f32,
+    /// Dopamine lost when the hangover starts.
     pub hangover_penalty: f32,
+    /// Decay multiplier while hung over.
     pub hangover_decay_multiplier: f32,
+    /// Scaled seconds the hangover lasts.
     pub hangover_duration_seconds: f32,
+    /// Fraction of task rewards lost while intoxicated or hung over.
     pub quality_penalty: f32,
+    /// Schedule activities containing one of these words count as a drink.
     pub trigger_keywords: Vec<String>,
+    /// Day fraction after which delivered drinks are drunk on receipt instead of stocked.
+    pub evening_start_fraction: f32,
 }
 
+/// Which schedule activities count as leisure.
 #[derive(Debug, Clone)]
 pub struct LeisureConfig {
+    /// Schedule activities containing one of these words earn the leisure gain.
     pub keywords: Vec<String>,
 }
 
+/// Sampling cadence and retention for per-NPC mood timelines.
+#[derive(Debug, Clone)]
+pub struct MotivationHistoryConfig {
+    /// Scaled simulation seconds between dopamine samples.
+    pub sample_interval_seconds: f32,
+    /// Maximum samples (and markers) retained per NPC.
+    pub capacity: usize,
+    /// Rewards or penalties at least this large are kept as timeline causes.
+    pub notable_change: f32,
+}
+
+/// Per-unit dopamine shift when the player gives goods to, or takes goods from, an NPC.
+#[derive(Debug, Clone)]
+pub struct PlayerTransferConfig {
+    /// Reward per unit the player gives.
+    pub give_bonus: f32,
+    /// Penalty per unit the player takes.
+    pub take_penalty: f32,
+}
+
+/// One-time boost for the celebrating NPC and a smaller social lift for those nearby.
+#[derive(Debug, Clone)]
+pub struct BirthdayConfig {
+    /// Boost for the NPC whose birthday it is.
+    pub reward: f32,
+    /// Boost for each NPC within `neighbour_radius`.
+    pub neighbour_reward: f32,
+    /// World units within which neighbours share the celebration.
+    pub neighbour_radius: f32,
+}
+
+/// Boost for reaching a new profession skill level.
+#[derive(Debug, Clone)]
+pub struct SkillRewardConfig {
+    /// Boost per level gained.
+    pub level_up_reward: f32,
+}
+
+/// Penalty for goods that spoil in an NPC's inventory.
+#[derive(Debug, Clone)]
+pub struct SpoilageConfig {
+    /// Penalty per spoiled unit.
+    pub penalty_per_unit: f32,
+    /// Most an NPC loses to spoilage in one day.
+    pub max_penalty: f32,
+}
+
+impl SpoilageConfig {
+    /// Penalty for losing `units` in one day, capped at `max_penalty`.
+    pub fn penalty_for(&self, units: u32) -> f32 {
+        (self.penalty_per_unit * units as f32).min(self.max_penalty)
+    }
+}
+
+/// Boost for turning up at the weekly market.
+#[derive(Debug, Clone)]
+pub struct MarketDayRewardConfig {
+    /// Boost on arriving, once per market day.
+    pub attendance_reward: f32,
+}
+
+/// Duty satisfaction for walking a patrol route at night.
+#[derive(Debug, Clone)]
+pub struct PatrolRewardConfig {
+    /// Boost per `reward_interval_seconds` of patrol.
+    pub duty_reward: f32,
+    /// Scaled seconds of night patrol per reward; at least 1.
+    pub reward_interval_seconds: f32,
+}
+
+/// Daily allowance of NPC-initiated dialogue requests, scaled by mood.
+#[derive(Debug, Clone)]
+pub struct ChatterConfig {
+    /// Requests per day before mood scaling; player-directed lines are exempt.
+    pub base_budget: u32,
+    /// Extra requests granted to each market-day attendee on arrival.
+    pub market_day_bonus: u32,
+    /// Budget multiplier for each mood.
+    pub modifiers: ChatterModifiers,
+    /// In-game minutes before the same two NPCs may chat again.
+    pub pair_window_minutes: f32,
+}
+
+/// Multipliers applied to the base chatter budget for each mood.
+#[derive(Debug, Clone)]
+pub struct ChatterModifiers {
+    /// Multiplier while energised.
+    pub energised: f32,
+    /// Multiplier while content.
+    pub content: f32,
+    /// Multiplier while tired.
+    pub tired: f32,
+    /// Multiplier while depressed.
+    pub depressed: f32,
+}
+
+/// Dopamine regained per scaled second while an NPC sleeps; replaces decay.
+#[derive(Debug, Clone)]
+pub struct SleepConfig {
+    /// Dopamine regained per scaled second asleep.
+    pub regen_per_second: f32,
+}
+
 impl MotivationConfig {
+    /// Reads and parses `config/motivation.toml`, migrating it on disk first when its
+    /// `schema_version` is older than `MOTIVATION_SCHEMA`.
+    ///
+    /// ```
+    /// use thegame::prelude::MotivationConfig;
+    ///
+    /// let config = MotivationConfig::load()?;
+    /// assert!(config.defaults.min <= config.defaults.start);
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn load() -> Result<Self, String> {
+        let raw = migration::read_migrated(&MOTIVATION_SCHEMA, CONFIG_PATH)?;
+        Self::from_toml_str(&raw)
+    }
+
+    /// Parses motivation TOML; missing sections and fields take their defaults. An older
+    /// schema version is migrated in memory first, and a newer one is an error.
+    ///
+    /// ```
+    /// use thegame::prelude::MotivationConfig;
+    ///
+    /// let config = MotivationConfig::from_toml_str("[sleep]\nregen_per_second = 2.5")?;
+    /// assert_eq!(config.sleep.regen_per_second, 2.5);
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn from_toml_str(data: &str) -> Result<Self, String> {
+        let data = migration::migrate_str(&MOTIVATION_SCHEMA, data)
+            .map_err(|err| format!("invalid motivation config: {err}"))?;
+        let parsed = toml::from_str::<RawMotivationConfig>(&data)
+            .map_err(|err| format!("invalid motivation config: {err}"))?;
+        Ok(parsed.into())
+    }
+
+    /// `load`, or the defaults with a warning when the file is missing or invalid.
     pub fn load_or_default() -> Self {
-        let path = Path::new(CONFIG_PATH);
-        match fs::read_to_string(path) {
-            Ok(raw) => match toml::from_str::<RawMotivationConfig>(&raw) {
-                Ok(parsed) => parsed.into(),
-                Err(err) => {
-                    warn!(
-                        "Failed to parse {} ({}). Falling back to defaults.",
-                        CONFIG_PATH, err
-                    );
-                    RawMotivationConfig::default().into()
-                }
-            },
-            Err(err) => {
-                warn!(
-                    "Failed to read {} ({}). Falling back to defaults.",
-                    CONFIG_PATH, err
-                );
-                RawMotivationConfig::default().into()
-            }
-        }
+        Self::load().unwrap_or_else(|err| {
+            warn!(
+                "Failed to load {} ({}). Falling back to defaults.",
+                CONFIG_PATH, err
+            );
+            Self::default()
+        })
+    }
+}
+
+impl Default for MotivationConfig {
+    fn default() -> Self {
+        RawMotivationConfig::default().into()
     }
 }
 
@@ -287,12 +646,64 @@ impl From<RawMotivationConfig> for MotivationConfig {
             hangover_duration_seconds: value.alcohol.hangover_duration_seconds.max(0.0),
             quality_penalty: value.alcohol.quality_penalty.clamp(0.0, 1.0),
             trigger_keywords: normalise_keywords(&value.alcohol.trigger_keywords),
+            evening_start_fraction: value.alcohol.evening_start_fraction.clamp(0.0, 1.0),
         };
 
         let leisure = LeisureConfig {
             keywords: normalise_keywords(&value.leisure.keywords),
         };
 
+        let history = MotivationHistoryConfig {
+            sample_interval_seconds: value.history.sample_interval_seconds.max(0.1),
+            capacity: value.history.capacity.max(1),
+            notable_change: value.history.notable_change.max(0.0),
+        };
+
+        let player_transfer = PlayerTransferConfig {
+            give_bonus: value.player_transfer.give_bonus.max(0.0),
+            take_penalty: value.player_transfer.take_penalty.max(0.0),
+        };
+
+        let birthday = BirthdayConfig {
+            reward: value.birthday.reward.max(0.0),
+            neighbour_reward: value.birthday.neighbour_reward.max(0.0),
+            neighbour_radius: value.birthday.neighbour_radius.max(0.0),
+        };
+
+        let chatter = ChatterConfig {
+            base_budget: value.chatter.base_budget,
+            market_day_bonus: value.chatter.market_day_bonus,
+            modifiers: ChatterModifiers {
+                energised: value.chatter.energised_multiplier.max(0.0),
+                content: value.chatter.content_multiplier.max(0.0),
+                tired: value.chatter.tired_multiplier.max(0.0),
+                depressed: value.chatter.depressed_multiplier.max(0.0),
+            },
+            pair_window_minutes: value.chatter.pair_window_minutes.max(0.0),
+        };
+
+        let sleep = SleepConfig {
+            regen_per_second: value.sleep.regen_per_second.max(0.0),
+        };
+
+        let skill = SkillRewardConfig {
+            level_up_reward: value.skill.level_up_reward.max(0.0),
+        };
+
+        let spoilage = SpoilageConfig {
+            penalty_per_unit: value.spoilage.penalty_per_unit.max(0.0),
+            max_penalty: value.spoilage.max_penalty.max(0.0),
+        };
+
+        let market_day = MarketDayRewardConfig {
+            attendance_reward: value.market_day.attendance_reward.max(0.0),
+        };
+
+        let patrol = PatrolRewardConfig {
+            duty_reward: value.patrol.duty_reward.max(0.0),
+            reward_interval_seconds: value.patrol.reward_interval_seconds.max(1.0),
+        };
+
         Self {
             defaults,
             gains,
@@ -301,6 +712,15 @@ impl From<RawMotivationConfig> for MotivationConfig {
             thresholds,
             alcohol,
             leisure,
+            history,
+            player_transfer,
+            birthday,
+            chatter,
+            sleep,
+            skill,
+            spoilage,
+            market_day,
+            patrol,
         }
     }
 }
@@ -0,0 +1,377 @@
+//! Per-NPC dopamine timelines: periodic samples, mood-change markers, and notable causes.
+use std::collections::{HashMap, VecDeque};
+
+use bevy::prelude::*;
+
+use crate::{
+    core::plugin::SimulationClock,
+    npc::components::{Identity, NpcId},
+    world::time::{DayChangedEvent, WorldClock},
+};
+
+use super::{
+    config::{MotivationConfig, MotivationHistoryConfig},
+    state::{MotivationReason, NpcMood, NpcMotivation},
+};
+
+const SPARKLINE_BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const SPARKLINE_WIDTH: usize = 24;
+
+/// Dopamine level captured at a point on the world clock.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MotivationSample {
+    pub day: u64,
+    pub time_of_day: f32,
+    pub dopamine: f32,
+}
+
+/// Moment an NPC's mood band changed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MoodChangeMarker {
+    pub day: u64,
+    pub time_of_day: f32,
+    pub mood: NpcMood,
+}
+
+/// Reward or penalty worth surfacing on the timeline (hangovers, dependency penalties, big rewards).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MotivationCauseMarker {
+    pub day: u64,
+    pub time_of_day: f32,
+    pub reason: MotivationReason,
+    pub delta: f32,
+}
+
+/// Bounded history for a single NPC; every buffer drops its oldest entry once full.
+#[derive(Debug, Clone)]
+pub struct MotivationTimeline {
+    capacity: usize,
+    samples: VecDeque<MotivationSample>,
+    mood_changes: VecDeque<MoodChangeMarker>,
+    causes: VecDeque<MotivationCauseMarker>,
+    last_mood: Option<NpcMood>,
+}
+
+impl MotivationTimeline {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            samples: VecDeque::new(),
+            mood_changes: VecDeque::new(),
+            causes: VecDeque::new(),
+            last_mood: None,
+        }
+    }
+
+    pub fn samples(&self) -> impl Iterator<Item = &MotivationSample> {
+        self.samples.iter()
+    }
+
+    pub fn causes(&self) -> impl Iterator<Item = &MotivationCauseMarker> {
+        self.causes.iter()
+    }
+
+    pub fn mood_changes_today(&self, day: u64) -> impl Iterator<Item = &MoodChangeMarker> {
+        self.mood_changes
+            .iter()
+            .filter(move |marker| marker.day == day)
+    }
+
+    pub fn record_sample(&mut self, sample: MotivationSample) {
+        push_bounded(&mut self.samples, sample, self.capacity);
+    }
+
+    /// Records a marker when `mood` differs from the last observed mood. The first
+    /// observation only seeds the baseline.
+    pub fn observe_mood(&mut self, day: u64, time_of_day: f32, mood: NpcMood) {
+        let previous = self.last_mood.replace(mood);
+        if previous.is_some_and(|previous| previous != mood) {
+            push_bounded(
+                &mut self.mood_changes,
+                MoodChangeMarker {
+                    day,
+                    time_of_day,
+                    mood,
+                },
+                self.capacity,
+            );
+        }
+    }
+
+    pub fn record_cause(&mut self, cause: MotivationCauseMarker) {
+        push_bounded(&mut self.causes, cause, self.capacity);
+    }
+
+    /// Returns `points` dopamine values evenly spaced across the retained samples, always
+    /// including the first and last. Returns every sample when fewer than `points` exist.
+    pub fn downsample(&self, points: usize) -> Vec<f32> {
+        let len = self.samples.len();
+        if points == 0 || len == 0 {
+            return Vec::new();
+        }
+        if len <= points {
+            return self.samples().map(|sample| sample.dopamine).collect();
+        }
+        if points == 1 {
+            return vec![self.samples[len - 1].dopamine];
+        }
+
+        (0..points)
+            .map(|index| {
+                let position = index * (len - 1) / (points - 1);
+                self.samples[position].dopamine
+            })
+            .collect()
+    }
+}
+
+/// Mood timelines keyed by NPC, sampled on scaled simulation time.
+#[derive(Resource, Debug, Default)]
+pub struct MotivationHistory {
+    timelines: HashMap<NpcId, MotivationTimeline>,
+    since_last_sample: f32,
+}
+
+impl MotivationHistory {
+    pub fn timeline(&self, npc: NpcId) -> Option<&MotivationTimeline> {
+        self.timelines.get(&npc)
+    }
+
+    fn timeline_mut(&mut self, npc: NpcId, capacity: usize) -> &mut MotivationTimeline {
+        self.timelines
+            .entry(npc)
+            .or_insert_with(|| MotivationTimeline::new(capacity))
+    }
+
+    /// Accumulates scaled time and reports whether a sample is due.
+    fn advance(&mut self, delta_seconds: f32, config: &MotivationHistoryConfig) -> bool {
+        self.since_last_sample += delta_seconds;
+        if self.since_last_sample < config.sample_interval_seconds {
+            return false;
+        }
+        self.since_last_sample = 0.0;
+        true
+    }
+}
+
+/// True for causes that always appear on the timeline, or rewards/penalties above the threshold.
+pub fn is_notable_change(reason: MotivationReason, delta: f32, notable_change: f32) -> bool {
+    matches!(
+        reason,
+        MotivationReason::Hangover | MotivationReason::DependencyDeficit
+    ) || delta.abs() >= notable_change
+}
+
+/// Renders values as unicode block characters scaled between `min` and `max`.
+pub fn sparkline(values: &[f32], min: f32, max: f32) -> String {
+    let range = (max - min).max(f32::EPSILON);
+    let top = SPARKLINE_BLOCKS.len() - 1;
+    values
+        .iter()
+        .map(|value| {
+            let normalised = ((value - min) / range).clamp(0.0, 1.0);
+            SPARKLINE_BLOCKS[(normalised * top as f32).round() as usize]
+        })
+        .collect()
+}
+
+/// Drains tagged motivation changes, tracks mood transitions, samples dopamine on the
+/// configured interval, and logs each NPC's sparkline for the day just ended whenever a
+/// `DayChangedEvent` arrives.
+pub fn record_motivation_history(
+    sim_clock: Res<SimulationClock>,
+    clock: Res<WorldClock>,
+    config: Res<MotivationConfig>,
+    mut day_changes: MessageReader<DayChangedEvent>,
+    mut history: ResMut<MotivationHistory>,
+    mut query: Query<(&Identity, &mut NpcMotivation)>,
+) {
+    let day = clock.day_count();
+    let time_of_day = clock.time_of_day();
+    let history_config = &config.history;
+
+    let ended = day_changes
+        .read()
+        .filter_map(|change| change.previous_day)
+        .last();
+    if let Some(yesterday) = ended {
+        for (identity, _) in query.iter() {
+            if let Some(timeline) = history.timeline(identity.id) {
+                let causes: Vec<&str> = timeline
+                    .causes()
+                    .filter(|cause| cause.day == yesterday)
+                    .map(|cause| cause.reason.label())
+                    .collect();
+                debug!(
+                    "{} mood timeline: {} ({} mood changes yesterday; causes: {})",
+                    identity.display_name,
+                    sparkline(
+                        &timeline.downsample(SPARKLINE_WIDTH),
+                        config.defaults.min,
+                        config.defaults.max
+                    ),
+                    timeline.mood_changes_today(yesterday).count(),
+                    if causes.is_empty() {
+                        "none".to_string()
+                    } else {
+                        causes.join(", ")
+                    }
+                );
+            }
+        }
+    }
+
+    let sample_due = history.advance(sim_clock.last_scaled_delta().as_secs_f32(), history_config);
+
+    for (identity, mut motivation) in query.iter_mut() {
+        let timeline = history.timeline_mut(identity.id, history_config.capacity);
+        for change in motivation.take_changes() {
+            if is_notable_change(change.reason, change.delta, history_config.notable_change) {
+                timeline.record_cause(MotivationCauseMarker {
+                    day,
+                    time_of_day,
+                    reason: change.reason,
+                    delta: change.delta,
+                });
+            }
+        }
+
+        timeline.observe_mood(day, time_of_day, motivation.mood());
+
+        if sample_due {
+            timeline.record_sample(MotivationSample {
+                day,
+                time_of_day,
+                dopamine: motivation.dopamine(),
+            });
+        }
+    }
+}
+
+fn push_bounded<T>(buffer: &mut VecDeque<T>, value: T, capacity: usize) {
+    if buffer.len() == capacity {
+        buffer.pop_front();
+    }
+    buffer.push_back(value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(index: usize) -> MotivationSample {
+        MotivationSample {
+            day: 0,
+            time_of_day: index as f32 / 100.0,
+            dopamine: index as f32,
+        }
+    }
+
+    #[test]
+    fn ring_buffers_stay_within_capacity() {
+        let mut timeline = MotivationTimeline::new(4);
+        for index in 0..10 {
+            timeline.record_sample(sample(index));
+            timeline.record_cause(MotivationCauseMarker {
+                day: 0,
+                time_of_day: 0.0,
+                reason: MotivationReason::Trade,
+                delta: index as f32,
+            });
+        }
+
+        let kept: Vec<f32> = timeline.samples().map(|sample| sample.dopamine).collect();
+        assert_eq!(kept, vec![6.0, 7.0, 8.0, 9.0], "oldest samples are dropped");
+        assert_eq!(timeline.causes().count(), 4);
+    }
+
+    #[test]
+    fn downsample_returns_evenly_spaced_points() {
+        let mut timeline = MotivationTimeline::new(100);
+        for index in 0..9 {
+            timeline.record_sample(sample(index));
+        }
+
+        assert_eq!(timeline.downsample(5), vec![0.0, 2.0, 4.0, 6.0, 8.0]);
+        assert_eq!(timeline.downsample(2), vec![0.0, 8.0]);
+        assert_eq!(timeline.downsample(1), vec![8.0]);
+        assert_eq!(
+            timeline.downsample(20).len(),
+            9,
+            "short history is returned whole"
+        );
+        assert!(timeline.downsample(0).is_empty());
+        assert!(MotivationTimeline::new(4).downsample(5).is_empty());
+    }
+
+    #[test]
+    fn mood_markers_are_filtered_by_day() {
+        let mut timeline = MotivationTimeline::new(8);
+        timeline.observe_mood(1, 0.2, NpcMood::Content);
+        timeline.observe_mood(1, 0.4, NpcMood::Content);
+        timeline.observe_mood(1, 0.6, NpcMood::Tired);
+        timeline.observe_mood(2, 0.1, NpcMood::Depressed);
+
+        let day_one: Vec<_> = timeline.mood_changes_today(1).collect();
+        assert_eq!(day_one.len(), 1, "baseline and repeats are not markers");
+        assert_eq!(day_one[0].mood, NpcMood::Tired);
+        assert_eq!(timeline.mood_changes_today(2).count(), 1);
+    }
+
+    #[test]
+    fn notable_changes_follow_reason_and_threshold() {
+        assert!(is_notable_change(MotivationReason::Hangover, -1.0, 10.0));
+        assert!(is_notable_change(
+            MotivationReason::DependencyDeficit,
+            -7.5,
+            10.0
+        ));
+        assert!(is_notable_change(MotivationReason::Alcohol, 12.0, 10.0));
+        assert!(!is_notable_change(MotivationReason::Social, 6.0, 10.0));
+    }
+
+    #[test]
+    fn system_attributes_causes_and_samples_on_interval() {
+        let config = MotivationConfig::load_or_default();
+        let mut app = App::new();
+        app.insert_resource(config.clone())
+            .insert_resource(
+                SimulationClock::new(1.0)
+                    .with_max_frame_delta(config.history.sample_interval_seconds),
+            )
+            .insert_resource(WorldClock::new())
+            .init_resource::<MotivationHistory>()
+            .add_message::<DayChangedEvent>()
+            .add_systems(Update, record_motivation_history);
+
+        let npc = NpcId::new(0);
+        let mut motivation = NpcMotivation::new(&config);
+        motivation.apply_penalty(
+            config.dependency.deficit_penalty,
+            MotivationReason::DependencyDeficit,
+            &config,
+        );
+        motivation.apply_reward(1.0, MotivationReason::Social, &config);
+        app.world_mut()
+            .spawn((Identity::new(npc, "Maren", 28.0), motivation));
+
+        app.world_mut()
+            .resource_mut::<SimulationClock>()
+            .tick(std::time::Duration::from_secs_f32(
+                config.history.sample_interval_seconds,
+            ));
+        app.update();
+
+        let history = app.world().resource::<MotivationHistory>();
+        let timeline = history.timeline(npc).expect("timeline created");
+        let reasons: Vec<_> = timeline.causes().map(|cause| cause.reason).collect();
+        assert_eq!(reasons, vec![MotivationReason::DependencyDeficit]);
+        assert_eq!(timeline.samples().count(), 1);
+    }
+
+    #[test]
+    fn sparkline_scales_between_bounds() {
+        assert_eq!(sparkline(&[0.0, 50.0, 100.0, 150.0], 0.0, 100.0), "▁▅██");
+        assert_eq!(sparkline(&[], 0.0, 100.0), "");
+    }
+}
@@ -0,0 +1,173 @@
+//! Schema versions of `config/motivation.toml`. Version 2 writes out every setting added
+//! after the first release, which older files only picked up as silent defaults, and gives
+//! untouched leisure keywords the `idle` keyword that came with schedule gaps.
+use toml_edit::{DocumentMut, Item, Value};
+
+use crate::core::migration::{ConfigSchema, MigrationStep};
+
+pub const MOTIVATION_SCHEMA: ConfigSchema = ConfigSchema {
+    name: "motivation",
+    steps: &[MigrationStep {
+        summary: "explicit reward sections",
+        apply: write_out_added_settings,
+    }],
+};
+
+/// Keys added to the motivation file after the first release, with the value the game
+/// used for each while it was missing. Pinned here so later default changes do not change
+/// what old files migrate to.
+const ADDED_SETTINGS: &[(&str, &str, f64)] = &[
+    ("alcohol", "evening_start_fraction", 0.7),
+    ("history", "sample_interval_seconds", 5.0),
+    ("history", "capacity", 288.0),
+    ("history", "notable_change", 10.0),
+    ("player_transfer", "give_bonus", 3.0),
+    ("player_transfer", "take_penalty", 2.0),
+    ("birthday", "reward", 10.0),
+    ("birthday", "neighbour_reward", 3.0),
+    ("birthday", "neighbour_radius", 12.0),
+    ("chatter", "base_budget", 6.0),
+    ("chatter", "market_day_bonus", 3.0),
+    ("chatter", "energised_multiplier", 1.5),
+    ("chatter", "content_multiplier", 1.0),
+    ("chatter", "tired_multiplier", 1.0),
+    ("chatter", "depressed_multiplier", 0.3),
+    ("sleep", "regen_per_second", 0.4),
+    ("skill", "level_up_reward", 8.0),
+    ("spoilage", "penalty_per_unit", 1.0),
+    ("spoilage", "max_penalty", 5.0),
+    ("market_day", "attendance_reward", 4.0),
+    ("patrol", "duty_reward", 0.5),
+    ("patrol", "reward_interval_seconds", 30.0),
+];
+
+/// Keys read as integers; the rest are floats.
+const INTEGER_SETTINGS: [&str; 3] = ["capacity", "base_budget", "market_day_bonus"];
+
+/// The leisure keywords of the first release; a file still listing exactly these never
+/// chose them, so it gets the keyword added since.
+const FIRST_LEISURE_KEYWORDS: [&str; 5] = ["supper", "stories", "lute", "rest", "tavern"];
+const ADDED_LEISURE_KEYWORD: &str = "idle";
+
+/// Fills every missing `ADDED_SETTINGS` key with its default, creating sections as
+/// needed, and appends `idle` to an unedited first-release leisure keyword list. Keys
+/// already present keep their value.
+fn write_out_added_settings(document: &mut DocumentMut) -> Vec<String> {
+    let mut changes = Vec::new();
+    for (section, key, default) in ADDED_SETTINGS {
+        let Some(section_table) = document
+            .entry(section)
+            .or_insert(toml_edit::table())
+            .as_table_like_mut()
+        else {
+            // Not a table: left for the loader to reject.
+            continue;
+        };
+        if section_table.contains_key(key) {
+            continue;
+        }
+        let value = if INTEGER_SETTINGS.contains(key) {
+            Value::from(*default as i64)
+        } else {
+            Value::from(*default)
+        };
+        changes.push(format!("{section}.{key} defaulted to {value}"));
+        section_table.insert(key, Item::Value(value));
+    }
+
+    let keywords = document
+        .get_mut("leisure")
+        .and_then(|leisure| leisure.get_mut("keywords"))
+        .and_then(Item::as_array_mut);
+    if let Some(keywords) = keywords {
+        let unedited = keywords
+            .iter()
+            .map(Value::as_str)
+            .eq(FIRST_LEISURE_KEYWORDS.iter().map(|keyword| Some(*keyword)));
+        if unedited {
+            keywords.push(ADDED_LEISURE_KEYWORD);
+            changes.push(format!(
+                "leisure.keywords gained \"{ADDED_LEISURE_KEYWORD}\", a default since schedules \
+                 gained idle gaps"
+            ));
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        core::migration::{migrate_document, migrate_str},
+        npc::motivation::MotivationConfig,
+    };
+
+    /// `config/motivation.toml` as it shipped before versioning, with a tuned sleep rate.
+    const LEGACY_MOTIVATION: &str = r#"
+[gains]
+task = 9.0
+
+[leisure]
+keywords = ["supper", "stories", "lute", "rest", "tavern"]
+
+[sleep]
+regen_per_second = 2.5
+"#;
+
+    #[test]
+    fn legacy_files_get_every_added_setting_written_out() {
+        let mut document: DocumentMut = LEGACY_MOTIVATION.parse().unwrap();
+        let report = migrate_document(&MOTIVATION_SCHEMA, &mut document)
+            .unwrap()
+            .expect("a legacy file migrates");
+        assert_eq!((report.from, report.to), (1, 2));
+        assert_eq!(
+            report.changes.len(),
+            ADDED_SETTINGS.len(),
+            "every added key but the tuned sleep rate, plus the leisure keyword"
+        );
+        assert!(report
+            .changes
+            .contains(&"explicit reward sections: history.capacity defaulted to 288".to_string()));
+        assert!(!report.changes.iter().any(|change| change.contains("sleep")));
+
+        let migrated = migrate_str(&MOTIVATION_SCHEMA, LEGACY_MOTIVATION).unwrap();
+        let config = MotivationConfig::from_toml_str(&migrated).unwrap();
+        let defaults = MotivationConfig::default();
+        assert_eq!(config.sleep.regen_per_second, 2.5, "tuned values survive");
+        assert_eq!(config.gains.task, 9.0);
+        assert_eq!(config.history.capacity, defaults.history.capacity);
+        assert_eq!(config.chatter.base_budget, defaults.chatter.base_budget);
+        assert_eq!(config.patrol.duty_reward, defaults.patrol.duty_reward);
+        assert!(config.leisure.keywords.contains(&"idle".to_string()));
+    }
+
+    #[test]
+    fn pinned_settings_match_the_current_defaults() {
+        let mut document = DocumentMut::new();
+        write_out_added_settings(&mut document);
+        let pinned = MotivationConfig::from_toml_str(&document.to_string()).unwrap();
+        let defaults = MotivationConfig::default();
+        assert_eq!(
+            format!("{pinned:?}"),
+            format!("{defaults:?}"),
+            "migrating an empty file changes nothing the game would have used"
+        );
+    }
+
+    #[test]
+    fn customised_leisure_keywords_are_left_alone() {
+        let mut document: DocumentMut = "[leisure]\nkeywords = [\"supper\", \"dice\"]"
+            .parse()
+            .unwrap();
+        let changes = write_out_added_settings(&mut document);
+        assert!(!changes.iter().any(|change| change.contains("leisure")));
+        assert_eq!(
+            document["leisure"]["keywords"]
+                .as_array()
+                .map(toml_edit::Array::len),
+            Some(2)
+        );
+    }
+}
@@ -1,10 +1,16 @@
+pub mod adjustments;
 pub mod config;
+pub mod history;
+pub mod migration;
 pub mod state;
 pub mod systems;
 
+pub use adjustments::{apply_motivation_adjustments, MotivationAdjustmentEvent};
 pub use config::MotivationConfig;
+pub use history::{record_motivation_history, MotivationHistory};
 pub use state::{DailyDependencyTracker, NpcMotivation};
 pub use systems::{
-    decay_npc_motivation, evaluate_dependency_impacts, reward_from_dialogue_responses,
-    reward_from_leisure, reward_from_trade_events, track_dependency_satisfaction,
+    decay_npc_motivation, drink_delivered_ale, evaluate_dependency_impacts,
+    reload_motivation_config, reward_from_dialogue_responses, reward_from_leisure,
+    reward_from_trade_events, track_dependency_satisfaction,
 };
@@ -1,4 +1,4 @@
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, VecDeque};
 
 use bevy::prelude::*;
 
@@ -7,7 +7,7 @@ use crate::economy::dependency::DependencyCategory;
 use super::config::{AlcoholConfig, MotivationConfig};
 use crate::npc::components::NpcId;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum NpcMood {
     Energised,
     Content,
@@ -16,6 +16,8 @@ pub enum NpcMood {
 }
 
 impl NpcMood {
+    pub const ALL: [NpcMood; 4] = [Self::Energised, Self::Content, Self::Tired, Self::Depressed];
+
     pub fn label(self) -> &'static str {
         match self {
             Self::Energised => "energised",
@@ -26,12 +28,73 @@ impl NpcMood {
     }
 }
 
+/// Upper bound on tagged changes buffered between history samples.
+const MAX_PENDING_CHANGES: usize = 32;
+
+/// Why dopamine moved; attached to every reward or penalty for the mood timeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MotivationReason {
+    Trade,
+    Social,
+    Leisure,
+    Alcohol,
+    Hangover,
+    DependencySatisfied,
+    DependencyDeficit,
+    PlayerTransfer,
+    Birthday,
+    MarketDay,
+    SkillLevelUp,
+    Spoilage,
+    Decay,
+    Sleep,
+    QuestFulfilled,
+    Duty,
+    /// Set by hand from the developer console.
+    Console,
+}
+
+impl MotivationReason {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Trade => "trade work",
+            Self::Social => "conversation",
+            Self::Leisure => "leisure",
+            Self::Alcohol => "drink",
+            Self::Hangover => "hangover",
+            Self::DependencySatisfied => "needs met",
+            Self::DependencyDeficit => "dependency penalty",
+            Self::PlayerTransfer => "player transfer",
+            Self::Birthday => "birthday",
+            Self::MarketDay => "market day",
+            Self::SkillLevelUp => "skill level-up",
+            Self::Spoilage => "spoiled goods",
+            Self::Decay => "decay",
+            Self::Sleep => "sleep",
+            Self::QuestFulfilled => "fetch quest",
+            Self::Duty => "night duty",
+            Self::Console => "console",
+        }
+    }
+}
+
+/// Signed dopamine change after clamping, tagged with its reason.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MotivationChange {
+    pub reason: MotivationReason,
+    pub delta: f32,
+}
+
 #[derive(Component, Debug, Clone)]
 pub struct NpcMotivation {
     dopamine: f32,
     mood: NpcMood,
     intoxication_timer: f32,
     hangover_timer: f32,
+    pending_changes: VecDeque<MotivationChange>,
+    /// Decay and sleep totals not yet recorded; see `flush_continuous_changes`.
+    unrecorded_decay: f32,
+    unrecorded_sleep: f32,
 }
 
 impl NpcMotivation {
@@ -41,6 +104,9 @@ impl NpcMotivation {
             mood: NpcMood::Content,
             intoxication_timer: 0.0,
             hangover_timer: 0.0,
+            pending_changes: VecDeque::new(),
+            unrecorded_decay: 0.0,
+            unrecorded_sleep: 0.0,
         };
         motivation.recompute_mood(config);
         motivation
@@ -62,32 +128,66 @@ impl NpcMotivation {
         self.hangover_timer > 0.0
     }
 
-    pub fn apply_reward(&mut self, amount: f32, config: &MotivationConfig) {
+    /// Moves dopamine by a signed `amount`, clamped to the configured range, and returns
+    /// the change that actually landed. Alcohol adjustments also start the intoxication
+    /// timer. The change is not recorded; callers pass it to `record_change`.
+    pub fn apply_adjustment(
+        &mut self,
+        amount: f32,
+        reason: MotivationReason,
+        config: &MotivationConfig,
+    ) -> f32 {
+        if reason == MotivationReason::Alcohol {
+            self.intoxication_timer = config.alcohol.intoxication_seconds;
+        }
+        let previous = self.dopamine;
+        if amount > 0.0 {
+            self.dopamine = (self.dopamine + amount).min(config.defaults.max);
+        } else if amount < 0.0 {
+            self.dopamine = (self.dopamine + amount).max(config.defaults.min);
+        } else {
+            return 0.0;
+        }
+        self.recompute_mood(config);
+        self.dopamine - previous
+    }
+
+    pub fn apply_reward(
+        &mut self,
+        amount: f32,
+        reason: MotivationReason,
+        config: &MotivationConfig,
+    ) {
         if amount <= 0.0 {
             return;
         }
-        self.dopamine = (self.dopamine + amount).min(config.defaults.max);
-        self.recompute_mood(config);
+        let delta = self.apply_adjustment(amount, reason, config);
+        self.record_change(reason, delta);
     }
 
-    pub fn apply_penalty(&mut self, amount: f32, config: &MotivationConfig) {
+    pub fn apply_penalty(
+        &mut self,
+        amount: f32,
+        reason: MotivationReason,
+        config: &MotivationConfig,
+    ) {
         if amount <= 0.0 {
             return;
         }
-        self.dopamine = (self.dopamine - amount).max(config.defaults.min);
-        self.recompute_mood(config);
+        let delta = self.apply_adjustment(-amount, reason, config);
+        self.record_change(reason, delta);
     }
 
-    pub fn trigger_alcohol_boost(&mut self, config: &MotivationConfig) {
-        self.apply_reward(config.alcohol.boost, config);
-        self.intoxication_timer = config.alcohol.intoxication_seconds;
+    /// Drains tagged changes recorded since the last call, oldest first.
+    pub fn take_changes(&mut self) -> Vec<MotivationChange> {
+        self.pending_changes.drain(..).collect()
     }
 
     pub fn tick(&mut self, delta_seconds: f32, config: &MotivationConfig) -> MotivationTickOutcome {
-        let mut outcome = MotivationTickOutcome::default();
         if delta_seconds <= 0.0 {
-            return outcome;
+            return MotivationTickOutcome::default();
         }
+        let previous_mood = self.mood;
 
         let mut decay_amount = config.decay.per_second * delta_seconds;
         if self.hangover_timer > 0.0 {
@@ -95,27 +195,90 @@ impl NpcMotivation {
             decay_amount *= config.alcohol.hangover_decay_multiplier;
         }
 
-        self.apply_penalty(decay_amount, config);
+        self.apply_penalty(decay_amount, MotivationReason::Decay, config);
+        self.finish_tick(delta_seconds, previous_mood, config)
+    }
+
+    /// `tick` for a sleeping NPC: dopamine regenerates at `sleep.regen_per_second` instead
+    /// of decaying, while drink and hangover timers keep running.
+    pub fn tick_sleeping(
+        &mut self,
+        delta_seconds: f32,
+        config: &MotivationConfig,
+    ) -> MotivationTickOutcome {
+        if delta_seconds <= 0.0 {
+            return MotivationTickOutcome::default();
+        }
+        let previous_mood = self.mood;
+
+        self.hangover_timer = (self.hangover_timer - delta_seconds).max(0.0);
+        self.apply_reward(
+            config.sleep.regen_per_second * delta_seconds,
+            MotivationReason::Sleep,
+            config,
+        );
+        self.finish_tick(delta_seconds, previous_mood, config)
+    }
 
+    /// Runs the intoxication timer and reports a mood change since `previous_mood`.
+    fn finish_tick(
+        &mut self,
+        delta_seconds: f32,
+        previous_mood: NpcMood,
+        config: &MotivationConfig,
+    ) -> MotivationTickOutcome {
+        let mut outcome = MotivationTickOutcome::default();
         if self.intoxication_timer > 0.0 {
             let previous = self.intoxication_timer;
             self.intoxication_timer = (self.intoxication_timer - delta_seconds).max(0.0);
             if previous > 0.0 && self.intoxication_timer == 0.0 {
-                self.apply_penalty(config.alcohol.hangover_penalty, config);
+                self.apply_penalty(
+                    config.alcohol.hangover_penalty,
+                    MotivationReason::Hangover,
+                    config,
+                );
                 self.hangover_timer = config.alcohol.hangover_duration_seconds;
                 outcome.hangover_triggered = true;
             }
         }
 
-        let new_mood = determine_mood(self.dopamine, config);
-        if new_mood != self.mood {
-            self.mood = new_mood;
-            outcome.mood_changed = Some(new_mood);
+        self.recompute_mood(config);
+        if self.mood != previous_mood {
+            outcome.mood_changed = Some(self.mood);
         }
 
         outcome
     }
 
+    /// Queues a change for the timeline. Continuous decay and sleep regeneration are only
+    /// summed here; `flush_continuous_changes` records the totals as one change each.
+    pub fn record_change(&mut self, reason: MotivationReason, delta: f32) {
+        match reason {
+            MotivationReason::Decay => self.unrecorded_decay += delta,
+            MotivationReason::Sleep => self.unrecorded_sleep += delta,
+            _ => self.push_change(reason, delta),
+        }
+    }
+
+    /// Records the decay and sleep totals summed since the previous flush.
+    pub fn flush_continuous_changes(&mut self) {
+        let decay = std::mem::take(&mut self.unrecorded_decay);
+        let sleep = std::mem::take(&mut self.unrecorded_sleep);
+        self.push_change(MotivationReason::Decay, decay);
+        self.push_change(MotivationReason::Sleep, sleep);
+    }
+
+    fn push_change(&mut self, reason: MotivationReason, delta: f32) {
+        if delta == 0.0 {
+            return;
+        }
+        if self.pending_changes.len() == MAX_PENDING_CHANGES {
+            self.pending_changes.pop_front();
+        }
+        self.pending_changes
+            .push_back(MotivationChange { reason, delta });
+    }
+
     fn recompute_mood(&mut self, config: &MotivationConfig) {
         self.mood = determine_mood(self.dopamine, config);
     }
@@ -205,12 +368,82 @@ mod tests {
     fn motivation_tick_updates_mood() {
         let config = MotivationConfig::load_or_default();
         let mut motivation = NpcMotivation::new(&config);
-        motivation.apply_penalty(50.0, &config);
+        motivation.apply_penalty(50.0, MotivationReason::DependencyDeficit, &config);
         assert_eq!(motivation.mood(), NpcMood::Depressed);
-        motivation.apply_reward(80.0, &config);
+        motivation.apply_reward(80.0, MotivationReason::Trade, &config);
         assert_eq!(motivation.mood(), NpcMood::Energised);
     }
 
+    #[test]
+    fn changes_are_tagged_with_their_reason() {
+        let config = MotivationConfig::load_or_default();
+        let mut motivation = NpcMotivation::new(&config);
+        motivation.apply_reward(5.0, MotivationReason::Social, &config);
+        motivation.apply_penalty(7.5, MotivationReason::DependencyDeficit, &config);
+        motivation.tick(1.0, &config);
+
+        let changes = motivation.take_changes();
+        assert_eq!(
+            changes,
+            vec![
+                MotivationChange {
+                    reason: MotivationReason::Social,
+                    delta: 5.0
+                },
+                MotivationChange {
+                    reason: MotivationReason::DependencyDeficit,
+                    delta: -7.5
+                },
+            ],
+            "decay is not buffered"
+        );
+        assert!(motivation.take_changes().is_empty());
+    }
+
+    #[test]
+    fn hangover_and_clamped_rewards_are_attributed() {
+        let config = MotivationConfig::load_or_default();
+        let mut motivation = NpcMotivation::new(&config);
+        motivation.apply_reward(1000.0, MotivationReason::Trade, &config);
+        let reward = motivation.take_changes();
+        assert_eq!(reward.len(), 1);
+        assert_eq!(
+            reward[0].delta,
+            config.defaults.max - config.defaults.start,
+            "delta reflects the clamped change"
+        );
+
+        motivation.apply_reward(config.alcohol.boost, MotivationReason::Alcohol, &config);
+        motivation.tick(config.alcohol.intoxication_seconds + 1.0, &config);
+        let reasons: Vec<_> = motivation
+            .take_changes()
+            .into_iter()
+            .map(|change| change.reason)
+            .collect();
+        assert!(reasons.contains(&MotivationReason::Hangover));
+    }
+
+    #[test]
+    fn sleep_regenerates_where_waking_decays() {
+        let config = MotivationConfig::default();
+        let mut awake = NpcMotivation::new(&config);
+        let mut asleep = NpcMotivation::new(&config);
+        awake.tick(10.0, &config);
+        asleep.tick_sleeping(10.0, &config);
+
+        let start = config.defaults.start;
+        assert!((awake.dopamine() - (start - config.decay.per_second * 10.0)).abs() < 1e-4);
+        assert!((asleep.dopamine() - (start + config.sleep.regen_per_second * 10.0)).abs() < 1e-4);
+        assert!(
+            asleep.take_changes().is_empty(),
+            "regeneration is not buffered"
+        );
+
+        let outcome = asleep.tick_sleeping(1000.0, &config);
+        assert_eq!(asleep.dopamine(), config.defaults.max);
+        assert_eq!(outcome.mood_changed, Some(NpcMood::Energised));
+    }
+
     #[test]
     fn dependency_tracker_records_flags() {
         let mut tracker = DailyDependencyTracker::default();
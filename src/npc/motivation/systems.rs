@@ -3,29 +3,54 @@ use std::collections::HashMap;
 use bevy::prelude::*;
 
 use crate::{
-    core::plugin::SimulationClock,
+    core::{
+        config::{ConfigDiagnostics, ConfigReloadRequested},
+        format::spoken_time,
+        plugin::SimulationClock,
+    },
     dialogue::events::DialogueResponseEvent,
     economy::{
-        components::Profession,
+        components::{Inventory, Profession},
         dependency::EconomyDependencyMatrix,
-        events::{ProfessionDependencyUpdateEvent, TradeCompletedEvent, TradeReason},
+        events::{
+            InventoryChangedEvent, ProfessionDependencyUpdateEvent, TradeCompletedEvent,
+            TradeReason,
+        },
     },
     npc::{
         components::{Identity, NpcId},
         events::NpcActivityChangedEvent,
+        sleep::Sleeping,
+        spatial::NpcIndex,
     },
-    world::time::WorldClock,
+    world::time::{DayChangedEvent, WorldClock},
 };
 
 use super::{
-    config::MotivationConfig,
-    state::{adjusted_task_reward, DailyDependencyTracker, NpcMotivation},
+    adjustments::MotivationAdjustmentEvent,
+    config::{MotivationConfig, CONFIG_PATH},
+    state::{CategoryFlags, DailyDependencyTracker, MotivationReason, NpcMotivation},
 };
 
+/// Re-reads `config/motivation.toml` on request, swapping the tuning in when it parses.
+pub fn reload_motivation_config(
+    mut requests: MessageReader<ConfigReloadRequested>,
+    mut diagnostics: ResMut<ConfigDiagnostics>,
+    mut config: ResMut<MotivationConfig>,
+) {
+    if !requests.read().any(|request| request.is_for(CONFIG_PATH)) {
+        return;
+    }
+    if let Some(reloaded) = diagnostics.report_reload(CONFIG_PATH, MotivationConfig::load()) {
+        *config = reloaded;
+    }
+}
+
 pub fn reward_from_leisure(
     mut events: MessageReader<NpcActivityChangedEvent>,
     config: Res<MotivationConfig>,
-    mut query: Query<(&Identity, &mut NpcMotivation)>,
+    mut adjustments_writer: MessageWriter<MotivationAdjustmentEvent>,
+    query: Query<&Identity>,
 ) {
     #[derive(Default)]
     struct Adjustment {
@@ -59,76 +84,185 @@ pub fn reward_from_leisure(
         }
     }
 
-    for (identity, mut motivation) in query.iter_mut() {
+    for identity in query.iter() {
         if let Some(adjustment) = adjustments.get(&identity.id) {
             if adjustment.leisure {
-                motivation.apply_reward(config.gains.leisure, &config);
-                let mood_label = motivation.mood().label();
+                adjustments_writer.write(MotivationAdjustmentEvent::new(
+                    identity.id,
+                    config.gains.leisure,
+                    MotivationReason::Leisure,
+                ));
                 if let Some(fraction) = adjustment.last_time_of_day {
                     info!(
-                        "{} enjoys downtime near day fraction {:.2} and feels {}",
-                        identity.display_name, fraction, mood_label
+                        "{} enjoys downtime {}",
+                        identity.display_name,
+                        spoken_time(fraction)
                     );
                 } else {
-                    info!(
-                        "{} enjoys downtime and feels {}",
-                        identity.display_name, mood_label
-                    );
+                    info!("{} enjoys downtime", identity.display_name);
                 }
             }
 
             if adjustment.alcohol {
-                motivation.trigger_alcohol_boost(&config);
-                let mood_label = motivation.mood().label();
+                adjustments_writer.write(MotivationAdjustmentEvent::new(
+                    identity.id,
+                    config.alcohol.boost,
+                    MotivationReason::Alcohol,
+                ));
                 if let Some(fraction) = adjustment.last_time_of_day {
                     info!(
-                        "{} indulges in a drink near day fraction {:.2} and now feels {}",
-                        identity.display_name, fraction, mood_label
+                        "{} indulges in a drink {}",
+                        identity.display_name,
+                        spoken_time(fraction)
                     );
                 } else {
-                    info!(
-                        "{} indulges in a drink and now feels {}",
-                        identity.display_name, mood_label
-                    );
+                    info!("{} indulges in a drink", identity.display_name);
                 }
             }
         }
     }
 }
 
+/// Signed motivation shift per NPC for a player transfer: giving goods to an NPC cheers
+/// them up, taking goods from their crate dampens their mood. The player is never included.
+pub fn player_transfer_adjustments(
+    event: &TradeCompletedEvent,
+    config: &MotivationConfig,
+) -> Vec<(NpcId, f32)> {
+    if event.reason != TradeReason::PlayerTransfer {
+        return Vec::new();
+    }
+
+    let units = event.quantity as f32;
+    let mut adjustments = Vec::new();
+    if let Some(receiver) = event.to.filter(|npc| !npc.is_player()) {
+        adjustments.push((receiver, config.player_transfer.give_bonus * units));
+    }
+    if let Some(giver) = event.from.filter(|npc| !npc.is_player()) {
+        adjustments.push((giver, -config.player_transfer.take_penalty * units));
+    }
+    adjustments
+}
+
+/// Rewards trade work (drink quality penalties are applied with the adjustment) and
+/// player crate transfers.
 pub fn reward_from_trade_events(
     mut trades: MessageReader<TradeCompletedEvent>,
     config: Res<MotivationConfig>,
-    mut query: Query<(&Identity, &mut NpcMotivation)>,
+    mut adjustments_writer: MessageWriter<MotivationAdjustmentEvent>,
+    query: Query<&Identity>,
 ) {
     let mut rewards: HashMap<NpcId, f32> = HashMap::new();
+    let mut player_transfers: HashMap<NpcId, f32> = HashMap::new();
     for event in trades.read() {
-        let actor = event.from.or(event.to);
-        if let Some(actor) = actor {
-            let reward = match event.reason {
-                TradeReason::Production | TradeReason::Processing => config.gains.task,
-                TradeReason::Exchange => config.gains.task * 0.5,
-            };
+        let reward = match event.reason {
+            TradeReason::Production | TradeReason::Processing => config.gains.task,
+            TradeReason::Exchange => config.gains.task * 0.5,
+            TradeReason::PlayerTransfer => {
+                for (npc, amount) in player_transfer_adjustments(event, &config) {
+                    *player_transfers.entry(npc).or_insert(0.0) += amount;
+                }
+                continue;
+            }
+            // Shuffling goods into or out of household storage is upkeep, not work.
+            TradeReason::Storage => continue,
+        };
+        if let Some(actor) = event.from.or(event.to) {
             *rewards.entry(actor).or_insert(0.0) += reward;
         }
     }
 
-    for (identity, mut motivation) in query.iter_mut() {
+    for identity in query.iter() {
         if let Some(amount) = rewards.remove(&identity.id) {
-            let adjusted = adjusted_task_reward(amount, &config.alcohol, &motivation);
-            motivation.apply_reward(adjusted, &config);
+            adjustments_writer.write(MotivationAdjustmentEvent::new(
+                identity.id,
+                amount,
+                MotivationReason::Trade,
+            ));
             info!(
-                "{} completes trade work and gains {:.1} motivation",
-                identity.display_name, adjusted
+                "{} completes trade work (+{:.1} motivation before drink penalties)",
+                identity.display_name, amount
             );
         }
+
+        if let Some(amount) = player_transfers.remove(&identity.id) {
+            adjustments_writer.write(MotivationAdjustmentEvent::new(
+                identity.id,
+                amount,
+                MotivationReason::PlayerTransfer,
+            ));
+            info!(
+                "{} reacts to the player's crate transfer ({:+.1} motivation)",
+                identity.display_name, amount
+            );
+        }
+    }
+}
+
+/// NPC that should drink a delivery on receipt: drinks handed to a villager (not produced
+/// by them) once the evening has started.
+pub fn drink_recipient(
+    event: &TradeCompletedEvent,
+    time_of_day: f32,
+    config: &MotivationConfig,
+) -> Option<NpcId> {
+    let handed_over = matches!(
+        event.reason,
+        TradeReason::Exchange | TradeReason::PlayerTransfer
+    );
+    if !event.good.is_drink() || !handed_over || time_of_day < config.alcohol.evening_start_fraction
+    {
+        return None;
+    }
+    event.to.filter(|npc| !npc.is_player())
+}
+
+/// Consumes drinks delivered during the evening and triggers the alcohol boost.
+pub fn drink_delivered_ale(
+    mut trades: MessageReader<TradeCompletedEvent>,
+    clock: Res<WorldClock>,
+    config: Res<MotivationConfig>,
+    mut inventory_writer: MessageWriter<InventoryChangedEvent>,
+    mut adjustments_writer: MessageWriter<MotivationAdjustmentEvent>,
+    npc_index: Res<NpcIndex>,
+    mut query: Query<(&Identity, &mut Inventory)>,
+) {
+    let time_of_day = clock.time_of_day();
+    for event in trades.read() {
+        let Some(recipient) = drink_recipient(event, time_of_day, &config) else {
+            continue;
+        };
+        let drinker = npc_index
+            .entity(recipient)
+            .and_then(|entity| query.get_mut(entity).ok());
+        let Some((identity, mut inventory)) = drinker else {
+            continue;
+        };
+        let Some(change) = inventory.remove_good(event.good, event.quantity) else {
+            continue;
+        };
+
+        inventory_writer.write(InventoryChangedEvent::from_change(
+            recipient, event.day, change,
+        ));
+        adjustments_writer.write(MotivationAdjustmentEvent::new(
+            recipient,
+            config.alcohol.boost,
+            MotivationReason::Alcohol,
+        ));
+        info!(
+            "{} drinks the delivered {}",
+            identity.display_name,
+            event.good.label()
+        );
     }
 }
 
 pub fn reward_from_dialogue_responses(
     mut responses: MessageReader<DialogueResponseEvent>,
     config: Res<MotivationConfig>,
-    mut query: Query<(&Identity, &mut NpcMotivation)>,
+    mut adjustments_writer: MessageWriter<MotivationAdjustmentEvent>,
+    query: Query<&Identity>,
 ) {
     let mut rewards: HashMap<NpcId, f32> = HashMap::new();
     for event in responses.read() {
@@ -140,9 +274,13 @@ pub fn reward_from_dialogue_responses(
         }
     }
 
-    for (identity, mut motivation) in query.iter_mut() {
+    for identity in query.iter() {
         if let Some(amount) = rewards.remove(&identity.id) {
-            motivation.apply_reward(amount, &config);
+            adjustments_writer.write(MotivationAdjustmentEvent::new(
+                identity.id,
+                amount,
+                MotivationReason::Social,
+            ));
             debug!(
                 "{} feels uplifted after conversation (+{:.1})",
                 identity.display_name, amount
@@ -177,20 +315,42 @@ pub fn track_dependency_satisfaction(
     }
 }
 
+/// Settles each finished day's dependency rewards and penalties when a `DayChangedEvent`
+/// arrives. After a multi-day jump every tracked day before the new one is settled.
 pub fn evaluate_dependency_impacts(
-    clock: Res<WorldClock>,
+    mut day_changes: MessageReader<DayChangedEvent>,
     matrix: Res<EconomyDependencyMatrix>,
     config: Res<MotivationConfig>,
     mut tracker: ResMut<DailyDependencyTracker>,
-    mut query: Query<(&Identity, &Profession, &mut NpcMotivation)>,
+    mut adjustments_writer: MessageWriter<MotivationAdjustmentEvent>,
+    query: Query<(&Identity, &Profession)>,
 ) {
-    let current_day = clock.day_count();
-    let Some(evaluated_day) = tracker.next_ready_day(current_day) else {
+    let Some(current_day) = day_changes.read().last().map(|change| change.new_day) else {
         return;
     };
 
-    let satisfied_map = tracker.take_satisfied_for_day(evaluated_day);
-    for (identity, profession, mut motivation) in query.iter_mut() {
+    while let Some(evaluated_day) = tracker.next_ready_day(current_day) {
+        let satisfied_map = tracker.take_satisfied_for_day(evaluated_day);
+        settle_dependency_day(
+            evaluated_day,
+            &satisfied_map,
+            &matrix,
+            &config,
+            &mut adjustments_writer,
+            &query,
+        );
+    }
+}
+
+fn settle_dependency_day(
+    evaluated_day: u64,
+    satisfied_map: &HashMap<NpcId, CategoryFlags>,
+    matrix: &EconomyDependencyMatrix,
+    config: &MotivationConfig,
+    adjustments_writer: &mut MessageWriter<MotivationAdjustmentEvent>,
+    query: &Query<(&Identity, &Profession)>,
+) {
+    for (identity, profession) in query.iter() {
         let requirements = matrix.requirements(*profession);
         if requirements.is_empty() {
             continue;
@@ -205,7 +365,11 @@ pub fn evaluate_dependency_impacts(
             }
 
             missing += 1;
-            motivation.apply_penalty(config.dependency.deficit_penalty, &config);
+            adjustments_writer.write(MotivationAdjustmentEvent::new(
+                identity.id,
+                -config.dependency.deficit_penalty,
+                MotivationReason::DependencyDeficit,
+            ));
             warn!(
                 "{} lacks {} support on day {}",
                 identity.display_name,
@@ -215,7 +379,11 @@ pub fn evaluate_dependency_impacts(
         }
 
         if missing == 0 {
-            motivation.apply_reward(config.dependency.satisfaction_bonus, &config);
+            adjustments_writer.write(MotivationAdjustmentEvent::new(
+                identity.id,
+                config.dependency.satisfaction_bonus,
+                MotivationReason::DependencySatisfied,
+            ));
             debug!(
                 "{} satisfied wellbeing dependencies for day {}",
                 identity.display_name, evaluated_day
@@ -224,18 +392,30 @@ pub fn evaluate_dependency_impacts(
     }
 }
 
+/// Ticks decay (or sleep regeneration) and the drink timers. The continuous change is
+/// summed per NPC and recorded for the timeline once per history sample interval.
 pub fn decay_npc_motivation(
     sim_clock: Res<SimulationClock>,
     config: Res<MotivationConfig>,
-    mut query: Query<(&Identity, &mut NpcMotivation)>,
+    mut since_record: Local<f32>,
+    mut query: Query<(&Identity, &mut NpcMotivation, Has<Sleeping>)>,
 ) {
     let delta = sim_clock.last_scaled_delta().as_secs_f32();
     if delta <= 0.0 {
         return;
     }
+    *since_record += delta;
+    let record_due = *since_record >= config.history.sample_interval_seconds;
+    if record_due {
+        *since_record = 0.0;
+    }
 
-    for (identity, mut motivation) in query.iter_mut() {
-        let outcome = motivation.tick(delta, &config);
+    for (identity, mut motivation, sleeping) in query.iter_mut() {
+        let outcome = if sleeping {
+            motivation.tick_sleeping(delta, &config)
+        } else {
+            motivation.tick(delta, &config)
+        };
         if let Some(mood) = outcome.mood_changed {
             info!(
                 "{} mood shifts to {} (dopamine {:.1})",
@@ -248,5 +428,140 @@ pub fn decay_npc_motivation(
         if outcome.hangover_triggered {
             warn!("{} enters a hangover crash", identity.display_name);
         }
+        if record_due {
+            motivation.flush_continuous_changes();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        economy::components::TradeGood,
+        npc::{motivation::apply_motivation_adjustments, spatial::index_npcs},
+    };
+
+    fn transfer(from: NpcId, to: NpcId) -> TradeCompletedEvent {
+        TradeCompletedEvent {
+            day: 1,
+            from: Some(from),
+            to: Some(to),
+            good: TradeGood::Grain,
+            quantity: 2,
+            reason: TradeReason::PlayerTransfer,
+        }
+    }
+
+    #[test]
+    fn player_transfers_reward_receivers_and_penalise_givers() {
+        let config = MotivationConfig::load_or_default();
+        let npc = NpcId::new(4);
+
+        let given = player_transfer_adjustments(&transfer(NpcId::player(), npc), &config);
+        assert_eq!(given, vec![(npc, config.player_transfer.give_bonus * 2.0)]);
+
+        let taken = player_transfer_adjustments(&transfer(npc, NpcId::player()), &config);
+        assert_eq!(
+            taken,
+            vec![(npc, -config.player_transfer.take_penalty * 2.0)]
+        );
+
+        let mut exchange = transfer(npc, NpcId::new(5));
+        exchange.reason = TradeReason::Exchange;
+        assert!(player_transfer_adjustments(&exchange, &config).is_empty());
+    }
+
+    fn ale_delivery(to: NpcId) -> TradeCompletedEvent {
+        TradeCompletedEvent {
+            day: 1,
+            from: Some(NpcId::new(9)),
+            to: Some(to),
+            good: TradeGood::Ale,
+            quantity: 1,
+            reason: TradeReason::Exchange,
+        }
+    }
+
+    #[test]
+    fn drinks_are_only_consumed_when_handed_over_in_the_evening() {
+        let config = MotivationConfig::load_or_default();
+        let npc = NpcId::new(2);
+        let evening = config.alcohol.evening_start_fraction;
+
+        assert_eq!(
+            drink_recipient(&ale_delivery(npc), evening, &config),
+            Some(npc)
+        );
+        assert_eq!(
+            drink_recipient(&ale_delivery(npc), evening - 0.1, &config),
+            None
+        );
+
+        let mut brewed = ale_delivery(npc);
+        brewed.reason = TradeReason::Processing;
+        assert_eq!(drink_recipient(&brewed, evening, &config), None);
+
+        let mut grain = ale_delivery(npc);
+        grain.good = TradeGood::Grain;
+        assert_eq!(drink_recipient(&grain, evening, &config), None);
+        assert_eq!(
+            drink_recipient(&ale_delivery(NpcId::player()), evening, &config),
+            None
+        );
+    }
+
+    #[test]
+    fn evening_ale_delivery_is_drunk_and_boosts_motivation() {
+        let mut config = MotivationConfig::load_or_default();
+        config.alcohol.evening_start_fraction = 0.0;
+        let mut app = App::new();
+        app.insert_resource(WorldClock::new())
+            .insert_resource(config.clone())
+            .add_message::<TradeCompletedEvent>()
+            .add_message::<InventoryChangedEvent>()
+            .add_message::<MotivationAdjustmentEvent>()
+            .init_resource::<NpcIndex>()
+            .add_systems(
+                Update,
+                (
+                    index_npcs,
+                    drink_delivered_ale,
+                    apply_motivation_adjustments,
+                )
+                    .chain(),
+            );
+
+        let npc = NpcId::new(2);
+        let mut inventory = Inventory::default();
+        inventory.add_good(TradeGood::Ale, 1);
+        let entity = app
+            .world_mut()
+            .spawn((
+                Identity::new(npc, "Bryn", 30.0),
+                inventory,
+                NpcMotivation::new(&config),
+            ))
+            .id();
+
+        app.world_mut().write_message(ale_delivery(npc));
+        app.update();
+
+        let world = app.world();
+        assert_eq!(
+            world
+                .get::<Inventory>(entity)
+                .unwrap()
+                .quantity_of(TradeGood::Ale),
+            0
+        );
+        let motivation = world.get::<NpcMotivation>(entity).unwrap();
+        assert!(motivation.is_intoxicated());
+        assert!(motivation.dopamine() > config.defaults.start);
+
+        let changes = world.resource::<Messages<InventoryChangedEvent>>();
+        let changes: Vec<_> = changes.get_cursor().read(changes).cloned().collect();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].new_total, 0);
     }
 }
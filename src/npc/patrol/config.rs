@@ -0,0 +1,186 @@
+//! Patrol settings and routes from the `[patrol]` and `[[patrols]]` entries of
+//! `config/npcs.toml`, and their reload.
+use std::{collections::HashMap, fs, path::Path};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{core::config::ConfigReloadRequested, npc::household::CONFIG_PATH};
+
+use super::{PatrolRoute, Waypoint};
+
+const DEFAULT_KEYWORD: &str = "patrol";
+const DEFAULT_REMARK_INTERVAL_SECONDS: f32 = 240.0;
+
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+struct RawPatrolConfig {
+    #[serde(default)]
+    patrol: RawPatrolSection,
+    #[serde(default)]
+    patrols: Vec<RawPatrolRoute>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+struct RawPatrolSection {
+    keyword: String,
+    remark_interval_seconds: f32,
+}
+
+impl Default for RawPatrolSection {
+    fn default() -> Self {
+        Self {
+            keyword: DEFAULT_KEYWORD.to_string(),
+            remark_interval_seconds: DEFAULT_REMARK_INTERVAL_SECONDS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct RawPatrolRoute {
+    name: String,
+    #[serde(default)]
+    waypoints: Vec<RawWaypoint>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct RawWaypoint {
+    position: [f32; 3],
+    #[serde(default)]
+    dwell_seconds: f32,
+}
+
+/// Patrol keyword, remark pacing, and routes by NPC display name, from the `[patrol]` and
+/// `[[patrols]]` entries of `config/npcs.toml`.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct PatrolConfig {
+    /// Lowercased; empty turns patrols off.
+    pub keyword: String,
+    /// Scaled seconds between a night patroller's remarks; at least 1.
+    pub remark_interval_seconds: f32,
+    routes: HashMap<String, PatrolRoute>,
+}
+
+impl Default for PatrolConfig {
+    fn default() -> Self {
+        Self {
+            keyword: DEFAULT_KEYWORD.to_string(),
+            remark_interval_seconds: DEFAULT_REMARK_INTERVAL_SECONDS,
+            routes: HashMap::new(),
+        }
+    }
+}
+
+impl PatrolConfig {
+    /// Reads and parses `config/npcs.toml`.
+    pub fn load() -> Result<Self, String> {
+        let data = fs::read_to_string(Path::new(CONFIG_PATH))
+            .map_err(|err| format!("unable to read file: {err}"))?;
+        Self::parse(&data)
+    }
+
+    /// Parses the `[patrol]` section and `[[patrols]]` entries. Every route needs a name
+    /// and at least one waypoint with a finite position; negative dwell times count as 0.
+    pub fn parse(data: &str) -> Result<Self, String> {
+        let raw = toml::from_str::<RawPatrolConfig>(data)
+            .map_err(|err| format!("invalid npc config: {err}"))?;
+
+        let mut routes = HashMap::new();
+        for route in raw.patrols {
+            let name = route.name.trim().to_string();
+            if name.is_empty() {
+                return Err("patrol entry needs a name".to_string());
+            }
+            let mut waypoints = Vec::with_capacity(route.waypoints.len());
+            for waypoint in route.waypoints {
+                let position = Vec3::from_array(waypoint.position);
+                if !position.is_finite() {
+                    return Err(format!("{name} has a patrol waypoint that is not finite"));
+                }
+                waypoints.push(Waypoint {
+                    position,
+                    dwell_seconds: waypoint.dwell_seconds.max(0.0),
+                });
+            }
+            let route = PatrolRoute::new(waypoints)
+                .ok_or_else(|| format!("{name}'s patrol needs at least one waypoint"))?;
+            routes.insert(name, route);
+        }
+        Ok(Self {
+            keyword: raw.patrol.keyword.trim().to_ascii_lowercase(),
+            remark_interval_seconds: raw.patrol.remark_interval_seconds.max(1.0),
+            routes,
+        })
+    }
+
+    pub fn route(&self, display_name: &str) -> Option<&PatrolRoute> {
+        self.routes.get(display_name)
+    }
+}
+
+/// Validates the `[patrol]` section and `[[patrols]]` entries of `data` like
+/// `PatrolConfig::parse`, and returns them with every default written out, for village
+/// presets.
+pub fn explicit_toml(data: &str) -> Result<toml::Table, String> {
+    PatrolConfig::parse(data)?;
+    let raw = toml::from_str::<RawPatrolConfig>(data)
+        .map_err(|err| format!("invalid npc config: {err}"))?;
+    toml::Table::try_from(raw).map_err(|err| format!("unable to write npc config: {err}"))
+}
+
+/// The `[patrol]` defaults and no routes, which is what `PatrolConfig` falls back to.
+pub fn fallback_toml() -> toml::Table {
+    toml::Table::try_from(RawPatrolConfig::default()).expect("default patrol config serializes")
+}
+
+/// Re-reads the patrol entries alongside the household reload. Problems are already
+/// reported by that reload, so a failed parse here just keeps the current routes.
+pub fn reload_patrol_config(
+    mut requests: MessageReader<ConfigReloadRequested>,
+    mut config: ResMut<PatrolConfig>,
+) {
+    if !requests.read().any(|request| request.is_for(CONFIG_PATH)) {
+        return;
+    }
+    match PatrolConfig::load() {
+        Ok(reloaded) => *config = reloaded,
+        Err(error) => warn!("Keeping current patrol routes ({error})"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn routes_are_parsed_by_display_name() {
+        let config = PatrolConfig::parse(
+            r#"
+            [patrol]
+            keyword = "  Watch "
+
+            [[patrols]]
+            name = "Cedric"
+            waypoints = [
+                { position = [1.0, 0.0, 2.0], dwell_seconds = 3.0 },
+                { position = [4.0, 0.0, 2.0], dwell_seconds = -1.0 },
+            ]
+            "#,
+        )
+        .expect("patrol entries parse");
+        assert_eq!(config.keyword, "watch");
+        let route = config.route("Cedric").unwrap();
+        assert_eq!(route.waypoints().len(), 2);
+        assert_eq!(route.waypoints()[1].dwell_seconds, 0.0);
+        assert!(config.route("Alric").is_none());
+
+        assert!(PatrolConfig::parse("[[patrols]]\nname = \"Ann\"").is_err());
+        assert!(PatrolConfig::parse(
+            "[[patrols]]\nname = \" \"\nwaypoints = [{ position = [0.0, 0.0, 0.0] }]"
+        )
+        .is_err());
+
+        let shipped = PatrolConfig::load().expect("shipped npc config is valid");
+        assert!(shipped.route("Cedric").is_some());
+    }
+}
@@ -0,0 +1,205 @@
+//! Patrol routes: while an NPC's schedule names the patrol keyword, they walk the waypoints
+//! from `config/npcs.toml` in order, stand at each for its dwell time, and loop until the
+//! activity changes. A conversation or an economy task pauses the round; it picks up at the
+//! same waypoint afterwards. Walking it at night earns duty satisfaction and, now and then,
+//! a remark about the quiet night.
+use bevy::prelude::*;
+
+mod config;
+mod systems;
+
+pub use config::{explicit_toml, fallback_toml, reload_patrol_config, PatrolConfig};
+pub use systems::{assign_patrol_routes, walk_patrol_routes};
+
+/// One stop on a patrol route.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Waypoint {
+    pub position: Vec3,
+    /// Scaled seconds to stand at the waypoint before walking on.
+    pub dwell_seconds: f32,
+}
+
+/// The waypoints an NPC walks, in order, while on patrol duty.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct PatrolRoute {
+    waypoints: Vec<Waypoint>,
+}
+
+impl PatrolRoute {
+    /// A route needs at least one waypoint.
+    pub fn new(waypoints: Vec<Waypoint>) -> Option<Self> {
+        (!waypoints.is_empty()).then_some(Self { waypoints })
+    }
+
+    #[cfg(test)]
+    pub fn waypoints(&self) -> &[Waypoint] {
+        &self.waypoints
+    }
+}
+
+/// Where an NPC is on their route. Kept while a conversation or task pauses the patrol, and
+/// reset when the duty ends so the next round starts from the first waypoint.
+#[derive(Component, Debug, Clone, Default, PartialEq)]
+pub struct PatrolState {
+    on_duty: bool,
+    waypoint: usize,
+    /// Dwell time left at the current waypoint; `None` while walking to it.
+    dwell_remaining: Option<f32>,
+    laps: u32,
+    /// Night patrol seconds not yet paid out as duty satisfaction.
+    unrewarded_seconds: f32,
+    /// Seconds until the next remark about the quiet night.
+    remark_cooldown: f32,
+}
+
+impl PatrolState {
+    pub fn is_on_duty(&self) -> bool {
+        self.on_duty
+    }
+
+    #[cfg(test)]
+    pub fn waypoint(&self) -> usize {
+        self.waypoint
+    }
+
+    /// Completed rounds of the route since the duty began.
+    #[cfg(test)]
+    pub fn laps(&self) -> u32 {
+        self.laps
+    }
+
+    fn begin_duty(&mut self, remark_interval_seconds: f32) {
+        *self = Self {
+            on_duty: true,
+            remark_cooldown: remark_interval_seconds,
+            ..Self::default()
+        };
+    }
+
+    fn end_duty(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Advances the patrol for an NPC standing at `here`. Returns the waypoint to walk to,
+    /// or `None` while dwelling. Reaching a waypoint (within `reach` on XZ) starts its
+    /// dwell; once the dwell runs out the next waypoint is returned, wrapping to the first
+    /// after the last.
+    pub fn step(
+        &mut self,
+        route: &PatrolRoute,
+        here: Vec3,
+        reach: f32,
+        delta_seconds: f32,
+    ) -> Option<Vec3> {
+        if self.waypoint >= route.waypoints.len() {
+            self.waypoint = 0;
+            self.dwell_remaining = None;
+        }
+        let current = route.waypoints[self.waypoint];
+        let dwell = match self.dwell_remaining {
+            Some(remaining) => remaining,
+            None if here.xz().distance(current.position.xz()) > reach => {
+                return Some(current.position);
+            }
+            None => current.dwell_seconds,
+        };
+        let remaining = dwell - delta_seconds;
+        if remaining > 0.0 {
+            self.dwell_remaining = Some(remaining);
+            return None;
+        }
+        self.dwell_remaining = None;
+        self.waypoint += 1;
+        if self.waypoint == route.waypoints.len() {
+            self.waypoint = 0;
+            self.laps += 1;
+        }
+        Some(route.waypoints[self.waypoint].position)
+    }
+}
+
+/// Whether `activity` names the patrol `keyword` (case-insensitive, anywhere in the name).
+pub fn is_patrol_activity(activity: &str, keyword: &str) -> bool {
+    !keyword.is_empty() && activity.to_ascii_lowercase().contains(keyword)
+}
+
+/// Whether an NPC walks their route this frame: on patrol duty, with no economy task or
+/// conversation claiming them.
+pub fn patrol_active(activity: &str, keyword: &str, has_task: bool, in_conversation: bool) -> bool {
+    is_patrol_activity(activity, keyword) && !has_task && !in_conversation
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub(super) fn square_route() -> PatrolRoute {
+        PatrolRoute::new(vec![
+            Waypoint {
+                position: Vec3::new(0.0, 1.0, 0.0),
+                dwell_seconds: 1.0,
+            },
+            Waypoint {
+                position: Vec3::new(4.0, 1.0, 0.0),
+                dwell_seconds: 0.0,
+            },
+            Waypoint {
+                position: Vec3::new(4.0, 1.0, 4.0),
+                dwell_seconds: 2.0,
+            },
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn waypoints_are_walked_in_order_and_loop() {
+        let route = square_route();
+        let points: Vec<Vec3> = route.waypoints().iter().map(|w| w.position).collect();
+        let mut state = PatrolState::default();
+
+        // Away from the route the NPC first walks to the current waypoint.
+        assert_eq!(
+            state.step(&route, Vec3::new(-5.0, 1.0, 0.0), 0.1, 0.5),
+            Some(points[0])
+        );
+
+        // Standing on it, the dwell runs down before the next waypoint is handed out.
+        assert_eq!(state.step(&route, points[0], 0.1, 0.5), None);
+        assert_eq!(state.step(&route, points[0], 0.1, 0.5), Some(points[1]));
+        assert_eq!(state.waypoint(), 1);
+        // No dwell: arriving moves straight on.
+        assert_eq!(state.step(&route, points[1], 0.1, 0.5), Some(points[2]));
+        assert_eq!(state.step(&route, points[2], 0.1, 1.5), None);
+        assert_eq!(state.laps(), 0);
+        // The last waypoint leads back to the first.
+        assert_eq!(state.step(&route, points[2], 0.1, 1.0), Some(points[0]));
+        assert_eq!(state.waypoint(), 0);
+        assert_eq!(state.laps(), 1);
+
+        // A single-waypoint route keeps the NPC on that spot.
+        let post = PatrolRoute::new(vec![route.waypoints()[1]]).unwrap();
+        let mut guard = PatrolState::default();
+        assert_eq!(guard.step(&post, points[1], 0.1, 0.5), Some(points[1]));
+        assert_eq!(guard.laps(), 1);
+        assert!(PatrolRoute::new(Vec::new()).is_none());
+    }
+
+    #[test]
+    fn patrol_needs_the_keyword_and_a_free_npc() {
+        assert!(patrol_active("Guard patrol", "patrol", false, false));
+        assert!(patrol_active(
+            "PATROLLING the walls",
+            "patrol",
+            false,
+            false
+        ));
+        assert!(!patrol_active("Tavern chatter", "patrol", false, false));
+        assert!(!patrol_active("Guard patrol", "patrol", true, false));
+        assert!(!patrol_active("Guard patrol", "patrol", false, true));
+        assert!(
+            !patrol_active("Guard patrol", "", false, false),
+            "empty keyword"
+        );
+        assert!(is_patrol_activity("Night watch", "watch"));
+    }
+}
@@ -0,0 +1,370 @@
+//! Patrol systems: handing out routes by display name and walking them while on duty.
+use bevy::prelude::*;
+
+use crate::{
+    core::plugin::SimulationClock,
+    dialogue::{
+        chatter::ChatterBudgets,
+        queue::DialogueRequestQueue,
+        types::{DialogueContext, DialogueRequest, DialogueTopicHint},
+    },
+    economy::tasks::ActorTaskQueues,
+    npc::{
+        components::{Identity, InConversation, MovementTarget, NpcLocomotion, ScheduleState},
+        motivation::{state::MotivationReason, MotivationAdjustmentEvent, MotivationConfig},
+        sleep::{is_night, Sleeping},
+    },
+    world::time::{WorldClock, WorldTimeSettings},
+};
+
+use super::{is_patrol_activity, patrol_active, PatrolConfig, PatrolRoute, PatrolState};
+
+const PATROL_LABEL: &str = "patrol";
+
+/// Gives each NPC the route configured for their display name, starting a fresh
+/// `PatrolState` when the route changes, and takes it away when none is configured.
+pub fn assign_patrol_routes(
+    mut commands: Commands,
+    config: Res<PatrolConfig>,
+    npcs: Query<(Entity, Ref<Identity>, Option<&PatrolRoute>)>,
+) {
+    for (entity, identity, current) in npcs.iter() {
+        if !config.is_changed() && !identity.is_changed() {
+            continue;
+        }
+        match (config.route(&identity.display_name), current) {
+            (Some(route), Some(current)) if route == current => {}
+            (Some(route), _) => {
+                commands
+                    .entity(entity)
+                    .insert((route.clone(), PatrolState::default()));
+            }
+            (None, Some(_)) => {
+                commands
+                    .entity(entity)
+                    .remove::<(PatrolRoute, PatrolState)>();
+            }
+            (None, None) => {}
+        }
+    }
+}
+
+/// Walks NPCs on patrol duty through their routes. Duty starts and ends with the schedule
+/// activity; conversations and economy tasks pause it without losing the waypoint. At night
+/// each `reward_interval_seconds` of walking pays a small duty reward, and every
+/// `remark_interval_seconds` the patroller queues a Status line if their chatter budget
+/// allows.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn walk_patrol_routes(
+    clock: Res<WorldClock>,
+    settings: Res<WorldTimeSettings>,
+    sim_clock: Res<SimulationClock>,
+    config: Res<PatrolConfig>,
+    motivation: Res<MotivationConfig>,
+    task_queues: Res<ActorTaskQueues>,
+    mut budgets: ResMut<ChatterBudgets>,
+    mut queue: ResMut<DialogueRequestQueue>,
+    mut npcs: Query<
+        (
+            &Identity,
+            &Transform,
+            &ScheduleState,
+            &PatrolRoute,
+            &mut PatrolState,
+            &mut NpcLocomotion,
+            Has<InConversation>,
+        ),
+        Without<Sleeping>,
+    >,
+    mut adjustments: MessageWriter<MotivationAdjustmentEvent>,
+) {
+    let delta_seconds = sim_clock.last_scaled_delta().as_secs_f32();
+    let night = is_night(
+        clock.time_of_day(),
+        settings.sunrise_fraction,
+        settings.sunset_fraction,
+    );
+
+    for (identity, transform, schedule, route, mut state, mut locomotion, in_conversation) in
+        npcs.iter_mut()
+    {
+        let activity = schedule.current_activity.as_str();
+        if !is_patrol_activity(activity, &config.keyword) {
+            if state.on_duty {
+                state.end_duty();
+                if locomotion.active_label() == Some(PATROL_LABEL) {
+                    locomotion.clear_target();
+                }
+                info!("{} ends their patrol", identity.display_name);
+            }
+            continue;
+        }
+        if !state.on_duty {
+            state.begin_duty(config.remark_interval_seconds);
+            info!("{} starts their patrol", identity.display_name);
+        }
+        let has_task = task_queues.peek(identity.id).is_some();
+        if !patrol_active(activity, &config.keyword, has_task, in_conversation) {
+            continue;
+        }
+
+        let here = transform.translation;
+        match state.step(route, here, locomotion.arrive_distance(), delta_seconds) {
+            Some(point) => {
+                locomotion.set_target(MovementTarget::Position(point), PATROL_LABEL, here);
+            }
+            None if locomotion.active_label() == Some(PATROL_LABEL) => locomotion.clear_target(),
+            None => {}
+        }
+
+        if !night {
+            continue;
+        }
+        state.unrewarded_seconds += delta_seconds;
+        let interval = motivation.patrol.reward_interval_seconds;
+        while state.unrewarded_seconds >= interval {
+            state.unrewarded_seconds -= interval;
+            adjustments.write(MotivationAdjustmentEvent::new(
+                identity.id,
+                motivation.patrol.duty_reward,
+                MotivationReason::Duty,
+            ));
+        }
+        state.remark_cooldown -= delta_seconds;
+        if state.remark_cooldown > 0.0 {
+            continue;
+        }
+        state.remark_cooldown = config.remark_interval_seconds;
+        if budgets.has_remaining(identity.id) {
+            budgets.spend(identity.id);
+            queue.enqueue(quiet_night_request(identity));
+        }
+    }
+}
+
+fn quiet_night_request(identity: &Identity) -> DialogueRequest {
+    let context = DialogueContext {
+        summary: Some(format!(
+            "{} is walking the night patrol; the village is asleep and all is quiet.",
+            identity.display_name
+        )),
+        ..Default::default()
+    };
+    let prompt = format!(
+        "{} remarks on how quiet the night is while walking the patrol.",
+        identity.id
+    );
+
+    DialogueRequest::new(
+        identity.id,
+        None,
+        prompt,
+        DialogueTopicHint::Status,
+        context,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::super::tests::square_route;
+    use super::*;
+    use crate::{
+        dialogue::types::DialogueRequestId,
+        economy::{
+            components::{Profession, TradeGood},
+            tasks::ActorTask,
+        },
+        npc::{
+            components::{ConversationState, NpcId},
+            events::NpcActivityChangedEvent,
+            sleep::{update_night_rest, HeadingHome, HomePosition, SleepRoster},
+        },
+    };
+
+    fn patrol_app(time_of_day: f32) -> App {
+        let mut app = App::new();
+        let mut clock = WorldClock::new();
+        clock.set_time_of_day(time_of_day);
+        app.insert_resource(clock)
+            .init_resource::<WorldTimeSettings>()
+            // Tests step up to two reward intervals at a time.
+            .insert_resource(SimulationClock::new(1.0).with_max_frame_delta(60.0))
+            .init_resource::<PatrolConfig>()
+            .insert_resource(MotivationConfig::default())
+            .init_resource::<ActorTaskQueues>()
+            .init_resource::<ChatterBudgets>()
+            .init_resource::<DialogueRequestQueue>()
+            .add_message::<MotivationAdjustmentEvent>()
+            .add_systems(Update, walk_patrol_routes);
+        app
+    }
+
+    fn watchman(app: &mut App, at: Vec3, activity: &str) -> Entity {
+        app.world_mut()
+            .spawn((
+                Identity::new(NpcId::new(1), "Cedric", 30.0),
+                Transform::from_translation(at),
+                ScheduleState {
+                    current_activity: activity.to_string(),
+                },
+                square_route(),
+                PatrolState::default(),
+                NpcLocomotion::default(),
+            ))
+            .id()
+    }
+
+    fn step(app: &mut App, seconds: f32) {
+        app.world_mut()
+            .resource_mut::<SimulationClock>()
+            .tick(Duration::from_secs_f32(seconds));
+        app.update();
+    }
+
+    fn target(app: &App, npc: Entity) -> Option<MovementTarget> {
+        app.world().get::<NpcLocomotion>(npc).unwrap().target()
+    }
+
+    #[test]
+    fn conversations_and_tasks_pause_the_patrol_at_the_same_waypoint() {
+        let mut app = patrol_app(0.9);
+        let route = square_route();
+        let points: Vec<Vec3> = route.waypoints().iter().map(|w| w.position).collect();
+        let npc = watchman(&mut app, points[0], "Guard patrol");
+
+        // Dwell at the first waypoint, then head for the second.
+        step(&mut app, 0.5);
+        assert_eq!(target(&app, npc), None);
+        step(&mut app, 0.6);
+        assert_eq!(target(&app, npc), Some(MovementTarget::Position(points[1])));
+        assert_eq!(app.world().get::<PatrolState>(npc).unwrap().waypoint(), 1);
+
+        // A conversation stops the NPC; the patrol leaves them be and keeps its place.
+        app.world_mut().entity_mut(npc).insert(InConversation::new(
+            NpcId::new(2),
+            DialogueRequestId::new(0),
+            0.0,
+            ConversationState::WaitingAtDestination,
+        ));
+        app.world_mut()
+            .get_mut::<NpcLocomotion>(npc)
+            .unwrap()
+            .clear_target();
+        for _ in 0..5 {
+            step(&mut app, 1.0);
+        }
+        assert_eq!(target(&app, npc), None);
+        let state = app.world().get::<PatrolState>(npc).unwrap();
+        assert!(state.is_on_duty());
+        assert_eq!(state.waypoint(), 1);
+
+        // Afterwards the round resumes towards the same waypoint.
+        app.world_mut().entity_mut(npc).remove::<InConversation>();
+        step(&mut app, 0.1);
+        assert_eq!(target(&app, npc), Some(MovementTarget::Position(points[1])));
+
+        // An economy task pauses it the same way.
+        app.world_mut()
+            .get_mut::<NpcLocomotion>(npc)
+            .unwrap()
+            .clear_target();
+        app.world_mut()
+            .resource_mut::<ActorTaskQueues>()
+            .ensure_queue(NpcId::new(1))
+            .push_back(ActorTask::Deliver {
+                good: TradeGood::Grain,
+                quantity: 1,
+                target: Profession::Miller,
+                recipient: None,
+            });
+        step(&mut app, 0.1);
+        assert_eq!(target(&app, npc), None);
+        assert_eq!(app.world().get::<PatrolState>(npc).unwrap().waypoint(), 1);
+
+        // When the activity changes the duty ends and the patrol target is dropped.
+        app.world_mut()
+            .resource_mut::<ActorTaskQueues>()
+            .ensure_queue(NpcId::new(1))
+            .clear();
+        step(&mut app, 0.1);
+        app.world_mut()
+            .get_mut::<ScheduleState>(npc)
+            .unwrap()
+            .current_activity = "Sleeping".to_string();
+        step(&mut app, 0.1);
+        assert_eq!(target(&app, npc), None);
+        let state = app.world().get::<PatrolState>(npc).unwrap();
+        assert!(!state.is_on_duty());
+        assert_eq!(state.waypoint(), 0);
+    }
+
+    #[test]
+    fn night_patrols_pay_duty_and_remark_within_the_chatter_budget() {
+        let mut app = patrol_app(0.9);
+        app.world_mut()
+            .resource_mut::<ChatterBudgets>()
+            .reset(0, [(NpcId::new(1), 1)]);
+        let npc = watchman(&mut app, Vec3::new(-5.0, 1.0, 0.0), "Guard patrol");
+        let config = app.world().resource::<PatrolConfig>().clone();
+        let reward = MotivationConfig::default().patrol;
+        let mut cursor = app
+            .world()
+            .resource::<Messages<MotivationAdjustmentEvent>>()
+            .get_cursor();
+
+        // Two remark intervals pass; the budget allows a single line.
+        let rounds = 2 * (config.remark_interval_seconds / reward.reward_interval_seconds) as usize;
+        let mut rewards = Vec::new();
+        for _ in 0..rounds {
+            step(&mut app, reward.reward_interval_seconds);
+            let messages = app
+                .world()
+                .resource::<Messages<MotivationAdjustmentEvent>>();
+            rewards.extend(cursor.read(messages).copied());
+        }
+        assert_eq!(rewards.len(), rounds);
+        assert!(rewards
+            .iter()
+            .all(|event| event.reason == MotivationReason::Duty));
+        let queue = app.world().resource::<DialogueRequestQueue>();
+        assert_eq!(queue.iter_pending().count(), 1);
+        assert!(app.world().get::<PatrolState>(npc).unwrap().is_on_duty());
+
+        // By day the patrol walks on without rewards.
+        let mut day = patrol_app(0.5);
+        watchman(&mut day, Vec3::new(-5.0, 1.0, 0.0), "Guard patrol");
+        step(&mut day, reward.reward_interval_seconds * 2.0);
+        assert!(day
+            .world()
+            .resource::<Messages<MotivationAdjustmentEvent>>()
+            .is_empty());
+    }
+
+    #[test]
+    fn night_rest_waits_until_the_patrol_ends() {
+        let mut app = patrol_app(0.9);
+        app.init_resource::<SleepRoster>()
+            .add_message::<NpcActivityChangedEvent>()
+            .add_systems(Update, update_night_rest.after(walk_patrol_routes));
+        let home = Vec3::new(-8.0, 1.0, 5.0);
+        let npc = watchman(&mut app, Vec3::new(-5.0, 1.0, 0.0), "Guard patrol");
+        app.world_mut().entity_mut(npc).insert(HomePosition(home));
+
+        step(&mut app, 0.1);
+        assert!(app.world().get::<HeadingHome>(npc).is_none());
+        assert_eq!(
+            target(&app, npc),
+            Some(MovementTarget::Position(Vec3::new(0.0, 1.0, 0.0)))
+        );
+
+        app.world_mut()
+            .get_mut::<ScheduleState>(npc)
+            .unwrap()
+            .current_activity = "Sleeping".to_string();
+        step(&mut app, 0.1);
+        assert!(app.world().get::<HeadingHome>(npc).is_some());
+        assert_eq!(target(&app, npc), Some(MovementTarget::Position(home)));
+    }
+}
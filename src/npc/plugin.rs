@@ -1,50 +1,261 @@
 //! NPC plugin wiring identity data and debug spawners.
 use bevy::prelude::*;
 
+#[cfg(feature = "profiling")]
+use crate::core::profiling::time_system;
+use crate::dialogue::queue::run_dialogue_request_queue;
 use crate::{
+    core::{config::report_config_result, focus::window_focused, schedule::FramePhase},
+    dialogue::chronicle::ChronicleAppExt,
     npc::{
-        components::{NpcIdGenerator, ScheduleTicker},
-        events::NpcActivityChangedEvent,
+        aging::{
+            advance_npc_ages, celebrate_npc_birthdays, distill_birthday, refresh_speaker_profiles,
+        },
+        census::{
+            log_village_stats_on_exit, reload_census_settings, tally_village_activity,
+            update_village_stats, CensusSettings, VillageActivity, VillageStats,
+            VillageStatsUpdatedEvent,
+        },
+        components::{ActiveConversations, ConversationSettings, NpcIdGenerator, ScheduleTicker},
+        events::{
+            NpcActivityChangedEvent, NpcBirthdayEvent, NpcDespawnedEvent, NpcScheduleChangedEvent,
+        },
+        facing::{apply_npc_facing, face_work_crates, FacingConfig},
+        household::{
+            reload_household_config, spawn_households, HouseholdConfig, HouseholdRegistry,
+            CONFIG_PATH as NPC_CONFIG_PATH,
+        },
+        market_day::{mill_around_market, update_market_attendance, MarketAttendance},
         motivation::{
-            decay_npc_motivation, evaluate_dependency_impacts, reward_from_dialogue_responses,
+            apply_motivation_adjustments, config::CONFIG_PATH as MOTIVATION_CONFIG_PATH,
+            decay_npc_motivation, drink_delivered_ale, evaluate_dependency_impacts,
+            record_motivation_history, reload_motivation_config, reward_from_dialogue_responses,
             reward_from_leisure, reward_from_trade_events, track_dependency_satisfaction,
-            DailyDependencyTracker, MotivationConfig,
+            DailyDependencyTracker, MotivationAdjustmentEvent, MotivationConfig, MotivationHistory,
+        },
+        patrol::{assign_patrol_routes, reload_patrol_config, walk_patrol_routes, PatrolConfig},
+        reflection::{
+            enqueue_dusk_reflections, journal_npc_day, DailyReflectionJournal, DuskReflectionLatch,
         },
+        rumors::{learn_rumors_from_dialogue, relay_rumors_in_conversation, RumorConfig},
+        schedule_editor::{apply_schedule_commands, cycle_debug_schedule, ScheduleCommand},
+        separation::{separate_npc_crowds, CrowdSeparationConfig},
+        sleep::{update_night_rest, SleepRoster},
+        spatial::{index_npcs, rebuild_spatial_index, NpcIndex, SpatialIndex},
         systems::{
-            cleanup_conversations, drive_npc_locomotion, orient_conversing_npcs, spawn_debug_npcs,
-            start_conversations, tick_schedule_state,
+            announce_removed_npcs, cleanup_conversations, drive_npc_locomotion,
+            forget_despawned_npcs, orient_conversing_npcs, release_failed_conversations,
+            spawn_debug_npcs, start_conversations, tick_schedule_state,
         },
+        voice::{register_voice_examples, reload_npc_voice_config, NpcVoiceConfig},
+        yielding::{yield_to_conversations, ConversationYieldConfig},
     },
     world::systems::spawn_world_environment,
 };
 
+/// NPC spawning, schedules, motivation, and movement.
 pub struct NpcPlugin;
 
 impl Plugin for NpcPlugin {
     fn build(&self, app: &mut App) {
-        let motivation_config = MotivationConfig::load_or_default();
+        let motivation_config = report_config_result(
+            app.world_mut(),
+            MOTIVATION_CONFIG_PATH,
+            MotivationConfig::load(),
+            MotivationConfig::default,
+        );
+        let household_config = report_config_result(
+            app.world_mut(),
+            NPC_CONFIG_PATH,
+            HouseholdConfig::load(),
+            HouseholdConfig::default,
+        );
+        let voice_config = report_config_result(
+            app.world_mut(),
+            NPC_CONFIG_PATH,
+            NpcVoiceConfig::load(),
+            NpcVoiceConfig::default,
+        );
+        let census_settings = report_config_result(
+            app.world_mut(),
+            NPC_CONFIG_PATH,
+            CensusSettings::load(),
+            CensusSettings::default,
+        );
+        let patrol_config = report_config_result(
+            app.world_mut(),
+            NPC_CONFIG_PATH,
+            PatrolConfig::load(),
+            PatrolConfig::default,
+        );
         app.insert_resource(motivation_config)
+            .insert_resource(household_config)
+            .insert_resource(voice_config)
+            .insert_resource(census_settings)
+            .insert_resource(patrol_config)
+            .init_resource::<HouseholdRegistry>()
             .init_resource::<NpcIdGenerator>()
             .init_resource::<ScheduleTicker>()
+            .init_resource::<ActiveConversations>()
+            .init_resource::<ConversationSettings>()
             .init_resource::<DailyDependencyTracker>()
+            .init_resource::<MotivationHistory>()
+            .init_resource::<DailyReflectionJournal>()
+            .init_resource::<DuskReflectionLatch>()
+            .init_resource::<CrowdSeparationConfig>()
+            .init_resource::<ConversationYieldConfig>()
+            .init_resource::<SleepRoster>()
+            .init_resource::<MarketAttendance>()
+            .init_resource::<RumorConfig>()
+            .init_resource::<FacingConfig>()
+            .init_resource::<VillageActivity>()
+            .init_resource::<VillageStats>()
+            .init_resource::<NpcIndex>()
+            .init_resource::<SpatialIndex>()
             .add_message::<NpcActivityChangedEvent>()
+            .add_message::<NpcBirthdayEvent>()
+            .add_message::<MotivationAdjustmentEvent>()
+            .add_message::<NpcScheduleChangedEvent>()
+            .add_message::<NpcDespawnedEvent>()
+            .add_message::<ScheduleCommand>()
+            .add_message::<VillageStatsUpdatedEvent>()
+            .add_chronicle_distiller(distill_birthday)
             .add_systems(Startup, spawn_debug_npcs.after(spawn_world_environment))
+            .add_systems(Startup, spawn_households.after(spawn_debug_npcs))
+            .add_systems(
+                Update,
+                (
+                    reload_motivation_config,
+                    reload_household_config,
+                    register_voice_examples.after(reload_npc_voice_config),
+                    reload_npc_voice_config,
+                    reload_census_settings,
+                    assign_patrol_routes.after(reload_patrol_config),
+                    reload_patrol_config,
+                )
+                    .in_set(FramePhase::SimTick),
+            )
+            .add_systems(
+                Update,
+                (index_npcs, rebuild_spatial_index)
+                    .chain()
+                    .in_set(FramePhase::SimTick),
+            )
+            .add_systems(
+                Update,
+                (
+                    tally_village_activity,
+                    update_village_stats,
+                    log_village_stats_on_exit,
+                )
+                    .chain()
+                    .in_set(FramePhase::NpcUpdate),
+            )
+            .add_systems(
+                Update,
+                (announce_removed_npcs, forget_despawned_npcs)
+                    .chain()
+                    .before(start_conversations)
+                    .in_set(FramePhase::NpcUpdate),
+            )
+            .add_systems(
+                Update,
+                (cycle_debug_schedule, apply_schedule_commands)
+                    .chain()
+                    .before(tick_schedule_state)
+                    .in_set(FramePhase::NpcUpdate),
+            )
+            .add_systems(
+                Update,
+                learn_rumors_from_dialogue.in_set(FramePhase::DialoguePoll),
+            )
+            .add_systems(
+                Update,
+                relay_rumors_in_conversation
+                    .before(run_dialogue_request_queue)
+                    .in_set(FramePhase::DialogueDispatch),
+            )
+            .add_systems(
+                Update,
+                (face_work_crates, apply_npc_facing)
+                    .chain()
+                    .after(orient_conversing_npcs)
+                    .run_if(window_focused)
+                    .in_set(FramePhase::NpcUpdate),
+            )
+            .add_systems(
+                Update,
+                walk_patrol_routes
+                    .after(tick_schedule_state)
+                    .before(update_night_rest)
+                    .before(apply_motivation_adjustments)
+                    .in_set(FramePhase::NpcUpdate),
+            )
+            .add_systems(
+                Update,
+                update_night_rest
+                    .after(tick_schedule_state)
+                    .before(drive_npc_locomotion)
+                    .in_set(FramePhase::NpcUpdate),
+            )
+            .add_systems(
+                Update,
+                (update_market_attendance, mill_around_market)
+                    .chain()
+                    .after(update_night_rest)
+                    .before(drive_npc_locomotion)
+                    .before(apply_motivation_adjustments)
+                    .in_set(FramePhase::NpcUpdate),
+            )
             .add_systems(
                 Update,
                 (
                     start_conversations,
                     cleanup_conversations,
+                    release_failed_conversations,
                     tick_schedule_state,
+                    advance_npc_ages,
+                    celebrate_npc_birthdays,
+                    refresh_speaker_profiles,
                     reward_from_leisure,
                     reward_from_trade_events,
+                    drink_delivered_ale,
                     reward_from_dialogue_responses,
                     track_dependency_satisfaction,
-                    evaluate_dependency_impacts,
+                    (evaluate_dependency_impacts, apply_motivation_adjustments).chain(),
                     decay_npc_motivation,
-                    drive_npc_locomotion,
-                    orient_conversing_npcs,
+                    record_motivation_history,
+                    journal_npc_day,
+                    enqueue_dusk_reflections,
+                    (
+                        drive_npc_locomotion,
+                        yield_to_conversations,
+                        separate_npc_crowds,
+                    )
+                        .chain(),
+                    orient_conversing_npcs.run_if(window_focused),
                 )
-                    .chain(),
+                    .chain()
+                    .in_set(FramePhase::NpcUpdate),
             );
+
+        #[cfg(feature = "transform_sanity")]
+        app.add_message::<crate::npc::sanity::TransformCorruptionDetected>()
+            .add_systems(
+                Update,
+                crate::npc::sanity::sanitize_transforms
+                    .after(separate_npc_crowds)
+                    .in_set(FramePhase::NpcUpdate),
+            );
+
+        #[cfg(feature = "profiling")]
+        time_system(app, Update, "drive_npc_locomotion", drive_npc_locomotion);
+
+        #[cfg(feature = "chaos")]
+        {
+            use crate::core::chaos::ChaosAppExt;
+            app.register_chaos_fault("respawn_npc", crate::npc::chaos::respawn_random_npc)
+                .register_chaos_fault("zero_dopamine", crate::npc::chaos::zero_random_dopamine);
+        }
     }
 }
@@ -0,0 +1,330 @@
+//! Dusk reflections: journals each NPC's day and queues a short end-of-day dialogue.
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::{
+    dialogue::{
+        queue::DialogueRequestQueue,
+        types::{
+            DialogueContext, DialogueContextEvent, DialogueRequest, DialogueTopicHint,
+            TradeContext, TradeDescriptor,
+        },
+    },
+    economy::{
+        dependency::DependencyCategory,
+        events::{ProfessionDependencyUpdateEvent, TradeCompletedEvent},
+    },
+    npc::{
+        components::{Identity, NpcId},
+        events::NpcActivityChangedEvent,
+        motivation::{state::NpcMood, NpcMotivation},
+    },
+    world::time::{WorldClock, WorldTimeSettings},
+};
+
+const REFLECTION_PROMPT_ACTION: &str = "reflects on how the day went";
+const MOOD_STEADY_THRESHOLD: f32 = 1.0;
+
+/// Everything an NPC did during the current world day, as collected by `journal_npc_day`.
+#[derive(Debug, Clone, Default)]
+pub struct ReflectionDayRecord {
+    pub trades: Vec<TradeContext>,
+    pub activities: Vec<String>,
+    pub starting_dopamine: Option<f32>,
+    pub missing_dependencies: Vec<DependencyCategory>,
+}
+
+/// Inputs required to summarise a single NPC's day.
+#[derive(Debug, Clone, Copy)]
+pub struct ReflectionSnapshot<'a> {
+    pub day: u64,
+    pub display_name: &'a str,
+    pub record: &'a ReflectionDayRecord,
+    pub ending_dopamine: f32,
+    pub mood: NpcMood,
+}
+
+/// Per-day journal of trades, activities, and needs keyed by NPC.
+#[derive(Resource, Debug, Default)]
+pub struct DailyReflectionJournal {
+    day: Option<u64>,
+    records: HashMap<NpcId, ReflectionDayRecord>,
+}
+
+impl DailyReflectionJournal {
+    /// Clears the journal when the world day changes.
+    pub fn begin_day(&mut self, day: u64) {
+        if self.day != Some(day) {
+            self.day = Some(day);
+            self.records.clear();
+        }
+    }
+
+    pub fn record(&self, npc: NpcId) -> Option<&ReflectionDayRecord> {
+        self.records.get(&npc)
+    }
+
+    fn entry(&mut self, npc: NpcId) -> &mut ReflectionDayRecord {
+        self.records.entry(npc).or_default()
+    }
+}
+
+/// Remembers the last day each NPC reflected so dusk only fires once per day.
+#[derive(Resource, Debug, Default)]
+pub struct DuskReflectionLatch {
+    last_reflected_day: HashMap<NpcId, u64>,
+}
+
+impl DuskReflectionLatch {
+    /// Returns true exactly once per NPC per day, the first time the clock is at or past dusk.
+    pub fn try_fire(&mut self, npc: NpcId, day: u64, time_of_day: f32, dusk_fraction: f32) -> bool {
+        if time_of_day < dusk_fraction {
+            return false;
+        }
+
+        if self
+            .last_reflected_day
+            .get(&npc)
+            .is_some_and(|last| *last >= day)
+        {
+            return false;
+        }
+
+        self.last_reflected_day.insert(npc, day);
+        true
+    }
+}
+
+/// Builds the dialogue context describing an NPC's day for a reflection request.
+pub fn build_reflection_context(snapshot: &ReflectionSnapshot<'_>) -> DialogueContext {
+    let record = snapshot.record;
+    let mut sentences = vec![format!(
+        "Day {} reflection for {}.",
+        snapshot.day, snapshot.display_name
+    )];
+
+    sentences.push(match record.trades.len() {
+        0 => "No trades today.".to_string(),
+        1 => "Took part in 1 trade.".to_string(),
+        count => format!("Took part in {count} trades."),
+    });
+
+    if record.activities.is_empty() {
+        sentences.push("No notable activities.".to_string());
+    } else {
+        sentences.push(format!("Activities: {}.", record.activities.join(", ")));
+    }
+
+    let start = record.starting_dopamine.unwrap_or(snapshot.ending_dopamine);
+    let change = snapshot.ending_dopamine - start;
+    let trend = if change > MOOD_STEADY_THRESHOLD {
+        "rose"
+    } else if change < -MOOD_STEADY_THRESHOLD {
+        "fell"
+    } else {
+        "held steady"
+    };
+    sentences.push(format!(
+        "Mood {trend} from {start:.0} to {end:.0}, ending {mood}.",
+        end = snapshot.ending_dopamine,
+        mood = snapshot.mood.label()
+    ));
+
+    if !record.missing_dependencies.is_empty() {
+        sentences.push(format!(
+            "Still lacking {}.",
+            record
+                .missing_dependencies
+                .iter()
+                .map(|category| category.label())
+                .collect::<Vec<_>>()
+                .join(" and ")
+        ));
+    }
+
+    let events = record
+        .trades
+        .iter()
+        .cloned()
+        .map(DialogueContextEvent::Trade)
+        .collect();
+    let mut context = DialogueContext::with_events(events);
+    context.summary = Some(sentences.join(" "));
+    context
+}
+
+/// Collects trades, activities, needs, and starting mood into the daily journal.
+pub fn journal_npc_day(
+    clock: Res<WorldClock>,
+    mut journal: ResMut<DailyReflectionJournal>,
+    mut trades: MessageReader<TradeCompletedEvent>,
+    mut activities: MessageReader<NpcActivityChangedEvent>,
+    mut dependencies: MessageReader<ProfessionDependencyUpdateEvent>,
+    npcs: Query<(&Identity, &NpcMotivation)>,
+) {
+    let day = clock.day_count();
+    journal.begin_day(day);
+
+    for (identity, motivation) in npcs.iter() {
+        let entry = journal.entry(identity.id);
+        if entry.starting_dopamine.is_none() {
+            entry.starting_dopamine = Some(motivation.dopamine());
+        }
+    }
+
+    for trade in trades.read() {
+        if trade.day != day {
+            continue;
+        }
+
+        let context = TradeContext {
+            day: trade.day,
+            time_of_day: None,
+            from: trade.from,
+            to: trade.to,
+            descriptor: TradeDescriptor::new(trade.good.label(), trade.quantity),
+            reason: trade.reason.into(),
+        };
+        for participant in [trade.from, trade.to]
+            .into_iter()
+            .flatten()
+            .filter(|participant| !participant.is_player())
+        {
+            journal.entry(participant).trades.push(context.clone());
+        }
+    }
+
+    for activity in activities.read() {
+        let entry = journal.entry(activity.npc);
+        if entry.activities.last() != Some(&activity.activity) {
+            entry.activities.push(activity.activity.clone());
+        }
+    }
+
+    for update in dependencies.read() {
+        if update.day == day {
+            journal.entry(update.npc).missing_dependencies = update.missing_categories.clone();
+        }
+    }
+}
+
+/// Queues one Status reflection per NPC the first time the clock passes dusk each day.
+pub fn enqueue_dusk_reflections(
+    clock: Res<WorldClock>,
+    settings: Res<WorldTimeSettings>,
+    journal: Res<DailyReflectionJournal>,
+    mut latch: ResMut<DuskReflectionLatch>,
+    mut queue: ResMut<DialogueRequestQueue>,
+    npcs: Query<(&Identity, &NpcMotivation)>,
+) {
+    let day = clock.day_count();
+    let time_of_day = clock.time_of_day();
+    let empty = ReflectionDayRecord::default();
+
+    for (identity, motivation) in npcs.iter() {
+        if !latch.try_fire(identity.id, day, time_of_day, settings.sunset_fraction) {
+            continue;
+        }
+
+        let context = build_reflection_context(&ReflectionSnapshot {
+            day,
+            display_name: &identity.display_name,
+            record: journal.record(identity.id).unwrap_or(&empty),
+            ending_dopamine: motivation.dopamine(),
+            mood: motivation.mood(),
+        });
+        let prompt = format!("{} {REFLECTION_PROMPT_ACTION}.", identity.id);
+        let request = DialogueRequest::new(
+            identity.id,
+            None,
+            prompt,
+            DialogueTopicHint::Status,
+            context,
+        );
+        let id = queue.enqueue(request);
+        debug!(
+            "Queued dusk reflection ({}) for {} on day {}",
+            id, identity.display_name, day
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue::types::TradeContextReason;
+
+    #[test]
+    fn latch_fires_once_per_day_after_dusk() {
+        let mut latch = DuskReflectionLatch::default();
+        let npc = NpcId::new(1);
+
+        assert!(!latch.try_fire(npc, 0, 0.5, 0.75), "before dusk");
+        assert!(latch.try_fire(npc, 0, 0.76, 0.75));
+        assert!(!latch.try_fire(npc, 0, 0.8, 0.75), "same evening");
+        assert!(!latch.try_fire(npc, 0, 0.99, 0.75), "clock jump");
+        assert!(
+            latch.try_fire(NpcId::new(2), 0, 0.99, 0.75),
+            "latch is per NPC"
+        );
+
+        assert!(!latch.try_fire(npc, 1, 0.1, 0.75), "next morning");
+        assert!(latch.try_fire(npc, 1, 0.9, 0.75), "next dusk");
+    }
+
+    #[test]
+    fn reflection_context_summarises_day() {
+        let trade = TradeContext {
+            day: 2,
+            time_of_day: None,
+            from: Some(NpcId::new(1)),
+            to: Some(NpcId::new(2)),
+            descriptor: TradeDescriptor::new("grain crate", 1),
+            reason: TradeContextReason::Exchange,
+        };
+        let record = ReflectionDayRecord {
+            trades: vec![trade],
+            activities: vec!["Working the fields".to_string(), "Tavern".to_string()],
+            starting_dopamine: Some(50.0),
+            missing_dependencies: vec![DependencyCategory::Tools],
+        };
+
+        let context = build_reflection_context(&ReflectionSnapshot {
+            day: 2,
+            display_name: "Alric",
+            record: &record,
+            ending_dopamine: 62.0,
+            mood: NpcMood::Energised,
+        });
+
+        let summary = context.summary.expect("reflection should have a summary");
+        assert!(summary.contains("Day 2 reflection for Alric"));
+        assert!(summary.contains("1 trade"));
+        assert!(summary.contains("Working the fields, Tavern"));
+        assert!(summary.contains("rose from 50 to 62, ending energised"));
+        assert!(summary.contains("lacking tools"));
+        assert!(matches!(
+            context.events.as_slice(),
+            [DialogueContextEvent::Trade(_)]
+        ));
+    }
+
+    #[test]
+    fn quiet_day_reports_steady_mood() {
+        let record = ReflectionDayRecord::default();
+        let context = build_reflection_context(&ReflectionSnapshot {
+            day: 0,
+            display_name: "Bryn",
+            record: &record,
+            ending_dopamine: 40.0,
+            mood: NpcMood::Content,
+        });
+
+        let summary = context.summary.unwrap();
+        assert!(summary.contains("No trades today"));
+        assert!(summary.contains("held steady"));
+        assert!(!summary.contains("lacking"));
+        assert!(context.events.is_empty());
+    }
+}
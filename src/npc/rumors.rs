@@ -0,0 +1,454 @@
+//! Rumors: NPCs remember trades they hear about in conversation and may pass them on,
+//! hedged, when they next chat with someone else.
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::{
+    core::format::format_quantity,
+    dialogue::{
+        events::{DialogueRequestedEvent, DialogueResponseEvent},
+        queue::DialogueRequestQueue,
+        types::{DialogueContextEvent, HearsayContext, RumorFidelity, TradeContext},
+    },
+    economy::{data::EconomyRegistry, rng::DailyRng},
+    npc::{
+        components::{Identity, NpcId},
+        spatial::NpcIndex,
+    },
+    world::time::WorldClock,
+};
+
+const DEFAULT_RELAY_CHANCE: f32 = 0.35;
+const DEFAULT_MAX_AGE_DAYS: u64 = 3;
+const DEFAULT_CAPACITY: usize = 8;
+/// Keeps rumor rolls independent of the economy's demand and scarcity streams.
+const RUMOR_STREAM: u64 = 2;
+
+/// Tunables for how rumors spread and fade.
+#[derive(Resource, Debug, Clone)]
+pub struct RumorConfig {
+    /// Chance an NPC with something to share attaches a rumor to an outgoing chat.
+    pub relay_chance: f32,
+    /// Rumors about events older than this many days are forgotten.
+    pub max_age_days: u64,
+    /// Rumors each NPC remembers; the oldest-learned is dropped first.
+    pub capacity: usize,
+}
+
+impl Default for RumorConfig {
+    fn default() -> Self {
+        Self {
+            relay_chance: DEFAULT_RELAY_CHANCE,
+            max_age_days: DEFAULT_MAX_AGE_DAYS,
+            capacity: DEFAULT_CAPACITY,
+        }
+    }
+}
+
+/// Something an NPC heard in conversation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rumor {
+    pub origin_npc: NpcId,
+    pub subject: String,
+    pub day: u64,
+    pub fidelity: RumorFidelity,
+}
+
+impl Rumor {
+    /// The rumor as the next listener hears it, one fidelity step worse, or `None` when
+    /// it is too vague to repeat.
+    pub fn retold(&self) -> Option<HearsayContext> {
+        Some(HearsayContext {
+            origin: self.origin_npc,
+            subject: self.subject.clone(),
+            day: self.day,
+            fidelity: self.fidelity.degrade()?,
+        })
+    }
+
+    fn is_stale(&self, today: u64, max_age_days: u64) -> bool {
+        today.saturating_sub(self.day) > max_age_days
+    }
+}
+
+/// Rumors an NPC remembers, oldest-learned first.
+#[derive(Component, Debug, Clone, Default)]
+pub struct NpcKnowledge {
+    rumors: VecDeque<Rumor>,
+}
+
+impl NpcKnowledge {
+    #[cfg(test)]
+    pub fn rumors(&self) -> impl Iterator<Item = &Rumor> {
+        self.rumors.iter()
+    }
+
+    /// Remembers `rumor`. Hearing a known rumor again keeps the more reliable version;
+    /// past `capacity` the oldest-learned rumor is dropped.
+    pub fn learn(&mut self, rumor: Rumor, capacity: usize) {
+        if let Some(known) = self.rumors.iter_mut().find(|known| {
+            known.origin_npc == rumor.origin_npc
+                && known.day == rumor.day
+                && known.subject == rumor.subject
+        }) {
+            known.fidelity = known.fidelity.min(rumor.fidelity);
+            return;
+        }
+        self.rumors.push_back(rumor);
+        while self.rumors.len() > capacity {
+            self.rumors.pop_front();
+        }
+    }
+
+    /// Drops rumors about events more than `max_age_days` before `today`.
+    pub fn forget_stale(&mut self, today: u64, max_age_days: u64) {
+        self.rumors
+            .retain(|rumor| !rumor.is_stale(today, max_age_days));
+    }
+
+    /// Newest rumor worth telling `listener`: never one about the listener themselves,
+    /// and never one too vague to repeat.
+    pub fn rumor_for(&self, listener: NpcId) -> Option<&Rumor> {
+        self.rumors
+            .iter()
+            .rev()
+            .find(|rumor| rumor.origin_npc != listener && rumor.fidelity.degrade().is_some())
+    }
+}
+
+/// What a listener takes away from a line of dialogue: trades the speaker talked about,
+/// heard straight from the source, plus any hearsay the speaker passed along.
+pub fn rumors_heard(
+    speaker: NpcId,
+    listener: NpcId,
+    context: &[DialogueContextEvent],
+) -> Vec<Rumor> {
+    context
+        .iter()
+        .filter_map(|event| match event {
+            DialogueContextEvent::Trade(trade) => Some(Rumor {
+                origin_npc: speaker,
+                subject: describe_trade(speaker, trade),
+                day: trade.day,
+                fidelity: RumorFidelity::Exact,
+            }),
+            DialogueContextEvent::Hearsay(hearsay) => Some(Rumor {
+                origin_npc: hearsay.origin,
+                subject: hearsay.subject.clone(),
+                day: hearsay.day,
+                fidelity: hearsay.fidelity,
+            }),
+            // Village news is common knowledge already, not something to pass on.
+            DialogueContextEvent::ScheduleUpdate { .. }
+            | DialogueContextEvent::Custom { .. }
+            | DialogueContextEvent::VillageNews(_) => None,
+        })
+        .filter(|rumor| rumor.origin_npc != listener)
+        .collect()
+}
+
+fn describe_trade(speaker: NpcId, trade: &TradeContext) -> String {
+    let mut subject = format!(
+        "{} {} {}",
+        trade.from.unwrap_or(speaker),
+        trade.reason.past_tense(),
+        format_quantity(&trade.descriptor.label, trade.descriptor.quantity)
+    );
+    if let Some(to) = trade.to {
+        subject.push_str(&format!(" for {to}"));
+    }
+    subject
+}
+
+/// Whether `speaker` passes a rumor on in the chat queued as `request_seed`. Rolls on the
+/// economy seed, so a replayed day spreads the same rumors.
+pub fn rolls_relay(seed: u64, day: u64, request_seed: u64, chance: f32) -> bool {
+    DailyRng::for_day(seed, day, RUMOR_STREAM ^ request_seed.rotate_left(8)).chance(chance)
+}
+
+/// NPC listeners remember what they heard in NPC-to-NPC dialogue.
+pub fn learn_rumors_from_dialogue(
+    mut responses: MessageReader<DialogueResponseEvent>,
+    config: Res<RumorConfig>,
+    clock: Res<WorldClock>,
+    npc_index: Res<NpcIndex>,
+    mut npcs: Query<(&Identity, &mut NpcKnowledge)>,
+) {
+    for event in responses.read() {
+        let speaker = event.response.speaker;
+        let Some(listener) = event.response.target.filter(|target| !target.is_player()) else {
+            continue;
+        };
+        let heard = rumors_heard(speaker, listener, &event.context.events);
+        if heard.is_empty() {
+            continue;
+        }
+        let listener_npc = npc_index
+            .entity(listener)
+            .and_then(|entity| npcs.get_mut(entity).ok());
+        let Some((identity, mut knowledge)) = listener_npc else {
+            continue;
+        };
+        knowledge.forget_stale(clock.day_count(), config.max_age_days);
+        for rumor in heard {
+            if rumor.is_stale(clock.day_count(), config.max_age_days) {
+                continue;
+            }
+            debug!("{} heard: {}", identity.display_name, rumor.subject);
+            knowledge.learn(rumor, config.capacity);
+        }
+    }
+}
+
+/// When an NPC starts a chat with another NPC, they may mention a rumor they picked up,
+/// attached to the queued request as hearsay one fidelity step worse.
+pub fn relay_rumors_in_conversation(
+    mut requested: MessageReader<DialogueRequestedEvent>,
+    config: Res<RumorConfig>,
+    clock: Res<WorldClock>,
+    registry: Res<EconomyRegistry>,
+    mut queue: ResMut<DialogueRequestQueue>,
+    npc_index: Res<NpcIndex>,
+    mut npcs: Query<(&Identity, &mut NpcKnowledge)>,
+) {
+    let today = clock.day_count();
+    for event in requested.read() {
+        let Some(listener) = event.target.filter(|target| !target.is_player()) else {
+            continue;
+        };
+        let speaker = npc_index
+            .entity(event.speaker)
+            .and_then(|entity| npcs.get_mut(entity).ok());
+        let Some((identity, mut knowledge)) = speaker else {
+            continue;
+        };
+        knowledge.forget_stale(today, config.max_age_days);
+        let Some(hearsay) = knowledge.rumor_for(listener).and_then(Rumor::retold) else {
+            continue;
+        };
+        if !rolls_relay(
+            registry.seed(),
+            today,
+            event.request_id.value(),
+            config.relay_chance,
+        ) {
+            continue;
+        }
+        let Some(request) = queue.pending_mut(event.request_id) else {
+            continue;
+        };
+        debug!(
+            "{} passes on a rumor to {}: {}",
+            identity.display_name, listener, hearsay.subject
+        );
+        request
+            .context
+            .events
+            .push(DialogueContextEvent::Hearsay(hearsay));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue::{
+        broker::DialogueProviderKind,
+        types::{
+            DialogueContext, DialogueRequest, DialogueRequestId, DialogueResponse,
+            DialogueTopicHint, TradeContextReason, TradeDescriptor,
+        },
+    };
+    use crate::npc::spatial::index_npcs;
+
+    fn rumor(origin: u64, subject: &str, day: u64, fidelity: RumorFidelity) -> Rumor {
+        Rumor {
+            origin_npc: NpcId::new(origin),
+            subject: subject.to_string(),
+            day,
+            fidelity,
+        }
+    }
+
+    fn trade(day: u64) -> DialogueContextEvent {
+        DialogueContextEvent::Trade(TradeContext {
+            day,
+            time_of_day: None,
+            from: Some(NpcId::new(1)),
+            to: Some(NpcId::new(2)),
+            descriptor: TradeDescriptor::new("grain crate", 2),
+            reason: TradeContextReason::Exchange,
+        })
+    }
+
+    #[test]
+    fn relay_rolls_replay_for_a_seed() {
+        let rolls = |seed: u64| -> Vec<bool> {
+            (0..64)
+                .map(|request| rolls_relay(seed, 4, request, 0.35))
+                .collect()
+        };
+        assert_eq!(rolls(7), rolls(7));
+        assert_ne!(rolls(7), rolls(8));
+        let hits = rolls(7).iter().filter(|hit| **hit).count();
+        assert!((8..=40).contains(&hits), "roughly 35% of 64: {hits}");
+        assert!(!rolls_relay(7, 4, 1, 0.0));
+        assert!(rolls_relay(7, 4, 1, 1.0));
+    }
+
+    #[test]
+    fn retelling_degrades_fidelity_until_it_stops() {
+        let exact = rumor(
+            1,
+            "NPC-0001 exchanged 2 grain crate",
+            3,
+            RumorFidelity::Exact,
+        );
+        let told = exact.retold().unwrap();
+        assert_eq!(told.fidelity, RumorFidelity::Secondhand);
+        assert_eq!(told.origin, NpcId::new(1));
+
+        let heard = rumors_heard(
+            NpcId::new(2),
+            NpcId::new(3),
+            &[DialogueContextEvent::Hearsay(told)],
+        );
+        let secondhand = heard[0].retold().unwrap();
+        assert_eq!(secondhand.fidelity, RumorFidelity::Hazy);
+        let hazy = rumor(1, "x", 3, RumorFidelity::Hazy);
+        assert!(hazy.retold().is_none());
+
+        let mut knowledge = NpcKnowledge::default();
+        knowledge.learn(hazy, 4);
+        assert!(
+            knowledge.rumor_for(NpcId::new(9)).is_none(),
+            "hazy rumors are not repeated"
+        );
+        knowledge.learn(rumor(1, "x", 3, RumorFidelity::Exact), 4);
+        assert_eq!(knowledge.rumors().count(), 1);
+        assert_eq!(
+            knowledge.rumors().next().unwrap().fidelity,
+            RumorFidelity::Exact,
+            "hearing it again from the source sharpens it"
+        );
+    }
+
+    #[test]
+    fn rumors_never_bounce_back_to_their_origin() {
+        let heard = rumors_heard(NpcId::new(1), NpcId::new(2), &[trade(3)]);
+        assert_eq!(
+            heard,
+            [rumor(
+                1,
+                "NPC-0001 exchanged 2 grain crates for NPC-0002",
+                3,
+                RumorFidelity::Exact
+            )]
+        );
+
+        let told = heard[0].retold().unwrap();
+        assert!(
+            rumors_heard(
+                NpcId::new(2),
+                NpcId::new(1),
+                &[DialogueContextEvent::Hearsay(told)]
+            )
+            .is_empty(),
+            "the origin does not learn its own news secondhand"
+        );
+
+        let mut knowledge = NpcKnowledge::default();
+        knowledge.learn(heard[0].clone(), 4);
+        assert!(knowledge.rumor_for(NpcId::new(1)).is_none());
+        assert!(knowledge.rumor_for(NpcId::new(3)).is_some());
+    }
+
+    #[test]
+    fn rumors_expire_and_storage_stays_bounded() {
+        let mut knowledge = NpcKnowledge::default();
+        for day in 0..6 {
+            knowledge.learn(
+                rumor(1, &format!("day {day}"), day, RumorFidelity::Exact),
+                4,
+            );
+        }
+        let days: Vec<u64> = knowledge.rumors().map(|rumor| rumor.day).collect();
+        assert_eq!(
+            days,
+            [2, 3, 4, 5],
+            "oldest-learned rumors are dropped first"
+        );
+
+        knowledge.forget_stale(7, 3);
+        let days: Vec<u64> = knowledge.rumors().map(|rumor| rumor.day).collect();
+        assert_eq!(days, [4, 5]);
+    }
+
+    #[test]
+    fn heard_trades_spread_to_the_next_conversation() {
+        let mut app = App::new();
+        app.insert_resource(RumorConfig {
+            relay_chance: 1.0,
+            ..RumorConfig::default()
+        })
+        .insert_resource(WorldClock::new())
+        .init_resource::<EconomyRegistry>()
+        .init_resource::<DialogueRequestQueue>()
+        .init_resource::<NpcIndex>()
+        .add_message::<DialogueResponseEvent>()
+        .add_message::<DialogueRequestedEvent>()
+        .add_systems(
+            Update,
+            (
+                index_npcs,
+                learn_rumors_from_dialogue,
+                relay_rumors_in_conversation,
+            )
+                .chain(),
+        );
+        for (id, name) in [(1, "Alric"), (2, "Bryn"), (3, "Cedric")] {
+            app.world_mut().spawn((
+                Identity::new(NpcId::new(id), name, 30.0),
+                NpcKnowledge::default(),
+            ));
+        }
+
+        app.world_mut().write_message(DialogueResponseEvent {
+            response: DialogueResponse::new(
+                DialogueRequestId::new(0),
+                DialogueProviderKind::OpenAi,
+                NpcId::new(1),
+                Some(NpcId::new(2)),
+                "Fine grain this year.",
+            ),
+            context: DialogueContext::with_events(vec![trade(0)]),
+        });
+        let request_id = app
+            .world_mut()
+            .resource_mut::<DialogueRequestQueue>()
+            .enqueue(DialogueRequest::new(
+                NpcId::new(2),
+                Some(NpcId::new(3)),
+                "Bryn greets Cedric.",
+                DialogueTopicHint::Status,
+                DialogueContext::default(),
+            ));
+        app.world_mut().write_message(DialogueRequestedEvent {
+            request_id,
+            speaker: NpcId::new(2),
+            target: Some(NpcId::new(3)),
+        });
+        app.update();
+
+        let mut queue = app.world_mut().resource_mut::<DialogueRequestQueue>();
+        let request = queue.pending_mut(request_id).unwrap();
+        assert!(matches!(
+            request.context.events.as_slice(),
+            [DialogueContextEvent::Hearsay(HearsayContext {
+                origin,
+                fidelity: RumorFidelity::Secondhand,
+                ..
+            })] if *origin == NpcId::new(1)
+        ));
+    }
+}
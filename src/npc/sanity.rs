@@ -0,0 +1,154 @@
+//! Debug safety net for corrupted NPC transforms, built with the `transform_sanity` feature.
+//! Locomotion already refuses to write non-finite positions; this catches whatever else
+//! slips through (physics, scripts, bad saves) before the NaN spreads to bubbles and labels.
+use bevy::prelude::*;
+
+use crate::npc::components::Identity;
+
+/// Real seconds between sweeps; corruption is rare, so there is no need to check every frame.
+const SANITIZE_INTERVAL_SECONDS: f32 = 1.0;
+
+/// Most recent finite translation seen for an NPC, used to restore a corrupted one.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct LastGoodPosition(pub Vec3);
+
+/// Fired when an NPC's translation was found non-finite and reset.
+#[derive(Event, Message, Debug, Clone, PartialEq)]
+pub struct TransformCorruptionDetected {
+    pub entity: Entity,
+    pub name: String,
+    /// `None` when the NPC was never seen at a finite position and was left untouched.
+    pub restored_to: Option<Vec3>,
+}
+
+#[derive(Deref, DerefMut)]
+pub struct SanitizeTimer(Timer);
+
+impl Default for SanitizeTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(
+            SANITIZE_INTERVAL_SECONDS,
+            TimerMode::Repeating,
+        ))
+    }
+}
+
+/// Records finite NPC translations and resets non-finite ones to the last recorded value.
+pub fn sanitize_transforms(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut timer: Local<SanitizeTimer>,
+    mut npcs: Query<(
+        Entity,
+        &Identity,
+        &mut Transform,
+        Option<&mut LastGoodPosition>,
+    )>,
+    mut detected: MessageWriter<TransformCorruptionDetected>,
+) {
+    if !timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    for (entity, identity, mut transform, last_good) in &mut npcs {
+        if transform.translation.is_finite() {
+            match last_good {
+                Some(mut last_good) => last_good.0 = transform.translation,
+                None => {
+                    commands
+                        .entity(entity)
+                        .insert(LastGoodPosition(transform.translation));
+                }
+            }
+            continue;
+        }
+
+        let restored_to = last_good.map(|last_good| last_good.0);
+        match restored_to {
+            Some(position) => {
+                transform.translation = position;
+                warn!(
+                    "{} had a non-finite position; restored to {}",
+                    identity.display_name, position
+                );
+            }
+            None => warn!(
+                "{} has a non-finite position and no known good one to restore",
+                identity.display_name
+            ),
+        }
+        detected.write(TransformCorruptionDetected {
+            entity,
+            name: identity.display_name.clone(),
+            restored_to,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+    use crate::npc::components::NpcId;
+
+    /// Virtual time caps each frame at 0.25 s, so a sweep spans several updates.
+    const STEP: Duration = Duration::from_millis(250);
+
+    #[test]
+    fn corrupted_translation_is_restored_to_the_last_good_one() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(STEP))
+            .add_message::<TransformCorruptionDetected>()
+            .add_systems(Update, sanitize_transforms);
+        let npc = app
+            .world_mut()
+            .spawn((
+                Identity::new(NpcId::new(1), "Alric", 30.0),
+                Transform::from_xyz(4.0, 1.0, 2.0),
+            ))
+            .id();
+        let mut cursor = app
+            .world()
+            .resource::<Messages<TransformCorruptionDetected>>()
+            .get_cursor();
+
+        let sweep = |app: &mut App| {
+            // Four 0.25 s steps per interval, plus one for the frame that primes `Time`.
+            for _ in 0..5 {
+                app.update();
+            }
+        };
+
+        sweep(&mut app);
+        assert_eq!(
+            app.world().get::<LastGoodPosition>(npc),
+            Some(&LastGoodPosition(Vec3::new(4.0, 1.0, 2.0)))
+        );
+
+        app.world_mut()
+            .get_mut::<Transform>(npc)
+            .unwrap()
+            .translation = Vec3::new(f32::NAN, 1.0, 2.0);
+        sweep(&mut app);
+
+        assert_eq!(
+            app.world().get::<Transform>(npc).unwrap().translation,
+            Vec3::new(4.0, 1.0, 2.0)
+        );
+        let events = app
+            .world()
+            .resource::<Messages<TransformCorruptionDetected>>();
+        let detected: Vec<_> = cursor.read(events).cloned().collect();
+        assert_eq!(
+            detected,
+            vec![TransformCorruptionDetected {
+                entity: npc,
+                name: "Alric".to_string(),
+                restored_to: Some(Vec3::new(4.0, 1.0, 2.0)),
+            }]
+        );
+    }
+}
@@ -0,0 +1,333 @@
+//! Runtime schedule edits for iterating on NPC routines without recompiling.
+use std::fmt;
+
+use bevy::prelude::*;
+
+use crate::{
+    core::input::{ActionInput, InputAction},
+    npc::{
+        components::{
+            clamp_schedule_start, DailySchedule, Identity, NpcId, ScheduleEntry, ScheduleState,
+            START_TOLERANCE,
+        },
+        events::NpcScheduleChangedEvent,
+        spatial::{NpcIndex, SpatialIndex},
+    },
+    world::selection::SelectedNpc,
+};
+
+/// Debug edits to an NPC's `DailySchedule`. `ReplaceSchedule` comes from the cycle-schedule
+/// binding; the console's `schedule` and `unschedule` commands send the other two.
+#[derive(Message, Debug, Clone)]
+pub enum ScheduleCommand {
+    ReplaceSchedule {
+        npc: NpcId,
+        entries: Vec<ScheduleEntry>,
+    },
+    InsertEntry {
+        npc: NpcId,
+        entry: ScheduleEntry,
+    },
+    RemoveEntryAt {
+        npc: NpcId,
+        start: f32,
+    },
+}
+
+impl ScheduleCommand {
+    pub fn npc(&self) -> NpcId {
+        match self {
+            Self::ReplaceSchedule { npc, .. }
+            | Self::InsertEntry { npc, .. }
+            | Self::RemoveEntryAt { npc, .. } => *npc,
+        }
+    }
+}
+
+/// Why a schedule command was rejected; the schedule is left untouched.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScheduleEditError {
+    DuplicateStart(f32),
+    NoEntryAt(f32),
+}
+
+impl fmt::Display for ScheduleEditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DuplicateStart(start) => write!(f, "two entries start at {start:.3}"),
+            Self::NoEntryAt(start) => write!(f, "no entry starts at {start:.3}"),
+        }
+    }
+}
+
+/// Applies `command` to `schedule`, keeping entries sorted by start. Duplicate starts are
+/// rejected; the result is then validated by `DailySchedule::new`, which trims overlapping
+/// ends.
+pub fn apply_schedule_command(
+    schedule: &mut DailySchedule,
+    command: &ScheduleCommand,
+) -> Result<(), ScheduleEditError> {
+    let mut entries = match command {
+        ScheduleCommand::ReplaceSchedule { entries, .. } => entries.clone(),
+        ScheduleCommand::InsertEntry { entry, .. } => {
+            let mut entries = schedule.entries.clone();
+            entries.push(entry.clone());
+            entries
+        }
+        ScheduleCommand::RemoveEntryAt { start, .. } => {
+            let start = clamp_schedule_start(*start);
+            let index = schedule
+                .entries
+                .iter()
+                .position(|entry| (entry.start - start).abs() < START_TOLERANCE)
+                .ok_or(ScheduleEditError::NoEntryAt(start))?;
+            let mut entries = schedule.entries.clone();
+            entries.remove(index);
+            entries
+        }
+    };
+
+    for entry in &mut entries {
+        entry.start = clamp_schedule_start(entry.start);
+    }
+    entries.sort_by(|a, b| a.start.total_cmp(&b.start));
+    if let Some(pair) = entries
+        .windows(2)
+        .find(|pair| pair[1].start - pair[0].start < START_TOLERANCE)
+    {
+        return Err(ScheduleEditError::DuplicateStart(pair[1].start));
+    }
+
+    *schedule = DailySchedule::new(entries);
+    Ok(())
+}
+
+/// Applies queued schedule commands, clearing the NPC's current activity so the next
+/// schedule tick re-announces it.
+pub fn apply_schedule_commands(
+    mut commands: MessageReader<ScheduleCommand>,
+    npc_index: Res<NpcIndex>,
+    mut npcs: Query<(&Identity, &mut DailySchedule, &mut ScheduleState)>,
+    mut changed: MessageWriter<NpcScheduleChangedEvent>,
+) {
+    for command in commands.read() {
+        let npc = command.npc();
+        let Some((identity, mut schedule, mut state)) = npc_index
+            .entity(npc)
+            .and_then(|entity| npcs.get_mut(entity).ok())
+        else {
+            warn!("Schedule command for unknown NPC {}", npc);
+            continue;
+        };
+
+        if let Err(error) = apply_schedule_command(&mut schedule, command) {
+            warn!(
+                "Schedule command for {} rejected: {}",
+                identity.display_name, error
+            );
+            continue;
+        }
+        state.current_activity.clear();
+        info!(
+            "{} now follows a {}-entry schedule",
+            identity.display_name,
+            schedule.entries.len()
+        );
+        changed.write(NpcScheduleChangedEvent { npc });
+    }
+}
+
+/// Predefined routines cycled by the debug key.
+fn test_schedules() -> [Vec<ScheduleEntry>; 2] {
+    [
+        vec![
+            ScheduleEntry::new(0.00, "Sleeping"),
+            ScheduleEntry::new(0.15, "Early rounds"),
+            ScheduleEntry::new(0.40, "Long lunch"),
+            ScheduleEntry::new(0.60, "Odd jobs"),
+            ScheduleEntry::new(0.85, "Turning in early"),
+        ],
+        vec![
+            ScheduleEntry::new(0.10, "Sleeping late"),
+            ScheduleEntry::new(0.45, "Slow breakfast"),
+            ScheduleEntry::new(0.70, "Night watch"),
+        ],
+    ]
+}
+
+/// The cycle-schedule binding (F12) steps the selected NPC, or the one nearest the camera,
+/// through the test routines.
+pub fn cycle_debug_schedule(
+    input: ActionInput,
+    selected: Option<Res<SelectedNpc>>,
+    cameras: Query<&GlobalTransform, With<Camera3d>>,
+    spatial: Res<SpatialIndex>,
+    npcs: Query<&Identity, With<DailySchedule>>,
+    mut writer: MessageWriter<ScheduleCommand>,
+    mut next_schedule: Local<usize>,
+) {
+    if !input.just_pressed(InputAction::CycleSchedule) {
+        return;
+    }
+
+    let target = selected
+        .and_then(|selected| selected.entity())
+        .and_then(|entity| npcs.get(entity).ok())
+        .or_else(|| {
+            let camera = cameras.iter().next()?.translation();
+            let nearest = spatial.nearest(camera, |entity, _| npcs.contains(entity))?;
+            npcs.get(nearest.entity).ok()
+        });
+    let Some(identity) = target else {
+        debug!("Schedule cycle pressed but no NPC to edit");
+        return;
+    };
+
+    let schedules = test_schedules();
+    let index = *next_schedule % schedules.len();
+    *next_schedule = next_schedule.wrapping_add(1);
+    info!(
+        "Assigning test schedule {} to {}",
+        index + 1,
+        identity.display_name
+    );
+    writer.write(ScheduleCommand::ReplaceSchedule {
+        npc: identity.id,
+        entries: schedules[index].clone(),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::npc::{components::LATEST_START, spatial::index_npcs};
+
+    fn schedule() -> DailySchedule {
+        DailySchedule::new(vec![
+            ScheduleEntry::new(0.0, "Sleeping"),
+            ScheduleEntry::new(0.5, "Working"),
+        ])
+    }
+
+    fn activities(schedule: &DailySchedule) -> Vec<(f32, &str)> {
+        schedule
+            .entries
+            .iter()
+            .map(|entry| (entry.start, entry.activity.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn replace_sorts_and_clamps_entries() {
+        let mut schedule = schedule();
+        let command = ScheduleCommand::ReplaceSchedule {
+            npc: NpcId::new(0),
+            entries: vec![
+                ScheduleEntry {
+                    start: 1.4,
+                    end: None,
+                    activity: "Late".to_string(),
+                },
+                ScheduleEntry::new(0.3, "Morning"),
+                ScheduleEntry {
+                    start: -0.2,
+                    end: None,
+                    activity: "Midnight".to_string(),
+                },
+            ],
+        };
+
+        apply_schedule_command(&mut schedule, &command).unwrap();
+        assert_eq!(
+            activities(&schedule),
+            [(0.0, "Midnight"), (0.3, "Morning"), (LATEST_START, "Late")]
+        );
+    }
+
+    #[test]
+    fn insert_keeps_order_and_rejects_duplicate_starts() {
+        let mut schedule = schedule();
+        let npc = NpcId::new(0);
+        apply_schedule_command(
+            &mut schedule,
+            &ScheduleCommand::InsertEntry {
+                npc,
+                entry: ScheduleEntry::new(0.25, "Fetching water"),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            activities(&schedule),
+            [
+                (0.0, "Sleeping"),
+                (0.25, "Fetching water"),
+                (0.5, "Working")
+            ]
+        );
+
+        let error = apply_schedule_command(
+            &mut schedule,
+            &ScheduleCommand::InsertEntry {
+                npc,
+                entry: ScheduleEntry::new(0.5, "Napping"),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(error, ScheduleEditError::DuplicateStart(0.5));
+        assert_eq!(schedule.entries.len(), 3, "rejected edits change nothing");
+    }
+
+    #[test]
+    fn remove_targets_the_entry_at_a_start() {
+        let mut schedule = schedule();
+        let npc = NpcId::new(0);
+        assert_eq!(
+            apply_schedule_command(
+                &mut schedule,
+                &ScheduleCommand::RemoveEntryAt { npc, start: 0.4 }
+            ),
+            Err(ScheduleEditError::NoEntryAt(0.4))
+        );
+        apply_schedule_command(
+            &mut schedule,
+            &ScheduleCommand::RemoveEntryAt { npc, start: 0.5 },
+        )
+        .unwrap();
+        assert_eq!(activities(&schedule), [(0.0, "Sleeping")]);
+    }
+
+    #[test]
+    fn applied_commands_reset_state_and_announce_the_change() {
+        let mut app = App::new();
+        app.init_resource::<NpcIndex>()
+            .add_message::<ScheduleCommand>()
+            .add_message::<NpcScheduleChangedEvent>()
+            .add_systems(Update, (index_npcs, apply_schedule_commands).chain());
+        let npc = NpcId::new(2);
+        let entity = app
+            .world_mut()
+            .spawn((
+                Identity::new(npc, "Cedric", 30.0),
+                schedule(),
+                ScheduleState {
+                    current_activity: "Working".to_string(),
+                },
+            ))
+            .id();
+
+        app.world_mut()
+            .write_message(ScheduleCommand::ReplaceSchedule {
+                npc,
+                entries: vec![ScheduleEntry::new(0.2, "Patrol")],
+            });
+        app.update();
+
+        let state = app.world().get::<ScheduleState>(entity).unwrap();
+        assert!(state.current_activity.is_empty());
+        let schedule = app.world().get::<DailySchedule>(entity).unwrap();
+        assert_eq!(activities(schedule), [(0.2, "Patrol")]);
+        let messages = app.world().resource::<Messages<NpcScheduleChangedEvent>>();
+        let changed: Vec<_> = messages.get_cursor().read(messages).cloned().collect();
+        assert_eq!(changed, [NpcScheduleChangedEvent { npc }]);
+    }
+}
@@ -0,0 +1,236 @@
+//! Soft-body crowd separation so NPCs sharing a destination don't stand inside each other.
+use bevy::prelude::*;
+
+use crate::{
+    core::plugin::SimulationClock,
+    npc::{
+        components::{InConversation, NpcLocomotion},
+        spatial::SpatialGrid,
+    },
+};
+
+const DEFAULT_PERSONAL_SPACE_RADIUS: f32 = 0.7;
+const DEFAULT_MAX_PUSH_PER_SECOND: f32 = 1.5;
+const DEFAULT_ARRIVAL_LEASH: f32 = 0.2;
+/// Below this distance two NPCs count as stacked and split along a fallback axis.
+const STACKED_EPSILON: f32 = 1e-4;
+
+/// Tunables for the separation pass that runs after locomotion.
+#[derive(Resource, Debug, Clone)]
+pub struct CrowdSeparationConfig {
+    /// NPCs closer than this (on the XZ plane) push each other apart.
+    pub personal_space_radius: f32,
+    /// Cap on how far separation may move one NPC per second of scaled sim time.
+    pub max_push_per_second: f32,
+    /// Furthest separation may carry an NPC from its active arrival point, kept below
+    /// the locomotion arrive distance so crate tasks still see the NPC as arrived.
+    pub arrival_leash: f32,
+}
+
+impl Default for CrowdSeparationConfig {
+    fn default() -> Self {
+        Self {
+            personal_space_radius: DEFAULT_PERSONAL_SPACE_RADIUS,
+            max_push_per_second: DEFAULT_MAX_PUSH_PER_SECOND,
+            arrival_leash: DEFAULT_ARRIVAL_LEASH,
+        }
+    }
+}
+
+/// One NPC as seen by the separation math, flattened onto the XZ plane.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SeparationAgent {
+    pub position: Vec2,
+    /// Conversing NPCs stand close on purpose; pairs involving them are skipped.
+    pub exempt: bool,
+    pub anchor: Option<Vec2>,
+}
+
+/// Per-agent XZ displacement for this frame, in the same order as `agents`.
+pub fn separation_offsets(
+    agents: &[SeparationAgent],
+    config: &CrowdSeparationConfig,
+    delta_seconds: f32,
+) -> Vec<Vec2> {
+    let mut offsets = vec![Vec2::ZERO; agents.len()];
+    let radius = config.personal_space_radius;
+    if radius <= 0.0 || delta_seconds <= 0.0 {
+        return offsets;
+    }
+
+    for (a, b) in overlapping_pairs(agents, radius) {
+        if agents[a].exempt || agents[b].exempt {
+            continue;
+        }
+
+        let between = agents[a].position - agents[b].position;
+        let distance = between.length();
+        let direction = if distance > STACKED_EPSILON {
+            between / distance
+        } else {
+            // Deterministic split for NPCs snapped onto the same point.
+            Vec2::X
+        };
+        let half_overlap = (radius - distance) * 0.5;
+        offsets[a] += direction * half_overlap;
+        offsets[b] -= direction * half_overlap;
+    }
+
+    let max_step = config.max_push_per_second * delta_seconds;
+    for (offset, agent) in offsets.iter_mut().zip(agents) {
+        *offset = offset.clamp_length_max(max_step);
+        if let Some(anchor) = agent.anchor {
+            *offset = leash_offset(agent.position, *offset, anchor, config.arrival_leash);
+        }
+    }
+
+    offsets
+}
+
+/// Limits `offset` so the agent ends no further than `leash` from `anchor`, without
+/// pulling in an agent that already stood outside it.
+fn leash_offset(position: Vec2, offset: Vec2, anchor: Vec2, leash: f32) -> Vec2 {
+    let allowed = leash.max(position.distance(anchor));
+    let moved = position + offset;
+    let from_anchor = moved - anchor;
+    if from_anchor.length() <= allowed {
+        return offset;
+    }
+    anchor + from_anchor.clamp_length_max(allowed) - position
+}
+
+/// Index pairs `(a, b)` with `a < b` closer than `radius`, found via the shared
+/// `SpatialGrid` so the check stays near-linear as the village grows. Built from this
+/// frame's moved positions rather than the start-of-frame `SpatialIndex`.
+fn overlapping_pairs(agents: &[SeparationAgent], radius: f32) -> Vec<(usize, usize)> {
+    let on_ground = |position: Vec2| Vec3::new(position.x, 0.0, position.y);
+    let mut grid = SpatialGrid::new(radius);
+    for (index, agent) in agents.iter().enumerate() {
+        grid.insert(index, on_ground(agent.position));
+    }
+
+    let mut pairs = Vec::new();
+    for (a, agent) in agents.iter().enumerate() {
+        pairs.extend(
+            grid.within(on_ground(agent.position), radius)
+                .into_iter()
+                .filter(|&(b, distance)| b > a && distance < radius)
+                .map(|(b, _)| (a, b)),
+        );
+    }
+    pairs
+}
+
+/// Nudges overlapping NPCs apart after locomotion has moved (or snapped) them.
+pub fn separate_npc_crowds(
+    sim_clock: Res<SimulationClock>,
+    config: Res<CrowdSeparationConfig>,
+    mut npcs: Query<(&mut Transform, &NpcLocomotion, Option<&InConversation>)>,
+) {
+    let delta_seconds = sim_clock.last_scaled_delta().as_secs_f32();
+    if delta_seconds <= f32::EPSILON {
+        return;
+    }
+
+    let agents: Vec<SeparationAgent> = npcs
+        .iter()
+        .map(|(transform, locomotion, conversation)| SeparationAgent {
+            position: transform.translation.xz(),
+            exempt: conversation.is_some(),
+            anchor: locomotion.arrival_point().map(|point| point.xz()),
+        })
+        .collect();
+    if agents.len() < 2 {
+        return;
+    }
+
+    let offsets = separation_offsets(&agents, &config, delta_seconds);
+    for ((mut transform, _, _), offset) in npcs.iter_mut().zip(offsets) {
+        if offset != Vec2::ZERO {
+            transform.translation.x += offset.x;
+            transform.translation.z += offset.y;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn agent(x: f32, z: f32) -> SeparationAgent {
+        SeparationAgent {
+            position: Vec2::new(x, z),
+            exempt: false,
+            anchor: None,
+        }
+    }
+
+    fn config() -> CrowdSeparationConfig {
+        CrowdSeparationConfig {
+            personal_space_radius: 1.0,
+            max_push_per_second: 10.0,
+            arrival_leash: 0.2,
+        }
+    }
+
+    #[test]
+    fn overlapping_pair_splits_half_the_overlap_each() {
+        let agents = [agent(0.0, 0.0), agent(0.6, 0.0), agent(5.0, 5.0)];
+        let offsets = separation_offsets(&agents, &config(), 0.1);
+
+        assert!((offsets[0] - Vec2::new(-0.2, 0.0)).length() < 1e-5);
+        assert!((offsets[1] - Vec2::new(0.2, 0.0)).length() < 1e-5);
+        assert_eq!(offsets[2], Vec2::ZERO);
+    }
+
+    #[test]
+    fn push_is_capped_per_frame_and_stacked_npcs_still_split() {
+        let agents = [agent(2.0, 2.0), agent(2.0, 2.0)];
+        let mut slow = config();
+        slow.max_push_per_second = 1.0;
+        let offsets = separation_offsets(&agents, &slow, 0.05);
+
+        assert!((offsets[0].length() - 0.05).abs() < 1e-5);
+        assert!((offsets[0] + offsets[1]).length() < 1e-5, "pushes mirror");
+    }
+
+    #[test]
+    fn conversing_npcs_are_exempt() {
+        let mut talking = agent(0.0, 0.0);
+        talking.exempt = true;
+        let agents = [talking, agent(0.3, 0.0), agent(0.3, 0.5)];
+        let offsets = separation_offsets(&agents, &config(), 0.1);
+
+        assert_eq!(offsets[0], Vec2::ZERO);
+        assert!(offsets[1].y < 0.0, "non-exempt pair still separates");
+        assert!(offsets[1].x.abs() < 1e-5, "no push from the conversing NPC");
+    }
+
+    #[test]
+    fn arrival_leash_keeps_npcs_near_their_arrival_point() {
+        let mut first = agent(0.0, 0.0);
+        first.anchor = Some(Vec2::ZERO);
+        let agents = [first, agent(0.1, 0.0)];
+        let mut offsets = Vec::new();
+        let mut positions = agents;
+        for _ in 0..20 {
+            offsets = separation_offsets(&positions, &config(), 0.1);
+            for (agent, offset) in positions.iter_mut().zip(&offsets) {
+                agent.position += *offset;
+            }
+        }
+
+        assert!(positions[0].position.length() <= 0.2 + 1e-5);
+        assert!(
+            positions[1].position.x > 0.7,
+            "unleashed NPC keeps its space"
+        );
+        assert!(offsets[0].length() < 1e-5);
+    }
+
+    #[test]
+    fn grid_finds_pairs_across_cell_boundaries() {
+        let agents = [agent(-0.1, 0.0), agent(0.1, 0.0), agent(0.95, 0.0)];
+        assert_eq!(overlapping_pairs(&agents, 1.0), vec![(0, 1), (1, 2)]);
+    }
+}
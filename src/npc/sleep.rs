@@ -0,0 +1,292 @@
+//! Night-time rest: after sunset NPCs walk home and sleep until sunrise, regaining
+//! motivation instead of losing it.
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+
+use crate::{
+    economy::tasks::{ActorTask, ActorTaskQueues},
+    npc::{
+        components::{
+            Identity, InConversation, MovementTarget, NpcId, NpcLocomotion, ScheduleState,
+        },
+        events::NpcActivityChangedEvent,
+        patrol::PatrolState,
+    },
+    world::time::{WorldClock, WorldTimeSettings},
+};
+
+const HOME_LABEL: &str = "home";
+const WAKE_ACTIVITY: &str = "Waking up";
+
+/// Where an NPC sleeps: their household's home, or their spawn point without one.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct HomePosition(pub Vec3);
+
+/// Walking home for the night; economy tasks wait until sunrise.
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct HeadingHome;
+
+/// Asleep at home. Excluded from chatter, player interaction, and economy tasks.
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct Sleeping;
+
+/// Authoritative set of sleeping NPCs, for lookups by id. Updated immediately, unlike the
+/// `Sleeping` marker, which waits on Commands.
+#[derive(Resource, Debug, Default)]
+pub struct SleepRoster {
+    asleep: HashSet<NpcId>,
+}
+
+impl SleepRoster {
+    pub fn is_asleep(&self, npc: NpcId) -> bool {
+        self.asleep.contains(&npc)
+    }
+
+    fn fall_asleep(&mut self, npc: NpcId) {
+        self.asleep.insert(npc);
+    }
+
+    fn wake(&mut self, npc: NpcId) {
+        self.asleep.remove(&npc);
+    }
+}
+
+/// True between sunset and sunrise. Handles a night that wraps past midnight (the usual
+/// case) as well as settings where sunrise comes after sunset within the same day.
+pub fn is_night(time_of_day: f32, sunrise_fraction: f32, sunset_fraction: f32) -> bool {
+    if sunrise_fraction <= sunset_fraction {
+        time_of_day >= sunset_fraction || time_of_day < sunrise_fraction
+    } else {
+        time_of_day >= sunset_fraction && time_of_day < sunrise_fraction
+    }
+}
+
+/// A delivery someone is waiting on keeps its courier, and a recipient called to the
+/// marketplace, up until it is handed over.
+pub(crate) fn has_critical_task(task_queues: &ActorTaskQueues, npc: NpcId) -> bool {
+    task_queues
+        .peek(npc)
+        .is_some_and(ActorTask::is_market_bound)
+}
+
+/// Sends NPCs home after sunset, puts them to sleep on arrival, and wakes everyone at
+/// sunrise. NPCs mid-conversation or carrying a delivery set off once they are free, and
+/// NPCs on patrol duty once it ends.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn update_night_rest(
+    mut commands: Commands,
+    clock: Res<WorldClock>,
+    settings: Res<WorldTimeSettings>,
+    task_queues: Res<ActorTaskQueues>,
+    mut roster: ResMut<SleepRoster>,
+    mut npcs: Query<(
+        Entity,
+        &Identity,
+        &Transform,
+        &HomePosition,
+        &mut NpcLocomotion,
+        Option<&mut ScheduleState>,
+        Option<&PatrolState>,
+        Has<HeadingHome>,
+        Has<Sleeping>,
+        Has<InConversation>,
+    )>,
+    mut activity_events: MessageWriter<NpcActivityChangedEvent>,
+) {
+    let time_of_day = clock.time_of_day();
+    let night = is_night(
+        time_of_day,
+        settings.sunrise_fraction,
+        settings.sunset_fraction,
+    );
+
+    for (
+        entity,
+        identity,
+        transform,
+        home,
+        mut locomotion,
+        schedule_state,
+        patrol,
+        heading_home,
+        sleeping,
+        in_conversation,
+    ) in npcs.iter_mut()
+    {
+        if !night {
+            if heading_home {
+                commands.entity(entity).remove::<HeadingHome>();
+                locomotion.clear_target();
+            }
+            if sleeping {
+                commands.entity(entity).remove::<Sleeping>();
+                roster.wake(identity.id);
+                if let Some(mut state) = schedule_state {
+                    state.current_activity.clear();
+                }
+                info!("{} wakes up", identity.display_name);
+                activity_events.write(NpcActivityChangedEvent {
+                    npc: identity.id,
+                    activity: WAKE_ACTIVITY.to_string(),
+                    time_of_day,
+                });
+            }
+            continue;
+        }
+
+        if sleeping {
+            continue;
+        }
+
+        if patrol.is_some_and(PatrolState::is_on_duty) {
+            if heading_home {
+                commands.entity(entity).remove::<HeadingHome>();
+            }
+            continue;
+        }
+
+        if heading_home {
+            let offset = Vec2::new(
+                home.0.x - transform.translation.x,
+                home.0.z - transform.translation.z,
+            );
+            if offset.length() <= locomotion.arrive_distance() {
+                commands
+                    .entity(entity)
+                    .remove::<HeadingHome>()
+                    .insert(Sleeping);
+                roster.fall_asleep(identity.id);
+                info!("{} falls asleep", identity.display_name);
+            } else {
+                locomotion.set_target(
+                    MovementTarget::Position(home.0),
+                    HOME_LABEL,
+                    transform.translation,
+                );
+            }
+            continue;
+        }
+
+        if in_conversation || has_critical_task(&task_queues, identity.id) {
+            continue;
+        }
+        commands.entity(entity).insert(HeadingHome);
+        if locomotion.set_target(
+            MovementTarget::Position(home.0),
+            HOME_LABEL,
+            transform.translation,
+        ) {
+            info!("{} heads home for the night", identity.display_name);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::economy::components::{Profession, TradeGood};
+
+    #[test]
+    fn night_window_wraps_past_midnight() {
+        let (sunrise, sunset) = (0.22, 0.78);
+        assert!(is_night(0.0, sunrise, sunset));
+        assert!(is_night(0.1, sunrise, sunset));
+        assert!(!is_night(0.22, sunrise, sunset), "sunrise is day");
+        assert!(!is_night(0.5, sunrise, sunset));
+        assert!(is_night(0.78, sunrise, sunset), "sunset is night");
+        assert!(is_night(0.99, sunrise, sunset));
+
+        // Sunrise after sunset: the night sits inside the day instead of across midnight.
+        assert!(is_night(0.5, 0.7, 0.3));
+        assert!(!is_night(0.1, 0.7, 0.3));
+        assert!(!is_night(0.9, 0.7, 0.3));
+    }
+
+    fn night_app(time_of_day: f32) -> App {
+        let mut app = App::new();
+        let mut clock = WorldClock::new();
+        clock.set_time_of_day(time_of_day);
+        app.insert_resource(clock)
+            .init_resource::<WorldTimeSettings>()
+            .init_resource::<ActorTaskQueues>()
+            .init_resource::<SleepRoster>()
+            .add_message::<NpcActivityChangedEvent>()
+            .add_systems(Update, update_night_rest);
+        app
+    }
+
+    fn villager(app: &mut App, id: u64, at: Vec3, home: Vec3) -> Entity {
+        app.world_mut()
+            .spawn((
+                Identity::new(NpcId::new(id), "Villager", 30.0),
+                Transform::from_translation(at),
+                HomePosition(home),
+                NpcLocomotion::default(),
+                ScheduleState {
+                    current_activity: "Sleeping".to_string(),
+                },
+            ))
+            .id()
+    }
+
+    #[test]
+    fn npcs_walk_home_sleep_and_wake_at_sunrise() {
+        let mut app = night_app(0.9);
+        let home = Vec3::new(5.0, 1.0, 5.0);
+        let walker = villager(&mut app, 1, Vec3::new(0.0, 1.0, 0.0), home);
+        let courier = villager(&mut app, 2, Vec3::ZERO, home);
+        app.world_mut()
+            .resource_mut::<ActorTaskQueues>()
+            .ensure_queue(NpcId::new(2))
+            .push_back(ActorTask::Deliver {
+                good: TradeGood::Grain,
+                quantity: 1,
+                target: Profession::Miller,
+                recipient: None,
+            });
+
+        app.update();
+        let world = app.world();
+        assert!(world.get::<HeadingHome>(walker).is_some());
+        assert_eq!(
+            world.get::<NpcLocomotion>(walker).unwrap().target(),
+            Some(MovementTarget::Position(home))
+        );
+        assert!(
+            world.get::<HeadingHome>(courier).is_none(),
+            "a pending delivery keeps the courier working"
+        );
+
+        app.world_mut()
+            .get_mut::<Transform>(walker)
+            .unwrap()
+            .translation = home;
+        app.update();
+        assert!(app.world().get::<Sleeping>(walker).is_some());
+        assert!(app
+            .world()
+            .resource::<SleepRoster>()
+            .is_asleep(NpcId::new(1)));
+
+        app.world_mut()
+            .resource_mut::<WorldClock>()
+            .set_time_of_day(0.3);
+        app.update();
+        let world = app.world();
+        assert!(world.get::<Sleeping>(walker).is_none());
+        assert!(!world.resource::<SleepRoster>().is_asleep(NpcId::new(1)));
+        assert!(world
+            .get::<ScheduleState>(walker)
+            .unwrap()
+            .current_activity
+            .is_empty());
+        let messages = world.resource::<Messages<NpcActivityChangedEvent>>();
+        let woken: Vec<_> = messages
+            .get_cursor()
+            .read(messages)
+            .map(|event| (event.npc, event.activity.clone()))
+            .collect();
+        assert_eq!(woken, [(NpcId::new(1), WAKE_ACTIVITY.to_string())]);
+    }
+}
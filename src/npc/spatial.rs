@@ -0,0 +1,422 @@
+//! Shared lookups for systems that ask "who is near here" or "which entity is this NPC".
+//! `SpatialIndex` buckets every NPC into a uniform grid on the XZ plane, rebuilt once at the
+//! start of each frame, so proximity queries only visit nearby cells. `NpcIndex` maps each
+//! `NpcId` to its entity as NPCs spawn and despawn, replacing linear scans over identities.
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::npc::components::{Identity, NpcId};
+
+/// Grid cell edge in world units; a few times the interaction range, so common queries
+/// touch at most a 3x3 block of cells.
+pub const DEFAULT_CELL_SIZE: f32 = 4.0;
+
+/// Items bucketed by their XZ cell. Distances are measured in 3D, like the scans it
+/// replaces; the grid only narrows down which items to measure. Non-finite positions are
+/// not stored.
+#[derive(Debug, Clone)]
+pub struct SpatialGrid<T> {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<(T, Vec3)>>,
+    /// Smallest and largest occupied cell on each axis, bounding every search.
+    bounds: Option<((i32, i32), (i32, i32))>,
+}
+
+impl<T: Copy> SpatialGrid<T> {
+    /// An empty grid; cell sizes that are not positive and finite use `DEFAULT_CELL_SIZE`.
+    pub fn new(cell_size: f32) -> Self {
+        let cell_size = if cell_size.is_finite() && cell_size > 0.0 {
+            cell_size
+        } else {
+            DEFAULT_CELL_SIZE
+        };
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+            bounds: None,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.bounds = None;
+    }
+
+    pub fn insert(&mut self, item: T, position: Vec3) {
+        if !position.is_finite() {
+            return;
+        }
+        let cell = self.cell_of(position);
+        self.cells.entry(cell).or_default().push((item, position));
+        self.bounds = Some(match self.bounds {
+            None => (cell, cell),
+            Some((min, max)) => (
+                (min.0.min(cell.0), min.1.min(cell.1)),
+                (max.0.max(cell.0), max.1.max(cell.1)),
+            ),
+        });
+    }
+
+    /// Items within `radius` of `position`, nearest first.
+    pub fn within(&self, position: Vec3, radius: f32) -> Vec<(T, f32)> {
+        let Some((min, max)) = self.bounds else {
+            return Vec::new();
+        };
+        if !position.is_finite() || radius.is_nan() || radius < 0.0 {
+            return Vec::new();
+        }
+        let radius = radius.min(f32::MAX);
+        let low = self.cell_of(position - Vec3::new(radius, 0.0, radius));
+        let high = self.cell_of(position + Vec3::new(radius, 0.0, radius));
+
+        let mut found = Vec::new();
+        for x in low.0.max(min.0)..=high.0.min(max.0) {
+            for z in low.1.max(min.1)..=high.1.min(max.1) {
+                for &(item, at) in self.cells.get(&(x, z)).into_iter().flatten() {
+                    let distance = at.distance(position);
+                    if distance <= radius {
+                        found.push((item, distance));
+                    }
+                }
+            }
+        }
+        found.sort_by(|a, b| a.1.total_cmp(&b.1));
+        found
+    }
+
+    /// The nearest item `filter` accepts, searching outwards ring by ring and stopping once
+    /// no unvisited cell could hold anything closer. Ties keep the first item found.
+    pub fn nearest(&self, position: Vec3, mut filter: impl FnMut(&T) -> bool) -> Option<(T, f32)> {
+        let (min, max) = self.bounds?;
+        if !position.is_finite() {
+            return None;
+        }
+        let centre = self.cell_of(position);
+        // Rings closer than the occupied bounds hold nothing.
+        let first_ring = [
+            min.0 - centre.0,
+            centre.0 - max.0,
+            min.1 - centre.1,
+            centre.1 - max.1,
+        ]
+        .into_iter()
+        .max()
+        .unwrap_or(0)
+        .max(0);
+        let last_ring = [
+            centre.0 - min.0,
+            max.0 - centre.0,
+            centre.1 - min.1,
+            max.1 - centre.1,
+        ]
+        .into_iter()
+        .max()
+        .unwrap_or(0)
+        .max(0);
+
+        let mut best: Option<(T, f32)> = None;
+        for ring in first_ring..=last_ring {
+            for cell in ring_cells(centre, ring) {
+                for &(item, at) in self.cells.get(&cell).into_iter().flatten() {
+                    let distance = at.distance(position);
+                    if best.is_some_and(|(_, nearest)| nearest <= distance) || !filter(&item) {
+                        continue;
+                    }
+                    best = Some((item, distance));
+                }
+            }
+            // Anything in a later ring is more than `ring` cells away on some axis.
+            if best.is_some_and(|(_, nearest)| nearest <= ring as f32 * self.cell_size) {
+                break;
+            }
+        }
+        best
+    }
+
+    fn cell_of(&self, position: Vec3) -> (i32, i32) {
+        (
+            (position.x / self.cell_size).floor() as i32,
+            (position.z / self.cell_size).floor() as i32,
+        )
+    }
+}
+
+/// Cells exactly `ring` steps (Chebyshev) from `centre`: the top and bottom rows, then
+/// the sides between them.
+fn ring_cells(centre: (i32, i32), ring: i32) -> impl Iterator<Item = (i32, i32)> {
+    let (cx, cz) = centre;
+    let rows = (-ring..=ring).flat_map(move |dx| {
+        let bottom = (ring > 0).then_some((cx + dx, cz + ring));
+        std::iter::once((cx + dx, cz - ring)).chain(bottom)
+    });
+    let sides = (1 - ring..ring).flat_map(move |dz| [(cx - ring, cz + dz), (cx + ring, cz + dz)]);
+    rows.chain(sides)
+}
+
+/// An NPC found by a `SpatialIndex` query.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NpcNeighbor {
+    pub entity: Entity,
+    pub npc: NpcId,
+    pub distance: f32,
+}
+
+/// Every NPC's position as of the start of this frame, for proximity queries. Systems
+/// that move NPCs later in the frame still see where they stood when it began.
+#[derive(Resource, Debug, Clone)]
+pub struct SpatialIndex {
+    grid: SpatialGrid<(Entity, NpcId)>,
+}
+
+impl Default for SpatialIndex {
+    fn default() -> Self {
+        Self {
+            grid: SpatialGrid::new(DEFAULT_CELL_SIZE),
+        }
+    }
+}
+
+impl SpatialIndex {
+    /// Replaces the index with `npcs`.
+    pub fn rebuild(&mut self, npcs: impl IntoIterator<Item = (Entity, NpcId, Vec3)>) {
+        self.grid.clear();
+        for (entity, npc, position) in npcs {
+            self.grid.insert((entity, npc), position);
+        }
+    }
+
+    /// NPCs within `radius` of `position`, nearest first.
+    pub fn neighbors_within(&self, position: Vec3, radius: f32) -> Vec<NpcNeighbor> {
+        self.grid
+            .within(position, radius)
+            .into_iter()
+            .map(|((entity, npc), distance)| NpcNeighbor {
+                entity,
+                npc,
+                distance,
+            })
+            .collect()
+    }
+
+    /// The nearest NPC `filter` accepts, at any distance.
+    pub fn nearest(
+        &self,
+        position: Vec3,
+        mut filter: impl FnMut(Entity, NpcId) -> bool,
+    ) -> Option<NpcNeighbor> {
+        self.grid
+            .nearest(position, |(entity, npc)| filter(*entity, *npc))
+            .map(|((entity, npc), distance)| NpcNeighbor {
+                entity,
+                npc,
+                distance,
+            })
+    }
+}
+
+/// Rebuilds `SpatialIndex` from every NPC's transform.
+pub fn rebuild_spatial_index(
+    mut index: ResMut<SpatialIndex>,
+    npcs: Query<(Entity, &Identity, &Transform)>,
+) {
+    index.rebuild(
+        npcs.iter()
+            .map(|(entity, identity, transform)| (entity, identity.id, transform.translation)),
+    );
+}
+
+/// The entity of each NPC, by id.
+#[derive(Resource, Debug, Default)]
+pub struct NpcIndex {
+    entities: HashMap<NpcId, Entity>,
+    /// The id each indexed entity was last seen with, so despawns and id changes touch
+    /// only their own entries.
+    ids: HashMap<Entity, NpcId>,
+}
+
+impl NpcIndex {
+    pub fn entity(&self, npc: NpcId) -> Option<Entity> {
+        self.entities.get(&npc).copied()
+    }
+
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    #[cfg(test)]
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    /// Drops `npc` unless it has already moved on to another entity.
+    fn forget(&mut self, npc: NpcId, entity: Entity) {
+        if self.entities.get(&npc) == Some(&entity) {
+            self.entities.remove(&npc);
+        }
+    }
+}
+
+/// Keeps `NpcIndex` in step with spawned, changed and despawned identities. An id that
+/// moves to a new entity (a respawn) points at the new one.
+pub fn index_npcs(
+    mut index: ResMut<NpcIndex>,
+    changed: Query<(Entity, &Identity), Changed<Identity>>,
+    mut removed: RemovedComponents<Identity>,
+) {
+    for entity in removed.read() {
+        if let Some(npc) = index.ids.remove(&entity) {
+            index.forget(npc, entity);
+        }
+    }
+    for (entity, identity) in changed.iter() {
+        if let Some(previous) = index.ids.insert(entity, identity.id) {
+            if previous != identity.id {
+                index.forget(previous, entity);
+            }
+        }
+        index.entities.insert(identity.id, entity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::economy::rng::DailyRng;
+
+    /// Items within `radius`, measured against every item.
+    fn brute_force_within(points: &[Vec3], position: Vec3, radius: f32) -> Vec<(usize, f32)> {
+        let mut found: Vec<(usize, f32)> = points
+            .iter()
+            .enumerate()
+            .map(|(index, point)| (index, point.distance(position)))
+            .filter(|(_, distance)| *distance <= radius)
+            .collect();
+        found.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+        found
+    }
+
+    fn random_point(rng: &mut DailyRng, extent: f32) -> Vec3 {
+        Vec3::new(
+            (rng.next_f32() * 2.0 - 1.0) * extent,
+            rng.next_f32() * 2.0,
+            (rng.next_f32() * 2.0 - 1.0) * extent,
+        )
+    }
+
+    #[test]
+    fn grid_queries_match_a_brute_force_scan_on_random_villages() {
+        for seed in 0..20 {
+            let mut rng = DailyRng::for_day(seed, 0, 0);
+            let points: Vec<Vec3> = (0..150).map(|_| random_point(&mut rng, 40.0)).collect();
+            let cell_size = 0.5 + rng.next_f32() * 6.0;
+            let mut grid = SpatialGrid::new(cell_size);
+            for (index, point) in points.iter().enumerate() {
+                grid.insert(index, *point);
+            }
+
+            for _ in 0..25 {
+                let position = random_point(&mut rng, 50.0);
+                let radius = rng.next_f32() * 15.0;
+                let mut within = grid.within(position, radius);
+                within.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+                assert_eq!(
+                    within,
+                    brute_force_within(&points, position, radius),
+                    "seed {seed}, cell {cell_size}, radius {radius} at {position}"
+                );
+
+                let even = |index: &usize| index.is_multiple_of(2);
+                let nearest = grid.nearest(position, even).map(|(_, distance)| distance);
+                let expected = points
+                    .iter()
+                    .enumerate()
+                    .filter(|(index, _)| even(index))
+                    .map(|(_, point)| point.distance(position))
+                    .min_by(f32::total_cmp);
+                assert_eq!(nearest, expected, "seed {seed} at {position}");
+            }
+        }
+    }
+
+    #[test]
+    fn empty_and_degenerate_queries_find_nothing() {
+        let mut grid = SpatialGrid::new(0.0);
+        assert!(grid.within(Vec3::ZERO, 5.0).is_empty());
+        assert_eq!(grid.nearest(Vec3::ZERO, |_: &u32| true), None);
+
+        grid.insert(1, Vec3::new(1.0, 0.0, 0.0));
+        grid.insert(2, Vec3::splat(f32::NAN));
+        assert_eq!(grid.within(Vec3::ZERO, 5.0), [(1, 1.0)]);
+        assert!(grid.within(Vec3::ZERO, -1.0).is_empty());
+        assert!(grid.within(Vec3::splat(f32::INFINITY), 5.0).is_empty());
+        assert_eq!(grid.within(Vec3::ZERO, f32::INFINITY), [(1, 1.0)]);
+        assert_eq!(
+            grid.nearest(Vec3::new(-90.0, 0.0, 30.0), |_| true)
+                .map(|(item, _)| item),
+            Some(1)
+        );
+        assert_eq!(grid.nearest(Vec3::ZERO, |item| *item != 1), None);
+    }
+
+    #[test]
+    fn npc_index_follows_spawns_respawns_and_despawns() {
+        let mut app = App::new();
+        app.init_resource::<NpcIndex>()
+            .init_resource::<SpatialIndex>()
+            .add_systems(Update, (index_npcs, rebuild_spatial_index));
+        let alric = NpcId::new(1);
+        let bryn = NpcId::new(2);
+        let first = app
+            .world_mut()
+            .spawn((Identity::new(alric, "Alric", 30.0), Transform::default()))
+            .id();
+        let second = app
+            .world_mut()
+            .spawn((
+                Identity::new(bryn, "Bryn", 30.0),
+                Transform::from_xyz(2.0, 0.0, 0.0),
+            ))
+            .id();
+        app.update();
+        let index = app.world().resource::<NpcIndex>();
+        assert_eq!(index.entity(alric), Some(first));
+        assert_eq!(index.entity(bryn), Some(second));
+
+        let neighbors = app
+            .world()
+            .resource::<SpatialIndex>()
+            .neighbors_within(Vec3::new(1.5, 0.0, 0.0), 1.0);
+        assert_eq!(
+            neighbors,
+            [NpcNeighbor {
+                entity: second,
+                npc: bryn,
+                distance: 0.5
+            }]
+        );
+
+        app.world_mut().entity_mut(first).despawn();
+        app.world_mut().entity_mut(second).despawn();
+        let respawned = app
+            .world_mut()
+            .spawn((Identity::new(bryn, "Bryn", 30.0), Transform::default()))
+            .id();
+        app.update();
+        let index = app.world().resource::<NpcIndex>();
+        assert_eq!(index.entity(alric), None);
+        assert_eq!(index.entity(bryn), Some(respawned));
+        assert_eq!(index.len(), 1);
+
+        app.world_mut().get_mut::<Identity>(respawned).unwrap().id = alric;
+        app.update();
+        let index = app.world().resource::<NpcIndex>();
+        assert_eq!(index.entity(bryn), None, "the old id is released");
+        assert_eq!(index.entity(alric), Some(respawned));
+
+        app.world_mut().entity_mut(respawned).despawn();
+        app.update();
+        assert!(app.world().resource::<NpcIndex>().is_empty());
+    }
+}
@@ -0,0 +1,378 @@
+use bevy::prelude::*;
+
+use crate::{
+    core::plugin::SimulationClock,
+    dialogue::events::{DialogueRequestFailedEvent, DialogueRequestedEvent},
+    npc::{
+        components::{
+            ActiveConversations, ConversationSettings, ConversationState, Identity, InConversation,
+            NpcId,
+        },
+        facing::{yaw_toward, DesiredFacing},
+        spatial::NpcIndex,
+    },
+};
+
+/// Points NPCs' conversation facing at their partner once they have stopped to talk.
+/// Handles both NPC-to-NPC and NPC-to-Player conversations; `apply_npc_facing` does the
+/// actual turning, with this taking precedence over travel and work facing.
+pub fn orient_conversing_npcs(
+    npc_index: Res<NpcIndex>,
+    player_query: Query<Entity, With<crate::player::components::Player>>,
+    positions: Query<&Transform>,
+    mut npcs: Query<(
+        &Identity,
+        &Transform,
+        Option<&InConversation>,
+        &mut DesiredFacing,
+    )>,
+) {
+    for (identity, transform, conversation, mut facing) in npcs.iter_mut() {
+        // Only orient when stopped (not while approaching)
+        let Some(conversation) =
+            conversation.filter(|conv| conv.state != ConversationState::Approaching)
+        else {
+            facing.conversation = None;
+            continue;
+        };
+
+        let partner_entity = if conversation.partner.is_player() {
+            match player_query.single() {
+                Ok(player_entity) => Some(player_entity),
+                Err(_) => {
+                    warn!(
+                        "{} in conversation with player but player not found",
+                        identity.display_name
+                    );
+                    None
+                }
+            }
+        } else {
+            let npc_entity = npc_index.entity(conversation.partner);
+            if npc_entity.is_none() {
+                warn!(
+                    "{} in conversation but partner {} not found",
+                    identity.display_name, conversation.partner
+                );
+            }
+            npc_entity
+        };
+
+        // Y-axis only; a partner standing on top of us keeps the previous facing.
+        facing.conversation = partner_entity
+            .and_then(|partner| positions.get(partner).ok())
+            .and_then(|partner| {
+                yaw_toward(Vec2::new(
+                    partner.translation.x - transform.translation.x,
+                    partner.translation.z - transform.translation.z,
+                ))
+            })
+            .or(facing.conversation);
+    }
+}
+
+/// Starts conversations by adding InConversation components when dialogue is requested.
+/// Handles both NPC-to-NPC and NPC-to-Player conversations. Participants are reserved in
+/// `ActiveConversations` first; a request whose speaker or target is already talking is
+/// skipped so nobody is double-booked.
+#[allow(clippy::too_many_arguments)]
+pub fn start_conversations(
+    mut commands: Commands,
+    mut events: MessageReader<DialogueRequestedEvent>,
+    time: Res<Time>,
+    sim_clock: Res<SimulationClock>,
+    settings: Res<ConversationSettings>,
+    mut active: ResMut<ActiveConversations>,
+    npc_index: Res<NpcIndex>,
+    npcs: Query<(), With<Identity>>,
+) {
+    let find_npc = |npc: NpcId| {
+        npc_index
+            .entity(npc)
+            .filter(|&entity| npcs.contains(entity))
+    };
+    for event in events.read() {
+        let Some(target) = event.target else {
+            continue; // No conversation if no target
+        };
+        if event.speaker.is_player() {
+            continue; // The player is not an entity we can hold in place
+        }
+
+        // Find speaker entity (always an NPC)
+        let Some(speaker_entity) = find_npc(event.speaker) else {
+            warn!("Speaker {} not found for conversation", event.speaker);
+            continue;
+        };
+
+        let current_time = settings
+            .basis_for(target)
+            .elapsed(&time, &sim_clock)
+            .as_secs_f32();
+
+        let participants = if target.is_player() {
+            vec![event.speaker]
+        } else {
+            vec![event.speaker, target]
+        };
+        if !active.try_reserve(event.request_id, &participants) {
+            debug!(
+                "Skipping conversation {} -> {} ({}): a participant is already talking",
+                event.speaker, target, event.request_id
+            );
+            continue;
+        }
+
+        // Check if target is the player (special case)
+        if target.is_player() {
+            // Player interaction - only add InConversation to the NPC speaker
+            commands.entity(speaker_entity).insert(InConversation::new(
+                target,
+                event.request_id,
+                current_time,
+                ConversationState::WaitingAtDestination,
+            ));
+
+            info!(
+                "Started player conversation: {} -> player ({})",
+                event.speaker, event.request_id
+            );
+        } else {
+            // NPC-to-NPC conversation - add InConversation to both
+            let Some(target_entity) = find_npc(target) else {
+                warn!("Target {} not found for conversation", target);
+                active.release_request(event.request_id);
+                continue;
+            };
+
+            commands.entity(speaker_entity).insert(InConversation::new(
+                target,
+                event.request_id,
+                current_time,
+                ConversationState::WaitingAtDestination,
+            ));
+
+            commands.entity(target_entity).insert(InConversation::new(
+                event.speaker,
+                event.request_id,
+                current_time,
+                ConversationState::WaitingAtDestination,
+            ));
+
+            info!(
+                "Started conversation: {} <-> {} ({})",
+                event.speaker, target, event.request_id
+            );
+        }
+    }
+}
+
+/// Cleans up conversations after a timeout period, measured on the clock
+/// `ConversationSettings` picks for the partner.
+/// This removes InConversation components so NPCs can resume their tasks.
+pub fn cleanup_conversations(
+    mut commands: Commands,
+    time: Res<Time>,
+    sim_clock: Res<SimulationClock>,
+    settings: Res<ConversationSettings>,
+    mut active: ResMut<ActiveConversations>,
+    conversing: Query<(Entity, &Identity, &InConversation)>,
+) {
+    for (entity, identity, conversation) in conversing.iter() {
+        let now = settings
+            .basis_for(conversation.partner)
+            .elapsed(&time, &sim_clock)
+            .as_secs_f32();
+        let elapsed = now - conversation.started_at;
+        if elapsed >= settings.timeout_seconds {
+            commands.entity(entity).remove::<InConversation>();
+            active.release(identity.id, conversation.request_id);
+            info!(
+                "{} conversation ended (elapsed: {:.1}s), resuming activity",
+                identity.display_name, elapsed
+            );
+        }
+    }
+}
+
+/// Ends conversations whose dialogue request failed, so both participants are free again.
+pub fn release_failed_conversations(
+    mut commands: Commands,
+    mut failures: MessageReader<DialogueRequestFailedEvent>,
+    mut active: ResMut<ActiveConversations>,
+    conversing: Query<(Entity, &InConversation)>,
+) {
+    for failure in failures.read() {
+        let request = failure.error.request_id;
+        active.release_request(request);
+        for (entity, conversation) in conversing.iter() {
+            if conversation.request_id == request {
+                commands.entity(entity).remove::<InConversation>();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::super::tests::{conversation_app, request};
+    use super::*;
+    use crate::{
+        core::plugin::SimulationClock,
+        dialogue::{
+            broker::DialogueProviderKind,
+            errors::{DialogueError, DialogueErrorKind},
+            events::DialogueRequestFailedEvent,
+            queue::{announce_queued_dialogue_requests, DialogueRequestQueue},
+            types::{DialogueRequest, DialogueRequestId, DialogueTopicHint},
+        },
+        npc::components::{
+            ActiveConversations, ConversationSettings, ConversationState, Identity, InConversation,
+            NpcId,
+        },
+    };
+
+    fn enqueue(app: &mut App, speaker: NpcId, target: Option<NpcId>) -> DialogueRequestId {
+        let mut builder = DialogueRequest::builder(speaker)
+            .topic(DialogueTopicHint::Status)
+            .prompt("Morning.");
+        if let Some(target) = target {
+            builder = builder.target(target);
+        }
+        builder
+            .enqueue(&mut app.world_mut().resource_mut::<DialogueRequestQueue>())
+            .unwrap()
+    }
+
+    fn conversations(app: &mut App) -> Vec<(NpcId, u64)> {
+        let mut pairs: Vec<_> = app
+            .world_mut()
+            .query::<(&Identity, &InConversation)>()
+            .iter(app.world())
+            .map(|(identity, conversation)| (identity.id, conversation.request_id.value()))
+            .collect();
+        pairs.sort();
+        pairs
+    }
+
+    #[test]
+    fn simultaneous_requests_sharing_a_participant_start_one_conversation() {
+        let mut app = conversation_app();
+        request(&mut app, 10, 1, 2);
+        request(&mut app, 11, 3, 2);
+        app.update();
+
+        assert_eq!(
+            conversations(&mut app),
+            vec![(NpcId::new(1), 10), (NpcId::new(2), 10)]
+        );
+        let active = app.world().resource::<ActiveConversations>();
+        assert!(active.is_in_conversation(NpcId::new(2)));
+        assert!(!active.is_in_conversation(NpcId::new(3)));
+    }
+
+    #[test]
+    fn queued_targeted_requests_start_conversations() {
+        let mut app = conversation_app();
+        app.init_resource::<DialogueRequestQueue>().add_systems(
+            Update,
+            announce_queued_dialogue_requests.before(start_conversations),
+        );
+        let chat = enqueue(&mut app, NpcId::new(1), Some(NpcId::new(2)));
+        enqueue(&mut app, NpcId::new(3), None);
+        enqueue(&mut app, NpcId::player(), Some(NpcId::new(3)));
+        app.update();
+
+        let chat = chat.value();
+        assert_eq!(
+            conversations(&mut app),
+            vec![(NpcId::new(1), chat), (NpcId::new(2), chat)]
+        );
+
+        let greeting = enqueue(&mut app, NpcId::new(3), Some(NpcId::player()));
+        app.update();
+        assert_eq!(
+            conversations(&mut app),
+            vec![
+                (NpcId::new(1), chat),
+                (NpcId::new(2), chat),
+                (NpcId::new(3), greeting.value())
+            ],
+            "only the NPC side of a player conversation is held"
+        );
+    }
+
+    #[test]
+    fn failed_requests_release_their_participants() {
+        let mut app = conversation_app();
+        request(&mut app, 10, 1, 2);
+        app.update();
+        assert_eq!(conversations(&mut app).len(), 2);
+
+        app.world_mut().write_message(DialogueRequestFailedEvent {
+            error: DialogueError::new(
+                DialogueRequestId::new(10),
+                DialogueProviderKind::OpenAi,
+                DialogueErrorKind::provider_failure("boom"),
+            ),
+            speaker: NpcId::new(1),
+            target: Some(NpcId::new(2)),
+        });
+        app.update();
+
+        assert!(conversations(&mut app).is_empty());
+        let active = app.world().resource::<ActiveConversations>();
+        assert!(!active.is_in_conversation(NpcId::new(1)));
+        assert!(!active.is_in_conversation(NpcId::new(2)));
+
+        // Freed participants can be booked again.
+        request(&mut app, 12, 2, 3);
+        app.update();
+        assert_eq!(
+            conversations(&mut app),
+            vec![(NpcId::new(2), 12), (NpcId::new(3), 12)]
+        );
+    }
+
+    #[test]
+    fn conversation_timeout_survives_frame_spike() {
+        let mut app = App::new();
+        app.insert_resource(SimulationClock::new(1.0))
+            .init_resource::<Time>()
+            .init_resource::<ConversationSettings>()
+            .init_resource::<ActiveConversations>()
+            .add_systems(Update, cleanup_conversations);
+        let npc = app
+            .world_mut()
+            .spawn((
+                Identity::new(NpcId::new(1), "Bryn", 30.0),
+                InConversation::new(
+                    NpcId::new(2),
+                    DialogueRequestId::new(1),
+                    0.0,
+                    ConversationState::WaitingAtDestination,
+                ),
+            ))
+            .id();
+
+        let tick = |app: &mut App, seconds: f32| {
+            app.world_mut()
+                .resource_mut::<SimulationClock>()
+                .tick(Duration::from_secs_f32(seconds));
+            app.update();
+        };
+
+        tick(&mut app, 10.0);
+        assert!(
+            app.world().get::<InConversation>(npc).is_some(),
+            "a suspended frame must not expire the conversation"
+        );
+
+        for _ in 0..40 {
+            tick(&mut app, 0.2);
+        }
+        assert!(app.world().get::<InConversation>(npc).is_none());
+    }
+}
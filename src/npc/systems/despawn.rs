@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::npc::{
+    components::{ActiveConversations, Identity, NpcId},
+    events::NpcDespawnedEvent,
+};
+
+/// Despawns an NPC and announces it with `NpcDespawnedEvent` in the same frame. The chaos
+/// respawn fault is the only runtime caller; a despawn path added later should use it too.
+#[cfg(any(test, feature = "chaos"))]
+pub fn despawn_npc(commands: &mut Commands, entity: Entity, npc_id: NpcId) {
+    commands.entity(entity).despawn();
+    commands.write_message(NpcDespawnedEvent { entity, npc_id });
+}
+
+/// Safety net for NPCs despawned without `despawn_npc`: announces every entity that lost
+/// its `Identity` and was not already announced.
+pub fn announce_removed_npcs(
+    added: Query<(Entity, &Identity), Added<Identity>>,
+    mut removed: RemovedComponents<Identity>,
+    mut known: Local<HashMap<Entity, NpcId>>,
+    mut despawned: ParamSet<(
+        MessageReader<NpcDespawnedEvent>,
+        MessageWriter<NpcDespawnedEvent>,
+    )>,
+) {
+    for (entity, identity) in added.iter() {
+        known.insert(entity, identity.id);
+    }
+    for event in despawned.p0().read() {
+        known.remove(&event.entity);
+    }
+
+    let unannounced: Vec<NpcDespawnedEvent> = removed
+        .read()
+        .filter_map(|entity| {
+            let npc_id = known.remove(&entity)?;
+            debug!("{npc_id} was despawned without despawn_npc; announcing it");
+            Some(NpcDespawnedEvent { entity, npc_id })
+        })
+        .collect();
+    despawned.p1().write_batch(unannounced);
+}
+
+/// Drops despawned NPCs from conversation reservations.
+pub fn forget_despawned_npcs(
+    mut despawned: MessageReader<NpcDespawnedEvent>,
+    mut active: ResMut<ActiveConversations>,
+) {
+    for event in despawned.read() {
+        active.forget(event.npc_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{conversation_app, request};
+    use super::*;
+    use crate::npc::{
+        components::{ActiveConversations, Identity, NpcId},
+        events::NpcDespawnedEvent,
+    };
+
+    #[test]
+    fn despawned_npcs_are_announced_once_and_leave_their_conversations() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        let mut app = conversation_app();
+        app.add_message::<NpcDespawnedEvent>().add_systems(
+            Update,
+            (announce_removed_npcs, forget_despawned_npcs).chain(),
+        );
+        request(&mut app, 10, 1, 2);
+        app.update();
+        let entity_of = |app: &mut App, id: u64| {
+            app.world_mut()
+                .query::<(Entity, &Identity)>()
+                .iter(app.world())
+                .find(|(_, identity)| identity.id == NpcId::new(id))
+                .map(|(entity, _)| entity)
+                .unwrap()
+        };
+        let (helper, stray) = (entity_of(&mut app, 1), entity_of(&mut app, 2));
+        let mut cursor = app
+            .world()
+            .resource::<Messages<NpcDespawnedEvent>>()
+            .get_cursor();
+
+        app.world_mut()
+            .run_system_once(move |mut commands: Commands| {
+                despawn_npc(&mut commands, helper, NpcId::new(1))
+            })
+            .unwrap();
+        app.world_mut().despawn(stray);
+        app.update();
+
+        let announced: Vec<NpcDespawnedEvent> = cursor
+            .read(app.world().resource::<Messages<NpcDespawnedEvent>>())
+            .copied()
+            .collect();
+        assert_eq!(
+            announced,
+            vec![
+                NpcDespawnedEvent {
+                    entity: helper,
+                    npc_id: NpcId::new(1),
+                },
+                NpcDespawnedEvent {
+                    entity: stray,
+                    npc_id: NpcId::new(2),
+                },
+            ],
+            "the safety net only announces the NPC despawned without the helper"
+        );
+        let active = app.world().resource::<ActiveConversations>();
+        assert!(!active.is_in_conversation(NpcId::new(1)));
+        assert!(!active.is_in_conversation(NpcId::new(2)));
+    }
+}
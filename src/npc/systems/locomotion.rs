@@ -0,0 +1,263 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+
+use crate::{
+    core::plugin::SimulationClock,
+    npc::{
+        components::{
+            ConversationState, Identity, InConversation, LocomotionState, MovementTarget,
+            NpcLocomotion, SpeedModifiers,
+        },
+        facing::{yaw_toward, DesiredFacing},
+    },
+    world::collision::{arrival_distance, MoverCollider, StaticCollider},
+};
+
+/// Moves NPCs toward their active destinations using the simulation clock delta, and
+/// records the travel direction as their desired facing. Destinations with a
+/// `StaticCollider` count as reached once the NPC stands beside them; the NPC stays where
+/// it stopped instead of snapping into the prop. Walking speed includes any
+/// `SpeedModifiers`.
+#[allow(clippy::type_complexity)]
+pub fn drive_npc_locomotion(
+    sim_clock: Res<SimulationClock>,
+    mut movers: Query<(
+        Entity,
+        &Identity,
+        &mut Transform,
+        &mut NpcLocomotion,
+        Option<&InConversation>,
+        Option<&mut DesiredFacing>,
+        Option<&MoverCollider>,
+        Option<&SpeedModifiers>,
+    )>,
+    world_transforms: Query<(&GlobalTransform, Option<&StaticCollider>)>,
+    mut warned_non_finite: Local<HashSet<Entity>>,
+) {
+    #[cfg(feature = "profiling")]
+    let _span = info_span!("drive_npc_locomotion").entered();
+    let delta_seconds = sim_clock.last_scaled_delta().as_secs_f32();
+    if delta_seconds <= f32::EPSILON {
+        return;
+    }
+
+    for (
+        entity,
+        identity,
+        mut transform,
+        mut locomotion,
+        conversation,
+        mut facing,
+        body,
+        modifiers,
+    ) in movers.iter_mut()
+    {
+        if let Some(facing) = facing.as_deref_mut() {
+            facing.travel = None;
+        }
+
+        // Freeze movement if in conversation (but allow Approaching state)
+        if let Some(conv) = conversation {
+            if conv.state != ConversationState::Approaching {
+                continue; // Skip movement for waiting/speaking NPCs
+            }
+        }
+
+        let Some(target) = locomotion.target() else {
+            continue;
+        };
+
+        let (target_position, collider) = match target {
+            MovementTarget::Entity(entity) => match world_transforms.get(entity) {
+                Ok((global, collider)) => {
+                    let mut pos = global.translation();
+                    pos.y = transform.translation.y;
+                    (pos, collider)
+                }
+                Err(_) => {
+                    warn!(
+                        "Clearing locomotion target for {}: entity {entity:?} missing transform",
+                        identity.display_name
+                    );
+                    locomotion.clear_target();
+                    continue;
+                }
+            },
+            MovementTarget::Position(position) => (
+                Vec3::new(position.x, transform.translation.y, position.z),
+                None,
+            ),
+        };
+
+        let displacement = Vec2::new(
+            target_position.x - transform.translation.x,
+            target_position.z - transform.translation.z,
+        );
+        let distance = displacement.length();
+        if !distance.is_finite() {
+            // NaN compares false against the arrival distance, so without this the mover
+            // would step by a NaN direction and poison its own transform.
+            if warned_non_finite.insert(entity) {
+                warn!(
+                    "Skipping locomotion for {}: non-finite distance from {} to {}",
+                    identity.display_name, transform.translation, target_position
+                );
+            }
+            continue;
+        }
+        let arrive_distance = arrival_distance(locomotion.arrive_distance(), collider, body);
+
+        let was_moving = locomotion.state() == LocomotionState::Moving;
+
+        if distance <= arrive_distance {
+            let arrival_label = locomotion.active_label().map(|label| label.to_string());
+            if collider.is_some() {
+                let stopped = transform.translation;
+                locomotion.arrive_at(stopped);
+            } else {
+                transform.translation.x = target_position.x;
+                transform.translation.z = target_position.z;
+                locomotion.arrive_at(target_position);
+            }
+
+            if was_moving {
+                if let Some(label) = arrival_label {
+                    info!("{} arrived at {}", identity.display_name, label);
+                } else {
+                    info!("{} completed travel", identity.display_name);
+                }
+            }
+            continue;
+        }
+
+        let direction = displacement / distance;
+        let step = locomotion.effective_move_speed(modifiers) * delta_seconds;
+        let travel = direction * step.min(distance);
+        let moved = transform.translation + Vec3::new(travel.x, 0.0, travel.y);
+        if !moved.is_finite() {
+            if warned_non_finite.insert(entity) {
+                warn!(
+                    "Skipping locomotion for {}: step toward {} is not finite",
+                    identity.display_name, target_position
+                );
+            }
+            continue;
+        }
+
+        transform.translation = moved;
+        if let Some(facing) = facing.as_deref_mut() {
+            facing.travel = yaw_toward(direction);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{
+        core::plugin::SimulationClock,
+        npc::components::{Identity, MovementTarget, NpcId, NpcLocomotion},
+    };
+
+    fn locomotion_app() -> App {
+        let mut app = App::new();
+        app.insert_resource(SimulationClock::new(1.0))
+            .add_systems(Update, drive_npc_locomotion);
+        app
+    }
+
+    fn step(app: &mut App) {
+        app.world_mut()
+            .resource_mut::<SimulationClock>()
+            .tick(Duration::from_secs_f32(0.1));
+        app.update();
+    }
+
+    #[test]
+    fn targets_at_the_movers_own_position_are_rejected() {
+        let here = Vec3::new(3.0, 1.0, -2.0);
+        let mut locomotion = NpcLocomotion::default();
+        assert!(!locomotion.set_target(MovementTarget::Position(here), "here", here));
+        assert!(!locomotion.set_target(
+            MovementTarget::Position(Vec3::new(3.0, 0.0, -2.0)),
+            "below",
+            here
+        ));
+        assert!(!locomotion.set_target(
+            MovementTarget::Position(Vec3::new(f32::NAN, 0.0, 0.0)),
+            "nowhere",
+            here
+        ));
+        assert!(locomotion.target().is_none());
+        assert!(locomotion.set_target(MovementTarget::Position(Vec3::ZERO), "origin", here));
+    }
+
+    #[test]
+    fn zero_distance_entity_target_arrives_without_nan() {
+        let mut app = locomotion_app();
+        let here = Vec3::new(3.0, 1.0, -2.0);
+        let post = app
+            .world_mut()
+            .spawn((
+                Transform::from_translation(here),
+                GlobalTransform::from_translation(here),
+            ))
+            .id();
+        let mut locomotion = NpcLocomotion::default();
+        assert!(locomotion.set_target(MovementTarget::Entity(post), "post", here));
+        let npc = app
+            .world_mut()
+            .spawn((
+                Identity::new(NpcId::new(1), "Alric", 30.0),
+                Transform::from_translation(here),
+                locomotion,
+            ))
+            .id();
+
+        step(&mut app);
+
+        assert_eq!(app.world().get::<Transform>(npc).unwrap().translation, here);
+        assert!(app
+            .world()
+            .get::<NpcLocomotion>(npc)
+            .unwrap()
+            .target()
+            .is_none());
+    }
+
+    #[test]
+    fn non_finite_positions_are_not_stepped() {
+        let mut app = locomotion_app();
+        let mut locomotion = NpcLocomotion::default();
+        assert!(locomotion.set_target(
+            MovementTarget::Position(Vec3::new(10.0, 0.0, 0.0)),
+            "mill",
+            Vec3::ZERO
+        ));
+        let npc = app
+            .world_mut()
+            .spawn((
+                Identity::new(NpcId::new(1), "Alric", 30.0),
+                Transform::from_xyz(f32::NAN, 1.0, 0.0),
+                locomotion,
+            ))
+            .id();
+
+        step(&mut app);
+        step(&mut app);
+
+        let translation = app.world().get::<Transform>(npc).unwrap().translation;
+        assert!(translation.x.is_nan());
+        assert_eq!((translation.y, translation.z), (1.0, 0.0));
+        app.world_mut()
+            .get_mut::<Transform>(npc)
+            .unwrap()
+            .translation = Vec3::new(0.0, 1.0, 0.0);
+        step(&mut app);
+        let translation = app.world().get::<Transform>(npc).unwrap().translation;
+        assert!(translation.is_finite() && translation.x > 0.0);
+    }
+}
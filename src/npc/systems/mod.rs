@@ -0,0 +1,203 @@
+//! Systems related to NPC spawning and scheduling.
+
+use bevy::{math::primitives::Capsule3d, prelude::*};
+
+use crate::{
+    core::plugin::SimulationClock,
+    npc::{
+        components::{
+            DailySchedule, Identity, NpcIdGenerator, NpcLocomotion, ScheduleEntry, ScheduleState,
+            ScheduleTicker,
+        },
+        events::NpcActivityChangedEvent,
+        facing::DesiredFacing,
+        motivation::{MotivationConfig, NpcMotivation},
+        rumors::NpcKnowledge,
+        sleep::HomePosition,
+    },
+    world::{collision::MoverCollider, time::WorldClock},
+};
+
+mod conversation;
+mod despawn;
+mod locomotion;
+
+pub use conversation::{
+    cleanup_conversations, orient_conversing_npcs, release_failed_conversations,
+    start_conversations,
+};
+#[cfg(feature = "chaos")]
+pub use despawn::despawn_npc;
+pub use despawn::{announce_removed_npcs, forget_despawned_npcs};
+pub use locomotion::drive_npc_locomotion;
+
+/// Matches the 0.3 radius, 1.6 tall capsule mesh.
+const NPC_COLLIDER: MoverCollider = MoverCollider::new(0.3, 0.8);
+
+/// Spawns a handful of debug NPCs with unique identities.
+pub fn spawn_debug_npcs(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut id_generator: ResMut<NpcIdGenerator>,
+    motivation_config: Res<MotivationConfig>,
+) {
+    let prototypes = [
+        (
+            "Alric",
+            Color::srgb_u8(200, 90, 90),
+            Vec3::new(4.0, 1.0, 2.0),
+            vec![
+                ScheduleEntry::new(0.00, "Sleeping"),
+                ScheduleEntry::new(0.25, "Fetching water"),
+                ScheduleEntry::new(0.50, "Working the fields"),
+                ScheduleEntry::new(0.75, "Supper & stories"),
+            ],
+        ),
+        (
+            "Bryn",
+            Color::srgb_u8(90, 150, 210),
+            Vec3::new(6.5, 1.0, -1.5),
+            vec![
+                ScheduleEntry::new(0.00, "Sleeping").until(0.25),
+                ScheduleEntry::new(0.30, "Preparing meals"),
+                ScheduleEntry::new(0.55, "Market errands"),
+                ScheduleEntry::new(0.80, "Evening lute practice"),
+            ],
+        ),
+        (
+            "Cedric",
+            Color::srgb_u8(140, 200, 120),
+            Vec3::new(3.0, 1.0, -4.0),
+            vec![
+                ScheduleEntry::new(0.00, "Sleeping"),
+                ScheduleEntry::new(0.20, "Tending livestock"),
+                ScheduleEntry::new(0.60, "Tavern chatter"),
+                ScheduleEntry::new(0.78, "Guard patrol"),
+            ],
+        ),
+        (
+            "Dunstan",
+            Color::srgb_u8(210, 170, 90),
+            Vec3::new(-2.0, 1.0, 4.5),
+            vec![
+                ScheduleEntry::new(0.00, "Sleeping"),
+                ScheduleEntry::new(0.35, "Brewing ale"),
+                ScheduleEntry::new(0.55, "Stocking the cellar"),
+                ScheduleEntry::new(0.70, "Tavern evening service"),
+            ],
+        ),
+    ];
+
+    for (name, color, position, schedule_entries) in prototypes {
+        let id = id_generator.next_id();
+        let identity = Identity::new(id, name, 24.0);
+
+        commands.spawn((
+            Mesh3d(meshes.add(Mesh::from(Capsule3d::new(0.3, 1.0)))),
+            MeshMaterial3d(materials.add(StandardMaterial {
+                base_color: color,
+                ..default()
+            })),
+            Transform::from_translation(position),
+            identity,
+            DailySchedule::new(schedule_entries),
+            ScheduleState::default(),
+            NpcLocomotion::default(),
+            HomePosition(position),
+            NpcMotivation::new(&motivation_config),
+            NpcKnowledge::default(),
+            DesiredFacing::default(),
+            NPC_COLLIDER,
+            Name::new(format!("{} ({})", name, id)),
+        ));
+    }
+}
+
+/// Updates each NPC's current activity when pending ticks exist.
+pub fn tick_schedule_state(
+    mut ticker: ResMut<ScheduleTicker>,
+    sim_clock: Res<SimulationClock>,
+    clock: Res<WorldClock>,
+    mut query: Query<(&Identity, &DailySchedule, &mut ScheduleState)>,
+    mut activity_events: MessageWriter<NpcActivityChangedEvent>,
+) {
+    let delta = sim_clock.last_scaled_delta().as_secs_f32();
+    ticker.accumulate(delta);
+
+    let pending = ticker.take_pending();
+    if pending == 0 || query.is_empty() {
+        return;
+    }
+
+    let time_of_day = clock.time_of_day();
+
+    for (identity, schedule, mut state) in query.iter_mut() {
+        if schedule.entries.is_empty() {
+            continue;
+        }
+
+        let current_activity = schedule.current_activity(time_of_day);
+        if state.current_activity != current_activity {
+            info!(
+                "{} transitions to activity: {}",
+                identity.display_name, current_activity
+            );
+            state.current_activity = current_activity.to_string();
+            activity_events.write(NpcActivityChangedEvent {
+                npc: identity.id,
+                activity: current_activity.to_string(),
+                time_of_day,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        core::plugin::SimulationClock,
+        dialogue::{
+            events::{DialogueRequestFailedEvent, DialogueRequestedEvent},
+            types::DialogueRequestId,
+        },
+        npc::{
+            components::{ActiveConversations, ConversationSettings, Identity, NpcId},
+            spatial::{index_npcs, NpcIndex},
+        },
+    };
+
+    pub(super) fn conversation_app() -> App {
+        let mut app = App::new();
+        app.insert_resource(SimulationClock::new(1.0))
+            .init_resource::<Time>()
+            .init_resource::<ConversationSettings>()
+            .init_resource::<ActiveConversations>()
+            .init_resource::<NpcIndex>()
+            .add_message::<DialogueRequestedEvent>()
+            .add_message::<DialogueRequestFailedEvent>()
+            .add_systems(
+                Update,
+                (
+                    index_npcs,
+                    start_conversations,
+                    release_failed_conversations,
+                )
+                    .chain(),
+            );
+        for (id, name) in [(1, "Alric"), (2, "Bryn"), (3, "Cedric")] {
+            app.world_mut()
+                .spawn(Identity::new(NpcId::new(id), name, 30.0));
+        }
+        app
+    }
+
+    pub(super) fn request(app: &mut App, id: u64, speaker: u64, target: u64) {
+        app.world_mut().write_message(DialogueRequestedEvent {
+            request_id: DialogueRequestId::new(id),
+            speaker: NpcId::new(speaker),
+            target: Some(NpcId::new(target)),
+        });
+    }
+}
@@ -0,0 +1,200 @@
+//! Per-NPC voice: example lines from `config/npcs.toml` that keep an NPC's dialogue
+//! sounding like the same person from one request to the next.
+use std::{collections::HashMap, fs, path::Path};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    core::config::ConfigReloadRequested,
+    dialogue::queue::DialogueSpeakerProfiles,
+    npc::{components::Identity, household::CONFIG_PATH},
+};
+
+/// Fewer lines than this give the provider too little to imitate.
+const MIN_EXAMPLE_LINES: usize = 2;
+/// Lines past this are dropped; each one costs prompt tokens on every request.
+const MAX_EXAMPLE_LINES: usize = 4;
+
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+struct RawVoiceConfig {
+    #[serde(default)]
+    npcs: Vec<RawNpcVoice>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct RawNpcVoice {
+    name: String,
+    #[serde(default)]
+    example_lines: Vec<String>,
+}
+
+/// Example lines per NPC display name, loaded from the `[[npcs]]` entries.
+#[derive(Resource, Debug, Clone, Default, PartialEq)]
+pub struct NpcVoiceConfig {
+    examples: HashMap<String, Vec<String>>,
+}
+
+impl NpcVoiceConfig {
+    /// Reads and parses `config/npcs.toml`.
+    pub fn load() -> Result<Self, String> {
+        let data = fs::read_to_string(Path::new(CONFIG_PATH))
+            .map_err(|err| format!("unable to read file: {err}"))?;
+        Self::parse(&data)
+    }
+
+    /// Parses the `[[npcs]]` entries. Lines are trimmed and blanks dropped; lists outside
+    /// 2-4 lines are kept (truncated to 4) with a warning.
+    pub fn parse(data: &str) -> Result<Self, String> {
+        let raw = toml::from_str::<RawVoiceConfig>(data)
+            .map_err(|err| format!("invalid npc config: {err}"))?;
+
+        let mut examples = HashMap::new();
+        for voice in raw.npcs {
+            let name = voice.name.trim().to_string();
+            if name.is_empty() {
+                return Err("npc voice entry needs a name".to_string());
+            }
+            let mut lines: Vec<String> = voice
+                .example_lines
+                .iter()
+                .map(|line| line.trim())
+                .filter(|line| !line.is_empty())
+                .map(str::to_string)
+                .collect();
+            if lines.len() > MAX_EXAMPLE_LINES {
+                warn!(
+                    "{name} has {} example lines; keeping the first {MAX_EXAMPLE_LINES}",
+                    lines.len()
+                );
+                lines.truncate(MAX_EXAMPLE_LINES);
+            } else if !lines.is_empty() && lines.len() < MIN_EXAMPLE_LINES {
+                warn!("{name} has a single example line; {MIN_EXAMPLE_LINES}-{MAX_EXAMPLE_LINES} work best");
+            }
+            examples.insert(name, lines);
+        }
+        Ok(Self { examples })
+    }
+
+    pub fn examples(&self, display_name: &str) -> &[String] {
+        self.examples.get(display_name).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// Validates the `[[npcs]]` entries of `data` like `NpcVoiceConfig::parse`, and returns
+/// them with every default written out, for village presets.
+pub fn explicit_toml(data: &str) -> Result<toml::Table, String> {
+    NpcVoiceConfig::parse(data)?;
+    let raw = toml::from_str::<RawVoiceConfig>(data)
+        .map_err(|err| format!("invalid npc config: {err}"))?;
+    toml::Table::try_from(raw).map_err(|err| format!("unable to write npc config: {err}"))
+}
+
+/// No voices, which is what `NpcVoiceConfig` falls back to.
+pub fn fallback_toml() -> toml::Table {
+    toml::Table::try_from(RawVoiceConfig::default()).expect("default voice config serializes")
+}
+
+/// Re-reads the voice entries alongside the household reload. Problems are already
+/// reported by that reload, so a failed parse here just keeps the current lines.
+pub fn reload_npc_voice_config(
+    mut requests: MessageReader<ConfigReloadRequested>,
+    mut config: ResMut<NpcVoiceConfig>,
+) {
+    if !requests.read().any(|request| request.is_for(CONFIG_PATH)) {
+        return;
+    }
+    match NpcVoiceConfig::load() {
+        Ok(reloaded) => *config = reloaded,
+        Err(error) => warn!("Keeping current NPC example lines ({error})"),
+    }
+}
+
+/// Hands each NPC's example lines to the dialogue profiles, matched by display name.
+pub fn register_voice_examples(
+    config: Res<NpcVoiceConfig>,
+    mut profiles: ResMut<DialogueSpeakerProfiles>,
+    npcs: Query<Ref<Identity>>,
+) {
+    for identity in npcs.iter() {
+        if config.is_changed() || identity.is_changed() {
+            profiles.set_examples(
+                identity.id,
+                config.examples(&identity.display_name).to_vec(),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::npc::components::NpcId;
+
+    #[test]
+    fn example_lines_are_trimmed_and_capped() {
+        let config = NpcVoiceConfig::parse(
+            r#"
+            [storage]
+            personal_keep = 2
+
+            [[npcs]]
+            name = "Alric"
+            example_lines = ["  Rain's coming, mark me.  ", "", "Fields won't wait."]
+
+            [[npcs]]
+            name = "Bryn"
+            example_lines = ["One.", "Two.", "Three.", "Four.", "Five."]
+
+            [[npcs]]
+            name = "Cedric"
+            "#,
+        )
+        .expect("voice entries parse");
+
+        assert_eq!(
+            config.examples("Alric"),
+            ["Rain's coming, mark me.", "Fields won't wait."]
+        );
+        assert_eq!(config.examples("Bryn").len(), MAX_EXAMPLE_LINES);
+        assert!(config.examples("Cedric").is_empty());
+        assert!(config.examples("Nobody").is_empty());
+
+        assert!(NpcVoiceConfig::parse("[[npcs]]\nname = \" \"").is_err());
+        assert!(NpcVoiceConfig::parse("[[npcs]]\nname = \"Ann\"\nexample_lines = 3").is_err());
+    }
+
+    #[test]
+    fn shipped_config_gives_every_npc_a_voice() {
+        let config = NpcVoiceConfig::load().expect("shipped npc config is valid");
+        for name in ["Alric", "Bryn", "Cedric", "Dunstan"] {
+            let lines = config.examples(name).len();
+            assert!(
+                (MIN_EXAMPLE_LINES..=MAX_EXAMPLE_LINES).contains(&lines),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn examples_reach_the_speaker_profiles() {
+        let mut app = App::new();
+        app.insert_resource(
+            NpcVoiceConfig::parse(
+                "[[npcs]]\nname = \"Alric\"\nexample_lines = [\"Morning.\", \"Evening.\"]",
+            )
+            .unwrap(),
+        )
+        .init_resource::<DialogueSpeakerProfiles>()
+        .add_systems(Update, register_voice_examples);
+        app.world_mut()
+            .spawn(Identity::new(NpcId::new(1), "Alric", 30.0));
+        app.world_mut()
+            .spawn(Identity::new(NpcId::new(2), "Bryn", 30.0));
+        app.update();
+
+        let profiles = app.world().resource::<DialogueSpeakerProfiles>();
+        assert_eq!(profiles.examples(NpcId::new(1)), ["Morning.", "Evening."]);
+        assert!(profiles.examples(NpcId::new(2)).is_empty());
+    }
+}
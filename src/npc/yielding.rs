@@ -0,0 +1,317 @@
+//! Social space around conversations: walkers whose next step passes close to a talking
+//! pair sidestep around it instead of cutting between the two.
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::{
+    core::plugin::SimulationClock,
+    npc::components::{
+        Identity, InConversation, MovementTarget, NpcId, NpcLocomotion, SpeedModifiers,
+    },
+    player::components::Player,
+};
+
+const DEFAULT_SOCIAL_RADIUS: f32 = 1.5;
+const DEFAULT_SIDESTEP_SPEED: f32 = 2.0;
+const DEFAULT_RECOVERY_PER_SECOND: f32 = 4.0;
+
+/// Tunables for the yielding pass that runs after locomotion.
+#[derive(Resource, Debug, Clone)]
+pub struct ConversationYieldConfig {
+    /// Walkers keep at least this far (on the XZ plane) from a talking pair's midpoint.
+    pub social_radius: f32,
+    /// Fastest a walker moves sideways while stepping aside, per second of scaled sim time.
+    pub sidestep_speed: f32,
+    /// How quickly the sideways drift dies down once the path is clear, in units per
+    /// second per second.
+    pub recovery_per_second: f32,
+}
+
+impl Default for ConversationYieldConfig {
+    fn default() -> Self {
+        Self {
+            social_radius: DEFAULT_SOCIAL_RADIUS,
+            sidestep_speed: DEFAULT_SIDESTEP_SPEED,
+            recovery_per_second: DEFAULT_RECOVERY_PER_SECOND,
+        }
+    }
+}
+
+/// Sideways displacement that keeps a walker moving `travel` from `position` at least
+/// `radius` from a pair talking at `midpoint`, or zero when this step stays clear. The
+/// offset is perpendicular to `travel`, on the side away from the pair; a pair dead ahead
+/// is always passed on the `-travel.perp()` side. A walker already inside the radius gets
+/// the same sideways step out rather than a push back along its path.
+pub fn detour_offset(position: Vec2, travel: Vec2, midpoint: Vec2, radius: f32) -> Vec2 {
+    let length = travel.length();
+    if radius <= 0.0 || length <= f32::EPSILON || !length.is_finite() {
+        return Vec2::ZERO;
+    }
+    let direction = travel / length;
+    let to_pair = midpoint - position;
+    let along = to_pair.dot(direction).clamp(0.0, length);
+    if (position + direction * along).distance(midpoint) >= radius {
+        return Vec2::ZERO;
+    }
+
+    let left = direction.perp();
+    let lateral = to_pair.dot(left);
+    let away = if lateral < 0.0 { left } else { -left };
+    away * (radius - lateral.abs())
+}
+
+/// Midpoints (XZ) of every conversation in progress, one per pair. A partner whose
+/// position is unknown this frame, e.g. before their `InConversation` lands, is skipped.
+pub fn conversation_midpoints(
+    talkers: impl IntoIterator<Item = (NpcId, Vec2, NpcId)>,
+    player: Option<Vec2>,
+) -> Vec<Vec2> {
+    let talkers: Vec<(NpcId, Vec2, NpcId)> = talkers.into_iter().collect();
+    let positions: HashMap<NpcId, Vec2> = talkers
+        .iter()
+        .map(|(npc, position, _)| (*npc, *position))
+        .collect();
+    talkers
+        .iter()
+        .filter_map(|(npc, position, partner)| {
+            let partner_position = if partner.is_player() {
+                player?
+            } else if npc < partner {
+                *positions.get(partner)?
+            } else {
+                // The partner's own entry covers the pair.
+                return None;
+            };
+            Some((*position + partner_position) * 0.5)
+        })
+        .collect()
+}
+
+/// Steps walking NPCs aside when this frame's step would pass within
+/// `ConversationYieldConfig::social_radius` of a talking pair, then lets the sideways
+/// drift die down once clear. Conversing NPCs never yield, and a pair standing at the
+/// walker's destination is not avoided, so arrivals still happen.
+#[allow(clippy::type_complexity)]
+pub fn yield_to_conversations(
+    sim_clock: Res<SimulationClock>,
+    config: Res<ConversationYieldConfig>,
+    talkers: Query<(&Identity, &Transform, &InConversation)>,
+    players: Query<&Transform, (With<Player>, Without<NpcLocomotion>)>,
+    destinations: Query<&GlobalTransform>,
+    mut walkers: Query<
+        (
+            Entity,
+            &mut Transform,
+            &NpcLocomotion,
+            Option<&SpeedModifiers>,
+        ),
+        Without<InConversation>,
+    >,
+    mut drift: Local<HashMap<Entity, Vec2>>,
+) {
+    let delta_seconds = sim_clock.last_scaled_delta().as_secs_f32();
+    if delta_seconds <= f32::EPSILON {
+        return;
+    }
+    drift.retain(|entity, _| walkers.contains(*entity));
+
+    let midpoints = conversation_midpoints(
+        talkers.iter().map(|(identity, transform, conversation)| {
+            (
+                identity.id,
+                transform.translation.xz(),
+                conversation.partner,
+            )
+        }),
+        players.iter().next().map(|player| player.translation.xz()),
+    );
+    if midpoints.is_empty() && drift.is_empty() {
+        return;
+    }
+
+    let radius = config.social_radius;
+    let max_step = config.sidestep_speed * delta_seconds;
+    for (entity, mut transform, locomotion, modifiers) in &mut walkers {
+        let destination = match locomotion.target() {
+            Some(MovementTarget::Entity(target)) => destinations
+                .get(target)
+                .ok()
+                .map(|global| global.translation().xz()),
+            Some(MovementTarget::Position(position)) => Some(position.xz()),
+            None => None,
+        };
+        let Some(destination) = destination else {
+            drift.remove(&entity);
+            continue;
+        };
+
+        let position = transform.translation.xz();
+        let travel = (destination - position)
+            .clamp_length_max(locomotion.effective_move_speed(modifiers) * delta_seconds);
+        let wanted: Vec2 = midpoints
+            .iter()
+            .filter(|midpoint| midpoint.distance(destination) >= radius)
+            .map(|midpoint| detour_offset(position, travel, *midpoint, radius))
+            .sum();
+
+        let velocity = drift.entry(entity).or_default();
+        let step = if wanted != Vec2::ZERO {
+            *velocity = wanted.normalize_or_zero() * config.sidestep_speed;
+            wanted.clamp_length_max(max_step)
+        } else {
+            let speed = (velocity.length() - config.recovery_per_second * delta_seconds).max(0.0);
+            *velocity = velocity.normalize_or_zero() * speed;
+            *velocity * delta_seconds
+        };
+        if *velocity == Vec2::ZERO {
+            drift.remove(&entity);
+        }
+        if step != Vec2::ZERO && step.is_finite() {
+            transform.translation.x += step.x;
+            transform.translation.z += step.y;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dialogue::types::DialogueRequestId,
+        economy::systems::{advance_actor_tasks, test_support::GrainCourier},
+        npc::{components::ConversationState, systems::drive_npc_locomotion},
+        world::collision::resolve_static_collisions,
+    };
+
+    const RADIUS: f32 = 1.5;
+
+    fn assert_close(actual: Vec2, expected: Vec2) {
+        assert!(actual.abs_diff_eq(expected, 1e-5), "{actual} != {expected}");
+    }
+
+    #[test]
+    fn walkers_step_away_from_the_side_the_pair_is_on() {
+        let travel = Vec2::new(1.0, 0.0);
+        let pair_on_plus_z = detour_offset(Vec2::ZERO, travel, Vec2::new(2.0, 0.5), RADIUS);
+        assert_close(pair_on_plus_z, Vec2::new(0.0, -1.0));
+
+        let pair_on_minus_z = detour_offset(Vec2::ZERO, travel, Vec2::new(2.0, -0.5), RADIUS);
+        assert_close(pair_on_minus_z, Vec2::new(0.0, 1.0));
+
+        let dead_ahead = detour_offset(Vec2::ZERO, travel, Vec2::new(1.5, 0.0), RADIUS);
+        assert_close(dead_ahead, -travel.perp() * RADIUS);
+
+        assert_eq!(
+            detour_offset(Vec2::ZERO, travel, Vec2::new(5.0, 0.0), RADIUS),
+            Vec2::ZERO,
+            "a pair beyond this step is not avoided yet"
+        );
+        assert_eq!(
+            detour_offset(Vec2::ZERO, Vec2::ZERO, Vec2::new(0.5, 0.0), RADIUS),
+            Vec2::ZERO,
+            "standing still needs no detour"
+        );
+    }
+
+    #[test]
+    fn walkers_already_inside_the_radius_step_out_sideways() {
+        let offset = detour_offset(
+            Vec2::ZERO,
+            Vec2::new(0.1, 0.0),
+            Vec2::new(0.2, -0.3),
+            RADIUS,
+        );
+        assert_close(offset, Vec2::new(0.0, 1.2));
+
+        let behind = detour_offset(
+            Vec2::ZERO,
+            Vec2::new(0.1, 0.0),
+            Vec2::new(-0.4, 0.5),
+            RADIUS,
+        );
+        assert_close(behind, Vec2::new(0.0, -1.0));
+    }
+
+    #[test]
+    fn each_pair_has_one_midpoint() {
+        let alric = NpcId::new(1);
+        let bryn = NpcId::new(2);
+        let cedric = NpcId::new(3);
+        let midpoints = conversation_midpoints(
+            [
+                (alric, Vec2::new(0.0, 0.0), bryn),
+                (bryn, Vec2::new(2.0, 0.0), alric),
+                (cedric, Vec2::new(5.0, 5.0), NpcId::player()),
+            ],
+            Some(Vec2::new(5.0, 7.0)),
+        );
+        assert_eq!(midpoints, vec![Vec2::new(1.0, 0.0), Vec2::new(5.0, 6.0)]);
+
+        let partner_pending = conversation_midpoints([(alric, Vec2::ZERO, bryn)], None);
+        assert!(partner_pending.is_empty());
+    }
+
+    /// Runs one farmer-to-miller grain exchange, optionally with two villagers talking
+    /// halfway along the farmer's way to the stall. Returns how far the farmer walked and
+    /// whether the miller got the grain.
+    fn deliver_past_conversation(with_pair: bool) -> (f32, bool) {
+        let mut courier = GrainCourier::new(1);
+        courier
+            .app
+            .init_resource::<ConversationYieldConfig>()
+            .add_systems(
+                Update,
+                (
+                    drive_npc_locomotion,
+                    yield_to_conversations,
+                    resolve_static_collisions,
+                )
+                    .chain()
+                    .after(advance_actor_tasks),
+            );
+        if with_pair {
+            // Straddling the farmer's straight line from (4.8, 0) to the stall.
+            let (alric, bryn) = (NpcId::new(50), NpcId::new(51));
+            for (npc, partner, x) in [(alric, bryn, 2.1), (bryn, alric, 2.7)] {
+                courier.app.world_mut().spawn((
+                    Identity::new(npc, "Talker", 30.0),
+                    Transform::from_xyz(x, 1.0, 2.5),
+                    InConversation::new(
+                        partner,
+                        DialogueRequestId::new(1),
+                        0.0,
+                        ConversationState::WaitingAtDestination,
+                    ),
+                ));
+            }
+        }
+
+        let farmer = courier.farmer;
+        let mut walked = 0.0;
+        let mut last = courier
+            .app
+            .world()
+            .get::<Transform>(farmer)
+            .unwrap()
+            .translation;
+        let (_, delivered) = courier.run(600, |app| {
+            let position = app.world().get::<Transform>(farmer).unwrap().translation;
+            walked += position.xz().distance(last.xz());
+            last = position;
+        });
+        (walked, delivered)
+    }
+
+    #[test]
+    fn courier_steps_around_a_conversation_and_still_delivers() {
+        let (direct, delivered_directly) = deliver_past_conversation(false);
+        let (detoured, delivered_around) = deliver_past_conversation(true);
+        assert!(delivered_directly);
+        assert!(delivered_around, "the detour does not stop the delivery");
+        assert!(
+            detoured > direct + 0.2,
+            "the courier walks around the pair ({detoured} vs {direct})"
+        );
+    }
+}
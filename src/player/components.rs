@@ -1,7 +1,16 @@
 //! Components and resources for player interaction system.
 use bevy::prelude::*;
 
-use crate::npc::components::NpcId;
+use crate::{
+    dialogue::{
+        broker::DialogueProviderKind,
+        events::{ConversationEndReason, PlayerInteractionEvent},
+        types::DialogueRequestId,
+    },
+    economy::components::{Profession, TradeGood},
+    npc::components::NpcId,
+    player::inventory::CrateTransferDirection,
+};
 
 /// Marker component identifying the player entity (attached to camera).
 #[derive(Component, Debug)]
@@ -14,12 +23,90 @@ pub struct PlayerInteractionState {
     pub nearby_npc: Option<NearbyNpcInfo>,
     /// Current NPC the player is conversing with (if any).
     pub active_dialogue: Option<NpcId>,
+    /// Request the active conversation is waiting on. Only its reply may fill the response
+    /// window; any other reply addressed to the player shows as a dialogue panel alone.
+    pub pending_request: Option<DialogueRequestId>,
     /// Display name for the active NPC (cached for prompt building).
     pub active_npc_name: Option<String>,
     /// Last line spoken by the NPC.
     pub last_npc_line: Option<String>,
     /// Active response window entity (if shown).
     pub response_window: Option<Entity>,
+    /// Profession crate the player is currently near.
+    pub nearby_crate: Option<NearbyCrateInfo>,
+    /// Crate whose panel is open (if any).
+    pub open_crate: Option<Profession>,
+    /// Crate panel entity (if shown).
+    pub crate_panel: Option<Entity>,
+    /// Set when crate contents changed and the panel needs rebuilding.
+    pub crate_panel_dirty: bool,
+    /// True while the response window shows a canned line for a failed request.
+    pub failure_notice: bool,
+    /// NPC to re-offer once their rate-limit cooldown expires.
+    pub retry_offer: Option<DialogueRetryOffer>,
+    /// Conversation tracked for telemetry. Unlike `active_dialogue` it survives failure
+    /// notices and retries, ending only on timeout, "Leave" or "Goodbye", turning to
+    /// another NPC, or walking out of range.
+    pub conversation: Option<PlayerConversation>,
+}
+
+impl PlayerInteractionState {
+    /// True when E should open the nearby crate rather than greet an NPC: a crate is in
+    /// range and no NPC is closer.
+    pub fn crate_has_priority(&self) -> bool {
+        match (&self.nearby_crate, &self.nearby_npc) {
+            (Some(crate_info), Some(npc)) => crate_info.distance < npc.distance,
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+
+    /// True while `npc` is the active conversation and its reply has not arrived yet.
+    pub fn is_waiting_on(&self, npc: NpcId) -> bool {
+        self.pending_request.is_some() && self.active_dialogue == Some(npc)
+    }
+
+    /// Whether `request_id` is the reply the active conversation is waiting for.
+    pub fn is_current_request(&self, request_id: DialogueRequestId) -> bool {
+        self.pending_request == Some(request_id)
+    }
+
+    /// Forgets the active exchange and ends the tracked conversation; the caller closes the
+    /// window and withdraws the pending request.
+    pub fn leave_conversation(
+        &mut self,
+        ended_by: ConversationEndReason,
+    ) -> Option<PlayerInteractionEvent> {
+        self.active_dialogue = None;
+        self.pending_request = None;
+        self.active_npc_name = None;
+        self.last_npc_line = None;
+        self.failure_notice = false;
+        self.retry_offer = None;
+        self.end_conversation(ended_by)
+    }
+
+    /// Ends the tracked conversation, returning the telemetry event describing it.
+    pub fn end_conversation(
+        &mut self,
+        ended_by: ConversationEndReason,
+    ) -> Option<PlayerInteractionEvent> {
+        self.conversation
+            .take()
+            .map(|conversation| PlayerInteractionEvent::ConversationEnded {
+                npc: conversation.npc_id,
+                turns: conversation.turns,
+                ended_by,
+            })
+    }
+}
+
+/// A player conversation as seen by telemetry: who it is with and how many replies the
+/// player has chosen so far.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayerConversation {
+    pub npc_id: NpcId,
+    pub turns: u32,
 }
 
 /// Information about an NPC that is near the player.
@@ -33,6 +120,24 @@ pub struct NearbyNpcInfo {
     pub distance: f32,
 }
 
+/// Information about a profession crate that is near the player.
+#[derive(Debug, Clone)]
+pub struct NearbyCrateInfo {
+    /// Profession owning the crate
+    pub profession: Profession,
+    /// Distance from player to crate (in world units)
+    pub distance: f32,
+}
+
+/// NPC whose rate-limited reply should be re-offered once they can talk again.
+#[derive(Debug, Clone)]
+pub struct DialogueRetryOffer {
+    pub npc_id: NpcId,
+    pub name: String,
+    /// Provider that rate-limited the request; its cooldown decides when to re-offer.
+    pub provider: DialogueProviderKind,
+}
+
 /// Marker component for the player response UI window.
 #[derive(Component, Debug)]
 pub struct PlayerResponseWindow;
@@ -43,3 +148,59 @@ pub struct PlayerResponseButton {
     pub npc_id: NpcId,
     pub response_index: usize,
 }
+
+/// "History" button in the response window; opens the transcript with `npc_id`.
+#[derive(Component, Debug)]
+pub struct PlayerHistoryButton {
+    pub npc_id: NpcId,
+}
+
+/// Marker component for the transcript viewer window.
+#[derive(Component, Debug)]
+pub struct PlayerTranscriptWindow;
+
+/// Scrollable list inside the transcript viewer.
+#[derive(Component, Debug)]
+pub struct PlayerTranscriptScroll;
+
+/// Close button in the transcript viewer.
+#[derive(Component, Debug)]
+pub struct PlayerTranscriptCloseButton;
+
+/// Which transcript the viewer shows, if open, and the window showing it.
+#[derive(Resource, Debug, Default)]
+pub struct PlayerTranscriptViewer {
+    pub npc_id: Option<NpcId>,
+    pub window: Option<Entity>,
+    /// `TranscriptStore::recorded` for the shown pair when its lines were last listed.
+    pub shown_lines: u64,
+}
+
+/// Button shown in the response window after a failed request.
+#[derive(Component, Debug)]
+pub struct PlayerNoticeButton {
+    pub action: PlayerNoticeAction,
+}
+
+/// What a notice button does when pressed.
+#[derive(Debug, Clone)]
+pub enum PlayerNoticeAction {
+    /// Close the window and end the exchange.
+    Leave,
+    /// Say goodbye from the response window, ending the exchange like `Leave`.
+    Goodbye,
+    /// Greet the NPC again now that their cooldown has expired.
+    TalkAgain(DialogueRetryOffer),
+}
+
+/// Marker component for the crate inventory panel.
+#[derive(Component, Debug)]
+pub struct PlayerCratePanel;
+
+/// Component attached to each crate "Take 1" / "Give 1" button.
+#[derive(Component, Debug)]
+pub struct CrateTransferButton {
+    pub profession: Profession,
+    pub good: TradeGood,
+    pub direction: CrateTransferDirection,
+}
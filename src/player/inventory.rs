@@ -0,0 +1,205 @@
+//! Player-held goods and transfers between the player and NPC crate stock.
+use bevy::prelude::*;
+
+use crate::{
+    economy::{
+        components::{Inventory, InventoryChange, TradeGood},
+        events::{TradeCompletedEvent, TradeReason},
+    },
+    npc::components::NpcId,
+};
+
+/// Goods the player is carrying, mutated through the same `Inventory` path as NPC trades.
+#[derive(Resource, Debug, Default)]
+pub struct PlayerInventory {
+    inventory: Inventory,
+}
+
+impl PlayerInventory {
+    pub fn quantity_of(&self, good: TradeGood) -> u32 {
+        self.inventory.quantity_of(good)
+    }
+}
+
+/// Which way goods move relative to the player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrateTransferDirection {
+    /// From the NPC's crate stock into the player's inventory.
+    Take,
+    /// From the player's inventory into the NPC's crate stock.
+    Give,
+}
+
+impl CrateTransferDirection {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Take => "Take 1",
+            Self::Give => "Give 1",
+        }
+    }
+}
+
+/// Completed transfer: the NPC-side stack change (for `InventoryChangedEvent`) and the trade record.
+#[derive(Debug, Clone)]
+pub struct CrateTransfer {
+    pub npc_change: InventoryChange,
+    pub trade: TradeCompletedEvent,
+}
+
+/// Moves `quantity` of `good` between an NPC inventory and the player. Taking never dips
+/// into the `reserved` units the NPC's own recipes are holding (see `ReservedStock`).
+/// Returns `None` when the source stack is too small, leaving both inventories untouched.
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_with_npc(
+    direction: CrateTransferDirection,
+    npc: NpcId,
+    npc_inventory: &mut Inventory,
+    reserved: u32,
+    player: &mut PlayerInventory,
+    good: TradeGood,
+    quantity: u32,
+    day: u64,
+) -> Option<CrateTransfer> {
+    if quantity == 0 {
+        return None;
+    }
+
+    let (npc_change, from, to) = match direction {
+        CrateTransferDirection::Take => {
+            let (change, stacks) = npc_inventory.take_unreserved(good, quantity, reserved)?;
+            player.inventory.add_stacks(good, &stacks);
+            (change, npc, NpcId::player())
+        }
+        CrateTransferDirection::Give => {
+            let (_, stacks) = player.inventory.take_good(good, quantity)?;
+            let change = npc_inventory.add_stacks(good, &stacks)?;
+            (change, NpcId::player(), npc)
+        }
+    };
+
+    Some(CrateTransfer {
+        npc_change,
+        trade: TradeCompletedEvent {
+            day,
+            from: Some(from),
+            to: Some(to),
+            good,
+            quantity,
+            reason: TradeReason::PlayerTransfer,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue::types::TradeContextReason;
+
+    #[test]
+    fn take_moves_goods_from_npc_to_player() {
+        let npc = NpcId::new(2);
+        let mut stock = Inventory::default();
+        stock.add_good(TradeGood::Flour, 2);
+        let mut player = PlayerInventory::default();
+
+        let transfer = transfer_with_npc(
+            CrateTransferDirection::Take,
+            npc,
+            &mut stock,
+            0,
+            &mut player,
+            TradeGood::Flour,
+            1,
+            3,
+        )
+        .expect("stock available");
+
+        assert_eq!(stock.quantity_of(TradeGood::Flour), 1);
+        assert_eq!(player.quantity_of(TradeGood::Flour), 1);
+        assert_eq!(transfer.npc_change.delta, -1);
+        assert_eq!(transfer.npc_change.new_total, 1);
+        assert_eq!(transfer.trade.from, Some(npc));
+        assert_eq!(transfer.trade.to, Some(NpcId::player()));
+        assert_eq!(transfer.trade.reason, TradeReason::PlayerTransfer);
+    }
+
+    #[test]
+    fn take_leaves_reserved_stock_alone() {
+        let npc = NpcId::new(2);
+        let mut stock = Inventory::default();
+        stock.add_good(TradeGood::Grain, 2);
+        let mut player = PlayerInventory::default();
+        let mut take = |stock: &mut Inventory| {
+            transfer_with_npc(
+                CrateTransferDirection::Take,
+                npc,
+                stock,
+                1,
+                &mut player,
+                TradeGood::Grain,
+                1,
+                0,
+            )
+        };
+
+        assert!(take(&mut stock).is_some());
+        assert!(take(&mut stock).is_none(), "the last grain is reserved");
+        assert_eq!(stock.quantity_of(TradeGood::Grain), 1);
+    }
+
+    #[test]
+    fn give_requires_player_stock() {
+        let npc = NpcId::new(2);
+        let mut stock = Inventory::default();
+        let mut player = PlayerInventory::default();
+
+        assert!(transfer_with_npc(
+            CrateTransferDirection::Give,
+            npc,
+            &mut stock,
+            0,
+            &mut player,
+            TradeGood::Tools,
+            1,
+            0,
+        )
+        .is_none());
+        assert!(transfer_with_npc(
+            CrateTransferDirection::Take,
+            npc,
+            &mut stock,
+            0,
+            &mut player,
+            TradeGood::Tools,
+            1,
+            0,
+        )
+        .is_none());
+
+        player.inventory.add_good(TradeGood::Tools, 1);
+        let transfer = transfer_with_npc(
+            CrateTransferDirection::Give,
+            npc,
+            &mut stock,
+            0,
+            &mut player,
+            TradeGood::Tools,
+            1,
+            0,
+        )
+        .expect("player holds tools");
+        assert_eq!(player.quantity_of(TradeGood::Tools), 0);
+        assert_eq!(stock.quantity_of(TradeGood::Tools), 1);
+        assert_eq!(transfer.npc_change.delta, 1);
+        assert_eq!(transfer.trade.from, Some(NpcId::player()));
+        assert_eq!(transfer.trade.to, Some(npc));
+    }
+
+    #[test]
+    fn player_transfer_reason_reaches_dialogue_context() {
+        assert_eq!(
+            TradeContextReason::from(TradeReason::PlayerTransfer),
+            TradeContextReason::PlayerTransfer
+        );
+    }
+}
@@ -0,0 +1,205 @@
+//! Player journal (J by default): the fetch quests still open and how warmly each villager
+//! the player knows regards them, both read from the `QuestLog`.
+use bevy::prelude::*;
+
+use crate::{
+    core::input::{ActionInput, InputAction},
+    npc::{
+        components::{Identity, NpcId},
+        spatial::NpcIndex,
+    },
+    player::quest_log::QuestLog,
+    ui::{layout::ScreenAnchor, visibility::UiLayer},
+};
+
+const JOURNAL_TITLE: &str = "Journal";
+const REQUESTS_HEADING: &str = "Requests";
+const VILLAGERS_HEADING: &str = "Villagers";
+const NO_REQUESTS_LINE: &str = "Nobody is waiting on you.";
+const NO_VILLAGERS_LINE: &str = "You have not met anyone yet.";
+
+/// Whether the journal is open, and its window while it is.
+#[derive(Resource, Debug, Default)]
+pub struct PlayerJournal {
+    pub open: bool,
+    window: Option<Entity>,
+}
+
+/// Root node of the journal window.
+#[derive(Component)]
+pub struct PlayerJournalWindow;
+
+/// "Bryn: +3" for a villager's affinity towards the player.
+pub fn format_affinity_line(name: &str, affinity: i32) -> String {
+    format!("{name}: {affinity:+}")
+}
+
+/// Request lines and villager lines for the journal, naming NPCs with `name_of`. Either
+/// list holds a single placeholder line when it has nothing to show.
+pub fn journal_sections(
+    log: &QuestLog,
+    name_of: impl Fn(NpcId) -> String,
+) -> (Vec<String>, Vec<String>) {
+    let mut requests: Vec<String> = log
+        .active()
+        .map(|quest| quest.describe(&name_of(quest.requester)))
+        .collect();
+    if requests.is_empty() {
+        requests.push(NO_REQUESTS_LINE.to_string());
+    }
+    let mut villagers: Vec<String> = log
+        .affinities()
+        .into_iter()
+        .map(|(npc, affinity)| format_affinity_line(&name_of(npc), affinity))
+        .collect();
+    if villagers.is_empty() {
+        villagers.push(NO_VILLAGERS_LINE.to_string());
+    }
+    (requests, villagers)
+}
+
+/// Opens and closes the journal.
+pub fn toggle_player_journal(input: ActionInput, mut journal: ResMut<PlayerJournal>) {
+    if input.just_pressed(InputAction::ToggleJournal) {
+        journal.open = !journal.open;
+    }
+}
+
+/// Rebuilds the journal window when it opens or the quest log changes, and removes it
+/// when it closes.
+pub fn sync_player_journal(
+    mut commands: Commands,
+    mut journal: ResMut<PlayerJournal>,
+    log: Res<QuestLog>,
+    npc_index: Res<NpcIndex>,
+    identities: Query<&Identity>,
+) {
+    let log_changed = journal.open && log.is_changed();
+    if !journal.is_changed() && !log_changed {
+        return;
+    }
+    let journal = journal.bypass_change_detection();
+    if let Some(window) = journal.window.take() {
+        commands.entity(window).despawn();
+    }
+    if !journal.open {
+        return;
+    }
+
+    let (requests, villagers) =
+        journal_sections(&log, |npc| Identity::name_of(&npc_index, &identities, npc));
+    let window = commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(20.0),
+                left: Val::Px(20.0),
+                width: Val::Px(340.0),
+                padding: UiRect::all(Val::Px(12.0)),
+                border: UiRect::all(Val::Px(2.0)),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(6.0),
+                ..Default::default()
+            },
+            BackgroundColor(Color::srgba(0.08, 0.08, 0.1, 0.95)),
+            BorderColor::from(Color::srgb(0.3, 0.3, 0.32)),
+            PlayerJournalWindow,
+            ScreenAnchor::TopLeft,
+            UiLayer::Screen,
+            Name::new("Player Journal"),
+        ))
+        .with_children(|parent| {
+            spawn_journal_text(parent, JOURNAL_TITLE, 16.0, Color::WHITE);
+            for (heading, lines) in [(REQUESTS_HEADING, requests), (VILLAGERS_HEADING, villagers)] {
+                spawn_journal_text(parent, heading, 14.0, Color::srgb(0.85, 0.8, 0.6));
+                for line in lines {
+                    spawn_journal_text(parent, line, 13.0, Color::srgb(0.85, 0.85, 0.88));
+                }
+            }
+        })
+        .id();
+    journal.window = Some(window);
+}
+
+fn spawn_journal_text(
+    parent: &mut ChildSpawnerCommands,
+    text: impl Into<String>,
+    font_size: f32,
+    color: Color,
+) {
+    parent.spawn((
+        Text::new(text),
+        TextFont {
+            font_size,
+            ..Default::default()
+        },
+        TextColor(color),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::economy::components::TradeGood;
+
+    fn name_of(npc: NpcId) -> String {
+        match npc.value() {
+            2 => "Bryn".to_string(),
+            3 => "Cedric".to_string(),
+            _ => npc.to_string(),
+        }
+    }
+
+    #[test]
+    fn journal_lists_open_requests_and_villager_affinity() {
+        let (requests, villagers) = journal_sections(&QuestLog::default(), name_of);
+        assert_eq!(requests, [NO_REQUESTS_LINE]);
+        assert_eq!(villagers, [NO_VILLAGERS_LINE]);
+
+        let mut log = QuestLog::default();
+        log.meet(NpcId::new(3));
+        log.offer(NpcId::new(2), TradeGood::Grain, 2, 3, 4);
+        log.adjust_affinity(NpcId::new(2), 3);
+        let (requests, villagers) = journal_sections(&log, name_of);
+        assert_eq!(requests, ["Bryn wants 2 grain crates (by day 4)"]);
+        assert_eq!(villagers, ["Bryn: +3", "Cedric: +0"]);
+    }
+
+    #[test]
+    fn the_journal_opens_closes_and_follows_the_quest_log() {
+        let mut app = App::new();
+        app.init_resource::<PlayerJournal>()
+            .init_resource::<QuestLog>()
+            .init_resource::<NpcIndex>()
+            .add_systems(Update, sync_player_journal);
+        let windows = |app: &mut App| {
+            app.world_mut()
+                .query_filtered::<Entity, With<PlayerJournalWindow>>()
+                .iter(app.world())
+                .collect::<Vec<_>>()
+        };
+
+        app.update();
+        assert!(windows(&mut app).is_empty(), "closed by default");
+
+        app.world_mut().resource_mut::<PlayerJournal>().open = true;
+        app.update();
+        let first = windows(&mut app);
+        assert_eq!(first.len(), 1);
+
+        app.update();
+        assert_eq!(windows(&mut app), first, "left alone while nothing changes");
+
+        app.world_mut()
+            .resource_mut::<QuestLog>()
+            .adjust_affinity(NpcId::new(2), 1);
+        app.update();
+        let rebuilt = windows(&mut app);
+        assert_eq!(rebuilt.len(), 1);
+        assert_ne!(rebuilt, first);
+
+        app.world_mut().resource_mut::<PlayerJournal>().open = false;
+        app.update();
+        assert!(windows(&mut app).is_empty());
+    }
+}
@@ -1,7 +1,16 @@
-//! Player interaction module - handles player-NPC proximity detection and dialogue initiation.
+//! Player interaction module - handles player-NPC proximity detection, dialogue initiation,
+//! crate interaction, fetch quests, village reputation, and the conversation history viewer.
+//! The quest log (open quests, acquaintances, and each NPC's affinity) is saved to
+//! `logs/quest_log.json` next to the player memory, and the journal (J) shows it.
 
 pub mod components;
+pub mod inventory;
+pub mod journal;
 pub mod plugin;
+pub mod quest_log;
+pub mod quests;
+pub mod reputation;
 pub mod systems;
+pub mod transcript;
 
 pub use plugin::PlayerPlugin;
@@ -1,27 +1,126 @@
 //! Player plugin wiring interaction systems.
 use bevy::prelude::*;
 
-use crate::player::{
-    components::PlayerInteractionState,
-    systems::{
-        cleanup_player_response_window, detect_nearby_npcs, handle_player_interaction_input,
-        handle_player_response_buttons, spawn_player_response_window,
+use crate::{
+    core::{config::report_config_result, schedule::FramePhase},
+    dialogue::chronicle::ChronicleAppExt,
+    player::{
+        components::{PlayerInteractionState, PlayerTranscriptViewer},
+        inventory::PlayerInventory,
+        journal::{sync_player_journal, toggle_player_journal, PlayerJournal},
+        quest_log::{save_quest_log, QuestLog, DEFAULT_QUEST_LOG_PATH},
+        quests::{
+            distill_quest_resolution, expire_fetch_quests, fulfill_fetch_quests,
+            note_player_acquaintances, offer_fetch_quests, FetchQuestResolvedEvent, QuestConfig,
+        },
+        reputation::{
+            decay_player_reputation, reload_reputation_config, track_player_reputation,
+            PlayerReputation, ReputationConfig, CONFIG_PATH as REPUTATION_CONFIG_PATH,
+        },
+        systems::{
+            cleanup_player_response_window, detect_nearby_crates, detect_nearby_npcs,
+            end_conversation_on_walk_away, handle_crate_interaction_input,
+            handle_crate_transfer_buttons, handle_player_dialogue_failures,
+            handle_player_interaction_input, handle_player_notice_buttons,
+            handle_player_response_buttons, restore_player_interaction_offer,
+            spawn_player_response_window, sync_crate_panel,
+        },
+        transcript::{handle_transcript_buttons, scroll_transcript_viewer, sync_transcript_viewer},
     },
+    ui::visibility::{screen_ui_visible, UiVisibilityState},
 };
 
+/// The player: interactions, inventory, transcripts, quests, reputation, and the journal.
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<PlayerInteractionState>().add_systems(
-            Update,
-            (
-                detect_nearby_npcs,
-                handle_player_interaction_input.after(detect_nearby_npcs),
-                spawn_player_response_window,
-                handle_player_response_buttons.after(spawn_player_response_window),
-                cleanup_player_response_window.after(handle_player_response_buttons),
-            ),
+        let reputation_config = report_config_result(
+            app.world_mut(),
+            REPUTATION_CONFIG_PATH,
+            ReputationConfig::load(),
+            ReputationConfig::default,
         );
+
+        app.init_resource::<PlayerInteractionState>()
+            .init_resource::<PlayerInventory>()
+            .init_resource::<PlayerTranscriptViewer>()
+            .init_resource::<QuestConfig>()
+            .insert_resource(QuestLog::load_or_default(DEFAULT_QUEST_LOG_PATH))
+            .init_resource::<PlayerJournal>()
+            .add_message::<FetchQuestResolvedEvent>()
+            .add_chronicle_distiller(distill_quest_resolution)
+            .insert_resource(reputation_config)
+            .init_resource::<PlayerReputation>()
+            .init_resource::<UiVisibilityState>()
+            .add_systems(
+                Update,
+                (
+                    detect_nearby_npcs,
+                    detect_nearby_crates,
+                    // Input is paused while the UI toggle hides the windows it drives; replies
+                    // still arrive and wait in their hidden window.
+                    handle_player_interaction_input
+                        .after(detect_nearby_npcs)
+                        .after(detect_nearby_crates)
+                        .run_if(screen_ui_visible),
+                    handle_crate_interaction_input
+                        .after(handle_player_interaction_input)
+                        .run_if(screen_ui_visible),
+                    handle_crate_transfer_buttons.run_if(screen_ui_visible),
+                    sync_crate_panel
+                        .after(handle_crate_interaction_input)
+                        .after(handle_crate_transfer_buttons)
+                        .after(fulfill_fetch_quests),
+                    spawn_player_response_window,
+                    handle_player_response_buttons
+                        .after(spawn_player_response_window)
+                        .run_if(screen_ui_visible),
+                    handle_player_dialogue_failures.after(spawn_player_response_window),
+                    restore_player_interaction_offer.after(handle_player_dialogue_failures),
+                    handle_player_notice_buttons
+                        .after(restore_player_interaction_offer)
+                        .run_if(screen_ui_visible),
+                    cleanup_player_response_window
+                        .after(handle_player_response_buttons)
+                        .after(handle_player_notice_buttons),
+                    end_conversation_on_walk_away.after(cleanup_player_response_window),
+                )
+                    .in_set(FramePhase::Presentation),
+            )
+            .add_systems(
+                Update,
+                (
+                    note_player_acquaintances,
+                    offer_fetch_quests,
+                    fulfill_fetch_quests.after(handle_crate_transfer_buttons),
+                    expire_fetch_quests,
+                    save_quest_log,
+                    decay_player_reputation,
+                    track_player_reputation,
+                )
+                    .chain()
+                    .in_set(FramePhase::Presentation),
+            )
+            .add_systems(Update, reload_reputation_config.in_set(FramePhase::SimTick))
+            .add_systems(
+                Update,
+                (
+                    handle_transcript_buttons.run_if(screen_ui_visible),
+                    sync_transcript_viewer,
+                    scroll_transcript_viewer,
+                )
+                    .chain()
+                    .in_set(FramePhase::Presentation),
+            )
+            .add_systems(
+                Update,
+                (
+                    toggle_player_journal.run_if(screen_ui_visible),
+                    sync_player_journal.after(save_quest_log),
+                )
+                    .chain()
+                    .in_set(FramePhase::Presentation),
+            );
     }
 }
@@ -0,0 +1,366 @@
+//! The player's quest log: open fetch quests, the NPCs the player has met, and each NPC's
+//! affinity for the player. It is saved to disk like `PlayerMemory`, so quests and
+//! affinity outlast a restart.
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fs::{self, create_dir_all},
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    core::format::format_quantity, economy::components::TradeGood, npc::components::NpcId,
+};
+
+/// There is no save system yet, so every run shares this file, next to
+/// `DEFAULT_PLAYER_MEMORY_PATH`.
+pub const DEFAULT_QUEST_LOG_PATH: &str = "logs/quest_log.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct QuestId(u64);
+
+impl QuestId {
+    #[cfg(test)]
+    pub fn new(value: u64) -> Self {
+        Self(value)
+    }
+
+    pub fn value(self) -> u64 {
+        self.0
+    }
+}
+
+/// An NPC's request for the player to bring `quantity` of `good` by the end of
+/// `expires_day`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FetchQuest {
+    pub id: QuestId,
+    pub requester: NpcId,
+    pub good: TradeGood,
+    pub quantity: u32,
+    /// Goods the player has handed over so far.
+    pub delivered: u32,
+    pub reward_affinity: i32,
+    pub expires_day: u64,
+}
+
+impl FetchQuest {
+    pub fn remaining(&self) -> u32 {
+        self.quantity.saturating_sub(self.delivered)
+    }
+
+    /// "Bryn wants 2 grain crates (by day 3)", for the crate panel.
+    pub fn describe(&self, requester_name: &str) -> String {
+        format!(
+            "{requester_name} wants {} (by day {})",
+            format_quantity(self.good.label(), self.remaining()),
+            self.expires_day
+        )
+    }
+}
+
+/// Open fetch quests (at most one per NPC), who the player has spoken to, and each NPC's
+/// affinity for the player. Saved to `path` whenever it changes; without a path it only
+/// lasts for the session.
+#[derive(Resource, Debug, Default)]
+pub struct QuestLog {
+    next_id: u64,
+    active: BTreeMap<QuestId, FetchQuest>,
+    acquainted: HashSet<NpcId>,
+    affinity: HashMap<NpcId, i32>,
+    path: Option<PathBuf>,
+}
+
+impl QuestLog {
+    /// The log saved at `path`, which later changes are saved back to. A missing file is a
+    /// fresh start; an unreadable one is logged and left alone until the next save.
+    pub fn load_or_default(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let mut log = match Self::load(&path) {
+            Ok(log) => log,
+            Err(err) if err.kind() == ErrorKind::NotFound => Self::default(),
+            Err(err) => {
+                warn!(
+                    "Failed to load the quest log from {} ({}). Starting without quests.",
+                    path.display(),
+                    err
+                );
+                Self::default()
+            }
+        };
+        log.path = Some(path);
+        log
+    }
+
+    /// Reads a log written by `save`.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let saved: SavedQuestLog = serde_json::from_str(&fs::read_to_string(path)?)?;
+        let active: BTreeMap<QuestId, FetchQuest> = saved
+            .quests
+            .into_iter()
+            .map(|quest| {
+                let id = QuestId(quest.id);
+                let quest = FetchQuest {
+                    id,
+                    requester: NpcId::new(quest.requester),
+                    good: quest.good,
+                    quantity: quest.quantity,
+                    delivered: quest.delivered,
+                    reward_affinity: quest.reward_affinity,
+                    expires_day: quest.expires_day,
+                };
+                (id, quest)
+            })
+            .collect();
+        // Never hand out an id a saved quest still holds.
+        let highest_id = active.last_key_value().map_or(0, |(id, _)| id.value());
+        Ok(Self {
+            next_id: saved.next_id.max(highest_id),
+            active,
+            acquainted: saved.acquainted.into_iter().map(NpcId::new).collect(),
+            affinity: saved
+                .affinity
+                .into_iter()
+                .map(|(npc, affinity)| (NpcId::new(npc), affinity))
+                .collect(),
+            path: None,
+        })
+    }
+
+    /// Writes open quests, acquaintances, and affinity as JSON keyed by NPC id.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent)?;
+        }
+        let mut acquainted: Vec<u64> = self.acquainted.iter().map(|npc| npc.value()).collect();
+        acquainted.sort_unstable();
+        let saved = SavedQuestLog {
+            next_id: self.next_id,
+            quests: self
+                .active
+                .values()
+                .map(|quest| SavedQuest {
+                    id: quest.id.value(),
+                    requester: quest.requester.value(),
+                    good: quest.good,
+                    quantity: quest.quantity,
+                    delivered: quest.delivered,
+                    reward_affinity: quest.reward_affinity,
+                    expires_day: quest.expires_day,
+                })
+                .collect(),
+            acquainted,
+            affinity: self
+                .affinity
+                .iter()
+                .map(|(npc, affinity)| (npc.value(), *affinity))
+                .collect(),
+        };
+        fs::write(path, serde_json::to_string_pretty(&saved)?)
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Records that the player has spoken with `npc`.
+    pub fn meet(&mut self, npc: NpcId) {
+        self.acquainted.insert(npc);
+    }
+
+    pub fn has_met(&self, npc: NpcId) -> bool {
+        self.acquainted.contains(&npc)
+    }
+
+    pub fn active(&self) -> impl Iterator<Item = &FetchQuest> {
+        self.active.values()
+    }
+
+    pub fn quest_for(&self, npc: NpcId) -> Option<&FetchQuest> {
+        self.active.values().find(|quest| quest.requester == npc)
+    }
+
+    /// Opens a quest for `requester`, unless they already have one.
+    pub fn offer(
+        &mut self,
+        requester: NpcId,
+        good: TradeGood,
+        quantity: u32,
+        reward_affinity: i32,
+        expires_day: u64,
+    ) -> Option<QuestId> {
+        if quantity == 0 || self.quest_for(requester).is_some() {
+            return None;
+        }
+        self.next_id += 1;
+        let id = QuestId(self.next_id);
+        self.active.insert(
+            id,
+            FetchQuest {
+                id,
+                requester,
+                good,
+                quantity,
+                delivered: 0,
+                reward_affinity,
+                expires_day,
+            },
+        );
+        Some(id)
+    }
+
+    /// Counts `quantity` of `good` handed to `npc` towards their quest. Returns the quest
+    /// once it is fulfilled, removing it from the log.
+    pub fn record_delivery(
+        &mut self,
+        npc: NpcId,
+        good: TradeGood,
+        quantity: u32,
+    ) -> Option<FetchQuest> {
+        let quest = self
+            .active
+            .values_mut()
+            .find(|quest| quest.requester == npc && quest.good == good)?;
+        quest.delivered = quest.delivered.saturating_add(quantity);
+        if quest.remaining() > 0 {
+            return None;
+        }
+        let id = quest.id;
+        self.active.remove(&id)
+    }
+
+    /// Removes and returns the quests whose last day is before `today`.
+    pub fn expire(&mut self, today: u64) -> Vec<FetchQuest> {
+        let expired: Vec<QuestId> = self
+            .active
+            .values()
+            .filter(|quest| quest.expires_day < today)
+            .map(|quest| quest.id)
+            .collect();
+        expired
+            .into_iter()
+            .filter_map(|id| self.active.remove(&id))
+            .collect()
+    }
+
+    /// How warmly `npc` regards the player: fulfilled quests raise it, lapsed ones lower
+    /// it. Zero for NPCs the log knows nothing about.
+    pub fn affinity(&self, npc: NpcId) -> i32 {
+        self.affinity.get(&npc).copied().unwrap_or(0)
+    }
+
+    /// Affinity of every NPC the player has met or whose affinity has moved, by NPC id.
+    pub fn affinities(&self) -> Vec<(NpcId, i32)> {
+        let npcs: BTreeSet<NpcId> = self
+            .acquainted
+            .iter()
+            .chain(self.affinity.keys())
+            .copied()
+            .collect();
+        npcs.into_iter()
+            .map(|npc| (npc, self.affinity(npc)))
+            .collect()
+    }
+
+    pub fn adjust_affinity(&mut self, npc: NpcId, delta: i32) {
+        let affinity = self.affinity.entry(npc).or_insert(0);
+        *affinity = affinity.saturating_add(delta);
+    }
+}
+
+/// `QuestLog` as written to disk, with NPCs and quests by id.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct SavedQuestLog {
+    next_id: u64,
+    quests: Vec<SavedQuest>,
+    acquainted: Vec<u64>,
+    affinity: BTreeMap<u64, i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedQuest {
+    id: u64,
+    requester: u64,
+    good: TradeGood,
+    quantity: u32,
+    delivered: u32,
+    reward_affinity: i32,
+    expires_day: u64,
+}
+
+/// Saves the quest log whenever it changes, so quests and affinity survive a restart.
+pub fn save_quest_log(log: Res<QuestLog>) {
+    if !log.is_changed() || log.is_added() {
+        return;
+    }
+    if let Some(path) = log.path() {
+        if let Err(err) = log.save(path) {
+            warn!(
+                "Failed to save the quest log to {}: {}",
+                path.display(),
+                err
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    #[test]
+    fn quests_and_affinity_survive_a_save_and_load() {
+        let dir = env::temp_dir().join(format!("thegame_quest_log_{}", std::process::id()));
+        let path = dir.join("quest_log.json");
+        let _ = fs::remove_dir_all(&dir);
+        let bryn = NpcId::new(2);
+        let cedric = NpcId::new(3);
+        let mut log = QuestLog::default();
+        log.meet(bryn);
+        let id = log.offer(bryn, TradeGood::Grain, 2, 3, 4).unwrap();
+        log.record_delivery(bryn, TradeGood::Grain, 1);
+        log.adjust_affinity(cedric, -1);
+        log.adjust_affinity(bryn, 3);
+        log.save(&path).unwrap();
+
+        let mut loaded = QuestLog::load(&path).unwrap();
+        assert!(loaded.has_met(bryn));
+        assert!(!loaded.has_met(cedric));
+        assert_eq!(loaded.quest_for(bryn), log.quest_for(bryn));
+        assert_eq!(loaded.quest_for(bryn).map(FetchQuest::remaining), Some(1));
+        assert_eq!(loaded.affinities(), [(bryn, 3), (cedric, -1)]);
+        let next = loaded.offer(cedric, TradeGood::Tools, 1, 3, 4).unwrap();
+        assert!(next.value() > id.value(), "saved ids are not reused");
+
+        let fresh = QuestLog::load_or_default(dir.join("missing.json"));
+        assert_eq!(fresh.active().count(), 0);
+        assert_eq!(fresh.path(), Some(dir.join("missing.json").as_path()));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn only_changes_after_startup_are_saved() {
+        let dir = env::temp_dir().join(format!("thegame_quest_save_{}", std::process::id()));
+        let path = dir.join("quest_log.json");
+        let _ = fs::remove_dir_all(&dir);
+        let mut app = App::new();
+        app.insert_resource(QuestLog::load_or_default(&path))
+            .add_systems(Update, save_quest_log);
+        app.update();
+        assert!(!path.exists(), "loading alone writes nothing");
+
+        app.world_mut()
+            .resource_mut::<QuestLog>()
+            .adjust_affinity(NpcId::new(5), 2);
+        app.update();
+        assert_eq!(QuestLog::load(&path).unwrap().affinity(NpcId::new(5)), 2);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,512 @@
+//! Fetch quests: an NPC short of a dependency asks the player to bring the goods, and
+//! thanks them (with affinity and a motivation boost) when the player hands them over.
+use bevy::prelude::*;
+
+use crate::{
+    core::format::format_quantity,
+    dialogue::{
+        chronicle::{ChronicleDraft, ChronicleRoster},
+        events::PlayerInteractionEvent,
+        queue::DialogueRequestQueue,
+        types::{
+            DialogueRequest, DialogueTopicHint, TradeContext, TradeContextReason, TradeDescriptor,
+        },
+    },
+    economy::{
+        components::TradeGood,
+        dependency::{DependencyCategory, EconomyDependencyMatrix},
+        events::{ProfessionDependencyUpdateEvent, TradeCompletedEvent, TradeReason},
+    },
+    npc::{
+        components::{Identity, NpcId},
+        motivation::{state::MotivationReason, MotivationAdjustmentEvent},
+        spatial::NpcIndex,
+    },
+    player::{
+        components::Player,
+        quest_log::{QuestId, QuestLog},
+    },
+    world::time::WorldClock,
+};
+
+const DEFAULT_QUEST_QUANTITY: u32 = 2;
+const DEFAULT_QUEST_DAYS: u64 = 2;
+const DEFAULT_REWARD_AFFINITY: i32 = 3;
+const DEFAULT_EXPIRY_PENALTY: i32 = 1;
+const DEFAULT_MOTIVATION_REWARD: f32 = 6.0;
+const DEFAULT_NEARBY_DISTANCE: f32 = 8.0;
+const FULFILLED_QUEST_SIGNIFICANCE: f32 = 0.55;
+const EXPIRED_QUEST_SIGNIFICANCE: f32 = 0.35;
+
+/// Tunables for fetch quests.
+#[derive(Resource, Debug, Clone)]
+pub struct QuestConfig {
+    /// Goods asked for per quest.
+    pub quantity: u32,
+    /// Whole days after the day it is offered that a quest stays open.
+    pub duration_days: u64,
+    /// Affinity the requester gains when the quest is fulfilled.
+    pub reward_affinity: i32,
+    /// Affinity the requester loses when the quest expires.
+    pub expiry_penalty: i32,
+    /// Dopamine the requester gains when the quest is fulfilled.
+    pub motivation_reward: f32,
+    /// NPCs the player has never spoken to only ask while the player is this close.
+    pub nearby_distance: f32,
+}
+
+impl Default for QuestConfig {
+    fn default() -> Self {
+        Self {
+            quantity: DEFAULT_QUEST_QUANTITY,
+            duration_days: DEFAULT_QUEST_DAYS,
+            reward_affinity: DEFAULT_REWARD_AFFINITY,
+            expiry_penalty: DEFAULT_EXPIRY_PENALTY,
+            motivation_reward: DEFAULT_MOTIVATION_REWARD,
+            nearby_distance: DEFAULT_NEARBY_DISTANCE,
+        }
+    }
+}
+
+/// How a fetch quest left the log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuestOutcome {
+    Fulfilled,
+    Expired,
+}
+
+/// A fetch quest was fulfilled or lapsed.
+#[derive(Event, Message, Debug, Clone, PartialEq)]
+pub struct FetchQuestResolvedEvent {
+    pub quest: QuestId,
+    pub requester: NpcId,
+    pub outcome: QuestOutcome,
+}
+
+/// Chronicle line for a resolved fetch quest, news to everyone but the requester.
+pub fn distill_quest_resolution(
+    event: &FetchQuestResolvedEvent,
+    roster: &ChronicleRoster,
+) -> Option<ChronicleDraft> {
+    let requester = roster.name(event.requester);
+    let (text, significance) = match event.outcome {
+        QuestOutcome::Fulfilled => (
+            format!("The player brought {requester} the goods they asked for"),
+            FULFILLED_QUEST_SIGNIFICANCE,
+        ),
+        QuestOutcome::Expired => (
+            format!("The player never brought {requester} the goods they asked for"),
+            EXPIRED_QUEST_SIGNIFICANCE,
+        ),
+    };
+    Some(ChronicleDraft::new(
+        text,
+        vec![event.requester],
+        significance,
+    ))
+}
+
+/// Only NPCs the player has spoken to, or who can see the player nearby, ask for help.
+pub fn may_offer_quest(met: bool, player_distance: Option<f32>, nearby_distance: f32) -> bool {
+    met || player_distance.is_some_and(|distance| distance <= nearby_distance)
+}
+
+/// The first good (in `TradeGood::ALL` order) that satisfies `category`.
+pub fn quest_good(
+    matrix: &EconomyDependencyMatrix,
+    category: DependencyCategory,
+) -> Option<TradeGood> {
+    TradeGood::ALL
+        .into_iter()
+        .find(|good| matrix.categories_for_good(*good).contains(&category))
+}
+
+/// Remembers every NPC the player greets, so they may later ask for help.
+pub fn note_player_acquaintances(
+    mut events: MessageReader<PlayerInteractionEvent>,
+    mut log: ResMut<QuestLog>,
+) {
+    for event in events.read() {
+        if let PlayerInteractionEvent::Started { npc, .. } = event {
+            log.meet(*npc);
+        }
+    }
+}
+
+/// Turns a dependency shortfall into a fetch quest for NPCs the player knows or stands
+/// near, and has the NPC explain the need to the player.
+#[allow(clippy::too_many_arguments)]
+pub fn offer_fetch_quests(
+    mut updates: MessageReader<ProfessionDependencyUpdateEvent>,
+    config: Res<QuestConfig>,
+    matrix: Res<EconomyDependencyMatrix>,
+    mut log: ResMut<QuestLog>,
+    mut queue: ResMut<DialogueRequestQueue>,
+    npc_index: Res<NpcIndex>,
+    npcs: Query<(&Identity, &GlobalTransform)>,
+    player: Query<&GlobalTransform, With<Player>>,
+) {
+    let player_position = player.single().ok().map(GlobalTransform::translation);
+    for update in updates.read() {
+        if update.npc.is_player() || log.quest_for(update.npc).is_some() {
+            continue;
+        }
+        let Some((category, good)) = update
+            .missing_categories
+            .iter()
+            .find_map(|category| Some((*category, quest_good(&matrix, *category)?)))
+        else {
+            continue;
+        };
+        let Some((identity, transform)) = npc_index
+            .entity(update.npc)
+            .and_then(|entity| npcs.get(entity).ok())
+        else {
+            continue;
+        };
+        let distance = player_position.map(|position| position.distance(transform.translation()));
+        if !may_offer_quest(log.has_met(update.npc), distance, config.nearby_distance) {
+            continue;
+        }
+
+        let expires_day = update.day + config.duration_days;
+        let Some(id) = log.offer(
+            update.npc,
+            good,
+            config.quantity,
+            config.reward_affinity,
+            expires_day,
+        ) else {
+            continue;
+        };
+        let goods = format_quantity(good.label(), config.quantity);
+        info!(
+            "{} asks the player for {goods} (quest #{})",
+            identity.display_name,
+            id.value()
+        );
+        let queued = DialogueRequest::builder(update.npc)
+            .target(NpcId::player())
+            .speaker_name(&identity.display_name)
+            .topic(DialogueTopicHint::Status)
+            .prompt(format!(
+                "{} is short of {} and asks the player to bring {goods} by day {}. Explain why they need it.",
+                identity.display_name,
+                category.label(),
+                expires_day
+            ))
+            .summary(format!(
+                "{} needs {goods} for {}.",
+                identity.display_name,
+                category.label()
+            ))
+            .enqueue(&mut queue);
+        if let Err(error) = queued {
+            warn!(
+                "Quest request from {} not queued: {}",
+                identity.display_name, error
+            );
+        }
+    }
+}
+
+/// Counts goods the player gives through the crate panel towards open quests. A fulfilled
+/// quest grants its affinity and a motivation boost, and the NPC thanks the player.
+#[allow(clippy::too_many_arguments)]
+pub fn fulfill_fetch_quests(
+    mut trades: MessageReader<TradeCompletedEvent>,
+    config: Res<QuestConfig>,
+    mut log: ResMut<QuestLog>,
+    mut queue: ResMut<DialogueRequestQueue>,
+    mut adjustments: MessageWriter<MotivationAdjustmentEvent>,
+    mut resolved: MessageWriter<FetchQuestResolvedEvent>,
+    npc_index: Res<NpcIndex>,
+    identities: Query<&Identity>,
+) {
+    for trade in trades.read() {
+        if trade.reason != TradeReason::PlayerTransfer || trade.from != Some(NpcId::player()) {
+            continue;
+        }
+        let Some(npc) = trade.to else {
+            continue;
+        };
+        let Some(quest) = log.record_delivery(npc, trade.good, trade.quantity) else {
+            continue;
+        };
+
+        log.adjust_affinity(npc, quest.reward_affinity);
+        resolved.write(FetchQuestResolvedEvent {
+            quest: quest.id,
+            requester: npc,
+            outcome: QuestOutcome::Fulfilled,
+        });
+        adjustments.write(MotivationAdjustmentEvent::new(
+            npc,
+            config.motivation_reward,
+            MotivationReason::QuestFulfilled,
+        ));
+        let name = Identity::name_of(&npc_index, &identities, npc);
+        let goods = format_quantity(quest.good.label(), quest.quantity);
+        info!(
+            "{name} received {goods} from the player (quest #{})",
+            quest.id.value()
+        );
+        let queued = DialogueRequest::builder(npc)
+            .target(NpcId::player())
+            .speaker_name(&name)
+            .topic(DialogueTopicHint::Trade)
+            .trade_event(TradeContext {
+                day: trade.day,
+                time_of_day: None,
+                from: trade.from,
+                to: trade.to,
+                descriptor: TradeDescriptor::new(quest.good.label(), quest.quantity),
+                reason: TradeContextReason::PlayerTransfer,
+            })
+            .prompt(format!(
+                "{name} thanks the player warmly for bringing the {goods} they asked for."
+            ))
+            .summary(format!("The player brought {name} {goods}."))
+            .enqueue(&mut queue);
+        if let Err(error) = queued {
+            warn!("Thank-you from {name} not queued: {error}");
+        }
+    }
+}
+
+/// Closes quests past their last day; the requester's affinity drops a little.
+pub fn expire_fetch_quests(
+    clock: Res<WorldClock>,
+    config: Res<QuestConfig>,
+    mut log: ResMut<QuestLog>,
+    mut resolved: MessageWriter<FetchQuestResolvedEvent>,
+) {
+    let today = clock.day_count();
+    // Only touch the log when something lapses, so the crate panel is not rebuilt every frame.
+    if log.active().all(|quest| quest.expires_day >= today) {
+        return;
+    }
+    for quest in log.expire(today) {
+        info!(
+            "Quest #{} for {} expired undelivered",
+            quest.id.value(),
+            quest.requester
+        );
+        log.adjust_affinity(quest.requester, -config.expiry_penalty);
+        resolved.write(FetchQuestResolvedEvent {
+            quest: quest.id,
+            requester: quest.requester,
+            outcome: QuestOutcome::Expired,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::economy::components::Profession;
+    use crate::npc::spatial::index_npcs;
+    use crate::player::quest_log::FetchQuest;
+
+    fn quest_app() -> App {
+        let mut app = App::new();
+        app.insert_resource(WorldClock::new())
+            .init_resource::<QuestConfig>()
+            .init_resource::<QuestLog>()
+            .init_resource::<EconomyDependencyMatrix>()
+            .init_resource::<DialogueRequestQueue>()
+            .init_resource::<NpcIndex>()
+            .add_message::<PlayerInteractionEvent>()
+            .add_message::<ProfessionDependencyUpdateEvent>()
+            .add_message::<TradeCompletedEvent>()
+            .add_message::<MotivationAdjustmentEvent>()
+            .add_message::<FetchQuestResolvedEvent>()
+            .add_systems(
+                Update,
+                (
+                    index_npcs,
+                    note_player_acquaintances,
+                    offer_fetch_quests,
+                    fulfill_fetch_quests,
+                    expire_fetch_quests,
+                )
+                    .chain(),
+            );
+        app
+    }
+
+    fn spawn_npc(app: &mut App, id: u64, name: &str, x: f32) -> NpcId {
+        let npc = NpcId::new(id);
+        app.world_mut().spawn((
+            Identity::new(npc, name, 30.0),
+            GlobalTransform::from_xyz(x, 0.0, 0.0),
+        ));
+        npc
+    }
+
+    fn short_of_food(app: &mut App, npc: NpcId, day: u64) {
+        app.world_mut()
+            .write_message(ProfessionDependencyUpdateEvent {
+                day,
+                npc,
+                profession: Profession::Miller,
+                satisfied_categories: Vec::new(),
+                missing_categories: vec![DependencyCategory::Food],
+            });
+    }
+
+    fn give(app: &mut App, npc: NpcId, good: TradeGood, quantity: u32) {
+        app.world_mut().write_message(TradeCompletedEvent {
+            day: 0,
+            from: Some(NpcId::player()),
+            to: Some(npc),
+            good,
+            quantity,
+            reason: TradeReason::PlayerTransfer,
+        });
+    }
+
+    fn queued_for_player(app: &App) -> usize {
+        app.world()
+            .resource::<DialogueRequestQueue>()
+            .iter_pending()
+            .filter(|view| view.target == Some(NpcId::player()))
+            .count()
+    }
+
+    #[test]
+    fn only_acquaintances_or_nearby_npcs_ask_for_help() {
+        assert!(may_offer_quest(true, None, 8.0));
+        assert!(may_offer_quest(false, Some(8.0), 8.0));
+        assert!(!may_offer_quest(false, Some(8.5), 8.0));
+        assert!(!may_offer_quest(false, None, 8.0));
+
+        let mut app = quest_app();
+        app.world_mut()
+            .spawn((Player, GlobalTransform::from_xyz(0.0, 0.0, 0.0)));
+        let stranger = spawn_npc(&mut app, 1, "Alric", 30.0);
+        let neighbour = spawn_npc(&mut app, 2, "Bryn", 2.0);
+        let friend = spawn_npc(&mut app, 3, "Cedric", 30.0);
+        app.world_mut()
+            .write_message(PlayerInteractionEvent::Started {
+                npc: friend,
+                distance: 2.0,
+            });
+        for npc in [stranger, neighbour, friend] {
+            short_of_food(&mut app, npc, 0);
+        }
+        app.update();
+
+        let log = app.world().resource::<QuestLog>();
+        assert!(log.quest_for(stranger).is_none());
+        let quest = log.quest_for(neighbour).expect("nearby NPC asks");
+        assert_eq!(quest.good, TradeGood::Grain);
+        assert_eq!(quest.quantity, DEFAULT_QUEST_QUANTITY);
+        assert_eq!(quest.expires_day, DEFAULT_QUEST_DAYS);
+        assert!(
+            log.quest_for(friend).is_some(),
+            "acquaintance asks from afar"
+        );
+        assert_eq!(queued_for_player(&app), 2, "each asker explains the need");
+
+        short_of_food(&mut app, neighbour, 1);
+        app.update();
+        assert_eq!(
+            app.world().resource::<QuestLog>().active().count(),
+            2,
+            "one open quest per NPC"
+        );
+    }
+
+    #[test]
+    fn handing_over_the_goods_fulfils_the_quest_and_pays_the_reward() {
+        let mut app = quest_app();
+        let npc = spawn_npc(&mut app, 2, "Bryn", 0.0);
+        app.world_mut().resource_mut::<QuestLog>().meet(npc);
+        short_of_food(&mut app, npc, 0);
+        app.update();
+        let mut cursor = app
+            .world()
+            .resource::<Messages<MotivationAdjustmentEvent>>()
+            .get_cursor();
+
+        give(&mut app, npc, TradeGood::Flour, 5);
+        give(&mut app, npc, TradeGood::Grain, 1);
+        app.update();
+        let log = app.world().resource::<QuestLog>();
+        assert_eq!(log.quest_for(npc).map(FetchQuest::remaining), Some(1));
+        assert_eq!(log.affinity(npc), 0);
+
+        give(&mut app, npc, TradeGood::Grain, 1);
+        app.update();
+        let log = app.world().resource::<QuestLog>();
+        assert!(log.quest_for(npc).is_none());
+        assert_eq!(log.affinity(npc), DEFAULT_REWARD_AFFINITY);
+        let boosts: Vec<MotivationAdjustmentEvent> = cursor
+            .read(
+                app.world()
+                    .resource::<Messages<MotivationAdjustmentEvent>>(),
+            )
+            .copied()
+            .collect();
+        assert_eq!(
+            boosts,
+            vec![MotivationAdjustmentEvent::new(
+                npc,
+                DEFAULT_MOTIVATION_REWARD,
+                MotivationReason::QuestFulfilled
+            )]
+        );
+        assert_eq!(queued_for_player(&app), 2, "request plus thank-you");
+    }
+
+    #[test]
+    fn unfulfilled_quests_expire_with_an_affinity_penalty() {
+        let mut log = QuestLog::default();
+        let npc = NpcId::new(4);
+        let id = log.offer(npc, TradeGood::Tools, 1, 3, 2).unwrap();
+        assert_eq!(log.offer(npc, TradeGood::Ale, 1, 3, 2), None);
+        assert!(log.expire(2).is_empty(), "the last day is still open");
+        assert_eq!(log.expire(3).first().map(|quest| quest.id), Some(id));
+        assert!(log.quest_for(npc).is_none());
+
+        let mut app = quest_app();
+        app.world_mut()
+            .resource_mut::<QuestLog>()
+            .offer(npc, TradeGood::Tools, 1, 3, 0);
+        app.world_mut().resource_mut::<WorldClock>().skip_days(1);
+        app.update();
+        let log = app.world().resource::<QuestLog>();
+        assert_eq!(log.active().count(), 0);
+        assert_eq!(log.affinity(npc), -DEFAULT_EXPIRY_PENALTY);
+        let resolved = app.world().resource::<Messages<FetchQuestResolvedEvent>>();
+        let outcomes: Vec<QuestOutcome> = resolved
+            .get_cursor()
+            .read(resolved)
+            .map(|event| event.outcome)
+            .collect();
+        assert_eq!(outcomes, [QuestOutcome::Expired]);
+    }
+
+    #[test]
+    fn resolved_quests_become_chronicle_lines_about_the_requester() {
+        let bryn = Identity::new(NpcId::new(2), "Bryn", 30.0);
+        let roster = ChronicleRoster::new([(&bryn, None)]);
+        let mut event = FetchQuestResolvedEvent {
+            quest: QuestId::new(1),
+            requester: bryn.id,
+            outcome: QuestOutcome::Fulfilled,
+        };
+        let fulfilled = distill_quest_resolution(&event, &roster).unwrap();
+        assert_eq!(
+            fulfilled.text,
+            "The player brought Bryn the goods they asked for"
+        );
+        assert_eq!(fulfilled.involved, [bryn.id]);
+
+        event.outcome = QuestOutcome::Expired;
+        let expired = distill_quest_resolution(&event, &roster).unwrap();
+        assert!(expired.text.starts_with("The player never brought Bryn"));
+        assert!(expired.significance < fulfilled.significance);
+    }
+}
@@ -0,0 +1,512 @@
+//! Village reputation: a single score for how the village regards the player. Giving goods
+//! and fulfilling fetch quests raise it; taking goods, letting quests lapse, and walking away
+//! mid-conversation lower it. It drifts back toward neutral every in-game day. The score maps
+//! to a `ReputationTier` that NPCs addressing the player hear about, so greetings warm or
+//! cool with it.
+use std::{fs, path::Path};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    core::config::{ConfigDiagnostics, ConfigReloadRequested},
+    dialogue::{
+        events::{ConversationEndReason, PlayerInteractionEvent},
+        types::{DialogueContextEvent, DialogueRequest},
+    },
+    economy::events::{TradeCompletedEvent, TradeReason},
+    npc::components::NpcId,
+    player::quests::{FetchQuestResolvedEvent, QuestOutcome},
+    world::time::DayChangedEvent,
+};
+
+pub const CONFIG_PATH: &str = "config/reputation.toml";
+const REPUTATION_CONTEXT_PREFIX: &str = "Village reputation:";
+
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(default)]
+struct RawReputationConfig {
+    reputation: RawReputation,
+    changes: ReputationChanges,
+    tiers: ReputationThresholds,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+struct RawReputation {
+    max_score: f32,
+    decay_per_day: f32,
+}
+
+impl Default for RawReputation {
+    fn default() -> Self {
+        Self {
+            max_score: 100.0,
+            decay_per_day: 2.0,
+        }
+    }
+}
+
+/// Score change for each player action.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ReputationChanges {
+    /// Per unit the player hands to an NPC through the crate panel.
+    pub give_per_unit: f32,
+    /// Per unit the player takes from an NPC's crate; usually negative.
+    pub take_per_unit: f32,
+    pub quest_fulfilled: f32,
+    pub quest_expired: f32,
+    /// The player turned to someone else mid-conversation.
+    pub walked_away: f32,
+}
+
+impl Default for ReputationChanges {
+    fn default() -> Self {
+        Self {
+            give_per_unit: 1.0,
+            take_per_unit: -1.5,
+            quest_fulfilled: 10.0,
+            quest_expired: -6.0,
+            walked_away: -2.0,
+        }
+    }
+}
+
+/// Scores at which the tiers begin. Below `hostile_below` is Hostile, from `friendly_from`
+/// Friendly, from `beloved_from` Beloved, and Neutral in between.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ReputationThresholds {
+    pub hostile_below: f32,
+    pub friendly_from: f32,
+    pub beloved_from: f32,
+}
+
+impl Default for ReputationThresholds {
+    fn default() -> Self {
+        Self {
+            hostile_below: -40.0,
+            friendly_from: 20.0,
+            beloved_from: 60.0,
+        }
+    }
+}
+
+impl ReputationThresholds {
+    pub fn tier_for(&self, score: f32) -> ReputationTier {
+        if score < self.hostile_below {
+            ReputationTier::Hostile
+        } else if score >= self.beloved_from {
+            ReputationTier::Beloved
+        } else if score >= self.friendly_from {
+            ReputationTier::Friendly
+        } else {
+            ReputationTier::Neutral
+        }
+    }
+}
+
+/// Reputation tunables from `config/reputation.toml`.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct ReputationConfig {
+    /// The score stays within `[-max_score, max_score]`.
+    pub max_score: f32,
+    /// Points the score moves toward 0 per in-game day.
+    pub decay_per_day: f32,
+    pub changes: ReputationChanges,
+    pub tiers: ReputationThresholds,
+}
+
+impl Default for ReputationConfig {
+    fn default() -> Self {
+        Self::try_from(RawReputationConfig::default()).expect("default reputation config is valid")
+    }
+}
+
+impl ReputationConfig {
+    /// Reads and parses `config/reputation.toml`.
+    pub fn load() -> Result<Self, String> {
+        let data = fs::read_to_string(Path::new(CONFIG_PATH))
+            .map_err(|err| format!("unable to read file: {err}"))?;
+        Self::parse(&data)
+    }
+
+    pub fn parse(data: &str) -> Result<Self, String> {
+        let raw = toml::from_str::<RawReputationConfig>(data)
+            .map_err(|err| format!("invalid reputation config: {err}"))?;
+        Self::try_from(raw)
+    }
+}
+
+impl TryFrom<RawReputationConfig> for ReputationConfig {
+    type Error = String;
+
+    fn try_from(value: RawReputationConfig) -> Result<Self, Self::Error> {
+        let tiers = value.tiers;
+        if !(tiers.hostile_below <= 0.0
+            && 0.0 < tiers.friendly_from
+            && tiers.friendly_from <= tiers.beloved_from)
+        {
+            return Err(
+                "invalid reputation config: tiers need hostile_below <= 0 < friendly_from <= beloved_from"
+                    .to_string(),
+            );
+        }
+        Ok(Self {
+            max_score: value.reputation.max_score.max(0.0),
+            decay_per_day: value.reputation.decay_per_day.max(0.0),
+            changes: value.changes,
+            tiers,
+        })
+    }
+}
+
+/// How the village as a whole regards the player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReputationTier {
+    Hostile,
+    #[default]
+    Neutral,
+    Friendly,
+    Beloved,
+}
+
+impl ReputationTier {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Hostile => "Hostile",
+            Self::Neutral => "Neutral",
+            Self::Friendly => "Friendly",
+            Self::Beloved => "Beloved",
+        }
+    }
+
+    /// Guidance for a speaker greeting the player.
+    fn greeting_note(self) -> &'static str {
+        match self {
+            Self::Hostile => "The village distrusts the player; keep the greeting cool.",
+            Self::Neutral => "The village has no strong opinion of the player yet.",
+            Self::Friendly => "The village speaks well of the player.",
+            Self::Beloved => "The whole village adores the player; greet them warmly.",
+        }
+    }
+}
+
+/// `score + delta`, kept within `[-max_score, max_score]`.
+pub fn adjusted_score(score: f32, delta: f32, max_score: f32) -> f32 {
+    (score + delta).clamp(-max_score, max_score)
+}
+
+/// `score` after `days` of drifting `per_day` points toward 0, never past it.
+pub fn decayed_score(score: f32, days: u64, per_day: f32) -> f32 {
+    let decay = per_day * days as f32;
+    if score > 0.0 {
+        (score - decay).max(0.0)
+    } else {
+        (score + decay).min(0.0)
+    }
+}
+
+/// The score change `trade` earns, if it is the player giving or taking goods.
+pub fn trade_change(trade: &TradeCompletedEvent, changes: &ReputationChanges) -> Option<f32> {
+    if trade.reason != TradeReason::PlayerTransfer {
+        return None;
+    }
+    let per_unit = if trade.from == Some(NpcId::player()) {
+        changes.give_per_unit
+    } else if trade.to == Some(NpcId::player()) {
+        changes.take_per_unit
+    } else {
+        return None;
+    };
+    Some(per_unit * trade.quantity as f32)
+}
+
+/// The village's standing score for the player and its current tier.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct PlayerReputation {
+    score: f32,
+    tier: ReputationTier,
+}
+
+impl PlayerReputation {
+    pub fn score(&self) -> f32 {
+        self.score
+    }
+
+    pub fn tier(&self) -> ReputationTier {
+        self.tier
+    }
+
+    /// Applies `delta` and returns the new tier if it changed.
+    pub fn adjust(&mut self, delta: f32, config: &ReputationConfig) -> Option<ReputationTier> {
+        self.set_score(adjusted_score(self.score, delta, config.max_score), config)
+    }
+
+    /// Decays the score for `days` passed days and returns the new tier if it changed.
+    pub fn decay_days(&mut self, days: u64, config: &ReputationConfig) -> Option<ReputationTier> {
+        if days == 0 {
+            return None;
+        }
+        self.set_score(
+            decayed_score(self.score, days, config.decay_per_day),
+            config,
+        )
+    }
+
+    fn set_score(&mut self, score: f32, config: &ReputationConfig) -> Option<ReputationTier> {
+        self.score = score;
+        let tier = config.tiers.tier_for(score);
+        (tier != std::mem::replace(&mut self.tier, tier)).then_some(tier)
+    }
+
+    /// Adds the tier as context when an NPC addresses the player; other requests are left
+    /// untouched.
+    pub fn attach_to(&self, request: &mut DialogueRequest) {
+        if request.speaker.is_player() || !request.target.is_some_and(|target| target.is_player()) {
+            return;
+        }
+        request.context.events.push(DialogueContextEvent::Custom {
+            text: format!(
+                "{REPUTATION_CONTEXT_PREFIX} {}. {}",
+                self.tier.label(),
+                self.tier.greeting_note()
+            ),
+        });
+    }
+}
+
+/// Moves the reputation for crate gives and takes, resolved fetch quests, and conversations
+/// the player walked away from.
+pub fn track_player_reputation(
+    mut trades: MessageReader<TradeCompletedEvent>,
+    mut quests: MessageReader<FetchQuestResolvedEvent>,
+    mut interactions: MessageReader<PlayerInteractionEvent>,
+    config: Res<ReputationConfig>,
+    mut reputation: ResMut<PlayerReputation>,
+) {
+    let changes = &config.changes;
+    let deltas: Vec<f32> = trades
+        .read()
+        .filter_map(|trade| trade_change(trade, changes))
+        .chain(quests.read().map(|resolved| match resolved.outcome {
+            QuestOutcome::Fulfilled => changes.quest_fulfilled,
+            QuestOutcome::Expired => changes.quest_expired,
+        }))
+        .chain(interactions.read().filter_map(|event| match event {
+            PlayerInteractionEvent::ConversationEnded {
+                ended_by: ConversationEndReason::WalkedAway,
+                ..
+            } => Some(changes.walked_away),
+            _ => None,
+        }))
+        .collect();
+    for delta in deltas {
+        if let Some(tier) = reputation.adjust(delta, &config) {
+            info!(
+                "Village reputation is now {} ({:.0})",
+                tier.label(),
+                reputation.score()
+            );
+        }
+    }
+}
+
+/// Lets the reputation drift toward neutral by the days each `DayChangedEvent` spans.
+pub fn decay_player_reputation(
+    mut day_changes: MessageReader<DayChangedEvent>,
+    config: Res<ReputationConfig>,
+    mut reputation: ResMut<PlayerReputation>,
+) {
+    let days: u64 = day_changes.read().map(DayChangedEvent::days_elapsed).sum();
+    if let Some(tier) = reputation.decay_days(days, &config) {
+        info!(
+            "Village reputation eased to {} ({:.0})",
+            tier.label(),
+            reputation.score()
+        );
+    }
+}
+
+/// Re-reads `config/reputation.toml` on request.
+pub fn reload_reputation_config(
+    mut requests: MessageReader<ConfigReloadRequested>,
+    mut diagnostics: ResMut<ConfigDiagnostics>,
+    mut config: ResMut<ReputationConfig>,
+) {
+    if !requests.read().any(|request| request.is_for(CONFIG_PATH)) {
+        return;
+    }
+    if let Some(reloaded) = diagnostics.report_reload(CONFIG_PATH, ReputationConfig::load()) {
+        *config = reloaded;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dialogue::{
+            broker::openai::render_prompt,
+            prompts::PromptTemplates,
+            types::{DialogueContext, DialogueTopicHint},
+        },
+        economy::components::TradeGood,
+        player::quest_log::QuestLog,
+    };
+
+    #[test]
+    fn scores_stay_bounded_and_decay_toward_neutral() {
+        assert_eq!(adjusted_score(95.0, 10.0, 100.0), 100.0);
+        assert_eq!(adjusted_score(-95.0, -10.0, 100.0), -100.0);
+        assert_eq!(adjusted_score(5.0, -2.5, 100.0), 2.5);
+
+        assert_eq!(decayed_score(10.0, 3, 2.0), 4.0);
+        assert_eq!(decayed_score(3.0, 3, 2.0), 0.0, "decay stops at neutral");
+        assert_eq!(decayed_score(-10.0, 2, 2.0), -6.0);
+        assert_eq!(decayed_score(-1.0, 5, 2.0), 0.0);
+        assert_eq!(decayed_score(7.0, 0, 2.0), 7.0);
+
+        let config = ReputationConfig::default();
+        let mut reputation = PlayerReputation::default();
+        assert_eq!(
+            reputation.adjust(25.0, &config),
+            Some(ReputationTier::Friendly)
+        );
+        assert_eq!(reputation.decay_days(0, &config), None);
+        assert_eq!(
+            reputation.decay_days(3, &config),
+            Some(ReputationTier::Neutral)
+        );
+        assert_eq!(reputation.score(), 19.0);
+    }
+
+    #[test]
+    fn tiers_follow_the_configured_thresholds() {
+        let tiers = ReputationThresholds::default();
+        assert_eq!(tiers.tier_for(-40.5), ReputationTier::Hostile);
+        assert_eq!(tiers.tier_for(-40.0), ReputationTier::Neutral);
+        assert_eq!(tiers.tier_for(19.9), ReputationTier::Neutral);
+        assert_eq!(tiers.tier_for(20.0), ReputationTier::Friendly);
+        assert_eq!(tiers.tier_for(60.0), ReputationTier::Beloved);
+
+        let config = ReputationConfig::parse(
+            "[reputation]\ndecay_per_day = 5.0\n[changes]\nwalked_away = -4.0\n[tiers]\nfriendly_from = 10.0\n",
+        )
+        .unwrap();
+        assert_eq!(config.decay_per_day, 5.0);
+        assert_eq!(config.changes.walked_away, -4.0);
+        assert_eq!(
+            config.changes.quest_fulfilled, 10.0,
+            "unset keys keep defaults"
+        );
+        assert_eq!(config.tiers.tier_for(10.0), ReputationTier::Friendly);
+        assert!(ReputationConfig::parse("[tiers]\nfriendly_from = 80.0\n").is_err());
+        assert!(ReputationConfig::parse("[tiers]\nhostile_below = 5.0\n").is_err());
+    }
+
+    #[test]
+    fn trades_only_count_when_the_player_gives_or_takes() {
+        let changes = ReputationChanges::default();
+        let trade = |from: NpcId, to: NpcId, reason: TradeReason| TradeCompletedEvent {
+            day: 1,
+            from: Some(from),
+            to: Some(to),
+            good: TradeGood::Grain,
+            quantity: 2,
+            reason,
+        };
+        let (player, npc) = (NpcId::player(), NpcId::new(3));
+        assert_eq!(
+            trade_change(&trade(player, npc, TradeReason::PlayerTransfer), &changes),
+            Some(2.0)
+        );
+        assert_eq!(
+            trade_change(&trade(npc, player, TradeReason::PlayerTransfer), &changes),
+            Some(-3.0)
+        );
+        assert_eq!(
+            trade_change(&trade(npc, NpcId::new(4), TradeReason::Exchange), &changes),
+            None
+        );
+    }
+
+    #[test]
+    fn scripted_gives_and_quests_make_the_village_friendly_in_prompts() {
+        let mut app = App::new();
+        app.init_resource::<ReputationConfig>()
+            .init_resource::<PlayerReputation>()
+            .add_message::<TradeCompletedEvent>()
+            .add_message::<FetchQuestResolvedEvent>()
+            .add_message::<PlayerInteractionEvent>()
+            .add_systems(Update, track_player_reputation);
+        let (player, npc) = (NpcId::player(), NpcId::new(2));
+        let mut quests = QuestLog::default();
+        let mut quest = |requester| quests.offer(requester, TradeGood::Flour, 2, 3, 9).unwrap();
+        let give = |quantity| TradeCompletedEvent {
+            day: 1,
+            from: Some(player),
+            to: Some(npc),
+            good: TradeGood::Grain,
+            quantity,
+            reason: TradeReason::PlayerTransfer,
+        };
+
+        app.world_mut().write_message(give(4));
+        app.world_mut().write_message(FetchQuestResolvedEvent {
+            quest: quest(npc),
+            requester: npc,
+            outcome: QuestOutcome::Fulfilled,
+        });
+        app.update();
+        // 4 + 10 so far; taking a crate back and walking away cost 1.5 + 2.
+        app.world_mut().write_message(TradeCompletedEvent {
+            from: Some(npc),
+            to: Some(player),
+            quantity: 1,
+            ..give(1)
+        });
+        app.world_mut()
+            .write_message(PlayerInteractionEvent::ConversationEnded {
+                npc,
+                turns: 0,
+                ended_by: ConversationEndReason::WalkedAway,
+            });
+        app.world_mut().write_message(give(3));
+        app.world_mut().write_message(FetchQuestResolvedEvent {
+            quest: quest(NpcId::new(3)),
+            requester: NpcId::new(3),
+            outcome: QuestOutcome::Fulfilled,
+        });
+        app.update();
+
+        let reputation = app.world().resource::<PlayerReputation>();
+        assert_eq!(reputation.score(), 23.5);
+        assert_eq!(reputation.tier(), ReputationTier::Friendly);
+
+        let mut request = DialogueRequest::new(
+            npc,
+            Some(player),
+            "Greet the player.",
+            DialogueTopicHint::Status,
+            DialogueContext::default(),
+        );
+        reputation.attach_to(&mut request);
+        let prompt = render_prompt(&PromptTemplates::default(), &request);
+        assert!(
+            prompt.contains("Village reputation: Friendly. The village speaks well of the player."),
+            "{prompt}"
+        );
+
+        let mut ambient = DialogueRequest::new(
+            npc,
+            Some(NpcId::new(5)),
+            "Chat.",
+            DialogueTopicHint::Status,
+            DialogueContext::default(),
+        );
+        reputation.attach_to(&mut ambient);
+        assert!(ambient.context.events.is_empty());
+    }
+}
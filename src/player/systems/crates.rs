@@ -0,0 +1,327 @@
+//! Profession crates: opening the nearest one with interact, its panel of goods, and moving
+//! goods between the player and the crate's owner.
+use crate::{
+    core::input::{ActionInput, InputAction},
+    economy::{
+        components::{Inventory, Profession, ProfessionCrate, TradeGood},
+        events::{InventoryChangedEvent, TradeCompletedEvent},
+        reservations::ReservedStock,
+        systems::spawning::CrateOwners,
+    },
+    npc::components::Identity,
+    player::{
+        components::{
+            CrateTransferButton, NearbyCrateInfo, Player, PlayerCratePanel, PlayerInteractionState,
+        },
+        inventory::{transfer_with_npc, CrateTransferDirection, PlayerInventory},
+        quest_log::{FetchQuest, QuestLog},
+    },
+    ui::{layout::ScreenAnchor, visibility::UiLayer},
+    world::time::WorldClock,
+};
+use bevy::log::{debug, info, warn};
+use bevy::prelude::*;
+
+use super::despawn_with_children;
+
+/// Maximum distance (in world units) for opening a profession crate.
+const CRATE_INTERACTION_RANGE: f32 = 2.5;
+
+/// Goods moved per crate button press.
+const CRATE_TRANSFER_QUANTITY: u32 = 1;
+
+/// Detects the nearest profession crate within reach of the player.
+pub fn detect_nearby_crates(
+    player_query: Query<&Transform, With<Player>>,
+    crate_query: Query<(&GlobalTransform, &ProfessionCrate)>,
+    mut interaction_state: ResMut<PlayerInteractionState>,
+) {
+    let Ok(player_transform) = player_query.single() else {
+        interaction_state.nearby_crate = None;
+        return;
+    };
+    let player_pos = player_transform.translation;
+
+    interaction_state.nearby_crate = crate_query
+        .iter()
+        .map(|(transform, profession_crate)| NearbyCrateInfo {
+            profession: profession_crate.profession,
+            distance: player_pos.distance(transform.translation()),
+        })
+        .filter(|info| info.distance <= CRATE_INTERACTION_RANGE)
+        .min_by(|a, b| a.distance.total_cmp(&b.distance));
+}
+
+/// Toggles the crate panel with interact (E) when a crate is the closest interactable.
+pub fn handle_crate_interaction_input(
+    input: ActionInput,
+    mut interaction_state: ResMut<PlayerInteractionState>,
+) {
+    if !input.just_pressed(InputAction::Interact) {
+        return;
+    }
+
+    if interaction_state.open_crate.take().is_some() {
+        return;
+    }
+
+    if !interaction_state.crate_has_priority() {
+        return;
+    }
+
+    if let Some(nearby) = interaction_state.nearby_crate.clone() {
+        info!(
+            "Player opens the {} crate (distance: {:.1})",
+            nearby.profession.label(),
+            nearby.distance
+        );
+        interaction_state.open_crate = Some(nearby.profession);
+        interaction_state.crate_panel_dirty = true;
+    }
+}
+
+/// Applies crate button presses through `transfer_with_npc`, forwarding the NPC-side
+/// inventory change and a `TradeReason::PlayerTransfer` trade event. Stock the owner has
+/// reserved for today's recipes cannot be taken.
+#[allow(clippy::too_many_arguments)]
+pub fn handle_crate_transfer_buttons(
+    clock: Res<WorldClock>,
+    mut interaction_state: ResMut<PlayerInteractionState>,
+    mut player_inventory: ResMut<PlayerInventory>,
+    reserved: Res<ReservedStock>,
+    crate_owners: CrateOwners,
+    mut owners: Query<(&Identity, &mut Inventory)>,
+    buttons: Query<(&Interaction, &CrateTransferButton), Changed<Interaction>>,
+    mut inventory_writer: MessageWriter<InventoryChangedEvent>,
+    mut trade_writer: MessageWriter<TradeCompletedEvent>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        let Some((identity, mut inventory)) = crate_owners
+            .owner_of(button.profession)
+            .and_then(|owner| owners.get_mut(owner).ok())
+        else {
+            warn!(
+                "No NPC owns the {} crate; transfer ignored",
+                button.profession.label()
+            );
+            continue;
+        };
+
+        let Some(transfer) = transfer_with_npc(
+            button.direction,
+            identity.id,
+            &mut inventory,
+            reserved.reserved(identity.id, button.good),
+            &mut player_inventory,
+            button.good,
+            CRATE_TRANSFER_QUANTITY,
+            clock.day_count(),
+        ) else {
+            debug!(
+                "Crate transfer of {} skipped: not enough unreserved stock",
+                button.good.label()
+            );
+            continue;
+        };
+
+        info!(
+            "Player {} {} {}",
+            match button.direction {
+                CrateTransferDirection::Take => "takes from",
+                CrateTransferDirection::Give => "gives to",
+            },
+            identity.display_name,
+            button.good.label()
+        );
+        inventory_writer.write(InventoryChangedEvent::from_change(
+            identity.id,
+            clock.day_count(),
+            transfer.npc_change,
+        ));
+        trade_writer.write(transfer.trade);
+        interaction_state.crate_panel_dirty = true;
+    }
+}
+
+/// Keeps the crate panel in sync: closes it when the player walks away and rebuilds it
+/// after transfers so quantities stay current.
+pub fn sync_crate_panel(
+    mut commands: Commands,
+    mut interaction_state: ResMut<PlayerInteractionState>,
+    player_inventory: Res<PlayerInventory>,
+    quests: Res<QuestLog>,
+    crate_owners: CrateOwners,
+    owners: Query<(&Identity, &Inventory)>,
+    children_query: Query<&Children>,
+) {
+    let still_near = match (
+        &interaction_state.open_crate,
+        &interaction_state.nearby_crate,
+    ) {
+        (Some(open), Some(nearby)) => *open == nearby.profession,
+        _ => false,
+    };
+    if !still_near {
+        interaction_state.open_crate = None;
+    }
+
+    let Some(profession) = interaction_state.open_crate else {
+        if let Some(panel) = interaction_state.crate_panel.take() {
+            despawn_with_children(&mut commands, panel, &children_query);
+        }
+        return;
+    };
+
+    if !interaction_state.crate_panel_dirty
+        && !quests.is_changed()
+        && interaction_state.crate_panel.is_some()
+    {
+        return;
+    }
+    interaction_state.crate_panel_dirty = false;
+
+    if let Some(panel) = interaction_state.crate_panel.take() {
+        despawn_with_children(&mut commands, panel, &children_query);
+    }
+
+    let owner = crate_owners
+        .owner_of(profession)
+        .and_then(|owner| owners.get(owner).ok());
+    let quest = owner.and_then(|(identity, _)| quests.quest_for(identity.id));
+    let panel = spawn_crate_panel(&mut commands, profession, owner, quest, &player_inventory);
+    interaction_state.crate_panel = Some(panel);
+}
+
+fn spawn_crate_panel(
+    commands: &mut Commands,
+    profession: Profession,
+    owner: Option<(&Identity, &Inventory)>,
+    quest: Option<&FetchQuest>,
+    player_inventory: &PlayerInventory,
+) -> Entity {
+    let title = match owner {
+        Some((identity, _)) => {
+            format!("{}'s {} crate", identity.display_name, profession.label())
+        }
+        None => format!("Unattended {} crate", profession.label()),
+    };
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(20.0),
+                left: Val::Px(20.0),
+                width: Val::Px(340.0),
+                padding: UiRect::all(Val::Px(12.0)),
+                border: UiRect::all(Val::Px(2.0)),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(8.0),
+                ..Default::default()
+            },
+            BackgroundColor(Color::srgba(0.08, 0.08, 0.1, 0.95)),
+            BorderColor::from(Color::srgb(0.3, 0.3, 0.32)),
+            PlayerCratePanel,
+            ScreenAnchor::TopLeft,
+            UiLayer::Screen,
+            Name::new("Player Crate Panel"),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(title),
+                TextFont {
+                    font_size: 16.0,
+                    ..Default::default()
+                },
+                TextColor(Color::WHITE),
+            ));
+
+            let Some((identity, inventory)) = owner else {
+                return;
+            };
+
+            if let Some(quest) = quest {
+                parent.spawn((
+                    Text::new(quest.describe(&identity.display_name)),
+                    TextFont {
+                        font_size: 14.0,
+                        ..Default::default()
+                    },
+                    TextColor(Color::srgb(0.95, 0.85, 0.5)),
+                ));
+            }
+
+            for good in TradeGood::ALL {
+                parent
+                    .spawn(Node {
+                        width: Val::Percent(100.0),
+                        flex_direction: FlexDirection::Row,
+                        align_items: AlignItems::Center,
+                        column_gap: Val::Px(6.0),
+                        ..Default::default()
+                    })
+                    .with_children(|row| {
+                        row.spawn((
+                            Node {
+                                flex_grow: 1.0,
+                                ..Default::default()
+                            },
+                            Text::new(format!(
+                                "{}: {} (you: {})",
+                                good.label(),
+                                inventory.quantity_of(good),
+                                player_inventory.quantity_of(good)
+                            )),
+                            TextFont {
+                                font_size: 14.0,
+                                ..Default::default()
+                            },
+                            TextColor(Color::WHITE),
+                        ));
+
+                        for direction in
+                            [CrateTransferDirection::Take, CrateTransferDirection::Give]
+                        {
+                            row.spawn((
+                                Node {
+                                    padding: UiRect::axes(Val::Px(8.0), Val::Px(4.0)),
+                                    border: UiRect::all(Val::Px(1.5)),
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    ..Default::default()
+                                },
+                                Button,
+                                Interaction::None,
+                                BackgroundColor(Color::srgba(0.18, 0.18, 0.22, 0.95)),
+                                BorderColor::from(Color::srgb(0.4, 0.4, 0.45)),
+                                CrateTransferButton {
+                                    profession,
+                                    good,
+                                    direction,
+                                },
+                                Name::new(format!(
+                                    "Crate {} Button {}",
+                                    direction.label(),
+                                    good.label()
+                                )),
+                            ))
+                            .with_children(|button| {
+                                button.spawn((
+                                    Text::new(direction.label()),
+                                    TextFont {
+                                        font_size: 13.0,
+                                        ..Default::default()
+                                    },
+                                    TextColor(Color::WHITE),
+                                ));
+                            });
+                        }
+                    });
+            }
+        })
+        .id()
+}
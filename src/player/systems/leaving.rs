@@ -0,0 +1,220 @@
+//! Ending a conversation with the player: walking out of range, or choosing "Leave",
+//! "Goodbye", or "Talk again" in a notice or the response window.
+use crate::{
+    dialogue::{
+        events::{ConversationEndReason, PlayerInteractionEvent},
+        queue::DialogueRequestQueue,
+    },
+    npc::spatial::NpcIndex,
+    player::components::{Player, PlayerInteractionState, PlayerNoticeAction, PlayerNoticeButton},
+};
+use bevy::log::{debug, info, warn};
+use bevy::prelude::*;
+
+use super::{despawn_with_children, greeting_request};
+
+/// Distance at which an open conversation ends as walked away; a little past
+/// `INTERACTION_RANGE` so shuffling at the edge does not end it.
+const WALK_AWAY_RANGE: f32 = 4.0;
+
+/// Ends the conversation as walked away once the player is farther than `WALK_AWAY_RANGE`
+/// from the NPC they are talking to, or that NPC is gone. The window closes and an
+/// unanswered request that has not been dispatched is withdrawn.
+#[allow(clippy::too_many_arguments)]
+pub fn end_conversation_on_walk_away(
+    mut commands: Commands,
+    mut interaction_state: ResMut<PlayerInteractionState>,
+    mut queue: ResMut<DialogueRequestQueue>,
+    player_query: Query<&Transform, With<Player>>,
+    npc_index: Res<NpcIndex>,
+    npcs: Query<&Transform, Without<Player>>,
+    children_query: Query<&Children>,
+    mut interaction_events: MessageWriter<PlayerInteractionEvent>,
+) {
+    let Some(npc_id) = interaction_state
+        .conversation
+        .as_ref()
+        .map(|conversation| conversation.npc_id)
+    else {
+        return;
+    };
+    let Ok(player) = player_query.single() else {
+        return;
+    };
+    let in_range = npc_index
+        .entity(npc_id)
+        .and_then(|entity| npcs.get(entity).ok())
+        .is_some_and(|npc| npc.translation.distance(player.translation) <= WALK_AWAY_RANGE);
+    if in_range {
+        return;
+    }
+
+    if let Some(window) = interaction_state.response_window.take() {
+        despawn_with_children(&mut commands, window, &children_query);
+    }
+    if let Some(pending) = interaction_state.pending_request {
+        if queue.cancel(pending) {
+            debug!("Withdrew unanswered {}", pending);
+        }
+    }
+    if let Some(ended) = interaction_state.leave_conversation(ConversationEndReason::WalkedAway) {
+        info!("Player walked away from {}", npc_id);
+        interaction_events.write(ended);
+    }
+}
+
+/// Handles "Leave" / "Talk again" presses in the failure notice window and "Goodbye" in the
+/// response window. Leaving or saying goodbye ends the tracked conversation as a goodbye.
+#[allow(clippy::type_complexity)]
+pub fn handle_player_notice_buttons(
+    mut commands: Commands,
+    mut interaction_state: ResMut<PlayerInteractionState>,
+    mut queue: ResMut<DialogueRequestQueue>,
+    children_query: Query<&Children>,
+    buttons: Query<(&Interaction, &PlayerNoticeButton), (Changed<Interaction>, With<Button>)>,
+    mut interaction_events: MessageWriter<PlayerInteractionEvent>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        if let Some(window) = interaction_state.response_window.take() {
+            despawn_with_children(&mut commands, window, &children_query);
+        }
+        interaction_state.failure_notice = false;
+        interaction_state.retry_offer = None;
+
+        if let PlayerNoticeAction::Leave | PlayerNoticeAction::Goodbye = button.action {
+            if let Some(pending) = interaction_state.pending_request {
+                queue.cancel(pending);
+            }
+            if let Some(ended) =
+                interaction_state.leave_conversation(ConversationEndReason::Goodbye)
+            {
+                interaction_events.write(ended);
+            }
+        }
+
+        if let PlayerNoticeAction::TalkAgain(offer) = &button.action {
+            let request_id = match greeting_request(offer.npc_id, &offer.name).enqueue(&mut queue) {
+                Ok(id) => id,
+                Err(error) => {
+                    warn!("Greeting for {} not queued: {}", offer.name, error);
+                    continue;
+                }
+            };
+            interaction_state.active_dialogue = Some(offer.npc_id);
+            interaction_state.pending_request = Some(request_id);
+            interaction_state.active_npc_name = Some(offer.name.clone());
+            info!("Player tries {} again ({})", offer.name, request_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::npc::components::{Identity, NpcId};
+
+    use super::super::{
+        notices::GOODBYE_OPTION, response_window::spawn_player_response_window, test_support::*,
+    };
+
+    fn walk_away_app() -> App {
+        let mut app = interrupt_app();
+        app.init_resource::<SeenInteractions>().add_systems(
+            Update,
+            (
+                handle_player_notice_buttons,
+                end_conversation_on_walk_away,
+                collect_interactions,
+            )
+                .chain()
+                .after(spawn_player_response_window),
+        );
+        app.world_mut().spawn((Player, Transform::default()));
+        let brom = app
+            .world_mut()
+            .query::<(Entity, &Identity)>()
+            .iter(app.world())
+            .find(|(_, identity)| identity.id == NpcId::new(3))
+            .map(|(entity, _)| entity)
+            .unwrap();
+        app.world_mut()
+            .entity_mut(brom)
+            .insert(Transform::from_xyz(1.0, 0.0, 0.0));
+        app
+    }
+
+    fn ended(app: &App) -> Vec<ConversationEndReason> {
+        app.world()
+            .resource::<SeenInteractions>()
+            .0
+            .iter()
+            .filter_map(|event| match event {
+                PlayerInteractionEvent::ConversationEnded { ended_by, .. } => Some(*ended_by),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn walking_out_of_range_closes_the_window_as_walked_away() {
+        let mut app = walk_away_app();
+        let brom = NpcId::new(3);
+
+        press_e_near(&mut app, brom, "Brom");
+        let greeting = pending_request(&app).expect("greeting queued");
+        reply(&mut app, greeting, brom, "Morning.");
+        app.world_mut()
+            .query_filtered::<&mut Transform, With<Player>>()
+            .single_mut(app.world_mut())
+            .unwrap()
+            .translation
+            .x = 3.5;
+        app.update();
+        assert!(app
+            .world()
+            .resource::<PlayerInteractionState>()
+            .response_window
+            .is_some());
+        assert!(ended(&app).is_empty(), "still within walk-away range");
+
+        app.world_mut()
+            .query_filtered::<&mut Transform, With<Player>>()
+            .single_mut(app.world_mut())
+            .unwrap()
+            .translation
+            .x = 8.0;
+        app.update();
+        let state = app.world().resource::<PlayerInteractionState>();
+        assert!(state.response_window.is_none());
+        assert!(state.active_dialogue.is_none() && state.conversation.is_none());
+        assert_eq!(ended(&app), [ConversationEndReason::WalkedAway]);
+    }
+
+    #[test]
+    fn goodbye_closes_the_response_window() {
+        let mut app = walk_away_app();
+        let brom = NpcId::new(3);
+
+        press_e_near(&mut app, brom, "Brom");
+        let greeting = pending_request(&app).expect("greeting queued");
+        reply(&mut app, greeting, brom, "Morning.");
+        assert!(window_texts(&mut app).contains(&GOODBYE_OPTION.to_string()));
+        app.world_mut().spawn((
+            Button,
+            Interaction::Pressed,
+            PlayerNoticeButton {
+                action: PlayerNoticeAction::Goodbye,
+            },
+        ));
+        app.update();
+
+        let state = app.world().resource::<PlayerInteractionState>();
+        assert!(state.response_window.is_none());
+        assert!(state.active_dialogue.is_none());
+        assert_eq!(ended(&app), [ConversationEndReason::Goodbye]);
+    }
+}
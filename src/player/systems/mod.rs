@@ -0,0 +1,363 @@
+//! Systems for player interaction with NPCs and profession crates.
+use crate::{
+    core::input::{ActionInput, InputAction},
+    dialogue::{
+        builder::DialogueRequestBuilder,
+        events::{ConversationEndReason, PlayerInteractionEvent},
+        queue::DialogueRequestQueue,
+        types::DialogueRequest,
+    },
+    npc::{
+        components::{ActiveConversations, Identity, InConversation, NpcId},
+        sleep::Sleeping,
+        spatial::SpatialIndex,
+    },
+    player::components::{NearbyNpcInfo, Player, PlayerConversation, PlayerInteractionState},
+};
+use bevy::log::{debug, info, warn};
+use bevy::prelude::*;
+
+pub mod crates;
+pub mod leaving;
+pub mod notices;
+pub mod response_window;
+#[cfg(test)]
+mod test_support;
+
+pub use crates::{
+    detect_nearby_crates, handle_crate_interaction_input, handle_crate_transfer_buttons,
+    sync_crate_panel,
+};
+pub use leaving::{end_conversation_on_walk_away, handle_player_notice_buttons};
+pub use notices::{handle_player_dialogue_failures, restore_player_interaction_offer};
+pub use response_window::{
+    cleanup_player_response_window, handle_player_response_buttons, spawn_player_response_window,
+};
+
+use notices::spawn_notice_window;
+
+/// Maximum distance (in world units) for player-NPC interaction.
+const INTERACTION_RANGE: f32 = 3.0;
+
+const THINKING_SUFFIX: &str = "is still thinking...";
+
+/// Detects NPCs near the player and updates interaction state. NPCs reserved in
+/// `ActiveConversations` are skipped even before their `InConversation` lands. Candidates
+/// come from the `SpatialIndex`, so only NPCs within range are looked at.
+#[allow(clippy::type_complexity)]
+pub fn detect_nearby_npcs(
+    player_query: Query<&Transform, With<Player>>,
+    npc_query: Query<&Identity, (Without<InConversation>, Without<Sleeping>)>,
+    spatial: Res<SpatialIndex>,
+    active: Res<ActiveConversations>,
+    mut interaction_state: ResMut<PlayerInteractionState>,
+) {
+    let Ok(player_transform) = player_query.single() else {
+        interaction_state.nearby_npc = None;
+        return;
+    };
+
+    let nearest = spatial
+        .neighbors_within(player_transform.translation, INTERACTION_RANGE)
+        .into_iter()
+        .filter(|neighbor| !active.is_in_conversation(neighbor.npc))
+        .find_map(|neighbor| {
+            let identity = npc_query.get(neighbor.entity).ok()?;
+            Some((identity, neighbor.distance))
+        });
+
+    interaction_state.nearby_npc = nearest.map(|(identity, distance)| NearbyNpcInfo {
+        npc_id: identity.id,
+        name: identity.display_name.clone(),
+        distance,
+    });
+}
+
+/// Handles player input to initiate dialogue with nearby NPCs. Pressing interact again while the
+/// same NPC's reply is pending shows a waiting notice instead of queueing a second greeting;
+/// turning to another NPC withdraws the unanswered request if it has not been dispatched and
+/// ends the previous conversation as walked away.
+pub fn handle_player_interaction_input(
+    mut commands: Commands,
+    input: ActionInput,
+    mut interaction_state: ResMut<PlayerInteractionState>,
+    mut queue: ResMut<DialogueRequestQueue>,
+    children_query: Query<&Children>,
+    mut interaction_events: MessageWriter<PlayerInteractionEvent>,
+) {
+    if !input.just_pressed(InputAction::Interact) {
+        return;
+    }
+
+    if interaction_state.crate_has_priority() || interaction_state.open_crate.is_some() {
+        return;
+    }
+
+    let Some(nearby) = interaction_state.nearby_npc.clone() else {
+        debug!("Player pressed E but no NPC nearby");
+        return;
+    };
+
+    if interaction_state.is_waiting_on(nearby.npc_id) {
+        if let Some(window) = interaction_state.response_window.take() {
+            despawn_with_children(&mut commands, window, &children_query);
+        }
+        let window = spawn_notice_window(
+            &mut commands,
+            format!("{} {}", nearby.name, THINKING_SUFFIX),
+            Vec::new(),
+        );
+        interaction_state.failure_notice = false;
+        interaction_state.response_window = Some(window);
+        return;
+    }
+
+    if let Some(previous) = interaction_state.pending_request.take() {
+        if queue.cancel(previous) {
+            debug!("Withdrew unanswered {}", previous);
+        } else {
+            debug!(
+                "Already dispatched ({}); its reply will only show as a dialogue panel",
+                previous
+            );
+        }
+    }
+
+    let request_id = match greeting_request(nearby.npc_id, &nearby.name).enqueue(&mut queue) {
+        Ok(id) => id,
+        Err(error) => {
+            warn!("Greeting for {} not queued: {}", nearby.name, error);
+            return;
+        }
+    };
+
+    interaction_state.active_dialogue = Some(nearby.npc_id);
+    interaction_state.pending_request = Some(request_id);
+    interaction_state.active_npc_name = Some(nearby.name.clone());
+    interaction_state.last_npc_line = None;
+    interaction_state.failure_notice = false;
+    interaction_state.retry_offer = None;
+
+    let continuing = interaction_state
+        .conversation
+        .as_ref()
+        .is_some_and(|conversation| conversation.npc_id == nearby.npc_id);
+    if !continuing {
+        if let Some(ended) = interaction_state.end_conversation(ConversationEndReason::WalkedAway) {
+            interaction_events.write(ended);
+        }
+        interaction_state.conversation = Some(PlayerConversation {
+            npc_id: nearby.npc_id,
+            turns: 0,
+        });
+        interaction_events.write(PlayerInteractionEvent::Started {
+            npc: nearby.npc_id,
+            distance: nearby.distance,
+        });
+    }
+
+    info!(
+        "Player initiates conversation with {} (distance: {:.1}, {})",
+        nearby.name, nearby.distance, request_id
+    );
+}
+
+fn greeting_request(npc_id: NpcId, name: &str) -> DialogueRequestBuilder {
+    DialogueRequest::builder(npc_id)
+        .target(NpcId::player())
+        .speaker_name(name)
+        .prompt(format!(
+            "{} notices the player nearby and greets them. Respond naturally to the player.",
+            name
+        ))
+        .summary(format!(
+            "The player initiated a conversation with {}.",
+            name
+        ))
+}
+
+fn despawn_with_children(
+    commands: &mut Commands,
+    entity: Entity,
+    children_query: &Query<&Children>,
+) {
+    if let Ok(children) = children_query.get(entity) {
+        let child_ids = children.to_vec();
+        for child in child_ids {
+            despawn_with_children(commands, child, children_query);
+        }
+    }
+    commands.entity(entity).despawn();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dialogue::{queue::DialogueRequestQueue, transcripts::TranscriptStore},
+        npc::spatial::rebuild_spatial_index,
+        player::components::PlayerResponseButton,
+        world::time::WorldClock,
+    };
+
+    use super::{
+        response_window::{
+            handle_player_response_buttons, spawn_player_response_window, PLAYER_RESPONSE_OPTIONS,
+        },
+        test_support::*,
+    };
+
+    #[test]
+    fn nearby_npc_is_the_closest_awake_one_in_range() {
+        let mut app = App::new();
+        app.init_resource::<PlayerInteractionState>()
+            .init_resource::<ActiveConversations>()
+            .init_resource::<SpatialIndex>()
+            .add_systems(Update, (rebuild_spatial_index, detect_nearby_npcs).chain());
+        app.world_mut()
+            .spawn((Player, Transform::from_xyz(10.0, 0.0, 10.0)));
+        for (id, name, x, asleep) in [
+            (1, "Sleepy Tam", 10.5, true),
+            (2, "Brom", 12.0, false),
+            (3, "Dagna", 11.5, false),
+            (4, "Far Ede", 20.0, false),
+        ] {
+            let mut npc = app.world_mut().spawn((
+                Identity::new(NpcId::new(id), name, 40.0),
+                Transform::from_xyz(x, 0.0, 10.0),
+            ));
+            if asleep {
+                npc.insert(Sleeping);
+            }
+        }
+
+        app.update();
+        let nearby = app
+            .world()
+            .resource::<PlayerInteractionState>()
+            .nearby_npc
+            .clone()
+            .expect("Dagna is in range");
+        assert_eq!(
+            (nearby.npc_id, nearby.name.as_str()),
+            (NpcId::new(3), "Dagna")
+        );
+        assert!((nearby.distance - 1.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn out_of_order_replies_only_fill_the_window_for_the_active_conversation() {
+        let mut app = interrupt_app();
+        let (brom, dagna) = (NpcId::new(3), NpcId::new(4));
+
+        press_e_near(&mut app, brom, "Brom");
+        let to_brom = pending_request(&app).expect("greeting queued");
+        press_e_near(&mut app, dagna, "Dagna");
+        let to_dagna = pending_request(&app).expect("second greeting queued");
+        assert_ne!(to_brom, to_dagna);
+        let queued: Vec<_> = app
+            .world()
+            .resource::<DialogueRequestQueue>()
+            .iter_pending()
+            .map(|view| view.id)
+            .collect();
+        assert_eq!(queued, [to_dagna], "the abandoned greeting is withdrawn");
+
+        reply(&mut app, to_brom, brom, "Brom's late hello.");
+        let state = app.world().resource::<PlayerInteractionState>();
+        assert!(state.response_window.is_none());
+        assert_eq!(state.active_dialogue, Some(dagna));
+
+        reply(&mut app, to_dagna, dagna, "Dagna says hi.");
+        reply(&mut app, to_brom, brom, "Brom again, even later.");
+        let state = app.world().resource::<PlayerInteractionState>();
+        assert!(state.response_window.is_some());
+        assert_eq!(state.active_dialogue, Some(dagna));
+        assert_eq!(state.last_npc_line.as_deref(), Some("Dagna says hi."));
+        assert!(state.pending_request.is_none());
+        let texts = window_texts(&mut app);
+        assert!(texts.contains(&"Dagna says:\n\"Dagna says hi.\"".to_string()));
+        assert!(!texts.iter().any(|text| text.contains("Brom")));
+    }
+
+    #[test]
+    fn turning_to_another_npc_ends_the_conversation_as_walked_away() {
+        let mut app = interrupt_app();
+        app.init_resource::<TranscriptStore>()
+            .init_resource::<SeenInteractions>()
+            .insert_resource(WorldClock::new())
+            .add_systems(
+                Update,
+                (
+                    handle_player_response_buttons.after(spawn_player_response_window),
+                    collect_interactions.after(handle_player_response_buttons),
+                ),
+            );
+        let (brom, dagna) = (NpcId::new(3), NpcId::new(4));
+
+        press_e_near(&mut app, brom, "Brom");
+        let greeting = pending_request(&app).expect("greeting queued");
+        reply(&mut app, greeting, brom, "Morning.");
+        app.world_mut().spawn((
+            Button,
+            Interaction::Pressed,
+            PlayerResponseButton {
+                npc_id: brom,
+                response_index: 2,
+            },
+        ));
+        app.update();
+        press_e_near(&mut app, brom, "Brom");
+        press_e_near(&mut app, dagna, "Dagna");
+
+        assert_eq!(
+            app.world().resource::<SeenInteractions>().0,
+            [
+                PlayerInteractionEvent::Started {
+                    npc: brom,
+                    distance: 1.0,
+                },
+                PlayerInteractionEvent::ResponseChosen {
+                    npc: brom,
+                    option_index: 2,
+                    option_text: PLAYER_RESPONSE_OPTIONS[2].to_string(),
+                },
+                PlayerInteractionEvent::ConversationEnded {
+                    npc: brom,
+                    turns: 1,
+                    ended_by: ConversationEndReason::WalkedAway,
+                },
+                PlayerInteractionEvent::Started {
+                    npc: dagna,
+                    distance: 1.0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn pressing_again_while_waiting_shows_the_thinking_notice() {
+        let mut app = interrupt_app();
+        let brom = NpcId::new(3);
+
+        press_e_near(&mut app, brom, "Brom");
+        let greeting = pending_request(&app).expect("greeting queued");
+        press_e_near(&mut app, brom, "Brom");
+
+        assert_eq!(pending_request(&app), Some(greeting));
+        assert_eq!(
+            app.world()
+                .resource::<DialogueRequestQueue>()
+                .iter_pending()
+                .count(),
+            1,
+            "no second greeting is queued"
+        );
+        assert!(window_texts(&mut app).contains(&format!("Brom {THINKING_SUFFIX}")));
+
+        reply(&mut app, greeting, brom, "Sorry, where was I?");
+        let texts = window_texts(&mut app);
+        assert!(!texts.iter().any(|text| text.contains(THINKING_SUFFIX)));
+        assert!(texts.contains(&"Brom says:\n\"Sorry, where was I?\"".to_string()));
+    }
+}
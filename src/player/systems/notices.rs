@@ -0,0 +1,341 @@
+//! Notices shown in place of a reply: the "seems distracted" line when an NPC fails to
+//! answer the player, and the re-offer once a rate-limited NPC can talk again.
+use crate::{
+    dialogue::{
+        errors::DialogueErrorKind, events::DialogueRequestFailedEvent,
+        queue::DialogueRateLimitState,
+    },
+    npc::{components::Identity, spatial::NpcIndex},
+    player::components::{
+        DialogueRetryOffer, PlayerInteractionState, PlayerNoticeAction, PlayerNoticeButton,
+        PlayerResponseWindow,
+    },
+    ui::{layout::ScreenAnchor, visibility::UiLayer},
+};
+use bevy::log::{debug, info};
+use bevy::prelude::*;
+
+use super::despawn_with_children;
+
+/// Canned lines shown when an NPC fails to answer the player.
+const DISTRACTED_SUFFIX: &str = "seems distracted.";
+const READY_AGAIN_SUFFIX: &str = "looks ready to talk again.";
+const LEAVE_OPTION: &str = "Leave them be.";
+pub(super) const GOODBYE_OPTION: &str = "Goodbye.";
+const TALK_AGAIN_OPTION: &str = "Talk again";
+
+/// Replaces a pending player exchange with a canned "seems distracted" line when the NPC's
+/// request fails. Rate-limited failures also show the estimated wait and queue a re-offer.
+/// Failures of requests the player has since walked away from are ignored.
+pub fn handle_player_dialogue_failures(
+    mut commands: Commands,
+    mut interaction_state: ResMut<PlayerInteractionState>,
+    limits: Res<DialogueRateLimitState>,
+    mut failures: MessageReader<DialogueRequestFailedEvent>,
+    npc_index: Res<NpcIndex>,
+    identities: Query<&Identity>,
+    children_query: Query<&Children>,
+) {
+    for event in failures.read() {
+        if !event.target.is_some_and(|target| target.is_player()) {
+            continue;
+        }
+        if !interaction_state.is_current_request(event.error.request_id) {
+            continue;
+        }
+
+        let name = Identity::name_of(&npc_index, &identities, event.speaker);
+
+        let wait_seconds = match event.error.kind {
+            DialogueErrorKind::RateLimited {
+                retry_after_seconds,
+            } => Some(
+                limits
+                    .remaining_for(event.error.provider, event.speaker)
+                    .max(retry_after_seconds),
+            ),
+            _ => None,
+        };
+
+        info!(
+            "{} failed to answer the player ({}); showing fallback line",
+            name, event.error.kind
+        );
+
+        if let Some(window) = interaction_state.response_window.take() {
+            despawn_with_children(&mut commands, window, &children_query);
+        }
+
+        interaction_state.active_dialogue = None;
+        interaction_state.pending_request = None;
+        interaction_state.active_npc_name = None;
+        interaction_state.last_npc_line = None;
+        interaction_state.failure_notice = true;
+        interaction_state.retry_offer = wait_seconds.map(|_| DialogueRetryOffer {
+            npc_id: event.speaker,
+            name: name.clone(),
+            provider: event.error.provider,
+        });
+
+        let window = spawn_notice_window(
+            &mut commands,
+            distracted_line(&name, wait_seconds),
+            vec![PlayerNoticeAction::Leave],
+        );
+        interaction_state.response_window = Some(window);
+    }
+}
+
+/// Re-offers the conversation once a rate-limited NPC's cooldown has expired.
+pub fn restore_player_interaction_offer(
+    mut commands: Commands,
+    mut interaction_state: ResMut<PlayerInteractionState>,
+    limits: Res<DialogueRateLimitState>,
+    children_query: Query<&Children>,
+) {
+    let Some(offer) = interaction_state.retry_offer.clone() else {
+        return;
+    };
+    if !limits.can_process(offer.provider, offer.npc_id) {
+        return;
+    }
+    interaction_state.retry_offer = None;
+
+    if let Some(window) = interaction_state.response_window.take() {
+        despawn_with_children(&mut commands, window, &children_query);
+    }
+
+    debug!(
+        "{} can talk again; re-offering the conversation",
+        offer.name
+    );
+    let window = spawn_notice_window(
+        &mut commands,
+        format!("{} {}", offer.name, READY_AGAIN_SUFFIX),
+        vec![
+            PlayerNoticeAction::TalkAgain(offer),
+            PlayerNoticeAction::Leave,
+        ],
+    );
+    interaction_state.failure_notice = true;
+    interaction_state.response_window = Some(window);
+}
+
+fn distracted_line(name: &str, wait_seconds: Option<f32>) -> String {
+    match wait_seconds {
+        Some(seconds) => format!(
+            "{name} {DISTRACTED_SUFFIX} Try again in ~{}s.",
+            seconds.ceil().max(1.0) as u32
+        ),
+        None => format!("{name} {DISTRACTED_SUFFIX}"),
+    }
+}
+
+pub(super) fn spawn_notice_window(
+    commands: &mut Commands,
+    line: String,
+    actions: Vec<PlayerNoticeAction>,
+) -> Entity {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(20.0),
+                left: Val::Px(20.0),
+                width: Val::Px(360.0),
+                padding: UiRect::all(Val::Px(14.0)),
+                border: UiRect::all(Val::Px(2.0)),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(10.0),
+                ..Default::default()
+            },
+            BackgroundColor(Color::srgba(0.08, 0.08, 0.1, 0.95)),
+            BorderColor::from(Color::srgb(0.3, 0.3, 0.32)),
+            PlayerResponseWindow,
+            ScreenAnchor::ResponseWindow,
+            UiLayer::Screen,
+            Name::new("Player Response Window"),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(line),
+                TextFont {
+                    font_size: 16.0,
+                    ..Default::default()
+                },
+                TextColor(Color::WHITE),
+            ));
+
+            for action in actions {
+                spawn_notice_button(parent, action);
+            }
+        })
+        .id()
+}
+
+pub(super) fn spawn_notice_button(parent: &mut ChildSpawnerCommands, action: PlayerNoticeAction) {
+    let label = match action {
+        PlayerNoticeAction::Leave => LEAVE_OPTION,
+        PlayerNoticeAction::Goodbye => GOODBYE_OPTION,
+        PlayerNoticeAction::TalkAgain(_) => TALK_AGAIN_OPTION,
+    };
+    parent
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                border: UiRect::all(Val::Px(1.5)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            Button,
+            Interaction::None,
+            BackgroundColor(Color::srgba(0.18, 0.18, 0.22, 0.95)),
+            BorderColor::from(Color::srgb(0.4, 0.4, 0.45)),
+            PlayerNoticeButton { action },
+            Name::new(format!("Player Notice Button {}", label)),
+        ))
+        .with_children(|button| {
+            button.spawn((
+                Text::new(label),
+                TextFont {
+                    font_size: 15.0,
+                    ..Default::default()
+                },
+                TextColor(Color::WHITE),
+            ));
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dialogue::{broker::DialogueProviderKind, errors::DialogueError, types::DialogueRequestId},
+        npc::{components::NpcId, spatial::index_npcs},
+    };
+
+    use super::super::test_support::window_texts;
+
+    fn failure_app() -> (App, NpcId) {
+        let mut app = App::new();
+        app.init_resource::<PlayerInteractionState>()
+            .init_resource::<DialogueRateLimitState>()
+            .init_resource::<NpcIndex>()
+            .add_message::<DialogueRequestFailedEvent>()
+            .add_systems(
+                Update,
+                (
+                    index_npcs,
+                    handle_player_dialogue_failures,
+                    restore_player_interaction_offer,
+                )
+                    .chain(),
+            );
+
+        let npc = NpcId::new(3);
+        app.world_mut().spawn(Identity::new(npc, "Brom", 45.0));
+        {
+            let mut state = app.world_mut().resource_mut::<PlayerInteractionState>();
+            state.active_dialogue = Some(npc);
+            state.pending_request = Some(DialogueRequestId::new(9));
+        }
+        (app, npc)
+    }
+
+    fn fail(app: &mut App, npc: NpcId, target: Option<NpcId>, kind: DialogueErrorKind) {
+        app.world_mut().write_message(DialogueRequestFailedEvent {
+            error: DialogueError::new(
+                DialogueRequestId::new(9),
+                DialogueProviderKind::OpenAi,
+                kind,
+            ),
+            speaker: npc,
+            target,
+        });
+    }
+
+    #[test]
+    fn player_targeted_failure_shows_apology() {
+        let (mut app, npc) = failure_app();
+        fail(
+            &mut app,
+            npc,
+            Some(NpcId::player()),
+            DialogueErrorKind::provider_failure("timeout"),
+        );
+        app.update();
+
+        let state = app.world().resource::<PlayerInteractionState>();
+        assert!(state.response_window.is_some());
+        assert!(state.failure_notice);
+        assert!(state.active_dialogue.is_none());
+        assert!(state.retry_offer.is_none());
+
+        let texts = window_texts(&mut app);
+        assert!(texts.contains(&"Brom seems distracted.".to_string()));
+        assert!(texts.contains(&LEAVE_OPTION.to_string()));
+    }
+
+    #[test]
+    fn npc_to_npc_failure_leaves_player_state_alone() {
+        let (mut app, npc) = failure_app();
+        fail(
+            &mut app,
+            npc,
+            Some(NpcId::new(4)),
+            DialogueErrorKind::provider_failure("timeout"),
+        );
+        app.update();
+
+        let state = app.world().resource::<PlayerInteractionState>();
+        assert!(state.response_window.is_none());
+        assert_eq!(state.active_dialogue, Some(npc));
+    }
+
+    #[test]
+    fn rate_limited_failure_reoffers_after_cooldown() {
+        let (mut app, npc) = failure_app();
+        app.world_mut()
+            .resource_mut::<DialogueRateLimitState>()
+            .apply_backoff(DialogueProviderKind::OpenAi, npc, 12.0);
+        fail(
+            &mut app,
+            npc,
+            Some(NpcId::player()),
+            DialogueErrorKind::rate_limited(5.0),
+        );
+        app.update();
+
+        assert!(window_texts(&mut app)
+            .contains(&"Brom seems distracted. Try again in ~12s.".to_string()));
+        assert!(app
+            .world()
+            .resource::<PlayerInteractionState>()
+            .retry_offer
+            .is_some());
+
+        app.update();
+        assert!(
+            app.world()
+                .resource::<PlayerInteractionState>()
+                .retry_offer
+                .is_some(),
+            "offer waits while the cooldown is active"
+        );
+
+        app.world_mut()
+            .resource_mut::<DialogueRateLimitState>()
+            .tick(12.0);
+        app.update();
+
+        let state = app.world().resource::<PlayerInteractionState>();
+        assert!(state.retry_offer.is_none());
+        assert!(state.response_window.is_some());
+        let texts = window_texts(&mut app);
+        assert!(texts.contains(&"Brom looks ready to talk again.".to_string()));
+        assert!(texts.contains(&TALK_AGAIN_OPTION.to_string()));
+        assert!(!texts.iter().any(|text| text.contains("Try again")));
+    }
+}
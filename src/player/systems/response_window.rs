@@ -0,0 +1,407 @@
+//! The player's response window: the NPC's line, canned replies to choose from, and the
+//! history button, plus closing it when the conversation times out.
+use crate::{
+    dialogue::{
+        events::{ConversationEndReason, DialogueResponseEvent, PlayerInteractionEvent},
+        queue::DialogueRequestQueue,
+        transcripts::{TranscriptEntry, TranscriptStore},
+        types::DialogueRequest,
+    },
+    npc::{
+        components::{Identity, InConversation, NpcId},
+        spatial::NpcIndex,
+    },
+    player::components::{
+        PlayerHistoryButton, PlayerInteractionState, PlayerNoticeAction, PlayerResponseButton,
+        PlayerResponseWindow,
+    },
+    ui::{
+        layout::ScreenAnchor,
+        visibility::{UiLayer, UiVisibilityState},
+    },
+    world::time::WorldClock,
+};
+use bevy::log::{debug, warn};
+use bevy::prelude::*;
+
+use super::{despawn_with_children, notices::spawn_notice_button};
+
+const HISTORY_OPTION: &str = "History";
+
+/// Canned responses the player can choose from when replying to an NPC.
+pub(super) const PLAYER_RESPONSE_OPTIONS: [&str; 3] = [
+    "That's interesting! Tell me more.",
+    "How can I help with that?",
+    "Sounds tough. Stay strong out there.",
+];
+
+/// Spawns (or refreshes) the response window when the reply the active conversation is
+/// waiting on arrives. Replies to withdrawn or superseded requests are left to the dialogue
+/// panel.
+pub fn spawn_player_response_window(
+    mut commands: Commands,
+    mut interaction_state: ResMut<PlayerInteractionState>,
+    mut responses: MessageReader<DialogueResponseEvent>,
+    npc_index: Res<NpcIndex>,
+    identities: Query<&Identity>,
+    children_query: Query<&Children>,
+) {
+    for event in responses.read() {
+        let Some(target) = event.response.target else {
+            continue;
+        };
+        if !target.is_player() {
+            continue;
+        }
+        if !interaction_state.is_current_request(event.response.request_id) {
+            debug!(
+                "Ignoring stale reply ({}) from {} for the response window",
+                event.response.request_id, event.response.speaker
+            );
+            continue;
+        }
+
+        let npc_id = event.response.speaker;
+        let npc_identity = npc_index
+            .entity(npc_id)
+            .and_then(|entity| identities.get(entity).ok());
+        let Some(npc_identity) = npc_identity else {
+            warn!(
+                "NPC identity for {} not found when spawning response window",
+                npc_id
+            );
+            continue;
+        };
+
+        if let Some(window) = interaction_state.response_window.take() {
+            despawn_with_children(&mut commands, window, &children_query);
+        }
+
+        interaction_state.active_dialogue = Some(npc_id);
+        interaction_state.pending_request = None;
+        interaction_state.active_npc_name = Some(npc_identity.display_name.clone());
+        interaction_state.last_npc_line = Some(event.response.content.clone());
+        interaction_state.failure_notice = false;
+        interaction_state.retry_offer = None;
+
+        let window = commands
+            .spawn((
+                Node {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(20.0),
+                    left: Val::Px(20.0),
+                    width: Val::Px(360.0),
+                    padding: UiRect::all(Val::Px(14.0)),
+                    border: UiRect::all(Val::Px(2.0)),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(10.0),
+                    ..Default::default()
+                },
+                BackgroundColor(Color::srgba(0.08, 0.08, 0.1, 0.95)),
+                BorderColor::from(Color::srgb(0.3, 0.3, 0.32)),
+                PlayerResponseWindow,
+                ScreenAnchor::ResponseWindow,
+                UiLayer::Screen,
+                Name::new("Player Response Window"),
+            ))
+            .with_children(|parent| {
+                parent.spawn((
+                    Text::new(format!(
+                        "{} says:\n\"{}\"",
+                        npc_identity.display_name, event.response.content
+                    )),
+                    TextFont {
+                        font_size: 16.0,
+                        ..Default::default()
+                    },
+                    TextColor(Color::WHITE),
+                ));
+
+                for (index, option) in PLAYER_RESPONSE_OPTIONS.iter().enumerate() {
+                    parent
+                        .spawn((
+                            Node {
+                                width: Val::Percent(100.0),
+                                padding: UiRect::all(Val::Px(8.0)),
+                                border: UiRect::all(Val::Px(1.5)),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..Default::default()
+                            },
+                            Button,
+                            Interaction::None,
+                            BackgroundColor(Color::srgba(0.18, 0.18, 0.22, 0.95)),
+                            BorderColor::from(Color::srgb(0.4, 0.4, 0.45)),
+                            PlayerResponseButton {
+                                npc_id,
+                                response_index: index,
+                            },
+                            Name::new(format!("Player Response Button {}", index)),
+                        ))
+                        .with_children(|button| {
+                            button.spawn((
+                                Text::new(*option),
+                                TextFont {
+                                    font_size: 15.0,
+                                    ..Default::default()
+                                },
+                                TextColor(Color::WHITE),
+                            ));
+                        });
+                }
+                spawn_notice_button(parent, PlayerNoticeAction::Goodbye);
+
+                parent
+                    .spawn((
+                        Node {
+                            align_self: AlignSelf::FlexEnd,
+                            padding: UiRect::axes(Val::Px(10.0), Val::Px(4.0)),
+                            border: UiRect::all(Val::Px(1.0)),
+                            ..Default::default()
+                        },
+                        Button,
+                        Interaction::None,
+                        BackgroundColor(Color::srgba(0.12, 0.12, 0.15, 0.95)),
+                        BorderColor::from(Color::srgb(0.35, 0.35, 0.4)),
+                        PlayerHistoryButton { npc_id },
+                        Name::new("Player History Button"),
+                    ))
+                    .with_children(|button| {
+                        button.spawn((
+                            Text::new(HISTORY_OPTION),
+                            TextFont {
+                                font_size: 13.0,
+                                ..Default::default()
+                            },
+                            TextColor(Color::srgb(0.8, 0.8, 0.85)),
+                        ));
+                    });
+            })
+            .id();
+
+        interaction_state.response_window = Some(window);
+    }
+}
+
+/// Handles button presses in the player response window, queues follow-up dialogue, and
+/// records the chosen reply in the transcript with that NPC.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn handle_player_response_buttons(
+    mut commands: Commands,
+    mut interaction_state: ResMut<PlayerInteractionState>,
+    mut queue: ResMut<DialogueRequestQueue>,
+    mut transcripts: ResMut<TranscriptStore>,
+    clock: Res<WorldClock>,
+    children_query: Query<&Children>,
+    mut buttons: Query<(&Interaction, &PlayerResponseButton), (Changed<Interaction>, With<Button>)>,
+    mut interaction_events: MessageWriter<PlayerInteractionEvent>,
+) {
+    for (interaction, button) in buttons.iter_mut() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        let Some(active_npc) = interaction_state.active_dialogue else {
+            continue;
+        };
+        if active_npc != button.npc_id {
+            continue;
+        }
+
+        let Some(npc_name) = interaction_state.active_npc_name.as_deref() else {
+            continue;
+        };
+
+        let option_index = if button.response_index < PLAYER_RESPONSE_OPTIONS.len() {
+            button.response_index
+        } else {
+            0
+        };
+        let player_reply = PLAYER_RESPONSE_OPTIONS[option_index];
+
+        let prompt = interaction_state
+            .last_npc_line
+            .as_deref()
+            .map(|last_line| {
+                format!(
+                    "{npc_name} previously said: \"{last_line}\". The player replies: \"{player_reply}\". Respond in character to the player's reply.",
+                )
+            })
+            .unwrap_or_else(|| {
+                format!(
+                    "{npc_name} hears the player say: \"{player_reply}\". Respond in character to the player.",
+                )
+            });
+
+        let request_id = match DialogueRequest::builder(active_npc)
+            .target(NpcId::player())
+            .speaker_name(npc_name)
+            .prompt(prompt)
+            .summary(format!("Player replies: {}", player_reply))
+            .enqueue(&mut queue)
+        {
+            Ok(id) => id,
+            Err(error) => {
+                warn!("Player reply to {} not queued: {}", npc_name, error);
+                continue;
+            }
+        };
+        transcripts.record(
+            active_npc,
+            TranscriptEntry::new(
+                NpcId::player(),
+                player_reply,
+                clock.day_count(),
+                clock.time_of_day(),
+            ),
+        );
+        if let Some(conversation) = interaction_state.conversation.as_mut() {
+            conversation.turns += 1;
+        }
+        interaction_events.write(PlayerInteractionEvent::ResponseChosen {
+            npc: active_npc,
+            option_index,
+            option_text: player_reply.to_string(),
+        });
+
+        if let Some(window) = interaction_state.response_window.take() {
+            despawn_with_children(&mut commands, window, &children_query);
+        }
+
+        interaction_state.last_npc_line = None;
+        interaction_state.pending_request = Some(request_id);
+    }
+}
+
+/// Cleans up the response window when no conversations with the player remain, ending the
+/// tracked conversation as timed out. A window
+/// that was hidden by the UI toggle is kept until the player answers or moves on, so a
+/// conversation timing out during a cinematic does not take the choice with it.
+pub fn cleanup_player_response_window(
+    mut commands: Commands,
+    mut interaction_state: ResMut<PlayerInteractionState>,
+    ui_visibility: Res<UiVisibilityState>,
+    mut held_window: Local<Option<Entity>>,
+    conversing: Query<&InConversation>,
+    children_query: Query<&Children>,
+    mut interaction_events: MessageWriter<PlayerInteractionEvent>,
+) {
+    if interaction_state.response_window.is_none() || interaction_state.failure_notice {
+        return;
+    }
+    if !ui_visibility.mode.shows(UiLayer::Screen) {
+        *held_window = interaction_state.response_window;
+        return;
+    }
+    if held_window.is_some() && *held_window == interaction_state.response_window {
+        return;
+    }
+    *held_window = None;
+
+    let player_in_conversation = conversing
+        .iter()
+        .any(|conversation| conversation.partner.is_player());
+
+    if !player_in_conversation {
+        if let Some(window) = interaction_state.response_window.take() {
+            despawn_with_children(&mut commands, window, &children_query);
+        }
+        interaction_state.active_dialogue = None;
+        interaction_state.pending_request = None;
+        interaction_state.active_npc_name = None;
+        interaction_state.last_npc_line = None;
+        if let Some(ended) = interaction_state.end_conversation(ConversationEndReason::Timeout) {
+            interaction_events.write(ended);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::visibility::{apply_ui_visibility, UiVisibilityMode};
+
+    use super::super::test_support::*;
+
+    #[test]
+    fn hidden_response_window_keeps_its_reply_until_the_ui_returns() {
+        let mut app = interrupt_app();
+        app.init_resource::<UiVisibilityState>().add_systems(
+            Update,
+            (cleanup_player_response_window, apply_ui_visibility)
+                .chain()
+                .after(spawn_player_response_window),
+        );
+        let brom = NpcId::new(3);
+
+        press_e_near(&mut app, brom, "Brom");
+        let greeting = pending_request(&app).expect("greeting queued");
+        app.world_mut().resource_mut::<UiVisibilityState>().mode = UiVisibilityMode::None;
+        reply(&mut app, greeting, brom, "Hidden hello.");
+        // Brom's conversation is already over (nobody is `InConversation`), which would
+        // normally close the window.
+        app.update();
+
+        let window = app
+            .world()
+            .resource::<PlayerInteractionState>()
+            .response_window
+            .expect("window kept while hidden");
+        assert_eq!(
+            app.world().get::<Node>(window).unwrap().display,
+            Display::None
+        );
+
+        app.world_mut().resource_mut::<UiVisibilityState>().mode = UiVisibilityMode::All;
+        app.update();
+        app.update();
+
+        let state = app.world().resource::<PlayerInteractionState>();
+        assert_eq!(state.response_window, Some(window));
+        assert_eq!(state.last_npc_line.as_deref(), Some("Hidden hello."));
+        assert_eq!(
+            app.world().get::<Node>(window).unwrap().display,
+            Display::Flex
+        );
+        let texts = window_texts(&mut app);
+        assert!(texts.contains(&"Brom says:\n\"Hidden hello.\"".to_string()));
+        for option in PLAYER_RESPONSE_OPTIONS {
+            assert!(texts.contains(&option.to_string()));
+        }
+    }
+
+    #[test]
+    fn chosen_reply_is_recorded_in_the_transcript() {
+        let mut app = App::new();
+        app.init_resource::<PlayerInteractionState>()
+            .init_resource::<DialogueRequestQueue>()
+            .init_resource::<TranscriptStore>()
+            .insert_resource(WorldClock::new())
+            .add_message::<PlayerInteractionEvent>()
+            .add_systems(Update, handle_player_response_buttons);
+
+        let npc = NpcId::new(3);
+        {
+            let mut state = app.world_mut().resource_mut::<PlayerInteractionState>();
+            state.active_dialogue = Some(npc);
+            state.active_npc_name = Some("Brom".to_string());
+            state.last_npc_line = Some("Cold morning.".to_string());
+        }
+        app.world_mut().spawn((
+            Button,
+            Interaction::Pressed,
+            PlayerResponseButton {
+                npc_id: npc,
+                response_index: 1,
+            },
+        ));
+        app.update();
+
+        let store = app.world().resource::<TranscriptStore>();
+        let entries: Vec<_> = store.entries(npc, NpcId::player()).collect();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].speaker, NpcId::player());
+        assert_eq!(entries[0].text, PLAYER_RESPONSE_OPTIONS[1]);
+        assert!(!app.world().resource::<DialogueRequestQueue>().is_empty());
+    }
+}
@@ -0,0 +1,99 @@
+//! Headless fixtures shared by the player interaction tests.
+use bevy::prelude::*;
+
+use crate::{
+    core::input::InputBindings,
+    dialogue::{
+        broker::DialogueProviderKind,
+        events::{DialogueResponseEvent, PlayerInteractionEvent},
+        queue::DialogueRequestQueue,
+        types::{DialogueContext, DialogueRequestId, DialogueResponse},
+    },
+    npc::{
+        components::{Identity, NpcId},
+        spatial::{index_npcs, NpcIndex},
+    },
+    player::components::{NearbyNpcInfo, PlayerInteractionState},
+};
+
+use super::{handle_player_interaction_input, spawn_player_response_window};
+
+pub fn window_texts(app: &mut App) -> Vec<String> {
+    app.world_mut()
+        .query::<&Text>()
+        .iter(app.world())
+        .map(|text| text.0.clone())
+        .collect()
+}
+
+pub fn interrupt_app() -> App {
+    let mut app = App::new();
+    app.init_resource::<PlayerInteractionState>()
+        .init_resource::<DialogueRequestQueue>()
+        .init_resource::<ButtonInput<KeyCode>>()
+        .init_resource::<InputBindings>()
+        .add_message::<DialogueResponseEvent>()
+        .add_message::<PlayerInteractionEvent>()
+        .init_resource::<NpcIndex>()
+        .add_systems(
+            Update,
+            (
+                index_npcs,
+                handle_player_interaction_input,
+                spawn_player_response_window,
+            )
+                .chain(),
+        );
+    for (id, name) in [(3, "Brom"), (4, "Dagna")] {
+        app.world_mut()
+            .spawn(Identity::new(NpcId::new(id), name, 40.0));
+    }
+    app
+}
+
+pub fn press_e_near(app: &mut App, npc: NpcId, name: &str) {
+    app.world_mut()
+        .resource_mut::<PlayerInteractionState>()
+        .nearby_npc = Some(NearbyNpcInfo {
+        npc_id: npc,
+        name: name.to_string(),
+        distance: 1.0,
+    });
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .press(KeyCode::KeyE);
+    app.update();
+    let mut keyboard = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+    keyboard.release(KeyCode::KeyE);
+    keyboard.clear();
+}
+
+pub fn reply(app: &mut App, request_id: DialogueRequestId, npc: NpcId, line: &str) {
+    app.world_mut().write_message(DialogueResponseEvent {
+        response: DialogueResponse::new(
+            request_id,
+            DialogueProviderKind::OpenAi,
+            npc,
+            Some(NpcId::player()),
+            line,
+        ),
+        context: DialogueContext::default(),
+    });
+    app.update();
+}
+
+pub fn pending_request(app: &App) -> Option<DialogueRequestId> {
+    app.world()
+        .resource::<PlayerInteractionState>()
+        .pending_request
+}
+
+#[derive(Resource, Default)]
+pub struct SeenInteractions(pub Vec<PlayerInteractionEvent>);
+
+pub fn collect_interactions(
+    mut events: MessageReader<PlayerInteractionEvent>,
+    mut seen: ResMut<SeenInteractions>,
+) {
+    seen.0.extend(events.read().cloned());
+}
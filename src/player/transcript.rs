@@ -0,0 +1,384 @@
+//! Transcript viewer opened from the response window's "History" button.
+use bevy::{input::mouse::AccumulatedMouseScroll, prelude::*, ui::ComputedNode};
+
+use crate::{
+    core::format::FormatSettings,
+    dialogue::transcripts::{TranscriptEntry, TranscriptStore},
+    npc::{
+        components::{Identity, NpcId},
+        spatial::NpcIndex,
+    },
+    player::components::{
+        PlayerHistoryButton, PlayerTranscriptCloseButton, PlayerTranscriptScroll,
+        PlayerTranscriptViewer, PlayerTranscriptWindow,
+    },
+    ui::{layout::ScreenAnchor, visibility::UiLayer},
+};
+
+const PLAYER_LABEL: &str = "You";
+const EMPTY_TRANSCRIPT_LINE: &str = "Nothing said yet.";
+const CLOSE_OPTION: &str = "Close";
+/// Logical pixels scrolled per wheel line.
+const SCROLL_LINE_HEIGHT: f32 = 20.0;
+
+/// "[Day 3 08:15] Alric: text", with the player shown as "You".
+pub fn format_transcript_line(
+    format: &FormatSettings,
+    entry: &TranscriptEntry,
+    npc_name: &str,
+) -> String {
+    let speaker = if entry.speaker.is_player() {
+        PLAYER_LABEL
+    } else {
+        npc_name
+    };
+    format!(
+        "[Day {} {}] {}: {}",
+        entry.day,
+        format.format_time(entry.time_of_day),
+        speaker,
+        entry.text
+    )
+}
+
+/// New scroll offset after a wheel movement, kept within the scrollable range.
+pub fn scrolled_offset(current: f32, wheel_lines: f32, max_offset: f32) -> f32 {
+    let max_offset = max_offset.max(0.0);
+    (current.min(max_offset) - wheel_lines * SCROLL_LINE_HEIGHT).clamp(0.0, max_offset)
+}
+
+/// Opens or closes the viewer from the History and Close buttons.
+#[allow(clippy::type_complexity)]
+pub fn handle_transcript_buttons(
+    mut viewer: ResMut<PlayerTranscriptViewer>,
+    history_buttons: Query<
+        (&Interaction, &PlayerHistoryButton),
+        (Changed<Interaction>, With<Button>),
+    >,
+    close_buttons: Query<&Interaction, (Changed<Interaction>, With<PlayerTranscriptCloseButton>)>,
+) {
+    for (interaction, button) in &history_buttons {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        viewer.npc_id = if viewer.npc_id == Some(button.npc_id) {
+            None
+        } else {
+            Some(button.npc_id)
+        };
+    }
+    if close_buttons
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        viewer.npc_id = None;
+    }
+}
+
+/// Whether a list scrolled to `current` shows its last line, so new lines should keep it
+/// pinned to the bottom.
+pub fn follows_latest(current: f32, max_offset: f32) -> bool {
+    current >= max_offset.max(0.0) - SCROLL_LINE_HEIGHT / 2.0
+}
+
+/// Rebuilds the viewer when it opens, closes, or switches NPC; a rebuilt list starts
+/// scrolled to the bottom so the latest line is in view. New lines in the shown transcript
+/// only refill the list, which keeps its scroll position unless it was following the
+/// latest line.
+#[allow(clippy::type_complexity)]
+pub fn sync_transcript_viewer(
+    mut commands: Commands,
+    mut viewer: ResMut<PlayerTranscriptViewer>,
+    store: Res<TranscriptStore>,
+    format: Res<FormatSettings>,
+    npc_index: Res<NpcIndex>,
+    identities: Query<&Identity>,
+    mut lists: Query<
+        (
+            Entity,
+            &mut ScrollPosition,
+            &ComputedNode,
+            Option<&Children>,
+        ),
+        With<PlayerTranscriptScroll>,
+    >,
+) {
+    let recorded = viewer
+        .npc_id
+        .map_or(0, |npc_id| store.recorded(npc_id, NpcId::player()));
+    if !viewer.is_changed() {
+        if viewer.window.is_none() || recorded == viewer.shown_lines {
+            return;
+        }
+        let Some(npc_id) = viewer.npc_id else {
+            return;
+        };
+        let npc_name = Identity::name_of(&npc_index, &identities, npc_id);
+        let lines = transcript_lines(&store, &format, npc_id, &npc_name);
+        for (list, mut position, node, children) in &mut lists {
+            for child in children.into_iter().flatten() {
+                commands.entity(*child).despawn();
+            }
+            commands
+                .entity(list)
+                .with_children(|list| spawn_transcript_lines(list, lines.clone()));
+            if follows_latest(position.y, max_scroll_offset(node)) {
+                position.y = f32::MAX;
+            }
+        }
+        viewer.bypass_change_detection().shown_lines = recorded;
+        return;
+    }
+
+    if let Some(window) = viewer.window.take() {
+        commands.entity(window).despawn();
+    }
+    let Some(npc_id) = viewer.npc_id else {
+        return;
+    };
+
+    let npc_name = Identity::name_of(&npc_index, &identities, npc_id);
+    let lines = transcript_lines(&store, &format, npc_id, &npc_name);
+
+    let window = commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(20.0),
+                left: Val::Px(20.0),
+                width: Val::Px(420.0),
+                height: Val::Percent(45.0),
+                padding: UiRect::all(Val::Px(12.0)),
+                border: UiRect::all(Val::Px(2.0)),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(8.0),
+                ..Default::default()
+            },
+            BackgroundColor(Color::srgba(0.08, 0.08, 0.1, 0.95)),
+            BorderColor::from(Color::srgb(0.3, 0.3, 0.32)),
+            PlayerTranscriptWindow,
+            ScreenAnchor::TopLeft,
+            UiLayer::Screen,
+            Name::new("Player Transcript Window"),
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(Node {
+                    justify_content: JustifyContent::SpaceBetween,
+                    align_items: AlignItems::Center,
+                    ..Default::default()
+                })
+                .with_children(|header| {
+                    header.spawn((
+                        Text::new(format!("History with {npc_name}")),
+                        TextFont {
+                            font_size: 16.0,
+                            ..Default::default()
+                        },
+                        TextColor(Color::WHITE),
+                    ));
+                    header
+                        .spawn((
+                            Node {
+                                padding: UiRect::axes(Val::Px(10.0), Val::Px(4.0)),
+                                border: UiRect::all(Val::Px(1.0)),
+                                ..Default::default()
+                            },
+                            Button,
+                            Interaction::None,
+                            BackgroundColor(Color::srgba(0.18, 0.18, 0.22, 0.95)),
+                            BorderColor::from(Color::srgb(0.4, 0.4, 0.45)),
+                            PlayerTranscriptCloseButton,
+                        ))
+                        .with_children(|button| {
+                            button.spawn((
+                                Text::new(CLOSE_OPTION),
+                                TextFont {
+                                    font_size: 13.0,
+                                    ..Default::default()
+                                },
+                                TextColor(Color::WHITE),
+                            ));
+                        });
+                });
+
+            parent
+                .spawn((
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        flex_grow: 1.0,
+                        min_height: Val::Px(0.0),
+                        row_gap: Val::Px(4.0),
+                        overflow: Overflow::scroll_y(),
+                        ..Default::default()
+                    },
+                    ScrollPosition(Vec2::new(0.0, f32::MAX)),
+                    PlayerTranscriptScroll,
+                ))
+                .with_children(|list| spawn_transcript_lines(list, lines));
+        })
+        .id();
+    let viewer = viewer.bypass_change_detection();
+    viewer.window = Some(window);
+    viewer.shown_lines = recorded;
+}
+
+/// The formatted lines between `npc_id` and the player, or a placeholder when empty.
+fn transcript_lines(
+    store: &TranscriptStore,
+    format: &FormatSettings,
+    npc_id: NpcId,
+    npc_name: &str,
+) -> Vec<String> {
+    let mut lines: Vec<String> = store
+        .entries(npc_id, NpcId::player())
+        .map(|entry| format_transcript_line(format, entry, npc_name))
+        .collect();
+    if lines.is_empty() {
+        lines.push(EMPTY_TRANSCRIPT_LINE.to_string());
+    }
+    lines
+}
+
+fn spawn_transcript_lines(list: &mut ChildSpawnerCommands, lines: Vec<String>) {
+    for line in lines {
+        list.spawn((
+            Text::new(line),
+            TextFont {
+                font_size: 14.0,
+                ..Default::default()
+            },
+            TextColor(Color::srgb(0.9, 0.9, 0.92)),
+        ));
+    }
+}
+
+/// Scrolls the transcript list with the mouse wheel.
+pub fn scroll_transcript_viewer(
+    wheel: Res<AccumulatedMouseScroll>,
+    mut lists: Query<(&mut ScrollPosition, &ComputedNode), With<PlayerTranscriptScroll>>,
+) {
+    if wheel.delta.y == 0.0 {
+        return;
+    }
+    for (mut position, node) in &mut lists {
+        position.y = scrolled_offset(position.y, wheel.delta.y, max_scroll_offset(node));
+    }
+}
+
+/// How far, in logical pixels, the list's content reaches below its visible area.
+fn max_scroll_offset(node: &ComputedNode) -> f32 {
+    (node.content_size().y - node.size().y) * node.inverse_scale_factor()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_show_stamp_and_speaker() {
+        let npc = TranscriptEntry::new(NpcId::new(1), "Fresh bread!", 3, 0.25);
+        let player = TranscriptEntry::new(NpcId::player(), "I'll take one.", 3, 0.26);
+        let format = FormatSettings::default();
+        assert_eq!(
+            format_transcript_line(&format, &npc, "Alric"),
+            "[Day 3 06:00] Alric: Fresh bread!"
+        );
+        assert_eq!(
+            format_transcript_line(&format, &player, "Alric"),
+            "[Day 3 06:14] You: I'll take one."
+        );
+    }
+
+    fn viewer_app() -> App {
+        let mut app = App::new();
+        app.init_resource::<TranscriptStore>()
+            .init_resource::<FormatSettings>()
+            .init_resource::<PlayerTranscriptViewer>()
+            .init_resource::<NpcIndex>()
+            .add_systems(Update, sync_transcript_viewer);
+        app
+    }
+
+    fn say(app: &mut App, speaker: NpcId, listener: NpcId, text: &str) {
+        app.world_mut()
+            .resource_mut::<TranscriptStore>()
+            .record(listener, TranscriptEntry::new(speaker, text, 1, 0.5));
+    }
+
+    fn list(app: &mut App) -> (Entity, usize) {
+        let mut lists = app
+            .world_mut()
+            .query_filtered::<(Entity, Option<&Children>), With<PlayerTranscriptScroll>>();
+        let (entity, children) = lists.single(app.world()).expect("one open list");
+        (entity, children.map_or(0, |children| children.len()))
+    }
+
+    #[test]
+    fn new_lines_refill_the_open_list_and_keep_its_scroll() {
+        let mut app = viewer_app();
+        let (alric, bryn) = (NpcId::new(1), NpcId::new(2));
+        say(&mut app, alric, NpcId::player(), "Morning.");
+        app.world_mut()
+            .resource_mut::<PlayerTranscriptViewer>()
+            .npc_id = Some(alric);
+        app.update();
+        let window = app.world().resource::<PlayerTranscriptViewer>().window;
+        let (scroll, lines) = list(&mut app);
+        assert_eq!(lines, 1);
+
+        say(&mut app, alric, bryn, "Not for the player's ears.");
+        app.update();
+        assert_eq!(
+            app.world().resource::<PlayerTranscriptViewer>().window,
+            window,
+            "other pairs leave the viewer alone"
+        );
+        assert_eq!(list(&mut app).1, 1);
+
+        // Scrolled up through a long history: a new line must not yank the list around.
+        app.world_mut().entity_mut(scroll).insert((
+            ScrollPosition(Vec2::new(0.0, 40.0)),
+            ComputedNode {
+                size: Vec2::new(400.0, 100.0),
+                content_size: Vec2::new(400.0, 300.0),
+                inverse_scale_factor: 1.0,
+                ..Default::default()
+            },
+        ));
+        say(&mut app, NpcId::player(), alric, "Morning to you.");
+        app.update();
+        assert_eq!(
+            app.world().resource::<PlayerTranscriptViewer>().window,
+            window
+        );
+        assert_eq!(list(&mut app), (scroll, 2));
+        assert_eq!(app.world().get::<ScrollPosition>(scroll).unwrap().y, 40.0);
+
+        app.world_mut().get_mut::<ScrollPosition>(scroll).unwrap().y = 200.0;
+        say(&mut app, alric, NpcId::player(), "Fresh bread today.");
+        app.update();
+        assert_eq!(list(&mut app), (scroll, 3));
+        assert_eq!(
+            app.world().get::<ScrollPosition>(scroll).unwrap().y,
+            f32::MAX,
+            "a list at the bottom follows the latest line"
+        );
+    }
+
+    #[test]
+    fn scrolling_starts_from_the_bottom_and_stays_in_range() {
+        // A freshly opened list sits at the bottom (f32::MAX clamps to the max offset).
+        assert_eq!(scrolled_offset(f32::MAX, 1.0, 200.0), 180.0);
+        assert_eq!(scrolled_offset(10.0, 1.0, 200.0), 0.0);
+        assert_eq!(scrolled_offset(190.0, -1.0, 200.0), 200.0);
+        assert_eq!(scrolled_offset(50.0, 1.0, -5.0), 0.0);
+        assert!(follows_latest(f32::MAX, 200.0));
+        assert!(follows_latest(195.0, 200.0));
+        assert!(!follows_latest(40.0, 200.0));
+        assert!(
+            follows_latest(0.0, -5.0),
+            "a list that fits is always at the bottom"
+        );
+    }
+}
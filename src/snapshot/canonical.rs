@@ -0,0 +1,76 @@
+//! Canonical snapshot JSON: object keys sorted and floats rounded, so the same simulation
+//! state always serialises to the same bytes.
+use serde_json::{Map, Number, Value};
+
+/// Decimal places kept for floats; anything finer is float noise between runs.
+pub const FLOAT_DECIMALS: i32 = 4;
+
+/// Copy of `value` with every object's keys in sorted order and every float rounded to
+/// `FLOAT_DECIMALS`. Array order is kept, since it is meaningful.
+pub fn canonicalize(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key.clone(), canonicalize(value)))
+                    .collect::<Map<String, Value>>(),
+            )
+        }
+        Value::Array(items) => Value::Array(items.iter().map(canonicalize).collect()),
+        Value::Number(number) if number.is_f64() => {
+            round_float(number.as_f64().unwrap_or_default())
+        }
+        other => other.clone(),
+    }
+}
+
+/// Pretty-printed canonical form of `value`.
+pub fn to_canonical_string(value: &Value) -> String {
+    serde_json::to_string_pretty(&canonicalize(value)).expect("JSON values always serialise")
+}
+
+fn round_float(value: f64) -> Value {
+    let scale = 10f64.powi(FLOAT_DECIMALS);
+    // Adding zero turns a rounded -0.0 into 0.0.
+    let rounded = (value * scale).round() / scale + 0.0;
+    Number::from_f64(rounded).map_or(Value::Null, Value::Number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn key_order_does_not_change_the_output() {
+        let mut forward = Map::new();
+        forward.insert("alpha".into(), json!(1));
+        forward.insert("beta".into(), json!({"y": 2, "x": 1}));
+        let mut backward = Map::new();
+        backward.insert("beta".into(), json!({"x": 1, "y": 2}));
+        backward.insert("alpha".into(), json!(1));
+
+        let text = to_canonical_string(&Value::Object(forward));
+        assert_eq!(text, to_canonical_string(&Value::Object(backward)));
+        assert!(text.find("alpha").unwrap() < text.find("beta").unwrap());
+        assert!(text.find("\"x\"").unwrap() < text.find("\"y\"").unwrap());
+    }
+
+    #[test]
+    fn floats_are_rounded_and_arrays_keep_their_order() {
+        let value = json!({
+            "dopamine": 0.3f32,
+            "drift": -0.00001,
+            "count": 7,
+            "days": [3, 1, 2],
+        });
+        assert_eq!(
+            canonicalize(&value),
+            json!({"count": 7, "days": [3, 1, 2], "dopamine": 0.3, "drift": 0.0})
+        );
+        assert!(!to_canonical_string(&value).contains("-0.0"));
+    }
+}
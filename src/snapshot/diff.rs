@@ -0,0 +1,212 @@
+//! Structured diff between two snapshot documents. Objects are compared key by key in
+//! sorted order and arrays index by index, so the output is stable however either file
+//! was written. Only serde_json is used; the `diff_snapshots` binary reaches this through
+//! the library prelude.
+use std::{collections::BTreeSet, fmt};
+
+use serde_json::Value;
+
+/// Float differences up to this are treated as equal unless `--epsilon` says otherwise.
+pub const DEFAULT_EPSILON: f64 = 1e-3;
+
+/// How much two snapshots may differ before the comparison fails.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tolerances {
+    /// Largest float difference still treated as equal. Integers always compare exactly.
+    pub epsilon: f64,
+    /// Field differences allowed before the comparison counts as a regression.
+    pub max_changes: usize,
+}
+
+impl Default for Tolerances {
+    fn default() -> Self {
+        Self {
+            epsilon: DEFAULT_EPSILON,
+            max_changes: 0,
+        }
+    }
+}
+
+impl Tolerances {
+    /// Whether `differences` field differences are more than allowed.
+    pub fn exceeded_by(&self, differences: usize) -> bool {
+        differences > self.max_changes
+    }
+}
+
+/// How one field differs between the old and new snapshot.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    /// Only in the new snapshot.
+    Added(Value),
+    /// Only in the old snapshot.
+    Removed(Value),
+    /// In both, with different values.
+    Changed {
+        /// The old snapshot's value.
+        old: Value,
+        /// The new snapshot's value.
+        new: Value,
+    },
+}
+
+/// One differing field, addressed by a dotted path such as `npcs.NPC-0001.dopamine`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldDiff {
+    /// Dotted path to the field.
+    pub path: String,
+    /// How it differs.
+    pub change: Change,
+}
+
+impl fmt::Display for FieldDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.change {
+            Change::Added(value) => write!(f, "+ {}: {value}", self.path),
+            Change::Removed(value) => write!(f, "- {}: {value}", self.path),
+            Change::Changed { old, new } => write!(f, "~ {}: {old} -> {new}", self.path),
+        }
+    }
+}
+
+/// Every field that differs between `old` and `new`, in path order.
+pub fn diff_values(old: &Value, new: &Value, epsilon: f64) -> Vec<FieldDiff> {
+    let mut differences = Vec::new();
+    walk("", old, new, epsilon, &mut differences);
+    differences
+}
+
+fn walk(path: &str, old: &Value, new: &Value, epsilon: f64, out: &mut Vec<FieldDiff>) {
+    match (old, new) {
+        (Value::Object(old_map), Value::Object(new_map)) => {
+            let keys: BTreeSet<&String> = old_map.keys().chain(new_map.keys()).collect();
+            for key in keys {
+                let child = join(path, key);
+                match (old_map.get(key), new_map.get(key)) {
+                    (Some(old), Some(new)) => walk(&child, old, new, epsilon, out),
+                    (Some(old), None) => out.push(FieldDiff {
+                        path: child,
+                        change: Change::Removed(old.clone()),
+                    }),
+                    (None, Some(new)) => out.push(FieldDiff {
+                        path: child,
+                        change: Change::Added(new.clone()),
+                    }),
+                    (None, None) => {}
+                }
+            }
+        }
+        (Value::Array(old_items), Value::Array(new_items)) => {
+            for index in 0..old_items.len().max(new_items.len()) {
+                let child = format!("{path}[{index}]");
+                match (old_items.get(index), new_items.get(index)) {
+                    (Some(old), Some(new)) => walk(&child, old, new, epsilon, out),
+                    (Some(old), None) => out.push(FieldDiff {
+                        path: child,
+                        change: Change::Removed(old.clone()),
+                    }),
+                    (None, Some(new)) => out.push(FieldDiff {
+                        path: child,
+                        change: Change::Added(new.clone()),
+                    }),
+                    (None, None) => {}
+                }
+            }
+        }
+        (Value::Number(a), Value::Number(b)) if a.is_f64() || b.is_f64() => {
+            let (a, b) = (
+                a.as_f64().unwrap_or(f64::NAN),
+                b.as_f64().unwrap_or(f64::NAN),
+            );
+            // Written so NaN never counts as equal.
+            let equal = (a - b).abs() <= epsilon;
+            if !equal {
+                out.push(changed(path, old, new));
+            }
+        }
+        _ if old != new => out.push(changed(path, old, new)),
+        _ => {}
+    }
+}
+
+fn changed(path: &str, old: &Value, new: &Value) -> FieldDiff {
+    FieldDiff {
+        path: path.to_string(),
+        change: Change::Changed {
+            old: old.clone(),
+            new: new.clone(),
+        },
+    }
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn floats_within_epsilon_are_equal() {
+        let old = json!({"dopamine": 0.5, "count": 3});
+        assert!(diff_values(&old, &json!({"dopamine": 0.5004, "count": 3}), 1e-3).is_empty());
+
+        let differences = diff_values(&old, &json!({"dopamine": 0.52, "count": 3}), 1e-3);
+        assert_eq!(differences.len(), 1);
+        assert_eq!(differences[0].to_string(), "~ dopamine: 0.5 -> 0.52");
+
+        let differences = diff_values(&old, &json!({"dopamine": 0.5, "count": 4}), 10.0);
+        assert_eq!(differences.len(), 1, "integers ignore the epsilon");
+    }
+
+    #[test]
+    fn missing_keys_are_added_or_removed() {
+        let old = json!({"npcs": {"NPC-0001": {"mood": "content"}, "NPC-0002": {"mood": "tired"}}});
+        let new = json!({"npcs": {"NPC-0001": {"mood": "content", "skills": {"farmer": 2}}}});
+        let lines: Vec<String> = diff_values(&old, &new, DEFAULT_EPSILON)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                "+ npcs.NPC-0001.skills: {\"farmer\":2}",
+                "- npcs.NPC-0002: {\"mood\":\"tired\"}",
+            ]
+        );
+    }
+
+    #[test]
+    fn output_order_is_stable_and_arrays_compare_by_index() {
+        let old: Value = serde_json::from_str(r#"{"b": 1, "a": [1, 2], "c": "x"}"#).unwrap();
+        let new: Value = serde_json::from_str(r#"{"c": "y", "a": [1, 3, 4], "b": 2}"#).unwrap();
+        let paths: Vec<String> = diff_values(&old, &new, DEFAULT_EPSILON)
+            .into_iter()
+            .map(|difference| difference.path)
+            .collect();
+        assert_eq!(paths, ["a[1]", "a[2]", "b", "c"]);
+        assert_eq!(
+            diff_values(&new, &old, DEFAULT_EPSILON).len(),
+            4,
+            "symmetric"
+        );
+        assert!(diff_values(&old, &old, 0.0).is_empty());
+    }
+
+    #[test]
+    fn tolerances_allow_a_number_of_changes() {
+        let differences = diff_values(&json!({"a": 1, "b": 2}), &json!({"a": 2, "b": 3}), 0.0);
+        assert!(Tolerances::default().exceeded_by(differences.len()));
+        let lenient = Tolerances {
+            max_changes: 2,
+            ..Tolerances::default()
+        };
+        assert!(!lenient.exceeded_by(differences.len()));
+    }
+}
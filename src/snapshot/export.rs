@@ -0,0 +1,383 @@
+//! Day-boundary snapshot export. When a configured day ends, the NPCs' inventories,
+//! motivation, and skills plus the trade and dialogue totals since the previous snapshot
+//! are written to `<directory>/day_<N>.json` in canonical form.
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+use crate::{
+    core::config::{ConfigDiagnostics, ConfigReloadRequested},
+    dialogue::events::{DialogueRequestFailedEvent, DialogueResponseEvent},
+    economy::{
+        components::{Inventory, Profession, TradeGood},
+        data::EconomyRegistry,
+        events::TradeCompletedEvent,
+        skills::{Skill, SkillCurve},
+    },
+    npc::{
+        components::{Identity, NpcId},
+        motivation::NpcMotivation,
+    },
+    world::time::DayChangedEvent,
+};
+
+use super::canonical::to_canonical_string;
+
+pub const CONFIG_PATH: &str = "config/snapshots.toml";
+const DEFAULT_DIRECTORY: &str = "logs/snapshots";
+
+#[derive(Debug, Clone, Deserialize, Default)]
+struct RawSnapshotConfig {
+    #[serde(default)]
+    snapshots: RawSnapshotSection,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+struct RawSnapshotSection {
+    enabled: bool,
+    every_days: u64,
+    directory: String,
+}
+
+impl Default for RawSnapshotSection {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            every_days: 1,
+            directory: DEFAULT_DIRECTORY.to_string(),
+        }
+    }
+}
+
+/// When and where day snapshots are written.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct SnapshotSettings {
+    pub enabled: bool,
+    /// A snapshot is written after every day whose number is a multiple of this.
+    pub every_days: u64,
+    pub directory: PathBuf,
+}
+
+impl SnapshotSettings {
+    /// Reads and parses the `[snapshots]` section of `config/snapshots.toml`.
+    pub fn load() -> Result<Self, String> {
+        let data = fs::read_to_string(Path::new(CONFIG_PATH))
+            .map_err(|err| format!("unable to read file: {err}"))?;
+        let raw = toml::from_str::<RawSnapshotConfig>(&data)
+            .map_err(|err| format!("invalid snapshot config: {err}"))?;
+        Ok(raw.into())
+    }
+
+    /// Whether the end of `day` gets a snapshot.
+    pub fn is_snapshot_day(&self, day: u64) -> bool {
+        self.enabled && day.is_multiple_of(self.every_days)
+    }
+}
+
+impl Default for SnapshotSettings {
+    fn default() -> Self {
+        RawSnapshotConfig::default().into()
+    }
+}
+
+impl From<RawSnapshotConfig> for SnapshotSettings {
+    fn from(value: RawSnapshotConfig) -> Self {
+        let section = value.snapshots;
+        let directory = if section.directory.trim().is_empty() {
+            DEFAULT_DIRECTORY.to_string()
+        } else {
+            section.directory
+        };
+        Self {
+            enabled: section.enabled,
+            every_days: section.every_days.max(1),
+            directory: PathBuf::from(directory),
+        }
+    }
+}
+
+/// Trades and dialogue counted since the last snapshot.
+#[derive(Resource, Debug, Default)]
+pub struct SnapshotTally {
+    trades: u64,
+    traded: BTreeMap<&'static str, u64>,
+    responses: BTreeMap<NpcId, u64>,
+    failures: u64,
+}
+
+impl SnapshotTally {
+    pub fn record_trade(&mut self, good: TradeGood, quantity: u32) {
+        self.trades += 1;
+        *self.traded.entry(good.label()).or_default() += u64::from(quantity);
+    }
+
+    pub fn record_response(&mut self, speaker: NpcId) {
+        *self.responses.entry(speaker).or_default() += 1;
+    }
+
+    pub fn record_failure(&mut self) {
+        self.failures += 1;
+    }
+
+    fn to_json(&self) -> (Value, Value) {
+        let trades = json!({
+            "count": self.trades,
+            "quantity_by_good": self.traded,
+        });
+        let by_speaker: Map<String, Value> = self
+            .responses
+            .iter()
+            .map(|(speaker, count)| (speaker.to_string(), json!(count)))
+            .collect();
+        let dialogue = json!({
+            "responses": self.responses.values().sum::<u64>(),
+            "failures": self.failures,
+            "responses_by_speaker": by_speaker,
+        });
+        (trades, dialogue)
+    }
+}
+
+/// One NPC's state as it goes into a snapshot.
+pub struct NpcSnapshot<'a> {
+    pub identity: &'a Identity,
+    pub inventory: &'a Inventory,
+    pub motivation: &'a NpcMotivation,
+    pub skill: Option<&'a Skill>,
+}
+
+/// Snapshot document for the end of `day`, keyed by NPC id so runs line up entry by entry.
+/// Every good is listed, held or not; skills list only practised professions.
+pub fn build_snapshot<'a>(
+    day: u64,
+    npcs: impl IntoIterator<Item = NpcSnapshot<'a>>,
+    tally: &SnapshotTally,
+    curve: &SkillCurve,
+) -> Value {
+    let npcs: Map<String, Value> = npcs
+        .into_iter()
+        .map(|npc| {
+            let inventory: Map<String, Value> = TradeGood::ALL
+                .iter()
+                .map(|good| {
+                    (
+                        good.label().to_string(),
+                        json!(npc.inventory.quantity_of(*good)),
+                    )
+                })
+                .collect();
+            let skills: Map<String, Value> = npc
+                .skill
+                .map(|skill| {
+                    Profession::ALL
+                        .iter()
+                        .filter(|profession| skill.experience(**profession) > 0)
+                        .map(|profession| {
+                            (
+                                profession.label().to_string(),
+                                json!(skill.level(*profession, curve)),
+                            )
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            (
+                npc.identity.id.to_string(),
+                json!({
+                    "name": npc.identity.display_name,
+                    "inventory": inventory,
+                    "dopamine": npc.motivation.dopamine(),
+                    "mood": npc.motivation.mood().label(),
+                    "skills": skills,
+                }),
+            )
+        })
+        .collect();
+    let (trades, dialogue) = tally.to_json();
+    json!({
+        "day": day,
+        "npcs": npcs,
+        "trades": trades,
+        "dialogue": dialogue,
+    })
+}
+
+/// Re-reads `config/snapshots.toml` on request.
+pub fn reload_snapshot_settings(
+    mut requests: MessageReader<ConfigReloadRequested>,
+    mut diagnostics: ResMut<ConfigDiagnostics>,
+    mut settings: ResMut<SnapshotSettings>,
+) {
+    if !requests.read().any(|request| request.is_for(CONFIG_PATH)) {
+        return;
+    }
+    if let Some(reloaded) = diagnostics.report_reload(CONFIG_PATH, SnapshotSettings::load()) {
+        *settings = reloaded;
+    }
+}
+
+/// Counts completed trades and dialogue outcomes for the next snapshot.
+pub fn tally_snapshot_activity(
+    mut trades: MessageReader<TradeCompletedEvent>,
+    mut responses: MessageReader<DialogueResponseEvent>,
+    mut failures: MessageReader<DialogueRequestFailedEvent>,
+    mut tally: ResMut<SnapshotTally>,
+) {
+    for trade in trades.read() {
+        tally.record_trade(trade.good, trade.quantity);
+    }
+    for event in responses.read() {
+        tally.record_response(event.response.speaker);
+    }
+    for _ in failures.read() {
+        tally.record_failure();
+    }
+}
+
+/// Writes the snapshot for a day once a `DayChangedEvent` moves the clock past it. Trade
+/// and dialogue totals cover everything since the previous snapshot.
+#[allow(clippy::type_complexity)]
+pub fn export_day_snapshots(
+    mut day_changes: MessageReader<DayChangedEvent>,
+    settings: Res<SnapshotSettings>,
+    registry: Res<EconomyRegistry>,
+    mut tally: ResMut<SnapshotTally>,
+    npcs: Query<(&Identity, &Inventory, &NpcMotivation, Option<&Skill>)>,
+) {
+    let Some(ended) = day_changes
+        .read()
+        .filter_map(|change| change.previous_day)
+        .last()
+    else {
+        return;
+    };
+    if !settings.enabled {
+        *tally = SnapshotTally::default();
+        return;
+    }
+    if !settings.is_snapshot_day(ended) {
+        return;
+    }
+
+    let mut entries: Vec<_> = npcs.iter().collect();
+    entries.sort_by_key(|(identity, ..)| identity.id);
+    let snapshot = build_snapshot(
+        ended,
+        entries
+            .into_iter()
+            .map(|(identity, inventory, motivation, skill)| NpcSnapshot {
+                identity,
+                inventory,
+                motivation,
+                skill,
+            }),
+        &tally,
+        registry.skill_curve(),
+    );
+    *tally = SnapshotTally::default();
+
+    let path = settings.directory.join(format!("day_{ended}.json"));
+    let written = fs::create_dir_all(&settings.directory)
+        .and_then(|_| fs::write(&path, to_canonical_string(&snapshot) + "\n"));
+    match written {
+        Ok(()) => info!("Wrote simulation snapshot {}", path.display()),
+        Err(error) => warn!("Failed to write snapshot {}: {error}", path.display()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        npc::motivation::MotivationConfig,
+        world::time::{announce_day_change, WorldClock},
+    };
+    use std::{env, time::SystemTime};
+
+    #[test]
+    fn snapshot_lists_every_good_and_practised_skills() {
+        let curve = SkillCurve::default();
+        let identity = Identity::new(NpcId::new(2), "Bryn", 30.0);
+        let mut inventory = Inventory::default();
+        inventory.add_good(TradeGood::Flour, 3);
+        let motivation = NpcMotivation::new(&MotivationConfig::default());
+        let mut skill = Skill::default();
+        skill.gain(Profession::Miller, 50, &curve);
+
+        let mut tally = SnapshotTally::default();
+        tally.record_trade(TradeGood::Flour, 2);
+        tally.record_trade(TradeGood::Flour, 1);
+        tally.record_response(NpcId::new(2));
+        tally.record_failure();
+
+        let snapshot = build_snapshot(
+            4,
+            [NpcSnapshot {
+                identity: &identity,
+                inventory: &inventory,
+                motivation: &motivation,
+                skill: Some(&skill),
+            }],
+            &tally,
+            &curve,
+        );
+        let bryn = &snapshot["npcs"]["NPC-0002"];
+        assert_eq!(snapshot["day"], 4);
+        assert_eq!(bryn["name"], "Bryn");
+        assert_eq!(bryn["inventory"]["flour crate"], 3);
+        assert_eq!(bryn["inventory"]["ale cask"], 0);
+        assert_eq!(bryn["skills"], json!({"miller": 2}));
+        assert_eq!(bryn["mood"], motivation.mood().label());
+        assert_eq!(snapshot["trades"]["count"], 2);
+        assert_eq!(snapshot["trades"]["quantity_by_good"]["flour crate"], 3);
+        assert_eq!(snapshot["dialogue"]["responses"], 1);
+        assert_eq!(snapshot["dialogue"]["failures"], 1);
+        assert_eq!(snapshot["dialogue"]["responses_by_speaker"]["NPC-0002"], 1);
+    }
+
+    #[test]
+    fn snapshot_is_written_when_the_day_ends() {
+        let unique_suffix = SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let directory = env::temp_dir().join(format!("snapshots_test_{unique_suffix}"));
+
+        let mut app = App::new();
+        app.insert_resource(WorldClock::new())
+            .insert_resource(EconomyRegistry::default())
+            .insert_resource(SnapshotSettings {
+                enabled: true,
+                every_days: 2,
+                directory: directory.clone(),
+            })
+            .init_resource::<SnapshotTally>()
+            .add_message::<DayChangedEvent>()
+            .add_systems(Update, (announce_day_change, export_day_snapshots).chain());
+        app.world_mut().spawn((
+            Identity::new(NpcId::new(1), "Alric", 25.0),
+            Inventory::default(),
+            NpcMotivation::new(&MotivationConfig::default()),
+        ));
+
+        app.update();
+        assert!(!directory.exists(), "nothing is written mid-day");
+        for _ in 0..3 {
+            app.world_mut().resource_mut::<WorldClock>().skip_days(1);
+            app.update();
+        }
+
+        let written = fs::read_to_string(directory.join("day_0.json")).expect("day 0 snapshot");
+        assert!(written.contains("\"Alric\""));
+        assert!(!directory.join("day_1.json").exists(), "every other day");
+        assert!(directory.join("day_2.json").exists());
+        let _ = fs::remove_dir_all(&directory);
+    }
+}
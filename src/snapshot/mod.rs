@@ -0,0 +1,9 @@
+//! Simulation snapshots for comparing runs. `export.rs` writes one canonical JSON file per
+//! day boundary; `diff.rs` compares two of them, for the `diff_snapshots` binary and other
+//! tools through the library prelude.
+pub mod canonical;
+pub mod diff;
+pub mod export;
+pub mod plugin;
+
+pub use plugin::SnapshotPlugin;
@@ -0,0 +1,35 @@
+//! Snapshot plugin wiring the day-boundary exporter.
+use bevy::prelude::*;
+
+use crate::core::{config::report_config_result, schedule::FramePhase};
+
+use super::export::{
+    export_day_snapshots, reload_snapshot_settings, tally_snapshot_activity, SnapshotSettings,
+    SnapshotTally, CONFIG_PATH,
+};
+
+/// Writes a simulation snapshot at each day boundary for regression comparison.
+pub struct SnapshotPlugin;
+
+impl Plugin for SnapshotPlugin {
+    fn build(&self, app: &mut App) {
+        let settings = report_config_result(
+            app.world_mut(),
+            CONFIG_PATH,
+            SnapshotSettings::load(),
+            SnapshotSettings::default,
+        );
+        app.insert_resource(settings)
+            .init_resource::<SnapshotTally>()
+            .add_systems(
+                Update,
+                (
+                    reload_snapshot_settings,
+                    tally_snapshot_activity,
+                    export_day_snapshots,
+                )
+                    .chain()
+                    .in_set(FramePhase::Presentation),
+            );
+    }
+}
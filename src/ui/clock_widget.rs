@@ -0,0 +1,312 @@
+// src/ui/clock_widget.rs
+//
+// Top-right HUD clock: day number, clock time (`FormatSettings`), and a day-progress bar
+// with sunrise/sunset ticks and the selected NPC's schedule boundaries. Below the bar, the
+// player's village reputation tier.
+
+use bevy::prelude::*;
+
+use crate::core::format::FormatSettings;
+use crate::npc::{components::DailySchedule, events::NpcScheduleChangedEvent};
+use crate::player::reputation::PlayerReputation;
+use crate::ui::{layout::ScreenAnchor, visibility::UiLayer};
+use crate::world::{
+    selection::SelectedNpc,
+    time::{minute_of_day, WorldClock, WorldTimeSettings},
+};
+
+const WIDGET_OFFSET: f32 = 12.0;
+const WIDGET_PADDING: f32 = 8.0;
+const BAR_WIDTH: f32 = 180.0;
+const BAR_HEIGHT: f32 = 8.0;
+const SUN_TICK_WIDTH: f32 = 2.0;
+const SUN_TICK_HEIGHT: f32 = 14.0;
+const SCHEDULE_MARKER_WIDTH: f32 = 2.0;
+const LABEL_FONT_SIZE: f32 = 16.0;
+const REPUTATION_FONT_SIZE: f32 = 13.0;
+const WIDGET_BACKGROUND: Color = Color::srgba(0.1, 0.1, 0.1, 0.75);
+const BAR_BACKGROUND: Color = Color::srgb(0.18, 0.2, 0.28);
+const FILL_COLOR: Color = Color::srgb(0.95, 0.8, 0.35);
+const SUN_TICK_COLOR: Color = Color::srgb(1.0, 0.55, 0.2);
+const SCHEDULE_MARKER_COLOR: Color = Color::srgb(0.55, 0.85, 1.0);
+const LABEL_COLOR: Color = Color::WHITE;
+const REPUTATION_COLOR: Color = Color::srgb(0.8, 0.8, 0.85);
+
+/// Root node of the clock widget.
+#[derive(Component)]
+pub struct ClockWidget;
+
+/// "Day N, HH:MM" label.
+#[derive(Component)]
+pub struct ClockLabel;
+
+/// "Reputation: <tier>" label under the bar.
+#[derive(Component)]
+pub struct ReputationLabel;
+
+/// Progress bar track; sun ticks and schedule markers are its children.
+#[derive(Component)]
+pub struct ClockBar;
+
+/// Filled part of the bar, from midnight to now.
+#[derive(Component)]
+pub struct ClockBarFill;
+
+/// Tick marking sunrise or sunset.
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub enum SunTick {
+    Sunrise,
+    Sunset,
+}
+
+/// Boundary between two of the selected NPC's schedule entries.
+#[derive(Component)]
+pub struct ScheduleMarker;
+
+/// Left offset (percent of the bar) for a day fraction; out-of-range input wraps so 1.0
+/// and 0.0 both land at midnight on the left edge.
+pub fn bar_position_percent(fraction: f32) -> f32 {
+    if !fraction.is_finite() {
+        return 0.0;
+    }
+    fraction.rem_euclid(1.0) * 100.0
+}
+
+/// Bar fill width in percent. Snapped to the in-game minute the label shows, so the fill
+/// and the clock text never disagree.
+pub fn day_progress_percent(time_of_day: f32) -> f32 {
+    minute_of_day(time_of_day) as f32 / (24.0 * 60.0) * 100.0
+}
+
+/// Marker positions for each schedule entry start, sorted, with duplicates removed. An
+/// entry starting at midnight sits on the left edge.
+pub fn schedule_marker_percents(schedule: &DailySchedule) -> Vec<f32> {
+    let mut positions: Vec<f32> = schedule
+        .entries
+        .iter()
+        .map(|entry| bar_position_percent(entry.start))
+        .collect();
+    positions.sort_by(f32::total_cmp);
+    positions.dedup_by(|a, b| (*a - *b).abs() < f32::EPSILON);
+    positions
+}
+
+/// Spawns the widget with an empty label; `update_clock_widget` fills it in.
+pub fn spawn_clock_widget(mut commands: Commands, settings: Res<WorldTimeSettings>) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(WIDGET_OFFSET),
+                right: Val::Px(WIDGET_OFFSET),
+                padding: UiRect::all(Val::Px(WIDGET_PADDING)),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(6.0),
+                ..default()
+            },
+            BackgroundColor(WIDGET_BACKGROUND),
+            ClockWidget,
+            ScreenAnchor::TopRight,
+            UiLayer::Screen,
+        ))
+        .with_children(|widget| {
+            widget.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: LABEL_FONT_SIZE,
+                    ..default()
+                },
+                TextColor(LABEL_COLOR),
+                ClockLabel,
+            ));
+            widget
+                .spawn((
+                    Node {
+                        width: Val::Px(BAR_WIDTH),
+                        height: Val::Px(BAR_HEIGHT),
+                        ..default()
+                    },
+                    BackgroundColor(BAR_BACKGROUND),
+                    ClockBar,
+                ))
+                .with_children(|bar| {
+                    bar.spawn((
+                        Node {
+                            position_type: PositionType::Absolute,
+                            left: Val::Px(0.0),
+                            width: Val::Percent(0.0),
+                            height: Val::Percent(100.0),
+                            ..default()
+                        },
+                        BackgroundColor(FILL_COLOR),
+                        ClockBarFill,
+                    ));
+                    for (tick, fraction) in [
+                        (SunTick::Sunrise, settings.sunrise_fraction),
+                        (SunTick::Sunset, settings.sunset_fraction),
+                    ] {
+                        bar.spawn((
+                            marker_node(fraction, SUN_TICK_WIDTH, SUN_TICK_HEIGHT),
+                            BackgroundColor(SUN_TICK_COLOR),
+                            tick,
+                        ));
+                    }
+                });
+            widget.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: REPUTATION_FONT_SIZE,
+                    ..default()
+                },
+                TextColor(REPUTATION_COLOR),
+                ReputationLabel,
+            ));
+        });
+}
+
+/// Keeps the reputation label on the player's current tier.
+pub fn update_reputation_label(
+    reputation: Option<Res<PlayerReputation>>,
+    mut labels: Query<&mut Text, With<ReputationLabel>>,
+) {
+    let Some(reputation) = reputation else {
+        return;
+    };
+    let text = format!("Reputation: {}", reputation.tier().label());
+    for mut label in &mut labels {
+        if label.0 != text {
+            label.0.clone_from(&text);
+        }
+    }
+}
+
+/// Refreshes the label and fill once per in-game minute, moves the sun ticks when
+/// `WorldTimeSettings` changes, and rebuilds schedule markers when the selection or any
+/// schedule changes.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn update_clock_widget(
+    mut commands: Commands,
+    clock: Res<WorldClock>,
+    format: Res<FormatSettings>,
+    settings: Res<WorldTimeSettings>,
+    selected: Res<SelectedNpc>,
+    schedules: Query<&DailySchedule>,
+    mut schedule_changes: MessageReader<NpcScheduleChangedEvent>,
+    bars: Query<Entity, With<ClockBar>>,
+    markers: Query<Entity, With<ScheduleMarker>>,
+    mut layout: ParamSet<(
+        Query<&mut Text, With<ClockLabel>>,
+        Query<&mut Node, With<ClockBarFill>>,
+        Query<(&mut Node, &SunTick)>,
+    )>,
+    mut last_minute: Local<Option<u64>>,
+    mut marked_npc: Local<Option<Option<Entity>>>,
+) {
+    let minute_stamp = clock.day_count() * 24 * 60 + u64::from(minute_of_day(clock.time_of_day()));
+    if *last_minute != Some(minute_stamp) || format.is_changed() {
+        *last_minute = Some(minute_stamp);
+        for mut text in &mut layout.p0() {
+            text.0 = format!(
+                "Day {}, {}",
+                clock.day_count(),
+                format.format_time(clock.time_of_day())
+            );
+        }
+        for mut node in &mut layout.p1() {
+            node.width = Val::Percent(day_progress_percent(clock.time_of_day()));
+        }
+    }
+
+    if settings.is_changed() {
+        for (mut node, tick) in &mut layout.p2() {
+            let fraction = match tick {
+                SunTick::Sunrise => settings.sunrise_fraction,
+                SunTick::Sunset => settings.sunset_fraction,
+            };
+            node.left = Val::Percent(bar_position_percent(fraction));
+        }
+    }
+
+    if schedule_changes.read().count() > 0 {
+        *marked_npc = None;
+    }
+    let npc = selected.entity();
+    if *marked_npc == Some(npc) {
+        return;
+    }
+    let Ok(bar) = bars.single() else {
+        return;
+    };
+    *marked_npc = Some(npc);
+    for marker in &markers {
+        commands.entity(marker).despawn();
+    }
+    let Some(schedule) = npc.and_then(|entity| schedules.get(entity).ok()) else {
+        return;
+    };
+    commands.entity(bar).with_children(|bar| {
+        for position in schedule_marker_percents(schedule) {
+            bar.spawn((
+                marker_node(position / 100.0, SCHEDULE_MARKER_WIDTH, BAR_HEIGHT),
+                BackgroundColor(SCHEDULE_MARKER_COLOR),
+                ScheduleMarker,
+            ));
+        }
+    });
+}
+
+fn marker_node(fraction: f32, width: f32, height: f32) -> Node {
+    Node {
+        position_type: PositionType::Absolute,
+        left: Val::Percent(bar_position_percent(fraction)),
+        top: Val::Px((BAR_HEIGHT - height) / 2.0),
+        margin: UiRect::left(Val::Px(-width / 2.0)),
+        width: Val::Px(width),
+        height: Val::Px(height),
+        ..default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::npc::components::ScheduleEntry;
+
+    #[test]
+    fn bar_positions_wrap_at_midnight() {
+        assert_eq!(bar_position_percent(0.0), 0.0);
+        assert_eq!(bar_position_percent(1.0), 0.0);
+        assert!((bar_position_percent(0.25) - 25.0).abs() < 1e-4);
+        assert!((bar_position_percent(-0.25) - 75.0).abs() < 1e-4);
+        assert!((bar_position_percent(1.75) - 75.0).abs() < 1e-4);
+        assert_eq!(bar_position_percent(f32::NAN), 0.0);
+    }
+
+    #[test]
+    fn progress_matches_the_displayed_minute() {
+        assert_eq!(day_progress_percent(0.0), 0.0);
+        // 06:00 sunrise and 18:00 sunset land exactly on their ticks.
+        assert!((day_progress_percent(0.25) - 25.0).abs() < 1e-4);
+        assert!((day_progress_percent(0.75) - 75.0).abs() < 1e-4);
+        // The last minute before midnight stays just short of full, then wraps.
+        let last_minute = day_progress_percent(0.9999);
+        assert!(last_minute < 100.0 && last_minute > 99.9);
+        assert_eq!(day_progress_percent(1.0), 0.0);
+    }
+
+    #[test]
+    fn schedule_markers_are_sorted_and_deduplicated() {
+        let schedule = DailySchedule::new(vec![
+            ScheduleEntry::new(0.8, "Evening"),
+            ScheduleEntry::new(0.0, "Sleeping"),
+            ScheduleEntry::new(1.0, "Also midnight"),
+            ScheduleEntry::new(0.3, "Work"),
+        ]);
+
+        let markers = schedule_marker_percents(&schedule);
+        assert_eq!(markers.len(), 3);
+        assert_eq!(markers[0], 0.0);
+        assert!((markers[1] - 30.0).abs() < 1e-4);
+        assert!((markers[2] - 80.0).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,194 @@
+// src/ui/config_banner.rs
+//
+// Top-of-screen banner listing config files that fell back to defaults, with the reload
+// binding (F10 by default) to re-open it and retry loading them.
+
+use std::time::SystemTime;
+
+use bevy::prelude::*;
+
+use crate::core::config::{ConfigDiagnostics, ConfigLoadRecord, ConfigReloadRequested};
+use crate::core::input::{ActionInput, InputAction, InputBinding, InputBindings};
+use crate::ui::visibility::UiLayer;
+
+const BANNER_LIFETIME_SECONDS: f32 = 15.0;
+const BANNER_TOP_OFFSET: f32 = 12.0;
+const BANNER_WIDTH: f32 = 640.0;
+const BANNER_PADDING: f32 = 10.0;
+const BANNER_FONT_SIZE: f32 = 15.0;
+const BANNER_BACKGROUND: Color = Color::srgba(0.35, 0.08, 0.08, 0.9);
+const BANNER_HEALTHY_BACKGROUND: Color = Color::srgba(0.1, 0.25, 0.12, 0.9);
+const BANNER_TEXT_COLOR: Color = Color::WHITE;
+
+/// Visibility of the config banner and the entity currently showing it.
+#[derive(Resource, Debug, Default)]
+pub struct ConfigBanner {
+    remaining_seconds: f32,
+    entity: Option<Entity>,
+}
+
+impl ConfigBanner {
+    fn open(&mut self) {
+        self.remaining_seconds = BANNER_LIFETIME_SECONDS;
+    }
+
+    fn is_open(&self) -> bool {
+        self.remaining_seconds > 0.0
+    }
+}
+
+/// Marker for the banner's root UI node.
+#[derive(Component)]
+pub struct ConfigBannerNode;
+
+/// Banner body: one line per fallback config with its error, or an all-clear line.
+pub fn compose_config_banner_text<'a>(
+    fallbacks: impl IntoIterator<Item = &'a ConfigLoadRecord>,
+    reload_binding: InputBinding,
+    now: SystemTime,
+) -> String {
+    let mut lines = Vec::new();
+    for record in fallbacks {
+        let age = now
+            .duration_since(record.timestamp)
+            .map(|age| age.as_secs())
+            .unwrap_or(0);
+        lines.push(format!(
+            "{} is using defaults: {} ({}s ago)",
+            record.path,
+            record.error.as_deref().unwrap_or("unknown error"),
+            age
+        ));
+    }
+
+    if lines.is_empty() {
+        return "All config files loaded.".to_string();
+    }
+    lines.push(format!(
+        "Fix the file and press {reload_binding} to reload it."
+    ));
+    lines.join("\n")
+}
+
+/// Shows the banner at startup when any config fell back.
+pub fn open_config_banner_on_startup(
+    diagnostics: Res<ConfigDiagnostics>,
+    mut banner: ResMut<ConfigBanner>,
+) {
+    if diagnostics.fallbacks().next().is_some() {
+        banner.open();
+    }
+}
+
+/// The reload binding re-opens the banner and asks the owning plugins to reload every
+/// fallback config.
+pub fn handle_config_banner_key(
+    input: ActionInput,
+    diagnostics: Res<ConfigDiagnostics>,
+    mut banner: ResMut<ConfigBanner>,
+    mut reloads: MessageWriter<ConfigReloadRequested>,
+) {
+    if !input.just_pressed(InputAction::ReloadConfig) {
+        return;
+    }
+    banner.open();
+    for record in diagnostics.fallbacks() {
+        reloads.write(ConfigReloadRequested {
+            path: record.path.clone(),
+        });
+    }
+}
+
+/// Spawns, refreshes, and expires the banner node.
+pub fn sync_config_banner(
+    mut commands: Commands,
+    time: Res<Time>,
+    diagnostics: Res<ConfigDiagnostics>,
+    bindings: Res<InputBindings>,
+    mut banner: ResMut<ConfigBanner>,
+) {
+    banner.remaining_seconds = (banner.remaining_seconds - time.delta_secs()).max(0.0);
+
+    // Rebuild when reload results arrive so the text never lags the diagnostics.
+    if !banner.is_open() || diagnostics.is_changed() || bindings.is_changed() {
+        if let Some(entity) = banner.entity.take() {
+            commands.entity(entity).despawn();
+        }
+    }
+    if !banner.is_open() || banner.entity.is_some() {
+        return;
+    }
+
+    let healthy = diagnostics.fallbacks().next().is_none();
+    let text = compose_config_banner_text(
+        diagnostics.fallbacks(),
+        bindings.binding(InputAction::ReloadConfig),
+        SystemTime::now(),
+    );
+    let entity = commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(BANNER_TOP_OFFSET),
+                left: Val::Percent(50.0),
+                margin: UiRect::left(Val::Px(-BANNER_WIDTH / 2.0)),
+                width: Val::Px(BANNER_WIDTH),
+                padding: UiRect::all(Val::Px(BANNER_PADDING)),
+                ..default()
+            },
+            BackgroundColor(if healthy {
+                BANNER_HEALTHY_BACKGROUND
+            } else {
+                BANNER_BACKGROUND
+            }),
+            ConfigBannerNode,
+            UiLayer::Screen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(text),
+                TextFont {
+                    font_size: BANNER_FONT_SIZE,
+                    ..default()
+                },
+                TextColor(BANNER_TEXT_COLOR),
+            ));
+        })
+        .id();
+    banner.entity = Some(entity);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::core::config::ConfigLoadStatus;
+
+    #[test]
+    fn banner_lists_each_fallback_with_its_error() {
+        let now = SystemTime::now();
+        let record = ConfigLoadRecord {
+            path: "config/economy.toml".to_string(),
+            status: ConfigLoadStatus::Fallback,
+            error: Some("invalid economy config: unknown field `recipies`".to_string()),
+            timestamp: now - Duration::from_secs(4),
+        };
+
+        assert_eq!(
+            compose_config_banner_text([&record], InputBinding::Key(KeyCode::F10), now),
+            "config/economy.toml is using defaults: invalid economy config: unknown field \
+             `recipies` (4s ago)\nFix the file and press F10 to reload it."
+        );
+        assert_eq!(
+            compose_config_banner_text([&record], InputBinding::Key(KeyCode::KeyR), now)
+                .lines()
+                .last(),
+            Some("Fix the file and press KeyR to reload it.")
+        );
+        assert_eq!(
+            compose_config_banner_text([], InputBinding::Key(KeyCode::F10), now),
+            "All config files loaded."
+        );
+    }
+}
@@ -0,0 +1,456 @@
+// src/ui/console/execute.rs
+//
+// Runs parsed console commands against the world through the paths the simulation itself
+// uses: stock changes and trades are announced as messages, moods move through
+// `MotivationAdjustmentEvent`, lines go through the dialogue queue, schedule edits are sent
+// as `ScheduleCommand`, and `plan` asks day prep to plan the day again.
+
+use bevy::prelude::*;
+
+use crate::{
+    core::format::format_quantity,
+    dialogue::{
+        queue::DialogueRequestQueue,
+        types::{DialogueRequest, DialogueTopicHint},
+    },
+    economy::{
+        components::{Inventory, InventoryChange, TradeGood},
+        events::{InventoryChangedEvent, TradeCompletedEvent, TradeReason},
+        tasks::EconomyDayState,
+    },
+    npc::{
+        components::{Identity, ScheduleEntry},
+        motivation::{
+            state::MotivationReason, MotivationAdjustmentEvent, MotivationConfig, NpcMotivation,
+        },
+        schedule_editor::ScheduleCommand,
+    },
+    world::time::WorldClock,
+};
+
+use super::parse::ConsoleCommand;
+
+/// Runs `command`, returning the line to print back or why nothing changed. Takes ignore
+/// `ReservedStock`, so a console take can empty stock today's recipes were counting on.
+pub fn execute_console_command(
+    world: &mut World,
+    command: &ConsoleCommand,
+) -> Result<String, String> {
+    let day = world
+        .get_resource::<WorldClock>()
+        .map_or(0, WorldClock::day_count);
+    match command {
+        ConsoleCommand::Give {
+            npc,
+            good,
+            quantity,
+        } => give(world, day, npc, *good, *quantity),
+        ConsoleCommand::Take {
+            npc,
+            good,
+            quantity,
+        } => take(world, day, npc, *good, *quantity),
+        ConsoleCommand::Trade {
+            from,
+            to,
+            good,
+            quantity,
+        } => trade(world, day, from, to, *good, *quantity),
+        ConsoleCommand::Mood { npc, dopamine } => set_dopamine(world, npc, *dopamine),
+        ConsoleCommand::Say { npc, text } => say(world, npc, text),
+        ConsoleCommand::Schedule {
+            npc,
+            start,
+            activity,
+        } => {
+            let (_, identity) = find_npc(world, npc)?;
+            world.write_message(ScheduleCommand::InsertEntry {
+                npc: identity.id,
+                entry: ScheduleEntry::new(*start, activity.clone()),
+            });
+            Ok(format!(
+                "scheduling '{activity}' for {} at {start:.2}",
+                identity.display_name
+            ))
+        }
+        ConsoleCommand::Unschedule { npc, start } => {
+            let (_, identity) = find_npc(world, npc)?;
+            world.write_message(ScheduleCommand::RemoveEntryAt {
+                npc: identity.id,
+                start: *start,
+            });
+            Ok(format!(
+                "removing {}'s entry at {start:.2}",
+                identity.display_name
+            ))
+        }
+        ConsoleCommand::Plan => {
+            let mut day_state = world
+                .get_resource_mut::<EconomyDayState>()
+                .ok_or_else(|| "the economy is not running".to_string())?;
+            day_state.request_replan(day);
+            Ok(format!("planning day {day} again"))
+        }
+    }
+}
+
+/// The NPC whose display name is `name`, ignoring case.
+fn find_npc(world: &mut World, name: &str) -> Result<(Entity, Identity), String> {
+    let mut found: Vec<(Entity, Identity)> = world
+        .query::<(Entity, &Identity)>()
+        .iter(world)
+        .filter(|(_, identity)| {
+            !identity.id.is_player() && identity.display_name.eq_ignore_ascii_case(name)
+        })
+        .map(|(entity, identity)| (entity, identity.clone()))
+        .collect();
+    match found.len() {
+        0 => Err(format!("no NPC named '{name}'")),
+        1 => Ok(found.remove(0)),
+        count => Err(format!("{count} NPCs are named '{name}'")),
+    }
+}
+
+fn no_inventory(identity: &Identity) -> String {
+    format!("{} has no inventory", identity.display_name)
+}
+
+fn give(
+    world: &mut World,
+    day: u64,
+    npc: &str,
+    good: TradeGood,
+    quantity: u32,
+) -> Result<String, String> {
+    let (entity, identity) = find_npc(world, npc)?;
+    let mut inventory = world
+        .get_mut::<Inventory>(entity)
+        .ok_or_else(|| no_inventory(&identity))?;
+    let change = inventory
+        .add_good_on(good, quantity, day)
+        .ok_or_else(|| "nothing to give".to_string())?;
+    world.write_message(InventoryChangedEvent::from_change(identity.id, day, change));
+    Ok(format!(
+        "gave {} {} (now {})",
+        identity.display_name,
+        format_quantity(good.label(), quantity),
+        change.new_total
+    ))
+}
+
+/// Removes `quantity` of `good` from `entity` oldest first, returning the change and the
+/// `(acquired_day, quantity)` stacks taken, or explains why it holds too little.
+fn remove_stock(
+    world: &mut World,
+    entity: Entity,
+    identity: &Identity,
+    good: TradeGood,
+    quantity: u32,
+) -> Result<(InventoryChange, Vec<(u64, u32)>), String> {
+    let mut inventory = world
+        .get_mut::<Inventory>(entity)
+        .ok_or_else(|| no_inventory(identity))?;
+    let held = inventory.quantity_of(good);
+    inventory.take_good(good, quantity).ok_or_else(|| {
+        format!(
+            "{} holds only {}",
+            identity.display_name,
+            format_quantity(good.label(), held)
+        )
+    })
+}
+
+fn take(
+    world: &mut World,
+    day: u64,
+    npc: &str,
+    good: TradeGood,
+    quantity: u32,
+) -> Result<String, String> {
+    let (entity, identity) = find_npc(world, npc)?;
+    let (change, _) = remove_stock(world, entity, &identity, good, quantity)?;
+    world.write_message(InventoryChangedEvent::from_change(identity.id, day, change));
+    Ok(format!(
+        "took {} from {} (now {})",
+        format_quantity(good.label(), quantity),
+        identity.display_name,
+        change.new_total
+    ))
+}
+
+/// Moves stock between two NPCs and records it as an exchange, so everything listening
+/// for completed trades (reflections, rumors, toasts, telemetry) hears about it.
+fn trade(
+    world: &mut World,
+    day: u64,
+    from: &str,
+    to: &str,
+    good: TradeGood,
+    quantity: u32,
+) -> Result<String, String> {
+    let (from_entity, sender) = find_npc(world, from)?;
+    let (to_entity, recipient) = find_npc(world, to)?;
+    if sender.id == recipient.id {
+        return Err(format!(
+            "{} cannot trade with themselves",
+            sender.display_name
+        ));
+    }
+    if world.get::<Inventory>(to_entity).is_none() {
+        return Err(no_inventory(&recipient));
+    }
+    let (removed, stacks) = remove_stock(world, from_entity, &sender, good, quantity)?;
+    let added = world
+        .get_mut::<Inventory>(to_entity)
+        .and_then(|mut inventory| inventory.add_stacks(good, &stacks))
+        .ok_or_else(|| no_inventory(&recipient))?;
+
+    world.write_message(InventoryChangedEvent::from_change(sender.id, day, removed));
+    world.write_message(InventoryChangedEvent::from_change(recipient.id, day, added));
+    world.write_message(TradeCompletedEvent {
+        day,
+        from: Some(sender.id),
+        to: Some(recipient.id),
+        good,
+        quantity,
+        reason: TradeReason::Exchange,
+    });
+    Ok(format!(
+        "{} traded {} to {}",
+        sender.display_name,
+        format_quantity(good.label(), quantity),
+        recipient.display_name
+    ))
+}
+
+/// Requests the dopamine change that lands the NPC on `dopamine`, clamped to the
+/// configured bounds. It applies with the frame's other adjustments.
+fn set_dopamine(world: &mut World, npc: &str, dopamine: f32) -> Result<String, String> {
+    let (entity, identity) = find_npc(world, npc)?;
+    let (min, max) = world
+        .get_resource::<MotivationConfig>()
+        .map(|config| (config.defaults.min, config.defaults.max))
+        .ok_or_else(|| "motivation is not running".to_string())?;
+    let current = world
+        .get::<NpcMotivation>(entity)
+        .ok_or_else(|| format!("{} has no motivation", identity.display_name))?
+        .dopamine();
+    let target = dopamine.clamp(min, max);
+    world.write_message(MotivationAdjustmentEvent::new(
+        identity.id,
+        target - current,
+        MotivationReason::Console,
+    ));
+    Ok(format!(
+        "{}'s dopamine {current:.1} -> {target:.1}",
+        identity.display_name
+    ))
+}
+
+/// Queues a Status line spoken by the NPC. Chatter budgets, sleep, and pair cooldowns do
+/// not apply.
+fn say(world: &mut World, npc: &str, text: &str) -> Result<String, String> {
+    let (_, identity) = find_npc(world, npc)?;
+    let mut queue = world
+        .get_resource_mut::<DialogueRequestQueue>()
+        .ok_or_else(|| "dialogue is not running".to_string())?;
+    let id = DialogueRequest::builder(identity.id)
+        .speaker_name(identity.display_name.clone())
+        .topic(DialogueTopicHint::Status)
+        .prompt(text)
+        .enqueue(&mut queue)
+        .map_err(|error| error.to_string())?;
+    Ok(format!("queued {id} for {}", identity.display_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        npc::{components::NpcId, motivation::apply_motivation_adjustments},
+        ui::console::parse::parse_console_command,
+    };
+
+    fn console_app() -> App {
+        let mut app = App::new();
+        app.insert_resource(WorldClock::new())
+            .init_resource::<MotivationConfig>()
+            .init_resource::<DialogueRequestQueue>()
+            .init_resource::<EconomyDayState>()
+            .add_message::<InventoryChangedEvent>()
+            .add_message::<TradeCompletedEvent>()
+            .add_message::<MotivationAdjustmentEvent>()
+            .add_message::<ScheduleCommand>()
+            .add_systems(Update, apply_motivation_adjustments);
+        let config = app.world().resource::<MotivationConfig>().clone();
+        for (id, name) in [(1, "Bryn"), (2, "Cedric")] {
+            app.world_mut().spawn((
+                Identity::new(NpcId::new(id), name, 30.0),
+                Inventory::default(),
+                NpcMotivation::new(&config),
+            ));
+        }
+        app
+    }
+
+    fn run(app: &mut App, line: &str) -> Result<String, String> {
+        let command = parse_console_command(line).map_err(|error| error.to_string())?;
+        execute_console_command(app.world_mut(), &command)
+    }
+
+    fn held(app: &mut App, name: &str, good: TradeGood) -> u32 {
+        let world = app.world_mut();
+        world
+            .query::<(&Identity, &Inventory)>()
+            .iter(world)
+            .find(|(identity, _)| identity.display_name == name)
+            .map_or(0, |(_, inventory)| inventory.quantity_of(good))
+    }
+
+    fn drain<M: Message>(app: &mut App) -> Vec<M> {
+        app.world_mut()
+            .resource_mut::<Messages<M>>()
+            .drain()
+            .collect()
+    }
+
+    #[test]
+    fn give_and_take_change_stock_and_announce_it() {
+        let mut app = console_app();
+        assert_eq!(
+            run(&mut app, "give bryn grain 5"),
+            Ok("gave Bryn 5 grain crates (now 5)".to_string())
+        );
+        assert_eq!(held(&mut app, "Bryn", TradeGood::Grain), 5);
+
+        assert_eq!(
+            run(&mut app, "take BRYN grain 9"),
+            Err("Bryn holds only 5 grain crates".to_string())
+        );
+        assert_eq!(
+            run(&mut app, "take Bryn grain 2"),
+            Ok("took 2 grain crates from Bryn (now 3)".to_string())
+        );
+        assert_eq!(held(&mut app, "Bryn", TradeGood::Grain), 3);
+
+        let changes = drain::<InventoryChangedEvent>(&mut app);
+        let deltas: Vec<(NpcId, i64, u32)> = changes
+            .iter()
+            .map(|change| (change.npc, change.delta, change.new_total))
+            .collect();
+        assert_eq!(deltas, [(NpcId::new(1), 5, 5), (NpcId::new(1), -2, 3)]);
+        assert!(drain::<TradeCompletedEvent>(&mut app).is_empty());
+    }
+
+    #[test]
+    fn trade_moves_stock_and_records_an_exchange() {
+        let mut app = console_app();
+        run(&mut app, "give Bryn flour 4").unwrap();
+        drain::<InventoryChangedEvent>(&mut app);
+
+        assert_eq!(
+            run(&mut app, "trade Bryn cedric flour 3"),
+            Ok("Bryn traded 3 flour crates to Cedric".to_string())
+        );
+        assert_eq!(held(&mut app, "Bryn", TradeGood::Flour), 1);
+        assert_eq!(held(&mut app, "Cedric", TradeGood::Flour), 3);
+
+        let changes = drain::<InventoryChangedEvent>(&mut app);
+        assert_eq!(changes.len(), 2);
+        assert_eq!((changes[0].npc, changes[0].delta), (NpcId::new(1), -3));
+        assert_eq!((changes[1].npc, changes[1].delta), (NpcId::new(2), 3));
+        let trades = drain::<TradeCompletedEvent>(&mut app);
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].from, Some(NpcId::new(1)));
+        assert_eq!(trades[0].to, Some(NpcId::new(2)));
+        assert_eq!(trades[0].quantity, 3);
+        assert!(matches!(trades[0].reason, TradeReason::Exchange));
+
+        assert_eq!(
+            run(&mut app, "trade Bryn Cedric flour 2"),
+            Err("Bryn holds only 1 flour crate".to_string())
+        );
+        assert_eq!(
+            run(&mut app, "trade Bryn bryn flour 1"),
+            Err("Bryn cannot trade with themselves".to_string())
+        );
+        assert_eq!(
+            run(&mut app, "trade Bryn Dunstan flour 1"),
+            Err("no NPC named 'Dunstan'".to_string())
+        );
+        assert_eq!(held(&mut app, "Bryn", TradeGood::Flour), 1);
+        assert!(drain::<InventoryChangedEvent>(&mut app).is_empty());
+        assert!(drain::<TradeCompletedEvent>(&mut app).is_empty());
+    }
+
+    #[test]
+    fn mood_lands_on_the_value_through_the_adjustment_path() {
+        let mut app = console_app();
+        let max = app.world().resource::<MotivationConfig>().defaults.max;
+        run(&mut app, "mood cedric 90").unwrap();
+        app.update();
+        let dopamine = |app: &mut App| {
+            let world = app.world_mut();
+            world
+                .query::<(&Identity, &NpcMotivation)>()
+                .iter(world)
+                .find(|(identity, _)| identity.display_name == "Cedric")
+                .map(|(_, motivation)| motivation.dopamine())
+                .unwrap()
+        };
+        assert!((dopamine(&mut app) - 90.0).abs() < 1e-3);
+
+        assert_eq!(
+            run(&mut app, "mood Cedric 500"),
+            Ok(format!("Cedric's dopamine 90.0 -> {max:.1}"))
+        );
+        app.update();
+        assert!((dopamine(&mut app) - max).abs() < 1e-3);
+    }
+
+    #[test]
+    fn say_queues_a_status_line_and_plan_requests_a_replan() {
+        let mut app = console_app();
+        app.world_mut().resource_mut::<WorldClock>().skip_days(2);
+
+        let reply = run(&mut app, "say Bryn The well is dry.").unwrap();
+        let mut queue = app.world_mut().resource_mut::<DialogueRequestQueue>();
+        let queued = queue.iter_pending().next().unwrap();
+        assert_eq!(reply, format!("queued {} for Bryn", queued.id));
+        assert_eq!(queued.speaker, NpcId::new(1));
+        assert_eq!(queued.topic, DialogueTopicHint::Status);
+        let request = queue.pending_mut(queued.id).unwrap();
+        assert_eq!(request.prompt, "The well is dry.");
+        assert_eq!(request.speaker_name.as_deref(), Some("Bryn"));
+
+        assert_eq!(
+            run(&mut app, "plan"),
+            Ok("planning day 2 again".to_string())
+        );
+        assert_eq!(
+            app.world().resource::<EconomyDayState>().replan_requested,
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn schedule_edits_are_sent_as_schedule_commands() {
+        let mut app = console_app();
+        assert_eq!(
+            run(&mut app, "schedule bryn 0.5 Mending nets"),
+            Ok("scheduling 'Mending nets' for Bryn at 0.50".to_string())
+        );
+        assert_eq!(
+            run(&mut app, "unschedule Bryn 0.25"),
+            Ok("removing Bryn's entry at 0.25".to_string())
+        );
+        let sent = drain::<ScheduleCommand>(&mut app);
+        assert!(matches!(
+            &sent[..],
+            [
+                ScheduleCommand::InsertEntry { npc, entry },
+                ScheduleCommand::RemoveEntryAt { start, .. },
+            ] if *npc == NpcId::new(1) && entry.activity == "Mending nets" && *start == 0.25
+        ));
+    }
+}
@@ -0,0 +1,8 @@
+// src/ui/console/mod.rs
+//
+// Developer console for poking the economy at runtime: give, take, and trade goods, set an
+// NPC's mood, queue a line, or plan the day again.
+
+pub mod execute;
+pub mod parse;
+pub mod systems;
@@ -0,0 +1,468 @@
+// src/ui/console/parse.rs
+//
+// Command grammar for the developer console. Parsing is a plain function over the typed
+// line, so it is tested without an app; NPC names stay unresolved until execution.
+
+use std::fmt;
+
+use crate::economy::components::TradeGood;
+
+/// Every command with its argument pattern, listed in help and error messages.
+const USAGES: &[(&str, &str)] = &[
+    ("give", "give <npc> <good> <qty>"),
+    ("take", "take <npc> <good> <qty>"),
+    ("trade", "trade <from> <to> <good> <qty>"),
+    ("mood", "mood <npc> <value>"),
+    ("say", "say <npc> <text>"),
+    ("plan", "plan"),
+    ("schedule", "schedule <npc> <start> <activity>"),
+    ("unschedule", "unschedule <npc> <start>"),
+];
+
+/// One parsed console line.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConsoleCommand {
+    /// Adds goods to an NPC's inventory out of thin air.
+    Give {
+        npc: String,
+        good: TradeGood,
+        quantity: u32,
+    },
+    /// Removes goods from an NPC's inventory.
+    Take {
+        npc: String,
+        good: TradeGood,
+        quantity: u32,
+    },
+    /// Moves goods between two NPCs as an exchange.
+    Trade {
+        from: String,
+        to: String,
+        good: TradeGood,
+        quantity: u32,
+    },
+    /// Sets an NPC's dopamine, within the configured bounds.
+    Mood { npc: String, dopamine: f32 },
+    /// Queues a Status line with `text` as its prompt.
+    Say { npc: String, text: String },
+    /// Plans the current economy day's outstanding requests again.
+    Plan,
+    /// Adds an entry starting at `start` (a fraction of the day) to an NPC's schedule.
+    Schedule {
+        npc: String,
+        start: f32,
+        activity: String,
+    },
+    /// Removes the schedule entry that starts at `start`.
+    Unschedule { npc: String, start: f32 },
+}
+
+/// Why a console line did not parse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConsoleParseError {
+    Empty,
+    UnknownCommand(String),
+    MissingArgument {
+        command: &'static str,
+        argument: &'static str,
+    },
+    ExtraArgument {
+        command: &'static str,
+        argument: String,
+    },
+    UnknownGood(String),
+    InvalidQuantity(String),
+    InvalidDopamine(String),
+    InvalidStart(String),
+}
+
+impl fmt::Display for ConsoleParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "type a command: {}", command_list()),
+            Self::UnknownCommand(name) => {
+                write!(f, "unknown command '{name}'; try {}", command_list())
+            }
+            Self::MissingArgument { command, argument } => {
+                write!(f, "missing {argument}; usage: {}", usage(command))
+            }
+            Self::ExtraArgument { command, argument } => {
+                write!(f, "unexpected '{argument}'; usage: {}", usage(command))
+            }
+            Self::UnknownGood(name) => {
+                write!(
+                    f,
+                    "unknown good '{name}'; expected grain, flour, tools, or ale"
+                )
+            }
+            Self::InvalidQuantity(value) => {
+                write!(
+                    f,
+                    "'{value}' is not a quantity; expected a whole number above 0"
+                )
+            }
+            Self::InvalidDopamine(value) => {
+                write!(
+                    f,
+                    "'{value}' is not a dopamine value; expected a number such as 60"
+                )
+            }
+            Self::InvalidStart(value) => {
+                write!(
+                    f,
+                    "'{value}' is not a start; expected a fraction of the day such as 0.25"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConsoleParseError {}
+
+fn usage(command: &str) -> &'static str {
+    USAGES
+        .iter()
+        .find(|(name, _)| *name == command)
+        .map_or("", |(_, usage)| *usage)
+}
+
+fn command_list() -> String {
+    let names: Vec<&str> = USAGES.iter().map(|(name, _)| *name).collect();
+    names.join(", ")
+}
+
+/// Splits off the first whitespace-separated word, returning it and the rest with leading
+/// whitespace trimmed.
+fn next_word(text: &str) -> Option<(&str, &str)> {
+    let text = text.trim_start();
+    if text.is_empty() {
+        return None;
+    }
+    let end = text.find(char::is_whitespace).unwrap_or(text.len());
+    Some((&text[..end], text[end..].trim_start()))
+}
+
+/// Reads a command's positional arguments one word at a time.
+struct Arguments<'a> {
+    command: &'static str,
+    rest: &'a str,
+}
+
+impl<'a> Arguments<'a> {
+    fn word(&mut self, argument: &'static str) -> Result<&'a str, ConsoleParseError> {
+        let (word, rest) = next_word(self.rest).ok_or(ConsoleParseError::MissingArgument {
+            command: self.command,
+            argument,
+        })?;
+        self.rest = rest;
+        Ok(word)
+    }
+
+    fn good(&mut self) -> Result<TradeGood, ConsoleParseError> {
+        let word = self.word("<good>")?;
+        TradeGood::from_name(word).ok_or_else(|| ConsoleParseError::UnknownGood(word.to_string()))
+    }
+
+    fn quantity(&mut self) -> Result<u32, ConsoleParseError> {
+        let word = self.word("<qty>")?;
+        word.parse::<u32>()
+            .ok()
+            .filter(|quantity| *quantity > 0)
+            .ok_or_else(|| ConsoleParseError::InvalidQuantity(word.to_string()))
+    }
+
+    fn dopamine(&mut self) -> Result<f32, ConsoleParseError> {
+        let word = self.word("<value>")?;
+        word.parse::<f32>()
+            .ok()
+            .filter(|value| value.is_finite())
+            .ok_or_else(|| ConsoleParseError::InvalidDopamine(word.to_string()))
+    }
+
+    fn start(&mut self) -> Result<f32, ConsoleParseError> {
+        let word = self.word("<start>")?;
+        word.parse::<f32>()
+            .ok()
+            .filter(|start| (0.0..1.0).contains(start))
+            .ok_or_else(|| ConsoleParseError::InvalidStart(word.to_string()))
+    }
+
+    /// The rest of the line, which must not be blank.
+    fn text(&mut self, argument: &'static str) -> Result<&'a str, ConsoleParseError> {
+        let text = std::mem::take(&mut self.rest).trim_end();
+        if text.is_empty() {
+            return Err(ConsoleParseError::MissingArgument {
+                command: self.command,
+                argument,
+            });
+        }
+        Ok(text)
+    }
+
+    fn finish(self) -> Result<(), ConsoleParseError> {
+        match next_word(self.rest) {
+            Some((extra, _)) => Err(ConsoleParseError::ExtraArgument {
+                command: self.command,
+                argument: extra.to_string(),
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Parses one console line. Command names and goods ignore case; NPC names are kept as
+/// typed for `Identity` lookup. `say` and `schedule` take the rest of the line as their
+/// text.
+pub fn parse_console_command(line: &str) -> Result<ConsoleCommand, ConsoleParseError> {
+    let (name, rest) = next_word(line).ok_or(ConsoleParseError::Empty)?;
+    let lowered = name.to_ascii_lowercase();
+    let command = USAGES
+        .iter()
+        .map(|(command, _)| *command)
+        .find(|command| *command == lowered)
+        .ok_or_else(|| ConsoleParseError::UnknownCommand(name.to_string()))?;
+    let mut args = Arguments { command, rest };
+
+    let parsed = match command {
+        "give" | "take" => {
+            let npc = args.word("<npc>")?.to_string();
+            let good = args.good()?;
+            let quantity = args.quantity()?;
+            if command == "give" {
+                ConsoleCommand::Give {
+                    npc,
+                    good,
+                    quantity,
+                }
+            } else {
+                ConsoleCommand::Take {
+                    npc,
+                    good,
+                    quantity,
+                }
+            }
+        }
+        "trade" => ConsoleCommand::Trade {
+            from: args.word("<from>")?.to_string(),
+            to: args.word("<to>")?.to_string(),
+            good: args.good()?,
+            quantity: args.quantity()?,
+        },
+        "mood" => ConsoleCommand::Mood {
+            npc: args.word("<npc>")?.to_string(),
+            dopamine: args.dopamine()?,
+        },
+        "say" => ConsoleCommand::Say {
+            npc: args.word("<npc>")?.to_string(),
+            text: args.text("<text>")?.to_string(),
+        },
+        "schedule" => ConsoleCommand::Schedule {
+            npc: args.word("<npc>")?.to_string(),
+            start: args.start()?,
+            activity: args.text("<activity>")?.to_string(),
+        },
+        "unschedule" => ConsoleCommand::Unschedule {
+            npc: args.word("<npc>")?.to_string(),
+            start: args.start()?,
+        },
+        _ => ConsoleCommand::Plan,
+    };
+    args.finish()?;
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Result<ConsoleCommand, ConsoleParseError> {
+        parse_console_command(line)
+    }
+
+    #[test]
+    fn every_command_parses_with_its_arguments() {
+        assert_eq!(
+            parse("give Bryn grain 5"),
+            Ok(ConsoleCommand::Give {
+                npc: "Bryn".to_string(),
+                good: TradeGood::Grain,
+                quantity: 5,
+            })
+        );
+        assert_eq!(
+            parse("  TAKE  alric Tools   2 "),
+            Ok(ConsoleCommand::Take {
+                npc: "alric".to_string(),
+                good: TradeGood::Tools,
+                quantity: 2,
+            })
+        );
+        assert_eq!(
+            parse("trade Bryn Cedric tool 1"),
+            Ok(ConsoleCommand::Trade {
+                from: "Bryn".to_string(),
+                to: "Cedric".to_string(),
+                good: TradeGood::Tools,
+                quantity: 1,
+            })
+        );
+        assert_eq!(
+            parse("mood Dunstan 72.5"),
+            Ok(ConsoleCommand::Mood {
+                npc: "Dunstan".to_string(),
+                dopamine: 72.5,
+            })
+        );
+        assert_eq!(
+            parse("mood Dunstan -3"),
+            Ok(ConsoleCommand::Mood {
+                npc: "Dunstan".to_string(),
+                dopamine: -3.0,
+            })
+        );
+        assert_eq!(parse("plan"), Ok(ConsoleCommand::Plan));
+        assert_eq!(parse("Plan "), Ok(ConsoleCommand::Plan));
+        assert_eq!(
+            parse("schedule Bryn 0.5 Mending  nets"),
+            Ok(ConsoleCommand::Schedule {
+                npc: "Bryn".to_string(),
+                start: 0.5,
+                activity: "Mending  nets".to_string(),
+            })
+        );
+        assert_eq!(
+            parse("unschedule Bryn 0.25"),
+            Ok(ConsoleCommand::Unschedule {
+                npc: "Bryn".to_string(),
+                start: 0.25,
+            })
+        );
+    }
+
+    #[test]
+    fn say_keeps_the_rest_of_the_line_as_typed() {
+        assert_eq!(
+            parse("say Bryn  The mill wheel is  squeaking again.  "),
+            Ok(ConsoleCommand::Say {
+                npc: "Bryn".to_string(),
+                text: "The mill wheel is  squeaking again.".to_string(),
+            })
+        );
+        assert_eq!(
+            parse("say Bryn 5"),
+            Ok(ConsoleCommand::Say {
+                npc: "Bryn".to_string(),
+                text: "5".to_string(),
+            })
+        );
+        assert_eq!(
+            parse("say Bryn   "),
+            Err(ConsoleParseError::MissingArgument {
+                command: "say",
+                argument: "<text>",
+            })
+        );
+    }
+
+    #[test]
+    fn goods_parse_by_name_ignoring_case() {
+        for (name, good) in [
+            ("grain", TradeGood::Grain),
+            ("Flour", TradeGood::Flour),
+            ("TOOLS", TradeGood::Tools),
+            ("tool", TradeGood::Tools),
+            ("ale", TradeGood::Ale),
+        ] {
+            assert_eq!(TradeGood::from_name(name), Some(good));
+        }
+        assert_eq!(TradeGood::from_name("grain crate"), None);
+        assert_eq!(TradeGood::from_name("bread"), None);
+    }
+
+    #[test]
+    fn bad_lines_report_what_is_wrong() {
+        assert_eq!(parse("   "), Err(ConsoleParseError::Empty));
+        assert_eq!(
+            parse("spawn Bryn"),
+            Err(ConsoleParseError::UnknownCommand("spawn".to_string()))
+        );
+        assert_eq!(
+            parse("give Bryn grain"),
+            Err(ConsoleParseError::MissingArgument {
+                command: "give",
+                argument: "<qty>",
+            })
+        );
+        assert_eq!(
+            parse("trade Bryn"),
+            Err(ConsoleParseError::MissingArgument {
+                command: "trade",
+                argument: "<to>",
+            })
+        );
+        assert_eq!(
+            parse("mood"),
+            Err(ConsoleParseError::MissingArgument {
+                command: "mood",
+                argument: "<npc>",
+            })
+        );
+        assert_eq!(
+            parse("give Bryn bread 2"),
+            Err(ConsoleParseError::UnknownGood("bread".to_string()))
+        );
+        for quantity in ["0", "-2", "1.5", "lots", "99999999999"] {
+            assert_eq!(
+                parse(&format!("take Bryn ale {quantity}")),
+                Err(ConsoleParseError::InvalidQuantity(quantity.to_string()))
+            );
+        }
+        for value in ["happy", "NaN", "inf"] {
+            assert_eq!(
+                parse(&format!("mood Bryn {value}")),
+                Err(ConsoleParseError::InvalidDopamine(value.to_string()))
+            );
+        }
+        for start in ["1", "-0.1", "noon"] {
+            assert_eq!(
+                parse(&format!("unschedule Bryn {start}")),
+                Err(ConsoleParseError::InvalidStart(start.to_string()))
+            );
+        }
+        assert_eq!(
+            parse("give Bryn grain 2 please"),
+            Err(ConsoleParseError::ExtraArgument {
+                command: "give",
+                argument: "please".to_string(),
+            })
+        );
+        assert_eq!(
+            parse("plan tomorrow"),
+            Err(ConsoleParseError::ExtraArgument {
+                command: "plan",
+                argument: "tomorrow".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn error_messages_carry_the_usage() {
+        assert_eq!(
+            parse("give Bryn grain").unwrap_err().to_string(),
+            "missing <qty>; usage: give <npc> <good> <qty>"
+        );
+        assert_eq!(
+            parse("teleport").unwrap_err().to_string(),
+            "unknown command 'teleport'; try give, take, trade, mood, say, plan, schedule, \
+             unschedule"
+        );
+        assert_eq!(
+            parse("trade a b c 1").unwrap_err().to_string(),
+            "unknown good 'c'; expected grain, flour, tools, or ale"
+        );
+        assert_eq!(
+            parse("plan now").unwrap_err().to_string(),
+            "unexpected 'now'; usage: plan"
+        );
+    }
+}
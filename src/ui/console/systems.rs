@@ -0,0 +1,368 @@
+// src/ui/console/systems.rs
+//
+// Console state, typing, the runner that executes submitted lines, and the drop-down panel
+// that shows the scrollback.
+
+use std::collections::VecDeque;
+
+use bevy::{
+    input::{
+        keyboard::{Key, KeyboardInput},
+        mouse::AccumulatedMouseScroll,
+        ButtonState,
+    },
+    prelude::*,
+    ui::ComputedNode,
+};
+
+use crate::{
+    core::input::{ActionInput, InputAction, KeyboardCapture},
+    player::transcript::scrolled_offset,
+    ui::visibility::UiLayer,
+};
+
+use super::{execute::execute_console_command, parse::parse_console_command};
+
+/// Oldest lines are dropped past this many.
+const MAX_HISTORY_LINES: usize = 200;
+const PANEL_HEIGHT_PERCENT: f32 = 40.0;
+const PANEL_PADDING: f32 = 10.0;
+const LINE_GAP: f32 = 2.0;
+const FONT_SIZE: f32 = 14.0;
+/// Above every other screen panel.
+const PANEL_Z_INDEX: i32 = 100;
+const PROMPT: &str = "> ";
+const CURSOR: &str = "_";
+const PANEL_BACKGROUND: Color = Color::srgba(0.03, 0.03, 0.05, 0.92);
+const INPUT_COLOR: Color = Color::srgb(0.6, 0.6, 0.65);
+const OUTPUT_COLOR: Color = Color::srgb(0.88, 0.9, 0.88);
+const ERROR_COLOR: Color = Color::srgb(0.95, 0.45, 0.4);
+
+/// Where a scrollback line came from; picks its color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsoleLineKind {
+    Input,
+    Output,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsoleLine {
+    pub kind: ConsoleLineKind,
+    pub text: String,
+}
+
+/// The developer console: whether it is open, the line being typed, the scrollback, and
+/// lines waiting for `run_console_commands`.
+#[derive(Resource, Debug, Default)]
+pub struct DevConsole {
+    open: bool,
+    input: String,
+    history: VecDeque<ConsoleLine>,
+    submitted: Vec<String>,
+    panel: Option<Entity>,
+}
+
+impl DevConsole {
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    pub fn history(&self) -> impl Iterator<Item = &ConsoleLine> {
+        self.history.iter()
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    /// Applies one key press: Enter submits the line, Backspace deletes, Escape closes, and
+    /// anything else appends its printable text.
+    pub fn type_key(&mut self, key: &Key, text: Option<&str>) {
+        match key {
+            Key::Enter => {
+                let line = std::mem::take(&mut self.input);
+                self.submit(line);
+            }
+            Key::Backspace => {
+                self.input.pop();
+            }
+            Key::Escape => self.open = false,
+            _ => {
+                if let Some(text) = text {
+                    self.input
+                        .extend(text.chars().filter(|character| !character.is_control()));
+                }
+            }
+        }
+    }
+
+    /// Echoes `line` into the scrollback and queues it to run. Blank lines are dropped.
+    pub fn submit(&mut self, line: impl Into<String>) {
+        let line = line.into();
+        let line = line.trim();
+        if line.is_empty() {
+            return;
+        }
+        self.push_line(ConsoleLineKind::Input, format!("{PROMPT}{line}"));
+        self.submitted.push(line.to_string());
+    }
+
+    fn push_line(&mut self, kind: ConsoleLineKind, text: String) {
+        if self.history.len() == MAX_HISTORY_LINES {
+            self.history.pop_front();
+        }
+        self.history.push_back(ConsoleLine { kind, text });
+    }
+}
+
+/// Drop-down panel root.
+#[derive(Component, Debug)]
+pub struct DevConsolePanel;
+
+/// Scrollback list inside the panel.
+#[derive(Component, Debug)]
+pub struct DevConsoleScroll;
+
+/// Opens and closes the console, and while it is open feeds typed keys into it and holds
+/// them back from the game through `KeyboardCapture`. The key press that toggles the
+/// console is not typed.
+pub fn type_in_dev_console(
+    input: ActionInput,
+    mut keys: MessageReader<KeyboardInput>,
+    mut console: ResMut<DevConsole>,
+    mut capture: ResMut<KeyboardCapture>,
+) {
+    let toggled = input.just_pressed(InputAction::ToggleConsole);
+    if toggled {
+        console.toggle();
+    }
+    if console.is_open() && !toggled {
+        for event in keys.read() {
+            if event.state == ButtonState::Pressed {
+                console.type_key(&event.logical_key, event.text.as_deref());
+            }
+        }
+    } else {
+        keys.clear();
+    }
+    capture.set_if_neq(KeyboardCapture {
+        active: console.is_open(),
+    });
+}
+
+/// Parses and runs the lines submitted since the last frame, printing each result into
+/// the scrollback and the log.
+pub fn run_console_commands(world: &mut World) {
+    let lines = match world.get_resource_mut::<DevConsole>() {
+        Some(mut console) if !console.submitted.is_empty() => {
+            std::mem::take(&mut console.submitted)
+        }
+        _ => return,
+    };
+    for line in lines {
+        let result = parse_console_command(&line)
+            .map_err(|error| error.to_string())
+            .and_then(|command| execute_console_command(world, &command));
+        let (kind, text) = match result {
+            Ok(text) => {
+                info!("Console: {line} -> {text}");
+                (ConsoleLineKind::Output, text)
+            }
+            Err(text) => {
+                info!("Console: {line} failed: {text}");
+                (ConsoleLineKind::Error, text)
+            }
+        };
+        world.resource_mut::<DevConsole>().push_line(kind, text);
+    }
+}
+
+fn line_color(kind: ConsoleLineKind) -> Color {
+    match kind {
+        ConsoleLineKind::Input => INPUT_COLOR,
+        ConsoleLineKind::Output => OUTPUT_COLOR,
+        ConsoleLineKind::Error => ERROR_COLOR,
+    }
+}
+
+fn console_text(text: impl Into<String>, color: Color) -> impl Bundle {
+    (
+        Text::new(text),
+        TextFont {
+            font_size: FONT_SIZE,
+            ..Default::default()
+        },
+        TextColor(color),
+    )
+}
+
+/// Rebuilds the panel whenever the console changes, scrolled to the newest line, and
+/// removes it when the console closes.
+pub fn sync_dev_console_panel(mut commands: Commands, mut console: ResMut<DevConsole>) {
+    if !console.is_changed() {
+        return;
+    }
+    if let Some(panel) = console.bypass_change_detection().panel.take() {
+        commands.entity(panel).despawn();
+    }
+    if !console.is_open() {
+        return;
+    }
+
+    let lines: Vec<ConsoleLine> = console.history().cloned().collect();
+    let input_line = format!("{PROMPT}{}{CURSOR}", console.input());
+    let panel = commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(0.0),
+                left: Val::Px(0.0),
+                width: Val::Percent(100.0),
+                height: Val::Percent(PANEL_HEIGHT_PERCENT),
+                padding: UiRect::all(Val::Px(PANEL_PADDING)),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(LINE_GAP * 2.0),
+                ..Default::default()
+            },
+            BackgroundColor(PANEL_BACKGROUND),
+            GlobalZIndex(PANEL_Z_INDEX),
+            DevConsolePanel,
+            UiLayer::Screen,
+            Name::new("Developer Console"),
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        flex_grow: 1.0,
+                        min_height: Val::Px(0.0),
+                        row_gap: Val::Px(LINE_GAP),
+                        overflow: Overflow::scroll_y(),
+                        ..Default::default()
+                    },
+                    ScrollPosition(Vec2::new(0.0, f32::MAX)),
+                    DevConsoleScroll,
+                ))
+                .with_children(|list| {
+                    for line in lines {
+                        list.spawn(console_text(line.text, line_color(line.kind)));
+                    }
+                });
+            parent.spawn(console_text(input_line, OUTPUT_COLOR));
+        })
+        .id();
+    console.bypass_change_detection().panel = Some(panel);
+}
+
+/// Scrolls the scrollback with the mouse wheel.
+pub fn scroll_dev_console(
+    wheel: Res<AccumulatedMouseScroll>,
+    mut lists: Query<(&mut ScrollPosition, &ComputedNode), With<DevConsoleScroll>>,
+) {
+    if wheel.delta.y == 0.0 {
+        return;
+    }
+    for (mut position, node) in &mut lists {
+        let max_offset = (node.content_size().y - node.size().y) * node.inverse_scale_factor();
+        position.y = scrolled_offset(position.y, wheel.delta.y, max_offset);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        economy::{components::Inventory, events::InventoryChangedEvent},
+        npc::components::{Identity, NpcId},
+        world::time::WorldClock,
+    };
+
+    fn type_line(console: &mut DevConsole, line: &str) {
+        for character in line.chars() {
+            let text = character.to_string();
+            console.type_key(&Key::Character(text.as_str().into()), Some(&text));
+        }
+        console.type_key(&Key::Enter, None);
+    }
+
+    #[test]
+    fn typed_keys_edit_the_line_and_enter_submits_it() {
+        let mut console = DevConsole::default();
+        console.toggle();
+        type_line(&mut console, "plann");
+        assert_eq!(console.input(), "");
+        console.type_key(&Key::Character("x".into()), Some("x"));
+        console.type_key(&Key::Backspace, None);
+        console.type_key(&Key::Tab, Some("\t"));
+        assert_eq!(console.input(), "");
+        console.type_key(&Key::Enter, None);
+        assert_eq!(
+            console.submitted,
+            ["plann"],
+            "blank lines are not submitted"
+        );
+
+        console.type_key(&Key::Escape, None);
+        assert!(!console.is_open());
+    }
+
+    #[test]
+    fn submitted_lines_run_and_print_their_results() {
+        let mut app = App::new();
+        app.insert_resource(WorldClock::new())
+            .init_resource::<DevConsole>()
+            .add_message::<InventoryChangedEvent>()
+            .add_systems(Update, run_console_commands);
+        app.world_mut().spawn((
+            Identity::new(NpcId::new(1), "Bryn", 30.0),
+            Inventory::default(),
+        ));
+
+        {
+            let mut console = app.world_mut().resource_mut::<DevConsole>();
+            type_line(&mut console, "give Bryn ale 2");
+            type_line(&mut console, "give Bryn");
+            type_line(&mut console, "plan");
+        }
+        app.update();
+
+        let history: Vec<(ConsoleLineKind, String)> = app
+            .world()
+            .resource::<DevConsole>()
+            .history()
+            .map(|line| (line.kind, line.text.clone()))
+            .collect();
+        assert_eq!(
+            history,
+            [
+                (ConsoleLineKind::Input, "> give Bryn ale 2".to_string()),
+                (ConsoleLineKind::Input, "> give Bryn".to_string()),
+                (ConsoleLineKind::Input, "> plan".to_string()),
+                (
+                    ConsoleLineKind::Output,
+                    "gave Bryn 2 ale casks (now 2)".to_string()
+                ),
+                (
+                    ConsoleLineKind::Error,
+                    "missing <good>; usage: give <npc> <good> <qty>".to_string()
+                ),
+                (
+                    ConsoleLineKind::Error,
+                    "the economy is not running".to_string()
+                ),
+            ]
+        );
+        assert_eq!(
+            app.world()
+                .resource::<Messages<InventoryChangedEvent>>()
+                .len(),
+            1
+        );
+    }
+}
@@ -5,6 +5,7 @@
 use bevy::prelude::*;
 use std::collections::HashMap;
 
+use crate::core::time_basis::TimeBasis;
 use crate::npc::components::NpcId;
 
 /// Component attached to dialogue panel UI entities.
@@ -28,6 +29,9 @@ pub struct DialoguePanel {
 
     /// Duration of fade-out effect (stored for fade calculation).
     fade_duration: f32,
+
+    /// Entrance animation timer: the panel slides up and fades in until it finishes.
+    intro: Timer,
 }
 
 impl DialoguePanel {
@@ -38,6 +42,7 @@ impl DialoguePanel {
         content: String,
         lifetime_secs: f32,
         fade_duration: f32,
+        intro_secs: f32,
     ) -> Self {
         Self {
             npc_id,
@@ -45,6 +50,7 @@ impl DialoguePanel {
             content,
             lifetime: Timer::from_seconds(lifetime_secs, TimerMode::Once),
             fade_duration,
+            intro: Timer::from_seconds(intro_secs.max(0.0), TimerMode::Once),
         }
     }
 
@@ -53,9 +59,10 @@ impl DialoguePanel {
         self.npc_id
     }
 
-    /// Tick the lifetime timer.
+    /// Tick the lifetime and entrance timers.
     pub fn tick(&mut self, delta: std::time::Duration) {
         self.lifetime.tick(delta);
+        self.intro.tick(delta);
     }
 
     /// Check if the panel's lifetime has expired.
@@ -63,19 +70,60 @@ impl DialoguePanel {
         self.lifetime.is_finished()
     }
 
+    /// Entrance progress from 0.0 (just spawned) to 1.0 (settled in place).
+    pub fn intro_progress(&self) -> f32 {
+        intro_progress(
+            self.intro.elapsed_secs(),
+            self.intro.duration().as_secs_f32(),
+        )
+    }
+
     /// Calculate the alpha fade value (1.0 = fully visible, 0.0 = transparent).
     ///
-    /// Fades out during the final `fade_duration` seconds of lifetime.
+    /// Fades in over the entrance animation and out during the final `fade_duration`
+    /// seconds of lifetime.
     pub fn fade_alpha(&self) -> f32 {
-        let remaining = self.lifetime.remaining_secs();
-        if remaining < self.fade_duration {
-            remaining / self.fade_duration
-        } else {
-            1.0
-        }
+        self.intro_progress().min(fade_out_alpha(
+            self.lifetime.remaining_secs(),
+            self.fade_duration,
+        ))
+    }
+}
+
+/// Linear entrance progress after `elapsed` of a `duration`-long intro. A zero-length
+/// intro is already complete.
+pub fn intro_progress(elapsed: f32, duration: f32) -> f32 {
+    if duration <= 0.0 {
+        1.0
+    } else {
+        (elapsed / duration).clamp(0.0, 1.0)
+    }
+}
+
+/// Alpha while `remaining` seconds are left: full until the last `fade_duration` seconds,
+/// then linear down to zero.
+pub fn fade_out_alpha(remaining: f32, fade_duration: f32) -> f32 {
+    if remaining < fade_duration {
+        (remaining / fade_duration).max(0.0)
+    } else {
+        1.0
     }
 }
 
+/// Node `bottom` offset at entrance `progress`, eased out so the panel decelerates as it
+/// settles from `from` to `to`.
+pub fn slide_offset(progress: f32, from: f32, to: f32) -> f32 {
+    let eased = 1.0 - (1.0 - progress.clamp(0.0, 1.0)).powi(3);
+    from + (to - from) * eased
+}
+
+/// Text inside a dialogue panel. Keeps the unfaded color so the panel's alpha can be
+/// reapplied every frame.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct DialoguePanelText {
+    pub base_color: Color,
+}
+
 /// Resource tracking the currently active dialogue panel.
 ///
 /// Ensures only one panel is displayed at a time.
@@ -94,11 +142,32 @@ pub struct DialoguePanelSettings {
     /// How long panels remain visible (seconds).
     pub lifetime_seconds: f32,
 
+    /// How long the "…" panel shown after a failed request remains visible (seconds).
+    pub failure_lifetime_seconds: f32,
+
     /// Duration of fade-out animation (seconds).
     pub fade_seconds: f32,
 
-    /// Panel width (pixels).
-    pub panel_width: f32,
+    /// Clock the lifetime, fade, and entrance follow. Real by default, so fast-forwarding
+    /// does not rush the player's reading.
+    pub time_basis: TimeBasis,
+
+    /// Duration of the slide-up and fade-in entrance (seconds).
+    pub intro_seconds: f32,
+
+    /// Dialogue panel width as a fraction of the layout band's width.
+    pub panel_width_ratio: f32,
+
+    /// Dialogue panel width bounds (pixels).
+    pub panel_min_width: f32,
+    pub panel_max_width: f32,
+
+    /// Player response window width as a fraction of the layout band's width.
+    pub response_width_ratio: f32,
+
+    /// Player response window width bounds (pixels).
+    pub response_min_width: f32,
+    pub response_max_width: f32,
 
     /// Maximum panel height (pixels).
     pub panel_max_height: f32,
@@ -109,11 +178,16 @@ pub struct DialoguePanelSettings {
     /// Border width (pixels).
     pub border_width: f32,
 
-    /// Offset from bottom edge of screen (pixels).
-    pub bottom_offset: f32,
+    /// Gap between panels and the screen edges as a fraction of window height.
+    pub edge_offset_ratio: f32,
+
+    /// Screen edge gap bounds (pixels).
+    pub edge_offset_min: f32,
+    pub edge_offset_max: f32,
 
-    /// Offset from right edge of screen (pixels).
-    pub right_offset: f32,
+    /// Widest aspect ratio panels spread across; wider windows centre them in a band of
+    /// this aspect instead of pushing them to the far corners.
+    pub max_layout_aspect: f32,
 
     /// Font size for NPC name (points).
     pub name_font_size: f32,
@@ -123,22 +197,147 @@ pub struct DialoguePanelSettings {
 
     /// Font size for icon emoji (points).
     pub icon_font_size: f32,
+
+    /// Speaker and listener farther apart than this (world units) shout.
+    pub shout_distance: f32,
+
+    /// A line to the player from within this distance (world units) is whispered.
+    pub whisper_distance: f32,
+
+    /// Dialogue text size multiplier for shouted lines.
+    pub shout_font_scale: f32,
+
+    /// Dialogue text size multiplier for whispered lines.
+    pub whisper_font_scale: f32,
+
+    /// Clockwise slant of whispered text (radians), standing in for italics.
+    pub whisper_slant_radians: f32,
 }
 
 impl Default for DialoguePanelSettings {
     fn default() -> Self {
         Self {
             lifetime_seconds: 10.0,
+            failure_lifetime_seconds: 3.0,
             fade_seconds: 2.0,
-            panel_width: 350.0,
+            time_basis: TimeBasis::Real,
+            intro_seconds: 0.35,
+            panel_width_ratio: 0.25,
+            panel_min_width: 300.0,
+            panel_max_width: 480.0,
+            response_width_ratio: 0.1875,
+            response_min_width: 280.0,
+            response_max_width: 420.0,
             panel_max_height: 200.0,
             padding: 12.0,
             border_width: 2.0,
-            bottom_offset: 20.0,
-            right_offset: 20.0,
+            edge_offset_ratio: 0.02,
+            edge_offset_min: 10.0,
+            edge_offset_max: 40.0,
+            max_layout_aspect: 16.0 / 9.0,
             name_font_size: 18.0,
             text_font_size: 16.0,
             icon_font_size: 20.0,
+            shout_distance: 15.0,
+            whisper_distance: 2.5,
+            shout_font_scale: 1.3,
+            whisper_font_scale: 0.85,
+            whisper_slant_radians: 0.05,
+        }
+    }
+}
+
+impl DialoguePanelSettings {
+    /// Where the entrance slide starts: a full panel height below the screen edge.
+    pub fn intro_start_offset(&self) -> f32 {
+        -self.panel_max_height
+    }
+}
+
+/// How a line is delivered, judged from how far apart the speakers stand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DialogueDelivery {
+    #[default]
+    Normal,
+    /// Called across a distance: larger, warmer text with a "!" prefix.
+    Shout,
+    /// Said quietly to the player up close: smaller, dimmer, slanted text.
+    Whisper,
+}
+
+/// Classifies a line from the speaker-listener `distance` (`None` when either position is
+/// unknown). Whispers need the player as listener and win over shouting.
+pub fn classify_delivery(
+    distance: Option<f32>,
+    is_player_target: bool,
+    settings: &DialoguePanelSettings,
+) -> DialogueDelivery {
+    match distance {
+        Some(distance) if is_player_target && distance <= settings.whisper_distance => {
+            DialogueDelivery::Whisper
         }
+        Some(distance) if distance > settings.shout_distance => DialogueDelivery::Shout,
+        _ => DialogueDelivery::Normal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intro_and_fade_out_alpha_interpolate_linearly() {
+        assert_eq!(intro_progress(0.0, 0.4), 0.0);
+        assert!((intro_progress(0.1, 0.4) - 0.25).abs() < 1e-6);
+        assert_eq!(intro_progress(1.0, 0.4), 1.0);
+        assert_eq!(intro_progress(0.0, 0.0), 1.0, "no intro means fully in");
+
+        assert_eq!(fade_out_alpha(5.0, 2.0), 1.0);
+        assert!((fade_out_alpha(0.5, 2.0) - 0.25).abs() < 1e-6);
+        assert_eq!(fade_out_alpha(0.0, 2.0), 0.0);
+        assert_eq!(fade_out_alpha(0.0, 0.0), 1.0, "no fade means no dimming");
+    }
+
+    #[test]
+    fn slide_eases_from_below_the_edge_into_place() {
+        assert_eq!(slide_offset(0.0, -200.0, 20.0), -200.0);
+        assert_eq!(slide_offset(1.0, -200.0, 20.0), 20.0);
+        assert_eq!(slide_offset(3.0, -200.0, 20.0), 20.0);
+        let halfway = slide_offset(0.5, -200.0, 20.0);
+        assert!(
+            halfway > -90.0 && halfway < 20.0,
+            "ease-out covers most of the distance early"
+        );
+    }
+
+    #[test]
+    fn delivery_follows_distance_and_listener() {
+        let settings = DialoguePanelSettings::default();
+        let classify = |distance, player| classify_delivery(distance, player, &settings);
+        assert_eq!(classify(Some(5.0), false), DialogueDelivery::Normal);
+        assert_eq!(classify(Some(15.0), false), DialogueDelivery::Normal);
+        assert_eq!(classify(Some(15.1), false), DialogueDelivery::Shout);
+        assert_eq!(classify(Some(40.0), true), DialogueDelivery::Shout);
+        assert_eq!(classify(Some(2.5), true), DialogueDelivery::Whisper);
+        assert_eq!(
+            classify(Some(1.0), false),
+            DialogueDelivery::Normal,
+            "only the player is whispered to"
+        );
+        assert_eq!(classify(Some(3.0), true), DialogueDelivery::Normal);
+        assert_eq!(classify(None, true), DialogueDelivery::Normal);
+    }
+
+    #[test]
+    fn panel_alpha_combines_intro_and_fade_out() {
+        let mut panel = DialoguePanel::new(NpcId::new(1), "Ann".into(), "Hi".into(), 2.0, 1.0, 0.5);
+        assert_eq!(panel.fade_alpha(), 0.0);
+        panel.tick(std::time::Duration::from_millis(250));
+        assert!((panel.fade_alpha() - 0.5).abs() < 1e-4);
+        panel.tick(std::time::Duration::from_millis(1250));
+        assert!(
+            (panel.fade_alpha() - 0.5).abs() < 1e-4,
+            "half a second left"
+        );
     }
 }
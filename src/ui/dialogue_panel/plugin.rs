@@ -2,25 +2,220 @@
 //
 // UiPlugin coordinates dialogue panel systems and resources.
 
-use bevy::prelude::*;
+use bevy::{diagnostic::FrameTimeDiagnosticsPlugin, prelude::*, ui::UiSystems};
 
 use super::components::{DialoguePanelSettings, DialoguePanelTracker};
-use super::systems::{spawn_dialogue_panel, update_dialogue_panel};
+use super::systems::{
+    forget_despawned_npc_panels, spawn_dialogue_panel, spawn_failure_panel, update_dialogue_panel,
+};
+#[cfg(feature = "profiling")]
+use crate::core::profiling::time_system;
+use crate::{
+    core::{config::report_config_result, input::KeyboardCapture, schedule::FramePhase},
+    dialogue::{events::ApiBudgetExhaustedEvent, status::DialogueBrokerStatus},
+    economy::events::{SkillLevelUpEvent, TradeCompletedEvent},
+    ui::{
+        clock_widget::{spawn_clock_widget, update_clock_widget, update_reputation_label},
+        config_banner::{
+            handle_config_banner_key, open_config_banner_on_startup, sync_config_banner,
+            ConfigBanner,
+        },
+        console::systems::{
+            run_console_commands, scroll_dev_console, sync_dev_console_panel, type_in_dev_console,
+            DevConsole,
+        },
+        emote::{
+            fade_emote_labels, reload_emote_settings, show_pending_emotes, show_response_emotes,
+            EmoteSettings,
+        },
+        layout::{apply_ui_layout, update_ui_layout, UiLayout},
+        minimap::{
+            settings::{reload_minimap_settings, MinimapSettings},
+            systems::{
+                select_from_minimap, sync_minimap_panel, toggle_minimap, update_minimap_markers,
+                MinimapState,
+            },
+        },
+        subtitles::{
+            queue::SubtitleQueue,
+            settings::{reload_subtitle_settings, SubtitleSettings, CONFIG_PATH as UI_CONFIG_PATH},
+            systems::{
+                collect_subtitles, spawn_subtitle_strip, toggle_subtitles, update_subtitle_strip,
+            },
+        },
+        thinking_indicator::{animate_thinking_indicators, sync_thinking_indicators},
+        toasts::{
+            queue::ToastQueue,
+            settings::{reload_toast_settings, ToastSettings},
+            systems::{
+                spawn_toast_stack, toast_broker_changes, toast_budget_exhaustion,
+                toast_skill_level_ups, toast_trades, update_toast_stack,
+            },
+        },
+        visibility::{
+            apply_ui_visibility, cycle_ui_visibility, screen_ui_visible, world_ui_visible,
+            UiVisibilityChangedEvent, UiVisibilityState,
+        },
+        window_title::update_window_title,
+        world_label::place_world_labels,
+    },
+    world::selection::SelectedNpc,
+};
 
+/// Panels, HUD, world labels, and the developer console.
 pub struct UiPlugin;
 
 impl Plugin for UiPlugin {
     fn build(&self, app: &mut App) {
         info!("UiPlugin registered");
 
+        let subtitle_settings = report_config_result(
+            app.world_mut(),
+            UI_CONFIG_PATH,
+            SubtitleSettings::load(),
+            SubtitleSettings::default,
+        );
+        let emote_settings = report_config_result(
+            app.world_mut(),
+            UI_CONFIG_PATH,
+            EmoteSettings::load(),
+            EmoteSettings::default,
+        );
+        let toast_settings = report_config_result(
+            app.world_mut(),
+            UI_CONFIG_PATH,
+            ToastSettings::load(),
+            ToastSettings::default,
+        );
+        let minimap_settings = report_config_result(
+            app.world_mut(),
+            UI_CONFIG_PATH,
+            MinimapSettings::load(),
+            MinimapSettings::default,
+        );
+
+        // Feeds the FPS reading in the window title.
+        if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+            app.add_plugins(FrameTimeDiagnosticsPlugin::default());
+        }
+
         app.insert_resource(DialoguePanelSettings::default())
             .insert_resource(DialoguePanelTracker::default())
+            .init_resource::<UiLayout>()
+            .init_resource::<ConfigBanner>()
+            .insert_resource(SubtitleQueue::new(&subtitle_settings))
+            .insert_resource(subtitle_settings)
+            .insert_resource(emote_settings)
+            .insert_resource(ToastQueue::new(toast_settings.max_visible))
+            .insert_resource(toast_settings)
+            .insert_resource(MinimapState::new(minimap_settings.open_on_start))
+            .insert_resource(minimap_settings)
+            .init_resource::<UiVisibilityState>()
+            .init_resource::<DevConsole>()
+            .init_resource::<KeyboardCapture>()
+            .add_message::<UiVisibilityChangedEvent>()
             .add_systems(
-                Update,
+                Startup,
                 (
-                    spawn_dialogue_panel,
-                    update_dialogue_panel.after(spawn_dialogue_panel),
+                    open_config_banner_on_startup,
+                    spawn_subtitle_strip,
+                    spawn_clock_widget,
+                    spawn_toast_stack,
                 ),
+            )
+            .add_systems(
+                Update,
+                (
+                    update_ui_layout.before(spawn_dialogue_panel),
+                    spawn_dialogue_panel.run_if(screen_ui_visible),
+                    spawn_failure_panel
+                        .after(spawn_dialogue_panel)
+                        .run_if(screen_ui_visible),
+                    update_dialogue_panel.after(spawn_failure_panel),
+                    forget_despawned_npc_panels.after(update_dialogue_panel),
+                    update_window_title,
+                    update_clock_widget,
+                    update_reputation_label,
+                    handle_config_banner_key,
+                    sync_config_banner.after(handle_config_banner_key),
+                    (
+                        reload_subtitle_settings,
+                        toggle_subtitles,
+                        collect_subtitles,
+                        update_subtitle_strip,
+                    )
+                        .chain(),
+                    cycle_ui_visibility,
+                    sync_thinking_indicators,
+                    animate_thinking_indicators.after(sync_thinking_indicators),
+                    place_world_labels
+                        .after(sync_thinking_indicators)
+                        .run_if(world_ui_visible),
+                    (
+                        reload_emote_settings,
+                        show_pending_emotes,
+                        show_response_emotes,
+                        fade_emote_labels,
+                    )
+                        .chain()
+                        .before(place_world_labels),
+                )
+                    .in_set(FramePhase::Presentation),
+            )
+            .add_systems(
+                Update,
+                (
+                    reload_toast_settings,
+                    // Each producer only runs once the module that writes its input is added.
+                    (
+                        toast_trades.run_if(resource_exists::<Messages<TradeCompletedEvent>>),
+                        toast_skill_level_ups
+                            .run_if(resource_exists::<Messages<SkillLevelUpEvent>>),
+                        toast_budget_exhaustion
+                            .run_if(resource_exists::<Messages<ApiBudgetExhaustedEvent>>),
+                        toast_broker_changes.run_if(resource_exists::<DialogueBrokerStatus>),
+                    ),
+                    update_toast_stack,
+                )
+                    .chain()
+                    .in_set(FramePhase::Presentation),
+            )
+            // Console commands land before the simulation reads this frame's state.
+            .add_systems(
+                Update,
+                (type_in_dev_console, run_console_commands)
+                    .chain()
+                    .in_set(FramePhase::SimTick),
+            )
+            .add_systems(
+                Update,
+                (sync_dev_console_panel, scroll_dev_console)
+                    .chain()
+                    .in_set(FramePhase::Presentation),
+            )
+            .add_systems(
+                Update,
+                (
+                    reload_minimap_settings,
+                    toggle_minimap,
+                    sync_minimap_panel,
+                    update_minimap_markers,
+                    select_from_minimap
+                        .run_if(screen_ui_visible)
+                        .run_if(resource_exists::<SelectedNpc>),
+                )
+                    .chain()
+                    .in_set(FramePhase::Presentation),
+            )
+            .add_systems(
+                PostUpdate,
+                (apply_ui_visibility, apply_ui_layout).before(UiSystems::Layout),
             );
+
+        #[cfg(feature = "profiling")]
+        {
+            time_system(app, Update, "spawn_dialogue_panel", spawn_dialogue_panel);
+            time_system(app, Update, "update_dialogue_panel", update_dialogue_panel);
+        }
     }
 }
@@ -4,10 +4,21 @@
 
 use bevy::{ecs::message::MessageReader, prelude::*};
 
-use crate::dialogue::events::DialogueResponseEvent;
-use crate::npc::components::Identity;
+use crate::core::plugin::SimulationClock;
+use crate::dialogue::errors::DialogueErrorKind;
+use crate::dialogue::events::{DialogueRequestFailedEvent, DialogueResponseEvent};
+use crate::dialogue::trace::{RequestTracing, TracePhase};
+use crate::npc::components::{Identity, NpcId};
+use crate::npc::events::NpcDespawnedEvent;
+use crate::npc::spatial::NpcIndex;
+use crate::player::components::Player;
+use crate::ui::layout::{ScreenAnchor, UiLayout};
+use crate::ui::visibility::UiLayer;
 
-use super::components::{DialoguePanel, DialoguePanelSettings, DialoguePanelTracker};
+use super::components::{
+    classify_delivery, slide_offset, DialogueDelivery, DialoguePanel, DialoguePanelSettings,
+    DialoguePanelText, DialoguePanelTracker,
+};
 
 // Visual constants
 const BACKGROUND_COLOR: Color = Color::srgba(0.1, 0.1, 0.1, 0.9);
@@ -16,145 +27,342 @@ const TEXT_COLOR: Color = Color::WHITE;
 const NAME_COLOR: Color = Color::srgb(1.0, 0.9, 0.4); // Yellow/gold
 const ICON_TEXT: &str = "💬 ";
 
+// Delivery styles for the dialogue body
+const SHOUT_TEXT_COLOR: Color = Color::srgb(1.0, 0.75, 0.45);
+const SHOUT_PREFIX: &str = "! ";
+const WHISPER_TEXT_COLOR: Color = Color::srgba(0.8, 0.8, 0.85, 0.7);
+
+// Quiet footer under a reply the provider cut off at its output cap
+const TRUNCATED_HINT_TEXT: &str = "(cut short)";
+const TRUNCATED_HINT_COLOR: Color = Color::srgba(0.7, 0.7, 0.7, 0.6);
+const TRUNCATED_HINT_SCALE: f32 = 0.7;
+
+// Failure feedback shown in place of a reply
+const FAILURE_BUBBLE_TEXT: &str = "…";
+const RATE_LIMITED_BUBBLE_TEXT: &str = "*mumbles*";
+
 /// Spawn or update dialogue panels when NPCs speak.
 ///
-/// Creates UI NodeBundle hierarchy positioned at bottom-right corner.
+/// Creates UI NodeBundle hierarchy anchored at the bottom-right of the layout. The body is styled
+/// as a shout or whisper from how far the speaker stands from their listener, and a reply the
+/// provider cut short gets a faint footer. Each shown reply is stamped `Rendered` on its
+/// request's trace.
+#[allow(clippy::too_many_arguments)]
 pub fn spawn_dialogue_panel(
     mut commands: Commands,
     mut tracker: ResMut<DialoguePanelTracker>,
     settings: Res<DialoguePanelSettings>,
+    layout: Res<UiLayout>,
     mut events: MessageReader<DialogueResponseEvent>,
+    npc_index: Res<NpcIndex>,
     npc_query: Query<&Identity>,
+    positions: Query<&Transform, With<Identity>>,
+    player: Query<&Transform, With<Player>>,
+    mut tracing: RequestTracing,
 ) {
+    #[cfg(feature = "profiling")]
+    let _span = info_span!("spawn_dialogue_panel").entered();
     for event in events.read() {
         let npc_id = event.response.speaker;
 
         // Find the NPC's display name
-        let speaker_name = npc_query
-            .iter()
-            .find(|identity| identity.id == npc_id)
-            .map(|identity| identity.display_name.clone())
-            .unwrap_or_else(|| format!("NPC-{}", npc_id));
+        let speaker_name = Identity::name_of(&npc_index, &npc_query, npc_id);
 
         // Find the target's display name (if speaking to someone specific)
         let target_name = event.response.target.and_then(|target_id| {
+            let entity = npc_index.entity(target_id)?;
             npc_query
-                .iter()
-                .find(|identity| identity.id == target_id)
+                .get(entity)
+                .ok()
                 .map(|identity| identity.display_name.clone())
         });
 
         let content = event.response.content.clone();
 
+        let is_player_target = event
+            .response
+            .target
+            .is_some_and(|target| target.is_player());
+        let position_of = |id: NpcId| {
+            if id.is_player() {
+                return player.single().ok().map(|transform| transform.translation);
+            }
+            let entity = npc_index.entity(id)?;
+            positions
+                .get(entity)
+                .ok()
+                .map(|transform| transform.translation)
+        };
+        let distance = event
+            .response
+            .target
+            .and_then(|target| Some(position_of(npc_id)?.distance(position_of(target)?)));
+        let delivery = classify_delivery(distance, is_player_target, &settings);
+
+        let request_id = event.response.request_id;
         if let Some(ref target) = target_name {
             info!(
-                "Spawning dialogue panel for {} ({} → {}): \"{}\"",
-                npc_id, speaker_name, target, content
+                "Spawning dialogue panel for {} ({} → {}, {}): \"{}\"",
+                npc_id, speaker_name, target, request_id, content
             );
         } else {
             info!(
-                "Spawning dialogue panel for {} ({}): \"{}\"",
-                npc_id, speaker_name, content
+                "Spawning dialogue panel for {} ({}, {}): \"{}\"",
+                npc_id, speaker_name, request_id, content
             );
         }
+        tracing.record(request_id, TracePhase::Rendered);
 
-        // If panel already exists, despawn it first
-        if let Some(old_panel) = tracker.active_panel {
-            commands.entity(old_panel).despawn();
-        }
+        spawn_panel(
+            &mut commands,
+            &mut tracker,
+            &settings,
+            &layout,
+            PanelContent {
+                npc_id,
+                speaker_name,
+                target_name,
+                content,
+                delivery,
+                truncated: event.response.truncated,
+            },
+            settings.lifetime_seconds,
+        );
+    }
+}
 
-        // Spawn new panel
-        let panel_entity = commands
-            .spawn((
+/// Spawns a short-lived "…" panel when a speaker's request fails, so the silence reads as
+/// the NPC being distracted rather than the game hanging.
+pub fn spawn_failure_panel(
+    mut commands: Commands,
+    mut tracker: ResMut<DialoguePanelTracker>,
+    settings: Res<DialoguePanelSettings>,
+    layout: Res<UiLayout>,
+    mut failures: MessageReader<DialogueRequestFailedEvent>,
+    npc_index: Res<NpcIndex>,
+    npc_query: Query<&Identity>,
+) {
+    for event in failures.read() {
+        let content = match event.error.kind {
+            DialogueErrorKind::RateLimited { .. } => RATE_LIMITED_BUBBLE_TEXT,
+            _ => FAILURE_BUBBLE_TEXT,
+        };
+
+        debug!(
+            "Showing failure feedback for {} ({})",
+            event.speaker, event.error.request_id
+        );
+
+        spawn_panel(
+            &mut commands,
+            &mut tracker,
+            &settings,
+            &layout,
+            PanelContent {
+                npc_id: event.speaker,
+                speaker_name: Identity::name_of(&npc_index, &npc_query, event.speaker),
+                target_name: None,
+                content: content.to_string(),
+                delivery: DialogueDelivery::Normal,
+                truncated: false,
+            },
+            settings.failure_lifetime_seconds,
+        );
+    }
+}
+
+struct PanelContent {
+    npc_id: NpcId,
+    speaker_name: String,
+    target_name: Option<String>,
+    content: String,
+    delivery: DialogueDelivery,
+    truncated: bool,
+}
+
+/// Body text, font size, color, and slant for a line delivered as `delivery`.
+fn body_style(
+    content: &str,
+    delivery: DialogueDelivery,
+    settings: &DialoguePanelSettings,
+) -> (String, f32, Color, UiTransform) {
+    match delivery {
+        DialogueDelivery::Normal => (
+            content.to_string(),
+            settings.text_font_size,
+            TEXT_COLOR,
+            UiTransform::IDENTITY,
+        ),
+        DialogueDelivery::Shout => (
+            format!("{SHOUT_PREFIX}{content}"),
+            settings.text_font_size * settings.shout_font_scale,
+            SHOUT_TEXT_COLOR,
+            UiTransform::IDENTITY,
+        ),
+        DialogueDelivery::Whisper => (
+            content.to_string(),
+            settings.text_font_size * settings.whisper_font_scale,
+            WHISPER_TEXT_COLOR,
+            UiTransform::from_rotation(Rot2::radians(settings.whisper_slant_radians)),
+        ),
+    }
+}
+
+/// Replaces the active panel with a new one for `panel.npc_id`.
+fn spawn_panel(
+    commands: &mut Commands,
+    tracker: &mut DialoguePanelTracker,
+    settings: &DialoguePanelSettings,
+    layout: &UiLayout,
+    panel: PanelContent,
+    lifetime_seconds: f32,
+) {
+    let PanelContent {
+        npc_id,
+        speaker_name,
+        target_name,
+        content,
+        delivery,
+        truncated,
+    } = panel;
+    let (body, body_font_size, body_color, body_transform) =
+        body_style(&content, delivery, settings);
+
+    // If panel already exists, despawn it first
+    if let Some(old_panel) = tracker.active_panel {
+        commands.entity(old_panel).despawn();
+    }
+
+    // Spawn new panel below the screen edge and fully transparent; the entrance animation
+    // in `update_dialogue_panel` brings it in.
+    let mut node = Node {
+        position_type: PositionType::Absolute,
+        bottom: Val::Px(settings.intro_start_offset()),
+        max_height: Val::Px(settings.panel_max_height),
+        padding: UiRect::all(Val::Px(settings.padding)),
+        border: UiRect::all(Val::Px(settings.border_width)),
+        flex_direction: FlexDirection::Column,
+        ..default()
+    };
+    layout.place(ScreenAnchor::DialoguePanel, &mut node);
+    let panel_entity = commands
+        .spawn((
+            node,
+            BackgroundColor(BACKGROUND_COLOR.with_alpha(0.0)),
+            BorderColor::from(BORDER_COLOR.with_alpha(0.0)),
+            DialoguePanel::new(
+                npc_id,
+                speaker_name.clone(),
+                content.clone(),
+                lifetime_seconds,
+                settings.fade_seconds,
+                settings.intro_seconds,
+            ),
+            ScreenAnchor::DialoguePanel,
+            UiLayer::Screen,
+        ))
+        .with_children(|parent| {
+            // Header row (icon + name)
+            parent
+                .spawn(Node {
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    margin: UiRect::bottom(Val::Px(8.0)),
+                    ..default()
+                })
+                .with_children(|header| {
+                    // Icon
+                    header.spawn((
+                        Text::new(ICON_TEXT),
+                        TextFont {
+                            font_size: settings.icon_font_size,
+                            ..default()
+                        },
+                        panel_text(TEXT_COLOR),
+                    ));
+
+                    // NPC Name (with target if available)
+                    let display_text = if let Some(ref target) = target_name {
+                        format!("{} → {}", speaker_name, target)
+                    } else {
+                        speaker_name.clone()
+                    };
+
+                    header.spawn((
+                        Text::new(display_text),
+                        TextFont {
+                            font_size: settings.name_font_size,
+                            ..default()
+                        },
+                        panel_text(NAME_COLOR),
+                    ));
+                });
+
+            // Dialogue text body
+            parent.spawn((
+                Text::new(body),
+                TextFont {
+                    font_size: body_font_size,
+                    ..default()
+                },
+                panel_text(body_color),
                 Node {
-                    position_type: PositionType::Absolute,
-                    bottom: Val::Px(settings.bottom_offset),
-                    right: Val::Px(settings.right_offset),
-                    width: Val::Px(settings.panel_width),
-                    max_height: Val::Px(settings.panel_max_height),
-                    padding: UiRect::all(Val::Px(settings.padding)),
-                    border: UiRect::all(Val::Px(settings.border_width)),
-                    flex_direction: FlexDirection::Column,
+                    max_width: Val::Percent(100.0),
                     ..default()
                 },
-                BackgroundColor(BACKGROUND_COLOR),
-                BorderColor::from(BORDER_COLOR),
-                DialoguePanel::new(
-                    npc_id,
-                    speaker_name.clone(),
-                    content.clone(),
-                    settings.lifetime_seconds,
-                    settings.fade_seconds,
-                ),
-            ))
-            .with_children(|parent| {
-                // Header row (icon + name)
-                parent
-                    .spawn(Node {
-                        flex_direction: FlexDirection::Row,
-                        align_items: AlignItems::Center,
-                        margin: UiRect::bottom(Val::Px(8.0)),
-                        ..default()
-                    })
-                    .with_children(|header| {
-                        // Icon
-                        header.spawn((
-                            Text::new(ICON_TEXT),
-                            TextFont {
-                                font_size: settings.icon_font_size,
-                                ..default()
-                            },
-                            TextColor(TEXT_COLOR),
-                        ));
-
-                        // NPC Name (with target if available)
-                        let display_text = if let Some(ref target) = target_name {
-                            format!("{} → {}", speaker_name, target)
-                        } else {
-                            speaker_name.clone()
-                        };
-
-                        header.spawn((
-                            Text::new(display_text),
-                            TextFont {
-                                font_size: settings.name_font_size,
-                                ..default()
-                            },
-                            TextColor(NAME_COLOR),
-                        ));
-                    });
-
-                // Dialogue text body
+                body_transform,
+            ));
+
+            if truncated {
                 parent.spawn((
-                    Text::new(&content),
+                    Text::new(TRUNCATED_HINT_TEXT),
                     TextFont {
-                        font_size: settings.text_font_size,
-                        ..default()
-                    },
-                    TextColor(TEXT_COLOR),
-                    Node {
-                        max_width: Val::Px(settings.panel_width - settings.padding * 2.0),
+                        font_size: settings.text_font_size * TRUNCATED_HINT_SCALE,
                         ..default()
                     },
+                    panel_text(TRUNCATED_HINT_COLOR),
                 ));
-            })
-            .id();
+            }
+        })
+        .id();
 
-        tracker.active_panel = Some(panel_entity);
-        tracker.by_npc.insert(npc_id, panel_entity);
-    }
+    tracker.active_panel = Some(panel_entity);
+    tracker.by_npc.insert(npc_id, panel_entity);
 }
 
-/// Update dialogue panels: tick lifetime, apply fade-out, despawn when finished.
+/// Panel text starts invisible; `update_dialogue_panel` fades it in with the panel.
+fn panel_text(base_color: Color) -> (TextColor, DialoguePanelText) {
+    (
+        TextColor(base_color.with_alpha(0.0)),
+        DialoguePanelText { base_color },
+    )
+}
+
+/// Update dialogue panels: tick lifetime, slide and fade in on entrance, fade out at the
+/// end (background, border, and every text descendant), and despawn when finished.
+#[allow(clippy::too_many_arguments)]
 pub fn update_dialogue_panel(
     mut commands: Commands,
     time: Res<Time>,
+    sim_clock: Res<SimulationClock>,
+    settings: Res<DialoguePanelSettings>,
+    layout: Res<UiLayout>,
     mut tracker: ResMut<DialoguePanelTracker>,
-    mut panel_query: Query<(Entity, &mut DialoguePanel)>,
-    mut background_query: Query<&mut BackgroundColor>,
+    mut panel_query: Query<(
+        Entity,
+        &mut DialoguePanel,
+        &mut Node,
+        &mut BackgroundColor,
+        &mut BorderColor,
+    )>,
+    children: Query<&Children>,
+    mut text_query: Query<(&DialoguePanelText, &mut TextColor)>,
 ) {
-    for (entity, mut panel) in panel_query.iter_mut() {
-        panel.tick(time.delta());
+    #[cfg(feature = "profiling")]
+    let _span = info_span!("update_dialogue_panel").entered();
+    let delta = settings.time_basis.delta(&time, &sim_clock);
+    for (entity, mut panel, mut node, mut background, mut border) in panel_query.iter_mut() {
+        panel.tick(delta);
 
         if panel.is_finished() {
             // Despawn panel
@@ -164,17 +372,270 @@ pub fn update_dialogue_panel(
             continue;
         }
 
-        // Apply fade-out during final seconds
+        node.bottom = Val::Px(slide_offset(
+            panel.intro_progress(),
+            settings.intro_start_offset(),
+            layout.edge_offset,
+        ));
+
         let alpha = panel.fade_alpha();
+        background.0 = BACKGROUND_COLOR.with_alpha(alpha * BACKGROUND_COLOR.alpha());
+        *border = BorderColor::from(BORDER_COLOR.with_alpha(alpha));
+        for descendant in children.iter_descendants(entity) {
+            if let Ok((text, mut color)) = text_query.get_mut(descendant) {
+                color.0 = text.base_color.with_alpha(text.base_color.alpha() * alpha);
+            }
+        }
+    }
+}
+
+/// Drops despawned NPCs from the panel tracker. A panel already on screen stays until it
+/// expires.
+pub fn forget_despawned_npc_panels(
+    mut despawned: MessageReader<NpcDespawnedEvent>,
+    mut tracker: ResMut<DialoguePanelTracker>,
+) {
+    for event in despawned.read() {
+        tracker.by_npc.remove(&event.npc_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue::{
+        broker::DialogueProviderKind, errors::DialogueError, types::DialogueRequestId,
+    };
+    use crate::npc::spatial::index_npcs;
+
+    fn failure(kind: DialogueErrorKind, speaker: NpcId) -> DialogueRequestFailedEvent {
+        DialogueRequestFailedEvent {
+            error: DialogueError::new(
+                DialogueRequestId::new(1),
+                DialogueProviderKind::OpenAi,
+                kind,
+            ),
+            speaker,
+            target: None,
+        }
+    }
+
+    fn panel_texts(app: &mut App) -> Vec<String> {
+        app.world_mut()
+            .query::<&Text>()
+            .iter(app.world())
+            .map(|text| text.0.clone())
+            .collect()
+    }
+
+    #[test]
+    fn failure_event_spawns_short_lived_panel_for_speaker() {
+        let mut app = App::new();
+        app.insert_resource(DialoguePanelSettings::default())
+            .init_resource::<DialoguePanelTracker>()
+            .init_resource::<UiLayout>()
+            .add_message::<DialogueRequestFailedEvent>()
+            .init_resource::<NpcIndex>()
+            .add_systems(Update, (index_npcs, spawn_failure_panel).chain());
+
+        let speaker = NpcId::new(7);
+        app.world_mut().spawn(Identity::new(speaker, "Edda", 33.0));
+        app.world_mut().write_message(failure(
+            DialogueErrorKind::provider_failure("boom"),
+            speaker,
+        ));
+        app.update();
+
+        let panel = app.world().resource::<DialoguePanelTracker>().by_npc[&speaker];
+        let settings = DialoguePanelSettings::default();
+        let component = app.world().get::<DialoguePanel>(panel).unwrap();
+        assert_eq!(component.npc_id(), speaker);
+        assert!(!component.is_finished());
+
+        let texts = panel_texts(&mut app);
+        assert!(texts.contains(&FAILURE_BUBBLE_TEXT.to_string()));
+        assert!(texts.contains(&"Edda".to_string()));
+        assert!(settings.failure_lifetime_seconds < settings.lifetime_seconds);
+
+        app.world_mut()
+            .write_message(failure(DialogueErrorKind::rate_limited(4.0), speaker));
+        app.update();
+        assert!(panel_texts(&mut app).contains(&RATE_LIMITED_BUBBLE_TEXT.to_string()));
+    }
+
+    #[test]
+    fn shouted_and_whispered_lines_get_their_body_style() {
+        use crate::dialogue::types::DialogueResponse;
+
+        let settings = DialoguePanelSettings::default();
+        let mut app = App::new();
+        app.insert_resource(DialoguePanelSettings::default())
+            .init_resource::<DialoguePanelTracker>()
+            .init_resource::<UiLayout>()
+            .add_message::<DialogueResponseEvent>()
+            .init_resource::<NpcIndex>()
+            .add_systems(Update, (index_npcs, spawn_dialogue_panel).chain());
+        let (speaker, far) = (NpcId::new(1), NpcId::new(2));
+        app.world_mut()
+            .spawn((Identity::new(speaker, "Alric", 30.0), Transform::default()));
+        app.world_mut().spawn((
+            Identity::new(far, "Bryn", 30.0),
+            Transform::from_xyz(30.0, 0.0, 0.0),
+        ));
+        app.world_mut()
+            .spawn((Player, Transform::from_xyz(1.0, 0.0, 0.0)));
+
+        let mut say = |target: NpcId, line: &str| {
+            app.world_mut().write_message(DialogueResponseEvent {
+                response: DialogueResponse::new(
+                    DialogueRequestId::new(1),
+                    DialogueProviderKind::OpenAi,
+                    speaker,
+                    Some(target),
+                    line,
+                ),
+                context: Default::default(),
+            });
+            app.update();
+            app.world_mut()
+                .query::<(&Text, &TextFont, &DialoguePanelText, &UiTransform)>()
+                .iter(app.world())
+                .find(|(text, ..)| text.0.contains(line))
+                .map(|(text, font, color, transform)| {
+                    (text.0.clone(), font.font_size, color.base_color, *transform)
+                })
+                .unwrap()
+        };
+
+        let (text, size, color, transform) = say(far, "Grain's ready!");
+        assert_eq!(text, format!("{SHOUT_PREFIX}Grain's ready!"));
+        assert_eq!(size, settings.text_font_size * settings.shout_font_scale);
+        assert_eq!(color, SHOUT_TEXT_COLOR);
+        assert_eq!(transform, UiTransform::IDENTITY);
+
+        let (text, size, color, transform) = say(NpcId::player(), "Keep this quiet.");
+        assert_eq!(text, "Keep this quiet.");
+        assert_eq!(size, settings.text_font_size * settings.whisper_font_scale);
+        assert_eq!(color, WHISPER_TEXT_COLOR);
+        assert_eq!(
+            transform.rotation,
+            Rot2::radians(settings.whisper_slant_radians)
+        );
+    }
+
+    #[test]
+    fn truncated_replies_get_a_faint_footer() {
+        use crate::dialogue::types::DialogueResponse;
+
+        let mut app = App::new();
+        app.insert_resource(DialoguePanelSettings::default())
+            .init_resource::<DialoguePanelTracker>()
+            .init_resource::<UiLayout>()
+            .add_message::<DialogueResponseEvent>()
+            .init_resource::<NpcIndex>()
+            .add_systems(Update, (index_npcs, spawn_dialogue_panel).chain());
+        let speaker = NpcId::new(4);
+        app.world_mut().spawn(Identity::new(speaker, "Oswin", 50.0));
 
-        // Fade background (maintain transparency)
-        if let Ok(mut bg) = background_query.get_mut(entity) {
-            bg.0 = BACKGROUND_COLOR.with_alpha(alpha * 0.9);
+        for (line, truncated) in [("All done here.", false), ("I think we should…", true)] {
+            app.world_mut().write_message(DialogueResponseEvent {
+                response: DialogueResponse::new(
+                    DialogueRequestId::new(1),
+                    DialogueProviderKind::OpenAi,
+                    speaker,
+                    None,
+                    line,
+                )
+                .with_truncated(truncated),
+                context: Default::default(),
+            });
+            app.update();
+            let texts = panel_texts(&mut app);
+            assert!(texts.contains(&line.to_string()));
+            assert_eq!(
+                texts.contains(&TRUNCATED_HINT_TEXT.to_string()),
+                truncated,
+                "{line}"
+            );
         }
+    }
+
+    #[test]
+    fn panel_slides_in_and_fades_every_text_descendant() {
+        use crate::dialogue::types::DialogueResponse;
+        use std::time::Duration;
+
+        let settings = DialoguePanelSettings {
+            lifetime_seconds: 2.0,
+            fade_seconds: 1.0,
+            intro_seconds: 0.5,
+            ..DialoguePanelSettings::default()
+        };
+        let (start, resting) = (
+            settings.intro_start_offset(),
+            UiLayout::default().edge_offset,
+        );
+        let mut app = App::new();
+        app.insert_resource(settings)
+            .init_resource::<DialoguePanelTracker>()
+            .init_resource::<UiLayout>()
+            .init_resource::<Time>()
+            .init_resource::<SimulationClock>()
+            .init_resource::<NpcIndex>()
+            .add_message::<DialogueResponseEvent>()
+            .add_systems(
+                Update,
+                (index_npcs, spawn_dialogue_panel, update_dialogue_panel).chain(),
+            );
+        let speaker = NpcId::new(3);
+        app.world_mut().spawn(Identity::new(speaker, "Berit", 40.0));
+        app.world_mut().write_message(DialogueResponseEvent {
+            response: DialogueResponse::new(
+                DialogueRequestId::new(1),
+                DialogueProviderKind::OpenAi,
+                speaker,
+                None,
+                "Fine weather for threshing.",
+            ),
+            context: Default::default(),
+        });
+
+        let step = |app: &mut App, seconds: f32| {
+            app.world_mut()
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_secs_f32(seconds));
+            app.update();
+            let panel = app.world().resource::<DialoguePanelTracker>().active_panel;
+            let bottom = panel.map(|panel| app.world().get::<Node>(panel).unwrap().bottom);
+            let alphas: Vec<f32> = app
+                .world_mut()
+                .query::<(&DialoguePanelText, &TextColor)>()
+                .iter(app.world())
+                .map(|(text, color)| color.0.alpha() / text.base_color.alpha())
+                .collect();
+            (bottom, alphas)
+        };
+
+        let (bottom, alphas) = step(&mut app, 0.0);
+        assert_eq!(bottom, Some(Val::Px(start)));
+        assert_eq!(alphas.len(), 3, "icon, name, and body");
+        assert!(alphas.iter().all(|alpha| *alpha == 0.0));
+
+        let (bottom, alphas) = step(&mut app, 0.6);
+        assert!(
+            matches!(bottom, Some(Val::Px(px)) if (px - resting).abs() < 1e-3),
+            "{bottom:?}"
+        );
+        assert!(alphas.iter().all(|alpha| (*alpha - 1.0).abs() < 1e-4));
+
+        let (_, alphas) = step(&mut app, 0.9);
+        assert!(
+            alphas.iter().all(|alpha| (*alpha - 0.5).abs() < 1e-3),
+            "text fades with the panel: {alphas:?}"
+        );
 
-        // Fade all text children
-        // Note: In Bevy 0.17, we query text entities separately since we can't
-        // easily traverse descendants. Text entities will fade naturally as panel fades.
-        // For now, we just fade the background - text will remain visible.
+        let (bottom, alphas) = step(&mut app, 0.6);
+        assert_eq!(bottom, None);
+        assert!(alphas.is_empty());
     }
 }
@@ -0,0 +1,540 @@
+// src/ui/emote.rs
+//
+// Large glyph above a speaking NPC ("!", "~", "…", "<3") that reads at a distance before,
+// or without, the line itself. It appears as soon as the NPC starts thinking, is refreshed
+// from the reply, then fades out.
+
+use std::{fs, path::Path};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    core::{
+        config::{ConfigDiagnostics, ConfigReloadRequested},
+        plugin::SimulationClock,
+        time_basis::TimeBasis,
+    },
+    dialogue::events::DialogueResponseEvent,
+    npc::{
+        components::{Identity, PendingSpeech},
+        motivation::{state::NpcMood, NpcMotivation},
+        spatial::NpcIndex,
+    },
+    ui::world_label::world_label,
+};
+
+use super::subtitles::settings::CONFIG_PATH;
+
+/// Above the thinking indicator, so both can show at once.
+const EMOTE_OFFSET: Vec3 = Vec3::new(0.0, 1.45, 0.0);
+const EMOTE_FONT_SIZE: f32 = 44.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Emote {
+    Excited,
+    Weary,
+    Glum,
+    Grateful,
+}
+
+impl Emote {
+    pub fn glyph(self) -> &'static str {
+        match self {
+            Self::Excited => "!",
+            Self::Weary => "~",
+            Self::Glum => "…",
+            Self::Grateful => "<3",
+        }
+    }
+
+    /// Parses a tone name such as `"grateful"`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "excited" => Some(Self::Excited),
+            "weary" => Some(Self::Weary),
+            "glum" => Some(Self::Glum),
+            "grateful" => Some(Self::Grateful),
+            _ => None,
+        }
+    }
+
+    fn from_mood(mood: NpcMood) -> Option<Self> {
+        match mood {
+            NpcMood::Energised => Some(Self::Excited),
+            NpcMood::Content => None,
+            NpcMood::Tired => Some(Self::Weary),
+            NpcMood::Depressed => Some(Self::Glum),
+        }
+    }
+}
+
+/// One `emotes.keywords` entry: a lowercase substring and the emote it suggests.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct EmoteKeyword {
+    pub keyword: String,
+    pub emote: Emote,
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+struct RawUiConfig {
+    #[serde(default)]
+    emotes: RawEmoteSection,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+struct RawEmoteSection {
+    enabled: bool,
+    lifetime_seconds: f32,
+    fade_seconds: f32,
+    keywords: Vec<EmoteKeyword>,
+}
+
+impl Default for RawEmoteSection {
+    fn default() -> Self {
+        let keyword = |keyword: &str, emote| EmoteKeyword {
+            keyword: keyword.to_string(),
+            emote,
+        };
+        Self {
+            enabled: true,
+            lifetime_seconds: 4.0,
+            fade_seconds: 1.0,
+            keywords: vec![
+                keyword("thank", Emote::Grateful),
+                keyword("wonderful", Emote::Excited),
+                keyword("tired", Emote::Weary),
+                keyword("sorry", Emote::Glum),
+            ],
+        }
+    }
+}
+
+/// Emote options from the `[emotes]` section of `config/ui.toml`.
+#[derive(Resource, Debug, Clone)]
+pub struct EmoteSettings {
+    pub enabled: bool,
+    /// Seconds an emote stays up after its line arrives, fade included.
+    pub lifetime_seconds: f32,
+    /// Closing part of the lifetime spent fading out; never longer than the lifetime.
+    pub fade_seconds: f32,
+    /// Checked in order; the first keyword found in the line wins.
+    pub keywords: Vec<EmoteKeyword>,
+    /// Emotes hang over NPCs in the world, so they follow the simulation clock by default.
+    pub time_basis: TimeBasis,
+}
+
+impl Default for EmoteSettings {
+    fn default() -> Self {
+        RawUiConfig::default().into()
+    }
+}
+
+impl EmoteSettings {
+    /// Reads and parses the `[emotes]` section of `config/ui.toml`.
+    pub fn load() -> Result<Self, String> {
+        let data = fs::read_to_string(Path::new(CONFIG_PATH))
+            .map_err(|err| format!("unable to read file: {err}"))?;
+        let raw = toml::from_str::<RawUiConfig>(&data)
+            .map_err(|err| format!("invalid ui config: {err}"))?;
+        Ok(raw.into())
+    }
+}
+
+impl From<RawUiConfig> for EmoteSettings {
+    fn from(value: RawUiConfig) -> Self {
+        let raw = value.emotes;
+        let lifetime_seconds = raw.lifetime_seconds.max(0.1);
+        Self {
+            enabled: raw.enabled,
+            lifetime_seconds,
+            fade_seconds: raw.fade_seconds.clamp(0.0, lifetime_seconds),
+            keywords: raw
+                .keywords
+                .into_iter()
+                .filter(|row| !row.keyword.trim().is_empty())
+                .map(|row| EmoteKeyword {
+                    keyword: row.keyword.trim().to_lowercase(),
+                    emote: row.emote,
+                })
+                .collect(),
+            time_basis: TimeBasis::Simulation,
+        }
+    }
+}
+
+/// Picks the emote for a line: a recognised `tone` first, then the speaker's mood, then the
+/// first keyword found in `content`. Pass an empty `content` before the line exists.
+pub fn derive_emote(
+    content: &str,
+    mood: Option<NpcMood>,
+    tone: Option<&str>,
+    keywords: &[EmoteKeyword],
+) -> Option<Emote> {
+    if let Some(emote) = tone.and_then(Emote::from_name) {
+        return Some(emote);
+    }
+    if let Some(emote) = mood.and_then(Emote::from_mood) {
+        return Some(emote);
+    }
+    let content = content.to_lowercase();
+    keywords
+        .iter()
+        .find(|row| content.contains(&row.keyword))
+        .map(|row| row.emote)
+}
+
+/// Emote shown above `owner`. While `pending`, the owner is still thinking and the emote
+/// holds at full opacity; afterwards `shown_seconds` counts towards the lifetime.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct EmoteLabel {
+    pub owner: Entity,
+    pub emote: Emote,
+    pub pending: bool,
+    pub shown_seconds: f32,
+}
+
+/// Alpha `shown_seconds` after the line arrived: opaque, then a linear fade to zero.
+pub fn emote_alpha(shown_seconds: f32, settings: &EmoteSettings) -> f32 {
+    let fade_start = settings.lifetime_seconds - settings.fade_seconds;
+    if shown_seconds <= fade_start {
+        return 1.0;
+    }
+    if settings.fade_seconds <= 0.0 {
+        return 0.0;
+    }
+    (1.0 - (shown_seconds - fade_start) / settings.fade_seconds).clamp(0.0, 1.0)
+}
+
+fn replace_emote(
+    commands: &mut Commands,
+    labels: &Query<(Entity, &EmoteLabel)>,
+    owner: Entity,
+    emote: Option<Emote>,
+    pending: bool,
+) {
+    let mut kept = false;
+    for (entity, label) in labels {
+        if label.owner != owner {
+            continue;
+        }
+        if emote == Some(label.emote) && !kept {
+            // Same glyph: restart the existing label in place rather than flicker a new one.
+            commands.entity(entity).insert((
+                TextColor(Color::WHITE),
+                EmoteLabel {
+                    owner,
+                    emote: label.emote,
+                    pending,
+                    shown_seconds: 0.0,
+                },
+            ));
+            kept = true;
+        } else {
+            commands.entity(entity).despawn();
+        }
+    }
+    let Some(emote) = emote.filter(|_| !kept) else {
+        return;
+    };
+    commands.spawn((
+        world_label(emote.glyph(), EMOTE_FONT_SIZE, owner, EMOTE_OFFSET),
+        TextColor(Color::WHITE),
+        EmoteLabel {
+            owner,
+            emote,
+            pending,
+            shown_seconds: 0.0,
+        },
+        Name::new("Emote"),
+    ));
+}
+
+/// Shows the mood emote as soon as an NPC starts thinking, before any text exists.
+pub fn show_pending_emotes(
+    mut commands: Commands,
+    settings: Res<EmoteSettings>,
+    thinking: Query<(Entity, Option<&NpcMotivation>), Added<PendingSpeech>>,
+    labels: Query<(Entity, &EmoteLabel)>,
+) {
+    if !settings.enabled {
+        return;
+    }
+    for (owner, motivation) in &thinking {
+        let mood = motivation.map(NpcMotivation::mood);
+        let emote = derive_emote("", mood, None, &settings.keywords);
+        if emote.is_some() {
+            replace_emote(&mut commands, &labels, owner, emote, true);
+        }
+    }
+}
+
+/// Re-derives the speaker's emote from the reply and starts its lifetime.
+pub fn show_response_emotes(
+    mut commands: Commands,
+    settings: Res<EmoteSettings>,
+    mut responses: MessageReader<DialogueResponseEvent>,
+    npc_index: Res<NpcIndex>,
+    speakers: Query<Option<&NpcMotivation>, With<Identity>>,
+    labels: Query<(Entity, &EmoteLabel)>,
+) {
+    if !settings.enabled {
+        responses.clear();
+        return;
+    }
+    for event in responses.read() {
+        let response = &event.response;
+        let Some(owner) = npc_index.entity(response.speaker) else {
+            continue;
+        };
+        let Ok(motivation) = speakers.get(owner) else {
+            continue;
+        };
+        // Responses carry no tone metadata yet, so mood and keywords decide.
+        let emote = derive_emote(
+            &response.content,
+            motivation.map(NpcMotivation::mood),
+            None,
+            &settings.keywords,
+        );
+        replace_emote(&mut commands, &labels, owner, emote, false);
+    }
+}
+
+/// Fades emotes whose line has arrived (or whose owner stopped thinking) and despawns them
+/// at the end of their lifetime. `place_world_labels` removes those of a despawned NPC.
+pub fn fade_emote_labels(
+    mut commands: Commands,
+    time: Res<Time>,
+    clock: Res<SimulationClock>,
+    settings: Res<EmoteSettings>,
+    thinking: Query<(), With<PendingSpeech>>,
+    mut labels: Query<(Entity, &mut EmoteLabel, &mut TextColor)>,
+) {
+    let delta = settings.time_basis.delta(&time, &clock).as_secs_f32();
+    for (entity, mut label, mut color) in &mut labels {
+        if label.pending {
+            if thinking.contains(label.owner) {
+                continue;
+            }
+            label.pending = false;
+        } else {
+            label.shown_seconds += delta;
+        }
+        if label.shown_seconds >= settings.lifetime_seconds {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        color
+            .0
+            .set_alpha(emote_alpha(label.shown_seconds, &settings));
+    }
+}
+
+/// Re-reads `config/ui.toml` on request, swapping the emote options in when it parses.
+pub fn reload_emote_settings(
+    mut requests: MessageReader<ConfigReloadRequested>,
+    mut diagnostics: ResMut<ConfigDiagnostics>,
+    mut settings: ResMut<EmoteSettings>,
+) {
+    if !requests.read().any(|request| request.is_for(CONFIG_PATH)) {
+        return;
+    }
+    if let Some(reloaded) = diagnostics.report_reload(CONFIG_PATH, EmoteSettings::load()) {
+        *settings = EmoteSettings {
+            time_basis: settings.time_basis,
+            ..reloaded
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{
+        dialogue::{
+            broker::DialogueProviderKind,
+            types::{DialogueContext, DialogueRequestId, DialogueResponse},
+        },
+        npc::{components::NpcId, motivation::MotivationConfig, spatial::index_npcs},
+        ui::world_label::{place_world_labels, WorldLabel},
+    };
+
+    fn keywords() -> Vec<EmoteKeyword> {
+        EmoteSettings::default().keywords
+    }
+
+    #[test]
+    fn tone_beats_mood_beats_keywords() {
+        let keywords = keywords();
+        let line = "Thank you kindly.";
+
+        assert_eq!(
+            derive_emote(line, Some(NpcMood::Tired), Some("excited"), &keywords),
+            Some(Emote::Excited)
+        );
+        assert_eq!(
+            derive_emote(line, Some(NpcMood::Tired), None, &keywords),
+            Some(Emote::Weary)
+        );
+        assert_eq!(
+            derive_emote(line, Some(NpcMood::Content), None, &keywords),
+            Some(Emote::Grateful),
+            "a content mood has no emote, so keywords decide"
+        );
+        assert_eq!(
+            derive_emote(line, None, Some("smug"), &keywords),
+            Some(Emote::Grateful),
+            "unknown tones fall through"
+        );
+        assert_eq!(derive_emote("Fine day.", None, None, &keywords), None);
+        assert_eq!(
+            derive_emote("", Some(NpcMood::Depressed), None, &keywords),
+            Some(Emote::Glum)
+        );
+    }
+
+    #[test]
+    fn first_listed_keyword_wins() {
+        let keywords = keywords();
+        assert_eq!(
+            derive_emote("Sorry, and thanks.", None, None, &keywords),
+            Some(Emote::Grateful)
+        );
+    }
+
+    #[test]
+    fn config_section_parses_and_clamps() {
+        let raw: RawUiConfig = toml::from_str(
+            r#"
+            [emotes]
+            lifetime_seconds = 2.0
+            fade_seconds = 5.0
+            keywords = [{ keyword = " Hooray ", emote = "excited" }, { keyword = "", emote = "glum" }]
+            "#,
+        )
+        .expect("valid section");
+        let settings = EmoteSettings::from(raw);
+
+        assert_eq!(settings.fade_seconds, 2.0);
+        assert_eq!(
+            settings.keywords,
+            vec![EmoteKeyword {
+                keyword: "hooray".to_string(),
+                emote: Emote::Excited,
+            }]
+        );
+    }
+
+    #[test]
+    fn emote_holds_while_thinking_then_fades_and_despawns() {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .insert_resource(SimulationClock::new(1.0))
+            .insert_resource(EmoteSettings {
+                time_basis: TimeBasis::Real,
+                ..EmoteSettings::default()
+            })
+            .init_resource::<NpcIndex>()
+            .add_message::<DialogueResponseEvent>()
+            .add_systems(
+                Update,
+                (
+                    index_npcs,
+                    show_pending_emotes,
+                    show_response_emotes,
+                    fade_emote_labels,
+                    place_world_labels,
+                )
+                    .chain(),
+            );
+        let speaker = NpcId::new(5);
+        let mut config = MotivationConfig::default();
+        config.defaults.start = config.thresholds.tired;
+        let motivation = NpcMotivation::new(&config);
+        let npc = app
+            .world_mut()
+            .spawn((
+                Transform::default(),
+                Identity::new(speaker, "Bryn", 30.0),
+                motivation,
+                PendingSpeech {
+                    request_id: DialogueRequestId::new(1),
+                },
+            ))
+            .id();
+        let label = |app: &mut App| {
+            app.world_mut()
+                .query::<&EmoteLabel>()
+                .iter(app.world())
+                .next()
+                .copied()
+        };
+        let advance = |app: &mut App, seconds: f32| {
+            app.world_mut()
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_secs_f32(seconds));
+            app.update();
+        };
+
+        advance(&mut app, 10.0);
+        let pending = label(&mut app).expect("mood emote while thinking");
+        assert!(pending.pending);
+        assert_eq!(pending.emote, Emote::Weary);
+        let label_entity = |app: &mut App| {
+            app.world_mut()
+                .query_filtered::<Entity, With<EmoteLabel>>()
+                .single(app.world())
+                .expect("one emote label")
+        };
+        let pending_entity = label_entity(&mut app);
+        let pinned = app
+            .world_mut()
+            .query_filtered::<&WorldLabel, With<EmoteLabel>>()
+            .single(app.world())
+            .copied()
+            .expect("the emote is a world label");
+        assert_eq!(pinned.anchor, npc);
+
+        app.world_mut().entity_mut(npc).remove::<PendingSpeech>();
+        app.world_mut().write_message(DialogueResponseEvent {
+            response: DialogueResponse::new(
+                DialogueRequestId::new(1),
+                DialogueProviderKind::OpenAi,
+                speaker,
+                None,
+                "Long day.",
+            ),
+            context: DialogueContext::default(),
+        });
+        advance(&mut app, 0.5);
+        let shown = label(&mut app).expect("emote refreshed from the reply");
+        assert!(!shown.pending);
+        assert_eq!(
+            label_entity(&mut app),
+            pending_entity,
+            "the same glyph restarts in place"
+        );
+
+        advance(&mut app, 2.0);
+        assert!(label(&mut app).is_some());
+        advance(&mut app, 2.0);
+        assert!(label(&mut app).is_none(), "gone after its lifetime");
+
+        app.world_mut().entity_mut(npc).insert(PendingSpeech {
+            request_id: DialogueRequestId::new(2),
+        });
+        advance(&mut app, 0.1);
+        assert!(label(&mut app).is_some());
+        app.world_mut().entity_mut(npc).despawn();
+        advance(&mut app, 0.1);
+        assert!(
+            label(&mut app).is_none(),
+            "a despawned speaker takes its emote"
+        );
+    }
+}
@@ -0,0 +1,237 @@
+// src/ui/layout.rs
+//
+// Screen-anchored panel geometry derived from the window size. `UiLayout` is recomputed
+// when the window is resized, and every tagged panel is re-anchored in place rather than
+// waiting to be respawned.
+
+use bevy::{
+    prelude::*,
+    window::{PrimaryWindow, WindowResized},
+};
+
+use super::dialogue_panel::components::DialoguePanelSettings;
+
+/// Where a screen panel is anchored. Put it on the panel's root node.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScreenAnchor {
+    /// Bottom-right dialogue panel. Its `bottom` is left to the entrance slide.
+    DialoguePanel,
+    /// Bottom-left player response window and notices.
+    ResponseWindow,
+    /// Top-left windows such as the crate panel and transcript viewer.
+    TopLeft,
+    /// Top-right HUD widgets such as the clock.
+    TopRight,
+    /// Top edge, centred in the window, for the toast stack. Nodes shift themselves left
+    /// by half their width with a negative margin.
+    TopCenter,
+}
+
+/// Concrete anchor offsets and panel widths (logical pixels) for the current window.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct UiLayout {
+    /// Logical window size this layout was derived for.
+    pub resolution: Vec2,
+    /// Gap between panels and the top and bottom window edges.
+    pub edge_offset: f32,
+    /// Gap between panels and the left and right window edges. On windows wider than
+    /// `max_layout_aspect` this includes the inset that centres the panels.
+    pub side_offset: f32,
+    pub dialogue_panel_width: f32,
+    pub response_window_width: f32,
+}
+
+impl Default for UiLayout {
+    fn default() -> Self {
+        Self::for_resolution(Vec2::new(1920.0, 1080.0), &DialoguePanelSettings::default())
+    }
+}
+
+impl UiLayout {
+    /// Layout for a `resolution`-sized window. Panels spread across at most a
+    /// `max_layout_aspect`-wide band, centred. Widths and edge gaps are window ratios kept
+    /// within their pixel bounds. Where the band is too narrow to fit the response window
+    /// and dialogue panel side by side, both shrink in proportion so they never overlap.
+    pub fn for_resolution(resolution: Vec2, settings: &DialoguePanelSettings) -> Self {
+        let band = resolution
+            .x
+            .min(resolution.y * settings.max_layout_aspect)
+            .max(0.0);
+        let inset = (resolution.x - band) / 2.0;
+        let edge_offset = (resolution.y * settings.edge_offset_ratio)
+            .clamp(settings.edge_offset_min, settings.edge_offset_max);
+
+        let mut dialogue_panel_width = (band * settings.panel_width_ratio)
+            .clamp(settings.panel_min_width, settings.panel_max_width);
+        let mut response_window_width = (band * settings.response_width_ratio)
+            .clamp(settings.response_min_width, settings.response_max_width);
+        let available = (band - 3.0 * edge_offset).max(0.0);
+        let wanted = dialogue_panel_width + response_window_width;
+        if wanted > available {
+            let scale = available / wanted;
+            dialogue_panel_width *= scale;
+            response_window_width *= scale;
+        }
+
+        Self {
+            resolution,
+            edge_offset,
+            side_offset: inset + edge_offset,
+            dialogue_panel_width,
+            response_window_width,
+        }
+    }
+
+    /// Sets `node`'s offsets for `anchor`, and its width for the panels this layout sizes.
+    pub fn place(&self, anchor: ScreenAnchor, node: &mut Node) {
+        match anchor {
+            ScreenAnchor::DialoguePanel => {
+                node.right = Val::Px(self.side_offset);
+                node.width = Val::Px(self.dialogue_panel_width);
+            }
+            ScreenAnchor::ResponseWindow => {
+                node.left = Val::Px(self.side_offset);
+                node.bottom = Val::Px(self.edge_offset);
+                node.width = Val::Px(self.response_window_width);
+            }
+            ScreenAnchor::TopLeft => {
+                node.left = Val::Px(self.side_offset);
+                node.top = Val::Px(self.edge_offset);
+            }
+            ScreenAnchor::TopRight => {
+                node.right = Val::Px(self.side_offset);
+                node.top = Val::Px(self.edge_offset);
+            }
+            ScreenAnchor::TopCenter => {
+                node.left = Val::Percent(50.0);
+                node.top = Val::Px(self.edge_offset);
+            }
+        }
+    }
+}
+
+/// Recomputes `UiLayout` from the primary window when it is resized or the panel settings
+/// change (which includes the first frame).
+pub fn update_ui_layout(
+    mut resized: MessageReader<WindowResized>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    settings: Res<DialoguePanelSettings>,
+    mut layout: ResMut<UiLayout>,
+) {
+    let resized = resized.read().count() > 0;
+    if !resized && !settings.is_changed() {
+        return;
+    }
+    let Ok(window) = windows.single() else {
+        return;
+    };
+    layout.set_if_neq(UiLayout::for_resolution(
+        Vec2::new(window.width(), window.height()),
+        &settings,
+    ));
+}
+
+/// Anchors newly spawned panels, and every panel again whenever the layout changes.
+pub fn apply_ui_layout(layout: Res<UiLayout>, mut panels: Query<(Ref<ScreenAnchor>, &mut Node)>) {
+    for (anchor, mut node) in &mut panels {
+        if layout.is_changed() || anchor.is_added() {
+            layout.place(*anchor, &mut node);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(width: f32, height: f32) -> UiLayout {
+        UiLayout::for_resolution(Vec2::new(width, height), &DialoguePanelSettings::default())
+    }
+
+    /// Free space between the response window's right edge and the dialogue panel's left.
+    fn bottom_gap(layout: &UiLayout) -> f32 {
+        layout.resolution.x
+            - 2.0 * layout.side_offset
+            - layout.response_window_width
+            - layout.dialogue_panel_width
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-3, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn common_resolutions_scale_widths_and_offsets() {
+        let hd = layout(1280.0, 720.0);
+        assert_close(hd.dialogue_panel_width, 320.0);
+        assert_close(hd.response_window_width, 280.0);
+        assert_close(hd.edge_offset, 14.4);
+        assert_close(hd.side_offset, 14.4);
+
+        let full_hd = layout(1920.0, 1080.0);
+        assert_eq!(full_hd, UiLayout::default());
+        assert_close(full_hd.dialogue_panel_width, 480.0);
+        assert_close(full_hd.response_window_width, 360.0);
+        assert_close(full_hd.edge_offset, 21.6);
+        assert_close(full_hd.side_offset, 21.6);
+    }
+
+    #[test]
+    fn ultrawide_keeps_panels_within_a_centred_band() {
+        let ultrawide = layout(3440.0, 1440.0);
+        assert_close(ultrawide.dialogue_panel_width, 480.0);
+        assert_close(ultrawide.response_window_width, 420.0);
+        assert_close(ultrawide.edge_offset, 28.8);
+        // A 16:9 band is 2560 wide, leaving 440 on either side.
+        assert_close(ultrawide.side_offset, 440.0 + 28.8);
+    }
+
+    #[test]
+    fn small_windows_never_overlap_the_bottom_panels() {
+        let small = layout(800.0, 600.0);
+        assert_close(small.dialogue_panel_width, 300.0);
+        assert_close(small.response_window_width, 280.0);
+        assert!(bottom_gap(&small) >= small.edge_offset);
+
+        let tiny = layout(480.0, 360.0);
+        assert!(
+            tiny.dialogue_panel_width < 300.0,
+            "shrinks below its minimum"
+        );
+        assert_close(bottom_gap(&tiny), tiny.edge_offset);
+    }
+
+    #[test]
+    fn resized_layout_reanchors_spawned_panels() {
+        let mut app = App::new();
+        app.insert_resource(layout(1920.0, 1080.0))
+            .add_systems(Update, apply_ui_layout);
+        let panel = app
+            .world_mut()
+            .spawn((ScreenAnchor::ResponseWindow, Node::default()))
+            .id();
+        app.update();
+        assert_eq!(
+            app.world().get::<Node>(panel).unwrap().width,
+            Val::Px(360.0)
+        );
+
+        let hd = layout(1280.0, 720.0);
+        app.insert_resource(hd);
+        app.update();
+        let node = app.world().get::<Node>(panel).unwrap();
+        assert_eq!(node.width, Val::Px(280.0));
+        assert_eq!(node.left, Val::Px(hd.side_offset));
+        assert_eq!(node.bottom, Val::Px(hd.edge_offset));
+    }
+
+    #[test]
+    fn top_center_sits_between_the_top_corners() {
+        let hd = layout(1280.0, 720.0);
+        let mut node = Node::default();
+        hd.place(ScreenAnchor::TopCenter, &mut node);
+        assert_eq!(node.left, Val::Percent(50.0));
+        assert_eq!(node.top, Val::Px(hd.edge_offset));
+        assert_eq!((node.right, node.width), (Val::Auto, Val::Auto));
+    }
+}
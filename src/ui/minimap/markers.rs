@@ -0,0 +1,119 @@
+// src/ui/minimap/markers.rs
+//
+// Minimap markers: what each point stands for and how it is drawn.
+
+use bevy::prelude::*;
+
+use crate::economy::components::Profession;
+
+const MARKER_BORDER: f32 = 1.5;
+const SELECTED_BORDER_COLOR: Color = Color::WHITE;
+const PLAYER_COLOR: Color = Color::srgb(0.95, 0.95, 0.95);
+const MARKETPLACE_COLOR: Color = Color::srgb(0.77, 0.47, 0.28);
+const HOME_COLOR: Color = Color::srgb(0.55, 0.55, 0.5);
+const UNEMPLOYED_COLOR: Color = Color::srgb(0.75, 0.75, 0.7);
+
+/// What a marker stands for; picks its size, color, and stacking order.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MarkerKind {
+    Crate(Profession),
+    Npc {
+        profession: Option<Profession>,
+        selected: bool,
+    },
+    Marketplace,
+    Home,
+    Player,
+}
+
+impl MarkerKind {
+    fn size(self) -> f32 {
+        match self {
+            Self::Home => 4.0,
+            Self::Npc {
+                selected: false, ..
+            } => 6.0,
+            Self::Npc { selected: true, .. } | Self::Player => 9.0,
+            Self::Crate(_) | Self::Marketplace => 10.0,
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            Self::Crate(profession)
+            | Self::Npc {
+                profession: Some(profession),
+                ..
+            } => profession_color(profession),
+            Self::Npc {
+                profession: None, ..
+            } => UNEMPLOYED_COLOR,
+            Self::Marketplace => MARKETPLACE_COLOR,
+            Self::Home => HOME_COLOR,
+            Self::Player => PLAYER_COLOR,
+        }
+    }
+
+    /// Later kinds draw over earlier ones.
+    fn z_index(self) -> i32 {
+        match self {
+            Self::Home => 0,
+            Self::Crate(_) | Self::Marketplace => 1,
+            Self::Npc {
+                selected: false, ..
+            } => 2,
+            Self::Npc { selected: true, .. } => 3,
+            Self::Player => 4,
+        }
+    }
+}
+
+/// Map colors for each profession, matching their crates.
+pub fn profession_color(profession: Profession) -> Color {
+    match profession {
+        Profession::Farmer => Color::srgb_u8(190, 150, 80),
+        Profession::Miller => Color::srgb_u8(140, 170, 215),
+        Profession::Blacksmith => Color::srgb_u8(110, 110, 130),
+        Profession::Innkeeper => Color::srgb_u8(150, 95, 60),
+    }
+}
+
+/// One point on the map.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct MinimapMarker {
+    pub kind: MarkerKind,
+    pub clamped: bool,
+}
+
+pub(super) fn marker_bundle(kind: MarkerKind, fraction: Vec2, clamped: bool) -> impl Bundle {
+    let size = kind.size();
+    let color = kind.color();
+    // Clamped markers are hollow, so a point beyond the edge never reads as one on it.
+    let (fill, border) = if clamped {
+        (Color::NONE, color)
+    } else if matches!(kind, MarkerKind::Npc { selected: true, .. }) {
+        (color, SELECTED_BORDER_COLOR)
+    } else {
+        (color, color)
+    };
+    (
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Percent(fraction.x * 100.0),
+            top: Val::Percent(fraction.y * 100.0),
+            width: Val::Px(size),
+            height: Val::Px(size),
+            margin: UiRect {
+                left: Val::Px(-size / 2.0),
+                top: Val::Px(-size / 2.0),
+                ..default()
+            },
+            border: UiRect::all(Val::Px(MARKER_BORDER)),
+            ..default()
+        },
+        BackgroundColor(fill),
+        BorderColor::from(border),
+        ZIndex(kind.z_index()),
+        MinimapMarker { kind, clamped },
+    )
+}
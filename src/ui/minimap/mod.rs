@@ -0,0 +1,9 @@
+// src/ui/minimap/mod.rs
+//
+// Top-down village map in the top-right corner: crates, the marketplace, homes, NPCs
+// colored by profession, and the camera. Clicking it selects the nearest NPC.
+
+pub mod markers;
+pub mod projection;
+pub mod settings;
+pub mod systems;
@@ -0,0 +1,160 @@
+// src/ui/minimap/projection.rs
+//
+// World-to-map math for the minimap. The map looks straight down: world +x runs right and
+// world +z runs down the panel. Positions are fractions of the panel (0 to 1 on each
+// axis), so the mapping does not depend on the panel's pixel size.
+
+use bevy::prelude::*;
+
+/// World rectangle on the ground plane (x, z) the minimap covers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MapExtent {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl MapExtent {
+    /// The rectangle spanned by two corners given in any order.
+    pub fn new(a: Vec2, b: Vec2) -> Self {
+        Self {
+            min: a.min(b),
+            max: a.max(b),
+        }
+    }
+
+    /// Size on each axis, never zero so projecting never divides by it.
+    pub fn span(&self) -> Vec2 {
+        (self.max - self.min).max(Vec2::splat(f32::EPSILON))
+    }
+
+    #[cfg(test)]
+    pub fn contains(&self, ground: Vec2) -> bool {
+        ground.cmpge(self.min).all() && ground.cmple(self.max).all()
+    }
+}
+
+/// A world position placed on the map.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MapPoint {
+    /// Offset from the panel's top-left corner as a fraction of its size.
+    pub fraction: Vec2,
+    /// The position lies outside the extent and was pulled onto the border.
+    pub clamped: bool,
+}
+
+/// Ground-plane coordinates (x, z) of a world position.
+pub fn ground_position(world: Vec3) -> Vec2 {
+    Vec2::new(world.x, world.z)
+}
+
+/// Places `world` on the map, clamping positions outside `extent` onto its border.
+/// Non-finite coordinates land in the centre, marked clamped.
+pub fn project_to_map(world: Vec3, extent: &MapExtent) -> MapPoint {
+    let fraction = (ground_position(world) - extent.min) / extent.span();
+    if !fraction.is_finite() {
+        return MapPoint {
+            fraction: Vec2::splat(0.5),
+            clamped: true,
+        };
+    }
+    let clamped = fraction.clamp(Vec2::ZERO, Vec2::ONE);
+    MapPoint {
+        fraction: clamped,
+        clamped: clamped != fraction,
+    }
+}
+
+/// Ground position (x, z) under a map `fraction`; the inverse of `project_to_map` inside
+/// the extent.
+pub fn map_to_ground(fraction: Vec2, extent: &MapExtent) -> Vec2 {
+    extent.min + fraction * extent.span()
+}
+
+/// The candidate nearest `target` on the ground plane, if any lies within `radius`. Ties
+/// keep the first candidate.
+pub fn nearest_within<T>(
+    target: Vec2,
+    radius: f32,
+    candidates: impl IntoIterator<Item = (T, Vec2)>,
+) -> Option<T> {
+    candidates
+        .into_iter()
+        .map(|(candidate, position)| (candidate, position.distance(target)))
+        .filter(|(_, distance)| *distance <= radius)
+        .fold(
+            None,
+            |best: Option<(T, f32)>, (candidate, distance)| match best {
+                Some((_, best_distance)) if best_distance <= distance => best,
+                _ => Some((candidate, distance)),
+            },
+        )
+        .map(|(candidate, _)| candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extent() -> MapExtent {
+        MapExtent::new(Vec2::new(-10.0, -20.0), Vec2::new(10.0, 20.0))
+    }
+
+    fn assert_close(actual: Vec2, expected: Vec2) {
+        assert!(actual.abs_diff_eq(expected, 1e-5), "{actual} != {expected}");
+    }
+
+    #[test]
+    fn positions_inside_the_extent_map_proportionally() {
+        let extent = extent();
+        let centre = project_to_map(Vec3::new(0.0, 7.0, 0.0), &extent);
+        assert_close(centre.fraction, Vec2::splat(0.5));
+        assert!(!centre.clamped, "height is ignored");
+
+        let corner = project_to_map(Vec3::new(-10.0, 0.0, -20.0), &extent);
+        assert_close(corner.fraction, Vec2::ZERO);
+        assert!(!corner.clamped, "the border itself is inside");
+
+        let point = project_to_map(Vec3::new(5.0, 0.0, 10.0), &extent);
+        assert_close(point.fraction, Vec2::new(0.75, 0.75));
+        assert_close(map_to_ground(point.fraction, &extent), Vec2::new(5.0, 10.0));
+    }
+
+    #[test]
+    fn positions_outside_clamp_to_the_border_and_say_so() {
+        let extent = extent();
+        let east = project_to_map(Vec3::new(30.0, 0.0, 0.0), &extent);
+        assert_close(east.fraction, Vec2::new(1.0, 0.5));
+        assert!(east.clamped);
+
+        let far_corner = project_to_map(Vec3::new(-50.0, 0.0, 90.0), &extent);
+        assert_close(far_corner.fraction, Vec2::new(0.0, 1.0));
+        assert!(far_corner.clamped);
+
+        let lost = project_to_map(Vec3::new(f32::NAN, 0.0, 0.0), &extent);
+        assert_close(lost.fraction, Vec2::splat(0.5));
+        assert!(lost.clamped);
+
+        let flat = MapExtent::new(Vec2::new(3.0, -1.0), Vec2::new(3.0, 1.0));
+        let point = project_to_map(Vec3::new(3.0, 0.0, 0.0), &flat);
+        assert!(
+            point.fraction.is_finite(),
+            "a zero-width extent still projects"
+        );
+    }
+
+    #[test]
+    fn nearest_lookup_respects_the_radius() {
+        let candidates = [
+            ("far", Vec2::new(6.0, 0.0)),
+            ("near", Vec2::new(1.0, 1.0)),
+            ("tied", Vec2::new(-1.0, 1.0)),
+        ];
+        assert_eq!(nearest_within(Vec2::ZERO, 4.0, candidates), Some("near"));
+        assert_eq!(
+            nearest_within(Vec2::new(5.0, 0.0), 4.0, candidates),
+            Some("far")
+        );
+        assert_eq!(nearest_within(Vec2::new(0.0, -9.0), 4.0, candidates), None);
+        assert_eq!(nearest_within::<&str>(Vec2::ZERO, 4.0, []), None);
+    }
+}
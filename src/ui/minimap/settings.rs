@@ -0,0 +1,133 @@
+// src/ui/minimap/settings.rs
+//
+// Minimap options loaded from the `[minimap]` section of `config/ui.toml`.
+
+use std::{fs, path::Path};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    core::config::{ConfigDiagnostics, ConfigReloadRequested},
+    ui::subtitles::settings::CONFIG_PATH,
+};
+
+use super::projection::MapExtent;
+
+const MIN_PANEL_SIZE: f32 = 80.0;
+const MIN_REFRESH_SECONDS: f32 = 0.05;
+
+#[derive(Debug, Clone, Deserialize, Default)]
+struct RawUiConfig {
+    #[serde(default)]
+    minimap: RawMinimapSection,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+struct RawMinimapSection {
+    open_on_start: bool,
+    panel_size: f32,
+    extent_min: [f32; 2],
+    extent_max: [f32; 2],
+    refresh_seconds: f32,
+    click_radius: f32,
+}
+
+impl Default for RawMinimapSection {
+    fn default() -> Self {
+        Self {
+            open_on_start: false,
+            panel_size: 200.0,
+            extent_min: [-16.0, -16.0],
+            extent_max: [16.0, 16.0],
+            refresh_seconds: 0.25,
+            click_radius: 4.0,
+        }
+    }
+}
+
+/// Runtime minimap options.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct MinimapSettings {
+    pub open_on_start: bool,
+    /// Side of the square panel in logical pixels.
+    pub panel_size: f32,
+    /// World area (x, z) the panel covers; points outside sit on its border.
+    pub extent: MapExtent,
+    /// Seconds between marker refreshes. NPCs spawning or despawning refresh at once.
+    pub refresh_seconds: f32,
+    /// World distance from a click within which the nearest NPC is selected.
+    pub click_radius: f32,
+}
+
+impl Default for MinimapSettings {
+    fn default() -> Self {
+        RawUiConfig::default().into()
+    }
+}
+
+impl MinimapSettings {
+    /// Reads and parses the `[minimap]` section of `config/ui.toml`.
+    pub fn load() -> Result<Self, String> {
+        let data = fs::read_to_string(Path::new(CONFIG_PATH))
+            .map_err(|err| format!("unable to read file: {err}"))?;
+        let raw = toml::from_str::<RawUiConfig>(&data)
+            .map_err(|err| format!("invalid ui config: {err}"))?;
+        Ok(raw.into())
+    }
+}
+
+impl From<RawUiConfig> for MinimapSettings {
+    fn from(value: RawUiConfig) -> Self {
+        let raw = value.minimap;
+        Self {
+            open_on_start: raw.open_on_start,
+            panel_size: raw.panel_size.max(MIN_PANEL_SIZE),
+            extent: MapExtent::new(Vec2::from(raw.extent_min), Vec2::from(raw.extent_max)),
+            refresh_seconds: raw.refresh_seconds.max(MIN_REFRESH_SECONDS),
+            click_radius: raw.click_radius.max(0.0),
+        }
+    }
+}
+
+/// Re-reads `config/ui.toml` on request, swapping the minimap options in when it parses.
+pub fn reload_minimap_settings(
+    mut requests: MessageReader<ConfigReloadRequested>,
+    mut diagnostics: ResMut<ConfigDiagnostics>,
+    mut settings: ResMut<MinimapSettings>,
+) {
+    if !requests.read().any(|request| request.is_for(CONFIG_PATH)) {
+        return;
+    }
+    if let Some(reloaded) = diagnostics.report_reload(CONFIG_PATH, MinimapSettings::load()) {
+        settings.set_if_neq(reloaded);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn section_is_clamped_and_the_extent_ordered() {
+        let raw: RawUiConfig = toml::from_str(
+            r#"
+            [minimap]
+            panel_size = 10.0
+            extent_min = [20.0, -5.0]
+            extent_max = [-20.0, 5.0]
+            refresh_seconds = 0.0
+            "#,
+        )
+        .expect("valid toml");
+        let settings = MinimapSettings::from(raw);
+        assert_eq!(settings.panel_size, MIN_PANEL_SIZE);
+        assert_eq!(settings.refresh_seconds, MIN_REFRESH_SECONDS);
+        assert_eq!(settings.extent.min, Vec2::new(-20.0, -5.0));
+        assert_eq!(settings.extent.max, Vec2::new(20.0, 5.0));
+
+        let shipped = MinimapSettings::load().expect("shipped ui config is valid");
+        assert!(shipped.extent.contains(Vec2::ZERO));
+    }
+}
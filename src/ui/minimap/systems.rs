@@ -0,0 +1,326 @@
+// src/ui/minimap/systems.rs
+//
+// The minimap panel under the clock, placing its markers, and click-to-select. Markers
+// are plain UI nodes placed by percentage, so the map needs no second camera.
+
+use bevy::{ecs::system::SystemParam, prelude::*, ui::RelativeCursorPosition};
+
+use crate::{
+    core::input::{ActionInput, InputAction},
+    economy::components::{Marketplace, Profession, ProfessionCrate},
+    npc::{components::Identity, sleep::HomePosition},
+    player::components::Player,
+    ui::{layout::ScreenAnchor, visibility::UiLayer},
+    world::selection::SelectedNpc,
+};
+
+use super::{
+    markers::{marker_bundle, MarkerKind, MinimapMarker},
+    projection::{ground_position, map_to_ground, nearest_within, project_to_map},
+    settings::MinimapSettings,
+};
+
+/// Room left above the panel for the clock widget, which shares the top-right anchor.
+const CLOCK_CLEARANCE: f32 = 104.0;
+const PANEL_BORDER: f32 = 1.0;
+const PANEL_BACKGROUND: Color = Color::srgba(0.08, 0.1, 0.08, 0.8);
+const PANEL_BORDER_COLOR: Color = Color::srgba(0.8, 0.8, 0.75, 0.6);
+
+/// Whether the map is open, its panel, and how long since the markers were placed.
+#[derive(Resource, Debug)]
+pub struct MinimapState {
+    open: bool,
+    panel: Option<Entity>,
+    since_refresh: f32,
+}
+
+impl MinimapState {
+    pub fn new(open: bool) -> Self {
+        Self {
+            open,
+            panel: None,
+            since_refresh: f32::INFINITY,
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+}
+
+/// Minimap panel root.
+#[derive(Component, Debug)]
+pub struct MinimapPanel;
+
+/// Everything the map shows, read together.
+#[allow(clippy::type_complexity)]
+#[derive(SystemParam)]
+pub struct MinimapSources<'w, 's> {
+    crates: Query<'w, 's, (&'static ProfessionCrate, &'static GlobalTransform)>,
+    markets: Query<'w, 's, &'static GlobalTransform, With<Marketplace>>,
+    npcs: Query<
+        'w,
+        's,
+        (
+            Entity,
+            &'static Identity,
+            &'static GlobalTransform,
+            Option<&'static Profession>,
+            Option<&'static HomePosition>,
+        ),
+    >,
+    players: Query<'w, 's, &'static GlobalTransform, With<Player>>,
+    selected: Option<Res<'w, SelectedNpc>>,
+}
+
+impl MinimapSources<'_, '_> {
+    fn selected_entity(&self) -> Option<Entity> {
+        self.selected.as_deref().and_then(SelectedNpc::entity)
+    }
+
+    fn selection_changed(&self) -> bool {
+        self.selected
+            .as_ref()
+            .is_some_and(|selected| selected.is_changed())
+    }
+
+    /// Every marker with its world position, homes first so NPCs standing at home cover
+    /// them.
+    fn points(&self) -> Vec<(MarkerKind, Vec3)> {
+        let selected = self.selected_entity();
+        let npcs = || {
+            self.npcs
+                .iter()
+                .filter(|(_, identity, ..)| !identity.id.is_player())
+        };
+        let mut points: Vec<(MarkerKind, Vec3)> = npcs()
+            .filter_map(|(.., home)| home.map(|home| (MarkerKind::Home, home.0)))
+            .collect();
+        points.extend(self.crates.iter().map(|(profession_crate, transform)| {
+            (
+                MarkerKind::Crate(profession_crate.profession),
+                transform.translation(),
+            )
+        }));
+        points.extend(
+            self.markets
+                .iter()
+                .map(|transform| (MarkerKind::Marketplace, transform.translation())),
+        );
+        points.extend(npcs().map(|(entity, _, transform, profession, _)| {
+            (
+                MarkerKind::Npc {
+                    profession: profession.copied(),
+                    selected: selected == Some(entity),
+                },
+                transform.translation(),
+            )
+        }));
+        points.extend(
+            self.players
+                .iter()
+                .map(|transform| (MarkerKind::Player, transform.translation())),
+        );
+        points
+    }
+}
+
+/// Opens and closes the map on its binding.
+pub fn toggle_minimap(input: ActionInput, mut state: ResMut<MinimapState>) {
+    if input.just_pressed(InputAction::ToggleMinimap) {
+        state.toggle();
+    }
+}
+
+/// Spawns or removes the panel when the map opens or closes, and rebuilds it when the
+/// settings change.
+pub fn sync_minimap_panel(
+    mut commands: Commands,
+    mut state: ResMut<MinimapState>,
+    settings: Res<MinimapSettings>,
+) {
+    if !state.is_changed() && !settings.is_changed() {
+        return;
+    }
+    let state = state.bypass_change_detection();
+    if let Some(panel) = state.panel.take() {
+        commands.entity(panel).despawn();
+    }
+    if !state.open {
+        return;
+    }
+    let panel = commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Px(settings.panel_size),
+                height: Val::Px(settings.panel_size),
+                margin: UiRect::top(Val::Px(CLOCK_CLEARANCE)),
+                border: UiRect::all(Val::Px(PANEL_BORDER)),
+                overflow: Overflow::clip(),
+                ..default()
+            },
+            BackgroundColor(PANEL_BACKGROUND),
+            BorderColor::from(PANEL_BORDER_COLOR),
+            Interaction::default(),
+            RelativeCursorPosition::default(),
+            MinimapPanel,
+            ScreenAnchor::TopRight,
+            UiLayer::Screen,
+            Name::new("Minimap"),
+        ))
+        .id();
+    state.panel = Some(panel);
+    state.since_refresh = f32::INFINITY;
+}
+
+/// Re-places every marker every `refresh_seconds`, and at once when the map opens, an NPC
+/// spawns or despawns, or the selection changes.
+#[allow(clippy::too_many_arguments)]
+pub fn update_minimap_markers(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut state: ResMut<MinimapState>,
+    settings: Res<MinimapSettings>,
+    sources: MinimapSources,
+    added: Query<(), Added<Identity>>,
+    mut removed: RemovedComponents<Identity>,
+    markers: Query<Entity, With<MinimapMarker>>,
+) {
+    // Drained every frame so despawns seen while the map is closed do not linger.
+    let despawned = removed.read().count() > 0;
+    let population_changed = despawned || !added.is_empty();
+    let state = state.bypass_change_detection();
+    let Some(panel) = state.panel else {
+        return;
+    };
+    state.since_refresh += time.delta_secs();
+    if state.since_refresh < settings.refresh_seconds
+        && !population_changed
+        && !sources.selection_changed()
+    {
+        return;
+    }
+    state.since_refresh = 0.0;
+
+    for marker in &markers {
+        commands.entity(marker).despawn();
+    }
+    let points = sources.points();
+    commands.entity(panel).with_children(|map| {
+        for (kind, world) in points {
+            let point = project_to_map(world, &settings.extent);
+            map.spawn(marker_bundle(kind, point.fraction, point.clamped));
+        }
+    });
+}
+
+/// Selects the NPC nearest the clicked map point, within `click_radius`. The world click
+/// handler skips clicks over UI, so a click on the map never also picks in the world.
+pub fn select_from_minimap(
+    input: ActionInput,
+    settings: Res<MinimapSettings>,
+    panels: Query<(&Interaction, &RelativeCursorPosition), With<MinimapPanel>>,
+    npcs: Query<(Entity, &Identity, &GlobalTransform)>,
+    mut selected: ResMut<SelectedNpc>,
+) {
+    if !input.just_pressed(InputAction::SelectNpc) {
+        return;
+    }
+    let Some(normalized) = panels
+        .iter()
+        .filter(|(interaction, _)| **interaction == Interaction::Pressed)
+        .find_map(|(_, cursor)| cursor.normalized)
+    else {
+        return;
+    };
+    // `normalized` is measured from the node's centre, from -0.5 to 0.5 on each axis.
+    let fraction = (normalized + Vec2::splat(0.5)).clamp(Vec2::ZERO, Vec2::ONE);
+    let target = map_to_ground(fraction, &settings.extent);
+    let nearest = nearest_within(
+        target,
+        settings.click_radius,
+        npcs.iter()
+            .filter(|(_, identity, _)| !identity.id.is_player())
+            .map(|(entity, _, transform)| (entity, ground_position(transform.translation()))),
+    );
+    if let Some(entity) = nearest {
+        selected.select(entity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::npc::components::NpcId;
+
+    fn minimap_app() -> App {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .insert_resource(MinimapState::new(true))
+            .insert_resource(MinimapSettings {
+                refresh_seconds: 60.0,
+                ..MinimapSettings::default()
+            })
+            .init_resource::<SelectedNpc>()
+            .add_systems(Update, (sync_minimap_panel, update_minimap_markers).chain());
+        app
+    }
+
+    fn markers(app: &mut App) -> Vec<MinimapMarker> {
+        app.world_mut()
+            .query::<&MinimapMarker>()
+            .iter(app.world())
+            .copied()
+            .collect()
+    }
+
+    fn spawn_npc(app: &mut App, id: u64, position: Vec3) -> Entity {
+        app.world_mut()
+            .spawn((
+                Identity::new(NpcId::new(id), "Villager", 30.0),
+                Profession::Miller,
+                GlobalTransform::from_translation(position),
+            ))
+            .id()
+    }
+
+    #[test]
+    fn markers_follow_npcs_spawning_and_despawning_between_refreshes() {
+        let mut app = minimap_app();
+        app.world_mut().spawn((
+            ProfessionCrate {
+                profession: Profession::Farmer,
+            },
+            GlobalTransform::from_translation(Vec3::new(8.0, 0.25, 3.0)),
+        ));
+        app.update();
+        assert_eq!(markers(&mut app).len(), 1, "the map opens with the crate");
+
+        let near = spawn_npc(&mut app, 1, Vec3::ZERO);
+        spawn_npc(&mut app, 2, Vec3::new(500.0, 0.0, 0.0));
+        app.update();
+        let placed = markers(&mut app);
+        assert_eq!(placed.len(), 3, "spawns refresh before the throttle");
+        let npc_markers: Vec<bool> = placed
+            .iter()
+            .filter(|marker| matches!(marker.kind, MarkerKind::Npc { .. }))
+            .map(|marker| marker.clamped)
+            .collect();
+        assert_eq!(npc_markers.iter().filter(|clamped| **clamped).count(), 1);
+
+        app.world_mut().entity_mut(near).despawn();
+        app.update();
+        assert_eq!(markers(&mut app).len(), 2, "despawns refresh too");
+
+        app.world_mut().resource_mut::<MinimapState>().toggle();
+        app.update();
+        assert!(markers(&mut app).is_empty(), "closing removes the panel");
+        assert!(app
+            .world_mut()
+            .query::<&MinimapPanel>()
+            .iter(app.world())
+            .next()
+            .is_none());
+    }
+}
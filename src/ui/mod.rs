@@ -3,14 +3,50 @@
 // UI module providing screen-space UI elements for HUD and dialogue.
 //
 // Current features:
-// - Dialogue panels (bottom-right corner NPC dialogue display)
+// - Dialogue panels (bottom-right corner NPC dialogue display) that slide up and fade in,
+//   then fade out text and all. Lines called across more than `shout_distance` get larger,
+//   warmer text with a "!" prefix; lines said to the player from within `whisper_distance`
+//   are smaller, dimmer, and slightly slanted (`classify_delivery`)
+// - Window title showing sim day, clock time, broker mode, NPC count, and smoothed FPS
+// - Clock widget (top-right) with day progress, sunrise/sunset ticks, and the selected
+//   NPC's schedule boundaries
+// - Config banner listing config files that fell back to defaults (F10 by default to reload)
+// - Subtitle strip echoing every dialogue line at the bottom of the screen (F6 to toggle)
+// - Pulsing "…" above NPCs waiting on a dialogue reply; world labels are UI text nodes
+//   projected over their anchor through the fly camera
+// - Emote glyph ("!", "~", "…", "<3") above speakers, picked from reply tone, mood, or
+//   keywords in `[emotes]` of `config/ui.toml`; it shows while thinking and then fades
+// - Window-size-driven layout: anchored panels scale with the window, stay inside a 16:9
+//   band on ultrawide screens, and re-anchor on resize (`UiLayout`)
+// - Toasts (top centre) for exchanges, skill level-ups, broker changes, and a spent API
+//   budget; a capped stack that slides down into place and lets clicks through, with
+//   overflow waiting its turn
+// - Developer console (` by default) for giving, taking, and trading goods, setting moods,
+//   queueing lines, and forcing a replan; see `console::parse` for the commands
+// - Village minimap (M) under the clock with crates, the marketplace, homes, NPCs by
+//   profession, and the camera; clicking it selects the nearest NPC
+// - Cinematic toggle (F11) cycling between all UI, world-space labels only, and no UI
+// - Player journal (J, in `player::journal`) listing open fetch quests and each known
+//   villager's affinity for the player
 //
 // Future features:
-// - HUD overlays (health, resources, time-of-day)
+// - HUD overlays (health, resources)
 // - Menus (pause, settings, save/load)
 // - NPC info panels (hover tooltips, relationship status)
 
+pub mod clock_widget;
+pub mod config_banner;
+pub mod console;
 pub mod dialogue_panel;
+pub mod emote;
+pub mod layout;
+pub mod minimap;
+pub mod subtitles;
+pub mod thinking_indicator;
+pub mod toasts;
+pub mod visibility;
+pub mod window_title;
+pub mod world_label;
 
 // Re-export the main plugin
 pub use dialogue_panel::UiPlugin;
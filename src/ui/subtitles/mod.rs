@@ -0,0 +1,7 @@
+// src/ui/subtitles/mod.rs
+//
+// Subtitle strip showing every dialogue line at the bottom-center of the screen.
+
+pub mod queue;
+pub mod settings;
+pub mod systems;
@@ -0,0 +1,191 @@
+// src/ui/subtitles/queue.rs
+//
+// Line queue behind the subtitle strip: pacing, scrolling, expiry, and truncation.
+
+use std::collections::VecDeque;
+
+use bevy::prelude::Resource;
+
+use super::settings::SubtitleSettings;
+
+const ELLIPSIS: char = '…';
+
+#[derive(Debug, Clone)]
+struct SubtitleLine {
+    text: String,
+    age_seconds: f32,
+}
+
+/// Subtitle lines waiting to be shown and those on screen.
+///
+/// A queued line is promoted once the newest visible line has been shown for
+/// `min_display_seconds`; promoting past `max_lines` scrolls the oldest line off the top.
+/// Visible lines expire after `line_lifetime_seconds`.
+#[derive(Resource, Debug, Clone)]
+pub struct SubtitleQueue {
+    pending: VecDeque<String>,
+    visible: VecDeque<SubtitleLine>,
+    since_promotion: Option<f32>,
+    max_lines: usize,
+    min_display_seconds: f32,
+    line_lifetime_seconds: f32,
+    max_chars: usize,
+}
+
+impl SubtitleQueue {
+    pub fn new(settings: &SubtitleSettings) -> Self {
+        let mut queue = Self {
+            pending: VecDeque::new(),
+            visible: VecDeque::new(),
+            since_promotion: None,
+            max_lines: 1,
+            min_display_seconds: 0.0,
+            line_lifetime_seconds: 0.0,
+            max_chars: 1,
+        };
+        queue.apply_settings(settings);
+        queue
+    }
+
+    /// Adopts new pacing and size limits, keeping lines already queued.
+    pub fn apply_settings(&mut self, settings: &SubtitleSettings) {
+        self.max_lines = settings.max_lines;
+        self.min_display_seconds = settings.min_display_seconds;
+        self.line_lifetime_seconds = settings.line_lifetime_seconds;
+        self.max_chars = settings.max_chars;
+        while self.visible.len() > self.max_lines {
+            self.visible.pop_front();
+        }
+    }
+
+    /// Queues a line, truncated to the character budget, and shows it at once if the
+    /// strip is ready for it.
+    pub fn push(&mut self, line: impl AsRef<str>) {
+        self.pending
+            .push_back(truncate_with_ellipsis(line.as_ref(), self.max_chars));
+        self.promote_ready();
+    }
+
+    /// Ages visible lines, drops expired ones, and promotes the next queued line.
+    pub fn tick(&mut self, delta_seconds: f32) {
+        let delta = delta_seconds.max(0.0);
+        for line in &mut self.visible {
+            line.age_seconds += delta;
+        }
+        if let Some(since) = self.since_promotion.as_mut() {
+            *since += delta;
+        }
+        let lifetime = self.line_lifetime_seconds;
+        self.visible.retain(|line| line.age_seconds < lifetime);
+        self.promote_ready();
+    }
+
+    /// On-screen lines, oldest (top) first.
+    pub fn visible_lines(&self) -> impl Iterator<Item = &str> {
+        self.visible.iter().map(|line| line.text.as_str())
+    }
+
+    #[cfg(test)]
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    fn promote_ready(&mut self) {
+        while !self.pending.is_empty() && self.ready_for_next() {
+            let text = self.pending.pop_front().expect("pending checked non-empty");
+            self.visible.push_back(SubtitleLine {
+                text,
+                age_seconds: 0.0,
+            });
+            while self.visible.len() > self.max_lines {
+                self.visible.pop_front();
+            }
+            self.since_promotion = Some(0.0);
+        }
+    }
+
+    fn ready_for_next(&self) -> bool {
+        self.visible.is_empty()
+            || self
+                .since_promotion
+                .is_none_or(|since| since >= self.min_display_seconds)
+    }
+}
+
+/// Cuts `text` to at most `max_chars` characters, ending in an ellipsis when shortened.
+pub fn truncate_with_ellipsis(text: &str, max_chars: usize) -> String {
+    let text = text.trim();
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let kept: String = text.chars().take(max_chars.saturating_sub(1)).collect();
+    format!("{}{}", kept.trim_end(), ELLIPSIS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue(max_lines: usize, min_display: f32, lifetime: f32) -> SubtitleQueue {
+        SubtitleQueue::new(&SubtitleSettings {
+            max_lines,
+            min_display_seconds: min_display,
+            line_lifetime_seconds: lifetime,
+            max_chars: 40,
+            ..SubtitleSettings::default()
+        })
+    }
+
+    fn lines(queue: &SubtitleQueue) -> Vec<&str> {
+        queue.visible_lines().collect()
+    }
+
+    #[test]
+    fn lines_scroll_up_in_arrival_order_and_expire() {
+        let mut queue = queue(2, 1.0, 5.0);
+        queue.push("Alric → Bryn: one");
+        queue.tick(1.0);
+        queue.push("Bryn → Alric: two");
+        assert_eq!(lines(&queue), ["Alric → Bryn: one", "Bryn → Alric: two"]);
+
+        queue.tick(1.0);
+        queue.push("Cedric: three");
+        assert_eq!(lines(&queue), ["Bryn → Alric: two", "Cedric: three"]);
+
+        queue.tick(4.5);
+        assert_eq!(lines(&queue), ["Cedric: three"]);
+        queue.tick(1.0);
+        assert!(lines(&queue).is_empty());
+    }
+
+    #[test]
+    fn bursts_wait_for_the_minimum_display_time() {
+        let mut queue = queue(1, 2.0, 10.0);
+        queue.push("first");
+        queue.push("second");
+        queue.push("third");
+        assert_eq!(lines(&queue), ["first"]);
+        assert_eq!(queue.pending_len(), 2);
+
+        queue.tick(1.9);
+        assert_eq!(lines(&queue), ["first"]);
+        queue.tick(0.1);
+        assert_eq!(lines(&queue), ["second"]);
+        queue.tick(2.0);
+        assert_eq!(lines(&queue), ["third"]);
+        assert_eq!(queue.pending_len(), 0);
+    }
+
+    #[test]
+    fn long_lines_are_truncated_with_an_ellipsis() {
+        assert_eq!(truncate_with_ellipsis("short line", 40), "short line");
+        assert_eq!(truncate_with_ellipsis("abcdefghij", 5), "abcd…");
+        assert_eq!(truncate_with_ellipsis("abc def ghi", 5), "abc…");
+        assert_eq!(truncate_with_ellipsis("åäöåäöåäö", 4), "åäö…");
+
+        let mut queue = queue(1, 0.0, 5.0);
+        queue.push("x".repeat(100));
+        assert_eq!(lines(&queue)[0].chars().count(), 40);
+        assert!(lines(&queue)[0].ends_with('…'));
+    }
+}
@@ -0,0 +1,104 @@
+// src/ui/subtitles/settings.rs
+//
+// Subtitle strip options loaded from `config/ui.toml`.
+
+use std::{fs, path::Path};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::core::config::{ConfigDiagnostics, ConfigReloadRequested};
+
+pub const CONFIG_PATH: &str = "config/ui.toml";
+const MAX_SUBTITLE_LINES: usize = 3;
+
+#[derive(Debug, Clone, Deserialize, Default)]
+struct RawUiConfig {
+    #[serde(default)]
+    subtitles: RawSubtitleSection,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+struct RawSubtitleSection {
+    enabled: bool,
+    max_lines: usize,
+    min_display_seconds: f32,
+    line_lifetime_seconds: f32,
+    max_chars: usize,
+    font_size: f32,
+}
+
+impl Default for RawSubtitleSection {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_lines: 2,
+            min_display_seconds: 2.5,
+            line_lifetime_seconds: 8.0,
+            max_chars: 140,
+            font_size: 16.0,
+        }
+    }
+}
+
+/// Runtime subtitle options; `enabled` is also flipped by the toggle key.
+#[derive(Resource, Debug, Clone)]
+pub struct SubtitleSettings {
+    pub enabled: bool,
+    /// Lines on screen at once (1-3).
+    pub max_lines: usize,
+    pub min_display_seconds: f32,
+    /// Total on-screen time per line; never shorter than `min_display_seconds`.
+    pub line_lifetime_seconds: f32,
+    /// Character budget per line, ellipsis included.
+    pub max_chars: usize,
+    pub font_size: f32,
+}
+
+impl Default for SubtitleSettings {
+    fn default() -> Self {
+        RawUiConfig::default().into()
+    }
+}
+
+impl SubtitleSettings {
+    /// Reads and parses the `[subtitles]` section of `config/ui.toml`.
+    pub fn load() -> Result<Self, String> {
+        let data = fs::read_to_string(Path::new(CONFIG_PATH))
+            .map_err(|err| format!("unable to read file: {err}"))?;
+        let raw = toml::from_str::<RawUiConfig>(&data)
+            .map_err(|err| format!("invalid ui config: {err}"))?;
+        Ok(raw.into())
+    }
+}
+
+impl From<RawUiConfig> for SubtitleSettings {
+    fn from(value: RawUiConfig) -> Self {
+        let raw = value.subtitles;
+        let min_display_seconds = raw.min_display_seconds.max(0.0);
+        Self {
+            enabled: raw.enabled,
+            max_lines: raw.max_lines.clamp(1, MAX_SUBTITLE_LINES),
+            min_display_seconds,
+            line_lifetime_seconds: raw.line_lifetime_seconds.max(min_display_seconds),
+            // Room for at least one character and the ellipsis.
+            max_chars: raw.max_chars.max(2),
+            font_size: raw.font_size.max(1.0),
+        }
+    }
+}
+
+/// Re-reads `config/ui.toml` on request, swapping the options in when it parses.
+pub fn reload_subtitle_settings(
+    mut requests: MessageReader<ConfigReloadRequested>,
+    mut diagnostics: ResMut<ConfigDiagnostics>,
+    mut settings: ResMut<SubtitleSettings>,
+) {
+    if !requests.read().any(|request| request.is_for(CONFIG_PATH)) {
+        return;
+    }
+    if let Some(reloaded) = diagnostics.report_reload(CONFIG_PATH, SubtitleSettings::load()) {
+        *settings = reloaded;
+    }
+}
@@ -0,0 +1,157 @@
+// src/ui/subtitles/systems.rs
+//
+// Feeds dialogue responses into the subtitle queue and renders its visible lines.
+
+use bevy::{ecs::message::MessageReader, prelude::*};
+
+use crate::core::input::{ActionInput, InputAction};
+use crate::dialogue::events::DialogueResponseEvent;
+use crate::npc::{
+    components::{Identity, NpcId},
+    spatial::NpcIndex,
+};
+use crate::ui::visibility::UiLayer;
+
+use super::{queue::SubtitleQueue, settings::SubtitleSettings};
+
+const STRIP_BOTTOM_OFFSET: f32 = 24.0;
+const STRIP_WIDTH_PERCENT: f32 = 60.0;
+const STRIP_PADDING: f32 = 8.0;
+const STRIP_BACKGROUND: Color = Color::srgba(0.0, 0.0, 0.0, 0.6);
+const STRIP_TEXT_COLOR: Color = Color::WHITE;
+const PLAYER_LABEL: &str = "You";
+
+/// Root node of the subtitle strip.
+#[derive(Component)]
+pub struct SubtitleStrip;
+
+/// Text node inside the strip that holds the visible lines.
+#[derive(Component)]
+pub struct SubtitleText;
+
+/// Formats a response as "Alric → Bryn: text", or "Alric: text" without a listener.
+pub fn format_subtitle(speaker: &str, target: Option<&str>, content: &str) -> String {
+    match target {
+        Some(target) => format!("{speaker} → {target}: {content}"),
+        None => format!("{speaker}: {content}"),
+    }
+}
+
+/// Spawns the hidden strip; sizes are logical pixels, so they follow `UiScale`.
+pub fn spawn_subtitle_strip(mut commands: Commands, settings: Res<SubtitleSettings>) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(STRIP_BOTTOM_OFFSET),
+                left: Val::Percent((100.0 - STRIP_WIDTH_PERCENT) / 2.0),
+                width: Val::Percent(STRIP_WIDTH_PERCENT),
+                padding: UiRect::all(Val::Px(STRIP_PADDING)),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            BackgroundColor(STRIP_BACKGROUND),
+            Visibility::Hidden,
+            SubtitleStrip,
+            UiLayer::Screen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: settings.font_size,
+                    ..default()
+                },
+                TextColor(STRIP_TEXT_COLOR),
+                TextLayout::new_with_justify(Justify::Center),
+                SubtitleText,
+            ));
+        });
+}
+
+/// Toggles the strip on and off at runtime.
+pub fn toggle_subtitles(input: ActionInput, mut settings: ResMut<SubtitleSettings>) {
+    if input.just_pressed(InputAction::ToggleSubtitles) {
+        settings.enabled = !settings.enabled;
+        info!("Subtitles {}", if settings.enabled { "on" } else { "off" });
+    }
+}
+
+/// Queues a subtitle line for every dialogue response while subtitles are enabled.
+pub fn collect_subtitles(
+    mut events: MessageReader<DialogueResponseEvent>,
+    settings: Res<SubtitleSettings>,
+    mut queue: ResMut<SubtitleQueue>,
+    npc_index: Res<NpcIndex>,
+    identities: Query<&Identity>,
+) {
+    if settings.is_changed() {
+        queue.apply_settings(&settings);
+    }
+    for event in events.read() {
+        if !settings.enabled {
+            continue;
+        }
+        let response = &event.response;
+        let speaker = display_name(&npc_index, &identities, response.speaker);
+        let target = response
+            .target
+            .map(|target| display_name(&npc_index, &identities, target));
+        queue.push(format_subtitle(
+            &speaker,
+            target.as_deref(),
+            &response.content,
+        ));
+    }
+}
+
+/// Advances the queue and writes its visible lines into the strip.
+pub fn update_subtitle_strip(
+    time: Res<Time>,
+    settings: Res<SubtitleSettings>,
+    mut queue: ResMut<SubtitleQueue>,
+    mut strips: Query<&mut Visibility, With<SubtitleStrip>>,
+    mut texts: Query<(&mut Text, &mut TextFont), With<SubtitleText>>,
+) {
+    queue.tick(time.delta_secs());
+
+    let lines: Vec<&str> = queue.visible_lines().collect();
+    let show = settings.enabled && !lines.is_empty();
+    for mut visibility in &mut strips {
+        visibility.set_if_neq(if show {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+    }
+    for (mut text, mut font) in &mut texts {
+        let joined = lines.join("\n");
+        if text.0 != joined {
+            text.0 = joined;
+        }
+        if font.font_size != settings.font_size {
+            font.font_size = settings.font_size;
+        }
+    }
+}
+
+fn display_name(npc_index: &NpcIndex, identities: &Query<&Identity>, npc: NpcId) -> String {
+    if npc.is_player() {
+        return PLAYER_LABEL.to_string();
+    }
+    Identity::name_of(npc_index, identities, npc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subtitles_name_speaker_and_listener() {
+        assert_eq!(
+            format_subtitle("Alric", Some("Bryn"), "Fresh grain today."),
+            "Alric → Bryn: Fresh grain today."
+        );
+        assert_eq!(format_subtitle("Bryn", None, "Hm."), "Bryn: Hm.");
+    }
+}
@@ -0,0 +1,121 @@
+// src/ui/thinking_indicator.rs
+//
+// Pulsing ellipsis above NPCs who are waiting on their dialogue request (`PendingSpeech`),
+// so a slow live reply does not look like the NPC ignoring everyone.
+
+use std::collections::HashSet;
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use crate::npc::components::PendingSpeech;
+use crate::ui::world_label::world_label;
+
+/// Just above the NPC capsule (0.3 radius, 1.0 body), measured from its centre.
+const INDICATOR_OFFSET: Vec3 = Vec3::new(0.0, 1.1, 0.0);
+const INDICATOR_FONT_SIZE: f32 = 28.0;
+const PULSES_PER_SECOND: f32 = 1.2;
+const MIN_ALPHA: f32 = 0.25;
+
+/// The ellipsis shown above `owner`.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct ThinkingIndicator {
+    pub owner: Entity,
+}
+
+/// Indicator alpha `seconds` into the game: a sine between `MIN_ALPHA` and fully opaque.
+pub fn pulse_alpha(seconds: f32) -> f32 {
+    let wave = 0.5 + 0.5 * (seconds * PULSES_PER_SECOND * TAU).sin();
+    MIN_ALPHA + (1.0 - MIN_ALPHA) * wave
+}
+
+/// Gives each thinking NPC an indicator and removes indicators whose NPC stopped thinking
+/// or was despawned.
+pub fn sync_thinking_indicators(
+    mut commands: Commands,
+    thinking: Query<Entity, With<PendingSpeech>>,
+    indicators: Query<(Entity, &ThinkingIndicator)>,
+) {
+    let mut shown = HashSet::new();
+    for (entity, indicator) in &indicators {
+        if thinking.contains(indicator.owner) {
+            shown.insert(indicator.owner);
+        } else {
+            commands.entity(entity).despawn();
+        }
+    }
+    for owner in &thinking {
+        if shown.contains(&owner) {
+            continue;
+        }
+        commands.spawn((
+            world_label("…", INDICATOR_FONT_SIZE, owner, INDICATOR_OFFSET),
+            TextColor(Color::WHITE.with_alpha(pulse_alpha(0.0))),
+            ThinkingIndicator { owner },
+            Name::new("Thinking indicator"),
+        ));
+    }
+}
+
+pub fn animate_thinking_indicators(
+    time: Res<Time>,
+    mut indicators: Query<&mut TextColor, With<ThinkingIndicator>>,
+) {
+    let alpha = pulse_alpha(time.elapsed_secs());
+    for mut color in &mut indicators {
+        color.0.set_alpha(alpha);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialogue::types::DialogueRequestId;
+
+    #[test]
+    fn pulse_stays_visible() {
+        for step in 0..40 {
+            let alpha = pulse_alpha(step as f32 * 0.05);
+            assert!((MIN_ALPHA - 1e-6..=1.0 + 1e-6).contains(&alpha));
+        }
+        assert!((pulse_alpha(0.25 / PULSES_PER_SECOND) - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn indicator_follows_the_marker() {
+        let mut app = App::new();
+        app.add_systems(Update, sync_thinking_indicators);
+        let npc = app
+            .world_mut()
+            .spawn((
+                Transform::default(),
+                PendingSpeech {
+                    request_id: DialogueRequestId::new(3),
+                },
+            ))
+            .id();
+        let count = |app: &mut App| {
+            app.world_mut()
+                .query::<&ThinkingIndicator>()
+                .iter(app.world())
+                .count()
+        };
+
+        app.update();
+        app.update();
+        assert_eq!(count(&mut app), 1, "one indicator per thinking NPC");
+
+        app.world_mut().entity_mut(npc).remove::<PendingSpeech>();
+        app.update();
+        assert_eq!(count(&mut app), 0);
+
+        app.world_mut().entity_mut(npc).insert(PendingSpeech {
+            request_id: DialogueRequestId::new(4),
+        });
+        app.update();
+        assert_eq!(count(&mut app), 1);
+        app.world_mut().entity_mut(npc).despawn();
+        app.update();
+        assert_eq!(count(&mut app), 0, "despawning the NPC takes its indicator");
+    }
+}
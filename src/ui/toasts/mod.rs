@@ -0,0 +1,7 @@
+// src/ui/toasts/mod.rs
+//
+// Transient toast notifications for economy and broker moments, stacked at the top centre.
+
+pub mod queue;
+pub mod settings;
+pub mod systems;
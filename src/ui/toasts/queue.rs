@@ -0,0 +1,195 @@
+// src/ui/toasts/queue.rs
+//
+// Toast queue: a capped stack of visible toasts, overflow waiting in arrival order.
+
+use std::collections::VecDeque;
+
+use bevy::prelude::Resource;
+
+/// What a toast reports; picks its accent color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToastKind {
+    Trade,
+    SkillUp,
+    Broker,
+    Budget,
+}
+
+/// One notification and how long it has been on screen.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Toast {
+    id: u64,
+    kind: ToastKind,
+    message: String,
+    duration_seconds: f32,
+    age_seconds: f32,
+}
+
+impl Toast {
+    /// Stable for the toast's lifetime, so the UI can match nodes to toasts.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn kind(&self) -> ToastKind {
+        self.kind
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn age_seconds(&self) -> f32 {
+        self.age_seconds
+    }
+
+    pub fn remaining_seconds(&self) -> f32 {
+        (self.duration_seconds - self.age_seconds).max(0.0)
+    }
+}
+
+/// Toasts on screen and those waiting for a slot.
+///
+/// At most `max_visible` toasts show at once, oldest on top. A push beyond that waits in
+/// arrival order and takes the first slot an expiring toast frees. Visible toasts expire
+/// after their own duration; a waiting toast's clock starts when it is shown.
+#[derive(Resource, Debug, Clone)]
+pub struct ToastQueue {
+    pending: VecDeque<Toast>,
+    visible: Vec<Toast>,
+    max_visible: usize,
+    next_id: u64,
+}
+
+impl ToastQueue {
+    pub fn new(max_visible: usize) -> Self {
+        Self {
+            pending: VecDeque::new(),
+            visible: Vec::new(),
+            max_visible: max_visible.max(1),
+            next_id: 0,
+        }
+    }
+
+    /// Changes the cap. Toasts already on screen stay until they expire.
+    pub fn set_max_visible(&mut self, max_visible: usize) {
+        self.max_visible = max_visible.max(1);
+        self.promote_ready();
+    }
+
+    /// Queues a toast, showing it at once if a slot is free.
+    pub fn push(&mut self, kind: ToastKind, message: impl Into<String>, duration_seconds: f32) {
+        self.pending.push_back(Toast {
+            id: self.next_id,
+            kind,
+            message: message.into(),
+            duration_seconds: duration_seconds.max(0.0),
+            age_seconds: 0.0,
+        });
+        self.next_id += 1;
+        self.promote_ready();
+    }
+
+    /// Ages visible toasts, drops expired ones, and shows waiting toasts in their place.
+    pub fn tick(&mut self, delta_seconds: f32) {
+        let delta = delta_seconds.max(0.0);
+        for toast in &mut self.visible {
+            toast.age_seconds += delta;
+        }
+        self.visible
+            .retain(|toast| toast.age_seconds < toast.duration_seconds);
+        self.promote_ready();
+    }
+
+    /// On-screen toasts, oldest (top) first.
+    pub fn visible(&self) -> impl Iterator<Item = &Toast> {
+        self.visible.iter()
+    }
+
+    #[cfg(test)]
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    fn promote_ready(&mut self) {
+        while self.visible.len() < self.max_visible {
+            let Some(toast) = self.pending.pop_front() else {
+                break;
+            };
+            self.visible.push(toast);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(queue: &ToastQueue) -> Vec<&str> {
+        queue.visible().map(Toast::message).collect()
+    }
+
+    #[test]
+    fn toasts_show_at_once_and_expire_after_their_duration() {
+        let mut queue = ToastQueue::new(3);
+        queue.push(ToastKind::Trade, "Brom delivered 2 tool crates", 4.0);
+        queue.tick(1.0);
+        queue.push(ToastKind::SkillUp, "Maren reached farmer level 2", 2.0);
+        assert_eq!(
+            messages(&queue),
+            [
+                "Brom delivered 2 tool crates",
+                "Maren reached farmer level 2"
+            ]
+        );
+
+        queue.tick(2.0);
+        assert_eq!(messages(&queue), ["Brom delivered 2 tool crates"]);
+        let toast = queue.visible().next().unwrap();
+        assert_eq!(toast.kind(), ToastKind::Trade);
+        assert!((toast.remaining_seconds() - 1.0).abs() < 1e-6);
+
+        queue.tick(1.0);
+        assert!(messages(&queue).is_empty());
+    }
+
+    #[test]
+    fn overflow_waits_in_arrival_order_for_a_free_slot() {
+        let mut queue = ToastQueue::new(2);
+        for (index, duration) in [3.0, 1.0, 5.0, 5.0].into_iter().enumerate() {
+            queue.push(ToastKind::Trade, format!("toast {index}"), duration);
+        }
+        assert_eq!(messages(&queue), ["toast 0", "toast 1"]);
+        assert_eq!(queue.pending_len(), 2);
+
+        queue.tick(1.0);
+        assert_eq!(messages(&queue), ["toast 0", "toast 2"]);
+        let promoted = queue.visible().last().unwrap();
+        assert_eq!(promoted.age_seconds(), 0.0, "waiting does not age a toast");
+
+        queue.tick(2.0);
+        assert_eq!(messages(&queue), ["toast 2", "toast 3"]);
+        assert_eq!(queue.pending_len(), 0);
+    }
+
+    #[test]
+    fn ids_stay_unique_and_a_raised_cap_shows_waiting_toasts() {
+        let mut queue = ToastQueue::new(1);
+        queue.push(ToastKind::Broker, "OpenAi live", 2.0);
+        queue.push(ToastKind::Budget, "Budget spent", 2.0);
+        assert_eq!(queue.pending_len(), 1);
+
+        queue.set_max_visible(4);
+        let ids: Vec<u64> = queue.visible().map(Toast::id).collect();
+        assert_eq!(ids, [0, 1]);
+
+        queue.set_max_visible(0);
+        queue.push(ToastKind::Trade, "third", 2.0);
+        assert_eq!(
+            queue.visible().count(),
+            2,
+            "shown toasts are not hidden again"
+        );
+        assert_eq!(queue.pending_len(), 1);
+    }
+}
@@ -0,0 +1,163 @@
+// src/ui/toasts/settings.rs
+//
+// Toast options loaded from the `[toasts]` section of `config/ui.toml`.
+
+use std::{fs, path::Path};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    core::config::{ConfigDiagnostics, ConfigReloadRequested},
+    economy::events::TradeReason,
+    ui::subtitles::settings::CONFIG_PATH,
+};
+
+const MAX_VISIBLE_TOASTS: usize = 6;
+
+#[derive(Debug, Clone, Deserialize, Default)]
+struct RawUiConfig {
+    #[serde(default)]
+    toasts: RawToastSection,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+struct RawToastSection {
+    enabled: bool,
+    max_visible: usize,
+    duration_seconds: f32,
+    slide_seconds: f32,
+    font_size: f32,
+    trade_reasons: Vec<String>,
+}
+
+impl Default for RawToastSection {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_visible: 4,
+            duration_seconds: 5.0,
+            slide_seconds: 0.35,
+            font_size: 14.0,
+            trade_reasons: vec!["exchange".to_string()],
+        }
+    }
+}
+
+/// Runtime toast options.
+#[derive(Resource, Debug, Clone)]
+pub struct ToastSettings {
+    pub enabled: bool,
+    /// Toasts on screen at once (1-6); more wait for a slot.
+    pub max_visible: usize,
+    /// On-screen time per toast, slides included.
+    pub duration_seconds: f32,
+    /// Length of the slide in and of the slide out; at most half the duration.
+    pub slide_seconds: f32,
+    pub font_size: f32,
+    /// Trades that raise a toast; the rest only reach the log.
+    pub trade_reasons: Vec<TradeReason>,
+}
+
+impl Default for ToastSettings {
+    fn default() -> Self {
+        RawUiConfig::default().into()
+    }
+}
+
+impl ToastSettings {
+    /// Reads and parses the `[toasts]` section of `config/ui.toml`.
+    pub fn load() -> Result<Self, String> {
+        let data = fs::read_to_string(Path::new(CONFIG_PATH))
+            .map_err(|err| format!("unable to read file: {err}"))?;
+        let raw = toml::from_str::<RawUiConfig>(&data)
+            .map_err(|err| format!("invalid ui config: {err}"))?;
+        Ok(raw.into())
+    }
+
+    pub fn shows_trade(&self, reason: TradeReason) -> bool {
+        self.trade_reasons.contains(&reason)
+    }
+}
+
+impl From<RawUiConfig> for ToastSettings {
+    fn from(value: RawUiConfig) -> Self {
+        let raw = value.toasts;
+        let duration_seconds = raw.duration_seconds.max(0.1);
+        Self {
+            enabled: raw.enabled,
+            max_visible: raw.max_visible.clamp(1, MAX_VISIBLE_TOASTS),
+            duration_seconds,
+            slide_seconds: raw.slide_seconds.clamp(0.0, duration_seconds / 2.0),
+            font_size: raw.font_size.max(1.0),
+            trade_reasons: raw
+                .trade_reasons
+                .iter()
+                .filter_map(|name| {
+                    let reason = trade_reason_from_name(name);
+                    if reason.is_none() {
+                        warn!("Ignoring unknown toast trade reason `{name}`");
+                    }
+                    reason
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Parses a trade reason name such as `"exchange"` or `"player_transfer"`.
+pub fn trade_reason_from_name(name: &str) -> Option<TradeReason> {
+    match name.trim().to_ascii_lowercase().as_str() {
+        "production" => Some(TradeReason::Production),
+        "processing" => Some(TradeReason::Processing),
+        "exchange" => Some(TradeReason::Exchange),
+        "player_transfer" => Some(TradeReason::PlayerTransfer),
+        "storage" => Some(TradeReason::Storage),
+        _ => None,
+    }
+}
+
+/// Re-reads `config/ui.toml` on request, swapping the toast options in when it parses.
+pub fn reload_toast_settings(
+    mut requests: MessageReader<ConfigReloadRequested>,
+    mut diagnostics: ResMut<ConfigDiagnostics>,
+    mut settings: ResMut<ToastSettings>,
+) {
+    if !requests.read().any(|request| request.is_for(CONFIG_PATH)) {
+        return;
+    }
+    if let Some(reloaded) = diagnostics.report_reload(CONFIG_PATH, ToastSettings::load()) {
+        *settings = reloaded;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn section_is_clamped_and_trade_reasons_parsed() {
+        let raw: RawUiConfig = toml::from_str(
+            r#"
+            [toasts]
+            max_visible = 20
+            duration_seconds = 2.0
+            slide_seconds = 5.0
+            trade_reasons = ["Exchange", "player_transfer", "gift"]
+            "#,
+        )
+        .expect("valid toml");
+        let settings = ToastSettings::from(raw);
+        assert_eq!(settings.max_visible, MAX_VISIBLE_TOASTS);
+        assert_eq!(settings.slide_seconds, 1.0);
+        assert_eq!(
+            settings.trade_reasons,
+            [TradeReason::Exchange, TradeReason::PlayerTransfer]
+        );
+        assert!(!settings.shows_trade(TradeReason::Production));
+
+        let shipped = ToastSettings::load().expect("shipped ui config is valid");
+        assert!(shipped.shows_trade(TradeReason::Exchange));
+    }
+}
@@ -0,0 +1,355 @@
+// src/ui/toasts/systems.rs
+//
+// Producers that turn economy and broker events into toasts, and the stack that shows them.
+
+use bevy::prelude::*;
+
+use crate::{
+    core::format::format_quantity,
+    dialogue::{events::ApiBudgetExhaustedEvent, status::DialogueBrokerStatus},
+    economy::events::{SkillLevelUpEvent, TradeCompletedEvent},
+    npc::{components::Identity, spatial::NpcIndex},
+    ui::{
+        dialogue_panel::components::{fade_out_alpha, intro_progress, slide_offset},
+        layout::ScreenAnchor,
+        visibility::UiLayer,
+    },
+};
+
+use super::{
+    queue::{Toast, ToastKind, ToastQueue},
+    settings::ToastSettings,
+};
+
+const TOAST_WIDTH: f32 = 280.0;
+const TOAST_GAP: f32 = 6.0;
+const TOAST_PADDING: f32 = 8.0;
+const ACCENT_WIDTH: f32 = 4.0;
+/// How far above its slot a toast starts its slide in, and ends its slide out.
+const SLIDE_DISTANCE: f32 = 24.0;
+const TOAST_BACKGROUND: Color = Color::srgba(0.05, 0.05, 0.07, 0.8);
+const TOAST_TEXT_COLOR: Color = Color::WHITE;
+
+/// Left-edge accent for each kind of toast.
+pub fn accent_color(kind: ToastKind) -> Color {
+    match kind {
+        ToastKind::Trade => Color::srgb(0.35, 0.75, 0.4),
+        ToastKind::SkillUp => Color::srgb(0.95, 0.75, 0.25),
+        ToastKind::Broker => Color::srgb(0.4, 0.6, 0.95),
+        ToastKind::Budget => Color::srgb(0.9, 0.35, 0.3),
+    }
+}
+
+/// Column at the top centre, clear of the corner windows and HUD, that holds one node per
+/// visible toast. Neither it nor its toasts take pointer input.
+#[derive(Component)]
+pub struct ToastStack;
+
+/// A toast's node; `id` matches `Toast::id`.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct ToastNode {
+    pub id: u64,
+}
+
+/// Spawns the empty stack; toasts are added and removed as children.
+pub fn spawn_toast_stack(mut commands: Commands) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(TOAST_GAP),
+            width: Val::Px(TOAST_WIDTH),
+            margin: UiRect::left(Val::Px(-TOAST_WIDTH / 2.0)),
+            ..default()
+        },
+        Pickable::IGNORE,
+        ToastStack,
+        ScreenAnchor::TopCenter,
+        UiLayer::Screen,
+    ));
+}
+
+/// Toasts every trade whose reason `[toasts] trade_reasons` lists, e.g. "Brom delivered
+/// 2 tool crates to Maren".
+pub fn toast_trades(
+    mut trades: MessageReader<TradeCompletedEvent>,
+    settings: Res<ToastSettings>,
+    mut queue: ResMut<ToastQueue>,
+    npc_index: Res<NpcIndex>,
+    identities: Query<&Identity>,
+) {
+    for trade in trades.read() {
+        if !settings.enabled || !settings.shows_trade(trade.reason) {
+            continue;
+        }
+        let goods = format_quantity(trade.good.label(), trade.quantity);
+        let name = |npc| Identity::name_of(&npc_index, &identities, npc);
+        let message = match (trade.from, trade.to) {
+            (Some(from), Some(to)) => format!("{} delivered {goods} to {}", name(from), name(to)),
+            (Some(from), None) => format!("{} used {goods}", name(from)),
+            (None, Some(to)) => format!("{} made {goods}", name(to)),
+            (None, None) => continue,
+        };
+        queue.push(ToastKind::Trade, message, settings.duration_seconds);
+    }
+}
+
+/// Toasts every skill level reached.
+pub fn toast_skill_level_ups(
+    mut level_ups: MessageReader<SkillLevelUpEvent>,
+    settings: Res<ToastSettings>,
+    mut queue: ResMut<ToastQueue>,
+    npc_index: Res<NpcIndex>,
+    identities: Query<&Identity>,
+) {
+    for level_up in level_ups.read() {
+        if !settings.enabled {
+            continue;
+        }
+        queue.push(
+            ToastKind::SkillUp,
+            format!(
+                "{} reached {} level {}",
+                Identity::name_of(&npc_index, &identities, level_up.npc),
+                level_up.profession.label(),
+                level_up.level
+            ),
+            settings.duration_seconds,
+        );
+    }
+}
+
+/// Toasts the day's API budget running out.
+pub fn toast_budget_exhaustion(
+    mut exhausted: MessageReader<ApiBudgetExhaustedEvent>,
+    settings: Res<ToastSettings>,
+    mut queue: ResMut<ToastQueue>,
+) {
+    for event in exhausted.read() {
+        if !settings.enabled {
+            continue;
+        }
+        let resumes = event
+            .resets_at
+            .map(|at| format!(" until {}", at.format("%H:%M")))
+            .unwrap_or_default();
+        queue.push(
+            ToastKind::Budget,
+            format!(
+                "Daily API {} budget spent; dialogue falls back{resumes}",
+                event.limit.label()
+            ),
+            settings.duration_seconds,
+        );
+    }
+}
+
+/// Toasts the broker's provider or connection label changing. The first reading is
+/// remembered without a toast.
+pub fn toast_broker_changes(
+    status: Res<DialogueBrokerStatus>,
+    settings: Res<ToastSettings>,
+    mut queue: ResMut<ToastQueue>,
+    mut last_label: Local<Option<String>>,
+) {
+    if !status.is_changed() {
+        return;
+    }
+    let label = format!("{} {}", status.provider(), status.connection_label());
+    let previous = last_label.replace(label.clone());
+    if previous.is_none() || previous.as_deref() == Some(label.as_str()) || !settings.enabled {
+        return;
+    }
+    queue.push(
+        ToastKind::Broker,
+        format!("Dialogue broker: {label}"),
+        settings.duration_seconds,
+    );
+}
+
+/// Advances the queue, keeps one node per visible toast under the stack, and slides each
+/// down into place and back up as it expires.
+pub fn update_toast_stack(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<ToastSettings>,
+    mut queue: ResMut<ToastQueue>,
+    stacks: Query<Entity, With<ToastStack>>,
+    mut nodes: Query<(
+        Entity,
+        &ToastNode,
+        &mut Node,
+        &mut BackgroundColor,
+        &Children,
+    )>,
+    mut texts: Query<&mut TextColor>,
+) {
+    if settings.is_changed() {
+        queue.set_max_visible(settings.max_visible);
+    }
+    queue.tick(time.delta_secs());
+    let Ok(stack) = stacks.single() else {
+        return;
+    };
+
+    let mut shown = Vec::new();
+    for (entity, toast_node, mut node, mut background, children) in &mut nodes {
+        let Some(toast) = queue.visible().find(|toast| toast.id() == toast_node.id) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+        shown.push(toast.id());
+        let alpha = toast_alpha(toast, settings.slide_seconds);
+        node.top = Val::Px(slide_offset(alpha, -SLIDE_DISTANCE, 0.0));
+        background.0 = TOAST_BACKGROUND.with_alpha(TOAST_BACKGROUND.alpha() * alpha);
+        for child in children.iter() {
+            if let Ok(mut color) = texts.get_mut(child) {
+                color.0 = TOAST_TEXT_COLOR.with_alpha(alpha);
+            }
+        }
+    }
+
+    for toast in queue.visible().filter(|toast| !shown.contains(&toast.id())) {
+        let node = spawn_toast_node(&mut commands, toast, settings.font_size);
+        commands.entity(stack).add_child(node);
+    }
+}
+
+/// Slide progress doubling as opacity: rises over the first `slide_seconds` of a toast's
+/// life and falls over its last.
+pub fn toast_alpha(toast: &Toast, slide_seconds: f32) -> f32 {
+    intro_progress(toast.age_seconds(), slide_seconds)
+        .min(fade_out_alpha(toast.remaining_seconds(), slide_seconds))
+}
+
+fn spawn_toast_node(commands: &mut Commands, toast: &Toast, font_size: f32) -> Entity {
+    commands
+        .spawn((
+            Node {
+                top: Val::Px(-SLIDE_DISTANCE),
+                padding: UiRect::all(Val::Px(TOAST_PADDING)),
+                border: UiRect::left(Val::Px(ACCENT_WIDTH)),
+                ..default()
+            },
+            BackgroundColor(TOAST_BACKGROUND.with_alpha(0.0)),
+            BorderColor::from(accent_color(toast.kind())),
+            Pickable::IGNORE,
+            ToastNode { id: toast.id() },
+        ))
+        .with_children(|node| {
+            node.spawn((
+                Text::new(toast.message()),
+                TextFont {
+                    font_size,
+                    ..default()
+                },
+                TextColor(TOAST_TEXT_COLOR.with_alpha(0.0)),
+                Pickable::IGNORE,
+            ));
+        })
+        .id()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{
+        economy::{components::TradeGood, events::TradeReason},
+        npc::{components::NpcId, spatial::index_npcs},
+    };
+
+    fn toast_app(settings: ToastSettings) -> App {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .insert_resource(ToastQueue::new(settings.max_visible))
+            .insert_resource(settings)
+            .init_resource::<NpcIndex>()
+            .add_message::<TradeCompletedEvent>()
+            .add_systems(Startup, spawn_toast_stack)
+            .add_systems(
+                Update,
+                (index_npcs, toast_trades, update_toast_stack).chain(),
+            );
+        app
+    }
+
+    fn exchange(day: u64, quantity: u32) -> TradeCompletedEvent {
+        TradeCompletedEvent {
+            day,
+            from: Some(NpcId::new(1)),
+            to: Some(NpcId::new(2)),
+            good: TradeGood::Tools,
+            quantity,
+            reason: TradeReason::Exchange,
+        }
+    }
+
+    fn toast_nodes(app: &mut App) -> usize {
+        app.world_mut()
+            .query::<&ToastNode>()
+            .iter(app.world())
+            .count()
+    }
+
+    #[test]
+    fn the_stack_never_shows_more_toasts_than_the_cap() {
+        let settings = ToastSettings {
+            max_visible: 3,
+            duration_seconds: 2.0,
+            ..ToastSettings::default()
+        };
+        let mut app = toast_app(settings);
+        app.world_mut()
+            .spawn(Identity::new(NpcId::new(1), "Brom", 40.0));
+        app.world_mut()
+            .spawn(Identity::new(NpcId::new(2), "Maren", 31.0));
+        for quantity in 1..=5 {
+            app.world_mut().write_message(exchange(0, quantity));
+        }
+        let mut production = exchange(0, 1);
+        production.reason = TradeReason::Production;
+        app.world_mut().write_message(production);
+
+        app.update();
+        app.update();
+        assert_eq!(toast_nodes(&mut app), 3, "capped at max_visible");
+        assert_eq!(app.world().resource::<ToastQueue>().pending_len(), 2);
+        let message = app
+            .world()
+            .resource::<ToastQueue>()
+            .visible()
+            .next()
+            .map(|toast| toast.message().to_string());
+        assert_eq!(
+            message.as_deref(),
+            Some("Brom delivered 1 tool crate to Maren")
+        );
+
+        app.world_mut()
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs_f32(2.5));
+        app.update();
+        assert_eq!(
+            toast_nodes(&mut app),
+            2,
+            "expired toasts make room for the two waiting"
+        );
+        assert_eq!(app.world().resource::<ToastQueue>().pending_len(), 0);
+    }
+
+    #[test]
+    fn toasts_slide_in_and_out_over_the_slide_time() {
+        let mut queue = ToastQueue::new(1);
+        queue.push(ToastKind::Trade, "hello", 4.0);
+        let alpha = |queue: &ToastQueue| toast_alpha(queue.visible().next().unwrap(), 0.5);
+        assert_eq!(alpha(&queue), 0.0);
+        queue.tick(0.25);
+        assert!((alpha(&queue) - 0.5).abs() < 1e-6);
+        queue.tick(2.0);
+        assert_eq!(alpha(&queue), 1.0);
+        queue.tick(1.5);
+        assert!((alpha(&queue) - 0.5).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,228 @@
+// src/ui/visibility.rs
+//
+// Cinematic UI toggle: the cycle binding (F11 by default) steps between showing
+// everything, only world-space labels, and nothing. Tagged elements are hidden in place,
+// never despawned, so they come back exactly as they were.
+
+use bevy::prelude::*;
+
+use crate::core::input::{ActionInput, InputAction};
+
+/// How much UI is drawn. Each mode shows everything the next one does plus one more tier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UiVisibilityMode {
+    #[default]
+    All,
+    WorldOnly,
+    None,
+}
+
+impl UiVisibilityMode {
+    /// Mode the toggle key moves to: All → WorldOnly → None → All.
+    pub fn next(self) -> Self {
+        match self {
+            Self::All => Self::WorldOnly,
+            Self::WorldOnly => Self::None,
+            Self::None => Self::All,
+        }
+    }
+
+    /// Whether elements on `layer` are drawn in this mode.
+    pub fn shows(self, layer: UiLayer) -> bool {
+        match layer {
+            UiLayer::Screen => self == Self::All,
+            UiLayer::World => self != Self::None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::All => "all UI",
+            Self::WorldOnly => "world-space UI only",
+            Self::None => "no UI",
+        }
+    }
+}
+
+/// Current UI visibility mode; change it and tagged elements follow next frame.
+#[derive(Resource, Debug, Default)]
+pub struct UiVisibilityState {
+    pub mode: UiVisibilityMode,
+}
+
+/// Emitted whenever the toggle changes the mode.
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UiVisibilityChangedEvent {
+    pub previous: UiVisibilityMode,
+    pub current: UiVisibilityMode,
+}
+
+/// Tier an element belongs to. Put it on the root entity; children hide with it.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UiLayer {
+    /// Panels, windows, HUD widgets, and the subtitle strip.
+    Screen,
+    /// Labels drawn in the world, such as crate counts.
+    World,
+}
+
+/// What hiding replaced, so restoring puts it back. UI nodes are hidden through
+/// `Node::display`, leaving their `Visibility` to the systems that already drive it.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub enum UiHidden {
+    Display(Display),
+    Visibility(Visibility),
+}
+
+/// Run condition for systems that spawn or interact with screen-space UI.
+pub fn screen_ui_visible(state: Res<UiVisibilityState>) -> bool {
+    state.mode.shows(UiLayer::Screen)
+}
+
+/// Run condition for systems that place world-space UI; labels stay put while it is hidden.
+pub fn world_ui_visible(state: Res<UiVisibilityState>) -> bool {
+    state.mode.shows(UiLayer::World)
+}
+
+/// The cycle binding advances the mode and announces the change.
+pub fn cycle_ui_visibility(
+    input: ActionInput,
+    mut state: ResMut<UiVisibilityState>,
+    mut changes: MessageWriter<UiVisibilityChangedEvent>,
+) {
+    if !input.just_pressed(InputAction::CycleUiVisibility) {
+        return;
+    }
+    let previous = state.mode;
+    state.mode = previous.next();
+    changes.write(UiVisibilityChangedEvent {
+        previous,
+        current: state.mode,
+    });
+    info!("Showing {}", state.mode.label());
+}
+
+/// Hides tagged elements whose tier the mode excludes and restores the rest. Runs on every
+/// mode change, and for newly spawned elements so they never flash in while hidden.
+#[allow(clippy::type_complexity)]
+pub fn apply_ui_visibility(
+    mut commands: Commands,
+    state: Res<UiVisibilityState>,
+    mut elements: Query<(
+        Entity,
+        Ref<UiLayer>,
+        Option<&UiHidden>,
+        Option<&mut Node>,
+        Option<&mut Visibility>,
+    )>,
+) {
+    let mode_changed = state.is_changed();
+    for (entity, layer, hidden, node, visibility) in elements.iter_mut() {
+        if !mode_changed && !layer.is_added() {
+            continue;
+        }
+        match (state.mode.shows(*layer), hidden) {
+            (false, None) => {
+                let stashed = if let Some(mut node) = node {
+                    UiHidden::Display(std::mem::replace(&mut node.display, Display::None))
+                } else if let Some(mut visibility) = visibility {
+                    UiHidden::Visibility(std::mem::replace(&mut *visibility, Visibility::Hidden))
+                } else {
+                    continue;
+                };
+                commands.entity(entity).insert(stashed);
+            }
+            (true, Some(stashed)) => {
+                match (*stashed, node, visibility) {
+                    (UiHidden::Display(display), Some(mut node), _) => node.display = display,
+                    (UiHidden::Visibility(previous), _, Some(mut visibility)) => {
+                        *visibility = previous
+                    }
+                    _ => {}
+                }
+                commands.entity(entity).remove::<UiHidden>();
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::input::InputBindings;
+
+    #[test]
+    fn run_conditions_follow_the_mode_tiers() {
+        let expected = [
+            (UiVisibilityMode::All, true, true),
+            (UiVisibilityMode::WorldOnly, false, true),
+            (UiVisibilityMode::None, false, false),
+        ];
+        for (mode, screen, world) in expected {
+            let mut world_state = World::new();
+            world_state.insert_resource(UiVisibilityState { mode });
+            let screen_shown = world_state.run_system_cached(screen_ui_visible).unwrap();
+            let world_shown = world_state.run_system_cached(world_ui_visible).unwrap();
+            assert_eq!((screen_shown, world_shown), (screen, world), "{mode:?}");
+        }
+        assert_eq!(UiVisibilityMode::None.next(), UiVisibilityMode::All);
+    }
+
+    #[test]
+    fn toggle_cycles_modes_and_hides_tiers_in_place() {
+        let mut app = App::new();
+        app.init_resource::<UiVisibilityState>()
+            .init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<InputBindings>()
+            .add_message::<UiVisibilityChangedEvent>()
+            .add_systems(Update, (cycle_ui_visibility, apply_ui_visibility).chain());
+        let panel = app
+            .world_mut()
+            .spawn((
+                Node {
+                    display: Display::Grid,
+                    ..default()
+                },
+                UiLayer::Screen,
+            ))
+            .id();
+        let label = app
+            .world_mut()
+            .spawn((Visibility::Inherited, UiLayer::World))
+            .id();
+        app.update();
+
+        let press = |app: &mut App| {
+            let mut keys = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+            keys.release(KeyCode::F11);
+            keys.clear();
+            keys.press(KeyCode::F11);
+            app.update();
+        };
+        let shown = |app: &App| {
+            (
+                app.world().get::<Node>(panel).unwrap().display,
+                *app.world().get::<Visibility>(label).unwrap(),
+            )
+        };
+
+        press(&mut app);
+        assert_eq!(shown(&app), (Display::None, Visibility::Inherited));
+        press(&mut app);
+        assert_eq!(shown(&app), (Display::None, Visibility::Hidden));
+        press(&mut app);
+        assert_eq!(shown(&app), (Display::Grid, Visibility::Inherited));
+        assert!(app.world().get::<UiHidden>(panel).is_none());
+
+        let messages = app.world().resource::<Messages<UiVisibilityChangedEvent>>();
+        let last = messages.get_cursor().read(messages).last().copied();
+        assert_eq!(
+            last,
+            Some(UiVisibilityChangedEvent {
+                previous: UiVisibilityMode::None,
+                current: UiVisibilityMode::All,
+            })
+        );
+    }
+}
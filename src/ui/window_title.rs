@@ -0,0 +1,147 @@
+// src/ui/window_title.rs
+//
+// Keeps the primary window title in sync with the sim day, clock time, broker mode, and FPS.
+
+use bevy::{
+    diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+    prelude::*,
+    window::PrimaryWindow,
+};
+
+use crate::core::format::FormatSettings;
+use crate::dialogue::status::DialogueBrokerStatus;
+use crate::npc::components::Identity;
+use crate::world::time::{minute_of_day, WorldClock};
+
+const GAME_TITLE: &str = "TheGame";
+const TITLE_SEPARATOR: &str = " — ";
+
+/// Builds the window title, e.g. "TheGame — Day 4, 13:20 — OpenAi live — 3 NPCs — 60 FPS".
+pub fn compose_window_title(
+    format: &FormatSettings,
+    day: u64,
+    time_of_day: f32,
+    broker: Option<&DialogueBrokerStatus>,
+    npc_count: usize,
+    fps: Option<f64>,
+) -> String {
+    let mut sections = vec![
+        GAME_TITLE.to_string(),
+        format!("Day {}, {}", day, format.format_time(time_of_day)),
+    ];
+
+    if let Some(status) = broker {
+        sections.push(format!(
+            "{} {}",
+            status.provider(),
+            status.connection_label()
+        ));
+    }
+
+    let noun = if npc_count == 1 { "NPC" } else { "NPCs" };
+    sections.push(format!("{npc_count} {noun}"));
+    if let Some(fps) = fps {
+        sections.push(format!("{fps:.0} FPS"));
+    }
+    sections.join(TITLE_SEPARATOR)
+}
+
+/// Rewrites the title once per in-game minute, or when the clock format changes; does
+/// nothing when running headless.
+/// FPS is the smoothed reading from `FrameTimeDiagnosticsPlugin`, left out until it has one.
+pub fn update_window_title(
+    clock: Res<WorldClock>,
+    format: Res<FormatSettings>,
+    broker: Option<Res<DialogueBrokerStatus>>,
+    diagnostics: Option<Res<DiagnosticsStore>>,
+    npcs: Query<(), With<Identity>>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    mut last_minute: Local<Option<u64>>,
+) {
+    let Ok(mut window) = windows.single_mut() else {
+        return;
+    };
+
+    let minute_stamp = clock.day_count() * 24 * 60 + u64::from(minute_of_day(clock.time_of_day()));
+    if *last_minute == Some(minute_stamp) && !format.is_changed() {
+        return;
+    }
+    *last_minute = Some(minute_stamp);
+
+    window.title = compose_window_title(
+        &format,
+        clock.day_count(),
+        clock.time_of_day(),
+        broker.as_deref(),
+        npcs.iter().count(),
+        diagnostics
+            .as_deref()
+            .and_then(|store| store.get(&FrameTimeDiagnosticsPlugin::FPS))
+            .and_then(|fps| fps.smoothed()),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::format::ClockFormat;
+    use crate::dialogue::{broker::DialogueProviderKind, status::DialogueConnectionState};
+    use crate::npc::components::NpcId;
+
+    fn title_app() -> App {
+        let mut app = App::new();
+        app.insert_resource(WorldClock::new())
+            .init_resource::<FormatSettings>()
+            .insert_resource(DialogueBrokerStatus::new(
+                DialogueProviderKind::OpenAi,
+                DialogueConnectionState::Fallback,
+            ))
+            .add_systems(Update, update_window_title);
+        app
+    }
+
+    #[test]
+    fn title_includes_day_time_broker_npcs_and_fps() {
+        let status =
+            DialogueBrokerStatus::new(DialogueProviderKind::OpenAi, DialogueConnectionState::Live);
+        let format = FormatSettings::default();
+        let afternoon = 13.0 / 24.0 + 20.5 / 1440.0;
+        assert_eq!(
+            compose_window_title(&format, 4, afternoon, Some(&status), 3, Some(59.6)),
+            "TheGame — Day 4, 13:20 — OpenAi live — 3 NPCs — 60 FPS"
+        );
+        assert_eq!(
+            compose_window_title(&format, 0, 0.0, None, 1, None),
+            "TheGame — Day 0, 00:00 — 1 NPC"
+        );
+        let twelve_hour = FormatSettings {
+            clock: ClockFormat::TwelveHour,
+        };
+        assert_eq!(
+            compose_window_title(&twelve_hour, 4, afternoon, None, 3, None),
+            "TheGame — Day 4, 1:20 PM — 3 NPCs"
+        );
+    }
+
+    #[test]
+    fn headless_app_without_window_does_not_panic() {
+        let mut app = title_app();
+        app.update();
+        app.update();
+    }
+
+    #[test]
+    fn primary_window_title_is_updated() {
+        let mut app = title_app();
+        let window = app
+            .world_mut()
+            .spawn((Window::default(), PrimaryWindow))
+            .id();
+        app.world_mut()
+            .spawn(Identity::new(NpcId::new(0), "Alric", 30.0));
+        app.update();
+
+        let title = &app.world().get::<Window>(window).unwrap().title;
+        assert_eq!(title, "TheGame — Day 0, 00:00 — OpenAi fallback — 1 NPC");
+    }
+}
@@ -0,0 +1,135 @@
+// src/ui/world_label.rs
+//
+// Small text labels pinned to points in the world (crate counts, "thinking" ellipses,
+// emotes). They are UI text nodes projected through the fly camera each frame, since the
+// 3D camera does not draw `Text2d`; sharing one helper keeps their tagging and placement
+// the same.
+
+use bevy::prelude::*;
+
+use crate::ui::visibility::UiLayer;
+use crate::world::components::FlyCamera;
+
+/// Text node kept over `offset` in `anchor`'s local space.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct WorldLabel {
+    pub anchor: Entity,
+    pub offset: Vec3,
+}
+
+/// Text label over `offset` relative to `anchor`. Hidden with the other world-space UI, and
+/// until `place_world_labels` first finds it on screen.
+pub fn world_label(
+    text: impl Into<String>,
+    font_size: f32,
+    anchor: Entity,
+    offset: Vec3,
+) -> impl Bundle {
+    (
+        Text::new(text),
+        TextFont {
+            font_size,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            ..default()
+        },
+        Visibility::Hidden,
+        WorldLabel { anchor, offset },
+        UiLayer::World,
+    )
+}
+
+/// Top-left corner that centres a label of logical `size` over `projected`, or `None` to
+/// hide it when its point is off screen.
+pub fn label_position(projected: Option<Vec2>, size: Vec2) -> Option<Vec2> {
+    projected.map(|point| point - size / 2.0)
+}
+
+/// Moves each `WorldLabel` over its anchor as seen by the fly camera, hides the ones whose
+/// anchor is behind the camera, and despawns the ones whose anchor is gone.
+pub fn place_world_labels(
+    mut commands: Commands,
+    cameras: Query<(&Camera, &GlobalTransform), With<FlyCamera>>,
+    anchors: Query<&GlobalTransform, Without<WorldLabel>>,
+    mut labels: Query<(
+        Entity,
+        &WorldLabel,
+        &mut Node,
+        &mut Visibility,
+        Option<&ComputedNode>,
+    )>,
+) {
+    let camera = cameras.single().ok();
+    for (entity, label, mut node, mut visibility, computed) in &mut labels {
+        let Ok(anchor) = anchors.get(label.anchor) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+        let projected = camera.and_then(|(camera, camera_transform)| {
+            camera
+                .world_to_viewport(camera_transform, anchor.transform_point(label.offset))
+                .ok()
+        });
+        let size = computed.map_or(Vec2::ZERO, |computed| {
+            computed.size() * computed.inverse_scale_factor()
+        });
+        apply_label_position(&mut node, &mut visibility, label_position(projected, size));
+    }
+}
+
+/// Writes a placement from `label_position` onto the label's node.
+pub fn apply_label_position(node: &mut Node, visibility: &mut Visibility, position: Option<Vec2>) {
+    let Some(position) = position else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    node.left = Val::Px(position.x);
+    node.top = Val::Px(position.y);
+    *visibility = Visibility::Inherited;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_centre_over_their_point_and_hide_off_screen() {
+        assert_eq!(
+            label_position(Some(Vec2::new(100.0, 50.0)), Vec2::new(20.0, 10.0)),
+            Some(Vec2::new(90.0, 45.0))
+        );
+        assert_eq!(label_position(None, Vec2::new(20.0, 10.0)), None);
+
+        let mut node = Node::default();
+        let mut visibility = Visibility::Hidden;
+        apply_label_position(&mut node, &mut visibility, Some(Vec2::new(90.0, 45.0)));
+        assert_eq!((node.left, node.top), (Val::Px(90.0), Val::Px(45.0)));
+        assert_eq!(visibility, Visibility::Inherited);
+        apply_label_position(&mut node, &mut visibility, None);
+        assert_eq!(visibility, Visibility::Hidden);
+    }
+
+    #[test]
+    fn labels_of_despawned_anchors_are_removed() {
+        let mut app = App::new();
+        app.add_systems(Update, place_world_labels);
+        let anchor = app.world_mut().spawn(GlobalTransform::default()).id();
+        let label = app
+            .world_mut()
+            .spawn(world_label("x9", 14.0, anchor, Vec3::Y))
+            .id();
+
+        app.update();
+        assert_eq!(
+            app.world().get::<Visibility>(label),
+            Some(&Visibility::Hidden),
+            "no camera, nothing to project onto"
+        );
+
+        app.world_mut().despawn(anchor);
+        app.update();
+        assert!(app.world().get_entity(label).is_err());
+    }
+}
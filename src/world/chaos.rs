@@ -0,0 +1,25 @@
+//! World faults for chaos runs (`--features chaos`): the clock lurching forward.
+use bevy::prelude::*;
+
+use crate::{economy::rng::DailyRng, world::time::WorldClock};
+
+/// Longest jump, as a fraction of a day.
+const MAX_JUMP_FRACTION: f32 = 0.25;
+
+/// Moves the world clock forward by up to a quarter day, rolling into the next day when it
+/// passes midnight.
+pub fn jump_world_clock(world: &mut World, rng: &mut DailyRng) -> Option<String> {
+    let mut clock = world.get_resource_mut::<WorldClock>()?;
+    let jump = rng.next_f32() * MAX_JUMP_FRACTION;
+    let from = clock.time_of_day();
+    let to = from + jump;
+    if to >= 1.0 {
+        clock.skip_days(1);
+    }
+    clock.set_time_of_day(to);
+    Some(format!(
+        "clock jumped {jump:.3} of a day from {from:.3} to day {} at {:.3}",
+        clock.day_count(),
+        clock.time_of_day()
+    ))
+}
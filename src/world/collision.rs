@@ -0,0 +1,251 @@
+//! Lightweight collision: movers (NPCs and the player) are upright cylinders, props are
+//! axis-aligned boxes, and overlaps are resolved on the XZ plane only by pushing the
+//! mover out. No physics engine, no mover-vs-mover checks (crowd separation covers NPCs).
+use bevy::prelude::*;
+
+/// Below this a mover's centre counts as touching the box surface; the push then falls
+/// back to the nearest face.
+const SURFACE_EPSILON: f32 = 1e-5;
+/// Extra reach past a box corner so a mover pressed against it still counts as arrived.
+const CONTACT_SLOP: f32 = 0.05;
+
+/// Solid prop the movers cannot walk through. Rotation is ignored.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct StaticCollider {
+    /// Half size of the box on each axis, centred on the entity's translation.
+    pub half_extents: Vec3,
+}
+
+impl StaticCollider {
+    pub fn new(half_extents: Vec3) -> Self {
+        Self { half_extents }
+    }
+
+    /// Horizontal distance from the box centre within which a mover counts as arrived:
+    /// `arrive_distance` past the nearest face, and never less than a mover pressed
+    /// against a corner can reach.
+    pub fn arrival_reach(&self, mover_radius: f32, arrive_distance: f32) -> f32 {
+        let footprint = self.half_extents.xz();
+        (footprint.max_element() + arrive_distance).max(footprint.length() + CONTACT_SLOP)
+            + mover_radius
+    }
+
+    /// Whether a mover centred at `mover_y` overlaps the box vertically.
+    fn spans_height(&self, box_y: f32, mover_y: f32, mover: &MoverCollider) -> bool {
+        (mover_y - box_y).abs() < self.half_extents.y + mover.half_height
+    }
+}
+
+/// Upright cylinder around a moving entity's translation.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct MoverCollider {
+    pub radius: f32,
+    /// Half the cylinder's height; a camera flying above a prop's top passes over it.
+    pub half_height: f32,
+}
+
+impl MoverCollider {
+    pub const fn new(radius: f32, half_height: f32) -> Self {
+        Self {
+            radius,
+            half_height,
+        }
+    }
+}
+
+/// Arrive distance for a walk toward `collider`, or the plain distance when the
+/// destination has no collider.
+pub fn arrival_distance(
+    arrive_distance: f32,
+    collider: Option<&StaticCollider>,
+    mover: Option<&MoverCollider>,
+) -> f32 {
+    match collider {
+        Some(collider) => {
+            collider.arrival_reach(mover.map_or(0.0, |mover| mover.radius), arrive_distance)
+        }
+        None => arrive_distance,
+    }
+}
+
+/// Smallest XZ displacement that moves a circle at `center` out of the box, or `None`
+/// when they don't overlap. A centre outside the box is pushed straight away from the
+/// closest point on it; a centre inside (or on) the box leaves through the nearest face.
+pub fn circle_aabb_penetration(
+    center: Vec2,
+    radius: f32,
+    box_center: Vec2,
+    half_extents: Vec2,
+) -> Option<Vec2> {
+    let local = center - box_center;
+    let closest = local.clamp(-half_extents, half_extents);
+    let outside = local - closest;
+    let distance = outside.length();
+
+    if distance > SURFACE_EPSILON {
+        return (distance < radius).then(|| outside / distance * (radius - distance));
+    }
+
+    // Distance to each face along its outward normal; leave through the nearest one.
+    let faces = [
+        (half_extents.x - local.x, Vec2::X),
+        (half_extents.x + local.x, Vec2::NEG_X),
+        (half_extents.y - local.y, Vec2::Y),
+        (half_extents.y + local.y, Vec2::NEG_Y),
+    ];
+    let (depth, normal) = faces
+        .into_iter()
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .expect("four faces");
+    Some(normal * (depth + radius))
+}
+
+/// Pushes movers out of every static collider they overlap. Runs after all movement so
+/// locomotion, crowd separation, and the fly camera never leave anyone inside a prop.
+pub fn resolve_static_collisions(
+    colliders: Query<(&GlobalTransform, &StaticCollider)>,
+    mut movers: Query<(&mut Transform, &MoverCollider), Without<StaticCollider>>,
+) {
+    if colliders.is_empty() {
+        return;
+    }
+    for (mut transform, mover) in movers.iter_mut() {
+        for (global, collider) in colliders.iter() {
+            let box_center = global.translation();
+            if !collider.spans_height(box_center.y, transform.translation.y, mover) {
+                continue;
+            }
+            if let Some(push) = circle_aabb_penetration(
+                transform.translation.xz(),
+                mover.radius,
+                box_center.xz(),
+                collider.half_extents.xz(),
+            ) {
+                transform.translation.x += push.x;
+                transform.translation.z += push.y;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HALF: Vec2 = Vec2::new(0.5, 0.5);
+
+    fn assert_close(actual: Vec2, expected: Vec2) {
+        assert!(
+            (actual - expected).length() < 1e-5,
+            "{actual:?} != {expected:?}"
+        );
+    }
+
+    #[test]
+    fn separated_shapes_need_no_push() {
+        assert_eq!(
+            circle_aabb_penetration(Vec2::new(2.0, 0.0), 0.3, Vec2::ZERO, HALF),
+            None
+        );
+        assert_eq!(
+            circle_aabb_penetration(Vec2::new(0.8, 0.0), 0.3, Vec2::ZERO, HALF),
+            None,
+            "exactly touching a face is not an overlap"
+        );
+        assert_eq!(
+            circle_aabb_penetration(Vec2::new(0.75, 0.75), 0.3, Vec2::ZERO, HALF),
+            None,
+            "near a corner the circle's curve clears the box"
+        );
+    }
+
+    #[test]
+    fn overlap_from_outside_pushes_away_from_the_closest_point() {
+        let push = circle_aabb_penetration(Vec2::new(0.7, 0.1), 0.3, Vec2::ZERO, HALF)
+            .expect("overlapping a face");
+        assert_close(push, Vec2::new(0.1, 0.0));
+
+        let corner = Vec2::new(0.6, 0.6);
+        let push = circle_aabb_penetration(corner, 0.3, Vec2::ZERO, HALF).expect("corner");
+        let pushed = corner + push;
+        assert_close(push.normalize(), Vec2::ONE.normalize());
+        assert!(((pushed - HALF).length() - 0.3).abs() < 1e-5);
+    }
+
+    #[test]
+    fn centre_inside_leaves_through_the_nearest_face() {
+        let push =
+            circle_aabb_penetration(Vec2::new(0.2, -0.4), 0.3, Vec2::ZERO, HALF).expect("inside");
+        assert_close(push, Vec2::new(0.0, -0.4));
+
+        let push =
+            circle_aabb_penetration(Vec2::new(-0.45, 0.0), 0.25, Vec2::ZERO, HALF).expect("inside");
+        assert_close(push, Vec2::new(-0.3, 0.0));
+    }
+
+    #[test]
+    fn centre_on_the_surface_or_at_the_middle_still_resolves() {
+        let push = circle_aabb_penetration(Vec2::new(0.5, 0.2), 0.3, Vec2::ZERO, HALF)
+            .expect("on the face");
+        assert_close(push, Vec2::new(0.3, 0.0));
+
+        let push = circle_aabb_penetration(Vec2::ZERO, 0.3, Vec2::ZERO, HALF).expect("centre");
+        assert!((push.length() - 0.8).abs() < 1e-5, "deterministic exit");
+    }
+
+    #[test]
+    fn offset_and_stretched_boxes() {
+        let box_center = Vec2::new(8.0, 3.0);
+        let half = Vec2::new(1.0, 0.25);
+        let push = circle_aabb_penetration(Vec2::new(8.6, 3.1), 0.3, box_center, half)
+            .expect("inside the long box");
+        assert_close(push, Vec2::new(0.0, 0.45));
+    }
+
+    #[test]
+    fn arrival_reach_stops_adjacent_from_any_side() {
+        let collider = StaticCollider::new(Vec3::new(0.45, 0.3, 0.45));
+        let reach = collider.arrival_reach(0.3, 0.35);
+
+        assert!(reach > 0.45 + 0.3, "a mover stopped at a face has arrived");
+        assert!(
+            reach > Vec2::splat(0.45).length() + 0.3,
+            "a mover stopped at a corner has arrived"
+        );
+        assert!(
+            reach <= 0.45 + 0.3 + 0.35 + 1e-5,
+            "no further than arrive distance"
+        );
+        assert_eq!(arrival_distance(0.35, None, None), 0.35);
+    }
+
+    #[test]
+    fn resolver_ignores_movers_above_the_prop() {
+        let mut app = App::new();
+        app.add_systems(Update, resolve_static_collisions);
+        app.world_mut().spawn((
+            GlobalTransform::from_translation(Vec3::new(0.0, 0.25, 0.0)),
+            StaticCollider::new(Vec3::new(0.45, 0.3, 0.45)),
+        ));
+        let walker = app
+            .world_mut()
+            .spawn((
+                Transform::from_xyz(0.1, 1.0, 0.0),
+                MoverCollider::new(0.3, 0.8),
+            ))
+            .id();
+        let flyer = app
+            .world_mut()
+            .spawn((
+                Transform::from_xyz(0.1, 8.0, 0.0),
+                MoverCollider::new(0.4, 0.9),
+            ))
+            .id();
+        app.update();
+
+        let walked = app.world().get::<Transform>(walker).unwrap().translation;
+        assert!((walked.x - 0.75).abs() < 1e-5, "pushed out the +X face");
+        let flown = app.world().get::<Transform>(flyer).unwrap().translation;
+        assert_eq!(flown, Vec3::new(0.1, 8.0, 0.0));
+    }
+}
@@ -1,7 +1,13 @@
-//! World module housing environment setup and camera controls.
+//! World module housing environment setup, camera controls, NPC selection, prop
+//! collision, and scheduled world events.
+#[cfg(feature = "chaos")]
+pub mod chaos;
+pub mod collision;
 pub mod components;
 pub mod plugin;
+pub mod selection;
 pub mod systems;
 pub mod time;
+pub mod world_event;
 
 pub use plugin::WorldPlugin;
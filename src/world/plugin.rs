@@ -1,18 +1,43 @@
-//! WorldPlugin coordinates environment setup, camera controls, and time-of-day lighting.
+//! WorldPlugin coordinates environment setup, camera controls, NPC selection, prop
+//! collision, time-of-day lighting, and scheduled world events.
 use bevy::prelude::*;
 
-use crate::world::{
-    systems::{
-        fly_camera_mouse_look, fly_camera_translate, spawn_world_environment, update_cursor_grab,
+use crate::{
+    core::{config::report_config_result, focus::window_focused, schedule::FramePhase},
+    dialogue::chronicle::ChronicleAppExt,
+    world::{
+        collision::resolve_static_collisions,
+        selection::{
+            camera_is_free, draw_selection_ring, follow_selected_npc, handle_selection_keys,
+            select_npc_on_click, SelectedNpc,
+        },
+        systems::{
+            fly_camera_mouse_look, fly_camera_translate, spawn_world_environment,
+            update_cursor_grab,
+        },
+        time::{
+            advance_world_clock, announce_day_change, announce_season_change, apply_world_lighting,
+            distill_season_change, handle_debug_day_skip, reload_time_settings, DayChangedEvent,
+            SeasonChangedEvent, WorldClock, WorldTimeSettings, CONFIG_PATH as TIME_CONFIG_PATH,
+        },
+        world_event::{
+            advance_world_event, distill_market_transition, reload_world_events, MarketDayConfig,
+            WorldEvent, WorldEventTransition, CONFIG_PATH as WORLD_EVENTS_CONFIG_PATH,
+        },
     },
-    time::{advance_world_clock, apply_world_lighting, WorldClock, WorldTimeSettings},
 };
 
+/// The world clock, the village environment, world events, and NPC selection.
 pub struct WorldPlugin;
 
 impl Plugin for WorldPlugin {
     fn build(&self, app: &mut App) {
-        let time_settings = WorldTimeSettings::load_or_default();
+        let time_settings = report_config_result(
+            app.world_mut(),
+            TIME_CONFIG_PATH,
+            WorldTimeSettings::load(),
+            WorldTimeSettings::default,
+        );
         info!(
             "World time configured: day length {:.2} minutes (sunrise {:.2}, sunset {:.2})",
             time_settings.seconds_per_day / 60.0,
@@ -20,20 +45,70 @@ impl Plugin for WorldPlugin {
             time_settings.sunset_fraction
         );
 
+        let market_day = report_config_result(
+            app.world_mut(),
+            WORLD_EVENTS_CONFIG_PATH,
+            MarketDayConfig::load(),
+            MarketDayConfig::default,
+        );
+
         app.insert_resource(time_settings)
             .insert_resource(WorldClock::new())
+            .add_message::<DayChangedEvent>()
+            .add_message::<SeasonChangedEvent>()
+            .add_chronicle_distiller(distill_season_change)
+            .insert_resource(market_day)
+            .init_resource::<WorldEvent>()
+            .add_message::<WorldEventTransition>()
+            .add_chronicle_distiller(distill_market_transition)
+            .init_resource::<SelectedNpc>()
             .add_systems(Startup, spawn_world_environment)
             .add_systems(
                 Update,
                 (
-                    advance_world_clock,
+                    reload_time_settings,
+                    advance_world_clock.after(reload_time_settings),
+                    handle_debug_day_skip.after(advance_world_clock),
+                    announce_day_change.after(handle_debug_day_skip),
+                    announce_season_change.after(announce_day_change),
+                    reload_world_events,
+                    advance_world_event
+                        .after(handle_debug_day_skip)
+                        .after(reload_world_events),
+                )
+                    .in_set(FramePhase::SimTick),
+            )
+            .add_systems(
+                Update,
+                (
                     (
                         update_cursor_grab,
                         fly_camera_mouse_look.after(update_cursor_grab),
-                        fly_camera_translate,
+                        fly_camera_translate.run_if(camera_is_free),
+                    ),
+                    (
+                        select_npc_on_click,
+                        handle_selection_keys.after(select_npc_on_click),
+                        follow_selected_npc
+                            .after(handle_selection_keys)
+                            .after(fly_camera_mouse_look),
+                        draw_selection_ring
+                            .after(follow_selected_npc)
+                            .run_if(window_focused),
                     ),
-                    apply_world_lighting.after(advance_world_clock),
-                ),
+                    apply_world_lighting.run_if(window_focused),
+                    resolve_static_collisions
+                        .after(fly_camera_translate)
+                        .before(follow_selected_npc),
+                )
+                    .in_set(FramePhase::Presentation),
             );
+
+        #[cfg(feature = "chaos")]
+        crate::core::chaos::ChaosAppExt::register_chaos_fault(
+            app,
+            "jump_clock",
+            crate::world::chaos::jump_world_clock,
+        );
     }
 }
@@ -0,0 +1,363 @@
+//! Click selection of NPCs and the follow camera mode built on it.
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use crate::{
+    core::input::{ActionInput, InputAction},
+    npc::components::Identity,
+    world::components::FlyCamera,
+};
+
+/// How close (in world units) a cursor ray must pass to an NPC's centre to select it.
+const NPC_PICK_RADIUS: f32 = 0.8;
+/// Distance the follow camera keeps back along its view direction from the NPC.
+const FOLLOW_DISTANCE: f32 = 6.0;
+/// Extra lift above the NPC so the camera looks down over its shoulder.
+const FOLLOW_HEIGHT: f32 = 1.5;
+/// Exponential approach rate; higher values track the NPC more tightly.
+const FOLLOW_SMOOTHING_RATE: f32 = 4.0;
+const RING_RADIUS: f32 = 0.55;
+const RING_HEIGHT: f32 = 0.05;
+const RING_COLOR: Color = Color::srgba(1.0, 0.92, 0.55, 0.8);
+
+/// The NPC picked with the mouse, and whether the camera is following it.
+#[derive(Resource, Debug, Default)]
+pub struct SelectedNpc {
+    entity: Option<Entity>,
+    following: bool,
+}
+
+impl SelectedNpc {
+    pub fn entity(&self) -> Option<Entity> {
+        self.entity
+    }
+
+    pub fn is_following(&self) -> bool {
+        self.following
+    }
+
+    /// Selects `entity`, keeping follow mode on if it was already active.
+    pub fn select(&mut self, entity: Entity) {
+        self.entity = Some(entity);
+    }
+
+    /// Drops the selection and releases the camera back to free flight.
+    pub fn clear(&mut self) {
+        self.entity = None;
+        self.following = false;
+    }
+
+    /// Toggles follow mode; does nothing without a selection.
+    pub fn toggle_follow(&mut self) {
+        self.following = self.entity.is_some() && !self.following;
+    }
+}
+
+/// Distance along `ray` to the point closest to `point`, and how far `point` sits from the
+/// ray there. Returns `None` for points behind the ray origin.
+pub fn ray_point_offset(ray: Ray3d, point: Vec3) -> Option<(f32, f32)> {
+    let along = (point - ray.origin).dot(*ray.direction);
+    if along < 0.0 {
+        return None;
+    }
+    let closest = ray.get_point(along);
+    Some((along, closest.distance(point)))
+}
+
+/// Picks the candidate within `radius` of the ray that lies nearest the ray origin.
+pub fn pick_nearest_along_ray(
+    ray: Ray3d,
+    radius: f32,
+    candidates: impl IntoIterator<Item = (Entity, Vec3)>,
+) -> Option<Entity> {
+    candidates
+        .into_iter()
+        .filter_map(|(entity, position)| {
+            let (along, offset) = ray_point_offset(ray, position)?;
+            (offset <= radius).then_some((entity, along))
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(entity, _)| entity)
+}
+
+/// Where the follow camera should sit: backed off along its own view direction so the NPC
+/// stays centred whatever yaw/pitch mouse look has applied, then lifted by `height`.
+pub fn follow_camera_position(
+    target: Vec3,
+    camera_rotation: Quat,
+    distance: f32,
+    height: f32,
+) -> Vec3 {
+    target + camera_rotation * Vec3::Z * distance + Vec3::Y * height
+}
+
+/// Frame-rate independent lerp factor for an exponential approach at `rate` per second.
+pub fn follow_smoothing_factor(rate: f32, delta_secs: f32) -> f32 {
+    1.0 - (-rate * delta_secs.max(0.0)).exp()
+}
+
+/// Selects the NPC under the cursor on the select binding (left-click by default), or
+/// clears the selection when the click lands on empty ground. Clicks over UI buttons are
+/// ignored.
+pub fn select_npc_on_click(
+    input: ActionInput,
+    window: Option<Single<&Window, With<PrimaryWindow>>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<FlyCamera>>,
+    interactions: Query<&Interaction>,
+    npcs: Query<(Entity, &GlobalTransform), With<Identity>>,
+    mut selected: ResMut<SelectedNpc>,
+) {
+    if !input.just_pressed(InputAction::SelectNpc) {
+        return;
+    }
+    if interactions
+        .iter()
+        .any(|interaction| *interaction != Interaction::None)
+    {
+        return;
+    }
+    let Some(cursor) = window.and_then(|window| window.cursor_position()) else {
+        return;
+    };
+    let Ok((camera, camera_transform)) = cameras.single() else {
+        return;
+    };
+    let Ok(ray) = camera.viewport_to_world(camera_transform, cursor) else {
+        return;
+    };
+
+    let hit = pick_nearest_along_ray(
+        ray,
+        NPC_PICK_RADIUS,
+        npcs.iter()
+            .map(|(entity, transform)| (entity, transform.translation())),
+    );
+    match hit {
+        Some(entity) => selected.select(entity),
+        None => selected.clear(),
+    }
+}
+
+/// Handles the selection hotkeys and drops selections whose NPC no longer exists.
+pub fn handle_selection_keys(
+    input: ActionInput,
+    npcs: Query<(), With<Identity>>,
+    mut selected: ResMut<SelectedNpc>,
+) {
+    if let Some(entity) = selected.entity() {
+        if !npcs.contains(entity) {
+            selected.clear();
+        }
+    }
+    if input.just_pressed(InputAction::Deselect) {
+        selected.clear();
+    } else if input.just_pressed(InputAction::FollowSelected) {
+        selected.toggle_follow();
+        if selected.is_following() {
+            info!("Camera following selected NPC");
+        }
+    }
+}
+
+/// Eases the fly camera toward its follow position behind the selected NPC. Rotation stays
+/// under mouse-look control, so leaving follow mode keeps the camera exactly where it is.
+pub fn follow_selected_npc(
+    time: Res<Time>,
+    selected: Res<SelectedNpc>,
+    npcs: Query<&Transform, (With<Identity>, Without<FlyCamera>)>,
+    mut cameras: Query<&mut Transform, With<FlyCamera>>,
+) {
+    if !selected.is_following() {
+        return;
+    }
+    let Some(target) = selected
+        .entity()
+        .and_then(|entity| npcs.get(entity).ok())
+        .map(|transform| transform.translation)
+    else {
+        return;
+    };
+    let Ok(mut camera) = cameras.single_mut() else {
+        return;
+    };
+
+    let goal = follow_camera_position(target, camera.rotation, FOLLOW_DISTANCE, FOLLOW_HEIGHT);
+    let factor = follow_smoothing_factor(FOLLOW_SMOOTHING_RATE, time.delta_secs());
+    camera.translation = camera.translation.lerp(goal, factor);
+}
+
+/// Run condition keeping WASD flight off while the camera follows an NPC.
+pub fn camera_is_free(selected: Res<SelectedNpc>) -> bool {
+    !selected.is_following()
+}
+
+/// Draws a flat ring on the ground beneath the selected NPC.
+pub fn draw_selection_ring(
+    mut gizmos: Gizmos,
+    selected: Res<SelectedNpc>,
+    npcs: Query<&GlobalTransform, With<Identity>>,
+) {
+    let Some(transform) = selected.entity().and_then(|entity| npcs.get(entity).ok()) else {
+        return;
+    };
+    let position = transform.translation();
+    gizmos.circle(
+        Isometry3d::new(
+            Vec3::new(position.x, RING_HEIGHT, position.z),
+            Quat::from_rotation_x(FRAC_PI_2),
+        ),
+        RING_RADIUS,
+        RING_COLOR,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+    use crate::{core::input::InputBindings, npc::components::NpcId};
+
+    fn ray_down_z() -> Ray3d {
+        Ray3d::new(Vec3::ZERO, Dir3::NEG_Z)
+    }
+
+    #[test]
+    fn ray_offset_ignores_points_behind_the_camera() {
+        let (along, offset) =
+            ray_point_offset(ray_down_z(), Vec3::new(0.3, 0.4, -5.0)).expect("point is in front");
+        assert!((along - 5.0).abs() < 1e-5);
+        assert!((offset - 0.5).abs() < 1e-5);
+        assert!(ray_point_offset(ray_down_z(), Vec3::new(0.0, 0.0, 2.0)).is_none());
+    }
+
+    #[test]
+    fn pick_prefers_the_closest_npc_within_radius() {
+        let near = Entity::from_raw_u32(1).unwrap();
+        let far = Entity::from_raw_u32(2).unwrap();
+        let wide = Entity::from_raw_u32(3).unwrap();
+        let candidates = [
+            (far, Vec3::new(0.0, 0.0, -12.0)),
+            (near, Vec3::new(0.2, 0.0, -4.0)),
+            (wide, Vec3::new(2.0, 0.0, -2.0)),
+        ];
+
+        assert_eq!(
+            pick_nearest_along_ray(ray_down_z(), 0.8, candidates),
+            Some(near)
+        );
+        assert_eq!(
+            pick_nearest_along_ray(ray_down_z(), 0.8, [(wide, Vec3::new(2.0, 0.0, -2.0))]),
+            None
+        );
+    }
+
+    #[test]
+    fn follow_position_sits_behind_and_above_along_view() {
+        let target = Vec3::new(4.0, 1.0, -3.0);
+        let level = follow_camera_position(target, Quat::IDENTITY, 6.0, 1.5);
+        assert!(level.abs_diff_eq(Vec3::new(4.0, 2.5, 3.0), 1e-5));
+
+        // Turning the camera orbits it around the target instead of losing sight of it.
+        let turned = follow_camera_position(target, Quat::from_rotation_y(FRAC_PI_2), 6.0, 0.0);
+        assert!(turned.abs_diff_eq(Vec3::new(10.0, 1.0, -3.0), 1e-5));
+    }
+
+    #[test]
+    fn smoothing_factor_is_frame_rate_independent() {
+        let one_step = follow_smoothing_factor(4.0, 0.1);
+        let half = follow_smoothing_factor(4.0, 0.05);
+        let two_steps = 1.0 - (1.0 - half) * (1.0 - half);
+        assert!((one_step - two_steps).abs() < 1e-5);
+        assert_eq!(follow_smoothing_factor(4.0, 0.0), 0.0);
+        assert!(follow_smoothing_factor(4.0, 100.0) <= 1.0);
+    }
+
+    #[test]
+    fn selection_follow_and_release_transitions() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+                100,
+            )))
+            .init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<InputBindings>()
+            .init_resource::<SelectedNpc>()
+            .add_systems(
+                Update,
+                (
+                    handle_selection_keys,
+                    follow_selected_npc.after(handle_selection_keys),
+                ),
+            );
+        let npc = app
+            .world_mut()
+            .spawn((
+                Identity::new(NpcId::new(1), "Alric", 30.0),
+                Transform::from_xyz(0.0, 1.0, 0.0),
+            ))
+            .id();
+        let camera = app
+            .world_mut()
+            .spawn((
+                FlyCamera::new(0.0, 0.0),
+                Transform::from_xyz(-20.0, 8.0, 20.0),
+            ))
+            .id();
+        // No InputPlugin here, so clear the press after the frame that sees it.
+        let press = |app: &mut App, key: KeyCode| {
+            app.world_mut()
+                .resource_mut::<ButtonInput<KeyCode>>()
+                .press(key);
+            app.update();
+            let mut keys = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+            keys.release_all();
+            keys.clear();
+        };
+        let camera_position = |app: &App| app.world().get::<Transform>(camera).unwrap().translation;
+
+        // Follow needs a selection first.
+        press(&mut app, KeyCode::KeyF);
+        assert!(!app.world().resource::<SelectedNpc>().is_following());
+
+        app.world_mut().resource_mut::<SelectedNpc>().select(npc);
+        press(&mut app, KeyCode::KeyF);
+        assert!(app.world().resource::<SelectedNpc>().is_following());
+        let start = camera_position(&app);
+        for _ in 0..5 {
+            app.update();
+        }
+        let goal = follow_camera_position(
+            Vec3::new(0.0, 1.0, 0.0),
+            Quat::IDENTITY,
+            FOLLOW_DISTANCE,
+            FOLLOW_HEIGHT,
+        );
+        assert!(
+            camera_position(&app).distance(goal) < start.distance(goal),
+            "camera eases toward the follow position"
+        );
+
+        // Toggling off releases the camera where it is.
+        press(&mut app, KeyCode::KeyF);
+        let released = camera_position(&app);
+        app.update();
+        assert_eq!(camera_position(&app), released);
+        assert_eq!(app.world().resource::<SelectedNpc>().entity(), Some(npc));
+
+        press(&mut app, KeyCode::KeyF);
+        press(&mut app, KeyCode::Escape);
+        let selected = app.world().resource::<SelectedNpc>();
+        assert_eq!(selected.entity(), None);
+        assert!(!selected.is_following());
+
+        // A despawned NPC drops out of the selection.
+        app.world_mut().resource_mut::<SelectedNpc>().select(npc);
+        app.world_mut().despawn(npc);
+        app.update();
+        assert_eq!(app.world().resource::<SelectedNpc>().entity(), None);
+    }
+}
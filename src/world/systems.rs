@@ -1,19 +1,25 @@
 //! Systems for the world module.
 use bevy::{
     ecs::message::MessageReader,
-    input::{mouse::MouseMotion, ButtonInput},
+    input::mouse::MouseMotion,
     math::primitives::Plane3d,
     prelude::*,
     window::{CursorGrabMode, CursorOptions},
 };
 
 use crate::{
+    core::input::{ActionInput, InputAction},
     player::components::Player,
-    world::components::{FlyCamera, PrimarySun},
+    world::{
+        collision::MoverCollider,
+        components::{FlyCamera, PrimarySun},
+    },
 };
 
 const GROUND_SCALE: f32 = 100.0;
 const CAMERA_START_POS: Vec3 = Vec3::new(-12.0, 8.0, 16.0);
+/// The camera bumps into props like a person would when flown down to walking height.
+const PLAYER_COLLIDER: MoverCollider = MoverCollider::new(0.4, 0.9);
 
 /// Spawns the initial scene: ground plane, light, and a fly camera.
 pub fn spawn_world_environment(
@@ -51,27 +57,25 @@ pub fn spawn_world_environment(
         camera_transform,
         FlyCamera::new(yaw, pitch),
         Player, // Player marker for interaction system
+        PLAYER_COLLIDER,
     ));
 }
 
 /// Toggles cursor grab when engaging the fly camera look mode.
-pub fn update_cursor_grab(
-    mouse_buttons: Res<ButtonInput<MouseButton>>,
-    mut cursor_options: Single<&mut CursorOptions>,
-) {
-    if mouse_buttons.just_pressed(MouseButton::Right) {
+pub fn update_cursor_grab(input: ActionInput, mut cursor_options: Single<&mut CursorOptions>) {
+    if input.just_pressed(InputAction::CameraLook) {
         cursor_options.visible = false;
         cursor_options.grab_mode = CursorGrabMode::Locked;
-    } else if mouse_buttons.just_released(MouseButton::Right) {
+    } else if input.just_released(InputAction::CameraLook) {
         cursor_options.visible = true;
         cursor_options.grab_mode = CursorGrabMode::None;
     }
 }
 
-/// Applies mouse look to the fly camera when the right mouse button is held.
+/// Applies mouse look to the fly camera while the look binding (right mouse) is held.
 pub fn fly_camera_mouse_look(
     mut motion_events: MessageReader<MouseMotion>,
-    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    input: ActionInput,
     time: Res<Time>,
     mut query: Query<(&mut FlyCamera, &mut Transform)>,
 ) {
@@ -80,7 +84,7 @@ pub fn fly_camera_mouse_look(
         cumulative_delta += ev.delta;
     }
 
-    if !mouse_buttons.pressed(MouseButton::Right) {
+    if !input.pressed(InputAction::CameraLook) {
         return;
     }
 
@@ -99,9 +103,9 @@ pub fn fly_camera_mouse_look(
     }
 }
 
-/// Moves the fly camera using WASD + Space/LShift.
+/// Moves the fly camera with the movement bindings (WASD + Space/LShift by default).
 pub fn fly_camera_translate(
-    keyboard: Res<ButtonInput<KeyCode>>,
+    input: ActionInput,
     time: Res<Time>,
     mut query: Query<(&FlyCamera, &mut Transform)>,
 ) {
@@ -115,27 +119,27 @@ pub fn fly_camera_translate(
             let r = transform.right().as_vec3();
             Vec3::new(r.x, 0.0, r.z).normalize_or_zero()
         };
-        if keyboard.pressed(KeyCode::KeyW) {
+        if input.pressed(InputAction::MoveForward) {
             direction += forward;
         }
-        if keyboard.pressed(KeyCode::KeyS) {
+        if input.pressed(InputAction::MoveBackward) {
             direction += -forward;
         }
-        if keyboard.pressed(KeyCode::KeyA) {
+        if input.pressed(InputAction::MoveLeft) {
             direction += -right;
         }
-        if keyboard.pressed(KeyCode::KeyD) {
+        if input.pressed(InputAction::MoveRight) {
             direction += right;
         }
-        if keyboard.pressed(KeyCode::Space) {
+        if input.pressed(InputAction::MoveUp) {
             direction += Vec3::Y;
         }
-        if keyboard.pressed(KeyCode::ShiftLeft) {
+        if input.pressed(InputAction::MoveDown) {
             direction += -Vec3::Y;
         }
 
         if direction.length_squared() > 0.0 {
-            let modifier = if keyboard.pressed(KeyCode::ControlLeft) {
+            let modifier = if input.pressed(InputAction::MoveFast) {
                 2.5
             } else {
                 1.0
@@ -2,22 +2,34 @@
 use std::{f32::consts::TAU, fs, path::Path};
 
 use bevy::prelude::*;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-use crate::core::plugin::SimulationClock;
+use crate::core::{
+    config::{ConfigDiagnostics, ConfigReloadRequested},
+    input::{ActionInput, InputAction},
+    plugin::SimulationClock,
+};
+use crate::dialogue::chronicle::{ChronicleDraft, ChronicleRoster};
 use crate::world::components::PrimarySun;
 
-const CONFIG_PATH: &str = "config/time.toml";
+/// Where `WorldTimeSettings::load` reads the time config.
+pub const CONFIG_PATH: &str = "config/time.toml";
+/// Days in the calendar week; weekdays are `day_count % 7`, with day 0 as weekday 0.
+pub const DAYS_PER_WEEK: u64 = 7;
+const MINUTES_PER_DAY: u32 = 24 * 60;
+const SEASON_TURN_SIGNIFICANCE: f32 = 0.8;
 
-#[derive(Debug, Clone, Deserialize, Default)]
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
 struct RawTimeConfig {
     #[serde(default)]
     clock: RawClockSection,
     #[serde(default)]
     lighting: RawLightingSection,
+    #[serde(default)]
+    calendar: RawCalendarSection,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(default)]
 struct RawClockSection {
     day_length_minutes: f32,
@@ -37,7 +49,7 @@ impl Default for RawClockSection {
     }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(default)]
 struct RawLightingSection {
     noon_lux: f32,
@@ -57,41 +69,117 @@ impl Default for RawLightingSection {
     }
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+struct RawCalendarSection {
+    days_per_year: f32,
+    seasons: Vec<String>,
+}
+
+impl Default for RawCalendarSection {
+    fn default() -> Self {
+        Self {
+            days_per_year: 24.0,
+            seasons: ["spring", "summer", "autumn", "winter"]
+                .map(String::from)
+                .to_vec(),
+        }
+    }
+}
+
 /// Tunable parameters describing how the world clock behaves.
 #[derive(Resource, Debug, Clone)]
 pub struct WorldTimeSettings {
+    /// Real seconds in an in-game day at time scale 1.
     pub seconds_per_day: f32,
+    /// Day fraction (0-1) the sun rises at.
     pub sunrise_fraction: f32,
+    /// Day fraction (0-1) the sun sets at.
     pub sunset_fraction: f32,
+    /// Tilt of the sun's path, in radians.
     pub sun_declination: f32,
+    /// Sunlight intensity at noon.
     pub noon_lux: f32,
+    /// Sunlight intensity at night.
     pub night_lux: f32,
+    /// Ambient light colour by day.
     pub ambient_day: Vec3,
+    /// Ambient light colour by night.
     pub ambient_night: Vec3,
+    /// In-game days that make up one year of NPC aging.
+    pub days_per_year: f32,
+    /// Season names in calendar order, lowercase; each spans an equal share of the year.
+    /// Empty when the calendar has no seasons.
+    pub seasons: Vec<String>,
 }
 
 impl WorldTimeSettings {
+    /// Reads and parses `config/time.toml`.
+    ///
+    /// ```
+    /// use thegame::prelude::WorldTimeSettings;
+    ///
+    /// let settings = WorldTimeSettings::load()?;
+    /// assert!(settings.sunrise_fraction < settings.sunset_fraction);
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn load() -> Result<Self, String> {
+        let data = fs::read_to_string(Path::new(CONFIG_PATH))
+            .map_err(|err| format!("unable to read file: {err}"))?;
+        Self::from_toml_str(&data)
+    }
+
+    /// Parses time TOML; missing fields take their defaults.
+    pub fn from_toml_str(data: &str) -> Result<Self, String> {
+        let raw = toml::from_str::<RawTimeConfig>(data)
+            .map_err(|err| format!("invalid time config: {err}"))?;
+        Ok(raw.into())
+    }
+
+    /// `load`, or the defaults with a warning when the file is missing or invalid.
     pub fn load_or_default() -> Self {
-        let path = Path::new(CONFIG_PATH);
-        match fs::read_to_string(path) {
-            Ok(data) => match toml::from_str::<RawTimeConfig>(&data) {
-                Ok(raw) => raw.into(),
-                Err(err) => {
-                    warn!(
-                        "Failed to parse {} ({}). Falling back to defaults.",
-                        CONFIG_PATH, err
-                    );
-                    RawTimeConfig::default().into()
-                }
-            },
-            Err(err) => {
-                warn!(
-                    "Failed to read {} ({}). Falling back to defaults.",
-                    CONFIG_PATH, err
-                );
-                RawTimeConfig::default().into()
-            }
+        Self::load().unwrap_or_else(|err| {
+            warn!(
+                "Failed to load {} ({}). Falling back to defaults.",
+                CONFIG_PATH, err
+            );
+            Self::default()
+        })
+    }
+
+    /// Seasons elapsed since day 0, counting across years, so the same season in the next
+    /// year has a different number. `None` when the calendar has no seasons.
+    pub fn season_number(&self, day: u64) -> Option<u64> {
+        if self.seasons.is_empty() {
+            return None;
         }
+        let seasons = self.seasons.len() as f64;
+        Some((day as f64 * seasons / f64::from(self.days_per_year)).floor() as u64)
+    }
+
+    /// Name of the season `day` falls in.
+    pub fn season_of(&self, day: u64) -> Option<&str> {
+        let number = self.season_number(day)?;
+        Some(&self.seasons[(number % self.seasons.len() as u64) as usize])
+    }
+}
+
+/// Parses `data` like `WorldTimeSettings::load` and returns it with every default written
+/// out, for village presets.
+pub fn explicit_toml(data: &str) -> Result<toml::Table, String> {
+    let raw = toml::from_str::<RawTimeConfig>(data)
+        .map_err(|err| format!("invalid time config: {err}"))?;
+    toml::Table::try_from(raw).map_err(|err| format!("unable to write time config: {err}"))
+}
+
+/// The defaults `WorldTimeSettings` falls back to, written out in full.
+pub fn fallback_toml() -> toml::Table {
+    toml::Table::try_from(RawTimeConfig::default()).expect("default time config serializes")
+}
+
+impl Default for WorldTimeSettings {
+    fn default() -> Self {
+        RawTimeConfig::default().into()
     }
 }
 
@@ -126,8 +214,48 @@ impl From<RawTimeConfig> for WorldTimeSettings {
                 lighting.ambient_night[1],
                 lighting.ambient_night[2],
             ),
+            days_per_year: value.calendar.days_per_year.max(1.0),
+            seasons: normalized_seasons(value.calendar.seasons),
+        }
+    }
+}
+
+/// Trims and lowercases season names, dropping blanks and repeats.
+fn normalized_seasons(raw: Vec<String>) -> Vec<String> {
+    let mut seasons: Vec<String> = Vec::with_capacity(raw.len());
+    for name in raw {
+        let name = name.trim().to_lowercase();
+        if !name.is_empty() && !seasons.contains(&name) {
+            seasons.push(name);
         }
     }
+    seasons
+}
+
+/// Written once whenever the calendar day changes. A jump over several days (a debug skip,
+/// a slow frame) is a single event spanning the range, not one event per skipped day, so
+/// per-day systems catch up in one pass. The first frame announces the starting day with
+/// no `previous_day`, which is how day 0 gets planned like any other.
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DayChangedEvent {
+    pub previous_day: Option<u64>,
+    pub new_day: u64,
+}
+
+impl DayChangedEvent {
+    /// Whole days that passed; 0 for the startup announcement.
+    pub fn days_elapsed(&self) -> u64 {
+        self.previous_day
+            .map_or(0, |previous| self.new_day.saturating_sub(previous))
+    }
+}
+
+/// Written when a day change crosses into another season. A jump over several seasons is
+/// one event naming the season the calendar landed in.
+#[derive(Message, Debug, Clone, PartialEq, Eq)]
+pub struct SeasonChangedEvent {
+    pub season: String,
+    pub day: u64,
 }
 
 /// Runtime state for the world clock.
@@ -135,6 +263,8 @@ impl From<RawTimeConfig> for WorldTimeSettings {
 pub struct WorldClock {
     time_of_day: f32,
     day_count: u64,
+    /// Last day handed out by `take_day_change`.
+    announced_day: Option<u64>,
 }
 
 impl WorldClock {
@@ -142,6 +272,7 @@ impl WorldClock {
         Self {
             time_of_day: 0.0,
             day_count: 0,
+            announced_day: None,
         }
     }
 
@@ -153,6 +284,33 @@ impl WorldClock {
         self.day_count
     }
 
+    /// Moves the clock to `fraction` of the current day, wrapping out-of-range input.
+    #[cfg_attr(not(any(test, feature = "chaos")), allow(dead_code))]
+    pub fn set_time_of_day(&mut self, fraction: f32) {
+        self.time_of_day = if fraction.is_finite() {
+            fraction.rem_euclid(1.0)
+        } else {
+            0.0
+        };
+    }
+
+    /// Jumps the calendar forward whole days, keeping the time of day.
+    pub fn skip_days(&mut self, days: u64) {
+        self.day_count = self.day_count.saturating_add(days);
+    }
+
+    /// The day change since the previous call, if any. The first call reports the
+    /// starting day with no `previous_day`.
+    pub fn take_day_change(&mut self) -> Option<DayChangedEvent> {
+        if self.announced_day == Some(self.day_count) {
+            return None;
+        }
+        Some(DayChangedEvent {
+            previous_day: self.announced_day.replace(self.day_count),
+            new_day: self.day_count,
+        })
+    }
+
     fn tick(&mut self, delta_seconds: f32, settings: &WorldTimeSettings) {
         let mut fraction = delta_seconds / settings.seconds_per_day;
         if fraction.is_nan() || !fraction.is_finite() {
@@ -165,6 +323,41 @@ impl WorldClock {
     }
 }
 
+/// Weekday (0..7) of a day count.
+pub fn weekday(day: u64) -> u64 {
+    day % DAYS_PER_WEEK
+}
+
+/// Whole in-game minute (0..1440) for a day fraction; out-of-range input wraps.
+pub fn minute_of_day(fraction: f32) -> u32 {
+    let wrapped = if fraction.is_finite() {
+        fraction.rem_euclid(1.0)
+    } else {
+        0.0
+    };
+    ((wrapped * MINUTES_PER_DAY as f32) as u32).min(MINUTES_PER_DAY - 1)
+}
+
+/// Formats a day fraction as 24-hour `HH:MM` clock time.
+pub fn format_clock_time(fraction: f32) -> String {
+    let minute = minute_of_day(fraction);
+    format!("{:02}:{:02}", minute / 60, minute % 60)
+}
+
+/// Re-reads `config/time.toml` on request, swapping the settings in when it parses.
+pub fn reload_time_settings(
+    mut requests: MessageReader<ConfigReloadRequested>,
+    mut diagnostics: ResMut<ConfigDiagnostics>,
+    mut settings: ResMut<WorldTimeSettings>,
+) {
+    if !requests.read().any(|request| request.is_for(CONFIG_PATH)) {
+        return;
+    }
+    if let Some(reloaded) = diagnostics.report_reload(CONFIG_PATH, WorldTimeSettings::load()) {
+        *settings = reloaded;
+    }
+}
+
 /// Advances the world clock based on the SimulationClock delta.
 pub fn advance_world_clock(
     mut clock: ResMut<WorldClock>,
@@ -175,6 +368,84 @@ pub fn advance_world_clock(
     clock.tick(delta, &settings);
 }
 
+/// Writes a `DayChangedEvent` when the clock's day differs from the last one announced,
+/// including the very first frame. Runs after everything that moves the clock in
+/// `SimTick`, so per-day systems read the day from the event rather than each keeping a
+/// "did the day change" latch of their own.
+pub fn announce_day_change(
+    mut clock: ResMut<WorldClock>,
+    mut day_changes: MessageWriter<DayChangedEvent>,
+) {
+    if let Some(change) = clock.take_day_change() {
+        day_changes.write(change);
+    }
+}
+
+/// Writes a `SeasonChangedEvent` for each day change that crosses a season boundary. The
+/// startup announcement is skipped, since the starting season is not news.
+pub fn announce_season_change(
+    settings: Res<WorldTimeSettings>,
+    mut day_changes: MessageReader<DayChangedEvent>,
+    mut season_changes: MessageWriter<SeasonChangedEvent>,
+) {
+    for change in day_changes.read() {
+        let Some(previous) = change.previous_day else {
+            continue;
+        };
+        if settings.season_number(previous) == settings.season_number(change.new_day) {
+            continue;
+        }
+        if let Some(season) = settings.season_of(change.new_day) {
+            season_changes.write(SeasonChangedEvent {
+                season: season.to_string(),
+                day: change.new_day,
+            });
+        }
+    }
+}
+
+/// Chronicle line for the turn of a season.
+pub fn distill_season_change(
+    change: &SeasonChangedEvent,
+    _roster: &ChronicleRoster,
+) -> Option<ChronicleDraft> {
+    let mut chars = change.season.chars();
+    let first = chars.next()?;
+    Some(ChronicleDraft::new(
+        format!(
+            "{}{} came to the village",
+            first.to_uppercase(),
+            chars.as_str()
+        ),
+        Vec::new(),
+        SEASON_TURN_SIGNIFICANCE,
+    ))
+}
+
+/// Debug time skip: the skip binding (F9) jumps one day ahead, or a whole calendar year
+/// while the year modifier (Left Shift) is held.
+pub fn handle_debug_day_skip(
+    input: ActionInput,
+    settings: Res<WorldTimeSettings>,
+    mut clock: ResMut<WorldClock>,
+) {
+    if !input.just_pressed(InputAction::SkipDay) {
+        return;
+    }
+
+    let days = if input.pressed(InputAction::SkipYearModifier) {
+        settings.days_per_year.round() as u64
+    } else {
+        1
+    };
+    clock.skip_days(days);
+    info!(
+        "Debug time skip: advanced {} day(s) to day {}",
+        days,
+        clock.day_count()
+    );
+}
+
 /// Applies time-of-day lighting to the primary sun and ambient light.
 pub fn apply_world_lighting(
     clock: Res<WorldClock>,
@@ -216,3 +487,197 @@ pub fn apply_world_lighting(
         light.illuminance = intensity;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seasons_split_the_year_evenly_and_repeat() {
+        let settings = WorldTimeSettings::from_toml_str(
+            r#"
+            [calendar]
+            days_per_year = 8.0
+            seasons = [" Wet ", "dry", "", "WET"]
+            "#,
+        )
+        .expect("valid time toml");
+        assert_eq!(settings.seasons, ["wet", "dry"]);
+        let seasons: Vec<Option<&str>> = (0..10).map(|day| settings.season_of(day)).collect();
+        assert_eq!(
+            seasons,
+            [
+                Some("wet"),
+                Some("wet"),
+                Some("wet"),
+                Some("wet"),
+                Some("dry"),
+                Some("dry"),
+                Some("dry"),
+                Some("dry"),
+                Some("wet"),
+                Some("wet"),
+            ]
+        );
+        assert_eq!(settings.season_number(9), Some(2), "next year's wet season");
+
+        let default = WorldTimeSettings::default();
+        assert_eq!(default.season_of(0), Some("spring"));
+        assert_eq!(default.season_of(18), Some("winter"));
+
+        let seasonless = WorldTimeSettings::from_toml_str("[calendar]\nseasons = []\n")
+            .expect("valid time toml");
+        assert_eq!(seasonless.season_of(3), None);
+    }
+
+    #[test]
+    fn clock_time_formats_key_points() {
+        assert_eq!(format_clock_time(0.0), "00:00");
+        assert_eq!(format_clock_time(0.5), "12:00");
+        assert_eq!(format_clock_time(0.25), "06:00");
+        assert_eq!(
+            format_clock_time(13.0 / 24.0 + 20.0 / 1440.0 + 0.0001),
+            "13:20"
+        );
+    }
+
+    #[test]
+    fn clock_time_never_reaches_twenty_four() {
+        assert_eq!(format_clock_time(0.99999), "23:59");
+        assert_eq!(
+            format_clock_time(f32::from_bits(1.0f32.to_bits() - 1)),
+            "23:59"
+        );
+        assert_eq!(format_clock_time(1.0), "00:00");
+        assert_eq!(format_clock_time(-0.25), "18:00");
+        assert_eq!(format_clock_time(f32::NAN), "00:00");
+    }
+
+    fn day_change_app() -> App {
+        let mut app = App::new();
+        app.insert_resource(WorldClock::new())
+            .add_message::<DayChangedEvent>()
+            .add_systems(Update, announce_day_change);
+        app
+    }
+
+    fn season_change_app(settings: WorldTimeSettings) -> App {
+        let mut app = day_change_app();
+        app.insert_resource(settings)
+            .add_message::<SeasonChangedEvent>()
+            .add_systems(Update, announce_season_change.after(announce_day_change));
+        app
+    }
+
+    fn drain_season_changes(app: &mut App) -> Vec<SeasonChangedEvent> {
+        app.world_mut()
+            .resource_mut::<Messages<SeasonChangedEvent>>()
+            .drain()
+            .collect()
+    }
+
+    fn drain_day_changes(app: &mut App) -> Vec<DayChangedEvent> {
+        app.world_mut()
+            .resource_mut::<Messages<DayChangedEvent>>()
+            .drain()
+            .collect()
+    }
+
+    #[test]
+    fn the_first_frame_announces_the_starting_day() {
+        let mut app = day_change_app();
+        app.update();
+        assert_eq!(
+            drain_day_changes(&mut app),
+            vec![DayChangedEvent {
+                previous_day: None,
+                new_day: 0
+            }]
+        );
+        app.update();
+        assert!(drain_day_changes(&mut app).is_empty(), "no repeat");
+    }
+
+    #[test]
+    fn each_rollover_is_announced_exactly_once() {
+        let mut app = day_change_app();
+        let settings = WorldTimeSettings::default();
+        app.update();
+        drain_day_changes(&mut app);
+
+        let step = settings.seconds_per_day / 4.0;
+        let mut announced = Vec::new();
+        for _ in 0..9 {
+            app.world_mut()
+                .resource_mut::<WorldClock>()
+                .tick(step, &settings);
+            app.update();
+            announced.extend(drain_day_changes(&mut app));
+        }
+        let days: Vec<(Option<u64>, u64)> = announced
+            .iter()
+            .map(|change| (change.previous_day, change.new_day))
+            .collect();
+        assert_eq!(days, vec![(Some(0), 1), (Some(1), 2)]);
+        assert!(announced.iter().all(|change| change.days_elapsed() == 1));
+    }
+
+    #[test]
+    fn a_multi_day_jump_is_one_event_spanning_the_range() {
+        let mut app = day_change_app();
+        app.update();
+        drain_day_changes(&mut app);
+
+        app.world_mut().resource_mut::<WorldClock>().skip_days(5);
+        app.update();
+        let announced = drain_day_changes(&mut app);
+        assert_eq!(
+            announced,
+            vec![DayChangedEvent {
+                previous_day: Some(0),
+                new_day: 5
+            }]
+        );
+        assert_eq!(announced[0].days_elapsed(), 5);
+
+        app.update();
+        assert!(drain_day_changes(&mut app).is_empty(), "no repeat");
+    }
+
+    #[test]
+    fn only_crossing_a_season_boundary_is_announced() {
+        let settings = WorldTimeSettings::default();
+        let mut app = season_change_app(settings.clone());
+        app.update();
+        assert!(drain_season_changes(&mut app).is_empty(), "starting season");
+
+        app.world_mut().resource_mut::<WorldClock>().skip_days(5);
+        app.update();
+        assert!(drain_season_changes(&mut app).is_empty(), "still spring");
+
+        app.world_mut().resource_mut::<WorldClock>().skip_days(1);
+        app.update();
+        let announced = drain_season_changes(&mut app);
+        assert_eq!(
+            announced,
+            vec![SeasonChangedEvent {
+                season: "summer".to_string(),
+                day: 6
+            }]
+        );
+        let draft = distill_season_change(&announced[0], &ChronicleRoster::default())
+            .expect("a season turn is chronicled");
+        assert_eq!(draft.text, "Summer came to the village");
+
+        app.world_mut().resource_mut::<WorldClock>().skip_days(12);
+        app.update();
+        assert_eq!(
+            drain_season_changes(&mut app),
+            vec![SeasonChangedEvent {
+                season: "winter".to_string(),
+                day: 18
+            }],
+            "a multi-season jump names where it landed"
+        );
+    }
+}
@@ -0,0 +1,378 @@
+//! Scheduled village events. The weekly market day is the only one so far: on its weekday it
+//! is announced in the morning, runs for a window of the day, then ends. `WorldEvent` tracks
+//! the phase and `advance_world_event` emits a `WorldEventTransition` for every step, which
+//! NPC attendance, the economy planner, and dialogue context react to.
+use std::{fs, path::Path};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::core::config::{ConfigDiagnostics, ConfigReloadRequested};
+use crate::dialogue::chronicle::{ChronicleDraft, ChronicleRoster};
+use crate::world::time::{weekday, WorldClock, DAYS_PER_WEEK};
+
+pub const CONFIG_PATH: &str = "config/world_events.toml";
+const MARKET_HELD_SIGNIFICANCE: f32 = 0.6;
+
+#[derive(Debug, Clone, Deserialize, Default)]
+struct RawWorldEventsConfig {
+    #[serde(default)]
+    market_day: RawMarketDay,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+struct RawMarketDay {
+    enabled: bool,
+    weekday: u64,
+    announce_fraction: f32,
+    start_fraction: f32,
+    end_fraction: f32,
+    gathering_point: Option<[f32; 3]>,
+    wander_radius: f32,
+    wander_pause_seconds: f32,
+}
+
+impl Default for RawMarketDay {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            weekday: 3,
+            announce_fraction: 0.25,
+            start_fraction: 0.375,
+            end_fraction: 0.625,
+            gathering_point: None,
+            wander_radius: 3.0,
+            wander_pause_seconds: 4.0,
+        }
+    }
+}
+
+/// When and where the weekly market day is held.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct MarketDayConfig {
+    pub enabled: bool,
+    /// Weekday (0..7) the market is held on.
+    pub weekday: u64,
+    /// Day fractions at which the market is announced, opens, and closes; ascending.
+    pub announce_fraction: f32,
+    pub start_fraction: f32,
+    pub end_fraction: f32,
+    /// Gathering point; `None` uses the marketplace stall.
+    pub gathering_point: Option<Vec3>,
+    pub wander_radius: f32,
+    pub wander_pause_seconds: f32,
+}
+
+impl MarketDayConfig {
+    /// Reads and parses `config/world_events.toml`.
+    pub fn load() -> Result<Self, String> {
+        let data = fs::read_to_string(Path::new(CONFIG_PATH))
+            .map_err(|err| format!("unable to read file: {err}"))?;
+        let raw = toml::from_str::<RawWorldEventsConfig>(&data)
+            .map_err(|err| format!("invalid world events config: {err}"))?;
+        Self::try_from(raw.market_day)
+    }
+
+    /// Whether `day` is a market day.
+    pub fn is_market_day(&self, day: u64) -> bool {
+        self.enabled && weekday(day) == self.weekday
+    }
+
+    /// Phase the market should be in on `day` at `time_of_day`.
+    pub fn scheduled_phase(&self, day: u64, time_of_day: f32) -> WorldEventPhase {
+        if !self.is_market_day(day) || time_of_day < self.announce_fraction {
+            WorldEventPhase::Idle
+        } else if time_of_day < self.start_fraction {
+            WorldEventPhase::Announced
+        } else if time_of_day < self.end_fraction {
+            WorldEventPhase::Active
+        } else {
+            WorldEventPhase::Ended
+        }
+    }
+}
+
+impl Default for MarketDayConfig {
+    fn default() -> Self {
+        Self::try_from(RawMarketDay::default()).expect("default market day config is valid")
+    }
+}
+
+impl TryFrom<RawMarketDay> for MarketDayConfig {
+    type Error = String;
+
+    fn try_from(value: RawMarketDay) -> Result<Self, Self::Error> {
+        if value.weekday >= DAYS_PER_WEEK {
+            return Err(format!(
+                "invalid world events config: market_day.weekday must be below {DAYS_PER_WEEK}, got {}",
+                value.weekday
+            ));
+        }
+        let fractions = [
+            value.announce_fraction,
+            value.start_fraction,
+            value.end_fraction,
+        ];
+        if fractions
+            .iter()
+            .any(|fraction| !(0.0..=1.0).contains(fraction))
+            || !fractions.is_sorted()
+        {
+            return Err(
+                "invalid world events config: market_day fractions must be ascending within 0-1"
+                    .to_string(),
+            );
+        }
+        Ok(Self {
+            enabled: value.enabled,
+            weekday: value.weekday,
+            announce_fraction: value.announce_fraction,
+            start_fraction: value.start_fraction,
+            end_fraction: value.end_fraction,
+            gathering_point: value.gathering_point.map(Vec3::from_array),
+            wander_radius: value.wander_radius.max(0.0),
+            wander_pause_seconds: value.wander_pause_seconds.max(0.0),
+        })
+    }
+}
+
+/// Lifecycle of a scheduled event; phases only move forward within a day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum WorldEventPhase {
+    #[default]
+    Idle,
+    Announced,
+    Active,
+    Ended,
+}
+
+impl WorldEventPhase {
+    fn next(self) -> Self {
+        match self {
+            Self::Idle => Self::Announced,
+            Self::Announced => Self::Active,
+            Self::Active | Self::Ended => Self::Ended,
+        }
+    }
+}
+
+/// Fired whenever the market day changes phase.
+#[derive(Event, Message, Debug, Clone, Copy, PartialEq)]
+pub struct WorldEventTransition {
+    pub phase: WorldEventPhase,
+    pub day: u64,
+}
+
+/// Chronicle line once the market opens; the other phases are covered by `notice`.
+pub fn distill_market_transition(
+    transition: &WorldEventTransition,
+    _roster: &ChronicleRoster,
+) -> Option<ChronicleDraft> {
+    (transition.phase == WorldEventPhase::Active).then(|| {
+        ChronicleDraft::new(
+            "The weekly market was held",
+            Vec::new(),
+            MARKET_HELD_SIGNIFICANCE,
+        )
+    })
+}
+
+/// Current phase of the market day.
+#[derive(Resource, Debug, Default, Clone, PartialEq)]
+pub struct WorldEvent {
+    phase: WorldEventPhase,
+    day: u64,
+}
+
+impl WorldEvent {
+    #[cfg(test)]
+    pub fn phase(&self) -> WorldEventPhase {
+        self.phase
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.phase == WorldEventPhase::Active
+    }
+
+    /// Line for dialogue context while the market is announced or running.
+    pub fn notice(&self) -> Option<&'static str> {
+        match self.phase {
+            WorldEventPhase::Announced => Some("The market opens later today."),
+            WorldEventPhase::Active => Some("The market is on today."),
+            WorldEventPhase::Idle | WorldEventPhase::Ended => None,
+        }
+    }
+
+    /// Moves toward the scheduled phase for `day` and `time_of_day`, returning each
+    /// transition in order. Skipped phases are still reported, so a clock jump across the
+    /// whole window yields Announced, Active, Ended. An event that is no longer scheduled
+    /// (config change, clock wound back, day skipped mid-event) ends rather than rewinding.
+    pub fn advance(
+        &mut self,
+        config: &MarketDayConfig,
+        day: u64,
+        time_of_day: f32,
+    ) -> Vec<WorldEventTransition> {
+        let mut transitions = Vec::new();
+        if day != self.day {
+            if matches!(
+                self.phase,
+                WorldEventPhase::Announced | WorldEventPhase::Active
+            ) {
+                transitions.push(self.enter(WorldEventPhase::Ended));
+            }
+            self.phase = WorldEventPhase::Idle;
+            self.day = day;
+        }
+
+        let target = config.scheduled_phase(day, time_of_day);
+        if target > self.phase {
+            while self.phase < target {
+                let next = self.phase.next();
+                transitions.push(self.enter(next));
+            }
+        } else if target < self.phase
+            && matches!(
+                self.phase,
+                WorldEventPhase::Announced | WorldEventPhase::Active
+            )
+        {
+            transitions.push(self.enter(WorldEventPhase::Ended));
+        }
+        transitions
+    }
+
+    fn enter(&mut self, phase: WorldEventPhase) -> WorldEventTransition {
+        self.phase = phase;
+        WorldEventTransition {
+            phase,
+            day: self.day,
+        }
+    }
+}
+
+/// Steps the market day state machine along the world clock.
+pub fn advance_world_event(
+    clock: Res<WorldClock>,
+    config: Res<MarketDayConfig>,
+    mut event: ResMut<WorldEvent>,
+    mut transitions: MessageWriter<WorldEventTransition>,
+) {
+    for transition in event.advance(&config, clock.day_count(), clock.time_of_day()) {
+        match transition.phase {
+            WorldEventPhase::Announced => info!("Market day announced for day {}", transition.day),
+            WorldEventPhase::Active => info!("Market day open on day {}", transition.day),
+            WorldEventPhase::Ended => info!("Market day over on day {}", transition.day),
+            WorldEventPhase::Idle => {}
+        }
+        transitions.write(transition);
+    }
+}
+
+/// Re-reads `config/world_events.toml` on request.
+pub fn reload_world_events(
+    mut requests: MessageReader<ConfigReloadRequested>,
+    mut diagnostics: ResMut<ConfigDiagnostics>,
+    mut config: ResMut<MarketDayConfig>,
+) {
+    if !requests.read().any(|request| request.is_for(CONFIG_PATH)) {
+        return;
+    }
+    if let Some(reloaded) = diagnostics.report_reload(CONFIG_PATH, MarketDayConfig::load()) {
+        *config = reloaded;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use WorldEventPhase::*;
+
+    fn market_day() -> u64 {
+        MarketDayConfig::default().weekday + DAYS_PER_WEEK
+    }
+
+    fn phases(transitions: Vec<WorldEventTransition>) -> Vec<WorldEventPhase> {
+        transitions
+            .into_iter()
+            .map(|transition| transition.phase)
+            .collect()
+    }
+
+    #[test]
+    fn phases_follow_the_configured_fractions() {
+        let config = MarketDayConfig::default();
+        let day = market_day();
+        let mut event = WorldEvent::default();
+
+        assert!(phases(event.advance(&config, day, 0.1)).is_empty());
+        assert_eq!(phases(event.advance(&config, day, 0.25)), vec![Announced]);
+        assert_eq!(event.notice(), Some("The market opens later today."));
+        assert!(phases(event.advance(&config, day, 0.3)).is_empty());
+        assert_eq!(phases(event.advance(&config, day, 0.375)), vec![Active]);
+        assert!(event.is_active());
+        assert_eq!(event.notice(), Some("The market is on today."));
+        assert_eq!(phases(event.advance(&config, day, 0.7)), vec![Ended]);
+        assert_eq!(event.notice(), None);
+
+        // The next day starts idle and is not a market day.
+        assert!(phases(event.advance(&config, day + 1, 0.5)).is_empty());
+        assert_eq!(event.phase(), Idle);
+    }
+
+    #[test]
+    fn a_clock_jump_reports_every_skipped_phase() {
+        let config = MarketDayConfig::default();
+        let mut event = WorldEvent::default();
+        let transitions = event.advance(&config, market_day(), 0.9);
+        assert_eq!(phases(transitions.clone()), vec![Announced, Active, Ended]);
+        assert!(transitions
+            .iter()
+            .all(|transition| transition.day == market_day()));
+        let chronicled: Vec<_> = transitions
+            .iter()
+            .filter_map(|transition| {
+                distill_market_transition(transition, &ChronicleRoster::default())
+            })
+            .map(|draft| draft.text)
+            .collect();
+        assert_eq!(chronicled, ["The weekly market was held"]);
+    }
+
+    #[test]
+    fn events_end_when_no_longer_scheduled() {
+        let mut config = MarketDayConfig::default();
+        let day = market_day();
+        let mut event = WorldEvent::default();
+        event.advance(&config, day, 0.5);
+
+        // Skipping to the next day mid-event closes it on the day it ran.
+        let transitions = event.advance(&config, day + 1, 0.5);
+        assert_eq!(
+            transitions,
+            vec![WorldEventTransition { phase: Ended, day }]
+        );
+
+        event.advance(&config, day + DAYS_PER_WEEK, 0.3);
+        config.enabled = false;
+        assert_eq!(
+            phases(event.advance(&config, day + DAYS_PER_WEEK, 0.4)),
+            vec![Ended]
+        );
+    }
+
+    #[test]
+    fn out_of_order_fractions_are_rejected() {
+        let raw = RawMarketDay {
+            start_fraction: 0.7,
+            ..RawMarketDay::default()
+        };
+        assert!(MarketDayConfig::try_from(raw).is_err());
+        let raw = RawMarketDay {
+            weekday: 7,
+            ..RawMarketDay::default()
+        };
+        assert!(MarketDayConfig::try_from(raw).is_err());
+    }
+}